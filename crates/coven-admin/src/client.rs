@@ -1,7 +1,17 @@
 // ABOUTME: gRPC client utilities with JWT auth injection
-// ABOUTME: Provides interceptor for adding Authorization header to requests
+// ABOUTME: Provides the Authorization interceptor and the agent feed behind 'agents list --watch'
 
+use coven_proto::coven::{
+    client_service_client::ClientServiceClient, AgentInfo, ListAgentsRequest, ListAgentsResponse,
+};
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tokio::time::{Interval, MissedTickBehavior};
+use tonic::codec::Streaming;
+use tonic::service::interceptor::InterceptedService;
 use tonic::service::Interceptor;
+use tonic::transport::Channel;
+use tonic::{Code, Status};
 
 /// Interceptor that adds JWT Bearer token to requests
 #[derive(Clone)]
@@ -26,3 +36,85 @@ impl Interceptor for AuthInterceptor {
         Ok(req)
     }
 }
+
+/// ClientService client that sends the caller's token
+pub type AuthClientService = ClientServiceClient<InterceptedService<Channel, AuthInterceptor>>;
+
+/// Successive agent lists: pushed by the gateway when it supports
+/// WatchAgents, otherwise fetched with ListAgents on a fixed interval.
+pub enum AgentFeed {
+    Stream(Streaming<ListAgentsResponse>),
+    Poll {
+        client: AuthClientService,
+        request: ListAgentsRequest,
+        ticker: Interval,
+    },
+}
+
+impl AgentFeed {
+    /// Subscribe to agent changes, falling back to polling every
+    /// `poll_interval` when the gateway doesn't implement WatchAgents.
+    pub async fn start(
+        mut client: AuthClientService,
+        request: ListAgentsRequest,
+        poll_interval: Duration,
+    ) -> Result<Self, Status> {
+        match client.watch_agents(request.clone()).await {
+            Ok(response) => Ok(Self::Stream(response.into_inner())),
+            Err(status) if status.code() == Code::Unimplemented => {
+                let mut ticker = tokio::time::interval(poll_interval);
+                ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+                Ok(Self::Poll {
+                    client,
+                    request,
+                    ticker,
+                })
+            }
+            Err(status) => Err(status),
+        }
+    }
+
+    pub fn is_polling(&self) -> bool {
+        matches!(self, Self::Poll { .. })
+    }
+
+    /// The next agent list; None once the gateway ends the stream. The
+    /// first call returns the current list right away. Not cancel safe: a
+    /// poll dropped mid-request loses its list, so don't race it in a
+    /// `select!`; use `spawn` instead.
+    pub async fn next(&mut self) -> Result<Option<Vec<AgentInfo>>, Status> {
+        match self {
+            Self::Stream(stream) => Ok(stream.message().await?.map(|r| r.agents)),
+            Self::Poll {
+                client,
+                request,
+                ticker,
+            } => {
+                ticker.tick().await;
+                let response = client.list_agents(request.clone()).await?;
+                Ok(Some(response.into_inner().agents))
+            }
+        }
+    }
+
+    /// Run the feed in a task of its own, sending each list or the error
+    /// that ended it. The channel closes once the gateway ends the stream or
+    /// the receiver is dropped. Receiving is cancel safe where `next` isn't.
+    pub fn spawn(mut self) -> mpsc::Receiver<Result<Vec<AgentInfo>, Status>> {
+        let (tx, rx) = mpsc::channel(1);
+        tokio::spawn(async move {
+            loop {
+                let update = match self.next().await {
+                    Ok(Some(agents)) => Ok(agents),
+                    Ok(None) => break,
+                    Err(status) => Err(status),
+                };
+                let failed = update.is_err();
+                if tx.send(update).await.is_err() || failed {
+                    break;
+                }
+            }
+        });
+        rx
+    }
+}
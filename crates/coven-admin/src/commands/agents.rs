@@ -1,26 +1,71 @@
 // ABOUTME: Implementation of 'coven-admin agents' commands
-// ABOUTME: Lists connected agents from the gateway
+// ABOUTME: Lists connected agents, watches them live, shows one agent's details, and changes an agent's log filter
 
 use anyhow::{bail, Result};
 use colored::Colorize;
+use std::time::{Duration, Instant};
 
 use coven_grpc::ChannelConfig;
-use coven_proto::coven::{client_service_client::ClientServiceClient, ListAgentsRequest};
+use coven_proto::coven::{
+    admin_service_client::AdminServiceClient, client_service_client::ClientServiceClient,
+    AgentInfo, GetAgentRequest, GetAgentResponse, GitInfo, ListAgentsRequest,
+    SetAgentLogLevelRequest,
+};
 
 use super::AgentsCommand;
-use crate::client::AuthInterceptor;
+use crate::client::{AgentFeed, AuthInterceptor};
+use crate::output::{print_json, OutputFormat, Table};
+use crate::terminal::LiveView;
 
-pub async fn run(gateway: &str, token: Option<&str>, cmd: AgentsCommand) -> Result<()> {
-    let Some(token) = token else {
-        bail!("Authentication required. Set COVEN_TOKEN environment variable or use --token flag.");
-    };
+/// Columns of `agents list --output table`
+pub const AGENT_COLUMNS: &[&str] = &["ID", "NAME", "STATUS", "BACKEND", "WORKING_DIR"];
+
+/// Columns of `agents list --watch`
+pub const WATCH_COLUMNS: &[&str] = &["ID", "NAME", "STATUS", "PRESENCE", "LAST_CHANGE"];
+
+/// How long a connect or disconnect stays highlighted in the watch view
+const HIGHLIGHT_FOR: Duration = Duration::from_secs(10);
 
+pub async fn run(
+    gateway: &str,
+    token: Option<&str>,
+    cmd: AgentsCommand,
+    output: OutputFormat,
+) -> Result<()> {
     match cmd {
-        AgentsCommand::List { workspace } => list_agents(gateway, token, workspace).await,
+        AgentsCommand::List {
+            workspace,
+            watch,
+            interval,
+        } => {
+            let Some(token) = token else {
+                bail!("Authentication required. Set COVEN_TOKEN environment variable or use --token flag.");
+            };
+            if watch {
+                if output == OutputFormat::Json {
+                    bail!("--watch draws a live table and can't be combined with --output json");
+                }
+                let interval = Duration::from_secs(interval.max(1));
+                return watch_agents(gateway, token, workspace, interval, output).await;
+            }
+            list_agents(gateway, token, workspace, output).await
+        }
+        // Served by the admin service, which the local gateway runs without auth
+        AgentsCommand::Show { agent_id, activity } => {
+            show_agent(gateway, token, agent_id, activity, output).await
+        }
+        AgentsCommand::SetLogLevel { agent_id, filter } => {
+            set_log_level(gateway, token, agent_id, filter, output).await
+        }
     }
 }
 
-async fn list_agents(gateway: &str, token: &str, workspace: Option<String>) -> Result<()> {
+async fn list_agents(
+    gateway: &str,
+    token: &str,
+    workspace: Option<String>,
+    output: OutputFormat,
+) -> Result<()> {
     let config = ChannelConfig::new(gateway).without_keep_alive();
     let channel = coven_grpc::create_channel(&config).await?;
 
@@ -28,8 +73,16 @@ async fn list_agents(gateway: &str, token: &str, workspace: Option<String>) -> R
     let mut client = ClientServiceClient::with_interceptor(channel, interceptor);
 
     let request = ListAgentsRequest { workspace };
-    let response = client.list_agents(request).await?;
-    let agents = response.into_inner().agents;
+    let response = client.list_agents(request).await?.into_inner();
+    match output {
+        OutputFormat::Json => return print_json(&response),
+        OutputFormat::Table => {
+            agents_table(&response.agents).print();
+            return Ok(());
+        }
+        OutputFormat::Text => {}
+    }
+    let agents = response.agents;
 
     if agents.is_empty() {
         println!("{}", "No agents connected".dimmed());
@@ -79,3 +132,758 @@ async fn list_agents(gateway: &str, token: &str, workspace: Option<String>) -> R
 
     Ok(())
 }
+
+/// Redraw the agent list each time it changes until Ctrl+C. The gateway
+/// pushes changes when it can; otherwise it's polled every `interval`.
+async fn watch_agents(
+    gateway: &str,
+    token: &str,
+    workspace: Option<String>,
+    interval: Duration,
+    output: OutputFormat,
+) -> Result<()> {
+    let config = ChannelConfig::new(gateway);
+    let channel = coven_grpc::create_channel(&config).await?;
+
+    let interceptor = AuthInterceptor::new(Some(token.to_string()));
+    let client = ClientServiceClient::with_interceptor(channel, interceptor);
+    let feed = AgentFeed::start(client, ListAgentsRequest { workspace }, interval).await?;
+    let source = if feed.is_polling() {
+        format!("polling every {}s", interval.as_secs())
+    } else {
+        "live".to_string()
+    };
+    // Its own task, so a redraw never interrupts a poll in flight
+    let mut updates = feed.spawn();
+
+    let mut view = LiveView::stdout()?;
+    let color = view.is_live() && output == OutputFormat::Text;
+    let mut board = WatchBoard::default();
+    // Ages in LAST_CHANGE keep counting between updates
+    let mut redraw = tokio::time::interval(Duration::from_secs(1));
+    let ctrl_c = tokio::signal::ctrl_c();
+    tokio::pin!(ctrl_c);
+
+    loop {
+        tokio::select! {
+            _ = &mut ctrl_c => break,
+            update = updates.recv() => match update {
+                Some(agents) => board.update(agents?, Instant::now()),
+                None => bail!("gateway closed the agent stream"),
+            },
+            _ = redraw.tick() => {}
+        }
+        let header = format!(
+            "Agents: {} connected of {} ({}, Ctrl+C to exit)",
+            board.connected(),
+            board.len(),
+            source
+        );
+        let header = if color {
+            header.bold().to_string()
+        } else {
+            header
+        };
+        view.draw(&format!(
+            "{}\n\n{}",
+            header,
+            board.render(Instant::now(), color)
+        ))?;
+    }
+    Ok(())
+}
+
+/// How an agent last changed, as far as the watch view has seen
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Change {
+    Connected,
+    Disconnected,
+    Presence,
+    /// Appeared in the list without connecting
+    Added,
+}
+
+impl Change {
+    fn label(self) -> &'static str {
+        match self {
+            Change::Connected => "connected",
+            Change::Disconnected => "disconnected",
+            Change::Presence => "presence",
+            Change::Added => "added",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct WatchedAgent {
+    info: AgentInfo,
+    /// None for agents as they were when watching started
+    last_change: Option<(Change, Instant)>,
+}
+
+/// Agents as `agents list --watch` last saw them, with when each changed.
+/// Agents that drop out of the list stay on it as disconnected.
+#[derive(Debug, Default)]
+pub struct WatchBoard {
+    agents: Vec<WatchedAgent>,
+    started: bool,
+}
+
+impl WatchBoard {
+    /// Take in a fresh agent list received at `now`
+    pub fn update(&mut self, agents: Vec<AgentInfo>, now: Instant) {
+        let mut previous = std::mem::take(&mut self.agents);
+        for info in agents {
+            let before = previous
+                .iter()
+                .position(|w| w.info.id == info.id)
+                .map(|i| previous.remove(i));
+            let last_change = match before {
+                Some(before) => change_between(&before.info, &info)
+                    .map(|change| (change, now))
+                    .or(before.last_change),
+                None if !self.started => None,
+                None if info.connected => Some((Change::Connected, now)),
+                None => Some((Change::Added, now)),
+            };
+            self.agents.push(WatchedAgent { info, last_change });
+        }
+        for mut gone in previous {
+            if gone.info.connected {
+                gone.info.connected = false;
+                gone.last_change = Some((Change::Disconnected, now));
+            }
+            self.agents.push(gone);
+        }
+        self.started = true;
+    }
+
+    pub fn len(&self) -> usize {
+        self.agents.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.agents.is_empty()
+    }
+
+    pub fn connected(&self) -> usize {
+        self.agents.iter().filter(|w| w.info.connected).count()
+    }
+
+    /// The agents as a table, as of `now`. With `color`, recent connects
+    /// and disconnects are highlighted green and red.
+    pub fn render(&self, now: Instant, color: bool) -> String {
+        if self.agents.is_empty() {
+            return "No agents\n".to_string();
+        }
+        let table = self
+            .agents
+            .iter()
+            .fold(Table::new(WATCH_COLUMNS), |table, watched| {
+                let agent = &watched.info;
+                let presence = agent
+                    .metadata
+                    .as_ref()
+                    .and_then(|m| m.presence.as_ref())
+                    .map(|p| format!("{} {}", p.emoji, p.status).trim().to_string())
+                    .unwrap_or_default();
+                let last_change = watched
+                    .last_change
+                    .map(|(change, at)| {
+                        format!(
+                            "{} {}",
+                            change.label(),
+                            format_age(now.saturating_duration_since(at))
+                        )
+                    })
+                    .unwrap_or_default();
+                table.row([
+                    agent.id.clone(),
+                    agent.name.clone(),
+                    if agent.connected {
+                        "connected".to_string()
+                    } else {
+                        "disconnected".to_string()
+                    },
+                    presence,
+                    last_change,
+                ])
+            })
+            .render();
+        if !color {
+            return table;
+        }
+
+        let mut lines = table.lines();
+        let mut out = format!("{}\n", lines.next().unwrap_or_default().dimmed());
+        for (line, watched) in lines.zip(&self.agents) {
+            let recent = watched
+                .last_change
+                .filter(|(_, at)| now.saturating_duration_since(*at) < HIGHLIGHT_FOR)
+                .map(|(change, _)| change);
+            let line = match recent {
+                Some(Change::Connected) => line.green().bold().to_string(),
+                Some(Change::Disconnected) => line.red().bold().to_string(),
+                Some(Change::Presence | Change::Added) => line.yellow().to_string(),
+                None if !watched.info.connected => line.dimmed().to_string(),
+                None => line.to_string(),
+            };
+            out.push_str(&line);
+            out.push('\n');
+        }
+        out
+    }
+}
+
+/// What changed between two sightings of the same agent, if anything shown
+fn change_between(before: &AgentInfo, after: &AgentInfo) -> Option<Change> {
+    let presence = |agent: &AgentInfo| agent.metadata.as_ref().and_then(|m| m.presence.clone());
+    match (before.connected, after.connected) {
+        (false, true) => Some(Change::Connected),
+        (true, false) => Some(Change::Disconnected),
+        _ if presence(before) != presence(after) => Some(Change::Presence),
+        _ => None,
+    }
+}
+
+/// Compact age, e.g. `5s ago`, `3m ago`, `2h ago`
+fn format_age(age: Duration) -> String {
+    let secs = age.as_secs();
+    if secs < 60 {
+        format!("{}s ago", secs)
+    } else if secs < 3600 {
+        format!("{}m ago", secs / 60)
+    } else {
+        format!("{}h ago", secs / 3600)
+    }
+}
+
+async fn show_agent(
+    gateway: &str,
+    token: Option<&str>,
+    agent_id: String,
+    activity_limit: i32,
+    output: OutputFormat,
+) -> Result<()> {
+    let config = ChannelConfig::new(gateway).without_keep_alive();
+    let channel = coven_grpc::create_channel(&config).await?;
+
+    let interceptor = AuthInterceptor::new(token.map(String::from));
+    let mut client = AdminServiceClient::with_interceptor(channel, interceptor);
+
+    let response = client
+        .get_agent(GetAgentRequest {
+            agent_id,
+            activity_limit,
+        })
+        .await?
+        .into_inner();
+    match output {
+        OutputFormat::Json => print_json(&response),
+        OutputFormat::Table => {
+            agent_detail_table(&response).print();
+            Ok(())
+        }
+        OutputFormat::Text => {
+            print_agent_detail(response);
+            Ok(())
+        }
+    }
+}
+
+async fn set_log_level(
+    gateway: &str,
+    token: Option<&str>,
+    agent_id: String,
+    filter: String,
+    output: OutputFormat,
+) -> Result<()> {
+    // Catch typos here rather than have the agent quietly keep its filter
+    coven_log::reload::parse_filter(&filter)?;
+
+    let config = ChannelConfig::new(gateway).without_keep_alive();
+    let channel = coven_grpc::create_channel(&config).await?;
+
+    let interceptor = AuthInterceptor::new(token.map(String::from));
+    let mut client = AdminServiceClient::with_interceptor(channel, interceptor);
+
+    let request = SetAgentLogLevelRequest {
+        agent_id,
+        filter: filter.trim().to_string(),
+    };
+    client.set_agent_log_level(request.clone()).await?;
+    match output {
+        OutputFormat::Json => print_json(&request)?,
+        OutputFormat::Table => Table::new(&["AGENT_ID", "FILTER"])
+            .row([request.agent_id, request.filter])
+            .print(),
+        OutputFormat::Text => println!(
+            "{} {} to {}",
+            "Log filter sent:".green().bold(),
+            request.agent_id,
+            request.filter
+        ),
+    }
+    Ok(())
+}
+
+fn print_agent_detail(detail: GetAgentResponse) {
+    let agent = detail.agent.unwrap_or_default();
+    let connection = detail.connection.unwrap_or_default();
+    let status = if agent.connected {
+        "● connected".green()
+    } else {
+        "○ disconnected".red()
+    };
+    println!(
+        "{} {}  {}",
+        agent.name.bold(),
+        format!("({})", agent.id).dimmed(),
+        status
+    );
+    if !agent.connected {
+        println!(
+            "{}",
+            "Not currently connected; showing what the gateway has stored".dimmed()
+        );
+    }
+    println!();
+
+    println!("{}", "Metadata".bold());
+    let field = |label: &str, value: &str| {
+        if !value.is_empty() {
+            println!("  {:<16} {}", format!("{}:", label).dimmed(), value);
+        }
+    };
+    field("Backend", &agent.backend);
+    field("Working Dir", &agent.working_dir);
+    match agent.metadata {
+        Some(metadata) => {
+            field("Host", &metadata.hostname);
+            field("OS", &metadata.os);
+            if let Some(git) = &metadata.git {
+                field("Git", &git_summary(git));
+                field("Remote", &git.remote);
+            }
+            field("Workspaces", &metadata.workspaces.join(", "));
+            if let Some(presence) = &metadata.presence {
+                field(
+                    "Presence",
+                    format!("{} {}", presence.emoji, presence.status).trim(),
+                );
+            }
+        }
+        None => println!(
+            "  {}",
+            "Host details are only known while connected".dimmed()
+        ),
+    }
+    field("Capabilities", &connection.capabilities.join(", "));
+    field("Protocol", &connection.protocol_features.join(", "));
+    println!();
+
+    println!("{}", "Connection".bold());
+    field(
+        "Connected Since",
+        connection.connected_since.as_deref().unwrap_or_default(),
+    );
+    if agent.connected {
+        field(
+            "Last Heartbeat",
+            connection.last_heartbeat.as_deref().unwrap_or("none yet"),
+        );
+    }
+    if let Some(latency) = connection.heartbeat_latency_ms {
+        field("Latency", &format!("{} ms", latency));
+    }
+    field("Last Seen", detail.last_seen.as_deref().unwrap_or_default());
+    field("Queued Messages", &detail.queued_messages.to_string());
+    if agent.connected {
+        let errors = connection.recent_errors.to_string();
+        field(
+            "Errors (1h)",
+            &if connection.recent_errors > 0 {
+                errors.red().to_string()
+            } else {
+                errors
+            },
+        );
+    }
+    println!();
+
+    if agent.connected {
+        println!(
+            "{}",
+            format!("Working On ({})", connection.active_requests.len()).bold()
+        );
+        if connection.active_requests.is_empty() {
+            println!("  {}", "Idle".dimmed());
+        }
+        for request in &connection.active_requests {
+            println!(
+                "  {} {}  {}: {}",
+                request.started_at.dimmed(),
+                request.request_id,
+                "Thread".dimmed(),
+                request.thread_id
+            );
+        }
+        println!();
+    }
+
+    println!(
+        "{}",
+        format!("Recent Activity ({})", detail.recent_activity.len()).bold()
+    );
+    if detail.recent_activity.is_empty() {
+        println!("  {}", "No messages yet".dimmed());
+    }
+    for activity in &detail.recent_activity {
+        let arrow = if activity.direction == "inbound" {
+            "→".cyan()
+        } else {
+            "←".green()
+        };
+        let kind = if activity.message_type == "message" {
+            String::new()
+        } else {
+            format!(" [{}]", activity.message_type)
+        };
+        println!(
+            "  {} {} {}{}: {}",
+            activity.timestamp.dimmed(),
+            arrow,
+            activity.author.bold(),
+            kind.dimmed(),
+            activity.preview
+        );
+        println!("      {}: {}", "Thread".dimmed(), activity.thread_id);
+    }
+}
+
+/// One-line git state, e.g. `main @ 1a2b3c4 (dirty, 2 ahead)`
+/// The detail view as FIELD/VALUE rows, one per field. Each active request
+/// and recent activity gets a row of its own, in the order the gateway
+/// sent them.
+fn agent_detail_table(detail: &GetAgentResponse) -> Table {
+    let agent = detail.agent.clone().unwrap_or_default();
+    let connection = detail.connection.clone().unwrap_or_default();
+    let metadata = agent.metadata.unwrap_or_default();
+    let presence = metadata
+        .presence
+        .map(|presence| format!("{} {}", presence.emoji, presence.status))
+        .unwrap_or_default();
+    let status = if agent.connected {
+        "connected"
+    } else {
+        "disconnected"
+    };
+
+    let mut table = Table::new(&["FIELD", "VALUE"])
+        .row(["id".to_string(), agent.id])
+        .row(["name".to_string(), agent.name])
+        .row(["status", status])
+        .row(["backend".to_string(), agent.backend])
+        .row(["working_dir".to_string(), agent.working_dir])
+        .row(["host".to_string(), metadata.hostname])
+        .row(["os".to_string(), metadata.os])
+        .row([
+            "git".to_string(),
+            metadata.git.as_ref().map(git_summary).unwrap_or_default(),
+        ])
+        .row([
+            "remote".to_string(),
+            metadata.git.map(|git| git.remote).unwrap_or_default(),
+        ])
+        .row(["workspaces".to_string(), metadata.workspaces.join(",")])
+        .row(["presence".to_string(), presence])
+        .row([
+            "capabilities".to_string(),
+            connection.capabilities.join(","),
+        ])
+        .row([
+            "protocol".to_string(),
+            connection.protocol_features.join(","),
+        ])
+        .row([
+            "connected_since".to_string(),
+            connection.connected_since.unwrap_or_default(),
+        ])
+        .row([
+            "last_heartbeat".to_string(),
+            connection.last_heartbeat.unwrap_or_default(),
+        ])
+        .row([
+            "latency_ms".to_string(),
+            connection
+                .heartbeat_latency_ms
+                .map(|latency| latency.to_string())
+                .unwrap_or_default(),
+        ])
+        .row([
+            "last_seen".to_string(),
+            detail.last_seen.clone().unwrap_or_default(),
+        ])
+        .row([
+            "queued_messages".to_string(),
+            detail.queued_messages.to_string(),
+        ])
+        .row([
+            "recent_errors".to_string(),
+            connection.recent_errors.to_string(),
+        ]);
+    for request in &connection.active_requests {
+        table = table.row([
+            "active_request".to_string(),
+            format!(
+                "{} {} thread={}",
+                request.started_at, request.request_id, request.thread_id
+            ),
+        ]);
+    }
+    for activity in &detail.recent_activity {
+        table = table.row([
+            "activity".to_string(),
+            format!(
+                "{} {} {} {} thread={} {}",
+                activity.timestamp,
+                activity.direction,
+                activity.author,
+                activity.message_type,
+                activity.thread_id,
+                activity.preview
+            ),
+        ]);
+    }
+    table
+}
+
+fn git_summary(git: &GitInfo) -> String {
+    let mut summary = git.branch.clone();
+    if !git.commit.is_empty() {
+        let short: String = git.commit.chars().take(7).collect();
+        summary = format!("{} @ {}", summary, short);
+    }
+    let mut notes = Vec::new();
+    if git.dirty {
+        notes.push("dirty".to_string());
+    }
+    if git.ahead > 0 {
+        notes.push(format!("{} ahead", git.ahead));
+    }
+    if git.behind > 0 {
+        notes.push(format!("{} behind", git.behind));
+    }
+    if !notes.is_empty() {
+        summary = format!("{} ({})", summary, notes.join(", "));
+    }
+    summary
+}
+
+pub fn agents_table(agents: &[AgentInfo]) -> Table {
+    agents
+        .iter()
+        .fold(Table::new(AGENT_COLUMNS), |table, agent| {
+            table.row([
+                agent.id.as_str(),
+                agent.name.as_str(),
+                if agent.connected {
+                    "connected"
+                } else {
+                    "disconnected"
+                },
+                agent.backend.as_str(),
+                agent.working_dir.as_str(),
+            ])
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use coven_proto::coven::{
+        ActiveRequest, AgentActivity, AgentConnection, AgentMetadata, AgentPresence,
+    };
+
+    #[test]
+    fn test_agents_table() {
+        let agents = [
+            AgentInfo {
+                id: "agent-1".to_string(),
+                name: "Hex".to_string(),
+                backend: "direct".to_string(),
+                working_dir: "/src/coven".to_string(),
+                connected: true,
+                metadata: None,
+            },
+            AgentInfo {
+                id: "agent-22".to_string(),
+                name: "Quill".to_string(),
+                connected: false,
+                ..Default::default()
+            },
+        ];
+        assert_eq!(
+            agents_table(&agents).render(),
+            "ID        NAME   STATUS        BACKEND  WORKING_DIR\n\
+             agent-1   Hex    connected     direct   /src/coven\n\
+             agent-22  Quill  disconnected  -        -\n"
+        );
+    }
+
+    #[test]
+    fn test_agent_detail_table() {
+        let detail = GetAgentResponse {
+            agent: Some(AgentInfo {
+                id: "agent-1".to_string(),
+                name: "Hex".to_string(),
+                backend: "direct".to_string(),
+                connected: true,
+                metadata: Some(AgentMetadata {
+                    hostname: "forge".to_string(),
+                    workspaces: vec!["ops".to_string(), "web".to_string()],
+                    ..Default::default()
+                }),
+                ..Default::default()
+            }),
+            connection: Some(AgentConnection {
+                heartbeat_latency_ms: Some(12),
+                active_requests: vec![ActiveRequest {
+                    request_id: "req-1".to_string(),
+                    thread_id: "t-1".to_string(),
+                    started_at: "2026-10-16T09:00:00Z".to_string(),
+                }],
+                ..Default::default()
+            }),
+            queued_messages: 2,
+            recent_activity: vec![AgentActivity {
+                thread_id: "t-1".to_string(),
+                direction: "inbound".to_string(),
+                author: "ana".to_string(),
+                message_type: "message".to_string(),
+                preview: "deploy\nplease".to_string(),
+                timestamp: "2026-10-16T08:59:59Z".to_string(),
+            }],
+            ..Default::default()
+        };
+        assert_eq!(
+            agent_detail_table(&detail).render(),
+            "FIELD            VALUE\n\
+             id               agent-1\n\
+             name             Hex\n\
+             status           connected\n\
+             backend          direct\n\
+             working_dir      -\n\
+             host             forge\n\
+             os               -\n\
+             git              -\n\
+             remote           -\n\
+             workspaces       ops,web\n\
+             presence         -\n\
+             capabilities     -\n\
+             protocol         -\n\
+             connected_since  -\n\
+             last_heartbeat   -\n\
+             latency_ms       12\n\
+             last_seen        -\n\
+             queued_messages  2\n\
+             recent_errors    0\n\
+             active_request   2026-10-16T09:00:00Z req-1 thread=t-1\n\
+             activity         2026-10-16T08:59:59Z inbound ana message thread=t-1 deploy please\n"
+        );
+    }
+
+    fn watched(id: &str, connected: bool, status: Option<&str>) -> AgentInfo {
+        AgentInfo {
+            id: id.to_string(),
+            name: id.to_uppercase(),
+            connected,
+            metadata: status.map(|status| AgentMetadata {
+                presence: Some(AgentPresence {
+                    status: status.to_string(),
+                    emoji: String::new(),
+                }),
+                ..Default::default()
+            }),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_watch_board_tracks_changes() {
+        let start = Instant::now();
+        let mut board = WatchBoard::default();
+        board.update(
+            vec![
+                watched("a1", true, Some("available")),
+                watched("a2", false, None),
+            ],
+            start,
+        );
+        // Nothing has changed yet
+        assert_eq!(
+            board.render(start, false),
+            "ID  NAME  STATUS        PRESENCE   LAST_CHANGE\n\
+             a1  A1    connected     available  -\n\
+             a2  A2    disconnected  -          -\n"
+        );
+
+        let later = start + Duration::from_secs(5);
+        board.update(
+            vec![
+                watched("a1", true, Some("busy")),
+                watched("a2", true, Some("available")),
+                watched("a3", true, Some("available")),
+            ],
+            later,
+        );
+        assert_eq!(board.connected(), 3);
+        assert_eq!(
+            board.render(later + Duration::from_secs(90), false),
+            "ID  NAME  STATUS     PRESENCE   LAST_CHANGE\n\
+             a1  A1    connected  busy       presence 1m ago\n\
+             a2  A2    connected  available  connected 1m ago\n\
+             a3  A3    connected  available  connected 1m ago\n"
+        );
+    }
+
+    #[test]
+    fn test_watch_board_keeps_vanished_agents() {
+        let start = Instant::now();
+        let mut board = WatchBoard::default();
+        board.update(vec![watched("a1", true, None)], start);
+        board.update(vec![], start + Duration::from_secs(2));
+
+        assert_eq!(board.len(), 1);
+        assert_eq!(board.connected(), 0);
+        assert_eq!(
+            board.render(start + Duration::from_secs(2), false),
+            "ID  NAME  STATUS        PRESENCE  LAST_CHANGE\n\
+             a1  A1    disconnected  -         disconnected 0s ago\n"
+        );
+    }
+
+    #[test]
+    fn test_format_age() {
+        assert_eq!(format_age(Duration::from_secs(59)), "59s ago");
+        assert_eq!(format_age(Duration::from_secs(60)), "1m ago");
+        assert_eq!(format_age(Duration::from_secs(7300)), "2h ago");
+    }
+
+    #[test]
+    fn test_git_summary() {
+        let git = GitInfo {
+            branch: "main".to_string(),
+            commit: "1a2b3c4d5e6f".to_string(),
+            dirty: true,
+            ahead: 2,
+            ..Default::default()
+        };
+        assert_eq!(git_summary(&git), "main @ 1a2b3c4 (dirty, 2 ahead)");
+        let clean = GitInfo {
+            branch: "feature".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(git_summary(&clean), "feature");
+    }
+}
@@ -0,0 +1,352 @@
+// ABOUTME: TOML/JSON schema for 'bindings export/import' and the diff that reconciles an import
+// ABOUTME: Entries match existing bindings by frontend and channel; --prune plans deletes for the rest
+
+use anyhow::{bail, Context, Result};
+use coven_proto::coven::{AgentInfo, Binding};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::path::Path;
+
+/// File formats of `bindings export` and `bindings import`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum)]
+pub enum BindingsFormat {
+    #[default]
+    Toml,
+    Json,
+}
+
+impl BindingsFormat {
+    /// JSON for `.json` files, TOML for anything else, including stdin
+    pub fn for_path(path: &Path) -> Self {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some(ext) if ext.eq_ignore_ascii_case("json") => BindingsFormat::Json,
+            _ => BindingsFormat::Toml,
+        }
+    }
+}
+
+/// A bindings file:
+///
+/// ```toml
+/// [[bindings]]
+/// frontend = "slack"
+/// channel_id = "C0123"
+/// agent_id = "agent-1"
+///
+/// [[bindings]]
+/// frontend = "matrix"
+/// channel_id = "!ops:example.org"
+/// agent_id = "agent-2"
+/// ```
+///
+/// or the same as JSON: `{"bindings": [{"frontend": ..., ...}]}`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BindingFile {
+    #[serde(default)]
+    pub bindings: Vec<BindingEntry>,
+}
+
+/// One binding as written in a bindings file
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BindingEntry {
+    pub frontend: String,
+    pub channel_id: String,
+    pub agent_id: String,
+}
+
+impl BindingEntry {
+    fn route(&self) -> (&str, &str) {
+        (&self.frontend, &self.channel_id)
+    }
+}
+
+impl BindingFile {
+    /// Parse a bindings file, rejecting one that routes a channel twice
+    pub fn parse(text: &str, format: BindingsFormat) -> Result<Self> {
+        let file: Self = match format {
+            BindingsFormat::Toml => toml::from_str(text).context("invalid bindings file")?,
+            BindingsFormat::Json => serde_json::from_str(text).context("invalid bindings file")?,
+        };
+        let mut routes = HashSet::new();
+        for entry in &file.bindings {
+            if !routes.insert(entry.route()) {
+                bail!(
+                    "invalid bindings file: {}:{} is bound more than once",
+                    entry.frontend,
+                    entry.channel_id
+                );
+            }
+        }
+        Ok(file)
+    }
+
+    pub fn render(&self, format: BindingsFormat) -> Result<String> {
+        Ok(match format {
+            BindingsFormat::Toml => toml::to_string(self)?,
+            BindingsFormat::Json => serde_json::to_string_pretty(self)? + "\n",
+        })
+    }
+
+    /// The exportable fields of `bindings`; IDs and creation details belong
+    /// to the gateway and aren't carried over.
+    pub fn from_bindings(bindings: &[Binding]) -> Self {
+        let mut bindings: Vec<BindingEntry> = bindings.iter().map(entry_of).collect();
+        // Stable order, so exports of the same bindings diff cleanly
+        bindings.sort_by(|a, b| a.route().cmp(&b.route()));
+        Self { bindings }
+    }
+
+    /// Agent IDs the file routes to that aren't among `agents`, each once
+    pub fn unknown_agents(&self, agents: &[AgentInfo]) -> Vec<String> {
+        let known: HashSet<&str> = agents.iter().map(|a| a.id.as_str()).collect();
+        let mut unknown = Vec::new();
+        for entry in &self.bindings {
+            if !known.contains(entry.agent_id.as_str()) && !unknown.contains(&entry.agent_id) {
+                unknown.push(entry.agent_id.clone());
+            }
+        }
+        unknown
+    }
+}
+
+fn entry_of(binding: &Binding) -> BindingEntry {
+    BindingEntry {
+        frontend: binding.frontend.clone(),
+        channel_id: binding.channel_id.clone(),
+        agent_id: binding.agent_id.clone(),
+    }
+}
+
+/// What importing one entry, or pruning one binding, will do
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PlannedAction {
+    /// No binding for the channel; create one
+    Create,
+    /// Binding `id` routes the channel to `from_agent`; point it at the entry's agent
+    Update { id: String, from_agent: String },
+    /// Binding `id` matches exactly
+    Unchanged { id: String },
+    /// Binding `id` isn't in the file and --prune was given; delete it
+    Delete { id: String },
+}
+
+impl PlannedAction {
+    pub fn label(&self) -> &'static str {
+        match self {
+            PlannedAction::Create => "create",
+            PlannedAction::Update { .. } => "update",
+            PlannedAction::Unchanged { .. } => "no-op",
+            PlannedAction::Delete { .. } => "delete",
+        }
+    }
+}
+
+/// One binding and what the import will do with it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PlannedChange {
+    pub entry: BindingEntry,
+    pub action: PlannedAction,
+}
+
+/// Work out what importing `entries` into a gateway that has `existing`
+/// bindings will do. An entry matches the binding for its frontend and
+/// channel. With `prune`, bindings no entry matches are deleted, after the
+/// entries in file order; otherwise they're left alone.
+pub fn plan_import(
+    existing: &[Binding],
+    entries: &[BindingEntry],
+    prune: bool,
+) -> Vec<PlannedChange> {
+    let mut plan: Vec<PlannedChange> = entries
+        .iter()
+        .map(|entry| {
+            let current = existing
+                .iter()
+                .find(|b| (b.frontend.as_str(), b.channel_id.as_str()) == entry.route());
+            let action = match current {
+                None => PlannedAction::Create,
+                Some(current) if current.agent_id == entry.agent_id => PlannedAction::Unchanged {
+                    id: current.id.clone(),
+                },
+                Some(current) => PlannedAction::Update {
+                    id: current.id.clone(),
+                    from_agent: current.agent_id.clone(),
+                },
+            };
+            PlannedChange {
+                entry: entry.clone(),
+                action,
+            }
+        })
+        .collect();
+
+    if prune {
+        let wanted: HashSet<(&str, &str)> = entries.iter().map(BindingEntry::route).collect();
+        plan.extend(
+            existing
+                .iter()
+                .filter(|b| !wanted.contains(&(b.frontend.as_str(), b.channel_id.as_str())))
+                .map(|b| PlannedChange {
+                    entry: entry_of(b),
+                    action: PlannedAction::Delete { id: b.id.clone() },
+                }),
+        );
+    }
+    plan
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn binding(id: &str, frontend: &str, channel_id: &str, agent_id: &str) -> Binding {
+        Binding {
+            id: id.to_string(),
+            frontend: frontend.to_string(),
+            channel_id: channel_id.to_string(),
+            agent_id: agent_id.to_string(),
+            created_at: "2026-01-02T03:04:05Z".to_string(),
+            created_by: None,
+        }
+    }
+
+    fn entry(frontend: &str, channel_id: &str, agent_id: &str) -> BindingEntry {
+        BindingEntry {
+            frontend: frontend.to_string(),
+            channel_id: channel_id.to_string(),
+            agent_id: agent_id.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_parse_documented_schema() {
+        let toml = "[[bindings]]\n\
+                    frontend = \"slack\"\n\
+                    channel_id = \"C0123\"\n\
+                    agent_id = \"agent-1\"\n";
+        let json = r#"{"bindings": [{"frontend": "slack", "channel_id": "C0123", "agent_id": "agent-1"}]}"#;
+        let expected = vec![entry("slack", "C0123", "agent-1")];
+
+        assert_eq!(
+            BindingFile::parse(toml, BindingsFormat::Toml)
+                .unwrap()
+                .bindings,
+            expected
+        );
+        assert_eq!(
+            BindingFile::parse(json, BindingsFormat::Json)
+                .unwrap()
+                .bindings,
+            expected
+        );
+        assert!(BindingFile::parse("", BindingsFormat::Toml)
+            .unwrap()
+            .bindings
+            .is_empty());
+    }
+
+    #[test]
+    fn test_parse_rejects_unknown_fields_and_duplicate_routes() {
+        let unknown = "[[bindings]]\nfrontend = \"slack\"\nchannel = \"C1\"\nagent_id = \"a\"\n";
+        assert!(BindingFile::parse(unknown, BindingsFormat::Toml).is_err());
+
+        let twice = r#"{"bindings": [
+            {"frontend": "slack", "channel_id": "C1", "agent_id": "a"},
+            {"frontend": "slack", "channel_id": "C1", "agent_id": "b"}
+        ]}"#;
+        let err = BindingFile::parse(twice, BindingsFormat::Json).unwrap_err();
+        assert!(err.to_string().contains("slack:C1 is bound more than once"));
+    }
+
+    #[test]
+    fn test_format_for_path() {
+        assert_eq!(
+            BindingsFormat::for_path(Path::new("b.JSON")),
+            BindingsFormat::Json
+        );
+        assert_eq!(
+            BindingsFormat::for_path(Path::new("b.toml")),
+            BindingsFormat::Toml
+        );
+        assert_eq!(
+            BindingsFormat::for_path(Path::new("-")),
+            BindingsFormat::Toml
+        );
+    }
+
+    #[test]
+    fn test_export_round_trips() {
+        let bindings = [
+            binding("b-2", "slack", "C0456", "agent-2"),
+            binding("b-1", "matrix", "!ops:example.org", "agent-1"),
+        ];
+        let file = BindingFile::from_bindings(&bindings);
+        assert_eq!(file.bindings[0].frontend, "matrix");
+
+        for format in [BindingsFormat::Toml, BindingsFormat::Json] {
+            let parsed = BindingFile::parse(&file.render(format).unwrap(), format).unwrap();
+            assert_eq!(parsed, file);
+            // Importing an export into the same gateway changes nothing
+            assert!(plan_import(&bindings, &parsed.bindings, true)
+                .iter()
+                .all(|c| matches!(c.action, PlannedAction::Unchanged { .. })));
+        }
+    }
+
+    #[test]
+    fn test_plan_import() {
+        let existing = [
+            binding("b-1", "slack", "C1", "agent-1"),
+            binding("b-2", "slack", "C2", "agent-1"),
+            binding("b-3", "matrix", "C1", "agent-2"),
+        ];
+        let entries = [
+            entry("slack", "C1", "agent-1"),
+            entry("slack", "C2", "agent-2"),
+            entry("telegram", "C1", "agent-2"),
+        ];
+
+        let actions = |prune| {
+            plan_import(&existing, &entries, prune)
+                .into_iter()
+                .map(|c| c.action)
+                .collect::<Vec<_>>()
+        };
+        let kept = vec![
+            PlannedAction::Unchanged {
+                id: "b-1".to_string(),
+            },
+            PlannedAction::Update {
+                id: "b-2".to_string(),
+                from_agent: "agent-1".to_string(),
+            },
+            PlannedAction::Create,
+        ];
+        assert_eq!(actions(false), kept);
+
+        // Same channel ID on another frontend is another binding
+        let mut pruned = kept;
+        pruned.push(PlannedAction::Delete {
+            id: "b-3".to_string(),
+        });
+        assert_eq!(actions(true), pruned);
+    }
+
+    #[test]
+    fn test_unknown_agents() {
+        let agents = [AgentInfo {
+            id: "agent-1".to_string(),
+            ..Default::default()
+        }];
+        let file = BindingFile {
+            bindings: vec![
+                entry("slack", "C1", "agent-1"),
+                entry("slack", "C2", "ghost"),
+                entry("slack", "C3", "ghost"),
+            ],
+        };
+        assert_eq!(file.unknown_agents(&agents), ["ghost"]);
+    }
+}
@@ -1,35 +1,115 @@
 // ABOUTME: Implementation of 'coven-admin bindings' commands
-// ABOUTME: Manages bindings between frontends/channels and agents
+// ABOUTME: Manages bindings between frontends/channels and agents, interactively or from a bindings file
 
 use anyhow::{bail, Result};
 use colored::Colorize;
+use serde::Serialize;
+use std::path::Path;
 
 use coven_grpc::ChannelConfig;
 use coven_proto::coven::{
-    admin_service_client::AdminServiceClient, CreateBindingRequest, DeleteBindingRequest,
-    ListBindingsRequest,
+    admin_service_client::AdminServiceClient, client_service_client::ClientServiceClient,
+    AgentInfo, Binding, CreateBindingRequest, DeleteBindingRequest, ListAgentsRequest,
+    ListBindingsRequest, ListRecentChannelsRequest, RecentChannel, UpdateBindingRequest,
 };
+use tonic::service::interceptor::InterceptedService;
+use tonic::transport::Channel;
 
-use super::BindingsCommand;
+use super::binding_file::{plan_import, BindingFile, BindingsFormat, PlannedAction, PlannedChange};
+use super::picker::{pick_or_enter, Choice, Prompter, TerminalPrompter};
+use super::principals::read_input;
+use super::{otp, BindingsCommand};
 use crate::client::AuthInterceptor;
+use crate::output::{print_json, OutputFormat, Table};
 
-pub async fn run(gateway: &str, token: Option<&str>, cmd: BindingsCommand) -> Result<()> {
+/// Columns of `bindings list` and `bindings create` with `--output table`
+pub const BINDING_COLUMNS: &[&str] = &["ID", "FRONTEND", "CHANNEL_ID", "AGENT_ID", "CREATED_AT"];
+
+/// Columns of `bindings import --output table`
+pub const IMPORT_COLUMNS: &[&str] = &[
+    "ACTION",
+    "FRONTEND",
+    "CHANNEL_ID",
+    "AGENT_ID",
+    "ID",
+    "CHANGES",
+    "RESULT",
+];
+
+type AdminClient = AdminServiceClient<InterceptedService<Channel, AuthInterceptor>>;
+
+/// Frontends offered by `bindings create --interactive`
+pub const KNOWN_FRONTENDS: &[&str] = &["slack", "telegram", "matrix"];
+
+/// How many recently active channels `bindings create --interactive` offers
+const RECENT_CHANNEL_LIMIT: i32 = 20;
+
+pub async fn run(
+    gateway: &str,
+    token: Option<&str>,
+    cmd: BindingsCommand,
+    output: OutputFormat,
+) -> Result<()> {
     let Some(token) = token else {
         bail!("Authentication required. Set COVEN_TOKEN environment variable or use --token flag.");
     };
 
     match cmd {
-        BindingsCommand::List => list_bindings(gateway, token).await,
+        BindingsCommand::List => list_bindings(gateway, token, output).await,
         BindingsCommand::Create {
             frontend,
             channel_id,
             agent_id,
-        } => create_binding(gateway, token, frontend, channel_id, agent_id).await,
-        BindingsCommand::Delete { id } => delete_binding(gateway, token, id).await,
+            interactive,
+        } => {
+            let request = if interactive {
+                let mut prompter = TerminalPrompter::new()?;
+                prompt_binding(
+                    gateway,
+                    token,
+                    &mut prompter,
+                    frontend,
+                    channel_id,
+                    agent_id,
+                )
+                .await?
+            } else {
+                let (Some(frontend), Some(channel_id), Some(agent_id)) =
+                    (frontend, channel_id, agent_id)
+                else {
+                    bail!("--frontend, --channel-id, and --agent-id are required without --interactive");
+                };
+                CreateBindingRequest {
+                    frontend,
+                    channel_id,
+                    agent_id,
+                }
+            };
+            create_binding(gateway, token, request, output).await
+        }
+        BindingsCommand::Delete { id, otp } => {
+            delete_binding(gateway, token, id, otp, output).await
+        }
+        BindingsCommand::Export { format } => export_bindings(gateway, token, format, output).await,
+        BindingsCommand::Import {
+            file,
+            format,
+            prune,
+            dry_run,
+            otp,
+        } => {
+            let format = format.unwrap_or_else(|| BindingsFormat::for_path(&file));
+            let options = ImportOptions {
+                prune,
+                dry_run,
+                otp,
+            };
+            import_bindings(gateway, token, &file, format, options, output).await
+        }
     }
 }
 
-async fn list_bindings(gateway: &str, token: &str) -> Result<()> {
+async fn list_bindings(gateway: &str, token: &str, output: OutputFormat) -> Result<()> {
     let config = ChannelConfig::new(gateway).without_keep_alive();
     let channel = coven_grpc::create_channel(&config).await?;
 
@@ -41,8 +121,17 @@ async fn list_bindings(gateway: &str, token: &str) -> Result<()> {
             frontend: None,
             agent_id: None,
         })
-        .await?;
-    let bindings = response.into_inner().bindings;
+        .await?
+        .into_inner();
+    match output {
+        OutputFormat::Json => return print_json(&response),
+        OutputFormat::Table => {
+            bindings_table(&response.bindings).print();
+            return Ok(());
+        }
+        OutputFormat::Text => {}
+    }
+    let bindings = response.bindings;
 
     if bindings.is_empty() {
         println!("{}", "No bindings configured".dimmed());
@@ -69,12 +158,121 @@ async fn list_bindings(gateway: &str, token: &str) -> Result<()> {
     Ok(())
 }
 
+/// Ask for whichever of frontend, channel, and agent weren't passed as
+/// flags: the frontend from `KNOWN_FRONTENDS`, the channel from unbound ones
+/// the gateway recently heard from, and the agent from the gateway's agents.
+/// Each menu also allows typing a value in.
+async fn prompt_binding(
+    gateway: &str,
+    token: &str,
+    prompter: &mut dyn Prompter,
+    frontend: Option<String>,
+    channel_id: Option<String>,
+    agent_id: Option<String>,
+) -> Result<CreateBindingRequest> {
+    let config = ChannelConfig::new(gateway).without_keep_alive();
+    let channel = coven_grpc::create_channel(&config).await?;
+    let interceptor = AuthInterceptor::new(Some(token.to_string()));
+
+    let frontend = match frontend {
+        Some(frontend) => frontend,
+        None => pick_or_enter(prompter, "Frontend", &frontend_choices())?,
+    };
+
+    let channel_id = match channel_id {
+        Some(channel_id) => channel_id,
+        None => {
+            let mut client =
+                AdminServiceClient::with_interceptor(channel.clone(), interceptor.clone());
+            let request = ListRecentChannelsRequest {
+                frontend: Some(frontend.clone()),
+                unbound_only: true,
+                limit: RECENT_CHANNEL_LIMIT,
+            };
+            // Older gateways don't record channels; typing the ID still works
+            let recent = match client.list_recent_channels(request).await {
+                Ok(response) => response.into_inner().channels,
+                Err(status) => {
+                    eprintln!(
+                        "{} {}",
+                        "Can't list recent channels:".yellow(),
+                        status.message()
+                    );
+                    Vec::new()
+                }
+            };
+            pick_or_enter(prompter, "Channel ID", &channel_choices(&recent))?
+        }
+    };
+
+    let agent_id = match agent_id {
+        Some(agent_id) => agent_id,
+        None => {
+            let mut client = ClientServiceClient::with_interceptor(channel, interceptor);
+            let agents = client
+                .list_agents(ListAgentsRequest { workspace: None })
+                .await?
+                .into_inner()
+                .agents;
+            pick_or_enter(prompter, "Agent", &agent_choices(&agents))?
+        }
+    };
+
+    Ok(CreateBindingRequest {
+        frontend,
+        channel_id,
+        agent_id,
+    })
+}
+
+fn frontend_choices() -> Vec<Choice<String>> {
+    KNOWN_FRONTENDS
+        .iter()
+        .map(|frontend| Choice::new(*frontend, frontend.to_string()))
+        .collect()
+}
+
+/// Menu of recent channels, labeled with their name when the frontend gave one
+pub fn channel_choices(channels: &[RecentChannel]) -> Vec<Choice<String>> {
+    channels
+        .iter()
+        .map(|channel| {
+            let label = match channel.display_name.as_deref() {
+                Some(name) if !name.is_empty() => format!(
+                    "{} ({}), last message {}",
+                    name, channel.channel_id, channel.last_message_at
+                ),
+                _ => format!(
+                    "{}, last message {}",
+                    channel.channel_id, channel.last_message_at
+                ),
+            };
+            Choice::new(label, channel.channel_id.clone())
+        })
+        .collect()
+}
+
+/// Menu of agents, connected ones first
+pub fn agent_choices(agents: &[AgentInfo]) -> Vec<Choice<String>> {
+    let mut agents: Vec<&AgentInfo> = agents.iter().collect();
+    agents.sort_by_key(|agent| !agent.connected);
+    agents
+        .into_iter()
+        .map(|agent| {
+            let status = if agent.connected { "" } else { ", offline" };
+            Choice::new(
+                format!("{} ({}{})", agent.name, agent.id, status),
+                agent.id.clone(),
+            )
+        })
+        .collect()
+}
+
 async fn create_binding(
     gateway: &str,
     token: &str,
-    frontend: String,
-    channel_id: String,
-    agent_id: String,
+    request: CreateBindingRequest,
+    output: OutputFormat,
 ) -> Result<()> {
     let config = ChannelConfig::new(gateway).without_keep_alive();
     let channel = coven_grpc::create_channel(&config).await?;
@@ -82,14 +280,16 @@ async fn create_binding(
     let interceptor = AuthInterceptor::new(Some(token.to_string()));
     let mut client = AdminServiceClient::with_interceptor(channel, interceptor);
 
-    let request = CreateBindingRequest {
-        frontend: frontend.clone(),
-        channel_id: channel_id.clone(),
-        agent_id: agent_id.clone(),
-    };
-
     let response = client.create_binding(request).await?;
     let binding = response.into_inner();
+    match output {
+        OutputFormat::Json => return print_json(&binding),
+        OutputFormat::Table => {
+            bindings_table(std::slice::from_ref(&binding)).print();
+            return Ok(());
+        }
+        OutputFormat::Text => {}
+    }
 
     println!("{}", "Binding created".green().bold());
     println!("  {}: {}", "ID".dimmed(), binding.id);
@@ -104,17 +304,441 @@ async fn create_binding(
     Ok(())
 }
 
-async fn delete_binding(gateway: &str, token: &str, id: String) -> Result<()> {
+async fn delete_binding(
+    gateway: &str,
+    token: &str,
+    id: String,
+    otp: Option<String>,
+    output: OutputFormat,
+) -> Result<()> {
     let config = ChannelConfig::new(gateway).without_keep_alive();
     let channel = coven_grpc::create_channel(&config).await?;
 
     let interceptor = AuthInterceptor::new(Some(token.to_string()));
-    let mut client = AdminServiceClient::with_interceptor(channel, interceptor);
+    let client = AdminServiceClient::with_interceptor(channel, interceptor);
 
     let request = DeleteBindingRequest { id: id.clone() };
-    client.delete_binding(request).await?;
+    let response = otp::send(otp.as_deref(), request, |request| {
+        let mut client = client.clone();
+        async move { client.delete_binding(request).await }
+    })
+    .await?
+    .into_inner();
 
-    println!("{} {}", "Binding deleted:".green().bold(), id);
+    match output {
+        OutputFormat::Json => print_json(&response)?,
+        OutputFormat::Table => Table::new(&["ID", "DELETED"])
+            .row([id, "true".to_string()])
+            .print(),
+        OutputFormat::Text => println!("{} {}", "Binding deleted:".green().bold(), id),
+    }
 
     Ok(())
 }
+
+async fn fetch_bindings(client: &mut AdminClient) -> Result<Vec<Binding>> {
+    let response = client
+        .list_bindings(ListBindingsRequest {
+            frontend: None,
+            agent_id: None,
+        })
+        .await?
+        .into_inner();
+    Ok(response.bindings)
+}
+
+async fn export_bindings(
+    gateway: &str,
+    token: &str,
+    format: BindingsFormat,
+    output: OutputFormat,
+) -> Result<()> {
+    let config = ChannelConfig::new(gateway).without_keep_alive();
+    let channel = coven_grpc::create_channel(&config).await?;
+    let interceptor = AuthInterceptor::new(Some(token.to_string()));
+    let mut client = AdminServiceClient::with_interceptor(channel, interceptor);
+
+    let bindings = fetch_bindings(&mut client).await?;
+    let file = BindingFile::from_bindings(&bindings);
+    match output {
+        OutputFormat::Json => print_json(&file)?,
+        OutputFormat::Table => bindings_table(&bindings).print(),
+        OutputFormat::Text => print!("{}", file.render(format)?),
+    }
+    Ok(())
+}
+
+/// Outcome of one planned change, as printed by `bindings import`
+#[derive(Debug, Clone, Serialize)]
+pub struct ImportResult {
+    pub action: &'static str,
+    pub frontend: String,
+    pub channel_id: String,
+    pub agent_id: String,
+    /// The matched, deleted, or newly created binding
+    pub id: Option<String>,
+    pub changes: Vec<String>,
+    /// "planned" on a dry run, then "ok" or "failed"
+    pub result: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl ImportResult {
+    fn planned(change: &PlannedChange) -> Self {
+        let (id, changes) = match &change.action {
+            PlannedAction::Create => (None, Vec::new()),
+            PlannedAction::Update { id, from_agent } => (
+                Some(id.clone()),
+                vec![format!("agent {} -> {}", from_agent, change.entry.agent_id)],
+            ),
+            PlannedAction::Unchanged { id } | PlannedAction::Delete { id } => {
+                (Some(id.clone()), Vec::new())
+            }
+        };
+        Self {
+            action: change.action.label(),
+            frontend: change.entry.frontend.clone(),
+            channel_id: change.entry.channel_id.clone(),
+            agent_id: change.entry.agent_id.clone(),
+            id,
+            changes,
+            result: "planned",
+            error: None,
+        }
+    }
+}
+
+/// How `bindings import` applies its plan
+struct ImportOptions {
+    prune: bool,
+    dry_run: bool,
+    /// One-time code sent with pruning deletes
+    otp: Option<String>,
+}
+
+async fn import_bindings(
+    gateway: &str,
+    token: &str,
+    path: &Path,
+    format: BindingsFormat,
+    options: ImportOptions,
+    output: OutputFormat,
+) -> Result<()> {
+    let ImportOptions {
+        prune,
+        dry_run,
+        otp,
+    } = options;
+    let file = BindingFile::parse(&read_input(path)?, format)?;
+
+    let config = ChannelConfig::new(gateway).without_keep_alive();
+    let channel = coven_grpc::create_channel(&config).await?;
+    let interceptor = AuthInterceptor::new(Some(token.to_string()));
+    let mut agents_client =
+        ClientServiceClient::with_interceptor(channel.clone(), interceptor.clone());
+    let mut client = AdminServiceClient::with_interceptor(channel, interceptor);
+
+    // Check every agent up front, so a typo doesn't leave half the file applied
+    let agents = agents_client
+        .list_agents(ListAgentsRequest { workspace: None })
+        .await?
+        .into_inner()
+        .agents;
+    let unknown = file.unknown_agents(&agents);
+    if !unknown.is_empty() {
+        bail!(
+            "bindings file routes to unknown agents: {}; nothing was changed",
+            unknown.join(", ")
+        );
+    }
+
+    let existing = fetch_bindings(&mut client).await?;
+    let plan = plan_import(&existing, &file.bindings, prune);
+
+    let mut results: Vec<ImportResult> = plan.iter().map(ImportResult::planned).collect();
+    if !dry_run {
+        // One RPC per change; a failure is recorded and the import goes on
+        for (change, result) in plan.iter().zip(results.iter_mut()) {
+            match apply_change(&mut client, change, otp.as_deref()).await {
+                Ok(id) => {
+                    result.id = id.or(result.id.take());
+                    result.result = "ok";
+                }
+                Err(status) => {
+                    result.result = "failed";
+                    result.error = Some(status.message().to_string());
+                }
+            }
+        }
+    }
+
+    match output {
+        OutputFormat::Json => print_json(&results)?,
+        OutputFormat::Table => import_table(&results).print(),
+        OutputFormat::Text => print_import(&results, dry_run),
+    }
+
+    let failed = results.iter().filter(|r| r.result == "failed").count();
+    if failed > 0 {
+        bail!("{} of {} binding changes failed", failed, results.len());
+    }
+    Ok(())
+}
+
+/// Carry out one planned change, returning the ID of a created binding.
+async fn apply_change(
+    client: &mut AdminClient,
+    change: &PlannedChange,
+    otp: Option<&str>,
+) -> Result<Option<String>, tonic::Status> {
+    let entry = &change.entry;
+    match &change.action {
+        PlannedAction::Create => {
+            let binding = client
+                .create_binding(CreateBindingRequest {
+                    frontend: entry.frontend.clone(),
+                    channel_id: entry.channel_id.clone(),
+                    agent_id: entry.agent_id.clone(),
+                })
+                .await?
+                .into_inner();
+            Ok(Some(binding.id))
+        }
+        PlannedAction::Update { id, .. } => {
+            client
+                .update_binding(UpdateBindingRequest {
+                    id: id.clone(),
+                    agent_id: entry.agent_id.clone(),
+                })
+                .await?;
+            Ok(None)
+        }
+        PlannedAction::Delete { id } => {
+            let request = otp::request(DeleteBindingRequest { id: id.clone() }, otp)
+                .map_err(|e| tonic::Status::invalid_argument(e.to_string()))?;
+            client.delete_binding(request).await?;
+            Ok(None)
+        }
+        PlannedAction::Unchanged { .. } => Ok(None),
+    }
+}
+
+fn print_import(results: &[ImportResult], dry_run: bool) {
+    if dry_run {
+        println!("{}", "Dry run: nothing was changed".yellow().bold());
+    }
+    for r in results {
+        let padded = format!("{:<7}", r.action);
+        let action = match r.action {
+            "create" => padded.green(),
+            "update" => padded.yellow(),
+            "delete" => padded.red(),
+            _ => padded.dimmed(),
+        };
+        let outcome = match (r.result, &r.error) {
+            ("failed", Some(e)) => format!("failed: {}", e).red(),
+            ("ok", _) => "ok".green(),
+            _ => "".normal(),
+        };
+        let id =
+            r.id.as_deref()
+                .map(|id| format!("({})", id))
+                .unwrap_or_default();
+        println!(
+            "{} {} → {} {} {}",
+            action,
+            format!("{}:{}", r.frontend, r.channel_id).bold(),
+            r.agent_id,
+            id.dimmed(),
+            outcome
+        );
+        for change in &r.changes {
+            println!("        {}", change.dimmed());
+        }
+    }
+
+    let count = |action: &str| results.iter().filter(|r| r.action == action).count();
+    println!();
+    if dry_run {
+        println!(
+            "{} to create, {} to update, {} to delete, {} unchanged",
+            count("create"),
+            count("update"),
+            count("delete"),
+            count("no-op")
+        );
+    } else {
+        let failed = results.iter().filter(|r| r.result == "failed").count();
+        println!(
+            "{} created, {} updated, {} deleted, {} unchanged, {} failed",
+            count("create"),
+            count("update"),
+            count("delete"),
+            count("no-op"),
+            failed
+        );
+    }
+}
+
+pub fn import_table(results: &[ImportResult]) -> Table {
+    results.iter().fold(Table::new(IMPORT_COLUMNS), |table, r| {
+        let result = match &r.error {
+            Some(e) => format!("{}: {}", r.result, e),
+            None => r.result.to_string(),
+        };
+        table.row([
+            r.action.to_string(),
+            r.frontend.clone(),
+            r.channel_id.clone(),
+            r.agent_id.clone(),
+            r.id.clone().unwrap_or_default(),
+            r.changes.join("; "),
+            result,
+        ])
+    })
+}
+
+pub fn bindings_table(bindings: &[Binding]) -> Table {
+    bindings
+        .iter()
+        .fold(Table::new(BINDING_COLUMNS), |table, binding| {
+            table.row([
+                binding.id.as_str(),
+                binding.frontend.as_str(),
+                binding.channel_id.as_str(),
+                binding.agent_id.as_str(),
+                binding.created_at.as_str(),
+            ])
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::binding_file::BindingEntry;
+    use super::super::picker::testing::{Answer, ScriptedPrompter};
+    use super::super::picker::{pick_or_enter, ENTER_MANUALLY};
+    use super::*;
+
+    fn agent(id: &str, name: &str, connected: bool) -> AgentInfo {
+        AgentInfo {
+            id: id.to_string(),
+            name: name.to_string(),
+            connected,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_agent_choices_list_connected_first() {
+        let choices = agent_choices(&[
+            agent("agent-1", "archive", false),
+            agent("agent-2", "builder", true),
+        ]);
+        assert_eq!(
+            choices,
+            vec![
+                Choice::new("builder (agent-2)", "agent-2".to_string()),
+                Choice::new("archive (agent-1, offline)", "agent-1".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_channel_choices() {
+        let channels = [
+            RecentChannel {
+                frontend: "slack".to_string(),
+                channel_id: "C0123".to_string(),
+                last_message_at: "2026-01-02T03:04:05Z".to_string(),
+                display_name: Some("#ops".to_string()),
+                bound_agent_id: None,
+            },
+            RecentChannel {
+                frontend: "slack".to_string(),
+                channel_id: "C0456".to_string(),
+                last_message_at: "2026-01-01T00:00:00Z".to_string(),
+                display_name: None,
+                bound_agent_id: None,
+            },
+        ];
+        let mut prompter = ScriptedPrompter::new([Answer::Select(1)]);
+        let picked = pick_or_enter(&mut prompter, "Channel ID", &channel_choices(&channels));
+
+        assert_eq!(picked.unwrap(), "C0456");
+        assert_eq!(
+            prompter.menus[0].1,
+            vec![
+                "#ops (C0123), last message 2026-01-02T03:04:05Z".to_string(),
+                "C0456, last message 2026-01-01T00:00:00Z".to_string(),
+                ENTER_MANUALLY.to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_frontend_choices_allow_others() {
+        let mut prompter = ScriptedPrompter::new([
+            Answer::Select(KNOWN_FRONTENDS.len()),
+            Answer::Input("discord"),
+        ]);
+        let picked = pick_or_enter(&mut prompter, "Frontend", &frontend_choices());
+        assert_eq!(picked.unwrap(), "discord");
+    }
+
+    #[test]
+    fn test_bindings_table() {
+        let bindings = [Binding {
+            id: "b-1".to_string(),
+            frontend: "slack".to_string(),
+            channel_id: "C0123".to_string(),
+            agent_id: "agent-1".to_string(),
+            created_at: "2026-01-02T03:04:05Z".to_string(),
+            created_by: None,
+        }];
+        assert_eq!(
+            bindings_table(&bindings).render(),
+            "ID   FRONTEND  CHANNEL_ID  AGENT_ID  CREATED_AT\n\
+             b-1  slack     C0123       agent-1   2026-01-02T03:04:05Z\n"
+        );
+    }
+
+    #[test]
+    fn test_import_table_reports_each_change() {
+        let change = |entry: (&str, &str, &str), action| PlannedChange {
+            entry: BindingEntry {
+                frontend: entry.0.to_string(),
+                channel_id: entry.1.to_string(),
+                agent_id: entry.2.to_string(),
+            },
+            action,
+        };
+        let mut failed =
+            ImportResult::planned(&change(("slack", "C1", "agent-2"), PlannedAction::Create));
+        failed.result = "failed";
+        failed.error = Some("agent not found".to_string());
+        let results = [
+            ImportResult::planned(&change(
+                ("slack", "C2", "agent-2"),
+                PlannedAction::Update {
+                    id: "b-2".to_string(),
+                    from_agent: "agent-1".to_string(),
+                },
+            )),
+            failed,
+            ImportResult::planned(&change(
+                ("matrix", "C3", "agent-1"),
+                PlannedAction::Delete {
+                    id: "b-3".to_string(),
+                },
+            )),
+        ];
+        assert_eq!(
+            import_table(&results).render(),
+            "ACTION  FRONTEND  CHANNEL_ID  AGENT_ID  ID   CHANGES                   RESULT\n\
+             update  slack     C2          agent-2   b-2  agent agent-1 -> agent-2  planned\n\
+             create  slack     C1          agent-2   -    -                         failed: agent not found\n\
+             delete  matrix    C3          agent-1   b-3  -                         planned\n"
+        );
+    }
+}
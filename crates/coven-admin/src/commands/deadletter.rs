@@ -0,0 +1,231 @@
+// ABOUTME: Implementation of 'coven-admin deadletter' commands
+// ABOUTME: Lists, replays, and purges messages queued for offline agents
+
+use anyhow::{bail, Result};
+use colored::Colorize;
+
+use coven_grpc::ChannelConfig;
+use coven_proto::coven::{
+    admin_service_client::AdminServiceClient, DeadLetter, ListDeadLettersRequest,
+    PurgeDeadLettersRequest, ReplayDeadLettersRequest,
+};
+use tonic::service::interceptor::InterceptedService;
+use tonic::transport::Channel;
+
+use super::{otp, DeadletterCommand};
+use crate::client::AuthInterceptor;
+use crate::output::{print_json, OutputFormat, Table};
+
+/// Longest content preview shown by `list`
+const PREVIEW_CHARS: usize = 60;
+
+/// Columns of `deadletter list --output table`
+pub const DEADLETTER_COLUMNS: &[&str] = &[
+    "ID",
+    "AGENT_ID",
+    "SENDER",
+    "CREATED_AT",
+    "EXPIRES_AT",
+    "CONTENT",
+];
+
+pub async fn run(
+    gateway: &str,
+    token: Option<&str>,
+    cmd: DeadletterCommand,
+    output: OutputFormat,
+) -> Result<()> {
+    // The dead-letter queue lives in the local gateway, which has no auth,
+    // so the token is only sent when one is configured
+    let mut client = connect(gateway, token).await?;
+
+    match cmd {
+        DeadletterCommand::List { agent } => list(&mut client, agent, output).await,
+        DeadletterCommand::Replay { agent } => replay(&mut client, agent, output).await,
+        DeadletterCommand::Purge { agent, all, otp } => {
+            if agent.is_none() && !all {
+                bail!("Specify --agent <id> or --all to choose what to purge.");
+            }
+            purge(&client, agent, otp, output).await
+        }
+    }
+}
+
+type Client = AdminServiceClient<InterceptedService<Channel, AuthInterceptor>>;
+
+async fn connect(gateway: &str, token: Option<&str>) -> Result<Client> {
+    let config = ChannelConfig::new(gateway).without_keep_alive();
+    let channel = coven_grpc::create_channel(&config).await?;
+
+    let interceptor = AuthInterceptor::new(token.map(String::from));
+    Ok(AdminServiceClient::with_interceptor(channel, interceptor))
+}
+
+async fn list(client: &mut Client, agent: Option<String>, output: OutputFormat) -> Result<()> {
+    let response = client
+        .list_dead_letters(ListDeadLettersRequest { agent_id: agent })
+        .await?
+        .into_inner();
+    match output {
+        OutputFormat::Json => return print_json(&response),
+        OutputFormat::Table => {
+            dead_letters_table(&response.dead_letters).print();
+            return Ok(());
+        }
+        OutputFormat::Text => {}
+    }
+    let letters = response.dead_letters;
+
+    if letters.is_empty() {
+        println!("{}", "No queued messages".dimmed());
+        return Ok(());
+    }
+
+    println!("{}", format!("Queued Messages ({})", letters.len()).bold());
+    println!();
+
+    for letter in letters {
+        let from = letter.sender_display.as_deref().unwrap_or(&letter.sender);
+        println!(
+            "{} {} → {}",
+            "●".yellow(),
+            from.bold(),
+            letter.agent_id.green()
+        );
+        println!("    {}: {}", "ID".dimmed(), letter.id);
+        println!("    {}: {}", "Queued".dimmed(), letter.created_at);
+        println!("    {}: {}", "Expires".dimmed(), letter.expires_at);
+        println!("    {}", preview(&letter.content).dimmed());
+        println!();
+    }
+
+    Ok(())
+}
+
+async fn replay(client: &mut Client, agent: String, output: OutputFormat) -> Result<()> {
+    let response = client
+        .replay_dead_letters(ReplayDeadLettersRequest {
+            agent_id: agent.clone(),
+        })
+        .await?
+        .into_inner();
+    match output {
+        OutputFormat::Json => return print_json(&response),
+        OutputFormat::Table => {
+            Table::new(&["AGENT_ID", "DELIVERED", "REMAINING"])
+                .row([
+                    agent,
+                    response.delivered.to_string(),
+                    response.remaining.to_string(),
+                ])
+                .print();
+            return Ok(());
+        }
+        OutputFormat::Text => {}
+    }
+
+    println!(
+        "{} {} message(s) to {}",
+        "Delivered".green().bold(),
+        response.delivered,
+        agent
+    );
+    if response.remaining > 0 {
+        println!(
+            "  {} message(s) still queued (agent disconnected during replay)",
+            response.remaining
+        );
+    }
+
+    Ok(())
+}
+
+async fn purge(
+    client: &Client,
+    agent: Option<String>,
+    otp: Option<String>,
+    output: OutputFormat,
+) -> Result<()> {
+    let request = PurgeDeadLettersRequest {
+        agent_id: agent.clone(),
+    };
+    let response = otp::send(otp.as_deref(), request, |request| {
+        let mut client = client.clone();
+        async move { client.purge_dead_letters(request).await }
+    })
+    .await?
+    .into_inner();
+    match output {
+        OutputFormat::Json => return print_json(&response),
+        OutputFormat::Table => {
+            // An empty agent cell prints as "-": every agent was purged
+            Table::new(&["AGENT_ID", "PURGED"])
+                .row([agent.unwrap_or_default(), response.purged.to_string()])
+                .print();
+            return Ok(());
+        }
+        OutputFormat::Text => {}
+    }
+
+    let scope = agent.unwrap_or_else(|| "all agents".to_string());
+    println!(
+        "{} {} queued message(s) for {}",
+        "Purged".green().bold(),
+        response.purged,
+        scope
+    );
+
+    Ok(())
+}
+
+pub fn dead_letters_table(letters: &[DeadLetter]) -> Table {
+    letters
+        .iter()
+        .fold(Table::new(DEADLETTER_COLUMNS), |table, letter| {
+            table.row([
+                letter.id.clone(),
+                letter.agent_id.clone(),
+                letter
+                    .sender_display
+                    .clone()
+                    .unwrap_or_else(|| letter.sender.clone()),
+                letter.created_at.clone(),
+                letter.expires_at.clone(),
+                preview(&letter.content),
+            ])
+        })
+}
+
+/// Single-line preview of message content
+fn preview(content: &str) -> String {
+    let line = content.lines().next().unwrap_or_default();
+    if line.chars().count() > PREVIEW_CHARS || content.lines().nth(1).is_some() {
+        let truncated: String = line.chars().take(PREVIEW_CHARS).collect();
+        format!("{}…", truncated)
+    } else {
+        line.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dead_letters_table() {
+        let letters = [DeadLetter {
+            id: "dl-1".to_string(),
+            agent_id: "agent-1".to_string(),
+            sender: "U123".to_string(),
+            sender_display: Some("Alice".to_string()),
+            content: "deploy the\nstaging branch".to_string(),
+            created_at: "2026-01-02T03:04:05Z".to_string(),
+            expires_at: "2026-01-09T03:04:05Z".to_string(),
+        }];
+        assert_eq!(
+            dead_letters_table(&letters).render(),
+            "ID    AGENT_ID  SENDER  CREATED_AT            EXPIRES_AT            CONTENT\n\
+             dl-1  agent-1   Alice   2026-01-02T03:04:05Z  2026-01-09T03:04:05Z  deploy the…\n"
+        );
+    }
+}
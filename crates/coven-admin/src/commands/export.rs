@@ -0,0 +1,219 @@
+// ABOUTME: Implementation of 'coven-admin export'
+// ABOUTME: Writes the gateway's stored conversations to a JSON Lines file, with a running count on stderr
+
+use anyhow::{anyhow, bail, Context, Result};
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use colored::Colorize;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::io::{IsTerminal, Write};
+use std::path::{Path, PathBuf};
+
+use coven_grpc::ChannelConfig;
+use coven_proto::coven::{admin_service_client::AdminServiceClient, ExportThreadsRequest};
+
+use crate::client::AuthInterceptor;
+use crate::output::{print_json, OutputFormat, Table};
+
+/// What `export` asks the gateway for
+pub struct ExportOptions {
+    pub agent: Option<String>,
+    /// Age ("30d") or time, as `parse_since` takes them
+    pub since: Option<String>,
+    pub until: Option<String>,
+    pub state: Option<String>,
+    /// None writes to stdout
+    pub out: Option<PathBuf>,
+}
+
+/// Records written, by type
+#[derive(Debug, Default, Serialize)]
+struct Summary {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    path: Option<PathBuf>,
+    records: u64,
+    bytes: u64,
+    by_type: BTreeMap<String, u64>,
+}
+
+#[derive(Deserialize)]
+struct RecordType {
+    #[serde(rename = "type")]
+    kind: String,
+}
+
+pub async fn run(
+    gateway: &str,
+    token: Option<&str>,
+    options: ExportOptions,
+    output: OutputFormat,
+) -> Result<()> {
+    let now = Utc::now();
+    let request = ExportThreadsRequest {
+        agent_id: options.agent,
+        since: options
+            .since
+            .map(|s| parse_since(&s, now).map(|t| t.to_rfc3339()))
+            .transpose()?,
+        until: options
+            .until
+            .map(|s| parse_since(&s, now).map(|t| t.to_rfc3339()))
+            .transpose()?,
+        state: options.state,
+    };
+
+    let config = ChannelConfig::new(gateway).without_keep_alive();
+    let channel = coven_grpc::create_channel(&config).await?;
+    let interceptor = AuthInterceptor::new(token.map(String::from));
+    let mut client = AdminServiceClient::with_interceptor(channel, interceptor);
+    let mut stream = client.export_threads(request).await?.into_inner();
+
+    // Write next to the destination and move it into place once complete,
+    // so a failed export never leaves a truncated dump behind
+    let partial = options.out.as_deref().map(partial_path);
+    let mut writer: Box<dyn Write> = match &partial {
+        Some(path) => Box::new(std::io::BufWriter::new(
+            std::fs::File::create(path)
+                .with_context(|| format!("failed to create {}", path.display()))?,
+        )),
+        None => Box::new(std::io::BufWriter::new(std::io::stdout().lock())),
+    };
+    let progress = options.out.is_some() && std::io::stderr().is_terminal();
+
+    let mut summary = Summary::default();
+    let result: Result<()> = async {
+        while let Some(record) = stream.message().await? {
+            let kind = serde_json::from_str::<RecordType>(&record.json)
+                .map(|r| r.kind)
+                .map_err(|e| anyhow!("gateway sent a malformed record: {}", e))?;
+            writeln!(writer, "{}", record.json)?;
+            summary.records += 1;
+            summary.bytes += record.json.len() as u64 + 1;
+            *summary.by_type.entry(kind).or_default() += 1;
+            if progress && summary.records % 100 == 0 {
+                eprint!("\r{}", progress_line(&summary).dimmed());
+            }
+        }
+        writer.flush()?;
+        Ok(())
+    }
+    .await;
+    drop(writer);
+    if progress {
+        eprint!("\r\x1b[K");
+    }
+    if let Err(e) = result {
+        if let Some(partial) = &partial {
+            let _ = std::fs::remove_file(partial);
+        }
+        return Err(e);
+    }
+    if let (Some(partial), Some(out)) = (&partial, &options.out) {
+        std::fs::rename(partial, out)
+            .with_context(|| format!("failed to move the export to {}", out.display()))?;
+    }
+
+    summary.path = options.out;
+    // With the dump on stdout, keep the summary off it
+    if summary.path.is_none() {
+        eprintln!("{}", progress_line(&summary).dimmed());
+        return Ok(());
+    }
+    match output {
+        OutputFormat::Json => print_json(&summary)?,
+        OutputFormat::Table => {
+            let mut table = Table::new(&["TYPE", "RECORDS"]);
+            for (kind, count) in &summary.by_type {
+                table = table.row([kind.clone(), count.to_string()]);
+            }
+            table.print();
+        }
+        OutputFormat::Text => {
+            let path = summary.path.as_deref().unwrap_or(Path::new("-"));
+            println!(
+                "{} {} to {}",
+                "Exported".green().bold(),
+                progress_line(&summary),
+                path.display()
+            );
+        }
+    }
+    Ok(())
+}
+
+fn progress_line(summary: &Summary) -> String {
+    let threads = summary.by_type.get("thread").copied().unwrap_or(0);
+    let messages = summary.by_type.get("message").copied().unwrap_or(0);
+    format!(
+        "{} thread(s), {} message(s), {} record(s), {} bytes",
+        threads, messages, summary.records, summary.bytes
+    )
+}
+
+fn partial_path(out: &Path) -> PathBuf {
+    let mut name = out.file_name().unwrap_or_default().to_os_string();
+    name.push(".partial");
+    out.with_file_name(name)
+}
+
+/// A point in time: an age before `now` ("90m", "12h", "30d", "2w"), a
+/// date ("2026-01-31", midnight UTC), or an RFC 3339 timestamp
+pub fn parse_since(value: &str, now: DateTime<Utc>) -> Result<DateTime<Utc>> {
+    let value = value.trim();
+    if let Ok(time) = DateTime::parse_from_rfc3339(value) {
+        return Ok(time.with_timezone(&Utc));
+    }
+    if let Ok(date) = NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+        return Ok(date
+            .and_hms_opt(0, 0, 0)
+            .expect("midnight exists")
+            .and_utc());
+    }
+    let split = value.char_indices().last().map_or(0, |(i, _)| i);
+    let (count, unit) = value.split_at(split);
+    let Ok(count) = count.parse::<i64>() else {
+        bail!(
+            "invalid time {:?}: expected an age like 30d or 12h, a date, or an RFC 3339 timestamp",
+            value
+        );
+    };
+    let age = match unit {
+        "m" => Duration::try_minutes(count),
+        "h" => Duration::try_hours(count),
+        "d" => Duration::try_days(count),
+        "w" => Duration::try_weeks(count),
+        _ => bail!("invalid time {:?}: age units are m, h, d and w", value),
+    }
+    .ok_or_else(|| anyhow!("invalid time {:?}: too far back", value))?;
+    Ok(now - age)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_since() {
+        let now = DateTime::parse_from_rfc3339("2026-03-31T12:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let at = |s: &str| parse_since(s, now).unwrap().to_rfc3339();
+        assert_eq!(at("30d"), "2026-03-01T12:00:00+00:00");
+        assert_eq!(at("12h"), "2026-03-31T00:00:00+00:00");
+        assert_eq!(at("90m"), "2026-03-31T10:30:00+00:00");
+        assert_eq!(at("1w"), "2026-03-24T12:00:00+00:00");
+        assert_eq!(at("2026-01-31"), "2026-01-31T00:00:00+00:00");
+        assert_eq!(at("2026-02-01T08:00:00+02:00"), "2026-02-01T06:00:00+00:00");
+        for bad in ["", "d", "30", "30y", "30é", "soon"] {
+            assert!(parse_since(bad, now).is_err(), "{bad:?}");
+        }
+    }
+
+    #[test]
+    fn test_partial_path() {
+        assert_eq!(
+            partial_path(Path::new("/tmp/dump.jsonl")),
+            Path::new("/tmp/dump.jsonl.partial")
+        );
+    }
+}
@@ -7,9 +7,11 @@ use colored::Colorize;
 use coven_grpc::ChannelConfig;
 use coven_proto::coven::client_service_client::ClientServiceClient;
 
+use super::principals::PRINCIPAL_COLUMNS;
 use crate::client::AuthInterceptor;
+use crate::output::{print_json, OutputFormat, Table};
 
-pub async fn run(gateway: &str, token: Option<&str>) -> Result<()> {
+pub async fn run(gateway: &str, token: Option<&str>, output: OutputFormat) -> Result<()> {
     let Some(token) = token else {
         bail!("Authentication required. Set COVEN_TOKEN environment variable or use --token flag.");
     };
@@ -22,6 +24,22 @@ pub async fn run(gateway: &str, token: Option<&str>) -> Result<()> {
 
     let response = client.get_me(()).await?;
     let me = response.into_inner();
+    match output {
+        OutputFormat::Json => return print_json(&me),
+        OutputFormat::Table => {
+            Table::new(PRINCIPAL_COLUMNS)
+                .row([
+                    me.principal_id.clone(),
+                    me.principal_type.clone(),
+                    me.display_name.clone(),
+                    me.status.clone(),
+                    me.roles.join(","),
+                ])
+                .print();
+            return Ok(());
+        }
+        OutputFormat::Text => {}
+    }
 
     println!("{}", "Principal Info".bold());
     println!("  {}:        {}", "ID".dimmed(), me.principal_id);
@@ -2,12 +2,28 @@
 // ABOUTME: Defines subcommands for admin operations
 
 use clap::{Parser, Subcommand};
+use std::path::PathBuf;
+
+use crate::output::OutputFormat;
+use binding_file::BindingsFormat;
 
 pub mod agents;
+pub mod binding_file;
 pub mod bindings;
+pub mod deadletter;
+pub mod export;
 pub mod me;
+pub mod otp;
+pub mod packs;
+pub mod pair;
+pub mod picker;
+pub mod principal_file;
 pub mod principals;
+pub mod secrets;
+pub mod tail;
 pub mod token;
+pub mod usage;
+pub mod version;
 
 #[derive(Parser)]
 #[command(name = "coven-admin", about = "Admin CLI for coven-gateway")]
@@ -22,6 +38,10 @@ pub struct Cli {
     /// JWT authentication token
     #[arg(long, global = true, env = "COVEN_TOKEN")]
     pub token: Option<String>,
+
+    /// Output format: colored text, JSON for scripts, or aligned columns
+    #[arg(long, global = true, value_enum, default_value_t = OutputFormat::Text)]
+    pub output: OutputFormat,
 }
 
 #[derive(Subcommand)]
@@ -44,6 +64,72 @@ pub enum Command {
     /// Manage tokens
     #[command(subcommand)]
     Token(TokenCommand),
+
+    /// Manage messages queued for offline agents
+    #[command(subcommand)]
+    Deadletter(DeadletterCommand),
+
+    /// Inspect connected tool packs
+    #[command(subcommand)]
+    Packs(PacksCommand),
+
+    /// Manage secrets the gateway hands to tool packs
+    #[command(subcommand)]
+    Secrets(SecretsCommand),
+
+    /// Approve devices linking with a pairing code
+    #[command(subcommand)]
+    Pair(PairCommand),
+
+    /// Set up one-time codes, which gateways may require before deleting
+    #[command(subcommand)]
+    Otp(OtpCommand),
+
+    /// Follow all traffic through the gateway: inbound messages, where they
+    /// were routed, responses and errors (Ctrl+C to exit). The gateway must
+    /// have tailing enabled.
+    Tail {
+        /// Only show traffic to and from this agent
+        #[arg(long)]
+        agent: Option<String>,
+
+        /// Show metadata only (ids, sizes, timing), never message content
+        #[arg(long)]
+        redact: bool,
+    },
+
+    /// Show the tokens and cost agents have spent today against their
+    /// budgets (`[mux.budget]` in the agent's config)
+    Usage {
+        /// Only this agent (default: every connected agent)
+        #[arg(long)]
+        agent: Option<String>,
+    },
+
+    /// Export stored conversations as JSON Lines: each thread, then its
+    /// messages, tool calls and token usage
+    Export {
+        /// Only this agent's threads
+        #[arg(long)]
+        agent: Option<String>,
+
+        /// Leave out records older than this: an age (30d, 12h, 90m, 2w),
+        /// a date (2026-01-31) or an RFC 3339 timestamp
+        #[arg(long)]
+        since: Option<String>,
+
+        /// Leave out records from this time on, in the same forms as --since
+        #[arg(long)]
+        until: Option<String>,
+
+        /// Only threads in this state
+        #[arg(long, value_parser = ["open", "answered", "empty"])]
+        state: Option<String>,
+
+        /// File to write (default: stdout)
+        #[arg(long, short)]
+        out: Option<PathBuf>,
+    },
 }
 
 #[derive(Subcommand)]
@@ -53,6 +139,37 @@ pub enum AgentsCommand {
         /// Filter by workspace path
         #[arg(long)]
         workspace: Option<String>,
+
+        /// Keep the list on screen, redrawing it as agents connect,
+        /// disconnect, or change presence (Ctrl+C to exit)
+        #[arg(long)]
+        watch: bool,
+
+        /// Seconds between refreshes with --watch, for gateways that can't
+        /// push agent changes
+        #[arg(long, value_name = "SECS", default_value_t = 2, requires = "watch")]
+        interval: u64,
+    },
+
+    /// Show one agent in depth: metadata, connection, work in progress,
+    /// queue, and recent activity
+    #[command(visible_alias = "inspect")]
+    Show {
+        /// Agent ID
+        agent_id: String,
+
+        /// How many recent thread activities to show
+        #[arg(long, default_value_t = 10)]
+        activity: i32,
+    },
+
+    /// Change a connected agent's log filter without restarting it
+    SetLogLevel {
+        /// Agent ID
+        agent_id: String,
+
+        /// Filter in RUST_LOG syntax, e.g. "debug" or "coven_agent=trace,warn"
+        filter: String,
     },
 }
 
@@ -64,22 +181,61 @@ pub enum BindingsCommand {
     /// Create a binding
     Create {
         /// Frontend identifier (e.g., "slack", "matrix")
-        #[arg(long)]
-        frontend: String,
+        #[arg(long, required_unless_present = "interactive")]
+        frontend: Option<String>,
 
         /// Channel ID from the frontend
-        #[arg(long)]
-        channel_id: String,
+        #[arg(long, required_unless_present = "interactive")]
+        channel_id: Option<String>,
 
         /// Agent ID to route messages to
-        #[arg(long)]
-        agent_id: String,
+        #[arg(long, required_unless_present = "interactive")]
+        agent_id: Option<String>,
+
+        /// Pick whichever of the above weren't given from menus
+        #[arg(long, short = 'i')]
+        interactive: bool,
     },
 
     /// Delete a binding
     Delete {
         /// Binding ID to delete
         id: String,
+
+        /// One-time code, for gateways that require one before deleting
+        /// (asked for on the terminal if needed and not given)
+        #[arg(long, value_name = "CODE")]
+        otp: Option<String>,
+    },
+
+    /// Print all bindings as a bindings file
+    Export {
+        /// File format
+        #[arg(long, value_enum, default_value_t = BindingsFormat::Toml)]
+        format: BindingsFormat,
+    },
+
+    /// Make the gateway's bindings match a bindings file
+    Import {
+        /// Bindings file to import ("-" for stdin)
+        file: PathBuf,
+
+        /// File format (default: JSON for .json files, TOML otherwise)
+        #[arg(long, value_enum)]
+        format: Option<BindingsFormat>,
+
+        /// Also delete bindings the file doesn't list
+        #[arg(long)]
+        prune: bool,
+
+        /// Print the planned creates, updates, and deletes without making them
+        #[arg(long)]
+        dry_run: bool,
+
+        /// One-time code for the deletions of --prune, for gateways that
+        /// require one
+        #[arg(long, value_name = "CODE", requires = "prune")]
+        otp: Option<String>,
     },
 }
 
@@ -94,6 +250,24 @@ pub enum TokenCommand {
         #[arg(long, default_value = "2592000")]
         ttl: i64,
     },
+
+    /// List active tokens with when they were issued and last used
+    List {
+        /// Only show tokens for this principal
+        #[arg(long)]
+        principal_id: Option<String>,
+    },
+
+    /// Revoke a token; the gateway rejects it from the next call on
+    Revoke {
+        /// Token ID to revoke (from `token list`)
+        token_id: String,
+
+        /// One-time code, for gateways that require one before deleting
+        /// (asked for on the terminal if needed and not given)
+        #[arg(long, value_name = "CODE")]
+        otp: Option<String>,
+    },
 }
 
 #[derive(Subcommand)]
@@ -128,5 +302,139 @@ pub enum PrincipalsCommand {
     Delete {
         /// Principal ID to delete
         id: String,
+
+        /// One-time code, for gateways that require one before deleting
+        /// (asked for on the terminal if needed and not given)
+        #[arg(long, value_name = "CODE")]
+        otp: Option<String>,
+    },
+
+    /// Register a new SSH key for a principal; its current keys keep working
+    /// for a grace period
+    Rotate {
+        /// Principal ID
+        id: String,
+
+        /// Fingerprint of the new public key (SHA256 hex)
+        #[arg(long)]
+        fingerprint: String,
+
+        /// Seconds the current keys keep working (defaults to the gateway's)
+        #[arg(long, value_name = "SECS")]
+        grace_period: Option<u32>,
+    },
+
+    /// Print all principals as a YAML principals file
+    Export,
+
+    /// Create or update principals from a YAML principals file
+    Import {
+        /// Principals file to import ("-" for stdin)
+        file: PathBuf,
+
+        /// Print the planned creates and updates without making them
+        #[arg(long)]
+        dry_run: bool,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum PairCommand {
+    /// Approve the device showing `code` (from `coven link --pair`)
+    Approve {
+        /// Pairing code shown by the device, e.g. K7QM-3XWD
+        code: String,
+
+        /// Principal the device joins, taking its roles; one the gateway
+        /// doesn't know yet is created
+        #[arg(long)]
+        principal: String,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum PacksCommand {
+    /// List connected packs and their health
+    List,
+}
+
+#[derive(Subcommand)]
+pub enum SecretsCommand {
+    /// Set a pack secret, replacing any existing value
+    Set {
+        /// Pack ID the secret belongs to
+        pack_id: String,
+
+        /// Secret name (letters, digits, '_', '-', '.')
+        key: String,
+
+        /// Secret value (read from stdin if omitted)
+        value: Option<String>,
+    },
+
+    /// List a pack's secret names (values are never shown)
+    List {
+        /// Pack ID to list secrets for
+        pack_id: String,
+    },
+
+    /// Delete a pack secret
+    Delete {
+        /// Pack ID the secret belongs to
+        pack_id: String,
+
+        /// Secret name
+        key: String,
+
+        /// One-time code, for gateways that require one before deleting
+        /// (asked for on the terminal if needed and not given)
+        #[arg(long, value_name = "CODE")]
+        otp: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum DeadletterCommand {
+    /// List queued messages
+    List {
+        /// Only show messages for this agent
+        #[arg(long)]
+        agent: Option<String>,
+    },
+
+    /// Deliver an agent's queued messages now
+    Replay {
+        /// Agent ID to deliver to (must be connected)
+        agent: String,
+    },
+
+    /// Discard queued messages
+    Purge {
+        /// Only purge messages for this agent
+        #[arg(long, conflicts_with = "all")]
+        agent: Option<String>,
+
+        /// Purge messages for every agent
+        #[arg(long)]
+        all: bool,
+
+        /// One-time code, for gateways that require one before deleting
+        /// (asked for on the terminal if needed and not given)
+        #[arg(long, value_name = "CODE")]
+        otp: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum OtpCommand {
+    /// Get a TOTP secret for your authenticator app, or for another admin's
+    Enroll {
+        /// Current code from your existing secret
+        #[arg(long, value_name = "CODE")]
+        otp: Option<String>,
+
+        /// Enroll this principal instead of yourself, and pass the secret on
+        #[arg(long = "for", value_name = "PRINCIPAL")]
+        principal: Option<String>,
     },
 }
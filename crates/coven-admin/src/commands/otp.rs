@@ -0,0 +1,166 @@
+// ABOUTME: Implementation of 'coven-admin otp', and the one-time codes destructive commands send
+// ABOUTME: Enrolls a TOTP secret; attaches --otp codes to deletions and asks for one when the gateway wants it
+
+use anyhow::{anyhow, bail, Result};
+use colored::Colorize;
+use dialoguer::theme::ColorfulTheme;
+use dialoguer::Input;
+use std::future::Future;
+use std::io::IsTerminal;
+
+use coven_grpc::ChannelConfig;
+use coven_proto::coven::{admin_service_client::AdminServiceClient, EnrollOtpRequest};
+use coven_proto::OTP_METADATA;
+use tonic::{Code, Request, Response, Status};
+
+use super::OtpCommand;
+use crate::client::AuthInterceptor;
+use crate::output::{print_json, OutputFormat, Table};
+
+pub async fn run(
+    gateway: &str,
+    token: Option<&str>,
+    cmd: OtpCommand,
+    output: OutputFormat,
+) -> Result<()> {
+    match cmd {
+        OtpCommand::Enroll { otp, principal } => {
+            enroll(gateway, token, otp, principal, output).await
+        }
+    }
+}
+
+async fn enroll(
+    gateway: &str,
+    token: Option<&str>,
+    code: Option<String>,
+    principal_id: Option<String>,
+    output: OutputFormat,
+) -> Result<()> {
+    let config = ChannelConfig::new(gateway).without_keep_alive();
+    let channel = coven_grpc::create_channel(&config).await?;
+    let interceptor = AuthInterceptor::new(token.map(String::from));
+    let mut client = AdminServiceClient::with_interceptor(channel, interceptor);
+
+    let response = client
+        .enroll_otp(EnrollOtpRequest { code, principal_id })
+        .await
+        .map_err(|status| match status.code() {
+            Code::Unauthenticated if is_required(&status) => anyhow!(
+                "You already have a TOTP secret; pass its current code with --otp to enroll"
+            ),
+            _ => status.into(),
+        })?
+        .into_inner();
+
+    match output {
+        OutputFormat::Json => print_json(&response)?,
+        OutputFormat::Table => Table::new(&["PRINCIPAL_ID", "SECRET", "URI"])
+            .row([response.principal_id, response.secret, response.uri])
+            .print(),
+        OutputFormat::Text => {
+            println!(
+                "{} {}",
+                "TOTP secret enrolled for".green().bold(),
+                response.principal_id
+            );
+            println!();
+            println!("  Secret: {}", response.secret.bold());
+            println!("  URI:    {}", response.uri.dimmed());
+            println!();
+            println!("Add it to an authenticator app. Deletions on gateways that require");
+            println!("codes will ask for its current one, or take it with --otp.");
+        }
+    }
+    Ok(())
+}
+
+/// `message` as a request carrying the one-time `code`, if any
+pub fn request<T>(message: T, code: Option<&str>) -> Result<Request<T>> {
+    let mut request = Request::new(message);
+    if let Some(code) = code {
+        let value = code
+            .trim()
+            .parse()
+            .map_err(|_| anyhow!("invalid one-time code: {:?}", code))?;
+        request.metadata_mut().insert(OTP_METADATA, value);
+    }
+    Ok(request)
+}
+
+/// Whether the gateway refused a call for want of a (correct) one-time code
+pub fn is_required(status: &Status) -> bool {
+    status.code() == Code::Unauthenticated
+        && status
+            .metadata()
+            .get(OTP_METADATA)
+            .is_some_and(|value| value == "required")
+}
+
+/// Make a destructive call with the one-time `code`. If the gateway wants
+/// a code and none was given, ask for one on the terminal and try again.
+pub async fn send<T, R, F, Fut>(code: Option<&str>, message: T, mut call: F) -> Result<Response<R>>
+where
+    T: Clone,
+    F: FnMut(Request<T>) -> Fut,
+    Fut: Future<Output = Result<Response<R>, Status>>,
+{
+    match call(request(message.clone(), code)?).await {
+        Err(status) if code.is_none() && is_required(&status) => {
+            let code = ask()?;
+            Ok(call(request(message, Some(&code))?).await?)
+        }
+        result => Ok(result?),
+    }
+}
+
+fn ask() -> Result<String> {
+    if !std::io::stdin().is_terminal() {
+        bail!("The gateway requires a one-time code for this; pass it with --otp <code>");
+    }
+    let code: String = Input::with_theme(&ColorfulTheme::default())
+        .with_prompt("One-time code")
+        .interact_text()?;
+    Ok(code.trim().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn required() -> Status {
+        let mut status = Status::unauthenticated("this call needs a one-time code");
+        status
+            .metadata_mut()
+            .insert(OTP_METADATA, "required".parse().unwrap());
+        status
+    }
+
+    #[test]
+    fn test_request_carries_the_code() {
+        let with = request((), Some(" 123456 ")).unwrap();
+        assert_eq!(with.metadata().get(OTP_METADATA).unwrap(), "123456");
+        let without = request((), None).unwrap();
+        assert!(without.metadata().get(OTP_METADATA).is_none());
+    }
+
+    #[test]
+    fn test_is_required() {
+        assert!(is_required(&required()));
+        assert!(!is_required(&Status::unauthenticated("token revoked")));
+        assert!(!is_required(&Status::not_found("no token")));
+    }
+
+    #[tokio::test]
+    async fn test_send_with_a_code_does_not_ask_again() {
+        let mut calls = 0;
+        let err = send(Some("000000"), (), |_request| {
+            calls += 1;
+            async { Err::<Response<()>, _>(required()) }
+        })
+        .await
+        .unwrap_err();
+        assert_eq!(calls, 1);
+        assert!(err.to_string().contains("one-time code"), "{}", err);
+    }
+}
@@ -0,0 +1,133 @@
+// ABOUTME: Implementation of 'coven-admin packs' commands
+// ABOUTME: Lists connected tool packs and the health each last reported
+
+use anyhow::Result;
+use colored::Colorize;
+
+use coven_grpc::ChannelConfig;
+use coven_proto::coven::{admin_service_client::AdminServiceClient, ListPacksRequest, PackInfo};
+
+use super::PacksCommand;
+use crate::client::AuthInterceptor;
+use crate::output::{print_json, OutputFormat, Table};
+
+/// Columns of `packs list --output table`
+pub const PACK_COLUMNS: &[&str] = &["PACK_ID", "VERSION", "HEALTHY", "TOOLS", "STATUS"];
+
+pub async fn run(
+    gateway: &str,
+    token: Option<&str>,
+    cmd: PacksCommand,
+    output: OutputFormat,
+) -> Result<()> {
+    match cmd {
+        PacksCommand::List => list_packs(gateway, token, output).await,
+    }
+}
+
+async fn list_packs(gateway: &str, token: Option<&str>, output: OutputFormat) -> Result<()> {
+    let config = ChannelConfig::new(gateway).without_keep_alive();
+    let channel = coven_grpc::create_channel(&config).await?;
+
+    // Like the dead-letter queue, packs are served by the local gateway,
+    // which has no auth
+    let interceptor = AuthInterceptor::new(token.map(String::from));
+    let mut client = AdminServiceClient::with_interceptor(channel, interceptor);
+
+    let response = client.list_packs(ListPacksRequest {}).await?.into_inner();
+    match output {
+        OutputFormat::Json => return print_json(&response),
+        OutputFormat::Table => {
+            packs_table(&response.packs).print();
+            return Ok(());
+        }
+        OutputFormat::Text => {}
+    }
+    let packs = response.packs;
+
+    if packs.is_empty() {
+        println!("{}", "No packs connected".dimmed());
+        return Ok(());
+    }
+
+    println!("{}", format!("Connected Packs ({})", packs.len()).bold());
+    println!();
+
+    for pack in packs {
+        let status = if pack.healthy {
+            "●".green()
+        } else {
+            "●".yellow()
+        };
+
+        println!(
+            "{} {} {}",
+            status,
+            pack.pack_id.bold(),
+            format!("(v{})", pack.version).dimmed()
+        );
+
+        if !pack.healthy {
+            println!(
+                "    {}: {}",
+                "Degraded".yellow(),
+                if pack.status_message.is_empty() {
+                    "unhealthy"
+                } else {
+                    &pack.status_message
+                }
+            );
+        }
+        if let Some(at) = &pack.last_status_at {
+            println!("    {}: {}", "Last Check".dimmed(), at);
+        }
+        println!("    {}: {}", "Tools".dimmed(), pack.tools.join(", "));
+        println!();
+    }
+
+    Ok(())
+}
+
+pub fn packs_table(packs: &[PackInfo]) -> Table {
+    packs.iter().fold(Table::new(PACK_COLUMNS), |table, pack| {
+        table.row([
+            pack.pack_id.clone(),
+            pack.version.clone(),
+            pack.healthy.to_string(),
+            pack.tools.join(","),
+            pack.status_message.clone(),
+        ])
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_packs_table() {
+        let packs = [
+            PackInfo {
+                pack_id: "mcp-bridge".to_string(),
+                version: "0.3.0".to_string(),
+                tools: vec!["search".to_string(), "fetch".to_string()],
+                healthy: true,
+                ..Default::default()
+            },
+            PackInfo {
+                pack_id: "notes".to_string(),
+                version: "1.0.0".to_string(),
+                tools: vec!["note_add".to_string()],
+                healthy: false,
+                status_message: "database locked".to_string(),
+                ..Default::default()
+            },
+        ];
+        assert_eq!(
+            packs_table(&packs).render(),
+            "PACK_ID     VERSION  HEALTHY  TOOLS         STATUS\n\
+             mcp-bridge  0.3.0    true     search,fetch  -\n\
+             notes       1.0.0    false    note_add      database locked\n"
+        );
+    }
+}
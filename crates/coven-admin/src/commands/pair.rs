@@ -0,0 +1,88 @@
+// ABOUTME: Implementation of 'coven-admin pair' commands
+// ABOUTME: Approves a device that showed a pairing code from 'coven link --pair'
+
+use anyhow::Result;
+use colored::Colorize;
+
+use coven_grpc::ChannelConfig;
+use coven_proto::coven::{
+    admin_service_client::AdminServiceClient, ApprovePairingRequest, ApprovePairingResponse,
+};
+
+use super::PairCommand;
+use crate::client::AuthInterceptor;
+use crate::output::{print_json, OutputFormat, Table};
+
+/// Columns of `pair approve --output table`
+pub const PAIRING_COLUMNS: &[&str] = &["PRINCIPAL_ID", "DEVICE", "FINGERPRINT"];
+
+pub async fn run(
+    gateway: &str,
+    token: Option<&str>,
+    cmd: PairCommand,
+    output: OutputFormat,
+) -> Result<()> {
+    match cmd {
+        PairCommand::Approve { code, principal } => {
+            approve(gateway, token, code, principal, output).await
+        }
+    }
+}
+
+async fn approve(
+    gateway: &str,
+    token: Option<&str>,
+    code: String,
+    principal_id: String,
+    output: OutputFormat,
+) -> Result<()> {
+    let config = ChannelConfig::new(gateway).without_keep_alive();
+    let channel = coven_grpc::create_channel(&config).await?;
+
+    // Pairing is served by the local gateway too, which has no tokens
+    let interceptor = AuthInterceptor::new(token.map(String::from));
+    let mut client = AdminServiceClient::with_interceptor(channel, interceptor);
+
+    let response = client
+        .approve_pairing(ApprovePairingRequest { code, principal_id })
+        .await?
+        .into_inner();
+    match output {
+        OutputFormat::Json => print_json(&response)?,
+        OutputFormat::Table => pairing_table(&response).print(),
+        OutputFormat::Text => {
+            println!("{}", "Device paired".green().bold());
+            println!("  {}: {}", "Device".dimmed(), response.device_name);
+            println!("  {}: {}", "Principal".dimmed(), response.principal_id);
+            println!("  {}: {}", "Key".dimmed(), response.fingerprint);
+        }
+    }
+    Ok(())
+}
+
+pub fn pairing_table(response: &ApprovePairingResponse) -> Table {
+    Table::new(PAIRING_COLUMNS).row([
+        response.principal_id.as_str(),
+        response.device_name.as_str(),
+        response.fingerprint.as_str(),
+    ])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pairing_table() {
+        let response = ApprovePairingResponse {
+            principal_id: "device:phone".to_string(),
+            device_name: "phone".to_string(),
+            fingerprint: "3f9a".to_string(),
+        };
+        assert_eq!(
+            pairing_table(&response).render(),
+            "PRINCIPAL_ID  DEVICE  FINGERPRINT\n\
+             device:phone  phone   3f9a\n"
+        );
+    }
+}
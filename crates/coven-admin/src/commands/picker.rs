@@ -0,0 +1,214 @@
+// ABOUTME: Interactive pickers for admin commands that otherwise need exact IDs typed in
+// ABOUTME: Prompter abstracts the terminal so the picking logic runs against scripted answers in tests
+
+use anyhow::{bail, Result};
+use dialoguer::theme::ColorfulTheme;
+use dialoguer::{Input, Select};
+use std::io::IsTerminal;
+
+/// Label of the extra item `pick_or_enter` adds for typing a value in
+pub const ENTER_MANUALLY: &str = "Other (type it in)";
+
+/// One item of a menu: the line shown, and the value picking it yields
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Choice<T> {
+    pub label: String,
+    pub value: T,
+}
+
+impl<T> Choice<T> {
+    pub fn new(label: impl Into<String>, value: T) -> Self {
+        Self {
+            label: label.into(),
+            value,
+        }
+    }
+}
+
+/// Asks the user questions; `TerminalPrompter` is the real one
+pub trait Prompter: Send {
+    /// Index of the item picked from `items`
+    fn select(&mut self, prompt: &str, items: &[String]) -> Result<usize>;
+
+    /// A non-empty line of text, trimmed
+    fn input(&mut self, prompt: &str) -> Result<String>;
+}
+
+/// Menus and text prompts on the terminal
+pub struct TerminalPrompter {
+    theme: ColorfulTheme,
+}
+
+impl TerminalPrompter {
+    /// Fails when stdin isn't a terminal, since nobody could answer
+    pub fn new() -> Result<Self> {
+        if !std::io::stdin().is_terminal() {
+            bail!("--interactive needs a terminal; pass the values as flags instead");
+        }
+        Ok(Self {
+            theme: ColorfulTheme::default(),
+        })
+    }
+}
+
+impl Prompter for TerminalPrompter {
+    fn select(&mut self, prompt: &str, items: &[String]) -> Result<usize> {
+        Ok(Select::with_theme(&self.theme)
+            .with_prompt(prompt)
+            .items(items)
+            .default(0)
+            .interact()?)
+    }
+
+    fn input(&mut self, prompt: &str) -> Result<String> {
+        let value: String = Input::with_theme(&self.theme)
+            .with_prompt(prompt)
+            .validate_with(|value: &String| -> std::result::Result<(), &str> {
+                if value.trim().is_empty() {
+                    Err("a value is required")
+                } else {
+                    Ok(())
+                }
+            })
+            .interact_text()?;
+        Ok(value.trim().to_string())
+    }
+}
+
+/// Value of the choice the user picks. Fails when there's nothing to pick.
+pub fn pick<T: Clone>(
+    prompter: &mut dyn Prompter,
+    prompt: &str,
+    choices: &[Choice<T>],
+) -> Result<T> {
+    if choices.is_empty() {
+        bail!("nothing to choose for '{}'", prompt);
+    }
+    let labels: Vec<String> = choices.iter().map(|c| c.label.clone()).collect();
+    let index = prompter.select(prompt, &labels)?;
+    match choices.get(index) {
+        Some(choice) => Ok(choice.value.clone()),
+        None => bail!("no choice {} for '{}'", index, prompt),
+    }
+}
+
+/// Value of the choice the user picks, or one they type in instead by
+/// picking `ENTER_MANUALLY`. With no choices, asks for the value directly.
+pub fn pick_or_enter(
+    prompter: &mut dyn Prompter,
+    prompt: &str,
+    choices: &[Choice<String>],
+) -> Result<String> {
+    if choices.is_empty() {
+        return prompter.input(prompt);
+    }
+    let mut labels: Vec<String> = choices.iter().map(|c| c.label.clone()).collect();
+    labels.push(ENTER_MANUALLY.to_string());
+    let index = prompter.select(prompt, &labels)?;
+    match choices.get(index) {
+        Some(choice) => Ok(choice.value.clone()),
+        None => prompter.input(prompt),
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod testing {
+    use super::Prompter;
+    use anyhow::{anyhow, Result};
+    use std::collections::VecDeque;
+
+    /// An answer a `ScriptedPrompter` gives
+    #[derive(Debug)]
+    pub enum Answer {
+        Select(usize),
+        Input(&'static str),
+    }
+
+    /// Answers prompts from a script, recording the menus it was shown
+    #[derive(Default)]
+    pub struct ScriptedPrompter {
+        pub answers: VecDeque<Answer>,
+        pub menus: Vec<(String, Vec<String>)>,
+    }
+
+    impl ScriptedPrompter {
+        pub fn new(answers: impl IntoIterator<Item = Answer>) -> Self {
+            Self {
+                answers: answers.into_iter().collect(),
+                menus: Vec::new(),
+            }
+        }
+    }
+
+    impl Prompter for ScriptedPrompter {
+        fn select(&mut self, prompt: &str, items: &[String]) -> Result<usize> {
+            self.menus.push((prompt.to_string(), items.to_vec()));
+            match self.answers.pop_front() {
+                Some(Answer::Select(index)) => Ok(index),
+                other => Err(anyhow!("expected a select for '{prompt}', had {other:?}")),
+            }
+        }
+
+        fn input(&mut self, prompt: &str) -> Result<String> {
+            match self.answers.pop_front() {
+                Some(Answer::Input(value)) => Ok(value.to_string()),
+                other => Err(anyhow!("expected input for '{prompt}', had {other:?}")),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::testing::{Answer, ScriptedPrompter};
+    use super::*;
+
+    fn choices() -> Vec<Choice<String>> {
+        vec![
+            Choice::new("Alpha (a-1)", "a-1".to_string()),
+            Choice::new("Beta (b-2)", "b-2".to_string()),
+        ]
+    }
+
+    #[test]
+    fn test_pick_returns_value() {
+        let mut prompter = ScriptedPrompter::new([Answer::Select(1)]);
+        assert_eq!(pick(&mut prompter, "Agent", &choices()).unwrap(), "b-2");
+        assert_eq!(
+            prompter.menus[0].1,
+            vec!["Alpha (a-1)".to_string(), "Beta (b-2)".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_pick_without_choices_fails() {
+        let mut prompter = ScriptedPrompter::default();
+        assert!(pick::<String>(&mut prompter, "Agent", &[]).is_err());
+    }
+
+    #[test]
+    fn test_pick_or_enter_offers_manual_entry() {
+        let mut prompter = ScriptedPrompter::new([Answer::Select(2), Answer::Input("c-3")]);
+        assert_eq!(
+            pick_or_enter(&mut prompter, "Agent", &choices()).unwrap(),
+            "c-3"
+        );
+        assert_eq!(prompter.menus[0].1.last().unwrap(), ENTER_MANUALLY);
+
+        let mut prompter = ScriptedPrompter::new([Answer::Select(0)]);
+        assert_eq!(
+            pick_or_enter(&mut prompter, "Agent", &choices()).unwrap(),
+            "a-1"
+        );
+    }
+
+    #[test]
+    fn test_pick_or_enter_without_choices_asks() {
+        let mut prompter = ScriptedPrompter::new([Answer::Input("C0123")]);
+        assert_eq!(
+            pick_or_enter(&mut prompter, "Channel", &[]).unwrap(),
+            "C0123"
+        );
+        assert!(prompter.menus.is_empty());
+    }
+}
@@ -0,0 +1,279 @@
+// ABOUTME: YAML schema for 'principals export/import' and the diff that plans an import
+// ABOUTME: Entries match existing principals by fingerprint, then by type and name
+
+use anyhow::{Context, Result};
+use coven_proto::coven::Principal;
+use serde::{Deserialize, Serialize};
+
+/// A principals file:
+///
+/// ```yaml
+/// principals:
+///   - type: agent
+///     name: build-box
+///     fingerprint: 3f9a...   # SHA256 hex, optional
+///     roles: [member]
+///   - type: client
+///     name: Ops Laptop
+///     roles: [owner]
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PrincipalFile {
+    #[serde(default)]
+    pub principals: Vec<PrincipalEntry>,
+}
+
+/// One principal as written in a principals file
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PrincipalEntry {
+    /// "agent" or "client"
+    pub r#type: String,
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fingerprint: Option<String>,
+    #[serde(default)]
+    pub roles: Vec<String>,
+}
+
+impl PrincipalFile {
+    pub fn parse(yaml: &str) -> Result<Self> {
+        serde_yaml::from_str(yaml).context("invalid principals file")
+    }
+
+    pub fn to_yaml(&self) -> Result<String> {
+        Ok(serde_yaml::to_string(self)?)
+    }
+
+    /// The exportable fields of `principals`; IDs, status, and creation
+    /// times belong to the gateway and aren't carried over.
+    pub fn from_principals(principals: &[Principal]) -> Self {
+        Self {
+            principals: principals
+                .iter()
+                .map(|p| PrincipalEntry {
+                    r#type: p.r#type.clone(),
+                    name: p.display_name.clone(),
+                    fingerprint: p.pubkey_fp.clone().filter(|fp| !fp.is_empty()),
+                    roles: p.roles.clone(),
+                })
+                .collect(),
+        }
+    }
+}
+
+/// What importing one entry will do
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PlannedAction {
+    /// No matching principal; create one
+    Create,
+    /// Matches principal `id` whose name or roles differ; replace them
+    Update { id: String, changes: Vec<String> },
+    /// Matches principal `id` exactly
+    Unchanged { id: String },
+}
+
+impl PlannedAction {
+    pub fn label(&self) -> &'static str {
+        match self {
+            PlannedAction::Create => "create",
+            PlannedAction::Update { .. } => "update",
+            PlannedAction::Unchanged { .. } => "no-op",
+        }
+    }
+}
+
+/// One entry of a principals file and what importing it will do
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PlannedChange {
+    pub entry: PrincipalEntry,
+    pub action: PlannedAction,
+}
+
+/// Work out what importing `entries` into a gateway that has `existing`
+/// principals will do. An entry matches the principal with its fingerprint,
+/// or failing that, the one with its type and name. Role order doesn't
+/// matter, so importing an export of the same gateway plans only no-ops.
+pub fn plan_import(existing: &[Principal], entries: &[PrincipalEntry]) -> Vec<PlannedChange> {
+    entries
+        .iter()
+        .map(|entry| {
+            let action = match find_match(existing, entry) {
+                None => PlannedAction::Create,
+                Some(current) => {
+                    let changes = describe_changes(current, entry);
+                    if changes.is_empty() {
+                        PlannedAction::Unchanged {
+                            id: current.id.clone(),
+                        }
+                    } else {
+                        PlannedAction::Update {
+                            id: current.id.clone(),
+                            changes,
+                        }
+                    }
+                }
+            };
+            PlannedChange {
+                entry: entry.clone(),
+                action,
+            }
+        })
+        .collect()
+}
+
+fn find_match<'a>(existing: &'a [Principal], entry: &PrincipalEntry) -> Option<&'a Principal> {
+    let by_fingerprint = entry.fingerprint.as_deref().and_then(|fp| {
+        existing.iter().find(|p| {
+            p.pubkey_fp
+                .as_deref()
+                .is_some_and(|have| have.eq_ignore_ascii_case(fp))
+        })
+    });
+    by_fingerprint.or_else(|| {
+        existing
+            .iter()
+            .find(|p| p.r#type == entry.r#type && p.display_name == entry.name)
+    })
+}
+
+fn describe_changes(current: &Principal, entry: &PrincipalEntry) -> Vec<String> {
+    let mut changes = Vec::new();
+    if current.display_name != entry.name {
+        changes.push(format!(
+            "name {:?} -> {:?}",
+            current.display_name, entry.name
+        ));
+    }
+    if sorted(&current.roles) != sorted(&entry.roles) {
+        changes.push(format!(
+            "roles [{}] -> [{}]",
+            current.roles.join(","),
+            entry.roles.join(",")
+        ));
+    }
+    changes
+}
+
+fn sorted(roles: &[String]) -> Vec<&str> {
+    let mut roles: Vec<&str> = roles.iter().map(String::as_str).collect();
+    roles.sort_unstable();
+    roles.dedup();
+    roles
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn principal(id: &str, kind: &str, name: &str, fp: Option<&str>, roles: &[&str]) -> Principal {
+        Principal {
+            id: id.to_string(),
+            r#type: kind.to_string(),
+            display_name: name.to_string(),
+            status: "approved".to_string(),
+            pubkey_fp: fp.map(str::to_string),
+            roles: roles.iter().map(|r| r.to_string()).collect(),
+            ..Default::default()
+        }
+    }
+
+    fn entry(kind: &str, name: &str, fp: Option<&str>, roles: &[&str]) -> PrincipalEntry {
+        PrincipalEntry {
+            r#type: kind.to_string(),
+            name: name.to_string(),
+            fingerprint: fp.map(str::to_string),
+            roles: roles.iter().map(|r| r.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn test_parse_documented_schema() {
+        let file = PrincipalFile::parse(
+            "principals:\n\
+             \x20 - type: agent\n\
+             \x20   name: build-box\n\
+             \x20   fingerprint: 3f9a\n\
+             \x20   roles: [member]\n\
+             \x20 - type: client\n\
+             \x20   name: Ops Laptop\n",
+        )
+        .unwrap();
+        assert_eq!(
+            file.principals,
+            vec![
+                entry("agent", "build-box", Some("3f9a"), &["member"]),
+                entry("client", "Ops Laptop", None, &[]),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_rejects_unknown_fields() {
+        let err =
+            PrincipalFile::parse("principals:\n  - type: agent\n    name: ci\n    role: [owner]\n");
+        assert!(err.is_err());
+    }
+
+    #[test]
+    fn test_export_round_trips() {
+        let principals = [
+            principal("p-1", "agent", "build-box", Some("3f9a"), &["member"]),
+            principal("p-2", "client", "Ops Laptop", Some(""), &["owner"]),
+        ];
+        let file = PrincipalFile::from_principals(&principals);
+        assert_eq!(file.principals[1].fingerprint, None);
+
+        let parsed = PrincipalFile::parse(&file.to_yaml().unwrap()).unwrap();
+        assert_eq!(parsed, file);
+        // Importing an export into the same gateway changes nothing
+        assert!(plan_import(&principals, &parsed.principals)
+            .iter()
+            .all(|c| matches!(c.action, PlannedAction::Unchanged { .. })));
+    }
+
+    #[test]
+    fn test_plan_import() {
+        let existing = [
+            principal("p-1", "agent", "build-box", Some("3F9A"), &["member"]),
+            principal("p-2", "client", "Ops Laptop", None, &["owner", "member"]),
+        ];
+        let entries = [
+            // Fingerprint match wins over the new name
+            entry("agent", "builder", Some("3f9a"), &["member"]),
+            // Name match, same roles in another order
+            entry("client", "Ops Laptop", None, &["member", "owner"]),
+            // Same name, different type: a different principal
+            entry("agent", "Ops Laptop", None, &[]),
+        ];
+        let plan = plan_import(&existing, &entries);
+
+        assert_eq!(
+            plan.iter().map(|c| c.action.clone()).collect::<Vec<_>>(),
+            vec![
+                PlannedAction::Update {
+                    id: "p-1".to_string(),
+                    changes: vec![r#"name "build-box" -> "builder""#.to_string()],
+                },
+                PlannedAction::Unchanged {
+                    id: "p-2".to_string()
+                },
+                PlannedAction::Create,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_plan_import_updates_roles() {
+        let existing = [principal("p-1", "client", "ci", None, &["member"])];
+        let plan = plan_import(&existing, &[entry("client", "ci", None, &["owner"])]);
+        assert_eq!(
+            plan[0].action,
+            PlannedAction::Update {
+                id: "p-1".to_string(),
+                changes: vec!["roles [member] -> [owner]".to_string()],
+            }
+        );
+    }
+}
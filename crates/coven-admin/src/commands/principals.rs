@@ -1,36 +1,89 @@
 // ABOUTME: Implementation of 'coven-admin principals' commands
-// ABOUTME: Manages principals (agents, clients) in the gateway
+// ABOUTME: Manages principals (agents, clients) in the gateway, including YAML export and import
 
-use anyhow::{bail, Result};
+use anyhow::{bail, Context, Result};
 use colored::Colorize;
+use serde::Serialize;
+use std::io::Read;
+use std::path::Path;
 
 use coven_grpc::ChannelConfig;
 use coven_proto::coven::{
     admin_service_client::AdminServiceClient, CreatePrincipalRequest, DeletePrincipalRequest,
-    ListPrincipalsRequest,
+    ListPrincipalsRequest, Principal, RotatePrincipalKeyRequest, UpdatePrincipalRequest,
 };
+use tonic::service::interceptor::InterceptedService;
+use tonic::transport::Channel;
 
-use super::PrincipalsCommand;
+use super::principal_file::{plan_import, PlannedAction, PlannedChange, PrincipalFile};
+use super::{otp, PrincipalsCommand};
 use crate::client::AuthInterceptor;
+use crate::output::{print_json, OutputFormat, Table};
 
-pub async fn run(gateway: &str, token: Option<&str>, cmd: PrincipalsCommand) -> Result<()> {
+/// Columns of principal tables, also used by `me --output table`
+pub const PRINCIPAL_COLUMNS: &[&str] = &["ID", "TYPE", "NAME", "STATUS", "ROLES"];
+
+/// Columns of `principals import --output table`
+pub const IMPORT_COLUMNS: &[&str] = &["ACTION", "TYPE", "NAME", "ID", "CHANGES", "RESULT"];
+
+type AdminClient = AdminServiceClient<InterceptedService<Channel, AuthInterceptor>>;
+
+pub async fn run(
+    gateway: &str,
+    token: Option<&str>,
+    cmd: PrincipalsCommand,
+    output: OutputFormat,
+) -> Result<()> {
     let Some(token) = token else {
         bail!("Authentication required. Set COVEN_TOKEN environment variable or use --token flag.");
     };
 
     match cmd {
-        PrincipalsCommand::List { r#type } => list_principals(gateway, token, r#type).await,
+        PrincipalsCommand::List { r#type } => list_principals(gateway, token, r#type, output).await,
         PrincipalsCommand::Create {
             r#type,
             name,
             fingerprint,
             role,
-        } => create_principal(gateway, token, r#type, name, fingerprint, role).await,
-        PrincipalsCommand::Delete { id } => delete_principal(gateway, token, id).await,
+        } => create_principal(gateway, token, r#type, name, fingerprint, role, output).await,
+        PrincipalsCommand::Delete { id, otp } => {
+            delete_principal(gateway, token, id, otp, output).await
+        }
+        PrincipalsCommand::Rotate {
+            id,
+            fingerprint,
+            grace_period,
+        } => rotate_key(gateway, token, id, fingerprint, grace_period, output).await,
+        PrincipalsCommand::Export => export_principals(gateway, token, output).await,
+        PrincipalsCommand::Import { file, dry_run } => {
+            import_principals(gateway, token, &file, dry_run, output).await
+        }
     }
 }
 
-async fn list_principals(gateway: &str, token: &str, type_filter: Option<String>) -> Result<()> {
+async fn connect(gateway: &str, token: &str) -> Result<AdminClient> {
+    let config = ChannelConfig::new(gateway).without_keep_alive();
+    let channel = coven_grpc::create_channel(&config).await?;
+    let interceptor = AuthInterceptor::new(Some(token.to_string()));
+    Ok(AdminServiceClient::with_interceptor(channel, interceptor))
+}
+
+async fn fetch_principals(client: &mut AdminClient) -> Result<Vec<Principal>> {
+    let response = client
+        .list_principals(ListPrincipalsRequest {
+            r#type: None,
+            status: None,
+        })
+        .await?;
+    Ok(response.into_inner().principals)
+}
+
+async fn list_principals(
+    gateway: &str,
+    token: &str,
+    type_filter: Option<String>,
+    output: OutputFormat,
+) -> Result<()> {
     let config = ChannelConfig::new(gateway).without_keep_alive();
     let channel = coven_grpc::create_channel(&config).await?;
 
@@ -42,8 +95,17 @@ async fn list_principals(gateway: &str, token: &str, type_filter: Option<String>
             r#type: type_filter,
             status: None,
         })
-        .await?;
-    let principals = response.into_inner().principals;
+        .await?
+        .into_inner();
+    match output {
+        OutputFormat::Json => return print_json(&response),
+        OutputFormat::Table => {
+            principals_table(&response.principals).print();
+            return Ok(());
+        }
+        OutputFormat::Text => {}
+    }
+    let principals = response.principals;
 
     if principals.is_empty() {
         println!("{}", "No principals found".dimmed());
@@ -92,6 +154,7 @@ async fn create_principal(
     display_name: String,
     fingerprint: Option<String>,
     roles: Vec<String>,
+    output: OutputFormat,
 ) -> Result<()> {
     let config = ChannelConfig::new(gateway).without_keep_alive();
     let channel = coven_grpc::create_channel(&config).await?;
@@ -109,6 +172,14 @@ async fn create_principal(
 
     let response = client.create_principal(request).await?;
     let principal = response.into_inner();
+    match output {
+        OutputFormat::Json => return print_json(&principal),
+        OutputFormat::Table => {
+            principals_table(std::slice::from_ref(&principal)).print();
+            return Ok(());
+        }
+        OutputFormat::Text => {}
+    }
 
     println!("{}", "Principal created".green().bold());
     println!("  {}: {}", "ID".dimmed(), principal.id);
@@ -122,17 +193,366 @@ async fn create_principal(
     Ok(())
 }
 
-async fn delete_principal(gateway: &str, token: &str, id: String) -> Result<()> {
+async fn delete_principal(
+    gateway: &str,
+    token: &str,
+    id: String,
+    otp: Option<String>,
+    output: OutputFormat,
+) -> Result<()> {
     let config = ChannelConfig::new(gateway).without_keep_alive();
     let channel = coven_grpc::create_channel(&config).await?;
 
     let interceptor = AuthInterceptor::new(Some(token.to_string()));
-    let mut client = AdminServiceClient::with_interceptor(channel, interceptor);
+    let client = AdminServiceClient::with_interceptor(channel, interceptor);
 
     let request = DeletePrincipalRequest { id: id.clone() };
-    client.delete_principal(request).await?;
+    let response = otp::send(otp.as_deref(), request, |request| {
+        let mut client = client.clone();
+        async move { client.delete_principal(request).await }
+    })
+    .await?
+    .into_inner();
+
+    match output {
+        OutputFormat::Json => print_json(&response)?,
+        OutputFormat::Table => Table::new(&["ID", "DELETED"])
+            .row([id, "true".to_string()])
+            .print(),
+        OutputFormat::Text => println!("{} {}", "Principal deleted:".green().bold(), id),
+    }
+
+    Ok(())
+}
+
+async fn rotate_key(
+    gateway: &str,
+    token: &str,
+    id: String,
+    fingerprint: String,
+    grace_period: Option<u32>,
+    output: OutputFormat,
+) -> Result<()> {
+    let mut client = connect(gateway, token).await?;
+    let response = client
+        .rotate_principal_key(RotatePrincipalKeyRequest {
+            id,
+            new_pubkey_fp: fingerprint,
+            grace_period_secs: grace_period,
+        })
+        .await?
+        .into_inner();
+
+    match output {
+        OutputFormat::Json => print_json(&response)?,
+        OutputFormat::Table => Table::new(&["ID", "NEW KEY", "OLD KEYS", "OLD KEYS EXPIRE"])
+            .row([
+                response.principal_id,
+                response.new_fingerprint,
+                response.old_fingerprints.join(", "),
+                response.old_keys_expire_at,
+            ])
+            .print(),
+        OutputFormat::Text => {
+            println!("{}", "Key rotated".green().bold());
+            println!("  {}: {}", "ID".dimmed(), response.principal_id);
+            println!("  {}: {}", "New key".dimmed(), response.new_fingerprint);
+            for fingerprint in &response.old_fingerprints {
+                println!("  {}: {}", "Old key".dimmed(), fingerprint);
+            }
+            println!(
+                "  {}: {}",
+                "Old keys expire".dimmed(),
+                response.old_keys_expire_at
+            );
+        }
+    }
+    Ok(())
+}
 
-    println!("{} {}", "Principal deleted:".green().bold(), id);
+async fn export_principals(gateway: &str, token: &str, output: OutputFormat) -> Result<()> {
+    let mut client = connect(gateway, token).await?;
+    let principals = fetch_principals(&mut client).await?;
+    let file = PrincipalFile::from_principals(&principals);
 
+    match output {
+        OutputFormat::Json => print_json(&file)?,
+        OutputFormat::Table => principals_table(&principals).print(),
+        OutputFormat::Text => print!("{}", file.to_yaml()?),
+    }
     Ok(())
 }
+
+/// Outcome of importing one entry, as printed by `principals import`
+#[derive(Debug, Clone, Serialize)]
+pub struct ImportResult {
+    pub action: &'static str,
+    pub r#type: String,
+    pub name: String,
+    /// The matched or newly created principal
+    pub id: Option<String>,
+    pub changes: Vec<String>,
+    /// "planned" on a dry run, then "ok" or "failed"
+    pub result: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl ImportResult {
+    fn planned(change: &PlannedChange) -> Self {
+        let (id, changes) = match &change.action {
+            PlannedAction::Create => (None, Vec::new()),
+            PlannedAction::Update { id, changes } => (Some(id.clone()), changes.clone()),
+            PlannedAction::Unchanged { id } => (Some(id.clone()), Vec::new()),
+        };
+        Self {
+            action: change.action.label(),
+            r#type: change.entry.r#type.clone(),
+            name: change.entry.name.clone(),
+            id,
+            changes,
+            result: "planned",
+            error: None,
+        }
+    }
+}
+
+async fn import_principals(
+    gateway: &str,
+    token: &str,
+    path: &Path,
+    dry_run: bool,
+    output: OutputFormat,
+) -> Result<()> {
+    let file = PrincipalFile::parse(&read_input(path)?)?;
+
+    let mut client = connect(gateway, token).await?;
+    let existing = fetch_principals(&mut client).await?;
+    let plan = plan_import(&existing, &file.principals);
+
+    let mut results: Vec<ImportResult> = plan.iter().map(ImportResult::planned).collect();
+    if !dry_run {
+        // One RPC per entry; a failure is recorded and the import goes on
+        for (change, result) in plan.iter().zip(results.iter_mut()) {
+            match apply_change(&mut client, change).await {
+                Ok(id) => {
+                    result.id = id.or(result.id.take());
+                    result.result = "ok";
+                }
+                Err(status) => {
+                    result.result = "failed";
+                    result.error = Some(status.message().to_string());
+                }
+            }
+        }
+    }
+
+    match output {
+        OutputFormat::Json => print_json(&results)?,
+        OutputFormat::Table => import_table(&results).print(),
+        OutputFormat::Text => print_import(&results, dry_run),
+    }
+
+    let failed = results.iter().filter(|r| r.result == "failed").count();
+    if failed > 0 {
+        bail!(
+            "{} of {} principals failed to import",
+            failed,
+            results.len()
+        );
+    }
+    Ok(())
+}
+
+/// Read an import file, or stdin for `-`.
+pub(super) fn read_input(path: &Path) -> Result<String> {
+    if path == Path::new("-") {
+        let mut text = String::new();
+        std::io::stdin()
+            .read_to_string(&mut text)
+            .context("reading stdin")?;
+        return Ok(text);
+    }
+    std::fs::read_to_string(path).with_context(|| format!("reading {}", path.display()))
+}
+
+/// Carry out one planned change, returning the ID of a created principal.
+async fn apply_change(
+    client: &mut AdminClient,
+    change: &PlannedChange,
+) -> Result<Option<String>, tonic::Status> {
+    let entry = &change.entry;
+    match &change.action {
+        PlannedAction::Create => {
+            let principal = client
+                .create_principal(CreatePrincipalRequest {
+                    r#type: entry.r#type.clone(),
+                    display_name: entry.name.clone(),
+                    pubkey: None,
+                    pubkey_fp: entry.fingerprint.clone(),
+                    roles: entry.roles.clone(),
+                })
+                .await?
+                .into_inner();
+            Ok(Some(principal.id))
+        }
+        PlannedAction::Update { id, .. } => {
+            client
+                .update_principal(UpdatePrincipalRequest {
+                    id: id.clone(),
+                    display_name: entry.name.clone(),
+                    roles: entry.roles.clone(),
+                })
+                .await?;
+            Ok(None)
+        }
+        PlannedAction::Unchanged { .. } => Ok(None),
+    }
+}
+
+fn print_import(results: &[ImportResult], dry_run: bool) {
+    if dry_run {
+        println!("{}", "Dry run: nothing was changed".yellow().bold());
+    }
+    for r in results {
+        let padded = format!("{:<7}", r.action);
+        let action = match r.action {
+            "create" => padded.green(),
+            "update" => padded.yellow(),
+            _ => padded.dimmed(),
+        };
+        let outcome = match (r.result, &r.error) {
+            ("failed", Some(e)) => format!("failed: {}", e).red(),
+            ("ok", _) => "ok".green(),
+            _ => "".normal(),
+        };
+        let id =
+            r.id.as_deref()
+                .map(|id| format!("({})", id))
+                .unwrap_or_default();
+        println!(
+            "{} {} {} {} {}",
+            action,
+            r.name.bold(),
+            format!("[{}]", r.r#type).dimmed(),
+            id.dimmed(),
+            outcome
+        );
+        for change in &r.changes {
+            println!("        {}", change.dimmed());
+        }
+    }
+
+    let count = |action: &str| results.iter().filter(|r| r.action == action).count();
+    println!();
+    if dry_run {
+        println!(
+            "{} to create, {} to update, {} unchanged",
+            count("create"),
+            count("update"),
+            count("no-op")
+        );
+    } else {
+        let failed = results.iter().filter(|r| r.result == "failed").count();
+        println!(
+            "{} created, {} updated, {} unchanged, {} failed",
+            count("create"),
+            count("update"),
+            count("no-op"),
+            failed
+        );
+    }
+}
+
+pub fn import_table(results: &[ImportResult]) -> Table {
+    results.iter().fold(Table::new(IMPORT_COLUMNS), |table, r| {
+        let result = match &r.error {
+            Some(e) => format!("{}: {}", r.result, e),
+            None => r.result.to_string(),
+        };
+        table.row([
+            r.action.to_string(),
+            r.r#type.clone(),
+            r.name.clone(),
+            r.id.clone().unwrap_or_default(),
+            r.changes.join("; "),
+            result,
+        ])
+    })
+}
+
+pub fn principals_table(principals: &[Principal]) -> Table {
+    principals
+        .iter()
+        .fold(Table::new(PRINCIPAL_COLUMNS), |table, p| {
+            table.row([
+                p.id.clone(),
+                p.r#type.clone(),
+                p.display_name.clone(),
+                p.status.clone(),
+                p.roles.join(","),
+            ])
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_principals_table() {
+        let principals = [
+            Principal {
+                id: "p-1".to_string(),
+                r#type: "client".to_string(),
+                display_name: "Ops Laptop".to_string(),
+                status: "approved".to_string(),
+                roles: vec!["owner".to_string(), "member".to_string()],
+                ..Default::default()
+            },
+            Principal {
+                id: "p-2".to_string(),
+                r#type: "agent".to_string(),
+                display_name: "hex".to_string(),
+                status: "pending".to_string(),
+                ..Default::default()
+            },
+        ];
+        assert_eq!(
+            principals_table(&principals).render(),
+            "ID   TYPE    NAME        STATUS    ROLES\n\
+             p-1  client  Ops Laptop  approved  owner,member\n\
+             p-2  agent   hex         pending   -\n"
+        );
+    }
+
+    #[test]
+    fn test_import_table_reports_each_entry() {
+        let results = [
+            ImportResult {
+                action: "update",
+                r#type: "client".to_string(),
+                name: "ci".to_string(),
+                id: Some("p-1".to_string()),
+                changes: vec!["roles [member] -> [owner]".to_string()],
+                result: "ok",
+                error: None,
+            },
+            ImportResult {
+                action: "create",
+                r#type: "agent".to_string(),
+                name: "box".to_string(),
+                id: None,
+                changes: vec![],
+                result: "failed",
+                error: Some("fingerprint already registered".to_string()),
+            },
+        ];
+        assert_eq!(
+            import_table(&results).render(),
+            "ACTION  TYPE    NAME  ID   CHANGES                    RESULT\n\
+             update  client  ci    p-1  roles [member] -> [owner]  ok\n\
+             create  agent   box   -    -                          failed: fingerprint already registered\n"
+        );
+    }
+}
@@ -0,0 +1,189 @@
+// ABOUTME: Implementation of 'coven-admin secrets' commands
+// ABOUTME: Sets, lists, and deletes gateway-managed pack secrets without ever printing values
+
+use anyhow::{bail, Context, Result};
+use colored::Colorize;
+use std::io::BufRead;
+
+use coven_grpc::ChannelConfig;
+use coven_proto::coven::{
+    admin_service_client::AdminServiceClient, DeletePackSecretRequest, ListPackSecretsRequest,
+    PackSecretInfo, SetPackSecretRequest,
+};
+use tonic::service::interceptor::InterceptedService;
+use tonic::transport::Channel;
+
+use super::{otp, SecretsCommand};
+use crate::client::AuthInterceptor;
+use crate::output::{print_json, OutputFormat, Table};
+
+pub async fn run(
+    gateway: &str,
+    token: Option<&str>,
+    cmd: SecretsCommand,
+    output: OutputFormat,
+) -> Result<()> {
+    // Like the dead-letter queue, pack secrets live in the local gateway,
+    // which has no auth
+    let mut client = connect(gateway, token).await?;
+
+    match cmd {
+        SecretsCommand::Set {
+            pack_id,
+            key,
+            value,
+        } => {
+            let value = match value {
+                Some(value) => value,
+                None => read_value()?,
+            };
+            set(&mut client, pack_id, key, value, output).await
+        }
+        SecretsCommand::List { pack_id } => list(&mut client, pack_id, output).await,
+        SecretsCommand::Delete { pack_id, key, otp } => {
+            delete(&client, pack_id, key, otp, output).await
+        }
+    }
+}
+
+type Client = AdminServiceClient<InterceptedService<Channel, AuthInterceptor>>;
+
+async fn connect(gateway: &str, token: Option<&str>) -> Result<Client> {
+    let config = ChannelConfig::new(gateway).without_keep_alive();
+    let channel = coven_grpc::create_channel(&config).await?;
+
+    let interceptor = AuthInterceptor::new(token.map(String::from));
+    Ok(AdminServiceClient::with_interceptor(channel, interceptor))
+}
+
+/// Read the value from the first line of stdin, so it stays out of shell
+/// history.
+fn read_value() -> Result<String> {
+    let mut line = String::new();
+    std::io::stdin()
+        .lock()
+        .read_line(&mut line)
+        .context("reading secret value from stdin")?;
+    let value = line.trim_end_matches(['\r', '\n']).to_string();
+    if value.is_empty() {
+        bail!("No value given. Pass it as an argument or on stdin.");
+    }
+    Ok(value)
+}
+
+async fn set(
+    client: &mut Client,
+    pack_id: String,
+    key: String,
+    value: String,
+    output: OutputFormat,
+) -> Result<()> {
+    let response = client
+        .set_pack_secret(SetPackSecretRequest {
+            pack_id: pack_id.clone(),
+            key: key.clone(),
+            value,
+        })
+        .await?
+        .into_inner();
+    match output {
+        OutputFormat::Json => return print_json(&response),
+        OutputFormat::Table => {
+            Table::new(&["PACK_ID", "KEY", "CREATED"])
+                .row([pack_id, key, response.created.to_string()])
+                .print();
+            return Ok(());
+        }
+        OutputFormat::Text => {}
+    }
+
+    let action = if response.created { "Set" } else { "Replaced" };
+    println!("{} {} for {}", action.green().bold(), key, pack_id);
+
+    Ok(())
+}
+
+async fn list(client: &mut Client, pack_id: String, output: OutputFormat) -> Result<()> {
+    let response = client
+        .list_pack_secrets(ListPackSecretsRequest {
+            pack_id: pack_id.clone(),
+        })
+        .await?
+        .into_inner();
+    match output {
+        OutputFormat::Json => return print_json(&response),
+        OutputFormat::Table => {
+            secrets_table(&response.secrets).print();
+            return Ok(());
+        }
+        OutputFormat::Text => {}
+    }
+    let secrets = response.secrets;
+
+    if secrets.is_empty() {
+        println!("{}", format!("No secrets for {}", pack_id).dimmed());
+        return Ok(());
+    }
+
+    println!(
+        "{}",
+        format!("Secrets for {} ({})", pack_id, secrets.len()).bold()
+    );
+    println!();
+
+    for secret in secrets {
+        println!(
+            "  {} {}",
+            secret.key.bold(),
+            format!("(updated {})", secret.updated_at).dimmed()
+        );
+    }
+
+    Ok(())
+}
+
+async fn delete(
+    client: &Client,
+    pack_id: String,
+    key: String,
+    otp: Option<String>,
+    output: OutputFormat,
+) -> Result<()> {
+    let request = DeletePackSecretRequest {
+        pack_id: pack_id.clone(),
+        key: key.clone(),
+    };
+    let response = otp::send(otp.as_deref(), request, |request| {
+        let mut client = client.clone();
+        async move { client.delete_pack_secret(request).await }
+    })
+    .await?
+    .into_inner();
+    match output {
+        OutputFormat::Json => return print_json(&response),
+        OutputFormat::Table => {
+            Table::new(&["PACK_ID", "KEY", "DELETED"])
+                .row([pack_id, key, response.deleted.to_string()])
+                .print();
+            return Ok(());
+        }
+        OutputFormat::Text => {}
+    }
+
+    if response.deleted {
+        println!("{} {} for {}", "Deleted".green().bold(), key, pack_id);
+    } else {
+        println!("{}", format!("No secret {} for {}", key, pack_id).dimmed());
+    }
+
+    Ok(())
+}
+
+/// Secret names and when they changed; values never leave the gateway.
+pub fn secrets_table(secrets: &[PackSecretInfo]) -> Table {
+    secrets
+        .iter()
+        .fold(Table::new(&["KEY", "UPDATED_AT"]), |table, secret| {
+            table.row([secret.key.as_str(), secret.updated_at.as_str()])
+        })
+}
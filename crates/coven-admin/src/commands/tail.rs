@@ -0,0 +1,161 @@
+// ABOUTME: Implementation of 'coven-admin tail'
+// ABOUTME: Streams a live feed of the gateway's traffic, one line per event, until Ctrl+C
+
+use anyhow::{bail, Result};
+use colored::Colorize;
+
+use coven_grpc::ChannelConfig;
+use coven_proto::coven::{
+    admin_service_client::AdminServiceClient, TailTrafficRequest, TrafficEvent,
+};
+
+use crate::client::AuthInterceptor;
+use crate::output::OutputFormat;
+
+/// Longest content preview shown per event
+const PREVIEW_CHARS: usize = 80;
+
+pub async fn run(
+    gateway: &str,
+    token: Option<&str>,
+    agent: Option<String>,
+    redact: bool,
+    output: OutputFormat,
+) -> Result<()> {
+    let config = ChannelConfig::new(gateway).without_keep_alive();
+    let channel = coven_grpc::create_channel(&config).await?;
+    let interceptor = AuthInterceptor::new(token.map(String::from));
+    let mut client = AdminServiceClient::with_interceptor(channel, interceptor);
+
+    let mut stream = client
+        .tail_traffic(TailTrafficRequest {
+            agent_id: agent,
+            redact,
+        })
+        .await?
+        .into_inner();
+    if output != OutputFormat::Json {
+        eprintln!("{}", "Tailing gateway traffic (Ctrl+C to exit)".dimmed());
+    }
+
+    let ctrl_c = tokio::signal::ctrl_c();
+    tokio::pin!(ctrl_c);
+    loop {
+        let event = tokio::select! {
+            _ = &mut ctrl_c => break,
+            event = stream.message() => event?,
+        };
+        let Some(event) = event else {
+            bail!("gateway closed the traffic stream");
+        };
+        match output {
+            // One object per line, so the feed can be piped into jq
+            OutputFormat::Json => println!("{}", serde_json::to_string(&event)?),
+            OutputFormat::Text => println!("{}", format_event(&event, true)),
+            OutputFormat::Table => println!("{}", format_event(&event, false)),
+        }
+    }
+    Ok(())
+}
+
+/// One event as a line: time, kind, agent, then whatever else it carries
+pub fn format_event(event: &TrafficEvent, color: bool) -> String {
+    // HH:MM:SS.mmm out of the RFC 3339 timestamp
+    let time = event.timestamp.get(11..23).unwrap_or(&event.timestamp);
+    let kind = format!("{:<8}", event.kind);
+    let kind = if !color {
+        kind
+    } else {
+        match event.kind.as_str() {
+            "inbound" => kind.cyan(),
+            "queued" | "dropped" => kind.yellow(),
+            "response" => kind.green(),
+            "rejected" | "error" => kind.red(),
+            _ => kind.normal(),
+        }
+        .to_string()
+    };
+
+    let mut fields = vec![time.to_string(), kind, event.agent_id.clone()];
+    if !event.request_id.is_empty() {
+        fields.push(format!("req={}", event.request_id));
+    }
+    if !event.sender.is_empty() {
+        fields.push(format!("from={}", event.sender));
+    }
+    if event.kind != "dropped" {
+        fields.push(format!("{}B", event.content_bytes));
+    }
+    if let Some(latency) = event.latency_ms {
+        fields.push(format!("{}ms", latency));
+    }
+    if let Some(detail) = &event.detail {
+        fields.push(format!("({})", detail));
+    }
+    if let Some(content) = &event.content {
+        fields.push(format!("{:?}", preview(content)));
+    }
+    fields.retain(|field| !field.is_empty());
+    fields.join("  ")
+}
+
+/// `content` cut to `PREVIEW_CHARS`, with `…` if anything was left out
+fn preview(content: &str) -> String {
+    let content = content.trim();
+    if content.chars().count() > PREVIEW_CHARS {
+        let truncated: String = content.chars().take(PREVIEW_CHARS).collect();
+        format!("{}…", truncated)
+    } else {
+        content.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(kind: &str) -> TrafficEvent {
+        TrafficEvent {
+            timestamp: "2026-01-02T03:04:05.678901+00:00".to_string(),
+            kind: kind.to_string(),
+            agent_id: "agent-1".to_string(),
+            request_id: "req-1".to_string(),
+            content_bytes: 11,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_format_event() {
+        let inbound = TrafficEvent {
+            sender: "U123".to_string(),
+            content: Some("hello\nthere".to_string()),
+            ..event("inbound")
+        };
+        assert_eq!(
+            format_event(&inbound, false),
+            "03:04:05.678  inbound   agent-1  req=req-1  from=U123  11B  \"hello\\nthere\""
+        );
+
+        // Redacted events have sizes and timing but no content
+        let response = TrafficEvent {
+            latency_ms: Some(1250),
+            ..event("response")
+        };
+        assert_eq!(
+            format_event(&response, false),
+            "03:04:05.678  response  agent-1  req=req-1  11B  1250ms"
+        );
+
+        let dropped = TrafficEvent {
+            agent_id: String::new(),
+            request_id: String::new(),
+            detail: Some("3 events skipped".to_string()),
+            ..event("dropped")
+        };
+        assert_eq!(
+            format_event(&dropped, false),
+            "03:04:05.678  dropped   (3 events skipped)"
+        );
+    }
+}
@@ -1,23 +1,45 @@
 // ABOUTME: Implementation of 'coven-admin token' commands
-// ABOUTME: Creates JWT tokens for principals
+// ABOUTME: Creates, lists, and revokes JWT tokens for principals
 
 use anyhow::{bail, Result};
 use colored::Colorize;
 
 use coven_grpc::ChannelConfig;
-use coven_proto::coven::{admin_service_client::AdminServiceClient, CreateTokenRequest};
+use coven_proto::coven::{
+    admin_service_client::AdminServiceClient, CreateTokenRequest, CreateTokenResponse,
+    ListTokensRequest, RevokeTokenRequest, TokenInfo,
+};
 
-use super::TokenCommand;
+use super::{otp, TokenCommand};
 use crate::client::AuthInterceptor;
+use crate::output::{print_json, OutputFormat, Table};
 
-pub async fn run(gateway: &str, token: Option<&str>, cmd: TokenCommand) -> Result<()> {
+/// Columns of `token create --output table`
+pub const TOKEN_COLUMNS: &[&str] = &["TOKEN_ID", "PRINCIPAL_ID", "EXPIRES_AT", "TOKEN"];
+
+/// Columns of `token list --output table`
+pub const TOKEN_LIST_COLUMNS: &[&str] =
+    &["ID", "PRINCIPAL_ID", "ISSUED_AT", "EXPIRES_AT", "LAST_USED"];
+
+pub async fn run(
+    gateway: &str,
+    token: Option<&str>,
+    cmd: TokenCommand,
+    output: OutputFormat,
+) -> Result<()> {
     let Some(token) = token else {
         bail!("Authentication required. Set COVEN_TOKEN environment variable or use --token flag.");
     };
 
     match cmd {
         TokenCommand::Create { principal_id, ttl } => {
-            create_token(gateway, token, principal_id, ttl).await
+            create_token(gateway, token, principal_id, ttl, output).await
+        }
+        TokenCommand::List { principal_id } => {
+            list_tokens(gateway, token, principal_id, output).await
+        }
+        TokenCommand::Revoke { token_id, otp } => {
+            revoke_token(gateway, token, token_id, otp, output).await
         }
     }
 }
@@ -27,6 +49,7 @@ async fn create_token(
     token: &str,
     principal_id: String,
     ttl_seconds: i64,
+    output: OutputFormat,
 ) -> Result<()> {
     let config = ChannelConfig::new(gateway).without_keep_alive();
     let channel = coven_grpc::create_channel(&config).await?;
@@ -41,9 +64,20 @@ async fn create_token(
 
     let response = client.create_token(request).await?;
     let token_response = response.into_inner();
+    match output {
+        OutputFormat::Json => return print_json(&token_response),
+        OutputFormat::Table => {
+            token_table(&principal_id, &token_response).print();
+            return Ok(());
+        }
+        OutputFormat::Text => {}
+    }
 
     println!("{}", "Token created".green().bold());
     println!();
+    if !token_response.token_id.is_empty() {
+        println!("{}: {}", "Token ID".dimmed(), token_response.token_id);
+    }
     println!("{}: {}", "Principal ID".dimmed(), principal_id);
     println!("{}: {}", "Expires".dimmed(), format_ttl(ttl_seconds));
     println!();
@@ -56,6 +90,113 @@ async fn create_token(
     Ok(())
 }
 
+async fn list_tokens(
+    gateway: &str,
+    token: &str,
+    principal_id: Option<String>,
+    output: OutputFormat,
+) -> Result<()> {
+    let config = ChannelConfig::new(gateway).without_keep_alive();
+    let channel = coven_grpc::create_channel(&config).await?;
+
+    let interceptor = AuthInterceptor::new(Some(token.to_string()));
+    let mut client = AdminServiceClient::with_interceptor(channel, interceptor);
+
+    let response = client
+        .list_tokens(ListTokensRequest { principal_id })
+        .await?
+        .into_inner();
+    match output {
+        OutputFormat::Json => return print_json(&response),
+        OutputFormat::Table => {
+            tokens_table(&response.tokens).print();
+            return Ok(());
+        }
+        OutputFormat::Text => {}
+    }
+    let tokens = response.tokens;
+
+    if tokens.is_empty() {
+        println!("{}", "No active tokens".dimmed());
+        return Ok(());
+    }
+
+    println!("{}", format!("Tokens ({})", tokens.len()).bold());
+    println!();
+
+    for t in tokens {
+        println!("🔑 {}", t.id.bold());
+        println!("    {}: {}", "Principal".dimmed(), t.principal_id);
+        println!("    {}: {}", "Issued".dimmed(), t.issued_at);
+        println!("    {}: {}", "Expires".dimmed(), t.expires_at);
+        println!(
+            "    {}: {}",
+            "Last used".dimmed(),
+            t.last_used_at.as_deref().unwrap_or("never")
+        );
+        println!();
+    }
+
+    Ok(())
+}
+
+async fn revoke_token(
+    gateway: &str,
+    token: &str,
+    token_id: String,
+    otp: Option<String>,
+    output: OutputFormat,
+) -> Result<()> {
+    let config = ChannelConfig::new(gateway).without_keep_alive();
+    let channel = coven_grpc::create_channel(&config).await?;
+
+    let interceptor = AuthInterceptor::new(Some(token.to_string()));
+    let client = AdminServiceClient::with_interceptor(channel, interceptor);
+
+    let request = RevokeTokenRequest {
+        token_id: token_id.clone(),
+    };
+    let response = otp::send(otp.as_deref(), request, |request| {
+        let mut client = client.clone();
+        async move { client.revoke_token(request).await }
+    })
+    .await?
+    .into_inner();
+
+    match output {
+        OutputFormat::Json => print_json(&response)?,
+        OutputFormat::Table => Table::new(&["TOKEN_ID", "REVOKED"])
+            .row([token_id, "true".to_string()])
+            .print(),
+        OutputFormat::Text => println!("{} {}", "Token revoked:".green().bold(), token_id),
+    }
+
+    Ok(())
+}
+
+pub fn token_table(principal_id: &str, response: &CreateTokenResponse) -> Table {
+    Table::new(TOKEN_COLUMNS).row([
+        response.token_id.as_str(),
+        principal_id,
+        response.expires_at.as_str(),
+        response.token.as_str(),
+    ])
+}
+
+pub fn tokens_table(tokens: &[TokenInfo]) -> Table {
+    tokens
+        .iter()
+        .fold(Table::new(TOKEN_LIST_COLUMNS), |table, t| {
+            table.row([
+                t.id.clone(),
+                t.principal_id.clone(),
+                t.issued_at.clone(),
+                t.expires_at.clone(),
+                t.last_used_at.clone().unwrap_or_default(),
+            ])
+        })
+}
+
 fn format_ttl(seconds: i64) -> String {
     let days = seconds / 86400;
     let hours = (seconds % 86400) / 3600;
@@ -72,3 +213,55 @@ fn format_ttl(seconds: i64) -> String {
         format!("{} seconds", seconds)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_token_table() {
+        let response = CreateTokenResponse {
+            token: "eyJhbGciOi.payload.sig".to_string(),
+            expires_at: "2026-02-01T00:00:00Z".to_string(),
+            token_id: "t-1".to_string(),
+        };
+        assert_eq!(
+            token_table("p-1", &response).render(),
+            "TOKEN_ID  PRINCIPAL_ID  EXPIRES_AT            TOKEN\n\
+             t-1       p-1           2026-02-01T00:00:00Z  eyJhbGciOi.payload.sig\n"
+        );
+    }
+
+    #[test]
+    fn test_tokens_table_marks_unused_tokens() {
+        let tokens = [
+            TokenInfo {
+                id: "t-1".to_string(),
+                principal_id: "p-1".to_string(),
+                issued_at: "2026-01-01T00:00:00Z".to_string(),
+                expires_at: "2026-01-31T00:00:00Z".to_string(),
+                last_used_at: Some("2026-01-02T09:30:00Z".to_string()),
+            },
+            TokenInfo {
+                id: "t-2".to_string(),
+                principal_id: "p-2".to_string(),
+                issued_at: "2026-01-05T00:00:00Z".to_string(),
+                expires_at: "2026-02-04T00:00:00Z".to_string(),
+                last_used_at: None,
+            },
+        ];
+        assert_eq!(
+            tokens_table(&tokens).render(),
+            "ID   PRINCIPAL_ID  ISSUED_AT             EXPIRES_AT            LAST_USED\n\
+             t-1  p-1           2026-01-01T00:00:00Z  2026-01-31T00:00:00Z  2026-01-02T09:30:00Z\n\
+             t-2  p-2           2026-01-05T00:00:00Z  2026-02-04T00:00:00Z  -\n"
+        );
+    }
+
+    #[test]
+    fn test_format_ttl() {
+        assert_eq!(format_ttl(2_592_000), "30 days");
+        assert_eq!(format_ttl(90_000), "1 days, 1 hours");
+        assert_eq!(format_ttl(59), "59 seconds");
+    }
+}
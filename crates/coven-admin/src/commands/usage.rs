@@ -0,0 +1,213 @@
+// ABOUTME: Implementation of 'coven-admin usage' command
+// ABOUTME: Shows the token and cost spend connected agents report against their budgets
+
+use anyhow::{bail, Result};
+use colored::Colorize;
+use serde::Serialize;
+
+use coven_grpc::ChannelConfig;
+use coven_proto::coven::{
+    admin_service_client::AdminServiceClient, client_service_client::ClientServiceClient,
+    BudgetStatus, GetAgentRequest, ListAgentsRequest,
+};
+
+use crate::client::AuthInterceptor;
+use crate::output::{print_json, OutputFormat, Table};
+
+/// Columns of `usage --output table`
+pub const USAGE_COLUMNS: &[&str] = &["AGENT", "DAY", "TOKENS", "MAX_TOKENS", "COST", "MAX_COST"];
+
+/// One agent's reported spend; `budget` is None until the agent has
+/// reported any, or when it has no budget configured
+#[derive(Debug, Serialize)]
+pub struct AgentUsage {
+    pub agent_id: String,
+    pub name: String,
+    pub budget: Option<BudgetStatus>,
+}
+
+pub async fn run(
+    gateway: &str,
+    token: Option<&str>,
+    agent: Option<String>,
+    output: OutputFormat,
+) -> Result<()> {
+    let config = ChannelConfig::new(gateway).without_keep_alive();
+    let channel = coven_grpc::create_channel(&config).await?;
+
+    let agent_ids = match agent {
+        Some(agent_id) => vec![agent_id],
+        None => {
+            let Some(token) = token else {
+                bail!("Authentication required to list agents. Set COVEN_TOKEN environment variable, use --token flag, or pass --agent.");
+            };
+            let interceptor = AuthInterceptor::new(Some(token.to_string()));
+            let mut client = ClientServiceClient::with_interceptor(channel.clone(), interceptor);
+            let response = client
+                .list_agents(ListAgentsRequest { workspace: None })
+                .await?
+                .into_inner();
+            response
+                .agents
+                .into_iter()
+                .filter(|agent| agent.connected)
+                .map(|agent| agent.id)
+                .collect()
+        }
+    };
+
+    // Served by the admin service, which the local gateway runs without auth
+    let interceptor = AuthInterceptor::new(token.map(String::from));
+    let mut client = AdminServiceClient::with_interceptor(channel, interceptor);
+    let mut usage = Vec::with_capacity(agent_ids.len());
+    for agent_id in agent_ids {
+        let response = client
+            .get_agent(GetAgentRequest {
+                agent_id: agent_id.clone(),
+                activity_limit: 0,
+            })
+            .await?
+            .into_inner();
+        usage.push(AgentUsage {
+            agent_id,
+            name: response.agent.map(|agent| agent.name).unwrap_or_default(),
+            budget: response.connection.and_then(|connection| connection.budget),
+        });
+    }
+
+    match output {
+        OutputFormat::Json => print_json(&usage),
+        OutputFormat::Table => {
+            usage_table(&usage).print();
+            Ok(())
+        }
+        OutputFormat::Text => {
+            print_usage(&usage);
+            Ok(())
+        }
+    }
+}
+
+fn print_usage(usage: &[AgentUsage]) {
+    if usage.is_empty() {
+        println!("{}", "No agents connected".dimmed());
+        return;
+    }
+
+    println!("{}", "Budget Usage".bold());
+    println!();
+    for agent in usage {
+        println!(
+            "{} {}",
+            agent.name.bold(),
+            format!("({})", agent.agent_id).dimmed()
+        );
+        let Some(budget) = &agent.budget else {
+            println!("    {}", "No spend reported".dimmed());
+            continue;
+        };
+        println!("    {}: {}", "Day".dimmed(), budget.day);
+        println!(
+            "    {}: {}",
+            "Tokens".dimmed(),
+            spend(
+                budget.day_tokens.to_string(),
+                budget.max_tokens_per_day.map(|max| max.to_string()),
+                budget.day_tokens as f64,
+                budget.max_tokens_per_day.map(|max| max as f64),
+            )
+        );
+        println!(
+            "    {}: {}",
+            "Cost".dimmed(),
+            spend(
+                format!("${:.2}", budget.day_cost_usd),
+                budget
+                    .max_cost_usd_per_day
+                    .map(|max| format!("${:.2}", max)),
+                budget.day_cost_usd,
+                budget.max_cost_usd_per_day,
+            )
+        );
+        for alert in &budget.alerts {
+            let line = format!("{} {} of {}", alert.limit, alert.spent, alert.max);
+            if alert.kind == "exceeded" {
+                println!("    {} {}", "exceeded:".red(), line);
+            } else {
+                println!("    {} {}", "warning:".yellow(), line);
+            }
+        }
+    }
+}
+
+/// `spent` of `max`, colored by how much of the limit is used
+fn spend(spent: String, max: Option<String>, spent_value: f64, max_value: Option<f64>) -> String {
+    let (Some(max), Some(max_value)) = (max, max_value) else {
+        return spent;
+    };
+    let text = format!("{} of {}", spent, max);
+    if spent_value >= max_value {
+        text.red().to_string()
+    } else if spent_value >= max_value * 0.8 {
+        text.yellow().to_string()
+    } else {
+        text
+    }
+}
+
+fn usage_table(usage: &[AgentUsage]) -> Table {
+    usage
+        .iter()
+        .fold(Table::new(USAGE_COLUMNS), |table, agent| {
+            let Some(budget) = &agent.budget else {
+                return table.row([agent.agent_id.clone()]);
+            };
+            table.row([
+                agent.agent_id.clone(),
+                budget.day.clone(),
+                budget.day_tokens.to_string(),
+                budget
+                    .max_tokens_per_day
+                    .map(|max| max.to_string())
+                    .unwrap_or_default(),
+                format!("{:.2}", budget.day_cost_usd),
+                budget
+                    .max_cost_usd_per_day
+                    .map(|max| format!("{:.2}", max))
+                    .unwrap_or_default(),
+            ])
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_usage_table() {
+        let usage = [
+            AgentUsage {
+                agent_id: "agent-1".to_string(),
+                name: "builder".to_string(),
+                budget: Some(BudgetStatus {
+                    day: "2026-10-16".to_string(),
+                    day_tokens: 120_000,
+                    day_cost_usd: 1.5,
+                    max_cost_usd_per_day: Some(20.0),
+                    ..Default::default()
+                }),
+            },
+            AgentUsage {
+                agent_id: "agent-2".to_string(),
+                name: "reviewer".to_string(),
+                budget: None,
+            },
+        ];
+        assert_eq!(
+            usage_table(&usage).render(),
+            "AGENT    DAY         TOKENS  MAX_TOKENS  COST  MAX_COST\n\
+             agent-1  2026-10-16  120000  -           1.50  20.00\n\
+             agent-2  -           -       -           -     -\n"
+        );
+    }
+}
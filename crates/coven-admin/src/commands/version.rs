@@ -0,0 +1,45 @@
+// ABOUTME: Gateway version lookup used by 'coven version --check'
+// ABOUTME: Queries ClientService.GetVersion, treating an unimplemented RPC as an unknown version
+
+use anyhow::Result;
+use std::time::Duration;
+
+use coven_grpc::ChannelConfig;
+use coven_proto::coven::client_service_client::ClientServiceClient;
+
+use crate::client::AuthInterceptor;
+
+/// How long to wait for the gateway before reporting it unreachable
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Gateway version as reported by GetVersion
+pub struct GatewayVersion {
+    pub version: String,
+    pub component: String,
+}
+
+/// Ask the gateway for its version.
+///
+/// Returns `Ok(None)` when the gateway predates the GetVersion RPC, and an
+/// error when it can't be reached.
+pub async fn fetch(gateway: &str, token: Option<&str>) -> Result<Option<GatewayVersion>> {
+    let config = ChannelConfig::new(gateway)
+        .without_keep_alive()
+        .with_connect_timeout(CONNECT_TIMEOUT);
+    let channel = coven_grpc::create_channel(&config).await?;
+
+    let interceptor = AuthInterceptor::new(token.map(String::from));
+    let mut client = ClientServiceClient::with_interceptor(channel, interceptor);
+
+    match client.get_version(()).await {
+        Ok(response) => {
+            let response = response.into_inner();
+            Ok(Some(GatewayVersion {
+                version: response.version,
+                component: response.component,
+            }))
+        }
+        Err(status) if status.code() == tonic::Code::Unimplemented => Ok(None),
+        Err(status) => Err(status.into()),
+    }
+}
@@ -2,27 +2,34 @@
 // ABOUTME: Allows coven-cli to integrate admin commands directly
 
 use anyhow::Result;
+use colored::Colorize;
 
 pub mod client;
 pub mod commands;
+pub mod output;
+pub mod terminal;
 
-pub use commands::{AgentsCommand, BindingsCommand, Command, PrincipalsCommand, TokenCommand};
+pub use commands::binding_file::BindingsFormat;
+pub use commands::{
+    AgentsCommand, BindingsCommand, Command, DeadletterCommand, OtpCommand, PacksCommand,
+    PairCommand, PrincipalsCommand, SecretsCommand, TokenCommand,
+};
+pub use output::OutputFormat;
 
-/// Config file structure (subset of what coven-link writes)
-#[derive(serde::Deserialize, Default)]
+/// Gateway and token from the coven-link config, for the active profile
+#[derive(Default)]
 pub struct CovenConfig {
-    #[serde(default)]
     pub gateway: Option<String>,
-    #[serde(default)]
     pub token: Option<String>,
 }
 
 impl CovenConfig {
     pub fn load() -> Self {
-        dirs::home_dir()
-            .map(|d| d.join(".config/coven/config.toml"))
-            .and_then(|p| std::fs::read_to_string(p).ok())
-            .and_then(|s| toml::from_str(&s).ok())
+        coven_link::config::CovenConfig::load()
+            .map(|config| Self {
+                gateway: Some(config.gateway),
+                token: Some(config.token).filter(|t| !t.is_empty()),
+            })
             .unwrap_or_default()
     }
 }
@@ -30,19 +37,19 @@ impl CovenConfig {
 /// Normalize gateway address to include scheme
 pub fn normalize_gateway(gateway: &str) -> String {
     let g = gateway.trim();
-    if g.starts_with("http://") || g.starts_with("https://") {
+    if g.starts_with("http://") || g.starts_with("https://") || g.starts_with("unix://") {
         return g.to_string();
     }
     // Default to http for gRPC (TLS usually handled at network layer e.g. Tailscale)
     format!("http://{}", g)
 }
 
-/// Run an admin command with the given gateway and token
-pub async fn run_command(
-    command: Command,
+/// Resolve the gateway address and token to use, filling in whatever the
+/// caller didn't pass from the environment and the config file.
+pub fn resolve_connection(
     gateway: Option<String>,
     token: Option<String>,
-) -> Result<()> {
+) -> (String, Option<String>) {
     // Load config file for defaults
     let config = CovenConfig::load();
 
@@ -58,13 +65,57 @@ pub async fn run_command(
         .or_else(|| std::env::var("COVEN_TOKEN").ok())
         .or(config.token);
 
+    (gateway, token)
+}
+
+/// Run an admin command with the given gateway and token, printing the
+/// result in `output` format. RPC failures are returned as errors in every
+/// format, so the process exits non-zero even when printing JSON.
+pub async fn run_command(
+    command: Command,
+    gateway: Option<String>,
+    token: Option<String>,
+    output: OutputFormat,
+) -> Result<()> {
+    // Only the config's token is ours to renew
+    if token.is_none() && std::env::var("COVEN_TOKEN").is_err() {
+        if let Err(e) = coven_link::refresh_token_if_needed().await {
+            eprintln!("{} {:#}", "Can't refresh token:".yellow(), e);
+        }
+    }
+    let (gateway, token) = resolve_connection(gateway, token);
+    let token = token.as_deref();
+
     match command {
-        Command::Me => commands::me::run(&gateway, token.as_deref()).await,
-        Command::Agents(cmd) => commands::agents::run(&gateway, token.as_deref(), cmd).await,
-        Command::Bindings(cmd) => commands::bindings::run(&gateway, token.as_deref(), cmd).await,
-        Command::Principals(cmd) => {
-            commands::principals::run(&gateway, token.as_deref(), cmd).await
+        Command::Me => commands::me::run(&gateway, token, output).await,
+        Command::Agents(cmd) => commands::agents::run(&gateway, token, cmd, output).await,
+        Command::Bindings(cmd) => commands::bindings::run(&gateway, token, cmd, output).await,
+        Command::Principals(cmd) => commands::principals::run(&gateway, token, cmd, output).await,
+        Command::Token(cmd) => commands::token::run(&gateway, token, cmd, output).await,
+        Command::Deadletter(cmd) => commands::deadletter::run(&gateway, token, cmd, output).await,
+        Command::Packs(cmd) => commands::packs::run(&gateway, token, cmd, output).await,
+        Command::Secrets(cmd) => commands::secrets::run(&gateway, token, cmd, output).await,
+        Command::Pair(cmd) => commands::pair::run(&gateway, token, cmd, output).await,
+        Command::Otp(cmd) => commands::otp::run(&gateway, token, cmd, output).await,
+        Command::Tail { agent, redact } => {
+            commands::tail::run(&gateway, token, agent, redact, output).await
+        }
+        Command::Usage { agent } => commands::usage::run(&gateway, token, agent, output).await,
+        Command::Export {
+            agent,
+            since,
+            until,
+            state,
+            out,
+        } => {
+            let options = commands::export::ExportOptions {
+                agent,
+                since,
+                until,
+                state,
+                out,
+            };
+            commands::export::run(&gateway, token, options, output).await
         }
-        Command::Token(cmd) => commands::token::run(&gateway, token.as_deref(), cmd).await,
     }
 }
@@ -15,5 +15,5 @@ async fn main() -> Result<()> {
 
     let cli = Cli::parse();
 
-    coven_admin::run_command(cli.command, cli.gateway, cli.token).await
+    coven_admin::run_command(cli.command, cli.gateway, cli.token, cli.output).await
 }
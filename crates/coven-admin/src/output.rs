@@ -0,0 +1,181 @@
+// ABOUTME: Output formats shared by admin commands: colored text, JSON, and aligned tables.
+// ABOUTME: JSON prints the gateway's response messages as they are; tables are plain padded columns.
+
+use anyhow::Result;
+use serde::Serialize;
+
+/// How a command prints its result
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum)]
+pub enum OutputFormat {
+    /// Human-readable, colored text
+    #[default]
+    Text,
+    /// The gateway's response as JSON, for scripts
+    Json,
+    /// Aligned columns without color, for grep and awk
+    Table,
+}
+
+/// Print a gateway response as pretty JSON on stdout.
+pub fn print_json<T: Serialize>(value: &T) -> Result<()> {
+    println!("{}", serde_json::to_string_pretty(value)?);
+    Ok(())
+}
+
+/// Rows of plain text under a header, padded so columns line up.
+///
+/// Every row is one line: newlines in a cell become spaces, and an empty
+/// cell shows as `-` so each line splits into the same number of fields.
+#[derive(Debug, Clone)]
+pub struct Table {
+    headers: Vec<&'static str>,
+    rows: Vec<Vec<String>>,
+}
+
+impl Table {
+    pub fn new(headers: &[&'static str]) -> Self {
+        Self {
+            headers: headers.to_vec(),
+            rows: Vec::new(),
+        }
+    }
+
+    /// Add a row; missing cells are left empty and extra ones dropped.
+    pub fn row<I, S>(mut self, cells: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        let mut row: Vec<String> = cells
+            .into_iter()
+            .map(|cell| clean_cell(&cell.into()))
+            .take(self.headers.len())
+            .collect();
+        row.resize(self.headers.len(), "-".to_string());
+        self.rows.push(row);
+        self
+    }
+
+    pub fn render(&self) -> String {
+        let mut widths: Vec<usize> = self.headers.iter().map(|h| h.chars().count()).collect();
+        for row in &self.rows {
+            for (width, cell) in widths.iter_mut().zip(row) {
+                *width = (*width).max(cell.chars().count());
+            }
+        }
+
+        let mut out = String::new();
+        let header: Vec<String> = self.headers.iter().map(|h| h.to_string()).collect();
+        for row in std::iter::once(&header).chain(&self.rows) {
+            let mut line = String::new();
+            for (i, (cell, width)) in row.iter().zip(&widths).enumerate() {
+                if i > 0 {
+                    line.push_str("  ");
+                }
+                line.push_str(cell);
+                line.extend(std::iter::repeat(' ').take(width - cell.chars().count()));
+            }
+            out.push_str(line.trim_end());
+            out.push('\n');
+        }
+        out
+    }
+
+    pub fn print(&self) {
+        print!("{}", self.render());
+    }
+}
+
+fn clean_cell(cell: &str) -> String {
+    let cell = cell.split_whitespace().collect::<Vec<_>>().join(" ");
+    if cell.is_empty() {
+        "-".to_string()
+    } else {
+        cell
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use coven_proto::coven::{
+        AgentInfo, Binding, ListAgentsResponse, ListBindingsResponse, ListPrincipalsResponse,
+        Principal,
+    };
+    use serde::de::DeserializeOwned;
+
+    #[test]
+    fn test_table_aligns_columns() {
+        let table = Table::new(&["ID", "NAME", "STATUS"])
+            .row(["a1", "alpha", "online"])
+            .row(["agent-two", "b", "offline"]);
+        assert_eq!(
+            table.render(),
+            "ID         NAME   STATUS\n\
+             a1         alpha  online\n\
+             agent-two  b      offline\n"
+        );
+    }
+
+    #[test]
+    fn test_table_keeps_rows_on_one_line() {
+        let table = Table::new(&["KEY", "VALUE", "NOTE"])
+            .row(["k", "two\nlines", ""])
+            .row(["short"]);
+        assert_eq!(
+            table.render(),
+            "KEY    VALUE      NOTE\n\
+             k      two lines  -\n\
+             short  -          -\n"
+        );
+    }
+
+    #[test]
+    fn test_empty_table_prints_header() {
+        assert_eq!(Table::new(&["ID", "AGENT"]).render(), "ID  AGENT\n");
+    }
+
+    fn assert_json_round_trip<T>(value: T)
+    where
+        T: Serialize + DeserializeOwned + PartialEq + std::fmt::Debug,
+    {
+        let json = serde_json::to_string_pretty(&value).unwrap();
+        let back: T = serde_json::from_str(&json).unwrap();
+        assert_eq!(back, value);
+    }
+
+    #[test]
+    fn test_json_output_round_trips_to_responses() {
+        assert_json_round_trip(ListAgentsResponse {
+            agents: vec![AgentInfo {
+                id: "agent-1".to_string(),
+                name: "Hex".to_string(),
+                backend: "direct".to_string(),
+                working_dir: "/src".to_string(),
+                connected: true,
+                ..Default::default()
+            }],
+        });
+        assert_json_round_trip(ListBindingsResponse {
+            bindings: vec![Binding {
+                id: "b-1".to_string(),
+                frontend: "slack".to_string(),
+                channel_id: "C1".to_string(),
+                agent_id: "agent-1".to_string(),
+                created_at: "2026-01-02T03:04:05Z".to_string(),
+                ..Default::default()
+            }],
+        });
+        assert_json_round_trip(ListPrincipalsResponse {
+            principals: vec![Principal {
+                id: "p-1".to_string(),
+                r#type: "client".to_string(),
+                display_name: "Ops".to_string(),
+                status: "approved".to_string(),
+                roles: vec!["owner".to_string()],
+                pubkey_fp: Some("ab12".to_string()),
+                ..Default::default()
+            }],
+        });
+    }
+}
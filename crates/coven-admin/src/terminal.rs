@@ -0,0 +1,93 @@
+// ABOUTME: Redraws a block of text in place on the terminal, for commands that watch the gateway
+// ABOUTME: Hides the cursor while drawing and always shows it again when dropped
+
+use std::io::{self, IsTerminal, Stdout, Write};
+
+const CLEAR_SCREEN: &str = "\x1b[H\x1b[2J";
+const HIDE_CURSOR: &str = "\x1b[?25l";
+const SHOW_CURSOR: &str = "\x1b[?25h";
+
+/// Output whose frames replace each other on a terminal. Anywhere else
+/// (a pipe, a file) frames are appended one after another instead.
+pub struct LiveView<W: Write> {
+    out: W,
+    live: bool,
+}
+
+impl LiveView<Stdout> {
+    /// Draw on stdout, in place when it's a terminal
+    pub fn stdout() -> io::Result<Self> {
+        let live = io::stdout().is_terminal();
+        Self::new(io::stdout(), live)
+    }
+}
+
+impl<W: Write> LiveView<W> {
+    pub fn new(mut out: W, live: bool) -> io::Result<Self> {
+        if live {
+            out.write_all(HIDE_CURSOR.as_bytes())?;
+            out.flush()?;
+        }
+        Ok(Self { out, live })
+    }
+
+    /// Whether frames are redrawn in place
+    pub fn is_live(&self) -> bool {
+        self.live
+    }
+
+    /// Replace the previous frame with `frame`
+    pub fn draw(&mut self, frame: &str) -> io::Result<()> {
+        if self.live {
+            self.out.write_all(CLEAR_SCREEN.as_bytes())?;
+        }
+        self.out.write_all(frame.as_bytes())?;
+        if !frame.ends_with('\n') {
+            self.out.write_all(b"\n")?;
+        }
+        if !self.live {
+            self.out.write_all(b"\n")?;
+        }
+        self.out.flush()
+    }
+}
+
+impl<W: Write> Drop for LiveView<W> {
+    fn drop(&mut self) {
+        if self.live {
+            let _ = self.out.write_all(SHOW_CURSOR.as_bytes());
+            let _ = self.out.flush();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_live_frames_redraw_and_restore_cursor() {
+        let mut out = Vec::new();
+        {
+            let mut view = LiveView::new(&mut out, true).unwrap();
+            view.draw("one").unwrap();
+            view.draw("two\n").unwrap();
+        }
+        let out = String::from_utf8(out).unwrap();
+        assert_eq!(
+            out,
+            format!("{HIDE_CURSOR}{CLEAR_SCREEN}one\n{CLEAR_SCREEN}two\n{SHOW_CURSOR}")
+        );
+    }
+
+    #[test]
+    fn test_plain_frames_append() {
+        let mut out = Vec::new();
+        {
+            let mut view = LiveView::new(&mut out, false).unwrap();
+            view.draw("one").unwrap();
+            view.draw("two").unwrap();
+        }
+        assert_eq!(String::from_utf8(out).unwrap(), "one\n\ntwo\n\n");
+    }
+}
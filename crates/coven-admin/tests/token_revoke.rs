@@ -0,0 +1,364 @@
+// ABOUTME: Runs the token list and revoke commands against an in-process fake gateway.
+// ABOUTME: The fake checks revocation on every call, so a revoked token fails its next RPC.
+
+use coven_admin::{Command, OutputFormat, TokenCommand};
+use coven_proto::client::AdminServiceClient;
+use coven_proto::server::{AdminService, AdminServiceServer};
+use coven_proto::{
+    ApprovePairingRequest, ApprovePairingResponse, Binding, CreateBindingRequest,
+    CreatePrincipalRequest, CreateTokenRequest, CreateTokenResponse, DeleteBindingRequest,
+    DeleteBindingResponse, DeletePackSecretRequest, DeletePackSecretResponse,
+    DeletePrincipalRequest, DeletePrincipalResponse, EnrollOtpRequest, EnrollOtpResponse,
+    ExportRecord, ExportThreadsRequest, GetAgentRequest, GetAgentResponse, ListBindingsRequest,
+    ListBindingsResponse, ListDeadLettersRequest, ListDeadLettersResponse, ListPackSecretsRequest,
+    ListPackSecretsResponse, ListPacksRequest, ListPacksResponse, ListPrincipalsRequest,
+    ListPrincipalsResponse, ListPushTokensRequest, ListPushTokensResponse,
+    ListRecentChannelsRequest, ListRecentChannelsResponse, ListTokensRequest, ListTokensResponse,
+    Principal, PurgeDeadLettersRequest, PurgeDeadLettersResponse, ReplayDeadLettersRequest,
+    ReplayDeadLettersResponse, RevokeTokenRequest, RevokeTokenResponse, RotateKeyResponse,
+    RotatePrincipalKeyRequest, SetAgentLogLevelRequest, SetAgentLogLevelResponse,
+    SetPackSecretRequest, SetPackSecretResponse, TailTrafficRequest, TokenInfo, TrafficEvent,
+    UpdateBindingRequest, UpdatePrincipalRequest,
+};
+use std::collections::BTreeMap;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use tokio::net::TcpListener;
+use tokio_stream::wrappers::TcpListenerStream;
+use tonic::{Request, Response, Status};
+
+/// Issued tokens by ID; the bearer token for ID `x` is `jwt-x`.
+type Tokens = Arc<Mutex<BTreeMap<String, TokenInfo>>>;
+
+/// Reject any call whose bearer token is unknown or revoked, as the real
+/// gateway must on every authenticated call.
+fn authenticate(tokens: &Tokens, request: &Request<()>) -> Result<(), Status> {
+    let id = request
+        .metadata()
+        .get("authorization")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer jwt-"))
+        .ok_or_else(|| Status::unauthenticated("missing token"))?;
+    if tokens.lock().unwrap().contains_key(id) {
+        Ok(())
+    } else {
+        Err(Status::unauthenticated("token revoked"))
+    }
+}
+
+struct FakeGateway {
+    tokens: Tokens,
+}
+
+#[tonic::async_trait]
+impl AdminService for FakeGateway {
+    async fn list_tokens(
+        &self,
+        request: Request<ListTokensRequest>,
+    ) -> Result<Response<ListTokensResponse>, Status> {
+        let filter = request.into_inner().principal_id;
+        let tokens = self
+            .tokens
+            .lock()
+            .unwrap()
+            .values()
+            .filter(|t| filter.as_ref().is_none_or(|p| *p == t.principal_id))
+            .cloned()
+            .collect();
+        Ok(Response::new(ListTokensResponse { tokens }))
+    }
+
+    async fn revoke_token(
+        &self,
+        request: Request<RevokeTokenRequest>,
+    ) -> Result<Response<RevokeTokenResponse>, Status> {
+        let id = request.into_inner().token_id;
+        match self.tokens.lock().unwrap().remove(&id) {
+            Some(_) => Ok(Response::new(RevokeTokenResponse {})),
+            None => Err(Status::not_found(format!("no token {}", id))),
+        }
+    }
+
+    async fn create_token(
+        &self,
+        _request: Request<CreateTokenRequest>,
+    ) -> Result<Response<CreateTokenResponse>, Status> {
+        Err(Status::unimplemented("create_token"))
+    }
+
+    async fn list_bindings(
+        &self,
+        _request: Request<ListBindingsRequest>,
+    ) -> Result<Response<ListBindingsResponse>, Status> {
+        Err(Status::unimplemented("list_bindings"))
+    }
+
+    async fn create_binding(
+        &self,
+        _request: Request<CreateBindingRequest>,
+    ) -> Result<Response<Binding>, Status> {
+        Err(Status::unimplemented("create_binding"))
+    }
+
+    async fn update_binding(
+        &self,
+        _request: Request<UpdateBindingRequest>,
+    ) -> Result<Response<Binding>, Status> {
+        Err(Status::unimplemented("update_binding"))
+    }
+
+    async fn delete_binding(
+        &self,
+        _request: Request<DeleteBindingRequest>,
+    ) -> Result<Response<DeleteBindingResponse>, Status> {
+        Err(Status::unimplemented("delete_binding"))
+    }
+
+    async fn list_recent_channels(
+        &self,
+        _request: Request<ListRecentChannelsRequest>,
+    ) -> Result<Response<ListRecentChannelsResponse>, Status> {
+        Err(Status::unimplemented("list_recent_channels"))
+    }
+
+    async fn list_principals(
+        &self,
+        _request: Request<ListPrincipalsRequest>,
+    ) -> Result<Response<ListPrincipalsResponse>, Status> {
+        Err(Status::unimplemented("list_principals"))
+    }
+
+    async fn create_principal(
+        &self,
+        _request: Request<CreatePrincipalRequest>,
+    ) -> Result<Response<Principal>, Status> {
+        Err(Status::unimplemented("create_principal"))
+    }
+
+    async fn update_principal(
+        &self,
+        _request: Request<UpdatePrincipalRequest>,
+    ) -> Result<Response<Principal>, Status> {
+        Err(Status::unimplemented("update_principal"))
+    }
+
+    async fn delete_principal(
+        &self,
+        _request: Request<DeletePrincipalRequest>,
+    ) -> Result<Response<DeletePrincipalResponse>, Status> {
+        Err(Status::unimplemented("delete_principal"))
+    }
+
+    async fn rotate_principal_key(
+        &self,
+        _request: Request<RotatePrincipalKeyRequest>,
+    ) -> Result<Response<RotateKeyResponse>, Status> {
+        Err(Status::unimplemented("rotate_principal_key"))
+    }
+
+    async fn approve_pairing(
+        &self,
+        _request: Request<ApprovePairingRequest>,
+    ) -> Result<Response<ApprovePairingResponse>, Status> {
+        Err(Status::unimplemented("approve_pairing"))
+    }
+
+    async fn list_dead_letters(
+        &self,
+        _request: Request<ListDeadLettersRequest>,
+    ) -> Result<Response<ListDeadLettersResponse>, Status> {
+        Err(Status::unimplemented("list_dead_letters"))
+    }
+
+    async fn replay_dead_letters(
+        &self,
+        _request: Request<ReplayDeadLettersRequest>,
+    ) -> Result<Response<ReplayDeadLettersResponse>, Status> {
+        Err(Status::unimplemented("replay_dead_letters"))
+    }
+
+    async fn purge_dead_letters(
+        &self,
+        _request: Request<PurgeDeadLettersRequest>,
+    ) -> Result<Response<PurgeDeadLettersResponse>, Status> {
+        Err(Status::unimplemented("purge_dead_letters"))
+    }
+
+    async fn list_packs(
+        &self,
+        _request: Request<ListPacksRequest>,
+    ) -> Result<Response<ListPacksResponse>, Status> {
+        Err(Status::unimplemented("list_packs"))
+    }
+
+    async fn set_pack_secret(
+        &self,
+        _request: Request<SetPackSecretRequest>,
+    ) -> Result<Response<SetPackSecretResponse>, Status> {
+        Err(Status::unimplemented("set_pack_secret"))
+    }
+
+    async fn delete_pack_secret(
+        &self,
+        _request: Request<DeletePackSecretRequest>,
+    ) -> Result<Response<DeletePackSecretResponse>, Status> {
+        Err(Status::unimplemented("delete_pack_secret"))
+    }
+
+    async fn list_pack_secrets(
+        &self,
+        _request: Request<ListPackSecretsRequest>,
+    ) -> Result<Response<ListPackSecretsResponse>, Status> {
+        Err(Status::unimplemented("list_pack_secrets"))
+    }
+
+    async fn get_agent(
+        &self,
+        _request: Request<GetAgentRequest>,
+    ) -> Result<Response<GetAgentResponse>, Status> {
+        Err(Status::unimplemented("get_agent"))
+    }
+
+    type TailTrafficStream =
+        Pin<Box<dyn tokio_stream::Stream<Item = Result<TrafficEvent, Status>> + Send>>;
+
+    async fn tail_traffic(
+        &self,
+        _request: Request<TailTrafficRequest>,
+    ) -> Result<Response<Self::TailTrafficStream>, Status> {
+        Err(Status::unimplemented("tail_traffic"))
+    }
+
+    async fn list_push_tokens(
+        &self,
+        _request: Request<ListPushTokensRequest>,
+    ) -> Result<Response<ListPushTokensResponse>, Status> {
+        Err(Status::unimplemented("list_push_tokens"))
+    }
+
+    async fn enroll_otp(
+        &self,
+        _request: Request<EnrollOtpRequest>,
+    ) -> Result<Response<EnrollOtpResponse>, Status> {
+        Err(Status::unimplemented("enroll_otp"))
+    }
+
+    async fn set_agent_log_level(
+        &self,
+        _request: Request<SetAgentLogLevelRequest>,
+    ) -> Result<Response<SetAgentLogLevelResponse>, Status> {
+        Err(Status::unimplemented("set_agent_log_level"))
+    }
+
+    type ExportThreadsStream =
+        Pin<Box<dyn tokio_stream::Stream<Item = Result<ExportRecord, Status>> + Send>>;
+
+    async fn export_threads(
+        &self,
+        _request: Request<ExportThreadsRequest>,
+    ) -> Result<Response<Self::ExportThreadsStream>, Status> {
+        Err(Status::unimplemented("export_threads"))
+    }
+}
+
+fn token(id: &str, principal_id: &str) -> TokenInfo {
+    TokenInfo {
+        id: id.to_string(),
+        principal_id: principal_id.to_string(),
+        issued_at: "2026-01-01T00:00:00Z".to_string(),
+        expires_at: "2026-01-31T00:00:00Z".to_string(),
+        last_used_at: None,
+    }
+}
+
+async fn start_gateway(tokens: Tokens) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    let auth_tokens = tokens.clone();
+    let service = AdminServiceServer::with_interceptor(FakeGateway { tokens }, move |req| {
+        authenticate(&auth_tokens, &req).map(|()| req)
+    });
+    tokio::spawn(async move {
+        tonic::transport::Server::builder()
+            .add_service(service)
+            .serve_with_incoming(TcpListenerStream::new(listener))
+            .await
+            .unwrap();
+    });
+    url
+}
+
+async fn run_token(url: &str, bearer: &str, cmd: TokenCommand) -> anyhow::Result<()> {
+    coven_admin::run_command(
+        Command::Token(cmd),
+        Some(url.to_string()),
+        Some(bearer.to_string()),
+        OutputFormat::Json,
+    )
+    .await
+}
+
+#[tokio::test]
+async fn test_revoked_token_is_rejected_on_next_rpc() {
+    let tokens: Tokens = Arc::new(Mutex::new(BTreeMap::from([
+        ("admin".to_string(), token("admin", "p-owner")),
+        ("leaked".to_string(), token("leaked", "p-laptop")),
+    ])));
+    let url = start_gateway(tokens.clone()).await;
+
+    // The leaked token works until it is revoked
+    run_token(
+        &url,
+        "jwt-leaked",
+        TokenCommand::List { principal_id: None },
+    )
+    .await
+    .unwrap();
+
+    run_token(
+        &url,
+        "jwt-admin",
+        TokenCommand::Revoke {
+            token_id: "leaked".to_string(),
+            otp: None,
+        },
+    )
+    .await
+    .unwrap();
+
+    let err = run_token(
+        &url,
+        "jwt-leaked",
+        TokenCommand::List { principal_id: None },
+    )
+    .await
+    .unwrap_err();
+    let status = err.downcast_ref::<Status>().expect("gRPC status");
+    assert_eq!(status.code(), tonic::Code::Unauthenticated);
+
+    // The admin token is unaffected and no longer lists the revoked one
+    let mut client = AdminServiceClient::connect(url.clone()).await.unwrap();
+    let mut request = Request::new(ListTokensRequest { principal_id: None });
+    request
+        .metadata_mut()
+        .insert("authorization", "Bearer jwt-admin".parse().unwrap());
+    let listed = client.list_tokens(request).await.unwrap().into_inner();
+    let ids: Vec<_> = listed.tokens.iter().map(|t| t.id.as_str()).collect();
+    assert_eq!(ids, vec!["admin"]);
+
+    // Revoking an unknown token is an error, not a silent success
+    let err = run_token(
+        &url,
+        "jwt-admin",
+        TokenCommand::Revoke {
+            token_id: "leaked".to_string(),
+            otp: None,
+        },
+    )
+    .await
+    .unwrap_err();
+    assert_eq!(
+        err.downcast_ref::<Status>().map(Status::code),
+        Some(tonic::Code::NotFound)
+    );
+}
@@ -0,0 +1,638 @@
+// ABOUTME: Agent config discovery, loading, and offline validation.
+// ABOUTME: Shared by run_agent and the validate-config subcommand.
+
+use anyhow::{Context, Result};
+use coven_grpc::{KeepAliveConfig, KeepAliveSettings};
+use coven_ssh::PassphraseSource;
+use std::path::{Path, PathBuf};
+
+/// Backends accepted by the `backend` config key and `--backend` flag.
+pub const VALID_BACKENDS: &[&str] = &["mux", "cli", "codex", "amplifier"];
+
+/// Longest project_name accepted from .coven/project.toml.
+const MAX_PROJECT_NAME_LEN: usize = 64;
+
+/// Outbound stream buffer from the `stream_buffer` key: how many messages
+/// the agent queues for the gateway before its responses wait for the
+/// network. Defaults to `coven_grpc::DEFAULT_CHANNEL_BUFFER`.
+pub fn stream_buffer(config: &toml::Table) -> usize {
+    config
+        .get("stream_buffer")
+        .and_then(|v| v.as_integer())
+        .and_then(|n| usize::try_from(n).ok())
+        .filter(|&n| n > 0)
+        .unwrap_or(coven_grpc::DEFAULT_CHANNEL_BUFFER)
+}
+
+/// Passphrase source for an encrypted agent key from the `key_passphrase`
+/// key, e.g. `key_passphrase = "cmd:pass show coven/agent"`. Accepts the
+/// forms of `PassphraseSource::parse`; unset means the key isn't encrypted.
+pub fn key_passphrase(config: &toml::Table) -> Result<Option<PassphraseSource>> {
+    config
+        .get("key_passphrase")
+        .and_then(|v| v.as_str())
+        .map(PassphraseSource::parse)
+        .transpose()
+        .context("invalid 'key_passphrase'")
+}
+
+/// HTTP/2 keep-alive for the gateway connection from the `[keepalive]`
+/// table; see `KeepAliveSettings` for its keys. Unset keys take the
+/// coven-grpc defaults, and `enabled = false` gives None.
+pub fn keep_alive(config: &toml::Table) -> Result<Option<KeepAliveConfig>> {
+    let settings: KeepAliveSettings = match config.get("keepalive") {
+        Some(value) => value.clone().try_into().context("invalid 'keepalive'")?,
+        None => KeepAliveSettings::default(),
+    };
+    settings.to_config().context("invalid 'keepalive'")
+}
+
+/// Get XDG-style config directory (~/.config/coven)
+/// Respects XDG_CONFIG_HOME if set, otherwise uses ~/.config
+pub fn xdg_config_dir() -> Option<PathBuf> {
+    std::env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| dirs::home_dir().map(|h| h.join(".config")))
+        .map(|p| p.join("coven"))
+}
+
+/// Get default config path (~/.config/coven/agent.toml)
+pub fn default_config_path() -> Option<PathBuf> {
+    xdg_config_dir().map(|p| p.join("agent.toml"))
+}
+
+/// Find the config file to use: explicit path > project-local > user-global.
+pub fn discover_config_path(explicit: Option<PathBuf>) -> Option<PathBuf> {
+    explicit.or_else(|| {
+        // Check for project-local config first (.coven/agent.toml in cwd)
+        if let Ok(cwd) = std::env::current_dir() {
+            let project_config = cwd.join(".coven").join("agent.toml");
+            if project_config.exists() {
+                return Some(project_config);
+            }
+        }
+        // Fall back to user-global config (~/.config/coven/agent.toml)
+        let default = default_config_path()?;
+        if default.exists() {
+            Some(default)
+        } else {
+            None
+        }
+    })
+}
+
+/// Read and parse an agent config file, following an `agent = "name"`
+/// reference to ~/.config/coven/agents/{name}.toml if present.
+pub fn load_config_file(config_path: &Path) -> Result<toml::Table> {
+    tracing::info!("Loading config from: {}", config_path.display());
+    let config_content = std::fs::read_to_string(config_path)
+        .with_context(|| format!("failed to read config file: {}", config_path.display()))?;
+    let config: toml::Table = toml::from_str(&config_content)
+        .with_context(|| format!("failed to parse config file: {}", config_path.display()))?;
+
+    if config.get("agent").and_then(|v| v.as_str()).is_some() {
+        let agents_dir = xdg_config_dir()
+            .ok_or_else(|| anyhow::anyhow!("Could not find config directory"))?
+            .join("agents");
+        resolve_agent_reference(config, &agents_dir)
+    } else {
+        Ok(config)
+    }
+}
+
+/// Replace a config of the form `agent = "name"` with the contents of
+/// `{agents_dir}/{name}.toml`. Configs without a reference are returned as-is.
+pub fn resolve_agent_reference(config: toml::Table, agents_dir: &Path) -> Result<toml::Table> {
+    let Some(agent_ref) = config.get("agent").and_then(|v| v.as_str()) else {
+        return Ok(config);
+    };
+
+    let agent_config_path = agents_dir.join(format!("{}.toml", agent_ref));
+    tracing::info!(
+        "Resolving agent reference '{}' -> {}",
+        agent_ref,
+        agent_config_path.display()
+    );
+    let agent_content = std::fs::read_to_string(&agent_config_path).with_context(|| {
+        format!(
+            "failed to read agent config '{}' at {}",
+            agent_ref,
+            agent_config_path.display()
+        )
+    })?;
+    toml::from_str(&agent_content).with_context(|| {
+        format!(
+            "failed to parse agent config '{}' at {}",
+            agent_ref,
+            agent_config_path.display()
+        )
+    })
+}
+
+/// A single problem found while validating a config file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfigIssue {
+    /// File the problem was found in
+    pub path: PathBuf,
+    /// Human-readable description of the problem
+    pub message: String,
+}
+
+/// Result of validating an agent config and its project config.
+#[derive(Debug, Default)]
+pub struct ValidationReport {
+    /// Files that were examined
+    pub checked: Vec<PathBuf>,
+    /// Problems found across all checked files
+    pub issues: Vec<ConfigIssue>,
+}
+
+impl ValidationReport {
+    pub fn is_ok(&self) -> bool {
+        self.issues.is_empty()
+    }
+
+    fn issue(&mut self, path: &Path, message: impl Into<String>) {
+        self.issues.push(ConfigIssue {
+            path: path.to_path_buf(),
+            message: message.into(),
+        });
+    }
+}
+
+/// Validate the agent config that `run_agent` would load, plus the
+/// `.coven/project.toml` of its working directory if one exists.
+/// Never touches the network.
+pub fn validate(explicit: Option<PathBuf>) -> ValidationReport {
+    let mut report = ValidationReport::default();
+
+    let Some(config_path) = discover_config_path(explicit) else {
+        report.issue(
+            Path::new("agent.toml"),
+            "no configuration found (searched .coven/agent.toml and ~/.config/coven/agent.toml)",
+        );
+        return report;
+    };
+
+    let agents_dir = xdg_config_dir().map(|d| d.join("agents"));
+    let config = validate_agent_config(&config_path, agents_dir.as_deref(), &mut report);
+
+    // The project config lives next to the agent's working directory
+    let working_dir = config
+        .as_ref()
+        .and_then(|c| c.get("working_dir"))
+        .and_then(|v| v.as_str())
+        .map(PathBuf::from)
+        .or_else(|| std::env::current_dir().ok());
+    if let Some(dir) = working_dir {
+        let project_path = dir.join(".coven").join("project.toml");
+        if project_path.exists() {
+            validate_project_config(&project_path, &mut report);
+        }
+    }
+
+    report
+}
+
+/// Validate an agent config file, recording problems in `report`.
+/// Returns the resolved config table if it could be loaded.
+pub fn validate_agent_config(
+    config_path: &Path,
+    agents_dir: Option<&Path>,
+    report: &mut ValidationReport,
+) -> Option<toml::Table> {
+    report.checked.push(config_path.to_path_buf());
+
+    let content = match std::fs::read_to_string(config_path) {
+        Ok(c) => c,
+        Err(e) => {
+            report.issue(config_path, format!("cannot read file: {}", e));
+            return None;
+        }
+    };
+    let config: toml::Table = match toml::from_str(&content) {
+        Ok(c) => c,
+        Err(e) => {
+            report.issue(config_path, format!("invalid TOML: {}", e));
+            return None;
+        }
+    };
+
+    // Follow agent references so the checks below apply to the real config
+    let (config, source) = match config.get("agent") {
+        None => (config, config_path.to_path_buf()),
+        Some(toml::Value::String(agent_ref)) => {
+            let Some(agents_dir) = agents_dir else {
+                report.issue(
+                    config_path,
+                    format!(
+                        "cannot resolve agent reference '{}': no config directory",
+                        agent_ref
+                    ),
+                );
+                return None;
+            };
+            let agent_path = agents_dir.join(format!("{}.toml", agent_ref));
+            match resolve_agent_reference(config.clone(), agents_dir) {
+                Ok(resolved) => {
+                    report.checked.push(agent_path.clone());
+                    (resolved, agent_path)
+                }
+                Err(e) => {
+                    report.issue(config_path, format!("{:#}", e));
+                    return None;
+                }
+            }
+        }
+        Some(_) => {
+            report.issue(config_path, "'agent' must be a string");
+            return None;
+        }
+    };
+
+    check_agent_table(&config, &source, report);
+    Some(config)
+}
+
+/// Check the keys of a resolved agent config table.
+fn check_agent_table(config: &toml::Table, path: &Path, report: &mut ValidationReport) {
+    if let Some(value) = config.get("name") {
+        match value.as_str() {
+            Some(name) if name.trim().is_empty() => report.issue(path, "'name' is empty"),
+            Some(_) => {}
+            None => report.issue(path, "'name' must be a string"),
+        }
+    }
+
+    if let Some(value) = config.get("server") {
+        match value.as_str() {
+            Some(server) => {
+                if let Err(problem) = check_server_url(server) {
+                    report.issue(path, format!("'server' {}", problem));
+                }
+            }
+            None => report.issue(path, "'server' must be a string"),
+        }
+    }
+
+    if let Some(value) = config.get("backend") {
+        match value.as_str() {
+            Some(backend) if VALID_BACKENDS.contains(&backend) => {}
+            Some(backend) => report.issue(
+                path,
+                format!(
+                    "unknown backend '{}' (expected one of: {})",
+                    backend,
+                    VALID_BACKENDS.join(", ")
+                ),
+            ),
+            None => report.issue(path, "'backend' must be a string"),
+        }
+    }
+
+    if let Some(value) = config.get("working_dir") {
+        match value.as_str() {
+            Some(dir) => {
+                let dir_path = Path::new(dir);
+                if !dir_path.exists() {
+                    report.issue(path, format!("working_dir '{}' does not exist", dir));
+                } else if !dir_path.is_dir() {
+                    report.issue(path, format!("working_dir '{}' is not a directory", dir));
+                }
+            }
+            None => report.issue(path, "'working_dir' must be a string"),
+        }
+    }
+
+    for key in ["status", "status_emoji"] {
+        if config.get(key).is_some_and(|v| !v.is_str()) {
+            report.issue(path, format!("'{}' must be a string", key));
+        }
+    }
+
+    if let Some(value) = config.get("stream_buffer") {
+        if !value.as_integer().is_some_and(|n| n > 0) {
+            report.issue(path, "'stream_buffer' must be a positive integer");
+        }
+    }
+
+    if config.contains_key("keepalive") {
+        if let Err(e) = keep_alive(config) {
+            report.issue(path, format!("{:#}", e));
+        }
+    }
+
+    if let Some(value) = config.get("key_passphrase") {
+        match value.as_str() {
+            Some(spec) => {
+                if let Err(e) = PassphraseSource::parse(spec) {
+                    report.issue(path, format!("'key_passphrase': {}", e));
+                }
+            }
+            None => report.issue(path, "'key_passphrase' must be a string"),
+        }
+    }
+
+    for key in ["workspaces", "capabilities"] {
+        if let Some(value) = config.get(key) {
+            let all_strings = value
+                .as_array()
+                .is_some_and(|arr| arr.iter().all(|v| v.is_str()));
+            if !all_strings {
+                report.issue(path, format!("'{}' must be an array of strings", key));
+            }
+        }
+    }
+}
+
+/// Check that a server address is an http(s) URL with a host.
+fn check_server_url(server: &str) -> std::result::Result<(), String> {
+    let url =
+        url::Url::parse(server).map_err(|e| format!("'{}' is not a valid URL: {}", server, e))?;
+    if url.scheme() != "http" && url.scheme() != "https" {
+        return Err(format!(
+            "'{}' must use http:// or https:// (got {}://)",
+            server,
+            url.scheme()
+        ));
+    }
+    if url.host_str().is_none_or(str::is_empty) {
+        return Err(format!("'{}' has no host", server));
+    }
+    Ok(())
+}
+
+/// Validate a `.coven/project.toml` file, recording problems in `report`.
+pub fn validate_project_config(project_path: &Path, report: &mut ValidationReport) {
+    report.checked.push(project_path.to_path_buf());
+
+    let content = match std::fs::read_to_string(project_path) {
+        Ok(c) => c,
+        Err(e) => {
+            report.issue(project_path, format!("cannot read file: {}", e));
+            return;
+        }
+    };
+    let config: toml::Table = match content.parse() {
+        Ok(c) => c,
+        Err(e) => {
+            report.issue(project_path, format!("invalid TOML: {}", e));
+            return;
+        }
+    };
+
+    let Some(value) = config.get("project_name") else {
+        return;
+    };
+    let Some(name) = value.as_str() else {
+        report.issue(project_path, "'project_name' must be a string");
+        return;
+    };
+    let trimmed = name.trim();
+    if trimmed.is_empty() {
+        report.issue(project_path, "'project_name' is empty");
+    } else if trimmed.len() > MAX_PROJECT_NAME_LEN {
+        report.issue(
+            project_path,
+            format!(
+                "'project_name' is longer than {} characters",
+                MAX_PROJECT_NAME_LEN
+            ),
+        );
+    } else if crate::run::sanitize_project_name(trimmed).is_empty() {
+        report.issue(
+            project_path,
+            format!(
+                "'project_name' '{}' has no ASCII letters or digits and will be ignored",
+                trimmed
+            ),
+        );
+    }
+}
+
+/// Print a validation report and fail if it contains problems.
+pub fn print_report(report: &ValidationReport) -> Result<()> {
+    for path in &report.checked {
+        println!("Checked {}", path.display());
+    }
+
+    if report.is_ok() {
+        println!("✓ Configuration is valid");
+        return Ok(());
+    }
+
+    println!("✗ Found {} problem(s):", report.issues.len());
+    for issue in &report.issues {
+        println!("  - {}: {}", issue.path.display(), issue.message);
+    }
+    anyhow::bail!("configuration is invalid")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn write(dir: &Path, name: &str, content: &str) -> PathBuf {
+        let path = dir.join(name);
+        fs::write(&path, content).unwrap();
+        path
+    }
+
+    fn check(path: &Path, agents_dir: Option<&Path>) -> ValidationReport {
+        let mut report = ValidationReport::default();
+        validate_agent_config(path, agents_dir, &mut report);
+        report
+    }
+
+    #[test]
+    fn test_valid_config_passes() {
+        let dir = tempfile::tempdir().unwrap();
+        let content = format!(
+            "name = \"bot\"\nserver = \"http://localhost:50051\"\nbackend = \"cli\"\nworking_dir = \"{}\"\ncapabilities = [\"base\", \"chat\"]\n",
+            dir.path().display()
+        );
+        let path = write(dir.path(), "agent.toml", &content);
+
+        let report = check(&path, None);
+        assert!(report.is_ok(), "unexpected issues: {:?}", report.issues);
+        assert_eq!(report.checked, vec![path]);
+    }
+
+    #[test]
+    fn test_invalid_toml_reported() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = write(dir.path(), "agent.toml", "name = \n");
+
+        let report = check(&path, None);
+        assert_eq!(report.issues.len(), 1);
+        assert!(report.issues[0].message.starts_with("invalid TOML"));
+    }
+
+    #[test]
+    fn test_missing_file_reported() {
+        let dir = tempfile::tempdir().unwrap();
+        let report = check(&dir.path().join("nope.toml"), None);
+        assert_eq!(report.issues.len(), 1);
+        assert!(report.issues[0].message.starts_with("cannot read file"));
+    }
+
+    #[test]
+    fn test_unknown_backend_reported() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = write(dir.path(), "agent.toml", "backend = \"gpt\"\n");
+
+        let report = check(&path, None);
+        assert_eq!(report.issues.len(), 1);
+        assert!(report.issues[0].message.contains("unknown backend 'gpt'"));
+    }
+
+    #[test]
+    fn test_missing_working_dir_reported() {
+        let dir = tempfile::tempdir().unwrap();
+        let missing = dir.path().join("missing");
+        let content = format!("working_dir = \"{}\"\n", missing.display());
+        let path = write(dir.path(), "agent.toml", &content);
+
+        let report = check(&path, None);
+        assert_eq!(report.issues.len(), 1);
+        assert!(report.issues[0].message.contains("does not exist"));
+    }
+
+    #[test]
+    fn test_server_url_checks() {
+        assert!(check_server_url("http://localhost:50051").is_ok());
+        assert!(check_server_url("https://coven.example.com").is_ok());
+        assert!(check_server_url("localhost:50051").is_err());
+        assert!(check_server_url("ftp://example.com").is_err());
+        assert!(check_server_url("not a url").is_err());
+    }
+
+    #[test]
+    fn test_wrong_types_reported() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = write(
+            dir.path(),
+            "agent.toml",
+            "name = 3\ncapabilities = \"chat\"\nworkspaces = [1]\nstream_buffer = 0\n",
+        );
+
+        let report = check(&path, None);
+        assert_eq!(report.issues.len(), 4);
+    }
+
+    #[test]
+    fn test_stream_buffer() {
+        let config: toml::Table = toml::from_str("stream_buffer = 512").unwrap();
+        assert_eq!(stream_buffer(&config), 512);
+        let config: toml::Table = toml::from_str("stream_buffer = -1").unwrap();
+        assert_eq!(stream_buffer(&config), coven_grpc::DEFAULT_CHANNEL_BUFFER);
+        assert_eq!(
+            stream_buffer(&toml::Table::new()),
+            coven_grpc::DEFAULT_CHANNEL_BUFFER
+        );
+    }
+
+    #[test]
+    fn test_key_passphrase() {
+        let config: toml::Table = toml::from_str("key_passphrase = \"env:AGENT_PASS\"").unwrap();
+        assert!(matches!(
+            key_passphrase(&config).unwrap(),
+            Some(PassphraseSource::Env(name)) if name == "AGENT_PASS"
+        ));
+        assert!(key_passphrase(&toml::Table::new()).unwrap().is_none());
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = write(dir.path(), "agent.toml", "key_passphrase = \"hunter2\"\n");
+        let report = check(&path, None);
+        assert_eq!(report.issues.len(), 1);
+        assert!(report.issues[0].message.contains("key_passphrase"));
+    }
+
+    #[test]
+    fn test_keep_alive() {
+        let config: toml::Table =
+            toml::from_str("[keepalive]\ninterval_secs = 60\npermit_without_stream = true\n")
+                .unwrap();
+        let ka = keep_alive(&config).unwrap().unwrap();
+        assert_eq!(ka.interval, std::time::Duration::from_secs(60));
+        assert_eq!(ka.timeout, KeepAliveConfig::default().timeout);
+        assert!(ka.while_idle);
+        assert_eq!(
+            keep_alive(&toml::Table::new()).unwrap(),
+            Some(KeepAliveConfig::default())
+        );
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = write(
+            dir.path(),
+            "agent.toml",
+            "[keepalive]\ninterval_secs = 10\ntimeout_secs = 20\n",
+        );
+        let report = check(&path, None);
+        assert_eq!(report.issues.len(), 1);
+        assert!(report.issues[0]
+            .message
+            .contains("must be less than the interval"));
+    }
+
+    #[test]
+    fn test_agent_reference_resolved() {
+        let dir = tempfile::tempdir().unwrap();
+        let agents_dir = dir.path().join("agents");
+        fs::create_dir(&agents_dir).unwrap();
+        let agent_path = write(&agents_dir, "helper.toml", "backend = \"nope\"\n");
+        let path = write(dir.path(), "agent.toml", "agent = \"helper\"\n");
+
+        let report = check(&path, Some(&agents_dir));
+        assert_eq!(report.checked, vec![path, agent_path.clone()]);
+        assert_eq!(report.issues.len(), 1);
+        // Issues in the referenced file point at that file
+        assert_eq!(report.issues[0].path, agent_path);
+    }
+
+    #[test]
+    fn test_missing_agent_reference_reported() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = write(dir.path(), "agent.toml", "agent = \"ghost\"\n");
+
+        let report = check(&path, Some(dir.path()));
+        assert_eq!(report.issues.len(), 1);
+        assert!(report.issues[0]
+            .message
+            .contains("failed to read agent config 'ghost'"));
+    }
+
+    #[test]
+    fn test_resolve_agent_reference_passthrough() {
+        let mut table = toml::Table::new();
+        table.insert("name".into(), toml::Value::String("bot".into()));
+        let resolved = resolve_agent_reference(table.clone(), Path::new("/nonexistent")).unwrap();
+        assert_eq!(resolved, table);
+    }
+
+    #[test]
+    fn test_project_config_checks() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let ok = write(dir.path(), "ok.toml", "project_name = \"my-project\"\n");
+        let mut report = ValidationReport::default();
+        validate_project_config(&ok, &mut report);
+        assert!(report.is_ok());
+
+        let empty = write(dir.path(), "empty.toml", "project_name = \"  \"\n");
+        let long = write(
+            dir.path(),
+            "long.toml",
+            &format!("project_name = \"{}\"\n", "a".repeat(65)),
+        );
+        let symbols = write(dir.path(), "symbols.toml", "project_name = \"@#$\"\n");
+        let wrong_type = write(dir.path(), "type.toml", "project_name = 1\n");
+
+        let mut report = ValidationReport::default();
+        for path in [&empty, &long, &symbols, &wrong_type] {
+            validate_project_config(path, &mut report);
+        }
+        assert_eq!(report.issues.len(), 4);
+    }
+}
@@ -9,23 +9,33 @@ use coven_core::backend::{
     AmplifierCliBackend, AmplifierCliConfig, ApprovalCallback, Backend, CodexCliBackend,
     CodexCliConfig, DirectCliBackend, DirectCliConfig, MuxBackend, MuxConfig,
 };
-use coven_core::{Config, Coven, IncomingMessage, OutgoingEvent};
+use coven_core::{Config, Coven, FileAttachment, IncomingMessage, OutgoingEvent, RequestOverrides};
+use coven_grpc::{create_channel, ChannelConfig, KeepAliveConfig, OutboundStream, StreamSender};
 use coven_proto::coven_control_client::CovenControlClient;
-use coven_proto::{agent_message, server_message, AgentMessage, MessageResponse, RegisterAgent};
+use coven_proto::limits::MessageLimits;
+use coven_proto::redact::InputRedactor;
+use coven_proto::{
+    agent_message, server_message, AgentMessage, ForkThread, MessageResponse, RegisterAgent,
+    ThreadTitle,
+};
 use coven_ssh::{
-    compute_fingerprint, default_agent_key_path, load_or_generate_key, SshAuthCredentials,
+    compute_fingerprint, default_agent_key_path, load_or_generate_key_with_passphrase,
+    PassphraseSource, SshAuthCredentials,
 };
 use futures::StreamExt;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
 use std::pin::Pin;
 use std::sync::Arc;
-use tokio::sync::{mpsc, oneshot, Mutex, Semaphore};
-use tonic::transport::Channel;
+use tokio::sync::{broadcast, mpsc, oneshot, Mutex, Semaphore};
 use tonic::Code;
+use tracing::Instrument;
 
 use crate::pack_tool::{
-    handle_pack_tool_result, new_pending_pack_tools, PackTool, PendingPackTools,
+    handle_pack_tool_progress, handle_pack_tool_result, new_pending_pack_tools, sync_pack_tools,
+    PendingPackTools,
 };
+use crate::presence::PresenceTracker;
 
 /// Maximum concurrent message processing tasks (backpressure)
 const MAX_CONCURRENT_MESSAGES: usize = 8;
@@ -43,6 +53,9 @@ pub async fn run(
     working_dir: &std::path::Path,
     verbose: bool,
     metadata: crate::metadata::AgentMetadata,
+    stream_buffer: usize,
+    key_passphrase: Option<&PassphraseSource>,
+    keep_alive: Option<&KeepAliveConfig>,
 ) -> Result<()> {
     // Initialize coven core components
     let config = Config::load()?;
@@ -64,25 +77,7 @@ pub async fn run(
         "mux" => {
             eprintln!("Using MuxBackend (direct Anthropic API)");
             eprintln!("  Working dir: {}", working_dir.display());
-            let mux_settings = config.mux.clone();
-            let mux_config = MuxConfig {
-                model: std::env::var("ANTHROPIC_MODEL").unwrap_or(mux_settings.model),
-                max_tokens: std::env::var("ANTHROPIC_MAX_TOKENS")
-                    .ok()
-                    .and_then(|s| s.parse().ok())
-                    .unwrap_or(mux_settings.max_tokens),
-                working_dir: working_dir.to_path_buf(),
-                global_system_prompt_path: mux_settings
-                    .global_system_prompt_path
-                    .or_else(|| dirs::home_dir().map(|h| h.join(".mux/system.md"))),
-                local_prompt_files: mux_settings.local_prompt_files,
-                global_soul_path: mux_settings.global_soul_path,
-                agent_soul_path: mux_settings.agent_soul_path,
-                soul_files: mux_settings.soul_files,
-                mcp_servers: vec![],
-                skip_default_tools: false,
-                gateway_mcp: None, // Set after gateway connection
-            };
+            let mux_config = MuxConfig::from_settings(&config.mux, working_dir);
 
             // Create approval callback that waits for gateway response
             // Timeout after 5 minutes to prevent infinite hangs
@@ -140,6 +135,7 @@ pub async fn run(
             eprintln!("  Binary: {}", config.claude.binary);
             eprintln!("  Working dir: {}", working_dir.display());
             eprintln!("  Timeout: {}s", config.claude.timeout_secs);
+            eprintln!("  Subprocesses: {}", config.claude.pool_size);
             eprintln!("  MCP endpoint: (will be set after gateway connection)");
 
             // Don't set MCP endpoint yet - we'll get the token from Welcome message
@@ -148,6 +144,8 @@ pub async fn run(
                 working_dir: working_dir.to_path_buf(),
                 timeout_secs: config.claude.timeout_secs,
                 mcp_endpoint: None,
+                restart: config.claude.restart_policy(),
+                pool_size: config.claude.pool_size,
             };
             let backend = Arc::new(DirectCliBackend::new(cli_config));
             cli_backend = Some(backend.clone());
@@ -200,7 +198,7 @@ pub async fn run(
         .ok_or_else(|| anyhow::anyhow!("could not determine config directory for SSH key"))?;
 
     eprintln!("[1/5] Loading SSH key from {}...", key_path.display());
-    let private_key = load_or_generate_key(&key_path)?;
+    let private_key = load_or_generate_key_with_passphrase(&key_path, key_passphrase)?;
     let fingerprint = compute_fingerprint(private_key.public_key())?;
     eprintln!("  Fingerprint: {}", fingerprint);
     eprintln!("  (Register this fingerprint with the gateway using 'coven admin')");
@@ -211,7 +209,11 @@ pub async fn run(
     // Also handles auto-registration if fingerprint is unknown
     let mut suffix: usize = 0;
     let mut needs_reconnect = false;
-    let (tx, mut inbound, registered_id) = loop {
+    // Pack tools registered with the mux backend, kept in sync with the gateway
+    let mut pack_tool_names = HashSet::new();
+    // Size limits for the gRPC client, also applied to the responses we send
+    let limits = MessageLimits::default();
+    let (sender, mut inbound, registered_id) = loop {
         let current_id = if suffix == 0 {
             agent_id.to_string()
         } else {
@@ -222,11 +224,10 @@ pub async fn run(
         if !needs_reconnect {
             eprintln!("[2/5] Connecting to gateway at {}...", server_addr);
         }
-        let channel = Channel::from_shared(server_addr.to_string())?
-            .connect()
-            .await?;
+        let channel_config = channel_config(server_addr, keep_alive, limits, stream_buffer);
+        let channel = create_channel(&channel_config).await?;
         if !needs_reconnect {
-            eprintln!("[3/5] TCP connection established");
+            eprintln!("[3/5] Connection established");
         }
         needs_reconnect = false;
 
@@ -255,11 +256,17 @@ pub async fn run(
             Ok(req)
         };
 
-        let mut client = CovenControlClient::with_interceptor(channel, ssh_auth_interceptor);
+        let mut client = CovenControlClient::with_interceptor(channel, ssh_auth_interceptor)
+            .max_decoding_message_size(limits.max_decoding)
+            .max_encoding_message_size(limits.max_encoding);
 
-        // Create bidirectional stream
-        let (tx, rx) = mpsc::channel::<AgentMessage>(100);
-        let outbound = tokio_stream::wrappers::ReceiverStream::new(rx);
+        // Create bidirectional stream. Its sender logs when the network
+        // can't keep up with what we send
+        let OutboundStream {
+            sender,
+            stream: outbound,
+        } = OutboundStream::from_config(&channel_config);
+        let tx = sender.clone().into_inner();
 
         eprintln!("[4/5] Opening bidirectional stream...");
         let response = match client.agent_stream(outbound).await {
@@ -299,7 +306,11 @@ pub async fn run(
                 name: current_id.clone(),
                 capabilities: metadata.capabilities.clone(),
                 metadata: Some(metadata.clone().into()),
-                protocol_features: vec!["token_usage".to_string(), "tool_states".to_string()],
+                protocol_features: vec![
+                    "token_usage".to_string(),
+                    "tool_states".to_string(),
+                    ForkThread::PROTOCOL_FEATURE.to_string(),
+                ],
             })),
         })
         .await?;
@@ -399,11 +410,21 @@ pub async fn run(
                         eprintln!("  Pack tools: {} available", tool_count);
                         if let Some(ref mux) = mux_backend {
                             for tool_def in &welcome.available_tools {
-                                let pack_tool =
-                                    PackTool::new(tool_def, tx.clone(), pending_pack_tools.clone());
-                                eprintln!("    - {}", tool_def.name);
-                                mux.register_tool(pack_tool).await;
+                                match &tool_def.degraded_reason {
+                                    Some(reason) => {
+                                        eprintln!("    - {} (degraded: {})", tool_def.name, reason)
+                                    }
+                                    None => eprintln!("    - {}", tool_def.name),
+                                }
                             }
+                            sync_pack_tools(
+                                mux,
+                                &mut pack_tool_names,
+                                &welcome.available_tools,
+                                &tx,
+                                &pending_pack_tools,
+                            )
+                            .await;
                         } else if cli_backend.is_some()
                             || codex_backend.is_some()
                             || amplifier_backend.is_some()
@@ -422,7 +443,7 @@ pub async fn run(
                     eprintln!("  Matrix: !coven bind {}", welcome.instance_id);
                     eprintln!();
                     eprintln!("Ready and waiting for messages...");
-                    break (tx, inbound, welcome.agent_id);
+                    break (sender, inbound, welcome.agent_id);
                 }
                 Some(server_message::Payload::RegistrationError(err)) => {
                     eprintln!("Registration rejected: {} (trying with suffix)", err.reason);
@@ -481,6 +502,17 @@ pub async fn run(
     // Per-thread locks: ensure messages to the same thread are processed sequentially
     let thread_locks: ThreadLocks = Arc::new(Mutex::new(HashMap::new()));
 
+    let tx = sender.clone().into_inner();
+
+    // Shows the agent as busy in pickers while any message is in flight
+    let presence = PresenceTracker::new(metadata.presence.clone(), tx.clone());
+    forward_titles(&coven, tx.clone());
+
+    // Responses are the bulk of outbound traffic; send them through the
+    // stream's own sender so a buffer the network can't keep up with gets
+    // logged
+    let responses = sender;
+
     // Process server messages
     // IMPORTANT: Message processing is spawned in separate tasks so this loop
     // can continue receiving PackToolResult and ToolApproval messages that
@@ -508,27 +540,64 @@ pub async fn run(
                     send_msg.content.chars().take(100).collect::<String>()
                 );
 
+                // Attachments go in a private directory that's removed once
+                // the message has been processed
+                let attachment_dir = if send_msg.attachments.is_empty() {
+                    None
+                } else {
+                    attachment_dir()
+                        .map_err(|e| {
+                            eprintln!("  WARNING: Can't create a directory for attachments: {}", e)
+                        })
+                        .ok()
+                };
+                let attachments = match &attachment_dir {
+                    Some(dir) => save_attachments(dir.path(), &send_msg.attachments).await,
+                    None => Vec::new(),
+                };
+
                 // Convert to IncomingMessage
                 let incoming = IncomingMessage {
                     thread_id: send_msg.thread_id.clone(),
-                    sender: send_msg.sender.clone(),
+                    // Bridges identify the chat user and platform behind the message
+                    sender: send_msg
+                        .sender_platform_id
+                        .clone()
+                        .unwrap_or_else(|| send_msg.sender.clone()),
+                    sender_display: send_msg.sender_display.clone(),
                     content: send_msg.content.clone(),
-                    frontend: "grpc".to_string(),
-                    attachments: vec![], // TODO: handle file attachments from proto
+                    frontend: send_msg
+                        .sender_platform
+                        .clone()
+                        .unwrap_or_else(|| "grpc".to_string()),
+                    attachments,
+                    overrides: RequestOverrides {
+                        model: send_msg.model.clone(),
+                        max_tokens: send_msg.max_tokens,
+                    },
+                    reply_to_message_id: send_msg.reply_to_message_id.clone(),
                 };
 
                 // Spawn message processing in separate task so this loop can
                 // continue receiving PackToolResult and ToolApproval messages.
                 // Per-thread lock acquired first (no permit consumed while waiting),
                 // then semaphore permit limits actual concurrent processing.
-                let tx_clone = tx.clone();
+                let tx_clone = responses.clone();
                 let request_id = send_msg.request_id.clone();
                 let coven_clone = Arc::clone(&coven);
                 let sem_clone = Arc::clone(&message_semaphore);
                 let locks_clone = Arc::clone(&thread_locks);
                 let thread_id = send_msg.thread_id.clone();
+                let busy = presence.start();
+                let span = tracing::info_span!(
+                    "agent.message",
+                    request_id = %request_id,
+                    thread_id = %thread_id,
+                );
                 eprintln!("  Processing with backend...");
-                tokio::spawn(async move {
+                let task = async move {
+                    // Waiting on the thread lock still counts as busy
+                    let _busy = busy;
                     // Acquire per-thread lock first (serializes same-thread messages
                     // without consuming a semaphore permit while waiting)
                     let thread_lock = {
@@ -543,7 +612,9 @@ pub async fn run(
                     // Now acquire semaphore permit for global backpressure
                     let permit = sem_clone.acquire().await.expect("semaphore closed");
 
-                    process_message(coven_clone, incoming, request_id, tx_clone, verbose).await;
+                    process_message(coven_clone, incoming, request_id, tx_clone, limits, verbose)
+                        .await;
+                    drop(attachment_dir);
                     eprintln!("Ready and waiting for messages...");
 
                     // Release guards before eviction check
@@ -558,7 +629,8 @@ pub async fn run(
                             locks.remove(&thread_id);
                         }
                     }
-                });
+                };
+                tokio::spawn(task.instrument(span));
             }
             Some(server_message::Payload::Shutdown(shutdown)) => {
                 eprintln!("Server requested shutdown: {}", shutdown.reason);
@@ -618,6 +690,56 @@ pub async fn run(
                     eprintln!("  WARNING: No pending pack tool request found");
                 }
             }
+            Some(server_message::Payload::PackToolProgress(progress)) => {
+                // Progress for a finished call is expected now and then; drop it quietly
+                handle_pack_tool_progress(&pending_pack_tools, progress).await;
+            }
+            Some(server_message::Payload::AvailableTools(update)) => {
+                eprintln!("← Pack tools updated: {} available", update.tools.len());
+                // Subprocess backends list tools from the gateway's MCP server themselves
+                if let Some(ref mux) = mux_backend {
+                    let removed = sync_pack_tools(
+                        mux,
+                        &mut pack_tool_names,
+                        &update.tools,
+                        &tx,
+                        &pending_pack_tools,
+                    )
+                    .await;
+                    for name in removed {
+                        eprintln!("    - {} (removed)", name);
+                    }
+                }
+            }
+            Some(server_message::Payload::SetLogLevel(change)) => {
+                match coven_log::set_level(&change.filter) {
+                    Ok(()) => eprintln!("← Log filter set to {}", change.filter),
+                    Err(e) => eprintln!("  WARNING: Kept the current log filter: {}", e),
+                }
+            }
+            Some(server_message::Payload::ForkThread(fork)) => {
+                // Forked before reading on, as the next message may be for the fork
+                match coven
+                    .fork_thread_with_history(
+                        &fork.thread_id,
+                        &fork.new_thread_id,
+                        &fork
+                            .history
+                            .iter()
+                            .map(|m| (m.role.clone(), m.content.clone()))
+                            .collect::<Vec<_>>(),
+                    )
+                    .await
+                {
+                    Ok(_) => eprintln!(
+                        "← Forked thread {} into {}",
+                        fork.thread_id, fork.new_thread_id
+                    ),
+                    Err(e) => {
+                        eprintln!("  WARNING: Failed to fork thread {}: {}", fork.thread_id, e)
+                    }
+                }
+            }
             None => {}
         }
     }
@@ -625,6 +747,49 @@ pub async fn run(
     Ok(())
 }
 
+/// How the agent connects to the gateway at `server_addr`, with an outbound
+/// stream buffering up to `stream_buffer` messages
+fn channel_config(
+    server_addr: &str,
+    keep_alive: Option<&KeepAliveConfig>,
+    limits: MessageLimits,
+    stream_buffer: usize,
+) -> ChannelConfig {
+    ChannelConfig {
+        keep_alive: keep_alive.cloned(),
+        message_limits: limits,
+        ..ChannelConfig::new(server_addr)
+    }
+    .with_stream_buffer(stream_buffer)
+}
+
+/// Report each thread title `coven` sets to the gateway through `tx`, so
+/// clients can list threads by name, until the stream or router closes
+pub(crate) fn forward_titles(coven: &Coven, tx: mpsc::Sender<AgentMessage>) {
+    let mut titles = coven.subscribe_titles();
+    tokio::spawn(async move {
+        loop {
+            let titled = match titles.recv().await {
+                Ok(titled) => titled,
+                Err(broadcast::error::RecvError::Lagged(missed)) => {
+                    tracing::warn!("Missed {} thread titles", missed);
+                    continue;
+                }
+                Err(broadcast::error::RecvError::Closed) => break,
+            };
+            let message = AgentMessage {
+                payload: Some(agent_message::Payload::ThreadTitle(ThreadTitle {
+                    thread_id: titled.thread_id,
+                    title: titled.title,
+                })),
+            };
+            if tx.send(message).await.is_err() {
+                break;
+            }
+        }
+    });
+}
+
 /// Process a single message from the gateway.
 /// Runs in a spawned task so the main loop can continue receiving
 /// PackToolResult and ToolApproval messages.
@@ -632,7 +797,8 @@ async fn process_message(
     coven: Arc<Coven>,
     incoming: IncomingMessage,
     request_id: String,
-    tx: mpsc::Sender<AgentMessage>,
+    tx: StreamSender<AgentMessage>,
+    limits: MessageLimits,
     verbose: bool,
 ) {
     match coven.handle(incoming).await {
@@ -641,7 +807,9 @@ async fn process_message(
             while let Some(event) = stream.next().await {
                 event_count += 1;
                 log_event(event_count, &event, verbose);
-                let response = convert_event_to_response(&request_id, event).await;
+                // An oversize event would reset the stream; send an error in its place
+                let response =
+                    limits.fit_agent_message(convert_event_to_response(&request_id, event).await);
                 if let Err(e) = tx.send(response).await {
                     eprintln!("ERROR: Failed to send response: {}", e);
                     break;
@@ -693,9 +861,8 @@ fn log_event(n: usize, event: &OutgoingEvent, verbose: bool) {
                 eprintln!("  [{n}] 📝 Text: {preview}");
             }
             OutgoingEvent::ToolUse { id, name, input } => {
-                let input_preview = truncate(&input.to_string(), 80);
                 eprintln!("  [{n}] 🔧 Tool: {name} (id={id})");
-                eprintln!("        Input: {input_preview}");
+                log_tool_input(id, name, input);
             }
             OutgoingEvent::ToolResult {
                 id,
@@ -713,13 +880,23 @@ fn log_event(n: usize, event: &OutgoingEvent, verbose: bool) {
             OutgoingEvent::Error(e) => {
                 eprintln!("  [{n}] ⚠️  Error: {e}");
             }
+            OutgoingEvent::BudgetExceeded(exceeded) => {
+                eprintln!("  [{n}] 🛑 {exceeded}");
+            }
             OutgoingEvent::File { filename, path, .. } => {
                 eprintln!("  [{n}] 📎 File: {filename} -> {}", path.display());
             }
-            OutgoingEvent::ToolApprovalRequest { id, name, input } => {
-                let input_preview = truncate(&input.to_string(), 80);
+            OutgoingEvent::ToolApprovalRequest {
+                id,
+                name,
+                input,
+                confirm_message,
+            } => {
                 eprintln!("  [{n}] ⏳ Approval needed: {name} (id={id})");
-                eprintln!("        Input: {input_preview}");
+                if let Some(confirm) = confirm_message {
+                    eprintln!("        Confirm: {confirm}");
+                }
+                log_tool_input(id, name, input);
             }
             OutgoingEvent::SessionInit { session_id } => {
                 eprintln!("  [{n}] 🔗 Session: {session_id}");
@@ -742,6 +919,12 @@ fn log_event(n: usize, event: &OutgoingEvent, verbose: bool) {
                 let detail_str = detail.as_deref().unwrap_or("");
                 eprintln!("  [{n}] 🔄 Tool state: {id} -> {state} {detail_str}");
             }
+            OutgoingEvent::Budget(report) => {
+                eprintln!("  [{n}] 💰 {}", report.status.summary());
+                for alert in &report.alerts {
+                    eprintln!("        {alert}");
+                }
+            }
         }
     } else {
         // Headless mode: minimal output for servers
@@ -760,6 +943,9 @@ fn log_event(n: usize, event: &OutgoingEvent, verbose: bool) {
             OutgoingEvent::Error(e) => {
                 eprintln!("  error: {e}");
             }
+            OutgoingEvent::BudgetExceeded(exceeded) => {
+                eprintln!("  error: {exceeded}");
+            }
             OutgoingEvent::File { filename, .. } => {
                 eprintln!("  file: {filename}");
             }
@@ -774,10 +960,96 @@ fn log_event(n: usize, event: &OutgoingEvent, verbose: bool) {
             OutgoingEvent::ToolState { id, state, .. } => {
                 eprintln!("  tool_state: {id} -> {state}");
             }
+            OutgoingEvent::Budget(report) => {
+                for alert in &report.alerts {
+                    eprintln!("  budget: {alert}");
+                }
+            }
         }
     }
 }
 
+/// A new directory under temp for one message's attachments, readable only
+/// by this user and deleted along with its files when dropped
+fn attachment_dir() -> std::io::Result<tempfile::TempDir> {
+    let dir = tempfile::Builder::new()
+        .prefix("coven-attachments-")
+        .tempdir()?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(dir.path(), std::fs::Permissions::from_mode(0o700))?;
+    }
+    Ok(dir)
+}
+
+/// Save a message's attachments into `dir` so the backend can read them by
+/// path. Only the last component of each filename is used, so a sender
+/// can't write outside `dir`. A file that can't be saved is left out.
+async fn save_attachments(
+    dir: &Path,
+    attachments: &[coven_proto::FileAttachment],
+) -> Vec<FileAttachment> {
+    if attachments.is_empty() {
+        return Vec::new();
+    }
+    if let Err(e) = tokio::fs::create_dir_all(dir).await {
+        eprintln!(
+            "  WARNING: Can't create {} for attachments: {}",
+            dir.display(),
+            e
+        );
+        return Vec::new();
+    }
+
+    let mut saved: Vec<FileAttachment> = Vec::with_capacity(attachments.len());
+    for (i, attachment) in attachments.iter().enumerate() {
+        let mut name = Path::new(&attachment.filename)
+            .file_name()
+            .and_then(|n| n.to_str())
+            .unwrap_or("")
+            .to_string();
+        if name.is_empty() {
+            name = format!("attachment-{}", i + 1);
+        }
+        // Two attachments with the same name keep both files
+        if saved.iter().any(|f| f.path == dir.join(&name)) {
+            name = format!("{}-{}", i + 1, name);
+        }
+        let path = dir.join(&name);
+        if let Err(e) = tokio::fs::write(&path, &attachment.data).await {
+            eprintln!(
+                "  WARNING: Failed to save attachment {}: {}",
+                attachment.filename, e
+            );
+            continue;
+        }
+        eprintln!(
+            "  Saved attachment {} ({} bytes)",
+            path.display(),
+            attachment.data.len()
+        );
+        saved.push(FileAttachment {
+            path,
+            filename: attachment.filename.clone(),
+            mime_type: attachment.mime_type.clone(),
+            size: attachment.data.len() as u64,
+        });
+    }
+    saved
+}
+
+/// Tool inputs can carry credentials, so they only reach the debug log,
+/// redacted and cut short
+fn log_tool_input(id: &str, name: &str, input: &serde_json::Value) {
+    tracing::debug!(
+        tool_id = %id,
+        tool = %name,
+        input = %InputRedactor::new().summary(input, 80),
+        "Tool input"
+    );
+}
+
 /// Truncate a string for display
 fn truncate(s: &str, max: usize) -> String {
     if s.chars().count() <= max {
@@ -791,9 +1063,87 @@ fn truncate(s: &str, max: usize) -> String {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::path::PathBuf;
     use std::sync::atomic::{AtomicUsize, Ordering};
     use tokio::time::{sleep, Duration};
 
+    #[tokio::test]
+    async fn test_save_attachments() {
+        let dir = tempfile::tempdir().unwrap();
+        let attachment = |filename: &str, data: &[u8]| coven_proto::FileAttachment {
+            filename: filename.to_string(),
+            mime_type: "text/plain".to_string(),
+            data: data.to_vec(),
+        };
+        let saved = save_attachments(
+            dir.path(),
+            &[
+                attachment("notes.txt", b"one"),
+                attachment("../../etc/notes.txt", b"two"),
+                attachment("", b"three"),
+            ],
+        )
+        .await;
+
+        let paths: Vec<PathBuf> = saved.iter().map(|f| f.path.clone()).collect();
+        assert_eq!(
+            paths,
+            [
+                dir.path().join("notes.txt"),
+                dir.path().join("2-notes.txt"),
+                dir.path().join("attachment-3"),
+            ]
+        );
+        assert_eq!(saved[1].filename, "../../etc/notes.txt");
+        assert_eq!(saved[2].size, 5);
+        assert_eq!(std::fs::read(&paths[1]).unwrap(), b"two");
+        assert!(save_attachments(dir.path(), &[]).await.is_empty());
+    }
+
+    #[test]
+    fn test_attachment_dir_is_private_and_removed_on_drop() {
+        let dir = attachment_dir().unwrap();
+        let path = dir.path().to_path_buf();
+        std::fs::write(path.join("notes.txt"), b"one").unwrap();
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o700);
+        }
+        assert_ne!(attachment_dir().unwrap().path(), path);
+        drop(dir);
+        assert!(!path.exists());
+    }
+
+    #[tokio::test]
+    async fn test_outbound_stream_waits_for_slow_consumer() {
+        let config = channel_config("http://localhost:50051", None, MessageLimits::default(), 2);
+        let OutboundStream { sender, mut stream } = OutboundStream::from_config(&config);
+        let message = || AgentMessage { payload: None };
+
+        sender.send(message()).await.unwrap();
+        sender.send(message()).await.unwrap();
+        assert_eq!(sender.stats().queued, 2);
+
+        // A full buffer holds the next send until the consumer catches up
+        let waiting = {
+            let sender = sender.clone();
+            tokio::spawn(async move { sender.send(message()).await })
+        };
+        sleep(Duration::from_millis(50)).await;
+        assert!(!waiting.is_finished());
+        assert_eq!(sender.stats().full_waits, 1);
+
+        assert!(stream.next().await.is_some());
+        waiting.await.unwrap().unwrap();
+        let stats = sender.stats();
+        assert_eq!(
+            (stats.buffer_size, stats.queued, stats.full_waits),
+            (2, 2, 1)
+        );
+    }
+
     #[test]
     fn test_max_concurrent_messages_is_reasonable() {
         // Ensure the constant is a reasonable positive value
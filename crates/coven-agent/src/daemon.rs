@@ -0,0 +1,428 @@
+// ABOUTME: `--detach` for agents: re-runs the agent headless in its own session, tracked by pid file.
+// ABOUTME: Each detached agent keeps agent.pid and agent.log under ~/.local/state/coven/agents/<id>/.
+
+use anyhow::{bail, Context, Result};
+use coven_swarm_core::process::{self, CapturedOutput, RotatingLog};
+use std::ffi::{OsStr, OsString};
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+/// How long `stop` waits for a graceful exit before killing
+pub const STOP_TIMEOUT: Duration = Duration::from_secs(10);
+/// How often `logs --follow` checks the log for new output
+const FOLLOW_INTERVAL: Duration = Duration::from_millis(500);
+/// Set on a detached agent to the log its output should rotate through
+const LOG_ENV: &str = "COVEN_AGENT_LOG";
+
+/// A detached agent whose process is still alive
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DetachedAgent {
+    pub id: String,
+    pub pid: u32,
+    pub log: PathBuf,
+}
+
+/// Directory of per-agent pid files and logs
+#[derive(Debug, Clone)]
+pub struct Registry {
+    pub dir: PathBuf,
+}
+
+impl Registry {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    /// `~/.local/state/coven/agents`, or `$XDG_STATE_HOME/coven/agents`
+    pub fn from_env() -> Result<Self> {
+        let dir = process::state_dir("agents").context("Could not determine the home directory")?;
+        Ok(Self::new(dir))
+    }
+
+    pub fn agent_dir(&self, id: &str) -> PathBuf {
+        self.dir.join(id)
+    }
+
+    pub fn pid_path(&self, id: &str) -> PathBuf {
+        self.agent_dir(id).join("agent.pid")
+    }
+
+    pub fn log_path(&self, id: &str) -> PathBuf {
+        self.agent_dir(id).join("agent.log")
+    }
+
+    pub fn write_pid(&self, id: &str, pid: u32) -> Result<()> {
+        check_id(id)?;
+        let dir = self.agent_dir(id);
+        std::fs::create_dir_all(&dir)
+            .with_context(|| format!("Failed to create {}", dir.display()))?;
+        let path = self.pid_path(id);
+        std::fs::write(&path, format!("{}\n", pid))
+            .with_context(|| format!("Failed to write {}", path.display()))
+    }
+
+    fn read_pid(&self, id: &str) -> Result<Option<u32>> {
+        let path = self.pid_path(id);
+        match std::fs::read_to_string(&path) {
+            Ok(content) => content
+                .trim()
+                .parse()
+                .map(Some)
+                .with_context(|| format!("Invalid pid in {}", path.display())),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e).with_context(|| format!("Failed to read {}", path.display())),
+        }
+    }
+
+    fn remove_pid(&self, id: &str) -> Result<()> {
+        match std::fs::remove_file(self.pid_path(id)) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e.into()),
+            _ => Ok(()),
+        }
+    }
+
+    /// The pid of the agent if it is running. A pid file left behind by
+    /// an agent that has exited is deleted; the log is kept.
+    pub fn running(&self, id: &str) -> Result<Option<u32>> {
+        match self.read_pid(id) {
+            Ok(Some(pid)) if process::is_alive(pid) => Ok(Some(pid)),
+            Ok(None) => Ok(None),
+            Ok(Some(_)) | Err(_) => {
+                self.remove_pid(id)?;
+                Ok(None)
+            }
+        }
+    }
+
+    /// Every detached agent that is still running, by id
+    pub fn list(&self) -> Result<Vec<DetachedAgent>> {
+        let entries = match std::fs::read_dir(&self.dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => {
+                return Err(e).with_context(|| format!("Failed to read {}", self.dir.display()))
+            }
+        };
+
+        let mut agents = Vec::new();
+        for entry in entries.filter_map(|e| e.ok()) {
+            let Some(id) = entry.file_name().to_str().map(str::to_string) else {
+                continue;
+            };
+            if let Some(pid) = self.running(&id)? {
+                agents.push(DetachedAgent {
+                    log: self.log_path(&id),
+                    id,
+                    pid,
+                });
+            }
+        }
+        agents.sort_by(|a, b| a.id.cmp(&b.id));
+        Ok(agents)
+    }
+}
+
+/// Agent ids name directories under the registry, so one that could
+/// point outside it is refused
+fn check_id(id: &str) -> Result<()> {
+    process::check_state_name(id).context("Invalid agent id")
+}
+
+/// Arguments for the detached copy of this process: the current arguments
+/// without `--detach`, forced headless and pinned to `agent_id` so the
+/// child registers under the same id the pid file is stored by.
+pub fn child_args<I>(args: I, agent_id: &str, headless: bool, id_given: bool) -> Vec<OsString>
+where
+    I: IntoIterator<Item = OsString>,
+{
+    let mut out: Vec<OsString> = args
+        .into_iter()
+        .skip(1)
+        .filter(|arg| arg != OsStr::new("--detach"))
+        .collect();
+    if !headless {
+        out.push("--headless".into());
+    }
+    if !id_given {
+        out.push("--id".into());
+        out.push(agent_id.into());
+    }
+    out
+}
+
+/// Start a detached, headless copy of this agent in its own session with
+/// its output going to the agent's log, and record its pid. `args` are
+/// the arguments for the copy, from `child_args`.
+pub fn detach(registry: &Registry, agent_id: &str, args: &[OsString]) -> Result<DetachedAgent> {
+    check_id(agent_id)?;
+    if let Some(pid) = registry.running(agent_id)? {
+        bail!(
+            "Agent '{}' is already running (pid {}). Stop it with 'coven agent stop {}'",
+            agent_id,
+            pid,
+            agent_id
+        );
+    }
+
+    let log_path = registry.log_path(agent_id);
+    let log = open_log(&log_path)?;
+
+    let exe = std::env::current_exe().context("Failed to locate the agent binary")?;
+    let mut cmd = std::process::Command::new(exe);
+    cmd.args(args).env(LOG_ENV, &log_path);
+    process::detach(&mut cmd).context("Failed to detach the agent")?;
+    // Output before the agent starts rotating its log is appended directly
+    cmd.stdout(log.try_clone()?).stderr(log);
+    let child = cmd.spawn().context("Failed to start detached agent")?;
+
+    registry.write_pid(agent_id, child.id())?;
+    Ok(DetachedAgent {
+        id: agent_id.to_string(),
+        pid: child.id(),
+        log: log_path,
+    })
+}
+
+/// Open the log for appending
+fn open_log(path: &Path) -> Result<File> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)
+            .with_context(|| format!("Failed to create {}", dir.display()))?;
+    }
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .with_context(|| format!("Failed to open {}", path.display()))
+}
+
+/// In an agent started by `detach`, send its output through its log with
+/// rotation for as long as the returned guard is held, so an agent that
+/// runs for weeks doesn't grow one unbounded file. Does nothing otherwise.
+pub fn capture_log() -> Result<Option<CapturedOutput>> {
+    let Some(path) = std::env::var_os(LOG_ENV).map(PathBuf::from) else {
+        return Ok(None);
+    };
+    // Children the agent starts shouldn't think they were detached too
+    std::env::remove_var(LOG_ENV);
+
+    let log = RotatingLog::open(
+        &path,
+        RotatingLog::DEFAULT_MAX_BYTES,
+        RotatingLog::DEFAULT_KEEP,
+    )
+    .with_context(|| format!("Failed to open {}", path.display()))?;
+    let captured = process::capture_output(log)
+        .with_context(|| format!("Failed to send output to {}", path.display()))?;
+    Ok(Some(captured))
+}
+
+/// Stop a detached agent: SIGTERM, then SIGKILL if it is still running
+/// after `timeout`. Returns the pid that was stopped.
+pub fn stop(registry: &Registry, id: &str, timeout: Duration) -> Result<u32> {
+    check_id(id)?;
+    let pid = registry
+        .running(id)?
+        .with_context(|| format!("Agent '{}' is not running in the background", id))?;
+
+    process::terminate(pid).with_context(|| format!("Failed to signal pid {}", pid))?;
+
+    let deadline = Instant::now() + timeout;
+    while process::is_alive(pid) && Instant::now() < deadline {
+        std::thread::sleep(Duration::from_millis(100));
+    }
+    if process::is_alive(pid) {
+        let _ = process::force_kill(pid);
+    }
+
+    registry.remove_pid(id)?;
+    Ok(pid)
+}
+
+/// The last `lines` lines of `reader`
+fn tail_lines<R: BufRead>(reader: R, lines: usize) -> io::Result<Vec<String>> {
+    let mut last = std::collections::VecDeque::with_capacity(lines);
+    for line in reader.lines() {
+        if last.len() == lines {
+            last.pop_front();
+        }
+        if lines > 0 {
+            last.push_back(line?);
+        }
+    }
+    Ok(last.into())
+}
+
+/// Print the last `lines` lines of an agent's log to `out`, then with
+/// `follow` keep printing new output until interrupted. A log that shrinks
+/// (rotated or truncated) is read again from the start.
+pub fn logs(
+    registry: &Registry,
+    id: &str,
+    lines: usize,
+    follow: bool,
+    out: &mut impl Write,
+) -> Result<()> {
+    check_id(id)?;
+    let path = registry.log_path(id);
+    let mut file = File::open(&path).with_context(|| {
+        format!(
+            "No log for agent '{}' ({}); was it started with --detach?",
+            id,
+            path.display()
+        )
+    })?;
+
+    for line in tail_lines(BufReader::new(&mut file), lines)? {
+        writeln!(out, "{}", line)?;
+    }
+    out.flush()?;
+    if !follow {
+        return Ok(());
+    }
+
+    let mut offset = file.seek(SeekFrom::End(0))?;
+    let mut buf = Vec::new();
+    loop {
+        std::thread::sleep(FOLLOW_INTERVAL);
+        let len = match std::fs::metadata(&path) {
+            Ok(meta) => meta.len(),
+            Err(_) => continue,
+        };
+        if len < offset {
+            file = File::open(&path)?;
+            offset = 0;
+        }
+        if len == offset {
+            continue;
+        }
+        file.seek(SeekFrom::Start(offset))?;
+        buf.clear();
+        offset += file.read_to_end(&mut buf)? as u64;
+        out.write_all(&buf)?;
+        out.flush()?;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(list: &[&str]) -> Vec<OsString> {
+        list.iter().map(OsString::from).collect()
+    }
+
+    #[test]
+    fn test_child_args_strip_detach_and_pin_id() {
+        let out = child_args(
+            args(&["coven", "agent", "run", "--detach", "--name", "ops"]),
+            "ops-infra",
+            false,
+            false,
+        );
+        assert_eq!(
+            out,
+            args(&[
+                "agent",
+                "run",
+                "--name",
+                "ops",
+                "--headless",
+                "--id",
+                "ops-infra"
+            ])
+        );
+
+        let out = child_args(
+            args(&["coven-agent", "--headless", "--id", "x", "--detach"]),
+            "x",
+            true,
+            true,
+        );
+        assert_eq!(out, args(&["--headless", "--id", "x"]));
+    }
+
+    #[test]
+    fn test_running_cleans_stale_pid_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let registry = Registry::new(dir.path());
+        let mut alive = std::process::Command::new("sleep")
+            .arg("30")
+            .spawn()
+            .unwrap();
+        let mut dead = std::process::Command::new("true").spawn().unwrap();
+        dead.wait().unwrap();
+
+        registry.write_pid("alive", alive.id()).unwrap();
+        registry.write_pid("dead", dead.id()).unwrap();
+        std::fs::write(registry.log_path("dead"), "last words\n").unwrap();
+        std::fs::create_dir_all(registry.agent_dir("garbled")).unwrap();
+        std::fs::write(registry.pid_path("garbled"), "not a pid").unwrap();
+
+        let listed = registry.list().unwrap();
+        assert_eq!(listed.len(), 1);
+        assert_eq!(listed[0].id, "alive");
+        assert_eq!(listed[0].pid, alive.id());
+        assert!(!registry.pid_path("dead").exists());
+        assert!(!registry.pid_path("garbled").exists());
+        assert!(registry.log_path("dead").exists(), "logs outlive the agent");
+
+        alive.kill().unwrap();
+        alive.wait().unwrap();
+        assert_eq!(registry.running("alive").unwrap(), None);
+    }
+
+    #[test]
+    fn test_stop_terminates_and_removes_pid_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let registry = Registry::new(dir.path());
+        let mut agent = std::process::Command::new("sleep")
+            .arg("30")
+            .spawn()
+            .unwrap();
+        let pid = agent.id();
+        registry.write_pid("ops", pid).unwrap();
+
+        // Reap the process as soon as it exits, as init would for a detached one
+        let reaper = std::thread::spawn(move || agent.wait().unwrap());
+
+        assert_eq!(stop(&registry, "ops", Duration::from_secs(5)).unwrap(), pid);
+        assert!(!reaper.join().unwrap().success());
+        assert!(!registry.pid_path("ops").exists());
+
+        let err = stop(&registry, "ops", Duration::from_secs(5)).unwrap_err();
+        assert!(err.to_string().contains("not running"));
+    }
+
+    #[test]
+    fn test_logs_prints_the_tail() {
+        let dir = tempfile::tempdir().unwrap();
+        let registry = Registry::new(dir.path());
+        std::fs::create_dir_all(registry.agent_dir("ops")).unwrap();
+        std::fs::write(registry.log_path("ops"), "one\ntwo\nthree\n").unwrap();
+
+        let mut out = Vec::new();
+        logs(&registry, "ops", 2, false, &mut out).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), "two\nthree\n");
+
+        let err = logs(&registry, "missing", 10, false, &mut Vec::new()).unwrap_err();
+        assert!(err.to_string().contains("--detach"));
+    }
+
+    #[test]
+    fn test_ids_outside_the_registry_are_refused() {
+        let dir = tempfile::tempdir().unwrap();
+        let registry = Registry::new(dir.path().join("agents"));
+        std::fs::write(dir.path().join("agent.log"), "secret\n").unwrap();
+
+        for id in ["..", "../agents", "a/b"] {
+            assert!(registry.write_pid(id, 1).is_err(), "{}", id);
+            assert!(stop(&registry, id, Duration::ZERO).is_err(), "{}", id);
+            let err = logs(&registry, id, 10, false, &mut Vec::new()).unwrap_err();
+            assert!(err.to_string().contains("Invalid agent id"), "{}", id);
+        }
+        assert!(!dir.path().join("agent.pid").exists());
+    }
+}
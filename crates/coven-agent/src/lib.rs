@@ -1,16 +1,20 @@
 // ABOUTME: coven-agent library exports
 // ABOUTME: Re-exports client, wizard, and utility modules
 
+pub mod agent_config;
 pub mod client;
+pub mod daemon;
 pub mod metadata;
 pub mod pack_tool;
+pub mod presence;
+pub mod prompt;
 pub mod run;
 pub mod single;
 pub mod tui;
 pub mod wizard;
 
 // Re-export main entry points for convenience
-pub use run::{run_agent, run_wizard, AgentRunConfig};
+pub use run::{run_agent, run_wizard, show_prompt, validate_config, AgentRunConfig};
 
 /// Build MCP URL with token appended as a path segment.
 /// e.g., "http://localhost:8080/mcp" + "abc123" → "http://localhost:8080/mcp/abc123"
@@ -4,14 +4,16 @@
 mod client;
 mod metadata;
 mod pack_tool;
+mod presence;
 mod single;
 mod tui;
 mod wizard;
 
-use anyhow::{bail, Context, Result};
+use anyhow::{bail, Result};
 use clap::{Parser, Subcommand};
 
 // Re-export from lib for use in this binary and for tests
+use coven_agent::agent_config::{discover_config_path, load_config_file};
 pub use coven_agent::build_mcp_url;
 use std::path::PathBuf;
 
@@ -53,12 +55,32 @@ struct Cli {
     /// Run in single-user interactive mode (no gRPC server)
     #[arg(long, conflicts_with = "headless", global = true)]
     single: bool,
+
+    /// Run headless in the background, with a pid file and log under ~/.local/state/coven/agents/<id>/
+    #[arg(long, conflicts_with = "single", global = true)]
+    detach: bool,
+
+    /// Only log warnings and errors
+    #[arg(short, long, global = true, conflicts_with = "verbose")]
+    quiet: bool,
+
+    /// Log more detail (-v for debug, -vv for trace)
+    #[arg(short, long, action = clap::ArgAction::Count, global = true)]
+    verbose: u8,
 }
 
 #[derive(Subcommand)]
 enum Commands {
     /// Create a new agent configuration interactively
     New,
+    /// Check the agent configuration for problems without connecting
+    ValidateConfig,
+    /// Print the assembled system prompt with the source of each section
+    ShowPrompt {
+        /// Print the prompt exactly as sent, without source annotations
+        #[arg(long)]
+        raw: bool,
+    },
 }
 
 /// Determine display mode based on flags
@@ -80,20 +102,6 @@ impl DisplayMode {
     }
 }
 
-/// Get XDG-style config directory (~/.config/coven)
-/// Respects XDG_CONFIG_HOME if set, otherwise uses ~/.config
-fn xdg_config_dir() -> Option<PathBuf> {
-    std::env::var_os("XDG_CONFIG_HOME")
-        .map(PathBuf::from)
-        .or_else(|| dirs::home_dir().map(|h| h.join(".config")))
-        .map(|p| p.join("coven"))
-}
-
-/// Get default config path (~/.config/coven/agent.toml)
-fn default_config_path() -> Option<PathBuf> {
-    xdg_config_dir().map(|p| p.join("agent.toml"))
-}
-
 /// Resolve project name from .coven/project.toml or directory basename
 fn resolve_project_name(working_dir: &std::path::Path) -> String {
     // Try to load .coven/project.toml (no exists() check - read_to_string handles it)
@@ -164,18 +172,31 @@ async fn main() -> Result<()> {
     // Load .env file if present (ignore errors if not found)
     let _ = dotenvy::dotenv();
 
-    coven_log::init();
-
     let cli = Cli::parse();
+    let level = coven_log::level_from_flags(cli.quiet, cli.verbose);
+    #[cfg(feature = "otlp")]
+    let _otlp = coven_log::init_otlp("coven-agent", level);
+    #[cfg(not(feature = "otlp"))]
+    coven_log::init_with_level(level);
 
     match cli.command {
         Some(Commands::New) => {
             // Run the interactive wizard (no logging needed for TUI)
             wizard::run_with_prefix("coven-agent").await
         }
+        Some(Commands::ValidateConfig) => coven_agent::validate_config(cli.config),
+        Some(Commands::ShowPrompt { raw }) => {
+            coven_agent::show_prompt(cli.config, cli.working_dir, raw)
+        }
         None => {
             // Default: run the agent with provided flags
             let mode = DisplayMode::from_headless_flag(cli.headless);
+            // Headless agents re-read ~/.config/coven/log-level on SIGHUP;
+            // the TUI should still close with its terminal
+            #[cfg(unix)]
+            if cli.headless || cli.detach {
+                coven_log::reload_on_sighup();
+            }
             run_agent(
                 cli.server,
                 cli.name,
@@ -185,6 +206,7 @@ async fn main() -> Result<()> {
                 cli.config,
                 mode,
                 cli.single,
+                cli.detach,
             )
             .await
         }
@@ -201,152 +223,122 @@ async fn run_agent(
     config: Option<PathBuf>,
     mode: DisplayMode,
     single: bool,
+    detach: bool,
 ) -> Result<()> {
+    // A detached agent's output rotates through its log until it exits
+    let _log = coven_agent::daemon::capture_log()?;
+
     // Try to load config: explicit path > project-local > user-global
-    let config_path = config.or_else(|| {
-        // Check for project-local config first (.coven/agent.toml in cwd)
-        if let Ok(cwd) = std::env::current_dir() {
-            let project_config = cwd.join(".coven").join("agent.toml");
-            if project_config.exists() {
-                return Some(project_config);
-            }
-        }
-        // Fall back to user-global config (~/.config/coven/agent.toml)
-        let default = default_config_path()?;
-        if default.exists() {
-            Some(default)
-        } else {
-            None
-        }
-    });
+    let config_path = discover_config_path(config);
 
     // Load settings from config - required unless running in single mode
-    let (server, name, backend, working_dir, workspaces, capabilities) =
-        if let Some(ref config_path) = config_path {
-            tracing::info!("Loading config from: {}", config_path.display());
-            let config_content = std::fs::read_to_string(config_path).with_context(|| {
-                format!("failed to read config file: {}", config_path.display())
-            })?;
-            let config: toml::Table = toml::from_str(&config_content).with_context(|| {
-                format!("failed to parse config file: {}", config_path.display())
-            })?;
-
-            // Check if this is a reference to a global agent (agent = "name")
-            let config = if let Some(agent_ref) = config.get("agent").and_then(|v| v.as_str()) {
-                // Resolve to ~/.config/coven/agents/{agent}.toml
-                let agents_dir = xdg_config_dir()
-                    .ok_or_else(|| anyhow::anyhow!("Could not find config directory"))?
-                    .join("agents");
-                let agent_config_path = agents_dir.join(format!("{}.toml", agent_ref));
-                tracing::info!(
-                    "Resolving agent reference '{}' -> {}",
-                    agent_ref,
-                    agent_config_path.display()
-                );
-                let agent_content =
-                    std::fs::read_to_string(&agent_config_path).with_context(|| {
-                        format!(
-                            "failed to read agent config '{}' at {}",
-                            agent_ref,
-                            agent_config_path.display()
-                        )
-                    })?;
-                toml::from_str(&agent_content).with_context(|| {
-                    format!(
-                        "failed to parse agent config '{}' at {}",
-                        agent_ref,
-                        agent_config_path.display()
-                    )
-                })?
-            } else {
-                config
-            };
-
-            // Server can come from:
-            // 1. Agent config file (server = "...")
-            // 2. User's coven config from `coven link` (~/.config/coven/config.toml)
-            // 3. CLI args / defaults
-            let server = config
-                .get("server")
-                .and_then(|v| v.as_str())
-                .map(|s| s.to_string())
-                .or_else(|| {
-                    // Try to load gateway from user's coven config
-                    coven_link::config::CovenConfig::load().ok().map(|c| {
-                        // Gateway is "host:port" format, convert to URL
-                        if c.gateway.starts_with("http://") || c.gateway.starts_with("https://") {
-                            c.gateway
-                        } else {
-                            format!("http://{}", c.gateway)
-                        }
-                    })
+    let (
+        server,
+        name,
+        backend,
+        working_dir,
+        workspaces,
+        capabilities,
+        presence,
+        stream_buffer,
+        key_passphrase,
+        keep_alive,
+    ) = if let Some(ref config_path) = config_path {
+        let config = load_config_file(config_path)?;
+
+        // Server can come from:
+        // 1. Agent config file (server = "...")
+        // 2. User's coven config from `coven link` (~/.config/coven/config.toml)
+        // 3. CLI args / defaults
+        let server = config
+            .get("server")
+            .and_then(|v| v.as_str())
+            .map(|s| s.to_string())
+            .or_else(|| {
+                // Try to load gateway from user's coven config
+                coven_link::config::CovenConfig::load().ok().map(|c| {
+                    // Gateway is "host:port" format, convert to URL
+                    if c.gateway.starts_with("http://") || c.gateway.starts_with("https://") {
+                        c.gateway
+                    } else {
+                        format!("http://{}", c.gateway)
+                    }
                 })
-                .unwrap_or(server);
-            let name = config
-                .get("name")
+            })
+            .unwrap_or(server);
+        let name = config
+            .get("name")
+            .and_then(|v| v.as_str())
+            .map(|s| s.to_string())
+            .unwrap_or(name);
+        let backend = backend.or_else(|| {
+            config
+                .get("backend")
                 .and_then(|v| v.as_str())
                 .map(|s| s.to_string())
-                .unwrap_or(name);
-            let backend = backend.or_else(|| {
-                config
-                    .get("backend")
-                    .and_then(|v| v.as_str())
-                    .map(|s| s.to_string())
-            });
-            let config_working_dir = config
-                .get("working_dir")
-                .and_then(|v| v.as_str())
-                .map(PathBuf::from);
-
-            // Load workspaces from config
-            let workspaces: Vec<String> = config
-                .get("workspaces")
-                .and_then(|v| v.as_array())
-                .map(|arr| {
-                    arr.iter()
-                        .filter_map(|v| v.as_str().map(String::from))
-                        .collect()
-                })
-                .unwrap_or_default();
-
-            // Load capabilities from config (default to base + chat for gateway tools)
-            let capabilities: Vec<String> = config
-                .get("capabilities")
-                .and_then(|v| v.as_array())
-                .map(|arr| {
-                    arr.iter()
-                        .filter_map(|v| v.as_str().map(String::from))
-                        .collect()
-                })
-                .unwrap_or_else(metadata::default_capabilities);
-
-            (
-                server,
-                name,
-                backend,
-                working_dir.or(config_working_dir),
-                workspaces,
-                capabilities,
-            )
-        } else if !single {
-            // Config is required for gateway mode
-            bail!(
-                "No configuration found. Create one with 'coven-agent new' or specify --config.\n\
+        });
+        let config_working_dir = config
+            .get("working_dir")
+            .and_then(|v| v.as_str())
+            .map(PathBuf::from);
+
+        // Load workspaces from config
+        let workspaces: Vec<String> = config
+            .get("workspaces")
+            .and_then(|v| v.as_array())
+            .map(|arr| {
+                arr.iter()
+                    .filter_map(|v| v.as_str().map(String::from))
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        // Load capabilities from config (default to base + chat for gateway tools)
+        let capabilities: Vec<String> = config
+            .get("capabilities")
+            .and_then(|v| v.as_array())
+            .map(|arr| {
+                arr.iter()
+                    .filter_map(|v| v.as_str().map(String::from))
+                    .collect()
+            })
+            .unwrap_or_else(metadata::default_capabilities);
+
+        (
+            server,
+            name,
+            backend,
+            working_dir.or(config_working_dir),
+            workspaces,
+            capabilities,
+            presence::Presence::from_config(&config),
+            coven_agent::agent_config::stream_buffer(&config),
+            coven_agent::agent_config::key_passphrase(&config)?,
+            coven_agent::agent_config::keep_alive(&config)?,
+        )
+    } else if !single {
+        // Config is required for gateway mode
+        bail!(
+            "No configuration found. Create one with 'coven-agent new' or specify --config.\n\
              Searched:\n\
              - .coven/agent.toml (project-local)\n\
              - ~/.config/coven/agent.toml (user-global)"
-            );
-        } else {
-            // Single mode can work without config - use default capabilities
-            (
-                server,
-                name,
-                backend,
-                working_dir,
-                Vec::new(),
-                metadata::default_capabilities(),
-            )
-        };
+        );
+    } else {
+        // Single mode can work without config - use default capabilities
+        (
+            server,
+            name,
+            backend,
+            working_dir,
+            Vec::new(),
+            metadata::default_capabilities(),
+            presence::Presence::default(),
+            coven_grpc::DEFAULT_CHANNEL_BUFFER,
+            None,
+            Some(coven_grpc::KeepAliveConfig::default()),
+        )
+    };
 
     // Default to current directory if not specified
     let working_dir = working_dir
@@ -361,11 +353,22 @@ async fn run_agent(
 
     // Create agent ID from name + project name
     // Project name comes from: .coven/project.toml > directory basename
+    let id_given = id.is_some();
     let agent_id = id.unwrap_or_else(|| {
         let project_name = resolve_project_name(&working_dir);
         format!("{}-{}", name, project_name)
     });
 
+    if detach {
+        let args = coven_agent::daemon::child_args(
+            std::env::args_os(),
+            &agent_id,
+            mode == DisplayMode::Headless,
+            id_given,
+        );
+        return coven_agent::run::start_detached(&agent_id, &args);
+    }
+
     if single {
         return single::run(&name, &agent_id, &backend_type, &working_dir).await;
     }
@@ -378,6 +381,8 @@ async fn run_agent(
                 &backend_type,
                 &working_dir,
                 capabilities,
+                key_passphrase.as_ref(),
+                keep_alive,
             )
             .await
         }
@@ -387,6 +392,7 @@ async fn run_agent(
             metadata.workspaces = workspaces;
             metadata.backend = backend_type.to_string();
             metadata.capabilities = capabilities;
+            metadata.presence = presence;
 
             // Log metadata for debugging
             eprintln!("Agent metadata:");
@@ -395,6 +401,7 @@ async fn run_agent(
             eprintln!("  OS: {}", metadata.os);
             eprintln!("  Backend: {}", metadata.backend);
             eprintln!("  Capabilities: {:?}", metadata.capabilities);
+            eprintln!("  Status: {}", metadata.presence.status);
             if !metadata.workspaces.is_empty() {
                 eprintln!("  Workspaces: {:?}", metadata.workspaces);
             }
@@ -415,6 +422,9 @@ async fn run_agent(
                 &working_dir,
                 false,
                 metadata,
+                stream_buffer,
+                key_passphrase.as_ref(),
+                keep_alive.as_ref(),
             )
             .await
         }
@@ -1,6 +1,8 @@
 // ABOUTME: Gathers environment metadata for agent registration.
 // ABOUTME: Collects git info, hostname, OS at startup.
 
+use crate::presence::Presence;
+pub use coven_core::git::GitInfo;
 use std::path::Path;
 use std::process::Command;
 
@@ -11,17 +13,6 @@ pub fn default_capabilities() -> Vec<String> {
     vec!["base".to_string(), "chat".to_string()]
 }
 
-/// Git repository state
-#[derive(Debug, Clone, Default)]
-pub struct GitInfo {
-    pub branch: String,
-    pub commit: String,
-    pub dirty: bool,
-    pub remote: String,
-    pub ahead: i32,
-    pub behind: i32,
-}
-
 /// Environment metadata sent during registration
 #[derive(Debug, Clone)]
 pub struct AgentMetadata {
@@ -35,88 +26,8 @@ pub struct AgentMetadata {
     pub backend: String,
     /// Capabilities this agent supports (set from config, defaults to ["base", "chat"])
     pub capabilities: Vec<String>,
-}
-
-impl GitInfo {
-    /// Gather git info from the given directory. Returns None if not a git repo.
-    pub fn gather(working_dir: &Path) -> Option<Self> {
-        // Check if this is a git repo
-        let status = Command::new("git")
-            .args(["rev-parse", "--git-dir"])
-            .current_dir(working_dir)
-            .output()
-            .ok()?;
-
-        if !status.status.success() {
-            return None;
-        }
-
-        let branch =
-            run_git(working_dir, &["rev-parse", "--abbrev-ref", "HEAD"]).unwrap_or_default();
-
-        let commit = run_git(working_dir, &["rev-parse", "--short", "HEAD"]).unwrap_or_default();
-
-        let dirty = run_git(working_dir, &["status", "--porcelain"])
-            .map(|s| !s.is_empty())
-            .unwrap_or(false);
-
-        // Get remote tracking branch (may not exist)
-        let remote =
-            run_git(working_dir, &["rev-parse", "--abbrev-ref", "@{u}"]).unwrap_or_default();
-
-        // Get ahead/behind counts (only if we have a remote)
-        let (ahead, behind) = if !remote.is_empty() {
-            parse_ahead_behind(working_dir)
-        } else {
-            (0, 0)
-        };
-
-        Some(GitInfo {
-            branch,
-            commit,
-            dirty,
-            remote,
-            ahead,
-            behind,
-        })
-    }
-}
-
-fn run_git(working_dir: &Path, args: &[&str]) -> Option<String> {
-    let output = Command::new("git")
-        .args(args)
-        .current_dir(working_dir)
-        .output()
-        .ok()?;
-
-    if output.status.success() {
-        Some(String::from_utf8_lossy(&output.stdout).trim().to_string())
-    } else {
-        None
-    }
-}
-
-fn parse_ahead_behind(working_dir: &Path) -> (i32, i32) {
-    // git rev-list --left-right --count @{u}...HEAD
-    // Output: "3\t5" meaning 3 behind, 5 ahead
-    let output = run_git(
-        working_dir,
-        &["rev-list", "--left-right", "--count", "@{u}...HEAD"],
-    );
-
-    match output {
-        Some(s) => {
-            let parts: Vec<&str> = s.split('\t').collect();
-            if parts.len() == 2 {
-                let behind = parts[0].parse().unwrap_or(0);
-                let ahead = parts[1].parse().unwrap_or(0);
-                (ahead, behind)
-            } else {
-                (0, 0)
-            }
-        }
-        None => (0, 0),
-    }
+    /// Presence while idle (set from config, defaults to "available")
+    pub presence: Presence,
 }
 
 impl AgentMetadata {
@@ -142,6 +53,7 @@ impl AgentMetadata {
             workspaces: Vec::new(),   // Set by caller from config
             backend: String::new(),   // Set by caller
             capabilities: Vec::new(), // Set by caller from config
+            presence: Presence::default(),
         }
     }
 }
@@ -161,19 +73,6 @@ fn get_hostname() -> String {
 }
 
 // Conversion to protobuf types
-impl From<GitInfo> for coven_proto::GitInfo {
-    fn from(info: GitInfo) -> Self {
-        coven_proto::GitInfo {
-            branch: info.branch,
-            commit: info.commit,
-            dirty: info.dirty,
-            remote: info.remote,
-            ahead: info.ahead,
-            behind: info.behind,
-        }
-    }
-}
-
 impl From<AgentMetadata> for coven_proto::AgentMetadata {
     fn from(meta: AgentMetadata) -> Self {
         coven_proto::AgentMetadata {
@@ -183,6 +82,7 @@ impl From<AgentMetadata> for coven_proto::AgentMetadata {
             os: meta.os,
             workspaces: meta.workspaces,
             backend: meta.backend,
+            presence: Some(meta.presence.into()),
         }
     }
 }
@@ -224,25 +124,6 @@ mod tests {
         assert!(metadata.git.is_none());
     }
 
-    #[test]
-    fn test_git_info_branch_and_commit() {
-        let working_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
-            .parent()
-            .unwrap()
-            .parent()
-            .unwrap()
-            .to_path_buf();
-
-        let git = GitInfo::gather(&working_dir).expect("should be in git repo");
-
-        // Commit should be a short hash (7-8 chars typically)
-        assert!(git.commit.len() >= 7);
-        assert!(git.commit.len() <= 12);
-
-        // Branch should be non-empty
-        assert!(!git.branch.is_empty());
-    }
-
     #[test]
     fn test_os_is_known_value() {
         let metadata = AgentMetadata::gather(Path::new("/tmp"));
@@ -274,4 +155,12 @@ mod tests {
         let proto_meta: coven_proto::AgentMetadata = metadata.into();
         assert_eq!(proto_meta.backend, "mux");
     }
+
+    #[test]
+    fn test_metadata_to_proto_includes_presence() {
+        let metadata = AgentMetadata::gather(Path::new("/tmp"));
+
+        let proto_meta: coven_proto::AgentMetadata = metadata.into();
+        assert_eq!(proto_meta.presence.unwrap().status, "available");
+    }
 }
@@ -1,10 +1,13 @@
 // ABOUTME: PackTool wraps pack tools received from the gateway for local execution.
-// ABOUTME: Routes tool calls through gRPC to the gateway for pack execution.
+// ABOUTME: Routes tool calls through gRPC to the gateway for pack execution, relaying progress as tool state and applying tool list updates.
 
 use async_trait::async_trait;
-use coven_proto::{agent_message, AgentMessage, ExecutePackTool, PackToolResult, ToolDefinition};
+use coven_core::backend::{report_tool_progress, MuxBackend};
+use coven_proto::{
+    agent_message, AgentMessage, ExecutePackTool, PackToolProgress, PackToolResult, ToolDefinition,
+};
 use mux::tool::{Tool, ToolResult};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 use tokio::sync::{mpsc, oneshot, Mutex};
 use tracing::{info, warn};
@@ -12,8 +15,14 @@ use tracing::{info, warn};
 /// Timeout for pack tool execution (5 minutes)
 const PACK_TOOL_TIMEOUT_SECS: u64 = 300;
 
-/// Shared state for pending pack tool requests - maps request_id to response sender
-pub type PendingPackTools = Arc<Mutex<HashMap<String, oneshot::Sender<PackToolResult>>>>;
+/// A pack tool call waiting on the gateway
+pub struct PendingPackTool {
+    result_tx: oneshot::Sender<PackToolResult>,
+    progress_tx: mpsc::UnboundedSender<PackToolProgress>,
+}
+
+/// Shared state for pending pack tool requests - maps request_id to the waiting call
+pub type PendingPackTools = Arc<Mutex<HashMap<String, PendingPackTool>>>;
 
 /// Creates a new empty pending pack tools map
 pub fn new_pending_pack_tools() -> PendingPackTools {
@@ -44,9 +53,15 @@ impl PackTool {
             })
         });
 
+        // Let the model know up front that the tool may fail
+        let description = match &def.degraded_reason {
+            Some(reason) => format!("{} (currently degraded: {})", def.description, reason),
+            None => def.description.clone(),
+        };
+
         Self {
             name: def.name.clone(),
-            description: def.description.clone(),
+            description,
             schema,
             tx,
             pending,
@@ -79,13 +94,20 @@ impl Tool for PackTool {
             "→ Pack tool execute"
         );
 
-        // Create oneshot channel for response
-        let (resp_tx, resp_rx) = oneshot::channel();
+        // Create channels for the response and any progress before it
+        let (result_tx, mut resp_rx) = oneshot::channel();
+        let (progress_tx, mut progress_rx) = mpsc::unbounded_channel();
 
         // Register pending request
         {
             let mut pending = self.pending.lock().await;
-            pending.insert(request_id.clone(), resp_tx);
+            pending.insert(
+                request_id.clone(),
+                PendingPackTool {
+                    result_tx,
+                    progress_tx,
+                },
+            );
         }
 
         // Serialize input to JSON
@@ -113,9 +135,20 @@ impl Tool for PackTool {
             return Err(anyhow::anyhow!("Failed to send pack tool request: {}", e));
         }
 
-        // Wait for response with timeout
+        // Wait for response with timeout, passing progress on to the backend
+        // so it shows up as tool state while the pack works
+        let wait = async {
+            loop {
+                tokio::select! {
+                    result = &mut resp_rx => return result,
+                    Some(progress) = progress_rx.recv() => {
+                        report_tool_progress(progress_detail(&progress)).await;
+                    }
+                }
+            }
+        };
         let timeout = tokio::time::Duration::from_secs(PACK_TOOL_TIMEOUT_SECS);
-        match tokio::time::timeout(timeout, resp_rx).await {
+        match tokio::time::timeout(timeout, wait).await {
             Ok(Ok(result)) => {
                 let elapsed = started.elapsed();
                 // Convert PackToolResult to ToolResult
@@ -190,8 +223,8 @@ impl Tool for PackTool {
 pub async fn handle_pack_tool_result(pending: &PendingPackTools, result: PackToolResult) -> bool {
     let request_id = result.request_id.clone();
     let mut pending_guard = pending.lock().await;
-    if let Some(sender) = pending_guard.remove(&request_id) {
-        let delivered = sender.send(result).is_ok();
+    if let Some(waiting) = pending_guard.remove(&request_id) {
+        let delivered = waiting.result_tx.send(result).is_ok();
         if !delivered {
             warn!(request_id = %request_id, "Pack tool result receiver dropped");
         }
@@ -201,3 +234,110 @@ pub async fn handle_pack_tool_result(pending: &PendingPackTools, result: PackToo
         false
     }
 }
+
+/// Handle a PackToolProgress message from the gateway.
+/// Returns true if the progress was passed to a waiting caller.
+pub async fn handle_pack_tool_progress(
+    pending: &PendingPackTools,
+    progress: PackToolProgress,
+) -> bool {
+    let pending_guard = pending.lock().await;
+    match pending_guard.get(&progress.request_id) {
+        Some(waiting) => waiting.progress_tx.send(progress).is_ok(),
+        None => false,
+    }
+}
+
+/// Apply the gateway's full pack tool list to a mux backend, see
+/// [`MuxBackend::sync_tools`]. Returns the names of the removed tools.
+pub async fn sync_pack_tools(
+    mux: &MuxBackend,
+    registered: &mut HashSet<String>,
+    tools: &[ToolDefinition],
+    tx: &mpsc::Sender<AgentMessage>,
+    pending: &PendingPackTools,
+) -> Vec<String> {
+    mux.sync_tools(registered, tools, |def| {
+        PackTool::new(def, tx.clone(), pending.clone())
+    })
+    .await
+}
+
+/// Tool state detail for a progress report, e.g. "Compiling (40%)".
+fn progress_detail(progress: &PackToolProgress) -> String {
+    match (progress.percent, progress.message.is_empty()) {
+        (Some(percent), true) => format!("{}%", percent),
+        (Some(percent), false) => format!("{} ({}%)", progress.message, percent),
+        (None, _) => progress.message.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn progress(request_id: &str, percent: Option<u32>, message: &str) -> PackToolProgress {
+        PackToolProgress {
+            request_id: request_id.to_string(),
+            percent,
+            message: message.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_degraded_tool_description() {
+        let (tx, _rx) = mpsc::channel(1);
+        let mut def = ToolDefinition {
+            name: "note_search".to_string(),
+            description: "Search notes".to_string(),
+            input_schema_json: "{}".to_string(),
+            ..Default::default()
+        };
+        let tool = PackTool::new(&def, tx.clone(), new_pending_pack_tools());
+        assert_eq!(tool.description(), "Search notes");
+
+        def.degraded_reason = Some("database is locked".to_string());
+        let tool = PackTool::new(&def, tx, new_pending_pack_tools());
+        assert_eq!(
+            tool.description(),
+            "Search notes (currently degraded: database is locked)"
+        );
+    }
+
+    #[test]
+    fn test_progress_detail() {
+        assert_eq!(
+            progress_detail(&progress("r", Some(40), "Compiling")),
+            "Compiling (40%)"
+        );
+        assert_eq!(progress_detail(&progress("r", Some(40), "")), "40%");
+        assert_eq!(progress_detail(&progress("r", None, "Linking")), "Linking");
+    }
+
+    #[tokio::test]
+    async fn test_progress_routes_to_waiting_call_until_result() {
+        let pending = new_pending_pack_tools();
+        let (result_tx, result_rx) = oneshot::channel();
+        let (progress_tx, mut progress_rx) = mpsc::unbounded_channel();
+        pending.lock().await.insert(
+            "req-1".to_string(),
+            PendingPackTool {
+                result_tx,
+                progress_tx,
+            },
+        );
+
+        assert!(handle_pack_tool_progress(&pending, progress("req-1", Some(10), "Starting")).await);
+        assert!(!handle_pack_tool_progress(&pending, progress("other", None, "nope")).await);
+        assert_eq!(progress_rx.recv().await.unwrap().message, "Starting");
+
+        let result = PackToolResult {
+            request_id: "req-1".to_string(),
+            result: None,
+        };
+        assert!(handle_pack_tool_result(&pending, result).await);
+        assert_eq!(result_rx.await.unwrap().request_id, "req-1");
+        // Progress after the result has nowhere to go
+        assert!(!handle_pack_tool_progress(&pending, progress("req-1", None, "late")).await);
+    }
+}
@@ -0,0 +1,210 @@
+// ABOUTME: Agent presence: the human-friendly status shown next to the agent in pickers.
+// ABOUTME: PresenceTracker flips it to busy while messages are in flight. Cosmetic only.
+
+use coven_proto::{agent_message, AgentMessage, AgentPresence};
+use std::sync::{Arc, Mutex};
+use tokio::sync::{mpsc, watch};
+
+/// Status reported while the agent is working on a message
+pub const BUSY_STATUS: &str = "busy";
+
+/// Status and optional emoji the agent advertises
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Presence {
+    pub status: String,
+    pub emoji: String,
+}
+
+impl Default for Presence {
+    fn default() -> Self {
+        Self {
+            status: AgentPresence::DEFAULT_STATUS.to_string(),
+            emoji: String::new(),
+        }
+    }
+}
+
+impl Presence {
+    /// Presence while handling messages
+    pub fn busy() -> Self {
+        Self {
+            status: BUSY_STATUS.to_string(),
+            emoji: String::new(),
+        }
+    }
+
+    /// Idle presence from `status` and `status_emoji` in the agent config,
+    /// defaulting to "available"
+    pub fn from_config(config: &toml::Table) -> Self {
+        let get = |key: &str| {
+            config
+                .get(key)
+                .and_then(|v| v.as_str())
+                .map(|s| s.trim().to_string())
+                .filter(|s| !s.is_empty())
+        };
+        let default = Self::default();
+        Self {
+            status: get("status").unwrap_or(default.status),
+            emoji: get("status_emoji").unwrap_or(default.emoji),
+        }
+    }
+}
+
+impl From<Presence> for AgentPresence {
+    fn from(presence: Presence) -> Self {
+        AgentPresence {
+            status: presence.status,
+            emoji: presence.emoji,
+        }
+    }
+}
+
+/// Reports the agent as busy while any message is being handled, and back
+/// to its idle presence once the last one finishes.
+pub struct PresenceTracker {
+    idle: Presence,
+    in_flight: Mutex<usize>,
+    current: watch::Sender<Presence>,
+}
+
+impl PresenceTracker {
+    /// Track presence, forwarding changes to the gateway on `tx` from a
+    /// background task that ends when the tracker is dropped
+    pub fn new(idle: Presence, tx: mpsc::Sender<AgentMessage>) -> Arc<Self> {
+        let (current, changes) = watch::channel(idle.clone());
+        tokio::spawn(forward(changes, tx));
+        Arc::new(Self {
+            idle,
+            in_flight: Mutex::new(0),
+            current,
+        })
+    }
+
+    /// Mark a message as in flight until the returned guard is dropped
+    pub fn start(self: &Arc<Self>) -> BusyGuard {
+        let mut in_flight = self.in_flight.lock().unwrap_or_else(|e| e.into_inner());
+        *in_flight += 1;
+        if *in_flight == 1 {
+            self.send(Presence::busy());
+        }
+        BusyGuard {
+            tracker: Arc::clone(self),
+        }
+    }
+
+    fn finish(&self) {
+        let mut in_flight = self.in_flight.lock().unwrap_or_else(|e| e.into_inner());
+        *in_flight = in_flight.saturating_sub(1);
+        if *in_flight == 0 {
+            self.send(self.idle.clone());
+        }
+    }
+
+    /// Called with the count locked so the latest presence always matches it
+    fn send(&self, presence: Presence) {
+        self.current.send_replace(presence);
+    }
+}
+
+/// Send each presence change to the gateway. While the stream is full the
+/// sends wait rather than drop, and only the newest presence is sent once
+/// it has room, so the agent never stays busy after its work is done.
+async fn forward(mut changes: watch::Receiver<Presence>, tx: mpsc::Sender<AgentMessage>) {
+    while changes.changed().await.is_ok() {
+        let presence = changes.borrow_and_update().clone();
+        let msg = AgentMessage {
+            payload: Some(agent_message::Payload::UpdatePresence(presence.into())),
+        };
+        if tx.send(msg).await.is_err() {
+            break;
+        }
+    }
+}
+
+/// Keeps the agent busy while alive; see [`PresenceTracker::start`]
+pub struct BusyGuard {
+    tracker: Arc<PresenceTracker>,
+}
+
+impl Drop for BusyGuard {
+    fn drop(&mut self) {
+        self.tracker.finish();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn presence_of(msg: AgentMessage) -> AgentPresence {
+        match msg.payload {
+            Some(agent_message::Payload::UpdatePresence(p)) => p,
+            other => panic!("expected presence update, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_presence_from_config() {
+        let config: toml::Table = "status = \"on call\"\nstatus_emoji = \"📟\"\n"
+            .parse()
+            .unwrap();
+        let presence = Presence::from_config(&config);
+        assert_eq!(presence.status, "on call");
+        assert_eq!(presence.emoji, "📟");
+
+        let empty: toml::Table = "status = \"  \"\n".parse().unwrap();
+        assert_eq!(Presence::from_config(&empty), Presence::default());
+        assert_eq!(Presence::default().status, "available");
+    }
+
+    async fn next_presence(rx: &mut mpsc::Receiver<AgentMessage>) -> AgentPresence {
+        let msg = tokio::time::timeout(std::time::Duration::from_secs(5), rx.recv())
+            .await
+            .expect("no presence update")
+            .unwrap();
+        presence_of(msg)
+    }
+
+    #[tokio::test]
+    async fn test_tracker_busy_until_last_message_finishes() {
+        let (tx, mut rx) = mpsc::channel(8);
+        let idle = Presence {
+            status: "available".to_string(),
+            emoji: "🟢".to_string(),
+        };
+        let tracker = PresenceTracker::new(idle, tx);
+
+        let first = tracker.start();
+        assert_eq!(next_presence(&mut rx).await.status, BUSY_STATUS);
+
+        // Neither a second message nor the first finishing changes presence
+        let second = tracker.start();
+        drop(first);
+        assert_eq!(tracker.current.borrow().status, BUSY_STATUS);
+
+        drop(second);
+        let idle = next_presence(&mut rx).await;
+        assert_eq!(idle.status, "available");
+        assert_eq!(idle.emoji, "🟢");
+        assert!(rx.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_idle_is_sent_after_a_full_stream_drains() {
+        let (tx, mut rx) = mpsc::channel(1);
+        tx.send(AgentMessage::default()).await.unwrap();
+        let tracker = PresenceTracker::new(Presence::default(), tx);
+
+        // Busy and idle both happen while the stream has no room
+        drop(tracker.start());
+        assert!(rx.recv().await.unwrap().payload.is_none());
+
+        // Whatever is sent meanwhile, the last word is idle
+        let mut last = next_presence(&mut rx).await;
+        while last.status != Presence::default().status {
+            last = next_presence(&mut rx).await;
+        }
+        assert_eq!(tracker.current.borrow().status, "available");
+    }
+}
@@ -0,0 +1,102 @@
+// ABOUTME: Resolves and prints the system prompt an agent would send, without running it.
+// ABOUTME: Backs `coven agent show-prompt`, annotating each section with where it came from.
+
+use crate::agent_config::{discover_config_path, load_config_file};
+use anyhow::Result;
+use coven_core::backend::{
+    assemble_system_prompt, system_prompt_sections, MuxConfig, PromptSection,
+};
+use std::path::PathBuf;
+
+/// Resolve the prompt sections for the agent that `run_agent` would start
+/// with the same config and working directory.
+pub fn resolve(
+    config: Option<PathBuf>,
+    working_dir: Option<PathBuf>,
+) -> Result<(Option<String>, Vec<PromptSection>)> {
+    let loaded = discover_config_path(config)
+        .map(|path| load_config_file(&path))
+        .transpose()?;
+
+    let config_working_dir = loaded
+        .as_ref()
+        .and_then(|c| c.get("working_dir"))
+        .and_then(|v| v.as_str())
+        .map(PathBuf::from);
+    let backend = loaded
+        .as_ref()
+        .and_then(|c| c.get("backend"))
+        .and_then(|v| v.as_str())
+        .map(String::from);
+
+    let working_dir = working_dir
+        .or(config_working_dir)
+        .or_else(|| std::env::current_dir().ok())
+        .unwrap_or_else(|| PathBuf::from("."));
+    let working_dir = working_dir.canonicalize().unwrap_or(working_dir);
+
+    let settings = coven_core::Config::load()?;
+    let mux_config = MuxConfig::from_settings(&settings.mux, &working_dir);
+    Ok((backend, system_prompt_sections(&mux_config)))
+}
+
+/// Render sections with a header line naming each one's source
+pub fn render_annotated(sections: &[PromptSection]) -> String {
+    let mut out = String::new();
+    for (i, section) in sections.iter().enumerate() {
+        if i > 0 {
+            out.push('\n');
+        }
+        out.push_str(&format!("=== [{}] {} ===\n", i + 1, section.source));
+        out.push_str(section.content.trim_end());
+        out.push('\n');
+    }
+    out
+}
+
+/// Print the assembled system prompt: annotated by default, or exactly as
+/// sent to the model with `raw`.
+pub fn show(config: Option<PathBuf>, working_dir: Option<PathBuf>, raw: bool) -> Result<()> {
+    let (backend, sections) = resolve(config, working_dir)?;
+
+    if let Some(backend) = backend.filter(|b| b != "mux") {
+        eprintln!(
+            "Note: this agent uses the '{}' backend, which builds its own system prompt; \
+             this is what the mux backend would send.",
+            backend
+        );
+    }
+
+    if raw {
+        println!("{}", assemble_system_prompt(&sections));
+    } else {
+        print!("{}", render_annotated(&sections));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use coven_core::backend::PromptSource;
+
+    #[test]
+    fn test_render_annotated_labels_sections_in_order() {
+        let sections = vec![
+            PromptSection {
+                source: PromptSource::Environment,
+                content: "# Environment\n\nhere".to_string(),
+            },
+            PromptSection {
+                source: PromptSource::LocalPrompt(PathBuf::from("/work/CLAUDE.md")),
+                content: "Be terse.\n".to_string(),
+            },
+        ];
+
+        assert_eq!(
+            render_annotated(&sections),
+            "=== [1] environment (generated) ===\n# Environment\n\nhere\n\n\
+             === [2] local prompt: /work/CLAUDE.md ===\nBe terse.\n"
+        );
+    }
+}
@@ -1,7 +1,8 @@
 // ABOUTME: Public entry points for running coven-agent from external crates.
 // ABOUTME: Exposes run_agent and run_wizard for use by coven-cli.
 
-use anyhow::{bail, Context, Result};
+use crate::agent_config::{discover_config_path, load_config_file};
+use anyhow::{bail, Result};
 use std::path::PathBuf;
 
 /// Configuration options for running an agent.
@@ -23,6 +24,8 @@ pub struct AgentRunConfig {
     pub headless: bool,
     /// Run in single-user interactive mode (no gRPC server)
     pub single: bool,
+    /// Re-run headless in the background, tracked under ~/.local/state/coven/agents
+    pub detach: bool,
 }
 
 impl Default for AgentRunConfig {
@@ -36,6 +39,7 @@ impl Default for AgentRunConfig {
             config: None,
             headless: false,
             single: false,
+            detach: false,
         }
     }
 }
@@ -59,20 +63,6 @@ impl DisplayMode {
     }
 }
 
-/// Get XDG-style config directory (~/.config/coven)
-/// Respects XDG_CONFIG_HOME if set, otherwise uses ~/.config
-fn xdg_config_dir() -> Option<PathBuf> {
-    std::env::var_os("XDG_CONFIG_HOME")
-        .map(PathBuf::from)
-        .or_else(|| dirs::home_dir().map(|h| h.join(".config")))
-        .map(|p| p.join("coven"))
-}
-
-/// Get default config path (~/.config/coven/agent.toml)
-fn default_config_path() -> Option<PathBuf> {
-    xdg_config_dir().map(|p| p.join("agent.toml"))
-}
-
 /// Resolve project name from .coven/project.toml or directory basename
 fn resolve_project_name(working_dir: &std::path::Path) -> String {
     // Try to load .coven/project.toml
@@ -107,7 +97,7 @@ fn resolve_project_name(working_dir: &std::path::Path) -> String {
 }
 
 /// Sanitize project name for use in agent ID
-fn sanitize_project_name(name: &str) -> String {
+pub(crate) fn sanitize_project_name(name: &str) -> String {
     let result: String = name
         .chars()
         .map(|c| {
@@ -136,6 +126,19 @@ fn sanitize_project_name(name: &str) -> String {
     collapsed
 }
 
+/// Start a detached copy of the agent and report where it is tracked
+pub fn start_detached(agent_id: &str, args: &[std::ffi::OsString]) -> Result<()> {
+    let registry = crate::daemon::Registry::from_env()?;
+    let agent = crate::daemon::detach(&registry, agent_id, args)?;
+    println!(
+        "Agent {} running in the background (pid {})",
+        agent.id, agent.pid
+    );
+    println!("  Logs: coven agent logs {} -f", agent.id);
+    println!("  Stop: coven agent stop {}", agent.id);
+    Ok(())
+}
+
 /// Run an agent with the given configuration.
 ///
 /// This is the main entry point for running a coven agent. It handles:
@@ -144,150 +147,117 @@ fn sanitize_project_name(name: &str) -> String {
 /// - Running in TUI, headless, or single mode
 pub async fn run_agent(config: AgentRunConfig) -> Result<()> {
     let mode = DisplayMode::from_headless_flag(config.headless);
+    // A detached agent's output rotates through its log until it exits
+    let _log = crate::daemon::capture_log()?;
 
     // Try to load config: explicit path > project-local > user-global
-    let config_path = config.config.or_else(|| {
-        // Check for project-local config first (.coven/agent.toml in cwd)
-        if let Ok(cwd) = std::env::current_dir() {
-            let project_config = cwd.join(".coven").join("agent.toml");
-            if project_config.exists() {
-                return Some(project_config);
-            }
-        }
-        // Fall back to user-global config (~/.config/coven/agent.toml)
-        let default = default_config_path()?;
-        if default.exists() {
-            Some(default)
-        } else {
-            None
-        }
-    });
+    let config_path = discover_config_path(config.config);
 
     // Load settings from config - required unless running in single mode
-    let (server, name, backend, working_dir, workspaces, capabilities) =
-        if let Some(ref config_path) = config_path {
-            tracing::info!("Loading config from: {}", config_path.display());
-            let config_content = std::fs::read_to_string(config_path).with_context(|| {
-                format!("failed to read config file: {}", config_path.display())
-            })?;
-            let loaded_config: toml::Table =
-                toml::from_str(&config_content).with_context(|| {
-                    format!("failed to parse config file: {}", config_path.display())
-                })?;
-
-            // Check if this is a reference to a global agent (agent = "name")
-            let loaded_config =
-                if let Some(agent_ref) = loaded_config.get("agent").and_then(|v| v.as_str()) {
-                    let agents_dir = xdg_config_dir()
-                        .ok_or_else(|| anyhow::anyhow!("Could not find config directory"))?
-                        .join("agents");
-                    let agent_config_path = agents_dir.join(format!("{}.toml", agent_ref));
-                    tracing::info!(
-                        "Resolving agent reference '{}' -> {}",
-                        agent_ref,
-                        agent_config_path.display()
-                    );
-                    let agent_content =
-                        std::fs::read_to_string(&agent_config_path).with_context(|| {
-                            format!(
-                                "failed to read agent config '{}' at {}",
-                                agent_ref,
-                                agent_config_path.display()
-                            )
-                        })?;
-                    toml::from_str(&agent_content).with_context(|| {
-                        format!(
-                            "failed to parse agent config '{}' at {}",
-                            agent_ref,
-                            agent_config_path.display()
-                        )
-                    })?
-                } else {
-                    loaded_config
-                };
-
-            // Server can come from:
-            // 1. Agent config file (server = "...")
-            // 2. User's coven config from `coven link` (~/.config/coven/config.toml)
-            // 3. CLI args / defaults
-            let server = loaded_config
-                .get("server")
-                .and_then(|v| v.as_str())
-                .map(|s| s.to_string())
-                .or_else(|| {
-                    // Try to load gateway from user's coven config
-                    coven_link::config::CovenConfig::load().ok().map(|c| {
-                        // Gateway is "host:port" format, convert to URL
-                        if c.gateway.starts_with("http://") || c.gateway.starts_with("https://") {
-                            c.gateway
-                        } else {
-                            format!("http://{}", c.gateway)
-                        }
-                    })
-                })
-                .unwrap_or(config.server);
-            let name = loaded_config
-                .get("name")
-                .and_then(|v| v.as_str())
-                .map(|s| s.to_string())
-                .unwrap_or(config.name);
-            let backend = loaded_config
-                .get("backend")
-                .and_then(|v| v.as_str())
-                .map(|s| s.to_string())
-                .or(config.backend);
-            let config_working_dir = loaded_config
-                .get("working_dir")
-                .and_then(|v| v.as_str())
-                .map(PathBuf::from);
-
-            let workspaces: Vec<String> = loaded_config
-                .get("workspaces")
-                .and_then(|v| v.as_array())
-                .map(|arr| {
-                    arr.iter()
-                        .filter_map(|v| v.as_str().map(String::from))
-                        .collect()
-                })
-                .unwrap_or_default();
-
-            let capabilities: Vec<String> = loaded_config
-                .get("capabilities")
-                .and_then(|v| v.as_array())
-                .map(|arr| {
-                    arr.iter()
-                        .filter_map(|v| v.as_str().map(String::from))
-                        .collect()
+    let (
+        server,
+        name,
+        backend,
+        working_dir,
+        workspaces,
+        capabilities,
+        presence,
+        stream_buffer,
+        key_passphrase,
+        keep_alive,
+    ) = if let Some(ref config_path) = config_path {
+        let loaded_config = load_config_file(config_path)?;
+
+        // Server can come from:
+        // 1. Agent config file (server = "...")
+        // 2. User's coven config from `coven link` (~/.config/coven/config.toml)
+        // 3. CLI args / defaults
+        let server = loaded_config
+            .get("server")
+            .and_then(|v| v.as_str())
+            .map(|s| s.to_string())
+            .or_else(|| {
+                // Try to load gateway from user's coven config
+                coven_link::config::CovenConfig::load().ok().map(|c| {
+                    // Gateway is "host:port" format, convert to URL
+                    if c.gateway.starts_with("http://") || c.gateway.starts_with("https://") {
+                        c.gateway
+                    } else {
+                        format!("http://{}", c.gateway)
+                    }
                 })
-                .unwrap_or_else(crate::metadata::default_capabilities);
-
-            (
-                server,
-                name,
-                backend,
-                config.working_dir.or(config_working_dir),
-                workspaces,
-                capabilities,
-            )
-        } else if !config.single {
-            // Config is required for gateway mode
-            bail!(
-                "No configuration found. Create one with 'coven agent new' or specify --config.\n\
+            })
+            .unwrap_or(config.server);
+        let name = loaded_config
+            .get("name")
+            .and_then(|v| v.as_str())
+            .map(|s| s.to_string())
+            .unwrap_or(config.name);
+        let backend = loaded_config
+            .get("backend")
+            .and_then(|v| v.as_str())
+            .map(|s| s.to_string())
+            .or(config.backend);
+        let config_working_dir = loaded_config
+            .get("working_dir")
+            .and_then(|v| v.as_str())
+            .map(PathBuf::from);
+
+        let workspaces: Vec<String> = loaded_config
+            .get("workspaces")
+            .and_then(|v| v.as_array())
+            .map(|arr| {
+                arr.iter()
+                    .filter_map(|v| v.as_str().map(String::from))
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        let capabilities: Vec<String> = loaded_config
+            .get("capabilities")
+            .and_then(|v| v.as_array())
+            .map(|arr| {
+                arr.iter()
+                    .filter_map(|v| v.as_str().map(String::from))
+                    .collect()
+            })
+            .unwrap_or_else(crate::metadata::default_capabilities);
+
+        (
+            server,
+            name,
+            backend,
+            config.working_dir.or(config_working_dir),
+            workspaces,
+            capabilities,
+            crate::presence::Presence::from_config(&loaded_config),
+            crate::agent_config::stream_buffer(&loaded_config),
+            crate::agent_config::key_passphrase(&loaded_config)?,
+            crate::agent_config::keep_alive(&loaded_config)?,
+        )
+    } else if !config.single {
+        // Config is required for gateway mode
+        bail!(
+            "No configuration found. Create one with 'coven agent new' or specify --config.\n\
              Searched:\n\
              - .coven/agent.toml (project-local)\n\
              - ~/.config/coven/agent.toml (user-global)"
-            );
-        } else {
-            // Single mode can work without config - use default capabilities
-            (
-                config.server,
-                config.name,
-                config.backend,
-                config.working_dir,
-                Vec::new(),
-                crate::metadata::default_capabilities(),
-            )
-        };
+        );
+    } else {
+        // Single mode can work without config - use default capabilities
+        (
+            config.server,
+            config.name,
+            config.backend,
+            config.working_dir,
+            Vec::new(),
+            crate::metadata::default_capabilities(),
+            crate::presence::Presence::default(),
+            coven_grpc::DEFAULT_CHANNEL_BUFFER,
+            None,
+            Some(coven_grpc::KeepAliveConfig::default()),
+        )
+    };
 
     // Default to current directory if not specified
     let working_dir = working_dir
@@ -301,11 +271,18 @@ pub async fn run_agent(config: AgentRunConfig) -> Result<()> {
     let backend_type = backend.unwrap_or_else(|| "cli".to_string());
 
     // Create agent ID from name + project name
+    let id_given = config.id.is_some();
     let agent_id = config.id.unwrap_or_else(|| {
         let project_name = resolve_project_name(&working_dir);
         format!("{}-{}", name, project_name)
     });
 
+    if config.detach {
+        let args =
+            crate::daemon::child_args(std::env::args_os(), &agent_id, config.headless, id_given);
+        return start_detached(&agent_id, &args);
+    }
+
     if config.single {
         return crate::single::run(&name, &agent_id, &backend_type, &working_dir).await;
     }
@@ -318,6 +295,8 @@ pub async fn run_agent(config: AgentRunConfig) -> Result<()> {
                 &backend_type,
                 &working_dir,
                 capabilities,
+                key_passphrase.as_ref(),
+                keep_alive,
             )
             .await
         }
@@ -327,6 +306,7 @@ pub async fn run_agent(config: AgentRunConfig) -> Result<()> {
             metadata.workspaces = workspaces;
             metadata.backend = backend_type.to_string();
             metadata.capabilities = capabilities;
+            metadata.presence = presence;
 
             // Log metadata for debugging
             eprintln!("Agent metadata:");
@@ -335,6 +315,7 @@ pub async fn run_agent(config: AgentRunConfig) -> Result<()> {
             eprintln!("  OS: {}", metadata.os);
             eprintln!("  Backend: {}", metadata.backend);
             eprintln!("  Capabilities: {:?}", metadata.capabilities);
+            eprintln!("  Status: {}", metadata.presence.status);
             if !metadata.workspaces.is_empty() {
                 eprintln!("  Workspaces: {:?}", metadata.workspaces);
             }
@@ -355,6 +336,9 @@ pub async fn run_agent(config: AgentRunConfig) -> Result<()> {
                 &working_dir,
                 false,
                 metadata,
+                stream_buffer,
+                key_passphrase.as_ref(),
+                keep_alive.as_ref(),
             )
             .await
         }
@@ -373,6 +357,25 @@ pub async fn run_wizard(command_prefix: &str) -> Result<()> {
     crate::wizard::run_with_prefix(command_prefix).await
 }
 
+/// Validate agent configuration without connecting anywhere.
+///
+/// Checks the agent config that `run_agent` would load (following agent
+/// references) and the project's `.coven/project.toml`, prints a pass/fail
+/// summary, and returns an error if any problems were found.
+pub fn validate_config(config: Option<PathBuf>) -> Result<()> {
+    let report = crate::agent_config::validate(config);
+    crate::agent_config::print_report(&report)
+}
+
+/// Print the system prompt the agent would send, section by section.
+///
+/// Resolves the agent config and working directory the way `run_agent`
+/// does, then assembles the prompt from the `[mux]` settings without
+/// starting a backend. With `raw`, prints it exactly as sent.
+pub fn show_prompt(config: Option<PathBuf>, working_dir: Option<PathBuf>, raw: bool) -> Result<()> {
+    crate::prompt::show(config, working_dir, raw)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -4,7 +4,7 @@
 #![allow(dead_code)] // Types will be used by later tasks in the implementation
 
 use super::messages::ChatMessage;
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::time::Instant;
 
 /// Current application status
@@ -23,6 +23,8 @@ pub struct PendingApproval {
     pub tool_id: String,
     pub tool_name: String,
     pub input_json: String,
+    /// Human-readable confirmation text from the tool definition, if any
+    pub confirm_message: Option<String>,
 }
 
 /// Main application state
@@ -41,15 +43,21 @@ pub struct App {
     // View state
     pub scroll_offset: usize,
     pub follow_mode: bool, // Auto-scroll to bottom
+    /// Show tool results in full instead of their first few lines
+    pub expand_tool_results: bool,
 
     // Status
     pub status: AppStatus,
     pub error_message: Option<String>,
+    /// Spend against the backend's budget, when it has one
+    pub budget: Option<String>,
 
     // Approval state
     pub pending_approval: Option<PendingApproval>,
     pub auto_approve_all: bool,
     pub approved_tools_session: HashSet<String>,
+    /// Confirmation text from `[mux.confirm_messages]`, by tool name
+    pub confirm_messages: HashMap<String, String>,
 
     // Flags
     pub should_quit: bool,
@@ -71,11 +79,14 @@ impl App {
             input_cursor: 0,
             scroll_offset: 0,
             follow_mode: true,
+            expand_tool_results: false,
             status: AppStatus::Ready,
             error_message: None,
+            budget: None,
             pending_approval: None,
             auto_approve_all: false,
             approved_tools_session: HashSet::new(),
+            confirm_messages: HashMap::new(),
             should_quit: false,
             show_help: false,
             last_ctrl_c: None,
@@ -101,11 +112,20 @@ impl App {
         if self.approved_tools_session.contains(&name_lower) {
             return false;
         }
-        // Dangerous tools that modify state
+        // Dangerous tools that modify state, and any with confirmation text
         matches!(
             name_lower.as_str(),
             "bash" | "write" | "write_file" | "edit" | "notebookedit" | "todowrite"
-        )
+        ) || self.confirm_message(tool_name).is_some()
+    }
+
+    /// Configured confirmation text for a tool, matching the name as given
+    /// or lowercased
+    pub fn confirm_message(&self, tool_name: &str) -> Option<String> {
+        self.confirm_messages
+            .get(tool_name)
+            .or_else(|| self.confirm_messages.get(&tool_name.to_lowercase()))
+            .cloned()
     }
 
     /// Add a character to input at cursor position
@@ -57,6 +57,10 @@ pub fn handle_key(app: &mut App, key: KeyEvent) -> InputResult {
             app.messages.clear();
             return InputResult::Continue;
         }
+        (KeyModifiers::CONTROL, KeyCode::Char('o')) => {
+            app.expand_tool_results = !app.expand_tool_results;
+            return InputResult::Continue;
+        }
         (_, KeyCode::Char('?')) if app.input.is_empty() => {
             app.show_help = true;
             return InputResult::Continue;
@@ -21,6 +21,9 @@ pub struct ToolExecution {
     pub input_preview: String,
     pub status: ToolStatus,
     pub output_preview: Option<String>,
+    /// The whole result, shown in the chat a few lines at a time unless
+    /// tool results are expanded
+    pub output: Option<String>,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq)]
@@ -4,7 +4,7 @@
 mod app;
 mod input;
 mod messages;
-mod theme;
+pub mod theme;
 mod ui;
 
 use anyhow::{bail, Result};
@@ -88,6 +88,7 @@ pub async fn run(name: &str, agent_id: &str, backend_type: &str, working_dir: &P
         backend_type,
         &working_dir.display().to_string(),
     );
+    app.confirm_messages = config.mux.confirm_messages.clone();
 
     // Channel for backend events
     let (event_tx, mut event_rx) = mpsc::channel::<BackendMsg>(100);
@@ -95,8 +96,17 @@ pub async fn run(name: &str, agent_id: &str, backend_type: &str, working_dir: &P
     // Pending tool state for approval flow
     let mut pending_tool: Option<(String, String, String)> = None; // (id, name, input)
 
-    // Store agent_id for the loop
-    let agent_id_owned = agent_id.to_string();
+    // Every message goes to this agent's one thread
+    let thread_id = format!("single-{}", agent_id);
+
+    // Show what's already been spent before the first message
+    match coven.budget_status(&thread_id).await {
+        Ok(status) => app.budget = status.map(|status| status.summary()),
+        Err(e) => app.messages.push(ChatMessage::system(format!(
+            "Couldn't read budget spend: {}",
+            e
+        ))),
+    }
 
     // Main event loop
     loop {
@@ -126,11 +136,14 @@ pub async fn run(name: &str, agent_id: &str, backend_type: &str, working_dir: &P
 
                         // Create incoming message for backend
                         let incoming = IncomingMessage {
-                            thread_id: format!("single-{}", agent_id_owned),
+                            thread_id: thread_id.clone(),
                             sender: "user".to_string(),
+                            sender_display: None,
                             content,
                             frontend: "tui".to_string(),
                             attachments: vec![],
+                            overrides: Default::default(),
+                            reply_to_message_id: None,
                         };
 
                         // Spawn task to process with backend
@@ -323,6 +336,7 @@ fn handle_backend_event(
                             input_preview,
                             status,
                             output_preview: None,
+                            output: None,
                         });
                     }
 
@@ -333,6 +347,7 @@ fn handle_backend_event(
                             tool_id: id.clone(),
                             tool_name: name.clone(),
                             input_json: input.to_string(),
+                            confirm_message: app.confirm_message(&name),
                         });
                         *pending_tool = Some((id, name, input.to_string()));
                     }
@@ -357,6 +372,7 @@ fn handle_backend_event(
                                 ToolStatus::Completed
                             };
                             tool.output_preview = Some(truncate_string(&output, 100));
+                            tool.output = Some(output);
                         }
                     }
                 }
@@ -400,7 +416,12 @@ fn handle_backend_event(
                         ));
                     }
                 }
-                OutgoingEvent::ToolApprovalRequest { id, name, input } => {
+                OutgoingEvent::ToolApprovalRequest {
+                    id,
+                    name,
+                    input,
+                    confirm_message,
+                } => {
                     // Handle approval request from MuxBackend
                     // In single mode, we already handle local approval via ToolUse events,
                     // but this event comes from the backend when using approval callbacks.
@@ -422,6 +443,7 @@ fn handle_backend_event(
                                 input_preview,
                                 status: ToolStatus::Pending,
                                 output_preview: None,
+                                output: None,
                             });
                         }
                     }
@@ -432,6 +454,7 @@ fn handle_backend_event(
                         tool_id: id.clone(),
                         tool_name: name.clone(),
                         input_json: input.to_string(),
+                        confirm_message,
                     });
                     *pending_tool = Some((id, name, input.to_string()));
                 }
@@ -460,6 +483,20 @@ fn handle_backend_event(
                         input_tokens, output_tokens, cache_read_tokens, thinking_tokens
                     )));
                 }
+                OutgoingEvent::Budget(report) => {
+                    // Spend goes in the status bar; only alerts reach the chat
+                    app.budget = Some(report.status.summary());
+                    for alert in report.alerts {
+                        app.messages.push(ChatMessage::system(alert.to_string()));
+                    }
+                }
+                OutgoingEvent::BudgetExceeded(exceeded) => {
+                    app.budget = Some(exceeded.status.summary());
+                    app.error_message = Some(exceeded.to_string());
+                    app.status = AppStatus::Error;
+                    app.messages
+                        .push(ChatMessage::system(format!("Error: {}", exceeded)));
+                }
                 OutgoingEvent::ToolState { id, state, detail } => {
                     // Update tool status based on state (search backwards since
                     // System messages may be interleaved during streaming)
@@ -523,25 +560,7 @@ async fn create_backend(
         "mux" => {
             tracing::info!("Using MuxBackend (direct Anthropic API)");
             tracing::info!("  Working dir: {}", working_dir.display());
-            let mux_settings = config.mux.clone();
-            let mux_config = MuxConfig {
-                model: std::env::var("ANTHROPIC_MODEL").unwrap_or(mux_settings.model),
-                max_tokens: std::env::var("ANTHROPIC_MAX_TOKENS")
-                    .ok()
-                    .and_then(|s| s.parse().ok())
-                    .unwrap_or(mux_settings.max_tokens),
-                working_dir: working_dir.to_path_buf(),
-                global_system_prompt_path: mux_settings
-                    .global_system_prompt_path
-                    .or_else(|| dirs::home_dir().map(|h| h.join(".mux/system.md"))),
-                local_prompt_files: mux_settings.local_prompt_files,
-                global_soul_path: mux_settings.global_soul_path,
-                agent_soul_path: mux_settings.agent_soul_path,
-                soul_files: mux_settings.soul_files,
-                mcp_servers: vec![],
-                skip_default_tools: false,
-                gateway_mcp: None, // Set after gateway connection
-            };
+            let mux_config = MuxConfig::from_settings(&config.mux, working_dir);
 
             // Create approval callback that waits for TUI user response
             // Timeout after 5 minutes to prevent infinite hangs
@@ -602,6 +621,8 @@ async fn create_backend(
                 working_dir: working_dir.to_path_buf(),
                 timeout_secs: config.claude.timeout_secs,
                 mcp_endpoint: None, // No gateway MCP in single-shot mode
+                restart: config.claude.restart_policy(),
+                pool_size: config.claude.pool_size,
             };
             // CLI backend handles its own approval via stdin - no callback needed
             let _ = pending_approvals; // Acknowledge unused parameter for CLI backend
@@ -14,6 +14,7 @@ pub const ACCENT_SKY: Color = Color::Rgb(122, 162, 247);
 
 // Secondary palette
 pub const DIM_INK: Color = Color::Rgb(86, 95, 137);
+pub const MUTED_PAPER: Color = Color::Rgb(154, 165, 206);
 pub const WARNING_AMBER: Color = Color::Rgb(224, 175, 104);
 pub const ERROR_RUBY: Color = Color::Rgb(247, 118, 142);
 pub const SUCCESS_JADE: Color = Color::Rgb(115, 218, 202);
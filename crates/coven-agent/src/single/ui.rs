@@ -14,6 +14,9 @@ use ratatui::{
     Frame,
 };
 
+/// Result lines shown per tool until tool results are expanded
+const MAX_RESULT_LINES: usize = 3;
+
 /// Main UI render function
 pub fn render(f: &mut Frame, app: &App) {
     let chunks = Layout::default()
@@ -59,6 +62,11 @@ fn render_status_bar(f: &mut Frame, app: &App, area: Rect) {
     let backend_text = format!(" {} ", app.backend);
     let working_dir_text = format!(" {} ", truncate(&app.working_dir, 30));
     let status_fmt = format!(" {} ", status_text.0);
+    let budget_text = app
+        .budget
+        .as_ref()
+        .map(|budget| format!("| {} ", budget))
+        .unwrap_or_default();
     let time_suffix = format!("{} ", time);
 
     // Calculate actual content width to determine padding
@@ -70,6 +78,7 @@ fn render_status_bar(f: &mut Frame, app: &App, area: Rect) {
         + working_dir_text.chars().count()
         + 1 // |
         + status_fmt.chars().count()
+        + budget_text.chars().count()
         + time_suffix.chars().count();
 
     let padding_width = (area.width as usize).saturating_sub(content_width);
@@ -87,6 +96,7 @@ fn render_status_bar(f: &mut Frame, app: &App, area: Rect) {
         Span::styled(working_dir_text, Style::default().fg(theme::DIM_INK)),
         Span::styled("|", Style::default().fg(theme::DIM_INK)),
         Span::styled(status_fmt, Style::default().fg(status_text.1)),
+        Span::styled(budget_text, Style::default().fg(theme::DIM_INK)),
         Span::raw(" ".repeat(padding_width)),
         Span::styled(time_suffix, Style::default().fg(theme::DIM_INK)),
     ]);
@@ -150,6 +160,29 @@ fn render_messages(f: &mut Frame, app: &App, area: Rect) {
                     Style::default().fg(theme::DIM_INK),
                 ),
             ]));
+
+            // Tool result (truncated unless expanded)
+            if let Some(output) = &tool.output {
+                let output_lines: Vec<&str> = output.lines().collect();
+                let limit = if app.expand_tool_results {
+                    output_lines.len()
+                } else {
+                    MAX_RESULT_LINES
+                };
+                for output_line in output_lines.iter().take(limit) {
+                    lines.push(Line::from(Span::styled(
+                        format!("  | {}", output_line),
+                        Style::default().fg(theme::DIM_INK),
+                    )));
+                }
+                let remaining = output_lines.len().saturating_sub(limit);
+                if remaining > 0 {
+                    lines.push(Line::from(Span::styled(
+                        format!("  ... +{} lines (ctrl+o to expand)", remaining),
+                        Style::default().fg(theme::DIM_INK),
+                    )));
+                }
+            }
         }
 
         // Message content
@@ -255,6 +288,10 @@ fn render_help_overlay(f: &mut Frame, area: Rect) {
             Span::styled("Ctrl+L       ", Style::default().fg(theme::ACCENT_CORAL)),
             Span::raw("Clear screen"),
         ]),
+        Line::from(vec![
+            Span::styled("Ctrl+O       ", Style::default().fg(theme::ACCENT_CORAL)),
+            Span::raw("Expand/collapse tool results"),
+        ]),
         Line::from(vec![
             Span::styled("?/Ctrl+H     ", Style::default().fg(theme::ACCENT_CORAL)),
             Span::raw("Toggle help"),
@@ -313,10 +350,19 @@ fn render_approval_overlay(f: &mut Frame, app: &App, area: Rect) {
                 .add_modifier(Modifier::BOLD),
         )]),
         Line::from(""),
-        Line::from(Span::styled(
-            truncate(&approval.input_json, 200),
-            Style::default().fg(theme::DIM_INK),
-        )),
+        // Prefer the tool's confirmation message; fall back to the raw input preview
+        match &approval.confirm_message {
+            Some(message) => Line::from(Span::styled(
+                message.as_str(),
+                Style::default()
+                    .fg(theme::SOFT_PAPER)
+                    .add_modifier(Modifier::BOLD),
+            )),
+            None => Line::from(Span::styled(
+                truncate(&approval.input_json, 200),
+                Style::default().fg(theme::DIM_INK),
+            )),
+        },
         Line::from(""),
         Line::from(vec![
             Span::styled(
@@ -353,7 +399,9 @@ fn render_approval_overlay(f: &mut Frame, app: &App, area: Rect) {
 
     let approval_area = centered_rect(60, 40, area);
     f.render_widget(ratatui::widgets::Clear, approval_area);
-    let para = Paragraph::new(lines).block(block);
+    let para = Paragraph::new(lines)
+        .wrap(Wrap { trim: false })
+        .block(block);
     f.render_widget(para, approval_area);
 }
 
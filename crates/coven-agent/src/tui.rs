@@ -6,11 +6,16 @@ use coven_core::backend::{
     AmplifierCliBackend, AmplifierCliConfig, ApprovalCallback, Backend, CodexCliBackend,
     CodexCliConfig, DirectCliBackend, DirectCliConfig, MuxBackend, MuxConfig,
 };
-use coven_core::{Config, Coven, IncomingMessage, OutgoingEvent};
+use coven_core::{Config, Coven, IncomingMessage, OutgoingEvent, RequestOverrides};
+use coven_grpc::{create_channel, ChannelConfig, KeepAliveConfig};
 use coven_proto::coven_control_client::CovenControlClient;
-use coven_proto::{agent_message, server_message, AgentMessage, MessageResponse, RegisterAgent};
+use coven_proto::limits::MessageLimits;
+use coven_proto::{
+    agent_message, server_message, AgentMessage, ForkThread, MessageResponse, RegisterAgent,
+};
 use coven_ssh::{
-    compute_fingerprint, default_agent_key_path, load_or_generate_key, SshAuthCredentials,
+    compute_fingerprint, default_agent_key_path, load_or_generate_key_with_passphrase,
+    PassphraseSource, SshAuthCredentials,
 };
 use crossterm::{
     event::{self, Event, KeyCode, KeyEventKind, KeyModifiers},
@@ -28,17 +33,18 @@ use ratatui::{
     widgets::{Block, Borders, Paragraph},
     Frame, Terminal,
 };
-use std::collections::{HashMap, VecDeque};
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::io;
 use std::pin::Pin;
 use std::sync::Arc;
 use tokio::sync::{mpsc, oneshot, Mutex, Semaphore};
-use tonic::transport::Channel;
 use tonic::Code;
 
+use crate::client::forward_titles;
 use crate::metadata::AgentMetadata;
 use crate::pack_tool::{
-    handle_pack_tool_result, new_pending_pack_tools, PackTool, PendingPackTools,
+    handle_pack_tool_progress, handle_pack_tool_result, new_pending_pack_tools, sync_pack_tools,
+    PendingPackTools,
 };
 
 use coven_connect::event::convert_event_to_response;
@@ -90,6 +96,19 @@ fn pack_tools_message(tool_count: usize, is_subprocess_backend: bool) -> Option<
     }
 }
 
+/// Status message for a pack tools update pushed by the gateway.
+fn pack_tools_update_message(tool_count: usize, removed: &[String]) -> String {
+    if removed.is_empty() {
+        format!("Pack tools updated: {} available", tool_count)
+    } else {
+        format!(
+            "Pack tools updated: {} available, removed {}",
+            tool_count,
+            removed.join(", ")
+        )
+    }
+}
+
 /// Terminal guard for safe cleanup - handles both raw mode and alternate screen
 struct TerminalGuard {
     entered_alt_screen: bool,
@@ -230,6 +249,8 @@ struct App {
     status: String,
     last_message: String,
     pack_tools_count: usize,
+    /// Spend against the backend's budget, when it has one
+    budget: Option<String>,
     blocks: VecDeque<ContentBlock>,
     /// Scroll offset in visual lines (not blocks)
     scroll_offset: usize,
@@ -263,6 +284,7 @@ impl App {
             status: "Initializing...".to_string(),
             last_message: "Ready".to_string(),
             pack_tools_count: 0,
+            budget: None,
             blocks: VecDeque::with_capacity(MAX_LOG_LINES),
             scroll_offset: 0,
             visual_lines: Vec::new(),
@@ -433,6 +455,8 @@ fn wrap_text(text: &str, width: usize) -> Vec<String> {
 enum UiEvent {
     Block(BlockKind, String),
     Status(String),
+    /// Spend against the backend's budget, for the header
+    Budget(String),
     LastMessage(String),
     InstanceId(String),
     PackToolsCount(usize),
@@ -445,7 +469,20 @@ pub async fn run(
     backend_type: &str,
     working_dir: &std::path::Path,
     capabilities: Vec<String>,
+    key_passphrase: Option<&PassphraseSource>,
+    keep_alive: Option<KeepAliveConfig>,
 ) -> Result<()> {
+    // A terminal prompt can't run once the TUI owns the screen, so ask now
+    let key_passphrase = match key_passphrase {
+        Some(source @ PassphraseSource::Prompt(_)) => {
+            let key_path = default_agent_key_path().ok_or_else(|| {
+                anyhow::anyhow!("could not determine config directory for SSH key")
+            })?;
+            Some(PassphraseSource::Literal(source.passphrase(&key_path)?))
+        }
+        other => other.cloned(),
+    };
+
     // Setup terminal - guard created immediately after raw mode to ensure cleanup on panic
     enable_raw_mode()?;
     let mut _guard = TerminalGuard::new();
@@ -476,7 +513,17 @@ pub async fn run(
     let caps = capabilities;
 
     tokio::spawn(async move {
-        if let Err(e) = run_agent_task(&agent_tx, &server, &id, &backend_str, &work_dir, caps).await
+        if let Err(e) = run_agent_task(
+            &agent_tx,
+            &server,
+            &id,
+            &backend_str,
+            &work_dir,
+            caps,
+            key_passphrase.as_ref(),
+            keep_alive,
+        )
+        .await
         {
             let _ = agent_tx
                 .send(UiEvent::Block(
@@ -507,6 +554,7 @@ pub async fn run(
             match event {
                 UiEvent::Block(kind, content) => app.add_block(kind, content),
                 UiEvent::Status(status) => app.set_status(status),
+                UiEvent::Budget(budget) => app.budget = Some(budget),
                 UiEvent::LastMessage(msg) => app.set_last_message(msg),
                 UiEvent::InstanceId(id) => app.instance_id = id,
                 UiEvent::PackToolsCount(count) => app.pack_tools_count = count,
@@ -593,6 +641,8 @@ async fn run_agent_task(
     backend_type: &str,
     working_dir: &std::path::Path,
     capabilities: Vec<String>,
+    key_passphrase: Option<&PassphraseSource>,
+    keep_alive: Option<KeepAliveConfig>,
 ) -> Result<()> {
     tx.send(UiEvent::Block(
         BlockKind::System,
@@ -625,25 +675,7 @@ async fn run_agent_task(
                 "Using MuxBackend (direct Anthropic API)".to_string(),
             ))
             .await?;
-            let mux_settings = config.mux.clone();
-            let mux_config = MuxConfig {
-                model: std::env::var("ANTHROPIC_MODEL").unwrap_or(mux_settings.model),
-                max_tokens: std::env::var("ANTHROPIC_MAX_TOKENS")
-                    .ok()
-                    .and_then(|s| s.parse().ok())
-                    .unwrap_or(mux_settings.max_tokens),
-                working_dir: working_dir.to_path_buf(),
-                global_system_prompt_path: mux_settings
-                    .global_system_prompt_path
-                    .or_else(|| dirs::home_dir().map(|h| h.join(".mux/system.md"))),
-                local_prompt_files: mux_settings.local_prompt_files,
-                global_soul_path: mux_settings.global_soul_path,
-                agent_soul_path: mux_settings.agent_soul_path,
-                soul_files: mux_settings.soul_files,
-                mcp_servers: vec![],
-                skip_default_tools: false,
-                gateway_mcp: None, // Set after gateway connection
-            };
+            let mux_config = MuxConfig::from_settings(&config.mux, working_dir);
 
             // Create approval callback that waits for gateway response
             // Timeout after 5 minutes to prevent infinite hangs
@@ -702,6 +734,8 @@ async fn run_agent_task(
                 working_dir: working_dir.to_path_buf(),
                 timeout_secs: config.claude.timeout_secs,
                 mcp_endpoint: None, // Will be set after receiving Welcome with token
+                restart: config.claude.restart_policy(),
+                pool_size: config.claude.pool_size,
             };
             let backend = Arc::new(DirectCliBackend::new(cli_config));
             cli_backend = Some(backend.clone());
@@ -757,7 +791,7 @@ async fn run_agent_task(
     ))
     .await?;
 
-    let private_key = load_or_generate_key(&key_path)?;
+    let private_key = load_or_generate_key_with_passphrase(&key_path, key_passphrase)?;
     let fingerprint = compute_fingerprint(private_key.public_key())?;
     tx.send(UiEvent::Block(
         BlockKind::System,
@@ -805,6 +839,10 @@ async fn run_agent_task(
     let mut needs_reconnect = false;
     tx.send(UiEvent::Status("Connecting...".to_string()))
         .await?;
+    // Pack tools registered with the mux backend, kept in sync with the gateway
+    let mut pack_tool_names = HashSet::new();
+    // Size limits for the gRPC client, also applied to the responses we send
+    let limits = MessageLimits::default();
     let (msg_tx, mut inbound) = loop {
         let current_id = if suffix == 0 {
             agent_id.to_string()
@@ -820,13 +858,16 @@ async fn run_agent_task(
             ))
             .await?;
         }
-        let channel = Channel::from_shared(server_addr.to_string())?
-            .connect()
-            .await?;
+        let channel_config = ChannelConfig {
+            keep_alive: keep_alive.clone(),
+            message_limits: limits,
+            ..ChannelConfig::new(server_addr)
+        };
+        let channel = create_channel(&channel_config).await?;
         if !needs_reconnect {
             tx.send(UiEvent::Block(
                 BlockKind::System,
-                "TCP connection established".to_string(),
+                "Connection established".to_string(),
             ))
             .await?;
         }
@@ -857,7 +898,9 @@ async fn run_agent_task(
             Ok(req)
         };
 
-        let mut client = CovenControlClient::with_interceptor(channel, ssh_auth_interceptor);
+        let mut client = CovenControlClient::with_interceptor(channel, ssh_auth_interceptor)
+            .max_decoding_message_size(limits.max_decoding)
+            .max_encoding_message_size(limits.max_encoding);
 
         // Create bidirectional stream
         let (msg_tx, rx) = mpsc::channel::<AgentMessage>(100);
@@ -922,7 +965,11 @@ async fn run_agent_task(
                     name: current_id.clone(),
                     capabilities: metadata.capabilities.clone(),
                     metadata: Some(metadata.clone().into()),
-                    protocol_features: vec!["token_usage".to_string(), "tool_states".to_string()],
+                    protocol_features: vec![
+                        "token_usage".to_string(),
+                        "tool_states".to_string(),
+                        ForkThread::PROTOCOL_FEATURE.to_string(),
+                    ],
                 })),
             })
             .await?;
@@ -1044,18 +1091,20 @@ async fn run_agent_task(
                         }
                         if let Some(ref mux) = mux_backend {
                             for tool_def in &welcome.available_tools {
-                                let pack_tool = PackTool::new(
-                                    tool_def,
-                                    msg_tx.clone(),
-                                    pending_pack_tools.clone(),
-                                );
                                 tx.send(UiEvent::Block(
                                     BlockKind::System,
                                     format!("  Pack tool: {}", tool_def.name),
                                 ))
                                 .await?;
-                                mux.register_tool(pack_tool).await;
                             }
+                            sync_pack_tools(
+                                mux,
+                                &mut pack_tool_names,
+                                &welcome.available_tools,
+                                &msg_tx,
+                                &pending_pack_tools,
+                            )
+                            .await;
                         } else if is_subprocess_backend {
                             for tool_def in &welcome.available_tools {
                                 tx.send(UiEvent::Block(
@@ -1153,12 +1202,28 @@ async fn run_agent_task(
     // Wrap coven in Arc for sharing with spawned tasks
     let coven = Arc::new(coven);
 
+    // Show what's been spent today before the first message. No thread has
+    // one yet, so only the day totals count anything.
+    match coven.budget_status("").await {
+        Ok(Some(status)) => tx.send(UiEvent::Budget(status.summary())).await?,
+        Ok(None) => {}
+        Err(e) => {
+            tx.send(UiEvent::Block(
+                BlockKind::Error,
+                format!("Couldn't read budget spend: {}", e),
+            ))
+            .await?
+        }
+    }
+
     // Backpressure: limit concurrent message processing tasks
     let message_semaphore = Arc::new(Semaphore::new(MAX_CONCURRENT_MESSAGES));
 
     // Per-thread locks: ensure messages to the same thread are processed sequentially
     let thread_locks: ThreadLocks = Arc::new(Mutex::new(HashMap::new()));
 
+    forward_titles(&coven, msg_tx.clone());
+
     // Process server messages
     // Message processing is spawned in separate tasks so this loop
     // can continue receiving PackToolResult and ToolApproval messages that
@@ -1197,10 +1262,23 @@ async fn run_agent_task(
 
                 let incoming = IncomingMessage {
                     thread_id: send_msg.thread_id.clone(),
-                    sender: send_msg.sender.clone(),
+                    // Bridges identify the chat user and platform behind the message
+                    sender: send_msg
+                        .sender_platform_id
+                        .clone()
+                        .unwrap_or_else(|| send_msg.sender.clone()),
+                    sender_display: send_msg.sender_display.clone(),
                     content: send_msg.content.clone(),
-                    frontend: "grpc".to_string(),
+                    frontend: send_msg
+                        .sender_platform
+                        .clone()
+                        .unwrap_or_else(|| "grpc".to_string()),
                     attachments: vec![],
+                    overrides: RequestOverrides {
+                        model: send_msg.model.clone(),
+                        max_tokens: send_msg.max_tokens,
+                    },
+                    reply_to_message_id: send_msg.reply_to_message_id.clone(),
                 };
 
                 // Spawn message processing in separate task so this loop can
@@ -1233,6 +1311,7 @@ async fn run_agent_task(
                         request_id,
                         msg_tx_clone,
                         ui_tx.clone(),
+                        limits,
                     )
                     .await;
 
@@ -1332,6 +1411,64 @@ async fn run_agent_task(
                     .await?;
                 }
             }
+            Some(server_message::Payload::PackToolProgress(progress)) => {
+                // Shown through the running tool's state, not as its own block
+                handle_pack_tool_progress(&pending_pack_tools, progress).await;
+            }
+            Some(server_message::Payload::AvailableTools(update)) => {
+                tx.send(UiEvent::PackToolsCount(update.tools.len())).await?;
+                // Subprocess backends list tools from the gateway's MCP server themselves
+                if let Some(ref mux) = mux_backend {
+                    let removed = sync_pack_tools(
+                        mux,
+                        &mut pack_tool_names,
+                        &update.tools,
+                        &msg_tx,
+                        &pending_pack_tools,
+                    )
+                    .await;
+                    tx.send(UiEvent::Block(
+                        BlockKind::System,
+                        pack_tools_update_message(update.tools.len(), &removed),
+                    ))
+                    .await?;
+                }
+            }
+            Some(server_message::Payload::SetLogLevel(change)) => {
+                let text = match coven_log::set_level(&change.filter) {
+                    Ok(()) => format!("Log filter set to {}", change.filter),
+                    Err(e) => format!("Kept the current log filter: {}", e),
+                };
+                tx.send(UiEvent::Block(BlockKind::System, text)).await?;
+            }
+            Some(server_message::Payload::ForkThread(fork)) => {
+                // Forked before reading on, as the next message may be for the fork
+                let (kind, text) = match coven
+                    .fork_thread_with_history(
+                        &fork.thread_id,
+                        &fork.new_thread_id,
+                        &fork
+                            .history
+                            .iter()
+                            .map(|m| (m.role.clone(), m.content.clone()))
+                            .collect::<Vec<_>>(),
+                    )
+                    .await
+                {
+                    Ok(_) => (
+                        BlockKind::System,
+                        format!(
+                            "Forked thread {} into {}",
+                            fork.thread_id, fork.new_thread_id
+                        ),
+                    ),
+                    Err(e) => (
+                        BlockKind::Error,
+                        format!("Failed to fork thread {}: {}", fork.thread_id, e),
+                    ),
+                };
+                tx.send(UiEvent::Block(kind, text)).await?;
+            }
             None => {}
         }
     }
@@ -1361,6 +1498,7 @@ async fn process_message_tui(
     request_id: String,
     msg_tx: mpsc::Sender<AgentMessage>,
     ui_tx: mpsc::Sender<UiEvent>,
+    limits: MessageLimits,
 ) {
     match coven.handle(incoming).await {
         Ok(mut stream) => {
@@ -1439,6 +1577,12 @@ async fn process_message_tui(
                             .send(UiEvent::Block(BlockKind::Error, e.clone()))
                             .await;
                     }
+                    OutgoingEvent::BudgetExceeded(exceeded) => {
+                        let _ = ui_tx.send(UiEvent::Budget(exceeded.status.summary())).await;
+                        let _ = ui_tx
+                            .send(UiEvent::Block(BlockKind::Error, exceeded.to_string()))
+                            .await;
+                    }
                     OutgoingEvent::File { filename, .. } => {
                         let _ = ui_tx
                             .send(UiEvent::Block(
@@ -1507,9 +1651,20 @@ async fn process_message_tui(
                             ))
                             .await;
                     }
+                    OutgoingEvent::Budget(report) => {
+                        // Spend goes in the header; only alerts reach the log
+                        let _ = ui_tx.send(UiEvent::Budget(report.status.summary())).await;
+                        for alert in &report.alerts {
+                            let _ = ui_tx
+                                .send(UiEvent::Block(BlockKind::Error, alert.to_string()))
+                                .await;
+                        }
+                    }
                 }
 
-                let response = convert_event_to_response(&request_id, event).await;
+                // An oversize event would reset the stream; send an error in its place
+                let response =
+                    limits.fit_agent_message(convert_event_to_response(&request_id, event).await);
                 if msg_tx.send(response).await.is_err() {
                     break;
                 }
@@ -1652,6 +1807,13 @@ fn draw_header(f: &mut Frame, app: &App, area: ratatui::layout::Rect) {
                     Color::DarkGray
                 }),
             ),
+            Span::styled(
+                app.budget
+                    .as_ref()
+                    .map(|budget| format!("  {}", budget))
+                    .unwrap_or_default(),
+                Style::default().fg(Color::White),
+            ),
             Span::styled("  Dir: ", Style::default().fg(Color::DarkGray)),
             Span::styled(working_dir_short, Style::default().fg(Color::White)),
         ]),
@@ -1746,6 +1908,18 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_pack_tools_update_message() {
+        assert_eq!(
+            pack_tools_update_message(3, &[]),
+            "Pack tools updated: 3 available"
+        );
+        assert_eq!(
+            pack_tools_update_message(1, &["notes".to_string(), "todo".to_string()]),
+            "Pack tools updated: 1 available, removed notes, todo"
+        );
+    }
+
     #[test]
     fn test_pack_tools_message_mux_says_pending() {
         let msg = pack_tools_message(1, false).unwrap();
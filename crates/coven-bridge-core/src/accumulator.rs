@@ -0,0 +1,153 @@
+// ABOUTME: Accumulates streamed text chunks into a response and throttles intermediate flushes.
+// ABOUTME: Lets bridges coalesce many small chunks into occasional message edits.
+
+use std::time::{Duration, Instant};
+
+/// Collects streamed text chunks for a single agent response.
+///
+/// Bridges push every text chunk as it arrives. If the bridge edits a
+/// message while the response streams, `push` returns the text to show
+/// at most once per `min_interval`, so a burst of tiny chunks becomes a
+/// single edit. `finish` produces the final text, preferring the
+/// gateway's full response over what was accumulated.
+#[derive(Debug, Clone)]
+pub struct ResponseAccumulator {
+    text: String,
+    min_interval: Duration,
+    min_chars: usize,
+    last_flush: Option<Instant>,
+    flushed_len: usize,
+}
+
+impl ResponseAccumulator {
+    /// Create an accumulator that flushes at most once per `min_interval`.
+    pub fn new(min_interval: Duration) -> Self {
+        Self {
+            text: String::new(),
+            min_interval,
+            min_chars: 1,
+            last_flush: None,
+            flushed_len: 0,
+        }
+    }
+
+    /// Create an accumulator that never flushes intermediate text; the
+    /// response is only delivered by `finish`.
+    pub fn buffered() -> Self {
+        Self::new(Duration::MAX)
+    }
+
+    /// Create an accumulator from a `stream_interval_ms` config value.
+    /// Unset or zero keeps the response buffered until `finish`.
+    pub fn from_interval_ms(interval_ms: Option<u64>) -> Self {
+        match interval_ms {
+            Some(ms) if ms > 0 => Self::new(Duration::from_millis(ms)),
+            _ => Self::buffered(),
+        }
+    }
+
+    /// Require at least `min_chars` new bytes before a flush is emitted.
+    pub fn with_min_chars(mut self, min_chars: usize) -> Self {
+        self.min_chars = min_chars.max(1);
+        self
+    }
+
+    /// Append a chunk received at `now`. Returns the full text so far if an
+    /// intermediate flush is due.
+    pub fn push(&mut self, chunk: &str, now: Instant) -> Option<&str> {
+        self.text.push_str(chunk);
+        if self.flush_due(now) {
+            self.last_flush = Some(now);
+            self.flushed_len = self.text.len();
+            Some(&self.text)
+        } else {
+            None
+        }
+    }
+
+    fn flush_due(&self, now: Instant) -> bool {
+        if self.text.len() < self.flushed_len + self.min_chars {
+            return false;
+        }
+        match self.last_flush {
+            // The first flush is due as soon as there is text, unless
+            // intermediate flushes are disabled altogether
+            None => self.min_interval != Duration::MAX,
+            Some(last) => now.saturating_duration_since(last) >= self.min_interval,
+        }
+    }
+
+    /// Whether text has arrived since the last flush.
+    pub fn has_pending(&self) -> bool {
+        self.text.len() > self.flushed_len
+    }
+
+    /// Whether any text has been accumulated.
+    pub fn is_empty(&self) -> bool {
+        self.text.is_empty()
+    }
+
+    /// The text accumulated so far.
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Consume the accumulator and return the final response text,
+    /// preferring a non-empty `full_response` from the gateway.
+    pub fn finish(self, full_response: Option<String>) -> String {
+        full_response.filter(|s| !s.is_empty()).unwrap_or(self.text)
+    }
+}
+
+impl Default for ResponseAccumulator {
+    fn default() -> Self {
+        Self::buffered()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_buffered_never_flushes() {
+        let mut acc = ResponseAccumulator::buffered();
+        let now = Instant::now();
+        assert!(acc.push("hello ", now).is_none());
+        assert!(acc.push("world", now + Duration::from_secs(3600)).is_none());
+        assert_eq!(acc.text(), "hello world");
+        assert!(acc.has_pending());
+    }
+
+    #[test]
+    fn test_finish_prefers_full_response() {
+        let mut acc = ResponseAccumulator::buffered();
+        acc.push("partial", Instant::now());
+        assert_eq!(acc.clone().finish(Some("complete".into())), "complete");
+        assert_eq!(acc.clone().finish(Some(String::new())), "partial");
+        assert_eq!(acc.finish(None), "partial");
+    }
+
+    #[test]
+    fn test_from_interval_ms() {
+        let now = Instant::now();
+        assert!(ResponseAccumulator::from_interval_ms(None)
+            .push("a", now)
+            .is_none());
+        assert!(ResponseAccumulator::from_interval_ms(Some(0))
+            .push("a", now)
+            .is_none());
+        assert_eq!(
+            ResponseAccumulator::from_interval_ms(Some(1000)).push("a", now),
+            Some("a")
+        );
+    }
+
+    #[test]
+    fn test_empty_accumulator() {
+        let acc = ResponseAccumulator::default();
+        assert!(acc.is_empty());
+        assert!(!acc.has_pending());
+        assert_eq!(acc.finish(None), "");
+    }
+}
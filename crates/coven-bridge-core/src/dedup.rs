@@ -0,0 +1,144 @@
+// ABOUTME: Drops chat events the platform delivers more than once.
+// ABOUTME: Remembers recently seen message ids within a time window, capped in size.
+
+use std::collections::{HashSet, VecDeque};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// How long a message id is remembered. Platforms redeliver within seconds
+/// to minutes (Slack retries unacknowledged Socket Mode events), so this
+/// comfortably covers a redelivery without holding ids forever.
+pub const DEDUP_WINDOW: Duration = Duration::from_secs(600);
+
+/// Most message ids remembered at once; the oldest are forgotten first.
+pub const DEDUP_CAPACITY: usize = 10_000;
+
+/// Recently seen platform message ids, so a redelivered event isn't sent
+/// to the agent twice.
+#[derive(Debug)]
+pub struct MessageDeduplicator {
+    window: Duration,
+    capacity: usize,
+    seen: Mutex<SeenIds>,
+}
+
+#[derive(Debug, Default)]
+struct SeenIds {
+    ids: HashSet<String>,
+    /// Ids in the order they were first seen
+    order: VecDeque<(String, Instant)>,
+}
+
+impl MessageDeduplicator {
+    /// Remember ids for `window`, keeping at most `capacity` of them.
+    pub fn new(window: Duration, capacity: usize) -> Self {
+        Self {
+            window,
+            capacity: capacity.max(1),
+            seen: Mutex::new(SeenIds::default()),
+        }
+    }
+
+    /// Record `id` and report whether it was already seen.
+    pub fn is_duplicate(&self, id: &str) -> bool {
+        self.is_duplicate_at(id, Instant::now())
+    }
+
+    /// Record `id` as of `now` and report whether it was already seen
+    /// within the window.
+    pub fn is_duplicate_at(&self, id: &str, now: Instant) -> bool {
+        let mut seen = self.seen.lock().unwrap_or_else(|e| e.into_inner());
+
+        // Forget ids that have aged out of the window
+        while let Some((oldest, seen_at)) = seen.order.front() {
+            if now.saturating_duration_since(*seen_at) < self.window {
+                break;
+            }
+            let oldest = oldest.clone();
+            seen.ids.remove(&oldest);
+            seen.order.pop_front();
+        }
+
+        if seen.ids.contains(id) {
+            return true;
+        }
+
+        if seen.order.len() >= self.capacity {
+            if let Some((oldest, _)) = seen.order.pop_front() {
+                seen.ids.remove(&oldest);
+            }
+        }
+        seen.ids.insert(id.to_string());
+        seen.order.push_back((id.to_string(), now));
+        false
+    }
+
+    /// Number of ids currently remembered.
+    pub fn len(&self) -> usize {
+        self.seen
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .ids
+            .len()
+    }
+
+    /// Whether no ids are remembered.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl Default for MessageDeduplicator {
+    fn default() -> Self {
+        Self::new(DEDUP_WINDOW, DEDUP_CAPACITY)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_second_delivery_is_duplicate() {
+        let dedup = MessageDeduplicator::default();
+        assert!(!dedup.is_duplicate("1700000000.000100"));
+        assert!(dedup.is_duplicate("1700000000.000100"));
+        assert!(!dedup.is_duplicate("1700000000.000200"));
+        assert_eq!(dedup.len(), 2);
+    }
+
+    #[test]
+    fn test_ids_expire_after_window() {
+        let dedup = MessageDeduplicator::new(Duration::from_secs(60), 100);
+        let now = Instant::now();
+        assert!(!dedup.is_duplicate_at("evt", now));
+        assert!(dedup.is_duplicate_at("evt", now + Duration::from_secs(59)));
+
+        // A redelivery after the window is treated as new
+        assert!(!dedup.is_duplicate_at("evt", now + Duration::from_secs(60)));
+    }
+
+    #[test]
+    fn test_expired_ids_are_dropped() {
+        let dedup = MessageDeduplicator::new(Duration::from_secs(60), 100);
+        let now = Instant::now();
+        dedup.is_duplicate_at("a", now);
+        dedup.is_duplicate_at("b", now + Duration::from_secs(30));
+
+        dedup.is_duplicate_at("c", now + Duration::from_secs(61));
+        assert_eq!(dedup.len(), 2);
+    }
+
+    #[test]
+    fn test_capacity_forgets_oldest() {
+        let dedup = MessageDeduplicator::new(DEDUP_WINDOW, 2);
+        let now = Instant::now();
+        dedup.is_duplicate_at("a", now);
+        dedup.is_duplicate_at("b", now);
+        dedup.is_duplicate_at("c", now);
+
+        assert_eq!(dedup.len(), 2);
+        assert!(dedup.is_duplicate_at("c", now));
+        assert!(!dedup.is_duplicate_at("a", now));
+    }
+}
@@ -0,0 +1,120 @@
+// ABOUTME: Error types for coven-bridge-core.
+// ABOUTME: Defines BridgeCoreError covering gateway, connection, config, and storage failures.
+
+use thiserror::Error;
+
+/// Error types shared by the bridge building blocks.
+#[derive(Error, Debug)]
+pub enum BridgeCoreError {
+    /// Invalid configuration (e.g. a malformed gateway URL).
+    #[error("Configuration error: {0}")]
+    Config(String),
+
+    /// gRPC status error from gateway communication.
+    #[error("Gateway error: {0}")]
+    Gateway(Box<tonic::Status>),
+
+    /// gRPC connection/transport error.
+    #[error("Connection error: {0}")]
+    Connection(Box<tonic::transport::Error>),
+
+    /// Couldn't open a connection to the gateway.
+    #[error("Connection error: {0}")]
+    Connect(String),
+
+    /// Binding store failure.
+    #[error("Binding store error: {0}")]
+    Store(String),
+
+    /// IO error for file-backed stores.
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+
+    /// JSON (de)serialization error for file-backed stores.
+    #[error("JSON error: {0}")]
+    Json(#[from] serde_json::Error),
+}
+
+impl BridgeCoreError {
+    /// Whether the failed operation is worth retrying after reconnecting.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            BridgeCoreError::Connection(_) | BridgeCoreError::Connect(_) => true,
+            BridgeCoreError::Gateway(status) => matches!(
+                status.code(),
+                tonic::Code::Unavailable | tonic::Code::DeadlineExceeded
+            ),
+            _ => false,
+        }
+    }
+}
+
+impl From<crate::overrides::InvalidOverride> for BridgeCoreError {
+    fn from(e: crate::overrides::InvalidOverride) -> Self {
+        BridgeCoreError::Config(e.to_string())
+    }
+}
+
+impl From<tonic::Status> for BridgeCoreError {
+    fn from(e: tonic::Status) -> Self {
+        BridgeCoreError::Gateway(Box::new(e))
+    }
+}
+
+impl From<tonic::transport::Error> for BridgeCoreError {
+    fn from(e: tonic::transport::Error) -> Self {
+        BridgeCoreError::Connection(Box::new(e))
+    }
+}
+
+impl From<coven_grpc::GrpcClientError> for BridgeCoreError {
+    fn from(e: coven_grpc::GrpcClientError) -> Self {
+        match e {
+            coven_grpc::GrpcClientError::InvalidAddress(_)
+            | coven_grpc::GrpcClientError::InvalidConfig(_) => {
+                BridgeCoreError::Config(e.to_string())
+            }
+            _ => BridgeCoreError::Connect(e.to_string()),
+        }
+    }
+}
+
+impl From<sqlx::Error> for BridgeCoreError {
+    fn from(e: sqlx::Error) -> Self {
+        BridgeCoreError::Store(e.to_string())
+    }
+}
+
+/// Result type alias using BridgeCoreError.
+pub type Result<T> = std::result::Result<T, BridgeCoreError>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_retryable_errors() {
+        assert!(BridgeCoreError::from(tonic::Status::unavailable("down")).is_retryable());
+        assert!(BridgeCoreError::from(tonic::Status::deadline_exceeded("slow")).is_retryable());
+        assert!(!BridgeCoreError::from(tonic::Status::not_found("agent not found")).is_retryable());
+        assert!(!BridgeCoreError::from(tonic::Status::permission_denied("no")).is_retryable());
+        assert!(!BridgeCoreError::Config("bad".into()).is_retryable());
+        assert!(!BridgeCoreError::Store("bad".into()).is_retryable());
+
+        // Failing to connect is worth another try; a bad address isn't
+        use coven_grpc::GrpcClientError;
+        assert!(
+            BridgeCoreError::from(GrpcClientError::ConnectionFailed("refused".into()))
+                .is_retryable()
+        );
+        assert!(
+            !BridgeCoreError::from(GrpcClientError::InvalidAddress("bad".into())).is_retryable()
+        );
+    }
+
+    #[test]
+    fn test_gateway_error_display_keeps_status_message() {
+        let err = BridgeCoreError::from(tonic::Status::not_found("agent not found"));
+        assert!(err.to_string().contains("agent not found"));
+    }
+}
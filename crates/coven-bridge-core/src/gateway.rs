@@ -0,0 +1,506 @@
+// ABOUTME: gRPC client for coven-gateway plus a reconnecting, retrying session wrapper.
+// ABOUTME: GatewayClient is a thin ClientService wrapper; BridgeGateway adds reconnect and send-retry.
+
+use crate::error::{BridgeCoreError, Result};
+use crate::identity::{ChannelIdentity, SenderIdentity};
+use crate::overrides::RequestOverrides;
+use coven_grpc::{create_channel, ChannelConfig, KeepAliveConfig};
+use coven_proto::client::ClientServiceClient;
+use coven_proto::{
+    AgentInfo, AgentInitiatedEvent, ApproveToolRequest, ClientSendMessageRequest,
+    ClientSendMessageResponse, ClientStreamEvent, ListAgentsRequest, StreamAgentInitiatedRequest,
+    StreamEventsRequest,
+};
+use std::time::Duration;
+use tonic::transport::Channel;
+use tonic::{Request, Status};
+use tracing::{debug, error, info, warn};
+
+/// Send status returned when the agent is offline and the gateway queued
+/// the message for delivery once it reconnects.
+pub const SEND_STATUS_QUEUED: &str = "queued";
+
+/// Notice shown to the chat when a message was queued for an offline agent.
+pub const QUEUED_NOTICE: &str =
+    "The agent is offline. Your message will be delivered when it reconnects.";
+
+/// Send status returned when the gateway's content policy blocked the
+/// message; its `detail` is the reply for the sender.
+pub const SEND_STATUS_BLOCKED: &str = ClientSendMessageResponse::STATUS_BLOCKED;
+
+/// Notice shown to the chat when a message was blocked and the gateway gave
+/// no policy reply.
+pub const BLOCKED_NOTICE: &str = ClientSendMessageResponse::BLOCKED_NOTICE;
+
+/// The notice to post instead of the agent's reply when the gateway didn't
+/// pass the message on (queued or blocked), or None when the agent will answer.
+pub fn send_notice(response: &ClientSendMessageResponse) -> Option<&str> {
+    match response.status.as_str() {
+        SEND_STATUS_QUEUED => Some(QUEUED_NOTICE),
+        _ => response.blocked_reason(),
+    }
+}
+
+/// gRPC client for communicating with coven-gateway's ClientService.
+pub struct GatewayClient {
+    client: ClientServiceClient<
+        tonic::service::interceptor::InterceptedService<Channel, AuthInterceptor>,
+    >,
+}
+
+/// Interceptor that adds Bearer token authentication to outgoing requests,
+/// and with the `otlp` feature the current trace context.
+#[derive(Clone)]
+struct AuthInterceptor {
+    token: Option<String>,
+}
+
+impl tonic::service::Interceptor for AuthInterceptor {
+    fn call(&mut self, mut req: Request<()>) -> std::result::Result<Request<()>, Status> {
+        if let Some(ref token) = self.token {
+            let auth_value = format!("Bearer {}", token)
+                .parse()
+                .map_err(|_| Status::internal("invalid token format"))?;
+            req.metadata_mut().insert("authorization", auth_value);
+        }
+        #[cfg(feature = "otlp")]
+        coven_log::inject_context(req.metadata_mut());
+        Ok(req)
+    }
+}
+
+impl GatewayClient {
+    /// Connect to the gateway at the given URL with optional authentication token.
+    pub async fn connect(url: &str, token: Option<String>) -> Result<Self> {
+        Self::connect_with_keep_alive(url, token, Some(&KeepAliveConfig::default())).await
+    }
+
+    /// Connect with the given HTTP/2 keep-alive settings, or none.
+    pub async fn connect_with_keep_alive(
+        url: &str,
+        token: Option<String>,
+        keep_alive: Option<&KeepAliveConfig>,
+    ) -> Result<Self> {
+        info!(url = %url, "Connecting to gateway");
+
+        let channel_config = ChannelConfig {
+            keep_alive: keep_alive.cloned(),
+            ..ChannelConfig::new(url)
+        };
+        let channel = create_channel(&channel_config).await?;
+
+        let interceptor = AuthInterceptor { token };
+        let limits = channel_config.message_limits;
+        let client = ClientServiceClient::with_interceptor(channel, interceptor)
+            .max_decoding_message_size(limits.max_decoding)
+            .max_encoding_message_size(limits.max_encoding);
+
+        Ok(Self { client })
+    }
+
+    /// List all available agents from the gateway.
+    pub async fn list_agents(&mut self) -> Result<Vec<AgentInfo>> {
+        debug!("Listing agents");
+        let response = self
+            .client
+            .list_agents(ListAgentsRequest { workspace: None })
+            .await?;
+        Ok(response.into_inner().agents)
+    }
+
+    /// Send a message to the gateway for a given conversation, tagged with
+    /// the chat user who wrote it when known, the channel it came from, and
+    /// the chat's overrides. The idempotency key doubles as the gateway's
+    /// request ID.
+    #[tracing::instrument(
+        name = "bridge.send_message",
+        skip_all,
+        fields(conversation_key = %conversation_key, request_id = %idempotency_key)
+    )]
+    pub async fn send_message(
+        &mut self,
+        conversation_key: String,
+        content: String,
+        idempotency_key: String,
+        sender: Option<&SenderIdentity>,
+        channel: Option<&ChannelIdentity>,
+        reply_to: Option<&str>,
+        overrides: &RequestOverrides,
+    ) -> Result<ClientSendMessageResponse> {
+        info!(
+            conversation_key = %conversation_key,
+            content_len = content.len(),
+            idempotency_key = %idempotency_key,
+            "Sending message to gateway"
+        );
+
+        let request = ClientSendMessageRequest {
+            conversation_key: conversation_key.clone(),
+            content,
+            attachments: vec![],
+            idempotency_key,
+            sender_display: sender.map(|s| s.display_name.clone()),
+            sender_platform_id: sender.map(|s| s.platform_id.clone()),
+            sender_platform: sender.map(|s| s.platform.clone()),
+            model: overrides.model.clone(),
+            max_tokens: overrides.max_tokens,
+            reply_to_message_id: reply_to.map(str::to_string),
+            channel_frontend: channel.map(|c| c.platform.clone()),
+            channel_id: channel.map(|c| c.channel_id.clone()),
+            channel_name: channel.and_then(|c| c.display_name.clone()),
+        };
+
+        let response = self.client.send_message(request).await;
+        match &response {
+            Ok(r) => {
+                let inner = r.get_ref();
+                info!(
+                    status = %inner.status,
+                    message_id = %inner.message_id,
+                    "Gateway accepted message"
+                );
+            }
+            Err(e) => {
+                error!(
+                    error = %e,
+                    conversation_key = %conversation_key,
+                    "Gateway rejected message"
+                );
+            }
+        }
+        Ok(response?.into_inner())
+    }
+
+    /// Stream events from the gateway for a given conversation.
+    pub async fn stream_events(
+        &mut self,
+        conversation_key: String,
+    ) -> Result<impl futures::Stream<Item = std::result::Result<ClientStreamEvent, Status>>> {
+        debug!(conversation_key = %conversation_key, "Starting event stream");
+
+        let request = StreamEventsRequest {
+            conversation_key,
+            since_event_id: None,
+            stream: None,
+        };
+
+        let response = self.client.stream_events(request).await?;
+        Ok(response.into_inner())
+    }
+
+    /// Stream messages agents send on their own initiative, from every agent.
+    pub async fn stream_agent_initiated(
+        &mut self,
+    ) -> Result<impl futures::Stream<Item = std::result::Result<AgentInitiatedEvent, Status>>> {
+        debug!("Starting agent-initiated stream");
+
+        let request = StreamAgentInitiatedRequest { agent_ids: vec![] };
+
+        let response = self.client.stream_agent_initiated(request).await?;
+        Ok(response.into_inner())
+    }
+
+    /// Respond to a tool approval request.
+    pub async fn approve_tool(
+        &mut self,
+        agent_id: String,
+        tool_id: String,
+        approved: bool,
+        approve_all: bool,
+    ) -> Result<()> {
+        debug!(agent_id = %agent_id, tool_id = %tool_id, approved = %approved, "Responding to tool approval");
+
+        let request = ApproveToolRequest {
+            agent_id,
+            tool_id,
+            approved,
+            approve_all,
+        };
+
+        let response = self.client.approve_tool(request).await?;
+        let inner = response.into_inner();
+        if !inner.success {
+            let err_msg = inner.error.unwrap_or_else(|| "unknown error".to_string());
+            return Err(BridgeCoreError::Config(format!(
+                "tool approval failed: {}",
+                err_msg
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// How a BridgeGateway retries operations that fail with a transient error.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Total attempts per operation, including the first one.
+    pub max_attempts: u32,
+    /// Delay before the first retry; doubles on each subsequent retry.
+    pub initial_backoff: Duration,
+    /// Upper bound on the delay between retries.
+    pub max_backoff: Duration,
+}
+
+impl RetryPolicy {
+    /// A policy that never retries.
+    pub fn none() -> Self {
+        Self {
+            max_attempts: 1,
+            ..Self::default()
+        }
+    }
+
+    /// Delay to wait before retry number `retry` (1-based).
+    pub fn backoff(&self, retry: u32) -> Duration {
+        let factor = 2u32.saturating_pow(retry.saturating_sub(1));
+        self.initial_backoff
+            .saturating_mul(factor)
+            .min(self.max_backoff)
+    }
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            initial_backoff: Duration::from_millis(500),
+            max_backoff: Duration::from_secs(5),
+        }
+    }
+}
+
+/// Gateway session used by the bridges.
+///
+/// Wraps a GatewayClient and remembers how it was connected, so that
+/// transient failures (gateway restart, dropped connection) trigger a
+/// reconnect and a bounded retry instead of surfacing to the chat user.
+/// Sends are retried with the same idempotency key, so a message that
+/// reached the gateway before the failure is not delivered twice.
+pub struct BridgeGateway {
+    url: String,
+    token: Option<String>,
+    keep_alive: Option<KeepAliveConfig>,
+    client: GatewayClient,
+    retry: RetryPolicy,
+}
+
+impl BridgeGateway {
+    /// Connect to the gateway at the given URL with optional authentication token.
+    pub async fn connect(url: &str, token: Option<String>) -> Result<Self> {
+        Self::connect_with_keep_alive(url, token, Some(KeepAliveConfig::default())).await
+    }
+
+    /// Connect with the given HTTP/2 keep-alive settings, or none; reconnects
+    /// use the same settings.
+    pub async fn connect_with_keep_alive(
+        url: &str,
+        token: Option<String>,
+        keep_alive: Option<KeepAliveConfig>,
+    ) -> Result<Self> {
+        let client =
+            GatewayClient::connect_with_keep_alive(url, token.clone(), keep_alive.as_ref()).await?;
+        Ok(Self {
+            url: url.to_string(),
+            token,
+            keep_alive,
+            client,
+            retry: RetryPolicy::default(),
+        })
+    }
+
+    /// Replace the retry policy.
+    pub fn with_retry_policy(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    /// The retry policy in effect.
+    pub fn retry_policy(&self) -> RetryPolicy {
+        self.retry
+    }
+
+    /// Drop the current connection and establish a new one.
+    pub async fn reconnect(&mut self) -> Result<()> {
+        info!(url = %self.url, "Reconnecting to gateway");
+        self.client = GatewayClient::connect_with_keep_alive(
+            &self.url,
+            self.token.clone(),
+            self.keep_alive.as_ref(),
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// Wait out the backoff for `retry` and reconnect. Reconnect failures are
+    /// logged rather than returned so the next attempt can report the real error.
+    async fn prepare_retry(&mut self, retry: u32, err: &BridgeCoreError) {
+        let delay = self.retry.backoff(retry);
+        warn!(
+            error = %err,
+            retry,
+            max_attempts = self.retry.max_attempts,
+            delay_ms = delay.as_millis() as u64,
+            "Gateway request failed, retrying"
+        );
+        tokio::time::sleep(delay).await;
+        if let Err(e) = self.reconnect().await {
+            warn!(error = %e, "Gateway reconnect failed");
+        }
+    }
+
+    /// List all available agents from the gateway.
+    pub async fn list_agents(&mut self) -> Result<Vec<AgentInfo>> {
+        let mut attempt = 1;
+        loop {
+            match self.client.list_agents().await {
+                Err(e) if e.is_retryable() && attempt < self.retry.max_attempts => {
+                    self.prepare_retry(attempt, &e).await;
+                    attempt += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    /// Send a message to the gateway, retrying transient failures with the
+    /// same idempotency key.
+    pub async fn send_message(
+        &mut self,
+        conversation_key: String,
+        content: String,
+        idempotency_key: String,
+        sender: Option<&SenderIdentity>,
+        channel: Option<&ChannelIdentity>,
+        reply_to: Option<&str>,
+        overrides: &RequestOverrides,
+    ) -> Result<ClientSendMessageResponse> {
+        let mut attempt = 1;
+        loop {
+            let result = self
+                .client
+                .send_message(
+                    conversation_key.clone(),
+                    content.clone(),
+                    idempotency_key.clone(),
+                    sender,
+                    channel,
+                    reply_to,
+                    overrides,
+                )
+                .await;
+            match result {
+                Err(e) if e.is_retryable() && attempt < self.retry.max_attempts => {
+                    self.prepare_retry(attempt, &e).await;
+                    attempt += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    /// Stream events from the gateway for a given conversation.
+    pub async fn stream_events(
+        &mut self,
+        conversation_key: String,
+    ) -> Result<impl futures::Stream<Item = std::result::Result<ClientStreamEvent, Status>>> {
+        let mut attempt = 1;
+        loop {
+            match self.client.stream_events(conversation_key.clone()).await {
+                Err(e) if e.is_retryable() && attempt < self.retry.max_attempts => {
+                    self.prepare_retry(attempt, &e).await;
+                    attempt += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    /// Stream messages agents send on their own initiative, from every agent.
+    pub async fn stream_agent_initiated(
+        &mut self,
+    ) -> Result<impl futures::Stream<Item = std::result::Result<AgentInitiatedEvent, Status>>> {
+        let mut attempt = 1;
+        loop {
+            match self.client.stream_agent_initiated().await {
+                Err(e) if e.is_retryable() && attempt < self.retry.max_attempts => {
+                    self.prepare_retry(attempt, &e).await;
+                    attempt += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    /// Respond to a tool approval request.
+    pub async fn approve_tool(
+        &mut self,
+        agent_id: String,
+        tool_id: String,
+        approved: bool,
+        approve_all: bool,
+    ) -> Result<()> {
+        self.client
+            .approve_tool(agent_id, tool_id, approved, approve_all)
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_send_notice() {
+        let response = |status: &str, detail: Option<&str>| ClientSendMessageResponse {
+            status: status.to_string(),
+            message_id: String::new(),
+            detail: detail.map(String::from),
+        };
+        assert_eq!(send_notice(&response("accepted", None)), None);
+        assert_eq!(
+            send_notice(&response(SEND_STATUS_QUEUED, None)),
+            Some(QUEUED_NOTICE)
+        );
+        assert_eq!(
+            send_notice(&response(SEND_STATUS_BLOCKED, Some("Not here, please."))),
+            Some("Not here, please.")
+        );
+        assert_eq!(
+            send_notice(&response(SEND_STATUS_BLOCKED, None)),
+            Some(BLOCKED_NOTICE)
+        );
+    }
+
+    #[test]
+    fn test_default_retry_policy() {
+        let policy = RetryPolicy::default();
+        assert_eq!(policy.max_attempts, 3);
+        assert_eq!(policy.backoff(1), Duration::from_millis(500));
+    }
+
+    #[test]
+    fn test_backoff_doubles_and_caps() {
+        let policy = RetryPolicy {
+            max_attempts: 10,
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_millis(1000),
+        };
+        assert_eq!(policy.backoff(1), Duration::from_millis(100));
+        assert_eq!(policy.backoff(2), Duration::from_millis(200));
+        assert_eq!(policy.backoff(3), Duration::from_millis(400));
+        assert_eq!(policy.backoff(4), Duration::from_millis(800));
+        assert_eq!(policy.backoff(5), Duration::from_millis(1000));
+        assert_eq!(policy.backoff(64), Duration::from_millis(1000));
+    }
+
+    #[test]
+    fn test_retry_policy_none() {
+        assert_eq!(RetryPolicy::none().max_attempts, 1);
+    }
+
+    #[tokio::test]
+    async fn test_connect_rejects_invalid_url() {
+        let result = BridgeGateway::connect("not a url", None).await;
+        assert!(matches!(result, Err(BridgeCoreError::Config(_))));
+    }
+}
@@ -0,0 +1,180 @@
+// ABOUTME: Identity of the chat user and channel behind a relayed message, plus a short-lived lookup cache.
+// ABOUTME: Bridges resolve display names through platform APIs and forward them to the gateway.
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// How long a resolved display name is reused before asking the platform again.
+pub const IDENTITY_CACHE_TTL: Duration = Duration::from_secs(300);
+
+/// The person behind a message relayed from a chat platform.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SenderIdentity {
+    /// Platform name ("slack", "telegram", "matrix")
+    pub platform: String,
+    /// Stable platform identifier (Slack user ID, Telegram user ID, Matrix MXID)
+    pub platform_id: String,
+    /// Human-readable name to show the agent
+    pub display_name: String,
+}
+
+impl SenderIdentity {
+    pub fn new(
+        platform: impl Into<String>,
+        platform_id: impl Into<String>,
+        display_name: impl Into<String>,
+    ) -> Self {
+        Self {
+            platform: platform.into(),
+            platform_id: platform_id.into(),
+            display_name: display_name.into(),
+        }
+    }
+}
+
+/// The chat channel a relayed message came from, reported to the gateway
+/// so admins can pick it when creating a binding.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChannelIdentity {
+    /// Platform name ("slack", "telegram", "matrix")
+    pub platform: String,
+    /// Platform's channel identifier (Slack channel ID, Telegram chat ID, Matrix room ID)
+    pub channel_id: String,
+    /// Channel name, when the platform has one
+    pub display_name: Option<String>,
+}
+
+impl ChannelIdentity {
+    pub fn new(platform: impl Into<String>, channel_id: impl Into<String>) -> Self {
+        Self {
+            platform: platform.into(),
+            channel_id: channel_id.into(),
+            display_name: None,
+        }
+    }
+
+    /// Set the channel's name.
+    pub fn with_display_name(mut self, display_name: impl Into<String>) -> Self {
+        self.display_name = Some(display_name.into());
+        self
+    }
+}
+
+/// Caches resolved identities for one platform so a busy channel doesn't
+/// trigger a user lookup on every message.
+#[derive(Debug)]
+pub struct IdentityCache {
+    platform: String,
+    ttl: Duration,
+    entries: Mutex<HashMap<String, (SenderIdentity, Instant)>>,
+}
+
+impl IdentityCache {
+    /// Create a cache for `platform` whose entries live for `ttl`.
+    pub fn new(platform: impl Into<String>, ttl: Duration) -> Self {
+        Self {
+            platform: platform.into(),
+            ttl,
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// The cached identity for `platform_id`, if it is still fresh at `now`.
+    pub fn get(&self, platform_id: &str, now: Instant) -> Option<SenderIdentity> {
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        match entries.get(platform_id) {
+            Some((identity, cached_at)) if now.saturating_duration_since(*cached_at) < self.ttl => {
+                Some(identity.clone())
+            }
+            Some(_) => {
+                entries.remove(platform_id);
+                None
+            }
+            None => None,
+        }
+    }
+
+    /// Cache a display name for `platform_id` as of `now`.
+    pub fn insert(&self, platform_id: &str, display_name: &str, now: Instant) -> SenderIdentity {
+        let identity = SenderIdentity::new(&self.platform, platform_id, display_name);
+        self.entries
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(platform_id.to_string(), (identity.clone(), now));
+        identity
+    }
+
+    /// Return the identity for `platform_id`, calling `lookup` for its
+    /// display name on a cache miss. A failed lookup falls back to the
+    /// platform ID and is cached too, so a broken lookup isn't retried on
+    /// every message.
+    pub async fn resolve<F, Fut>(&self, platform_id: &str, lookup: F) -> SenderIdentity
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Option<String>>,
+    {
+        if let Some(identity) = self.get(platform_id, Instant::now()) {
+            return identity;
+        }
+        let display_name = lookup()
+            .await
+            .filter(|name| !name.trim().is_empty())
+            .unwrap_or_else(|| platform_id.to_string());
+        self.insert(platform_id, &display_name, Instant::now())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cache_hit_within_ttl() {
+        let cache = IdentityCache::new("slack", Duration::from_secs(60));
+        let now = Instant::now();
+        cache.insert("U123", "Alice", now);
+
+        let identity = cache.get("U123", now + Duration::from_secs(59)).unwrap();
+        assert_eq!(identity, SenderIdentity::new("slack", "U123", "Alice"));
+        assert!(cache.get("U999", now).is_none());
+    }
+
+    #[test]
+    fn test_cache_entry_expires() {
+        let cache = IdentityCache::new("slack", Duration::from_secs(60));
+        let now = Instant::now();
+        cache.insert("U123", "Alice", now);
+
+        assert!(cache.get("U123", now + Duration::from_secs(60)).is_none());
+        // Expired entries are dropped, not resurrected
+        assert!(cache.get("U123", now).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_resolve_uses_cache() {
+        let cache = IdentityCache::new("matrix", IDENTITY_CACHE_TTL);
+        let calls = std::sync::atomic::AtomicUsize::new(0);
+        let lookup = || async {
+            calls.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            Some("Bob".to_string())
+        };
+
+        let first = cache.resolve("@bob:example.org", lookup).await;
+        let second = cache.resolve("@bob:example.org", lookup).await;
+        assert_eq!(first.display_name, "Bob");
+        assert_eq!(first, second);
+        assert_eq!(calls.load(std::sync::atomic::Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_resolve_falls_back_to_platform_id() {
+        let cache = IdentityCache::new("slack", IDENTITY_CACHE_TTL);
+        let identity = cache.resolve("U123", || async { None }).await;
+        assert_eq!(identity.display_name, "U123");
+
+        let blank = cache.resolve("U456", || async { Some("  ".into()) }).await;
+        assert_eq!(blank.display_name, "U456");
+    }
+}
@@ -0,0 +1,148 @@
+// ABOUTME: Routing and formatting for messages agents send on their own initiative.
+// ABOUTME: Maps an agent-initiated event to the chats bound to the agent, or a notifications fallback.
+
+use coven_proto::AgentInitiatedEvent;
+use std::time::Duration;
+
+/// How long a bridge waits before resubscribing after the agent-initiated
+/// stream ends or fails.
+pub const INITIATED_RESUBSCRIBE_DELAY: Duration = Duration::from_secs(5);
+
+/// Prefix that marks a message as unprompted, so chat users can tell it
+/// apart from a reply.
+pub const INITIATED_MARKER: &str = "🔔 from agent";
+
+/// One place to post an agent-initiated message.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InitiatedDelivery {
+    /// Platform identifier of the chat, in the same form as binding targets
+    pub target: String,
+    /// Platform thread/topic to post in, if the agent named one
+    pub thread_id: Option<String>,
+}
+
+/// Decide where an agent-initiated message goes.
+///
+/// `bindings` yields `(target, conversation_key)` pairs. Every chat bound to
+/// the agent receives the message in the thread the agent named. When no
+/// chat is bound, it goes to the top level of `notifications` if one is
+/// configured; an empty result means the message should be dropped.
+pub fn route_initiated<'a>(
+    event: &AgentInitiatedEvent,
+    bindings: impl IntoIterator<Item = (&'a str, &'a str)>,
+    notifications: Option<&str>,
+) -> Vec<InitiatedDelivery> {
+    let mut targets: Vec<&str> = bindings
+        .into_iter()
+        .filter(|(_, conversation_key)| *conversation_key == event.agent_id)
+        .map(|(target, _)| target)
+        .collect();
+    targets.sort_unstable();
+    targets.dedup();
+
+    if targets.is_empty() {
+        // The agent's thread refers to a chat it isn't bound to, so post at
+        // the top level of the notifications chat instead
+        return notifications
+            .map(|target| InitiatedDelivery {
+                target: target.to_string(),
+                thread_id: None,
+            })
+            .into_iter()
+            .collect();
+    }
+
+    targets
+        .into_iter()
+        .map(|target| InitiatedDelivery {
+            target: target.to_string(),
+            thread_id: event.thread_id.clone(),
+        })
+        .collect()
+}
+
+/// Text to post for an agent-initiated message, marked as unprompted.
+pub fn format_initiated(event: &AgentInitiatedEvent) -> String {
+    let agent = if event.agent_name.is_empty() {
+        &event.agent_id
+    } else {
+        &event.agent_name
+    };
+    format!("{} {}\n\n{}", INITIATED_MARKER, agent, event.content)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(agent_id: &str, thread_id: Option<&str>) -> AgentInitiatedEvent {
+        AgentInitiatedEvent {
+            agent_id: agent_id.to_string(),
+            agent_name: "Builder".to_string(),
+            message_id: "m-1".to_string(),
+            content: "Deploy finished".to_string(),
+            thread_id: thread_id.map(String::from),
+            timestamp: "2026-01-01T00:00:00Z".to_string(),
+        }
+    }
+
+    const BINDINGS: [(&str, &str); 3] = [("C2", "agent-1"), ("C1", "agent-1"), ("C3", "agent-2")];
+
+    #[test]
+    fn test_routes_to_every_bound_chat_in_thread() {
+        let deliveries = route_initiated(&event("agent-1", Some("1700.01")), BINDINGS, None);
+        assert_eq!(
+            deliveries,
+            vec![
+                InitiatedDelivery {
+                    target: "C1".to_string(),
+                    thread_id: Some("1700.01".to_string()),
+                },
+                InitiatedDelivery {
+                    target: "C2".to_string(),
+                    thread_id: Some("1700.01".to_string()),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_bound_chat_takes_precedence_over_notifications() {
+        let deliveries = route_initiated(&event("agent-2", None), BINDINGS, Some("C-notify"));
+        assert_eq!(deliveries.len(), 1);
+        assert_eq!(deliveries[0].target, "C3");
+    }
+
+    #[test]
+    fn test_unbound_goes_to_notifications_top_level() {
+        let deliveries = route_initiated(
+            &event("agent-9", Some("1700.01")),
+            BINDINGS,
+            Some("C-notify"),
+        );
+        assert_eq!(
+            deliveries,
+            vec![InitiatedDelivery {
+                target: "C-notify".to_string(),
+                thread_id: None,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_unbound_without_notifications_is_dropped() {
+        assert!(route_initiated(&event("agent-9", None), BINDINGS, None).is_empty());
+    }
+
+    #[test]
+    fn test_format_marks_message_as_unprompted() {
+        let mut initiated = event("agent-1", None);
+        assert_eq!(
+            format_initiated(&initiated),
+            "🔔 from agent Builder\n\nDeploy finished"
+        );
+
+        initiated.agent_name.clear();
+        assert!(format_initiated(&initiated).starts_with("🔔 from agent agent-1\n"));
+    }
+}
@@ -0,0 +1,35 @@
+// ABOUTME: Shared building blocks for the Slack, Telegram, and Matrix bridges.
+// ABOUTME: Provides binding storage and overrides, a retrying gateway session, sender identities, inbound dedup and ordering, reply threading, response shaping, and agent-initiated routing.
+
+pub mod accumulator;
+pub mod dedup;
+pub mod error;
+pub mod gateway;
+pub mod identity;
+pub mod initiated;
+pub mod ordered;
+pub mod overrides;
+pub mod replies;
+pub mod split;
+pub mod store;
+
+pub use accumulator::ResponseAccumulator;
+pub use dedup::{MessageDeduplicator, DEDUP_CAPACITY, DEDUP_WINDOW};
+pub use error::{BridgeCoreError, Result};
+pub use gateway::{
+    send_notice, BridgeGateway, GatewayClient, RetryPolicy, BLOCKED_NOTICE, QUEUED_NOTICE,
+    SEND_STATUS_BLOCKED, SEND_STATUS_QUEUED,
+};
+pub use identity::{ChannelIdentity, IdentityCache, SenderIdentity, IDENTITY_CACHE_TTL};
+pub use initiated::{
+    format_initiated, route_initiated, InitiatedDelivery, INITIATED_MARKER,
+    INITIATED_RESUBSCRIBE_DELAY,
+};
+pub use ordered::{OrderedDispatcher, DEFAULT_MAX_CONCURRENT};
+pub use overrides::{validate_model, RequestOverrides};
+pub use replies::{ReplyIndex, REPLY_INDEX_CAPACITY};
+pub use split::split_message;
+pub use store::{
+    open_binding_store, BindingStore, JsonFileBindingStore, MemoryBindingStore, SqliteBindingStore,
+    StoredBinding,
+};
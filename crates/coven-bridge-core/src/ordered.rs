@@ -0,0 +1,170 @@
+// ABOUTME: Runs inbound chat messages in arrival order per thread while threads run concurrently.
+// ABOUTME: Queues tasks by key, one worker per busy key, with a cap on messages handled at once.
+
+use futures::future::BoxFuture;
+use futures::FutureExt;
+use std::collections::{HashMap, VecDeque};
+use std::future::Future;
+use std::panic::AssertUnwindSafe;
+use std::sync::{Arc, Mutex};
+use tokio::sync::Semaphore;
+use tracing::error;
+
+/// Messages handled at once across all threads unless configured otherwise.
+pub const DEFAULT_MAX_CONCURRENT: usize = 16;
+
+type Task = BoxFuture<'static, ()>;
+
+/// Runs tasks so those sharing a key (a chat thread or channel) run one at a
+/// time in the order they were submitted, while tasks with different keys
+/// run concurrently, at most `max_concurrent` at once.
+#[derive(Clone)]
+pub struct OrderedDispatcher {
+    /// Tasks waiting behind the running one, per busy key. A key is present
+    /// exactly while a worker is draining it.
+    queues: Arc<Mutex<HashMap<String, VecDeque<Task>>>>,
+    permits: Arc<Semaphore>,
+}
+
+impl OrderedDispatcher {
+    /// Allow at most `max_concurrent` tasks to run at once (at least one).
+    pub fn new(max_concurrent: usize) -> Self {
+        Self {
+            queues: Arc::new(Mutex::new(HashMap::new())),
+            permits: Arc::new(Semaphore::new(max_concurrent.max(1))),
+        }
+    }
+
+    /// Queue `task` behind any earlier tasks for `key` and return at once.
+    pub fn submit<F>(&self, key: impl Into<String>, task: F)
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+        let key = key.into();
+        let task: Task = Box::pin(task);
+        {
+            let mut queues = self.queues.lock().unwrap_or_else(|e| e.into_inner());
+            if let Some(waiting) = queues.get_mut(&key) {
+                waiting.push_back(task);
+                return;
+            }
+            queues.insert(key.clone(), VecDeque::new());
+        }
+        tokio::spawn(self.clone().drain(key, task));
+    }
+
+    /// Run `first`, then everything queued behind it for `key`.
+    async fn drain(self, key: String, first: Task) {
+        let mut next = Some(first);
+        while let Some(task) = next {
+            let permit = Arc::clone(&self.permits).acquire_owned().await;
+            // A panicking handler must not leave its thread stuck
+            if AssertUnwindSafe(task).catch_unwind().await.is_err() {
+                error!(key = %key, "Message handler panicked");
+            }
+            drop(permit);
+
+            let mut queues = self.queues.lock().unwrap_or_else(|e| e.into_inner());
+            next = queues.get_mut(&key).and_then(VecDeque::pop_front);
+            if next.is_none() {
+                queues.remove(&key);
+            }
+        }
+    }
+
+    /// Number of keys with a task running or waiting.
+    pub fn busy_keys(&self) -> usize {
+        self.queues.lock().unwrap_or_else(|e| e.into_inner()).len()
+    }
+}
+
+impl Default for OrderedDispatcher {
+    fn default() -> Self {
+        Self::new(DEFAULT_MAX_CONCURRENT)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+    use tokio::sync::mpsc;
+
+    #[tokio::test]
+    async fn test_same_key_runs_in_order() {
+        let dispatcher = OrderedDispatcher::default();
+        let (tx, mut rx) = mpsc::unbounded_channel();
+
+        // Earlier tasks take longer, so only queueing keeps them in order
+        for i in 0..5u64 {
+            let tx = tx.clone();
+            dispatcher.submit("thread", async move {
+                tokio::time::sleep(Duration::from_millis(50 - i * 10)).await;
+                tx.send(i).unwrap();
+            });
+        }
+        drop(tx);
+
+        let mut order = Vec::new();
+        while let Some(i) = rx.recv().await {
+            order.push(i);
+        }
+        assert_eq!(order, vec![0, 1, 2, 3, 4]);
+        assert_eq!(dispatcher.busy_keys(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_different_keys_run_concurrently() {
+        let dispatcher = OrderedDispatcher::default();
+        let (tx, mut rx) = mpsc::unbounded_channel();
+
+        let slow = tx.clone();
+        dispatcher.submit("slow", async move {
+            tokio::time::sleep(Duration::from_millis(200)).await;
+            slow.send("slow").unwrap();
+        });
+        dispatcher.submit("fast", async move {
+            tx.send("fast").unwrap();
+        });
+
+        assert_eq!(rx.recv().await, Some("fast"));
+        assert_eq!(rx.recv().await, Some("slow"));
+    }
+
+    #[tokio::test]
+    async fn test_limits_concurrent_tasks() {
+        let dispatcher = OrderedDispatcher::new(2);
+        let running = Arc::new(AtomicUsize::new(0));
+        let peak = Arc::new(AtomicUsize::new(0));
+        let (tx, mut rx) = mpsc::unbounded_channel();
+
+        for i in 0..6 {
+            let (running, peak, tx) = (Arc::clone(&running), Arc::clone(&peak), tx.clone());
+            dispatcher.submit(format!("thread-{}", i), async move {
+                let now = running.fetch_add(1, Ordering::SeqCst) + 1;
+                peak.fetch_max(now, Ordering::SeqCst);
+                tokio::time::sleep(Duration::from_millis(20)).await;
+                running.fetch_sub(1, Ordering::SeqCst);
+                tx.send(()).unwrap();
+            });
+        }
+        drop(tx);
+
+        while rx.recv().await.is_some() {}
+        assert_eq!(peak.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_panic_does_not_block_thread() {
+        let dispatcher = OrderedDispatcher::default();
+        let (tx, mut rx) = mpsc::unbounded_channel();
+
+        dispatcher.submit("thread", async { panic!("handler bug") });
+        dispatcher.submit("thread", async move {
+            tx.send(()).unwrap();
+        });
+
+        assert_eq!(rx.recv().await, Some(()));
+    }
+}
@@ -0,0 +1,7 @@
+// ABOUTME: Per-binding overrides of the agent's model and max tokens.
+// ABOUTME: The type is coven-proto's, so the gateway and agents check values the same way the bridges do.
+
+pub use coven_proto::overrides::{
+    parse_max_tokens, validate_model, InvalidOverride, RequestOverrides, MAX_MODEL_LEN,
+    MAX_TOKENS_LIMIT,
+};
@@ -0,0 +1,121 @@
+// ABOUTME: Maps chat platform message ids to the coven message ids the gateway assigned them.
+// ABOUTME: Lets a bridge tag a threaded reply with the coven id of the message it answers.
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+
+/// Most platform messages remembered at once; the oldest are forgotten first.
+pub const REPLY_INDEX_CAPACITY: usize = 10_000;
+
+/// Recently sent platform messages and their coven message ids, so a later
+/// reply on the platform can name its parent to the gateway.
+#[derive(Debug)]
+pub struct ReplyIndex {
+    capacity: usize,
+    entries: Mutex<Entries>,
+}
+
+#[derive(Debug, Default)]
+struct Entries {
+    ids: HashMap<String, String>,
+    /// Platform keys in the order they were recorded
+    order: VecDeque<String>,
+}
+
+impl ReplyIndex {
+    /// Remember at most `capacity` messages.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            entries: Mutex::new(Entries::default()),
+        }
+    }
+
+    /// Remember that the platform message `platform_key` became coven
+    /// message `message_id`. Empty ids (duplicate sends) are ignored.
+    pub fn record(&self, platform_key: &str, message_id: &str) {
+        if message_id.is_empty() {
+            return;
+        }
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        if entries
+            .ids
+            .insert(platform_key.to_string(), message_id.to_string())
+            .is_some()
+        {
+            return;
+        }
+        entries.order.push_back(platform_key.to_string());
+        while entries.order.len() > self.capacity {
+            if let Some(oldest) = entries.order.pop_front() {
+                entries.ids.remove(&oldest);
+            }
+        }
+    }
+
+    /// Coven message id of the platform message `platform_key`, if known.
+    pub fn lookup(&self, platform_key: &str) -> Option<String> {
+        self.entries
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .ids
+            .get(platform_key)
+            .cloned()
+    }
+
+    /// Number of messages currently remembered.
+    pub fn len(&self) -> usize {
+        self.entries
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .ids
+            .len()
+    }
+
+    /// Whether no messages are remembered.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl Default for ReplyIndex {
+    fn default() -> Self {
+        Self::new(REPLY_INDEX_CAPACITY)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lookup_recorded_message() {
+        let index = ReplyIndex::default();
+        index.record("C1:1700000000.000100", "msg-1");
+        assert_eq!(
+            index.lookup("C1:1700000000.000100").as_deref(),
+            Some("msg-1")
+        );
+        assert_eq!(index.lookup("C2:1700000000.000100"), None);
+    }
+
+    #[test]
+    fn test_empty_message_id_ignored() {
+        let index = ReplyIndex::default();
+        index.record("C1:1", "");
+        assert!(index.is_empty());
+    }
+
+    #[test]
+    fn test_capacity_forgets_oldest() {
+        let index = ReplyIndex::new(2);
+        index.record("a", "1");
+        index.record("b", "2");
+        index.record("a", "1");
+        index.record("c", "3");
+
+        assert_eq!(index.len(), 2);
+        assert_eq!(index.lookup("a"), None);
+        assert_eq!(index.lookup("c").as_deref(), Some("3"));
+    }
+}
@@ -0,0 +1,122 @@
+// ABOUTME: Splits long responses into chunks that fit a platform's message length limit.
+// ABOUTME: Prefers paragraph, line, sentence, then word boundaries and never splits a UTF-8 char.
+
+/// Split `text` into pieces of at most `max_len` bytes.
+///
+/// Each piece is cut at the last paragraph break that fits, falling back to
+/// a line break, a sentence end, whitespace, and finally a hard cut on a
+/// character boundary. Whitespace at the cut is dropped so pieces don't
+/// start or end with stray blank lines. Empty text yields no pieces.
+pub fn split_message(text: &str, max_len: usize) -> Vec<String> {
+    let max_len = max_len.max(1);
+    let mut pieces = Vec::new();
+    let mut rest = text.trim();
+
+    while !rest.is_empty() {
+        if rest.len() <= max_len {
+            pieces.push(rest.to_string());
+            break;
+        }
+
+        let cut = find_cut(rest, max_len);
+        let (head, tail) = rest.split_at(cut);
+        let head = head.trim_end();
+        if !head.is_empty() {
+            pieces.push(head.to_string());
+        }
+        rest = tail.trim_start();
+    }
+
+    pieces
+}
+
+/// Byte offset to cut `text` at, no greater than `max_len` and never zero.
+fn find_cut(text: &str, max_len: usize) -> usize {
+    let mut limit = max_len;
+    while !text.is_char_boundary(limit) {
+        limit -= 1;
+    }
+    if limit == 0 {
+        // A single character wider than the limit; emit it whole
+        return text.chars().next().map_or(text.len(), char::len_utf8);
+    }
+    let window = &text[..limit];
+
+    if let Some(i) = window.rfind("\n\n").filter(|&i| i > 0) {
+        return i;
+    }
+    if let Some(i) = window.rfind('\n').filter(|&i| i > 0) {
+        return i;
+    }
+    if let Some(i) = last_sentence_end(window) {
+        return i;
+    }
+    if let Some(i) = window.rfind(char::is_whitespace).filter(|&i| i > 0) {
+        return i;
+    }
+    limit
+}
+
+/// Offset just past the last `.`, `!` or `?` that is followed by whitespace.
+fn last_sentence_end(window: &str) -> Option<usize> {
+    window
+        .char_indices()
+        .zip(window.chars().skip(1))
+        .filter(|((_, c), next)| matches!(c, '.' | '!' | '?') && next.is_whitespace())
+        .map(|((i, c), _)| i + c.len_utf8())
+        .last()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_short_text_is_one_piece() {
+        assert_eq!(split_message("hello", 10), vec!["hello"]);
+        assert!(split_message("", 10).is_empty());
+        assert!(split_message("  \n ", 10).is_empty());
+    }
+
+    #[test]
+    fn test_prefers_paragraph_break() {
+        let text = "First para.\n\nSecond para here.";
+        assert_eq!(
+            split_message(text, 20),
+            vec!["First para.", "Second para here."]
+        );
+    }
+
+    #[test]
+    fn test_falls_back_to_sentence_then_word() {
+        assert_eq!(
+            split_message("One two. Three four five", 15),
+            vec!["One two.", "Three four five"]
+        );
+        assert_eq!(
+            split_message("alpha beta gamma", 11),
+            vec!["alpha beta", "gamma"]
+        );
+    }
+
+    #[test]
+    fn test_hard_cut_without_boundaries() {
+        assert_eq!(split_message("abcdefghij", 4), vec!["abcd", "efgh", "ij"]);
+    }
+
+    #[test]
+    fn test_never_splits_multibyte_chars() {
+        let text = "ééééé";
+        let pieces = split_message(text, 3);
+        assert!(pieces.iter().all(|p| p.len() <= 3));
+        assert_eq!(pieces.concat(), text);
+    }
+
+    #[test]
+    fn test_pieces_respect_limit() {
+        let text = "word ".repeat(500);
+        let pieces = split_message(&text, 97);
+        assert!(pieces.iter().all(|p| p.len() <= 97));
+        assert_eq!(pieces.join(" ").split_whitespace().count(), 500);
+    }
+}
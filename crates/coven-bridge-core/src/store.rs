@@ -0,0 +1,276 @@
+// ABOUTME: Persistent storage for chat-to-conversation bindings.
+// ABOUTME: BindingStore trait with in-memory, JSON-file, and SQLite implementations.
+
+use crate::error::Result;
+use crate::overrides::RequestOverrides;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use sqlx::{
+    sqlite::{SqliteConnectOptions, SqlitePoolOptions},
+    SqlitePool,
+};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::Arc;
+use tokio::sync::{Mutex, RwLock};
+
+/// A binding between a chat target (Slack channel, Telegram chat, Matrix
+/// room) and a gateway conversation, in a platform-neutral form.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StoredBinding {
+    /// Platform identifier of the chat, as a string
+    pub target: String,
+    /// Gateway conversation key (agent ID) the chat is bound to
+    pub conversation_key: String,
+    /// Platform user who created the binding, if tracked
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub owner: Option<String>,
+    /// Model and max_tokens overrides for messages from this chat
+    #[serde(flatten)]
+    pub overrides: RequestOverrides,
+}
+
+/// Storage backend for bindings. Each target has at most one binding.
+#[async_trait]
+pub trait BindingStore: Send + Sync {
+    /// Load every stored binding.
+    async fn load(&self) -> Result<Vec<StoredBinding>>;
+
+    /// Insert or replace the binding for `binding.target`.
+    async fn save(&self, binding: &StoredBinding) -> Result<()>;
+
+    /// Remove the binding for `target`. Returns true if one existed.
+    async fn remove(&self, target: &str) -> Result<bool>;
+}
+
+/// Open the binding store for a config value.
+///
+/// No path keeps bindings in memory only (lost on restart). A path ending
+/// in `.json` uses a JSON file; any other path is a SQLite database.
+pub async fn open_binding_store(path: Option<&Path>) -> Result<Arc<dyn BindingStore>> {
+    match path {
+        None => Ok(Arc::new(MemoryBindingStore::new())),
+        Some(p) if p.extension().is_some_and(|ext| ext == "json") => {
+            Ok(Arc::new(JsonFileBindingStore::new(p)))
+        }
+        Some(p) => Ok(Arc::new(SqliteBindingStore::open(p).await?)),
+    }
+}
+
+/// Bindings held in memory only.
+#[derive(Debug, Default)]
+pub struct MemoryBindingStore {
+    bindings: RwLock<BTreeMap<String, StoredBinding>>,
+}
+
+impl MemoryBindingStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl BindingStore for MemoryBindingStore {
+    async fn load(&self) -> Result<Vec<StoredBinding>> {
+        Ok(self.bindings.read().await.values().cloned().collect())
+    }
+
+    async fn save(&self, binding: &StoredBinding) -> Result<()> {
+        self.bindings
+            .write()
+            .await
+            .insert(binding.target.clone(), binding.clone());
+        Ok(())
+    }
+
+    async fn remove(&self, target: &str) -> Result<bool> {
+        Ok(self.bindings.write().await.remove(target).is_some())
+    }
+}
+
+/// Bindings stored as a JSON array in a single file.
+///
+/// Every change rewrites the whole file via a temporary file and rename,
+/// so a crash mid-write never leaves a truncated file behind.
+#[derive(Debug)]
+pub struct JsonFileBindingStore {
+    path: PathBuf,
+    lock: Mutex<()>,
+}
+
+impl JsonFileBindingStore {
+    /// Use the JSON file at `path`. The file is created on first write.
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            lock: Mutex::new(()),
+        }
+    }
+
+    async fn read_all(&self) -> Result<BTreeMap<String, StoredBinding>> {
+        let content = match tokio::fs::read_to_string(&self.path).await {
+            Ok(c) => c,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(BTreeMap::new()),
+            Err(e) => return Err(e.into()),
+        };
+        if content.trim().is_empty() {
+            return Ok(BTreeMap::new());
+        }
+        let list: Vec<StoredBinding> = serde_json::from_str(&content)?;
+        Ok(list.into_iter().map(|b| (b.target.clone(), b)).collect())
+    }
+
+    async fn write_all(&self, bindings: &BTreeMap<String, StoredBinding>) -> Result<()> {
+        if let Some(parent) = self.path.parent() {
+            if !parent.as_os_str().is_empty() {
+                tokio::fs::create_dir_all(parent).await?;
+            }
+        }
+        let list: Vec<&StoredBinding> = bindings.values().collect();
+        let json = serde_json::to_string_pretty(&list)?;
+        let tmp = self.path.with_extension("json.tmp");
+        tokio::fs::write(&tmp, json).await?;
+        tokio::fs::rename(&tmp, &self.path).await?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl BindingStore for JsonFileBindingStore {
+    async fn load(&self) -> Result<Vec<StoredBinding>> {
+        let _guard = self.lock.lock().await;
+        Ok(self.read_all().await?.into_values().collect())
+    }
+
+    async fn save(&self, binding: &StoredBinding) -> Result<()> {
+        let _guard = self.lock.lock().await;
+        let mut bindings = self.read_all().await?;
+        bindings.insert(binding.target.clone(), binding.clone());
+        self.write_all(&bindings).await
+    }
+
+    async fn remove(&self, target: &str) -> Result<bool> {
+        let _guard = self.lock.lock().await;
+        let mut bindings = self.read_all().await?;
+        let removed = bindings.remove(target).is_some();
+        if removed {
+            self.write_all(&bindings).await?;
+        }
+        Ok(removed)
+    }
+}
+
+/// Bindings stored in a SQLite database.
+pub struct SqliteBindingStore {
+    pool: SqlitePool,
+}
+
+impl SqliteBindingStore {
+    /// Open or create a binding database at the given path.
+    pub async fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let url = format!("sqlite:{}?mode=rwc", path.as_ref().display());
+        let options = SqliteConnectOptions::from_str(&url)?.create_if_missing(true);
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect_with(options)
+            .await?;
+        Self::init(pool).await
+    }
+
+    /// Create a store backed by a private in-memory database.
+    pub async fn in_memory() -> Result<Self> {
+        let options = SqliteConnectOptions::from_str("sqlite::memory:")?;
+        // A single connection, otherwise each one would see its own database
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect_with(options)
+            .await?;
+        Self::init(pool).await
+    }
+
+    async fn init(pool: SqlitePool) -> Result<Self> {
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS bindings (
+                target TEXT PRIMARY KEY,
+                conversation_key TEXT NOT NULL,
+                owner TEXT,
+                model TEXT,
+                max_tokens INTEGER
+            )
+            "#,
+        )
+        .execute(&pool)
+        .await?;
+
+        // Databases created before overrides existed lack their columns
+        let columns: Vec<(String,)> =
+            sqlx::query_as("SELECT name FROM pragma_table_info('bindings')")
+                .fetch_all(&pool)
+                .await?;
+        for (column, ty) in [("model", "TEXT"), ("max_tokens", "INTEGER")] {
+            if !columns.iter().any(|(name,)| name == column) {
+                sqlx::query(&format!(
+                    "ALTER TABLE bindings ADD COLUMN {} {}",
+                    column, ty
+                ))
+                .execute(&pool)
+                .await?;
+            }
+        }
+
+        Ok(Self { pool })
+    }
+}
+
+#[async_trait]
+impl BindingStore for SqliteBindingStore {
+    async fn load(&self) -> Result<Vec<StoredBinding>> {
+        let rows = sqlx::query_as::<
+            _,
+            (String, String, Option<String>, Option<String>, Option<i64>),
+        >(
+            "SELECT target, conversation_key, owner, model, max_tokens FROM bindings ORDER BY target",
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(
+                |(target, conversation_key, owner, model, max_tokens)| StoredBinding {
+                    target,
+                    conversation_key,
+                    owner,
+                    overrides: RequestOverrides {
+                        model,
+                        max_tokens: max_tokens.and_then(|n| u32::try_from(n).ok()),
+                    },
+                },
+            )
+            .collect())
+    }
+
+    async fn save(&self, binding: &StoredBinding) -> Result<()> {
+        sqlx::query(
+            "INSERT OR REPLACE INTO bindings (target, conversation_key, owner, model, max_tokens) VALUES (?, ?, ?, ?, ?)",
+        )
+        .bind(&binding.target)
+        .bind(&binding.conversation_key)
+        .bind(&binding.owner)
+        .bind(&binding.overrides.model)
+        .bind(binding.overrides.max_tokens.map(i64::from))
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    async fn remove(&self, target: &str) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM bindings WHERE target = ?")
+            .bind(target)
+            .execute(&self.pool)
+            .await?;
+        Ok(result.rows_affected() > 0)
+    }
+}
@@ -0,0 +1,247 @@
+// ABOUTME: Mock gateway for the coven-bridge-core integration tests
+// ABOUTME: Records every send it receives, and can fail the first few as unavailable
+
+use coven_proto::server::{ClientService, ClientServiceServer};
+use coven_proto::{
+    AgentInitiatedEvent, ApproveToolRequest, ApproveToolResponse, ClientSendMessageRequest,
+    ClientSendMessageResponse, ClientStreamEvent, ForkThreadRequest, ForkThreadResponse,
+    GetApprovalHistoryRequest, GetApprovalHistoryResponse, GetEventsRequest, GetEventsResponse,
+    GetPairingStatusRequest, ListAgentsRequest, ListAgentsResponse, ListPendingApprovalsRequest,
+    ListPendingApprovalsResponse, ListThreadsRequest, ListThreadsResponse, MeResponse, PairingCode,
+    PairingStatus, RefreshTokenRequest, RefreshTokenResponse, RegisterAgentRequest,
+    RegisterAgentResponse, RegisterClientRequest, RegisterClientResponse, RegisterPushTokenRequest,
+    RegisterPushTokenResponse, RequestPairingCodeRequest, RotateKeyRequest, RotateKeyResponse,
+    StreamAgentInitiatedRequest, StreamEventsRequest, UnregisterPushTokenRequest,
+    UnregisterPushTokenResponse, VersionResponse,
+};
+use std::pin::Pin;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::net::TcpListener;
+use tokio_stream::wrappers::TcpListenerStream;
+use tonic::{Request, Response, Status};
+
+/// Gateway that accepts every message. Each send is recorded, even one it
+/// then fails: the first `unavailable` sends fail as if the gateway were
+/// restarting.
+#[derive(Clone, Default)]
+pub struct MockGateway {
+    pub sent: Arc<Mutex<Vec<ClientSendMessageRequest>>>,
+    pub unavailable: Arc<AtomicUsize>,
+}
+
+impl MockGateway {
+    /// Idempotency keys of the sends received, in order
+    pub fn sent_keys(&self) -> Vec<String> {
+        self.sent
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|request| request.idempotency_key.clone())
+            .collect()
+    }
+
+    /// Content of the sends received, in order
+    pub fn sent_contents(&self) -> Vec<String> {
+        self.sent
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|request| request.content.clone())
+            .collect()
+    }
+}
+
+type BoxStream<T> = Pin<Box<dyn futures::Stream<Item = Result<T, Status>> + Send>>;
+
+#[tonic::async_trait]
+impl ClientService for MockGateway {
+    async fn get_me(&self, _request: Request<()>) -> Result<Response<MeResponse>, Status> {
+        Err(Status::unimplemented("get_me"))
+    }
+
+    async fn get_version(
+        &self,
+        _request: Request<()>,
+    ) -> Result<Response<VersionResponse>, Status> {
+        Err(Status::unimplemented("get_version"))
+    }
+
+    async fn get_events(
+        &self,
+        _request: Request<GetEventsRequest>,
+    ) -> Result<Response<GetEventsResponse>, Status> {
+        Err(Status::unimplemented("get_events"))
+    }
+
+    async fn send_message(
+        &self,
+        request: Request<ClientSendMessageRequest>,
+    ) -> Result<Response<ClientSendMessageResponse>, Status> {
+        let request = request.into_inner();
+        self.sent.lock().unwrap().push(request.clone());
+        let failing = self
+            .unavailable
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| n.checked_sub(1))
+            .is_ok();
+        if failing {
+            return Err(Status::unavailable("gateway restarting"));
+        }
+        Ok(Response::new(ClientSendMessageResponse {
+            status: "accepted".to_string(),
+            message_id: request.idempotency_key,
+            detail: None,
+        }))
+    }
+
+    type StreamEventsStream = BoxStream<ClientStreamEvent>;
+
+    async fn stream_events(
+        &self,
+        _request: Request<StreamEventsRequest>,
+    ) -> Result<Response<Self::StreamEventsStream>, Status> {
+        Err(Status::unimplemented("stream_events"))
+    }
+
+    type StreamAgentInitiatedStream = BoxStream<AgentInitiatedEvent>;
+
+    async fn stream_agent_initiated(
+        &self,
+        _request: Request<StreamAgentInitiatedRequest>,
+    ) -> Result<Response<Self::StreamAgentInitiatedStream>, Status> {
+        Err(Status::unimplemented("stream_agent_initiated"))
+    }
+
+    async fn list_agents(
+        &self,
+        _request: Request<ListAgentsRequest>,
+    ) -> Result<Response<ListAgentsResponse>, Status> {
+        Err(Status::unimplemented("list_agents"))
+    }
+
+    type WatchAgentsStream = BoxStream<ListAgentsResponse>;
+
+    async fn watch_agents(
+        &self,
+        _request: Request<ListAgentsRequest>,
+    ) -> Result<Response<Self::WatchAgentsStream>, Status> {
+        Err(Status::unimplemented("watch_agents"))
+    }
+
+    async fn register_agent(
+        &self,
+        _request: Request<RegisterAgentRequest>,
+    ) -> Result<Response<RegisterAgentResponse>, Status> {
+        Err(Status::unimplemented("register_agent"))
+    }
+
+    async fn register_client(
+        &self,
+        _request: Request<RegisterClientRequest>,
+    ) -> Result<Response<RegisterClientResponse>, Status> {
+        Err(Status::unimplemented("register_client"))
+    }
+
+    async fn approve_tool(
+        &self,
+        _request: Request<ApproveToolRequest>,
+    ) -> Result<Response<ApproveToolResponse>, Status> {
+        Err(Status::unimplemented("approve_tool"))
+    }
+
+    async fn fork_thread(
+        &self,
+        _request: Request<ForkThreadRequest>,
+    ) -> Result<Response<ForkThreadResponse>, Status> {
+        Err(Status::unimplemented("fork_thread"))
+    }
+
+    async fn list_threads(
+        &self,
+        _request: Request<ListThreadsRequest>,
+    ) -> Result<Response<ListThreadsResponse>, Status> {
+        Err(Status::unimplemented("list_threads"))
+    }
+
+    async fn register_push_token(
+        &self,
+        _request: Request<RegisterPushTokenRequest>,
+    ) -> Result<Response<RegisterPushTokenResponse>, Status> {
+        Err(Status::unimplemented("register_push_token"))
+    }
+
+    async fn unregister_push_token(
+        &self,
+        _request: Request<UnregisterPushTokenRequest>,
+    ) -> Result<Response<UnregisterPushTokenResponse>, Status> {
+        Err(Status::unimplemented("unregister_push_token"))
+    }
+
+    async fn rotate_key(
+        &self,
+        _request: Request<RotateKeyRequest>,
+    ) -> Result<Response<RotateKeyResponse>, Status> {
+        Err(Status::unimplemented("rotate_key"))
+    }
+
+    async fn request_pairing_code(
+        &self,
+        _request: Request<RequestPairingCodeRequest>,
+    ) -> Result<Response<PairingCode>, Status> {
+        Err(Status::unimplemented("request_pairing_code"))
+    }
+
+    async fn get_pairing_status(
+        &self,
+        _request: Request<GetPairingStatusRequest>,
+    ) -> Result<Response<PairingStatus>, Status> {
+        Err(Status::unimplemented("get_pairing_status"))
+    }
+
+    async fn refresh_token(
+        &self,
+        _request: Request<RefreshTokenRequest>,
+    ) -> Result<Response<RefreshTokenResponse>, Status> {
+        Err(Status::unimplemented("refresh_token"))
+    }
+
+    async fn list_pending_approvals(
+        &self,
+        _request: Request<ListPendingApprovalsRequest>,
+    ) -> Result<Response<ListPendingApprovalsResponse>, Status> {
+        Err(Status::unimplemented("list_pending_approvals"))
+    }
+
+    async fn get_approval_history(
+        &self,
+        _request: Request<GetApprovalHistoryRequest>,
+    ) -> Result<Response<GetApprovalHistoryResponse>, Status> {
+        Err(Status::unimplemented("get_approval_history"))
+    }
+}
+
+/// Serve `gateway` on a free port, returning its URL
+pub async fn serve(gateway: MockGateway) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        tonic::transport::Server::builder()
+            .add_service(ClientServiceServer::new(gateway))
+            .serve_with_incoming(TcpListenerStream::new(listener))
+            .await
+            .unwrap();
+    });
+    format!("http://{}", addr)
+}
+
+/// Wait until `check` holds, polling while background work runs
+pub async fn eventually(check: impl Fn() -> bool) {
+    for _ in 0..250 {
+        if check() {
+            return;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    panic!("condition not met in time");
+}
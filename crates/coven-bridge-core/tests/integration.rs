@@ -0,0 +1,475 @@
+// ABOUTME: Integration tests for coven-bridge-core.
+// ABOUTME: Tests accumulator throttling, binding store round-trips and migration, inbound dedup and ordering against a mock gateway, and agent-initiated routing.
+
+mod common;
+
+use common::MockGateway;
+use coven_bridge_core::{
+    open_binding_store, route_initiated, BindingStore, BridgeGateway, JsonFileBindingStore,
+    MemoryBindingStore, MessageDeduplicator, OrderedDispatcher, RequestOverrides,
+    ResponseAccumulator, RetryPolicy, SqliteBindingStore, StoredBinding,
+};
+use coven_proto::{AgentInitiatedEvent, ClientSendMessageResponse};
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+
+fn binding(target: &str, conversation_key: &str, owner: Option<&str>) -> StoredBinding {
+    StoredBinding {
+        target: target.to_string(),
+        conversation_key: conversation_key.to_string(),
+        owner: owner.map(String::from),
+        overrides: RequestOverrides::default(),
+    }
+}
+
+fn binding_with_overrides(
+    target: &str,
+    model: Option<&str>,
+    max_tokens: Option<u32>,
+) -> StoredBinding {
+    StoredBinding {
+        overrides: RequestOverrides {
+            model: model.map(String::from),
+            max_tokens,
+        },
+        ..binding(target, "agent-a", None)
+    }
+}
+
+#[test]
+fn test_accumulator_first_chunk_flushes_immediately() {
+    let mut acc = ResponseAccumulator::new(Duration::from_millis(500));
+    let now = Instant::now();
+    assert_eq!(acc.push("Hello", now), Some("Hello"));
+    assert!(!acc.has_pending());
+}
+
+#[test]
+fn test_accumulator_coalesces_chunks_within_interval() {
+    let mut acc = ResponseAccumulator::new(Duration::from_millis(500));
+    let start = Instant::now();
+    acc.push("a", start);
+
+    // Chunks inside the window are held back
+    assert!(acc.push("b", start + Duration::from_millis(100)).is_none());
+    assert!(acc.push("c", start + Duration::from_millis(499)).is_none());
+    assert!(acc.has_pending());
+
+    // The next chunk after the window flushes everything so far
+    assert_eq!(
+        acc.push("d", start + Duration::from_millis(500)),
+        Some("abcd")
+    );
+    assert!(!acc.has_pending());
+}
+
+#[test]
+fn test_accumulator_interval_restarts_after_flush() {
+    let mut acc = ResponseAccumulator::new(Duration::from_secs(1));
+    let start = Instant::now();
+    acc.push("one ", start);
+    let second = start + Duration::from_secs(1);
+    assert!(acc.push("two ", second).is_some());
+
+    assert!(acc
+        .push("three", second + Duration::from_millis(999))
+        .is_none());
+    assert_eq!(
+        acc.push("", second + Duration::from_secs(1)),
+        Some("one two three")
+    );
+}
+
+#[test]
+fn test_accumulator_min_chars_holds_small_updates() {
+    let mut acc = ResponseAccumulator::new(Duration::ZERO).with_min_chars(10);
+    let now = Instant::now();
+
+    assert!(acc.push("short", now).is_none());
+    assert_eq!(acc.push(" enough", now), Some("short enough"));
+    // An empty push never counts as new text
+    assert!(acc.push("", now).is_none());
+}
+
+#[test]
+fn test_accumulator_finish_after_flushes() {
+    let mut acc = ResponseAccumulator::new(Duration::ZERO);
+    let now = Instant::now();
+    acc.push("streamed ", now);
+    acc.push("text", now);
+    assert_eq!(acc.finish(None), "streamed text");
+}
+
+async fn assert_round_trip(store: &dyn BindingStore) {
+    assert!(store.load().await.unwrap().is_empty());
+
+    store
+        .save(&binding("C1", "agent-a", Some("@alice:example.org")))
+        .await
+        .unwrap();
+    store.save(&binding("C2", "agent-b", None)).await.unwrap();
+
+    let mut loaded = store.load().await.unwrap();
+    loaded.sort_by(|a, b| a.target.cmp(&b.target));
+    assert_eq!(
+        loaded,
+        vec![
+            binding("C1", "agent-a", Some("@alice:example.org")),
+            binding("C2", "agent-b", None),
+        ]
+    );
+
+    // Saving the same target replaces the binding
+    store.save(&binding("C1", "agent-c", None)).await.unwrap();
+    let loaded = store.load().await.unwrap();
+    assert_eq!(loaded.len(), 2);
+    assert!(loaded.contains(&binding("C1", "agent-c", None)));
+
+    assert!(store.remove("C1").await.unwrap());
+    assert!(!store.remove("C1").await.unwrap());
+    assert_eq!(
+        store.load().await.unwrap(),
+        vec![binding("C2", "agent-b", None)]
+    );
+}
+
+#[tokio::test]
+async fn test_memory_store_round_trip() {
+    assert_round_trip(&MemoryBindingStore::new()).await;
+}
+
+#[tokio::test]
+async fn test_json_store_round_trip() {
+    let dir = tempfile::tempdir().unwrap();
+    let store = JsonFileBindingStore::new(dir.path().join("bindings.json"));
+    assert_round_trip(&store).await;
+}
+
+#[tokio::test]
+async fn test_sqlite_store_round_trip() {
+    let store = SqliteBindingStore::in_memory().await.unwrap();
+    assert_round_trip(&store).await;
+}
+
+async fn assert_overrides_round_trip(store: &dyn BindingStore) {
+    let bindings = vec![
+        binding_with_overrides("C1", Some("claude-haiku-4-5"), Some(1024)),
+        binding_with_overrides("C2", None, Some(8000)),
+        binding_with_overrides("C3", None, None),
+    ];
+    for b in &bindings {
+        store.save(b).await.unwrap();
+    }
+
+    let mut loaded = store.load().await.unwrap();
+    loaded.sort_by(|a, b| a.target.cmp(&b.target));
+    assert_eq!(loaded, bindings);
+}
+
+#[tokio::test]
+async fn test_overrides_round_trip() {
+    let dir = tempfile::tempdir().unwrap();
+    assert_overrides_round_trip(&MemoryBindingStore::new()).await;
+    assert_overrides_round_trip(&JsonFileBindingStore::new(dir.path().join("b.json"))).await;
+    assert_overrides_round_trip(&SqliteBindingStore::in_memory().await.unwrap()).await;
+}
+
+#[tokio::test]
+async fn test_json_store_reads_bindings_without_overrides() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("bindings.json");
+    std::fs::write(
+        &path,
+        r#"[{"target": "C1", "conversation_key": "agent-a"}]"#,
+    )
+    .unwrap();
+
+    let loaded = JsonFileBindingStore::new(&path).load().await.unwrap();
+    assert_eq!(loaded, vec![binding("C1", "agent-a", None)]);
+}
+
+#[tokio::test]
+async fn test_sqlite_store_migrates_old_schema() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("bindings.db");
+
+    {
+        let pool = sqlx::SqlitePool::connect(&format!("sqlite:{}?mode=rwc", path.display()))
+            .await
+            .unwrap();
+        sqlx::query(
+            "CREATE TABLE bindings (target TEXT PRIMARY KEY, conversation_key TEXT NOT NULL, owner TEXT)",
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+        sqlx::query("INSERT INTO bindings VALUES ('C1', 'agent-a', NULL)")
+            .execute(&pool)
+            .await
+            .unwrap();
+        pool.close().await;
+    }
+
+    let store = SqliteBindingStore::open(&path).await.unwrap();
+    assert_eq!(
+        store.load().await.unwrap(),
+        vec![binding("C1", "agent-a", None)]
+    );
+
+    let updated = binding_with_overrides("C1", Some("claude-opus-4"), None);
+    store.save(&updated).await.unwrap();
+    assert_eq!(store.load().await.unwrap(), vec![updated]);
+}
+
+#[tokio::test]
+async fn test_json_store_persists_across_instances() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("nested").join("bindings.json");
+
+    JsonFileBindingStore::new(&path)
+        .save(&binding("12345", "agent-a", None))
+        .await
+        .unwrap();
+
+    let reopened = JsonFileBindingStore::new(&path);
+    assert_eq!(
+        reopened.load().await.unwrap(),
+        vec![binding("12345", "agent-a", None)]
+    );
+}
+
+#[tokio::test]
+async fn test_sqlite_store_persists_across_instances() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("bindings.db");
+
+    {
+        let store = SqliteBindingStore::open(&path).await.unwrap();
+        store
+            .save(&binding(
+                "!room:example.org",
+                "agent-a",
+                Some("@bob:example.org"),
+            ))
+            .await
+            .unwrap();
+    }
+
+    let reopened = SqliteBindingStore::open(&path).await.unwrap();
+    assert_eq!(
+        reopened.load().await.unwrap(),
+        vec![binding(
+            "!room:example.org",
+            "agent-a",
+            Some("@bob:example.org")
+        )]
+    );
+}
+
+#[tokio::test]
+async fn test_open_binding_store_selects_backend() {
+    let dir = tempfile::tempdir().unwrap();
+
+    let memory = open_binding_store(None).await.unwrap();
+    assert_round_trip(memory.as_ref()).await;
+
+    let json_path = dir.path().join("bindings.json");
+    let json = open_binding_store(Some(&json_path)).await.unwrap();
+    json.save(&binding("C1", "agent-a", None)).await.unwrap();
+    assert!(json_path.exists());
+
+    let db_path = dir.path().join("bindings.db");
+    let sqlite = open_binding_store(Some(&db_path)).await.unwrap();
+    sqlite.save(&binding("C1", "agent-a", None)).await.unwrap();
+    assert!(db_path.exists());
+}
+
+#[tokio::test]
+async fn test_json_store_rejects_corrupt_file() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("bindings.json");
+    std::fs::write(&path, "{ not json").unwrap();
+
+    let store = JsonFileBindingStore::new(&path);
+    assert!(store.load().await.is_err());
+}
+
+fn pairs(bindings: &[StoredBinding]) -> Vec<(&str, &str)> {
+    bindings
+        .iter()
+        .map(|b| (b.target.as_str(), b.conversation_key.as_str()))
+        .collect()
+}
+
+#[tokio::test]
+async fn test_initiated_routes_from_stored_bindings() {
+    let store = MemoryBindingStore::new();
+    store.save(&binding("C1", "agent-1", None)).await.unwrap();
+    store.save(&binding("C2", "agent-2", None)).await.unwrap();
+    let bindings = store.load().await.unwrap();
+
+    let event = AgentInitiatedEvent {
+        agent_id: "agent-2".to_string(),
+        agent_name: "Watcher".to_string(),
+        message_id: "m-1".to_string(),
+        content: "Build failed".to_string(),
+        thread_id: None,
+        timestamp: "2026-01-01T00:00:00Z".to_string(),
+    };
+    let targets: Vec<String> = route_initiated(&event, pairs(&bindings), Some("C-notify"))
+        .into_iter()
+        .map(|d| d.target)
+        .collect();
+    assert_eq!(targets, vec!["C2"]);
+
+    // After unbinding, the message falls back to the notifications chat
+    store.remove("C2").await.unwrap();
+    let bindings = store.load().await.unwrap();
+    let deliveries = route_initiated(&event, pairs(&bindings), Some("C-notify"));
+    assert_eq!(deliveries.len(), 1);
+    assert_eq!(deliveries[0].target, "C-notify");
+}
+
+/// Connect a bridge gateway session to `gateway`, retrying quickly
+async fn connect(gateway: &MockGateway) -> BridgeGateway {
+    let url = common::serve(gateway.clone()).await;
+    BridgeGateway::connect(&url, None)
+        .await
+        .unwrap()
+        .with_retry_policy(RetryPolicy {
+            max_attempts: 3,
+            initial_backoff: Duration::from_millis(10),
+            max_backoff: Duration::from_millis(10),
+        })
+}
+
+/// Forward a chat event to the gateway the way the bridges do: drop it if
+/// it was seen before, else send it keyed by its event ID
+async fn forward(
+    dedup: &MessageDeduplicator,
+    gateway: &mut BridgeGateway,
+    event_id: &str,
+) -> Option<ClientSendMessageResponse> {
+    if dedup.is_duplicate(event_id) {
+        return None;
+    }
+    let response = gateway
+        .send_message(
+            "agent-1".to_string(),
+            format!("text of {}", event_id),
+            event_id.to_string(),
+            None,
+            None,
+            None,
+            &RequestOverrides::default(),
+        )
+        .await
+        .unwrap();
+    Some(response)
+}
+
+#[tokio::test]
+async fn test_redelivered_event_reaches_gateway_once() {
+    let mock = MockGateway::default();
+    let mut gateway = connect(&mock).await;
+    let dedup = MessageDeduplicator::default();
+
+    assert!(forward(&dedup, &mut gateway, "$event-1").await.is_some());
+    assert!(forward(&dedup, &mut gateway, "$event-1").await.is_none());
+    assert!(forward(&dedup, &mut gateway, "$event-2").await.is_some());
+
+    assert_eq!(mock.sent_keys(), vec!["$event-1", "$event-2"]);
+}
+
+#[tokio::test]
+async fn test_retried_send_keeps_its_idempotency_key() {
+    let mock = MockGateway::default();
+    mock.unavailable.store(1, Ordering::SeqCst);
+    let mut gateway = connect(&mock).await;
+    let dedup = MessageDeduplicator::default();
+
+    // The gateway drops the first attempt; the retry carries the same key,
+    // so the gateway can tell it isn't a new message
+    let response = forward(&dedup, &mut gateway, "$event-1").await.unwrap();
+    assert_eq!(response.message_id, "$event-1");
+    assert_eq!(mock.sent_keys(), vec!["$event-1", "$event-1"]);
+
+    // A redelivery after the retry still isn't sent again
+    assert!(forward(&dedup, &mut gateway, "$event-1").await.is_none());
+    assert_eq!(mock.sent.lock().unwrap().len(), 2);
+}
+
+/// Hand a chat message to `dispatcher` the way the bridges do: keyed by its
+/// thread, doing `work_ms` of handling before it's sent to the gateway
+fn dispatch(
+    dispatcher: &OrderedDispatcher,
+    gateway: &Arc<Mutex<BridgeGateway>>,
+    thread: &str,
+    text: &str,
+    work_ms: u64,
+) {
+    let gateway = Arc::clone(gateway);
+    let thread_key = thread.to_string();
+    let text = text.to_string();
+    dispatcher.submit(thread, async move {
+        tokio::time::sleep(Duration::from_millis(work_ms)).await;
+        gateway
+            .lock()
+            .await
+            .send_message(
+                "agent-1".to_string(),
+                text.clone(),
+                format!("{}:{}", thread_key, text),
+                None,
+                None,
+                None,
+                &RequestOverrides::default(),
+            )
+            .await
+            .unwrap();
+    });
+}
+
+#[tokio::test]
+async fn test_messages_in_one_thread_reach_gateway_in_order() {
+    let mock = MockGateway::default();
+    let gateway = Arc::new(Mutex::new(connect(&mock).await));
+    let dispatcher = OrderedDispatcher::default();
+
+    // The first takes longer to handle than the second
+    dispatch(&dispatcher, &gateway, "C1:1700000000.000100", "first", 100);
+    dispatch(&dispatcher, &gateway, "C1:1700000000.000100", "second", 0);
+
+    common::eventually(|| mock.sent.lock().unwrap().len() == 2).await;
+    assert_eq!(mock.sent_contents(), vec!["first", "second"]);
+}
+
+#[tokio::test]
+async fn test_slow_thread_does_not_hold_up_others() {
+    let mock = MockGateway::default();
+    let gateway = Arc::new(Mutex::new(connect(&mock).await));
+    let dispatcher = OrderedDispatcher::default();
+
+    dispatch(&dispatcher, &gateway, "C1", "slow", 300);
+    dispatch(&dispatcher, &gateway, "C2", "quick", 0);
+
+    common::eventually(|| mock.sent_contents() == vec!["quick"]).await;
+    common::eventually(|| mock.sent_contents() == vec!["quick", "slow"]).await;
+    common::eventually(|| dispatcher.busy_keys() == 0).await;
+}
+
+#[tokio::test]
+async fn test_concurrency_cap_queues_other_threads() {
+    let mock = MockGateway::default();
+    let gateway = Arc::new(Mutex::new(connect(&mock).await));
+    let dispatcher = OrderedDispatcher::new(1);
+
+    // With one slot, the second thread waits for the first to finish
+    dispatch(&dispatcher, &gateway, "C1", "slow", 200);
+    dispatch(&dispatcher, &gateway, "C2", "quick", 0);
+
+    common::eventually(|| mock.sent.lock().unwrap().len() == 2).await;
+    assert_eq!(mock.sent_contents(), vec!["slow", "quick"]);
+}
@@ -0,0 +1,608 @@
+// ABOUTME: The checks behind `coven doctor`: config files, SSH key, gateway, agents, packs and backends.
+// ABOUTME: Each reads the machine through Environment and returns one Outcome with a remediation hint.
+
+use super::{Check, Environment, Outcome};
+use crate::BUILTIN_PACKS;
+use async_trait::async_trait;
+use coven_admin::client::{AuthClientService, AuthInterceptor};
+use coven_grpc::{ChannelConfig, ProbeStatus};
+use coven_proto::coven::client_service_client::ClientServiceClient;
+use coven_proto::coven::ListAgentsRequest;
+use std::time::Duration;
+
+/// How long the gateway checks wait to connect
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// `coven link` config exists and parses
+pub struct LinkConfigCheck;
+
+#[async_trait]
+impl Check for LinkConfigCheck {
+    fn name(&self) -> &'static str {
+        "link config"
+    }
+
+    async fn run(&self, env: &Environment) -> Outcome {
+        let path = env.link_config_path();
+        let content = match std::fs::read_to_string(&path) {
+            Ok(content) => content,
+            Err(_) => {
+                return Outcome::warn(
+                    format!("no link config at {}", path.display()),
+                    "run `coven link <gateway>` to link this device to a gateway",
+                )
+            }
+        };
+        let file = match coven_link::config::ConfigFile::parse(&content) {
+            Ok(file) => file,
+            Err(e) => {
+                return Outcome::fail(
+                    format!("cannot parse {}: {:#}", path.display(), e),
+                    "run `coven link <gateway>` again to rewrite it",
+                )
+            }
+        };
+        let profile = env.link_profile(&file);
+        match (file.resolve(profile.as_deref()), profile) {
+            (Ok(config), None) => Outcome::pass(format!(
+                "linked to {} as {}",
+                config.gateway, config.device_name
+            )),
+            (Ok(config), Some(profile)) => Outcome::pass(format!(
+                "profile '{}' linked to {} as {}",
+                profile, config.gateway, config.device_name
+            )),
+            (Err(e), _) => Outcome::fail(
+                format!("{}: {:#}", path.display(), e),
+                "pick another profile with `coven profile use`, or run `coven link <gateway>`",
+            ),
+        }
+    }
+}
+
+/// agent.toml exists and validates
+pub struct AgentConfigCheck;
+
+#[async_trait]
+impl Check for AgentConfigCheck {
+    fn name(&self) -> &'static str {
+        "agent config"
+    }
+
+    async fn run(&self, env: &Environment) -> Outcome {
+        let path = env.agent_config_path();
+        if !path.exists() {
+            return Outcome::warn(
+                format!("no agent config at {}", path.display()),
+                "run `coven agent new` to create one",
+            );
+        }
+        let mut report = coven_agent::agent_config::ValidationReport::default();
+        let agents_dir = env.agents_dir();
+        coven_agent::agent_config::validate_agent_config(&path, Some(&agents_dir), &mut report);
+        if report.is_ok() {
+            return Outcome::pass(format!("{} is valid", path.display()));
+        }
+        let problems: Vec<_> = report.issues.iter().map(|i| i.message.as_str()).collect();
+        Outcome::fail(
+            format!("{}: {}", path.display(), problems.join("; ")),
+            "fix the listed keys; `coven agent validate-config` shows every file checked",
+        )
+    }
+}
+
+/// swarm.toml parses, when there is one
+pub struct SwarmConfigCheck;
+
+#[async_trait]
+impl Check for SwarmConfigCheck {
+    fn name(&self) -> &'static str {
+        "swarm config"
+    }
+
+    async fn run(&self, env: &Environment) -> Outcome {
+        let path = env.swarm_config_path();
+        if !path.exists() {
+            return Outcome::pass(format!(
+                "no swarm config at {} (only needed for `coven swarm`)",
+                path.display()
+            ));
+        }
+        match coven_swarm_core::Config::load(&path) {
+            Ok(config) => {
+                let dir = match config.working_directory_expanded() {
+                    Ok(dir) => dir,
+                    Err(e) => {
+                        return Outcome::fail(
+                            format!("{:#}", e),
+                            format!(
+                                "set the variable or change working_directory in {}",
+                                path.display()
+                            ),
+                        )
+                    }
+                };
+                if dir.is_dir() {
+                    Outcome::pass(format!(
+                        "{} is valid (prefix '{}', backend {})",
+                        path.display(),
+                        config.prefix,
+                        config.default_backend
+                    ))
+                } else {
+                    Outcome::warn(
+                        format!("working_directory {} does not exist", dir.display()),
+                        format!(
+                            "create it or change working_directory in {}",
+                            path.display()
+                        ),
+                    )
+                }
+            }
+            Err(e) => Outcome::fail(format!("{:#}", e), "run `coven init` to rewrite it"),
+        }
+    }
+}
+
+/// The agent's SSH key exists, is private and loads
+pub struct SshKeyCheck;
+
+#[async_trait]
+impl Check for SshKeyCheck {
+    fn name(&self) -> &'static str {
+        "ssh key"
+    }
+
+    async fn run(&self, env: &Environment) -> Outcome {
+        let path = env.agent_key_path();
+        if !path.exists() {
+            return Outcome::warn(
+                format!("no agent key at {}", path.display()),
+                "`coven agent run` generates one on first start",
+            );
+        }
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            if let Ok(meta) = path.metadata() {
+                let mode = meta.permissions().mode() & 0o777;
+                if mode & 0o077 != 0 {
+                    return Outcome::fail(
+                        format!(
+                            "{} is readable by other users (mode {:o})",
+                            path.display(),
+                            mode
+                        ),
+                        format!("chmod 600 {}", path.display()),
+                    );
+                }
+            }
+        }
+
+        let key = match coven_ssh::load_key(&path) {
+            Ok(key) => key,
+            // Only the agent holds the passphrase
+            Err(coven_ssh::SshError::PassphraseRequired { .. }) => {
+                return Outcome::pass("key is encrypted; agents unlock it with `key_passphrase`")
+            }
+            Err(e) => {
+                return Outcome::fail(
+                    e.to_string(),
+                    format!(
+                        "remove {} to generate a new key, then register it again",
+                        path.display()
+                    ),
+                )
+            }
+        };
+        match coven_ssh::compute_fingerprint(key.public_key()) {
+            Ok(fingerprint) => Outcome::pass(format!("fingerprint {}", fingerprint)),
+            Err(e) => Outcome::fail(
+                e.to_string(),
+                "coven keys are ed25519; remove the key to generate a new one",
+            ),
+        }
+    }
+}
+
+/// ClientService client for the environment's gateway and token
+pub(crate) async fn connect(env: &Environment) -> Result<AuthClientService, String> {
+    let config = ChannelConfig::new(env.gateway_url())
+        .without_keep_alive()
+        .with_connect_timeout(CONNECT_TIMEOUT);
+    let channel = coven_grpc::create_channel(&config)
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(ClientServiceClient::with_interceptor(
+        channel,
+        AuthInterceptor::new(env.token()),
+    ))
+}
+
+/// The gateway is reachable and answers gRPC calls
+pub struct GatewayCheck;
+
+#[async_trait]
+impl Check for GatewayCheck {
+    fn name(&self) -> &'static str {
+        "gateway"
+    }
+
+    async fn run(&self, env: &Environment) -> Outcome {
+        let url = env.gateway_url();
+        let health = coven_grpc::probe_gateway_with_auth(
+            &url,
+            CONNECT_TIMEOUT,
+            AuthInterceptor::new(env.token()),
+        )
+        .await;
+        let detail = health.detail.clone().unwrap_or_default();
+        match health.status {
+            ProbeStatus::Healthy => {
+                let mut message = format!("{} answers", url);
+                if let (Some(component), Some(version)) = (&health.component, &health.version) {
+                    message.push_str(&format!(" as {} {}", component, version));
+                }
+                if let Some(latency) = health.latency {
+                    message.push_str(&format!(" in {} ms", latency.as_millis()));
+                }
+                Outcome::pass(message)
+            }
+            ProbeStatus::AuthRejected => Outcome::warn(
+                format!("{} rejected the token: {}", url, detail),
+                "run `coven link <gateway>` to get a new token",
+            ),
+            ProbeStatus::InvalidAddress => Outcome::fail(
+                format!("{} is not a gateway address: {}", url, detail),
+                "pass --gateway with an address like http://localhost:50051",
+            ),
+            ProbeStatus::DnsFailed => {
+                Outcome::fail(detail, "check the gateway's host name, or pass --gateway")
+            }
+            ProbeStatus::ConnectionRefused => Outcome::fail(
+                format!("{} refused the connection", url),
+                "start a local gateway with `coven serve`, or pass --gateway",
+            ),
+            ProbeStatus::TimedOut => Outcome::fail(
+                format!("{} did not answer: {}", url, detail),
+                "check the network and any firewall between here and the gateway",
+            ),
+            ProbeStatus::Unreachable => Outcome::fail(
+                format!("cannot reach {}: {}", url, detail),
+                "check that the gateway is running and the address is right",
+            ),
+        }
+    }
+}
+
+/// The gateway lists at least one connected agent
+pub struct AgentsVisibleCheck;
+
+#[async_trait]
+impl Check for AgentsVisibleCheck {
+    fn name(&self) -> &'static str {
+        "agents"
+    }
+
+    async fn run(&self, env: &Environment) -> Outcome {
+        let mut client = match connect(env).await {
+            Ok(client) => client,
+            Err(e) => {
+                return Outcome::fail(
+                    format!("cannot list agents: {}", e),
+                    "fix the gateway check first",
+                )
+            }
+        };
+        let agents = match client
+            .list_agents(ListAgentsRequest { workspace: None })
+            .await
+        {
+            Ok(response) => response.into_inner().agents,
+            Err(status) => {
+                return Outcome::fail(
+                    format!("cannot list agents: {}", status.message()),
+                    "check that your principal may list agents with `coven admin me`",
+                )
+            }
+        };
+        let connected: Vec<_> = agents
+            .iter()
+            .filter(|a| a.connected)
+            .map(|a| a.name.as_str())
+            .collect();
+        if connected.is_empty() {
+            return Outcome::warn(
+                "no agents connected",
+                "start one with `coven agent run` or `coven swarm start`",
+            );
+        }
+        Outcome::pass(format!(
+            "{} connected: {}",
+            connected.len(),
+            connected.join(", ")
+        ))
+    }
+}
+
+/// Every configured pack has its binary on PATH
+pub struct PackBinariesCheck;
+
+#[async_trait]
+impl Check for PackBinariesCheck {
+    fn name(&self) -> &'static str {
+        "packs"
+    }
+
+    async fn run(&self, env: &Environment) -> Outcome {
+        let mut packs: Vec<String> = std::fs::read_dir(&env.packs_dir)
+            .map(|entries| {
+                entries
+                    .filter_map(|e| e.ok())
+                    .filter(|e| e.path().is_dir())
+                    .filter_map(|e| e.file_name().into_string().ok())
+                    .collect()
+            })
+            .unwrap_or_default();
+        if packs.is_empty() {
+            return Outcome::pass("no packs configured");
+        }
+        packs.sort();
+
+        let missing: Vec<&str> = packs
+            .iter()
+            .map(|pack| pack_binary(pack))
+            .filter(|binary| env.find_binary(binary).is_none())
+            .collect();
+        if missing.is_empty() {
+            return Outcome::pass(format!(
+                "{} pack binaries on PATH: {}",
+                packs.len(),
+                packs.join(", ")
+            ));
+        }
+        Outcome::warn(
+            format!("not on PATH: {}", missing.join(", ")),
+            format!(
+                "build them (e.g. `cargo build --release -p {}`) and add them to PATH",
+                missing[0]
+            ),
+        )
+    }
+}
+
+/// Binary a pack runs from: the built-in pack's binary, else its own name
+fn pack_binary(pack: &str) -> &str {
+    BUILTIN_PACKS
+        .iter()
+        .find(|p| p.name == pack)
+        .map(|p| p.binary)
+        .unwrap_or(pack)
+}
+
+/// ANTHROPIC_API_KEY is set when a mux backend is configured
+pub struct ApiKeyCheck;
+
+#[async_trait]
+impl Check for ApiKeyCheck {
+    fn name(&self) -> &'static str {
+        "api key"
+    }
+
+    async fn run(&self, env: &Environment) -> Outcome {
+        if !env.backends().iter().any(|b| b == "mux") {
+            return Outcome::pass("not needed (no mux backend configured)");
+        }
+        if env.var("ANTHROPIC_API_KEY").is_some() {
+            return Outcome::pass("ANTHROPIC_API_KEY is set");
+        }
+        Outcome::fail(
+            "the mux backend needs ANTHROPIC_API_KEY, which is not set",
+            "export ANTHROPIC_API_KEY, or add it to a .env file",
+        )
+    }
+}
+
+/// The `claude` binary is on PATH when a cli backend is configured
+pub struct ClaudeCliCheck;
+
+#[async_trait]
+impl Check for ClaudeCliCheck {
+    fn name(&self) -> &'static str {
+        "claude cli"
+    }
+
+    async fn run(&self, env: &Environment) -> Outcome {
+        if !env.backends().iter().any(|b| b == "cli") {
+            return Outcome::pass("not needed (no cli backend configured)");
+        }
+        match env.find_binary("claude") {
+            Some(path) => Outcome::pass(format!("found {}", path.display())),
+            None => Outcome::fail(
+                "the cli backend needs the `claude` binary, which is not on PATH",
+                "install the Claude CLI, or switch the backend to mux",
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::doctor::Status;
+    use std::fs;
+    use std::path::Path;
+
+    fn env(dir: &Path) -> Environment {
+        let mut env = Environment::new(dir);
+        env.vars
+            .insert("PATH".to_string(), dir.join("bin").display().to_string());
+        env
+    }
+
+    #[cfg(unix)]
+    fn install_binary(dir: &Path, name: &str) {
+        use std::os::unix::fs::PermissionsExt;
+        let bin = dir.join("bin");
+        fs::create_dir_all(&bin).unwrap();
+        let path = bin.join(name);
+        fs::write(&path, "#!/bin/sh\n").unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o755)).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_link_config() {
+        let dir = tempfile::tempdir().unwrap();
+        let env = env(dir.path());
+        assert_eq!(LinkConfigCheck.run(&env).await.status, Status::Warn);
+
+        fs::write(env.link_config_path(), "gateway = ").unwrap();
+        assert_eq!(LinkConfigCheck.run(&env).await.status, Status::Fail);
+
+        fs::write(
+            env.link_config_path(),
+            "gateway = \"gw:50051\"\ntoken = \"t\"\nprincipal_id = \"p\"\ndevice_name = \"laptop\"\n",
+        )
+        .unwrap();
+        let outcome = LinkConfigCheck.run(&env).await;
+        assert_eq!(outcome, Outcome::pass("linked to gw:50051 as laptop"));
+
+        let mut env = env;
+        env.vars
+            .insert("COVEN_PROFILE".to_string(), "team".to_string());
+        assert_eq!(LinkConfigCheck.run(&env).await.status, Status::Fail);
+    }
+
+    #[tokio::test]
+    async fn test_agent_config() {
+        let dir = tempfile::tempdir().unwrap();
+        let env = env(dir.path());
+        assert_eq!(AgentConfigCheck.run(&env).await.status, Status::Warn);
+
+        fs::write(env.agent_config_path(), "backend = \"gpt\"\n").unwrap();
+        let outcome = AgentConfigCheck.run(&env).await;
+        assert_eq!(outcome.status, Status::Fail);
+        assert!(outcome.message.contains("unknown backend 'gpt'"));
+
+        fs::write(
+            env.agent_config_path(),
+            "name = \"bot\"\nbackend = \"mux\"\n",
+        )
+        .unwrap();
+        assert_eq!(AgentConfigCheck.run(&env).await.status, Status::Pass);
+    }
+
+    #[tokio::test]
+    async fn test_swarm_config() {
+        let dir = tempfile::tempdir().unwrap();
+        let env = env(dir.path());
+        assert_eq!(SwarmConfigCheck.run(&env).await.status, Status::Pass);
+
+        fs::write(env.swarm_config_path(), "prefix = 1\n").unwrap();
+        assert_eq!(SwarmConfigCheck.run(&env).await.status, Status::Fail);
+
+        fs::write(
+            env.swarm_config_path(),
+            format!(
+                "prefix = \"home\"\nworking_directory = \"{}\"\n",
+                dir.path().display()
+            ),
+        )
+        .unwrap();
+        assert_eq!(SwarmConfigCheck.run(&env).await.status, Status::Pass);
+    }
+
+    #[tokio::test]
+    async fn test_ssh_key() {
+        let dir = tempfile::tempdir().unwrap();
+        let env = env(dir.path());
+        assert_eq!(SshKeyCheck.run(&env).await.status, Status::Warn);
+
+        coven_ssh::generate_key(&env.agent_key_path()).unwrap();
+        let outcome = SshKeyCheck.run(&env).await;
+        assert_eq!(outcome.status, Status::Pass, "{:?}", outcome);
+        assert!(outcome.message.starts_with("fingerprint "));
+
+        coven_ssh::generate_key_encrypted(&env.agent_key_path(), "passphrase").unwrap();
+        let outcome = SshKeyCheck.run(&env).await;
+        assert_eq!(outcome.status, Status::Pass, "{:?}", outcome);
+        assert!(outcome.message.contains("encrypted"));
+
+        fs::write(env.agent_key_path(), "not a key").unwrap();
+        assert_eq!(SshKeyCheck.run(&env).await.status, Status::Fail);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_ssh_key_permissions() {
+        use std::os::unix::fs::PermissionsExt;
+        let dir = tempfile::tempdir().unwrap();
+        let env = env(dir.path());
+        coven_ssh::generate_key(&env.agent_key_path()).unwrap();
+        fs::set_permissions(env.agent_key_path(), fs::Permissions::from_mode(0o644)).unwrap();
+
+        let outcome = SshKeyCheck.run(&env).await;
+        assert_eq!(outcome.status, Status::Fail);
+        assert!(outcome.hint.unwrap().starts_with("chmod 600"));
+    }
+
+    #[tokio::test]
+    async fn test_gateway_unreachable() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut env = env(dir.path());
+        // Nothing listens on port 1
+        env.gateway = Some("http://127.0.0.1:1".to_string());
+        let outcome = GatewayCheck.run(&env).await;
+        assert_eq!(outcome.status, Status::Fail);
+        assert_eq!(outcome.message, "http://127.0.0.1:1 refused the connection");
+        assert_eq!(AgentsVisibleCheck.run(&env).await.status, Status::Fail);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_pack_binaries() {
+        let dir = tempfile::tempdir().unwrap();
+        let env = env(dir.path());
+        assert_eq!(PackBinariesCheck.run(&env).await.status, Status::Pass);
+
+        fs::create_dir_all(env.packs_dir.join("productivity")).unwrap();
+        let outcome = PackBinariesCheck.run(&env).await;
+        assert_eq!(outcome.status, Status::Warn);
+        assert_eq!(outcome.message, "not on PATH: productivity-pack");
+
+        install_binary(dir.path(), "productivity-pack");
+        assert_eq!(PackBinariesCheck.run(&env).await.status, Status::Pass);
+    }
+
+    #[tokio::test]
+    async fn test_api_key_only_needed_for_mux() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut env = env(dir.path());
+        fs::write(env.agent_config_path(), "backend = \"cli\"\n").unwrap();
+        assert_eq!(ApiKeyCheck.run(&env).await.status, Status::Pass);
+
+        fs::write(env.agent_config_path(), "backend = \"mux\"\n").unwrap();
+        assert_eq!(ApiKeyCheck.run(&env).await.status, Status::Fail);
+
+        env.vars
+            .insert("ANTHROPIC_API_KEY".to_string(), "sk-test".to_string());
+        assert_eq!(ApiKeyCheck.run(&env).await.status, Status::Pass);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_claude_cli_only_needed_for_cli() {
+        let dir = tempfile::tempdir().unwrap();
+        let env = env(dir.path());
+        fs::write(env.agent_config_path(), "backend = \"mux\"\n").unwrap();
+        assert_eq!(ClaudeCliCheck.run(&env).await.status, Status::Pass);
+
+        // The agent defaults to the cli backend
+        fs::write(env.agent_config_path(), "name = \"bot\"\n").unwrap();
+        assert_eq!(ClaudeCliCheck.run(&env).await.status, Status::Fail);
+
+        install_binary(dir.path(), "claude");
+        assert_eq!(ClaudeCliCheck.run(&env).await.status, Status::Pass);
+    }
+}
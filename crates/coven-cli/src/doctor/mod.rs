@@ -0,0 +1,508 @@
+// ABOUTME: `coven doctor` harness: runs environment checks and reports pass/warn/fail.
+// ABOUTME: Checks read everything through Environment so tests can point them at fixtures.
+
+pub mod checks;
+
+use async_trait::async_trait;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+
+/// Address used when nothing else names a gateway
+pub const DEFAULT_GATEWAY: &str = "http://127.0.0.1:50051";
+
+/// How a check came out
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Status {
+    Pass,
+    Warn,
+    Fail,
+}
+
+impl Status {
+    fn symbol(self) -> &'static str {
+        match self {
+            Status::Pass => "✓",
+            Status::Warn => "⚠",
+            Status::Fail => "✗",
+        }
+    }
+}
+
+/// What a check found, with a hint on how to fix anything short of a pass
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Outcome {
+    pub status: Status,
+    pub message: String,
+    pub hint: Option<String>,
+}
+
+impl Outcome {
+    pub fn pass(message: impl Into<String>) -> Self {
+        Self {
+            status: Status::Pass,
+            message: message.into(),
+            hint: None,
+        }
+    }
+
+    pub fn warn(message: impl Into<String>, hint: impl Into<String>) -> Self {
+        Self {
+            status: Status::Warn,
+            message: message.into(),
+            hint: Some(hint.into()),
+        }
+    }
+
+    pub fn fail(message: impl Into<String>, hint: impl Into<String>) -> Self {
+        Self {
+            status: Status::Fail,
+            message: message.into(),
+            hint: Some(hint.into()),
+        }
+    }
+}
+
+/// One diagnostic. Implement this and add it to a `Doctor` to extend
+/// `coven doctor`.
+#[async_trait]
+pub trait Check: Send + Sync {
+    /// Short name shown next to the result
+    fn name(&self) -> &'static str;
+
+    async fn run(&self, env: &Environment) -> Outcome;
+}
+
+/// Where the checks look: the coven config directory, the environment
+/// variables, and the gateway to contact.
+#[derive(Debug, Clone)]
+pub struct Environment {
+    /// Directory holding config.toml, agent.toml, swarm.toml and keys
+    /// (~/.config/coven)
+    pub config_dir: PathBuf,
+    /// Directory with one subdirectory per configured pack
+    pub packs_dir: PathBuf,
+    /// Agent config to check instead of `config_dir/agent.toml`
+    pub agent_config: Option<PathBuf>,
+    /// Gateway to contact instead of the configured one
+    pub gateway: Option<String>,
+    /// Link config profile chosen with `--profile`
+    pub profile: Option<String>,
+    /// Environment variables, including PATH
+    pub vars: HashMap<String, String>,
+}
+
+impl Environment {
+    /// Look in `config_dir` with no environment variables set
+    pub fn new(config_dir: impl Into<PathBuf>) -> Self {
+        let config_dir = config_dir.into();
+        Self {
+            packs_dir: config_dir.join("packs"),
+            config_dir,
+            agent_config: None,
+            gateway: None,
+            profile: None,
+            vars: HashMap::new(),
+        }
+    }
+
+    /// The real environment of this process. `gateway` overrides the
+    /// configured gateway address.
+    pub fn from_system(gateway: Option<String>) -> Self {
+        let config_dir = coven_ssh::xdg_config_dir().unwrap_or_else(|| PathBuf::from(".coven"));
+        let mut env = Self::new(config_dir);
+        // `coven pack` looks for packs under the platform config directory
+        if let Some(dir) = dirs::config_dir() {
+            env.packs_dir = dir.join("coven").join("packs");
+        }
+        env.agent_config = coven_agent::agent_config::discover_config_path(None);
+        env.gateway = gateway;
+        env.profile = coven_link::config::selected_profile().map(str::to_string);
+        env.vars = std::env::vars().collect();
+        env
+    }
+
+    pub fn var(&self, name: &str) -> Option<&str> {
+        self.vars
+            .get(name)
+            .map(String::as_str)
+            .filter(|v| !v.is_empty())
+    }
+
+    /// Config written by `coven link`
+    pub fn link_config_path(&self) -> PathBuf {
+        self.config_dir.join("config.toml")
+    }
+
+    pub fn agent_config_path(&self) -> PathBuf {
+        self.agent_config
+            .clone()
+            .unwrap_or_else(|| self.config_dir.join("agent.toml"))
+    }
+
+    pub fn agents_dir(&self) -> PathBuf {
+        self.config_dir.join("agents")
+    }
+
+    pub fn swarm_config_path(&self) -> PathBuf {
+        self.config_dir.join("swarm.toml")
+    }
+
+    pub fn agent_key_path(&self) -> PathBuf {
+        self.config_dir.join("agent_key")
+    }
+
+    /// The `coven link` config for the active profile, if there is one and
+    /// it parses
+    pub fn link_config(&self) -> Option<coven_link::config::CovenConfig> {
+        let content = std::fs::read_to_string(self.link_config_path()).ok()?;
+        let file = coven_link::config::ConfigFile::parse(&content).ok()?;
+        file.resolve(self.link_profile(&file).as_deref()).ok()
+    }
+
+    /// Profile to read from the link config: `--profile`, `COVEN_PROFILE`,
+    /// then the file's default
+    pub fn link_profile(&self, file: &coven_link::config::ConfigFile) -> Option<String> {
+        file.profile_name(
+            self.profile.as_deref(),
+            self.var(coven_link::config::PROFILE_ENV),
+        )
+    }
+
+    /// The agent config with any `agent = "name"` reference resolved
+    pub fn agent_table(&self) -> Option<toml::Table> {
+        let content = std::fs::read_to_string(self.agent_config_path()).ok()?;
+        let table: toml::Table = toml::from_str(&content).ok()?;
+        coven_agent::agent_config::resolve_agent_reference(table, &self.agents_dir()).ok()
+    }
+
+    pub fn swarm_config(&self) -> Option<coven_swarm_core::Config> {
+        let path = self.swarm_config_path();
+        if !path.exists() {
+            return None;
+        }
+        coven_swarm_core::Config::load(&path).ok()
+    }
+
+    /// Gateway address: the override, then `COVEN_GATEWAY_GRPC`, the link
+    /// config, the agent config's `server`, and finally the local default.
+    pub fn gateway_url(&self) -> String {
+        self.gateway
+            .clone()
+            .or_else(|| self.var("COVEN_GATEWAY_GRPC").map(str::to_string))
+            .or_else(|| self.link_config().map(|c| c.gateway))
+            .or_else(|| {
+                self.agent_table()
+                    .and_then(|t| t.get("server")?.as_str().map(str::to_string))
+            })
+            .map(|g| coven_admin::normalize_gateway(&g))
+            .unwrap_or_else(|| DEFAULT_GATEWAY.to_string())
+    }
+
+    /// Token for the gateway: `COVEN_TOKEN`, then the link config
+    pub fn token(&self) -> Option<String> {
+        self.var("COVEN_TOKEN")
+            .map(str::to_string)
+            .or_else(|| self.link_config().map(|c| c.token))
+            .filter(|t| !t.is_empty())
+    }
+
+    /// Backends the agent and swarm configs use, as "mux", "cli", "acp"
+    /// and so on. The swarm's "direct" backend is reported as "cli".
+    pub fn backends(&self) -> Vec<String> {
+        let mut backends = Vec::new();
+        if let Some(table) = self.agent_table() {
+            // coven agent run defaults to the CLI backend
+            let backend = table
+                .get("backend")
+                .and_then(|v| v.as_str())
+                .unwrap_or("cli");
+            backends.push(backend.to_string());
+        }
+        if let Some(config) = self.swarm_config() {
+            backends.push(config.default_backend.to_string());
+            backends.extend(config.workspace_backends.values().cloned());
+        }
+        for backend in &mut backends {
+            if backend == "direct" {
+                *backend = "cli".to_string();
+            }
+        }
+        backends.sort();
+        backends.dedup();
+        backends
+    }
+
+    /// First file named `name` in a PATH directory
+    pub fn find_binary(&self, name: &str) -> Option<PathBuf> {
+        let path = OsString::from(self.vars.get("PATH")?);
+        std::env::split_paths(&path)
+            .map(|dir| dir.join(name))
+            .find(|candidate| is_executable(candidate))
+    }
+}
+
+#[cfg(unix)]
+fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    path.metadata()
+        .is_ok_and(|m| m.is_file() && m.permissions().mode() & 0o111 != 0)
+}
+
+#[cfg(not(unix))]
+fn is_executable(path: &Path) -> bool {
+    path.is_file() || path.with_extension("exe").is_file()
+}
+
+/// One check's result
+#[derive(Debug, Clone, Serialize)]
+pub struct CheckResult {
+    pub name: String,
+    pub status: Status,
+    pub message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hint: Option<String>,
+}
+
+/// Results of a doctor run, in check order
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Report {
+    pub checks: Vec<CheckResult>,
+}
+
+impl Report {
+    pub fn count(&self, status: Status) -> usize {
+        self.checks.iter().filter(|c| c.status == status).count()
+    }
+
+    /// No check failed. Warnings don't count against a run.
+    pub fn passed(&self) -> bool {
+        self.count(Status::Fail) == 0
+    }
+
+    /// One line per check, hints indented below, and a summary
+    pub fn render_text(&self) -> String {
+        let width = self.checks.iter().map(|c| c.name.len()).max().unwrap_or(0);
+        let mut out = String::new();
+        for check in &self.checks {
+            out.push_str(&format!(
+                "{} {:width$}  {}\n",
+                check.status.symbol(),
+                check.name,
+                check.message,
+                width = width
+            ));
+            if let Some(hint) = &check.hint {
+                out.push_str(&format!("  {:width$}  → {}\n", "", hint, width = width));
+            }
+        }
+        out.push_str(&format!(
+            "\n{} passed, {} warning(s), {} failed\n",
+            self.count(Status::Pass),
+            self.count(Status::Warn),
+            self.count(Status::Fail)
+        ));
+        out
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("report serializes")
+    }
+}
+
+/// An ordered list of checks
+#[derive(Default)]
+pub struct Doctor {
+    checks: Vec<Box<dyn Check>>,
+}
+
+impl Doctor {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The checks `coven doctor` runs
+    pub fn standard() -> Self {
+        Self::new()
+            .with(checks::LinkConfigCheck)
+            .with(checks::AgentConfigCheck)
+            .with(checks::SwarmConfigCheck)
+            .with(checks::SshKeyCheck)
+            .with(checks::GatewayCheck)
+            .with(checks::AgentsVisibleCheck)
+            .with(checks::PackBinariesCheck)
+            .with(checks::ApiKeyCheck)
+            .with(checks::ClaudeCliCheck)
+    }
+
+    pub fn with(mut self, check: impl Check + 'static) -> Self {
+        self.checks.push(Box::new(check));
+        self
+    }
+
+    /// Run every check in order
+    pub async fn run(&self, env: &Environment) -> Report {
+        let mut report = Report::default();
+        for check in &self.checks {
+            let outcome = check.run(env).await;
+            report.checks.push(CheckResult {
+                name: check.name().to_string(),
+                status: outcome.status,
+                message: outcome.message,
+                hint: outcome.hint,
+            });
+        }
+        report
+    }
+}
+
+/// Run the standard checks against this machine, printing text or JSON.
+/// Fails when any check failed.
+pub async fn run(gateway: Option<String>, json: bool) -> anyhow::Result<()> {
+    let env = Environment::from_system(gateway);
+    let report = Doctor::standard().run(&env).await;
+    if json {
+        println!("{}", report.to_json());
+    } else {
+        print!("{}", report.render_text());
+    }
+    if !report.passed() {
+        anyhow::bail!("{} check(s) failed", report.count(Status::Fail));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Fixed(&'static str, Outcome);
+
+    #[async_trait]
+    impl Check for Fixed {
+        fn name(&self) -> &'static str {
+            self.0
+        }
+
+        async fn run(&self, _env: &Environment) -> Outcome {
+            self.1.clone()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_doctor_runs_checks_in_order() {
+        let dir = tempfile::tempdir().unwrap();
+        let report = Doctor::new()
+            .with(Fixed("first", Outcome::pass("ok")))
+            .with(Fixed("second", Outcome::warn("meh", "try harder")))
+            .run(&Environment::new(dir.path()))
+            .await;
+
+        let names: Vec<_> = report.checks.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, ["first", "second"]);
+        assert_eq!(report.checks[1].hint.as_deref(), Some("try harder"));
+        assert!(report.passed(), "warnings don't fail a run");
+    }
+
+    #[tokio::test]
+    async fn test_report_fails_on_any_failure() {
+        let dir = tempfile::tempdir().unwrap();
+        let report = Doctor::new()
+            .with(Fixed("good", Outcome::pass("ok")))
+            .with(Fixed("bad", Outcome::fail("broken", "fix it")))
+            .run(&Environment::new(dir.path()))
+            .await;
+
+        assert!(!report.passed());
+        assert_eq!(report.count(Status::Pass), 1);
+        assert_eq!(report.count(Status::Fail), 1);
+    }
+
+    #[test]
+    fn test_render_text() {
+        let report = Report {
+            checks: vec![
+                CheckResult {
+                    name: "gateway".to_string(),
+                    status: Status::Pass,
+                    message: "reachable".to_string(),
+                    hint: None,
+                },
+                CheckResult {
+                    name: "ssh key".to_string(),
+                    status: Status::Fail,
+                    message: "missing".to_string(),
+                    hint: Some("run coven agent run".to_string()),
+                },
+            ],
+        };
+
+        assert_eq!(
+            report.render_text(),
+            "✓ gateway  reachable\n\
+             ✗ ssh key  missing\n\
+             \x20          → run coven agent run\n\
+             \n1 passed, 0 warning(s), 1 failed\n"
+        );
+    }
+
+    #[test]
+    fn test_json_output() {
+        let report = Report {
+            checks: vec![CheckResult {
+                name: "packs".to_string(),
+                status: Status::Warn,
+                message: "none".to_string(),
+                hint: None,
+            }],
+        };
+        let json: serde_json::Value = serde_json::from_str(&report.to_json()).unwrap();
+        assert_eq!(json["checks"][0]["status"], "warn");
+        assert_eq!(json["checks"][0]["name"], "packs");
+        assert!(json["checks"][0].get("hint").is_none());
+    }
+
+    #[test]
+    fn test_gateway_url_precedence() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut env = Environment::new(dir.path());
+        assert_eq!(env.gateway_url(), DEFAULT_GATEWAY);
+
+        std::fs::write(
+            dir.path().join("agent.toml"),
+            "server = \"http://agent-host:50051\"\n",
+        )
+        .unwrap();
+        assert_eq!(env.gateway_url(), "http://agent-host:50051");
+
+        std::fs::write(
+            env.link_config_path(),
+            "gateway = \"linked:50051\"\ntoken = \"t\"\nprincipal_id = \"p\"\ndevice_name = \"d\"\n",
+        )
+        .unwrap();
+        assert_eq!(env.gateway_url(), "http://linked:50051");
+        assert_eq!(env.token().as_deref(), Some("t"));
+
+        env.gateway = Some("https://explicit".to_string());
+        assert_eq!(env.gateway_url(), "https://explicit");
+    }
+
+    #[test]
+    fn test_backends_merge_agent_and_swarm() {
+        let dir = tempfile::tempdir().unwrap();
+        let env = Environment::new(dir.path());
+        assert!(env.backends().is_empty());
+
+        std::fs::write(dir.path().join("agent.toml"), "name = \"bot\"\n").unwrap();
+        assert_eq!(env.backends(), ["cli"]);
+
+        std::fs::write(
+            env.swarm_config_path(),
+            "prefix = \"home\"\nworking_directory = \"/tmp\"\ndefault_backend = \"direct\"\n\n[workspace_backends]\nresearch = \"mux\"\n",
+        )
+        .unwrap();
+        assert_eq!(env.backends(), ["cli", "mux"]);
+    }
+}
@@ -16,14 +16,28 @@
 //! │   ├── stop                      # Stop supervisor
 //! │   └── status                    # Show running agents
 //! ├── agent
-//! │   ├── run                       # Run individual agent
-//! │   └── new                       # Create agent config
+//! │   ├── run [--detach]            # Run individual agent, or in the background
+//! │   ├── list                      # Show background agents
+//! │   ├── stop <id>                 # Stop a background agent
+//! │   ├── logs <id> [-f]            # Show or follow a background agent's log
+//! │   ├── new                       # Create agent config
+//! │   └── show-prompt               # Print the assembled system prompt
 //! ├── chat                          # Open TUI
+//! │   └── export <agent>            # Export history to Markdown or JSON
 //! ├── human                         # Act as human agent
 //! ├── pack
 //! │   ├── list                      # List available packs
-//! │   ├── install <pack>            # Install a pack
-//! │   └── run <pack>                # Run pack directly
+//! │   ├── install <git-url>         # Build and install a pack (or --path <dir>)
+//! │   ├── uninstall <name>          # Remove an installed pack
+//! │   ├── upgrade <name>            # Rebuild a pack from its recorded source
+//! │   ├── run <pack> [--daemon]     # Run pack directly or in the background
+//! │   ├── status                    # Show background packs
+//! │   └── stop <name>               # Stop a background pack
+//! ├── profile
+//! │   ├── list                      # List gateway profiles
+//! │   └── use <name>                # Set the default profile
+//! ├── status [--json]               # Gateway, link, swarm, agents and packs at a glance
+//! ├── doctor                        # Diagnose config, keys, gateway and backends
 //! └── version                       # Show version info
 //! ```
 //!
@@ -46,5 +60,37 @@
 //! coven pack list
 //! ```
 
+pub mod doctor;
+pub mod pack_daemon;
+pub mod pack_install;
+pub mod status;
+pub mod store;
+
 /// Version of the coven CLI
 pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// Built-in pack definitions.
+pub struct BuiltinPack {
+    pub name: &'static str,
+    pub binary: &'static str,
+    pub description: &'static str,
+}
+
+/// List of built-in packs that ship with coven.
+pub const BUILTIN_PACKS: &[BuiltinPack] = &[
+    BuiltinPack {
+        name: "productivity",
+        binary: "productivity-pack",
+        description: "Task management and productivity tools (todo, notes)",
+    },
+    BuiltinPack {
+        name: "mcp-bridge",
+        binary: "mcp-bridge-pack",
+        description: "Bridge to expose any MCP server as coven tools",
+    },
+    BuiltinPack {
+        name: "test",
+        binary: "test-pack",
+        description: "Echo tools for testing pack connectivity",
+    },
+];
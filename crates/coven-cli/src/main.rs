@@ -2,7 +2,10 @@
 // ABOUTME: Dispatches to swarm, agent, chat, pack, admin, link, bridge and other subcommands.
 
 use anyhow::{Context, Result};
-use clap::{Parser, Subcommand};
+use clap::{CommandFactory, Parser, Subcommand, ValueHint};
+use coven_cli::pack_daemon;
+use coven_cli::pack_install::{self, PackDirs, PackSource};
+use coven_cli::BUILTIN_PACKS;
 use std::path::PathBuf;
 use std::process::Command;
 
@@ -16,6 +19,18 @@ const DEFAULT_SERVER: &str = "http://127.0.0.1:50051";
 struct Cli {
     #[command(subcommand)]
     command: Commands,
+
+    /// Only log warnings and errors
+    #[arg(short, long, global = true, conflicts_with = "verbose")]
+    quiet: bool,
+
+    /// Log more detail (-v for debug, -vv for trace)
+    #[arg(short, long, action = clap::ArgAction::Count, global = true)]
+    verbose: u8,
+
+    /// Gateway profile from config.toml (default: COVEN_PROFILE, then `coven profile use`)
+    #[arg(long, global = true)]
+    profile: Option<String>,
 }
 
 #[derive(Subcommand)]
@@ -23,29 +38,101 @@ enum Commands {
     /// First-time setup wizard
     Init,
 
-    /// Run a local gateway server (super-trusted mode, no auth)
+    /// Run a local gateway server (super-trusted mode, no auth unless --roles is given)
     Serve {
-        /// gRPC listen address
+        /// gRPC listen address, or unix:///path/to.sock for a Unix domain socket
         #[arg(long, default_value = "127.0.0.1:50051")]
         grpc_addr: String,
 
+        /// Permissions for the Unix socket file, in octal
+        #[arg(long, default_value = "600", value_parser = parse_octal_mode)]
+        socket_mode: u32,
+
         /// SQLite database path
-        #[arg(long)]
+        #[arg(long, value_hint = ValueHint::FilePath)]
         db: Option<PathBuf>,
+
+        /// How long a database write waits on a lock before failing, in milliseconds
+        #[arg(long, default_value = "5000")]
+        db_busy_timeout_ms: u64,
+
+        /// Master key for pack secrets, created if missing (default: secrets.key next to the database)
+        #[arg(long, value_hint = ValueHint::FilePath)]
+        secrets_key: Option<PathBuf>,
+
+        /// Queue messages for offline agents and deliver them on reconnect
+        #[arg(long)]
+        dead_letter: bool,
+
+        /// How long queued messages stay deliverable, in seconds
+        #[arg(long, default_value = "86400", requires = "dead_letter")]
+        dead_letter_ttl: u64,
+
+        /// Maximum queued messages per agent (oldest are dropped first; 0 = no limit)
+        #[arg(long, default_value = "100", requires = "dead_letter")]
+        dead_letter_max: usize,
+
+        /// Largest gRPC message the gateway accepts or sends, in MiB
+        #[arg(long, default_value = "16")]
+        max_message_size_mb: usize,
+
+        /// Let `coven admin tail` watch all traffic, content included
+        #[arg(long)]
+        enable_tail: bool,
+
+        /// Webhook to POST push notifications to, for registered mobile devices
+        #[arg(long, value_hint = ValueHint::Url)]
+        push_webhook: Option<String>,
+
+        /// TOML file of principals' roles; admin RPCs and gated tool approvals then require them
+        #[arg(long, value_hint = ValueHint::FilePath)]
+        roles: Option<PathBuf>,
+
+        /// TOML file of content filter rules; blocked messages get a policy reply instead of reaching the agent
+        #[arg(long, value_hint = ValueHint::FilePath)]
+        moderation: Option<PathBuf>,
+
+        /// Regex; `coven admin export` leaves out tool inputs matching it (repeatable)
+        #[arg(long = "export-redact", value_name = "REGEX")]
+        export_redactions: Vec<String>,
+
+        /// TOML file of `[[webhook]]` tables; each is POSTed the gateway events it subscribes to
+        #[arg(long, value_hint = ValueHint::FilePath)]
+        webhooks: Option<PathBuf>,
+
+        /// Days recorded tool calls and token usage are kept for `coven admin export`,
+        /// and resolved tool approvals for their history
+        #[arg(long, default_value = "30")]
+        activity_retention_days: u64,
     },
 
     /// Link this device to a coven-gateway
+    #[command(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
     Link {
         /// Gateway URL (e.g., https://coven.example.com or http://localhost:8080)
-        gateway: String,
+        #[arg(value_hint = ValueHint::Url, required = true)]
+        gateway: Option<String>,
 
         /// Device name (defaults to hostname)
         #[arg(long, short = 'n')]
         name: Option<String>,
 
         /// Path to SSH key (defaults to ~/.config/coven/device_key)
-        #[arg(long)]
+        #[arg(long, value_hint = ValueHint::FilePath)]
         key: Option<String>,
+
+        /// Where the key's passphrase comes from: env:NAME, cmd:COMMAND,
+        /// prompt or pass:PASSPHRASE. A new key is written encrypted with it.
+        #[arg(long, env = "COVEN_SSH_KEY_PASSPHRASE", value_name = "SOURCE")]
+        key_passphrase: Option<String>,
+
+        /// Link with a one-time code (shown with a QR code) for an admin to
+        /// approve, instead of a code entered in the gateway web UI
+        #[arg(long)]
+        pair: bool,
+
+        #[command(subcommand)]
+        command: Option<LinkCommands>,
     },
 
     /// Swarm management commands
@@ -57,16 +144,20 @@ enum Commands {
     Agent(AgentCommands),
 
     /// Open the TUI chat interface
+    #[command(args_conflicts_with_subcommands = true)]
     Chat {
         /// Agent to start chatting with (skips picker)
         #[arg(short, long)]
         agent: Option<String>,
+
+        #[command(subcommand)]
+        command: Option<ChatCommands>,
     },
 
     /// Act as a human agent in the coven gateway
     Human {
         /// Gateway server URL
-        #[arg(short, long)]
+        #[arg(short, long, value_hint = ValueHint::Url)]
         gateway: Option<String>,
 
         /// Agent name (defaults to hostname)
@@ -76,22 +167,90 @@ enum Commands {
         /// Agent ID (auto-generated if not provided)
         #[arg(long)]
         id: Option<String>,
+
+        /// Nudge when a request has gone unanswered this many seconds (0 disables)
+        #[arg(long, value_name = "SECS", default_value_t = 120)]
+        nudge_after: u64,
+
+        /// Ring the terminal bell when the nudge appears
+        #[arg(long)]
+        bell: bool,
     },
 
     /// Pack management commands
     #[command(subcommand)]
     Pack(PackCommands),
 
-    /// Admin commands for gateway management
+    /// Manage gateway profiles in config.toml
     #[command(subcommand)]
-    Admin(AdminCommands),
+    Profile(ProfileCommands),
+
+    /// Admin commands for gateway management
+    Admin {
+        /// Output format: colored text, JSON for scripts, or aligned columns
+        #[arg(long, global = true, value_enum, default_value_t = coven_admin::OutputFormat::Text)]
+        output: coven_admin::OutputFormat,
+
+        #[command(subcommand)]
+        command: AdminCommands,
+    },
 
     /// Bridge commands for external services
     #[command(subcommand)]
     Bridge(BridgeCommands),
 
+    /// Show the gateway, device link, swarm, agents and packs at a glance
+    Status {
+        /// Gateway to query (default: COVEN_GATEWAY_GRPC, then config)
+        #[arg(long, value_hint = ValueHint::Url)]
+        gateway: Option<String>,
+
+        /// Print the overview as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Check config files, keys, gateway, agents, packs and backends
+    Doctor {
+        /// Gateway to check (default: COVEN_GATEWAY_GRPC, then config)
+        #[arg(long, value_hint = ValueHint::Url)]
+        gateway: Option<String>,
+
+        /// Print results as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Print a shell completion script
+    ///
+    /// Bash: `coven completion bash > ~/.local/share/bash-completion/completions/coven`.
+    /// Zsh: `coven completion zsh > ~/.zfunc/_coven`, with ~/.zfunc on fpath.
+    /// Fish: `coven completion fish > ~/.config/fish/completions/coven.fish`.
+    Completion {
+        /// Shell to generate completions for
+        #[arg(value_enum)]
+        shell: clap_complete::Shell,
+    },
+
+    /// Write man pages, for packaging
+    #[command(hide = true)]
+    Mangen {
+        /// Directory to write coven.1 and a page per subcommand to
+        /// (default: print coven.1 to stdout)
+        #[arg(long, value_hint = ValueHint::DirPath)]
+        out: Option<PathBuf>,
+    },
+
     /// Show version information
-    Version,
+    Version {
+        /// Also query the gateway's version and check compatibility
+        #[arg(long)]
+        check: bool,
+
+        /// Gateway to check (default: COVEN_GATEWAY_GRPC, then config)
+        #[arg(long, requires = "check", value_hint = ValueHint::Url)]
+        gateway: Option<String>,
+    },
 }
 
 #[derive(Subcommand)]
@@ -102,12 +261,16 @@ enum SwarmCommands {
     /// Start the supervisor daemon
     Start {
         /// Path to configuration file
-        #[arg(long)]
+        #[arg(long, value_hint = ValueHint::FilePath)]
         config: Option<PathBuf>,
 
         /// Run in headless mode (no TUI)
         #[arg(long)]
         headless: bool,
+
+        /// Draw the TUI without colors (also set by NO_COLOR)
+        #[arg(long)]
+        no_color: bool,
     },
 
     /// Stop the supervisor daemon
@@ -128,17 +291,62 @@ enum SwarmCommands {
         dispatch_mode: bool,
 
         /// Path to configuration file
-        #[arg(long)]
+        #[arg(long, value_hint = ValueHint::FilePath)]
         config: Option<PathBuf>,
     },
 }
 
+#[derive(Subcommand)]
+enum LinkCommands {
+    /// Replace this device's SSH key; the gateway accepts the old key for a grace period
+    Rotate {
+        /// Seconds the old key keeps working (defaults to the gateway's, a day for coven serve)
+        #[arg(long, value_name = "SECS")]
+        grace_period: Option<u32>,
+    },
+    /// List the gateways this device is linked to; * marks the active one
+    List,
+    /// Show the gateway in use and when its token expires
+    Status,
+    /// Use another linked gateway
+    Switch {
+        /// Link name, as shown by `coven link list`
+        name: String,
+    },
+    /// Forget a linked gateway
+    Remove {
+        /// Link name, as shown by `coven link list`
+        name: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum ChatCommands {
+    /// Export an agent's conversation history to Markdown or JSON
+    Export {
+        /// Agent ID or name
+        agent: String,
+
+        /// Output format
+        #[arg(long, value_enum, default_value = "md")]
+        format: coven_tui_v2::cli::export::ExportFormat,
+
+        /// File to write (default: stdout)
+        #[arg(long, value_hint = ValueHint::FilePath)]
+        out: Option<PathBuf>,
+
+        /// Conversation to export instead of the agent's main one
+        #[arg(long)]
+        thread: Option<String>,
+    },
+}
+
 #[derive(Subcommand)]
 enum AgentCommands {
     /// Run an individual agent
     Run {
         /// Control server address
-        #[arg(short, long, default_value = DEFAULT_SERVER)]
+        #[arg(short, long, default_value = DEFAULT_SERVER, value_hint = ValueHint::Url)]
         server: String,
 
         /// Agent name
@@ -150,15 +358,22 @@ enum AgentCommands {
         id: Option<String>,
 
         /// Backend to use: "mux" (direct API) or "cli" (Claude CLI)
-        #[arg(short, long, env = "COVEN_BACKEND")]
+        #[arg(
+            short,
+            long,
+            env = "COVEN_BACKEND",
+            value_parser = clap::builder::PossibleValuesParser::new(
+                coven_agent::agent_config::VALID_BACKENDS.iter().copied()
+            )
+        )]
         backend: Option<String>,
 
         /// Working directory for the agent
-        #[arg(short, long)]
+        #[arg(short, long, value_hint = ValueHint::DirPath)]
         working_dir: Option<PathBuf>,
 
         /// Load configuration from a file
-        #[arg(long)]
+        #[arg(long, value_hint = ValueHint::FilePath)]
         config: Option<PathBuf>,
 
         /// Headless mode (minimal output, for servers)
@@ -168,10 +383,59 @@ enum AgentCommands {
         /// Run in single-user interactive mode (no gRPC server)
         #[arg(long)]
         single: bool,
+
+        /// Run headless in the background, with a pid file and log under ~/.local/state/coven/agents/<id>/
+        #[arg(long, conflicts_with = "single")]
+        detach: bool,
+    },
+
+    /// List agents running in the background
+    List,
+
+    /// Stop an agent running in the background
+    Stop {
+        /// Agent ID
+        id: String,
+    },
+
+    /// Show the log of a background agent
+    Logs {
+        /// Agent ID
+        id: String,
+
+        /// Keep printing new output as it is written
+        #[arg(short, long)]
+        follow: bool,
+
+        /// Number of lines to show from the end of the log
+        #[arg(short = 'n', long, default_value_t = 50)]
+        lines: usize,
     },
 
     /// Create a new agent configuration interactively
     New,
+
+    /// Check an agent configuration for problems without connecting
+    ValidateConfig {
+        /// Configuration file to check (default: .coven/agent.toml or ~/.config/coven/agent.toml)
+        #[arg(long, value_hint = ValueHint::FilePath)]
+        config: Option<PathBuf>,
+    },
+
+    /// Print the system prompt the agent would send, with the source of each section
+    ShowPrompt {
+        /// Agent configuration file (default: .coven/agent.toml or ~/.config/coven/agent.toml)
+        #[arg(long, value_hint = ValueHint::FilePath)]
+        config: Option<PathBuf>,
+
+        /// Working directory to resolve local prompts and souls in
+        #[arg(short, long, value_hint = ValueHint::DirPath)]
+        working_dir: Option<PathBuf>,
+
+        /// Print the prompt exactly as sent, without source annotations
+        #[arg(long)]
+        raw: bool,
+    },
 }
 
 #[derive(Subcommand)]
@@ -179,16 +443,77 @@ enum PackCommands {
     /// List available packs
     List,
 
-    /// Install a pack
+    /// Build and install a pack from a git repository or a local directory
     Install {
-        /// Pack name to install
-        pack: String,
+        /// Git URL of the pack repository
+        #[arg(
+            value_hint = ValueHint::Url,
+            required_unless_present = "path",
+            conflicts_with = "path"
+        )]
+        source: Option<String>,
+
+        /// Install from a local crate directory instead
+        #[arg(long, value_hint = ValueHint::DirPath)]
+        path: Option<PathBuf>,
+    },
+
+    /// Remove an installed pack
+    Uninstall {
+        /// Installed pack name
+        name: String,
+
+        /// Also delete the pack's SSH key, giving it a new identity if reinstalled
+        #[arg(long)]
+        purge: bool,
+    },
+
+    /// Rebuild an installed pack from the source it was installed from
+    Upgrade {
+        /// Installed pack name
+        name: String,
     },
 
     /// Run a pack directly
     Run {
         /// Pack name to run
         pack: String,
+
+        /// Run in the background, restarting the pack if it crashes
+        #[arg(long)]
+        daemon: bool,
+    },
+
+    /// Show packs running in the background
+    Status,
+
+    /// Stop a pack running in the background
+    Stop {
+        /// Pack name to stop
+        name: String,
+    },
+
+    /// Run and restart a pack, logging its output (internal, spawned by run --daemon)
+    #[command(hide = true)]
+    Supervise {
+        /// Pack name
+        name: String,
+
+        /// Pack binary to run
+        #[arg(long)]
+        binary: PathBuf,
+    },
+}
+
+#[derive(Subcommand)]
+enum ProfileCommands {
+    /// List profiles, marking the active one
+    List,
+
+    /// Make a profile the default for every coven command
+    Use {
+        /// Profile name, or "none" to go back to the top-level keys
+        name: String,
     },
 }
 
@@ -197,7 +522,7 @@ enum AdminCommands {
     /// Show your identity (principal info)
     Me {
         /// Gateway gRPC address
-        #[arg(long, env = "COVEN_GATEWAY_GRPC")]
+        #[arg(long, env = "COVEN_GATEWAY_GRPC", value_hint = ValueHint::Url)]
         gateway: Option<String>,
 
         /// JWT authentication token
@@ -208,7 +533,7 @@ enum AdminCommands {
     /// Manage agents
     Agents {
         /// Gateway gRPC address
-        #[arg(long, env = "COVEN_GATEWAY_GRPC")]
+        #[arg(long, env = "COVEN_GATEWAY_GRPC", value_hint = ValueHint::Url)]
         gateway: Option<String>,
 
         /// JWT authentication token
@@ -222,7 +547,7 @@ enum AdminCommands {
     /// Manage bindings
     Bindings {
         /// Gateway gRPC address
-        #[arg(long, env = "COVEN_GATEWAY_GRPC")]
+        #[arg(long, env = "COVEN_GATEWAY_GRPC", value_hint = ValueHint::Url)]
         gateway: Option<String>,
 
         /// JWT authentication token
@@ -236,7 +561,7 @@ enum AdminCommands {
     /// Manage principals (agents, clients)
     Principals {
         /// Gateway gRPC address
-        #[arg(long, env = "COVEN_GATEWAY_GRPC")]
+        #[arg(long, env = "COVEN_GATEWAY_GRPC", value_hint = ValueHint::Url)]
         gateway: Option<String>,
 
         /// JWT authentication token
@@ -250,7 +575,7 @@ enum AdminCommands {
     /// Manage tokens
     Token {
         /// Gateway gRPC address
-        #[arg(long, env = "COVEN_GATEWAY_GRPC")]
+        #[arg(long, env = "COVEN_GATEWAY_GRPC", value_hint = ValueHint::Url)]
         gateway: Option<String>,
 
         /// JWT authentication token
@@ -260,6 +585,148 @@ enum AdminCommands {
         #[command(subcommand)]
         command: AdminTokenCommand,
     },
+
+    /// Manage messages queued for offline agents
+    Deadletter {
+        /// Gateway gRPC address
+        #[arg(long, env = "COVEN_GATEWAY_GRPC", value_hint = ValueHint::Url)]
+        gateway: Option<String>,
+
+        /// JWT authentication token
+        #[arg(long, env = "COVEN_TOKEN")]
+        token: Option<String>,
+
+        #[command(subcommand)]
+        command: AdminDeadletterCommand,
+    },
+
+    /// Inspect connected tool packs
+    Packs {
+        /// Gateway gRPC address
+        #[arg(long, env = "COVEN_GATEWAY_GRPC", value_hint = ValueHint::Url)]
+        gateway: Option<String>,
+
+        /// JWT authentication token
+        #[arg(long, env = "COVEN_TOKEN")]
+        token: Option<String>,
+
+        #[command(subcommand)]
+        command: AdminPacksCommand,
+    },
+
+    /// Approve devices linking with a pairing code
+    Pair {
+        /// Gateway gRPC address
+        #[arg(long, env = "COVEN_GATEWAY_GRPC", value_hint = ValueHint::Url)]
+        gateway: Option<String>,
+
+        /// JWT authentication token
+        #[arg(long, env = "COVEN_TOKEN")]
+        token: Option<String>,
+
+        #[command(subcommand)]
+        command: AdminPairCommand,
+    },
+
+    /// Set up one-time codes, which gateways may require before deleting
+    Otp {
+        /// Gateway gRPC address
+        #[arg(long, env = "COVEN_GATEWAY_GRPC", value_hint = ValueHint::Url)]
+        gateway: Option<String>,
+
+        /// JWT authentication token
+        #[arg(long, env = "COVEN_TOKEN")]
+        token: Option<String>,
+
+        #[command(subcommand)]
+        command: AdminOtpCommand,
+    },
+
+    /// Manage secrets the gateway hands to tool packs
+    Secrets {
+        /// Gateway gRPC address
+        #[arg(long, env = "COVEN_GATEWAY_GRPC", value_hint = ValueHint::Url)]
+        gateway: Option<String>,
+
+        /// JWT authentication token
+        #[arg(long, env = "COVEN_TOKEN")]
+        token: Option<String>,
+
+        #[command(subcommand)]
+        command: AdminSecretsCommand,
+    },
+
+    /// Follow all traffic through the gateway (needs `coven serve --enable-tail`)
+    Tail {
+        /// Gateway gRPC address
+        #[arg(long, env = "COVEN_GATEWAY_GRPC", value_hint = ValueHint::Url)]
+        gateway: Option<String>,
+
+        /// JWT authentication token
+        #[arg(long, env = "COVEN_TOKEN")]
+        token: Option<String>,
+
+        /// Only show traffic to and from this agent
+        #[arg(long)]
+        agent: Option<String>,
+
+        /// Show metadata only (ids, sizes, timing), never message content
+        #[arg(long)]
+        redact: bool,
+    },
+
+    /// Show the tokens and cost agents have spent today against their budgets
+    Usage {
+        /// Gateway gRPC address
+        #[arg(long, env = "COVEN_GATEWAY_GRPC", value_hint = ValueHint::Url)]
+        gateway: Option<String>,
+
+        /// JWT authentication token
+        #[arg(long, env = "COVEN_TOKEN")]
+        token: Option<String>,
+
+        /// Only this agent (default: every connected agent)
+        #[arg(long)]
+        agent: Option<String>,
+    },
+
+    /// Export the gateway's stored conversations as JSON Lines
+    Export {
+        /// Gateway gRPC address
+        #[arg(long, env = "COVEN_GATEWAY_GRPC", value_hint = ValueHint::Url)]
+        gateway: Option<String>,
+
+        /// JWT authentication token
+        #[arg(long, env = "COVEN_TOKEN")]
+        token: Option<String>,
+
+        /// Only this agent's threads
+        #[arg(long)]
+        agent: Option<String>,
+
+        /// Leave out records older than this: an age (30d, 12h, 90m, 2w),
+        /// a date (2026-01-31) or an RFC 3339 timestamp
+        #[arg(long)]
+        since: Option<String>,
+
+        /// Leave out records from this time on, in the same forms as --since
+        #[arg(long)]
+        until: Option<String>,
+
+        /// Only threads in this state
+        #[arg(long, value_parser = ["open", "answered", "empty"])]
+        state: Option<String>,
+
+        /// File to write (default: stdout)
+        #[arg(long, short, value_hint = ValueHint::FilePath)]
+        out: Option<PathBuf>,
+    },
+
+    /// Maintain the local thread store agents on this machine write to
+    Store {
+        #[command(subcommand)]
+        command: AdminStoreCommand,
+    },
 }
 
 #[derive(Subcommand)]
@@ -269,6 +736,37 @@ enum AdminAgentsCommand {
         /// Filter by workspace path
         #[arg(long)]
         workspace: Option<String>,
+
+        /// Keep the list on screen, redrawing it as agents connect,
+        /// disconnect, or change presence (Ctrl+C to exit)
+        #[arg(long)]
+        watch: bool,
+
+        /// Seconds between refreshes with --watch, for gateways that can't
+        /// push agent changes
+        #[arg(long, value_name = "SECS", default_value_t = 2, requires = "watch")]
+        interval: u64,
+    },
+
+    /// Show one agent in depth: metadata, connection, work in progress,
+    /// queue, and recent activity
+    #[command(visible_alias = "inspect")]
+    Show {
+        /// Agent ID
+        agent_id: String,
+
+        /// How many recent thread activities to show
+        #[arg(long, default_value_t = 10)]
+        activity: i32,
+    },
+
+    /// Change a connected agent's log filter without restarting it
+    SetLogLevel {
+        /// Agent ID
+        agent_id: String,
+
+        /// Filter in RUST_LOG syntax, e.g. "debug" or "coven_agent=trace,warn"
+        filter: String,
     },
 }
 
@@ -280,22 +778,61 @@ enum AdminBindingsCommand {
     /// Create a binding
     Create {
         /// Frontend identifier (e.g., "slack", "matrix")
-        #[arg(long)]
-        frontend: String,
+        #[arg(long, required_unless_present = "interactive")]
+        frontend: Option<String>,
 
         /// Channel ID from the frontend
-        #[arg(long)]
-        channel_id: String,
+        #[arg(long, required_unless_present = "interactive")]
+        channel_id: Option<String>,
 
         /// Agent ID to route messages to
-        #[arg(long)]
-        agent_id: String,
+        #[arg(long, required_unless_present = "interactive")]
+        agent_id: Option<String>,
+
+        /// Pick whichever of the above weren't given from menus
+        #[arg(long, short = 'i')]
+        interactive: bool,
     },
 
     /// Delete a binding
     Delete {
         /// Binding ID to delete
         id: String,
+
+        /// One-time code, for gateways that require one before deleting
+        /// (asked for on the terminal if needed and not given)
+        #[arg(long, value_name = "CODE")]
+        otp: Option<String>,
+    },
+
+    /// Print all bindings as a bindings file
+    Export {
+        /// File format
+        #[arg(long, value_enum, default_value_t = coven_admin::BindingsFormat::Toml)]
+        format: coven_admin::BindingsFormat,
+    },
+
+    /// Make the gateway's bindings match a bindings file
+    Import {
+        /// Bindings file to import ("-" for stdin)
+        file: PathBuf,
+
+        /// File format (default: JSON for .json files, TOML otherwise)
+        #[arg(long, value_enum)]
+        format: Option<coven_admin::BindingsFormat>,
+
+        /// Also delete bindings the file doesn't list
+        #[arg(long)]
+        prune: bool,
+
+        /// Print the planned creates, updates, and deletes without making them
+        #[arg(long)]
+        dry_run: bool,
+
+        /// One-time code for the deletions of --prune, for gateways that
+        /// require one
+        #[arg(long, value_name = "CODE", requires = "prune")]
+        otp: Option<String>,
     },
 }
 
@@ -331,6 +868,38 @@ enum AdminPrincipalsCommand {
     Delete {
         /// Principal ID to delete
         id: String,
+
+        /// One-time code, for gateways that require one before deleting
+        /// (asked for on the terminal if needed and not given)
+        #[arg(long, value_name = "CODE")]
+        otp: Option<String>,
+    },
+
+    /// Register a new SSH key for a principal; its current keys keep working for a grace period
+    Rotate {
+        /// Principal ID
+        id: String,
+
+        /// Fingerprint of the new public key (SHA256 hex)
+        #[arg(long)]
+        fingerprint: String,
+
+        /// Seconds the current keys keep working (defaults to the gateway's)
+        #[arg(long, value_name = "SECS")]
+        grace_period: Option<u32>,
+    },
+
+    /// Print all principals as a YAML principals file
+    Export,
+
+    /// Create or update principals from a YAML principals file
+    Import {
+        /// Principals file to import ("-" for stdin)
+        file: PathBuf,
+
+        /// Print the planned creates and updates without making them
+        #[arg(long)]
+        dry_run: bool,
     },
 }
 
@@ -345,6 +914,138 @@ enum AdminTokenCommand {
         #[arg(long, default_value = "2592000")]
         ttl: i64,
     },
+    /// List active tokens with when they were issued and last used
+    List {
+        /// Only show tokens for this principal
+        #[arg(long)]
+        principal_id: Option<String>,
+    },
+    /// Revoke a token; the gateway rejects it from the next call on
+    Revoke {
+        /// Token ID to revoke (from `token list`)
+        token_id: String,
+
+        /// One-time code, for gateways that require one before deleting
+        /// (asked for on the terminal if needed and not given)
+        #[arg(long, value_name = "CODE")]
+        otp: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+enum AdminPacksCommand {
+    /// List connected packs and their health
+    List,
+}
+
+#[derive(Subcommand)]
+enum AdminPairCommand {
+    /// Approve the device showing `code` (from `coven link --pair`)
+    Approve {
+        /// Pairing code shown by the device, e.g. K7QM-3XWD
+        code: String,
+
+        /// Principal the device joins, taking its roles; one the gateway
+        /// doesn't know yet is created
+        #[arg(long)]
+        principal: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum AdminSecretsCommand {
+    /// Set a pack secret, replacing any existing value
+    Set {
+        /// Pack ID the secret belongs to
+        pack_id: String,
+
+        /// Secret name (letters, digits, '_', '-', '.')
+        key: String,
+
+        /// Secret value (read from stdin if omitted)
+        value: Option<String>,
+    },
+
+    /// List a pack's secret names (values are never shown)
+    List {
+        /// Pack ID to list secrets for
+        pack_id: String,
+    },
+
+    /// Delete a pack secret
+    Delete {
+        /// Pack ID the secret belongs to
+        pack_id: String,
+
+        /// Secret name
+        key: String,
+
+        /// One-time code, for gateways that require one before deleting
+        /// (asked for on the terminal if needed and not given)
+        #[arg(long, value_name = "CODE")]
+        otp: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+enum AdminDeadletterCommand {
+    /// List queued messages
+    List {
+        /// Only show messages for this agent
+        #[arg(long)]
+        agent: Option<String>,
+    },
+
+    /// Deliver an agent's queued messages now
+    Replay {
+        /// Agent ID to deliver to (must be connected)
+        agent: String,
+    },
+
+    /// Discard queued messages
+    Purge {
+        /// Only purge messages for this agent
+        #[arg(long, conflicts_with = "all")]
+        agent: Option<String>,
+
+        /// Purge messages for every agent
+        #[arg(long)]
+        all: bool,
+
+        /// One-time code, for gateways that require one before deleting
+        /// (asked for on the terminal if needed and not given)
+        #[arg(long, value_name = "CODE")]
+        otp: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+enum AdminOtpCommand {
+    /// Get a TOTP secret for your authenticator app, or for another admin's
+    Enroll {
+        /// Current code from your existing secret
+        #[arg(long, value_name = "CODE")]
+        otp: Option<String>,
+
+        /// Enroll this principal instead of yourself, and pass the secret on
+        #[arg(long = "for", value_name = "PRINCIPAL")]
+        principal: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+enum AdminStoreCommand {
+    /// Merge streamed text and drop superseded tool states of finished
+    /// responses in the backend event log
+    Compact {
+        /// Thread store to compact (defaults to the configured database)
+        #[arg(long, value_hint = ValueHint::FilePath)]
+        db: Option<PathBuf>,
+
+        /// Report what would be saved without changing anything
+        #[arg(long)]
+        dry_run: bool,
+    },
 }
 
 #[derive(Subcommand)]
@@ -352,48 +1053,172 @@ enum BridgeCommands {
     /// Run Slack bridge
     Slack {
         /// Config file path
-        #[arg(short, long, env = "COVEN_SLACK_CONFIG")]
+        #[arg(short, long, env = "COVEN_SLACK_CONFIG", value_hint = ValueHint::FilePath)]
         config: Option<PathBuf>,
     },
 
     /// Run Matrix bridge
     Matrix {
         /// Config file path
-        #[arg(short, long, env = "COVEN_MATRIX_CONFIG")]
+        #[arg(short, long, env = "COVEN_MATRIX_CONFIG", value_hint = ValueHint::FilePath)]
         config: Option<PathBuf>,
     },
 
     /// Run Telegram bridge
     Telegram {
         /// Config file path
-        #[arg(short, long, env = "COVEN_TELEGRAM_CONFIG")]
+        #[arg(short, long, env = "COVEN_TELEGRAM_CONFIG", value_hint = ValueHint::FilePath)]
         config: Option<PathBuf>,
     },
 }
 
-#[tokio::main]
-async fn main() -> Result<()> {
+fn main() -> Result<()> {
+    // Only `swarm agent` uses it, but it clears an environment variable, so
+    // it's taken before the runtime starts any threads
+    let status = coven_swarm_core::process::StatusReporter::from_env();
+    tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()?
+        .block_on(run(status))
+}
+
+async fn run(status: Option<coven_swarm_core::process::StatusReporter>) -> Result<()> {
     // Load .env file if present
     let _ = dotenvy::dotenv();
 
-    // Initialize tracing
-    coven_log::init();
-
     let cli = Cli::parse();
 
+    // Initialize tracing
+    let level = coven_log::level_from_flags(cli.quiet, cli.verbose);
+    #[cfg(feature = "otlp")]
+    let _otlp = coven_log::init_otlp("coven", level);
+    #[cfg(not(feature = "otlp"))]
+    coven_log::init_with_level(level);
+
+    if let Some(profile) = &cli.profile {
+        coven_link::config::select_profile(profile.as_str());
+    }
+
     match cli.command {
         Commands::Init => run_init(),
-        Commands::Serve { grpc_addr, db } => run_serve(grpc_addr, db).await,
-        Commands::Link { gateway, name, key } => run_link(gateway, name, key).await,
-        Commands::Swarm(cmd) => run_swarm(cmd).await,
+        Commands::Serve {
+            grpc_addr,
+            socket_mode,
+            db,
+            db_busy_timeout_ms,
+            secrets_key,
+            dead_letter,
+            dead_letter_ttl,
+            dead_letter_max,
+            max_message_size_mb,
+            enable_tail,
+            push_webhook,
+            roles,
+            moderation,
+            export_redactions,
+            webhooks,
+            activity_retention_days,
+        } => {
+            // Re-read ~/.config/coven/log-level on SIGHUP
+            #[cfg(unix)]
+            coven_log::reload_on_sighup();
+            let dead_letter = dead_letter.then(|| coven_serve::DeadLetterConfig {
+                ttl: std::time::Duration::from_secs(dead_letter_ttl),
+                max_per_agent: dead_letter_max,
+            });
+            let db_busy_timeout = std::time::Duration::from_millis(db_busy_timeout_ms);
+            let message_limits = coven_serve::MessageLimits::new(max_message_size_mb * 1024 * 1024);
+            let roles = roles
+                .map(|path| coven_serve::RolesConfig::load(&path))
+                .transpose()?;
+            let moderation = moderation
+                .map(|path| coven_serve::ModerationConfig::load(&path))
+                .transpose()?;
+            let webhooks = webhooks
+                .map(|path| coven_serve::WebhookConfig::load(&path))
+                .transpose()?
+                .unwrap_or_default();
+            run_serve(
+                grpc_addr,
+                socket_mode,
+                db,
+                db_busy_timeout,
+                secrets_key,
+                dead_letter,
+                message_limits,
+                enable_tail,
+                push_webhook,
+                roles,
+                moderation,
+                export_redactions,
+                webhooks,
+                std::time::Duration::from_secs(
+                    activity_retention_days.saturating_mul(24 * 60 * 60),
+                ),
+            )
+            .await
+        }
+        Commands::Link {
+            gateway,
+            name,
+            key,
+            key_passphrase,
+            pair,
+            command,
+        } => run_link(gateway, name, key, key_passphrase, pair, command).await,
+        Commands::Swarm(cmd) => run_swarm(cmd, status).await,
         Commands::Agent(cmd) => run_agent(cmd).await,
-        Commands::Chat { agent } => run_chat(agent).await,
-        Commands::Human { gateway, name, id } => run_human(gateway, name, id).await,
+        Commands::Chat { agent, command } => run_chat(agent, command).await,
+        Commands::Human {
+            gateway,
+            name,
+            id,
+            nudge_after,
+            bell,
+        } => run_human(gateway, name, id, nudge_after, bell).await,
         Commands::Pack(cmd) => run_pack(cmd).await,
-        Commands::Admin(cmd) => run_admin(cmd).await,
-        Commands::Bridge(cmd) => run_bridge(cmd).await,
-        Commands::Version => {
+        Commands::Profile(cmd) => run_profile(cmd),
+        Commands::Admin { output, command } => run_admin(command, output).await,
+        Commands::Bridge(cmd) => {
+            // Re-read ~/.config/coven/log-level on SIGHUP
+            #[cfg(unix)]
+            coven_log::reload_on_sighup();
+            run_bridge(cmd).await
+        }
+        Commands::Status { gateway, json } => coven_cli::status::run(gateway, json).await,
+        Commands::Doctor { gateway, json } => coven_cli::doctor::run(gateway, json).await,
+        Commands::Completion { shell } => {
+            write_completion(shell, &mut std::io::stdout());
+            Ok(())
+        }
+        Commands::Mangen { out } => run_mangen(out),
+        Commands::Version { check, gateway } => {
             print_version();
+            if check {
+                run_version_check(gateway).await;
+            }
+            Ok(())
+        }
+    }
+}
+
+/// Write the completion script for `shell`
+fn write_completion(shell: clap_complete::Shell, out: &mut dyn std::io::Write) {
+    clap_complete::generate(shell, &mut Cli::command(), "coven", out);
+}
+
+/// Write man pages: every page into `out`, or the top-level page to stdout
+fn run_mangen(out: Option<PathBuf>) -> Result<()> {
+    let command = Cli::command();
+    match out {
+        Some(dir) => {
+            std::fs::create_dir_all(&dir).with_context(|| format!("creating {}", dir.display()))?;
+            clap_mangen::generate_to(command, &dir)
+                .with_context(|| format!("writing man pages to {}", dir.display()))?;
+            Ok(())
+        }
+        None => {
+            clap_mangen::Man::new(command).render(&mut std::io::stdout())?;
             Ok(())
         }
     }
@@ -405,31 +1230,151 @@ fn run_init() -> Result<()> {
 }
 
 /// Run the local gateway server
-async fn run_serve(grpc_addr: String, db: Option<PathBuf>) -> Result<()> {
+#[allow(clippy::too_many_arguments)]
+async fn run_serve(
+    grpc_addr: String,
+    socket_mode: u32,
+    db: Option<PathBuf>,
+    db_busy_timeout: std::time::Duration,
+    secrets_key: Option<PathBuf>,
+    dead_letter: Option<coven_serve::DeadLetterConfig>,
+    message_limits: coven_serve::MessageLimits,
+    enable_tail: bool,
+    push_webhook: Option<String>,
+    roles: Option<coven_serve::RolesConfig>,
+    moderation: Option<coven_serve::ModerationConfig>,
+    export_redactions: Vec<String>,
+    webhooks: Vec<coven_serve::WebhookConfig>,
+    activity_retention: std::time::Duration,
+) -> Result<()> {
     let config = coven_serve::ServeConfig {
         grpc_addr,
+        socket_mode,
         db_path: db.unwrap_or_else(|| {
             dirs::config_dir()
                 .map(|p| p.join("coven").join("local.db"))
                 .unwrap_or_else(|| PathBuf::from("local.db"))
         }),
+        db_busy_timeout,
+        dead_letter,
+        secrets_key_path: secrets_key,
+        message_limits,
+        enable_tail,
+        push_webhook,
+        roles,
+        moderation,
+        export_redactions,
+        webhooks,
+        activity_retention,
     };
     coven_serve::run(config).await
 }
 
+/// Handle profile subcommands
+fn run_profile(cmd: ProfileCommands) -> Result<()> {
+    use coven_link::config::{ConfigFile, CovenConfig};
+
+    let path = CovenConfig::config_path()?;
+    let mut file = ConfigFile::load_from(&path)?;
+    match cmd {
+        ProfileCommands::List => {
+            let active = CovenConfig::active_profile()?;
+            if file.profiles.is_empty() {
+                println!("No profiles in {}", path.display());
+            }
+            for (name, profile) in &file.profiles {
+                let marker = if active.as_deref() == Some(name) {
+                    "*"
+                } else {
+                    " "
+                };
+                println!(
+                    "{} {:15} {}",
+                    marker,
+                    name,
+                    profile.gateway.as_deref().unwrap_or("(no gateway)")
+                );
+            }
+            file.migrate_legacy();
+            if let Some(link) = file.active_link() {
+                let marker = if active.is_none() { "*" } else { " " };
+                println!(
+                    "{} {:15} {}",
+                    marker,
+                    format!("(link {})", link.name),
+                    link.gateway
+                );
+            }
+            Ok(())
+        }
+        ProfileCommands::Use { name } => {
+            if name == "none" {
+                file.default_profile = None;
+                file.save_to(&path)?;
+                println!("Using the active link by default");
+                return Ok(());
+            }
+            // Resolving checks the profile exists and names a gateway
+            let config = file.resolve(Some(&name))?;
+            file.default_profile = Some(name.clone());
+            file.save_to(&path)?;
+            println!("Using profile '{}' ({}) by default", name, config.gateway);
+            Ok(())
+        }
+    }
+}
+
+/// Parse a file mode such as `660` or `0o660`
+fn parse_octal_mode(s: &str) -> Result<u32, String> {
+    let digits = s.trim_start_matches("0o");
+    match u32::from_str_radix(digits, 8) {
+        Ok(mode) if mode <= 0o777 => Ok(mode),
+        _ => Err(format!("'{}' is not an octal file mode like 600", s)),
+    }
+}
+
 /// Link this device to a gateway
-async fn run_link(gateway: String, name: Option<String>, key: Option<String>) -> Result<()> {
-    coven_link::run(gateway, name, key).await
+async fn run_link(
+    gateway: Option<String>,
+    name: Option<String>,
+    key: Option<String>,
+    key_passphrase: Option<String>,
+    pair: bool,
+    command: Option<LinkCommands>,
+) -> Result<()> {
+    match command {
+        Some(LinkCommands::Rotate { grace_period }) => coven_link::rotate(grace_period).await,
+        Some(LinkCommands::List) => coven_link::links::list(),
+        Some(LinkCommands::Status) => coven_link::links::status(),
+        Some(LinkCommands::Switch { name }) => coven_link::links::switch(&name),
+        Some(LinkCommands::Remove { name }) => coven_link::links::remove(&name),
+        None => {
+            let gateway = gateway.context("a gateway URL is required")?;
+            if pair {
+                coven_link::pair(gateway, name, key, key_passphrase).await
+            } else {
+                coven_link::run(gateway, name, key, key_passphrase).await
+            }
+        }
+    }
 }
 
-/// Handle swarm subcommands
-async fn run_swarm(cmd: SwarmCommands) -> Result<()> {
+/// Handle swarm subcommands; `status` is the agent's, from main
+async fn run_swarm(
+    cmd: SwarmCommands,
+    status: Option<coven_swarm_core::process::StatusReporter>,
+) -> Result<()> {
     match cmd {
         SwarmCommands::Init => coven_swarm::run_init(),
-        SwarmCommands::Start { config, headless } => {
+        SwarmCommands::Start {
+            config,
+            headless,
+            no_color,
+        } => {
             let options = coven_swarm::SupervisorOptions {
                 config_path: config,
                 headless,
+                no_color,
             };
             coven_swarm::run_supervisor(options).await
         }
@@ -487,6 +1432,7 @@ async fn run_swarm(cmd: SwarmCommands) -> Result<()> {
                 workspace,
                 dispatch_mode,
                 config_path: config,
+                status,
             };
             coven_swarm::run_agent(options).await
         }
@@ -505,6 +1451,7 @@ async fn run_agent(cmd: AgentCommands) -> Result<()> {
             config,
             headless,
             single,
+            detach,
         } => {
             let agent_config = coven_agent::AgentRunConfig {
                 server,
@@ -515,16 +1462,63 @@ async fn run_agent(cmd: AgentCommands) -> Result<()> {
                 config,
                 headless,
                 single,
+                detach,
             };
             coven_agent::run_agent(agent_config).await
         }
+        AgentCommands::List => {
+            let registry = coven_agent::daemon::Registry::from_env()?;
+            let agents = registry.list()?;
+            if agents.is_empty() {
+                println!("No agents running in the background.");
+                return Ok(());
+            }
+
+            println!("{:30} {:>8}  LOG", "AGENT", "PID");
+            for agent in &agents {
+                println!("{:30} {:>8}  {}", agent.id, agent.pid, agent.log.display());
+            }
+            Ok(())
+        }
+        AgentCommands::Stop { id } => {
+            let registry = coven_agent::daemon::Registry::from_env()?;
+            let pid = coven_agent::daemon::stop(&registry, &id, coven_agent::daemon::STOP_TIMEOUT)?;
+            println!("Stopped agent {} (pid {})", id, pid);
+            Ok(())
+        }
+        AgentCommands::Logs { id, follow, lines } => {
+            let registry = coven_agent::daemon::Registry::from_env()?;
+            coven_agent::daemon::logs(&registry, &id, lines, follow, &mut std::io::stdout())
+        }
         AgentCommands::New => coven_agent::run_wizard("coven agent").await,
+        AgentCommands::ValidateConfig { config } => coven_agent::validate_config(config),
+        AgentCommands::ShowPrompt {
+            config,
+            working_dir,
+            raw,
+        } => coven_agent::show_prompt(config, working_dir, raw),
     }
 }
 
 /// Run the TUI chat interface in-process
-async fn run_chat(agent: Option<String>) -> Result<()> {
-    coven_tui_v2::run::run_async(agent).await
+async fn run_chat(agent: Option<String>, command: Option<ChatCommands>) -> Result<()> {
+    match command {
+        Some(ChatCommands::Export {
+            agent,
+            format,
+            out,
+            thread,
+        }) => {
+            coven_tui_v2::cli::export::run(coven_tui_v2::cli::export::ExportOptions {
+                agent,
+                thread,
+                format,
+                out,
+            })
+            .await
+        }
+        None => coven_tui_v2::run::run_async(agent).await,
+    }
 }
 
 /// Run the human agent TUI
@@ -532,16 +1526,27 @@ async fn run_human(
     gateway: Option<String>,
     name: Option<String>,
     id: Option<String>,
+    nudge_after: u64,
+    bell: bool,
 ) -> Result<()> {
-    let config = coven_human::HumanConfig { gateway, name, id };
+    let nudge = (nudge_after > 0).then(|| coven_human::NudgeConfig {
+        after: std::time::Duration::from_secs(nudge_after),
+        bell,
+    });
+    let config = coven_human::HumanConfig {
+        gateway,
+        name,
+        id,
+        nudge,
+    };
     coven_human::run_human(config).await
 }
 
 /// Handle admin subcommands
-async fn run_admin(cmd: AdminCommands) -> Result<()> {
+async fn run_admin(cmd: AdminCommands, output: coven_admin::OutputFormat) -> Result<()> {
     match cmd {
         AdminCommands::Me { gateway, token } => {
-            coven_admin::run_command(coven_admin::Command::Me, gateway, token).await
+            coven_admin::run_command(coven_admin::Command::Me, gateway, token, output).await
         }
         AdminCommands::Agents {
             gateway,
@@ -549,11 +1554,29 @@ async fn run_admin(cmd: AdminCommands) -> Result<()> {
             command,
         } => {
             let admin_cmd = match command {
-                AdminAgentsCommand::List { workspace } => {
-                    coven_admin::Command::Agents(coven_admin::AgentsCommand::List { workspace })
+                AdminAgentsCommand::List {
+                    workspace,
+                    watch,
+                    interval,
+                } => coven_admin::Command::Agents(coven_admin::AgentsCommand::List {
+                    workspace,
+                    watch,
+                    interval,
+                }),
+                AdminAgentsCommand::Show { agent_id, activity } => {
+                    coven_admin::Command::Agents(coven_admin::AgentsCommand::Show {
+                        agent_id,
+                        activity,
+                    })
+                }
+                AdminAgentsCommand::SetLogLevel { agent_id, filter } => {
+                    coven_admin::Command::Agents(coven_admin::AgentsCommand::SetLogLevel {
+                        agent_id,
+                        filter,
+                    })
                 }
             };
-            coven_admin::run_command(admin_cmd, gateway, token).await
+            coven_admin::run_command(admin_cmd, gateway, token, output).await
         }
         AdminCommands::Bindings {
             gateway,
@@ -568,16 +1591,34 @@ async fn run_admin(cmd: AdminCommands) -> Result<()> {
                     frontend,
                     channel_id,
                     agent_id,
+                    interactive,
                 } => coven_admin::Command::Bindings(coven_admin::BindingsCommand::Create {
                     frontend,
                     channel_id,
                     agent_id,
+                    interactive,
                 }),
-                AdminBindingsCommand::Delete { id } => {
-                    coven_admin::Command::Bindings(coven_admin::BindingsCommand::Delete { id })
+                AdminBindingsCommand::Delete { id, otp } => {
+                    coven_admin::Command::Bindings(coven_admin::BindingsCommand::Delete { id, otp })
+                }
+                AdminBindingsCommand::Export { format } => {
+                    coven_admin::Command::Bindings(coven_admin::BindingsCommand::Export { format })
                 }
+                AdminBindingsCommand::Import {
+                    file,
+                    format,
+                    prune,
+                    dry_run,
+                    otp,
+                } => coven_admin::Command::Bindings(coven_admin::BindingsCommand::Import {
+                    file,
+                    format,
+                    prune,
+                    dry_run,
+                    otp,
+                }),
             };
-            coven_admin::run_command(admin_cmd, gateway, token).await
+            coven_admin::run_command(admin_cmd, gateway, token, output).await
         }
         AdminCommands::Principals {
             gateway,
@@ -601,11 +1642,32 @@ async fn run_admin(cmd: AdminCommands) -> Result<()> {
                     fingerprint,
                     role,
                 }),
-                AdminPrincipalsCommand::Delete { id } => {
-                    coven_admin::Command::Principals(coven_admin::PrincipalsCommand::Delete { id })
+                AdminPrincipalsCommand::Delete { id, otp } => {
+                    coven_admin::Command::Principals(coven_admin::PrincipalsCommand::Delete {
+                        id,
+                        otp,
+                    })
+                }
+                AdminPrincipalsCommand::Rotate {
+                    id,
+                    fingerprint,
+                    grace_period,
+                } => coven_admin::Command::Principals(coven_admin::PrincipalsCommand::Rotate {
+                    id,
+                    fingerprint,
+                    grace_period,
+                }),
+                AdminPrincipalsCommand::Export => {
+                    coven_admin::Command::Principals(coven_admin::PrincipalsCommand::Export)
+                }
+                AdminPrincipalsCommand::Import { file, dry_run } => {
+                    coven_admin::Command::Principals(coven_admin::PrincipalsCommand::Import {
+                        file,
+                        dry_run,
+                    })
                 }
             };
-            coven_admin::run_command(admin_cmd, gateway, token).await
+            coven_admin::run_command(admin_cmd, gateway, token, output).await
         }
         AdminCommands::Token {
             gateway,
@@ -619,9 +1681,146 @@ async fn run_admin(cmd: AdminCommands) -> Result<()> {
                         ttl,
                     })
                 }
+                AdminTokenCommand::List { principal_id } => {
+                    coven_admin::Command::Token(coven_admin::TokenCommand::List { principal_id })
+                }
+                AdminTokenCommand::Revoke { token_id, otp } => {
+                    coven_admin::Command::Token(coven_admin::TokenCommand::Revoke { token_id, otp })
+                }
+            };
+            coven_admin::run_command(admin_cmd, gateway, token, output).await
+        }
+        AdminCommands::Deadletter {
+            gateway,
+            token,
+            command,
+        } => {
+            let admin_cmd = match command {
+                AdminDeadletterCommand::List { agent } => {
+                    coven_admin::Command::Deadletter(coven_admin::DeadletterCommand::List { agent })
+                }
+                AdminDeadletterCommand::Replay { agent } => {
+                    coven_admin::Command::Deadletter(coven_admin::DeadletterCommand::Replay {
+                        agent,
+                    })
+                }
+                AdminDeadletterCommand::Purge { agent, all, otp } => {
+                    coven_admin::Command::Deadletter(coven_admin::DeadletterCommand::Purge {
+                        agent,
+                        all,
+                        otp,
+                    })
+                }
+            };
+            coven_admin::run_command(admin_cmd, gateway, token, output).await
+        }
+        AdminCommands::Packs {
+            gateway,
+            token,
+            command,
+        } => {
+            let admin_cmd = match command {
+                AdminPacksCommand::List => {
+                    coven_admin::Command::Packs(coven_admin::PacksCommand::List)
+                }
+            };
+            coven_admin::run_command(admin_cmd, gateway, token, output).await
+        }
+        AdminCommands::Pair {
+            gateway,
+            token,
+            command,
+        } => {
+            let admin_cmd = match command {
+                AdminPairCommand::Approve { code, principal } => {
+                    coven_admin::Command::Pair(coven_admin::PairCommand::Approve {
+                        code,
+                        principal,
+                    })
+                }
+            };
+            coven_admin::run_command(admin_cmd, gateway, token, output).await
+        }
+        AdminCommands::Otp {
+            gateway,
+            token,
+            command,
+        } => {
+            let admin_cmd = match command {
+                AdminOtpCommand::Enroll { otp, principal } => {
+                    coven_admin::Command::Otp(coven_admin::OtpCommand::Enroll { otp, principal })
+                }
+            };
+            coven_admin::run_command(admin_cmd, gateway, token, output).await
+        }
+        AdminCommands::Secrets {
+            gateway,
+            token,
+            command,
+        } => {
+            let admin_cmd = match command {
+                AdminSecretsCommand::Set {
+                    pack_id,
+                    key,
+                    value,
+                } => coven_admin::Command::Secrets(coven_admin::SecretsCommand::Set {
+                    pack_id,
+                    key,
+                    value,
+                }),
+                AdminSecretsCommand::List { pack_id } => {
+                    coven_admin::Command::Secrets(coven_admin::SecretsCommand::List { pack_id })
+                }
+                AdminSecretsCommand::Delete { pack_id, key, otp } => {
+                    coven_admin::Command::Secrets(coven_admin::SecretsCommand::Delete {
+                        pack_id,
+                        key,
+                        otp,
+                    })
+                }
+            };
+            coven_admin::run_command(admin_cmd, gateway, token, output).await
+        }
+        AdminCommands::Tail {
+            gateway,
+            token,
+            agent,
+            redact,
+        } => {
+            let admin_cmd = coven_admin::Command::Tail { agent, redact };
+            coven_admin::run_command(admin_cmd, gateway, token, output).await
+        }
+        AdminCommands::Usage {
+            gateway,
+            token,
+            agent,
+        } => {
+            let admin_cmd = coven_admin::Command::Usage { agent };
+            coven_admin::run_command(admin_cmd, gateway, token, output).await
+        }
+        AdminCommands::Export {
+            gateway,
+            token,
+            agent,
+            since,
+            until,
+            state,
+            out,
+        } => {
+            let admin_cmd = coven_admin::Command::Export {
+                agent,
+                since,
+                until,
+                state,
+                out,
             };
-            coven_admin::run_command(admin_cmd, gateway, token).await
+            coven_admin::run_command(admin_cmd, gateway, token, output).await
         }
+        AdminCommands::Store { command } => match command {
+            AdminStoreCommand::Compact { db, dry_run } => {
+                coven_cli::store::compact(db, dry_run, output).await
+            }
+        },
     }
 }
 
@@ -634,39 +1833,13 @@ async fn run_bridge(cmd: BridgeCommands) -> Result<()> {
     }
 }
 
-/// Built-in pack definitions.
-struct BuiltinPack {
-    name: &'static str,
-    binary: &'static str,
-    description: &'static str,
-}
-
-/// List of built-in packs that ship with coven.
-const BUILTIN_PACKS: &[BuiltinPack] = &[
-    BuiltinPack {
-        name: "productivity",
-        binary: "productivity-pack",
-        description: "Task management and productivity tools (todo, notes)",
-    },
-    BuiltinPack {
-        name: "mcp-bridge",
-        binary: "mcp-bridge-pack",
-        description: "Bridge to expose any MCP server as coven tools",
-    },
-    BuiltinPack {
-        name: "test",
-        binary: "test-pack",
-        description: "Echo tools for testing pack connectivity",
-    },
-];
-
 /// Get the packs configuration directory (~/.config/coven/packs/).
 fn packs_config_dir() -> Option<PathBuf> {
     dirs::config_dir().map(|p| p.join("coven").join("packs"))
 }
 
-/// List installed packs by checking the packs config directory.
-fn list_installed_packs() -> Vec<String> {
+/// List packs that have SSH keys in the packs config directory.
+fn list_configured_packs() -> Vec<String> {
     let Some(packs_dir) = packs_config_dir() else {
         return Vec::new();
     };
@@ -686,8 +1859,21 @@ fn list_installed_packs() -> Vec<String> {
         .collect()
 }
 
-/// Find the pack binary path, checking PATH and cargo target directory.
+/// Find the pack binary path, checking installed packs, PATH and cargo target directory.
 fn find_pack_binary(pack_name: &str) -> Option<PathBuf> {
+    if let Ok(dirs) = PackDirs::from_env() {
+        if let Some(installed) = dirs
+            .manifest()
+            .ok()
+            .and_then(|m| m.packs.get(pack_name).cloned())
+        {
+            let path = dirs.binary_path(pack_name, &installed.binary);
+            if path.is_file() {
+                return Some(path);
+            }
+        }
+    }
+
     let binary_name = BUILTIN_PACKS
         .iter()
         .find(|p| p.name == pack_name)
@@ -750,10 +1936,25 @@ async fn run_pack(cmd: PackCommands) -> Result<()> {
             }
             println!();
 
-            let installed = list_installed_packs();
+            let installed = PackDirs::from_env()
+                .and_then(|dirs| dirs.manifest())
+                .map(|m| m.packs)
+                .unwrap_or_default();
             if !installed.is_empty() {
+                println!("Installed packs:");
+                for (name, pack) in &installed {
+                    println!("  {:15} {:10} {}", name, pack.version, pack.source);
+                }
+                println!();
+            }
+
+            let configured: Vec<_> = list_configured_packs()
+                .into_iter()
+                .filter(|name| !installed.contains_key(name))
+                .collect();
+            if !configured.is_empty() {
                 println!("Configured packs (have SSH keys):");
-                for pack in &installed {
+                for pack in &configured {
                     println!("  {}", pack);
                 }
                 println!();
@@ -762,28 +1963,74 @@ async fn run_pack(cmd: PackCommands) -> Result<()> {
             println!("Use 'coven pack run <name>' to start a pack.");
             Ok(())
         }
-        PackCommands::Install { pack } => {
-            println!("coven pack install {}", pack);
-            println!();
-
-            println!("Pack installation is not yet implemented.");
-            println!();
-            println!("To use built-in packs, build them with:");
-            println!("  cargo build -p {}-pack", pack);
+        PackCommands::Install { source, path } => {
+            let source = match (source, path) {
+                (_, Some(path)) => PackSource::local(&path)?,
+                (Some(source), None) => PackSource::from_arg(&source)?,
+                (None, None) => unreachable!("clap requires a source or --path"),
+            };
+            let dirs = PackDirs::from_env()?;
+
+            println!("Building pack from {}...", source);
+            let (name, installed) = pack_install::install(&dirs, &source, None)?;
+            println!("Installed {} {}", name, installed.version);
+            println!(
+                "  Binary: {}",
+                dirs.binary_path(&name, &installed.binary).display()
+            );
+            println!("  Key:    {}", dirs.key_path(&installed.package).display());
             println!();
-            println!("Then run with:");
-            println!("  coven pack run {}", pack);
+            println!("Run with:");
+            println!("  coven pack run {}", name);
 
             Ok(())
         }
-        PackCommands::Run { pack } => {
+        PackCommands::Uninstall { name, purge } => {
+            let dirs = PackDirs::from_env()?;
+            let removed = pack_install::uninstall(&dirs, &name, purge)?;
+            println!("Uninstalled {} {}", name, removed.version);
+            if !purge {
+                println!(
+                    "SSH key kept at {} (use --purge to delete it)",
+                    dirs.key_path(&removed.package).display()
+                );
+            }
+            Ok(())
+        }
+        PackCommands::Upgrade { name } => {
+            let dirs = PackDirs::from_env()?;
+            println!("Rebuilding {}...", name);
+            let (previous, upgraded) = pack_install::upgrade(&dirs, &name)?;
+            println!(
+                "Upgraded {}: {} -> {}",
+                name, previous.version, upgraded.version
+            );
+            Ok(())
+        }
+        PackCommands::Run { pack, daemon } => {
             let binary_path = find_pack_binary(&pack).with_context(|| {
                 format!(
-                    "Pack '{}' not found. Try building it first with: cargo build -p {}-pack",
+                    "Pack '{}' not found. Install it with 'coven pack install', or build it with: cargo build -p {}-pack",
                     pack, pack
                 )
             })?;
 
+            if daemon {
+                let registry = pack_daemon::Registry::from_env()?;
+                let record = pack_daemon::start(&registry, &pack, &binary_path)?;
+                println!(
+                    "Started pack {} in the background (pid {})",
+                    pack, record.supervisor_pid
+                );
+                println!("  Log: {}", record.log.display());
+                println!();
+                println!(
+                    "Check on it with 'coven pack status', stop it with 'coven pack stop {}'.",
+                    pack
+                );
+                return Ok(());
+            }
+
             println!("Starting pack: {}", pack);
             println!("Binary: {}", binary_path.display());
             println!();
@@ -804,6 +2051,45 @@ async fn run_pack(cmd: PackCommands) -> Result<()> {
 
             Ok(())
         }
+        PackCommands::Status => {
+            let registry = pack_daemon::Registry::from_env()?;
+            let running = registry.running()?;
+            if running.is_empty() {
+                println!("No packs running in the background.");
+                return Ok(());
+            }
+
+            println!(
+                "{:15} {:>8} {:>8} {:>8} {:>8}  LOG",
+                "PACK", "PID", "PACK PID", "UPTIME", "RESTARTS"
+            );
+            for record in &running {
+                let pack_pid = record
+                    .pid
+                    .map(|pid| pid.to_string())
+                    .unwrap_or_else(|| "-".to_string());
+                println!(
+                    "{:15} {:>8} {:>8} {:>8} {:>8}  {}",
+                    record.name,
+                    record.supervisor_pid,
+                    pack_pid,
+                    pack_daemon::format_uptime(record.uptime_secs()),
+                    record.restarts,
+                    record.log.display()
+                );
+            }
+            Ok(())
+        }
+        PackCommands::Stop { name } => {
+            let registry = pack_daemon::Registry::from_env()?;
+            let record = pack_daemon::stop(&registry, &name, pack_daemon::STOP_TIMEOUT)?;
+            println!("Stopped pack {} (pid {})", name, record.supervisor_pid);
+            Ok(())
+        }
+        PackCommands::Supervise { name, binary } => {
+            let registry = pack_daemon::Registry::from_env()?;
+            pack_daemon::supervise(&registry, &name, &binary).await
+        }
     }
 }
 
@@ -819,13 +2105,355 @@ fn print_version() {
     println!("Repository: https://github.com/2389-research/coven");
 }
 
+/// Whether this client and a gateway speak compatible protocol versions.
+#[derive(Debug, PartialEq, Eq)]
+enum Compatibility {
+    Compatible,
+    Mismatch,
+    Unknown,
+}
+
+/// The part of a semver string that must match for compatibility: the
+/// major version, or major.minor before 1.0 where minors can break the protocol.
+fn compat_key(version: &str) -> Option<(u64, u64)> {
+    let version = version.trim().trim_start_matches('v');
+    let core = version.split(['-', '+']).next()?;
+    let mut parts = core.split('.');
+    let major: u64 = parts.next()?.parse().ok()?;
+    let minor: u64 = parts.next()?.parse().ok()?;
+    Some(if major == 0 { (0, minor) } else { (major, 0) })
+}
+
+fn check_compatibility(client: &str, gateway: &str) -> Compatibility {
+    match (compat_key(client), compat_key(gateway)) {
+        (Some(c), Some(g)) if c == g => Compatibility::Compatible,
+        (Some(_), Some(_)) => Compatibility::Mismatch,
+        _ => Compatibility::Unknown,
+    }
+}
+
+/// Query the gateway's version and print a compatibility verdict. An
+/// unreachable gateway is reported rather than treated as an error.
+async fn run_version_check(gateway: Option<String>) {
+    let client_version = env!("CARGO_PKG_VERSION");
+    let (gateway, token) = coven_admin::resolve_connection(gateway, None);
+
+    println!();
+    match coven_admin::commands::version::fetch(&gateway, token.as_deref()).await {
+        Ok(Some(remote)) => {
+            println!(
+                "Gateway {}: {} {}",
+                gateway, remote.component, remote.version
+            );
+            match check_compatibility(client_version, &remote.version) {
+                Compatibility::Compatible => println!("Compatibility: ok"),
+                Compatibility::Mismatch => println!(
+                    "Compatibility: WARNING - client {} and gateway {} are different major versions; upgrade the older one",
+                    client_version, remote.version
+                ),
+                Compatibility::Unknown => println!(
+                    "Compatibility: unknown (can't parse version '{}')",
+                    remote.version
+                ),
+            }
+        }
+        Ok(None) => {
+            println!("Gateway {}: version not reported", gateway);
+            println!("Compatibility: unknown (the gateway is likely older than this client)");
+        }
+        Err(e) => {
+            println!("Gateway {}: unreachable ({:#})", gateway, e);
+            println!("Compatibility: unknown");
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use clap::CommandFactory;
 
     #[test]
     fn verify_cli_structure() {
         Cli::command().debug_assert();
     }
+
+    #[test]
+    fn test_completion_for_every_shell() {
+        use clap::ValueEnum;
+        for shell in clap_complete::Shell::value_variants() {
+            let mut out = Vec::new();
+            write_completion(*shell, &mut out);
+            let script = String::from_utf8(out).unwrap();
+            assert!(!script.is_empty(), "empty completions for {}", shell);
+            assert!(script.contains("coven"), "{} completions name coven", shell);
+        }
+    }
+
+    #[test]
+    fn test_completion_args() {
+        assert!(Cli::try_parse_from(["coven", "completion", "zsh"]).is_ok());
+        assert!(Cli::try_parse_from(["coven", "completion", "tcsh"]).is_err());
+    }
+
+    #[test]
+    fn test_mangen_writes_pages() {
+        let mut out = Vec::new();
+        clap_mangen::Man::new(Cli::command())
+            .render(&mut out)
+            .unwrap();
+        assert!(String::from_utf8(out).unwrap().contains("coven"));
+
+        let dir = tempfile::tempdir().unwrap();
+        run_mangen(Some(dir.path().to_path_buf())).unwrap();
+        assert!(dir.path().join("coven.1").exists());
+        assert!(dir.path().join("coven-agent-run.1").exists());
+    }
+
+    #[test]
+    fn test_pack_install_args() {
+        assert!(matches!(
+            Cli::try_parse_from(["coven", "pack", "install", "https://github.com/x/y-pack"])
+                .unwrap()
+                .command,
+            Commands::Pack(PackCommands::Install {
+                source: Some(_),
+                path: None
+            })
+        ));
+        assert!(Cli::try_parse_from(["coven", "pack", "install", "--path", "../my-pack"]).is_ok());
+        assert!(Cli::try_parse_from(["coven", "pack", "install"]).is_err());
+        assert!(Cli::try_parse_from([
+            "coven",
+            "pack",
+            "install",
+            "https://github.com/x/y-pack",
+            "--path",
+            "../my-pack"
+        ])
+        .is_err());
+    }
+
+    #[test]
+    fn test_pack_run_daemon_args() {
+        assert!(matches!(
+            Cli::try_parse_from(["coven", "pack", "run", "productivity", "--daemon"])
+                .unwrap()
+                .command,
+            Commands::Pack(PackCommands::Run { daemon: true, .. })
+        ));
+        assert!(Cli::try_parse_from(["coven", "pack", "stop", "productivity"]).is_ok());
+        assert!(Cli::try_parse_from(["coven", "pack", "stop"]).is_err());
+    }
+
+    #[test]
+    fn test_serve_socket_mode() {
+        assert_eq!(parse_octal_mode("660"), Ok(0o660));
+        assert_eq!(parse_octal_mode("0o600"), Ok(0o600));
+        assert!(parse_octal_mode("999").is_err());
+        assert!(parse_octal_mode("1777").is_err());
+        assert!(Cli::try_parse_from([
+            "coven",
+            "serve",
+            "--grpc-addr",
+            "unix:///run/coven/gateway.sock",
+            "--socket-mode",
+            "660"
+        ])
+        .is_ok());
+    }
+
+    #[test]
+    fn test_profile_args() {
+        let cli = Cli::try_parse_from(["coven", "admin", "me", "--profile", "team"]).unwrap();
+        assert_eq!(cli.profile.as_deref(), Some("team"));
+        assert!(Cli::try_parse_from(["coven", "profile", "use", "team"]).is_ok());
+        assert!(Cli::try_parse_from(["coven", "profile", "use"]).is_err());
+    }
+
+    #[test]
+    fn test_agent_detach_args() {
+        assert!(Cli::try_parse_from(["coven", "agent", "run", "--detach"]).is_ok());
+        assert!(Cli::try_parse_from(["coven", "agent", "run", "--detach", "--single"]).is_err());
+        match Cli::try_parse_from(["coven", "agent", "logs", "ops", "-f"])
+            .unwrap()
+            .command
+        {
+            Commands::Agent(AgentCommands::Logs { id, follow, lines }) => {
+                assert_eq!(id, "ops");
+                assert!(follow);
+                assert_eq!(lines, 50);
+            }
+            _ => panic!("expected agent logs"),
+        }
+        assert!(Cli::try_parse_from(["coven", "agent", "stop"]).is_err());
+    }
+
+    #[test]
+    fn test_link_args() {
+        match Cli::try_parse_from(["coven", "link", "http://localhost:8080", "-n", "laptop"])
+            .unwrap()
+            .command
+        {
+            Commands::Link {
+                gateway,
+                name,
+                pair,
+                command: None,
+                ..
+            } => {
+                assert_eq!(gateway.as_deref(), Some("http://localhost:8080"));
+                assert_eq!(name.as_deref(), Some("laptop"));
+                assert!(!pair);
+            }
+            _ => panic!("expected link"),
+        }
+        match Cli::try_parse_from(["coven", "link", "coven.example.com", "--pair"])
+            .unwrap()
+            .command
+        {
+            Commands::Link { pair, .. } => assert!(pair),
+            _ => panic!("expected link --pair"),
+        }
+        match Cli::try_parse_from(["coven", "link", "rotate", "--grace-period", "3600"])
+            .unwrap()
+            .command
+        {
+            Commands::Link {
+                gateway: None,
+                command: Some(LinkCommands::Rotate { grace_period }),
+                ..
+            } => assert_eq!(grace_period, Some(3600)),
+            _ => panic!("expected link rotate"),
+        }
+        match Cli::try_parse_from(["coven", "link", "switch", "coven.example.com"])
+            .unwrap()
+            .command
+        {
+            Commands::Link {
+                command: Some(LinkCommands::Switch { name }),
+                ..
+            } => assert_eq!(name, "coven.example.com"),
+            _ => panic!("expected link switch"),
+        }
+        assert!(Cli::try_parse_from(["coven", "link"]).is_err());
+    }
+
+    #[test]
+    fn test_human_nudge_args() {
+        let cli = Cli::try_parse_from(["coven", "human", "--nudge-after", "30", "--bell"]).unwrap();
+        match cli.command {
+            Commands::Human {
+                nudge_after, bell, ..
+            } => {
+                assert_eq!(nudge_after, 30);
+                assert!(bell);
+            }
+            _ => panic!("expected human command"),
+        }
+    }
+
+    #[test]
+    fn test_agent_backend_values() {
+        assert!(Cli::try_parse_from(["coven", "agent", "run", "--backend", "mux"]).is_ok());
+        assert!(Cli::try_parse_from(["coven", "agent", "run", "--backend", "gpt"]).is_err());
+    }
+
+    #[test]
+    fn test_compat_key() {
+        assert_eq!(compat_key("1.4.2"), Some((1, 0)));
+        assert_eq!(compat_key("v2.0.0-rc.1"), Some((2, 0)));
+        assert_eq!(compat_key("0.3.1"), Some((0, 3)));
+        assert_eq!(compat_key("0.3.1+build.7"), Some((0, 3)));
+        assert_eq!(compat_key("dev"), None);
+        assert_eq!(compat_key(""), None);
+    }
+
+    #[test]
+    fn test_check_compatibility() {
+        assert_eq!(
+            check_compatibility("1.2.0", "1.9.3"),
+            Compatibility::Compatible
+        );
+        assert_eq!(
+            check_compatibility("0.1.0", "0.1.7"),
+            Compatibility::Compatible
+        );
+        assert_eq!(
+            check_compatibility("1.2.0", "2.0.0"),
+            Compatibility::Mismatch
+        );
+        assert_eq!(
+            check_compatibility("0.1.0", "0.2.0"),
+            Compatibility::Mismatch
+        );
+        assert_eq!(
+            check_compatibility("0.1.0", "unknown"),
+            Compatibility::Unknown
+        );
+    }
+
+    #[test]
+    fn test_version_gateway_requires_check() {
+        assert!(Cli::try_parse_from(["coven", "version", "--gateway", "localhost:50051"]).is_err());
+        assert!(Cli::try_parse_from([
+            "coven",
+            "version",
+            "--check",
+            "--gateway",
+            "localhost:50051"
+        ])
+        .is_ok());
+    }
+
+    #[test]
+    fn test_chat_export_args() {
+        let cli = Cli::try_parse_from([
+            "coven", "chat", "export", "dev", "--format", "json", "--out", "dev.json", "--thread",
+            "thread-1",
+        ])
+        .unwrap();
+        let Commands::Chat {
+            agent: None,
+            command:
+                Some(ChatCommands::Export {
+                    agent,
+                    format,
+                    out,
+                    thread,
+                }),
+        } = cli.command
+        else {
+            panic!("expected chat export");
+        };
+        assert_eq!(agent, "dev");
+        assert_eq!(format, coven_tui_v2::cli::export::ExportFormat::Json);
+        assert_eq!(out, Some(PathBuf::from("dev.json")));
+        assert_eq!(thread.as_deref(), Some("thread-1"));
+
+        // Markdown by default, and plain `coven chat --agent` still works
+        assert!(matches!(
+            Cli::try_parse_from(["coven", "chat", "export", "dev"])
+                .unwrap()
+                .command,
+            Commands::Chat {
+                command: Some(ChatCommands::Export {
+                    format: coven_tui_v2::cli::export::ExportFormat::Markdown,
+                    ..
+                }),
+                ..
+            }
+        ));
+        assert!(matches!(
+            Cli::try_parse_from(["coven", "chat", "--agent", "dev"])
+                .unwrap()
+                .command,
+            Commands::Chat {
+                agent: Some(_),
+                command: None
+            }
+        ));
+        assert!(
+            Cli::try_parse_from(["coven", "chat", "export", "dev", "--format", "xml"]).is_err()
+        );
+    }
 }
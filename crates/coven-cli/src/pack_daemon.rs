@@ -0,0 +1,476 @@
+// ABOUTME: `coven pack run --daemon`, `pack status` and `pack stop`: detached packs tracked by pid files.
+// ABOUTME: A supervisor process restarts crashed packs and writes their output to rotating logs.
+
+use anyhow::{bail, Context, Result};
+use coven_swarm_core::process::{self, ManagedChild, RotatingLog};
+use serde::{Deserialize, Serialize};
+use std::ffi::OsStr;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::time::{Duration, Instant};
+use tokio::process::Command;
+use tokio::sync::mpsc;
+
+/// First delay before restarting a pack that exited with an error
+const MIN_BACKOFF: Duration = Duration::from_secs(1);
+/// Longest delay between restarts
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+/// A pack that ran at least this long restarts without accumulated backoff
+const STABLE_RUN: Duration = Duration::from_secs(60);
+/// How long `start` waits for the supervisor to register the pack
+const START_TIMEOUT: Duration = Duration::from_secs(5);
+/// How long `stop` waits for a graceful exit before killing
+pub const STOP_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Registry entry for a daemonized pack, stored as `<name>.json`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PackRecord {
+    pub name: String,
+    /// The `coven pack supervise` process that owns the pack
+    pub supervisor_pid: u32,
+    /// When the supervisor started, to tell it apart from a later process
+    /// given the same pid (see `process::start_token`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub supervisor_start: Option<String>,
+    /// The pack process itself, while it is running
+    pub pid: Option<u32>,
+    pub binary: PathBuf,
+    pub log: PathBuf,
+    /// When the supervisor started, in Unix seconds
+    pub started_at: u64,
+    /// How many times the pack has been restarted after exiting
+    pub restarts: u32,
+}
+
+impl PackRecord {
+    /// Whether the supervisor that wrote this record is still running
+    pub fn supervisor_running(&self) -> bool {
+        process::is_running(self.supervisor_pid, self.supervisor_start.as_deref())
+    }
+
+    /// Seconds since the supervisor started
+    pub fn uptime_secs(&self) -> u64 {
+        now_secs().saturating_sub(self.started_at)
+    }
+}
+
+/// Directory of pid files and logs for daemonized packs
+#[derive(Debug, Clone)]
+pub struct Registry {
+    pub dir: PathBuf,
+}
+
+impl Registry {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    /// `~/.local/state/coven/packs`, or `$XDG_STATE_HOME/coven/packs`
+    pub fn from_env() -> Result<Self> {
+        let dir = process::state_dir("packs").context("Could not determine the home directory")?;
+        Ok(Self::new(dir))
+    }
+
+    pub fn record_path(&self, name: &str) -> PathBuf {
+        self.dir.join(format!("{}.json", name))
+    }
+
+    pub fn log_path(&self, name: &str) -> PathBuf {
+        self.dir.join(format!("{}.log", name))
+    }
+
+    pub fn write(&self, record: &PackRecord) -> Result<()> {
+        std::fs::create_dir_all(&self.dir)
+            .with_context(|| format!("Failed to create {}", self.dir.display()))?;
+        let path = self.record_path(&record.name);
+        let tmp = path.with_extension("json.tmp");
+        std::fs::write(&tmp, serde_json::to_vec_pretty(record)?)
+            .with_context(|| format!("Failed to write {}", tmp.display()))?;
+        std::fs::rename(&tmp, &path)
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+        Ok(())
+    }
+
+    pub fn read(&self, name: &str) -> Result<Option<PackRecord>> {
+        let path = self.record_path(name);
+        match std::fs::read(&path) {
+            Ok(data) => serde_json::from_slice(&data)
+                .map(Some)
+                .with_context(|| format!("Failed to parse {}", path.display())),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e).with_context(|| format!("Failed to read {}", path.display())),
+        }
+    }
+
+    pub fn remove(&self, name: &str) -> Result<()> {
+        match std::fs::remove_file(self.record_path(name)) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+            _ => Ok(()),
+        }
+    }
+
+    /// Packs whose supervisor is still alive, by name. Records left behind
+    /// by a supervisor that died without cleaning up are deleted.
+    pub fn running(&self) -> Result<Vec<PackRecord>> {
+        let entries = match std::fs::read_dir(&self.dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => {
+                return Err(e).with_context(|| format!("Failed to read {}", self.dir.display()))
+            }
+        };
+
+        let mut running = Vec::new();
+        for entry in entries.filter_map(|e| e.ok()) {
+            let path = entry.path();
+            if path.extension() != Some(OsStr::new("json")) {
+                continue;
+            }
+            let Some(name) = path.file_stem().and_then(|s| s.to_str()) else {
+                continue;
+            };
+            match self.read(name) {
+                Ok(Some(record)) if record.supervisor_running() => running.push(record),
+                Ok(_) | Err(_) => {
+                    let _ = std::fs::remove_file(&path);
+                }
+            }
+        }
+        running.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(running)
+    }
+}
+
+fn now_secs() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// Render an uptime as e.g. `45s`, `12m03s`, `3h12m` or `2d04h`
+pub fn format_uptime(secs: u64) -> String {
+    let (days, hours, mins, secs) = (
+        secs / 86_400,
+        secs % 86_400 / 3600,
+        secs % 3600 / 60,
+        secs % 60,
+    );
+    if days > 0 {
+        format!("{}d{:02}h", days, hours)
+    } else if hours > 0 {
+        format!("{}h{:02}m", hours, mins)
+    } else if mins > 0 {
+        format!("{}m{:02}s", mins, secs)
+    } else {
+        format!("{}s", secs)
+    }
+}
+
+/// Start a detached supervisor for the pack and wait until it has
+/// registered. The supervisor is `coven pack supervise`, run in its own
+/// session so it survives the terminal closing.
+pub fn start(registry: &Registry, name: &str, binary: &Path) -> Result<PackRecord> {
+    process::check_state_name(name).context("Invalid pack name")?;
+    if let Some(record) = registry.read(name)? {
+        if record.supervisor_running() {
+            bail!(
+                "Pack '{}' is already running (pid {}). Stop it with 'coven pack stop {}'",
+                name,
+                record.supervisor_pid,
+                name
+            );
+        }
+        registry.remove(name)?;
+    }
+
+    let exe = std::env::current_exe().context("Failed to locate the coven binary")?;
+    let mut cmd = std::process::Command::new(exe);
+    cmd.args(["pack", "supervise", name, "--binary"])
+        .arg(binary);
+    process::detach(&mut cmd).context("Failed to detach the pack supervisor")?;
+    let mut supervisor = cmd.spawn().context("Failed to start pack supervisor")?;
+
+    let deadline = Instant::now() + START_TIMEOUT;
+    loop {
+        if let Some(record) = registry.read(name)? {
+            if record.supervisor_pid == supervisor.id() {
+                return Ok(record);
+            }
+        }
+        if let Some(status) = supervisor.try_wait()? {
+            bail!(
+                "Pack supervisor exited ({}); see {}",
+                status,
+                registry.log_path(name).display()
+            );
+        }
+        if Instant::now() > deadline {
+            bail!(
+                "Pack '{}' did not start within {}s; see {}",
+                name,
+                START_TIMEOUT.as_secs(),
+                registry.log_path(name).display()
+            );
+        }
+        std::thread::sleep(Duration::from_millis(100));
+    }
+}
+
+/// Stop a daemonized pack: ask its supervisor to shut down, and kill both
+/// the supervisor and the pack if that takes longer than `timeout`.
+pub fn stop(registry: &Registry, name: &str, timeout: Duration) -> Result<PackRecord> {
+    let record = registry
+        .read(name)?
+        .filter(PackRecord::supervisor_running)
+        .with_context(|| format!("Pack '{}' is not running", name))?;
+
+    process::terminate(record.supervisor_pid)
+        .with_context(|| format!("Failed to signal pid {}", record.supervisor_pid))?;
+
+    let deadline = Instant::now() + timeout;
+    while record.supervisor_running() && Instant::now() < deadline {
+        std::thread::sleep(Duration::from_millis(100));
+    }
+    if record.supervisor_running() {
+        let _ = process::force_kill(record.supervisor_pid);
+        if let Some(pid) = record.pid {
+            let _ = process::force_kill(pid);
+        }
+    }
+
+    registry.remove(name)?;
+    Ok(record)
+}
+
+/// Resolve once the supervisor is asked to stop
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        let _ = tokio::signal::ctrl_c().await;
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut signal) => {
+                signal.recv().await;
+            }
+            Err(_) => std::future::pending::<()>().await,
+        }
+    };
+
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {}
+        _ = terminate => {}
+    }
+}
+
+/// Run the pack in the foreground of the supervisor process, restarting it
+/// with backoff when it fails and appending its output to the pack's log.
+/// Returns when the pack exits cleanly or the supervisor is told to stop.
+pub async fn supervise(registry: &Registry, name: &str, binary: &Path) -> Result<()> {
+    let mut log = RotatingLog::open(
+        registry.log_path(name),
+        RotatingLog::DEFAULT_MAX_BYTES,
+        RotatingLog::DEFAULT_KEEP,
+    )
+    .with_context(|| format!("Failed to open {}", registry.log_path(name).display()))?;
+    let (line_tx, mut line_rx) = mpsc::channel::<String>(1024);
+
+    let mut record = PackRecord {
+        name: name.to_string(),
+        supervisor_pid: std::process::id(),
+        supervisor_start: process::start_token(std::process::id()),
+        pid: None,
+        binary: binary.to_path_buf(),
+        log: log.path().to_path_buf(),
+        started_at: now_secs(),
+        restarts: 0,
+    };
+    let mut backoff = MIN_BACKOFF;
+    let shutdown = shutdown_signal();
+    tokio::pin!(shutdown);
+
+    loop {
+        let mut cmd = Command::new(binary);
+        cmd.stdin(Stdio::null());
+        let mut child = match ManagedChild::spawn(cmd, line_tx.clone()) {
+            Ok(child) => child,
+            Err(e) => {
+                let _ = log.write_line(&format!(
+                    "[supervisor] failed to start {}: {}",
+                    binary.display(),
+                    e
+                ));
+                registry.remove(name)?;
+                return Err(e).with_context(|| format!("Failed to start {}", binary.display()));
+            }
+        };
+        record.pid = child.pid();
+        registry.write(&record)?;
+        let _ = log.write_line(&format!(
+            "[supervisor] started {} (pid {})",
+            name,
+            record.pid.unwrap_or_default()
+        ));
+        let started = Instant::now();
+
+        let status = loop {
+            tokio::select! {
+                status = child.wait() => break status,
+                Some(line) = line_rx.recv() => {
+                    let _ = log.write_line(&line);
+                }
+                _ = &mut shutdown => {
+                    let _ = child.kill().await;
+                    let _ = log.write_line("[supervisor] stopped");
+                    return registry.remove(name);
+                }
+            }
+        };
+        while let Ok(line) = line_rx.try_recv() {
+            let _ = log.write_line(&line);
+        }
+
+        match status {
+            Ok(status) if status.success() => {
+                let _ = log.write_line("[supervisor] pack exited cleanly");
+                return registry.remove(name);
+            }
+            Ok(status) => {
+                let _ = log.write_line(&format!("[supervisor] pack exited: {}", status));
+            }
+            Err(e) => {
+                let _ = log.write_line(&format!("[supervisor] lost track of pack: {}", e));
+            }
+        }
+
+        if started.elapsed() >= STABLE_RUN {
+            backoff = MIN_BACKOFF;
+        }
+        let _ = log.write_line(&format!(
+            "[supervisor] restarting in {}s",
+            backoff.as_secs()
+        ));
+        record.pid = None;
+        record.restarts += 1;
+        registry.write(&record)?;
+
+        tokio::select! {
+            _ = tokio::time::sleep(backoff) => {}
+            _ = &mut shutdown => {
+                let _ = log.write_line("[supervisor] stopped");
+                return registry.remove(name);
+            }
+        }
+        backoff = (backoff * 2).min(MAX_BACKOFF);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(name: &str, supervisor_pid: u32) -> PackRecord {
+        PackRecord {
+            name: name.to_string(),
+            supervisor_pid,
+            supervisor_start: None,
+            pid: None,
+            binary: PathBuf::from("/usr/bin/sleep"),
+            log: PathBuf::from(format!("/tmp/{}.log", name)),
+            started_at: now_secs(),
+            restarts: 0,
+        }
+    }
+
+    /// A long-running stand-in for a pack binary
+    #[cfg(unix)]
+    fn dummy_pack() -> std::process::Child {
+        std::process::Command::new("sleep")
+            .arg("30")
+            .spawn()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_registry_roundtrip() {
+        let dir = tempfile::tempdir().unwrap();
+        let registry = Registry::new(dir.path().join("packs"));
+        assert_eq!(registry.read("weather").unwrap(), None);
+
+        let mut entry = record("weather", 4242);
+        entry.pid = Some(4243);
+        entry.restarts = 2;
+        registry.write(&entry).unwrap();
+        assert_eq!(registry.read("weather").unwrap(), Some(entry));
+
+        registry.remove("weather").unwrap();
+        assert_eq!(registry.read("weather").unwrap(), None);
+        registry.remove("weather").unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_running_prunes_dead_supervisors() {
+        let dir = tempfile::tempdir().unwrap();
+        let registry = Registry::new(dir.path());
+        let mut alive = dummy_pack();
+        let mut dead = dummy_pack();
+        registry.write(&record("alive", alive.id())).unwrap();
+        registry.write(&record("dead", dead.id())).unwrap();
+        // A live pid that has since been given to another process
+        let reused = PackRecord {
+            supervisor_start: Some("an earlier process".to_string()),
+            ..record("reused", alive.id())
+        };
+        registry.write(&reused).unwrap();
+        std::fs::write(registry.log_path("alive"), "log lines\n").unwrap();
+
+        dead.kill().unwrap();
+        dead.wait().unwrap();
+
+        let running = registry.running().unwrap();
+        assert_eq!(running.len(), 1);
+        assert_eq!(running[0].name, "alive");
+        assert!(!registry.record_path("dead").exists());
+        assert!(!registry.record_path("reused").exists());
+        assert!(registry.log_path("alive").exists(), "logs aren't records");
+
+        alive.kill().unwrap();
+        alive.wait().unwrap();
+        assert!(registry.running().unwrap().is_empty());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_stop_terminates_and_unregisters() {
+        let dir = tempfile::tempdir().unwrap();
+        let registry = Registry::new(dir.path());
+        let mut supervisor = dummy_pack();
+        let pid = supervisor.id();
+        registry.write(&record("weather", pid)).unwrap();
+
+        // Reap the process as soon as it exits, as init would for a detached one
+        let reaper = std::thread::spawn(move || supervisor.wait().unwrap());
+
+        let stopped = stop(&registry, "weather", Duration::from_secs(5)).unwrap();
+        assert_eq!(stopped.supervisor_pid, pid);
+        assert!(!reaper.join().unwrap().success());
+        assert_eq!(registry.read("weather").unwrap(), None);
+
+        let err = stop(&registry, "weather", Duration::from_secs(5)).unwrap_err();
+        assert!(err.to_string().contains("not running"));
+    }
+
+    #[test]
+    fn test_format_uptime() {
+        assert_eq!(format_uptime(45), "45s");
+        assert_eq!(format_uptime(12 * 60 + 3), "12m03s");
+        assert_eq!(format_uptime(3 * 3600 + 12 * 60 + 9), "3h12m");
+        assert_eq!(format_uptime(2 * 86_400 + 4 * 3600), "2d04h");
+    }
+}
@@ -0,0 +1,597 @@
+// ABOUTME: `coven pack install/uninstall/upgrade`: builds packs from a git URL or local path.
+// ABOUTME: Binaries land in ~/.local/share/coven/packs/<name>/bin and are recorded in a manifest.
+
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Manifest of installed packs, kept next to the pack install directories
+const MANIFEST_FILE: &str = "installed.toml";
+
+/// Where a pack is built from
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PackSource {
+    /// A git repository whose root is the pack crate
+    Git(String),
+    /// A crate directory on this machine
+    Path(PathBuf),
+}
+
+impl PackSource {
+    /// Interpret the positional `coven pack install` argument. Only git URLs
+    /// are accepted here; local directories go through `--path`.
+    pub fn from_arg(arg: &str) -> Result<Self> {
+        if is_git_url(arg) {
+            Ok(PackSource::Git(arg.to_string()))
+        } else {
+            bail!(
+                "'{}' is not a git URL. Use 'coven pack install --path {}' for a local directory",
+                arg,
+                arg
+            )
+        }
+    }
+
+    /// A local crate directory, made absolute so upgrades work from anywhere
+    pub fn local(path: &Path) -> Result<Self> {
+        let path = std::fs::canonicalize(path)
+            .with_context(|| format!("Pack directory {} not found", path.display()))?;
+        Ok(PackSource::Path(path))
+    }
+}
+
+impl std::fmt::Display for PackSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PackSource::Git(url) => write!(f, "{}", url),
+            PackSource::Path(path) => write!(f, "{}", path.display()),
+        }
+    }
+}
+
+fn is_git_url(s: &str) -> bool {
+    ["https://", "http://", "ssh://", "git://", "git@", "file://"]
+        .iter()
+        .any(|prefix| s.starts_with(prefix))
+        || s.ends_with(".git")
+}
+
+/// One entry in the installed-packs manifest
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InstalledPack {
+    /// Crate name, which the pack passes to `PackConfig::load` and which
+    /// names its key directory
+    pub package: String,
+    /// Crate version the binary was built from
+    pub version: String,
+    /// Binary name under the pack's `bin` directory
+    pub binary: String,
+    /// Commit the pack was built from, for git sources
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub revision: Option<String>,
+    pub source: PackSource,
+}
+
+/// Installed packs by name
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Manifest {
+    #[serde(default)]
+    pub packs: BTreeMap<String, InstalledPack>,
+}
+
+/// Directories pack installs read and write: binaries under `data`, SSH keys
+/// under `config`.
+#[derive(Debug, Clone)]
+pub struct PackDirs {
+    pub data: PathBuf,
+    pub config: PathBuf,
+}
+
+impl PackDirs {
+    pub fn new(data: impl Into<PathBuf>, config: impl Into<PathBuf>) -> Self {
+        Self {
+            data: data.into(),
+            config: config.into(),
+        }
+    }
+
+    /// `~/.local/share/coven/packs` (or `$XDG_DATA_HOME/coven/packs`) for
+    /// binaries and `~/.config/coven/packs` for keys, where packs look for them.
+    pub fn from_env() -> Result<Self> {
+        let data = std::env::var_os("XDG_DATA_HOME")
+            .map(PathBuf::from)
+            .filter(|p| p.is_absolute())
+            .or_else(|| dirs::home_dir().map(|h| h.join(".local").join("share")))
+            .context("Could not determine the home directory")?
+            .join("coven")
+            .join("packs");
+        let config = coven_ssh::xdg_config_dir()
+            .context("Could not determine the config directory")?
+            .join("packs");
+        Ok(Self::new(data, config))
+    }
+
+    pub fn install_dir(&self, name: &str) -> PathBuf {
+        self.data.join(name)
+    }
+
+    /// Path of an installed pack's binary
+    pub fn binary_path(&self, name: &str, binary: &str) -> PathBuf {
+        self.install_dir(name).join("bin").join(format!(
+            "{}{}",
+            binary,
+            std::env::consts::EXE_SUFFIX
+        ))
+    }
+
+    /// The SSH key of the pack built from crate `package`, where
+    /// `PackConfig::load(package)` looks for it by default
+    pub fn key_path(&self, package: &str) -> PathBuf {
+        self.config.join(package).join("id_ed25519")
+    }
+
+    fn manifest_path(&self) -> PathBuf {
+        self.data.join(MANIFEST_FILE)
+    }
+
+    /// Read the manifest; nothing installed yet is an empty manifest
+    pub fn manifest(&self) -> Result<Manifest> {
+        let path = self.manifest_path();
+        let content = match std::fs::read_to_string(&path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Manifest::default()),
+            Err(e) => return Err(e).with_context(|| format!("Failed to read {}", path.display())),
+        };
+        toml::from_str(&content).with_context(|| format!("Failed to parse {}", path.display()))
+    }
+
+    fn save_manifest(&self, manifest: &Manifest) -> Result<()> {
+        let path = self.manifest_path();
+        let tmp = path.with_extension("toml.tmp");
+        std::fs::write(&tmp, toml::to_string_pretty(manifest)?)
+            .with_context(|| format!("Failed to write {}", tmp.display()))?;
+        std::fs::rename(&tmp, &path)
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+        Ok(())
+    }
+}
+
+/// What the pack crate's Cargo.toml says about it
+#[derive(Debug, PartialEq, Eq)]
+struct CrateInfo {
+    package: String,
+    version: String,
+    binary: String,
+}
+
+/// Read the package name, version and binary from `dir/Cargo.toml`. A crate
+/// with several binaries must have one named after the package or ending in
+/// `-pack`.
+fn read_crate_info(dir: &Path) -> Result<CrateInfo> {
+    let path = dir.join("Cargo.toml");
+    let content = std::fs::read_to_string(&path)
+        .with_context(|| format!("{} is not a Rust crate", dir.display()))?;
+    let manifest: toml::Value =
+        toml::from_str(&content).with_context(|| format!("Failed to parse {}", path.display()))?;
+
+    let Some(package) = manifest.get("package") else {
+        bail!(
+            "{} has no [package]; point --path at the pack crate inside the workspace",
+            path.display()
+        );
+    };
+    let name = package
+        .get("name")
+        .and_then(|n| n.as_str())
+        .with_context(|| format!("{} has no package name", path.display()))?
+        .to_string();
+    // Workspace-inherited versions aren't resolved here
+    let version = package
+        .get("version")
+        .and_then(|v| v.as_str())
+        .unwrap_or("unknown")
+        .to_string();
+
+    let bins: Vec<&str> = manifest
+        .get("bin")
+        .and_then(|b| b.as_array())
+        .map(|bins| {
+            bins.iter()
+                .filter_map(|b| b.get("name").and_then(|n| n.as_str()))
+                .collect()
+        })
+        .unwrap_or_default();
+    let binary = match bins.as_slice() {
+        [] => name.clone(),
+        [only] => only.to_string(),
+        several => several
+            .iter()
+            .find(|b| **b == name || b.ends_with("-pack"))
+            .with_context(|| {
+                format!(
+                    "{} has several binaries ({}); can't tell which is the pack",
+                    name,
+                    several.join(", ")
+                )
+            })?
+            .to_string(),
+    };
+
+    Ok(CrateInfo {
+        package: name,
+        version,
+        binary,
+    })
+}
+
+/// The name a pack is installed under: its crate name without `-pack`,
+/// matching the built-in packs.
+fn pack_name(package: &str) -> &str {
+    package
+        .strip_suffix("-pack")
+        .filter(|n| !n.is_empty())
+        .unwrap_or(package)
+}
+
+/// Scratch directory for one install, removed however the install ends
+struct Staging(PathBuf);
+
+impl Staging {
+    fn new(data: &Path) -> Result<Self> {
+        let path = data.join(format!(".staging-{}", std::process::id()));
+        if path.exists() {
+            std::fs::remove_dir_all(&path)?;
+        }
+        std::fs::create_dir_all(&path)
+            .with_context(|| format!("Failed to create {}", path.display()))?;
+        Ok(Self(path))
+    }
+}
+
+impl Drop for Staging {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+/// Run a build step, turning a failure into an error carrying its stderr
+fn run_step(command: &mut Command, what: &str) -> Result<()> {
+    let output = command
+        .output()
+        .with_context(|| format!("Failed to run {}", what))?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        bail!(
+            "{} failed ({}):\n{}",
+            what,
+            output.status,
+            stderr.trim_end()
+        );
+    }
+    Ok(())
+}
+
+fn git_clone(url: &str, dest: &Path) -> Result<()> {
+    run_step(
+        Command::new("git")
+            .args(["clone", "--depth", "1", "--quiet", url])
+            .arg(dest),
+        "git clone",
+    )
+}
+
+fn git_revision(checkout: &Path) -> Option<String> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(checkout)
+        .args(["rev-parse", "HEAD"])
+        .output()
+        .ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+fn cargo_install(src: &Path, root: &Path) -> Result<()> {
+    let cargo = std::env::var_os("CARGO").unwrap_or_else(|| "cargo".into());
+    run_step(
+        Command::new(cargo)
+            .args(["install", "--no-track", "--path"])
+            .arg(src)
+            .arg("--root")
+            .arg(root),
+        "cargo build",
+    )
+}
+
+/// Build a pack from `source` and install it. With `expect`, the source must
+/// still build the named pack (used by upgrades). On any failure the
+/// previous install, if there was one, is left untouched.
+pub fn install(
+    dirs: &PackDirs,
+    source: &PackSource,
+    expect: Option<&str>,
+) -> Result<(String, InstalledPack)> {
+    std::fs::create_dir_all(&dirs.data)
+        .with_context(|| format!("Failed to create {}", dirs.data.display()))?;
+    let staging = Staging::new(&dirs.data)?;
+
+    let (src, revision) = match source {
+        PackSource::Git(url) => {
+            let checkout = staging.0.join("src");
+            git_clone(url, &checkout)?;
+            let revision = git_revision(&checkout);
+            (checkout, revision)
+        }
+        PackSource::Path(path) => (path.clone(), None),
+    };
+
+    let info = read_crate_info(&src)?;
+    let name = pack_name(&info.package).to_string();
+    if let Some(expected) = expect {
+        if name != expected {
+            bail!("{} now builds pack '{}', not '{}'", source, name, expected);
+        }
+    }
+
+    let root = staging.0.join("root");
+    cargo_install(&src, &root)?;
+    let built = root
+        .join("bin")
+        .join(format!("{}{}", info.binary, std::env::consts::EXE_SUFFIX));
+    if !built.is_file() {
+        bail!(
+            "cargo built {} but no '{}' binary came out of it",
+            info.package,
+            info.binary
+        );
+    }
+
+    let key_path = dirs.key_path(&info.package);
+    if !key_path.exists() {
+        coven_ssh::generate_key(&key_path)
+            .with_context(|| format!("Failed to create SSH key for pack '{}'", name))?;
+    }
+
+    let installed = InstalledPack {
+        package: info.package,
+        version: info.version,
+        binary: info.binary,
+        revision,
+        source: source.clone(),
+    };
+
+    // Swap the new build in, keeping the old one until the manifest is written
+    let install_dir = dirs.install_dir(&name);
+    let previous = staging.0.join("previous");
+    if install_dir.exists() {
+        std::fs::rename(&install_dir, &previous)
+            .with_context(|| format!("Failed to replace {}", install_dir.display()))?;
+    }
+    let committed = std::fs::rename(&root, &install_dir)
+        .with_context(|| format!("Failed to write {}", install_dir.display()))
+        .and_then(|()| {
+            let mut manifest = dirs.manifest()?;
+            manifest.packs.insert(name.clone(), installed.clone());
+            dirs.save_manifest(&manifest)
+        });
+    if let Err(e) = committed {
+        let _ = std::fs::remove_dir_all(&install_dir);
+        if previous.exists() {
+            let _ = std::fs::rename(&previous, &install_dir);
+        }
+        return Err(e);
+    }
+
+    Ok((name, installed))
+}
+
+/// Rebuild an installed pack from the source it was installed from.
+/// Returns the previous and new manifest entries.
+pub fn upgrade(dirs: &PackDirs, name: &str) -> Result<(InstalledPack, InstalledPack)> {
+    let current = dirs
+        .manifest()?
+        .packs
+        .remove(name)
+        .with_context(|| format!("Pack '{}' is not installed", name))?;
+    let (_, upgraded) = install(dirs, &current.source, Some(name))?;
+    Ok((current, upgraded))
+}
+
+/// Remove an installed pack's binaries and manifest entry. The SSH key stays
+/// unless `purge` is set, so a reinstall keeps the pack's gateway identity.
+pub fn uninstall(dirs: &PackDirs, name: &str, purge: bool) -> Result<InstalledPack> {
+    let mut manifest = dirs.manifest()?;
+    let removed = manifest
+        .packs
+        .remove(name)
+        .with_context(|| format!("Pack '{}' is not installed", name))?;
+
+    let install_dir = dirs.install_dir(name);
+    if install_dir.exists() {
+        std::fs::remove_dir_all(&install_dir)
+            .with_context(|| format!("Failed to remove {}", install_dir.display()))?;
+    }
+    dirs.save_manifest(&manifest)?;
+
+    if purge {
+        let config_dir = dirs.config.join(&removed.package);
+        if config_dir.exists() {
+            std::fs::remove_dir_all(&config_dir)
+                .with_context(|| format!("Failed to remove {}", config_dir.display()))?;
+        }
+    }
+
+    Ok(removed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_crate(dir: &Path, cargo_toml: &str, main_rs: &str) {
+        std::fs::create_dir_all(dir.join("src")).unwrap();
+        std::fs::write(dir.join("Cargo.toml"), cargo_toml).unwrap();
+        std::fs::write(dir.join("src").join("main.rs"), main_rs).unwrap();
+    }
+
+    #[test]
+    fn test_source_from_arg() {
+        assert_eq!(
+            PackSource::from_arg("https://github.com/x/y-pack").unwrap(),
+            PackSource::Git("https://github.com/x/y-pack".into())
+        );
+        assert!(matches!(
+            PackSource::from_arg("git@github.com:x/y-pack.git"),
+            Ok(PackSource::Git(_))
+        ));
+        let err = PackSource::from_arg("../my-pack").unwrap_err();
+        assert!(err.to_string().contains("--path"));
+    }
+
+    #[test]
+    fn test_pack_name_strips_suffix() {
+        assert_eq!(pack_name("weather-pack"), "weather");
+        assert_eq!(pack_name("weather"), "weather");
+        assert_eq!(pack_name("-pack"), "-pack");
+    }
+
+    #[test]
+    fn test_read_crate_info() {
+        let dir = tempfile::tempdir().unwrap();
+        write_crate(
+            dir.path(),
+            "[package]\nname = \"weather-pack\"\nversion = \"0.3.1\"\n",
+            "fn main() {}",
+        );
+        assert_eq!(
+            read_crate_info(dir.path()).unwrap(),
+            CrateInfo {
+                package: "weather-pack".into(),
+                version: "0.3.1".into(),
+                binary: "weather-pack".into(),
+            }
+        );
+
+        write_crate(
+            dir.path(),
+            "[package]\nname = \"weather\"\nversion.workspace = true\n\n\
+             [[bin]]\nname = \"helper\"\n\n[[bin]]\nname = \"weather-pack\"\n",
+            "fn main() {}",
+        );
+        let info = read_crate_info(dir.path()).unwrap();
+        assert_eq!(info.version, "unknown");
+        assert_eq!(info.binary, "weather-pack");
+    }
+
+    #[test]
+    fn test_read_crate_info_rejects_workspace_root() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("Cargo.toml"),
+            "[workspace]\nmembers = [\"crates/*\"]\n",
+        )
+        .unwrap();
+        let err = read_crate_info(dir.path()).unwrap_err();
+        assert!(err.to_string().contains("no [package]"));
+    }
+
+    #[test]
+    fn test_manifest_roundtrip() {
+        let dir = tempfile::tempdir().unwrap();
+        let dirs = PackDirs::new(dir.path().join("data"), dir.path().join("config"));
+        assert!(dirs.manifest().unwrap().packs.is_empty());
+
+        std::fs::create_dir_all(&dirs.data).unwrap();
+        let mut manifest = Manifest::default();
+        manifest.packs.insert(
+            "weather".into(),
+            InstalledPack {
+                package: "weather-pack".into(),
+                version: "0.3.1".into(),
+                binary: "weather-pack".into(),
+                revision: Some("abc123".into()),
+                source: PackSource::Git("https://github.com/x/weather-pack".into()),
+            },
+        );
+        dirs.save_manifest(&manifest).unwrap();
+
+        assert_eq!(dirs.manifest().unwrap().packs, manifest.packs);
+    }
+
+    #[test]
+    fn test_uninstall_removes_binaries_and_keeps_key() {
+        let dir = tempfile::tempdir().unwrap();
+        let dirs = PackDirs::new(dir.path().join("data"), dir.path().join("config"));
+        std::fs::create_dir_all(dirs.install_dir("weather").join("bin")).unwrap();
+        std::fs::create_dir_all(dirs.config.join("weather-pack")).unwrap();
+        std::fs::write(dirs.key_path("weather-pack"), "key").unwrap();
+        let mut manifest = Manifest::default();
+        manifest.packs.insert(
+            "weather".into(),
+            InstalledPack {
+                package: "weather-pack".into(),
+                version: "0.3.1".into(),
+                binary: "weather-pack".into(),
+                revision: None,
+                source: PackSource::Path(dir.path().join("src")),
+            },
+        );
+        dirs.save_manifest(&manifest).unwrap();
+
+        uninstall(&dirs, "weather", false).unwrap();
+        assert!(!dirs.install_dir("weather").exists());
+        assert!(dirs.key_path("weather-pack").exists());
+        assert!(dirs.manifest().unwrap().packs.is_empty());
+        assert!(uninstall(&dirs, "weather", false).is_err());
+    }
+
+    #[test]
+    fn test_failed_build_leaves_nothing_behind() {
+        let dir = tempfile::tempdir().unwrap();
+        let dirs = PackDirs::new(dir.path().join("data"), dir.path().join("config"));
+        let src = dir.path().join("broken-pack");
+        write_crate(
+            &src,
+            "[package]\nname = \"broken-pack\"\nversion = \"0.1.0\"\nedition = \"2021\"\n\n[workspace]\n",
+            "fn main() { this is not rust }",
+        );
+
+        let err = install(&dirs, &PackSource::local(&src).unwrap(), None).unwrap_err();
+        assert!(format!("{:#}", err).contains("cargo build failed"));
+
+        let leftovers: Vec<_> = std::fs::read_dir(&dirs.data)
+            .unwrap()
+            .map(|e| e.unwrap().file_name())
+            .collect();
+        assert!(leftovers.is_empty(), "left behind {:?}", leftovers);
+        assert!(!dirs.config.join("broken-pack").exists());
+    }
+
+    #[test]
+    fn test_installed_key_is_where_the_pack_loads_it() {
+        let dir = tempfile::tempdir().unwrap();
+        let dirs = PackDirs::new(dir.path().join("data"), dir.path().join("config"));
+        let src = dir.path().join("weather-pack");
+        write_crate(
+            &src,
+            "[package]\nname = \"weather-pack\"\nversion = \"0.1.0\"\nedition = \"2021\"\n\n[workspace]\n",
+            "fn main() {}",
+        );
+
+        let (name, installed) = install(&dirs, &PackSource::local(&src).unwrap(), None).unwrap();
+        assert_eq!(name, "weather");
+        assert_eq!(installed.package, "weather-pack");
+
+        // The pack calls PackConfig::load("weather-pack")
+        let expected = coven_pack::PackConfig::default_key_path(&installed.package).unwrap();
+        let key_path = dirs.key_path(&installed.package);
+        let tail = |path: &Path| path.iter().rev().take(2).collect::<Vec<_>>();
+        assert_eq!(tail(&key_path), tail(&expected));
+        coven_ssh::load_key(&key_path).unwrap();
+    }
+}
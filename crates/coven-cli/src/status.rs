@@ -0,0 +1,559 @@
+// ABOUTME: `coven status`: one overview of the gateway, link, swarm, agents and packs.
+// ABOUTME: Each section reports what it could find instead of failing the whole command.
+
+use crate::doctor::{checks, Environment};
+use crate::pack_daemon::{self, PackRecord, Registry};
+use crate::pack_install::{Manifest, PackDirs};
+use base64::Engine;
+use coven_admin::client::AuthInterceptor;
+use coven_proto::coven::ListAgentsRequest;
+use coven_swarm::{AgentStatus, SocketClient};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+/// How long each step of the gateway probe may take
+const GATEWAY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Everything `coven status` reports
+#[derive(Debug, Clone, Serialize)]
+pub struct Overview {
+    pub gateway: GatewayStatus,
+    pub link: LinkStatus,
+    pub swarm: SwarmStatus,
+    pub agents: AgentsStatus,
+    pub packs: PacksStatus,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct GatewayStatus {
+    pub url: String,
+    pub reachable: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub component: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// The device identity written by `coven link`
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "state", rename_all = "snake_case")]
+pub enum LinkStatus {
+    NotLinked,
+    Linked {
+        device_name: String,
+        principal_id: String,
+        gateway: String,
+        /// The token's `exp` claim in Unix seconds, when it has one
+        token_expires_at: Option<i64>,
+    },
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "state", rename_all = "snake_case")]
+pub enum SwarmStatus {
+    NotConfigured,
+    NotRunning {
+        prefix: String,
+    },
+    Running {
+        prefix: String,
+        agents: Vec<AgentStatus>,
+    },
+}
+
+/// Agents registered with the gateway
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "state", rename_all = "snake_case")]
+pub enum AgentsStatus {
+    Unavailable {
+        error: String,
+    },
+    Listed {
+        online: Vec<String>,
+        offline: Vec<String>,
+    },
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "state", rename_all = "snake_case")]
+pub enum PacksStatus {
+    Unavailable { error: String },
+    Listed { packs: Vec<PackStatus> },
+}
+
+/// An installed pack, or one running in the background, or both
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PackStatus {
+    pub name: String,
+    /// Installed version; None for packs run from elsewhere
+    pub version: Option<String>,
+    pub running: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub uptime_secs: Option<u64>,
+}
+
+/// Collect every section. Sections that need the gateway or the swarm
+/// supervisor are queried concurrently.
+pub async fn gather(env: &Environment) -> Overview {
+    let (gateway, swarm, agents) =
+        tokio::join!(gateway_status(env), swarm_status(env), agents_status(env));
+    Overview {
+        gateway,
+        link: link_status(env),
+        swarm,
+        agents,
+        packs: packs_status(
+            PackDirs::from_env().and_then(|dirs| dirs.manifest()),
+            Registry::from_env().and_then(|registry| registry.running()),
+        ),
+    }
+}
+
+async fn gateway_status(env: &Environment) -> GatewayStatus {
+    let url = env.gateway_url();
+    let health = coven_grpc::probe_gateway_with_auth(
+        &url,
+        GATEWAY_TIMEOUT,
+        AuthInterceptor::new(env.token()),
+    )
+    .await;
+    let reachable = health.reachable();
+    GatewayStatus {
+        url,
+        reachable,
+        component: health.component,
+        version: health.version,
+        // A rejected token still means the gateway is up
+        error: health.detail.filter(|_| !reachable),
+    }
+}
+
+fn link_status(env: &Environment) -> LinkStatus {
+    match env.link_config() {
+        Some(config) => LinkStatus::Linked {
+            token_expires_at: token_expiry(&config.token),
+            device_name: config.device_name,
+            principal_id: config.principal_id,
+            gateway: config.gateway,
+        },
+        None => LinkStatus::NotLinked,
+    }
+}
+
+/// The `exp` claim of a JWT, read without verifying the signature
+pub fn token_expiry(token: &str) -> Option<i64> {
+    let payload = token.split('.').nth(1)?;
+    let bytes = base64::engine::general_purpose::URL_SAFE_NO_PAD
+        .decode(payload.trim_end_matches('='))
+        .ok()?;
+    let claims: serde_json::Value = serde_json::from_slice(&bytes).ok()?;
+    claims.get("exp")?.as_i64()
+}
+
+async fn swarm_status(env: &Environment) -> SwarmStatus {
+    let Some(config) = env.swarm_config() else {
+        return SwarmStatus::NotConfigured;
+    };
+    let status = match SocketClient::connect(&config.prefix).await {
+        Ok(mut client) => client.status().await,
+        Err(e) => Err(e),
+    };
+    match status {
+        Ok(status) => SwarmStatus::Running {
+            prefix: status.prefix,
+            agents: status.agents,
+        },
+        Err(_) => SwarmStatus::NotRunning {
+            prefix: config.prefix,
+        },
+    }
+}
+
+async fn agents_status(env: &Environment) -> AgentsStatus {
+    let mut client = match checks::connect(env).await {
+        Ok(client) => client,
+        Err(error) => return AgentsStatus::Unavailable { error },
+    };
+    match client
+        .list_agents(ListAgentsRequest { workspace: None })
+        .await
+    {
+        Ok(response) => {
+            let (online, offline): (Vec<_>, Vec<_>) = response
+                .into_inner()
+                .agents
+                .into_iter()
+                .partition(|a| a.connected);
+            AgentsStatus::Listed {
+                online: online.into_iter().map(|a| a.name).collect(),
+                offline: offline.into_iter().map(|a| a.name).collect(),
+            }
+        }
+        Err(status) => AgentsStatus::Unavailable {
+            error: status.message().to_string(),
+        },
+    }
+}
+
+/// Merge installed packs with those running in the background, by name
+pub fn packs_status(
+    manifest: anyhow::Result<Manifest>,
+    running: anyhow::Result<Vec<PackRecord>>,
+) -> PacksStatus {
+    let (manifest, running) = match (manifest, running) {
+        (Ok(manifest), Ok(running)) => (manifest, running),
+        (Err(e), _) | (_, Err(e)) => {
+            return PacksStatus::Unavailable {
+                error: format!("{:#}", e),
+            }
+        }
+    };
+
+    let mut packs: BTreeMap<String, PackStatus> = manifest
+        .packs
+        .into_iter()
+        .map(|(name, installed)| {
+            let status = PackStatus {
+                name: name.clone(),
+                version: Some(installed.version),
+                running: false,
+                uptime_secs: None,
+            };
+            (name, status)
+        })
+        .collect();
+    for record in running {
+        let entry = packs
+            .entry(record.name.clone())
+            .or_insert_with(|| PackStatus {
+                name: record.name.clone(),
+                version: None,
+                running: false,
+                uptime_secs: None,
+            });
+        entry.running = true;
+        entry.uptime_secs = Some(record.uptime_secs());
+    }
+    PacksStatus::Listed {
+        packs: packs.into_values().collect(),
+    }
+}
+
+impl Overview {
+    /// One line per section, with swarm agents and packs listed below.
+    /// `now` is Unix seconds, for the token's time to expiry.
+    pub fn render_text(&self, now: i64) -> String {
+        let mut out = String::new();
+        let mut line = |label: &str, text: String| {
+            out.push_str(&format!("{:8} {}\n", label, text));
+        };
+
+        let gateway = &self.gateway;
+        line(
+            "gateway:",
+            match (&gateway.error, &gateway.version) {
+                (Some(error), _) => format!("{} unreachable ({})", gateway.url, error),
+                (None, Some(version)) => format!(
+                    "{} reachable, {} {}",
+                    gateway.url,
+                    gateway.component.as_deref().unwrap_or("gateway"),
+                    version
+                ),
+                (None, None) => format!("{} reachable, version not reported", gateway.url),
+            },
+        );
+
+        line(
+            "link:",
+            match &self.link {
+                LinkStatus::NotLinked => "not linked (run `coven link <gateway>`)".to_string(),
+                LinkStatus::Linked {
+                    device_name,
+                    principal_id,
+                    gateway,
+                    token_expires_at,
+                } => format!(
+                    "{} ({}) via {}, {}",
+                    device_name,
+                    principal_id,
+                    gateway,
+                    describe_expiry(*token_expires_at, now)
+                ),
+            },
+        );
+
+        line(
+            "swarm:",
+            match &self.swarm {
+                SwarmStatus::NotConfigured => "not configured".to_string(),
+                SwarmStatus::NotRunning { prefix } => format!("not running (prefix '{}')", prefix),
+                SwarmStatus::Running { prefix, agents } => format!(
+                    "running (prefix '{}'), {}/{} agents up",
+                    prefix,
+                    agents.iter().filter(|a| a.running).count(),
+                    agents.len()
+                ),
+            },
+        );
+        if let SwarmStatus::Running { agents, .. } = &self.swarm {
+            for agent in agents {
+                let state = match (agent.running, agent.pid) {
+                    (true, Some(pid)) => format!("running (pid {})", pid),
+                    (true, None) => "running".to_string(),
+                    (false, _) => "stopped".to_string(),
+                };
+                line("", format!("  {} {}", agent.workspace, state));
+            }
+        }
+
+        line(
+            "agents:",
+            match &self.agents {
+                AgentsStatus::Unavailable { error } => format!("unavailable ({})", error),
+                AgentsStatus::Listed { online, offline } => {
+                    let mut text = format!("{} online, {} offline", online.len(), offline.len());
+                    if !online.is_empty() {
+                        text.push_str(&format!(": {}", online.join(", ")));
+                    }
+                    text
+                }
+            },
+        );
+
+        match &self.packs {
+            PacksStatus::Unavailable { error } => {
+                line("packs:", format!("unavailable ({})", error));
+            }
+            PacksStatus::Listed { packs } => {
+                line(
+                    "packs:",
+                    format!(
+                        "{} known, {} running",
+                        packs.len(),
+                        packs.iter().filter(|p| p.running).count()
+                    ),
+                );
+                for pack in packs {
+                    let version = pack
+                        .version
+                        .as_deref()
+                        .map(|v| format!(" {}", v))
+                        .unwrap_or_default();
+                    let state = match pack.uptime_secs {
+                        Some(secs) => format!("running {}", pack_daemon::format_uptime(secs)),
+                        None => "stopped".to_string(),
+                    };
+                    line("", format!("  {}{} {}", pack.name, version, state));
+                }
+            }
+        }
+
+        out
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("overview serializes")
+    }
+}
+
+/// Render a token expiry relative to `now`
+fn describe_expiry(expires_at: Option<i64>, now: i64) -> String {
+    match expires_at {
+        None => "token has no expiry".to_string(),
+        Some(exp) if exp <= now => format!(
+            "token EXPIRED {} ago",
+            pack_daemon::format_uptime((now - exp) as u64)
+        ),
+        Some(exp) => format!(
+            "token expires in {}",
+            pack_daemon::format_uptime((exp - now) as u64)
+        ),
+    }
+}
+
+/// Print the overview for this machine as text or JSON
+pub async fn run(gateway: Option<String>, json: bool) -> anyhow::Result<()> {
+    let env = Environment::from_system(gateway);
+    let overview = gather(&env).await;
+    if json {
+        println!("{}", overview.to_json());
+    } else {
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_secs() as i64)
+            .unwrap_or(0);
+        print!("{}", overview.render_text(now));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pack_install::{InstalledPack, PackSource};
+    use std::path::PathBuf;
+
+    fn jwt(claims: serde_json::Value) -> String {
+        let engine = base64::engine::general_purpose::URL_SAFE_NO_PAD;
+        format!(
+            "{}.{}.sig",
+            engine.encode(br#"{"alg":"HS256"}"#),
+            engine.encode(claims.to_string())
+        )
+    }
+
+    #[test]
+    fn test_token_expiry() {
+        assert_eq!(
+            token_expiry(&jwt(serde_json::json!({"sub": "p", "exp": 1_800_000_000}))),
+            Some(1_800_000_000)
+        );
+        assert_eq!(token_expiry(&jwt(serde_json::json!({"sub": "p"}))), None);
+        assert_eq!(token_expiry("not-a-jwt"), None);
+    }
+
+    #[test]
+    fn test_packs_status_merges_installed_and_running() {
+        let mut manifest = Manifest::default();
+        manifest.packs.insert(
+            "notes".to_string(),
+            InstalledPack {
+                version: "0.2.0".to_string(),
+                binary: "notes-pack".to_string(),
+                revision: None,
+                source: PackSource::Path(PathBuf::from("/src/notes")),
+            },
+        );
+        let running = vec![PackRecord {
+            name: "test".to_string(),
+            supervisor_pid: 10,
+            supervisor_start: None,
+            pid: Some(11),
+            binary: PathBuf::from("/bin/test-pack"),
+            log: PathBuf::from("/tmp/test.log"),
+            started_at: 0,
+            restarts: 0,
+        }];
+
+        let PacksStatus::Listed { packs } = packs_status(Ok(manifest), Ok(running)) else {
+            panic!("expected packs");
+        };
+        let summary: Vec<_> = packs
+            .iter()
+            .map(|p| (p.name.as_str(), p.version.as_deref(), p.running))
+            .collect();
+        assert_eq!(
+            summary,
+            [("notes", Some("0.2.0"), false), ("test", None, true)]
+        );
+
+        assert!(matches!(
+            packs_status(Err(anyhow::anyhow!("bad manifest")), Ok(Vec::new())),
+            PacksStatus::Unavailable { .. }
+        ));
+    }
+
+    fn degraded() -> Overview {
+        Overview {
+            gateway: GatewayStatus {
+                url: "http://127.0.0.1:50051".to_string(),
+                reachable: false,
+                component: None,
+                version: None,
+                error: Some("connection refused".to_string()),
+            },
+            link: LinkStatus::NotLinked,
+            swarm: SwarmStatus::NotRunning {
+                prefix: "home".to_string(),
+            },
+            agents: AgentsStatus::Unavailable {
+                error: "connection refused".to_string(),
+            },
+            packs: PacksStatus::Listed { packs: Vec::new() },
+        }
+    }
+
+    #[test]
+    fn test_render_text_degrades_per_section() {
+        assert_eq!(
+            degraded().render_text(0),
+            "gateway: http://127.0.0.1:50051 unreachable (connection refused)\n\
+             link:    not linked (run `coven link <gateway>`)\n\
+             swarm:   not running (prefix 'home')\n\
+             agents:  unavailable (connection refused)\n\
+             packs:   0 known, 0 running\n"
+        );
+    }
+
+    #[test]
+    fn test_render_text_healthy() {
+        let overview = Overview {
+            gateway: GatewayStatus {
+                url: "http://gw:50051".to_string(),
+                reachable: true,
+                component: Some("coven-gateway".to_string()),
+                version: Some("0.4.0".to_string()),
+                error: None,
+            },
+            link: LinkStatus::Linked {
+                device_name: "laptop".to_string(),
+                principal_id: "p-1".to_string(),
+                gateway: "gw:50051".to_string(),
+                token_expires_at: Some(1000 + 2 * 86_400),
+            },
+            swarm: SwarmStatus::Running {
+                prefix: "home".to_string(),
+                agents: vec![
+                    AgentStatus {
+                        workspace: "research".to_string(),
+                        pid: Some(42),
+                        running: true,
+                    },
+                    AgentStatus {
+                        workspace: "ops".to_string(),
+                        pid: None,
+                        running: false,
+                    },
+                ],
+            },
+            agents: AgentsStatus::Listed {
+                online: vec!["home_research".to_string()],
+                offline: vec!["home_ops".to_string()],
+            },
+            packs: PacksStatus::Listed {
+                packs: vec![PackStatus {
+                    name: "notes".to_string(),
+                    version: Some("0.2.0".to_string()),
+                    running: true,
+                    uptime_secs: Some(90),
+                }],
+            },
+        };
+
+        assert_eq!(
+            overview.render_text(1000),
+            "gateway: http://gw:50051 reachable, coven-gateway 0.4.0\n\
+             link:    laptop (p-1) via gw:50051, token expires in 2d00h\n\
+             swarm:   running (prefix 'home'), 1/2 agents up\n\
+             \x20          research running (pid 42)\n\
+             \x20          ops stopped\n\
+             agents:  1 online, 1 offline: home_research\n\
+             packs:   1 known, 1 running\n\
+             \x20          notes 0.2.0 running 1m30s\n"
+        );
+    }
+
+    #[test]
+    fn test_json_tags_section_states() {
+        let json: serde_json::Value = serde_json::from_str(&degraded().to_json()).unwrap();
+        assert_eq!(json["gateway"]["reachable"], false);
+        assert_eq!(json["link"]["state"], "not_linked");
+        assert_eq!(json["swarm"]["state"], "not_running");
+        assert_eq!(json["swarm"]["prefix"], "home");
+        assert_eq!(json["agents"]["state"], "unavailable");
+        assert_eq!(json["packs"]["state"], "listed");
+    }
+}
@@ -0,0 +1,72 @@
+// ABOUTME: `coven admin store`: maintenance of the local agent thread store.
+// ABOUTME: Compacts the backend event log in place, or reports what compacting would save.
+
+use anyhow::{Context, Result};
+use coven_admin::output::{print_json, Table};
+use coven_admin::OutputFormat;
+use coven_core::store::Compaction;
+use coven_core::ThreadStore;
+use std::path::{Path, PathBuf};
+
+/// Compact the store at `db`, or the one agents on this machine use
+pub async fn compact(db: Option<PathBuf>, dry_run: bool, output: OutputFormat) -> Result<()> {
+    let path = match db {
+        Some(path) => path,
+        None => coven_core::Config::load()?.db_path(),
+    };
+    if !path.exists() {
+        anyhow::bail!("No thread store at {}", path.display());
+    }
+    let store = ThreadStore::open(&path)
+        .await
+        .with_context(|| format!("failed to open {}", path.display()))?;
+    let compaction = store.compact_events(dry_run).await?;
+    report(&path, &compaction, dry_run, output)
+}
+
+fn report(path: &Path, compaction: &Compaction, dry_run: bool, output: OutputFormat) -> Result<()> {
+    match output {
+        OutputFormat::Json => print_json(compaction)?,
+        OutputFormat::Table => Table::new(&[
+            "THREADS",
+            "TEXT_MERGED",
+            "TOOL_STATES_DROPPED",
+            "BYTES_SAVED",
+        ])
+        .row([
+            compaction.threads.to_string(),
+            compaction.text_merged.to_string(),
+            compaction.tool_states_dropped.to_string(),
+            compaction.bytes_saved.to_string(),
+        ])
+        .print(),
+        OutputFormat::Text => {
+            let verb = if dry_run {
+                "Would compact"
+            } else {
+                "Compacted"
+            };
+            if compaction.threads == 0 {
+                println!("Nothing to compact in {}", path.display());
+                return Ok(());
+            }
+            println!(
+                "{verb} {} thread(s) in {}: {} text event(s) merged, {} tool state(s) dropped, {} saved",
+                compaction.threads,
+                path.display(),
+                compaction.text_merged,
+                compaction.tool_states_dropped,
+                format_bytes(compaction.bytes_saved)
+            );
+        }
+    }
+    Ok(())
+}
+
+fn format_bytes(bytes: u64) -> String {
+    match bytes {
+        b if b >= 1024 * 1024 => format!("{:.1} MiB", b as f64 / (1024.0 * 1024.0)),
+        b if b >= 1024 => format!("{:.1} KiB", b as f64 / 1024.0),
+        b => format!("{b} B"),
+    }
+}
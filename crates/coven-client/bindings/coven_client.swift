@@ -807,15 +807,19 @@ public struct Agent {
     public var backend: String
     public var workingDir: String
     public var connected: Bool
+    public var status: String
+    public var statusEmoji: String
 
     // Default memberwise initializers are never public by default, so we
     // declare one manually.
-    public init(id: String, name: String, backend: String, workingDir: String, connected: Bool) {
+    public init(id: String, name: String, backend: String, workingDir: String, connected: Bool, status: String, statusEmoji: String) {
         self.id = id
         self.name = name
         self.backend = backend
         self.workingDir = workingDir
         self.connected = connected
+        self.status = status
+        self.statusEmoji = statusEmoji
     }
 }
 
@@ -838,6 +842,12 @@ extension Agent: Equatable, Hashable {
         if lhs.connected != rhs.connected {
             return false
         }
+        if lhs.status != rhs.status {
+            return false
+        }
+        if lhs.statusEmoji != rhs.statusEmoji {
+            return false
+        }
         return true
     }
 
@@ -847,6 +857,8 @@ extension Agent: Equatable, Hashable {
         hasher.combine(backend)
         hasher.combine(workingDir)
         hasher.combine(connected)
+        hasher.combine(status)
+        hasher.combine(statusEmoji)
     }
 }
 
@@ -862,7 +874,9 @@ public struct FfiConverterTypeAgent: FfiConverterRustBuffer {
                 name: FfiConverterString.read(from: &buf), 
                 backend: FfiConverterString.read(from: &buf), 
                 workingDir: FfiConverterString.read(from: &buf), 
-                connected: FfiConverterBool.read(from: &buf)
+                connected: FfiConverterBool.read(from: &buf), 
+                status: FfiConverterString.read(from: &buf), 
+                statusEmoji: FfiConverterString.read(from: &buf)
         )
     }
 
@@ -872,6 +886,8 @@ public struct FfiConverterTypeAgent: FfiConverterRustBuffer {
         FfiConverterString.write(value.backend, into: &buf)
         FfiConverterString.write(value.workingDir, into: &buf)
         FfiConverterBool.write(value.connected, into: &buf)
+        FfiConverterString.write(value.status, into: &buf)
+        FfiConverterString.write(value.statusEmoji, into: &buf)
     }
 }
 
@@ -0,0 +1,379 @@
+// ABOUTME: Offline cache of conversations and unsent messages for coven-client
+// ABOUTME: One JSON file at a caller-provided path, merged with gateway history by message id
+
+use crate::error::CovenError;
+use crate::models::{Attachment, Message};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+/// Messages kept per agent when the caller sets no limit
+pub const DEFAULT_MAX_MESSAGES: u32 = 500;
+
+/// Where the offline cache lives and how much of each conversation it keeps
+#[derive(Debug, Clone)]
+pub struct CacheConfig {
+    /// Cache file, created on first write along with its directory
+    pub path: String,
+    /// Newest messages kept per agent; 0 means `DEFAULT_MAX_MESSAGES`
+    pub max_messages_per_agent: u32,
+}
+
+/// A message sent while the gateway was unreachable, waiting to go out.
+/// Its ID is both the local message's ID and the send's idempotency key,
+/// so a retry is never delivered twice and the gateway's copy replaces
+/// the local one when history is reconciled.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PendingSend {
+    pub id: String,
+    pub content: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub attachments: Vec<Attachment>,
+}
+
+/// What the cache file holds
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct CacheContents {
+    #[serde(default)]
+    pub messages: HashMap<String, Vec<Message>>,
+    #[serde(default)]
+    pub outbox: HashMap<String, Vec<PendingSend>>,
+}
+
+/// Borrowed view of the client's state, written without copying it
+#[derive(Serialize)]
+struct Snapshot<'a> {
+    messages: BTreeMap<&'a str, &'a [Message]>,
+    outbox: BTreeMap<&'a str, &'a [PendingSend]>,
+}
+
+/// The cache file and its limits
+#[derive(Debug, Clone)]
+pub struct MessageCache {
+    path: PathBuf,
+    max_messages: usize,
+    /// Numbers snapshots in the order they're taken
+    taken: Arc<AtomicU64>,
+    /// Newest snapshot written, so an older one never overwrites it
+    written: Arc<Mutex<u64>>,
+}
+
+/// The cache's contents at one moment, serialized while the client's state
+/// was locked and written after it's released
+#[derive(Debug)]
+pub struct CacheWrite {
+    cache: MessageCache,
+    generation: u64,
+    data: Vec<u8>,
+}
+
+impl MessageCache {
+    pub fn new(config: &CacheConfig) -> Self {
+        let max_messages = match config.max_messages_per_agent {
+            0 => DEFAULT_MAX_MESSAGES,
+            n => n,
+        };
+        Self {
+            path: PathBuf::from(&config.path),
+            max_messages: max_messages as usize,
+            taken: Arc::new(AtomicU64::new(0)),
+            written: Arc::new(Mutex::new(0)),
+        }
+    }
+
+    /// Read the cache. A missing file is an empty cache, and so is one that
+    /// can't be parsed: the gateway's history is the fallback either way.
+    pub fn load(&self) -> Result<CacheContents, CovenError> {
+        let data = match std::fs::read(&self.path) {
+            Ok(data) => data,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Default::default()),
+            Err(e) => {
+                return Err(CovenError::Api(format!(
+                    "failed to read message cache {}: {}",
+                    self.path.display(),
+                    e
+                )))
+            }
+        };
+        Ok(serde_json::from_slice(&data).unwrap_or_else(|e| {
+            tracing::warn!(
+                "Ignoring unreadable message cache {}: {}",
+                self.path.display(),
+                e
+            );
+            CacheContents::default()
+        }))
+    }
+
+    /// Write `messages` (the newest `max_messages` per agent) and `outbox`
+    pub fn save(
+        &self,
+        messages: &HashMap<String, Vec<Message>>,
+        outbox: &HashMap<String, Vec<PendingSend>>,
+    ) -> Result<(), CovenError> {
+        self.snapshot(messages, outbox)?.write()
+    }
+
+    /// Serialize `messages` (the newest `max_messages` per agent) and
+    /// `outbox`, to be written with `CacheWrite::write`
+    pub fn snapshot(
+        &self,
+        messages: &HashMap<String, Vec<Message>>,
+        outbox: &HashMap<String, Vec<PendingSend>>,
+    ) -> Result<CacheWrite, CovenError> {
+        let snapshot = Snapshot {
+            messages: messages
+                .iter()
+                .map(|(agent, list)| {
+                    let skip = list.len().saturating_sub(self.max_messages);
+                    (agent.as_str(), &list[skip..])
+                })
+                .collect(),
+            outbox: outbox
+                .iter()
+                .filter(|(_, pending)| !pending.is_empty())
+                .map(|(agent, pending)| (agent.as_str(), pending.as_slice()))
+                .collect(),
+        };
+        let data = serde_json::to_vec(&snapshot)
+            .map_err(|e| CovenError::Api(format!("failed to serialize message cache: {}", e)))?;
+        Ok(CacheWrite {
+            cache: self.clone(),
+            generation: self.taken.fetch_add(1, Ordering::SeqCst) + 1,
+            data,
+        })
+    }
+}
+
+impl CacheWrite {
+    /// Replace the file in one rename, so a crash never leaves it half
+    /// written. A snapshot older than the last one written is dropped.
+    pub fn write(self) -> Result<(), CovenError> {
+        let cache = &self.cache;
+        let mut written = cache.written.lock().unwrap_or_else(|e| e.into_inner());
+        if self.generation <= *written {
+            return Ok(());
+        }
+        let write = || -> std::io::Result<()> {
+            if let Some(dir) = cache.path.parent() {
+                std::fs::create_dir_all(dir)?;
+            }
+            let tmp = cache.path.with_extension("tmp");
+            std::fs::write(&tmp, &self.data)?;
+            std::fs::rename(&tmp, &cache.path)
+        };
+        write().map_err(|e| {
+            CovenError::Api(format!(
+                "failed to write message cache {}: {}",
+                cache.path.display(),
+                e
+            ))
+        })?;
+        *written = self.generation;
+        Ok(())
+    }
+}
+
+/// Merge history just fetched from the gateway into the messages already
+/// held for a conversation. The gateway's copy of each message wins. Local
+/// messages it doesn't have are kept when they predate the fetched window
+/// or are still waiting in the outbox; the rest were provisional copies of
+/// streamed replies, which the fetched history now holds under gateway IDs.
+pub fn reconcile(
+    cached: &[Message],
+    fetched: Vec<Message>,
+    pending: &[PendingSend],
+) -> Vec<Message> {
+    let fetched_ids: HashSet<&str> = fetched.iter().map(|m| m.id.as_str()).collect();
+    let pending_ids: HashSet<&str> = pending.iter().map(|p| p.id.as_str()).collect();
+    let window_start = fetched.iter().map(|m| m.timestamp).min();
+
+    let mut merged: Vec<Message> = cached
+        .iter()
+        .filter(|m| !fetched_ids.contains(m.id.as_str()))
+        .filter(|m| {
+            pending_ids.contains(m.id.as_str())
+                || window_start.is_some_and(|start| m.timestamp < start)
+        })
+        .cloned()
+        .collect();
+    let mut seen = HashSet::new();
+    merged.extend(fetched.into_iter().filter(|m| seen.insert(m.id.clone())));
+    // Stable, so messages with equal timestamps keep their order
+    merged.sort_by_key(|m| m.timestamp);
+    merged
+}
+
+/// Attachment bytes as one base64 string in the cache file. Files cached
+/// before this as arrays of numbers still load.
+pub(crate) mod base64_data {
+    use base64::engine::general_purpose::STANDARD;
+    use base64::Engine;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(data: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&STANDARD.encode(data))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Data {
+            Encoded(String),
+            Bytes(Vec<u8>),
+        }
+        match Data::deserialize(deserializer)? {
+            Data::Encoded(encoded) => STANDARD.decode(encoded).map_err(serde::de::Error::custom),
+            Data::Bytes(bytes) => Ok(bytes),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message(id: &str, timestamp: i64, is_user: bool) -> Message {
+        Message {
+            id: id.to_string(),
+            sender: if is_user { "You" } else { "Agent" }.to_string(),
+            content: format!("content of {}", id),
+            timestamp,
+            is_user,
+            reply_to_message_id: None,
+        }
+    }
+
+    fn ids(messages: &[Message]) -> Vec<&str> {
+        messages.iter().map(|m| m.id.as_str()).collect()
+    }
+
+    #[test]
+    fn test_reconcile_dedupes_and_keeps_pending() {
+        let cached = vec![
+            message("old", 10, true),
+            message("m1", 20, true),
+            message("agent-local", 30, false),
+            message("queued", 50, true),
+        ];
+        let fetched = vec![
+            message("m1", 20, true),
+            message("m2", 30, false),
+            message("missed", 40, false),
+            message("missed", 40, false),
+        ];
+        let pending = [PendingSend {
+            id: "queued".to_string(),
+            content: "content of queued".to_string(),
+            attachments: vec![],
+        }];
+
+        let merged = reconcile(&cached, fetched, &pending);
+        assert_eq!(ids(&merged), ["old", "m1", "m2", "missed", "queued"]);
+    }
+
+    #[test]
+    fn test_reconcile_with_no_history_keeps_only_pending() {
+        let cached = vec![
+            message("agent-local", 30, false),
+            message("queued", 50, true),
+        ];
+        let pending = [PendingSend {
+            id: "queued".to_string(),
+            content: String::new(),
+            attachments: vec![],
+        }];
+        assert_eq!(ids(&reconcile(&cached, vec![], &pending)), ["queued"]);
+    }
+
+    #[test]
+    fn test_cache_round_trip_trims_to_limit() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = MessageCache::new(&CacheConfig {
+            path: dir.path().join("nested/cache.json").display().to_string(),
+            max_messages_per_agent: 2,
+        });
+        assert!(cache.load().unwrap().messages.is_empty());
+
+        let messages = HashMap::from([(
+            "agent-1".to_string(),
+            vec![
+                message("a", 1, true),
+                message("b", 2, false),
+                message("c", 3, true),
+            ],
+        )]);
+        let outbox = HashMap::from([
+            (
+                "agent-1".to_string(),
+                vec![PendingSend {
+                    id: "c".to_string(),
+                    content: "content of c".to_string(),
+                    attachments: vec![],
+                }],
+            ),
+            ("agent-2".to_string(), vec![]),
+        ]);
+        cache.save(&messages, &outbox).unwrap();
+
+        let loaded = cache.load().unwrap();
+        assert_eq!(ids(&loaded.messages["agent-1"]), ["b", "c"]);
+        assert_eq!(loaded.outbox["agent-1"][0].id, "c");
+        assert!(!loaded.outbox.contains_key("agent-2"));
+    }
+
+    #[test]
+    fn test_attachments_are_cached_as_base64() {
+        let send = PendingSend {
+            id: "m1".to_string(),
+            content: String::new(),
+            attachments: vec![Attachment {
+                filename: "notes.txt".to_string(),
+                mime_type: "text/plain".to_string(),
+                data: b"hello".to_vec(),
+            }],
+        };
+        let json = serde_json::to_value(&send).unwrap();
+        assert_eq!(json["attachments"][0]["data"], "aGVsbG8=");
+        assert_eq!(serde_json::from_value::<PendingSend>(json).unwrap(), send);
+
+        // Caches written before base64 still load
+        let old = r#"{"id":"m1","content":"","attachments":[{"filename":"notes.txt","mime_type":"text/plain","data":[104,101,108,108,111]}]}"#;
+        assert_eq!(serde_json::from_str::<PendingSend>(old).unwrap(), send);
+    }
+
+    #[test]
+    fn test_older_snapshot_never_overwrites_newer() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = MessageCache::new(&CacheConfig {
+            path: dir.path().join("cache.json").display().to_string(),
+            max_messages_per_agent: 0,
+        });
+        let older = HashMap::from([("agent-1".to_string(), vec![message("a", 1, true)])]);
+        let newer = HashMap::from([(
+            "agent-1".to_string(),
+            vec![message("a", 1, true), message("b", 2, false)],
+        )]);
+        let first = cache.snapshot(&older, &HashMap::new()).unwrap();
+        let second = cache.snapshot(&newer, &HashMap::new()).unwrap();
+
+        // Written in the other order, as two threads releasing the lock might
+        second.write().unwrap();
+        first.write().unwrap();
+        assert_eq!(ids(&cache.load().unwrap().messages["agent-1"]), ["a", "b"]);
+    }
+
+    #[test]
+    fn test_unreadable_cache_is_empty() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("cache.json");
+        std::fs::write(&path, "not json").unwrap();
+        let cache = MessageCache::new(&CacheConfig {
+            path: path.display().to_string(),
+            max_messages_per_agent: 0,
+        });
+        assert!(cache.load().unwrap().outbox.is_empty());
+    }
+}
@@ -1,24 +1,37 @@
 // ABOUTME: Main CovenClient implementation using gRPC
 // ABOUTME: Provides async API for gateway communication (used by both FFI and native Rust)
 
+use crate::cache::{self, CacheConfig, CacheWrite, MessageCache, PendingSend};
+use crate::diagnostics::{self, DiagnosticReport, SystemResolver};
 use crate::error::CovenError;
 use crate::models::*;
 use crate::{StateCallback, StreamCallback};
 use coven_grpc::{create_channel, ChannelConfig};
-use coven_proto::client::ClientServiceClient;
+use coven_proto::client::{AdminServiceClient, ClientServiceClient};
 use coven_proto::{
-    client_stream_event, ApproveToolRequest, ClientSendMessageRequest, ClientStreamEvent,
-    GetEventsRequest, ListAgentsRequest, StreamEventsRequest,
+    client_stream_event, ApprovePairingRequest, ApproveToolRequest, ClientSendMessageRequest,
+    ClientSendMessageResponse, ClientStreamEvent, ForkThreadRequest, GetApprovalHistoryRequest,
+    GetEventsRequest, ListAgentsRequest, ListPendingApprovalsRequest, ListThreadsRequest,
+    RegisterPushTokenRequest, StreamEventsRequest, ToolSummary, UnregisterPushTokenRequest,
 };
-use coven_ssh::{load_or_generate_key, PrivateKey, SshAuthCredentials};
-use futures::StreamExt;
-use std::collections::HashMap;
+use coven_ssh::{
+    load_or_generate_key_with_passphrase, PassphraseSource, PrivateKey, SshAuthCredentials,
+};
+use futures::{Stream, StreamExt};
+use std::collections::{HashMap, HashSet};
+use std::ops::{Deref, DerefMut};
 use std::path::Path;
 use std::sync::atomic::{AtomicU64, Ordering};
-use std::sync::{Arc, RwLock};
+use std::sync::{Arc, RwLock, RwLockWriteGuard};
+use tokio::sync::{broadcast, mpsc, watch};
+use tokio::task::JoinHandle;
 use tokio_util::sync::CancellationToken;
 use tonic::transport::Channel;
 
+/// How many stream events a slow subscriber may fall behind before it
+/// starts missing them
+const EVENT_BUFFER: usize = 1024;
+
 /// Counter for generating unique idempotency keys
 static IDEMPOTENCY_COUNTER: AtomicU64 = AtomicU64::new(0);
 
@@ -31,6 +44,15 @@ fn generate_idempotency_key() -> String {
     format!("coven-client-{}-{}", timestamp, count)
 }
 
+/// A send the gateway answered can still have been refused by its content
+/// policy; that is an error for the caller, not a message in flight
+fn check_send_status(response: &ClientSendMessageResponse) -> Result<(), CovenError> {
+    match response.blocked_reason() {
+        Some(reason) => Err(CovenError::Blocked(reason.to_string())),
+        None => Ok(()),
+    }
+}
+
 /// Internal state for an active stream
 struct ActiveStream {
     cancel: CancellationToken,
@@ -42,24 +64,147 @@ struct ActiveStream {
 struct ClientState {
     // Agents
     agents: Vec<Agent>,
-    connection_status: ConnectionStatus,
+
+    // Event surfaces: every stream event as (agent_id, event), and the
+    // connection status. The FFI callbacks are fed from these.
+    events: broadcast::Sender<(String, StreamEvent)>,
+    connection: watch::Sender<ConnectionStatus>,
 
     // Per-agent state (keyed by conversation_key which is typically agent_id)
     messages: HashMap<String, Vec<Message>>,
+    // Agent ID of each conversation forked with `fork_thread`, by its key
+    forks: HashMap<String, String>,
     queues: HashMap<String, Vec<String>>,
     unread: HashMap<String, u32>,
 
-    // Active streams (keyed by conversation_key)
+    // Offline support: the cache file and a snapshot of it not yet written,
+    // messages sent while the gateway was unreachable, and conversations
+    // refreshed since the last reconnect
+    cache: Option<MessageCache>,
+    unsaved: Option<CacheWrite>,
+    outbox: HashMap<String, Vec<PendingSend>>,
+    refreshed: HashSet<String>,
+
+    // Active streams (keyed by conversation_key), and whether responses
+    // arrive as they're written or all at once when done
     streams: HashMap<String, ActiveStream>,
+    streaming: bool,
 
-    // Callbacks
-    stream_callback: Option<Box<dyn StreamCallback>>,
-    state_callback: Option<Box<dyn StateCallback>>,
+    // Callbacks, and the tasks forwarding the event surfaces to them
+    state_callback: Option<Arc<dyn StateCallback>>,
+    stream_callback: Option<mpsc::UnboundedSender<(String, StreamEvent)>>,
+    stream_forwarder: Option<JoinHandle<()>>,
+    connection_forwarder: Option<JoinHandle<()>>,
 
     // Session usage
     session_usage: UsageInfo,
 }
 
+impl ClientState {
+    /// Publish a stream event to the stream callback and every subscriber
+    fn emit(&self, agent_id: &str, event: StreamEvent) {
+        if let Some(callback) = &self.stream_callback {
+            let _ = callback.send((agent_id.to_string(), event.clone()));
+        }
+        // An error only means nobody is subscribed right now
+        let _ = self.events.send((agent_id.to_string(), event));
+    }
+
+    /// Messages waiting for an agent: queued behind its stream, or held
+    /// until the gateway is reachable
+    fn queue_count(&self, agent_id: &str) -> u32 {
+        let queued = self.queues.get(agent_id).map_or(0, Vec::len);
+        let pending = self.outbox.get(agent_id).map_or(0, Vec::len);
+        (queued + pending) as u32
+    }
+
+    fn notify_queue(&self, agent_id: &str) {
+        if let Some(cb) = &self.state_callback {
+            cb.on_queue_changed(agent_id.to_string(), self.queue_count(agent_id));
+        }
+    }
+
+    /// The agent a conversation is with. An agent's main conversation has
+    /// its ID as the key.
+    fn agent_for(&self, conversation_key: &str) -> Option<&Agent> {
+        let agent_id = self
+            .forks
+            .get(conversation_key)
+            .map_or(conversation_key, String::as_str);
+        self.agents.iter().find(|a| a.id == agent_id)
+    }
+
+    fn is_offline(&self) -> bool {
+        *self.connection.borrow() == ConnectionStatus::Disconnected
+    }
+
+    /// Add the user's copy of a message being sent
+    fn push_user_message(&mut self, agent_id: &str, send: &PendingSend) {
+        let message = Message {
+            id: send.id.clone(),
+            ..Message::user(send.content.clone())
+        };
+        self.messages
+            .entry(agent_id.to_string())
+            .or_default()
+            .push(message);
+    }
+
+    /// Snapshot the cache, if it is enabled, to be written once the lock
+    /// is released (see `StateGuard`). A failed write is logged; the
+    /// in-memory state is still good.
+    fn persist(&mut self) {
+        if let Some(cache) = &self.cache {
+            match cache.snapshot(&self.messages, &self.outbox) {
+                Ok(write) => self.unsaved = Some(write),
+                Err(e) => tracing::warn!("{}", e),
+            }
+        }
+    }
+}
+
+/// Write access to the client state. The cache snapshot `persist` took is
+/// written after the lock is released, so other callers never wait on the
+/// file.
+struct StateGuard<'a> {
+    guard: Option<RwLockWriteGuard<'a, ClientState>>,
+}
+
+fn lock_state(state: &RwLock<ClientState>) -> StateGuard<'_> {
+    StateGuard {
+        guard: Some(state.write().expect("lock poisoned")),
+    }
+}
+
+impl Deref for StateGuard<'_> {
+    type Target = ClientState;
+
+    fn deref(&self) -> &ClientState {
+        self.guard.as_ref().expect("held until dropped")
+    }
+}
+
+impl DerefMut for StateGuard<'_> {
+    fn deref_mut(&mut self) -> &mut ClientState {
+        self.guard.as_mut().expect("held until dropped")
+    }
+}
+
+impl Drop for StateGuard<'_> {
+    fn drop(&mut self) {
+        let Some(mut guard) = self.guard.take() else {
+            return;
+        };
+        let unsaved = guard.unsaved.take();
+        drop(guard);
+        if let Some(write) = unsaved {
+            if let Err(e) = write.write() {
+                tracing::warn!("{}", e);
+            }
+        }
+    }
+}
+
 /// The main coven gateway client (gRPC-based)
 ///
 /// All methods are truly async - safe to call from any async context.
@@ -91,8 +236,19 @@ impl CovenClient {
     /// to protect signed headers from observation and potential replay attacks.
     /// A warning is logged if the gateway URL uses plaintext HTTP.
     pub fn new_with_auth(gateway_url: String, ssh_key_path: &Path) -> Result<Self, CovenError> {
+        Self::new_with_auth_passphrase(gateway_url, ssh_key_path, None)
+    }
+
+    /// Create a new client with SSH key authentication like `new_with_auth`,
+    /// decrypting an encrypted key (or encrypting a new one) with the
+    /// passphrase from `passphrase`
+    pub fn new_with_auth_passphrase(
+        gateway_url: String,
+        ssh_key_path: &Path,
+        passphrase: Option<&PassphraseSource>,
+    ) -> Result<Self, CovenError> {
         Self::warn_if_insecure_with_auth(&gateway_url);
-        let key = load_or_generate_key(ssh_key_path)
+        let key = load_or_generate_key_with_passphrase(ssh_key_path, passphrase)
             .map_err(|e| CovenError::Connection(format!("failed to load SSH key: {}", e)))?;
         Ok(Self::new_internal(gateway_url, Some(Arc::new(key))))
     }
@@ -104,6 +260,21 @@ impl CovenClient {
         Self::new_with_auth(gateway_url, Path::new(&ssh_key_path))
     }
 
+    /// Create a new client with an encrypted SSH key (UniFFI-compatible),
+    /// e.g. with a passphrase kept in the platform keychain. A new key is
+    /// written encrypted with it.
+    pub fn new_with_encrypted_ssh_key(
+        gateway_url: String,
+        ssh_key_path: String,
+        passphrase: String,
+    ) -> Result<Self, CovenError> {
+        Self::new_with_auth_passphrase(
+            gateway_url,
+            Path::new(&ssh_key_path),
+            Some(&PassphraseSource::Literal(passphrase)),
+        )
+    }
+
     /// Create a new client with a pre-loaded SSH private key
     ///
     /// # Security Note
@@ -153,13 +324,22 @@ impl CovenClient {
             gateway_url,
             state: Arc::new(RwLock::new(ClientState {
                 agents: vec![],
-                connection_status: ConnectionStatus::Connecting,
+                events: broadcast::channel(EVENT_BUFFER).0,
+                connection: watch::channel(ConnectionStatus::Connecting).0,
                 messages: HashMap::new(),
+                forks: HashMap::new(),
                 queues: HashMap::new(),
                 unread: HashMap::new(),
+                cache: None,
+                unsaved: None,
+                outbox: HashMap::new(),
+                refreshed: HashSet::new(),
                 streams: HashMap::new(),
-                stream_callback: None,
+                streaming: true,
                 state_callback: None,
+                stream_callback: None,
+                stream_forwarder: None,
+                connection_forwarder: None,
                 session_usage: UsageInfo::default(),
             })),
             runtime: Some(runtime),
@@ -206,11 +386,21 @@ impl CovenClient {
         status
     }
 
-    /// Create a gRPC channel
+    /// Create a gRPC channel. Reaching the gateway marks the client
+    /// connected, which sends anything queued while it wasn't.
     async fn create_channel_internal(&self) -> Result<Channel, CovenError> {
         // Disable keep-alive to avoid h2 protocol errors with gateway
         // The gateway's keep-alive enforcement may be too strict for our ping timing
-        let config = ChannelConfig::new(&self.gateway_url).without_keep_alive();
+        let result = Self::connect(&self.gateway_url).await;
+        self.set_connection_status(match result {
+            Ok(_) => ConnectionStatus::Connected,
+            Err(_) => ConnectionStatus::Disconnected,
+        });
+        result
+    }
+
+    async fn connect(gateway_url: &str) -> Result<Channel, CovenError> {
+        let config = ChannelConfig::new(gateway_url).without_keep_alive();
         create_channel(&config)
             .await
             .map_err(|e| CovenError::Connection(e.to_string()))
@@ -224,13 +414,82 @@ impl CovenClient {
     }
 
     /// Set callback for stream events (synchronous - safe to call without runtime)
+    ///
+    /// The callback gets the same events as `subscribe_all_events`, on the
+    /// client's runtime, and replaces any previous callback. Its queue is
+    /// unbounded, so a slow callback is never skipped past a chunk.
     pub fn set_stream_callback(&self, callback: Box<dyn StreamCallback>) {
-        self.state.write().expect("lock poisoned").stream_callback = Some(callback);
+        let mut state = lock_state(&self.state);
+        let (tx, mut events) = mpsc::unbounded_channel();
+        let forwarder = self.runtime().spawn(async move {
+            while let Some((agent_id, event)) = events.recv().await {
+                callback.on_event(agent_id, event);
+            }
+        });
+        state.stream_callback = Some(tx);
+        if let Some(previous) = state.stream_forwarder.replace(forwarder) {
+            previous.abort();
+        }
     }
 
     /// Set callback for state changes (synchronous - safe to call without runtime)
+    ///
+    /// Connection status changes come from `connection_events`; the other
+    /// notifications are made as the state changes.
     pub fn set_state_callback(&self, callback: Box<dyn StateCallback>) {
-        self.state.write().expect("lock poisoned").state_callback = Some(callback);
+        let callback: Arc<dyn StateCallback> = Arc::from(callback);
+        let mut state = lock_state(&self.state);
+        let mut connection = state.connection.subscribe();
+        let forward_to = callback.clone();
+        let forwarder = self.runtime().spawn(async move {
+            while connection.changed().await.is_ok() {
+                let status = *connection.borrow_and_update();
+                forward_to.on_connection_status(status);
+            }
+        });
+        if let Some(previous) = state.connection_forwarder.replace(forwarder) {
+            previous.abort();
+        }
+        state.state_callback = Some(callback);
+    }
+
+    // =========================================================================
+    // Event Streams
+    // =========================================================================
+
+    /// Stream events for one agent from now on, ending when the client is
+    /// dropped. This is the native async counterpart of `StreamCallback`.
+    /// A subscriber that falls more than `EVENT_BUFFER` events behind
+    /// skips the missed events.
+    pub fn subscribe_events(&self, agent_id: String) -> impl Stream<Item = StreamEvent> + Send {
+        self.subscribe_all_events().filter_map(move |(id, event)| {
+            futures::future::ready((id == agent_id).then_some(event))
+        })
+    }
+
+    /// Stream events for every agent as `(agent_id, event)` pairs
+    pub fn subscribe_all_events(&self) -> impl Stream<Item = (String, StreamEvent)> + Send {
+        let events = self.state.read().expect("lock poisoned").events.subscribe();
+        futures::stream::unfold(events, |mut events| async move {
+            loop {
+                match events.recv().await {
+                    Ok(item) => return Some((item, events)),
+                    Err(broadcast::error::RecvError::Lagged(missed)) => {
+                        tracing::warn!("Event subscriber fell behind, missed {} events", missed);
+                    }
+                    Err(broadcast::error::RecvError::Closed) => return None,
+                }
+            }
+        })
+    }
+
+    /// The gateway connection status, updated as it changes
+    pub fn connection_events(&self) -> watch::Receiver<ConnectionStatus> {
+        self.state
+            .read()
+            .expect("lock poisoned")
+            .connection
+            .subscribe()
     }
 
     // =========================================================================
@@ -292,12 +551,46 @@ impl CovenClient {
         Ok(())
     }
 
-    /// Helper to update connection status and notify callback
+    /// Check the connection step by step, for troubleshooting when
+    /// `check_health` fails
+    ///
+    /// Runs DNS, TCP, TLS, gRPC, SSH auth and clock skew checks against the
+    /// gateway and reports how each went; it never fails itself. Unlike
+    /// `check_health` it leaves the connection status alone.
+    pub fn diagnose(&self) -> DiagnosticReport {
+        self.runtime().block_on(self.diagnose_async())
+    }
+
+    /// Async implementation of diagnose - use this from async contexts
+    pub async fn diagnose_async(&self) -> DiagnosticReport {
+        diagnostics::diagnose(&self.gateway_url, self.ssh_key.as_ref(), &SystemResolver).await
+    }
+
+    /// Helper to publish a connection status
+    ///
+    /// Coming back online (any call reaching the gateway) sends whatever
+    /// was queued while disconnected, and lets the next `get_messages` of
+    /// each conversation refresh it, since events may have been missed in
+    /// between.
     fn set_connection_status(&self, status: ConnectionStatus) {
-        let mut state_guard = self.state.write().expect("lock poisoned");
-        state_guard.connection_status = status;
-        if let Some(cb) = &state_guard.state_callback {
-            cb.on_connection_status(status);
+        let mut state_guard = lock_state(&self.state);
+        // Subscribers hear about changes only, as every call reports one
+        let changed = state_guard
+            .connection
+            .send_if_modified(|current| std::mem::replace(current, status) != status);
+        if status != ConnectionStatus::Connected || !changed {
+            return;
+        }
+        state_guard.refreshed.clear();
+        let has_pending = state_guard.outbox.values().any(|p| !p.is_empty());
+        drop(state_guard);
+
+        if has_pending {
+            self.runtime().spawn(Self::flush_outbox(
+                self.state.clone(),
+                self.gateway_url.clone(),
+                self.ssh_key.clone(),
+            ));
         }
     }
 
@@ -334,12 +627,8 @@ impl CovenClient {
             .map(Agent::from_proto)
             .collect();
 
-        let mut state_guard = self.state.write().expect("lock poisoned");
-        state_guard.agents = agents.clone();
-        state_guard.connection_status = ConnectionStatus::Connected;
-        if let Some(cb) = &state_guard.state_callback {
-            cb.on_connection_status(state_guard.connection_status);
-        }
+        lock_state(&self.state).agents = agents.clone();
+        self.set_connection_status(ConnectionStatus::Connected);
 
         Ok(agents)
     }
@@ -364,15 +653,60 @@ impl CovenClient {
     // Messages & History
     // =========================================================================
 
+    /// Keep conversations and unsent messages in a cache file, so they
+    /// survive restarts and network drops.
+    ///
+    /// Cached conversations are loaded now and served by `get_messages`
+    /// right away. With the cache on, the first `get_messages` of a
+    /// conversation after connecting also refreshes it from the gateway in
+    /// the background, and messages sent while the gateway is unreachable
+    /// are queued (see `get_queue_count`) and sent on reconnect.
+    pub fn enable_cache(&self, config: CacheConfig) -> Result<(), CovenError> {
+        let cache = MessageCache::new(&config);
+        let contents = cache.load()?;
+
+        let mut state_guard = lock_state(&self.state);
+        for (agent_id, messages) in contents.messages {
+            state_guard.messages.entry(agent_id).or_insert(messages);
+        }
+        for (agent_id, pending) in contents.outbox {
+            state_guard
+                .outbox
+                .entry(agent_id.clone())
+                .or_default()
+                .extend(pending);
+            state_guard.notify_queue(&agent_id);
+        }
+        state_guard.cache = Some(cache);
+        state_guard.persist();
+        Ok(())
+    }
+
     /// Get messages for an agent (from cache)
     pub fn get_messages(&self, agent_id: String) -> Vec<Message> {
-        self.state
-            .read()
-            .expect("lock poisoned")
+        let mut state_guard = lock_state(&self.state);
+        let messages = state_guard
             .messages
             .get(&agent_id)
             .cloned()
-            .unwrap_or_default()
+            .unwrap_or_default();
+
+        let refresh = state_guard.cache.is_some()
+            && !state_guard.is_offline()
+            && state_guard.refreshed.insert(agent_id.clone());
+        drop(state_guard);
+        if refresh {
+            let state = self.state.clone();
+            let gateway_url = self.gateway_url.clone();
+            let ssh_key = self.ssh_key.clone();
+            self.runtime().spawn(async move {
+                if let Err(e) = Self::refresh_history(&state, &gateway_url, ssh_key, agent_id).await
+                {
+                    tracing::debug!("Background history refresh failed: {}", e);
+                }
+            });
+        }
+        messages
     }
 
     /// Load history from server for a conversation
@@ -381,20 +715,43 @@ impl CovenClient {
     }
 
     /// Async implementation of load_history - use this from async contexts
+    ///
+    /// The fetched history is merged into the messages already held, by
+    /// message ID (see `cache::reconcile`), and the merged list returned.
     pub async fn load_history_async(&self, agent_id: String) -> Result<Vec<Message>, CovenError> {
+        let result = Self::refresh_history(
+            &self.state,
+            &self.gateway_url,
+            self.ssh_key.clone(),
+            agent_id,
+        )
+        .await;
+        match &result {
+            Ok(_) => self.set_connection_status(ConnectionStatus::Connected),
+            Err(CovenError::Connection(_)) => {
+                self.set_connection_status(ConnectionStatus::Disconnected)
+            }
+            Err(_) => {}
+        }
+        result
+    }
+
+    /// Fetch a conversation's history and merge it into the messages held
+    async fn refresh_history(
+        state: &Arc<RwLock<ClientState>>,
+        gateway_url: &str,
+        ssh_key: Option<Arc<PrivateKey>>,
+        agent_id: String,
+    ) -> Result<Vec<Message>, CovenError> {
         // Get agent name for message attribution
         let agent_name = {
-            let state_guard = self.state.read().expect("lock poisoned");
+            let state_guard = state.read().expect("lock poisoned");
             state_guard
-                .agents
-                .iter()
-                .find(|a| a.id == agent_id)
+                .agent_for(&agent_id)
                 .map(|a| a.name.clone())
                 .unwrap_or_else(|| "Agent".into())
         };
 
-        let channel = self.create_channel_internal().await?;
-
         // Use agent_id as conversation_key
         let request = GetEventsRequest {
             conversation_key: agent_id.clone(),
@@ -404,7 +761,71 @@ impl CovenClient {
             cursor: None,
         };
 
-        let response = if let Some(ref key) = self.ssh_key {
+        let events = Self::get_events(gateway_url, ssh_key, request)
+            .await?
+            .events;
+        let fetched: Vec<Message> = events
+            .into_iter()
+            .filter_map(|e| Message::from_event(e, &agent_name))
+            .collect();
+
+        // Cache the messages
+        let mut state_guard = lock_state(&state);
+        let messages = cache::reconcile(
+            state_guard
+                .messages
+                .get(&agent_id)
+                .map_or(&[], Vec::as_slice),
+            fetched,
+            state_guard.outbox.get(&agent_id).map_or(&[], Vec::as_slice),
+        );
+        state_guard
+            .messages
+            .insert(agent_id.clone(), messages.clone());
+        state_guard.persist();
+        if let Some(cb) = &state_guard.state_callback {
+            cb.on_messages_changed(agent_id.clone());
+        }
+
+        Ok(messages)
+    }
+
+    /// Load one page of a conversation's full event log, including tool
+    /// calls and results. Start with `cursor: None` and pass each page's
+    /// `next_cursor` to the next call until it is `None`. Unlike
+    /// `load_history`, nothing is cached.
+    pub async fn load_history_page_async(
+        &self,
+        conversation_key: String,
+        cursor: Option<String>,
+    ) -> Result<HistoryPage, CovenError> {
+        let request = GetEventsRequest {
+            conversation_key,
+            since: None,
+            until: None,
+            limit: Some(500),
+            cursor,
+        };
+
+        let response = Self::get_events(&self.gateway_url, self.ssh_key.clone(), request).await?;
+        Ok(HistoryPage {
+            events: response
+                .events
+                .into_iter()
+                .map(HistoryEvent::from_event)
+                .collect(),
+            next_cursor: response.next_cursor.filter(|_| response.has_more),
+        })
+    }
+
+    async fn get_events(
+        gateway_url: &str,
+        ssh_key: Option<Arc<PrivateKey>>,
+        request: GetEventsRequest,
+    ) -> Result<coven_proto::GetEventsResponse, CovenError> {
+        let channel = Self::connect(gateway_url).await?;
+
+        let response = if let Some(ref key) = ssh_key {
             let mut client = ClientServiceClient::with_interceptor(
                 channel,
                 Self::make_ssh_interceptor(key.clone()),
@@ -421,22 +842,7 @@ impl CovenClient {
                 .map_err(|e| CovenError::Api(e.to_string()))?
         };
 
-        let events = response.into_inner().events;
-        let messages: Vec<Message> = events
-            .into_iter()
-            .filter_map(|e| Message::from_event(e, &agent_name))
-            .collect();
-
-        // Cache the messages
-        let mut state_guard = self.state.write().expect("lock poisoned");
-        state_guard
-            .messages
-            .insert(agent_id.clone(), messages.clone());
-        if let Some(cb) = &state_guard.state_callback {
-            cb.on_messages_changed(agent_id.clone());
-        }
-
-        Ok(messages)
+        Ok(response.into_inner())
     }
 
     /// Get unread count for an agent
@@ -452,7 +858,7 @@ impl CovenClient {
 
     /// Clear unread count (when user views agent)
     pub fn clear_unread(&self, agent_id: String) {
-        let mut state = self.state.write().expect("lock poisoned");
+        let mut state = lock_state(&self.state);
         state.unread.insert(agent_id.clone(), 0);
         if let Some(cb) = &state.state_callback {
             cb.on_unread_changed(agent_id, 0);
@@ -465,10 +871,27 @@ impl CovenClient {
 
     /// Send a message to an agent (starts streaming response)
     pub fn send_message(&self, agent_id: String, content: String) -> Result<(), CovenError> {
-        let mut state_guard = self.state.write().expect("lock poisoned");
+        self.send_message_with_attachments(agent_id, content, Vec::new())
+    }
+
+    /// Send a message with files attached. The files must fit in
+    /// `MAX_ATTACHMENT_BYTES` together. Unlike plain text, a message with
+    /// attachments isn't queued behind a response still streaming from the
+    /// same agent; that fails with `AlreadyStreaming`.
+    pub fn send_message_with_attachments(
+        &self,
+        agent_id: String,
+        content: String,
+        attachments: Vec<Attachment>,
+    ) -> Result<(), CovenError> {
+        check_attachments(&attachments)?;
+        let mut state_guard = lock_state(&self.state);
 
         // Check if already streaming to this agent
         if state_guard.streams.contains_key(&agent_id) {
+            if !attachments.is_empty() {
+                return Err(CovenError::AlreadyStreaming);
+            }
             // Queue the message
             state_guard
                 .queues
@@ -476,32 +899,43 @@ impl CovenClient {
                 .or_default()
                 .push(content);
 
-            let count = state_guard
-                .queues
-                .get(&agent_id)
-                .map(|q| q.len() as u32)
-                .unwrap_or(0);
+            state_guard.notify_queue(&agent_id);
+            return Ok(());
+        }
+
+        // The ID doubles as the idempotency key, so the gateway's copy of
+        // the message has the same ID as ours
+        let send = PendingSend {
+            id: generate_idempotency_key(),
+            content,
+            attachments,
+        };
+
+        // Known to be offline with the cache on: hold it for reconnect
+        if state_guard.cache.is_some() && state_guard.is_offline() {
+            state_guard.push_user_message(&agent_id, &send);
+            state_guard
+                .outbox
+                .entry(agent_id.clone())
+                .or_default()
+                .push(send);
+            state_guard.persist();
             if let Some(cb) = &state_guard.state_callback {
-                cb.on_queue_changed(agent_id.clone(), count);
+                cb.on_messages_changed(agent_id.clone());
             }
+            state_guard.notify_queue(&agent_id);
             return Ok(());
         }
 
         // Get agent info
         let agent = state_guard
-            .agents
-            .iter()
-            .find(|a| a.id == agent_id)
+            .agent_for(&agent_id)
             .cloned()
             .ok_or_else(|| CovenError::AgentNotFound(agent_id.clone()))?;
 
         // Add user message to history
-        let user_msg = Message::user(content.clone());
-        state_guard
-            .messages
-            .entry(agent_id.clone())
-            .or_default()
-            .push(user_msg);
+        state_guard.push_user_message(&agent_id, &send);
+        state_guard.persist();
         if let Some(cb) = &state_guard.state_callback {
             cb.on_messages_changed(agent_id.clone());
         }
@@ -532,7 +966,7 @@ impl CovenClient {
                 state_clone,
                 gateway_url,
                 agent_id_clone,
-                content,
+                send,
                 cancel,
                 ssh_key,
             )
@@ -542,6 +976,15 @@ impl CovenClient {
         Ok(())
     }
 
+    /// Have responses streamed as the agent writes them (the default), or,
+    /// with `false`, delivered all at once when the agent is done: a
+    /// ToolState event per tool it used (the detail is the tool's name), one
+    /// Text event with the full response, then Done. Buffering saves battery
+    /// and data on mobile. Applies to messages sent from now on.
+    pub fn set_streaming(&self, enabled: bool) {
+        lock_state(&self.state).streaming = enabled;
+    }
+
     /// Check if an agent is currently streaming
     pub fn is_streaming(&self, agent_id: String) -> bool {
         self.state
@@ -574,15 +1017,13 @@ impl CovenClient {
     // Queue Management
     // =========================================================================
 
-    /// Get queued message count for an agent
+    /// Get queued message count for an agent, including messages waiting
+    /// for the gateway to be reachable again
     pub fn get_queue_count(&self, agent_id: String) -> u32 {
         self.state
             .read()
             .expect("lock poisoned")
-            .queues
-            .get(&agent_id)
-            .map(|q| q.len() as u32)
-            .unwrap_or(0)
+            .queue_count(&agent_id)
     }
 
     // =========================================================================
@@ -658,6 +1099,326 @@ impl CovenClient {
         }
     }
 
+    /// Tool approvals an agent is still waiting on, oldest first. Use this to
+    /// show approvals requested before the app was listening.
+    pub fn list_pending_approvals(
+        &self,
+        agent_id: String,
+    ) -> Result<Vec<PendingApproval>, CovenError> {
+        self.runtime()
+            .block_on(self.list_pending_approvals_async(agent_id))
+    }
+
+    /// Async implementation of list_pending_approvals - use this from async contexts
+    pub async fn list_pending_approvals_async(
+        &self,
+        agent_id: String,
+    ) -> Result<Vec<PendingApproval>, CovenError> {
+        let channel = self.create_channel_internal().await?;
+
+        let request = ListPendingApprovalsRequest { agent_id };
+
+        let response = if let Some(ref key) = self.ssh_key {
+            let mut client = ClientServiceClient::with_interceptor(
+                channel,
+                Self::make_ssh_interceptor(key.clone()),
+            );
+            client
+                .list_pending_approvals(request)
+                .await
+                .map_err(|e| CovenError::Api(e.to_string()))?
+        } else {
+            let mut client = ClientServiceClient::new(channel);
+            client
+                .list_pending_approvals(request)
+                .await
+                .map_err(|e| CovenError::Api(e.to_string()))?
+        };
+
+        let now = chrono::Utc::now();
+        Ok(response
+            .into_inner()
+            .approvals
+            .into_iter()
+            .map(|record| PendingApproval::from_proto(record, now))
+            .collect())
+    }
+
+    /// An agent's resolved tool approvals, most recent first. A `limit` of 0
+    /// uses the gateway's default.
+    pub fn approval_history(
+        &self,
+        agent_id: String,
+        limit: u32,
+    ) -> Result<Vec<ApprovalRecord>, CovenError> {
+        self.runtime()
+            .block_on(self.approval_history_async(agent_id, limit))
+    }
+
+    /// Async implementation of approval_history - use this from async contexts
+    pub async fn approval_history_async(
+        &self,
+        agent_id: String,
+        limit: u32,
+    ) -> Result<Vec<ApprovalRecord>, CovenError> {
+        let channel = self.create_channel_internal().await?;
+
+        let request = GetApprovalHistoryRequest {
+            agent_id,
+            limit: limit.min(i32::MAX as u32) as i32,
+        };
+
+        let response = if let Some(ref key) = self.ssh_key {
+            let mut client = ClientServiceClient::with_interceptor(
+                channel,
+                Self::make_ssh_interceptor(key.clone()),
+            );
+            client
+                .get_approval_history(request)
+                .await
+                .map_err(|e| CovenError::Api(e.to_string()))?
+        } else {
+            let mut client = ClientServiceClient::new(channel);
+            client
+                .get_approval_history(request)
+                .await
+                .map_err(|e| CovenError::Api(e.to_string()))?
+        };
+
+        Ok(response
+            .into_inner()
+            .approvals
+            .into_iter()
+            .map(ApprovalRecord::from_proto)
+            .collect())
+    }
+
+    // =========================================================================
+    // Forking
+    // =========================================================================
+
+    /// Start a new conversation from `conversation_key`'s messages up to
+    /// and including `message_id`, leaving the original as it is. Returns
+    /// the new conversation's key, which can be used wherever an agent ID
+    /// names a conversation. The agent must be connected.
+    pub fn fork_thread(
+        &self,
+        conversation_key: String,
+        message_id: String,
+    ) -> Result<String, CovenError> {
+        self.runtime()
+            .block_on(self.fork_thread_async(conversation_key, message_id))
+    }
+
+    /// Async implementation of fork_thread - use this from async contexts
+    pub async fn fork_thread_async(
+        &self,
+        conversation_key: String,
+        message_id: String,
+    ) -> Result<String, CovenError> {
+        let channel = self.create_channel_internal().await?;
+
+        let request = ForkThreadRequest {
+            conversation_key: conversation_key.clone(),
+            message_id,
+        };
+
+        let response = if let Some(ref key) = self.ssh_key {
+            let mut client = ClientServiceClient::with_interceptor(
+                channel,
+                Self::make_ssh_interceptor(key.clone()),
+            );
+            client
+                .fork_thread(request)
+                .await
+                .map_err(|e| CovenError::Api(e.to_string()))?
+        } else {
+            let mut client = ClientServiceClient::new(channel);
+            client
+                .fork_thread(request)
+                .await
+                .map_err(|e| CovenError::Api(e.to_string()))?
+        };
+
+        let fork = response.into_inner().conversation_key;
+        let mut state_guard = lock_state(&self.state);
+        let agent_id = state_guard
+            .forks
+            .get(&conversation_key)
+            .cloned()
+            .unwrap_or(conversation_key);
+        state_guard.forks.insert(fork.clone(), agent_id);
+        Ok(fork)
+    }
+
+    /// `agent_id`'s conversations, most recently active first, with the
+    /// titles the agent gave them
+    pub fn list_threads(&self, agent_id: String) -> Result<Vec<ThreadSummary>, CovenError> {
+        self.runtime().block_on(self.list_threads_async(agent_id))
+    }
+
+    /// Async implementation of list_threads - use this from async contexts
+    pub async fn list_threads_async(
+        &self,
+        agent_id: String,
+    ) -> Result<Vec<ThreadSummary>, CovenError> {
+        let channel = self.create_channel_internal().await?;
+        let request = ListThreadsRequest { agent_id };
+
+        let response = if let Some(ref key) = self.ssh_key {
+            let mut client = ClientServiceClient::with_interceptor(
+                channel,
+                Self::make_ssh_interceptor(key.clone()),
+            );
+            client
+                .list_threads(request)
+                .await
+                .map_err(|e| CovenError::Api(e.to_string()))?
+        } else {
+            let mut client = ClientServiceClient::new(channel);
+            client
+                .list_threads(request)
+                .await
+                .map_err(|e| CovenError::Api(e.to_string()))?
+        };
+
+        Ok(response
+            .into_inner()
+            .threads
+            .into_iter()
+            .map(ThreadSummary::from)
+            .collect())
+    }
+
+    // =========================================================================
+    // Pairing
+    // =========================================================================
+
+    /// Let in the device showing pairing `code` (from `coven link --pair`,
+    /// typed in or scanned from its QR code) as `principal_id`, which it
+    /// joins along with its roles. This client's key must belong to an
+    /// admin.
+    pub fn approve_pairing(
+        &self,
+        code: String,
+        principal_id: String,
+    ) -> Result<PairedDevice, CovenError> {
+        self.runtime()
+            .block_on(self.approve_pairing_async(code, principal_id))
+    }
+
+    /// Async implementation of approve_pairing - use this from async contexts
+    pub async fn approve_pairing_async(
+        &self,
+        code: String,
+        principal_id: String,
+    ) -> Result<PairedDevice, CovenError> {
+        let channel = self.create_channel_internal().await?;
+
+        let request = ApprovePairingRequest { code, principal_id };
+
+        let response = if let Some(ref key) = self.ssh_key {
+            let mut client = AdminServiceClient::with_interceptor(
+                channel,
+                Self::make_ssh_interceptor(key.clone()),
+            );
+            client
+                .approve_pairing(request)
+                .await
+                .map_err(|e| CovenError::Api(e.to_string()))?
+        } else {
+            let mut client = AdminServiceClient::new(channel);
+            client
+                .approve_pairing(request)
+                .await
+                .map_err(|e| CovenError::Api(e.to_string()))?
+        };
+
+        Ok(response.into_inner().into())
+    }
+
+    // =========================================================================
+    // Push Notifications
+    // =========================================================================
+
+    /// Register this device's push token so the gateway notifies it of
+    /// agent messages and pending tool approvals. Returns true if the token
+    /// was new, false if it was already registered.
+    pub fn register_push_token(
+        &self,
+        platform: PushPlatform,
+        token: String,
+    ) -> Result<bool, CovenError> {
+        self.runtime()
+            .block_on(self.register_push_token_async(platform, token))
+    }
+
+    /// Async implementation of register_push_token - use this from async contexts
+    pub async fn register_push_token_async(
+        &self,
+        platform: PushPlatform,
+        token: String,
+    ) -> Result<bool, CovenError> {
+        let channel = self.create_channel_internal().await?;
+
+        let request = RegisterPushTokenRequest {
+            platform: platform.as_str().to_string(),
+            token,
+        };
+
+        let response = if let Some(ref key) = self.ssh_key {
+            let mut client = ClientServiceClient::with_interceptor(
+                channel,
+                Self::make_ssh_interceptor(key.clone()),
+            );
+            client
+                .register_push_token(request)
+                .await
+                .map_err(|e| CovenError::Api(e.to_string()))?
+        } else {
+            let mut client = ClientServiceClient::new(channel);
+            client
+                .register_push_token(request)
+                .await
+                .map_err(|e| CovenError::Api(e.to_string()))?
+        };
+
+        Ok(response.into_inner().created)
+    }
+
+    /// Stop push notifications to a device, e.g. on sign-out. Returns true
+    /// if the token was registered.
+    pub fn unregister_push_token(&self, token: String) -> Result<bool, CovenError> {
+        self.runtime()
+            .block_on(self.unregister_push_token_async(token))
+    }
+
+    /// Async implementation of unregister_push_token - use this from async contexts
+    pub async fn unregister_push_token_async(&self, token: String) -> Result<bool, CovenError> {
+        let channel = self.create_channel_internal().await?;
+
+        let request = UnregisterPushTokenRequest { token };
+
+        let response = if let Some(ref key) = self.ssh_key {
+            let mut client = ClientServiceClient::with_interceptor(
+                channel,
+                Self::make_ssh_interceptor(key.clone()),
+            );
+            client
+                .unregister_push_token(request)
+                .await
+                .map_err(|e| CovenError::Api(e.to_string()))?
+        } else {
+            let mut client = ClientServiceClient::new(channel);
+            client
+                .unregister_push_token(request)
+                .await
+                .map_err(|e| CovenError::Api(e.to_string()))?
+        };
+
+        Ok(response.into_inner().removed)
+    }
+
     // =========================================================================
     // Internal Streaming Implementation
     // =========================================================================
@@ -667,7 +1428,7 @@ impl CovenClient {
         state: Arc<RwLock<ClientState>>,
         gateway_url: String,
         agent_id: String,
-        content: String,
+        send: PendingSend,
         cancel: CancellationToken,
         ssh_key: Option<Arc<PrivateKey>>,
     ) {
@@ -676,16 +1437,16 @@ impl CovenClient {
         let channel = match create_channel(&config).await {
             Ok(c) => c,
             Err(e) => {
-                Self::handle_stream_error(&state, &agent_id, e.to_string());
+                Self::handle_send_error(&state, &agent_id, send, true, e.to_string());
                 return;
             }
         };
 
         // Run with or without auth based on SSH key presence
         if let Some(key) = ssh_key {
-            Self::run_grpc_stream_with_auth(state, channel, agent_id, content, cancel, key).await;
+            Self::run_grpc_stream_with_auth(state, channel, agent_id, send, cancel, key).await;
         } else {
-            Self::run_grpc_stream_no_auth(state, channel, agent_id, content, cancel).await;
+            Self::run_grpc_stream_no_auth(state, channel, agent_id, send, cancel).await;
         }
     }
 
@@ -694,7 +1455,7 @@ impl CovenClient {
         state: Arc<RwLock<ClientState>>,
         channel: Channel,
         agent_id: String,
-        content: String,
+        send: PendingSend,
         cancel: CancellationToken,
         key: Arc<PrivateKey>,
     ) {
@@ -710,12 +1471,14 @@ impl CovenClient {
         let stream_request = StreamEventsRequest {
             conversation_key: agent_id.clone(),
             since_event_id: None, // We'll filter by timestamp instead
+            stream: Some(state.read().expect("lock poisoned").streaming),
         };
 
         let stream = match client.stream_events(stream_request).await {
             Ok(response) => response.into_inner(),
             Err(e) => {
-                Self::handle_stream_error(&state, &agent_id, e.to_string());
+                let unreachable = e.code() == tonic::Code::Unavailable;
+                Self::handle_send_error(&state, &agent_id, send, unreachable, e.to_string());
                 return;
             }
         };
@@ -723,14 +1486,32 @@ impl CovenClient {
         // Now send the message (we're already listening for the response)
         let send_request = ClientSendMessageRequest {
             conversation_key: agent_id.clone(),
-            content: content.clone(),
-            attachments: vec![],
-            idempotency_key: generate_idempotency_key(),
+            content: send.content.clone(),
+            attachments: send.attachments.iter().map(Attachment::to_proto).collect(),
+            idempotency_key: send.id.clone(),
+            sender_display: None,
+            sender_platform_id: None,
+            sender_platform: None,
+            model: None,
+            max_tokens: None,
+            reply_to_message_id: None,
+            channel_frontend: None,
+            channel_id: None,
+            channel_name: None,
         };
 
-        if let Err(e) = client.send_message(send_request).await {
-            Self::handle_stream_error(&state, &agent_id, e.to_string());
-            return;
+        match client.send_message(send_request).await {
+            Ok(response) => {
+                if let Err(e) = check_send_status(response.get_ref()) {
+                    Self::handle_stream_error(&state, &agent_id, e.to_string());
+                    return;
+                }
+            }
+            Err(e) => {
+                let unreachable = e.code() == tonic::Code::Unavailable;
+                Self::handle_send_error(&state, &agent_id, send, unreachable, e.to_string());
+                return;
+            }
         }
 
         tokio::pin!(stream);
@@ -749,13 +1530,8 @@ impl CovenClient {
                 _ = &mut idle_timeout, if saw_agent_response => {
                     // Agent responded and we've been idle - consider done
                     Self::finalize_stream(&state, &agent_id);
-                    // Notify callback of completion
-                    {
-                        let state_guard = state.read().expect("lock poisoned");
-                        if let Some(cb) = &state_guard.stream_callback {
-                            cb.on_event(agent_id.clone(), StreamEvent::Done);
-                        }
-                    }
+                    // Notify subscribers of completion
+                    state.read().expect("lock poisoned").emit(&agent_id, StreamEvent::Done);
                     break;
                 }
                 event = stream.next() => {
@@ -816,7 +1592,7 @@ impl CovenClient {
         state: Arc<RwLock<ClientState>>,
         channel: Channel,
         agent_id: String,
-        content: String,
+        send: PendingSend,
         cancel: CancellationToken,
     ) {
         let mut client = ClientServiceClient::new(channel);
@@ -830,12 +1606,14 @@ impl CovenClient {
         let stream_request = StreamEventsRequest {
             conversation_key: agent_id.clone(),
             since_event_id: None, // We'll filter by timestamp instead
+            stream: Some(state.read().expect("lock poisoned").streaming),
         };
 
         let stream = match client.stream_events(stream_request).await {
             Ok(response) => response.into_inner(),
             Err(e) => {
-                Self::handle_stream_error(&state, &agent_id, e.to_string());
+                let unreachable = e.code() == tonic::Code::Unavailable;
+                Self::handle_send_error(&state, &agent_id, send, unreachable, e.to_string());
                 return;
             }
         };
@@ -843,14 +1621,32 @@ impl CovenClient {
         // Now send the message (we're already listening for the response)
         let send_request = ClientSendMessageRequest {
             conversation_key: agent_id.clone(),
-            content,
-            attachments: vec![],
-            idempotency_key: generate_idempotency_key(),
+            content: send.content.clone(),
+            attachments: send.attachments.iter().map(Attachment::to_proto).collect(),
+            idempotency_key: send.id.clone(),
+            sender_display: None,
+            sender_platform_id: None,
+            sender_platform: None,
+            model: None,
+            max_tokens: None,
+            reply_to_message_id: None,
+            channel_frontend: None,
+            channel_id: None,
+            channel_name: None,
         };
 
-        if let Err(e) = client.send_message(send_request).await {
-            Self::handle_stream_error(&state, &agent_id, e.to_string());
-            return;
+        match client.send_message(send_request).await {
+            Ok(response) => {
+                if let Err(e) = check_send_status(response.get_ref()) {
+                    Self::handle_stream_error(&state, &agent_id, e.to_string());
+                    return;
+                }
+            }
+            Err(e) => {
+                let unreachable = e.code() == tonic::Code::Unavailable;
+                Self::handle_send_error(&state, &agent_id, send, unreachable, e.to_string());
+                return;
+            }
         }
 
         tokio::pin!(stream);
@@ -869,13 +1665,8 @@ impl CovenClient {
                 _ = &mut idle_timeout, if saw_agent_response => {
                     // Agent responded and we've been idle - consider done
                     Self::finalize_stream(&state, &agent_id);
-                    // Notify callback of completion
-                    {
-                        let state_guard = state.read().expect("lock poisoned");
-                        if let Some(cb) = &state_guard.stream_callback {
-                            cb.on_event(agent_id.clone(), StreamEvent::Done);
-                        }
-                    }
+                    // Notify subscribers of completion
+                    state.read().expect("lock poisoned").emit(&agent_id, StreamEvent::Done);
                     break;
                 }
                 event = stream.next() => {
@@ -937,7 +1728,7 @@ impl CovenClient {
         agent_id: &str,
         event: ClientStreamEvent,
     ) -> (bool, bool) {
-        let mut state_guard = state.write().expect("lock poisoned");
+        let mut state_guard = lock_state(&state);
 
         let stream_event = match event.payload {
             Some(client_stream_event::Payload::Text(chunk)) => {
@@ -989,12 +1780,33 @@ impl CovenClient {
                 state_guard.session_usage.accumulate(&info);
                 StreamEvent::Usage { info }
             }
-            Some(client_stream_event::Payload::Done(_)) => {
-                Self::finalize_stream_internal(&mut state_guard, agent_id);
-                // Notify callback before returning
-                if let Some(cb) = &state_guard.stream_callback {
-                    cb.on_event(agent_id.to_string(), StreamEvent::Done);
+            Some(client_stream_event::Payload::Done(done)) => {
+                // A buffered response arrives whole, with nothing streamed
+                // before it: its tools are known only from the summary
+                if !state_guard.streaming {
+                    for tool in &done.tools {
+                        state_guard.emit(agent_id, tool_summary_event(tool));
+                    }
+                }
+                let full_response = done.full_response.unwrap_or_default();
+                let whole = match state_guard.streams.get_mut(agent_id) {
+                    Some(stream) if stream.buffer.is_empty() && !full_response.is_empty() => {
+                        stream.buffer.push_str(&full_response);
+                        true
+                    }
+                    _ => false,
+                };
+                if whole {
+                    state_guard.emit(
+                        agent_id,
+                        StreamEvent::Text {
+                            content: full_response,
+                        },
+                    );
                 }
+                Self::finalize_stream_internal(&mut state_guard, agent_id);
+                // Notify subscribers before returning
+                state_guard.emit(agent_id, StreamEvent::Done);
                 return (true, false);
             }
             Some(client_stream_event::Payload::Error(err)) => {
@@ -1013,10 +1825,8 @@ impl CovenClient {
                 // Cleanup stream state
                 state_guard.streams.remove(agent_id);
 
-                // Notify callbacks
-                if let Some(cb) = &state_guard.stream_callback {
-                    cb.on_event(agent_id.to_string(), error_event);
-                }
+                // Notify subscribers and callbacks
+                state_guard.emit(agent_id, error_event);
                 if let Some(cb) = &state_guard.state_callback {
                     cb.on_streaming_changed(agent_id.to_string(), false);
                     cb.on_messages_changed(agent_id.to_string());
@@ -1056,9 +1866,7 @@ impl CovenClient {
                         },
                         "done" | "stream_done" => {
                             Self::finalize_stream_internal(&mut state_guard, agent_id);
-                            if let Some(cb) = &state_guard.stream_callback {
-                                cb.on_event(agent_id.to_string(), StreamEvent::Done);
-                            }
+                            state_guard.emit(agent_id, StreamEvent::Done);
                             return (true, false);
                         }
                         _ => {
@@ -1070,6 +1878,10 @@ impl CovenClient {
                     return (false, false);
                 }
             }
+            // A status line, shown like thinking so it stays out of the answer
+            Some(client_stream_event::Payload::Queued(queued)) => StreamEvent::Thinking {
+                content: queued.notice,
+            },
             Some(client_stream_event::Payload::ToolApproval(approval)) => {
                 StreamEvent::ToolApprovalRequest {
                     agent_id: approval.agent_id,
@@ -1077,6 +1889,7 @@ impl CovenClient {
                     tool_id: approval.tool_id,
                     tool_name: approval.tool_name,
                     input_json: approval.input_json,
+                    confirm_message: approval.confirm_message,
                 }
             }
             None => {
@@ -1084,26 +1897,139 @@ impl CovenClient {
             }
         };
 
-        // Notify callback
-        if let Some(cb) = &state_guard.stream_callback {
-            cb.on_event(agent_id.to_string(), stream_event);
-        }
+        // Notify subscribers
+        state_guard.emit(agent_id, stream_event);
 
         (false, false)
     }
 
-    fn handle_stream_error(state: &Arc<RwLock<ClientState>>, agent_id: &str, error: String) {
-        let mut state_guard = state.write().expect("lock poisoned");
-
-        // Notify via callback
-        if let Some(cb) = &state_guard.stream_callback {
-            cb.on_event(
-                agent_id.to_string(),
-                StreamEvent::Error {
-                    message: error.clone(),
-                },
-            );
+    /// Handle a send that didn't reach the gateway. With the cache on, an
+    /// unreachable gateway queues the message for reconnect instead of
+    /// failing it.
+    fn handle_send_error(
+        state: &Arc<RwLock<ClientState>>,
+        agent_id: &str,
+        send: PendingSend,
+        unreachable: bool,
+        error: String,
+    ) {
+        let mut state_guard = lock_state(&state);
+        if !unreachable || state_guard.cache.is_none() {
+            drop(state_guard);
+            Self::handle_stream_error(state, agent_id, error);
+            return;
+        }
+
+        tracing::debug!("Gateway unreachable, queueing message: {}", error);
+        state_guard.streams.remove(agent_id);
+        state_guard
+            .outbox
+            .entry(agent_id.to_string())
+            .or_default()
+            .push(send);
+        state_guard
+            .connection
+            .send_replace(ConnectionStatus::Disconnected);
+        state_guard.persist();
+        if let Some(cb) = &state_guard.state_callback {
+            cb.on_streaming_changed(agent_id.to_string(), false);
         }
+        state_guard.notify_queue(agent_id);
+    }
+
+    /// Send the messages queued while offline, oldest first, then refresh
+    /// their conversations so replies and anything missed in the meantime
+    /// are merged in. Stops at the first send the gateway can't be reached
+    /// for; a send it refuses is dropped with an error in the conversation.
+    async fn flush_outbox(
+        state: Arc<RwLock<ClientState>>,
+        gateway_url: String,
+        ssh_key: Option<Arc<PrivateKey>>,
+    ) {
+        let outbox: Vec<(String, Vec<PendingSend>)> = state
+            .read()
+            .expect("lock poisoned")
+            .outbox
+            .iter()
+            .filter(|(_, pending)| !pending.is_empty())
+            .map(|(agent_id, pending)| (agent_id.clone(), pending.clone()))
+            .collect();
+
+        for (agent_id, pending) in outbox {
+            for send in pending {
+                let request = ClientSendMessageRequest {
+                    conversation_key: agent_id.clone(),
+                    content: send.content.clone(),
+                    attachments: send.attachments.iter().map(Attachment::to_proto).collect(),
+                    idempotency_key: send.id.clone(),
+                    ..Default::default()
+                };
+                let result = Self::send_unary(&gateway_url, ssh_key.clone(), request).await;
+
+                let mut state_guard = lock_state(&state);
+                match result {
+                    Err(CovenError::Connection(e)) => {
+                        tracing::debug!("Gateway unreachable again, keeping queue: {}", e);
+                        state_guard
+                            .connection
+                            .send_replace(ConnectionStatus::Disconnected);
+                        return;
+                    }
+                    Err(e) => {
+                        let error_msg = Message::system(format!("Error: not delivered: {}", e));
+                        state_guard
+                            .messages
+                            .entry(agent_id.clone())
+                            .or_default()
+                            .push(error_msg);
+                        if let Some(cb) = &state_guard.state_callback {
+                            cb.on_messages_changed(agent_id.clone());
+                        }
+                    }
+                    Ok(()) => {}
+                }
+                if let Some(queue) = state_guard.outbox.get_mut(&agent_id) {
+                    queue.retain(|p| p.id != send.id);
+                }
+                state_guard.persist();
+                state_guard.notify_queue(&agent_id);
+            }
+
+            if let Err(e) =
+                Self::refresh_history(&state, &gateway_url, ssh_key.clone(), agent_id).await
+            {
+                tracing::debug!("History refresh after sending queue failed: {}", e);
+            }
+        }
+    }
+
+    async fn send_unary(
+        gateway_url: &str,
+        ssh_key: Option<Arc<PrivateKey>>,
+        request: ClientSendMessageRequest,
+    ) -> Result<(), CovenError> {
+        let channel = Self::connect(gateway_url).await?;
+        let response = if let Some(key) = ssh_key {
+            let mut client =
+                ClientServiceClient::with_interceptor(channel, Self::make_ssh_interceptor(key));
+            client.send_message(request).await?
+        } else {
+            let mut client = ClientServiceClient::new(channel);
+            client.send_message(request).await?
+        };
+        check_send_status(response.get_ref())
+    }
+
+    fn handle_stream_error(state: &Arc<RwLock<ClientState>>, agent_id: &str, error: String) {
+        let mut state_guard = lock_state(&state);
+
+        // Notify subscribers
+        state_guard.emit(
+            agent_id,
+            StreamEvent::Error {
+                message: error.clone(),
+            },
+        );
 
         // Add error message to history
         let error_msg = Message::system(format!("Error: {}", error));
@@ -1124,7 +2050,7 @@ impl CovenClient {
     }
 
     fn finalize_stream(state: &Arc<RwLock<ClientState>>, agent_id: &str) {
-        let mut state_guard = state.write().expect("lock poisoned");
+        let mut state_guard = lock_state(&state);
         Self::finalize_stream_internal(&mut state_guard, agent_id);
     }
 
@@ -1143,6 +2069,7 @@ impl CovenClient {
                 .entry(agent_id.to_string())
                 .or_default()
                 .push(agent_msg);
+            state.persist();
         }
 
         // Notify callbacks
@@ -1162,14 +2089,29 @@ impl CovenClient {
                     .or_default()
                     .push(combined);
 
-                if let Some(cb) = &state.state_callback {
-                    cb.on_queue_changed(agent_id.to_string(), 1);
-                }
+                state.notify_queue(agent_id);
             }
         }
     }
 }
 
+/// A tool the summary at the end of a buffered response lists, as the state
+/// it ended in; the detail is the tool's name
+fn tool_summary_event(tool: &ToolSummary) -> StreamEvent {
+    let state = if tool.is_error {
+        "failed"
+    } else if tool.completed {
+        "completed"
+    } else {
+        // The response ended before the tool's result came
+        "unknown"
+    };
+    StreamEvent::ToolState {
+        state: state.to_string(),
+        detail: tool.name.clone(),
+    }
+}
+
 impl Drop for CovenClient {
     fn drop(&mut self) {
         // Take ownership of the runtime to shut it down
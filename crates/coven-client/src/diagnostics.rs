@@ -0,0 +1,782 @@
+// ABOUTME: Connection diagnostics for a gateway the client can't reach
+// ABOUTME: Checks DNS, TCP, TLS, gRPC, SSH auth and clock skew, reporting each step
+
+use coven_grpc::{create_channel, ChannelConfig, UNIX_SCHEME};
+use coven_proto::client::ClientServiceClient;
+use coven_ssh::{compute_fingerprint, PrivateKey, SshAuthCredentials, MAX_SIGNATURE_AGE_SECS};
+use std::fmt;
+use std::future::Future;
+use std::io;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tonic::metadata::MetadataMap;
+use tonic::transport::Channel;
+
+/// How long any one network step may take
+const STEP_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Clock difference from the gateway worth a warning
+const SKEW_WARN: Duration = Duration::from_secs(30);
+
+/// The gateway rejects SSH signatures older than this, so a clock this far
+/// off breaks authentication
+const SKEW_FAIL: Duration = Duration::from_secs(MAX_SIGNATURE_AGE_SECS as u64);
+
+/// How a diagnostic step came out
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiagnosticStatus {
+    Ok,
+    /// Works, but something is worth fixing
+    Warning,
+    Failed,
+    /// Not run, because an earlier step failed or it doesn't apply
+    Skipped,
+}
+
+/// One step of a diagnosis
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiagnosticStep {
+    /// "dns", "tcp", "tls", "grpc", "auth" or "clock"
+    pub name: String,
+    pub status: DiagnosticStatus,
+    pub message: String,
+    /// Time spent on the network, 0 for steps that made no call
+    pub duration_ms: u64,
+}
+
+/// Everything `CovenClient::diagnose` found, in the order it checked
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiagnosticReport {
+    pub gateway_url: String,
+    pub steps: Vec<DiagnosticStep>,
+    /// Gateway clock minus local clock, when the gateway reported its time
+    pub clock_skew_ms: Option<i64>,
+    /// True when no step failed
+    pub healthy: bool,
+}
+
+impl fmt::Display for DiagnosticReport {
+    /// One line per step, e.g. "✓ dns    localhost resolved to 127.0.0.1"
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Gateway: {}", self.gateway_url)?;
+        for step in &self.steps {
+            let symbol = match step.status {
+                DiagnosticStatus::Ok => "✓",
+                DiagnosticStatus::Warning => "⚠",
+                DiagnosticStatus::Failed => "✗",
+                DiagnosticStatus::Skipped => "-",
+            };
+            writeln!(f, "{} {:<6} {}", symbol, step.name, step.message)?;
+        }
+        Ok(())
+    }
+}
+
+/// Host name lookup, a seam so tests can stand in for DNS
+#[async_trait::async_trait]
+pub(crate) trait Resolver: Send + Sync {
+    async fn resolve(&self, host: &str, port: u16) -> io::Result<Vec<SocketAddr>>;
+}
+
+/// The system resolver
+pub(crate) struct SystemResolver;
+
+#[async_trait::async_trait]
+impl Resolver for SystemResolver {
+    async fn resolve(&self, host: &str, port: u16) -> io::Result<Vec<SocketAddr>> {
+        Ok(tokio::net::lookup_host((host, port)).await?.collect())
+    }
+}
+
+/// Where the gateway URL points
+enum Target {
+    Tcp { host: String, port: u16, tls: bool },
+    Unix(String),
+}
+
+fn parse_target(gateway_url: &str) -> Result<Target, String> {
+    if let Some(path) = gateway_url.strip_prefix(UNIX_SCHEME) {
+        return Ok(Target::Unix(path.to_string()));
+    }
+    let with_scheme = if gateway_url.contains("://") {
+        gateway_url.to_string()
+    } else {
+        format!("http://{}", gateway_url)
+    };
+    let url = url::Url::parse(&with_scheme).map_err(|e| e.to_string())?;
+    let tls = match url.scheme() {
+        "https" => true,
+        "http" => false,
+        other => return Err(format!("unsupported scheme {}://", other)),
+    };
+    let host = url
+        .host_str()
+        .ok_or("no host")?
+        .trim_start_matches('[')
+        .trim_end_matches(']')
+        .to_string();
+    let port = url.port_or_known_default().ok_or("no port")?;
+    Ok(Target::Tcp { host, port, tls })
+}
+
+/// Collects steps, skipping everything after the first failure
+struct Steps {
+    steps: Vec<DiagnosticStep>,
+    failed: Option<&'static str>,
+}
+
+impl Steps {
+    fn new() -> Self {
+        Self {
+            steps: Vec::new(),
+            failed: None,
+        }
+    }
+
+    /// Whether steps that need the earlier ones should still run
+    fn running(&self) -> bool {
+        self.failed.is_none()
+    }
+
+    fn push(
+        &mut self,
+        name: &'static str,
+        status: DiagnosticStatus,
+        message: impl Into<String>,
+        elapsed: Duration,
+    ) {
+        if status == DiagnosticStatus::Failed && self.failed.is_none() {
+            self.failed = Some(name);
+        }
+        self.steps.push(DiagnosticStep {
+            name: name.to_string(),
+            status,
+            message: message.into(),
+            duration_ms: elapsed.as_millis() as u64,
+        });
+    }
+
+    fn skip(&mut self, name: &'static str, reason: impl Into<String>) {
+        self.push(name, DiagnosticStatus::Skipped, reason, Duration::ZERO);
+    }
+
+    /// Skip `name` because an earlier step failed
+    fn skip_after_failure(&mut self, name: &'static str) {
+        let failed = self.failed.unwrap_or("an earlier");
+        self.skip(name, format!("skipped: the {} step failed", failed));
+    }
+}
+
+/// Run `future` with the step timeout, timing it
+async fn timed<T>(future: impl Future<Output = T>) -> (Option<T>, Duration) {
+    let start = Instant::now();
+    let result = tokio::time::timeout(STEP_TIMEOUT, future).await.ok();
+    (result, start.elapsed())
+}
+
+/// Diagnose the connection to `gateway_url`, authenticating with `ssh_key`
+/// when there is one
+pub(crate) async fn diagnose(
+    gateway_url: &str,
+    ssh_key: Option<&Arc<PrivateKey>>,
+    resolver: &dyn Resolver,
+) -> DiagnosticReport {
+    let mut steps = Steps::new();
+
+    match parse_target(gateway_url) {
+        Err(e) => {
+            steps.push(
+                "dns",
+                DiagnosticStatus::Failed,
+                format!("invalid gateway URL {}: {}", gateway_url, e),
+                Duration::ZERO,
+            );
+            steps.skip_after_failure("tcp");
+            steps.skip_after_failure("tls");
+        }
+        Ok(Target::Unix(path)) => {
+            steps.skip("dns", format!("Unix socket {}, no lookup needed", path));
+            steps.skip("tcp", "Unix socket, no TCP connection");
+            steps.skip("tls", "Unix socket, no TLS");
+        }
+        Ok(Target::Tcp { host, port, tls }) => {
+            let addrs = check_dns(&mut steps, resolver, &host, port).await;
+            if steps.running() {
+                check_tcp(&mut steps, &addrs).await;
+            } else {
+                steps.skip_after_failure("tcp");
+            }
+            if steps.running() {
+                check_tls(&mut steps, &host, tls, ssh_key.is_some());
+            } else {
+                steps.skip_after_failure("tls");
+            }
+        }
+    }
+
+    let mut server_time = None;
+    let channel = if steps.running() {
+        check_grpc(&mut steps, gateway_url, &mut server_time).await
+    } else {
+        steps.skip_after_failure("grpc");
+        None
+    };
+
+    match (&channel, ssh_key) {
+        (None, _) => steps.skip_after_failure("auth"),
+        (Some(_), None) => steps.skip("auth", "no SSH key; connecting without authentication"),
+        (Some(channel), Some(key)) => {
+            check_auth(&mut steps, channel.clone(), key.clone(), &mut server_time).await
+        }
+    }
+
+    let clock_skew_ms = server_time.map(|(server_ms, local_ms)| server_ms - local_ms);
+    match clock_skew_ms {
+        Some(skew) => {
+            let (status, message) = clock_step(skew);
+            steps.push("clock", status, message, Duration::ZERO);
+        }
+        None if channel.is_none() => steps.skip_after_failure("clock"),
+        None => steps.skip("clock", "the gateway didn't report its time"),
+    }
+
+    let healthy = steps
+        .steps
+        .iter()
+        .all(|s| s.status != DiagnosticStatus::Failed);
+    DiagnosticReport {
+        gateway_url: gateway_url.to_string(),
+        steps: steps.steps,
+        clock_skew_ms,
+        healthy,
+    }
+}
+
+async fn check_dns(
+    steps: &mut Steps,
+    resolver: &dyn Resolver,
+    host: &str,
+    port: u16,
+) -> Vec<SocketAddr> {
+    if let Ok(ip) = host.parse::<IpAddr>() {
+        steps.push(
+            "dns",
+            DiagnosticStatus::Ok,
+            format!("{} is an IP address, no lookup needed", ip),
+            Duration::ZERO,
+        );
+        return vec![SocketAddr::new(ip, port)];
+    }
+
+    let (result, elapsed) = timed(resolver.resolve(host, port)).await;
+    match result {
+        Some(Ok(addrs)) if !addrs.is_empty() => {
+            let shown: Vec<String> = addrs.iter().take(3).map(|a| a.ip().to_string()).collect();
+            steps.push(
+                "dns",
+                DiagnosticStatus::Ok,
+                format!("{} resolved to {}", host, shown.join(", ")),
+                elapsed,
+            );
+            addrs
+        }
+        Some(Ok(_)) => {
+            steps.push(
+                "dns",
+                DiagnosticStatus::Failed,
+                format!("{} resolved to no addresses", host),
+                elapsed,
+            );
+            Vec::new()
+        }
+        Some(Err(e)) => {
+            steps.push(
+                "dns",
+                DiagnosticStatus::Failed,
+                format!("{} didn't resolve: {}", host, e),
+                elapsed,
+            );
+            Vec::new()
+        }
+        None => {
+            steps.push(
+                "dns",
+                DiagnosticStatus::Failed,
+                format!("looking up {} timed out", host),
+                elapsed,
+            );
+            Vec::new()
+        }
+    }
+}
+
+/// Connect to each address in turn until one answers
+async fn check_tcp(steps: &mut Steps, addrs: &[SocketAddr]) {
+    let mut errors = Vec::new();
+    let start = Instant::now();
+    for addr in addrs {
+        let (result, elapsed) = timed(tokio::net::TcpStream::connect(addr)).await;
+        match result {
+            Some(Ok(_)) => {
+                steps.push(
+                    "tcp",
+                    DiagnosticStatus::Ok,
+                    format!("connected to {} in {} ms", addr, elapsed.as_millis()),
+                    elapsed,
+                );
+                return;
+            }
+            Some(Err(e)) => errors.push(format!("{}: {}", addr, e)),
+            None => errors.push(format!("{}: timed out", addr)),
+        }
+    }
+    steps.push(
+        "tcp",
+        DiagnosticStatus::Failed,
+        format!("couldn't connect to {}", errors.join("; ")),
+        start.elapsed(),
+    );
+}
+
+/// Whether the connection is encrypted, and whether that matters. The
+/// handshake itself happens in the gRPC step.
+fn check_tls(steps: &mut Steps, host: &str, tls: bool, has_key: bool) {
+    let (status, message) = tls_step(host, tls, has_key);
+    steps.push("tls", status, message, Duration::ZERO);
+}
+
+fn tls_step(host: &str, tls: bool, has_key: bool) -> (DiagnosticStatus, String) {
+    if tls {
+        return (
+            DiagnosticStatus::Ok,
+            "encrypted with TLS (https)".to_string(),
+        );
+    }
+    let loopback = host == "localhost" || host.parse::<IpAddr>().is_ok_and(|ip| ip.is_loopback());
+    if has_key && !loopback {
+        (
+            DiagnosticStatus::Warning,
+            format!(
+                "cleartext (http) to {}; signed auth headers could be observed, use https://",
+                host
+            ),
+        )
+    } else {
+        (DiagnosticStatus::Ok, "cleartext (http)".to_string())
+    }
+}
+
+/// Open a channel and ask the gateway for its version, without
+/// authentication. Records the gateway's time as `(server_ms, local_ms)`.
+async fn check_grpc(
+    steps: &mut Steps,
+    gateway_url: &str,
+    server_time: &mut Option<(i64, i64)>,
+) -> Option<Channel> {
+    let config = ChannelConfig::new(gateway_url)
+        .without_keep_alive()
+        .with_connect_timeout(STEP_TIMEOUT);
+    let start = Instant::now();
+    let channel = match create_channel(&config).await {
+        Ok(channel) => channel,
+        Err(e) => {
+            steps.push(
+                "grpc",
+                DiagnosticStatus::Failed,
+                format!("couldn't open a gRPC connection: {}", e),
+                start.elapsed(),
+            );
+            return None;
+        }
+    };
+
+    let mut client = ClientServiceClient::new(channel.clone());
+    let sent_ms = chrono::Utc::now().timestamp_millis();
+    let (result, _) = timed(client.get_version(())).await;
+    let elapsed = start.elapsed();
+    let local_ms = (sent_ms + chrono::Utc::now().timestamp_millis()) / 2;
+    let (status, message) = match result {
+        Some(Ok(response)) => {
+            let reported = response.get_ref().server_time_ms;
+            if let Some(server_ms) = reported.or_else(|| date_header_ms(response.metadata())) {
+                *server_time = Some((server_ms, local_ms));
+            }
+            let version = response.into_inner();
+            let name = if version.component.is_empty() {
+                "gateway".to_string()
+            } else {
+                version.component
+            };
+            (
+                DiagnosticStatus::Ok,
+                format!(
+                    "{} {} answered in {} ms",
+                    name,
+                    version.version,
+                    elapsed.as_millis()
+                ),
+            )
+        }
+        // Any answer from the gateway proves gRPC works
+        Some(Err(status))
+            if matches!(
+                status.code(),
+                tonic::Code::Unimplemented
+                    | tonic::Code::Unauthenticated
+                    | tonic::Code::PermissionDenied
+            ) =>
+        {
+            if let Some(server_ms) = date_header_ms(status.metadata()) {
+                *server_time = Some((server_ms, local_ms));
+            }
+            (
+                DiagnosticStatus::Ok,
+                format!(
+                    "gateway answered in {} ms (no version: {})",
+                    elapsed.as_millis(),
+                    status.message()
+                ),
+            )
+        }
+        Some(Err(status)) => (
+            DiagnosticStatus::Failed,
+            format!(
+                "gateway didn't answer a gRPC call ({:?}): {}",
+                status.code(),
+                status.message()
+            ),
+        ),
+        None => (
+            DiagnosticStatus::Failed,
+            format!(
+                "gateway didn't answer a gRPC call within {} s",
+                STEP_TIMEOUT.as_secs()
+            ),
+        ),
+    };
+    let ok = status != DiagnosticStatus::Failed;
+    steps.push("grpc", status, message, elapsed);
+    ok.then_some(channel)
+}
+
+/// Make a signed call and see whether the gateway accepts the key
+async fn check_auth(
+    steps: &mut Steps,
+    channel: Channel,
+    key: Arc<PrivateKey>,
+    server_time: &mut Option<(i64, i64)>,
+) {
+    // The first 16 hex digits are plenty to tell keys apart on screen
+    let fingerprint = match compute_fingerprint(key.public_key()) {
+        Ok(fingerprint) => fingerprint.chars().take(16).collect::<String>(),
+        Err(e) => {
+            steps.push(
+                "auth",
+                DiagnosticStatus::Failed,
+                format!("SSH key is unusable: {}", e),
+                Duration::ZERO,
+            );
+            return;
+        }
+    };
+    let credentials = match SshAuthCredentials::new(&key) {
+        Ok(credentials) => credentials,
+        Err(e) => {
+            steps.push(
+                "auth",
+                DiagnosticStatus::Failed,
+                format!("couldn't sign with SSH key {}: {}", fingerprint, e),
+                Duration::ZERO,
+            );
+            return;
+        }
+    };
+
+    let mut request = tonic::Request::new(());
+    if let Err(e) = credentials.apply_to_request(&mut request) {
+        steps.push(
+            "auth",
+            DiagnosticStatus::Failed,
+            format!("couldn't sign with SSH key {}: {}", fingerprint, e),
+            Duration::ZERO,
+        );
+        return;
+    }
+
+    let mut client = ClientServiceClient::new(channel);
+    let sent_ms = chrono::Utc::now().timestamp_millis();
+    let (result, elapsed) = timed(client.get_me(request)).await;
+    let local_ms = (sent_ms + chrono::Utc::now().timestamp_millis()) / 2;
+    let (status, message) = match result {
+        Some(Ok(response)) => {
+            if server_time.is_none() {
+                if let Some(server_ms) = date_header_ms(response.metadata()) {
+                    *server_time = Some((server_ms, local_ms));
+                }
+            }
+            let me = response.into_inner();
+            let who = if me.display_name.is_empty() {
+                me.principal_id
+            } else {
+                me.display_name
+            };
+            (
+                DiagnosticStatus::Ok,
+                format!("gateway accepted SSH key {} as {}", fingerprint, who),
+            )
+        }
+        Some(Err(status))
+            if matches!(
+                status.code(),
+                tonic::Code::Unauthenticated | tonic::Code::PermissionDenied
+            ) =>
+        {
+            (
+                DiagnosticStatus::Failed,
+                format!(
+                    "gateway rejected SSH key {}: {}",
+                    fingerprint,
+                    status.message()
+                ),
+            )
+        }
+        Some(Err(status)) => (
+            DiagnosticStatus::Failed,
+            format!(
+                "couldn't verify SSH key {} ({:?}): {}",
+                fingerprint,
+                status.code(),
+                status.message()
+            ),
+        ),
+        None => (
+            DiagnosticStatus::Failed,
+            format!(
+                "gateway didn't answer a signed call within {} s",
+                STEP_TIMEOUT.as_secs()
+            ),
+        ),
+    };
+    steps.push("auth", status, message, elapsed);
+}
+
+fn clock_step(skew_ms: i64) -> (DiagnosticStatus, String) {
+    let skew = Duration::from_millis(skew_ms.unsigned_abs());
+    let direction = if skew_ms > 0 { "behind" } else { "ahead of" };
+    if skew < SKEW_WARN {
+        (
+            DiagnosticStatus::Ok,
+            format!("within {} s of the gateway's clock", skew.as_secs().max(1)),
+        )
+    } else if skew < SKEW_FAIL {
+        (
+            DiagnosticStatus::Warning,
+            format!(
+                "{} s {} the gateway's clock; auth fails at {} s",
+                skew.as_secs(),
+                direction,
+                SKEW_FAIL.as_secs()
+            ),
+        )
+    } else {
+        (
+            DiagnosticStatus::Failed,
+            format!(
+                "{} s {} the gateway's clock; the gateway rejects SSH signatures more than {} s old",
+                skew.as_secs(),
+                direction,
+                SKEW_FAIL.as_secs()
+            ),
+        )
+    }
+}
+
+/// The HTTP `date` response header, in Unix ms
+fn date_header_ms(metadata: &MetadataMap) -> Option<i64> {
+    let date = metadata.get("date")?.to_str().ok()?;
+    chrono::DateTime::parse_from_rfc2822(date)
+        .ok()
+        .map(|t| t.timestamp_millis())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::net::TcpListener;
+
+    /// Resolves every host to `addrs`, or fails with `error`
+    struct StubResolver {
+        addrs: Vec<SocketAddr>,
+        error: Option<io::ErrorKind>,
+    }
+
+    #[async_trait::async_trait]
+    impl Resolver for StubResolver {
+        async fn resolve(&self, _host: &str, _port: u16) -> io::Result<Vec<SocketAddr>> {
+            match self.error {
+                Some(kind) => Err(io::Error::new(kind, "name or service not known")),
+                None => Ok(self.addrs.clone()),
+            }
+        }
+    }
+
+    fn statuses(report: &DiagnosticReport) -> Vec<(&str, DiagnosticStatus)> {
+        report
+            .steps
+            .iter()
+            .map(|s| (s.name.as_str(), s.status))
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_dns_failure_skips_the_rest() {
+        let resolver = StubResolver {
+            addrs: vec![],
+            error: Some(io::ErrorKind::NotFound),
+        };
+        let report = diagnose("http://gateway.example:50051", None, &resolver).await;
+
+        use DiagnosticStatus::*;
+        assert_eq!(
+            statuses(&report),
+            [
+                ("dns", Failed),
+                ("tcp", Skipped),
+                ("tls", Skipped),
+                ("grpc", Skipped),
+                ("auth", Skipped),
+                ("clock", Skipped),
+            ]
+        );
+        assert!(report.steps[0].message.contains("gateway.example"));
+        assert_eq!(report.steps[1].message, "skipped: the dns step failed");
+        assert!(!report.healthy);
+        assert_eq!(report.clock_skew_ms, None);
+    }
+
+    #[tokio::test]
+    async fn test_tcp_failure_is_reported() {
+        // A port nothing listens on
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let closed = listener.local_addr().unwrap();
+        drop(listener);
+
+        let resolver = StubResolver {
+            addrs: vec![closed],
+            error: None,
+        };
+        let url = format!("http://gateway.example:{}", closed.port());
+        let report = diagnose(&url, None, &resolver).await;
+
+        use DiagnosticStatus::*;
+        assert_eq!(
+            statuses(&report)[..3],
+            [("dns", Ok), ("tcp", Failed), ("tls", Skipped)]
+        );
+        assert!(report.steps[0].message.contains("127.0.0.1"));
+        assert!(report.steps[1].message.contains(&closed.to_string()));
+        assert!(!report.healthy);
+    }
+
+    #[tokio::test]
+    async fn test_invalid_url_is_reported() {
+        let resolver = StubResolver {
+            addrs: vec![],
+            error: None,
+        };
+        let report = diagnose("ftp://gateway.example", None, &resolver).await;
+        assert_eq!(report.steps[0].status, DiagnosticStatus::Failed);
+        assert!(report.steps[0].message.contains("unsupported scheme"));
+        assert!(report.steps[1..]
+            .iter()
+            .all(|s| s.status == DiagnosticStatus::Skipped));
+    }
+
+    #[tokio::test]
+    async fn test_non_grpc_server_fails_grpc_step() {
+        // Accepts connections and closes them without speaking HTTP/2
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            while let Ok((socket, _)) = listener.accept().await {
+                drop(socket);
+            }
+        });
+
+        let resolver = StubResolver {
+            addrs: vec![],
+            error: None,
+        };
+        let report = diagnose(&format!("http://{}", addr), None, &resolver).await;
+
+        use DiagnosticStatus::*;
+        assert_eq!(
+            statuses(&report),
+            [
+                ("dns", Ok),
+                ("tcp", Ok),
+                ("tls", Ok),
+                ("grpc", Failed),
+                ("auth", Skipped),
+                ("clock", Skipped),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_tls_step() {
+        assert_eq!(tls_step("gw.example", true, true).0, DiagnosticStatus::Ok);
+        assert_eq!(tls_step("localhost", false, true).0, DiagnosticStatus::Ok);
+        assert_eq!(tls_step("::1", false, true).0, DiagnosticStatus::Ok);
+        assert_eq!(tls_step("gw.example", false, false).0, DiagnosticStatus::Ok);
+        let (status, message) = tls_step("gw.example", false, true);
+        assert_eq!(status, DiagnosticStatus::Warning);
+        assert!(message.contains("https://"));
+    }
+
+    #[test]
+    fn test_clock_step() {
+        assert_eq!(clock_step(1_500).0, DiagnosticStatus::Ok);
+        assert_eq!(clock_step(-45_000).0, DiagnosticStatus::Warning);
+        let (status, message) = clock_step(600_000);
+        assert_eq!(status, DiagnosticStatus::Failed);
+        assert!(message.starts_with("600 s behind"), "{}", message);
+        assert!(clock_step(-600_000).1.starts_with("600 s ahead of"));
+    }
+
+    #[test]
+    fn test_parse_target() {
+        let Ok(Target::Tcp { host, port, tls }) = parse_target("localhost:50051") else {
+            panic!("expected a TCP target");
+        };
+        assert_eq!((host.as_str(), port, tls), ("localhost", 50051, false));
+        let Ok(Target::Tcp { host, port, tls }) = parse_target("https://[::1]") else {
+            panic!("expected a TCP target");
+        };
+        assert_eq!((host.as_str(), port, tls), ("::1", 443, true));
+        assert!(matches!(
+            parse_target("unix:///run/coven.sock"),
+            Ok(Target::Unix(path)) if path == "/run/coven.sock"
+        ));
+    }
+
+    #[test]
+    fn test_report_display() {
+        let report = DiagnosticReport {
+            gateway_url: "http://localhost:50051".to_string(),
+            steps: vec![DiagnosticStep {
+                name: "dns".to_string(),
+                status: DiagnosticStatus::Ok,
+                message: "localhost resolved to 127.0.0.1".to_string(),
+                duration_ms: 1,
+            }],
+            clock_skew_ms: None,
+            healthy: true,
+        };
+        assert_eq!(
+            report.to_string(),
+            "Gateway: http://localhost:50051\n✓ dns    localhost resolved to 127.0.0.1\n"
+        );
+    }
+}
@@ -23,6 +23,14 @@ pub enum CovenError {
 
     #[error("Invalid response: {0}")]
     InvalidResponse(String),
+
+    #[error("Invalid attachment: {0}")]
+    InvalidAttachment(String),
+
+    /// The gateway's content policy refused the message; holds the reply
+    /// for the sender
+    #[error("Message blocked: {0}")]
+    Blocked(String),
 }
 
 impl From<tonic::Status> for CovenError {
@@ -83,6 +91,14 @@ mod tests {
         assert!(display.contains("Already streaming"));
     }
 
+    #[test]
+    fn test_coven_error_display_invalid_attachment() {
+        let err = CovenError::InvalidAttachment("too large".to_string());
+        let display = format!("{}", err);
+        assert!(display.contains("Invalid attachment"));
+        assert!(display.contains("too large"));
+    }
+
     #[test]
     fn test_coven_error_display_invalid_response() {
         let err = CovenError::InvalidResponse("malformed JSON".to_string());
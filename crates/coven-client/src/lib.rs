@@ -4,13 +4,19 @@
 // Allow empty lines after doc comments in generated UniFFI scaffolding code.
 #![allow(clippy::empty_line_after_doc_comments)]
 
+mod cache;
 mod client;
+mod diagnostics;
 mod error;
 mod models;
+mod replies;
 
+pub use cache::{reconcile, CacheConfig, PendingSend};
 pub use client::CovenClient;
+pub use diagnostics::{DiagnosticReport, DiagnosticStatus, DiagnosticStep};
 pub use error::CovenError;
 pub use models::*;
+pub use replies::{reply_quote, Threaded};
 
 // UniFFI scaffolding
 uniffi::include_scaffolding!("coven_client");
@@ -33,12 +39,31 @@ uniffi::include_scaffolding!("coven_client");
 /// - The key format is invalid
 /// - Directory creation fails
 pub fn generate_ssh_key(key_path: String) -> Result<String, CovenError> {
-    use coven_ssh::{compute_fingerprint, load_or_generate_key};
+    ssh_key_fingerprint(&key_path, None)
+}
+
+/// Like `generate_ssh_key`, for a key encrypted with `passphrase`: a new key
+/// is written encrypted, and an existing one is decrypted with it.
+pub fn generate_encrypted_ssh_key(
+    key_path: String,
+    passphrase: String,
+) -> Result<String, CovenError> {
+    ssh_key_fingerprint(
+        &key_path,
+        Some(&coven_ssh::PassphraseSource::Literal(passphrase)),
+    )
+}
+
+fn ssh_key_fingerprint(
+    key_path: &str,
+    passphrase: Option<&coven_ssh::PassphraseSource>,
+) -> Result<String, CovenError> {
+    use coven_ssh::{compute_fingerprint, load_or_generate_key_with_passphrase};
     use std::path::Path;
 
-    let path = Path::new(&key_path);
-    let key =
-        load_or_generate_key(path).map_err(|e| CovenError::Api(format!("SSH key error: {}", e)))?;
+    let path = Path::new(key_path);
+    let key = load_or_generate_key_with_passphrase(path, passphrase)
+        .map_err(|e| CovenError::Api(format!("SSH key error: {}", e)))?;
 
     let fingerprint = compute_fingerprint(key.public_key())
         .map_err(|e| CovenError::Api(format!("SSH fingerprint error: {}", e)))?;
@@ -50,12 +75,14 @@ pub fn generate_ssh_key(key_path: String) -> Result<String, CovenError> {
 // Callback Traits
 // ============================================================================
 
-/// Callback for streaming events from agents
+/// Callback for streaming events from agents. For FFI; Rust callers can use
+/// `CovenClient::subscribe_events`, which feeds this callback too.
 pub trait StreamCallback: Send + Sync {
     fn on_event(&self, agent_id: String, event: StreamEvent);
 }
 
-/// Callback for state changes (for UI updates)
+/// Callback for state changes (for UI updates). Connection status changes
+/// are forwarded from `CovenClient::connection_events`.
 pub trait StateCallback: Send + Sync {
     fn on_connection_status(&self, status: ConnectionStatus);
     fn on_messages_changed(&self, agent_id: String);
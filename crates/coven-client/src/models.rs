@@ -1,7 +1,11 @@
 // ABOUTME: Data models for coven-client
-// ABOUTME: Agent, Message, StreamEvent, and related types with proto conversion
+// ABOUTME: Agent, Message, StreamEvent, threads, tool approvals, paired devices, attachments, and related types with proto conversion
 
-use coven_proto::{AgentInfo, Event};
+use crate::error::CovenError;
+use coven_proto::{
+    AgentInfo, AgentPresence, ApprovePairingResponse, Event, FileAttachment, ThreadInfo,
+    ToolApprovalRecord,
+};
 
 /// Represents an AI agent available through the gateway
 #[derive(Debug, Clone)]
@@ -11,29 +15,41 @@ pub struct Agent {
     pub backend: String,
     pub working_dir: String,
     pub connected: bool,
+    /// Agent-set presence such as "available" or "busy"; cosmetic only
+    pub status: String,
+    /// Emoji shown alongside the status, empty when none is set
+    pub status_emoji: String,
 }
 
 impl Agent {
     /// Convert from proto AgentInfo
     pub fn from_proto(proto: AgentInfo) -> Self {
+        let presence = proto
+            .metadata
+            .and_then(|m| m.presence)
+            .unwrap_or_else(AgentPresence::available);
         Self {
             id: proto.id,
             name: proto.name,
             backend: proto.backend,
             working_dir: proto.working_dir,
             connected: proto.connected,
+            status: presence.status,
+            status_emoji: presence.emoji,
         }
     }
 }
 
 /// A chat message (user or agent)
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct Message {
     pub id: String,
     pub sender: String,
     pub content: String,
     pub timestamp: i64,
     pub is_user: bool,
+    /// ID of the earlier message this one replies to, for threaded replies
+    pub reply_to_message_id: Option<String>,
 }
 
 impl Message {
@@ -44,6 +60,7 @@ impl Message {
             content,
             timestamp: chrono::Utc::now().timestamp_millis(),
             is_user: true,
+            reply_to_message_id: None,
         }
     }
 
@@ -54,6 +71,7 @@ impl Message {
             content,
             timestamp: chrono::Utc::now().timestamp_millis(),
             is_user: false,
+            reply_to_message_id: None,
         }
     }
 
@@ -64,6 +82,7 @@ impl Message {
             content,
             timestamp: chrono::Utc::now().timestamp_millis(),
             is_user: false,
+            reply_to_message_id: None,
         }
     }
 
@@ -94,10 +113,53 @@ impl Message {
             content: text,
             timestamp,
             is_user,
+            reply_to_message_id: event.reply_to_message_id.filter(|id| !id.is_empty()),
         })
     }
 }
 
+/// One entry in a conversation's event log, as stored by the gateway.
+/// Unlike `Message`, tool calls, tool results, and system events are kept.
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct HistoryEvent {
+    pub id: String,
+    /// "inbound_to_agent" or "outbound_from_agent"
+    pub direction: String,
+    pub author: String,
+    /// ISO-8601, as the gateway reported it
+    pub timestamp: String,
+    /// "message", "tool_call", "tool_result", "system", or "error"
+    #[serde(rename = "type")]
+    pub kind: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>,
+    pub is_user: bool,
+}
+
+impl HistoryEvent {
+    /// Convert from proto Event (ledger event)
+    pub fn from_event(event: Event) -> Self {
+        Self {
+            // Local gateways record "inbound" rather than "inbound_to_agent"
+            is_user: event.direction.starts_with("inbound"),
+            id: event.id,
+            direction: event.direction,
+            author: event.author,
+            timestamp: event.timestamp,
+            kind: event.r#type,
+            text: event.text,
+        }
+    }
+}
+
+/// One page of a conversation's events, oldest first
+#[derive(Debug, Clone, Default)]
+pub struct HistoryPage {
+    pub events: Vec<HistoryEvent>,
+    /// Pass to the next request to continue; `None` on the last page
+    pub next_cursor: Option<String>,
+}
+
 /// Token usage information
 #[derive(Debug, Clone, Default)]
 pub struct UsageInfo {
@@ -145,6 +207,7 @@ pub enum StreamEvent {
         tool_id: String,
         tool_name: String,
         input_json: String,
+        confirm_message: Option<String>,
     },
     Usage {
         info: UsageInfo,
@@ -155,6 +218,143 @@ pub enum StreamEvent {
     },
 }
 
+/// Longest tool input shown in an approval preview, in characters
+const INPUT_PREVIEW_CHARS: usize = 200;
+
+/// A tool approval an agent is still waiting on
+#[derive(Debug, Clone, PartialEq)]
+pub struct PendingApproval {
+    pub agent_id: String,
+    pub request_id: String,
+    /// Pass to `approve_tool` to answer it
+    pub tool_id: String,
+    pub tool_name: String,
+    /// Tool input JSON, shortened for display
+    pub input_preview: String,
+    pub confirm_message: Option<String>,
+    /// When the agent asked, in milliseconds since the epoch
+    pub requested_at: i64,
+    /// How long the agent has been waiting, in seconds
+    pub age_seconds: u64,
+}
+
+impl PendingApproval {
+    /// Convert from a proto record, aging it relative to `now`
+    pub fn from_proto(record: ToolApprovalRecord, now: chrono::DateTime<chrono::Utc>) -> Self {
+        let requested_at = parse_millis(&record.requested_at);
+        Self {
+            agent_id: record.agent_id,
+            request_id: record.request_id,
+            tool_id: record.tool_id,
+            tool_name: record.tool_name,
+            input_preview: input_preview(&record.input_json),
+            confirm_message: record.confirm_message,
+            requested_at,
+            age_seconds: ((now.timestamp_millis() - requested_at).max(0) / 1000) as u64,
+        }
+    }
+}
+
+/// A tool approval that has been resolved
+#[derive(Debug, Clone, PartialEq)]
+pub struct ApprovalRecord {
+    pub agent_id: String,
+    pub request_id: String,
+    pub tool_id: String,
+    pub tool_name: String,
+    /// Tool input JSON, shortened for display
+    pub input_preview: String,
+    /// "approved", "approved_all", "denied", "timeout", "cancelled", or
+    /// "expired" (the agent disconnected first)
+    pub decision: String,
+    /// Who decided; None when the agent resolved it on its own
+    pub resolved_by: Option<String>,
+    /// Milliseconds since the epoch
+    pub requested_at: i64,
+    pub resolved_at: i64,
+}
+
+impl ApprovalRecord {
+    /// Convert from a proto record
+    pub fn from_proto(record: ToolApprovalRecord) -> Self {
+        Self {
+            agent_id: record.agent_id,
+            request_id: record.request_id,
+            tool_id: record.tool_id,
+            tool_name: record.tool_name,
+            input_preview: input_preview(&record.input_json),
+            decision: record.decision.unwrap_or_default(),
+            resolved_by: record.resolved_by,
+            requested_at: parse_millis(&record.requested_at),
+            resolved_at: record
+                .resolved_at
+                .as_deref()
+                .map(parse_millis)
+                .unwrap_or_default(),
+        }
+    }
+}
+
+/// A device let in by approving the pairing code it showed
+#[derive(Debug, Clone, PartialEq)]
+pub struct PairedDevice {
+    pub principal_id: String,
+    pub device_name: String,
+    /// Fingerprint of the device's key, to compare with what it showed
+    pub fingerprint: String,
+}
+
+impl From<ApprovePairingResponse> for PairedDevice {
+    fn from(response: ApprovePairingResponse) -> Self {
+        Self {
+            principal_id: response.principal_id,
+            device_name: response.device_name,
+            fingerprint: response.fingerprint,
+        }
+    }
+}
+
+/// One of an agent's conversations, as listed by `list_threads`
+#[derive(Debug, Clone, PartialEq)]
+pub struct ThreadSummary {
+    pub conversation_key: String,
+    /// Title the agent gave the thread; None until it has one
+    pub title: Option<String>,
+    /// When it last had a message, in Unix ms
+    pub updated_at: i64,
+}
+
+impl ThreadSummary {
+    /// The title, or the conversation key for untitled threads
+    pub fn display_title(&self) -> &str {
+        self.title.as_deref().unwrap_or(&self.conversation_key)
+    }
+}
+
+impl From<ThreadInfo> for ThreadSummary {
+    fn from(thread: ThreadInfo) -> Self {
+        Self {
+            updated_at: parse_millis(&thread.updated_at),
+            conversation_key: thread.conversation_key,
+            title: thread.title,
+        }
+    }
+}
+
+fn parse_millis(timestamp: &str) -> i64 {
+    chrono::DateTime::parse_from_rfc3339(timestamp)
+        .map(|dt| dt.timestamp_millis())
+        .unwrap_or_else(|_| chrono::Utc::now().timestamp_millis())
+}
+
+/// First `INPUT_PREVIEW_CHARS` characters of `input`, with an ellipsis if cut
+fn input_preview(input: &str) -> String {
+    match input.char_indices().nth(INPUT_PREVIEW_CHARS) {
+        Some((end, _)) => format!("{}…", &input[..end]),
+        None => input.to_string(),
+    }
+}
+
 /// Gateway connection status
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ConnectionStatus {
@@ -163,10 +363,143 @@ pub enum ConnectionStatus {
     Disconnected,
 }
 
+/// Push notification service a device token belongs to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PushPlatform {
+    /// Apple Push Notification service (iOS, macOS)
+    Apns,
+    /// Firebase Cloud Messaging (Android)
+    Fcm,
+}
+
+impl PushPlatform {
+    /// Name the gateway knows the platform by
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PushPlatform::Apns => "apns",
+            PushPlatform::Fcm => "fcm",
+        }
+    }
+}
+
+/// Largest total size of the files attached to one message, in bytes. Keeps
+/// a send well inside the gateway's 16 MB message limit.
+pub const MAX_ATTACHMENT_BYTES: u64 = 10 * 1024 * 1024;
+
+/// A file sent to an agent along with a message
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct Attachment {
+    pub filename: String,
+    /// MIME type, e.g. "text/plain"; "application/octet-stream" when unknown
+    pub mime_type: String,
+    #[serde(with = "crate::cache::base64_data")]
+    pub data: Vec<u8>,
+}
+
+impl Attachment {
+    /// Convert to proto FileAttachment
+    pub fn to_proto(&self) -> FileAttachment {
+        FileAttachment {
+            filename: self.filename.clone(),
+            mime_type: self.mime_type.clone(),
+            data: self.data.clone(),
+        }
+    }
+}
+
+/// Check that `attachments` can go out with one message: each has a name
+/// and together they fit in `MAX_ATTACHMENT_BYTES`
+pub fn check_attachments(attachments: &[Attachment]) -> Result<(), CovenError> {
+    if let Some(unnamed) = attachments.iter().find(|a| a.filename.trim().is_empty()) {
+        return Err(CovenError::InvalidAttachment(format!(
+            "attachment of {} bytes has no filename",
+            unnamed.data.len()
+        )));
+    }
+    let total: u64 = attachments.iter().map(|a| a.data.len() as u64).sum();
+    if total > MAX_ATTACHMENT_BYTES {
+        return Err(CovenError::InvalidAttachment(format!(
+            "{} bytes attached, over the limit of {} bytes per message",
+            total, MAX_ATTACHMENT_BYTES
+        )));
+    }
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_pending_approval_age_and_preview() {
+        let requested = chrono::Utc::now() - chrono::Duration::seconds(90);
+        let approval = PendingApproval::from_proto(
+            ToolApprovalRecord {
+                agent_id: "agent-1".to_string(),
+                request_id: "req-1".to_string(),
+                tool_id: "tool-1".to_string(),
+                tool_name: "write_file".to_string(),
+                input_json: format!(r#"{{"content":"{}"}}"#, "x".repeat(500)),
+                requested_at: requested.to_rfc3339(),
+                ..Default::default()
+            },
+            requested + chrono::Duration::seconds(90),
+        );
+        assert_eq!(approval.age_seconds, 90);
+        assert_eq!(approval.requested_at, requested.timestamp_millis());
+        assert_eq!(
+            approval.input_preview.chars().count(),
+            INPUT_PREVIEW_CHARS + 1
+        );
+        assert!(approval.input_preview.ends_with('…'));
+    }
+
+    #[test]
+    fn test_approval_record_from_proto() {
+        let record = ApprovalRecord::from_proto(ToolApprovalRecord {
+            tool_id: "tool-1".to_string(),
+            input_json: "{}".to_string(),
+            requested_at: "2026-01-01T00:00:00Z".to_string(),
+            resolved_at: Some("2026-01-01T00:00:05Z".to_string()),
+            decision: Some("denied".to_string()),
+            resolved_by: Some("local-user".to_string()),
+            ..Default::default()
+        });
+        assert_eq!(record.decision, "denied");
+        assert_eq!(record.resolved_at - record.requested_at, 5000);
+        assert_eq!(record.input_preview, "{}");
+    }
+
+    #[test]
+    fn test_history_event_keeps_tool_calls() {
+        let event = HistoryEvent::from_event(Event {
+            id: "evt-1".to_string(),
+            conversation_key: "agent-1".to_string(),
+            direction: "outbound_from_agent".to_string(),
+            author: "agent".to_string(),
+            timestamp: "2026-01-02T03:04:05Z".to_string(),
+            r#type: "tool_call".to_string(),
+            text: Some(r#"{"name":"bash"}"#.to_string()),
+            ..Default::default()
+        });
+
+        assert_eq!(event.kind, "tool_call");
+        assert!(!event.is_user);
+        assert!(Message::from_event(
+            Event {
+                r#type: "tool_call".to_string(),
+                text: event.text.clone(),
+                ..Default::default()
+            },
+            "Agent"
+        )
+        .is_none());
+
+        let json = serde_json::to_value(&event).unwrap();
+        assert_eq!(json["type"], "tool_call");
+        assert_eq!(json["timestamp"], "2026-01-02T03:04:05Z");
+    }
+
     #[test]
     fn test_agent_from_proto() {
         let proto = AgentInfo {
@@ -185,6 +518,31 @@ mod tests {
         assert_eq!(agent.backend, "claude");
         assert_eq!(agent.working_dir, "/home/user");
         assert!(agent.connected);
+        assert_eq!(agent.status, "available");
+        assert!(agent.status_emoji.is_empty());
+    }
+
+    #[test]
+    fn test_agent_from_proto_with_presence() {
+        let proto = AgentInfo {
+            id: "agent-789".to_string(),
+            name: "BusyAgent".to_string(),
+            backend: "mux".to_string(),
+            working_dir: "/tmp".to_string(),
+            connected: true,
+            metadata: Some(coven_proto::AgentMetadata {
+                presence: Some(AgentPresence {
+                    status: "busy".to_string(),
+                    emoji: "🔥".to_string(),
+                }),
+                ..Default::default()
+            }),
+        };
+
+        let agent = Agent::from_proto(proto);
+
+        assert_eq!(agent.status, "busy");
+        assert_eq!(agent.status_emoji, "🔥");
     }
 
     #[test]
@@ -277,6 +635,23 @@ mod tests {
         assert_eq!(msg.sender, "Claude");
         assert_eq!(msg.content, "Agent response");
         assert!(!msg.is_user);
+        assert_eq!(msg.reply_to_message_id, None);
+    }
+
+    #[test]
+    fn test_message_from_event_reply() {
+        let event = Event {
+            id: "evt-003".to_string(),
+            r#type: "message".to_string(),
+            direction: "inbound_to_agent".to_string(),
+            timestamp: "2024-01-15T10:32:00Z".to_string(),
+            text: Some("Following up".to_string()),
+            reply_to_message_id: Some("evt-001".to_string()),
+            ..Default::default()
+        };
+
+        let msg = Message::from_event(event, "Claude").unwrap();
+        assert_eq!(msg.reply_to_message_id.as_deref(), Some("evt-001"));
     }
 
     #[test]
@@ -437,6 +812,7 @@ mod tests {
             tool_id: "tool-1".to_string(),
             tool_name: "bash".to_string(),
             input_json: r#"{"command": "ls"}"#.to_string(),
+            confirm_message: None,
         };
         let usage = StreamEvent::Usage {
             info: UsageInfo::default(),
@@ -492,6 +868,8 @@ mod tests {
             backend: "claude".to_string(),
             working_dir: "/tmp".to_string(),
             connected: true,
+            status: "available".to_string(),
+            status_emoji: String::new(),
         };
 
         // Test Debug
@@ -550,4 +928,36 @@ mod tests {
             panic!("Expected Text variant");
         }
     }
+
+    #[test]
+    fn test_check_attachments() {
+        let file = |name: &str, len: usize| Attachment {
+            filename: name.to_string(),
+            mime_type: "text/plain".to_string(),
+            data: vec![0; len],
+        };
+        assert!(check_attachments(&[]).is_ok());
+        assert!(check_attachments(&[file("a.txt", 10), file("b.txt", 10)]).is_ok());
+
+        let half = (MAX_ATTACHMENT_BYTES / 2) as usize;
+        assert!(check_attachments(&[file("a.bin", half), file("b.bin", half)]).is_ok());
+        let err = check_attachments(&[file("a.bin", half), file("b.bin", half + 1)]).unwrap_err();
+        assert!(matches!(err, CovenError::InvalidAttachment(_)));
+
+        let err = check_attachments(&[file(" ", 1)]).unwrap_err();
+        assert!(err.to_string().contains("no filename"));
+    }
+
+    #[test]
+    fn test_attachment_to_proto() {
+        let attachment = Attachment {
+            filename: "notes.md".to_string(),
+            mime_type: "text/markdown".to_string(),
+            data: b"# Notes".to_vec(),
+        };
+        let proto = attachment.to_proto();
+        assert_eq!(proto.filename, "notes.md");
+        assert_eq!(proto.mime_type, "text/markdown");
+        assert_eq!(proto.data, b"# Notes");
+    }
 }
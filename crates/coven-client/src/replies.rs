@@ -0,0 +1,99 @@
+// ABOUTME: Quote line shown above a threaded reply in a chat transcript
+// ABOUTME: Shared by the terminal frontends so replies read the same everywhere
+
+use std::borrow::Cow;
+
+/// Longest parent excerpt shown above a reply
+const REPLY_QUOTE_CHARS: usize = 60;
+
+/// A transcript message that may reply to an earlier one
+pub trait Threaded {
+    /// Gateway ID of this message, when known
+    fn message_id(&self) -> Option<&str>;
+    /// Gateway ID of the message this one replies to
+    fn reply_to(&self) -> Option<&str>;
+    /// Sender name shown when this message is quoted
+    fn quote_sender(&self) -> &str;
+    /// Text shown when this message is quoted
+    fn quote_text(&self) -> Cow<'_, str>;
+}
+
+/// Quote line shown above `messages[index]` when it replies to a message
+/// other than the one right before it: the parent's sender and first line,
+/// or a placeholder when the parent isn't in the transcript.
+pub fn reply_quote<M: Threaded>(messages: &[M], index: usize) -> Option<String> {
+    let reply_to = messages.get(index)?.reply_to()?;
+    let parent = messages[..index]
+        .iter()
+        .rposition(|m| m.message_id() == Some(reply_to));
+    match parent {
+        Some(p) if p + 1 == index => None,
+        Some(p) => {
+            let parent = &messages[p];
+            let text = parent.quote_text();
+            let first = text.lines().next().unwrap_or("");
+            let mut excerpt: String = first.chars().take(REPLY_QUOTE_CHARS).collect();
+            if first.chars().count() > REPLY_QUOTE_CHARS || text.lines().nth(1).is_some() {
+                excerpt.push('…');
+            }
+            Some(format!("↳ {}: {}", parent.quote_sender(), excerpt))
+        }
+        None => Some("↳ reply to an earlier message".to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Line {
+        id: &'static str,
+        reply_to: Option<&'static str>,
+        text: String,
+    }
+
+    impl Threaded for Line {
+        fn message_id(&self) -> Option<&str> {
+            Some(self.id)
+        }
+
+        fn reply_to(&self) -> Option<&str> {
+            self.reply_to
+        }
+
+        fn quote_sender(&self) -> &str {
+            "alice"
+        }
+
+        fn quote_text(&self) -> Cow<'_, str> {
+            Cow::Borrowed(&self.text)
+        }
+    }
+
+    #[test]
+    fn test_long_parent_is_truncated() {
+        let messages = vec![
+            Line {
+                id: "m1",
+                reply_to: None,
+                text: "x".repeat(100),
+            },
+            Line {
+                id: "m2",
+                reply_to: None,
+                text: "unrelated".to_string(),
+            },
+            Line {
+                id: "m3",
+                reply_to: Some("m1"),
+                text: "about that".to_string(),
+            },
+        ];
+
+        let quote = reply_quote(&messages, 2).unwrap();
+        assert_eq!(
+            quote,
+            format!("↳ alice: {}…", "x".repeat(REPLY_QUOTE_CHARS))
+        );
+    }
+}
@@ -0,0 +1,366 @@
+// ABOUTME: Mock gateway shared by the coven-client integration tests
+// ABOUTME: One agent, a stored history, recorded sends, an optional reply, and a clock and auth to misbehave with
+
+// Each test binary uses a different part of this
+#![allow(dead_code)]
+
+use coven_proto::server::{ClientService, ClientServiceServer};
+use coven_proto::{
+    client_stream_event, AgentInfo, AgentInitiatedEvent, ApproveToolRequest, ApproveToolResponse,
+    ClientSendMessageRequest, ClientSendMessageResponse, ClientStreamEvent, Event,
+    ForkThreadRequest, ForkThreadResponse, GetApprovalHistoryRequest, GetApprovalHistoryResponse,
+    GetEventsRequest, GetEventsResponse, GetPairingStatusRequest, ListAgentsRequest,
+    ListAgentsResponse, ListPendingApprovalsRequest, ListPendingApprovalsResponse,
+    ListThreadsRequest, ListThreadsResponse, MeResponse, PairingCode, PairingStatus,
+    RefreshTokenRequest, RefreshTokenResponse, RegisterAgentRequest, RegisterAgentResponse,
+    RegisterClientRequest, RegisterClientResponse, RegisterPushTokenRequest,
+    RegisterPushTokenResponse, RequestPairingCodeRequest, RotateKeyRequest, RotateKeyResponse,
+    StreamAgentInitiatedRequest, StreamDone, StreamEventsRequest, TextChunk, TokenUsage,
+    ToolSummary, UnregisterPushTokenRequest, UnregisterPushTokenResponse, VersionResponse,
+};
+use std::net::SocketAddr;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::net::TcpListener;
+use tokio::sync::{mpsc, oneshot, Notify};
+use tokio::task::JoinHandle;
+use tokio_stream::wrappers::{ReceiverStream, TcpListenerStream};
+use tonic::{Request, Response, Status};
+
+pub const AGENT_ID: &str = "agent-1";
+
+/// Messages with this content are blocked by the gateway's content policy
+pub const BLOCKED_CONTENT: &str = "forbidden";
+
+/// Gateway with one agent whose conversation is whatever `history` holds.
+/// Sent messages are recorded and added to the history, the way a real
+/// gateway stores them, with the idempotency key as the event ID. With
+/// `replies` set, the agent answers each message with `reply()`; to
+/// subscribers that don't stream, only with its done. GetVersion reports
+/// the clock shifted by `skew_ms`, and GetMe fails if `reject_auth` is set.
+#[derive(Clone, Default)]
+pub struct MockGateway {
+    pub history: Arc<Mutex<Vec<Event>>>,
+    pub sent: Arc<Mutex<Vec<ClientSendMessageRequest>>>,
+    pub replies: bool,
+    pub skew_ms: i64,
+    pub reject_auth: bool,
+    pub message_sent: Arc<Notify>,
+}
+
+type BoxStream<T> = Pin<Box<dyn futures::Stream<Item = Result<T, Status>> + Send>>;
+
+pub fn event(
+    id: &str,
+    direction: &str,
+    text: &str,
+    timestamp: chrono::DateTime<chrono::Utc>,
+) -> Event {
+    Event {
+        id: id.to_string(),
+        conversation_key: AGENT_ID.to_string(),
+        direction: direction.to_string(),
+        timestamp: timestamp.to_rfc3339(),
+        r#type: "message".to_string(),
+        text: Some(text.to_string()),
+        ..Default::default()
+    }
+}
+
+/// What the agent answers with: "hello" in two chunks, a usage report,
+/// and done, listing a tool it used
+pub fn reply() -> Vec<client_stream_event::Payload> {
+    vec![
+        client_stream_event::Payload::Text(TextChunk {
+            content: "hel".to_string(),
+        }),
+        client_stream_event::Payload::Text(TextChunk {
+            content: "lo".to_string(),
+        }),
+        client_stream_event::Payload::Usage(TokenUsage {
+            input_tokens: 12,
+            output_tokens: 2,
+            ..Default::default()
+        }),
+        client_stream_event::Payload::Done(StreamDone {
+            full_response: Some("hello".to_string()),
+            tools: vec![ToolSummary {
+                id: "tool-1".to_string(),
+                name: "read_file".to_string(),
+                is_error: false,
+                completed: true,
+            }],
+        }),
+    ]
+}
+
+#[tonic::async_trait]
+impl ClientService for MockGateway {
+    async fn get_me(&self, _request: Request<()>) -> Result<Response<MeResponse>, Status> {
+        if self.reject_auth {
+            return Err(Status::unauthenticated("unknown public key"));
+        }
+        Ok(Response::new(MeResponse {
+            principal_id: "client-1".to_string(),
+            display_name: "Test Client".to_string(),
+            ..Default::default()
+        }))
+    }
+
+    async fn get_version(
+        &self,
+        _request: Request<()>,
+    ) -> Result<Response<VersionResponse>, Status> {
+        Ok(Response::new(VersionResponse {
+            version: "0.1.0".to_string(),
+            component: "mock-gateway".to_string(),
+            server_time_ms: Some(chrono::Utc::now().timestamp_millis() + self.skew_ms),
+        }))
+    }
+
+    async fn get_events(
+        &self,
+        _request: Request<GetEventsRequest>,
+    ) -> Result<Response<GetEventsResponse>, Status> {
+        Ok(Response::new(GetEventsResponse {
+            events: self.history.lock().unwrap().clone(),
+            ..Default::default()
+        }))
+    }
+
+    async fn send_message(
+        &self,
+        request: Request<ClientSendMessageRequest>,
+    ) -> Result<Response<ClientSendMessageResponse>, Status> {
+        let request = request.into_inner();
+        if request.content == BLOCKED_CONTENT {
+            return Ok(Response::new(ClientSendMessageResponse {
+                status: ClientSendMessageResponse::STATUS_BLOCKED.to_string(),
+                message_id: String::new(),
+                detail: Some("Not here, please.".to_string()),
+            }));
+        }
+        self.history.lock().unwrap().push(event(
+            &request.idempotency_key,
+            "inbound_to_agent",
+            &request.content,
+            chrono::Utc::now(),
+        ));
+        self.sent.lock().unwrap().push(request.clone());
+        self.message_sent.notify_one();
+        Ok(Response::new(ClientSendMessageResponse {
+            status: "accepted".to_string(),
+            message_id: request.idempotency_key,
+            detail: None,
+        }))
+    }
+
+    type StreamEventsStream = BoxStream<ClientStreamEvent>;
+
+    async fn stream_events(
+        &self,
+        request: Request<StreamEventsRequest>,
+    ) -> Result<Response<Self::StreamEventsStream>, Status> {
+        if !self.replies {
+            return Ok(Response::new(Box::pin(futures::stream::pending())));
+        }
+        let request = request.into_inner();
+        let conversation_key = request.conversation_key;
+        let buffered = request.stream == Some(false);
+        let message_sent = self.message_sent.clone();
+        let (tx, rx) = mpsc::channel(8);
+        tokio::spawn(async move {
+            message_sent.notified().await;
+            for payload in reply() {
+                if buffered && !matches!(payload, client_stream_event::Payload::Done(_)) {
+                    continue;
+                }
+                let event = ClientStreamEvent {
+                    conversation_key: conversation_key.clone(),
+                    timestamp: chrono::Utc::now().to_rfc3339(),
+                    payload: Some(payload),
+                };
+                if tx.send(Ok(event)).await.is_err() {
+                    break;
+                }
+            }
+        });
+        Ok(Response::new(Box::pin(ReceiverStream::new(rx))))
+    }
+
+    type StreamAgentInitiatedStream = BoxStream<AgentInitiatedEvent>;
+
+    async fn stream_agent_initiated(
+        &self,
+        _request: Request<StreamAgentInitiatedRequest>,
+    ) -> Result<Response<Self::StreamAgentInitiatedStream>, Status> {
+        Err(Status::unimplemented("stream_agent_initiated"))
+    }
+
+    async fn list_agents(
+        &self,
+        _request: Request<ListAgentsRequest>,
+    ) -> Result<Response<ListAgentsResponse>, Status> {
+        Ok(Response::new(ListAgentsResponse {
+            agents: vec![AgentInfo {
+                id: AGENT_ID.to_string(),
+                name: "Agent One".to_string(),
+                connected: true,
+                ..Default::default()
+            }],
+        }))
+    }
+
+    type WatchAgentsStream = BoxStream<ListAgentsResponse>;
+
+    async fn watch_agents(
+        &self,
+        _request: Request<ListAgentsRequest>,
+    ) -> Result<Response<Self::WatchAgentsStream>, Status> {
+        Err(Status::unimplemented("watch_agents"))
+    }
+
+    async fn register_agent(
+        &self,
+        _request: Request<RegisterAgentRequest>,
+    ) -> Result<Response<RegisterAgentResponse>, Status> {
+        Err(Status::unimplemented("register_agent"))
+    }
+
+    async fn register_client(
+        &self,
+        _request: Request<RegisterClientRequest>,
+    ) -> Result<Response<RegisterClientResponse>, Status> {
+        Err(Status::unimplemented("register_client"))
+    }
+
+    async fn approve_tool(
+        &self,
+        _request: Request<ApproveToolRequest>,
+    ) -> Result<Response<ApproveToolResponse>, Status> {
+        Err(Status::unimplemented("approve_tool"))
+    }
+
+    async fn fork_thread(
+        &self,
+        _request: Request<ForkThreadRequest>,
+    ) -> Result<Response<ForkThreadResponse>, Status> {
+        Err(Status::unimplemented("fork_thread"))
+    }
+
+    async fn list_threads(
+        &self,
+        _request: Request<ListThreadsRequest>,
+    ) -> Result<Response<ListThreadsResponse>, Status> {
+        Err(Status::unimplemented("list_threads"))
+    }
+
+    async fn register_push_token(
+        &self,
+        _request: Request<RegisterPushTokenRequest>,
+    ) -> Result<Response<RegisterPushTokenResponse>, Status> {
+        Err(Status::unimplemented("register_push_token"))
+    }
+
+    async fn unregister_push_token(
+        &self,
+        _request: Request<UnregisterPushTokenRequest>,
+    ) -> Result<Response<UnregisterPushTokenResponse>, Status> {
+        Err(Status::unimplemented("unregister_push_token"))
+    }
+
+    async fn rotate_key(
+        &self,
+        _request: Request<RotateKeyRequest>,
+    ) -> Result<Response<RotateKeyResponse>, Status> {
+        Err(Status::unimplemented("rotate_key"))
+    }
+
+    async fn request_pairing_code(
+        &self,
+        _request: Request<RequestPairingCodeRequest>,
+    ) -> Result<Response<PairingCode>, Status> {
+        Err(Status::unimplemented("request_pairing_code"))
+    }
+
+    async fn get_pairing_status(
+        &self,
+        _request: Request<GetPairingStatusRequest>,
+    ) -> Result<Response<PairingStatus>, Status> {
+        Err(Status::unimplemented("get_pairing_status"))
+    }
+
+    async fn refresh_token(
+        &self,
+        _request: Request<RefreshTokenRequest>,
+    ) -> Result<Response<RefreshTokenResponse>, Status> {
+        Err(Status::unimplemented("refresh_token"))
+    }
+
+    async fn list_pending_approvals(
+        &self,
+        _request: Request<ListPendingApprovalsRequest>,
+    ) -> Result<Response<ListPendingApprovalsResponse>, Status> {
+        Err(Status::unimplemented("list_pending_approvals"))
+    }
+
+    async fn get_approval_history(
+        &self,
+        _request: Request<GetApprovalHistoryRequest>,
+    ) -> Result<Response<GetApprovalHistoryResponse>, Status> {
+        Err(Status::unimplemented("get_approval_history"))
+    }
+}
+
+/// Serve `gateway` on a free port, returning its URL
+pub async fn serve(gateway: MockGateway) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        tonic::transport::Server::builder()
+            .add_service(ClientServiceServer::new(gateway))
+            .serve_with_incoming(TcpListenerStream::new(listener))
+            .await
+            .unwrap();
+    });
+    format!("http://{}", addr)
+}
+
+/// A running gateway that can be stopped and started again on the same address
+pub struct Running {
+    shutdown: oneshot::Sender<()>,
+    task: JoinHandle<()>,
+}
+
+/// Serve `gateway` on `addr` until stopped
+pub async fn serve_at(gateway: MockGateway, addr: SocketAddr) -> Running {
+    let listener = TcpListener::bind(addr).await.unwrap();
+    let (shutdown, stopped) = oneshot::channel();
+    let task = tokio::spawn(async move {
+        tonic::transport::Server::builder()
+            .add_service(ClientServiceServer::new(gateway))
+            .serve_with_incoming_shutdown(TcpListenerStream::new(listener), async {
+                let _ = stopped.await;
+            })
+            .await
+            .unwrap();
+    });
+    Running { shutdown, task }
+}
+
+pub async fn stop(running: Running) {
+    running.shutdown.send(()).unwrap();
+    running.task.await.unwrap();
+}
+
+/// Wait until `check` holds, polling while background work runs
+pub async fn eventually(check: impl Fn() -> bool) {
+    for _ in 0..100 {
+        if check() {
+            return;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    panic!("condition not met in time");
+}
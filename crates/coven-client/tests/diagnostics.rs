@@ -0,0 +1,109 @@
+// ABOUTME: Tests CovenClient::diagnose against a mock gateway and things that aren't one.
+// ABOUTME: Covers a healthy gateway, a rejected key, a skewed clock, and a server that isn't gRPC.
+
+mod common;
+
+use common::{serve, MockGateway};
+use coven_client::{CovenClient, DiagnosticReport, DiagnosticStatus};
+
+/// A client for `url` signing with a fresh key
+fn client_with_key(url: String, dir: &tempfile::TempDir) -> CovenClient {
+    CovenClient::new_with_auth(url, &dir.path().join("id_ed25519")).unwrap()
+}
+
+fn status_of(report: &DiagnosticReport, name: &str) -> DiagnosticStatus {
+    report
+        .steps
+        .iter()
+        .find(|s| s.name == name)
+        .unwrap_or_else(|| panic!("no {} step in {:?}", name, report))
+        .status
+}
+
+fn message_of<'a>(report: &'a DiagnosticReport, name: &str) -> &'a str {
+    &report
+        .steps
+        .iter()
+        .find(|s| s.name == name)
+        .unwrap()
+        .message
+}
+
+#[tokio::test]
+async fn test_healthy_gateway_passes_every_step() {
+    let dir = tempfile::tempdir().unwrap();
+    let url = serve(MockGateway::default()).await;
+    let client = client_with_key(url.clone(), &dir);
+
+    let report = client.diagnose_async().await;
+    let names: Vec<&str> = report.steps.iter().map(|s| s.name.as_str()).collect();
+    assert_eq!(names, ["dns", "tcp", "tls", "grpc", "auth", "clock"]);
+    assert!(
+        report
+            .steps
+            .iter()
+            .all(|s| s.status == DiagnosticStatus::Ok),
+        "{}",
+        report
+    );
+    assert!(report.healthy);
+    assert_eq!(report.gateway_url, url);
+    assert!(message_of(&report, "grpc").contains("mock-gateway 0.1.0"));
+    assert!(message_of(&report, "auth").contains("as Test Client"));
+    assert!(report.clock_skew_ms.unwrap().abs() < 5_000);
+}
+
+#[tokio::test]
+async fn test_without_key_auth_is_skipped() {
+    let url = serve(MockGateway::default()).await;
+    let report = CovenClient::new(url).diagnose_async().await;
+    assert_eq!(status_of(&report, "auth"), DiagnosticStatus::Skipped);
+    assert_eq!(status_of(&report, "clock"), DiagnosticStatus::Ok);
+    assert!(report.healthy);
+}
+
+#[tokio::test]
+async fn test_rejected_key_fails_auth() {
+    let dir = tempfile::tempdir().unwrap();
+    let url = serve(MockGateway {
+        reject_auth: true,
+        ..Default::default()
+    })
+    .await;
+
+    let report = client_with_key(url, &dir).diagnose_async().await;
+    assert_eq!(status_of(&report, "grpc"), DiagnosticStatus::Ok);
+    assert_eq!(status_of(&report, "auth"), DiagnosticStatus::Failed);
+    assert!(message_of(&report, "auth").starts_with("gateway rejected SSH key "));
+    assert!(message_of(&report, "auth").contains("unknown public key"));
+    // The clock still comes from the version check
+    assert_eq!(status_of(&report, "clock"), DiagnosticStatus::Ok);
+    assert!(!report.healthy);
+}
+
+#[tokio::test]
+async fn test_clock_skew_is_reported() {
+    let dir = tempfile::tempdir().unwrap();
+
+    let url = serve(MockGateway {
+        skew_ms: 60_000,
+        ..Default::default()
+    })
+    .await;
+    let report = client_with_key(url, &dir).diagnose_async().await;
+    assert_eq!(status_of(&report, "clock"), DiagnosticStatus::Warning);
+    assert!(message_of(&report, "clock").contains("behind"));
+    assert!(report.healthy);
+
+    let url = serve(MockGateway {
+        skew_ms: -600_000,
+        ..Default::default()
+    })
+    .await;
+    let report = client_with_key(url, &dir).diagnose_async().await;
+    assert_eq!(status_of(&report, "clock"), DiagnosticStatus::Failed);
+    assert!(message_of(&report, "clock").contains("ahead of"));
+    let skew = report.clock_skew_ms.unwrap();
+    assert!((-605_000..-595_000).contains(&skew), "{}", skew);
+    assert!(!report.healthy);
+}
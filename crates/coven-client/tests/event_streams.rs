@@ -0,0 +1,148 @@
+// ABOUTME: Tests that the async event streams and the FFI callbacks see the same events.
+// ABOUTME: A mock gateway answers one message with a fixed sequence of stream events, or just its end when buffering, or blocks it.
+
+mod common;
+
+use common::{eventually, serve, MockGateway, AGENT_ID, BLOCKED_CONTENT};
+use coven_client::{ConnectionStatus, CovenClient, StateCallback, StreamCallback, StreamEvent};
+use futures::StreamExt;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// A gateway whose agent answers every message
+async fn start_gateway() -> String {
+    serve(MockGateway {
+        replies: true,
+        ..Default::default()
+    })
+    .await
+}
+
+/// Records what the FFI callbacks are told
+#[derive(Clone, Default)]
+struct Recorder {
+    events: Arc<Mutex<Vec<String>>>,
+    statuses: Arc<Mutex<Vec<ConnectionStatus>>>,
+}
+
+impl StreamCallback for Recorder {
+    fn on_event(&self, agent_id: String, event: StreamEvent) {
+        assert_eq!(agent_id, AGENT_ID);
+        self.events.lock().unwrap().push(format!("{:?}", event));
+    }
+}
+
+impl StateCallback for Recorder {
+    fn on_connection_status(&self, status: ConnectionStatus) {
+        self.statuses.lock().unwrap().push(status);
+    }
+    fn on_messages_changed(&self, _agent_id: String) {}
+    fn on_queue_changed(&self, _agent_id: String, _count: u32) {}
+    fn on_unread_changed(&self, _agent_id: String, _count: u32) {}
+    fn on_streaming_changed(&self, _agent_id: String, _is_streaming: bool) {}
+}
+
+#[tokio::test]
+async fn test_streams_and_callbacks_see_the_same_events() {
+    let url = start_gateway().await;
+    let client = CovenClient::new(url);
+    let recorder = Recorder::default();
+    client.set_stream_callback(Box::new(recorder.clone()));
+    client.set_state_callback(Box::new(recorder.clone()));
+
+    let mut connection = client.connection_events();
+    assert_eq!(*connection.borrow(), ConnectionStatus::Connecting);
+    let events = client.subscribe_events(AGENT_ID.to_string());
+    let mut other_agent = Box::pin(client.subscribe_events("agent-2".to_string()));
+
+    client.refresh_agents_async().await.unwrap();
+    connection.changed().await.unwrap();
+    assert_eq!(*connection.borrow_and_update(), ConnectionStatus::Connected);
+
+    client
+        .send_message(AGENT_ID.to_string(), "hi".to_string())
+        .unwrap();
+    let streamed: Vec<String> = tokio::time::timeout(
+        Duration::from_secs(10),
+        events
+            .take_while(|event| futures::future::ready(!matches!(event, StreamEvent::Done)))
+            .map(|event| format!("{:?}", event))
+            .collect(),
+    )
+    .await
+    .expect("stream events");
+
+    assert_eq!(streamed.len(), 3, "{streamed:?}");
+    assert!(streamed[0].contains("hel") && streamed[1].contains("lo"));
+    assert!(streamed[2].starts_with("Usage"));
+
+    let mut expected = streamed.clone();
+    expected.push(format!("{:?}", StreamEvent::Done));
+    eventually(|| recorder.events.lock().unwrap().len() == expected.len()).await;
+    assert_eq!(*recorder.events.lock().unwrap(), expected);
+    assert_eq!(
+        *recorder.statuses.lock().unwrap(),
+        [ConnectionStatus::Connected]
+    );
+
+    // Events for other agents aren't delivered to this subscriber
+    assert!(
+        tokio::time::timeout(Duration::from_millis(100), other_agent.next())
+            .await
+            .is_err()
+    );
+    assert_eq!(client.get_session_usage().input_tokens, 12);
+}
+
+#[tokio::test]
+async fn test_buffered_responses_arrive_whole() {
+    let url = start_gateway().await;
+    let client = CovenClient::new(url);
+    client.set_streaming(false);
+    let events = client.subscribe_events(AGENT_ID.to_string());
+
+    client.refresh_agents_async().await.unwrap();
+    client
+        .send_message(AGENT_ID.to_string(), "hi".to_string())
+        .unwrap();
+    let events: Vec<StreamEvent> = tokio::time::timeout(
+        Duration::from_secs(10),
+        events
+            .take_while(|event| futures::future::ready(!matches!(event, StreamEvent::Done)))
+            .collect(),
+    )
+    .await
+    .expect("stream events");
+
+    // The tools it used come first, from the summary in its Done
+    assert_eq!(events.len(), 2, "{events:?}");
+    assert!(matches!(
+        &events[0],
+        StreamEvent::ToolState { state, detail } if state == "completed" && detail == "read_file"
+    ));
+    assert!(matches!(&events[1], StreamEvent::Text { content } if content == "hello"));
+    eventually(|| !client.is_streaming(AGENT_ID.to_string())).await;
+    let messages = client.get_messages(AGENT_ID.to_string());
+    assert_eq!(messages.last().unwrap().content, "hello");
+}
+
+#[tokio::test]
+async fn test_blocked_message_is_an_error() {
+    let url = start_gateway().await;
+    let client = CovenClient::new(url);
+    let events = client.subscribe_events(AGENT_ID.to_string());
+
+    client.refresh_agents_async().await.unwrap();
+    client
+        .send_message(AGENT_ID.to_string(), BLOCKED_CONTENT.to_string())
+        .unwrap();
+    let first = tokio::time::timeout(Duration::from_secs(10), Box::pin(events).next())
+        .await
+        .expect("stream events")
+        .unwrap();
+    assert!(
+        matches!(&first, StreamEvent::Error { message } if message.contains("Not here, please.")),
+        "{first:?}"
+    );
+    eventually(|| !client.is_streaming(AGENT_ID.to_string())).await;
+}
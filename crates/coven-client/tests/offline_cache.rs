@@ -0,0 +1,187 @@
+// ABOUTME: Tests the offline cache: cached conversations, sends queued while the gateway is down,
+// ABOUTME: replay on reconnect merged with history missed while disconnected, and attachments.
+
+mod common;
+
+use common::{event, eventually, serve_at, stop, MockGateway, AGENT_ID};
+use coven_client::{
+    Attachment, CacheConfig, ConnectionStatus, CovenClient, CovenError, StateCallback,
+    MAX_ATTACHMENT_BYTES,
+};
+use std::sync::{Arc, Mutex};
+use tokio::net::TcpListener;
+
+/// Records the queue counts the state callback is told about
+#[derive(Clone, Default)]
+struct Recorder {
+    queue_counts: Arc<Mutex<Vec<u32>>>,
+}
+
+impl StateCallback for Recorder {
+    fn on_connection_status(&self, _status: ConnectionStatus) {}
+    fn on_messages_changed(&self, _agent_id: String) {}
+    fn on_queue_changed(&self, agent_id: String, count: u32) {
+        assert_eq!(agent_id, AGENT_ID);
+        self.queue_counts.lock().unwrap().push(count);
+    }
+    fn on_unread_changed(&self, _agent_id: String, _count: u32) {}
+    fn on_streaming_changed(&self, _agent_id: String, _is_streaming: bool) {}
+}
+
+fn message_ids(client: &CovenClient) -> Vec<String> {
+    client
+        .get_messages(AGENT_ID.to_string())
+        .into_iter()
+        .map(|m| m.id)
+        .collect()
+}
+
+#[tokio::test]
+async fn test_sends_while_offline_are_replayed_on_reconnect() {
+    let dir = tempfile::tempdir().unwrap();
+    let cache = CacheConfig {
+        path: dir.path().join("cache/messages.json").display().to_string(),
+        max_messages_per_agent: 0,
+    };
+
+    let hour_ago = chrono::Utc::now() - chrono::Duration::hours(1);
+    let gateway = MockGateway::default();
+    gateway.history.lock().unwrap().extend([
+        event("m1", "inbound_to_agent", "hi", hour_ago),
+        event(
+            "m2",
+            "outbound_from_agent",
+            "hello",
+            hour_ago + chrono::Duration::seconds(1),
+        ),
+    ]);
+    let addr = {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        listener.local_addr().unwrap()
+    };
+    let running = serve_at(gateway.clone(), addr).await;
+    let url = format!("http://{}", addr);
+
+    let client = CovenClient::new(url.clone());
+    client.enable_cache(cache.clone()).unwrap();
+    client.refresh_agents_async().await.unwrap();
+    client
+        .load_history_async(AGENT_ID.to_string())
+        .await
+        .unwrap();
+    assert_eq!(message_ids(&client), ["m1", "m2"]);
+
+    // The gateway goes away, and the agent says something the client misses
+    stop(running).await;
+    gateway.history.lock().unwrap().push(event(
+        "missed",
+        "outbound_from_agent",
+        "are you there?",
+        hour_ago + chrono::Duration::seconds(2),
+    ));
+
+    // The first send finds the gateway unreachable and is queued; once
+    // the client knows it is offline, the next is queued straight away
+    client
+        .send_message(AGENT_ID.to_string(), "first".to_string())
+        .unwrap();
+    eventually(|| client.get_queue_count(AGENT_ID.to_string()) == 1).await;
+    assert_eq!(
+        *client.connection_events().borrow(),
+        ConnectionStatus::Disconnected
+    );
+    client
+        .send_message(AGENT_ID.to_string(), "second".to_string())
+        .unwrap();
+    assert_eq!(client.get_queue_count(AGENT_ID.to_string()), 2);
+    let local_ids = message_ids(&client);
+    assert_eq!(local_ids.len(), 4);
+    drop(client);
+
+    // A restarted app has the conversation and the queue before connecting
+    let client = CovenClient::new(url);
+    let recorder = Recorder::default();
+    client.set_state_callback(Box::new(recorder.clone()));
+    client.enable_cache(cache).unwrap();
+    assert_eq!(message_ids(&client), local_ids);
+    assert_eq!(client.get_queue_count(AGENT_ID.to_string()), 2);
+
+    // Reconnecting sends the queue in order, then merges in what was missed
+    let _running = serve_at(gateway.clone(), addr).await;
+    client.refresh_agents_async().await.unwrap();
+    eventually(|| client.get_queue_count(AGENT_ID.to_string()) == 0).await;
+    let sent: Vec<(String, String)> = gateway
+        .sent
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|r| (r.idempotency_key.clone(), r.content.clone()))
+        .collect();
+    assert_eq!(
+        sent,
+        [
+            (local_ids[2].clone(), "first".to_string()),
+            (local_ids[3].clone(), "second".to_string()),
+        ]
+    );
+
+    let expected = [
+        "m1",
+        "m2",
+        "missed",
+        local_ids[2].as_str(),
+        local_ids[3].as_str(),
+    ];
+    eventually(|| message_ids(&client) == expected).await;
+    assert_eq!(*recorder.queue_counts.lock().unwrap(), [2, 1, 0]);
+}
+
+#[tokio::test]
+async fn test_attachments_are_sent_with_the_message() {
+    let gateway = MockGateway::default();
+    let addr = {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        listener.local_addr().unwrap()
+    };
+    let _running = serve_at(gateway.clone(), addr).await;
+
+    let client = CovenClient::new(format!("http://{}", addr));
+    client.refresh_agents_async().await.unwrap();
+
+    let too_big = Attachment {
+        filename: "huge.bin".to_string(),
+        mime_type: "application/octet-stream".to_string(),
+        data: vec![0; MAX_ATTACHMENT_BYTES as usize + 1],
+    };
+    let err = client
+        .send_message_with_attachments(AGENT_ID.to_string(), "look".to_string(), vec![too_big])
+        .unwrap_err();
+    assert!(matches!(err, CovenError::InvalidAttachment(_)));
+
+    let files = vec![
+        Attachment {
+            filename: "notes.md".to_string(),
+            mime_type: "text/markdown".to_string(),
+            data: b"# Notes".to_vec(),
+        },
+        Attachment {
+            filename: "data.csv".to_string(),
+            mime_type: "text/csv".to_string(),
+            data: b"a,b\n1,2".to_vec(),
+        },
+    ];
+    client
+        .send_message_with_attachments(AGENT_ID.to_string(), "look".to_string(), files)
+        .unwrap();
+    eventually(|| gateway.sent.lock().unwrap().len() == 1).await;
+
+    let sent = gateway.sent.lock().unwrap()[0].clone();
+    assert_eq!(sent.content, "look");
+    let names: Vec<&str> = sent
+        .attachments
+        .iter()
+        .map(|a| a.filename.as_str())
+        .collect();
+    assert_eq!(names, ["notes.md", "data.csv"]);
+    assert_eq!(sent.attachments[1].data, b"a,b\n1,2");
+}
@@ -2,5 +2,6 @@
 // ABOUTME: Re-exports from coven-ssh with additional helpers
 
 pub use coven_ssh::{
-    compute_fingerprint, default_agent_key_path, load_or_generate_key, SshAuthCredentials,
+    compute_fingerprint, default_agent_key_path, load_or_generate_key,
+    load_or_generate_key_with_passphrase, PassphraseSource, SshAuthCredentials,
 };
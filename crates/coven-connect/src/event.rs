@@ -1,6 +1,7 @@
 // ABOUTME: Event conversion utilities for gateway communication
 // ABOUTME: Converts between coven-core OutgoingEvent and coven-proto types
 
+use coven_core::backend::{BudgetAlertKind, BudgetReport};
 use coven_core::OutgoingEvent;
 use coven_proto::{agent_message, message_response::Event, AgentMessage, MessageResponse};
 
@@ -22,6 +23,36 @@ pub fn tool_state_to_proto(state: &str) -> i32 {
     }
 }
 
+/// Convert a budget report to the proto sent to the gateway.
+pub fn budget_to_proto(report: BudgetReport) -> coven_proto::BudgetStatus {
+    let status = report.status;
+    coven_proto::BudgetStatus {
+        day: status.day,
+        request_tokens: status.request_tokens as i64,
+        thread_tokens: status.thread_tokens as i64,
+        day_tokens: status.day_tokens as i64,
+        day_cost_usd: status.day_cost_usd,
+        max_tokens_per_request: status.max_tokens_per_request.map(|max| max as i64),
+        max_tokens_per_thread: status.max_tokens_per_thread.map(|max| max as i64),
+        max_tokens_per_day: status.max_tokens_per_day.map(|max| max as i64),
+        max_cost_usd_per_day: status.max_cost_usd_per_day,
+        alerts: report
+            .alerts
+            .into_iter()
+            .map(|alert| coven_proto::BudgetAlert {
+                kind: match alert.kind {
+                    BudgetAlertKind::Warning => "warning",
+                    BudgetAlertKind::Exceeded => "exceeded",
+                }
+                .to_string(),
+                limit: alert.limit.as_str().to_string(),
+                spent: alert.spent,
+                max: alert.max,
+            })
+            .collect(),
+    }
+}
+
 /// Convert an OutgoingEvent to an AgentMessage response.
 /// Handles file reading asynchronously with size limits.
 pub async fn convert_event_to_response(request_id: &str, event: OutgoingEvent) -> AgentMessage {
@@ -44,13 +75,19 @@ pub async fn convert_event_to_response(request_id: &str, event: OutgoingEvent) -
         }),
         OutgoingEvent::Done { full_response } => Event::Done(coven_proto::Done { full_response }),
         OutgoingEvent::Error(e) => Event::Error(e),
-        OutgoingEvent::ToolApprovalRequest { id, name, input } => {
-            Event::ToolApprovalRequest(coven_proto::ToolApprovalRequest {
-                id,
-                name,
-                input_json: input.to_string(),
-            })
-        }
+        // Sent as an error so the request ends; its text starts with BUDGET_EXCEEDED
+        OutgoingEvent::BudgetExceeded(exceeded) => Event::Error(exceeded.to_string()),
+        OutgoingEvent::ToolApprovalRequest {
+            id,
+            name,
+            input,
+            confirm_message,
+        } => Event::ToolApprovalRequest(coven_proto::ToolApprovalRequest {
+            id,
+            name,
+            input_json: input.to_string(),
+            confirm_message,
+        }),
         OutgoingEvent::File {
             path,
             filename,
@@ -116,6 +153,7 @@ pub async fn convert_event_to_response(request_id: &str, event: OutgoingEvent) -
                 detail,
             })
         }
+        OutgoingEvent::Budget(report) => Event::Budget(budget_to_proto(report)),
     };
 
     AgentMessage {
@@ -180,6 +218,70 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn test_convert_budget_event() {
+        use coven_core::backend::{BudgetAlert, BudgetLimit, BudgetStatus};
+
+        let report = BudgetReport {
+            status: BudgetStatus {
+                day: "2026-10-16".to_string(),
+                day_tokens: 1600,
+                max_tokens_per_day: Some(2000),
+                ..BudgetStatus::default()
+            },
+            alerts: vec![BudgetAlert {
+                kind: BudgetAlertKind::Warning,
+                limit: BudgetLimit::MaxTokensPerDay,
+                spent: 1600.0,
+                max: 2000.0,
+            }],
+        };
+        let msg = convert_event_to_response("req-5", OutgoingEvent::Budget(report)).await;
+
+        match msg.payload {
+            Some(agent_message::Payload::Response(resp)) => match resp.event {
+                Some(Event::Budget(budget)) => {
+                    assert_eq!(budget.day_tokens, 1600);
+                    assert_eq!(budget.max_tokens_per_day, Some(2000));
+                    assert_eq!(budget.max_cost_usd_per_day, None);
+                    assert_eq!(budget.alerts[0].kind, "warning");
+                    assert_eq!(budget.alerts[0].limit, "max_tokens_per_day");
+                }
+                other => panic!("Expected Budget event, got {:?}", other),
+            },
+            other => panic!("Expected Response payload, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_convert_tool_approval_request_event() {
+        let msg = convert_event_to_response(
+            "req-4",
+            OutgoingEvent::ToolApprovalRequest {
+                id: "tool-1".to_string(),
+                name: "delete_files".to_string(),
+                input: serde_json::json!({"paths": ["a", "b", "c"]}),
+                confirm_message: Some("This will delete 3 files".to_string()),
+            },
+        )
+        .await;
+
+        match msg.payload {
+            Some(agent_message::Payload::Response(resp)) => match resp.event {
+                Some(Event::ToolApprovalRequest(req)) => {
+                    assert_eq!(req.id, "tool-1");
+                    assert_eq!(req.name, "delete_files");
+                    assert_eq!(
+                        req.confirm_message.as_deref(),
+                        Some("This will delete 3 files")
+                    );
+                }
+                other => panic!("Expected ToolApprovalRequest event, got {:?}", other),
+            },
+            other => panic!("Expected Response payload, got {:?}", other),
+        }
+    }
+
     #[tokio::test]
     async fn test_convert_error_event() {
         let msg =
@@ -193,4 +295,35 @@ mod tests {
             other => panic!("Expected Response payload, got {:?}", other),
         }
     }
+
+    #[tokio::test]
+    async fn test_convert_budget_exceeded_event() {
+        use coven_core::backend::{BudgetAlert, BudgetExceeded, BudgetLimit, BudgetStatus};
+
+        let exceeded = BudgetExceeded {
+            alert: BudgetAlert {
+                kind: BudgetAlertKind::Exceeded,
+                limit: BudgetLimit::MaxTokensPerDay,
+                spent: 2000.0,
+                max: 2000.0,
+            },
+            next_call: 500.0,
+            status: BudgetStatus::default(),
+        };
+        let msg = convert_event_to_response("req-6", OutgoingEvent::BudgetExceeded(exceeded)).await;
+
+        match msg.payload {
+            Some(agent_message::Payload::Response(resp)) => match resp.event {
+                Some(Event::Error(s)) => {
+                    assert!(
+                        s.starts_with("BUDGET_EXCEEDED: max_tokens_per_day reached"),
+                        "{}",
+                        s
+                    )
+                }
+                other => panic!("Expected Error event, got {:?}", other),
+            },
+            other => panic!("Expected Response payload, got {:?}", other),
+        }
+    }
 }
@@ -1,9 +1,11 @@
 // ABOUTME: Shared gateway connection utilities for coven agents
 // ABOUTME: Provides SSH auth, event conversion, and common constants
 
+use coven_grpc::{create_channel, ChannelConfig};
 use coven_proto::{agent_message, AgentMessage, MessageResponse};
 use coven_ssh::{
-    compute_fingerprint, default_agent_key_path, load_or_generate_key, SshAuthCredentials,
+    compute_fingerprint, default_agent_key_path, load_or_generate_key_with_passphrase,
+    PassphraseSource, SshAuthCredentials,
 };
 use ssh_key::PrivateKey;
 use std::sync::Arc;
@@ -27,13 +29,16 @@ pub struct SshCredentials {
     pub key_path: std::path::PathBuf,
 }
 
-/// Load or generate SSH key for gateway authentication.
+/// Load or generate SSH key for gateway authentication, decrypting an
+/// encrypted key (or encrypting a new one) with the passphrase from `passphrase`.
 /// Returns the private key, fingerprint, and key path.
-pub fn load_ssh_credentials() -> anyhow::Result<SshCredentials> {
+pub fn load_ssh_credentials(
+    passphrase: Option<&PassphraseSource>,
+) -> anyhow::Result<SshCredentials> {
     let key_path = default_agent_key_path()
         .ok_or_else(|| anyhow::anyhow!("could not determine config directory for SSH key"))?;
 
-    let private_key = load_or_generate_key(&key_path)?;
+    let private_key = load_or_generate_key_with_passphrase(&key_path, passphrase)?;
     let fingerprint = compute_fingerprint(private_key.public_key())?;
 
     Ok(SshCredentials {
@@ -44,7 +49,8 @@ pub fn load_ssh_credentials() -> anyhow::Result<SshCredentials> {
 }
 
 /// Create an SSH auth interceptor for gRPC requests.
-/// This closure signs each request with the agent's SSH key.
+/// This closure signs each request with the agent's SSH key, and with the
+/// `otlp` feature also passes on the current trace context.
 pub fn create_ssh_interceptor(
     private_key: Arc<PrivateKey>,
 ) -> impl Fn(tonic::Request<()>) -> Result<tonic::Request<()>, tonic::Status> + Clone {
@@ -65,15 +71,15 @@ pub fn create_ssh_interceptor(
                 )));
             }
         }
+        #[cfg(feature = "otlp")]
+        coven_log::inject_context(req.metadata_mut());
         Ok(req)
     }
 }
 
 /// Connect to a gateway server and return the gRPC channel.
 pub async fn connect_to_gateway(server_addr: &str) -> anyhow::Result<Channel> {
-    let channel = Channel::from_shared(server_addr.to_string())?
-        .connect()
-        .await?;
+    let channel = create_channel(&ChannelConfig::new(server_addr)).await?;
     Ok(channel)
 }
 
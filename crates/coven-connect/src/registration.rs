@@ -2,9 +2,9 @@
 // ABOUTME: Uses coven-link JWT token to register new SSH fingerprints
 
 use anyhow::Result;
+use coven_grpc::{create_channel, ChannelConfig};
 use coven_proto::client_service_client::ClientServiceClient;
 use coven_proto::RegisterAgentRequest;
-use tonic::transport::Channel;
 use tonic::Code;
 
 /// Result of attempting self-registration with the gateway.
@@ -83,7 +83,10 @@ pub async fn try_self_register(
     fingerprint: &str,
     display_name: &str,
 ) -> Result<SelfRegisterResult> {
-    // Load token
+    // Load token, renewed first if it's about to expire
+    if let Err(e) = coven_link::refresh_token_if_needed().await {
+        tracing::warn!(error = %format!("{:#}", e), "Can't refresh token");
+    }
     let token = match load_link_token() {
         Some(t) => t,
         None => {
@@ -100,9 +103,7 @@ pub async fn try_self_register(
     tracing::info!("Attempting auto-registration with gateway");
 
     // Connect to ClientService with JWT auth
-    let channel = Channel::from_shared(server_addr.to_string())?
-        .connect()
-        .await?;
+    let channel = create_channel(&ChannelConfig::new(server_addr)).await?;
 
     let token_clone = token.clone();
     let jwt_interceptor = move |mut req: tonic::Request<()>| -> std::result::Result<tonic::Request<()>, tonic::Status> {
@@ -0,0 +1,680 @@
+// ABOUTME: Token and cost limits for the mux backend, per request, per thread and per UTC day.
+// ABOUTME: Spend is kept in SQLite so limits hold across restarts; prices come from a built-in table.
+
+use crate::config::{BudgetConfig, ModelPrice};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use sqlx::{Row, SqlitePool};
+
+/// Start of a `BudgetExceeded`'s text, which is how a refusal reaches the
+/// gateway and its clients
+pub const BUDGET_EXCEEDED: &str = "BUDGET_EXCEEDED";
+
+/// Share of a limit at which a warning is sent
+pub const BUDGET_WARNING_RATIO: f64 = 0.8;
+
+/// Cache reads cost this much of the input price, cache writes this much
+const CACHE_READ_MULTIPLIER: f64 = 0.1;
+const CACHE_WRITE_MULTIPLIER: f64 = 1.25;
+
+/// Anthropic list prices as (model name prefix, input, output) in USD per
+/// million tokens. The longest matching prefix wins.
+const BUILTIN_PRICES: &[(&str, f64, f64)] = &[
+    ("claude-opus-4-5", 5.0, 25.0),
+    ("claude-opus-4", 15.0, 75.0),
+    ("claude-3-opus", 15.0, 75.0),
+    ("claude-sonnet-4", 3.0, 15.0),
+    ("claude-3-7-sonnet", 3.0, 15.0),
+    ("claude-3-5-sonnet", 3.0, 15.0),
+    ("claude-haiku-4-5", 1.0, 5.0),
+    ("claude-3-5-haiku", 0.8, 4.0),
+    ("claude-3-haiku", 0.25, 1.25),
+];
+
+/// The price of `model`: from `overrides` if one of its prefixes matches,
+/// else from the built-in table. None for models neither knows.
+pub fn model_price(
+    model: &str,
+    overrides: &std::collections::HashMap<String, ModelPrice>,
+) -> Option<ModelPrice> {
+    let configured = overrides
+        .iter()
+        .filter(|(prefix, _)| model.starts_with(prefix.as_str()))
+        .max_by_key(|(prefix, _)| prefix.len())
+        .map(|(_, price)| *price);
+    configured.or_else(|| {
+        BUILTIN_PRICES
+            .iter()
+            .filter(|(prefix, _, _)| model.starts_with(prefix))
+            .max_by_key(|(prefix, _, _)| prefix.len())
+            .map(|&(_, input, output)| ModelPrice {
+                input,
+                output,
+                cache_read: None,
+                cache_write: None,
+            })
+    })
+}
+
+/// Tokens billed for one model call
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TokenCounts {
+    pub input: u64,
+    pub output: u64,
+    pub cache_read: u64,
+    pub cache_write: u64,
+}
+
+impl TokenCounts {
+    /// Every billed token; this is what token limits count
+    pub fn total(&self) -> u64 {
+        self.input + self.output + self.cache_read + self.cache_write
+    }
+
+    /// What these tokens cost at `price`, in USD
+    pub fn cost(&self, price: &ModelPrice) -> f64 {
+        let cache_read = price
+            .cache_read
+            .unwrap_or(price.input * CACHE_READ_MULTIPLIER);
+        let cache_write = price
+            .cache_write
+            .unwrap_or(price.input * CACHE_WRITE_MULTIPLIER);
+        (self.input as f64 * price.input
+            + self.output as f64 * price.output
+            + self.cache_read as f64 * cache_read
+            + self.cache_write as f64 * cache_write)
+            / 1_000_000.0
+    }
+}
+
+/// One of the `[mux.budget]` limits
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BudgetLimit {
+    MaxTokensPerRequest,
+    MaxTokensPerThread,
+    MaxTokensPerDay,
+    MaxCostUsdPerDay,
+}
+
+impl BudgetLimit {
+    /// The limit's config key
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::MaxTokensPerRequest => "max_tokens_per_request",
+            Self::MaxTokensPerThread => "max_tokens_per_thread",
+            Self::MaxTokensPerDay => "max_tokens_per_day",
+            Self::MaxCostUsdPerDay => "max_cost_usd_per_day",
+        }
+    }
+
+    /// `amount` of what this limit counts, for messages
+    fn describe(&self, amount: f64) -> String {
+        match self {
+            Self::MaxCostUsdPerDay => format!("${:.2}", amount),
+            _ => format!("{} tokens", amount as u64),
+        }
+    }
+}
+
+impl std::fmt::Display for BudgetLimit {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Why a budget report needs attention
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BudgetAlertKind {
+    /// The last call took spend past 80% of the limit
+    Warning,
+    /// The limit is reached, or the next call could go past it
+    Exceeded,
+}
+
+/// A limit that's nearly or fully used
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BudgetAlert {
+    pub kind: BudgetAlertKind,
+    pub limit: BudgetLimit,
+    pub spent: f64,
+    pub max: f64,
+}
+
+impl std::fmt::Display for BudgetAlert {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let what = match self.kind {
+            BudgetAlertKind::Warning => "nearly reached",
+            BudgetAlertKind::Exceeded => "reached",
+        };
+        write!(
+            f,
+            "{} {} ({} of {})",
+            self.limit,
+            what,
+            self.limit.describe(self.spent),
+            self.limit.describe(self.max)
+        )
+    }
+}
+
+/// Spend so far against each configured limit
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct BudgetStatus {
+    /// UTC date the day totals cover (YYYY-MM-DD)
+    pub day: String,
+    /// Tokens used by the current request
+    pub request_tokens: u64,
+    pub thread_tokens: u64,
+    pub day_tokens: u64,
+    pub day_cost_usd: f64,
+    pub max_tokens_per_request: Option<u64>,
+    pub max_tokens_per_thread: Option<u64>,
+    pub max_tokens_per_day: Option<u64>,
+    pub max_cost_usd_per_day: Option<f64>,
+}
+
+impl BudgetStatus {
+    /// Each configured limit with what's been spent against it
+    pub fn limits(&self) -> Vec<(BudgetLimit, f64, f64)> {
+        [
+            (
+                BudgetLimit::MaxTokensPerRequest,
+                self.request_tokens as f64,
+                self.max_tokens_per_request.map(|max| max as f64),
+            ),
+            (
+                BudgetLimit::MaxTokensPerThread,
+                self.thread_tokens as f64,
+                self.max_tokens_per_thread.map(|max| max as f64),
+            ),
+            (
+                BudgetLimit::MaxTokensPerDay,
+                self.day_tokens as f64,
+                self.max_tokens_per_day.map(|max| max as f64),
+            ),
+            (
+                BudgetLimit::MaxCostUsdPerDay,
+                self.day_cost_usd,
+                self.max_cost_usd_per_day,
+            ),
+        ]
+        .into_iter()
+        .filter_map(|(limit, spent, max)| max.map(|max| (limit, spent, max)))
+        .collect()
+    }
+
+    /// One line of spend against each set limit, for status displays
+    pub fn summary(&self) -> String {
+        let parts: Vec<String> = self
+            .limits()
+            .into_iter()
+            .map(|(limit, spent, max)| match limit {
+                BudgetLimit::MaxTokensPerRequest => {
+                    format!("request {} of {} tokens", spent as u64, max as u64)
+                }
+                BudgetLimit::MaxTokensPerThread => {
+                    format!("thread {} of {} tokens", spent as u64, max as u64)
+                }
+                BudgetLimit::MaxTokensPerDay => {
+                    format!("today {} of {} tokens", spent as u64, max as u64)
+                }
+                BudgetLimit::MaxCostUsdPerDay => format!("today ${:.2} of ${:.2}", spent, max),
+            })
+            .collect();
+        format!("Budget: {}", parts.join(", "))
+    }
+}
+
+/// Current spend, with any limits that need attention
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct BudgetReport {
+    pub status: BudgetStatus,
+    pub alerts: Vec<BudgetAlert>,
+}
+
+/// A model call refused because it could take spend past a limit
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BudgetExceeded {
+    /// The first limit the call could exceed, with what's spent against it
+    pub alert: BudgetAlert,
+    /// The most the call was estimated to add, in the limit's units
+    pub next_call: f64,
+    /// Spend when the call was refused
+    pub status: BudgetStatus,
+}
+
+impl std::fmt::Display for BudgetExceeded {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let alert = &self.alert;
+        write!(f, "{}: ", BUDGET_EXCEEDED)?;
+        if alert.spent >= alert.max {
+            write!(f, "{}", alert)?;
+        } else {
+            write!(
+                f,
+                "{} would be exceeded ({} of {} used, and the next call may use up to {})",
+                alert.limit,
+                alert.limit.describe(alert.spent),
+                alert.limit.describe(alert.max),
+                alert.limit.describe(self.next_call)
+            )?;
+        }
+        write!(
+            f,
+            ". Raise the limit under [mux.budget]{}.",
+            match alert.limit {
+                BudgetLimit::MaxTokensPerDay | BudgetLimit::MaxCostUsdPerDay =>
+                    " or wait for the next UTC day",
+                BudgetLimit::MaxTokensPerThread => " or start a new thread",
+                BudgetLimit::MaxTokensPerRequest => " or send a smaller task",
+            }
+        )
+    }
+}
+
+impl std::error::Error for BudgetExceeded {}
+
+/// Spend tracked against `[mux.budget]`. Each model call is checked before
+/// it's made against the most it could spend, so a runaway tool loop stops
+/// before the call that would take it over a limit.
+pub struct Budget {
+    config: BudgetConfig,
+    pool: SqlitePool,
+}
+
+impl Budget {
+    /// Track spend in `pool`, creating its table if needed
+    pub async fn open(pool: SqlitePool, config: BudgetConfig) -> Result<Self> {
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS budget_spend (
+                day TEXT NOT NULL,
+                thread_id TEXT NOT NULL,
+                tokens INTEGER NOT NULL,
+                cost_usd REAL NOT NULL,
+                PRIMARY KEY (day, thread_id)
+            )",
+        )
+        .execute(&pool)
+        .await?;
+        Ok(Self { config, pool })
+    }
+
+    /// Spend so far in `thread` and today, with `request_tokens` used by the
+    /// current request
+    pub async fn status(&self, thread: &str, request_tokens: u64) -> Result<BudgetStatus> {
+        let day = today();
+        let row = sqlx::query(
+            "SELECT COALESCE(SUM(tokens), 0) AS tokens, COALESCE(SUM(cost_usd), 0.0) AS cost
+             FROM budget_spend WHERE day = ?",
+        )
+        .bind(&day)
+        .fetch_one(&self.pool)
+        .await?;
+        let day_tokens: i64 = row.get("tokens");
+        let day_cost_usd: f64 = row.get("cost");
+        let thread_tokens: i64 = sqlx::query_scalar(
+            "SELECT COALESCE(SUM(tokens), 0) FROM budget_spend WHERE thread_id = ?",
+        )
+        .bind(thread)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(BudgetStatus {
+            day,
+            request_tokens,
+            thread_tokens: thread_tokens as u64,
+            day_tokens: day_tokens as u64,
+            day_cost_usd,
+            max_tokens_per_request: self.config.max_tokens_per_request,
+            max_tokens_per_thread: self.config.max_tokens_per_thread,
+            max_tokens_per_day: self.config.max_tokens_per_day,
+            max_cost_usd_per_day: self.config.max_cost_usd_per_day,
+        })
+    }
+
+    /// Whether a call on `model` that could use up to `next_call` may be
+    /// made for a request that has used `request_tokens` so far. Returns
+    /// the first limit it could take spend past, or one already reached.
+    pub async fn check(
+        &self,
+        thread: &str,
+        request_tokens: u64,
+        model: &str,
+        next_call: TokenCounts,
+    ) -> Result<Option<BudgetExceeded>> {
+        let status = self.status(thread, request_tokens).await?;
+        let next_cost = model_price(model, &self.config.prices)
+            .map(|price| next_call.cost(&price))
+            .unwrap_or(0.0);
+        let exceeded = status
+            .limits()
+            .into_iter()
+            .map(|(limit, spent, max)| {
+                let next = match limit {
+                    BudgetLimit::MaxCostUsdPerDay => next_cost,
+                    _ => next_call.total() as f64,
+                };
+                (limit, spent, max, next)
+            })
+            .find(|(_, spent, max, next)| spent >= max || spent + next > *max)
+            .map(|(limit, spent, max, next)| BudgetExceeded {
+                alert: BudgetAlert {
+                    kind: BudgetAlertKind::Exceeded,
+                    limit,
+                    spent,
+                    max,
+                },
+                next_call: next,
+                status: status.clone(),
+            });
+        Ok(exceeded)
+    }
+
+    /// Add one call's `tokens` on `model` to the spend of `thread` and
+    /// today. `request_tokens` is what the request used before this call.
+    /// The report warns about each limit this call took past 80%.
+    pub async fn record(
+        &self,
+        thread: &str,
+        model: &str,
+        tokens: TokenCounts,
+        request_tokens: u64,
+    ) -> Result<BudgetReport> {
+        let before = self.status(thread, request_tokens).await?;
+
+        let cost = match model_price(model, &self.config.prices) {
+            Some(price) => tokens.cost(&price),
+            None => {
+                if self.config.max_cost_usd_per_day.is_some() {
+                    tracing::warn!(
+                        model = %model,
+                        "No price known for model, so its cost isn't counted; add it under [mux.budget.prices]"
+                    );
+                }
+                0.0
+            }
+        };
+        sqlx::query(
+            "INSERT INTO budget_spend (day, thread_id, tokens, cost_usd) VALUES (?, ?, ?, ?)
+             ON CONFLICT(day, thread_id) DO UPDATE SET
+                tokens = tokens + excluded.tokens,
+                cost_usd = cost_usd + excluded.cost_usd",
+        )
+        .bind(&before.day)
+        .bind(thread)
+        .bind(tokens.total() as i64)
+        .bind(cost)
+        .execute(&self.pool)
+        .await?;
+
+        let after = self.status(thread, request_tokens + tokens.total()).await?;
+        let was: Vec<f64> = before.limits().iter().map(|(_, spent, _)| *spent).collect();
+        let alerts = after
+            .limits()
+            .into_iter()
+            .zip(was)
+            .filter(|((_, spent, max), was)| {
+                let threshold = max * BUDGET_WARNING_RATIO;
+                *was < threshold && *spent >= threshold
+            })
+            .map(|((limit, spent, max), _)| BudgetAlert {
+                kind: BudgetAlertKind::Warning,
+                limit,
+                spent,
+                max,
+            })
+            .collect();
+        Ok(BudgetReport {
+            status: after,
+            alerts,
+        })
+    }
+}
+
+/// The current UTC date, which day totals are kept by
+fn today() -> String {
+    chrono::Utc::now().date_naive().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    /// A made-up model at $10 in, $100 out per million tokens, so costs
+    /// are easy to read
+    const FAKE_MODEL: &str = "fake-model-1";
+
+    async fn budget(config: BudgetConfig) -> (Budget, tempfile::TempDir) {
+        let dir = tempfile::tempdir().unwrap();
+        let url = format!("sqlite:{}?mode=rwc", dir.path().join("budget.db").display());
+        let pool = SqlitePool::connect(&url).await.unwrap();
+        let config = BudgetConfig {
+            prices: HashMap::from([(
+                "fake-model".to_string(),
+                ModelPrice {
+                    input: 10.0,
+                    output: 100.0,
+                    cache_read: None,
+                    cache_write: None,
+                },
+            )]),
+            ..config
+        };
+        (Budget::open(pool, config).await.unwrap(), dir)
+    }
+
+    fn tokens(input: u64, output: u64) -> TokenCounts {
+        TokenCounts {
+            input,
+            output,
+            ..TokenCounts::default()
+        }
+    }
+
+    #[test]
+    fn test_prices_use_longest_prefix() {
+        let none = HashMap::new();
+        assert_eq!(
+            model_price("claude-opus-4-5-20251101", &none).map(|p| p.input),
+            Some(5.0)
+        );
+        assert_eq!(
+            model_price("claude-opus-4-1-20250805", &none).map(|p| p.input),
+            Some(15.0)
+        );
+        assert_eq!(model_price("gpt-5", &none), None);
+
+        let price = model_price("claude-sonnet-4-20250514", &none).unwrap();
+        let cost = TokenCounts {
+            input: 1_000_000,
+            output: 1_000_000,
+            cache_read: 1_000_000,
+            cache_write: 1_000_000,
+        }
+        .cost(&price);
+        assert!((cost - (3.0 + 15.0 + 0.3 + 3.75)).abs() < 1e-9);
+    }
+
+    #[tokio::test]
+    async fn test_spend_accumulates_per_thread_and_day() {
+        let (budget, _dir) = budget(BudgetConfig {
+            max_cost_usd_per_day: Some(100.0),
+            ..BudgetConfig::default()
+        })
+        .await;
+
+        budget
+            .record("t1", FAKE_MODEL, tokens(1000, 100), 0)
+            .await
+            .unwrap();
+        let report = budget
+            .record("t1", FAKE_MODEL, tokens(2000, 200), 1100)
+            .await
+            .unwrap();
+        budget
+            .record("t2", FAKE_MODEL, tokens(500, 0), 0)
+            .await
+            .unwrap();
+
+        assert_eq!(report.status.request_tokens, 3300);
+        let status = budget.status("t1", 0).await.unwrap();
+        assert_eq!(status.thread_tokens, 3300);
+        assert_eq!(status.day_tokens, 3800);
+        // 3500 in at $10/M plus 300 out at $100/M
+        assert!((status.day_cost_usd - 0.065).abs() < 1e-9);
+        assert_eq!(budget.status("t2", 0).await.unwrap().thread_tokens, 500);
+    }
+
+    #[tokio::test]
+    async fn test_warns_once_when_crossing_80_percent() {
+        let (budget, _dir) = budget(BudgetConfig {
+            max_tokens_per_thread: Some(1000),
+            max_cost_usd_per_day: Some(1.0),
+            ..BudgetConfig::default()
+        })
+        .await;
+
+        let report = budget
+            .record("t", FAKE_MODEL, tokens(700, 0), 0)
+            .await
+            .unwrap();
+        assert!(report.alerts.is_empty());
+
+        let report = budget
+            .record("t", FAKE_MODEL, tokens(100, 0), 700)
+            .await
+            .unwrap();
+        assert_eq!(
+            report.alerts,
+            vec![BudgetAlert {
+                kind: BudgetAlertKind::Warning,
+                limit: BudgetLimit::MaxTokensPerThread,
+                spent: 800.0,
+                max: 1000.0,
+            }]
+        );
+        assert!(report
+            .alerts
+            .iter()
+            .all(|alert| alert.kind == BudgetAlertKind::Warning));
+
+        // Already past 80%, so no second warning
+        let report = budget
+            .record("t", FAKE_MODEL, tokens(50, 0), 800)
+            .await
+            .unwrap();
+        assert!(report.alerts.is_empty());
+
+        // 8000 output tokens cost $0.80 and cross the daily cost warning
+        let report = budget
+            .record("other", FAKE_MODEL, tokens(0, 8000), 0)
+            .await
+            .unwrap();
+        assert!(report
+            .alerts
+            .iter()
+            .any(|alert| alert.limit == BudgetLimit::MaxCostUsdPerDay));
+    }
+
+    #[tokio::test]
+    async fn test_check_refuses_calls_that_could_exceed_a_limit() {
+        let (budget, _dir) = budget(BudgetConfig {
+            max_tokens_per_request: Some(5000),
+            max_tokens_per_day: Some(2000),
+            ..BudgetConfig::default()
+        })
+        .await;
+
+        let small = tokens(100, 100);
+        assert_eq!(budget.check("t", 0, FAKE_MODEL, small).await.unwrap(), None);
+        budget
+            .record("t", FAKE_MODEL, tokens(1500, 300), 0)
+            .await
+            .unwrap();
+
+        // The day limit applies to every thread: a call that fits is made,
+        // one that could go past the limit isn't
+        assert_eq!(
+            budget.check("another", 0, FAKE_MODEL, small).await.unwrap(),
+            None
+        );
+        let refused = budget
+            .check("another", 0, FAKE_MODEL, tokens(100, 200))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(refused.alert.limit, BudgetLimit::MaxTokensPerDay);
+        assert_eq!(refused.alert.spent, 1800.0);
+        assert_eq!(refused.next_call, 300.0);
+        let text = refused.to_string();
+        assert!(
+            text.starts_with("BUDGET_EXCEEDED: max_tokens_per_day would be exceeded"),
+            "{}",
+            text
+        );
+        assert!(text.contains("1800 tokens of 2000 tokens used"), "{}", text);
+
+        assert_eq!(
+            refused.status.summary(),
+            "Budget: request 0 of 5000 tokens, today 1800 of 2000 tokens"
+        );
+
+        // Request tokens come from the caller, and a reached limit refuses
+        // even an empty call
+        let refused = budget
+            .check("another", 5000, FAKE_MODEL, TokenCounts::default())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(refused.alert.limit, BudgetLimit::MaxTokensPerRequest);
+        assert!(refused
+            .to_string()
+            .starts_with("BUDGET_EXCEEDED: max_tokens_per_request reached"));
+    }
+
+    #[tokio::test]
+    async fn test_check_prices_the_next_call() {
+        let (budget, _dir) = budget(BudgetConfig {
+            max_cost_usd_per_day: Some(1.0),
+            ..BudgetConfig::default()
+        })
+        .await;
+
+        // $0.90 spent; 500 more output tokens would cost $0.05, 2000 $0.20
+        budget
+            .record("t", FAKE_MODEL, tokens(0, 9000), 0)
+            .await
+            .unwrap();
+        assert_eq!(
+            budget
+                .check("t", 0, FAKE_MODEL, tokens(0, 500))
+                .await
+                .unwrap(),
+            None
+        );
+        let refused = budget
+            .check("t", 0, FAKE_MODEL, tokens(0, 2000))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(refused.alert.limit, BudgetLimit::MaxCostUsdPerDay);
+        assert!((refused.next_call - 0.2).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_budget_config_enabled_by_any_limit() {
+        assert!(!BudgetConfig::default().is_enabled());
+        let config: BudgetConfig = toml::from_str(
+            "max_cost_usd_per_day = 50.0\n[prices]\n\"claude-sonnet-4\" = { input = 2.0, output = 10.0 }\n",
+        )
+        .unwrap();
+        assert!(config.is_enabled());
+        assert_eq!(
+            model_price("claude-sonnet-4-5", &config.prices).map(|p| p.output),
+            Some(10.0)
+        );
+    }
+}
@@ -2,14 +2,24 @@
 // ABOUTME: Parses streaming JSONL from stdout, emits BackendEvents
 
 use super::{Backend, BackendEvent};
+use crate::types::RequestOverrides;
 use anyhow::{Context, Result};
 use async_trait::async_trait;
 use futures::stream::BoxStream;
 use serde_json::Value;
-use std::path::PathBuf;
+use std::collections::{HashMap, VecDeque};
+use std::path::{Path, PathBuf};
+use std::process::ExitStatus;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 use tokio::io::{AsyncBufReadExt, BufReader};
 use tokio::process::{Child, Command as ProcessCommand};
-use tokio::sync::mpsc;
+use tokio::sync::{mpsc, Notify, OwnedMutexGuard};
+use tokio::task::AbortHandle;
+
+/// Claude CLI subprocesses an agent runs at once unless configured
+/// otherwise, matching how many messages an agent takes at once
+pub const DEFAULT_CLI_POOL_SIZE: usize = 8;
 
 /// Configuration for the Direct CLI backend
 #[derive(Debug, Clone)]
@@ -23,6 +33,13 @@ pub struct DirectCliConfig {
     /// Gateway MCP endpoint URL for pack tools (e.g., "http://localhost:8080/mcp?token=xxx")
     /// Registered via `claude mcp add` so the CLI subprocess discovers it via Streamable HTTP
     pub mcp_endpoint: Option<String>,
+    /// How quickly the CLI is respawned after it crashes
+    pub restart: CliRestartPolicy,
+    /// Claude CLI subprocesses that may run at once; requests beyond this
+    /// wait for one to finish. With more than one, each runs in its own
+    /// directory under `working_dir/.coven/cli`, with `working_dir` added
+    /// to what it may touch, and keeps the sessions it starts.
+    pub pool_size: usize,
 }
 
 impl Default for DirectCliConfig {
@@ -32,7 +49,239 @@ impl Default for DirectCliConfig {
             working_dir: std::env::current_dir().unwrap_or_else(|_| PathBuf::from(".")),
             timeout_secs: 300,
             mcp_endpoint: None,
+            restart: CliRestartPolicy::default(),
+            pool_size: DEFAULT_CLI_POOL_SIZE,
+        }
+    }
+}
+
+/// Limits on respawning the Claude CLI after it exits unexpectedly. A crash
+/// ends the session; the next request starts a fresh one after a backoff
+/// that doubles with each crash, and once `max_restarts` crashes fall inside
+/// `window` requests are refused until the oldest ages out.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CliRestartPolicy {
+    /// Crashes tolerated within `window` before requests are refused
+    pub max_restarts: u32,
+    /// How far back crashes count against `max_restarts`
+    pub window: Duration,
+    /// Wait before respawning after a single crash
+    pub initial_backoff: Duration,
+    /// Longest wait before respawning
+    pub max_backoff: Duration,
+}
+
+impl Default for CliRestartPolicy {
+    fn default() -> Self {
+        Self {
+            max_restarts: 5,
+            window: Duration::from_secs(300),
+            initial_backoff: Duration::from_secs(1),
+            max_backoff: Duration::from_secs(30),
+        }
+    }
+}
+
+/// Recent crashes of one agent's CLI, used to pace respawns
+#[derive(Debug, Default)]
+struct CrashHistory {
+    crashes: VecDeque<Instant>,
+}
+
+impl CrashHistory {
+    fn record(&mut self, at: Instant) {
+        self.crashes.push_back(at);
+    }
+
+    /// How long to wait before spawning the CLI again, or an error message
+    /// when it has crashed too often to try.
+    fn delay_before_spawn(
+        &mut self,
+        policy: &CliRestartPolicy,
+        now: Instant,
+    ) -> std::result::Result<Duration, String> {
+        while let Some(&oldest) = self.crashes.front() {
+            if now.saturating_duration_since(oldest) >= policy.window {
+                self.crashes.pop_front();
+            } else {
+                break;
+            }
         }
+
+        let (Some(&oldest), Some(&last)) = (self.crashes.front(), self.crashes.back()) else {
+            return Ok(Duration::ZERO);
+        };
+        let count = self.crashes.len() as u32;
+        if count >= policy.max_restarts {
+            let retry_in = policy
+                .window
+                .saturating_sub(now.saturating_duration_since(oldest));
+            return Err(format!(
+                "Claude CLI crashed {} times in the last {}s; not restarting it for another {}s",
+                count,
+                policy.window.as_secs(),
+                retry_in.as_secs().max(1)
+            ));
+        }
+
+        let backoff = policy
+            .initial_backoff
+            .saturating_mul(2u32.saturating_pow(count - 1))
+            .min(policy.max_backoff);
+        Ok(backoff.saturating_sub(now.saturating_duration_since(last)))
+    }
+}
+
+/// Subprocess slots for one agent, each with its own working directory
+/// when there's more than one. The CLI keeps a session with the directory
+/// it ran in, so a session's requests check out the slot that started it,
+/// queueing while it's busy; a new session takes the first slot idle.
+#[derive(Debug)]
+struct CliPool {
+    slots: Vec<CliSlot>,
+    /// Slot of each session seen since startup
+    sessions: Mutex<HashMap<String, usize>>,
+    /// Woken whenever a slot is returned
+    returned: Notify,
+}
+
+#[derive(Debug)]
+struct CliSlot {
+    dir: PathBuf,
+    /// Held while the slot's subprocess runs
+    busy: Arc<tokio::sync::Mutex<()>>,
+}
+
+/// A checked-out slot; dropping it returns the slot to the pool
+struct CliLease {
+    pool: Arc<CliPool>,
+    slot: usize,
+    guard: Option<OwnedMutexGuard<()>>,
+}
+
+impl CliLease {
+    /// Directory the slot's subprocess runs in
+    fn dir(&self) -> &Path {
+        &self.pool.slots[self.slot].dir
+    }
+}
+
+impl CliPool {
+    fn new(size: usize, working_dir: &Path) -> Self {
+        let size = size.max(1);
+        let slots = (0..size)
+            .map(|n| CliSlot {
+                dir: if size == 1 {
+                    working_dir.to_path_buf()
+                } else {
+                    working_dir.join(".coven").join("cli").join(n.to_string())
+                },
+                busy: Arc::new(tokio::sync::Mutex::new(())),
+            })
+            .collect();
+        Self {
+            slots,
+            sessions: Mutex::new(HashMap::new()),
+            returned: Notify::new(),
+        }
+    }
+
+    /// Wait for `session_id`'s slot, or any slot for a new session, and
+    /// take it
+    async fn checkout(self: &Arc<Self>, session_id: &str, is_new_session: bool) -> CliLease {
+        let pinned = if is_new_session {
+            None
+        } else {
+            self.slot_of(session_id)
+        };
+        if let Some(slot) = pinned {
+            let guard = self.slots[slot].busy.clone().lock_owned().await;
+            return self.lease(slot, guard);
+        }
+
+        loop {
+            // Registered before looking, so a slot returned meanwhile wakes us
+            let returned = self.returned.notified();
+            tokio::pin!(returned);
+            returned.as_mut().enable();
+            let idle = self.slots.iter().enumerate().find_map(|(n, slot)| {
+                slot.busy
+                    .clone()
+                    .try_lock_owned()
+                    .ok()
+                    .map(|guard| (n, guard))
+            });
+            if let Some((slot, guard)) = idle {
+                self.pin(session_id, slot);
+                return self.lease(slot, guard);
+            }
+            returned.await;
+        }
+    }
+
+    fn lease(self: &Arc<Self>, slot: usize, guard: OwnedMutexGuard<()>) -> CliLease {
+        CliLease {
+            pool: self.clone(),
+            slot,
+            guard: Some(guard),
+        }
+    }
+
+    /// The slot that started `session_id`, if known. Sessions started
+    /// before a restart are found by the marker their slot left.
+    fn slot_of(&self, session_id: &str) -> Option<usize> {
+        if self.slots.len() == 1 {
+            return Some(0);
+        }
+        if let Some(slot) = self.sessions.lock().ok()?.get(session_id) {
+            return Some(*slot);
+        }
+        let slot = self
+            .slots
+            .iter()
+            .position(|slot| session_marker(&slot.dir, session_id).is_some_and(|p| p.exists()))?;
+        self.sessions
+            .lock()
+            .ok()?
+            .insert(session_id.to_string(), slot);
+        Some(slot)
+    }
+
+    /// Remember that `slot` runs `session_id`
+    fn pin(&self, session_id: &str, slot: usize) {
+        if self.slots.len() == 1 {
+            return;
+        }
+        if let Ok(mut sessions) = self.sessions.lock() {
+            sessions.insert(session_id.to_string(), slot);
+        }
+        if let Some(marker) = session_marker(&self.slots[slot].dir, session_id) {
+            let written = marker
+                .parent()
+                .map_or(Ok(()), std::fs::create_dir_all)
+                .and_then(|_| std::fs::write(&marker, b""));
+            if let Err(e) = written {
+                tracing::warn!(path = %marker.display(), error = %e, "Failed to record CLI session slot");
+            }
+        }
+    }
+}
+
+/// File marking that the slot in `dir` started `session_id`; None for IDs
+/// that aren't safe as file names
+fn session_marker(dir: &Path, session_id: &str) -> Option<PathBuf> {
+    let safe = !session_id.is_empty()
+        && session_id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_'));
+    safe.then(|| dir.join(".sessions").join(session_id))
+}
+
+impl Drop for CliLease {
+    fn drop(&mut self) {
+        // Released before waking the queue, so a woken request finds it idle
+        self.guard.take();
+        self.pool.returned.notify_waiters();
     }
 }
 
@@ -40,13 +289,22 @@ pub struct DirectCliBackend {
     config: DirectCliConfig,
     /// MCP endpoint can be set after construction (when token is received from gateway)
     mcp_endpoint_override: std::sync::RwLock<Option<String>>,
+    /// Crashes shared across this agent's requests, for restart pacing
+    crashes: Arc<Mutex<CrashHistory>>,
+    /// Limits how many CLI subprocesses run at once
+    pool: Arc<CliPool>,
+    /// Request tasks, aborted on drop so their subprocesses are killed
+    tasks: Mutex<Vec<AbortHandle>>,
 }
 
 impl DirectCliBackend {
     pub fn new(config: DirectCliConfig) -> Self {
         Self {
+            pool: Arc::new(CliPool::new(config.pool_size, &config.working_dir)),
             config,
             mcp_endpoint_override: std::sync::RwLock::new(None),
+            crashes: Arc::new(Mutex::new(CrashHistory::default())),
+            tasks: Mutex::new(Vec::new()),
         }
     }
 
@@ -68,6 +326,18 @@ impl DirectCliBackend {
     }
 }
 
+impl Drop for DirectCliBackend {
+    fn drop(&mut self) {
+        // Each task owns its subprocess, spawned with kill_on_drop, so
+        // aborting the task kills the CLI too
+        if let Ok(tasks) = self.tasks.lock() {
+            for task in tasks.iter() {
+                task.abort();
+            }
+        }
+    }
+}
+
 #[async_trait]
 impl Backend for DirectCliBackend {
     fn name(&self) -> &'static str {
@@ -79,23 +349,76 @@ impl Backend for DirectCliBackend {
         session_id: &str,
         message: &str,
         is_new_session: bool,
+    ) -> Result<BoxStream<'static, BackendEvent>> {
+        self.send_with_overrides(
+            session_id,
+            message,
+            is_new_session,
+            &RequestOverrides::default(),
+        )
+        .await
+    }
+
+    /// The CLI accepts a model per invocation; max_tokens has no CLI flag
+    /// and is ignored.
+    async fn send_with_overrides(
+        &self,
+        session_id: &str,
+        message: &str,
+        is_new_session: bool,
+        overrides: &RequestOverrides,
     ) -> Result<BoxStream<'static, BackendEvent>> {
         let config = self.config.clone();
+        let model = overrides.model.clone();
         let mcp_endpoint = self.effective_mcp_endpoint();
         let session_id = session_id.to_string();
         let message = message.to_string();
+        let crashes = self.crashes.clone();
+        let pool = self.pool.clone();
 
         let (tx, rx) = mpsc::channel::<BackendEvent>(100);
         let timeout_duration = std::time::Duration::from_secs(config.timeout_secs);
 
-        tokio::spawn(async move {
+        let task = tokio::spawn(async move {
+            // Queue for a subprocess slot; it's held until this task ends
+            let lease = pool.checkout(&session_id, is_new_session).await;
+            tracing::debug!(slot = lease.slot, "Checked out Claude CLI slot");
+
+            // Pace respawns after crashes, refusing outright if crash-looping
+            let delay = crashes
+                .lock()
+                .map(|mut history| history.delay_before_spawn(&config.restart, Instant::now()))
+                .unwrap_or(Ok(Duration::ZERO));
+            match delay {
+                Ok(delay) if !delay.is_zero() => {
+                    tracing::info!(
+                        delay_ms = delay.as_millis() as u64,
+                        "Waiting before respawning Claude CLI"
+                    );
+                    tokio::time::sleep(delay).await;
+                }
+                Ok(_) => {}
+                Err(message) => {
+                    tracing::error!("{}", message);
+                    let _ = tx.send(BackendEvent::Error(message)).await;
+                    let _ = tx
+                        .send(BackendEvent::Done {
+                            full_response: String::new(),
+                        })
+                        .await;
+                    return;
+                }
+            }
+
             // Spawn the child process first so we have a handle to kill on timeout
             let child_result = spawn_cli_process(
                 &config,
+                lease.dir(),
                 &session_id,
                 &message,
                 is_new_session,
                 mcp_endpoint.as_deref(),
+                model.as_deref(),
             )
             .await;
 
@@ -122,7 +445,27 @@ impl Backend for DirectCliBackend {
                     .await;
 
             match result {
-                Ok(Ok(())) => {}
+                Ok(Ok(CliOutcome::Completed)) => {}
+                Ok(Ok(CliOutcome::Crashed(status))) => {
+                    tracing::error!(%status, "Claude CLI exited unexpectedly");
+                    if let Ok(mut history) = crashes.lock() {
+                        history.record(Instant::now());
+                    }
+                    // The session died with the process; the client should
+                    // know earlier context is gone before the next message
+                    let _ = tx.send(BackendEvent::SessionOrphaned).await;
+                    let _ = tx
+                        .send(BackendEvent::Error(format!(
+                            "Claude CLI exited unexpectedly ({}); the next message starts a fresh session",
+                            status
+                        )))
+                        .await;
+                    let _ = tx
+                        .send(BackendEvent::Done {
+                            full_response: String::new(),
+                        })
+                        .await;
+                }
                 Ok(Err(e)) => {
                     tracing::error!(error = %e, "Direct CLI prompt failed");
                     let _ = tx.send(BackendEvent::Error(e.to_string())).await;
@@ -152,6 +495,10 @@ impl Backend for DirectCliBackend {
                 }
             }
         });
+        if let Ok(mut tasks) = self.tasks.lock() {
+            tasks.retain(|task| !task.is_finished());
+            tasks.push(task.abort_handle());
+        }
 
         Ok(Box::pin(tokio_stream::wrappers::ReceiverStream::new(rx)))
     }
@@ -164,10 +511,12 @@ impl Backend for DirectCliBackend {
 /// caused by other MCP servers configured in ~/.claude.
 async fn spawn_cli_process(
     config: &DirectCliConfig,
+    cwd: &Path,
     session_id: &str,
     text: &str,
     is_new_session: bool,
     mcp_endpoint: Option<&str>,
+    model: Option<&str>,
 ) -> Result<Child> {
     let mut args = vec![
         "--print".to_string(),
@@ -199,6 +548,22 @@ async fn spawn_cli_process(
         args.push(mcp_config_str);
     }
 
+    if let Some(model) = model {
+        args.push("--model".to_string());
+        args.push(model.to_string());
+    }
+
+    // A pooled subprocess runs in its own directory, but works on the agent's
+    if cwd != config.working_dir {
+        args.push("--add-dir".to_string());
+        args.push(config.working_dir.display().to_string());
+        args.push("--append-system-prompt".to_string());
+        args.push(format!(
+            "The project you are working on is in {}; use that directory for files and commands.",
+            config.working_dir.display()
+        ));
+    }
+
     // Only use --resume for existing sessions, not new ones
     if !is_new_session {
         args.push("--resume".to_string());
@@ -213,11 +578,13 @@ async fn spawn_cli_process(
         args.push(text.to_string());
     }
 
-    tracing::debug!(args = ?args, cwd = %config.working_dir.display(), use_stdin = use_stdin, "Spawning Claude CLI");
+    tracing::debug!(args = ?args, cwd = %cwd.display(), use_stdin = use_stdin, "Spawning Claude CLI");
 
+    std::fs::create_dir_all(cwd)
+        .with_context(|| format!("creating CLI working directory: {}", cwd.display()))?;
     let mut child = ProcessCommand::new(&config.binary)
         .args(&args)
-        .current_dir(&config.working_dir)
+        .current_dir(cwd)
         // Use piped stdin when MCP is enabled, otherwise null to prevent hangs
         .stdin(if use_stdin {
             std::process::Stdio::piped()
@@ -226,6 +593,7 @@ async fn spawn_cli_process(
         })
         .stdout(std::process::Stdio::piped())
         .stderr(std::process::Stdio::piped())
+        .kill_on_drop(true)
         .spawn()
         .context("Failed to spawn Claude CLI")?;
 
@@ -244,8 +612,20 @@ async fn spawn_cli_process(
     Ok(child)
 }
 
+/// How a Claude CLI run ended
+#[derive(Debug)]
+enum CliOutcome {
+    /// Output was handled, including error results the CLI reported itself
+    Completed,
+    /// The process exited with a failure before reporting a result
+    Crashed(ExitStatus),
+}
+
 /// Process output from a spawned Claude CLI process
-async fn process_cli_output(child: &mut Child, event_tx: mpsc::Sender<BackendEvent>) -> Result<()> {
+async fn process_cli_output(
+    child: &mut Child,
+    event_tx: mpsc::Sender<BackendEvent>,
+) -> Result<CliOutcome> {
     let stdout = child.stdout.take().context("Failed to capture stdout")?;
     let stderr = child.stderr.take().context("Failed to capture stderr")?;
 
@@ -275,6 +655,7 @@ async fn process_cli_output(child: &mut Child, event_tx: mpsc::Sender<BackendEve
     let reader = BufReader::new(stdout);
     let mut lines = reader.lines();
     let mut accumulated_text = String::new();
+    let mut saw_result = false;
 
     while let Ok(Some(line)) = lines.next_line().await {
         if line.is_empty() {
@@ -283,11 +664,12 @@ async fn process_cli_output(child: &mut Child, event_tx: mpsc::Sender<BackendEve
 
         match serde_json::from_str::<Value>(&line) {
             Ok(json) => {
+                saw_result |= json.get("type").and_then(|t| t.as_str()) == Some("result");
                 if let Some(events) = parse_cli_event(&json, &mut accumulated_text) {
                     for event in events {
                         if event_tx.send(event).await.is_err() {
                             tracing::debug!("Event receiver closed, stopping stream");
-                            return Ok(());
+                            return Ok(CliOutcome::Completed);
                         }
                     }
                 }
@@ -324,10 +706,13 @@ async fn process_cli_output(child: &mut Child, event_tx: mpsc::Sender<BackendEve
             })
             .await;
         // Don't check exit status - we know why it failed
-        return Ok(());
+        return Ok(CliOutcome::Completed);
     }
 
     let status = child.wait().await?;
+    if !status.success() && !saw_result {
+        return Ok(CliOutcome::Crashed(status));
+    }
     if !status.success() {
         let _ = event_tx
             .send(BackendEvent::Error(format!(
@@ -343,7 +728,7 @@ async fn process_cli_output(child: &mut Child, event_tx: mpsc::Sender<BackendEve
             .await;
     }
 
-    Ok(())
+    Ok(CliOutcome::Completed)
 }
 
 /// Truncate a string to `max_chars` characters, appending "...[truncated]" if it exceeds the limit.
@@ -1069,4 +1454,318 @@ mod tests {
             "accumulated text should be empty after result"
         );
     }
+
+    // ── Restart pacing after CLI crashes ─────────────────────────────────
+
+    fn fast_policy(max_restarts: u32) -> CliRestartPolicy {
+        CliRestartPolicy {
+            max_restarts,
+            window: Duration::from_secs(60),
+            initial_backoff: Duration::from_millis(10),
+            max_backoff: Duration::from_millis(40),
+        }
+    }
+
+    #[test]
+    fn crash_history_backs_off_then_refuses() {
+        let policy = CliRestartPolicy {
+            max_restarts: 3,
+            window: Duration::from_secs(60),
+            initial_backoff: Duration::from_secs(1),
+            max_backoff: Duration::from_secs(3),
+        };
+        let start = Instant::now();
+        let mut history = CrashHistory::default();
+        assert_eq!(
+            history.delay_before_spawn(&policy, start),
+            Ok(Duration::ZERO)
+        );
+
+        history.record(start);
+        assert_eq!(
+            history.delay_before_spawn(&policy, start),
+            Ok(Duration::from_secs(1))
+        );
+        // Time already waited counts toward the backoff
+        assert_eq!(
+            history.delay_before_spawn(&policy, start + Duration::from_secs(5)),
+            Ok(Duration::ZERO)
+        );
+
+        history.record(start + Duration::from_secs(5));
+        assert_eq!(
+            history.delay_before_spawn(&policy, start + Duration::from_secs(5)),
+            Ok(Duration::from_secs(2))
+        );
+
+        history.record(start + Duration::from_secs(10));
+        let refused = history
+            .delay_before_spawn(&policy, start + Duration::from_secs(10))
+            .unwrap_err();
+        assert!(refused.contains("crashed 3 times"), "{}", refused);
+
+        // Crashes older than the window stop counting
+        assert!(history
+            .delay_before_spawn(&policy, start + Duration::from_secs(61))
+            .is_ok());
+    }
+
+    #[cfg(unix)]
+    fn fake_cli(dir: &std::path::Path, script: &str) -> String {
+        use std::os::unix::fs::PermissionsExt;
+        let path = dir.join("fake-claude");
+        std::fs::write(&path, format!("#!/bin/sh\n{}\n", script)).unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+        path.to_string_lossy().into_owned()
+    }
+
+    #[cfg(unix)]
+    async fn collect(backend: &DirectCliBackend, is_new_session: bool) -> Vec<BackendEvent> {
+        use futures::StreamExt;
+        backend
+            .send("session-1", "hello", is_new_session)
+            .await
+            .unwrap()
+            .collect()
+            .await
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn crashed_cli_is_respawned_until_the_cap() {
+        let dir = tempfile::tempdir().unwrap();
+        let calls = dir.path().join("calls");
+        let binary = fake_cli(
+            dir.path(),
+            &format!("echo call >> '{}'\nexit 3", calls.display()),
+        );
+        let backend = DirectCliBackend::new(DirectCliConfig {
+            binary,
+            working_dir: dir.path().to_path_buf(),
+            restart: fast_policy(2),
+            ..DirectCliConfig::default()
+        });
+        let call_count = || std::fs::read_to_string(&calls).unwrap().lines().count();
+
+        let first = collect(&backend, false).await;
+        assert!(
+            matches!(first[1], BackendEvent::SessionOrphaned),
+            "crash should orphan the session: {:?}",
+            first
+        );
+        assert!(first
+            .iter()
+            .any(|e| matches!(e, BackendEvent::Error(m) if m.contains("exited unexpectedly"))));
+        assert!(matches!(first.last(), Some(BackendEvent::Done { .. })));
+        assert_eq!(call_count(), 1);
+
+        // The next request respawns the CLI after the backoff
+        let second = collect(&backend, true).await;
+        assert!(second
+            .iter()
+            .any(|e| matches!(e, BackendEvent::SessionOrphaned)));
+        assert_eq!(call_count(), 2);
+
+        // Two crashes inside the window hit the cap: no spawn at all
+        let third = collect(&backend, true).await;
+        assert!(third
+            .iter()
+            .any(|e| matches!(e, BackendEvent::Error(m) if m.contains("crashed 2 times"))));
+        assert!(matches!(third.last(), Some(BackendEvent::Done { .. })));
+        assert_eq!(call_count(), 2);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn error_result_is_not_a_crash() {
+        let dir = tempfile::tempdir().unwrap();
+        let binary = fake_cli(
+            dir.path(),
+            r#"echo '{"type":"result","is_error":true,"error":"rate limited"}'
+exit 1"#,
+        );
+        let backend = DirectCliBackend::new(DirectCliConfig {
+            binary,
+            working_dir: dir.path().to_path_buf(),
+            restart: fast_policy(1),
+            ..DirectCliConfig::default()
+        });
+
+        for _ in 0..2 {
+            let events = collect(&backend, true).await;
+            assert!(!events
+                .iter()
+                .any(|e| matches!(e, BackendEvent::SessionOrphaned)));
+            assert!(events
+                .iter()
+                .any(|e| matches!(e, BackendEvent::Error(m) if m.contains("rate limited"))));
+        }
+    }
+
+    /// A fake CLI that logs when it starts and ends, taking a second
+    #[cfg(unix)]
+    fn slow_backend(dir: &std::path::Path, pool_size: usize) -> DirectCliBackend {
+        let log = dir.join("log");
+        let binary = fake_cli(
+            dir,
+            &format!(
+                r#"echo start >> '{log}'
+sleep 1
+echo end >> '{log}'
+echo '{{"type":"result","is_error":false,"result":"ok"}}'"#,
+                log = log.display()
+            ),
+        );
+        DirectCliBackend::new(DirectCliConfig {
+            binary,
+            working_dir: dir.to_path_buf(),
+            pool_size,
+            ..DirectCliConfig::default()
+        })
+    }
+
+    #[cfg(unix)]
+    fn log_lines(dir: &std::path::Path) -> Vec<String> {
+        std::fs::read_to_string(dir.join("log"))
+            .unwrap()
+            .lines()
+            .map(str::to_string)
+            .collect()
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn concurrent_requests_use_separate_subprocesses() {
+        let dir = tempfile::tempdir().unwrap();
+        let backend = slow_backend(dir.path(), 2);
+
+        let (first, second) = tokio::join!(collect(&backend, true), collect(&backend, true));
+        assert!(matches!(first.last(), Some(BackendEvent::Done { .. })));
+        assert!(matches!(second.last(), Some(BackendEvent::Done { .. })));
+        // Both subprocesses were running at once
+        assert_eq!(log_lines(dir.path()), ["start", "start", "end", "end"]);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn requests_beyond_the_pool_queue() {
+        let dir = tempfile::tempdir().unwrap();
+        let backend = slow_backend(dir.path(), 1);
+
+        let (first, second) = tokio::join!(collect(&backend, true), collect(&backend, true));
+        assert!(matches!(first.last(), Some(BackendEvent::Done { .. })));
+        assert!(matches!(second.last(), Some(BackendEvent::Done { .. })));
+        assert_eq!(log_lines(dir.path()), ["start", "end", "start", "end"]);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn pooled_sessions_keep_their_own_directory() {
+        use futures::StreamExt;
+        let dir = tempfile::tempdir().unwrap();
+        let log = dir.path().join("dirs");
+        let binary = fake_cli(
+            dir.path(),
+            &format!(
+                r#"pwd >> '{}'
+sleep 1
+echo '{{"type":"result","is_error":false,"result":"ok"}}'"#,
+                log.display()
+            ),
+        );
+        let backend = DirectCliBackend::new(DirectCliConfig {
+            binary,
+            working_dir: dir.path().to_path_buf(),
+            pool_size: 2,
+            ..DirectCliConfig::default()
+        });
+        let send = |session: &'static str, is_new_session: bool| {
+            let backend = &backend;
+            async move {
+                backend
+                    .send(session, "hello", is_new_session)
+                    .await
+                    .unwrap()
+                    .collect::<Vec<_>>()
+                    .await
+            }
+        };
+
+        tokio::join!(send("session-a", true), send("session-b", true));
+        let started: Vec<String> = std::fs::read_to_string(&log)
+            .unwrap()
+            .lines()
+            .map(str::to_string)
+            .collect();
+        assert_eq!(started.len(), 2);
+        assert_ne!(started[0], started[1], "subprocesses shared a directory");
+        let cli_dir = dir
+            .path()
+            .join(".coven")
+            .join("cli")
+            .canonicalize()
+            .unwrap();
+        assert!(started
+            .iter()
+            .all(|d| d.starts_with(cli_dir.to_str().unwrap())));
+
+        // A fresh backend finds where session-b lives from its marker
+        let backend = DirectCliBackend::new(backend.config.clone());
+        let b_dir = started
+            .iter()
+            .find(|d| std::path::Path::new(d).join(".sessions/session-b").exists())
+            .unwrap()
+            .clone();
+        backend
+            .send("session-b", "again", false)
+            .await
+            .unwrap()
+            .collect::<Vec<_>>()
+            .await;
+        let resumed = std::fs::read_to_string(&log).unwrap();
+        assert_eq!(resumed.lines().last(), Some(b_dir.as_str()));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn dropping_the_backend_kills_its_subprocesses() {
+        let dir = tempfile::tempdir().unwrap();
+        let pid_file = dir.path().join("pid");
+        let binary = fake_cli(
+            dir.path(),
+            &format!("echo $$ > '{}'\nexec sleep 30", pid_file.display()),
+        );
+        let backend = DirectCliBackend::new(DirectCliConfig {
+            binary,
+            working_dir: dir.path().to_path_buf(),
+            ..DirectCliConfig::default()
+        });
+        let _events = backend.send("session-1", "hello", true).await.unwrap();
+
+        let pid = loop {
+            match std::fs::read_to_string(&pid_file) {
+                Ok(pid) if !pid.trim().is_empty() => break pid.trim().to_string(),
+                _ => tokio::time::sleep(Duration::from_millis(20)).await,
+            }
+        };
+        let alive = |pid: &str| {
+            std::process::Command::new("kill")
+                .args(["-0", pid])
+                .status()
+                .unwrap()
+                .success()
+        };
+        assert!(alive(&pid));
+
+        drop(backend);
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while alive(&pid) {
+            assert!(
+                Instant::now() < deadline,
+                "CLI {} outlived its backend",
+                pid
+            );
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+    }
 }
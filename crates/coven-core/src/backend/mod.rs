@@ -1,21 +1,40 @@
 // ABOUTME: Backend trait defining how coven connects to AI providers
-// ABOUTME: Implementations: DirectCli (preferred), Mux (native Rust), CodexCli, ClaudeSdk (legacy)
+// ABOUTME: Implementations: DirectCli (preferred), Mux (native Rust), CodexCli, ClaudeSdk (legacy), Replay (tests)
 
 mod amplifier_cli;
+mod budget;
 mod claude_sdk;
 mod codex_cli;
 mod direct_cli;
 mod mux;
 mod mux_tools;
+#[cfg(any(test, feature = "testing"))]
+mod replay;
+mod tool_cache;
+mod tool_progress;
+mod tool_retry;
 
 pub use amplifier_cli::{AmplifierCliBackend, AmplifierCliConfig};
+pub use budget::{
+    model_price, Budget, BudgetAlert, BudgetAlertKind, BudgetExceeded, BudgetLimit, BudgetReport,
+    BudgetStatus, TokenCounts, BUDGET_EXCEEDED, BUDGET_WARNING_RATIO,
+};
 pub use claude_sdk::ClaudeSdkBackend;
 pub use codex_cli::{CodexCliBackend, CodexCliConfig};
-pub use direct_cli::{DirectCliBackend, DirectCliConfig};
+pub use direct_cli::{CliRestartPolicy, DirectCliBackend, DirectCliConfig, DEFAULT_CLI_POOL_SIZE};
 pub use mux::{
-    default_dangerous_tools, ApprovalCallback, MuxBackend, MuxConfig, MuxMcpServerConfig,
+    assemble_system_prompt, default_dangerous_tools, system_prompt_sections, truncate_tool_result,
+    ApprovalCallback, MuxBackend, MuxConfig, MuxMcpServerConfig, PromptSection, PromptSource,
+    DEFAULT_MAX_PARALLEL_TOOLS, DEFAULT_TOOL_RESULT_MAX_BYTES,
 };
+#[cfg(any(test, feature = "testing"))]
+pub use replay::{ReplayBackend, ReplayBuilder, ReplayRequest};
+pub use tool_cache::{canonical_json, ToolCache, BUILTIN_PACK, CACHED_DETAIL};
+pub use tool_progress::report_tool_progress;
+pub use tool_retry::ToolRetry;
 
+use crate::store::Message;
+use crate::types::RequestOverrides;
 use anyhow::Result;
 use async_trait::async_trait;
 use futures::stream::BoxStream;
@@ -49,6 +68,8 @@ pub enum BackendEvent {
         id: String,
         name: String,
         input: serde_json::Value,
+        /// Human-readable confirmation text supplied by the tool definition
+        confirm_message: Option<String>,
     },
     /// Token usage statistics from LLM call
     Usage {
@@ -64,6 +85,12 @@ pub enum BackendEvent {
         state: ToolStateKind,
         detail: Option<String>,
     },
+    /// Spend against the backend's budget after a model call, with any
+    /// limit it took past 80%
+    Budget(BudgetReport),
+    /// The request was refused before a call that could go over budget;
+    /// like Error, this ends the response
+    BudgetExceeded(BudgetExceeded),
     /// Response complete
     Done { full_response: String },
     /// Error occurred
@@ -89,6 +116,12 @@ pub trait Backend: Send + Sync {
     /// Unique name for this backend
     fn name(&self) -> &'static str;
 
+    /// Model this backend answers with when no override is given, if known.
+    /// Backends that defer the choice to an external tool return None.
+    fn model(&self) -> Option<String> {
+        None
+    }
+
     /// Send a message and receive a stream of events
     ///
     /// - `session_id`: The session identifier for conversation continuity
@@ -100,4 +133,57 @@ pub trait Backend: Send + Sync {
         message: &str,
         is_new_session: bool,
     ) -> Result<BoxStream<'static, BackendEvent>>;
+
+    /// Send a message with per-request overrides of the backend's settings.
+    ///
+    /// Backends that can't change these settings per request ignore the
+    /// overrides and use their configured defaults.
+    async fn send_with_overrides(
+        &self,
+        session_id: &str,
+        message: &str,
+        is_new_session: bool,
+        overrides: &RequestOverrides,
+    ) -> Result<BoxStream<'static, BackendEvent>> {
+        if !overrides.is_empty() {
+            tracing::debug!(
+                backend = self.name(),
+                ?overrides,
+                "Backend doesn't support per-request overrides, using defaults"
+            );
+        }
+        self.send(session_id, message, is_new_session).await
+    }
+
+    /// Start session `session_id` with `history` as its earlier turns, so
+    /// a forked thread continues from the copied conversation. Returns
+    /// false when the backend can't, and the fork's first message then
+    /// starts a fresh session without that context.
+    async fn seed_session(&self, session_id: &str, history: &[Message]) -> Result<bool> {
+        let _ = (session_id, history);
+        Ok(false)
+    }
+
+    /// Drop session `session_id` from memory to bound the live working
+    /// set. Whatever the backend persisted stays, so a later message to
+    /// the session resumes it. Backends without in-memory sessions ignore
+    /// this.
+    async fn evict_session(&self, session_id: &str) {
+        let _ = session_id;
+    }
+
+    /// Keep at most the last `max_messages` messages of session
+    /// `session_id` in context. Backends that don't manage their own
+    /// context ignore this.
+    async fn trim_session(&self, session_id: &str, max_messages: usize) -> Result<()> {
+        let _ = (session_id, max_messages);
+        Ok(())
+    }
+
+    /// Spend so far against the backend's budget, in session `session_id`
+    /// and today. None for backends without a budget.
+    async fn budget_status(&self, session_id: &str) -> Result<Option<BudgetStatus>> {
+        let _ = session_id;
+        Ok(None)
+    }
 }
@@ -1,10 +1,16 @@
 // ABOUTME: Mux backend - uses mux-rs for native Rust agent execution.
 // ABOUTME: Provides streaming LLM responses with SQLx session persistence.
 
+use super::budget::{Budget, BudgetStatus, TokenCounts};
 use super::mux_tools::{
     WdBashTool, WdEditTool, WdListFilesTool, WdReadFileTool, WdSearchTool, WdWriteFileTool,
 };
-use super::{Backend, BackendEvent};
+use super::tool_cache::{ToolCache, BUILTIN_PACK, CACHED_DETAIL};
+use super::tool_progress::with_tool_progress;
+use super::tool_retry::{annotate_result, retry_detail, ToolRetry};
+use super::{Backend, BackendEvent, ToolStateKind};
+use crate::config::{BudgetConfig, MuxBackendConfig, ToolCacheConfig, ToolRetryConfig};
+use crate::types::RequestOverrides;
 use anyhow::{Context, Result};
 use async_trait::async_trait;
 use futures::stream::BoxStream;
@@ -14,6 +20,7 @@ use mux::tool::Registry;
 use mux::tools::{WebFetchTool, WebSearchTool};
 use serde::{Deserialize, Serialize};
 use sqlx::{Pool, Row, Sqlite, SqlitePool};
+use std::borrow::Cow;
 use std::collections::{HashMap, HashSet};
 use std::future::Future;
 use std::path::PathBuf;
@@ -51,6 +58,27 @@ pub struct MuxConfig {
     /// Filenames to search for soul in working_dir (default: ["soul.md", ".coven/soul.md"])
     #[serde(default = "default_soul_files")]
     pub soul_files: Vec<String>,
+    /// Largest tool result in bytes sent back to the model; longer results
+    /// are cut and marked. Clients still get the full output. 0 disables.
+    #[serde(default = "default_tool_result_max_bytes")]
+    pub tool_result_max_bytes: usize,
+    /// Reuse results of read-only tools within a session
+    #[serde(default)]
+    pub tool_cache: ToolCacheConfig,
+    /// Run tools that fail transiently again before the model sees them
+    #[serde(default)]
+    pub tool_retry: ToolRetryConfig,
+    /// Most read-only tool calls from one model response run at once;
+    /// results still go back in call order
+    #[serde(default = "default_max_parallel_tools")]
+    pub max_parallel_tools: usize,
+    /// Confirmation text shown when approval is requested, by tool name.
+    /// Listed tools need approval even if they aren't dangerous.
+    #[serde(default)]
+    pub confirm_messages: HashMap<String, String>,
+    /// Token and cost limits; spend isn't tracked when none is set
+    #[serde(default)]
+    pub budget: BudgetConfig,
     /// MCP servers to connect to (stdio transport)
     #[serde(default)]
     pub mcp_servers: Vec<MuxMcpServerConfig>,
@@ -104,6 +132,37 @@ fn default_soul_files() -> Vec<String> {
     vec!["soul.md".to_string(), ".coven/soul.md".to_string()]
 }
 
+/// Tool results longer than this are truncated before reaching the model.
+pub const DEFAULT_TOOL_RESULT_MAX_BYTES: usize = 64 * 1024;
+
+fn default_tool_result_max_bytes() -> usize {
+    DEFAULT_TOOL_RESULT_MAX_BYTES
+}
+
+/// Read-only tool calls from one model response that run at once by default.
+pub const DEFAULT_MAX_PARALLEL_TOOLS: usize = 4;
+
+fn default_max_parallel_tools() -> usize {
+    DEFAULT_MAX_PARALLEL_TOOLS
+}
+
+/// Cut a tool result down to `max_bytes` for the model, ending on a char
+/// boundary and noting how much was dropped. `max_bytes` of 0 means no limit.
+pub fn truncate_tool_result(output: &str, max_bytes: usize) -> Cow<'_, str> {
+    if max_bytes == 0 || output.len() <= max_bytes {
+        return Cow::Borrowed(output);
+    }
+    let mut end = max_bytes;
+    while !output.is_char_boundary(end) {
+        end -= 1;
+    }
+    Cow::Owned(format!(
+        "{}\n\n[truncated {} bytes]",
+        &output[..end],
+        output.len() - end
+    ))
+}
+
 impl Default for MuxConfig {
     fn default() -> Self {
         Self {
@@ -115,6 +174,12 @@ impl Default for MuxConfig {
             global_soul_path: None,
             agent_soul_path: None,
             soul_files: default_soul_files(),
+            tool_result_max_bytes: default_tool_result_max_bytes(),
+            tool_cache: ToolCacheConfig::default(),
+            tool_retry: ToolRetryConfig::default(),
+            max_parallel_tools: default_max_parallel_tools(),
+            confirm_messages: HashMap::new(),
+            budget: BudgetConfig::default(),
             mcp_servers: Vec::new(),
             skip_default_tools: false,
             gateway_mcp: None,
@@ -141,20 +206,76 @@ impl MuxSession {
         }
     }
 
+    /// A session whose earlier turns are `history`, for a forked thread.
+    /// Consecutive messages of one role, left by a request that failed
+    /// before answering, become one turn.
+    fn from_history(system_prompt: Option<String>, history: &[crate::store::Message]) -> Self {
+        let mut session = Self::new(system_prompt);
+        for turn in history.iter().filter(|turn| !turn.content.is_empty()) {
+            let role = if turn.role == "user" {
+                Role::User
+            } else {
+                Role::Assistant
+            };
+            let text = ContentBlock::Text {
+                text: turn.content.clone(),
+            };
+            let same_role = |last: &Message| {
+                matches!(
+                    (&last.role, &role),
+                    (Role::User, Role::User) | (Role::Assistant, Role::Assistant)
+                )
+            };
+            match session.messages.last_mut() {
+                Some(last) if same_role(last) => last.content.push(text),
+                _ => session.add_message(Message {
+                    role,
+                    content: vec![text],
+                }),
+            }
+        }
+        session
+    }
+
     /// Add a message to the session, pruning old messages if needed
     fn add_message(&mut self, message: Message) {
         self.messages.push(message);
-        // Keep only the most recent messages if we exceed the limit
-        if self.messages.len() > MAX_SESSION_MESSAGES {
-            // Remove oldest messages (keep the most recent ones)
-            let to_remove = self.messages.len() - MAX_SESSION_MESSAGES;
-            self.messages.drain(0..to_remove);
-            tracing::debug!(
-                removed = to_remove,
-                remaining = self.messages.len(),
-                "Pruned old messages from session"
-            );
+        self.trim(MAX_SESSION_MESSAGES);
+    }
+
+    /// Keep about the last `max` messages, returning how many were removed.
+    /// The kept history starts at a user's own message, never at an answer
+    /// or a tool result whose call was removed, so a single turn longer
+    /// than `max` is kept whole.
+    fn trim(&mut self, max: usize) -> usize {
+        if self.messages.len() <= max {
+            return 0;
         }
+        let starts_turn = |message: &Message| {
+            matches!(message.role, Role::User)
+                && !message
+                    .content
+                    .iter()
+                    .any(|block| matches!(block, ContentBlock::ToolResult { .. }))
+        };
+        let oldest = self.messages.len() - max;
+        let to_remove = match self.messages[oldest..].iter().position(starts_turn) {
+            Some(i) => oldest + i,
+            None => self.messages[..oldest]
+                .iter()
+                .rposition(starts_turn)
+                .unwrap_or(0),
+        };
+        if to_remove == 0 {
+            return 0;
+        }
+        self.messages.drain(0..to_remove);
+        tracing::debug!(
+            removed = to_remove,
+            remaining = self.messages.len(),
+            "Pruned old messages from session"
+        );
+        to_remove
     }
 }
 
@@ -348,6 +469,15 @@ pub struct MuxBackend {
     approval_callback: Option<ApprovalCallback>,
     /// Set of tool names that require approval
     dangerous_tools: HashSet<String>,
+    /// Confirmation messages keyed by tool name, shown in approval prompts.
+    /// Tools with a confirmation message always require approval.
+    confirm_messages: Arc<RwLock<HashMap<String, String>>>,
+    /// Results of read-only tools, when caching is enabled
+    tool_cache: Option<Arc<ToolCache>>,
+    /// Which failed tool calls run again, when any tool is retried
+    tool_retry: Option<Arc<ToolRetry>>,
+    /// Spend against the configured limits, when any is set
+    budget: Option<Arc<Budget>>,
 }
 
 impl MuxBackend {
@@ -383,6 +513,46 @@ impl MuxBackend {
             tracing::info!("Skipped default tools (meta-agent mode)");
         }
 
+        let tool_retry = ToolRetry::from_config(&config.tool_retry).map(|retry| {
+            tracing::info!(
+                tools = ?config.tool_retry.tools,
+                max_attempts = config.tool_retry.max_attempts,
+                "Retrying tools that fail transiently"
+            );
+            Arc::new(retry)
+        });
+
+        // Built-in tools share one pack, so bash or an edit clears cached reads
+        let tool_cache = ToolCache::from_config(&config.tool_cache).map(|cache| {
+            tracing::info!(
+                tools = ?config.tool_cache.tools,
+                ttl_secs = config.tool_cache.ttl_secs,
+                "Caching read-only tool results"
+            );
+            Arc::new(cache.with_pack(
+                BUILTIN_PACK,
+                [
+                    "read_file",
+                    "write_file",
+                    "edit",
+                    "bash",
+                    "list_files",
+                    "search",
+                    "web_fetch",
+                    "web_search",
+                ],
+            ))
+        });
+
+        // Spend is kept next to the sessions so limits survive restarts
+        let budget = if config.budget.is_enabled() {
+            let budget = Budget::open(session_db.pool.clone(), config.budget.clone()).await?;
+            tracing::info!(budget = ?config.budget, "Enforcing token and cost limits");
+            Some(Arc::new(budget))
+        } else {
+            None
+        };
+
         // Connect stdio MCP servers (background, don't block)
         let registry_clone = Arc::clone(&registry);
         let mcp_configs = config.mcp_servers.clone();
@@ -428,6 +598,8 @@ impl MuxBackend {
             });
         }
 
+        let confirm_messages = Arc::new(RwLock::new(config.confirm_messages.clone()));
+
         Ok(Self {
             config,
             client,
@@ -436,6 +608,10 @@ impl MuxBackend {
             registry,
             approval_callback: None,
             dangerous_tools: default_dangerous_tools(),
+            confirm_messages,
+            tool_cache,
+            tool_retry,
+            budget,
         })
     }
 
@@ -458,12 +634,63 @@ impl MuxBackend {
         self
     }
 
+    /// Set the confirmation message shown when approval is requested for a tool.
+    /// Tools with a confirmation message require approval even if they are not
+    /// in the dangerous tools set.
+    pub async fn set_confirm_message(
+        &self,
+        tool_name: impl Into<String>,
+        message: impl Into<String>,
+    ) {
+        self.confirm_messages
+            .write()
+            .await
+            .insert(tool_name.into(), message.into());
+    }
+
     /// Register an additional tool with the backend's registry.
     /// This allows injecting custom tools after backend construction.
     pub async fn register_tool<T: mux::tool::Tool + Send + Sync + 'static>(&self, tool: T) {
         self.registry.register(tool).await;
     }
 
+    /// Remove a tool registered with `register_tool`, along with any
+    /// confirmation message set for it, e.g. when a pack stops offering it.
+    pub async fn unregister_tool(&self, tool_name: &str) {
+        self.registry.unregister(tool_name).await;
+        self.confirm_messages.write().await.remove(tool_name);
+    }
+
+    /// Apply a pack's full tool list: every offered tool is (re)registered
+    /// via `make_tool`, so changed definitions replace the old ones, along
+    /// with its confirmation message, and tools in `registered` that are no
+    /// longer offered are removed. Updates `registered` to match and returns
+    /// the names of the removed tools.
+    pub async fn sync_tools<T, F>(
+        &self,
+        registered: &mut HashSet<String>,
+        tools: &[coven_proto::ToolDefinition],
+        make_tool: F,
+    ) -> Vec<String>
+    where
+        T: mux::tool::Tool + Send + Sync + 'static,
+        F: Fn(&coven_proto::ToolDefinition) -> T,
+    {
+        let removed = removed_tools(registered, tools);
+        for name in &removed {
+            self.unregister_tool(name).await;
+            registered.remove(name);
+        }
+        for def in tools {
+            self.register_tool(make_tool(def)).await;
+            if let Some(ref confirm) = def.confirm_message {
+                self.set_confirm_message(&def.name, confirm).await;
+            }
+            registered.insert(def.name.clone());
+        }
+        removed
+    }
+
     /// Connect to gateway MCP endpoint to access pack tools.
     /// This should be called after the gateway welcome message is received.
     /// Returns the number of pack tools registered.
@@ -486,11 +713,69 @@ impl Backend for MuxBackend {
         "mux"
     }
 
+    fn model(&self) -> Option<String> {
+        Some(self.config.model.clone())
+    }
+
+    async fn budget_status(&self, session_id: &str) -> Result<Option<BudgetStatus>> {
+        match &self.budget {
+            Some(budget) => Ok(Some(budget.status(session_id, 0).await?)),
+            None => Ok(None),
+        }
+    }
+
+    async fn seed_session(
+        &self,
+        session_id: &str,
+        history: &[crate::store::Message],
+    ) -> Result<bool> {
+        let session = MuxSession::from_history(build_system_prompt(&self.config), history);
+        self.session_db.save_session(session_id, &session).await?;
+        self.sessions
+            .write()
+            .await
+            .insert(session_id.to_string(), session);
+        Ok(true)
+    }
+
+    async fn evict_session(&self, session_id: &str) {
+        // Every change is saved to session_db as it happens, so the next
+        // message reloads the session from there
+        self.sessions.write().await.remove(session_id);
+    }
+
+    async fn trim_session(&self, session_id: &str, max_messages: usize) -> Result<()> {
+        let mut sessions = self.sessions.write().await;
+        let Some(session) = sessions.get_mut(session_id) else {
+            return Ok(());
+        };
+        if session.trim(max_messages) > 0 {
+            self.session_db.save_session(session_id, session).await?;
+        }
+        Ok(())
+    }
+
     async fn send(
         &self,
         session_id: &str,
         message: &str,
         is_new_session: bool,
+    ) -> Result<BoxStream<'static, BackendEvent>> {
+        self.send_with_overrides(
+            session_id,
+            message,
+            is_new_session,
+            &RequestOverrides::default(),
+        )
+        .await
+    }
+
+    async fn send_with_overrides(
+        &self,
+        session_id: &str,
+        message: &str,
+        is_new_session: bool,
+        overrides: &RequestOverrides,
     ) -> Result<BoxStream<'static, BackendEvent>> {
         let (tx, rx) = tokio::sync::mpsc::channel(64);
 
@@ -533,11 +818,21 @@ impl Backend for MuxBackend {
         let sessions = Arc::clone(&self.sessions);
         let session_db = Arc::clone(&self.session_db);
         let registry = Arc::clone(&self.registry);
-        let config = self.config.clone();
+        let mut config = self.config.clone();
+        if let Some(model) = &overrides.model {
+            config.model = model.clone();
+        }
+        if let Some(max_tokens) = overrides.max_tokens {
+            config.max_tokens = max_tokens;
+        }
         let session_id = session_id.to_string();
         let message = message.to_string();
         let approval_callback = self.approval_callback.clone();
         let dangerous_tools = self.dangerous_tools.clone();
+        let confirm_messages = self.confirm_messages.read().await.clone();
+        let tool_cache = self.tool_cache.clone();
+        let tool_retry = self.tool_retry.clone();
+        let budget = self.budget.clone();
 
         tokio::spawn(async move {
             if let Err(e) = run_prompt(
@@ -551,6 +846,10 @@ impl Backend for MuxBackend {
                 tx,
                 approval_callback,
                 &dangerous_tools,
+                &confirm_messages,
+                tool_cache.as_deref(),
+                tool_retry.as_deref(),
+                budget.as_deref(),
             )
             .await
             {
@@ -562,7 +861,88 @@ impl Backend for MuxBackend {
     }
 }
 
-/// Build system prompt from global and local files, including soul files for identity/personality.
+/// Where a section of the assembled system prompt came from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PromptSource {
+    /// Working directory context generated by coven
+    Environment,
+    /// Global system prompt (~/.mux/system.md or configured path)
+    GlobalSystemPrompt(PathBuf),
+    /// Global soul (~/.config/coven/soul.md or configured path)
+    GlobalSoul(PathBuf),
+    /// Per-agent soul (configured path or found via `soul_files`)
+    AgentSoul(PathBuf),
+    /// Local prompt file in the working directory (claude.md, agent.md, ...)
+    LocalPrompt(PathBuf),
+}
+
+impl std::fmt::Display for PromptSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PromptSource::Environment => write!(f, "environment (generated)"),
+            PromptSource::GlobalSystemPrompt(path) => {
+                write!(f, "global system prompt: {}", path.display())
+            }
+            PromptSource::GlobalSoul(path) => write!(f, "global soul: {}", path.display()),
+            PromptSource::AgentSoul(path) => write!(f, "agent soul: {}", path.display()),
+            PromptSource::LocalPrompt(path) => write!(f, "local prompt: {}", path.display()),
+        }
+    }
+}
+
+/// One section of the system prompt, as it is sent, with its source
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PromptSection {
+    pub source: PromptSource,
+    pub content: String,
+}
+
+/// Separator between sections in the assembled system prompt
+pub const PROMPT_SECTION_SEPARATOR: &str = "\n\n---\n\n";
+
+impl MuxConfig {
+    /// Build the backend config the agent runs with from the `[mux]`
+    /// settings, applying the `ANTHROPIC_MODEL` and `ANTHROPIC_MAX_TOKENS`
+    /// environment overrides.
+    pub fn from_settings(settings: &MuxBackendConfig, working_dir: &std::path::Path) -> Self {
+        let settings = settings.clone();
+        Self {
+            model: std::env::var("ANTHROPIC_MODEL").unwrap_or(settings.model),
+            max_tokens: std::env::var("ANTHROPIC_MAX_TOKENS")
+                .ok()
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(settings.max_tokens),
+            working_dir: working_dir.to_path_buf(),
+            global_system_prompt_path: settings
+                .global_system_prompt_path
+                .or_else(|| home_dir().map(|h| h.join(".mux").join("system.md"))),
+            local_prompt_files: settings.local_prompt_files,
+            global_soul_path: settings.global_soul_path,
+            agent_soul_path: settings.agent_soul_path,
+            soul_files: settings.soul_files,
+            tool_result_max_bytes: settings.tool_result_max_bytes,
+            tool_cache: settings.tool_cache,
+            tool_retry: settings.tool_retry,
+            max_parallel_tools: settings.max_parallel_tools,
+            confirm_messages: settings.confirm_messages,
+            budget: settings.budget,
+            mcp_servers: Vec::new(),
+            skip_default_tools: false,
+            gateway_mcp: None, // Set after gateway connection
+        }
+    }
+}
+
+/// Read a prompt file, treating missing and whitespace-only files as absent
+fn read_prompt_file(path: &std::path::Path) -> Option<String> {
+    std::fs::read_to_string(path)
+        .ok()
+        .filter(|content| !content.trim().is_empty())
+}
+
+/// Resolve every section of the system prompt, in the order they are sent.
+/// This reads the prompt files but doesn't need a running backend, so
+/// `coven agent show-prompt` can print exactly what the agent would send.
 ///
 /// Prompt building order:
 /// 1. Working directory context (always first)
@@ -570,57 +950,53 @@ impl Backend for MuxBackend {
 /// 3. Global soul (~/.config/coven/soul.md or configured path)
 /// 4. Per-agent soul (.coven/soul.md or configured path in working_dir)
 /// 5. Local project prompt (claude.md, CLAUDE.md, etc.)
-fn build_system_prompt(config: &MuxConfig) -> Option<String> {
-    let mut parts = Vec::new();
+pub fn system_prompt_sections(config: &MuxConfig) -> Vec<PromptSection> {
+    let mut sections = Vec::new();
 
     // 1. Working directory context - critical for tools to work correctly
     let working_dir_str = config.working_dir.to_string_lossy();
-    parts.push(format!(
-        "# Environment\n\n\
-        Your working directory is: {}\n\n\
-        When using file tools (read_file, write_file, list_files, search), paths are relative to this directory.\n\
-        When using the bash tool, pass working_dir: \"{}\" unless you need to run in a different directory.",
-        working_dir_str, working_dir_str
-    ));
+    sections.push(PromptSection {
+        source: PromptSource::Environment,
+        content: format!(
+            "# Environment\n\n\
+            Your working directory is: {}\n\n\
+            When using file tools (read_file, write_file, list_files, search), paths are relative to this directory.\n\
+            When using the bash tool, pass working_dir: \"{}\" unless you need to run in a different directory.",
+            working_dir_str, working_dir_str
+        ),
+    });
 
     // 2. Global system prompt (~/.mux/system.md or configured path)
-    if let Some(ref global_path) = config.global_system_prompt_path {
-        if let Ok(content) = std::fs::read_to_string(global_path) {
-            if !content.trim().is_empty() {
-                parts.push(content);
-            }
-        }
-    } else if let Some(home) = home_dir() {
-        // Default to ~/.mux/system.md
-        let default_path = home.join(".mux").join("system.md");
-        if let Ok(content) = std::fs::read_to_string(default_path) {
-            if !content.trim().is_empty() {
-                parts.push(content);
-            }
+    let global_path = config
+        .global_system_prompt_path
+        .clone()
+        .or_else(|| home_dir().map(|home| home.join(".mux").join("system.md")));
+    if let Some(path) = global_path {
+        if let Some(content) = read_prompt_file(&path) {
+            sections.push(PromptSection {
+                source: PromptSource::GlobalSystemPrompt(path),
+                content,
+            });
         }
     }
 
     // 3. Global soul (~/.config/coven/soul.md or configured path)
-    let global_soul_content = if let Some(ref soul_path) = config.global_soul_path {
+    let global_soul_path = match config.global_soul_path {
         // Expand tilde in configured path
-        let expanded = expand_tilde(soul_path);
-        std::fs::read_to_string(&expanded).ok()
-    } else if let Some(home) = home_dir() {
-        // Default to ~/.config/coven/soul.md
-        let default_soul_path = home.join(".config").join("coven").join("soul.md");
-        std::fs::read_to_string(default_soul_path).ok()
-    } else {
-        None
+        Some(ref soul_path) => Some(expand_tilde(soul_path)),
+        None => home_dir().map(|home| home.join(".config").join("coven").join("soul.md")),
     };
-
-    if let Some(content) = global_soul_content {
-        if !content.trim().is_empty() {
-            parts.push(format!("# Identity\n\n{}", content));
+    if let Some(path) = global_soul_path {
+        if let Some(content) = read_prompt_file(&path) {
+            sections.push(PromptSection {
+                source: PromptSource::GlobalSoul(path),
+                content: format!("# Identity\n\n{}", content),
+            });
         }
     }
 
     // 4. Per-agent soul (configured path or search in working_dir)
-    let agent_soul_content = if let Some(ref soul_path) = config.agent_soul_path {
+    let agent_soul = if let Some(ref soul_path) = config.agent_soul_path {
         // Expand tilde, then resolve relative paths against working_dir
         let expanded = expand_tilde(soul_path);
         let resolved = if expanded.is_absolute() {
@@ -628,41 +1004,64 @@ fn build_system_prompt(config: &MuxConfig) -> Option<String> {
         } else {
             config.working_dir.join(expanded)
         };
-        std::fs::read_to_string(resolved).ok()
+        std::fs::read_to_string(&resolved)
+            .ok()
+            .map(|content| (resolved, content))
     } else {
         // Search soul_files in working_dir
         config.soul_files.iter().find_map(|filename| {
             let path = config.working_dir.join(filename);
-            std::fs::read_to_string(&path).ok()
+            std::fs::read_to_string(&path)
+                .ok()
+                .map(|content| (path, content))
         })
     };
 
-    if let Some(content) = agent_soul_content {
+    if let Some((path, content)) = agent_soul {
         if !content.trim().is_empty() {
             // If no global soul was added, add the Identity header
-            let needs_header = !parts.iter().any(|p| p.starts_with("# Identity"));
-            if needs_header {
-                parts.push(format!("# Identity\n\n{}", content));
+            let needs_header = !sections.iter().any(|s| s.content.starts_with("# Identity"));
+            let content = if needs_header {
+                format!("# Identity\n\n{}", content)
             } else {
                 // Append to existing identity section with separator
-                parts.push(format!("## Agent Identity\n\n{}", content));
-            }
+                format!("## Agent Identity\n\n{}", content)
+            };
+            sections.push(PromptSection {
+                source: PromptSource::AgentSoul(path),
+                content,
+            });
         }
     }
 
     // 5. Local system prompt (claude.md, agent.md, etc. in working_dir)
     for filename in &config.local_prompt_files {
         let local_path = config.working_dir.join(filename);
-        if let Ok(content) = std::fs::read_to_string(&local_path) {
-            if !content.trim().is_empty() {
-                parts.push(content);
-                break;
-            }
+        if let Some(content) = read_prompt_file(&local_path) {
+            sections.push(PromptSection {
+                source: PromptSource::LocalPrompt(local_path),
+                content,
+            });
+            break;
         }
     }
 
+    sections
+}
+
+/// Join prompt sections into the system prompt sent to the model
+pub fn assemble_system_prompt(sections: &[PromptSection]) -> String {
+    sections
+        .iter()
+        .map(|s| s.content.as_str())
+        .collect::<Vec<_>>()
+        .join(PROMPT_SECTION_SEPARATOR)
+}
+
+/// Build system prompt from global and local files, including soul files for identity/personality.
+fn build_system_prompt(config: &MuxConfig) -> Option<String> {
     // Always have at least the working directory context
-    Some(parts.join("\n\n---\n\n"))
+    Some(assemble_system_prompt(&system_prompt_sections(config)))
 }
 
 /// Connect to an MCP server and register its tools
@@ -695,6 +1094,29 @@ async fn connect_mcp_server(config: &MuxMcpServerConfig, registry: &Registry) ->
     Ok(())
 }
 
+/// Rough characters per token, for sizing a call before it's made
+const CHARS_PER_TOKEN: u64 = 4;
+
+/// The most a call with `messages` and `system` could use: its prompt,
+/// estimated from its length, plus a full `max_tokens` of output
+fn estimate_call(messages: &[Message], system: Option<&str>, max_tokens: u32) -> TokenCounts {
+    let chars: usize = messages
+        .iter()
+        .flat_map(|message| &message.content)
+        .map(|block| match block {
+            ContentBlock::Text { text } => text.len(),
+            ContentBlock::ToolUse { name, input, .. } => name.len() + input.to_string().len(),
+            ContentBlock::ToolResult { content, .. } => content.len(),
+        })
+        .sum::<usize>()
+        + system.map_or(0, str::len);
+    TokenCounts {
+        input: chars as u64 / CHARS_PER_TOKEN,
+        output: u64::from(max_tokens),
+        ..TokenCounts::default()
+    }
+}
+
 #[allow(clippy::too_many_arguments)]
 async fn run_prompt(
     client: &AnthropicClient,
@@ -707,6 +1129,10 @@ async fn run_prompt(
     event_tx: tokio::sync::mpsc::Sender<BackendEvent>,
     approval_callback: Option<ApprovalCallback>,
     dangerous_tools: &HashSet<String>,
+    confirm_messages: &HashMap<String, String>,
+    tool_cache: Option<&ToolCache>,
+    tool_retry: Option<&ToolRetry>,
+    budget: Option<&Budget>,
 ) -> Result<()> {
     // Get tool definitions from registry
     let tools = registry.to_definitions().await;
@@ -737,6 +1163,8 @@ async fn run_prompt(
     // Limited to prevent infinite loops if LLM keeps requesting tools
     const MAX_ITERATIONS: usize = 50;
     let mut iteration = 0;
+    // Tokens this request has used across its tool-use rounds
+    let mut request_tokens: u64 = 0;
 
     loop {
         iteration += 1;
@@ -762,6 +1190,33 @@ async fn run_prompt(
             session.messages.clone()
         };
 
+        // Refuse the call instead of making it if it could take spend past
+        // a limit, or if spend can't be checked
+        if let Some(budget) = budget {
+            let next_call = estimate_call(&messages, system_prompt.as_deref(), config.max_tokens);
+            match budget
+                .check(session_id, request_tokens, &config.model, next_call)
+                .await
+            {
+                Ok(None) => {}
+                Ok(Some(exceeded)) => {
+                    tracing::warn!(session_id = %session_id, "{}", exceeded);
+                    let _ = event_tx.send(BackendEvent::BudgetExceeded(exceeded)).await;
+                    break;
+                }
+                Err(e) => {
+                    tracing::error!(error = %e, "Failed to check budget, refusing the call");
+                    let _ = event_tx
+                        .send(BackendEvent::Error(format!(
+                            "Couldn't check spend against the budget, so the model wasn't called: {}",
+                            e
+                        )))
+                        .await;
+                    break;
+                }
+            }
+        }
+
         // Build request
         let request = Request {
             model: config.model.clone(),
@@ -856,6 +1311,29 @@ async fn run_prompt(
                                     thinking_tokens: 0, // mux crate doesn't track this yet
                                 })
                                 .await;
+                            if let Some(budget) = budget {
+                                let tokens = TokenCounts {
+                                    input: usage.input_tokens as u64,
+                                    output: usage.output_tokens as u64,
+                                    cache_read: usage.cache_read_tokens as u64,
+                                    cache_write: usage.cache_write_tokens as u64,
+                                };
+                                match budget
+                                    .record(session_id, &config.model, tokens, request_tokens)
+                                    .await
+                                {
+                                    Ok(report) => {
+                                        for alert in &report.alerts {
+                                            tracing::warn!(session_id = %session_id, "Budget {}", alert);
+                                        }
+                                        let _ = event_tx.send(BackendEvent::Budget(report)).await;
+                                    }
+                                    Err(e) => {
+                                        tracing::error!(error = %e, "Failed to record budget spend");
+                                    }
+                                }
+                                request_tokens += tokens.total();
+                            }
                         }
                     }
                     StreamEvent::MessageStart { .. } | StreamEvent::MessageStop => {}
@@ -915,115 +1393,277 @@ async fn run_prompt(
             break;
         }
 
-        // Execute tools and collect results
-        let mut tool_results: Vec<ContentBlock> = Vec::new();
+        // Execute tools and collect results, in the order they were called
+        let calls = ToolCalls {
+            registry,
+            config,
+            session_id,
+            event_tx: &event_tx,
+            approval_callback: approval_callback.as_ref(),
+            dangerous_tools,
+            confirm_messages,
+            tool_cache,
+            tool_retry,
+        };
+        let tool_results = calls.run_all(tool_uses).await;
 
-        for (tool_id, tool_name, tool_input) in tool_uses {
-            // Check if this tool needs approval
-            if let Some(ref callback) = approval_callback {
-                if dangerous_tools.contains(&tool_name) {
-                    // Emit approval request event
-                    let _ = event_tx
-                        .send(BackendEvent::ToolApprovalRequest {
-                            id: tool_id.clone(),
-                            name: tool_name.clone(),
-                            input: tool_input.clone(),
-                        })
-                        .await;
+        // Add tool results as a user message and persist
+        {
+            let mut sessions_guard = sessions.write().await;
+            if let Some(session) = sessions_guard.get_mut(session_id) {
+                session.add_message(Message {
+                    role: Role::User,
+                    content: tool_results,
+                });
 
-                    // Wait for approval
-                    let approved =
-                        callback(tool_id.clone(), tool_name.clone(), tool_input.clone()).await;
+                // Persist after tool execution
+                if let Err(e) = session_db.save_session(session_id, session).await {
+                    tracing::error!(error = %e, "Failed to persist session after tool execution");
+                }
+            }
+        }
 
-                    if !approved {
-                        tracing::info!(tool = %tool_name, "Tool execution denied by user");
+        // Emit thinking for next iteration
+        let _ = event_tx.send(BackendEvent::Thinking).await;
+    }
 
-                        // Emit denial as tool result
-                        let output = "Tool execution denied by user".to_string();
-                        let _ = event_tx
-                            .send(BackendEvent::ToolResult {
-                                id: tool_id.clone(),
-                                output: output.clone(),
-                                is_error: true,
-                            })
-                            .await;
+    // Send done event
+    let _ = event_tx
+        .send(BackendEvent::Done {
+            full_response: accumulated_text,
+        })
+        .await;
+
+    Ok(())
+}
+
+/// What running one assistant message's tool calls needs
+struct ToolCalls<'a> {
+    registry: &'a Registry,
+    config: &'a MuxConfig,
+    session_id: &'a str,
+    event_tx: &'a tokio::sync::mpsc::Sender<BackendEvent>,
+    approval_callback: Option<&'a ApprovalCallback>,
+    dangerous_tools: &'a HashSet<String>,
+    confirm_messages: &'a HashMap<String, String>,
+    tool_cache: Option<&'a ToolCache>,
+    tool_retry: Option<&'a ToolRetry>,
+}
 
-                        tool_results.push(ContentBlock::ToolResult {
-                            tool_use_id: tool_id,
-                            content: output,
+impl ToolCalls<'_> {
+    /// Run `calls`, returning their results in call order. Neighbouring
+    /// calls that may overlap run up to `max_parallel_tools` at once; any
+    /// other call runs alone, after the calls before it have finished.
+    async fn run_all(&self, calls: Vec<(String, String, serde_json::Value)>) -> Vec<ContentBlock> {
+        let mut results = Vec::with_capacity(calls.len());
+        let mut calls = calls.into_iter().peekable();
+        while let Some((tool_id, tool_name, tool_input)) = calls.next() {
+            if !self.may_overlap(&tool_name) {
+                results.push(self.run(tool_id, tool_name, tool_input).await);
+                continue;
+            }
+            let mut batch = vec![(tool_id, tool_name, tool_input)];
+            while let Some(call) = calls.next_if(|(_, name, _)| self.may_overlap(name)) {
+                batch.push(call);
+            }
+            let batch_results: Vec<ContentBlock> = futures::stream::iter(batch)
+                .map(|(tool_id, tool_name, tool_input)| self.run(tool_id, tool_name, tool_input))
+                .buffered(self.config.max_parallel_tools.max(1))
+                .collect()
+                .await;
+            results.extend(batch_results);
+        }
+        results
+    }
+
+    /// Whether a call to `tool_name` may run alongside others: only
+    /// read-only tools, as listed for the tool cache, that need no approval.
+    /// Anything else may change what the calls around it see.
+    fn may_overlap(&self, tool_name: &str) -> bool {
+        self.tool_cache
+            .is_some_and(|cache| cache.is_cacheable(tool_name))
+            && !requires_approval(tool_name, self.dangerous_tools, self.confirm_messages)
+    }
+
+    /// Run one tool call, emitting its events, and return its result block
+    async fn run(
+        &self,
+        tool_id: String,
+        tool_name: String,
+        tool_input: serde_json::Value,
+    ) -> ContentBlock {
+        let event_tx = self.event_tx;
+        let config = self.config;
+        let session_id = self.session_id;
+        let tool_cache = self.tool_cache;
+
+        // Check if this tool needs approval
+        if let Some(callback) = self.approval_callback {
+            if requires_approval(&tool_name, self.dangerous_tools, self.confirm_messages) {
+                // Emit approval request event
+                let _ = event_tx
+                    .send(BackendEvent::ToolApprovalRequest {
+                        id: tool_id.clone(),
+                        name: tool_name.clone(),
+                        input: tool_input.clone(),
+                        confirm_message: self.confirm_messages.get(&tool_name).cloned(),
+                    })
+                    .await;
+
+                // Wait for approval
+                let approved =
+                    callback(tool_id.clone(), tool_name.clone(), tool_input.clone()).await;
+
+                if !approved {
+                    tracing::info!(tool = %tool_name, "Tool execution denied by user");
+
+                    // Emit denial as tool result
+                    let output = "Tool execution denied by user".to_string();
+                    let _ = event_tx
+                        .send(BackendEvent::ToolResult {
+                            id: tool_id.clone(),
+                            output: output.clone(),
                             is_error: true,
-                        });
-                        continue;
-                    }
+                        })
+                        .await;
 
-                    tracing::info!(tool = %tool_name, "Tool execution approved by user");
+                    return ContentBlock::ToolResult {
+                        tool_use_id: tool_id,
+                        content: output,
+                        is_error: true,
+                    };
                 }
+
+                tracing::info!(tool = %tool_name, "Tool execution approved by user");
             }
+        }
 
-            let start_time = Instant::now();
+        // A read-only tool already answered in this session isn't run again
+        let cached = tool_cache.and_then(|cache| cache.get(session_id, &tool_name, &tool_input));
+        if let Some(output) = cached {
+            tracing::debug!(tool = %tool_name, "Answered tool call from cache");
+            let _ = event_tx
+                .send(BackendEvent::ToolState {
+                    id: tool_id.clone(),
+                    state: ToolStateKind::Completed,
+                    detail: Some(CACHED_DETAIL.to_string()),
+                })
+                .await;
+            let content = truncate_tool_result(&output, config.tool_result_max_bytes).into_owned();
+            let _ = event_tx
+                .send(BackendEvent::ToolResult {
+                    id: tool_id.clone(),
+                    output,
+                    is_error: false,
+                })
+                .await;
+            return ContentBlock::ToolResult {
+                tool_use_id: tool_id,
+                content,
+                is_error: false,
+            };
+        }
 
-            // Look up and execute the tool
-            let (output, is_error) = if let Some(tool) = registry.get(&tool_name).await {
-                match tool.execute(tool_input.clone()).await {
+        let start_time = Instant::now();
+        let epoch = tool_cache.map(ToolCache::epoch).unwrap_or_default();
+
+        // Look up and execute the tool, again while it fails transiently
+        let retry = self.tool_retry.filter(|retry| retry.applies_to(&tool_name));
+        let mut attempt = 0;
+        let (output, is_error) = loop {
+            attempt += 1;
+            let (output, is_error) = if let Some(tool) = self.registry.get(&tool_name).await {
+                let execution = tool.execute(tool_input.clone());
+                match with_tool_progress(tool_id.clone(), event_tx.clone(), execution).await {
                     Ok(result) => (result.content, result.is_error),
                     Err(e) => (format!("Tool execution error: {}", e), true),
                 }
             } else {
                 (format!("Tool '{}' not found in registry", tool_name), true)
             };
-
-            let duration_ms = start_time.elapsed().as_millis() as u64;
-            tracing::debug!(
+            let Some(retry) = retry.filter(|retry| {
+                is_error && attempt < retry.max_attempts() && retry.is_transient(&output)
+            }) else {
+                break (output, is_error);
+            };
+            tracing::info!(
                 tool = %tool_name,
-                duration_ms = duration_ms,
-                is_error = is_error,
-                "Tool executed"
+                attempt = attempt,
+                error = %output,
+                "Retrying tool after transient failure"
             );
-
-            // Emit tool result event
             let _ = event_tx
-                .send(BackendEvent::ToolResult {
+                .send(BackendEvent::ToolState {
                     id: tool_id.clone(),
-                    output: output.clone(),
-                    is_error,
+                    state: ToolStateKind::Running,
+                    detail: Some(retry_detail(attempt, retry.max_attempts(), &output)),
                 })
                 .await;
+            tokio::time::sleep(retry.delay(attempt)).await;
+        };
 
-            tool_results.push(ContentBlock::ToolResult {
-                tool_use_id: tool_id,
-                content: output,
+        let duration_ms = start_time.elapsed().as_millis() as u64;
+        tracing::debug!(
+            tool = %tool_name,
+            duration_ms = duration_ms,
+            is_error = is_error,
+            attempts = attempt,
+            "Tool executed"
+        );
+        if let Some(cache) = tool_cache {
+            cache.record_since(
+                epoch,
+                session_id,
+                &tool_name,
+                &tool_input,
+                &output,
                 is_error,
-            });
+            );
         }
 
-        // Add tool results as a user message and persist
-        {
-            let mut sessions_guard = sessions.write().await;
-            if let Some(session) = sessions_guard.get_mut(session_id) {
-                session.add_message(Message {
-                    role: Role::User,
-                    content: tool_results,
-                });
-
-                // Persist after tool execution
-                if let Err(e) = session_db.save_session(session_id, session).await {
-                    tracing::error!(error = %e, "Failed to persist session after tool execution");
-                }
+        // The model only sees a bounded result; the event keeps it whole so
+        // the thread and clients have the full output.
+        let content = match truncate_tool_result(&output, config.tool_result_max_bytes) {
+            Cow::Borrowed(_) => output.clone(),
+            Cow::Owned(truncated) => {
+                tracing::info!(
+                    tool = %tool_name,
+                    bytes = output.len(),
+                    limit = config.tool_result_max_bytes,
+                    "Truncated tool result sent to model"
+                );
+                truncated
             }
-        }
+        };
 
-        // Emit thinking for next iteration
-        let _ = event_tx.send(BackendEvent::Thinking).await;
-    }
+        // Retries are noted so the model knows the call was flaky
+        let content = annotate_result(&content, is_error, attempt);
 
-    // Send done event
-    let _ = event_tx
-        .send(BackendEvent::Done {
-            full_response: accumulated_text,
-        })
-        .await;
+        // Emit tool result event
+        let _ = event_tx
+            .send(BackendEvent::ToolResult {
+                id: tool_id.clone(),
+                output,
+                is_error,
+            })
+            .await;
 
-    Ok(())
+        ContentBlock::ToolResult {
+            tool_use_id: tool_id,
+            content,
+            is_error,
+        }
+    }
+}
+
+/// Check whether a tool must be approved before execution.
+/// Tools are gated if they are marked dangerous or declare a confirmation message.
+fn requires_approval(
+    tool_name: &str,
+    dangerous_tools: &HashSet<String>,
+    confirm_messages: &HashMap<String, String>,
+) -> bool {
+    dangerous_tools.contains(tool_name) || confirm_messages.contains_key(tool_name)
 }
 
 fn home_dir() -> Option<PathBuf> {
@@ -1043,11 +1683,73 @@ fn now_unix_secs() -> String {
         .unwrap_or_else(|_| "0".to_string())
 }
 
+/// Names in `registered` missing from the offered `tools`, sorted.
+fn removed_tools(
+    registered: &HashSet<String>,
+    tools: &[coven_proto::ToolDefinition],
+) -> Vec<String> {
+    let offered: HashSet<&str> = tools.iter().map(|t| t.name.as_str()).collect();
+    let mut removed: Vec<String> = registered
+        .iter()
+        .filter(|name| !offered.contains(name.as_str()))
+        .cloned()
+        .collect();
+    removed.sort();
+    removed
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::time::Duration;
     use tempfile::TempDir;
 
+    #[test]
+    fn test_removed_tools() {
+        let registered: HashSet<String> = ["notes", "todo", "search"]
+            .into_iter()
+            .map(String::from)
+            .collect();
+        let offered = vec![
+            coven_proto::ToolDefinition {
+                name: "todo".to_string(),
+                ..Default::default()
+            },
+            coven_proto::ToolDefinition {
+                name: "calendar".to_string(),
+                ..Default::default()
+            },
+        ];
+        assert_eq!(
+            removed_tools(&registered, &offered),
+            vec!["notes", "search"]
+        );
+        assert!(removed_tools(&HashSet::new(), &offered).is_empty());
+    }
+
+    #[test]
+    fn test_estimate_call_counts_prompt_and_full_output() {
+        let messages = vec![
+            Message {
+                role: Role::User,
+                content: vec![ContentBlock::Text {
+                    text: "x".repeat(400),
+                }],
+            },
+            Message {
+                role: Role::User,
+                content: vec![ContentBlock::ToolResult {
+                    tool_use_id: "t1".to_string(),
+                    content: "y".repeat(200),
+                    is_error: false,
+                }],
+            },
+        ];
+        let estimate = estimate_call(&messages, Some(&"z".repeat(400)), 1000);
+        assert_eq!(estimate.input, 250);
+        assert_eq!(estimate.output, 1000);
+    }
+
     #[test]
     fn test_build_system_prompt_includes_working_dir() {
         let temp_dir = TempDir::new().unwrap();
@@ -1215,6 +1917,43 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_system_prompt_sections_report_sources() {
+        let temp_dir = TempDir::new().unwrap();
+        let system_prompt_path = temp_dir.path().join("system.md");
+        std::fs::write(&system_prompt_path, "GLOBAL_SYSTEM_MARKER").unwrap();
+        let global_soul_path = temp_dir.path().join("global-soul.md");
+        std::fs::write(&global_soul_path, "GLOBAL_SOUL_MARKER").unwrap();
+        std::fs::write(temp_dir.path().join("soul.md"), "AGENT_SOUL_MARKER").unwrap();
+        std::fs::write(temp_dir.path().join("CLAUDE.md"), "LOCAL_PROMPT_MARKER").unwrap();
+
+        let config = MuxConfig {
+            working_dir: temp_dir.path().to_path_buf(),
+            global_system_prompt_path: Some(system_prompt_path.clone()),
+            global_soul_path: Some(global_soul_path.clone()),
+            soul_files: vec!["soul.md".to_string()],
+            local_prompt_files: vec!["claude.md".to_string(), "CLAUDE.md".to_string()],
+            ..MuxConfig::default()
+        };
+
+        let sections = system_prompt_sections(&config);
+        let sources: Vec<_> = sections.iter().map(|s| s.source.clone()).collect();
+        assert_eq!(
+            sources,
+            vec![
+                PromptSource::Environment,
+                PromptSource::GlobalSystemPrompt(system_prompt_path),
+                PromptSource::GlobalSoul(global_soul_path),
+                PromptSource::AgentSoul(temp_dir.path().join("soul.md")),
+                PromptSource::LocalPrompt(temp_dir.path().join("CLAUDE.md")),
+            ]
+        );
+        assert_eq!(
+            assemble_system_prompt(&sections),
+            build_system_prompt(&config).unwrap()
+        );
+    }
+
     #[test]
     fn test_build_system_prompt_empty_soul_ignored() {
         let temp_dir = TempDir::new().unwrap();
@@ -1235,6 +1974,20 @@ mod tests {
         assert!(!prompt.contains("# Identity"));
     }
 
+    #[test]
+    fn test_requires_approval() {
+        let dangerous = default_dangerous_tools();
+        let mut confirm = HashMap::new();
+        confirm.insert(
+            "delete_files".to_string(),
+            "This will delete files".to_string(),
+        );
+
+        assert!(requires_approval("bash", &dangerous, &confirm));
+        assert!(requires_approval("delete_files", &dangerous, &confirm));
+        assert!(!requires_approval("read_file", &dangerous, &confirm));
+    }
+
     #[test]
     fn test_default_soul_files() {
         let files = default_soul_files();
@@ -1249,4 +2002,514 @@ mod tests {
         assert!(config.agent_soul_path.is_none());
         assert_eq!(config.soul_files, vec!["soul.md", ".coven/soul.md"]);
     }
+
+    #[test]
+    fn test_truncate_tool_result_keeps_small_output() {
+        let output = "line one\nline two";
+        assert!(matches!(
+            truncate_tool_result(output, 1024),
+            Cow::Borrowed("line one\nline two")
+        ));
+        assert!(matches!(truncate_tool_result(output, 0), Cow::Borrowed(_)));
+    }
+
+    #[test]
+    fn test_truncate_tool_result_cuts_oversized_output() {
+        let output = "x".repeat(3 * 1024 * 1024);
+        let truncated = truncate_tool_result(&output, DEFAULT_TOOL_RESULT_MAX_BYTES);
+
+        let marker = format!(
+            "\n\n[truncated {} bytes]",
+            output.len() - DEFAULT_TOOL_RESULT_MAX_BYTES
+        );
+        assert!(truncated.ends_with(&marker));
+        assert_eq!(
+            truncated.len(),
+            DEFAULT_TOOL_RESULT_MAX_BYTES + marker.len()
+        );
+    }
+
+    #[test]
+    fn test_truncate_tool_result_respects_char_boundaries() {
+        // Each snowman is three bytes, so a 4-byte limit lands mid-char
+        let truncated = truncate_tool_result("☃☃☃", 4);
+        assert_eq!(truncated, "☃\n\n[truncated 6 bytes]");
+    }
+
+    #[test]
+    fn test_tool_result_limit_defaults_when_missing_from_config() {
+        let config: MuxConfig =
+            serde_json::from_str(r#"{"model": "m", "working_dir": "/tmp"}"#).unwrap();
+        assert_eq!(config.tool_result_max_bytes, DEFAULT_TOOL_RESULT_MAX_BYTES);
+        assert!(!config.tool_cache.enabled);
+        assert_eq!(config.max_parallel_tools, DEFAULT_MAX_PARALLEL_TOOLS);
+        assert!(config.confirm_messages.is_empty());
+    }
+
+    #[test]
+    fn test_from_settings_keeps_configured_confirm_messages() {
+        let settings: crate::config::Config = toml::from_str(
+            r#"
+            [mux.confirm_messages]
+            bash = "Run this shell command?"
+            "#,
+        )
+        .unwrap();
+        let config = MuxConfig::from_settings(&settings.mux, std::path::Path::new("/tmp"));
+        assert_eq!(
+            config.confirm_messages.get("bash").map(String::as_str),
+            Some("Run this shell command?")
+        );
+    }
+
+    #[test]
+    fn test_session_from_history_merges_unanswered_turns() {
+        let turn = |role: &str, content: &str| crate::store::Message {
+            id: 0,
+            thread_id: "thread-1".to_string(),
+            role: role.to_string(),
+            content: content.to_string(),
+            created_at: chrono::Utc::now(),
+            reply_to_message_id: None,
+        };
+        let history = [
+            turn("user", "first"),
+            turn("user", "retry"),
+            turn("assistant", "answer"),
+            turn("assistant", ""),
+        ];
+
+        let session = MuxSession::from_history(None, &history);
+
+        assert_eq!(session.messages.len(), 2);
+        assert!(matches!(session.messages[0].role, Role::User));
+        assert_eq!(session.messages[0].content.len(), 2);
+        assert!(matches!(session.messages[1].role, Role::Assistant));
+        assert_eq!(session.messages[1].content.len(), 1);
+    }
+
+    #[test]
+    fn test_trim_keeps_whole_turns() {
+        let text = |role: Role| Message {
+            role,
+            content: vec![ContentBlock::Text {
+                text: "hi".to_string(),
+            }],
+        };
+        let tool_result = Message {
+            role: Role::User,
+            content: vec![ContentBlock::ToolResult {
+                tool_use_id: "tool-1".to_string(),
+                content: "done".to_string(),
+                is_error: false,
+            }],
+        };
+        let mut session = MuxSession::new(None);
+        session.messages = vec![
+            text(Role::User),
+            text(Role::Assistant),
+            tool_result.clone(),
+            text(Role::Assistant),
+            text(Role::User),
+            text(Role::Assistant),
+        ];
+
+        // The last three start at a tool result, so only the last turn stays
+        assert_eq!(session.trim(3), 4);
+        assert_eq!(session.messages.len(), 2);
+        assert!(matches!(session.messages[0].role, Role::User));
+
+        // A turn longer than the bound is kept whole
+        let mut session = MuxSession::new(None);
+        session.messages = vec![
+            text(Role::User),
+            text(Role::Assistant),
+            tool_result,
+            text(Role::Assistant),
+        ];
+        assert_eq!(session.trim(2), 0);
+        assert_eq!(session.messages.len(), 4);
+    }
+
+    /// Calls in flight across the probe tools of one test
+    struct Probe {
+        running: std::sync::atomic::AtomicUsize,
+        peak: std::sync::atomic::AtomicUsize,
+        meet: tokio::sync::Barrier,
+    }
+
+    /// Echoes its input's `label`, noting how many probe calls run at once.
+    /// Calls with `meet` set wait for the test's other meeting calls.
+    struct ProbeTool {
+        name: &'static str,
+        probe: Arc<Probe>,
+    }
+
+    #[async_trait]
+    impl mux::tool::Tool for ProbeTool {
+        fn name(&self) -> &str {
+            self.name
+        }
+
+        fn description(&self) -> &str {
+            "Echo, noting overlapping calls"
+        }
+
+        fn schema(&self) -> serde_json::Value {
+            serde_json::json!({"type": "object"})
+        }
+
+        async fn execute(
+            &self,
+            params: serde_json::Value,
+        ) -> Result<mux::tool::ToolResult, anyhow::Error> {
+            use std::sync::atomic::Ordering;
+            let running = self.probe.running.fetch_add(1, Ordering::SeqCst) + 1;
+            self.probe.peak.fetch_max(running, Ordering::SeqCst);
+            if params["meet"].as_bool().unwrap_or(false) {
+                self.probe.meet.wait().await;
+            } else {
+                // Give any call running alongside a chance to start
+                tokio::task::yield_now().await;
+            }
+            self.probe.running.fetch_sub(1, Ordering::SeqCst);
+            Ok(mux::tool::ToolResult::text(
+                params["label"].as_str().unwrap_or_default().to_string(),
+            ))
+        }
+    }
+
+    /// A call to the probe tool `tool`; `read` is read-only, `guarded_read`
+    /// is read-only but needs approval, and `write` is neither
+    fn probe_call(tool: &str, id: &str, meet: bool) -> (String, String, serde_json::Value) {
+        (
+            id.to_string(),
+            tool.to_string(),
+            serde_json::json!({"label": format!("done {}", id), "meet": meet}),
+        )
+    }
+
+    /// Tool use IDs and contents of result blocks, in order
+    fn results(blocks: &[ContentBlock]) -> Vec<(String, String)> {
+        blocks
+            .iter()
+            .map(|block| match block {
+                ContentBlock::ToolResult {
+                    tool_use_id,
+                    content,
+                    ..
+                } => (tool_use_id.clone(), content.clone()),
+                other => panic!("not a tool result: {:?}", other),
+            })
+            .collect()
+    }
+
+    /// IDs of ToolResult events in the order they were emitted, checking
+    /// each carries its own call's output
+    fn result_events(rx: &mut tokio::sync::mpsc::Receiver<BackendEvent>) -> Vec<String> {
+        let mut ids = Vec::new();
+        while let Ok(event) = rx.try_recv() {
+            if let BackendEvent::ToolResult { id, output, .. } = event {
+                assert_eq!(output, format!("done {}", id));
+                ids.push(id);
+            }
+        }
+        ids
+    }
+
+    /// Run `calls` against the probe tools, `meeting` of which set `meet`.
+    /// Returns the result blocks, the most calls that ran at once, and the
+    /// emitted events.
+    async fn run_calls(
+        max_parallel_tools: usize,
+        approval_callback: Option<&ApprovalCallback>,
+        meeting: usize,
+        calls: Vec<(String, String, serde_json::Value)>,
+    ) -> (
+        Vec<ContentBlock>,
+        usize,
+        tokio::sync::mpsc::Receiver<BackendEvent>,
+    ) {
+        let probe = Arc::new(Probe {
+            running: Default::default(),
+            peak: Default::default(),
+            meet: tokio::sync::Barrier::new(meeting),
+        });
+        let registry = Registry::new();
+        for name in ["read", "guarded_read", "write"] {
+            registry
+                .register(ProbeTool {
+                    name,
+                    probe: probe.clone(),
+                })
+                .await;
+        }
+        let config = MuxConfig {
+            max_parallel_tools,
+            ..MuxConfig::default()
+        };
+        let tool_cache = ToolCache::from_config(&ToolCacheConfig {
+            enabled: true,
+            tools: vec!["read".to_string(), "guarded_read".to_string()],
+            ..ToolCacheConfig::default()
+        })
+        .unwrap();
+        let dangerous_tools: HashSet<String> = ["guarded_read".to_string()].into();
+        let (tx, rx) = tokio::sync::mpsc::channel(64);
+        let calls_ctx = ToolCalls {
+            registry: &registry,
+            config: &config,
+            session_id: "session",
+            event_tx: &tx,
+            approval_callback,
+            dangerous_tools: &dangerous_tools,
+            confirm_messages: &HashMap::new(),
+            tool_cache: Some(&tool_cache),
+            tool_retry: None,
+        };
+        // Meeting calls that can't overlap never finish
+        let blocks = tokio::time::timeout(Duration::from_secs(5), calls_ctx.run_all(calls))
+            .await
+            .expect("meeting calls didn't run at once");
+        let peak = probe.peak.load(std::sync::atomic::Ordering::SeqCst);
+        (blocks, peak, rx)
+    }
+
+    #[tokio::test]
+    async fn test_read_only_calls_overlap_and_keep_call_order() {
+        let calls = vec![
+            probe_call("read", "a", true),
+            probe_call("read", "b", true),
+            probe_call("read", "c", true),
+        ];
+        let (blocks, peak, mut rx) = run_calls(4, None, 3, calls).await;
+
+        assert_eq!(peak, 3);
+        assert_eq!(
+            results(&blocks),
+            [
+                ("a".to_string(), "done a".to_string()),
+                ("b".to_string(), "done b".to_string()),
+                ("c".to_string(), "done c".to_string()),
+            ]
+        );
+        assert_eq!(result_events(&mut rx).len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_max_parallel_tools_of_one_runs_calls_in_turn() {
+        let calls = vec![
+            probe_call("read", "a", false),
+            probe_call("read", "b", false),
+        ];
+        let (blocks, peak, mut rx) = run_calls(1, None, 0, calls).await;
+        assert_eq!(peak, 1);
+        assert_eq!(results(&blocks).len(), 2);
+        assert_eq!(result_events(&mut rx), ["a", "b"]);
+    }
+
+    #[tokio::test]
+    async fn test_mutating_calls_run_alone() {
+        let calls = vec![
+            probe_call("read", "a", true),
+            probe_call("read", "b", true),
+            probe_call("write", "c", false),
+            probe_call("read", "d", false),
+        ];
+        let (blocks, peak, mut rx) = run_calls(4, None, 2, calls).await;
+
+        // Only the reads before the write ran together
+        assert_eq!(peak, 2);
+        let events = result_events(&mut rx);
+        assert_eq!(events[2..], ["c", "d"]);
+        let ids: Vec<String> = results(&blocks).into_iter().map(|(id, _)| id).collect();
+        assert_eq!(ids, ["a", "b", "c", "d"]);
+    }
+
+    #[tokio::test]
+    async fn test_calls_needing_approval_run_in_turn() {
+        // "c" is denied; the others are approved
+        let callback: ApprovalCallback =
+            Arc::new(|tool_id, _tool_name, _tool_input| Box::pin(async move { tool_id != "c" }));
+        let calls = vec![
+            probe_call("guarded_read", "a", false),
+            probe_call("guarded_read", "b", false),
+            probe_call("guarded_read", "c", false),
+        ];
+        let (blocks, peak, mut rx) = run_calls(4, Some(&callback), 0, calls).await;
+
+        let mut events = Vec::new();
+        while let Ok(event) = rx.try_recv() {
+            match event {
+                BackendEvent::ToolApprovalRequest { id, .. } => events.push(format!("ask {}", id)),
+                BackendEvent::ToolResult { id, .. } => events.push(format!("done {}", id)),
+                _ => {}
+            }
+        }
+        assert_eq!(
+            events,
+            ["ask a", "done a", "ask b", "done b", "ask c", "done c"]
+        );
+        assert_eq!(peak, 1);
+
+        let results = results(&blocks);
+        assert_eq!(results[0], ("a".to_string(), "done a".to_string()));
+        assert_eq!(results[1], ("b".to_string(), "done b".to_string()));
+        assert_eq!(
+            results[2],
+            ("c".to_string(), "Tool execution denied by user".to_string())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_cached_calls_still_need_approval() {
+        // The second call could be answered from the cache, but is denied
+        let callback: ApprovalCallback = Arc::new(|tool_id, _tool_name, _tool_input| {
+            Box::pin(async move { tool_id == "first" })
+        });
+        let input = serde_json::json!({"label": "done first"});
+        let calls = vec![
+            (
+                "first".to_string(),
+                "guarded_read".to_string(),
+                input.clone(),
+            ),
+            ("second".to_string(), "guarded_read".to_string(), input),
+        ];
+        let (blocks, _, mut rx) = run_calls(4, Some(&callback), 0, calls).await;
+
+        let mut approvals = Vec::new();
+        while let Ok(event) = rx.try_recv() {
+            if let BackendEvent::ToolApprovalRequest { id, .. } = event {
+                approvals.push(id);
+            }
+        }
+        assert_eq!(approvals, ["first", "second"]);
+        assert_eq!(
+            results(&blocks),
+            [
+                ("first".to_string(), "done first".to_string()),
+                (
+                    "second".to_string(),
+                    "Tool execution denied by user".to_string()
+                ),
+            ]
+        );
+    }
+
+    /// Fails with a transient error until it has been called `fail_times`
+    /// times
+    struct FlakyTool {
+        name: &'static str,
+        fail_times: u32,
+        calls: std::sync::atomic::AtomicU32,
+    }
+
+    #[async_trait]
+    impl mux::tool::Tool for FlakyTool {
+        fn name(&self) -> &str {
+            self.name
+        }
+
+        fn description(&self) -> &str {
+            "Fail, then succeed"
+        }
+
+        fn schema(&self) -> serde_json::Value {
+            serde_json::json!({"type": "object"})
+        }
+
+        async fn execute(
+            &self,
+            _params: serde_json::Value,
+        ) -> Result<mux::tool::ToolResult, anyhow::Error> {
+            let call = self.calls.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            if call < self.fail_times {
+                return Ok(mux::tool::ToolResult::error(
+                    "connection reset by peer".to_string(),
+                ));
+            }
+            Ok(mux::tool::ToolResult::text("fetched".to_string()))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_transient_tool_failures_are_retried() {
+        let registry = Registry::new();
+        for name in ["web_fetch", "bash"] {
+            registry
+                .register(FlakyTool {
+                    name,
+                    fail_times: 2,
+                    calls: Default::default(),
+                })
+                .await;
+        }
+        let config = MuxConfig::default();
+        let retry = ToolRetry::from_config(&ToolRetryConfig {
+            tools: vec!["web_fetch".to_string()],
+            backoff_ms: 1,
+            ..Default::default()
+        })
+        .unwrap();
+        let (tx, mut rx) = tokio::sync::mpsc::channel(64);
+        let calls_ctx = ToolCalls {
+            registry: &registry,
+            config: &config,
+            session_id: "session",
+            event_tx: &tx,
+            approval_callback: None,
+            dangerous_tools: &HashSet::new(),
+            confirm_messages: &HashMap::new(),
+            tool_cache: None,
+            tool_retry: Some(&retry),
+        };
+        let call = |id: &str, name: &str| (id.to_string(), name.to_string(), serde_json::json!({}));
+
+        let blocks = calls_ctx
+            .run_all(vec![call("a", "web_fetch"), call("b", "bash")])
+            .await;
+
+        // The model sees one result per call; only the matching tool retried
+        assert_eq!(
+            results(&blocks),
+            [
+                (
+                    "a".to_string(),
+                    "fetched\n\n(succeeded after 2 retries)".to_string()
+                ),
+                ("b".to_string(), "connection reset by peer".to_string()),
+            ]
+        );
+        let mut retries = Vec::new();
+        let mut finished = Vec::new();
+        while let Ok(event) = rx.try_recv() {
+            match event {
+                BackendEvent::ToolState {
+                    id,
+                    detail: Some(detail),
+                    ..
+                } => retries.push((id, detail)),
+                BackendEvent::ToolResult { id, is_error, .. } => finished.push((id, is_error)),
+                _ => {}
+            }
+        }
+        assert_eq!(
+            retries,
+            [
+                (
+                    "a".to_string(),
+                    "attempt 1 of 3 failed, retrying: connection reset by peer".to_string()
+                ),
+                (
+                    "a".to_string(),
+                    "attempt 2 of 3 failed, retrying: connection reset by peer".to_string()
+                ),
+            ]
+        );
+        assert_eq!(
+            finished,
+            [("b".to_string(), true), ("a".to_string(), false)]
+        );
+    }
 }
@@ -0,0 +1,309 @@
+// ABOUTME: Scripted backend that replays fixed BackendEvent sequences, for deterministic tests
+// ABOUTME: Built with ReplayBuilder or loaded from a JSON fixture; only with the `testing` feature
+
+use super::{Backend, BackendEvent, ToolStateKind};
+use crate::types::RequestOverrides;
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use futures::stream::BoxStream;
+use futures::StreamExt;
+use std::collections::VecDeque;
+use std::path::Path;
+use std::sync::Mutex;
+
+/// One request a `ReplayBackend` received
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReplayRequest {
+    pub session_id: String,
+    pub message: String,
+    pub is_new_session: bool,
+    pub overrides: RequestOverrides,
+}
+
+/// Backend that answers each request with the next scripted turn of events.
+///
+/// Turns are used in order, one per `send`; a request after the last turn
+/// is an error, so a test can't silently run past its script.
+pub struct ReplayBackend {
+    turns: Mutex<VecDeque<Vec<BackendEvent>>>,
+    requests: Mutex<Vec<ReplayRequest>>,
+}
+
+impl ReplayBackend {
+    /// Start scripting turns
+    pub fn builder() -> ReplayBuilder {
+        ReplayBuilder::default()
+    }
+
+    /// Replay the given turns
+    pub fn new(turns: Vec<Vec<BackendEvent>>) -> Self {
+        Self {
+            turns: Mutex::new(turns.into()),
+            requests: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Parse a fixture: a JSON array of turns, each an array of serialized
+    /// `BackendEvent`s, e.g. `[[{"Text": "Hi"}, {"Done": {"full_response": "Hi"}}]]`
+    pub fn from_json(json: &str) -> Result<Self> {
+        let turns = serde_json::from_str(json).context("invalid replay fixture")?;
+        Ok(Self::new(turns))
+    }
+
+    /// Read a fixture file in the format of `from_json`
+    pub fn from_file(path: &Path) -> Result<Self> {
+        let json = std::fs::read_to_string(path)
+            .with_context(|| format!("reading replay fixture: {}", path.display()))?;
+        Self::from_json(&json).with_context(|| format!("in {}", path.display()))
+    }
+
+    /// Requests received so far, oldest first
+    pub fn requests(&self) -> Vec<ReplayRequest> {
+        self.requests.lock().unwrap().clone()
+    }
+
+    /// Turns not yet replayed
+    pub fn remaining(&self) -> usize {
+        self.turns.lock().unwrap().len()
+    }
+}
+
+#[async_trait]
+impl Backend for ReplayBackend {
+    fn name(&self) -> &'static str {
+        "replay"
+    }
+
+    async fn send(
+        &self,
+        session_id: &str,
+        message: &str,
+        is_new_session: bool,
+    ) -> Result<BoxStream<'static, BackendEvent>> {
+        self.send_with_overrides(
+            session_id,
+            message,
+            is_new_session,
+            &RequestOverrides::default(),
+        )
+        .await
+    }
+
+    async fn send_with_overrides(
+        &self,
+        session_id: &str,
+        message: &str,
+        is_new_session: bool,
+        overrides: &RequestOverrides,
+    ) -> Result<BoxStream<'static, BackendEvent>> {
+        let mut requests = self.requests.lock().unwrap();
+        requests.push(ReplayRequest {
+            session_id: session_id.to_string(),
+            message: message.to_string(),
+            is_new_session,
+            overrides: overrides.clone(),
+        });
+        let turn = self.turns.lock().unwrap().pop_front().with_context(|| {
+            format!(
+                "replay script exhausted: no turn left for request {}",
+                requests.len()
+            )
+        })?;
+        Ok(futures::stream::iter(turn).boxed())
+    }
+}
+
+/// Scripts the turns of a `ReplayBackend`. Events go into the current turn;
+/// `next_turn` starts another.
+#[derive(Default)]
+pub struct ReplayBuilder {
+    turns: Vec<Vec<BackendEvent>>,
+}
+
+impl ReplayBuilder {
+    /// Append any event to the current turn
+    pub fn event(mut self, event: BackendEvent) -> Self {
+        if self.turns.is_empty() {
+            self.turns.push(Vec::new());
+        }
+        self.turns.last_mut().unwrap().push(event);
+        self
+    }
+
+    /// Start the next turn
+    pub fn next_turn(mut self) -> Self {
+        self.turns.push(Vec::new());
+        self
+    }
+
+    pub fn thinking(self) -> Self {
+        self.event(BackendEvent::Thinking)
+    }
+
+    pub fn session_init(self, session_id: &str) -> Self {
+        self.event(BackendEvent::SessionInit {
+            session_id: session_id.to_string(),
+        })
+    }
+
+    pub fn text(self, text: &str) -> Self {
+        self.event(BackendEvent::Text(text.to_string()))
+    }
+
+    pub fn tool_use(self, id: &str, name: &str, input: serde_json::Value) -> Self {
+        self.event(BackendEvent::ToolUse {
+            id: id.to_string(),
+            name: name.to_string(),
+            input,
+        })
+    }
+
+    /// Ask for approval of a tool, moving it to awaiting approval
+    pub fn tool_approval(self, id: &str, name: &str, input: serde_json::Value) -> Self {
+        self.event(BackendEvent::ToolApprovalRequest {
+            id: id.to_string(),
+            name: name.to_string(),
+            input,
+            confirm_message: None,
+        })
+        .tool_state(id, ToolStateKind::AwaitingApproval)
+    }
+
+    pub fn tool_state(self, id: &str, state: ToolStateKind) -> Self {
+        self.event(BackendEvent::ToolState {
+            id: id.to_string(),
+            state,
+            detail: None,
+        })
+    }
+
+    pub fn tool_result(self, id: &str, output: &str, is_error: bool) -> Self {
+        self.event(BackendEvent::ToolResult {
+            id: id.to_string(),
+            output: output.to_string(),
+            is_error,
+        })
+    }
+
+    pub fn usage(self, input_tokens: i32, output_tokens: i32) -> Self {
+        self.event(BackendEvent::Usage {
+            input_tokens,
+            output_tokens,
+            cache_read_tokens: 0,
+            cache_write_tokens: 0,
+            thinking_tokens: 0,
+        })
+    }
+
+    pub fn error(self, message: &str) -> Self {
+        self.event(BackendEvent::Error(message.to_string()))
+    }
+
+    /// End the current turn with the text it streamed as the full response
+    pub fn done(self) -> Self {
+        let full_response = self
+            .turns
+            .last()
+            .into_iter()
+            .flatten()
+            .filter_map(|event| match event {
+                BackendEvent::Text(text) => Some(text.as_str()),
+                _ => None,
+            })
+            .collect();
+        self.event(BackendEvent::Done { full_response })
+    }
+
+    /// The scripted turns, for writing a fixture
+    pub fn turns(&self) -> &[Vec<BackendEvent>] {
+        &self.turns
+    }
+
+    pub fn build(self) -> ReplayBackend {
+        ReplayBackend::new(self.turns)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(events: &[BackendEvent]) -> Vec<&'static str> {
+        events
+            .iter()
+            .map(|e| match e {
+                BackendEvent::Text(_) => "text",
+                BackendEvent::ToolUse { .. } => "tool_use",
+                BackendEvent::ToolApprovalRequest { .. } => "approval",
+                BackendEvent::ToolState { .. } => "tool_state",
+                BackendEvent::ToolResult { .. } => "tool_result",
+                BackendEvent::Done { .. } => "done",
+                _ => "other",
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_turns_replay_in_order_then_run_out() {
+        let backend = ReplayBackend::builder()
+            .text("Let me ")
+            .text("look.")
+            .tool_use("t1", "read_file", serde_json::json!({"path": "a"}))
+            .tool_approval("t1", "read_file", serde_json::json!({"path": "a"}))
+            .tool_result("t1", "contents", false)
+            .done()
+            .next_turn()
+            .text("Again")
+            .done()
+            .build();
+
+        let first: Vec<_> = backend
+            .send("s1", "hi", true)
+            .await
+            .unwrap()
+            .collect()
+            .await;
+        assert_eq!(
+            names(&first),
+            [
+                "text",
+                "text",
+                "tool_use",
+                "approval",
+                "tool_state",
+                "tool_result",
+                "done"
+            ]
+        );
+        assert!(matches!(
+            first.last(),
+            Some(BackendEvent::Done { full_response }) if full_response == "Let me look."
+        ));
+        assert_eq!(backend.remaining(), 1);
+
+        backend.send("s1", "more", false).await.unwrap();
+        let err = backend.send("s1", "too many", false).await.err().unwrap();
+        assert!(err.to_string().contains("replay script exhausted"));
+
+        let requests = backend.requests();
+        assert_eq!(requests.len(), 3);
+        assert_eq!(requests[0].message, "hi");
+        assert!(requests[0].is_new_session);
+        assert!(!requests[1].is_new_session);
+    }
+
+    #[tokio::test]
+    async fn test_fixture_round_trips() {
+        let builder = ReplayBuilder::default().thinking().text("Hi").done();
+        let json = serde_json::to_string(builder.turns()).unwrap();
+
+        let backend = ReplayBackend::from_json(&json).unwrap();
+        let events: Vec<_> = backend.send("s", "m", true).await.unwrap().collect().await;
+        assert!(matches!(
+            events.as_slice(),
+            [BackendEvent::Thinking, BackendEvent::Text(t), BackendEvent::Done { .. }] if t == "Hi"
+        ));
+
+        assert!(ReplayBackend::from_json("{}").is_err());
+    }
+}
@@ -0,0 +1,359 @@
+// ABOUTME: Per-thread memoization of read-only tool results for the mux backend.
+// ABOUTME: Entries expire after a TTL and are dropped when a mutating tool of the same pack runs.
+
+use crate::config::ToolCacheConfig;
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// ToolState detail reported for results answered from the cache
+pub const CACHED_DETAIL: &str = "cached";
+
+/// Pack the mux backend's built-in tools belong to
+pub const BUILTIN_PACK: &str = "builtin";
+
+/// A cached result is only reused for the same thread, tool and input
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct CacheKey {
+    thread: String,
+    tool: String,
+    input: String,
+}
+
+#[derive(Debug)]
+struct CacheEntry {
+    output: String,
+    pack: String,
+    stored_at: Instant,
+    /// Insertion order, so the oldest entry is evicted first even when
+    /// several were stored within the clock's resolution
+    sequence: u64,
+}
+
+#[derive(Debug, Default)]
+struct CacheState {
+    entries: HashMap<CacheKey, CacheEntry>,
+    next_sequence: u64,
+    /// Times a mutating tool has run, so results of tools that ran
+    /// alongside one aren't stored
+    epoch: u64,
+}
+
+/// Tool results reused within a thread. Only tools listed as cacheable are
+/// stored; running any other tool clears what its pack has cached, in every
+/// thread, since it may have changed what those tools would return.
+#[derive(Debug)]
+pub struct ToolCache {
+    ttl: Duration,
+    max_entries: usize,
+    cacheable: HashSet<String>,
+    /// Packs of tools whose pack isn't their name prefix
+    packs: HashMap<String, String>,
+    state: Mutex<CacheState>,
+}
+
+impl ToolCache {
+    /// Build the cache from config, or None when caching is off
+    pub fn from_config(config: &ToolCacheConfig) -> Option<Self> {
+        if !config.enabled || config.max_entries == 0 {
+            return None;
+        }
+        Some(Self {
+            ttl: Duration::from_secs(config.ttl_secs),
+            max_entries: config.max_entries,
+            cacheable: config.tools.iter().cloned().collect(),
+            packs: HashMap::new(),
+            state: Mutex::new(CacheState::default()),
+        })
+    }
+
+    /// Place `tools` in `pack` instead of the pack named by their prefix
+    pub fn with_pack<'a>(mut self, pack: &str, tools: impl IntoIterator<Item = &'a str>) -> Self {
+        for tool in tools {
+            self.packs.insert(tool.to_string(), pack.to_string());
+        }
+        self
+    }
+
+    /// Whether results of `tool` are kept
+    pub fn is_cacheable(&self, tool: &str) -> bool {
+        self.cacheable.contains(tool)
+    }
+
+    /// The pack `tool` belongs to: its configured pack, else its name up to
+    /// the first underscore (`todo_list` and `todo_add` are both `todo`)
+    pub fn pack_of<'a>(&'a self, tool: &'a str) -> &'a str {
+        if let Some(pack) = self.packs.get(tool) {
+            return pack;
+        }
+        tool.split_once('_').map_or(tool, |(prefix, _)| prefix)
+    }
+
+    /// The cached result of calling `tool` with `input` in `thread`, if it
+    /// hasn't expired
+    pub fn get(&self, thread: &str, tool: &str, input: &Value) -> Option<String> {
+        if !self.is_cacheable(tool) {
+            return None;
+        }
+        let key = CacheKey {
+            thread: thread.to_string(),
+            tool: tool.to_string(),
+            input: canonical_json(input),
+        };
+        let mut state = self.state.lock().unwrap();
+        let entries = &mut state.entries;
+        match entries.get(&key) {
+            Some(entry) if entry.stored_at.elapsed() < self.ttl => Some(entry.output.clone()),
+            Some(_) => {
+                entries.remove(&key);
+                None
+            }
+            None => None,
+        }
+    }
+
+    /// Counter that moves on whenever a mutating tool runs; see `record_since`
+    pub fn epoch(&self) -> u64 {
+        self.state.lock().unwrap().epoch
+    }
+
+    /// Note that `tool` ran in `thread`. Successful results of cacheable
+    /// tools are stored; any other tool invalidates its pack's entries.
+    pub fn record(&self, thread: &str, tool: &str, input: &Value, output: &str, is_error: bool) {
+        self.record_since(self.epoch(), thread, tool, input, output, is_error);
+    }
+
+    /// Like `record` for a tool that started at `epoch`. Its result isn't
+    /// stored if a mutating tool ran meanwhile, as it may predate the change.
+    pub fn record_since(
+        &self,
+        epoch: u64,
+        thread: &str,
+        tool: &str,
+        input: &Value,
+        output: &str,
+        is_error: bool,
+    ) {
+        if !self.is_cacheable(tool) {
+            self.invalidate_pack(self.pack_of(tool));
+            return;
+        }
+        if is_error {
+            return;
+        }
+        let mut state = self.state.lock().unwrap();
+        if state.epoch != epoch {
+            return;
+        }
+        let ttl = self.ttl;
+        if state.entries.len() >= self.max_entries {
+            state
+                .entries
+                .retain(|_, entry| entry.stored_at.elapsed() < ttl);
+        }
+        if state.entries.len() >= self.max_entries {
+            let oldest = state
+                .entries
+                .iter()
+                .min_by_key(|(_, entry)| entry.sequence)
+                .map(|(key, _)| key.clone());
+            if let Some(oldest) = oldest {
+                state.entries.remove(&oldest);
+            }
+        }
+        let sequence = state.next_sequence;
+        state.next_sequence += 1;
+        state.entries.insert(
+            CacheKey {
+                thread: thread.to_string(),
+                tool: tool.to_string(),
+                input: canonical_json(input),
+            },
+            CacheEntry {
+                output: output.to_string(),
+                pack: self.pack_of(tool).to_string(),
+                stored_at: Instant::now(),
+                sequence,
+            },
+        );
+    }
+
+    /// Drop every cached result of tools in `pack`
+    pub fn invalidate_pack(&self, pack: &str) {
+        let mut state = self.state.lock().unwrap();
+        state.epoch += 1;
+        let entries = &mut state.entries;
+        let before = entries.len();
+        entries.retain(|_, entry| entry.pack != pack);
+        let dropped = before - entries.len();
+        if dropped > 0 {
+            tracing::debug!(pack = %pack, dropped, "Invalidated cached tool results");
+        }
+    }
+
+    /// Cached results currently held, expired ones included
+    pub fn len(&self) -> usize {
+        self.state.lock().unwrap().entries.len()
+    }
+
+    /// True when nothing is cached
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// Serialize `value` with object keys sorted at every level, so inputs that
+/// differ only in key order share a cache entry
+pub fn canonical_json(value: &Value) -> String {
+    fn sorted(value: &Value) -> Value {
+        match value {
+            Value::Object(map) => {
+                let mut keys: Vec<&String> = map.keys().collect();
+                keys.sort();
+                let mut out = serde_json::Map::new();
+                for key in keys {
+                    out.insert(key.clone(), sorted(&map[key]));
+                }
+                Value::Object(out)
+            }
+            Value::Array(items) => Value::Array(items.iter().map(sorted).collect()),
+            other => other.clone(),
+        }
+    }
+    sorted(value).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn cache(tools: &[&str]) -> ToolCache {
+        ToolCache::from_config(&ToolCacheConfig {
+            enabled: true,
+            ttl_secs: 300,
+            max_entries: 8,
+            tools: tools.iter().map(|t| t.to_string()).collect(),
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn test_canonical_json_ignores_key_order() {
+        let a = json!({"url": "https://example.com", "options": {"b": 1, "a": [{"y": 2, "x": 1}]}});
+        let b = json!({"options": {"a": [{"x": 1, "y": 2}], "b": 1}, "url": "https://example.com"});
+        assert_eq!(canonical_json(&a), canonical_json(&b));
+        assert_eq!(
+            canonical_json(&b),
+            r#"{"options":{"a":[{"x":1,"y":2}],"b":1},"url":"https://example.com"}"#
+        );
+        // Array order still matters
+        assert_ne!(
+            canonical_json(&json!([1, 2])),
+            canonical_json(&json!([2, 1]))
+        );
+    }
+
+    #[test]
+    fn test_hits_are_per_thread_and_input() {
+        let cache = cache(&["todo_list"]);
+        let input = json!({"status": "open", "limit": 5});
+        cache.record("thread-1", "todo_list", &input, "3 todos", false);
+
+        let reordered = json!({"limit": 5, "status": "open"});
+        assert_eq!(
+            cache.get("thread-1", "todo_list", &reordered).as_deref(),
+            Some("3 todos")
+        );
+        assert_eq!(cache.get("thread-2", "todo_list", &input), None);
+        assert_eq!(cache.get("thread-1", "todo_list", &json!({})), None);
+    }
+
+    #[test]
+    fn test_errors_and_uncacheable_tools_are_not_stored() {
+        let cache = cache(&["todo_list"]);
+        cache.record("t", "todo_list", &json!({}), "backend down", true);
+        cache.record("t", "bash", &json!({"command": "ls"}), "files", false);
+        assert!(cache.is_empty());
+        assert_eq!(cache.get("t", "bash", &json!({"command": "ls"})), None);
+    }
+
+    #[test]
+    fn test_mutating_tool_invalidates_its_pack() {
+        let cache = cache(&["todo_list", "note_list"]);
+        cache.record("t1", "todo_list", &json!({}), "no todos", false);
+        cache.record("t2", "todo_list", &json!({}), "no todos", false);
+        cache.record("t1", "note_list", &json!({}), "no notes", false);
+
+        // Another pack's tool leaves todos alone
+        cache.record("t1", "note_add", &json!({"text": "hi"}), "added", false);
+        assert_eq!(cache.get("t1", "note_list", &json!({})), None);
+        assert!(cache.get("t1", "todo_list", &json!({})).is_some());
+
+        // A failed mutation may still have changed something
+        cache.record("t1", "todo_add", &json!({"title": "x"}), "timed out", true);
+        assert_eq!(cache.get("t1", "todo_list", &json!({})), None);
+        assert_eq!(cache.get("t2", "todo_list", &json!({})), None);
+        assert!(cache.is_empty());
+    }
+
+    #[test]
+    fn test_results_racing_a_mutation_are_not_stored() {
+        let cache = cache(&["todo_list"]);
+        let started = cache.epoch();
+        cache.record("t", "todo_done", &json!({"id": 1}), "done", false);
+        cache.record_since(started, "t", "todo_list", &json!({}), "1 open", false);
+        assert!(cache.is_empty());
+
+        cache.record_since(cache.epoch(), "t", "todo_list", &json!({}), "0 open", false);
+        assert_eq!(
+            cache.get("t", "todo_list", &json!({})).as_deref(),
+            Some("0 open")
+        );
+    }
+
+    #[test]
+    fn test_configured_packs_override_prefixes() {
+        let cache = cache(&["web_fetch"]).with_pack(BUILTIN_PACK, ["web_fetch", "bash"]);
+        assert_eq!(cache.pack_of("bash"), BUILTIN_PACK);
+        assert_eq!(cache.pack_of("mcp_list_resources"), "mcp");
+        assert_eq!(cache.pack_of("standalone"), "standalone");
+
+        cache.record("t", "web_fetch", &json!({"url": "u"}), "page", false);
+        cache.record(
+            "t",
+            "bash",
+            &json!({"command": "curl -X POST u"}),
+            "",
+            false,
+        );
+        assert!(cache.is_empty());
+    }
+
+    #[test]
+    fn test_expired_and_excess_entries_are_dropped() {
+        let expired = ToolCache::from_config(&ToolCacheConfig {
+            enabled: true,
+            ttl_secs: 0,
+            max_entries: 8,
+            tools: vec!["todo_list".to_string()],
+        })
+        .unwrap();
+        expired.record("t", "todo_list", &json!({}), "old", false);
+        assert_eq!(expired.get("t", "todo_list", &json!({})), None);
+
+        let cache = cache(&["todo_list"]);
+        for i in 0..10 {
+            cache.record("t", "todo_list", &json!({ "page": i }), "page", false);
+        }
+        assert_eq!(cache.len(), 8);
+        assert_eq!(cache.get("t", "todo_list", &json!({"page": 0})), None);
+        assert!(cache.get("t", "todo_list", &json!({"page": 9})).is_some());
+    }
+
+    #[test]
+    fn test_disabled_config_builds_nothing() {
+        assert!(ToolCache::from_config(&ToolCacheConfig::default()).is_none());
+    }
+}
@@ -0,0 +1,73 @@
+// ABOUTME: Lets a tool running under the mux backend report progress on its own tool call.
+// ABOUTME: The backend scopes each tool execution; reports become Running ToolState events.
+
+use super::{BackendEvent, ToolStateKind};
+use std::future::Future;
+use tokio::sync::mpsc;
+
+/// The tool call currently executing, and where its events go.
+#[derive(Clone)]
+struct ProgressSink {
+    tool_id: String,
+    event_tx: mpsc::Sender<BackendEvent>,
+}
+
+tokio::task_local! {
+    static TOOL_PROGRESS: ProgressSink;
+}
+
+/// Run a tool execution so that `report_tool_progress` calls inside it are
+/// emitted as ToolState events for `tool_id`.
+pub(crate) async fn with_tool_progress<F: Future>(
+    tool_id: String,
+    event_tx: mpsc::Sender<BackendEvent>,
+    execution: F,
+) -> F::Output {
+    TOOL_PROGRESS
+        .scope(ProgressSink { tool_id, event_tx }, execution)
+        .await
+}
+
+/// Report that the tool call being executed is still running, with a short
+/// status. Outside a mux backend tool execution this does nothing.
+pub async fn report_tool_progress(detail: impl Into<String>) {
+    let Ok(sink) = TOOL_PROGRESS.try_with(ProgressSink::clone) else {
+        return;
+    };
+    let _ = sink
+        .event_tx
+        .send(BackendEvent::ToolState {
+            id: sink.tool_id,
+            state: ToolStateKind::Running,
+            detail: Some(detail.into()),
+        })
+        .await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_progress_inside_scope_becomes_tool_state() {
+        let (tx, mut rx) = mpsc::channel(4);
+        with_tool_progress("tool-1".to_string(), tx, async {
+            report_tool_progress("halfway").await;
+        })
+        .await;
+
+        match rx.recv().await.unwrap() {
+            BackendEvent::ToolState { id, state, detail } => {
+                assert_eq!(id, "tool-1");
+                assert_eq!(state, ToolStateKind::Running);
+                assert_eq!(detail.as_deref(), Some("halfway"));
+            }
+            other => panic!("unexpected event: {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_progress_outside_scope_is_ignored() {
+        report_tool_progress("nobody is listening").await;
+    }
+}
@@ -0,0 +1,156 @@
+// ABOUTME: Retry policy for mux backend tool calls that fail with transient errors.
+// ABOUTME: Retries only tools listed by name as idempotent, matches failures by pattern, and notes retries in the model's result.
+
+use crate::config::ToolRetryConfig;
+use std::collections::HashSet;
+use std::time::Duration;
+
+/// Which failed tool calls are run again, how often, and how long to wait
+/// between attempts. Only the last attempt's result reaches the model.
+#[derive(Debug)]
+pub struct ToolRetry {
+    /// Tools listed as idempotent, safe to run twice
+    tools: HashSet<String>,
+    max_attempts: u32,
+    backoff: Duration,
+    /// Lowercased, so matching ignores case
+    transient_patterns: Vec<String>,
+}
+
+impl ToolRetry {
+    /// Build the policy from config, or None when no tool is retried
+    pub fn from_config(config: &ToolRetryConfig) -> Option<Self> {
+        // A failed call may still have done its work, so a tool is run again
+        // only when listed by its own name; no pattern stands in for that
+        let tools: HashSet<String> = config
+            .tools
+            .iter()
+            .filter(|tool| {
+                let pattern = tool.contains(['*', '?', '[']);
+                if pattern {
+                    tracing::warn!(tool = %tool, "Ignoring tool retry pattern; list idempotent tools by name");
+                }
+                !pattern
+            })
+            .cloned()
+            .collect();
+        if tools.is_empty() || config.max_attempts <= 1 {
+            return None;
+        }
+        Some(Self {
+            tools,
+            max_attempts: config.max_attempts,
+            backoff: Duration::from_millis(config.backoff_ms),
+            transient_patterns: config
+                .transient_patterns
+                .iter()
+                .map(|pattern| pattern.to_lowercase())
+                .collect(),
+        })
+    }
+
+    /// Most times a call runs, counting the first
+    pub fn max_attempts(&self) -> u32 {
+        self.max_attempts
+    }
+
+    /// Whether failures of `tool` are retried
+    pub fn applies_to(&self, tool: &str) -> bool {
+        self.tools.contains(tool)
+    }
+
+    /// Whether a failed call's `output` looks like it could succeed if run
+    /// again
+    pub fn is_transient(&self, output: &str) -> bool {
+        let output = output.to_lowercase();
+        self.transient_patterns
+            .iter()
+            .any(|pattern| output.contains(pattern.as_str()))
+    }
+
+    /// Wait before the retry following `attempt` (1 for the first call)
+    pub fn delay(&self, attempt: u32) -> Duration {
+        self.backoff
+            .saturating_mul(2u32.saturating_pow(attempt.saturating_sub(1)))
+    }
+}
+
+/// ToolState detail for a failed attempt that is about to be retried
+pub fn retry_detail(attempt: u32, max_attempts: u32, output: &str) -> String {
+    let reason = output.lines().next().unwrap_or_default();
+    format!(
+        "attempt {} of {} failed, retrying: {}",
+        attempt, max_attempts, reason
+    )
+}
+
+/// The result the model sees for a call that took `attempts` runs
+pub fn annotate_result(output: &str, is_error: bool, attempts: u32) -> String {
+    match (attempts, is_error) {
+        (0 | 1, _) => output.to_string(),
+        (attempts, false) => format!("{}\n\n(succeeded after {} retries)", output, attempts - 1),
+        (attempts, true) => format!("{}\n\n(failed after {} attempts)", output, attempts),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy(tools: &[&str]) -> Option<ToolRetry> {
+        ToolRetry::from_config(&ToolRetryConfig {
+            tools: tools.iter().map(|tool| tool.to_string()).collect(),
+            backoff_ms: 100,
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn test_matches_tools_by_name_and_failures_by_pattern() {
+        let retry = policy(&["web_fetch", "web_*"]).unwrap();
+        assert!(retry.applies_to("web_fetch"));
+        assert!(!retry.applies_to("web_search"));
+        assert!(!retry.applies_to("bash"));
+
+        assert!(retry.is_transient("Tool execution error: Connection Reset by peer"));
+        assert!(retry.is_transient("HTTP 503 Service Unavailable"));
+        assert!(retry.is_transient("upstream returned status: 502"));
+        assert!(!retry.is_transient("File not found: notes.md"));
+        // Status codes count only as status codes
+        assert!(!retry.is_transient("Issue #503 is closed"));
+        assert!(!retry.is_transient("wrote 504 bytes, then the disk filled"));
+    }
+
+    #[test]
+    fn test_backoff_doubles() {
+        let retry = policy(&["web_fetch"]).unwrap();
+        assert_eq!(retry.delay(1), Duration::from_millis(100));
+        assert_eq!(retry.delay(2), Duration::from_millis(200));
+        assert_eq!(retry.delay(3), Duration::from_millis(400));
+    }
+
+    #[test]
+    fn test_disabled_without_tools_or_retries() {
+        assert!(policy(&[]).is_none());
+        assert!(policy(&["*"]).is_none());
+        assert!(ToolRetry::from_config(&ToolRetryConfig {
+            tools: vec!["web_fetch".to_string()],
+            max_attempts: 1,
+            ..Default::default()
+        })
+        .is_none());
+    }
+
+    #[test]
+    fn test_annotate_result() {
+        assert_eq!(annotate_result("ok", false, 1), "ok");
+        assert_eq!(
+            annotate_result("ok", false, 3),
+            "ok\n\n(succeeded after 2 retries)"
+        );
+        assert_eq!(
+            annotate_result("timed out", true, 3),
+            "timed out\n\n(failed after 3 attempts)"
+        );
+    }
+}
@@ -0,0 +1,194 @@
+// ABOUTME: Slash commands the router answers itself instead of passing them to the backend
+// ABOUTME: Covers /reset, /model, /usage and /title; anything else starting with "/" is regular text
+
+use crate::store::BackendEventLog;
+
+/// A slash command recognized by the router
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SlashCommand {
+    /// Forget the backend session so the next message starts a fresh context
+    Reset,
+    /// Report which model answers in this thread, or choose one with the
+    /// text after the command ("default" goes back to the usual model)
+    Model,
+    /// Report the tokens used in this thread so far
+    Usage,
+    /// Set the thread's title to the text after the command, or regenerate
+    /// it when there is none
+    Title,
+}
+
+impl SlashCommand {
+    /// Parse a message as a slash command. Only the first word is matched, so
+    /// trailing text is ignored (except by `/model` and `/title`, see
+    /// `argument`); unknown
+    /// commands return None and are sent to the backend like any other message.
+    pub fn parse(content: &str) -> Option<Self> {
+        let name = content.trim_start().split_whitespace().next()?;
+        match name.to_ascii_lowercase().as_str() {
+            "/reset" => Some(Self::Reset),
+            "/model" => Some(Self::Model),
+            "/usage" => Some(Self::Usage),
+            "/title" => Some(Self::Title),
+            _ => None,
+        }
+    }
+
+    /// Text after the command word, trimmed
+    pub fn argument(content: &str) -> &str {
+        let content = content.trim_start();
+        let name_len = content.find(char::is_whitespace).unwrap_or(content.len());
+        content[name_len..].trim()
+    }
+}
+
+/// Token totals summed from a thread's logged usage events
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TokenUsage {
+    pub responses: u64,
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    pub cache_read_tokens: u64,
+    pub cache_write_tokens: u64,
+    pub thinking_tokens: u64,
+}
+
+impl TokenUsage {
+    /// Sum the "usage" events in a thread's event log
+    pub fn from_events(events: &[BackendEventLog]) -> Self {
+        let mut usage = Self::default();
+        for event in events.iter().filter(|e| e.event_type == "usage") {
+            let field = |key: &str| {
+                event.event_data[key]
+                    .as_i64()
+                    .map(|n| n.max(0) as u64)
+                    .unwrap_or(0)
+            };
+            usage.responses += 1;
+            usage.input_tokens += field("input_tokens");
+            usage.output_tokens += field("output_tokens");
+            usage.cache_read_tokens += field("cache_read_tokens");
+            usage.cache_write_tokens += field("cache_write_tokens");
+            usage.thinking_tokens += field("thinking_tokens");
+        }
+        usage
+    }
+
+    /// Human-readable summary used as the reply to `/usage`
+    pub fn summary(&self) -> String {
+        if self.responses == 0 {
+            return "No token usage recorded for this thread yet.".to_string();
+        }
+        format!(
+            "Token usage for this thread: {} input, {} output \
+             ({} cache read, {} cache write, {} thinking) across {} response{}.",
+            self.input_tokens,
+            self.output_tokens,
+            self.cache_read_tokens,
+            self.cache_write_tokens,
+            self.thinking_tokens,
+            self.responses,
+            if self.responses == 1 { "" } else { "s" },
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    fn event(event_type: &str, data: serde_json::Value) -> BackendEventLog {
+        BackendEventLog {
+            id: 0,
+            thread_id: "thread-1".to_string(),
+            event_type: event_type.to_string(),
+            event_data: data,
+            created_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_parse_known_commands() {
+        assert_eq!(SlashCommand::parse("/reset"), Some(SlashCommand::Reset));
+        assert_eq!(SlashCommand::parse("  /MODEL  "), Some(SlashCommand::Model));
+        assert_eq!(
+            SlashCommand::parse("/usage please"),
+            Some(SlashCommand::Usage)
+        );
+        assert_eq!(
+            SlashCommand::parse("/title Deploy failures"),
+            Some(SlashCommand::Title)
+        );
+    }
+
+    #[test]
+    fn test_argument() {
+        assert_eq!(
+            SlashCommand::argument("  /title  Deploy failures "),
+            "Deploy failures"
+        );
+        assert_eq!(SlashCommand::argument("/title"), "");
+        assert_eq!(
+            SlashCommand::argument("/title\nOn two\nlines"),
+            "On two\nlines"
+        );
+    }
+
+    #[test]
+    fn test_unknown_commands_pass_through() {
+        for content in [
+            "/deploy prod",
+            "/resetall",
+            "reset",
+            "what does /reset do?",
+            "/",
+            "",
+        ] {
+            assert_eq!(SlashCommand::parse(content), None, "{content:?}");
+        }
+    }
+
+    #[test]
+    fn test_usage_sums_usage_events() {
+        let events = vec![
+            event(
+                "usage",
+                serde_json::json!({
+                    "input_tokens": 100,
+                    "output_tokens": 20,
+                    "cache_read_tokens": 50,
+                    "cache_write_tokens": 0,
+                    "thinking_tokens": 5,
+                }),
+            ),
+            event("text", serde_json::json!({"content": "hi"})),
+            event(
+                "usage",
+                serde_json::json!({"input_tokens": 40, "output_tokens": 10}),
+            ),
+        ];
+        let usage = TokenUsage::from_events(&events);
+        assert_eq!(
+            usage,
+            TokenUsage {
+                responses: 2,
+                input_tokens: 140,
+                output_tokens: 30,
+                cache_read_tokens: 50,
+                cache_write_tokens: 0,
+                thinking_tokens: 5,
+            }
+        );
+        assert!(usage.summary().contains("140 input, 30 output"));
+        assert!(usage.summary().ends_with("across 2 responses."));
+    }
+
+    #[test]
+    fn test_usage_summary_when_empty() {
+        assert_eq!(
+            TokenUsage::default().summary(),
+            "No token usage recorded for this thread yet."
+        );
+    }
+}
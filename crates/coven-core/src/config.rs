@@ -27,6 +27,13 @@ pub struct Config {
     pub tui: TuiConfig,
     /// Matrix frontend settings
     pub matrix: MatrixConfig,
+    /// Prefix each message with who sent it ("Message from Alice (slack):")
+    /// so agents in shared channels can tell users apart
+    pub include_sender_context: bool,
+    /// How threads get readable titles
+    pub titles: TitleConfig,
+    /// Bounds on the threads kept live in the backend
+    pub threads: ThreadLimitsConfig,
 }
 
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
@@ -34,6 +41,9 @@ pub struct Config {
 pub struct DatabaseConfig {
     /// Path to SQLite database file
     pub path: Option<PathBuf>,
+    /// Seconds between compactions of the stored backend event log
+    /// (see `ThreadStore::compact_events`); unset never compacts
+    pub compact_interval_secs: Option<u64>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -47,15 +57,38 @@ pub struct ClaudeConfig {
     pub system_prompt: Option<String>,
     /// Base URL for Anthropic API (for SDK backend only)
     pub base_url: Option<String>,
+    /// CLI crashes tolerated within `restart_window_secs` before the agent
+    /// stops respawning it (for DirectCli backend only)
+    pub max_restarts: u32,
+    /// How far back CLI crashes count against `max_restarts`, in seconds
+    pub restart_window_secs: u64,
+    /// Claude CLI subprocesses the agent runs at once; further requests
+    /// queue (for DirectCli backend only)
+    pub pool_size: usize,
 }
 
 impl Default for ClaudeConfig {
     fn default() -> Self {
+        let restart = crate::backend::CliRestartPolicy::default();
         Self {
             binary: "claude".to_string(),
             timeout_secs: 300,
             system_prompt: None,
             base_url: None,
+            max_restarts: restart.max_restarts,
+            restart_window_secs: restart.window.as_secs(),
+            pool_size: crate::backend::DEFAULT_CLI_POOL_SIZE,
+        }
+    }
+}
+
+impl ClaudeConfig {
+    /// Restart pacing for the Claude CLI subprocess
+    pub fn restart_policy(&self) -> crate::backend::CliRestartPolicy {
+        crate::backend::CliRestartPolicy {
+            max_restarts: self.max_restarts,
+            window: std::time::Duration::from_secs(self.restart_window_secs),
+            ..Default::default()
         }
     }
 }
@@ -113,6 +146,18 @@ pub struct MuxBackendConfig {
     pub agent_soul_path: Option<PathBuf>,
     /// Filenames to search for soul.md in working directories
     pub soul_files: Vec<String>,
+    /// Largest tool result in bytes sent back to the model (0 = no limit)
+    pub tool_result_max_bytes: usize,
+    /// Reuse results of read-only tools within a thread
+    pub tool_cache: ToolCacheConfig,
+    /// Retry tools that fail with transient errors
+    pub tool_retry: ToolRetryConfig,
+    /// Most read-only tool calls from one model response that run at once
+    pub max_parallel_tools: usize,
+    /// Confirmation text shown in approval prompts, by tool name
+    pub confirm_messages: std::collections::HashMap<String, String>,
+    /// Token and cost limits
+    pub budget: BudgetConfig,
 }
 
 impl Default for MuxBackendConfig {
@@ -129,10 +174,127 @@ impl Default for MuxBackendConfig {
             global_soul_path: None,
             agent_soul_path: None,
             soul_files: vec!["soul.md".to_string(), ".coven/soul.md".to_string()],
+            tool_result_max_bytes: crate::backend::DEFAULT_TOOL_RESULT_MAX_BYTES,
+            tool_cache: ToolCacheConfig::default(),
+            tool_retry: ToolRetryConfig::default(),
+            max_parallel_tools: crate::backend::DEFAULT_MAX_PARALLEL_TOOLS,
+            confirm_messages: std::collections::HashMap::new(),
+            budget: BudgetConfig::default(),
+        }
+    }
+}
+
+/// Memoization of read-only tool results (see `backend::ToolCache`)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ToolCacheConfig {
+    /// Whether results are cached at all
+    pub enabled: bool,
+    /// How long a cached result is reused, in seconds
+    pub ttl_secs: u64,
+    /// Most results held across all threads; the oldest go first
+    pub max_entries: usize,
+    /// Tools whose results may be reused. Running any other tool from the
+    /// same pack clears that pack's cached results.
+    pub tools: Vec<String>,
+}
+
+impl Default for ToolCacheConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            ttl_secs: 300,
+            max_entries: 256,
+            tools: vec!["web_fetch".to_string()],
+        }
+    }
+}
+
+/// Automatic retry of tool calls that fail transiently (see
+/// `backend::ToolRetry`)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ToolRetryConfig {
+    /// Tools to retry, by exact name. List only idempotent tools, ones that
+    /// are safe to run twice: a call that failed may still have done its
+    /// work. Empty retries none.
+    pub tools: Vec<String>,
+    /// Most times a call runs, counting the first
+    pub max_attempts: u32,
+    /// Wait before the first retry, in milliseconds; doubles each retry
+    pub backoff_ms: u64,
+    /// Failures are retried only when their output contains one of these,
+    /// ignoring case. HTTP statuses are matched with their reason phrase or
+    /// a `status:` prefix, so a stray number doesn't count.
+    pub transient_patterns: Vec<String>,
+}
+
+impl Default for ToolRetryConfig {
+    fn default() -> Self {
+        Self {
+            tools: Vec::new(),
+            max_attempts: 3,
+            backoff_ms: 500,
+            transient_patterns: [
+                "timed out",
+                "timeout",
+                "connection reset",
+                "connection refused",
+                "temporarily unavailable",
+                "too many requests",
+                "502 bad gateway",
+                "503 service unavailable",
+                "504 gateway timeout",
+                "status: 502",
+                "status: 503",
+                "status: 504",
+            ]
+            .into_iter()
+            .map(String::from)
+            .collect(),
         }
     }
 }
 
+/// Spending limits for the mux backend (see `backend::Budget`). Unset
+/// limits don't apply; with none set, spend isn't tracked.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BudgetConfig {
+    /// Most tokens one message may use, across all its tool-use rounds
+    pub max_tokens_per_request: Option<u64>,
+    /// Most tokens one thread may use, ever
+    pub max_tokens_per_thread: Option<u64>,
+    /// Most tokens used per UTC day, across all threads
+    pub max_tokens_per_day: Option<u64>,
+    /// Most dollars spent per UTC day, across all threads
+    pub max_cost_usd_per_day: Option<f64>,
+    /// Prices by model name prefix, replacing the built-in ones
+    pub prices: std::collections::HashMap<String, ModelPrice>,
+}
+
+impl BudgetConfig {
+    /// Whether any limit is set
+    pub fn is_enabled(&self) -> bool {
+        self.max_tokens_per_request.is_some()
+            || self.max_tokens_per_thread.is_some()
+            || self.max_tokens_per_day.is_some()
+            || self.max_cost_usd_per_day.is_some()
+    }
+}
+
+/// What a model costs, in USD per million tokens. Cache prices default to
+/// Anthropic's multiples of the input price.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ModelPrice {
+    pub input: f64,
+    pub output: f64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cache_read: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cache_write: Option<f64>,
+}
+
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 #[serde(default)]
 pub struct SlackConfig {
@@ -157,6 +319,51 @@ impl Default for TuiConfig {
     }
 }
 
+/// Where a thread's title comes from
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TitleMode {
+    /// Leave threads untitled; they're shown by ID
+    Off,
+    /// The start of the thread's first message
+    #[default]
+    Truncate,
+    /// A one-shot backend call summarizing the first message, falling back
+    /// to truncation if it fails
+    Summarize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TitleConfig {
+    /// How titles are generated after a thread's first exchange
+    pub mode: TitleMode,
+    /// Longest title kept, in characters
+    pub max_chars: usize,
+}
+
+impl Default for TitleConfig {
+    fn default() -> Self {
+        Self {
+            mode: TitleMode::default(),
+            max_chars: 60,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ThreadLimitsConfig {
+    /// Threads with a live backend session at once. Past this, the least
+    /// recently active thread's session is dropped from the backend's
+    /// memory; its history stays stored and it resumes on its next
+    /// message. Unset keeps every thread live.
+    pub max_active: Option<usize>,
+    /// Messages each live session keeps in the backend's context, oldest
+    /// dropped first. Unset leaves the bound to the backend.
+    pub max_messages: Option<usize>,
+}
+
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 #[serde(default)]
 pub struct MatrixConfig {
@@ -259,11 +466,15 @@ workspace = "{home}"
 
 [database]
 # path = "~/.local/share/coven/threads.db"  # Default location
+# compact_interval_secs = 3600  # Merge streamed text of finished responses
 
 [claude]
 timeout_secs = 300
 # system_prompt = "You are a helpful assistant."
 # base_url = "http://localhost:4000"  # For proxies like LiteLLM
+# max_restarts = 5            # CLI crashes tolerated per window before giving up
+# restart_window_secs = 300
+# pool_size = 8               # CLI subprocesses running at once
 
 [codex]
 # binary = "codex"
@@ -281,6 +492,24 @@ timeout_secs = 300
 # global_soul_path = "~/.config/coven/soul.md"  # Agent identity/personality (supports ~)
 # agent_soul_path = ".coven/agent-soul.md"      # Per-agent soul (relative to working_dir)
 # soul_files = ["soul.md", ".coven/soul.md"]    # Auto-search for soul in working_dir
+# tool_result_max_bytes = 65536                 # Truncate larger tool results sent to the model (0 = off)
+# max_parallel_tools = 4                        # Read-only tool calls from one response run at once (1 = one by one)
+
+[mux.tool_cache]
+# enabled = false      # Reuse results of read-only tools within a thread
+# ttl_secs = 300
+# max_entries = 256
+# tools = ["web_fetch"]  # Cacheable tools; other tools of the same pack clear them
+
+[mux.budget]
+# A call is refused if it could go past a limit; a warning comes at 80%.
+# Days are UTC. Tokens include cache reads and writes.
+# max_tokens_per_request = 500000
+# max_tokens_per_thread = 5000000
+# max_tokens_per_day = 20000000
+# max_cost_usd_per_day = 50.0
+# [mux.budget.prices]  # USD per million tokens, by model name prefix
+# "claude-sonnet-4" = {{ input = 3.0, output = 15.0 }}
 
 [slack]
 # bot_token = "xoxb-..."
@@ -294,6 +523,14 @@ timeout_secs = 300
 
 [tui]
 default_thread = "default"
+
+[titles]
+# mode = "truncate"  # "truncate" (first message), "summarize" (ask the backend), or "off"
+# max_chars = 60
+
+[threads]
+# max_active = 50      # threads kept live in the backend; least recently active evicted first
+# max_messages = 100   # messages each live thread keeps in the backend's context
 "#
         )
     }
@@ -0,0 +1,142 @@
+// ABOUTME: Git repository state of a working directory: branch, commit, dirty, upstream.
+// ABOUTME: Shared by agents reporting their workspace and the swarm's git automation.
+
+use std::path::Path;
+use std::process::Command;
+
+/// Git repository state
+#[derive(Debug, Clone, Default)]
+pub struct GitInfo {
+    pub branch: String,
+    pub commit: String,
+    pub dirty: bool,
+    pub remote: String,
+    pub ahead: i32,
+    pub behind: i32,
+}
+
+impl GitInfo {
+    /// Gather git info from the given directory. Returns None if not a git repo.
+    pub fn gather(working_dir: &Path) -> Option<Self> {
+        // Check if this is a git repo
+        let status = Command::new("git")
+            .args(["rev-parse", "--git-dir"])
+            .current_dir(working_dir)
+            .output()
+            .ok()?;
+
+        if !status.status.success() {
+            return None;
+        }
+
+        let branch =
+            run_git(working_dir, &["rev-parse", "--abbrev-ref", "HEAD"]).unwrap_or_default();
+
+        let commit = run_git(working_dir, &["rev-parse", "--short", "HEAD"]).unwrap_or_default();
+
+        let dirty = run_git(working_dir, &["status", "--porcelain"])
+            .map(|s| !s.is_empty())
+            .unwrap_or(false);
+
+        // Get remote tracking branch (may not exist)
+        let remote =
+            run_git(working_dir, &["rev-parse", "--abbrev-ref", "@{u}"]).unwrap_or_default();
+
+        // Get ahead/behind counts (only if we have a remote)
+        let (ahead, behind) = if !remote.is_empty() {
+            parse_ahead_behind(working_dir)
+        } else {
+            (0, 0)
+        };
+
+        Some(GitInfo {
+            branch,
+            commit,
+            dirty,
+            remote,
+            ahead,
+            behind,
+        })
+    }
+}
+
+fn run_git(working_dir: &Path, args: &[&str]) -> Option<String> {
+    let output = Command::new("git")
+        .args(args)
+        .current_dir(working_dir)
+        .output()
+        .ok()?;
+
+    if output.status.success() {
+        Some(String::from_utf8_lossy(&output.stdout).trim().to_string())
+    } else {
+        None
+    }
+}
+
+fn parse_ahead_behind(working_dir: &Path) -> (i32, i32) {
+    // git rev-list --left-right --count @{u}...HEAD
+    // Output: "3\t5" meaning 3 behind, 5 ahead
+    let output = run_git(
+        working_dir,
+        &["rev-list", "--left-right", "--count", "@{u}...HEAD"],
+    );
+
+    match output {
+        Some(s) => {
+            let parts: Vec<&str> = s.split('\t').collect();
+            if parts.len() == 2 {
+                let behind = parts[0].parse().unwrap_or(0);
+                let ahead = parts[1].parse().unwrap_or(0);
+                (ahead, behind)
+            } else {
+                (0, 0)
+            }
+        }
+        None => (0, 0),
+    }
+}
+
+// Conversion to protobuf types
+impl From<GitInfo> for coven_proto::GitInfo {
+    fn from(info: GitInfo) -> Self {
+        coven_proto::GitInfo {
+            branch: info.branch,
+            commit: info.commit,
+            dirty: info.dirty,
+            remote: info.remote,
+            ahead: info.ahead,
+            behind: info.behind,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_git_info_branch_and_commit() {
+        let working_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .parent()
+            .unwrap()
+            .parent()
+            .unwrap()
+            .to_path_buf();
+
+        let git = GitInfo::gather(&working_dir).expect("should be in git repo");
+
+        // Commit should be a short hash (7-8 chars typically)
+        assert!(git.commit.len() >= 7);
+        assert!(git.commit.len() <= 12);
+
+        // Branch should be non-empty
+        assert!(!git.branch.is_empty());
+    }
+
+    #[test]
+    fn test_not_a_git_repo() {
+        assert!(GitInfo::gather(Path::new("/tmp")).is_none());
+    }
+}
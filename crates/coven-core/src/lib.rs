@@ -2,16 +2,23 @@
 // ABOUTME: Shared between coven-agent and coven-server
 
 pub mod backend;
+pub mod commands;
 pub mod config;
 pub mod files;
+pub mod git;
 pub mod mcp_http;
 pub mod router;
 pub mod store;
+pub mod titles;
 pub mod types;
 
 pub use backend::{BackendEvent, ToolStateKind};
+pub use commands::SlashCommand;
 pub use config::Config;
 pub use files::SessionFiles;
 pub use router::Coven;
 pub use store::ThreadStore;
-pub use types::{FileAttachment, IncomingMessage, OutgoingEvent, Thread};
+pub use titles::Titler;
+pub use types::{
+    FileAttachment, IncomingMessage, OutgoingEvent, RequestOverrides, Thread, ThreadTitle,
+};
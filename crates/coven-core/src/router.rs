@@ -2,16 +2,28 @@
 // ABOUTME: Core orchestration layer between frontends and backends
 
 use crate::backend::{Backend, BackendEvent, ToolStateKind};
+use crate::commands::{SlashCommand, TokenUsage};
 use crate::config::Config as FoldConfig;
 use crate::store::ThreadStore;
-use crate::types::{IncomingMessage, OutgoingEvent};
+use crate::titles::Titler;
+use crate::types::{IncomingMessage, OutgoingEvent, ThreadTitle};
 use anyhow::Result;
+use coven_proto::overrides::validate_model;
+use coven_proto::redact::InputRedactor;
 use futures::stream::BoxStream;
 use futures::StreamExt;
-use std::sync::Arc;
-use tokio::sync::RwLock;
+use std::collections::HashMap;
+use std::sync::{Arc, Weak};
+use std::time::Duration;
+use tokio::sync::{broadcast, RwLock};
 use uuid::Uuid;
 
+/// Longest tool input summary kept in the event log
+const LOGGED_INPUT_CHARS: usize = 500;
+
+/// Title changes buffered per subscriber before a slow one misses some
+const TITLE_BUFFER: usize = 64;
+
 /// Convert ToolStateKind to string representation
 fn tool_state_to_string(state: ToolStateKind) -> String {
     match state {
@@ -50,12 +62,136 @@ fn rewrite_with_attachments(msg: &IncomingMessage) -> String {
     }
 }
 
+/// Prefix message content with who sent it, when the sender's name is known
+fn with_sender_context(msg: &IncomingMessage, content: String) -> String {
+    match msg.sender_display.as_deref().map(str::trim) {
+        Some(name) if !name.is_empty() => {
+            format!("Message from {} ({}):\n{}", name, msg.frontend, content)
+        }
+        _ => content,
+    }
+}
+
+/// Compact `threads` every `interval` until the router drops it
+fn spawn_compaction(threads: Weak<ThreadStore>, interval: Duration) {
+    tokio::spawn(async move {
+        let mut ticks = tokio::time::interval(interval);
+        ticks.tick().await;
+        loop {
+            ticks.tick().await;
+            let Some(threads) = threads.upgrade() else {
+                break;
+            };
+            match threads.compact_events(false).await {
+                Ok(c) if c.threads > 0 => {
+                    tracing::info!(
+                        threads = c.threads,
+                        text_merged = c.text_merged,
+                        tool_states_dropped = c.tool_states_dropped,
+                        bytes_saved = c.bytes_saved,
+                        "Compacted backend event log"
+                    );
+                }
+                Ok(_) => {}
+                Err(e) => tracing::warn!(error = %e, "Failed to compact backend event log"),
+            }
+        }
+    });
+}
+
+/// A thread with a live backend session
+struct LiveThread {
+    session_id: String,
+    /// `LiveThreads::clock` when the thread last got a message
+    last_active: u64,
+    /// Cloned into each response stream, so a thread that is answering is
+    /// never evicted
+    in_use: Arc<()>,
+}
+
+/// Threads with a live backend session, at most `max` of them
+#[derive(Default)]
+struct LiveThreads {
+    threads: HashMap<String, LiveThread>,
+    clock: u64,
+    max: Option<usize>,
+}
+
+impl LiveThreads {
+    fn new(max: Option<usize>) -> Self {
+        Self {
+            max,
+            ..Default::default()
+        }
+    }
+
+    /// Mark a live thread active, returning its session ID and a use token
+    fn touch(&mut self, thread_id: &str) -> Option<(String, Arc<()>)> {
+        self.clock += 1;
+        let thread = self.threads.get_mut(thread_id)?;
+        thread.last_active = self.clock;
+        Some((thread.session_id.clone(), thread.in_use.clone()))
+    }
+
+    /// Make a thread live (or update its session), returning a use token
+    /// and the sessions of the least recently active threads evicted to
+    /// stay within `max`
+    fn insert(&mut self, thread_id: &str, session_id: &str) -> (Arc<()>, Vec<String>) {
+        self.clock += 1;
+        let clock = self.clock;
+        let thread = self
+            .threads
+            .entry(thread_id.to_string())
+            .or_insert_with(|| LiveThread {
+                session_id: String::new(),
+                last_active: clock,
+                in_use: Arc::new(()),
+            });
+        thread.session_id = session_id.to_string();
+        thread.last_active = clock;
+        let in_use = thread.in_use.clone();
+
+        let mut evicted = Vec::new();
+        let max = self.max.unwrap_or(usize::MAX);
+        while self.threads.len() > max {
+            let Some(oldest) = self
+                .threads
+                .iter()
+                .filter(|(id, thread)| {
+                    id.as_str() != thread_id && Arc::strong_count(&thread.in_use) == 1
+                })
+                .min_by_key(|(_, thread)| thread.last_active)
+                .map(|(id, _)| id.clone())
+            else {
+                break;
+            };
+            if let Some(thread) = self.threads.remove(&oldest) {
+                tracing::debug!(thread_id = %oldest, "Evicted least recently active thread");
+                evicted.push(thread.session_id);
+            }
+        }
+        (in_use, evicted)
+    }
+
+    fn remove(&mut self, thread_id: &str) {
+        self.threads.remove(thread_id);
+    }
+}
+
 /// The core router that handles messages and manages sessions
 pub struct Coven {
     threads: Arc<ThreadStore>,
     backend: Arc<dyn Backend>,
-    /// Cache of active session IDs
-    sessions: Arc<RwLock<std::collections::HashMap<String, String>>>,
+    /// Threads with a live backend session
+    live: Arc<RwLock<LiveThreads>>,
+    /// Messages each live session keeps in the backend's context
+    max_messages: Option<usize>,
+    /// Whether to tell the backend who sent each message
+    include_sender_context: bool,
+    /// Names threads after their first exchange
+    titler: Titler,
+    /// Titles as they're set, for frontends that list threads
+    titles: broadcast::Sender<ThreadTitle>,
 }
 
 impl Coven {
@@ -69,25 +205,56 @@ impl Coven {
         }
 
         let threads = Arc::new(ThreadStore::open(&db_path).await?);
+        if let Some(secs) = config.database.compact_interval_secs.filter(|&s| s > 0) {
+            spawn_compaction(Arc::downgrade(&threads), Duration::from_secs(secs));
+        }
 
         Ok(Self {
             threads,
+            titler: Titler::new(backend.clone(), config.titles.clone()),
             backend,
-            sessions: Arc::new(RwLock::new(std::collections::HashMap::new())),
+            live: Arc::new(RwLock::new(LiveThreads::new(config.threads.max_active))),
+            max_messages: config.threads.max_messages,
+            include_sender_context: config.include_sender_context,
+            titles: broadcast::channel(TITLE_BUFFER).0,
         })
     }
 
+    /// Every thread title set from now on, generated or given
+    pub fn subscribe_titles(&self) -> broadcast::Receiver<ThreadTitle> {
+        self.titles.subscribe()
+    }
+
+    /// Tell subscribers `thread_id` is now titled `title`
+    fn announce_title(&self, thread_id: &str, title: Option<&str>) {
+        announce_title(&self.titles, thread_id, title);
+    }
+
     /// Handle an incoming message and return a stream of response events
     pub async fn handle(&self, msg: IncomingMessage) -> Result<BoxStream<'static, OutgoingEvent>> {
         // Get or create the thread (keeping the thread for session ID lookup)
         let (thread, _is_new_thread) = self.threads.get_or_create(&msg.thread_id).await?;
 
+        // Slash commands are answered here and never reach the backend
+        if msg.attachments.is_empty() {
+            if let Some(command) = SlashCommand::parse(&msg.content) {
+                let reply = self.run_command(command, &msg).await?;
+                let events = vec![
+                    OutgoingEvent::Text(reply.clone()),
+                    OutgoingEvent::Done {
+                        full_response: reply,
+                    },
+                ];
+                return Ok(futures::stream::iter(events).boxed());
+            }
+        }
+
         // Get or create session ID (use write lock to avoid TOCTOU race)
         // Also track whether this is a new session for the backend
-        let (session_id, is_new_session) = {
-            let mut sessions = self.sessions.write().await;
-            if let Some(sid) = sessions.get(&msg.thread_id) {
-                (sid.clone(), false)
+        let (session_id, is_new_session, in_use, evicted) = {
+            let mut live = self.live.write().await;
+            if let Some((sid, in_use)) = live.touch(&msg.thread_id) {
+                (sid, false, in_use, Vec::new())
             } else {
                 // Use the thread we already have instead of re-fetching
                 let (session_id, is_new) = if thread.claude_session_id.is_empty() {
@@ -98,42 +265,76 @@ impl Coven {
                     (thread.claude_session_id.clone(), false)
                 };
 
-                sessions.insert(msg.thread_id.clone(), session_id.clone());
-                (session_id, is_new)
+                let (in_use, evicted) = live.insert(&msg.thread_id, &session_id);
+                (session_id, is_new, in_use, evicted)
             }
         };
+        for session in &evicted {
+            self.backend.evict_session(session).await;
+        }
 
         // Update last active
         self.threads.touch(&msg.thread_id).await?;
 
         // Rewrite message content to include file paths for Claude
-        let message_for_claude = rewrite_with_attachments(&msg);
+        let mut message_for_claude = rewrite_with_attachments(&msg);
+        if self.include_sender_context {
+            message_for_claude = with_sender_context(&msg, message_for_claude);
+        }
 
         // Store user message (with attachments info)
         if let Err(e) = self
             .threads
-            .add_message(&msg.thread_id, "user", &message_for_claude)
+            .add_reply(
+                &msg.thread_id,
+                "user",
+                &message_for_claude,
+                msg.reply_to_message_id.as_deref(),
+            )
             .await
         {
             tracing::warn!(error = %e, "Failed to store user message");
         }
 
+        // A model chosen with /model applies to every message in the thread
+        let mut overrides = msg.overrides.clone();
+        if let Some(model) = self.threads.get_model(&msg.thread_id).await? {
+            overrides.model = Some(model);
+        }
+
         // Send to backend
         let backend_stream = self
             .backend
-            .send(&session_id, &message_for_claude, is_new_session)
+            .send_with_overrides(&session_id, &message_for_claude, is_new_session, &overrides)
             .await?;
 
+        // Untitled threads are named from this message once it's answered
+        let titling = (thread.title.is_none() && self.titler.enabled()).then(|| {
+            (
+                self.titler.clone(),
+                self.titles.clone(),
+                msg.content.clone(),
+            )
+        });
+
         // Clone for the async stream
         let threads = self.threads.clone();
-        let sessions = self.sessions.clone();
+        let live = self.live.clone();
+        let backend = self.backend.clone();
+        let max_messages = self.max_messages;
         let thread_id = msg.thread_id.clone();
 
         // Map BackendEvent to OutgoingEvent and log events
         let mapped = backend_stream.then(move |event| {
             let threads = threads.clone();
-            let sessions = sessions.clone();
+            let live = live.clone();
+            let backend = backend.clone();
+            let session_id = session_id.clone();
             let thread_id = thread_id.clone();
+            let titling = titling.clone();
+            // The stream holds the use token until it ends, so the thread
+            // isn't evicted mid-reply
+            let _ = &in_use;
             async move {
                 // Log the event
                 let (event_type, event_data) = match &event {
@@ -143,8 +344,11 @@ impl Coven {
                         if let Err(e) = threads.set_session_id(&thread_id, session_id).await {
                             tracing::warn!(error = %e, "Failed to update session ID");
                         }
-                        // Update the cache too
-                        sessions.write().await.insert(thread_id.clone(), session_id.clone());
+                        // Update the live set too
+                        let (_, evicted) = live.write().await.insert(&thread_id, session_id);
+                        for session in &evicted {
+                            backend.evict_session(session).await;
+                        }
                         ("session_init", serde_json::json!({"session_id": session_id}))
                     }
                     BackendEvent::SessionOrphaned => {
@@ -152,20 +356,24 @@ impl Coven {
                         if let Err(e) = threads.set_session_id(&thread_id, "").await {
                             tracing::warn!(error = %e, "Failed to clear orphaned session ID");
                         }
-                        // Clear from cache too
-                        sessions.write().await.remove(&thread_id);
+                        // Clear from the live set too
+                        live.write().await.remove(&thread_id);
                         tracing::warn!(thread_id = %thread_id, "Cleared orphaned session - retry the message");
                         ("session_orphaned", serde_json::json!({}))
                     }
                     BackendEvent::Text(t) => ("text", serde_json::json!({"content": t})),
+                    // Inputs can carry credentials and whole files, so
+                    // only a redacted summary is kept
                     BackendEvent::ToolUse { id, name, input } => {
+                        let input = InputRedactor::new().summary(input, LOGGED_INPUT_CHARS);
                         ("tool_use", serde_json::json!({"id": id, "name": name, "input": input}))
                     }
                     BackendEvent::ToolResult { id, output, is_error } => {
                         ("tool_result", serde_json::json!({"id": id, "output": output, "is_error": is_error}))
                     }
-                    BackendEvent::ToolApprovalRequest { id, name, input } => {
-                        ("tool_approval_request", serde_json::json!({"id": id, "name": name, "input": input}))
+                    BackendEvent::ToolApprovalRequest { id, name, input, confirm_message } => {
+                        let input = InputRedactor::new().summary(input, LOGGED_INPUT_CHARS);
+                        ("tool_approval_request", serde_json::json!({"id": id, "name": name, "input": input, "confirm_message": confirm_message}))
                     }
                     BackendEvent::Usage {
                         input_tokens,
@@ -191,6 +399,14 @@ impl Coven {
                             "detail": detail,
                         }),
                     ),
+                    BackendEvent::Budget(report) => (
+                        "budget",
+                        serde_json::to_value(report).unwrap_or_default(),
+                    ),
+                    BackendEvent::BudgetExceeded(exceeded) => (
+                        "budget_exceeded",
+                        serde_json::to_value(exceeded).unwrap_or_default(),
+                    ),
                     BackendEvent::Done { full_response } => {
                         // Store assistant response (skip empty to avoid polluting history)
                         if !full_response.is_empty() {
@@ -201,6 +417,21 @@ impl Coven {
                                 tracing::warn!(error = %e, "Failed to store assistant message");
                             }
                         }
+                        if let Some(max) = max_messages {
+                            if let Err(e) = backend.trim_session(&session_id, max).await {
+                                tracing::warn!(error = %e, "Failed to trim session context");
+                            }
+                        }
+                        // Titling may call the backend, so it never holds up the reply
+                        if let Some((titler, titles, first_message)) = titling {
+                            tokio::spawn(generate_title(
+                                threads.clone(),
+                                titler,
+                                titles,
+                                thread_id.clone(),
+                                first_message,
+                            ));
+                        }
                         ("done", serde_json::json!({"length": full_response.len()}))
                     }
                     BackendEvent::Error(e) => ("error", serde_json::json!({"message": e})),
@@ -223,8 +454,8 @@ impl Coven {
                     BackendEvent::ToolResult { id, output, is_error } => {
                         OutgoingEvent::ToolResult { id, output, is_error }
                     }
-                    BackendEvent::ToolApprovalRequest { id, name, input } => {
-                        OutgoingEvent::ToolApprovalRequest { id, name, input }
+                    BackendEvent::ToolApprovalRequest { id, name, input, confirm_message } => {
+                        OutgoingEvent::ToolApprovalRequest { id, name, input, confirm_message }
                     }
                     BackendEvent::Usage {
                         input_tokens,
@@ -244,6 +475,10 @@ impl Coven {
                         state: tool_state_to_string(state),
                         detail,
                     },
+                    BackendEvent::Budget(report) => OutgoingEvent::Budget(report),
+                    BackendEvent::BudgetExceeded(exceeded) => {
+                        OutgoingEvent::BudgetExceeded(exceeded)
+                    }
                     BackendEvent::Done { full_response } => OutgoingEvent::Done { full_response },
                     BackendEvent::Error(e) => OutgoingEvent::Error(e),
                 }
@@ -253,19 +488,758 @@ impl Coven {
         Ok(Box::pin(mapped))
     }
 
+    /// Answer a slash command for the message's thread
+    async fn run_command(&self, command: SlashCommand, msg: &IncomingMessage) -> Result<String> {
+        tracing::debug!(thread_id = %msg.thread_id, ?command, "Handling slash command");
+        match command {
+            SlashCommand::Reset => {
+                // Same as an orphaned session: the next message starts a new one
+                self.live.write().await.remove(&msg.thread_id);
+                self.threads.set_session_id(&msg.thread_id, "").await?;
+                Ok("Context reset. The next message starts a fresh conversation.".to_string())
+            }
+            SlashCommand::Model => {
+                let given = SlashCommand::argument(&msg.content);
+                if given.eq_ignore_ascii_case("default") {
+                    self.threads.set_model(&msg.thread_id, None).await?;
+                    return Ok("This thread uses the default model again.".to_string());
+                }
+                if !given.is_empty() {
+                    if let Err(e) = validate_model(given) {
+                        return Ok(format!("{}. Give a model name, or /model default.", e));
+                    }
+                    self.threads.set_model(&msg.thread_id, Some(given)).await?;
+                    return Ok(format!("Model for this thread set to {}.", given));
+                }
+
+                let chosen = self.threads.get_model(&msg.thread_id).await?;
+                let model = match (chosen, &msg.overrides.model, self.backend.model()) {
+                    (Some(model), _, _) => format!("{} (chosen for this thread)", model),
+                    (None, Some(model), _) => {
+                        format!("{} (overridden for this conversation)", model)
+                    }
+                    (None, None, Some(model)) => model,
+                    (None, None, None) => {
+                        format!("the {} backend's default", self.backend.name())
+                    }
+                };
+                Ok(format!("Model: {}", model))
+            }
+            SlashCommand::Usage => {
+                let events = self.threads.get_events(&msg.thread_id).await?;
+                Ok(TokenUsage::from_events(&events).summary())
+            }
+            SlashCommand::Title => {
+                let given = SlashCommand::argument(&msg.content);
+                let title = if given.is_empty() {
+                    let messages = self.threads.get_messages(&msg.thread_id).await?;
+                    let Some(first) = messages.iter().find(|m| m.role == "user") else {
+                        return Ok("Nothing to title yet: send a message first.".to_string());
+                    };
+                    match self.titler.title(&first.content).await {
+                        Some(title) => title,
+                        None => {
+                            return Ok("Couldn't generate a title for this thread. \
+                                       Give one with /title <title>."
+                                .to_string())
+                        }
+                    }
+                } else {
+                    given.to_string()
+                };
+                self.threads.set_title(&msg.thread_id, Some(&title)).await?;
+                self.announce_title(&msg.thread_id, Some(&title));
+                Ok(format!("Thread titled \"{}\".", title))
+            }
+        }
+    }
+
+    /// Spend so far against the backend's budget, in thread `thread_id`
+    /// and today. None when the backend has no budget.
+    pub async fn budget_status(
+        &self,
+        thread_id: &str,
+    ) -> Result<Option<crate::backend::BudgetStatus>> {
+        let session_id = self
+            .threads
+            .get(thread_id)
+            .await?
+            .map(|thread| thread.claude_session_id)
+            .unwrap_or_default();
+        self.backend.budget_status(&session_id).await
+    }
+
     /// List all threads
     pub async fn list_threads(&self) -> Result<Vec<crate::types::Thread>> {
         self.threads.list().await
     }
 
+    /// Set or clear a thread's title. A cleared title is generated again
+    /// after the thread's next exchange.
+    pub async fn set_thread_title(&self, thread_id: &str, title: Option<&str>) -> Result<()> {
+        self.threads.set_title(thread_id, title).await?;
+        self.announce_title(thread_id, title);
+        Ok(())
+    }
+
+    /// Fork a thread at the message at `at_message_index` (0-based, oldest
+    /// first) into `new_thread_id`, or a generated ID when None, and return
+    /// the new thread's ID. The fork continues from the copied messages
+    /// when the backend can seed a session with them; otherwise its first
+    /// message starts a fresh session.
+    pub async fn fork_thread(
+        &self,
+        thread_id: &str,
+        at_message_index: usize,
+        new_thread_id: Option<&str>,
+    ) -> Result<String> {
+        let new_thread_id = match new_thread_id {
+            Some(id) => {
+                self.threads
+                    .fork_into(thread_id, at_message_index, id)
+                    .await?;
+                id.to_string()
+            }
+            None => self.threads.fork(thread_id, at_message_index).await?,
+        };
+        self.seed_fork(&new_thread_id).await?;
+        Ok(new_thread_id)
+    }
+
+    /// Fork `thread_id` into `new_thread_id` with `history` (role and
+    /// content, oldest first) as its messages, as the gateway asks with the
+    /// messages it recorded. Continues from them like `fork_thread`.
+    pub async fn fork_thread_with_history(
+        &self,
+        thread_id: &str,
+        new_thread_id: &str,
+        history: &[(String, String)],
+    ) -> Result<()> {
+        self.threads
+            .fork_with_history(thread_id, new_thread_id, history)
+            .await?;
+        self.seed_fork(new_thread_id).await
+    }
+
+    /// Give a new fork a backend session seeded with its messages, if the
+    /// backend can
+    async fn seed_fork(&self, new_thread_id: &str) -> Result<()> {
+        let history = self.threads.get_messages(new_thread_id).await?;
+        let session_id = Uuid::new_v4().to_string();
+        if self.backend.seed_session(&session_id, &history).await? {
+            self.threads
+                .set_session_id(new_thread_id, &session_id)
+                .await?;
+        } else {
+            tracing::warn!(
+                backend = self.backend.name(),
+                thread_id = %new_thread_id,
+                "Backend can't seed sessions, forked thread starts without its history"
+            );
+        }
+        Ok(())
+    }
+
     /// Delete a thread
     pub async fn delete_thread(&self, thread_id: &str) -> Result<()> {
-        // Remove from cache
-        let mut sessions = self.sessions.write().await;
-        sessions.remove(thread_id);
-        drop(sessions);
+        // Remove from the live set
+        self.live.write().await.remove(thread_id);
 
         // Remove from store
         self.threads.delete(thread_id).await
     }
 }
+
+/// Tell subscribers to `titles` that `thread_id` is now titled `title`
+fn announce_title(titles: &broadcast::Sender<ThreadTitle>, thread_id: &str, title: Option<&str>) {
+    // No subscribers just means no frontend lists threads
+    let _ = titles.send(ThreadTitle {
+        thread_id: thread_id.to_string(),
+        title: title.map(str::to_string),
+    });
+}
+
+/// Title a thread from its first message unless it got one meanwhile
+async fn generate_title(
+    threads: Arc<ThreadStore>,
+    titler: Titler,
+    titles: broadcast::Sender<ThreadTitle>,
+    thread_id: String,
+    first_message: String,
+) {
+    let Some(title) = titler.title(&first_message).await else {
+        return;
+    };
+    match threads.set_title_if_unset(&thread_id, &title).await {
+        Ok(true) => {
+            tracing::debug!(thread_id = %thread_id, title = %title, "Thread titled");
+            announce_title(&titles, &thread_id, Some(&title));
+        }
+        Ok(false) => {}
+        Err(e) => {
+            tracing::warn!(error = %e, thread_id = %thread_id, "Failed to store thread title")
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::RequestOverrides;
+
+    fn message(sender_display: Option<&str>, frontend: &str) -> IncomingMessage {
+        IncomingMessage {
+            thread_id: "thread-1".to_string(),
+            sender: "U123".to_string(),
+            sender_display: sender_display.map(String::from),
+            content: "What's on my calendar?".to_string(),
+            frontend: frontend.to_string(),
+            attachments: vec![],
+            overrides: RequestOverrides::default(),
+            reply_to_message_id: None,
+        }
+    }
+
+    /// Backend that records the overrides of each request and replies with
+    /// `reply`, or with nothing when it's empty
+    #[derive(Default)]
+    struct RecordingBackend {
+        overrides: std::sync::Mutex<Vec<RequestOverrides>>,
+        messages: std::sync::Mutex<Vec<(String, bool)>>,
+        seeded: std::sync::Mutex<Vec<(String, Vec<String>)>>,
+        evicted: std::sync::Mutex<Vec<String>>,
+        reply: String,
+    }
+
+    #[async_trait::async_trait]
+    impl Backend for RecordingBackend {
+        fn name(&self) -> &'static str {
+            "recording"
+        }
+
+        async fn send(
+            &self,
+            session_id: &str,
+            message: &str,
+            is_new_session: bool,
+        ) -> Result<BoxStream<'static, BackendEvent>> {
+            self.send_with_overrides(
+                session_id,
+                message,
+                is_new_session,
+                &RequestOverrides::default(),
+            )
+            .await
+        }
+
+        async fn send_with_overrides(
+            &self,
+            _session_id: &str,
+            message: &str,
+            is_new_session: bool,
+            overrides: &RequestOverrides,
+        ) -> Result<BoxStream<'static, BackendEvent>> {
+            self.overrides.lock().unwrap().push(overrides.clone());
+            self.messages
+                .lock()
+                .unwrap()
+                .push((message.to_string(), is_new_session));
+            if self.reply.is_empty() {
+                return Ok(futures::stream::empty().boxed());
+            }
+            let done = BackendEvent::Done {
+                full_response: self.reply.clone(),
+            };
+            Ok(futures::stream::iter([done]).boxed())
+        }
+
+        async fn seed_session(
+            &self,
+            session_id: &str,
+            history: &[crate::store::Message],
+        ) -> Result<bool> {
+            let history = history.iter().map(|m| m.content.clone()).collect();
+            self.seeded
+                .lock()
+                .unwrap()
+                .push((session_id.to_string(), history));
+            Ok(true)
+        }
+
+        async fn evict_session(&self, session_id: &str) {
+            self.evicted.lock().unwrap().push(session_id.to_string());
+        }
+    }
+
+    async fn router() -> (Coven, Arc<RecordingBackend>, std::path::PathBuf) {
+        router_with(FoldConfig::default(), RecordingBackend::default()).await
+    }
+
+    async fn router_with(
+        mut config: FoldConfig,
+        backend: RecordingBackend,
+    ) -> (Coven, Arc<RecordingBackend>, std::path::PathBuf) {
+        let db_path = std::env::temp_dir().join(format!("coven-router-{}.db", Uuid::new_v4()));
+        config.database.path = Some(db_path.clone());
+        let backend = Arc::new(backend);
+        let coven = Coven::new(&config, backend.clone()).await.unwrap();
+        (coven, backend, db_path)
+    }
+
+    /// Wait for the background titling task to store a title
+    async fn title_of(coven: &Coven, thread_id: &str) -> Option<String> {
+        for _ in 0..100 {
+            let thread = coven.threads.get(thread_id).await.unwrap().unwrap();
+            if thread.title.is_some() {
+                return thread.title;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+        None
+    }
+
+    fn command(content: &str) -> IncomingMessage {
+        IncomingMessage {
+            content: content.to_string(),
+            ..message(None, "tui")
+        }
+    }
+
+    /// Collect the reply text of a slash command
+    async fn reply(coven: &Coven, msg: IncomingMessage) -> String {
+        let events: Vec<_> = coven.handle(msg).await.unwrap().collect().await;
+        match events.as_slice() {
+            [OutgoingEvent::Text(text), OutgoingEvent::Done { full_response }] => {
+                assert_eq!(text, full_response);
+                text.clone()
+            }
+            other => panic!("expected text and done, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_overrides_reach_backend() {
+        let (coven, backend, db_path) = router().await;
+
+        let mut msg = message(None, "slack");
+        msg.overrides = RequestOverrides {
+            model: Some("claude-haiku-4-5".to_string()),
+            max_tokens: Some(1024),
+        };
+        coven.handle(msg.clone()).await.unwrap();
+        coven.handle(message(None, "slack")).await.unwrap();
+
+        let seen = backend.overrides.lock().unwrap().clone();
+        assert_eq!(seen, vec![msg.overrides, RequestOverrides::default()]);
+
+        let _ = std::fs::remove_file(&db_path);
+    }
+
+    #[tokio::test]
+    async fn test_slash_commands_skip_backend() {
+        let (coven, backend, db_path) = router().await;
+
+        assert_eq!(
+            reply(&coven, command("/model")).await,
+            "Model: the recording backend's default"
+        );
+        let mut msg = command("/model");
+        msg.overrides.model = Some("claude-haiku-4-5".to_string());
+        assert_eq!(
+            reply(&coven, msg).await,
+            "Model: claude-haiku-4-5 (overridden for this conversation)"
+        );
+
+        coven
+            .threads
+            .add_event(
+                "thread-1",
+                "usage",
+                &serde_json::json!({"input_tokens": 12, "output_tokens": 3}),
+            )
+            .await
+            .unwrap();
+        assert!(reply(&coven, command("/usage"))
+            .await
+            .starts_with("Token usage for this thread: 12 input, 3 output"));
+
+        assert!(backend.messages.lock().unwrap().is_empty());
+
+        // Unknown commands are regular text
+        coven.handle(command("/deploy prod")).await.unwrap();
+        assert_eq!(
+            backend.messages.lock().unwrap().clone(),
+            vec![("/deploy prod".to_string(), true)]
+        );
+
+        let _ = std::fs::remove_file(&db_path);
+    }
+
+    #[tokio::test]
+    async fn test_model_command_sets_thread_model() {
+        let (coven, backend, db_path) = router().await;
+
+        assert_eq!(
+            reply(&coven, command("/model claude-haiku-4-5")).await,
+            "Model for this thread set to claude-haiku-4-5."
+        );
+        assert_eq!(
+            reply(&coven, command("/model")).await,
+            "Model: claude-haiku-4-5 (chosen for this thread)"
+        );
+        assert!(reply(&coven, command("/model not a model"))
+            .await
+            .starts_with("invalid model"));
+
+        // Applies to the thread's messages, ahead of per-channel overrides
+        let mut msg = command("hello");
+        msg.overrides.model = Some("claude-sonnet-4-5".to_string());
+        coven.handle(msg).await.unwrap();
+        assert_eq!(
+            reply(&coven, command("/model default")).await,
+            "This thread uses the default model again."
+        );
+        coven.handle(command("again")).await.unwrap();
+
+        let models: Vec<Option<String>> = backend
+            .overrides
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|o| o.model.clone())
+            .collect();
+        assert_eq!(models, vec![Some("claude-haiku-4-5".to_string()), None]);
+
+        let _ = std::fs::remove_file(&db_path);
+    }
+
+    #[tokio::test]
+    async fn test_reset_starts_new_session() {
+        let (coven, backend, db_path) = router().await;
+
+        coven.handle(command("hello")).await.unwrap();
+        coven.handle(command("again")).await.unwrap();
+        assert!(reply(&coven, command("/reset"))
+            .await
+            .starts_with("Context reset."));
+        coven.handle(command("fresh start")).await.unwrap();
+
+        let new_sessions: Vec<bool> = backend
+            .messages
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(_, is_new)| *is_new)
+            .collect();
+        assert_eq!(new_sessions, vec![true, false, true]);
+
+        let _ = std::fs::remove_file(&db_path);
+    }
+
+    #[tokio::test]
+    async fn test_fork_continues_seeded_session() {
+        let backend = RecordingBackend {
+            reply: "ok".to_string(),
+            ..Default::default()
+        };
+        let (coven, backend, db_path) = router_with(FoldConfig::default(), backend).await;
+
+        coven.handle(command("first")).await.unwrap().count().await;
+        coven.handle(command("second")).await.unwrap().count().await;
+
+        // Fork after the first exchange: "first" and its answer
+        let fork = coven
+            .fork_thread("thread-1", 1, Some("thread-2"))
+            .await
+            .unwrap();
+        assert_eq!(fork, "thread-2");
+        let seeded = backend.seeded.lock().unwrap().clone();
+        assert_eq!(seeded.len(), 1);
+        assert_eq!(seeded[0].1, vec!["first", "ok"]);
+        let thread = coven.threads.get("thread-2").await.unwrap().unwrap();
+        assert_eq!(thread.claude_session_id, seeded[0].0);
+
+        coven
+            .handle(IncomingMessage {
+                thread_id: fork.clone(),
+                ..command("instead")
+            })
+            .await
+            .unwrap()
+            .count()
+            .await;
+        assert_eq!(
+            backend.messages.lock().unwrap().last().unwrap(),
+            &("instead".to_string(), false)
+        );
+        assert_eq!(
+            coven.threads.get_messages("thread-1").await.unwrap().len(),
+            4
+        );
+        assert_eq!(coven.threads.get_messages(&fork).await.unwrap().len(), 4);
+
+        assert!(coven.fork_thread("thread-1", 4, None).await.is_err());
+
+        let _ = std::fs::remove_file(&db_path);
+    }
+
+    #[tokio::test]
+    async fn test_least_recently_active_thread_is_evicted() {
+        let mut config = FoldConfig::default();
+        config.threads.max_active = Some(2);
+        let backend = RecordingBackend {
+            reply: "ok".to_string(),
+            ..Default::default()
+        };
+        let (coven, backend, db_path) = router_with(config, backend).await;
+        let send = |thread_id: &str| IncomingMessage {
+            thread_id: thread_id.to_string(),
+            ..command("hello")
+        };
+        let live = |coven: &Coven| {
+            let live = coven.live.try_read().unwrap();
+            let mut ids: Vec<String> = live.threads.keys().cloned().collect();
+            ids.sort();
+            ids
+        };
+
+        for thread_id in ["thread-1", "thread-2", "thread-3"] {
+            coven.handle(send(thread_id)).await.unwrap().count().await;
+        }
+        assert_eq!(live(&coven), ["thread-2", "thread-3"]);
+        let session_1 = coven
+            .threads
+            .get("thread-1")
+            .await
+            .unwrap()
+            .unwrap()
+            .claude_session_id;
+        assert_eq!(backend.evicted.lock().unwrap().clone(), vec![session_1]);
+
+        // Activity keeps thread-2 live; the evicted thread resumes its
+        // stored session, and a thread still answering isn't evicted
+        coven.handle(send("thread-2")).await.unwrap().count().await;
+        let answering = coven.handle(send("thread-2")).await.unwrap();
+        coven.handle(send("thread-1")).await.unwrap().count().await;
+        assert_eq!(live(&coven), ["thread-1", "thread-2"]);
+        assert_eq!(
+            backend.messages.lock().unwrap().last().unwrap(),
+            &("hello".to_string(), false)
+        );
+        coven.handle(send("thread-4")).await.unwrap().count().await;
+        assert_eq!(live(&coven), ["thread-2", "thread-4"]);
+        drop(answering);
+
+        let _ = std::fs::remove_file(&db_path);
+    }
+
+    #[tokio::test]
+    async fn test_first_exchange_titles_thread() {
+        let backend = RecordingBackend {
+            reply: "You have two meetings.".to_string(),
+            ..Default::default()
+        };
+        let (coven, _backend, db_path) = router_with(FoldConfig::default(), backend).await;
+        let mut titles = coven.subscribe_titles();
+
+        let _: Vec<_> = coven
+            .handle(command("What's on my calendar?\nI forget"))
+            .await
+            .unwrap()
+            .collect()
+            .await;
+        assert_eq!(
+            title_of(&coven, "thread-1").await.as_deref(),
+            Some("What's on my calendar?")
+        );
+        assert_eq!(
+            titles.recv().await.unwrap(),
+            ThreadTitle {
+                thread_id: "thread-1".to_string(),
+                title: Some("What's on my calendar?".to_string()),
+            }
+        );
+
+        // Later exchanges keep the title
+        let _: Vec<_> = coven
+            .handle(command("And tomorrow?"))
+            .await
+            .unwrap()
+            .collect()
+            .await;
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+        let threads = coven.list_threads().await.unwrap();
+        assert_eq!(threads[0].display_title(), "What's on my calendar?");
+
+        let _ = std::fs::remove_file(&db_path);
+    }
+
+    #[tokio::test]
+    async fn test_summarized_title_uses_a_separate_session() {
+        let mut config = FoldConfig::default();
+        config.titles.mode = crate::config::TitleMode::Summarize;
+        let backend = RecordingBackend {
+            reply: "\"Calendar check\"".to_string(),
+            ..Default::default()
+        };
+        let (coven, backend, db_path) = router_with(config, backend).await;
+
+        let _: Vec<_> = coven
+            .handle(command("What's on my calendar?"))
+            .await
+            .unwrap()
+            .collect()
+            .await;
+        assert_eq!(
+            title_of(&coven, "thread-1").await.as_deref(),
+            Some("Calendar check")
+        );
+
+        let messages = backend.messages.lock().unwrap().clone();
+        assert_eq!(messages.len(), 2);
+        let (prompt, is_new_session) = &messages[1];
+        assert!(prompt.ends_with("What's on my calendar?"));
+        assert!(is_new_session);
+
+        let _ = std::fs::remove_file(&db_path);
+    }
+
+    #[tokio::test]
+    async fn test_title_command() {
+        let mut config = FoldConfig::default();
+        config.titles.mode = crate::config::TitleMode::Off;
+        let (coven, _backend, db_path) = router_with(config, RecordingBackend::default()).await;
+        let mut titles = coven.subscribe_titles();
+
+        assert_eq!(
+            reply(&coven, command("/title")).await,
+            "Nothing to title yet: send a message first."
+        );
+        assert_eq!(
+            reply(&coven, command("/title  Deploy failures ")).await,
+            "Thread titled \"Deploy failures\"."
+        );
+        let threads = coven.list_threads().await.unwrap();
+        assert_eq!(threads[0].display_title(), "Deploy failures");
+
+        coven.set_thread_title("thread-1", None).await.unwrap();
+        let threads = coven.list_threads().await.unwrap();
+        assert_eq!(threads[0].display_title(), "thread-1");
+
+        // Frontends hear about both changes
+        let first = titles.recv().await.unwrap();
+        assert_eq!(first.title.as_deref(), Some("Deploy failures"));
+        assert_eq!(titles.recv().await.unwrap().title, None);
+
+        let _ = std::fs::remove_file(&db_path);
+    }
+
+    #[tokio::test]
+    async fn test_title_command_regenerates_from_first_message() {
+        let (coven, _backend, db_path) = router().await;
+
+        coven
+            .handle(command("Why did the deploy fail?"))
+            .await
+            .unwrap();
+        coven.handle(command("It was the 2am one")).await.unwrap();
+        assert_eq!(
+            reply(&coven, command("/title Something else")).await,
+            "Thread titled \"Something else\"."
+        );
+        assert_eq!(
+            reply(&coven, command("/title")).await,
+            "Thread titled \"Why did the deploy fail?\"."
+        );
+
+        let _ = std::fs::remove_file(&db_path);
+    }
+
+    #[tokio::test]
+    async fn test_scripted_events_flow_through() {
+        let backend = crate::backend::ReplayBackend::builder()
+            .session_init("backend-session")
+            .text("Checking. ")
+            .tool_use(
+                "t1",
+                "calendar",
+                serde_json::json!({"day": "today", "api_key": "sk-live"}),
+            )
+            .tool_approval("t1", "calendar", serde_json::json!({"day": "today"}))
+            .tool_result("t1", "2 meetings", false)
+            .text("Two meetings.")
+            .usage(12, 3)
+            .done()
+            .build();
+        let mut config = FoldConfig::default();
+        config.titles.mode = crate::config::TitleMode::Off;
+        let db_path = std::env::temp_dir().join(format!("coven-router-{}.db", Uuid::new_v4()));
+        config.database.path = Some(db_path.clone());
+        let backend = Arc::new(backend);
+        let coven = Coven::new(&config, backend.clone()).await.unwrap();
+
+        let events: Vec<_> = coven
+            .handle(message(None, "tui"))
+            .await
+            .unwrap()
+            .collect()
+            .await;
+        assert!(matches!(
+            events.as_slice(),
+            [
+                OutgoingEvent::SessionInit { session_id },
+                OutgoingEvent::Text(_),
+                OutgoingEvent::ToolUse { .. },
+                OutgoingEvent::ToolApprovalRequest { id, .. },
+                OutgoingEvent::ToolState { state, .. },
+                OutgoingEvent::ToolResult { output, is_error: false, .. },
+                OutgoingEvent::Text(_),
+                OutgoingEvent::Usage { input_tokens: 12, .. },
+                OutgoingEvent::Done { full_response },
+            ] if session_id == "backend-session"
+                && id == "t1"
+                && state == "awaiting_approval"
+                && output == "2 meetings"
+                && full_response == "Checking. Two meetings."
+        ));
+        assert_eq!(backend.requests()[0].message, "What's on my calendar?");
+
+        // The backend's session is kept and the reply stored
+        let thread = coven.threads.get("thread-1").await.unwrap().unwrap();
+        assert_eq!(thread.claude_session_id, "backend-session");
+        let messages = coven.threads.get_messages("thread-1").await.unwrap();
+        let roles: Vec<_> = messages.iter().map(|m| m.role.as_str()).collect();
+        assert_eq!(roles, ["user", "assistant"]);
+        let logged = coven.threads.get_events("thread-1").await.unwrap();
+        assert_eq!(logged.len(), events.len());
+
+        // Tool inputs are logged redacted
+        let tool_use = logged.iter().find(|e| e.event_type == "tool_use").unwrap();
+        assert_eq!(
+            tool_use.event_data["input"],
+            r#"{"api_key":"***","day":"today"}"#
+        );
+
+        let _ = std::fs::remove_file(&db_path);
+    }
+
+    #[test]
+    fn test_sender_context_prefix() {
+        let msg = message(Some("Alice"), "slack");
+        assert_eq!(
+            with_sender_context(&msg, msg.content.clone()),
+            "Message from Alice (slack):\nWhat's on my calendar?"
+        );
+    }
+
+    #[test]
+    fn test_sender_context_without_display_name() {
+        for display in [None, Some(""), Some("  ")] {
+            let msg = message(display, "matrix");
+            assert_eq!(
+                with_sender_context(&msg, msg.content.clone()),
+                "What's on my calendar?"
+            );
+        }
+    }
+}
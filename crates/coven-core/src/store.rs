@@ -11,6 +11,7 @@ use sqlx::{
 };
 use std::path::Path;
 use std::str::FromStr;
+use uuid::Uuid;
 
 /// Persistent storage for threads
 pub struct ThreadStore {
@@ -39,13 +40,31 @@ impl ThreadStore {
                 id TEXT PRIMARY KEY,
                 claude_session_id TEXT NOT NULL,
                 created_at TEXT NOT NULL,
-                last_active TEXT NOT NULL
+                last_active TEXT NOT NULL,
+                title TEXT
             )
             "#,
         )
         .execute(&pool)
         .await?;
 
+        // Databases created before thread titles existed lack the column
+        let columns: Vec<String> =
+            sqlx::query_scalar("SELECT name FROM pragma_table_info('threads')")
+                .fetch_all(&pool)
+                .await?;
+        if !columns.iter().any(|c| c == "title") {
+            sqlx::query("ALTER TABLE threads ADD COLUMN title TEXT")
+                .execute(&pool)
+                .await?;
+        }
+        // ...and before per-thread models existed
+        if !columns.iter().any(|c| c == "model") {
+            sqlx::query("ALTER TABLE threads ADD COLUMN model TEXT")
+                .execute(&pool)
+                .await?;
+        }
+
         // Messages table - stores user and assistant messages
         sqlx::query(
             r#"
@@ -55,6 +74,7 @@ impl ThreadStore {
                 role TEXT NOT NULL,
                 content TEXT NOT NULL,
                 created_at TEXT NOT NULL,
+                reply_to_message_id TEXT,
                 FOREIGN KEY (thread_id) REFERENCES threads(id) ON DELETE CASCADE
             )
             "#,
@@ -62,6 +82,17 @@ impl ThreadStore {
         .execute(&pool)
         .await?;
 
+        // Databases created before replies existed lack the column
+        let columns: Vec<String> =
+            sqlx::query_scalar("SELECT name FROM pragma_table_info('messages')")
+                .fetch_all(&pool)
+                .await?;
+        if !columns.iter().any(|c| c == "reply_to_message_id") {
+            sqlx::query("ALTER TABLE messages ADD COLUMN reply_to_message_id TEXT")
+                .execute(&pool)
+                .await?;
+        }
+
         // Backend events table - stores all events from the backend
         sqlx::query(
             r#"
@@ -92,7 +123,7 @@ impl ThreadStore {
     /// Get a thread by ID, or None if it doesn't exist
     pub async fn get(&self, thread_id: &str) -> Result<Option<Thread>> {
         let row = sqlx::query_as::<_, ThreadRow>(
-            "SELECT id, claude_session_id, created_at, last_active FROM threads WHERE id = ?",
+            "SELECT id, claude_session_id, created_at, last_active, title FROM threads WHERE id = ?",
         )
         .bind(thread_id)
         .fetch_optional(&self.pool)
@@ -140,6 +171,47 @@ impl ThreadStore {
         Ok(())
     }
 
+    /// Set or clear a thread's title
+    pub async fn set_title(&self, thread_id: &str, title: Option<&str>) -> Result<()> {
+        sqlx::query("UPDATE threads SET title = ? WHERE id = ?")
+            .bind(title)
+            .bind(thread_id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    /// Set or clear the model chosen for a thread with `/model`
+    pub async fn set_model(&self, thread_id: &str, model: Option<&str>) -> Result<()> {
+        sqlx::query("UPDATE threads SET model = ? WHERE id = ?")
+            .bind(model)
+            .bind(thread_id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    /// The model chosen for a thread with `/model`, if any
+    pub async fn get_model(&self, thread_id: &str) -> Result<Option<String>> {
+        let model: Option<Option<String>> =
+            sqlx::query_scalar("SELECT model FROM threads WHERE id = ?")
+                .bind(thread_id)
+                .fetch_optional(&self.pool)
+                .await?;
+        Ok(model.flatten())
+    }
+
+    /// Set a thread's title unless it already has one, so a generated title
+    /// never replaces one chosen by hand. Returns true if it was set.
+    pub async fn set_title_if_unset(&self, thread_id: &str, title: &str) -> Result<bool> {
+        let result = sqlx::query("UPDATE threads SET title = ? WHERE id = ? AND title IS NULL")
+            .bind(title)
+            .bind(thread_id)
+            .execute(&self.pool)
+            .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
     /// Update last_active timestamp
     pub async fn touch(&self, thread_id: &str) -> Result<()> {
         let now = Utc::now();
@@ -154,7 +226,7 @@ impl ThreadStore {
     /// List all threads, most recently active first
     pub async fn list(&self) -> Result<Vec<Thread>> {
         let rows = sqlx::query_as::<_, ThreadRow>(
-            "SELECT id, claude_session_id, created_at, last_active FROM threads ORDER BY last_active DESC",
+            "SELECT id, claude_session_id, created_at, last_active, title FROM threads ORDER BY last_active DESC",
         )
         .fetch_all(&self.pool)
         .await?;
@@ -171,16 +243,131 @@ impl ThreadStore {
         Ok(())
     }
 
+    /// Copy a thread's messages up to and including the one at
+    /// `at_message_index` (0-based, oldest first) into a new thread, so
+    /// another answer can be explored without touching the original. The
+    /// fork keeps the title and starts without a backend session. Returns
+    /// the new thread's ID.
+    pub async fn fork(&self, thread_id: &str, at_message_index: usize) -> Result<String> {
+        let new_thread_id = Uuid::new_v4().to_string();
+        self.fork_into(thread_id, at_message_index, &new_thread_id)
+            .await?;
+        Ok(new_thread_id)
+    }
+
+    /// `fork` into a thread named `new_thread_id`, for callers that choose
+    /// thread IDs themselves. Fails if that thread already exists.
+    pub async fn fork_into(
+        &self,
+        thread_id: &str,
+        at_message_index: usize,
+        new_thread_id: &str,
+    ) -> Result<()> {
+        let thread = self
+            .get(thread_id)
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("No thread {}", thread_id))?;
+        let messages = self.get_messages(thread_id).await?;
+        if at_message_index >= messages.len() {
+            anyhow::bail!(
+                "Thread {} has {} messages, can't fork at message {}",
+                thread_id,
+                messages.len(),
+                at_message_index
+            );
+        }
+
+        self.insert_fork(
+            new_thread_id,
+            thread.title.as_deref(),
+            &messages[..=at_message_index],
+        )
+        .await
+    }
+
+    /// Start thread `new_thread_id` as a fork of `thread_id` whose messages
+    /// are `history` (role and content, oldest first), for forks made from
+    /// the gateway's record of the thread rather than this store's. The
+    /// fork keeps the original's title if it's known here. Fails if
+    /// `new_thread_id` already exists.
+    pub async fn fork_with_history(
+        &self,
+        thread_id: &str,
+        new_thread_id: &str,
+        history: &[(String, String)],
+    ) -> Result<()> {
+        let title = self.get(thread_id).await?.and_then(|thread| thread.title);
+        let now = Utc::now();
+        let messages: Vec<Message> = history
+            .iter()
+            .map(|(role, content)| Message {
+                id: 0,
+                thread_id: new_thread_id.to_string(),
+                role: role.clone(),
+                content: content.clone(),
+                created_at: now,
+                reply_to_message_id: None,
+            })
+            .collect();
+        self.insert_fork(new_thread_id, title.as_deref(), &messages)
+            .await
+    }
+
+    async fn insert_fork(
+        &self,
+        new_thread_id: &str,
+        title: Option<&str>,
+        messages: &[Message],
+    ) -> Result<()> {
+        let now = Utc::now().to_rfc3339();
+        let mut tx = self.pool.begin().await?;
+        sqlx::query(
+            "INSERT INTO threads (id, claude_session_id, created_at, last_active, title) VALUES (?, '', ?, ?, ?)",
+        )
+        .bind(new_thread_id)
+        .bind(&now)
+        .bind(&now)
+        .bind(title)
+        .execute(&mut *tx)
+        .await?;
+        for message in messages {
+            sqlx::query(
+                "INSERT INTO messages (thread_id, role, content, created_at, reply_to_message_id) VALUES (?, ?, ?, ?, ?)",
+            )
+            .bind(new_thread_id)
+            .bind(&message.role)
+            .bind(&message.content)
+            .bind(message.created_at.to_rfc3339())
+            .bind(&message.reply_to_message_id)
+            .execute(&mut *tx)
+            .await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+
     /// Store a message in the conversation
     pub async fn add_message(&self, thread_id: &str, role: &str, content: &str) -> Result<i64> {
+        self.add_reply(thread_id, role, content, None).await
+    }
+
+    /// Store a message that replies to the frontend's message `reply_to`
+    pub async fn add_reply(
+        &self,
+        thread_id: &str,
+        role: &str,
+        content: &str,
+        reply_to: Option<&str>,
+    ) -> Result<i64> {
         let now = Utc::now();
         let result = sqlx::query(
-            "INSERT INTO messages (thread_id, role, content, created_at) VALUES (?, ?, ?, ?)",
+            "INSERT INTO messages (thread_id, role, content, created_at, reply_to_message_id) VALUES (?, ?, ?, ?, ?)",
         )
         .bind(thread_id)
         .bind(role)
         .bind(content)
         .bind(now.to_rfc3339())
+        .bind(reply_to)
         .execute(&self.pool)
         .await?;
 
@@ -190,7 +377,7 @@ impl ThreadStore {
     /// Get all messages for a thread
     pub async fn get_messages(&self, thread_id: &str) -> Result<Vec<Message>> {
         let rows = sqlx::query_as::<_, MessageRow>(
-            "SELECT id, thread_id, role, content, created_at FROM messages WHERE thread_id = ? ORDER BY id ASC",
+            "SELECT id, thread_id, role, content, created_at, reply_to_message_id FROM messages WHERE thread_id = ? ORDER BY id ASC",
         )
         .bind(thread_id)
         .fetch_all(&self.pool)
@@ -243,6 +430,160 @@ impl ThreadStore {
 
         Ok(rows.into_iter().map(|r| r.into()).collect())
     }
+
+    /// Shrink the backend event log of finished responses: runs of `text`
+    /// events become one event holding their joined content, and `tool_state`
+    /// events are dropped once the same tool reports a later state. Tool
+    /// calls, results and everything else are kept as they are. With
+    /// `dry_run`, only reports what would change.
+    ///
+    /// Events after a thread's last `done` or `error` belong to a response
+    /// that may still be streaming and are left alone. Appends never touch
+    /// the events compacted here, so agents can keep writing meanwhile.
+    pub async fn compact_events(&self, dry_run: bool) -> Result<Compaction> {
+        let thread_ids: Vec<String> =
+            sqlx::query_scalar("SELECT DISTINCT thread_id FROM backend_events")
+                .fetch_all(&self.pool)
+                .await?;
+
+        let mut summary = Compaction::default();
+        for thread_id in thread_ids {
+            let events = sqlx::query_as::<_, EventRecord>(
+                "SELECT id, event_type, event_data FROM backend_events WHERE thread_id = ? ORDER BY id ASC",
+            )
+            .bind(&thread_id)
+            .fetch_all(&self.pool)
+            .await?;
+            let plan = plan_compaction(&events);
+            if plan.is_empty() {
+                continue;
+            }
+            summary.threads += 1;
+            summary.text_merged += plan.text_merged;
+            summary.tool_states_dropped += plan.tool_states_dropped;
+            summary.bytes_saved += plan.bytes_saved;
+            if dry_run {
+                continue;
+            }
+
+            // One thread at a time, so each is either compacted or untouched
+            let mut tx = self.pool.begin().await?;
+            for (id, event_data) in &plan.rewrites {
+                sqlx::query("UPDATE backend_events SET event_data = ? WHERE id = ?")
+                    .bind(event_data)
+                    .bind(id)
+                    .execute(&mut *tx)
+                    .await?;
+            }
+            for id in &plan.deletes {
+                sqlx::query("DELETE FROM backend_events WHERE id = ?")
+                    .bind(id)
+                    .execute(&mut *tx)
+                    .await?;
+            }
+            tx.commit().await?;
+        }
+        Ok(summary)
+    }
+}
+
+/// What `compact_events` changed, or would change on a dry run
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Compaction {
+    /// Threads with events to compact
+    pub threads: u64,
+    /// Text events folded into the first event of their run
+    pub text_merged: u64,
+    /// Tool state events superseded by a later state of the same tool
+    pub tool_states_dropped: u64,
+    /// Bytes of event data saved
+    pub bytes_saved: u64,
+}
+
+/// Changes that compact one thread's events
+#[derive(Debug, Default, PartialEq)]
+struct CompactionPlan {
+    /// New data for the first event of each merged text run
+    rewrites: Vec<(i64, String)>,
+    /// Events made redundant
+    deletes: Vec<i64>,
+    text_merged: u64,
+    tool_states_dropped: u64,
+    bytes_saved: u64,
+}
+
+impl CompactionPlan {
+    fn is_empty(&self) -> bool {
+        self.deletes.is_empty()
+    }
+}
+
+/// Plan compacting one thread's `events`, in log order
+fn plan_compaction(events: &[EventRecord]) -> CompactionPlan {
+    let mut plan = CompactionPlan::default();
+    let ends_response = |e: &EventRecord| matches!(e.event_type.as_str(), "done" | "error");
+    let Some(last_end) = events.iter().rposition(ends_response) else {
+        return plan;
+    };
+    for response in events[..=last_end].split_inclusive(ends_response) {
+        plan_response(response, &mut plan);
+    }
+    plan
+}
+
+fn plan_response(events: &[EventRecord], plan: &mut CompactionPlan) {
+    // Only the last state of each tool is worth keeping
+    let mut last_state = std::collections::HashMap::new();
+    for (i, event) in events.iter().enumerate() {
+        if let Some(tool_id) = tool_state_id(event) {
+            last_state.insert(tool_id, i);
+        }
+    }
+    let mut kept = Vec::with_capacity(events.len());
+    for (i, event) in events.iter().enumerate() {
+        match tool_state_id(event) {
+            Some(tool_id) if last_state[&tool_id] != i => {
+                plan.deletes.push(event.id);
+                plan.tool_states_dropped += 1;
+                plan.bytes_saved += event.event_data.len() as u64;
+            }
+            _ => kept.push(event),
+        }
+    }
+
+    // Dropping states can bring text runs together, so merge afterwards
+    for run in kept.chunk_by(|a, b| a.event_type == "text" && b.event_type == "text") {
+        if run.len() < 2 || run[0].event_type != "text" {
+            continue;
+        }
+        let Some(content) = run
+            .iter()
+            .map(|e| text_content(e))
+            .collect::<Option<String>>()
+        else {
+            continue;
+        };
+        let merged = serde_json::json!({ "content": content }).to_string();
+        let before: usize = run.iter().map(|e| e.event_data.len()).sum();
+        plan.bytes_saved += before.saturating_sub(merged.len()) as u64;
+        plan.rewrites.push((run[0].id, merged));
+        plan.deletes.extend(run[1..].iter().map(|e| e.id));
+        plan.text_merged += run.len() as u64 - 1;
+    }
+}
+
+/// The tool a `tool_state` event is about
+fn tool_state_id(event: &EventRecord) -> Option<String> {
+    if event.event_type != "tool_state" {
+        return None;
+    }
+    let data: serde_json::Value = serde_json::from_str(&event.event_data).ok()?;
+    data.get("id")?.as_str().map(String::from)
+}
+
+fn text_content(event: &EventRecord) -> Option<String> {
+    let data: serde_json::Value = serde_json::from_str(&event.event_data).ok()?;
+    data.get("content")?.as_str().map(String::from)
 }
 
 /// A message in a conversation
@@ -253,6 +594,8 @@ pub struct Message {
     pub role: String, // "user" or "assistant"
     pub content: String,
     pub created_at: DateTime<Utc>,
+    /// Frontend's ID of the message this one replies to
+    pub reply_to_message_id: Option<String>,
 }
 
 /// A logged backend event
@@ -271,6 +614,7 @@ struct ThreadRow {
     claude_session_id: String,
     created_at: String,
     last_active: String,
+    title: Option<String>,
 }
 
 impl From<ThreadRow> for Thread {
@@ -284,6 +628,7 @@ impl From<ThreadRow> for Thread {
             last_active: chrono::DateTime::parse_from_rfc3339(&row.last_active)
                 .map(|dt| dt.with_timezone(&Utc))
                 .unwrap_or_else(|_| Utc::now()),
+            title: row.title,
         }
     }
 }
@@ -295,6 +640,7 @@ struct MessageRow {
     role: String,
     content: String,
     created_at: String,
+    reply_to_message_id: Option<String>,
 }
 
 impl From<MessageRow> for Message {
@@ -307,10 +653,19 @@ impl From<MessageRow> for Message {
             created_at: chrono::DateTime::parse_from_rfc3339(&row.created_at)
                 .map(|dt| dt.with_timezone(&Utc))
                 .unwrap_or_else(|_| Utc::now()),
+            reply_to_message_id: row.reply_to_message_id,
         }
     }
 }
 
+/// A backend event as compaction needs it
+#[derive(Debug, sqlx::FromRow)]
+struct EventRecord {
+    id: i64,
+    event_type: String,
+    event_data: String,
+}
+
 #[derive(sqlx::FromRow)]
 struct BackendEventRow {
     id: i64,
@@ -333,3 +688,226 @@ impl From<BackendEventRow> for BackendEventLog {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    async fn store() -> (ThreadStore, tempfile::TempDir) {
+        let dir = tempfile::tempdir().unwrap();
+        let store = ThreadStore::open(dir.path().join("threads.db"))
+            .await
+            .unwrap();
+        store.get_or_create("thread").await.unwrap();
+        (store, dir)
+    }
+
+    async fn log(store: &ThreadStore, events: &[(&str, serde_json::Value)]) {
+        for (event_type, data) in events {
+            store.add_event("thread", event_type, data).await.unwrap();
+        }
+    }
+
+    fn text(content: &str) -> (&'static str, serde_json::Value) {
+        ("text", json!({ "content": content }))
+    }
+
+    fn tool_state(id: &str, state: &str) -> (&'static str, serde_json::Value) {
+        (
+            "tool_state",
+            json!({ "id": id, "state": state, "detail": null }),
+        )
+    }
+
+    async fn logged(store: &ThreadStore) -> Vec<(String, serde_json::Value)> {
+        store
+            .get_events("thread")
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|e| (e.event_type, e.event_data))
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_fork_copies_messages_up_to_index() {
+        let (store, _dir) = store().await;
+        store.set_session_id("thread", "session-1").await.unwrap();
+        store.set_title("thread", Some("Plans")).await.unwrap();
+        for (role, content) in [
+            ("user", "Where should we eat?"),
+            ("assistant", "Try the noodle place."),
+            ("user", "Something closer?"),
+            ("assistant", "The cafe downstairs."),
+        ] {
+            store.add_message("thread", role, content).await.unwrap();
+        }
+
+        let fork = store.fork("thread", 1).await.unwrap();
+        let forked: Vec<_> = store
+            .get_messages(&fork)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|m| (m.role, m.content))
+            .collect();
+        assert_eq!(
+            forked,
+            vec![
+                ("user".to_string(), "Where should we eat?".to_string()),
+                ("assistant".to_string(), "Try the noodle place.".to_string()),
+            ]
+        );
+        let thread = store.get(&fork).await.unwrap().unwrap();
+        assert_eq!(thread.claude_session_id, "");
+        assert_eq!(thread.title.as_deref(), Some("Plans"));
+
+        // The original keeps its messages and session
+        store.add_message(&fork, "user", "Cheaper?").await.unwrap();
+        assert_eq!(store.get_messages("thread").await.unwrap().len(), 4);
+        let original = store.get("thread").await.unwrap().unwrap();
+        assert_eq!(original.claude_session_id, "session-1");
+
+        assert!(store.fork("thread", 4).await.is_err());
+        assert!(store.fork("missing", 0).await.is_err());
+        assert!(store.fork_into("thread", 0, &fork).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_fork_with_history_keeps_the_title() {
+        let (store, _dir) = store().await;
+        store.set_title("thread", Some("Plans")).await.unwrap();
+        let history = vec![
+            ("user".to_string(), "Where should we eat?".to_string()),
+            ("assistant".to_string(), "Try the noodle place.".to_string()),
+        ];
+
+        store
+            .fork_with_history("thread", "fork", &history)
+            .await
+            .unwrap();
+        let forked: Vec<_> = store
+            .get_messages("fork")
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|m| (m.role, m.content))
+            .collect();
+        assert_eq!(forked, history);
+        let thread = store.get("fork").await.unwrap().unwrap();
+        assert_eq!(thread.title.as_deref(), Some("Plans"));
+
+        // Forks of threads this store never saw start untitled
+        store
+            .fork_with_history("unknown", "other", &history)
+            .await
+            .unwrap();
+        assert_eq!(store.get("other").await.unwrap().unwrap().title, None);
+        assert!(store
+            .fork_with_history("thread", "fork", &history)
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_compact_merges_text_and_keeps_tool_calls() {
+        let (store, _dir) = store().await;
+        log(
+            &store,
+            &[
+                text("Let me "),
+                text("check."),
+                (
+                    "tool_use",
+                    json!({ "id": "t1", "name": "Read", "input": {} }),
+                ),
+                tool_state("t1", "pending"),
+                tool_state("t1", "running"),
+                tool_state("t1", "completed"),
+                (
+                    "tool_result",
+                    json!({ "tool_use_id": "t1", "output": "ok" }),
+                ),
+                text("All "),
+                text("good"),
+                text("."),
+                ("done", json!({ "length": 21 })),
+            ],
+        )
+        .await;
+
+        let compaction = store.compact_events(false).await.unwrap();
+        assert_eq!(compaction.threads, 1);
+        assert_eq!(compaction.text_merged, 3);
+        assert_eq!(compaction.tool_states_dropped, 2);
+        assert!(compaction.bytes_saved > 0);
+
+        let events = logged(&store).await;
+        let types: Vec<_> = events.iter().map(|(t, _)| t.as_str()).collect();
+        assert_eq!(
+            types,
+            [
+                "text",
+                "tool_use",
+                "tool_state",
+                "tool_result",
+                "text",
+                "done"
+            ]
+        );
+        assert_eq!(events[0].1, json!({ "content": "Let me check." }));
+        assert_eq!(events[2].1["state"], "completed");
+        assert_eq!(events[4].1, json!({ "content": "All good." }));
+
+        // Nothing is left to do the second time
+        assert_eq!(
+            store.compact_events(false).await.unwrap(),
+            Compaction::default()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_compact_dry_run_changes_nothing() {
+        let (store, _dir) = store().await;
+        log(
+            &store,
+            &[text("a"), text("b"), ("done", json!({ "length": 2 }))],
+        )
+        .await;
+        let before = logged(&store).await;
+
+        let dry_run = store.compact_events(true).await.unwrap();
+        assert_eq!(dry_run.text_merged, 1);
+        assert_eq!(logged(&store).await, before);
+        assert_eq!(store.compact_events(false).await.unwrap(), dry_run);
+    }
+
+    #[tokio::test]
+    async fn test_compact_leaves_unfinished_responses_alone() {
+        let (store, _dir) = store().await;
+        log(
+            &store,
+            &[
+                text("one"),
+                ("error", json!({ "message": "failed" })),
+                // Two text events in separate responses stay apart
+                text("two"),
+                ("done", json!({ "length": 3 })),
+                // Still streaming
+                text("thr"),
+                text("ee"),
+                tool_state("t2", "pending"),
+                tool_state("t2", "running"),
+            ],
+        )
+        .await;
+        let before = logged(&store).await;
+
+        assert_eq!(
+            store.compact_events(false).await.unwrap(),
+            Compaction::default()
+        );
+        assert_eq!(logged(&store).await, before);
+    }
+}
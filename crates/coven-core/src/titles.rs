@@ -0,0 +1,150 @@
+// ABOUTME: Short readable titles for threads, generated from their first message
+// ABOUTME: Either truncates the message or asks the backend for a one-shot summary
+
+use crate::backend::{Backend, BackendEvent};
+use crate::config::{TitleConfig, TitleMode};
+use anyhow::Result;
+use futures::StreamExt;
+use std::sync::Arc;
+use uuid::Uuid;
+
+/// Generates thread titles the way the config asks
+#[derive(Clone)]
+pub struct Titler {
+    backend: Arc<dyn Backend>,
+    config: TitleConfig,
+}
+
+impl Titler {
+    pub fn new(backend: Arc<dyn Backend>, config: TitleConfig) -> Self {
+        Self { backend, config }
+    }
+
+    /// Whether titles are generated at all
+    pub fn enabled(&self) -> bool {
+        self.config.mode != TitleMode::Off
+    }
+
+    /// Title for a thread that starts with `first_message`, or None if
+    /// titling is off or the message has no text to title it by
+    pub async fn title(&self, first_message: &str) -> Option<String> {
+        match self.config.mode {
+            TitleMode::Off => None,
+            TitleMode::Truncate => truncate_title(first_message, self.config.max_chars),
+            TitleMode::Summarize => match self.summarize(first_message).await {
+                Ok(Some(title)) => Some(title),
+                Ok(None) => truncate_title(first_message, self.config.max_chars),
+                Err(e) => {
+                    tracing::warn!(error = %e, "Title summary failed, truncating instead");
+                    truncate_title(first_message, self.config.max_chars)
+                }
+            },
+        }
+    }
+
+    /// Ask the backend for a title in a throwaway session, so the request
+    /// never shows up in the thread's own context
+    async fn summarize(&self, first_message: &str) -> Result<Option<String>> {
+        if first_message.trim().is_empty() {
+            return Ok(None);
+        }
+        let prompt = format!(
+            "Write a title of at most {} characters for a conversation that starts \
+             with the message below. Reply with the title only.\n\n{}",
+            self.config.max_chars, first_message
+        );
+        let session_id = Uuid::new_v4().to_string();
+        let mut events = self.backend.send(&session_id, &prompt, true).await?;
+
+        let mut text = String::new();
+        while let Some(event) = events.next().await {
+            match event {
+                BackendEvent::Text(chunk) => text.push_str(&chunk),
+                BackendEvent::Done { full_response } => {
+                    if !full_response.is_empty() {
+                        text = full_response;
+                    }
+                    break;
+                }
+                BackendEvent::Error(e) => anyhow::bail!(e),
+                BackendEvent::BudgetExceeded(exceeded) => return Err(exceeded.into()),
+                _ => {}
+            }
+        }
+
+        let line = text.lines().map(str::trim).find(|l| !l.is_empty());
+        let cleaned = line.map(|l| {
+            l.trim_matches(|c: char| c == '"' || c == '\'' || c == '#' || c.is_whitespace())
+        });
+        Ok(cleaned.and_then(|title| truncate_title(title, self.config.max_chars)))
+    }
+}
+
+/// The first line of `text` with whitespace collapsed, cut to `max_chars`
+/// (at a word boundary when one is close) with an ellipsis
+pub fn truncate_title(text: &str, max_chars: usize) -> Option<String> {
+    let line = text.lines().find(|l| !l.trim().is_empty())?;
+    let words: Vec<&str> = line.split_whitespace().collect();
+    let title = words.join(" ");
+    if title.chars().count() <= max_chars {
+        return Some(title);
+    }
+
+    // Leave room for the ellipsis
+    let keep = max_chars.saturating_sub(1);
+    let end = title
+        .char_indices()
+        .nth(keep)
+        .map(|(i, _)| i)
+        .unwrap_or(title.len());
+    let mut cut = &title[..end];
+    if let Some(space) = cut.rfind(' ').filter(|&i| i >= end / 2) {
+        cut = &cut[..space];
+    }
+    Some(format!("{}…", cut.trim_end()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_short_message_is_its_own_title() {
+        assert_eq!(
+            truncate_title("  What's on   my calendar?  ", 60).as_deref(),
+            Some("What's on my calendar?")
+        );
+    }
+
+    #[test]
+    fn test_long_message_is_cut_at_a_word() {
+        let title = truncate_title(
+            "Can you look through the deploy logs from last night and tell me why it failed",
+            30,
+        )
+        .unwrap();
+        assert_eq!(title, "Can you look through the…");
+        assert!(title.chars().count() <= 30);
+    }
+
+    #[test]
+    fn test_only_first_line_is_used() {
+        assert_eq!(
+            truncate_title("\n\nFix the build\nHere is the log: ...", 60).as_deref(),
+            Some("Fix the build")
+        );
+    }
+
+    #[test]
+    fn test_blank_message_has_no_title() {
+        assert_eq!(truncate_title("  \n ", 60), None);
+        assert_eq!(truncate_title("", 60), None);
+    }
+
+    #[test]
+    fn test_unbroken_text_is_cut_mid_word() {
+        let title = truncate_title(&"é".repeat(100), 10).unwrap();
+        assert_eq!(title.chars().count(), 10);
+        assert!(title.ends_with('…'));
+    }
+}
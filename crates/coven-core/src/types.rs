@@ -17,6 +17,23 @@ pub struct Thread {
     pub created_at: DateTime<Utc>,
     /// Last message activity
     pub last_active: DateTime<Utc>,
+    /// Short readable title, generated after the first exchange or set with
+    /// `/title`; None until then
+    pub title: Option<String>,
+}
+
+impl Thread {
+    /// The title, or the ID for untitled threads
+    pub fn display_title(&self) -> &str {
+        self.title.as_deref().unwrap_or(&self.id)
+    }
+}
+
+/// A thread's new title, or None when it was cleared
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ThreadTitle {
+    pub thread_id: String,
+    pub title: Option<String>,
 }
 
 /// A file attachment (downloaded to local temp storage)
@@ -39,14 +56,26 @@ pub struct IncomingMessage {
     pub thread_id: String,
     /// Who sent this message
     pub sender: String,
+    /// Human-readable name of the person behind the message, when the
+    /// frontend knows it (e.g. a chat user relayed by a bridge)
+    pub sender_display: Option<String>,
     /// The message content
     pub content: String,
     /// Which frontend this came from ("slack", "tui", "matrix")
     pub frontend: String,
     /// Optional file attachments (downloaded to temp storage)
     pub attachments: Vec<FileAttachment>,
+    /// Backend settings to use for this message instead of the agent's defaults
+    pub overrides: RequestOverrides,
+    /// Frontend's ID of the earlier message in this thread that this one
+    /// replies to; None for unthreaded messages
+    pub reply_to_message_id: Option<String>,
 }
 
+// Per-request overrides of backend settings (e.g. set per chat channel by a
+// bridge). Unset fields fall back to the agent's configured defaults.
+pub use coven_proto::overrides::RequestOverrides;
+
 /// Events sent back to the frontend
 #[derive(Debug, Clone)]
 pub enum OutgoingEvent {
@@ -71,6 +100,8 @@ pub enum OutgoingEvent {
         id: String,
         name: String,
         input: serde_json::Value,
+        /// Human-readable confirmation text supplied by the tool definition
+        confirm_message: Option<String>,
     },
     /// Token usage statistics from LLM call
     Usage {
@@ -86,6 +117,12 @@ pub enum OutgoingEvent {
         state: String, // "pending", "awaiting_approval", "running", "completed", "failed", "denied", "timeout", "cancelled"
         detail: Option<String>,
     },
+    /// Spend against the agent's budget after a model call, with any limit
+    /// it took past 80%
+    Budget(crate::backend::BudgetReport),
+    /// The request was refused rather than risk going over budget; ends the
+    /// response like Error
+    BudgetExceeded(crate::backend::BudgetExceeded),
     /// Response complete
     Done { full_response: String },
     /// Something went wrong
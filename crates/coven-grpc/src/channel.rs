@@ -1,36 +1,143 @@
 // ABOUTME: gRPC channel creation with keep-alive and TLS configuration.
-// ABOUTME: Provides configurable channel builder for coven gRPC connections.
-
-use std::time::Duration;
-use tonic::transport::{Channel, ClientTlsConfig, Endpoint};
+// ABOUTME: Provides configurable channel builder for coven gRPC connections, and a gateway health probe.
+
+use coven_proto::client::ClientServiceClient;
+use coven_proto::limits::MessageLimits;
+use serde::{Deserialize, Serialize};
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+use tonic::service::Interceptor;
+use tonic::transport::{Channel, ClientTlsConfig, Endpoint, Uri};
+use tonic::{Code, Request, Status};
 
 use crate::error::GrpcClientError;
+use crate::stream::DEFAULT_CHANNEL_BUFFER;
 
 /// Configuration for gRPC channel keep-alive behavior.
-#[derive(Debug, Clone)]
+///
+/// The defaults ping every five minutes, the shortest interval grpc-go and
+/// grpc-java servers accept without tuning; they answer more frequent pings
+/// with a `too_many_pings` GOAWAY. Behind NATs or load balancers that drop
+/// idle connections sooner, lower the interval to 30-60 seconds and make
+/// sure the gateway's keep-alive enforcement allows it.
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct KeepAliveConfig {
     /// Interval between keep-alive pings when the connection is idle.
     pub interval: Duration,
     /// Timeout waiting for keep-alive response before considering connection dead.
+    /// Must be shorter than `interval`.
     pub timeout: Duration,
-    /// Whether to send keep-alive pings even when no streams are active.
+    /// Whether to send keep-alive pings even when no streams are active
+    /// (gRPC's "permit without stream"). Servers reject these by default.
     pub while_idle: bool,
 }
 
 impl Default for KeepAliveConfig {
     fn default() -> Self {
         Self {
-            interval: Duration::from_secs(10),
+            interval: Duration::from_secs(300),
             timeout: Duration::from_secs(20),
-            while_idle: true,
+            while_idle: false,
         }
     }
 }
 
+impl KeepAliveConfig {
+    /// Check that the interval is set and the timeout is shorter than it,
+    /// so a dead peer is noticed before the next ping is due.
+    pub fn validate(&self) -> Result<(), GrpcClientError> {
+        if self.interval.is_zero() {
+            return Err(GrpcClientError::InvalidConfig(
+                "keep-alive interval must be greater than zero".to_string(),
+            ));
+        }
+        if self.timeout.is_zero() {
+            return Err(GrpcClientError::InvalidConfig(
+                "keep-alive timeout must be greater than zero".to_string(),
+            ));
+        }
+        if self.timeout >= self.interval {
+            return Err(GrpcClientError::InvalidConfig(format!(
+                "keep-alive timeout ({}s) must be less than the interval ({}s)",
+                self.timeout.as_secs_f64(),
+                self.interval.as_secs_f64()
+            )));
+        }
+        Ok(())
+    }
+
+    /// Apply these settings to an endpoint, for callers that build their
+    /// own instead of using `create_channel`.
+    pub fn apply(&self, endpoint: Endpoint) -> Endpoint {
+        endpoint
+            .http2_keep_alive_interval(self.interval)
+            .keep_alive_timeout(self.timeout)
+            .keep_alive_while_idle(self.while_idle)
+    }
+}
+
+/// Keep-alive settings as written in agent and bridge config files:
+///
+/// ```toml
+/// [keepalive]
+/// interval_secs = 60
+/// timeout_secs = 20
+/// permit_without_stream = false
+/// ```
+///
+/// Unset keys take the `KeepAliveConfig` defaults; `enabled = false` turns
+/// keep-alive pings off.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct KeepAliveSettings {
+    /// Send keep-alive pings at all.
+    pub enabled: bool,
+    /// Seconds between pings.
+    pub interval_secs: u64,
+    /// Seconds to wait for a ping's answer; less than `interval_secs`.
+    pub timeout_secs: u64,
+    /// Ping even when no streams are active.
+    pub permit_without_stream: bool,
+}
+
+impl Default for KeepAliveSettings {
+    fn default() -> Self {
+        let defaults = KeepAliveConfig::default();
+        Self {
+            enabled: true,
+            interval_secs: defaults.interval.as_secs(),
+            timeout_secs: defaults.timeout.as_secs(),
+            permit_without_stream: defaults.while_idle,
+        }
+    }
+}
+
+impl KeepAliveSettings {
+    /// The validated keep-alive config, or None when disabled.
+    pub fn to_config(&self) -> Result<Option<KeepAliveConfig>, GrpcClientError> {
+        if !self.enabled {
+            return Ok(None);
+        }
+        let config = KeepAliveConfig {
+            interval: Duration::from_secs(self.interval_secs),
+            timeout: Duration::from_secs(self.timeout_secs),
+            while_idle: self.permit_without_stream,
+        };
+        config.validate()?;
+        Ok(Some(config))
+    }
+}
+
+/// Scheme for gateways listening on a Unix domain socket, as in
+/// `unix:///run/coven/gateway.sock`.
+pub const UNIX_SCHEME: &str = "unix://";
+
 /// Configuration for creating a gRPC channel.
 #[derive(Debug, Clone)]
 pub struct ChannelConfig {
-    /// Server address to connect to (e.g., "http://localhost:50051").
+    /// Server address to connect to (e.g., "http://localhost:50051", or
+    /// "unix:///path/to.sock" for a Unix domain socket).
     pub address: String,
     /// Keep-alive configuration. If None, keep-alive is disabled.
     pub keep_alive: Option<KeepAliveConfig>,
@@ -38,6 +145,13 @@ pub struct ChannelConfig {
     pub connect_timeout: Option<Duration>,
     /// Enable TLS for the connection.
     pub use_tls: bool,
+    /// Message size limits for clients built on the channel. tonic sets
+    /// these per client, so pass them to the generated client's
+    /// `max_decoding_message_size` and `max_encoding_message_size`.
+    pub message_limits: MessageLimits,
+    /// Messages an outbound stream buffers before senders have to wait
+    /// for the network to drain it. See `OutboundStream::from_config`.
+    pub stream_buffer: usize,
 }
 
 impl ChannelConfig {
@@ -51,9 +165,16 @@ impl ChannelConfig {
             keep_alive: Some(KeepAliveConfig::default()),
             connect_timeout: Some(Duration::from_secs(30)),
             use_tls,
+            message_limits: MessageLimits::default(),
+            stream_buffer: DEFAULT_CHANNEL_BUFFER,
         }
     }
 
+    /// Socket path when the address uses the `unix://` scheme
+    pub fn unix_socket_path(&self) -> Option<PathBuf> {
+        self.address.strip_prefix(UNIX_SCHEME).map(PathBuf::from)
+    }
+
     /// Detect TLS from URL scheme (case-insensitive).
     fn detect_tls(addr: &str) -> bool {
         addr.to_lowercase().starts_with("https://")
@@ -89,6 +210,18 @@ impl ChannelConfig {
         self
     }
 
+    /// Set the message size limits for clients built on the channel.
+    pub fn with_message_limits(mut self, limits: MessageLimits) -> Self {
+        self.message_limits = limits;
+        self
+    }
+
+    /// Set the outbound stream buffer size (at least 1).
+    pub fn with_stream_buffer(mut self, size: usize) -> Self {
+        self.stream_buffer = size.max(1);
+        self
+    }
+
     /// Enable TLS for the connection.
     /// Also normalizes the address scheme to https:// if it was http://.
     pub fn with_tls(mut self) -> Self {
@@ -110,13 +243,22 @@ impl ChannelConfig {
 ///
 /// Applies keep-alive and TLS settings if configured. Keep-alive is important for
 /// long-lived streaming connections to detect dead peers and prevent
-/// connection resets from load balancers.
+/// connection resets from load balancers. `unix://` addresses connect over
+/// a Unix domain socket, never with TLS.
 pub async fn create_channel(config: &ChannelConfig) -> Result<Channel, GrpcClientError> {
-    let mut endpoint = Endpoint::from_shared(config.address.clone())
-        .map_err(|e| GrpcClientError::InvalidAddress(e.to_string()))?;
+    let socket_path = config.unix_socket_path();
+
+    // A Unix socket has no authority; the endpoint URI only carries the
+    // channel settings and is never dialed.
+    let uri = match &socket_path {
+        Some(_) => "http://[::]:50051".to_string(),
+        None => config.address.clone(),
+    };
+    let mut endpoint =
+        Endpoint::from_shared(uri).map_err(|e| GrpcClientError::InvalidAddress(e.to_string()))?;
 
     // Apply TLS if configured
-    if config.use_tls {
+    if config.use_tls && socket_path.is_none() {
         endpoint = endpoint
             .tls_config(ClientTlsConfig::new())
             .map_err(|e| GrpcClientError::ConnectionFailed(format!("TLS config error: {}", e)))?;
@@ -124,10 +266,8 @@ pub async fn create_channel(config: &ChannelConfig) -> Result<Channel, GrpcClien
 
     // Apply keep-alive settings if configured
     if let Some(ka) = &config.keep_alive {
-        endpoint = endpoint
-            .http2_keep_alive_interval(ka.interval)
-            .keep_alive_timeout(ka.timeout)
-            .keep_alive_while_idle(ka.while_idle);
+        ka.validate()?;
+        endpoint = ka.apply(endpoint);
     }
 
     // Apply connection timeout if configured
@@ -135,10 +275,11 @@ pub async fn create_channel(config: &ChannelConfig) -> Result<Channel, GrpcClien
         endpoint = endpoint.connect_timeout(timeout);
     }
 
-    let channel = endpoint
-        .connect()
-        .await
-        .map_err(|e| GrpcClientError::ConnectionFailed(e.to_string()))?;
+    let channel = match socket_path {
+        Some(path) => connect_unix(endpoint, path).await,
+        None => endpoint.connect().await,
+    }
+    .map_err(|e| GrpcClientError::ConnectionFailed(e.to_string()))?;
 
     tracing::debug!(
         address = %config.address,
@@ -150,12 +291,291 @@ pub async fn create_channel(config: &ChannelConfig) -> Result<Channel, GrpcClien
     Ok(channel)
 }
 
+#[cfg(unix)]
+async fn connect_unix(
+    endpoint: Endpoint,
+    path: PathBuf,
+) -> Result<Channel, tonic::transport::Error> {
+    endpoint
+        .connect_with_connector(tower::service_fn(move |_: tonic::transport::Uri| {
+            let path = path.clone();
+            async move {
+                let stream = tokio::net::UnixStream::connect(path).await?;
+                Ok::<_, std::io::Error>(hyper_util::rt::TokioIo::new(stream))
+            }
+        }))
+        .await
+}
+
+#[cfg(not(unix))]
+async fn connect_unix(
+    endpoint: Endpoint,
+    _path: PathBuf,
+) -> Result<Channel, tonic::transport::Error> {
+    // Unix sockets aren't available; fail the same way an unreachable
+    // server would.
+    endpoint
+        .connect_with_connector(tower::service_fn(|_: tonic::transport::Uri| async {
+            Err::<hyper_util::rt::TokioIo<tokio::net::TcpStream>, _>(std::io::Error::new(
+                std::io::ErrorKind::Unsupported,
+                "Unix domain sockets are not supported on this platform",
+            ))
+        }))
+        .await
+}
+
 /// Create a simple channel without keep-alive (useful for one-shot operations).
 pub async fn create_simple_channel(address: &str) -> Result<Channel, GrpcClientError> {
     let config = ChannelConfig::new(address).without_keep_alive();
     create_channel(&config).await
 }
 
+/// How a gateway probe came out
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProbeStatus {
+    /// The gateway answered, and accepted the credentials if any were sent.
+    Healthy,
+    /// The address isn't a usable gateway URL.
+    InvalidAddress,
+    /// The host name didn't resolve.
+    DnsFailed,
+    /// The host is up but nothing listens on the port or socket.
+    ConnectionRefused,
+    /// A step got no answer within the timeout.
+    TimedOut,
+    /// Something answered, but the TLS or gRPC handshake failed or the
+    /// gateway reported itself unavailable.
+    Unreachable,
+    /// The gateway answered but rejected the credentials.
+    AuthRejected,
+}
+
+/// What `probe_gateway` found out about a gateway.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GatewayHealth {
+    pub status: ProbeStatus,
+    /// Whether the credentials were accepted; None when none were sent or
+    /// the probe stopped before trying them.
+    pub auth_ok: Option<bool>,
+    /// Round trip of the version call, once the gateway answered it.
+    pub latency: Option<Duration>,
+    /// Gateway version, when it reports one.
+    pub version: Option<String>,
+    /// Gateway implementation, e.g. "coven-serve".
+    pub component: Option<String>,
+    /// Why the probe failed, for any status but Healthy.
+    pub detail: Option<String>,
+}
+
+impl GatewayHealth {
+    fn failed(status: ProbeStatus, detail: impl Into<String>) -> Self {
+        Self {
+            status,
+            auth_ok: None,
+            latency: None,
+            version: None,
+            component: None,
+            detail: Some(detail.into()),
+        }
+    }
+
+    /// Whether a gateway answered at all, even if it refused the credentials.
+    pub fn reachable(&self) -> bool {
+        matches!(
+            self.status,
+            ProbeStatus::Healthy | ProbeStatus::AuthRejected
+        )
+    }
+}
+
+/// Check whether a gateway is up without registering: resolve its host,
+/// open a connection, and ask for its version. Each step may take up to
+/// `timeout`. The version call needs no credentials, so `auth_ok` stays
+/// None; use `probe_gateway_with_auth` to check them too.
+pub async fn probe_gateway(url: &str, timeout: Duration) -> GatewayHealth {
+    probe(url, timeout, OptionalAuth::<NoAuth>(None)).await
+}
+
+/// `probe_gateway`, then an authenticated GetMe call through `auth` to
+/// tell whether the gateway accepts the caller's credentials.
+pub async fn probe_gateway_with_auth<I>(url: &str, timeout: Duration, auth: I) -> GatewayHealth
+where
+    I: Interceptor + Send,
+{
+    probe(url, timeout, OptionalAuth(Some(auth))).await
+}
+
+/// Interceptor type for probes that send no credentials
+type NoAuth = fn(Request<()>) -> Result<Request<()>, Status>;
+
+/// Runs `auth` when there is one and passes requests through otherwise
+struct OptionalAuth<I>(Option<I>);
+
+impl<I: Interceptor> Interceptor for OptionalAuth<I> {
+    fn call(&mut self, request: Request<()>) -> Result<Request<()>, Status> {
+        match &mut self.0 {
+            Some(auth) => auth.call(request),
+            None => Ok(request),
+        }
+    }
+}
+
+async fn probe<I: Interceptor + Send>(
+    url: &str,
+    timeout: Duration,
+    auth: OptionalAuth<I>,
+) -> GatewayHealth {
+    let config = ChannelConfig::new(url)
+        .without_keep_alive()
+        .with_connect_timeout(timeout);
+    let is_unix = config.unix_socket_path().is_some();
+
+    // Dial the host ourselves first: tonic's connect errors don't say
+    // whether the name, the port, or the handshake was the problem
+    if !is_unix {
+        if let Err(health) = check_tcp(&config, timeout).await {
+            return health;
+        }
+    }
+
+    let channel = match create_channel(&config).await {
+        Ok(channel) => channel,
+        // Nothing listens on the socket, or it isn't a socket at all
+        Err(e) if is_unix => {
+            return GatewayHealth::failed(ProbeStatus::ConnectionRefused, e.to_string())
+        }
+        Err(e) => return GatewayHealth::failed(ProbeStatus::Unreachable, e.to_string()),
+    };
+    let authenticate = auth.0.is_some();
+    let mut client = ClientServiceClient::with_interceptor(channel, auth);
+
+    let started = Instant::now();
+    let mut health = match tokio::time::timeout(timeout, client.get_version(())).await {
+        Err(_) => return GatewayHealth::failed(ProbeStatus::TimedOut, "no answer to GetVersion"),
+        Ok(Ok(response)) => {
+            let response = response.into_inner();
+            GatewayHealth {
+                status: ProbeStatus::Healthy,
+                auth_ok: None,
+                latency: Some(started.elapsed()),
+                version: Some(response.version),
+                component: Some(response.component),
+                detail: None,
+            }
+        }
+        Ok(Err(status)) => {
+            let mut health = rpc_failure(&status, started.elapsed());
+            if health.status != ProbeStatus::Healthy {
+                if health.status == ProbeStatus::AuthRejected && authenticate {
+                    health.auth_ok = Some(false);
+                }
+                return health;
+            }
+            health
+        }
+    };
+
+    if authenticate {
+        match tokio::time::timeout(timeout, client.get_me(())).await {
+            Err(_) => {
+                health.status = ProbeStatus::TimedOut;
+                health.detail = Some("no answer to GetMe".to_string());
+            }
+            Ok(Ok(_)) => health.auth_ok = Some(true),
+            Ok(Err(status)) => {
+                let failure = rpc_failure(&status, started.elapsed());
+                health.auth_ok = (failure.status == ProbeStatus::AuthRejected).then_some(false);
+                if failure.status != ProbeStatus::Healthy {
+                    health.status = failure.status;
+                    health.detail = failure.detail;
+                }
+            }
+        }
+    }
+    health
+}
+
+/// Resolve the gateway's host and open a TCP connection to it
+async fn check_tcp(config: &ChannelConfig, timeout: Duration) -> Result<(), GatewayHealth> {
+    let uri = config
+        .address
+        .parse::<Uri>()
+        .map_err(|e| GatewayHealth::failed(ProbeStatus::InvalidAddress, e.to_string()))?;
+    let Some(host) = uri.host() else {
+        return Err(GatewayHealth::failed(
+            ProbeStatus::InvalidAddress,
+            "no host in the address",
+        ));
+    };
+    let host = host.trim_start_matches('[').trim_end_matches(']');
+    let port = uri
+        .port_u16()
+        .unwrap_or(if config.use_tls { 443 } else { 80 });
+
+    let addrs: Vec<SocketAddr> =
+        match tokio::time::timeout(timeout, tokio::net::lookup_host((host, port))).await {
+            Err(_) => {
+                return Err(GatewayHealth::failed(
+                    ProbeStatus::TimedOut,
+                    format!("resolving {} timed out", host),
+                ))
+            }
+            Ok(Err(e)) => {
+                return Err(GatewayHealth::failed(
+                    ProbeStatus::DnsFailed,
+                    format!("cannot resolve {}: {}", host, e),
+                ))
+            }
+            Ok(Ok(addrs)) => addrs.collect(),
+        };
+    if addrs.is_empty() {
+        return Err(GatewayHealth::failed(
+            ProbeStatus::DnsFailed,
+            format!("{} has no addresses", host),
+        ));
+    }
+
+    match tokio::time::timeout(timeout, tokio::net::TcpStream::connect(&addrs[..])).await {
+        Err(_) => Err(GatewayHealth::failed(
+            ProbeStatus::TimedOut,
+            format!("connecting to {}:{} timed out", host, port),
+        )),
+        Ok(Err(e)) => {
+            let status = match e.kind() {
+                std::io::ErrorKind::ConnectionRefused => ProbeStatus::ConnectionRefused,
+                std::io::ErrorKind::TimedOut => ProbeStatus::TimedOut,
+                _ => ProbeStatus::Unreachable,
+            };
+            Err(GatewayHealth::failed(
+                status,
+                format!("cannot connect to {}:{}: {}", host, port, e),
+            ))
+        }
+        Ok(Ok(_)) => Ok(()),
+    }
+}
+
+/// What a failed probe call says about the gateway. Codes other than
+/// the ones below still prove it answered, so they count as healthy, and
+/// `Unimplemented` only means it predates the call.
+fn rpc_failure(status: &Status, elapsed: Duration) -> GatewayHealth {
+    let probe_status = match status.code() {
+        Code::Unauthenticated | Code::PermissionDenied => ProbeStatus::AuthRejected,
+        Code::Unavailable => ProbeStatus::Unreachable,
+        Code::DeadlineExceeded => ProbeStatus::TimedOut,
+        _ => ProbeStatus::Healthy,
+    };
+    GatewayHealth {
+        status: probe_status,
+        auth_ok: None,
+        latency: (probe_status != ProbeStatus::Unreachable).then_some(elapsed),
+        version: None,
+        component: None,
+        detail: (probe_status != ProbeStatus::Healthy).then(|| status.message().to_string()),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -168,9 +588,79 @@ mod tests {
     #[test]
     fn test_default_keep_alive() {
         let ka = KeepAliveConfig::default();
-        assert_eq!(ka.interval, Duration::from_secs(10));
+        assert_eq!(ka.interval, Duration::from_secs(300));
         assert_eq!(ka.timeout, Duration::from_secs(20));
+        assert!(!ka.while_idle);
+        assert!(ka.validate().is_ok());
+    }
+
+    #[test]
+    fn test_keep_alive_timeout_must_be_less_than_interval() {
+        let ka = KeepAliveConfig {
+            interval: Duration::from_secs(10),
+            timeout: Duration::from_secs(20),
+            while_idle: true,
+        };
+        let err = ka.validate().unwrap_err();
+        assert!(matches!(err, GrpcClientError::InvalidConfig(_)));
+        assert!(err.to_string().contains("less than the interval"));
+
+        let ka = KeepAliveConfig {
+            timeout: Duration::from_secs(10),
+            ..ka
+        };
+        assert!(ka.validate().is_err());
+
+        let ka = KeepAliveConfig {
+            interval: Duration::ZERO,
+            ..KeepAliveConfig::default()
+        };
+        assert!(ka.validate().is_err());
+    }
+
+    #[test]
+    fn test_keep_alive_settings() {
+        let settings = KeepAliveSettings::default();
+        assert_eq!(
+            settings.to_config().unwrap(),
+            Some(KeepAliveConfig::default())
+        );
+
+        let settings = KeepAliveSettings {
+            interval_secs: 30,
+            timeout_secs: 10,
+            permit_without_stream: true,
+            ..Default::default()
+        };
+        let ka = settings.to_config().unwrap().unwrap();
+        assert_eq!(ka.interval, Duration::from_secs(30));
+        assert_eq!(ka.timeout, Duration::from_secs(10));
         assert!(ka.while_idle);
+
+        let settings = KeepAliveSettings {
+            interval_secs: 10,
+            ..Default::default()
+        };
+        assert!(settings.to_config().is_err());
+
+        let settings = KeepAliveSettings {
+            enabled: false,
+            interval_secs: 10,
+            ..Default::default()
+        };
+        assert_eq!(settings.to_config().unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn test_create_channel_rejects_invalid_keep_alive() {
+        let config =
+            ChannelConfig::new("http://localhost:50051").with_keep_alive(KeepAliveConfig {
+                interval: Duration::from_secs(5),
+                timeout: Duration::from_secs(10),
+                while_idle: false,
+            });
+        let err = create_channel(&config).await.unwrap_err();
+        assert!(matches!(err, GrpcClientError::InvalidConfig(_)));
     }
 
     #[test]
@@ -190,6 +680,23 @@ mod tests {
         assert!(!ka.while_idle);
     }
 
+    #[test]
+    fn test_channel_config_message_limits() {
+        let config = ChannelConfig::new("http://localhost:50051");
+        assert_eq!(config.message_limits, MessageLimits::default());
+
+        let config = config.with_message_limits(MessageLimits::new(1024));
+        assert_eq!(config.message_limits.max_decoding, 1024);
+        assert_eq!(config.message_limits.max_encoding, 1024);
+    }
+
+    #[test]
+    fn test_channel_config_stream_buffer() {
+        let config = ChannelConfig::new("http://localhost:50051");
+        assert_eq!(config.stream_buffer, DEFAULT_CHANNEL_BUFFER);
+        assert_eq!(config.with_stream_buffer(0).stream_buffer, 1);
+    }
+
     #[test]
     fn test_channel_config_without_keep_alive() {
         let config = ChannelConfig::new("http://localhost:50051").without_keep_alive();
@@ -205,9 +712,7 @@ mod tests {
 
         // Check default keep-alive values
         let ka = config.keep_alive.unwrap();
-        assert_eq!(ka.interval, Duration::from_secs(10));
-        assert_eq!(ka.timeout, Duration::from_secs(20));
-        assert!(ka.while_idle);
+        assert_eq!(ka, KeepAliveConfig::default());
     }
 
     #[test]
@@ -513,4 +1018,62 @@ mod tests {
         // Explicitly drop server after await to prevent NLL from dropping early
         drop(server);
     }
+
+    #[tokio::test]
+    async fn test_probe_connection_refused() {
+        // Nothing listens on port 1
+        let health = probe_gateway("http://127.0.0.1:1", Duration::from_secs(1)).await;
+        assert_eq!(health.status, ProbeStatus::ConnectionRefused);
+        assert!(!health.reachable());
+        assert_eq!(health.latency, None);
+    }
+
+    #[tokio::test]
+    async fn test_probe_dns_failure() {
+        // .invalid never resolves
+        let health =
+            probe_gateway("http://coven-gateway.invalid:50051", Duration::from_secs(5)).await;
+        assert_eq!(health.status, ProbeStatus::DnsFailed);
+        assert!(health.detail.unwrap().contains("coven-gateway.invalid"));
+    }
+
+    #[tokio::test]
+    async fn test_probe_invalid_address() {
+        let health = probe_gateway("http://gate way:50051", Duration::from_secs(1)).await;
+        assert_eq!(health.status, ProbeStatus::InvalidAddress);
+    }
+
+    #[tokio::test]
+    async fn test_probe_handshake_failure() {
+        ensure_crypto_provider();
+        let server = PlaintextServer::start();
+        let addr = format!("https://127.0.0.1:{}", server.port);
+
+        // The port is open, but nothing there speaks TLS
+        let health = probe_gateway(&addr, Duration::from_millis(500)).await;
+        assert_eq!(health.status, ProbeStatus::Unreachable);
+        assert!(!health.reachable());
+
+        drop(server);
+    }
+
+    #[test]
+    fn test_rpc_failure_statuses() {
+        let elapsed = Duration::from_millis(12);
+
+        let health = rpc_failure(&Status::unauthenticated("bad token"), elapsed);
+        assert_eq!(health.status, ProbeStatus::AuthRejected);
+        assert!(health.reachable());
+        assert_eq!(health.latency, Some(elapsed));
+        assert_eq!(health.detail.as_deref(), Some("bad token"));
+
+        let health = rpc_failure(&Status::unavailable("shutting down"), elapsed);
+        assert_eq!(health.status, ProbeStatus::Unreachable);
+        assert_eq!(health.latency, None);
+
+        // An older gateway without the call still answered
+        let health = rpc_failure(&Status::unimplemented("GetVersion"), elapsed);
+        assert_eq!(health.status, ProbeStatus::Healthy);
+        assert_eq!(health.detail, None);
+    }
 }
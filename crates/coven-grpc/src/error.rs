@@ -10,6 +10,10 @@ pub enum GrpcClientError {
     #[error("invalid server address: {0}")]
     InvalidAddress(String),
 
+    /// Invalid channel configuration, e.g. keep-alive timings.
+    #[error("invalid channel config: {0}")]
+    InvalidConfig(String),
+
     /// Failed to connect to the server.
     #[error("connection failed: {0}")]
     ConnectionFailed(String),
@@ -30,6 +34,10 @@ pub enum GrpcClientError {
     #[error("stream closed unexpectedly")]
     StreamClosed,
 
+    /// The outbound buffer is full; only returned by non-blocking sends.
+    #[error("stream buffer full")]
+    StreamFull,
+
     /// Error on the gRPC stream.
     #[error("stream error: {0}")]
     StreamError(String),
@@ -119,6 +127,9 @@ mod tests {
         let stream_closed = GrpcClientError::StreamClosed;
         assert!(stream_closed.to_string().contains("stream closed"));
 
+        let stream_full = GrpcClientError::StreamFull;
+        assert!(stream_full.to_string().contains("buffer full"));
+
         let stream_error = GrpcClientError::StreamError("broken".to_string());
         assert!(stream_error.to_string().contains("stream error"));
 
@@ -8,7 +8,11 @@ pub mod registration;
 pub mod stream;
 
 // Channel creation
-pub use channel::{create_channel, create_simple_channel, ChannelConfig, KeepAliveConfig};
+pub use channel::{
+    create_channel, create_simple_channel, probe_gateway, probe_gateway_with_auth, ChannelConfig,
+    GatewayHealth, KeepAliveConfig, KeepAliveSettings, ProbeStatus, UNIX_SCHEME,
+};
+pub use coven_proto::limits::{MessageLimits, MessageTooLarge, DEFAULT_MAX_MESSAGE_SIZE};
 
 // Error types
 pub use error::GrpcClientError;
@@ -21,7 +25,8 @@ pub use registration::{
 
 // Stream management
 pub use stream::{
-    BidirectionalStream, OutboundStream, StreamReceiver, StreamSender, DEFAULT_CHANNEL_BUFFER,
+    BidirectionalStream, BufferStats, OutboundStream, StreamReceiver, StreamSender,
+    DEFAULT_CHANNEL_BUFFER,
 };
 
 // Message handling
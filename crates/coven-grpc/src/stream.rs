@@ -2,44 +2,146 @@
 // ABOUTME: Provides typed sender/receiver wrappers and stream creation utilities.
 
 use std::pin::Pin;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
 
 use futures::Stream;
 use tokio::sync::mpsc;
+use tokio::sync::mpsc::error::TrySendError;
 use tokio_stream::wrappers::ReceiverStream;
 use tonic::Streaming;
 
+use crate::channel::ChannelConfig;
 use crate::error::GrpcClientError;
 
 /// Default buffer size for outbound message channels.
 pub const DEFAULT_CHANNEL_BUFFER: usize = 100;
 
+/// How full the buffer must be, in percent, for a send to count as near full.
+const NEAR_FULL_PERCENT: usize = 90;
+
+/// Near-full sends in a row before warning that the buffer is too small.
+const NEAR_FULL_WARN_AFTER: u64 = 50;
+
+/// Sends that had to wait between repeated "buffer full" warnings.
+const FULL_WARN_EVERY: u64 = 100;
+
+/// Buffer pressure on one stream, shared by all clones of its sender.
+#[derive(Debug, Default)]
+struct Pressure {
+    full_waits: AtomicU64,
+    near_full_streak: AtomicU64,
+}
+
+/// How full an outbound stream's buffer is, for metrics and diagnostics.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BufferStats {
+    /// Configured buffer size.
+    pub buffer_size: usize,
+    /// Messages buffered and not yet taken by the network.
+    pub queued: usize,
+    /// Sends so far that found the buffer full and had to wait.
+    pub full_waits: u64,
+}
+
 /// Sender half of a bidirectional stream.
 ///
 /// Wraps an mpsc sender for outgoing messages with convenience methods.
+/// When the buffer is full, `send` waits for room rather than dropping the
+/// message, and logs a warning so an undersized buffer gets noticed.
 #[derive(Debug, Clone)]
 pub struct StreamSender<T> {
     inner: mpsc::Sender<T>,
+    pressure: Arc<Pressure>,
 }
 
 impl<T> StreamSender<T> {
     /// Create a stream sender from an mpsc sender.
     pub fn new(sender: mpsc::Sender<T>) -> Self {
-        Self { inner: sender }
+        Self {
+            inner: sender,
+            pressure: Arc::default(),
+        }
     }
 
-    /// Send a message on the stream.
+    /// Send a message on the stream, waiting for buffer space if needed.
     pub async fn send(&self, msg: T) -> Result<(), GrpcClientError> {
+        let msg = match self.inner.try_send(msg) {
+            Ok(()) => {
+                self.record_depth();
+                return Ok(());
+            }
+            Err(TrySendError::Closed(_)) => return Err(GrpcClientError::StreamClosed),
+            Err(TrySendError::Full(msg)) => msg,
+        };
+
+        let waits = self.pressure.full_waits.fetch_add(1, Ordering::Relaxed) + 1;
+        if waits == 1 || waits % FULL_WARN_EVERY == 0 {
+            tracing::warn!(
+                buffer_size = self.buffer_size(),
+                full_waits = waits,
+                "outbound stream buffer full, waiting for the network to drain it"
+            );
+        }
         self.inner
             .send(msg)
             .await
-            .map_err(|_| GrpcClientError::StreamClosed)
+            .map_err(|_| GrpcClientError::StreamClosed)?;
+        self.record_depth();
+        Ok(())
     }
 
     /// Try to send a message without waiting.
+    ///
+    /// Returns `StreamFull` if the buffer has no room; the message is not sent.
     pub fn try_send(&self, msg: T) -> Result<(), GrpcClientError> {
-        self.inner
-            .try_send(msg)
-            .map_err(|_| GrpcClientError::StreamClosed)
+        self.inner.try_send(msg).map_err(|e| match e {
+            TrySendError::Full(_) => GrpcClientError::StreamFull,
+            TrySendError::Closed(_) => GrpcClientError::StreamClosed,
+        })
+    }
+
+    /// Track how long the buffer has stayed near full, warning when it
+    /// stays that way for `NEAR_FULL_WARN_AFTER` sends in a row.
+    fn record_depth(&self) {
+        let queued = self.queued();
+        let buffer_size = self.buffer_size();
+        if queued * 100 < buffer_size * NEAR_FULL_PERCENT {
+            self.pressure.near_full_streak.store(0, Ordering::Relaxed);
+            return;
+        }
+        let streak = self
+            .pressure
+            .near_full_streak
+            .fetch_add(1, Ordering::Relaxed)
+            + 1;
+        if streak % NEAR_FULL_WARN_AFTER == 0 {
+            tracing::warn!(
+                buffer_size,
+                queued,
+                "outbound stream buffer near full for {} sends in a row; consider a larger stream buffer",
+                streak
+            );
+        }
+    }
+
+    /// The buffer size the stream was created with.
+    pub fn buffer_size(&self) -> usize {
+        self.inner.max_capacity()
+    }
+
+    /// Messages buffered and not yet taken by the network.
+    pub fn queued(&self) -> usize {
+        self.buffer_size() - self.inner.capacity()
+    }
+
+    /// Current buffer usage and how often senders have had to wait.
+    pub fn stats(&self) -> BufferStats {
+        BufferStats {
+            buffer_size: self.buffer_size(),
+            queued: self.queued(),
+            full_waits: self.pressure.full_waits.load(Ordering::Relaxed),
+        }
     }
 
     /// Check if the stream is closed.
@@ -123,6 +225,11 @@ impl<T> OutboundStream<T> {
     pub fn with_default_buffer() -> Self {
         Self::new(DEFAULT_CHANNEL_BUFFER)
     }
+
+    /// Create a outbound stream pair with the config's `stream_buffer` size.
+    pub fn from_config(config: &ChannelConfig) -> Self {
+        Self::new(config.stream_buffer)
+    }
 }
 
 /// A complete bidirectional stream pair after connection is established.
@@ -195,6 +302,54 @@ mod tests {
         assert_eq!(received, "hello");
     }
 
+    #[test]
+    fn test_stream_sender_try_send_full() {
+        let (tx, _rx) = mpsc::channel::<String>(1);
+        let sender = StreamSender::new(tx);
+
+        sender.try_send("first".to_string()).unwrap();
+        let result = sender.try_send("second".to_string());
+        assert!(matches!(result.unwrap_err(), GrpcClientError::StreamFull));
+    }
+
+    #[tokio::test]
+    async fn test_slow_consumer_applies_backpressure() {
+        let outbound: OutboundStream<usize> = OutboundStream::new(2);
+        let sender = outbound.sender.clone();
+        assert_eq!(sender.buffer_size(), 2);
+
+        // Drains one message every few milliseconds
+        let consumer = tokio::spawn(async move {
+            let mut stream = outbound.stream;
+            let mut received = Vec::new();
+            while let Some(n) = futures::StreamExt::next(&mut stream).await {
+                tokio::time::sleep(std::time::Duration::from_millis(5)).await;
+                received.push(n);
+            }
+            received
+        });
+
+        for n in 0..10 {
+            sender.send(n).await.unwrap();
+            assert!(sender.queued() <= 2);
+        }
+        let stats = sender.stats();
+        assert_eq!(stats.buffer_size, 2);
+        assert!(stats.full_waits > 0, "{stats:?}");
+        drop(sender);
+
+        // Nothing was dropped, and order was kept
+        assert_eq!(consumer.await.unwrap(), (0..10).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_outbound_stream_from_config() {
+        let config = ChannelConfig::new("http://localhost:50051").with_stream_buffer(7);
+        let outbound: OutboundStream<String> = OutboundStream::from_config(&config);
+        assert_eq!(outbound.sender.buffer_size(), 7);
+        assert_eq!(outbound.sender.stats().queued, 0);
+    }
+
     #[test]
     fn test_stream_sender_try_send_closed() {
         let (tx, rx) = mpsc::channel::<String>(10);
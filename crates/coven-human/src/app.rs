@@ -3,13 +3,17 @@
 
 use crate::messages::{Message, MessageDirection};
 use crate::ui;
-use crate::HumanConfig;
+use crate::{HumanConfig, NudgeConfig};
 use anyhow::{Context, Result};
 use chrono::Utc;
+use coven_grpc::{create_channel, ChannelConfig};
 use coven_link::config::CovenConfig;
 use coven_proto::client::CovenControlClient;
-use coven_proto::{agent_message, message_response, server_message, AgentMessage, RegisterAgent};
-use coven_ssh::{load_or_generate_key, SshAuthCredentials};
+use coven_proto::{
+    agent_message, message_response, server_message, AgentMessage, AgentMetadata, AgentPresence,
+    RegisterAgent,
+};
+use coven_ssh::{load_or_generate_key_with_passphrase, SshAuthCredentials};
 use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyModifiers};
 use crossterm::execute;
 use crossterm::terminal::{
@@ -18,16 +22,38 @@ use crossterm::terminal::{
 use futures::StreamExt;
 use ratatui::prelude::*;
 use ratatui::style::{Color, Style};
-use std::io::{self, Stdout};
-use std::time::Duration;
+use std::io::{self, Stdout, Write};
+use std::time::{Duration, Instant};
 use tokio::sync::mpsc;
-use tonic::transport::Channel;
 use tui_textarea::TextArea;
 
 /// Actions that need async handling (returned from handle_key)
 pub enum Action {
     Quit,
     SendReply,
+    SetPresence,
+}
+
+/// Prefix of the input command that changes the displayed status
+const STATUS_COMMAND: &str = "/status";
+
+/// Parse the argument of `/status`: an optional leading emoji, then the
+/// status text. No text means "available".
+fn parse_presence(arg: &str) -> AgentPresence {
+    let arg = arg.trim();
+    let (emoji, status) = match arg.split_once(char::is_whitespace) {
+        Some((first, rest)) if !first.chars().any(char::is_alphanumeric) => (first, rest.trim()),
+        None if !arg.is_empty() && !arg.chars().any(char::is_alphanumeric) => (arg, ""),
+        _ => ("", arg),
+    };
+    AgentPresence {
+        status: if status.is_empty() {
+            AgentPresence::DEFAULT_STATUS.to_string()
+        } else {
+            status.to_string()
+        },
+        emoji: emoji.to_string(),
+    }
 }
 
 /// Create a TextArea with black background styling (matches coven-tui-v2)
@@ -39,6 +65,17 @@ fn styled_textarea() -> TextArea<'static> {
     ta
 }
 
+/// An incoming request that hasn't been replied to yet
+#[derive(Debug, Clone)]
+struct WaitingRequest {
+    request_id: String,
+    thread_id: String,
+    /// When it arrived
+    since: Instant,
+    /// Its nudge was dismissed with Esc
+    dismissed: bool,
+}
+
 /// Application state for the human agent TUI
 pub struct App {
     /// Connection status
@@ -61,6 +98,14 @@ pub struct App {
     pub active_request_id: Option<String>,
     /// Thread ID of the active incoming message
     pub active_thread_id: Option<String>,
+    /// Status shown next to this agent in pickers, set with `/status`
+    pub presence: AgentPresence,
+    /// Requests awaiting a reply, in arrival order
+    waiting: Vec<WaitingRequest>,
+    /// A request has waited past the nudge timeout
+    pub nudging: bool,
+    /// Inactivity nudge settings (None disables the nudge)
+    nudge: Option<NudgeConfig>,
 }
 
 impl App {
@@ -77,13 +122,24 @@ impl App {
             should_quit: false,
             active_request_id: None,
             active_thread_id: None,
+            presence: AgentPresence::available(),
+            waiting: Vec::new(),
+            nudging: false,
+            nudge: None,
         }
     }
 
+    /// Nudge about requests left unanswered for `nudge.after`
+    pub fn with_nudge(mut self, nudge: Option<NudgeConfig>) -> Self {
+        self.nudge = nudge;
+        self
+    }
+
     /// Add a received message and set it as the reply target
     pub fn add_message(&mut self, msg: Message) {
         self.active_request_id = Some(msg.id.clone());
         self.active_thread_id = Some(msg.thread_id.clone());
+        self.start_waiting(&msg.id, &msg.thread_id, Instant::now());
         self.messages.push(msg);
         self.scroll_offset = 0;
         self.status = "New message received".to_string();
@@ -101,6 +157,25 @@ impl App {
             return Some(Action::Quit);
         }
 
+        // Esc silences the nudge for every request waiting now; they can
+        // still be answered
+        if key.code == KeyCode::Esc && self.nudging {
+            self.nudging = false;
+            for request in &mut self.waiting {
+                request.dismissed = true;
+            }
+            self.status = "Reminder dismissed".to_string();
+            return None;
+        }
+
+        // Enter on a `/status` command changes presence instead of replying
+        if key.code == KeyCode::Enter
+            && !key.modifiers.contains(KeyModifiers::SHIFT)
+            && self.status_command().is_some()
+        {
+            return Some(Action::SetPresence);
+        }
+
         // Enter (no Shift) sends reply when there's an active request and non-empty input.
         // Without an active request, Enter falls through to textarea as newline.
         if key.code == KeyCode::Enter
@@ -141,15 +216,89 @@ impl App {
         // Record the outgoing message in the chat history
         self.messages.push(Message::outgoing(text.clone()));
 
-        // Reset input and state
+        // Reset input, and make the newest request still waiting (if any)
+        // the reply target
         self.input = styled_textarea();
         self.scroll_offset = 0;
-        self.active_request_id = None;
-        self.active_thread_id = None;
+        self.stop_waiting(&request_id);
+        let next = self.waiting.last();
+        self.active_request_id = next.map(|r| r.request_id.clone());
+        self.active_thread_id = next.map(|r| r.thread_id.clone());
         self.status = "Reply sent".to_string();
         Some((request_id, thread_id, text))
     }
 
+    /// When the longest-waiting request whose nudge wasn't dismissed arrived
+    pub fn waiting_since(&self) -> Option<Instant> {
+        self.waiting
+            .iter()
+            .filter(|r| !r.dismissed)
+            .map(|r| r.since)
+            .min()
+    }
+
+    /// Check the waiting requests against the nudge timeout. Returns true
+    /// when the nudge has just appeared and the bell should ring.
+    pub fn check_nudge(&mut self, now: Instant) -> bool {
+        let (Some(nudge), Some(since)) = (self.nudge, self.waiting_since()) else {
+            return false;
+        };
+        if self.nudging || now.duration_since(since) < nudge.after {
+            return false;
+        }
+        self.nudging = true;
+        self.status = "A request is waiting for your reply".to_string();
+        nudge.bell
+    }
+
+    /// How long the longest-waiting request has waited, while the nudge shows
+    pub fn nudge_wait(&self, now: Instant) -> Option<Duration> {
+        self.waiting_since()
+            .filter(|_| self.nudging)
+            .map(|since| now.duration_since(since))
+    }
+
+    /// Start timing a request; one already waiting keeps its arrival time
+    fn start_waiting(&mut self, request_id: &str, thread_id: &str, now: Instant) {
+        if self.waiting.iter().any(|r| r.request_id == request_id) {
+            return;
+        }
+        self.waiting.push(WaitingRequest {
+            request_id: request_id.to_string(),
+            thread_id: thread_id.to_string(),
+            since: now,
+            dismissed: false,
+        });
+    }
+
+    /// Stop timing a replied request. The nudge hides until the next check
+    /// finds another request overdue.
+    fn stop_waiting(&mut self, request_id: &str) {
+        self.waiting.retain(|r| r.request_id != request_id);
+        self.nudging = false;
+    }
+
+    /// Apply a `/status` command from the input, returning the new presence
+    pub fn take_presence(&mut self) -> Option<AgentPresence> {
+        let presence = parse_presence(&self.status_command()?);
+        self.input = styled_textarea();
+        self.presence = presence.clone();
+        self.status = format!("Status set to {}", presence.label());
+        Some(presence)
+    }
+
+    /// The argument of a `/status` command in the input, if that's what it holds
+    fn status_command(&self) -> Option<String> {
+        let text = self.input.lines().join("\n");
+        let rest = text.trim().strip_prefix(STATUS_COMMAND)?;
+        // "/statusfoo" is a reply, not a command
+        if rest.is_empty() || rest.starts_with(char::is_whitespace) {
+            Some(rest.to_string())
+        } else {
+            None
+        }
+    }
+
     /// Check if the textarea input is empty
     fn input_is_empty(&self) -> bool {
         self.input.lines().join("").trim().is_empty()
@@ -205,15 +354,15 @@ pub async fn run(config: HumanConfig) -> Result<()> {
     // Load SSH key (same path as coven-tui-v2)
     let key_path = CovenConfig::key_path()
         .context("Could not determine SSH key path. Run 'coven link' first.")?;
-    let private_key = load_or_generate_key(&key_path)
+    let passphrase = CovenConfig::passphrase_source()?;
+    let private_key = load_or_generate_key_with_passphrase(&key_path, passphrase.as_ref())
         .with_context(|| format!("Failed to load SSH key from {}", key_path.display()))?;
 
     eprintln!("Connecting to gateway at {}...", gateway_url);
     eprintln!("Agent: {} ({})", agent_name, agent_id);
 
     // Connect to gateway
-    let channel = Channel::from_shared(gateway_url.clone())?
-        .connect()
+    let channel = create_channel(&ChannelConfig::new(&gateway_url))
         .await
         .with_context(|| format!("Failed to connect to gateway at {}", gateway_url))?;
 
@@ -259,7 +408,10 @@ pub async fn run(config: HumanConfig) -> Result<()> {
             agent_id: agent_id.clone(),
             name: agent_name.clone(),
             capabilities: vec!["human".to_string()],
-            metadata: None,
+            metadata: Some(AgentMetadata {
+                presence: Some(AgentPresence::available()),
+                ..Default::default()
+            }),
             protocol_features: vec![],
         })),
     })
@@ -269,7 +421,7 @@ pub async fn run(config: HumanConfig) -> Result<()> {
     eprintln!("Registration sent, waiting for welcome...");
 
     // Wait for Welcome message
-    let mut app = App::new(agent_id);
+    let mut app = App::new(agent_id).with_nudge(config.nudge);
     loop {
         match inbound.next().await {
             Some(Ok(server_msg)) => {
@@ -386,6 +538,14 @@ async fn run_main_loop(
                                 .await?;
                             }
                         }
+                        Action::SetPresence => {
+                            if let Some(presence) = app.take_presence() {
+                                tx.send(AgentMessage {
+                                    payload: Some(agent_message::Payload::UpdatePresence(presence)),
+                                })
+                                .await?;
+                            }
+                        }
                     }
                 }
             }
@@ -404,7 +564,8 @@ async fn run_main_loop(
                                         send_msg.content,
                                         Utc::now(),
                                         MessageDirection::Incoming,
-                                    );
+                                    )
+                                    .with_reply_to(send_msg.reply_to_message_id);
                                     app.add_message(message);
                                 }
                                 server_message::Payload::Shutdown(_) => {
@@ -431,8 +592,14 @@ async fn run_main_loop(
                 }
             }
 
-            // Tick for UI refresh
-            _ = tick_interval.tick() => {}
+            // Tick for UI refresh and the unanswered-request nudge
+            _ = tick_interval.tick() => {
+                if app.check_nudge(Instant::now()) {
+                    let backend = terminal.backend_mut();
+                    backend.write_all(b"\x07")?;
+                    backend.flush()?;
+                }
+            }
         }
     }
 
@@ -624,6 +791,40 @@ mod tests {
         assert!(action.is_none());
     }
 
+    #[test]
+    fn test_status_command_sets_presence() {
+        let mut app = App::new("test".to_string());
+        assert_eq!(app.presence.status, "available");
+        app.input.insert_str("/status 🍕 at lunch");
+
+        let action = app.handle_key(KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE));
+        assert!(matches!(action, Some(Action::SetPresence)));
+
+        let presence = app.take_presence().unwrap();
+        assert_eq!(presence.status, "at lunch");
+        assert_eq!(presence.emoji, "🍕");
+        assert_eq!(app.presence, presence);
+        assert!(app.input.is_empty());
+    }
+
+    #[test]
+    fn test_parse_presence() {
+        assert_eq!(parse_presence(" busy ").status, "busy");
+        assert!(parse_presence("busy").emoji.is_empty());
+        assert_eq!(parse_presence(""), AgentPresence::available());
+
+        let emoji_only = parse_presence("🔥");
+        assert_eq!(emoji_only.emoji, "🔥");
+        assert_eq!(emoji_only.status, "available");
+    }
+
+    #[test]
+    fn test_status_prefix_in_reply_is_not_a_command() {
+        let mut app = App::new("test".to_string());
+        app.input.insert_str("/statusline is broken");
+        assert!(app.take_presence().is_none());
+    }
+
     #[test]
     fn test_outgoing_message_recorded() {
         let mut app = App::new("test".to_string());
@@ -648,6 +849,113 @@ mod tests {
         assert_eq!(app.messages[1].sender, "you");
     }
 
+    fn incoming(id: &str) -> Message {
+        Message::new(
+            id.to_string(),
+            "thread-1".to_string(),
+            "sender".to_string(),
+            "Hello".to_string(),
+            Utc::now(),
+            MessageDirection::Incoming,
+        )
+    }
+
+    fn nudging_app(bell: bool) -> App {
+        App::new("test".to_string()).with_nudge(Some(NudgeConfig {
+            after: Duration::from_secs(60),
+            bell,
+        }))
+    }
+
+    #[test]
+    fn test_nudge_after_timeout_rings_once() {
+        let mut app = nudging_app(true);
+        app.add_message(incoming("req-1"));
+        let since = app.waiting_since().unwrap();
+
+        assert!(!app.check_nudge(since + Duration::from_secs(30)));
+        assert!(!app.nudging);
+
+        assert!(app.check_nudge(since + Duration::from_secs(61)));
+        assert!(app.nudging);
+        assert_eq!(
+            app.nudge_wait(since + Duration::from_secs(90)),
+            Some(Duration::from_secs(90))
+        );
+        // Already showing: no second bell
+        assert!(!app.check_nudge(since + Duration::from_secs(120)));
+    }
+
+    #[test]
+    fn test_nudge_clears_on_reply() {
+        let mut app = nudging_app(false);
+        app.add_message(incoming("req-1"));
+        let since = app.waiting_since().unwrap();
+        assert!(
+            !app.check_nudge(since + Duration::from_secs(61)),
+            "bell is off"
+        );
+        assert!(app.nudging);
+
+        app.input.insert_str("on it");
+        app.take_reply().unwrap();
+        assert!(!app.nudging);
+        assert!(app.waiting_since().is_none());
+        assert!(!app.check_nudge(since + Duration::from_secs(600)));
+        assert!(!app.nudging, "answered requests aren't nudged");
+    }
+
+    #[test]
+    fn test_dismissed_nudge_stays_quiet_until_next_request() {
+        let mut app = nudging_app(true);
+        app.add_message(incoming("req-1"));
+        let since = app.waiting_since().unwrap();
+        app.check_nudge(since + Duration::from_secs(61));
+
+        app.handle_key(KeyEvent::new(KeyCode::Esc, KeyModifiers::NONE));
+        assert!(!app.nudging);
+        assert_eq!(app.active_request_id.as_deref(), Some("req-1"));
+        assert!(!app.check_nudge(since + Duration::from_secs(600)));
+
+        // A new request starts a fresh wait
+        app.add_message(incoming("req-2"));
+        let since = app.waiting_since().unwrap();
+        assert!(app.check_nudge(since + Duration::from_secs(61)));
+    }
+
+    #[test]
+    fn test_concurrent_requests_are_timed_separately() {
+        let mut app = nudging_app(false);
+        app.add_message(incoming("req-1"));
+        let first = app.waiting_since().unwrap();
+        app.add_message(incoming("req-2"));
+        assert_eq!(app.waiting_since(), Some(first), "req-1 keeps its time");
+
+        // Answering the newer request leaves the older one waiting and
+        // makes it the reply target
+        app.input.insert_str("done");
+        let (request_id, _, _) = app.take_reply().unwrap();
+        assert_eq!(request_id, "req-2");
+        assert_eq!(app.active_request_id.as_deref(), Some("req-1"));
+        assert_eq!(app.waiting_since(), Some(first));
+        app.check_nudge(first + Duration::from_secs(61));
+        assert!(app.nudging);
+
+        app.input.insert_str("and this");
+        app.take_reply().unwrap();
+        assert!(app.waiting_since().is_none());
+        assert!(app.active_request_id.is_none());
+    }
+
+    #[test]
+    fn test_no_nudge_when_disabled() {
+        let mut app = App::new("test".to_string());
+        app.add_message(incoming("req-1"));
+        let since = app.waiting_since().unwrap();
+        assert!(!app.check_nudge(since + Duration::from_secs(3600)));
+        assert!(!app.nudging);
+    }
+
     #[test]
     fn test_resolve_name_default() {
         let name = resolve_name(None);
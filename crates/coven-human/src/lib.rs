@@ -8,6 +8,8 @@ mod ui;
 pub use app::{Action, App};
 pub use messages::{AppEvent, ConnectionEvent, IncomingMessageEvent, Message, MessageDirection};
 
+use std::time::Duration;
+
 /// Configuration for the human agent TUI
 #[derive(Debug, Clone)]
 pub struct HumanConfig {
@@ -17,6 +19,26 @@ pub struct HumanConfig {
     pub name: Option<String>,
     /// Agent ID (auto-generated UUID if not provided)
     pub id: Option<String>,
+    /// Nudge about requests left unanswered (None turns the nudge off)
+    pub nudge: Option<NudgeConfig>,
+}
+
+/// When and how to remind the human about an unanswered request
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NudgeConfig {
+    /// How long a request waits for a reply before the nudge shows
+    pub after: Duration,
+    /// Also ring the terminal bell when the nudge appears
+    pub bell: bool,
+}
+
+impl Default for NudgeConfig {
+    fn default() -> Self {
+        Self {
+            after: Duration::from_secs(120),
+            bell: false,
+        }
+    }
 }
 
 /// Run the human agent TUI
@@ -34,6 +56,7 @@ mod tests {
             gateway: None,
             name: None,
             id: None,
+            nudge: None,
         };
         assert!(config.gateway.is_none());
         assert!(config.name.is_none());
@@ -46,6 +69,7 @@ mod tests {
             gateway: Some("http://localhost:50051".to_string()),
             name: Some("human-1".to_string()),
             id: Some("agent-abc".to_string()),
+            nudge: Some(NudgeConfig::default()),
         };
         assert_eq!(config.gateway.as_deref(), Some("http://localhost:50051"));
         assert_eq!(config.name.as_deref(), Some("human-1"));
@@ -2,6 +2,8 @@
 // ABOUTME: Manages the message queue and communication with the gateway.
 
 use chrono::{DateTime, Utc};
+use coven_client::Threaded;
+use std::borrow::Cow;
 use std::fmt;
 
 /// Events that drive the application state machine
@@ -85,6 +87,8 @@ pub struct Message {
     pub timestamp: DateTime<Utc>,
     /// Whether this message is incoming or outgoing
     pub direction: MessageDirection,
+    /// ID of the earlier message this one replies to
+    pub reply_to: Option<String>,
 }
 
 impl Message {
@@ -104,9 +108,16 @@ impl Message {
             content,
             timestamp,
             direction,
+            reply_to: None,
         }
     }
 
+    /// Mark this message as a reply to message `reply_to`
+    pub fn with_reply_to(mut self, reply_to: Option<String>) -> Self {
+        self.reply_to = reply_to.filter(|id| !id.is_empty());
+        self
+    }
+
     /// Create an outgoing message from the human user
     pub fn outgoing(content: String) -> Self {
         Self {
@@ -116,6 +127,7 @@ impl Message {
             content,
             timestamp: Utc::now(),
             direction: MessageDirection::Outgoing,
+            reply_to: None,
         }
     }
 
@@ -142,6 +154,24 @@ impl Message {
     }
 }
 
+impl Threaded for Message {
+    fn message_id(&self) -> Option<&str> {
+        Some(&self.id)
+    }
+
+    fn reply_to(&self) -> Option<&str> {
+        self.reply_to.as_deref()
+    }
+
+    fn quote_sender(&self) -> &str {
+        &self.sender
+    }
+
+    fn quote_text(&self) -> Cow<'_, str> {
+        Cow::Borrowed(&self.content)
+    }
+}
+
 impl fmt::Display for Message {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "{}", self.format_display())
@@ -151,6 +181,7 @@ impl fmt::Display for Message {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use coven_client::reply_quote;
 
     #[test]
     fn test_message_creation() {
@@ -172,6 +203,38 @@ mod tests {
         assert_eq!(msg.direction, MessageDirection::Incoming);
     }
 
+    #[test]
+    fn test_reply_quote() {
+        let incoming = |id: &str, content: &str| {
+            Message::new(
+                id.to_string(),
+                "thread-1".to_string(),
+                "alice".to_string(),
+                content.to_string(),
+                Utc::now(),
+                MessageDirection::Incoming,
+            )
+        };
+        let messages = vec![
+            incoming("m1", "Can you check the deploy?"),
+            incoming("m2", "Also the logs").with_reply_to(Some("m1".to_string())),
+            incoming("m3", "Any news?").with_reply_to(Some("m1".to_string())),
+            incoming("m4", "And this?").with_reply_to(Some("gone".to_string())),
+        ];
+
+        assert_eq!(reply_quote(&messages, 0), None);
+        // Replying to the message right above needs no quote
+        assert_eq!(reply_quote(&messages, 1), None);
+        assert_eq!(
+            reply_quote(&messages, 2).as_deref(),
+            Some("↳ alice: Can you check the deploy?")
+        );
+        assert_eq!(
+            reply_quote(&messages, 3).as_deref(),
+            Some("↳ reply to an earlier message")
+        );
+    }
+
     #[test]
     fn test_message_direction() {
         assert_ne!(MessageDirection::Incoming, MessageDirection::Outgoing);
@@ -4,8 +4,10 @@
 use crate::app::App;
 use crate::messages::MessageDirection;
 use chrono::Local;
+use coven_client::reply_quote;
 use ratatui::prelude::*;
 use ratatui::widgets::{Block, Borders, Clear, Paragraph};
+use std::time::{Duration, Instant};
 
 /// Render the full TUI frame with 3-row layout: chat | input | status
 pub fn render(frame: &mut Frame, app: &App) {
@@ -64,13 +66,20 @@ fn render_chat(frame: &mut Frame, app: &App, area: Rect) {
             Style::default().dim(),
         )));
     } else {
-        for msg in &app.messages {
+        for (index, msg) in app.messages.iter().enumerate() {
             let time = msg
                 .timestamp
                 .with_timezone(&Local)
                 .format("%H:%M")
                 .to_string();
 
+            if let Some(quote) = reply_quote(&app.messages, index) {
+                lines.push(Line::from(Span::styled(
+                    format!("      {}", quote),
+                    Style::default().dim().italic(),
+                )));
+            }
+
             match msg.direction {
                 MessageDirection::Incoming => {
                     lines.push(Line::from(vec![
@@ -106,14 +115,22 @@ fn render_chat(frame: &mut Frame, app: &App, area: Rect) {
 
 /// Render the always-visible input area with TextArea widget
 fn render_input(frame: &mut Frame, app: &App, area: Rect) {
-    let (title, title_style) = if app.active_request_id.is_some() {
+    let (title, title_style) = if let Some(waited) = app.nudge_wait(Instant::now()) {
+        (
+            format!(
+                " Still there? Waiting {} for your reply (Enter to send, Esc to dismiss) ",
+                format_wait(waited)
+            ),
+            Style::default().fg(Color::Black).bg(Color::Yellow).bold(),
+        )
+    } else if app.active_request_id.is_some() {
         (
-            " Reply (Enter to send) ",
+            " Reply (Enter to send) ".to_string(),
             Style::default().fg(Color::Green).bg(Color::Rgb(0, 0, 0)),
         )
     } else {
         (
-            " Waiting for request... ",
+            " Waiting for request... ".to_string(),
             Style::default().fg(Color::Yellow).bg(Color::Rgb(0, 0, 0)),
         )
     };
@@ -135,6 +152,16 @@ fn render_input(frame: &mut Frame, app: &App, area: Rect) {
     frame.render_widget(&app.input, inner);
 }
 
+/// Render a wait as e.g. `45s` or `3m12s`
+fn format_wait(waited: Duration) -> String {
+    let secs = waited.as_secs();
+    if secs < 60 {
+        format!("{}s", secs)
+    } else {
+        format!("{}m{:02}s", secs / 60, secs % 60)
+    }
+}
+
 /// Render the status bar with connection dot, status message, and keybinds
 fn render_status(frame: &mut Frame, app: &App, area: Rect) {
     let dot = if app.connected { "●" } else { "○" };
@@ -148,7 +175,11 @@ fn render_status(frame: &mut Frame, app: &App, area: Rect) {
         Span::styled(format!("{} ", dot), dot_style),
         Span::styled(&app.status, Style::default().fg(Color::White)),
         Span::styled(
-            " | q:quit  PgUp/PgDn:scroll",
+            format!(" | {}", app.presence.label()),
+            Style::default().fg(Color::Cyan),
+        ),
+        Span::styled(
+            " | q:quit  PgUp/PgDn:scroll  /status:set status",
             Style::default().fg(Color::DarkGray),
         ),
     ]);
@@ -1,13 +1,34 @@
 // ABOUTME: Configuration management for coven tools
-// ABOUTME: Writes unified config that all coven tools can read
+// ABOUTME: Writes unified config that all coven tools can read, with linked gateways and named profiles
 
-use anyhow::{Context, Result};
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
 
-/// Unified coven configuration
-#[derive(Debug, Serialize, Deserialize)]
+/// Environment variable naming the profile to use
+pub const PROFILE_ENV: &str = "COVEN_PROFILE";
+
+/// Profile chosen on the command line (`coven --profile <name>`)
+static SELECTED_PROFILE: OnceLock<String> = OnceLock::new();
+
+/// Use the named profile for the rest of the process, ahead of
+/// `COVEN_PROFILE` and the config's default. Only the first call counts.
+pub fn select_profile(name: impl Into<String>) {
+    let _ = SELECTED_PROFILE.set(name.into());
+}
+
+/// The profile passed to `select_profile`, if any
+pub fn selected_profile() -> Option<&'static str> {
+    SELECTED_PROFILE.get().map(String::as_str)
+}
+
+/// Unified coven configuration: the connection to one gateway, from the
+/// selected profile, the active link, or the legacy top-level keys
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CovenConfig {
     /// Gateway gRPC address (e.g., "coven.example.com:50051")
     pub gateway: String,
@@ -20,6 +41,430 @@ pub struct CovenConfig {
 
     /// Device name
     pub device_name: String,
+
+    /// SSH key for this gateway, when it isn't the default device key
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub key: Option<PathBuf>,
+
+    /// Where the passphrase for an encrypted key comes from, in the forms
+    /// of `coven_ssh::PassphraseSource::parse` (e.g. "cmd:pass show coven")
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub key_passphrase: Option<String>,
+
+    /// When `token` expires, if it does; `refresh_token_if_needed` renews
+    /// it ahead of time
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub token_expires_at: Option<DateTime<Utc>>,
+}
+
+/// One named gateway connection in `[profiles.<name>]`
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Profile {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gateway: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub token: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub key: Option<PathBuf>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub key_passphrase: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub principal_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub device_name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub token_expires_at: Option<DateTime<Utc>>,
+}
+
+/// One gateway this device is linked to, in `[[links]]`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Link {
+    /// Name for `coven link switch` and `coven link remove`
+    pub name: String,
+    pub gateway: String,
+    #[serde(default)]
+    pub token: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub token_expires_at: Option<DateTime<Utc>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub key: Option<PathBuf>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub key_passphrase: Option<String>,
+    #[serde(default)]
+    pub principal_id: String,
+    #[serde(default)]
+    pub device_name: String,
+    /// When the device was linked; unknown for links migrated from the
+    /// legacy top-level keys
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub linked_at: Option<DateTime<Utc>>,
+    /// The link used when no profile is selected; exactly one link is
+    /// active while there are any
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub active: bool,
+}
+
+impl Link {
+    fn new(name: String, config: &CovenConfig, linked_at: Option<DateTime<Utc>>) -> Self {
+        Self {
+            name,
+            gateway: config.gateway.clone(),
+            token: config.token.clone(),
+            token_expires_at: config.token_expires_at,
+            key: config.key.clone(),
+            key_passphrase: config.key_passphrase.clone(),
+            principal_id: config.principal_id.clone(),
+            device_name: config.device_name.clone(),
+            linked_at,
+            active: false,
+        }
+    }
+
+    fn profile(&self) -> Profile {
+        Profile {
+            gateway: Some(self.gateway.clone()),
+            token: Some(self.token.clone()),
+            key: self.key.clone(),
+            key_passphrase: self.key_passphrase.clone(),
+            principal_id: Some(self.principal_id.clone()),
+            device_name: Some(self.device_name.clone()),
+            token_expires_at: self.token_expires_at,
+        }
+    }
+
+    /// Point the link at `config`'s connection, keeping its name
+    fn update(&mut self, config: &CovenConfig) {
+        self.gateway = config.gateway.clone();
+        self.token = config.token.clone();
+        self.token_expires_at = config.token_expires_at;
+        self.key = config.key.clone();
+        self.key_passphrase = config.key_passphrase.clone();
+        self.principal_id = config.principal_id.clone();
+        self.device_name = config.device_name.clone();
+    }
+}
+
+/// A link name for `gateway`: its host, e.g. "coven.example.com" for
+/// "https://coven.example.com:50051"
+pub fn link_name_for(gateway: &str) -> String {
+    let rest = gateway.split_once("://").map_or(gateway, |(_, rest)| rest);
+    let authority = rest.split('/').next().unwrap_or(rest);
+    let host = if authority.starts_with('[') {
+        authority.split_inclusive(']').next().unwrap_or(authority)
+    } else {
+        authority.split(':').next().unwrap_or(authority)
+    };
+    if host.is_empty() {
+        "gateway".to_string()
+    } else {
+        host.to_string()
+    }
+}
+
+/// Write `content` to a temporary file next to `path` and rename it over
+/// `path`, so readers see the old file or the new one and never half of it.
+/// The file is readable by its owner only, as it holds tokens.
+fn write_atomically(path: &Path, content: &[u8]) -> std::io::Result<()> {
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(format!(".tmp-{}", std::process::id()));
+    let tmp = PathBuf::from(tmp);
+    if let Err(e) = write_private(&tmp, content) {
+        let _ = fs::remove_file(&tmp);
+        return Err(e);
+    }
+    if let Err(e) = fs::rename(&tmp, path) {
+        let _ = fs::remove_file(&tmp);
+        return Err(e);
+    }
+    Ok(())
+}
+
+/// Create `path` afresh with `content`, readable by its owner only from
+/// the start
+#[cfg(unix)]
+fn write_private(path: &Path, content: &[u8]) -> std::io::Result<()> {
+    use std::io::Write;
+    use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+    let mut file = fs::OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .mode(0o600)
+        .open(path)?;
+    // A leftover temp file keeps its old mode; tighten it before writing
+    file.set_permissions(fs::Permissions::from_mode(0o600))?;
+    file.write_all(content)?;
+    file.sync_all()
+}
+
+#[cfg(not(unix))]
+fn write_private(path: &Path, content: &[u8]) -> std::io::Result<()> {
+    fs::write(path, content)
+}
+
+/// An exclusive lock on `<path>.lock`, held until the file is dropped, so
+/// processes renewing the token in `path` take turns
+pub(crate) fn lock_file(path: &Path) -> std::io::Result<fs::File> {
+    let mut name = path.as_os_str().to_owned();
+    name.push(".lock");
+    let file = fs::OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(PathBuf::from(name))?;
+    file.lock()?;
+    Ok(file)
+}
+
+/// config.toml as stored: the gateways this device is linked to, named
+/// profiles and the default profile, and the legacy top-level keys that
+/// `coven link` wrote before links existed (migrated to a link on the
+/// first write). Keys other tools keep in the file are preserved on save.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ConfigFile {
+    /// Profile used when neither `--profile` nor `COVEN_PROFILE` names one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default_profile: Option<String>,
+
+    /// Renew a token this many seconds before it expires; one day when
+    /// unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub token_refresh_window_secs: Option<u64>,
+
+    #[serde(flatten)]
+    pub legacy: Profile,
+
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub profiles: BTreeMap<String, Profile>,
+
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub links: Vec<Link>,
+
+    #[serde(flatten)]
+    pub other: toml::Table,
+}
+
+impl ConfigFile {
+    pub fn parse(content: &str) -> Result<Self> {
+        toml::from_str(content).context("Failed to parse config")
+    }
+
+    /// Read `path`; a missing file is an empty config
+    pub fn load_from(path: &Path) -> Result<Self> {
+        match fs::read_to_string(path) {
+            Ok(content) => Self::parse(&content),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e).context("Failed to read config file"),
+        }
+    }
+
+    pub fn save_to(&self, path: &Path) -> Result<()> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).context("Failed to create config directory")?;
+        }
+        let content = toml::to_string_pretty(self).context("Failed to serialize config")?;
+        write_atomically(path, content.as_bytes()).context("Failed to write config file")
+    }
+
+    /// The profile to use: `explicit` (the `--profile` flag), then `env`
+    /// (`COVEN_PROFILE`), then `default_profile`. None means the active
+    /// link.
+    pub fn profile_name(&self, explicit: Option<&str>, env: Option<&str>) -> Option<String> {
+        [explicit, env, self.default_profile.as_deref()]
+            .into_iter()
+            .flatten()
+            .find(|name| !name.is_empty())
+            .map(str::to_string)
+    }
+
+    /// The connection for `profile`, or for the active link when it is None
+    pub fn resolve(&self, profile: Option<&str>) -> Result<CovenConfig> {
+        let (entry, label) = match profile {
+            Some(name) => match self.profiles.get(name) {
+                Some(entry) => (entry.clone(), format!("profile '{}'", name)),
+                None => bail!(
+                    "No profile '{}' in config (known: {})",
+                    name,
+                    self.profile_list()
+                ),
+            },
+            None => self.unprofiled(),
+        };
+        let gateway = entry
+            .gateway
+            .clone()
+            .with_context(|| format!("No gateway set in {}", label))?;
+        Ok(CovenConfig {
+            gateway,
+            token: entry.token.clone().unwrap_or_default(),
+            principal_id: entry.principal_id.clone().unwrap_or_default(),
+            device_name: entry.device_name.clone().unwrap_or_default(),
+            key: entry.key.clone(),
+            key_passphrase: entry.key_passphrase.clone(),
+            token_expires_at: entry.token_expires_at,
+        })
+    }
+
+    /// The active link, or the legacy keys in a config not yet migrated,
+    /// with how to name it in errors
+    fn unprofiled(&self) -> (Profile, String) {
+        match self.active_link() {
+            Some(link) => (link.profile(), format!("link '{}'", link.name)),
+            None => (self.legacy.clone(), "config".to_string()),
+        }
+    }
+
+    pub fn active_link(&self) -> Option<&Link> {
+        self.links.iter().find(|link| link.active)
+    }
+
+    pub fn find_link(&self, name: &str) -> Option<&Link> {
+        self.links.iter().find(|link| link.name == name)
+    }
+
+    /// Move the legacy top-level keys into an active link, if there are
+    /// any and no links yet
+    pub fn migrate_legacy(&mut self) {
+        if !self.links.is_empty() {
+            return;
+        }
+        let Some(gateway) = self.legacy.gateway.take() else {
+            return;
+        };
+        let legacy = std::mem::take(&mut self.legacy);
+        self.links.push(Link {
+            name: link_name_for(&gateway),
+            gateway,
+            token: legacy.token.unwrap_or_default(),
+            token_expires_at: legacy.token_expires_at,
+            key: legacy.key,
+            key_passphrase: legacy.key_passphrase,
+            principal_id: legacy.principal_id.unwrap_or_default(),
+            device_name: legacy.device_name.unwrap_or_default(),
+            linked_at: None,
+            active: true,
+        });
+    }
+
+    /// Record a link to `config`'s gateway, linked at `linked_at`, and make
+    /// it the active one. A link to the same gateway is replaced and keeps
+    /// its name; otherwise the new link is named after the gateway's host.
+    /// Returns the link's name.
+    pub fn add_link(&mut self, config: &CovenConfig, linked_at: DateTime<Utc>) -> String {
+        self.migrate_legacy();
+        let index = match self
+            .links
+            .iter()
+            .position(|link| link.gateway == config.gateway)
+        {
+            Some(index) => {
+                self.links[index].update(config);
+                self.links[index].linked_at = Some(linked_at);
+                index
+            }
+            None => {
+                let name = self.unused_link_name(&link_name_for(&config.gateway));
+                self.links.push(Link::new(name, config, Some(linked_at)));
+                self.links.len() - 1
+            }
+        };
+        self.activate(index);
+        self.links[index].name.clone()
+    }
+
+    /// `base`, or `base-2`, `base-3`... if links already use it
+    fn unused_link_name(&self, base: &str) -> String {
+        (1..)
+            .map(|n| match n {
+                1 => base.to_string(),
+                n => format!("{}-{}", base, n),
+            })
+            .find(|name| self.find_link(name).is_none())
+            .expect("some suffix is free")
+    }
+
+    fn activate(&mut self, index: usize) {
+        for (i, link) in self.links.iter_mut().enumerate() {
+            link.active = i == index;
+        }
+    }
+
+    /// Make the link `name` the active one
+    pub fn switch_link(&mut self, name: &str) -> Result<&Link> {
+        self.migrate_legacy();
+        let index = self.link_index(name)?;
+        self.activate(index);
+        Ok(&self.links[index])
+    }
+
+    /// Forget the link `name`. If it was active, the most recently added
+    /// of the rest becomes active.
+    pub fn remove_link(&mut self, name: &str) -> Result<Link> {
+        self.migrate_legacy();
+        let index = self.link_index(name)?;
+        let removed = self.links.remove(index);
+        if removed.active && !self.links.is_empty() {
+            self.activate(self.links.len() - 1);
+        }
+        Ok(removed)
+    }
+
+    fn link_index(&self, name: &str) -> Result<usize> {
+        match self.links.iter().position(|link| link.name == name) {
+            Some(index) => Ok(index),
+            None if self.links.is_empty() => {
+                bail!(
+                    "No link '{}': this device isn't linked to any gateway",
+                    name
+                )
+            }
+            None => bail!(
+                "No link '{}' (known: {})",
+                name,
+                self.links
+                    .iter()
+                    .map(|link| link.name.as_str())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+        }
+    }
+
+    fn profile_list(&self) -> String {
+        if self.profiles.is_empty() {
+            return "none".to_string();
+        }
+        self.profiles.keys().cloned().collect::<Vec<_>>().join(", ")
+    }
+
+    /// Store `config` under `profile`, or in the active link when None.
+    /// Saving without a profile migrates the legacy keys to a link, and
+    /// adds a link if there is none.
+    pub fn set(&mut self, profile: Option<&str>, config: &CovenConfig) {
+        let entry = Profile {
+            gateway: Some(config.gateway.clone()),
+            token: Some(config.token.clone()),
+            key: config.key.clone(),
+            key_passphrase: config.key_passphrase.clone(),
+            principal_id: Some(config.principal_id.clone()),
+            device_name: Some(config.device_name.clone()),
+            token_expires_at: config.token_expires_at,
+        };
+        match profile {
+            Some(name) => {
+                self.profiles.insert(name.to_string(), entry);
+            }
+            None => {
+                self.migrate_legacy();
+                match self.links.iter_mut().find(|link| link.active) {
+                    Some(link) => link.update(config),
+                    None => {
+                        self.add_link(config, Utc::now());
+                    }
+                }
+            }
+        }
+    }
 }
 
 impl CovenConfig {
@@ -35,37 +480,314 @@ impl CovenConfig {
         Ok(Self::config_dir()?.join("config.toml"))
     }
 
-    /// Returns the path to the device key
+    /// The profile this process uses: `--profile`, then `COVEN_PROFILE`,
+    /// then the config's `default_profile`
+    pub fn active_profile() -> Result<Option<String>> {
+        let file = ConfigFile::load_from(&Self::config_path()?)?;
+        Ok(Self::profile_for(&file))
+    }
+
+    fn profile_for(file: &ConfigFile) -> Option<String> {
+        let env = std::env::var(PROFILE_ENV).ok();
+        file.profile_name(selected_profile(), env.as_deref())
+    }
+
+    /// Returns the path to the device key: the active profile's `key`, or
+    /// the default device key
     pub fn key_path() -> Result<PathBuf> {
-        Ok(Self::config_dir()?.join("device_key"))
+        match Self::active_field(|p| p.key.clone())? {
+            Some(key) => Ok(key),
+            None => Ok(Self::config_dir()?.join("device_key")),
+        }
     }
 
-    /// Saves the configuration to disk
-    pub fn save(&self) -> Result<()> {
-        let dir = Self::config_dir()?;
-        fs::create_dir_all(&dir).context("Failed to create config directory")?;
+    /// Returns the active profile's `key_passphrase`, if its key is encrypted
+    pub fn key_passphrase() -> Result<Option<String>> {
+        Self::active_field(|p| p.key_passphrase.clone())
+    }
 
-        let path = Self::config_path()?;
-        let content = toml::to_string_pretty(self).context("Failed to serialize config")?;
-        fs::write(&path, content).context("Failed to write config file")?;
+    /// The active profile's `key_passphrase`, parsed
+    pub fn passphrase_source() -> Result<Option<coven_ssh::PassphraseSource>> {
+        Self::key_passphrase()?
+            .as_deref()
+            .map(coven_ssh::PassphraseSource::parse)
+            .transpose()
+            .context("Invalid key_passphrase in config")
+    }
 
-        // Also write token to separate file for backwards compatibility
-        let token_path = dir.join("token");
-        fs::write(&token_path, &self.token).context("Failed to write token file")?;
+    /// A field of the active profile, or of the active link
+    fn active_field<T>(field: impl Fn(&Profile) -> Option<T>) -> Result<Option<T>> {
+        let file = ConfigFile::load_from(&Self::config_path()?)?;
+        Ok(match Self::profile_for(&file) {
+            Some(name) => file.profiles.get(&name).and_then(&field),
+            None => field(&file.unprofiled().0),
+        })
+    }
+
+    /// Saves the configuration to disk, under the active profile if there
+    /// is one, otherwise in the active link
+    pub fn save(&self) -> Result<()> {
+        let path = Self::config_path()?;
+        let mut file = ConfigFile::load_from(&path)?;
+        let profile = Self::profile_for(&file);
+        file.set(profile.as_deref(), self);
+        file.save_to(&path)?;
 
+        if profile.is_none() {
+            Self::write_token_file(Some(&self.token))?;
+        }
         Ok(())
     }
 
-    /// Loads existing configuration
-    pub fn load() -> Result<Self> {
+    /// Saves the configuration as a new link (or over the link to the same
+    /// gateway) and makes it the active link. Returns the link's name.
+    pub fn save_link(&self) -> Result<String> {
         let path = Self::config_path()?;
-        let content = fs::read_to_string(&path).context("Failed to read config file")?;
-        let config: Self = toml::from_str(&content).context("Failed to parse config")?;
-        Ok(config)
+        let mut file = ConfigFile::load_from(&path)?;
+        let name = file.add_link(self, Utc::now());
+        file.save_to(&path)?;
+        Self::write_token_file(Some(&self.token))?;
+        Ok(name)
+    }
+
+    /// Keep the token of the active link in a separate file for backwards
+    /// compatibility; None removes the file
+    pub fn write_token_file(token: Option<&str>) -> Result<()> {
+        let token_path = Self::config_dir()?.join("token");
+        match token {
+            Some(token) => write_atomically(&token_path, token.as_bytes())
+                .context("Failed to write token file"),
+            None => match fs::remove_file(&token_path) {
+                Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+                    Err(e).context("Failed to remove token file")
+                }
+                _ => Ok(()),
+            },
+        }
+    }
+
+    /// Loads the active profile's configuration
+    pub fn load() -> Result<Self> {
+        let file = ConfigFile::load_from(&Self::config_path()?)?;
+        file.resolve(Self::profile_for(&file).as_deref())
     }
 
-    /// Checks if configuration exists
+    /// Checks if the active profile has a gateway configured
     pub fn exists() -> bool {
-        Self::config_path().map(|p| p.exists()).unwrap_or(false)
+        Self::load().is_ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONFIG: &str = r#"
+gateway = "legacy:50051"
+token = "legacy-token"
+principal_id = "p-legacy"
+device_name = "laptop"
+default_profile = "local"
+
+[profiles.local]
+gateway = "127.0.0.1:50051"
+
+[profiles.team]
+gateway = "https://coven.example.com"
+token = "team-token"
+key = "/keys/team"
+key_passphrase = "env:TEAM_KEY_PASS"
+"#;
+
+    #[test]
+    fn test_profile_precedence() {
+        let file = ConfigFile::parse(CONFIG).unwrap();
+        // Flag beats env beats the default profile
+        assert_eq!(
+            file.profile_name(Some("team"), Some("local")).as_deref(),
+            Some("team")
+        );
+        assert_eq!(
+            file.profile_name(None, Some("team")).as_deref(),
+            Some("team")
+        );
+        assert_eq!(file.profile_name(None, None).as_deref(), Some("local"));
+        assert_eq!(file.profile_name(None, Some("")).as_deref(), Some("local"));
+
+        // Without a default, the legacy top-level keys are used
+        let legacy = ConfigFile {
+            default_profile: None,
+            ..file.clone()
+        };
+        assert_eq!(legacy.profile_name(None, None), None);
+        let resolved = legacy.resolve(None).unwrap();
+        assert_eq!(resolved.gateway, "legacy:50051");
+        assert_eq!(resolved.token, "legacy-token");
+    }
+
+    #[test]
+    fn test_resolve_profile() {
+        let file = ConfigFile::parse(CONFIG).unwrap();
+        let team = file.resolve(Some("team")).unwrap();
+        assert_eq!(team.gateway, "https://coven.example.com");
+        assert_eq!(team.token, "team-token");
+        assert_eq!(team.key, Some(PathBuf::from("/keys/team")));
+        assert_eq!(team.key_passphrase.as_deref(), Some("env:TEAM_KEY_PASS"));
+
+        let local = file.resolve(Some("local")).unwrap();
+        assert_eq!(local.token, "");
+        assert_eq!(local.key, None);
+
+        let err = file.resolve(Some("prod")).unwrap_err().to_string();
+        assert!(err.contains("local, team"), "{}", err);
+    }
+
+    #[test]
+    fn test_set_profile_keeps_everything_else() {
+        let mut file =
+            ConfigFile::parse(&format!("{}\n[tui]\ntheme = \"dark\"\n", CONFIG)).unwrap();
+        let config = CovenConfig {
+            gateway: "staging:50051".to_string(),
+            token: "t".to_string(),
+            principal_id: "p".to_string(),
+            device_name: "d".to_string(),
+            key: None,
+            key_passphrase: None,
+            token_expires_at: None,
+        };
+        file.set(Some("staging"), &config);
+
+        let reparsed = ConfigFile::parse(&toml::to_string_pretty(&file).unwrap()).unwrap();
+        assert_eq!(
+            reparsed.resolve(Some("staging")).unwrap().gateway,
+            "staging:50051"
+        );
+        assert_eq!(reparsed.legacy.gateway.as_deref(), Some("legacy:50051"));
+        assert_eq!(reparsed.profiles.len(), 3);
+        assert!(reparsed.other.contains_key("tui"));
+    }
+
+    #[test]
+    fn test_legacy_only_config_still_parses() {
+        let file = ConfigFile::parse(
+            "gateway = \"gw:50051\"\ntoken = \"t\"\nprincipal_id = \"p\"\ndevice_name = \"d\"\n",
+        )
+        .unwrap();
+        assert!(file.profiles.is_empty());
+        assert_eq!(file.resolve(None).unwrap().device_name, "d");
+    }
+
+    fn config(gateway: &str, token: &str) -> CovenConfig {
+        CovenConfig {
+            gateway: gateway.to_string(),
+            token: token.to_string(),
+            principal_id: "p".to_string(),
+            device_name: "laptop".to_string(),
+            key: None,
+            key_passphrase: None,
+            token_expires_at: None,
+        }
+    }
+
+    fn linked_at() -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339("2026-01-02T03:04:05Z")
+            .unwrap()
+            .with_timezone(&Utc)
+    }
+
+    #[test]
+    fn test_first_write_migrates_legacy_keys_to_a_link() {
+        let mut file = ConfigFile::parse(
+            "gateway = \"http://gw:50051\"\ntoken = \"old\"\nprincipal_id = \"p\"\n\
+             device_name = \"laptop\"\nkey = \"/keys/gw\"\n\n[tui]\ntheme = \"dark\"\n",
+        )
+        .unwrap();
+        file.set(None, &config("http://gw:50051", "new"));
+
+        let rendered = toml::to_string_pretty(&file).unwrap();
+        assert!(rendered.contains("[[links]]"), "{}", rendered);
+        let reparsed = ConfigFile::parse(&rendered).unwrap();
+        assert_eq!(reparsed.legacy, Profile::default());
+        assert!(reparsed.other.contains_key("tui"));
+        let [link] = reparsed.links.as_slice() else {
+            panic!("expected one link, got {:?}", reparsed.links);
+        };
+        assert_eq!(link.name, "gw");
+        assert!(link.active);
+        assert_eq!(link.linked_at, None);
+        assert_eq!(link.token, "new");
+        assert_eq!(reparsed.resolve(None).unwrap().token, "new");
+    }
+
+    #[test]
+    fn test_linking_another_gateway_keeps_the_first() {
+        let mut file = ConfigFile::parse(
+            "gateway = \"http://gw:50051\"\ntoken = \"t1\"\nkey = \"/keys/gw\"\n",
+        )
+        .unwrap();
+        let name = file.add_link(
+            &config("https://coven.example.com:50051", "t2"),
+            linked_at(),
+        );
+        assert_eq!(name, "coven.example.com");
+        assert_eq!(file.links.len(), 2);
+        assert_eq!(file.resolve(None).unwrap().token, "t2");
+        assert_eq!(file.active_link().unwrap().linked_at, Some(linked_at()));
+
+        // Same host, another gateway: another link. Same gateway: relinked.
+        let other_port = config("https://coven.example.com:50052", "t3");
+        assert_eq!(
+            file.add_link(&other_port, linked_at()),
+            "coven.example.com-2"
+        );
+        assert_eq!(
+            file.add_link(
+                &config("https://coven.example.com:50051", "t4"),
+                linked_at()
+            ),
+            "coven.example.com"
+        );
+        assert_eq!(file.links.len(), 3);
+        assert_eq!(file.links.iter().filter(|l| l.active).count(), 1);
+
+        // The migrated link keeps its own key
+        file.switch_link("gw").unwrap();
+        let resolved = file.resolve(None).unwrap();
+        assert_eq!(resolved.token, "t1");
+        assert_eq!(resolved.key, Some(PathBuf::from("/keys/gw")));
+
+        // A selected profile still wins over the active link
+        file.set(Some("team"), &config("team:50051", "t5"));
+        assert_eq!(file.resolve(Some("team")).unwrap().token, "t5");
+    }
+
+    #[test]
+    fn test_remove_link_activates_the_latest_remaining() {
+        let mut file = ConfigFile::default();
+        file.add_link(&config("http://a:50051", "a"), linked_at());
+        file.add_link(&config("http://b:50051", "b"), linked_at());
+        file.add_link(&config("http://c:50051", "c"), linked_at());
+        file.switch_link("a").unwrap();
+
+        file.remove_link("b").unwrap();
+        assert_eq!(file.active_link().unwrap().name, "a");
+        file.remove_link("a").unwrap();
+        assert_eq!(file.active_link().unwrap().name, "c");
+
+        let err = file.switch_link("a").unwrap_err().to_string();
+        assert!(err.contains("known: c"), "{}", err);
+        file.remove_link("c").unwrap();
+        assert!(file.resolve(None).is_err());
+    }
+
+    #[test]
+    fn test_link_name_for() {
+        assert_eq!(
+            link_name_for("https://coven.example.com:50051"),
+            "coven.example.com"
+        );
+        assert_eq!(link_name_for("http://[::1]:50051"), "[::1]");
+        assert_eq!(link_name_for("gw.local"), "gw.local");
+        assert_eq!(link_name_for("unix:///run/coven.sock"), "gateway");
     }
 }
@@ -3,5 +3,12 @@
 
 pub mod config;
 pub mod link;
+pub mod links;
+pub mod pair;
+pub mod refresh;
+pub mod rotate;
 
 pub use link::run;
+pub use pair::pair;
+pub use refresh::refresh_token_if_needed;
+pub use rotate::rotate;
@@ -3,10 +3,12 @@
 
 use anyhow::{bail, Context, Result};
 use colored::Colorize;
+use coven_ssh::PrivateKey;
 use serde::Deserialize;
+use std::path::PathBuf;
 use std::time::Duration;
 
-use crate::config::CovenConfig;
+use crate::config::{ConfigFile, CovenConfig};
 
 #[derive(Deserialize)]
 struct LinkRequestResponse {
@@ -21,50 +23,26 @@ struct LinkStatusResponse {
     principal_id: Option<String>,
 }
 
-pub async fn run(gateway: String, name: Option<String>, key_path: Option<String>) -> Result<()> {
-    // Check if already configured
-    if CovenConfig::exists() {
-        println!(
-            "{} Device already linked. Config at: {}",
-            "!".yellow().bold(),
-            CovenConfig::config_path()?.display()
-        );
-        println!("  To re-link, remove the config file first.");
+pub async fn run(
+    gateway: String,
+    name: Option<String>,
+    key_path: Option<String>,
+    key_passphrase: Option<String>,
+) -> Result<()> {
+    // Normalize gateway URL
+    let gateway_http = normalize_gateway_url(&gateway);
+    let gateway_grpc = derive_grpc_address(&gateway);
+
+    if already_linked(&gateway_grpc)? {
         return Ok(());
     }
-
-    // Determine device name
-    let device_name = name.unwrap_or_else(|| {
-        hostname::get()
-            .map(|h| h.to_string_lossy().to_string())
-            .unwrap_or_else(|_| "unknown".to_string())
-    });
+    let device_name = device_name(name);
 
     println!("{}", "Coven Device Linking".bold());
     println!();
 
-    // Load or generate SSH key
-    let key_path = match key_path {
-        Some(p) => std::path::PathBuf::from(p),
-        None => CovenConfig::key_path().context("Failed to determine key path")?,
-    };
-
-    println!(
-        "{} Loading SSH key from {}...",
-        "[1/4]".dimmed(),
-        key_path.display()
-    );
-
-    let private_key =
-        coven_ssh::load_or_generate_key(&key_path).context("Failed to load or generate SSH key")?;
-    let fingerprint = coven_ssh::compute_fingerprint(private_key.public_key())
-        .context("Failed to compute key fingerprint")?;
-
-    println!("  Fingerprint: {}", fingerprint.dimmed());
-
-    // Normalize gateway URL
-    let gateway_http = normalize_gateway_url(&gateway);
-    let gateway_grpc = derive_grpc_address(&gateway);
+    let device_key = load_device_key(key_path, key_passphrase)?;
+    let fingerprint = device_key.fingerprint.clone();
 
     println!(
         "{} Requesting link code from {}...",
@@ -164,14 +142,137 @@ pub async fn run(gateway: String, name: Option<String>, key_path: Option<String>
 
     // Save configuration
     println!("{} Saving configuration...", "[4/4]".dimmed());
+    save_config(gateway_grpc, token, principal_id, device_name, device_key)
+}
+
+/// Whether this device is already linked to `gateway_grpc` (or, with a
+/// profile selected, whether that profile is set up), saying so if it is.
+/// Linking another gateway adds a link alongside the existing ones.
+pub(crate) fn already_linked(gateway_grpc: &str) -> Result<bool> {
+    let path = CovenConfig::config_path()?;
+    if let Some(profile) = CovenConfig::active_profile()? {
+        if !CovenConfig::exists() {
+            return Ok(false);
+        }
+        println!(
+            "{} Profile '{}' is already linked. Config at: {}",
+            "!".yellow().bold(),
+            profile,
+            path.display()
+        );
+        println!("  To re-link, remove the profile from the config file first.");
+        return Ok(true);
+    }
+
+    let mut file = ConfigFile::load_from(&path)?;
+    file.migrate_legacy();
+    let Some(link) = file.links.iter().find(|link| link.gateway == gateway_grpc) else {
+        return Ok(false);
+    };
+    println!(
+        "{} Device already linked to {} as '{}'. Config at: {}",
+        "!".yellow().bold(),
+        gateway_grpc,
+        link.name,
+        path.display()
+    );
+    println!(
+        "  To re-link, run {} first.",
+        format!("coven link remove {}", link.name).cyan()
+    );
+    Ok(true)
+}
+
+/// The given device name, or else the hostname
+pub(crate) fn device_name(name: Option<String>) -> String {
+    name.unwrap_or_else(|| {
+        hostname::get()
+            .map(|h| h.to_string_lossy().to_string())
+            .unwrap_or_else(|_| "unknown".to_string())
+    })
+}
+
+/// This device's SSH key, loaded or generated for linking
+pub(crate) struct DeviceKey {
+    /// `--key`, when given; otherwise the default device key is used
+    pub explicit_path: Option<PathBuf>,
+    pub path: PathBuf,
+    /// Passphrase source of an encrypted key, kept across re-linking
+    pub passphrase_source: Option<String>,
+    pub key: PrivateKey,
+    pub fingerprint: String,
+}
 
+/// Step 1 of linking: load the key at `key_path` (default: the device key),
+/// generating it if it doesn't exist. `key_passphrase` (default: the one in
+/// the config) unlocks an encrypted key, or encrypts a new one.
+pub(crate) fn load_device_key(
+    key_path: Option<String>,
+    key_passphrase: Option<String>,
+) -> Result<DeviceKey> {
+    let explicit_path = key_path.map(PathBuf::from);
+    let path = match &explicit_path {
+        Some(p) => p.clone(),
+        None => CovenConfig::key_path().context("Failed to determine key path")?,
+    };
+
+    println!(
+        "{} Loading SSH key from {}...",
+        "[1/4]".dimmed(),
+        path.display()
+    );
+
+    // An encrypted key keeps its passphrase source across re-linking
+    let passphrase_source = match key_passphrase {
+        Some(spec) => Some(spec),
+        None => CovenConfig::key_passphrase()?,
+    };
+    let passphrase = passphrase_source
+        .as_deref()
+        .map(coven_ssh::PassphraseSource::parse)
+        .transpose()
+        .context("Invalid key passphrase source")?;
+    let key = coven_ssh::load_or_generate_key_with_passphrase(&path, passphrase.as_ref())
+        .context("Failed to load or generate SSH key")?;
+    let fingerprint = coven_ssh::compute_fingerprint(key.public_key())
+        .context("Failed to compute key fingerprint")?;
+
+    println!("  Fingerprint: {}", fingerprint.dimmed());
+
+    Ok(DeviceKey {
+        explicit_path,
+        path,
+        passphrase_source,
+        key,
+        fingerprint,
+    })
+}
+
+/// Last step of linking: save the approved connection and say what's next
+pub(crate) fn save_config(
+    gateway_grpc: String,
+    token: String,
+    principal_id: String,
+    device_name: String,
+    device_key: DeviceKey,
+) -> Result<()> {
     let config = CovenConfig {
         gateway: gateway_grpc,
+        token_expires_at: crate::refresh::token_expiry(&token),
         token,
         principal_id,
-        device_name: device_name.clone(),
+        device_name,
+        key: device_key.explicit_path,
+        key_passphrase: device_key.passphrase_source,
+    };
+    let profile = CovenConfig::active_profile()?;
+    let link = match profile {
+        Some(_) => {
+            config.save().context("Failed to save configuration")?;
+            None
+        }
+        None => Some(config.save_link().context("Failed to save configuration")?),
     };
-    config.save().context("Failed to save configuration")?;
 
     println!();
     println!("{}", "Device linked successfully!".green().bold());
@@ -180,11 +281,17 @@ pub async fn run(gateway: String, name: Option<String>, key_path: Option<String>
         "  Config saved to: {}",
         CovenConfig::config_path()?.display()
     );
-    println!(
-        "  Token saved to:  {}",
-        CovenConfig::config_dir()?.join("token").display()
-    );
-    println!("  SSH key at:      {}", key_path.display());
+    if let Some(profile) = profile {
+        println!("  Profile:         {}", profile);
+    }
+    if let Some(link) = link {
+        println!("  Link:            {} (active)", link);
+        println!(
+            "  Token saved to:  {}",
+            CovenConfig::config_dir()?.join("token").display()
+        );
+    }
+    println!("  SSH key at:      {}", device_key.path.display());
     println!();
     println!("You can now use:");
     println!(
@@ -217,7 +324,7 @@ fn normalize_gateway_url(gateway: &str) -> String {
 }
 
 /// Derives gRPC address from gateway URL
-fn derive_grpc_address(gateway: &str) -> String {
+pub(crate) fn derive_grpc_address(gateway: &str) -> String {
     // Default to http for gRPC (TLS usually handled at network layer e.g. Tailscale)
     // Only use https if gateway URL explicitly starts with https://
     let scheme = if gateway.starts_with("https://") {
@@ -0,0 +1,180 @@
+// ABOUTME: Managing the gateways this device is linked to: list, switch, remove
+// ABOUTME: The active link is the connection every coven tool uses when no profile is selected
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Duration, Utc};
+use colored::Colorize;
+
+use crate::config::{ConfigFile, CovenConfig};
+use crate::refresh::token_expiry;
+
+/// Print the linked gateways, marking the active one
+pub fn list() -> Result<()> {
+    let path = CovenConfig::config_path()?;
+    let mut file = ConfigFile::load_from(&path)?;
+    file.migrate_legacy();
+    if file.links.is_empty() {
+        println!("Not linked to any gateway. Run 'coven link <gateway>' to link one.");
+        return Ok(());
+    }
+    for link in &file.links {
+        let marker = if link.active { "*" } else { " " };
+        let linked_at = link
+            .linked_at
+            .map(|at| at.format("%Y-%m-%d %H:%M").to_string())
+            .unwrap_or_else(|| "-".to_string());
+        println!(
+            "{} {:20} {:35} {:15} {}",
+            marker, link.name, link.gateway, link.principal_id, linked_at
+        );
+    }
+    warn_if_profile_selected()
+}
+
+/// Show the connection in use and how long its token has left
+pub fn status() -> Result<()> {
+    let file = ConfigFile::load_from(&CovenConfig::config_path()?)?;
+    let profile = CovenConfig::active_profile()?;
+    let config = file
+        .resolve(profile.as_deref())
+        .context("Device not linked. Run 'coven link <gateway>' first.")?;
+    let using = match (&profile, file.active_link()) {
+        (Some(name), _) => format!("profile '{}'", name),
+        (None, Some(link)) => format!("link '{}'", link.name),
+        (None, None) => "config".to_string(),
+    };
+
+    println!("  Using:      {}", using);
+    println!("  Gateway:    {}", config.gateway);
+    println!("  Principal:  {}", config.principal_id);
+    println!("  Device:     {}", config.device_name);
+    println!("  Token:      {}", token_status(&config, Utc::now()));
+    Ok(())
+}
+
+/// How long `config`'s token has left at `now`, for `status`
+fn token_status(config: &CovenConfig, now: DateTime<Utc>) -> String {
+    if config.token.is_empty() {
+        return "none (the gateway knows this device by its key)".to_string();
+    }
+    let Some(expires_at) = config
+        .token_expires_at
+        .or_else(|| token_expiry(&config.token))
+    else {
+        return "does not expire".to_string();
+    };
+    let at = expires_at.format("%Y-%m-%d %H:%M UTC");
+    if expires_at <= now {
+        format!("expired {} ago ({})", describe(now - expires_at), at)
+    } else {
+        format!("expires in {} ({})", describe(expires_at - now), at)
+    }
+}
+
+/// `duration` in its two largest units, e.g. "3d 4h" or "12m"
+fn describe(duration: Duration) -> String {
+    let (days, hours, minutes) = (
+        duration.num_days(),
+        duration.num_hours() % 24,
+        duration.num_minutes() % 60,
+    );
+    match (days, hours) {
+        (0, 0) => format!("{}m", minutes.max(1)),
+        (0, _) => format!("{}h {}m", hours, minutes),
+        _ => format!("{}d {}h", days, hours),
+    }
+}
+
+/// Make the link `name` the active one
+pub fn switch(name: &str) -> Result<()> {
+    let path = CovenConfig::config_path()?;
+    let mut file = ConfigFile::load_from(&path)?;
+    let link = file.switch_link(name)?.clone();
+    file.save_to(&path)?;
+    CovenConfig::write_token_file(Some(&link.token))?;
+
+    println!(
+        "{} Now using '{}' ({})",
+        "✓".green().bold(),
+        link.name,
+        link.gateway
+    );
+    warn_if_profile_selected()
+}
+
+/// Forget the link `name`. The gateway still knows this device's key;
+/// revoke it there to lock the device out.
+pub fn remove(name: &str) -> Result<()> {
+    let path = CovenConfig::config_path()?;
+    let mut file = ConfigFile::load_from(&path)?;
+    let removed = file.remove_link(name)?;
+    file.save_to(&path)?;
+
+    println!(
+        "{} Removed '{}' ({})",
+        "✓".green().bold(),
+        removed.name,
+        removed.gateway
+    );
+    if removed.active {
+        let active = file.active_link();
+        CovenConfig::write_token_file(active.map(|link| link.token.as_str()))?;
+        match active {
+            Some(link) => println!("  Now using '{}' ({})", link.name, link.gateway),
+            None => println!("  No gateways linked any more."),
+        }
+    }
+    Ok(())
+}
+
+/// Links only apply when no profile is selected; say so if one is
+fn warn_if_profile_selected() -> Result<()> {
+    if let Some(profile) = CovenConfig::active_profile()? {
+        println!(
+            "{} Profile '{}' is selected, so it's used instead of the active link. Run {} to use links.",
+            "!".yellow().bold(),
+            profile,
+            "coven profile use none".cyan()
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(token: &str, token_expires_at: Option<&str>) -> CovenConfig {
+        CovenConfig {
+            gateway: "http://gw:50051".to_string(),
+            token: token.to_string(),
+            principal_id: "p".to_string(),
+            device_name: "laptop".to_string(),
+            key: None,
+            key_passphrase: None,
+            token_expires_at: token_expires_at.map(|at| at.parse().unwrap()),
+        }
+    }
+
+    #[test]
+    fn test_token_status() {
+        let now = "2026-01-01T00:00:00Z".parse().unwrap();
+        assert_eq!(
+            token_status(&config("t", Some("2026-01-04T05:30:00Z")), now),
+            "expires in 3d 5h (2026-01-04 05:30 UTC)"
+        );
+        assert_eq!(
+            token_status(&config("t", Some("2026-01-01T02:15:00Z")), now),
+            "expires in 2h 15m (2026-01-01 02:15 UTC)"
+        );
+        assert_eq!(
+            token_status(&config("t", Some("2025-12-31T23:59:30Z")), now),
+            "expired 1m ago (2025-12-31 23:59 UTC)"
+        );
+        assert_eq!(
+            token_status(&config("opaque", None), now),
+            "does not expire"
+        );
+        assert!(token_status(&config("", None), now).starts_with("none"));
+    }
+}
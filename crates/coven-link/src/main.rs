@@ -1,14 +1,20 @@
 // ABOUTME: Entry point for coven-link device linking tool
 // ABOUTME: Links this device to a coven-gateway and sets up local config
 
-use anyhow::Result;
-use clap::Parser;
+use anyhow::{Context, Result};
+use clap::{Parser, Subcommand};
 
 #[derive(Parser)]
-#[command(name = "coven-link", about = "Link this device to a coven-gateway")]
+#[command(
+    name = "coven-link",
+    about = "Link this device to a coven-gateway",
+    args_conflicts_with_subcommands = true,
+    subcommand_negates_reqs = true
+)]
 struct Cli {
     /// Gateway URL (e.g., https://coven.example.com or http://localhost:8080)
-    gateway: String,
+    #[arg(required = true)]
+    gateway: Option<String>,
 
     /// Device name (defaults to hostname)
     #[arg(long, short = 'n')]
@@ -17,6 +23,43 @@ struct Cli {
     /// Path to SSH key (defaults to ~/.config/coven/device_key)
     #[arg(long)]
     key: Option<String>,
+
+    /// Where the key's passphrase comes from: env:NAME, cmd:COMMAND, prompt
+    /// or pass:PASSPHRASE. A new key is written encrypted with it.
+    #[arg(long, env = "COVEN_SSH_KEY_PASSPHRASE", value_name = "SOURCE")]
+    key_passphrase: Option<String>,
+
+    /// Link with a one-time code (shown with a QR code) for an admin to
+    /// approve, instead of a code entered in the gateway web UI
+    #[arg(long)]
+    pair: bool,
+
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Replace this device's SSH key; the gateway accepts the old key for a grace period
+    Rotate {
+        /// Seconds the old key keeps working (defaults to the gateway's)
+        #[arg(long, value_name = "SECS")]
+        grace_period: Option<u32>,
+    },
+    /// List the gateways this device is linked to; * marks the active one
+    List,
+    /// Show the gateway in use and when its token expires
+    Status,
+    /// Use another linked gateway
+    Switch {
+        /// Link name, as shown by `list`
+        name: String,
+    },
+    /// Forget a linked gateway
+    Remove {
+        /// Link name, as shown by `list`
+        name: String,
+    },
 }
 
 #[tokio::main]
@@ -25,5 +68,19 @@ async fn main() -> Result<()> {
 
     let cli = Cli::parse();
 
-    coven_link::run(cli.gateway, cli.name, cli.key).await
+    match cli.command {
+        Some(Command::Rotate { grace_period }) => coven_link::rotate(grace_period).await,
+        Some(Command::List) => coven_link::links::list(),
+        Some(Command::Status) => coven_link::links::status(),
+        Some(Command::Switch { name }) => coven_link::links::switch(&name),
+        Some(Command::Remove { name }) => coven_link::links::remove(&name),
+        None => {
+            let gateway = cli.gateway.context("a gateway URL is required")?;
+            if cli.pair {
+                coven_link::pair(gateway, cli.name, cli.key, cli.key_passphrase).await
+            } else {
+                coven_link::run(gateway, cli.name, cli.key, cli.key_passphrase).await
+            }
+        }
+    }
 }
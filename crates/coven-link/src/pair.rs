@@ -0,0 +1,179 @@
+// ABOUTME: Pairing-code linking, for devices where copying fingerprints around is a chore
+// ABOUTME: Asks the gateway for a one-time code, shows it with a QR code, and waits for an admin to approve it
+
+use anyhow::{bail, Context, Result};
+use colored::Colorize;
+use coven_grpc::ChannelConfig;
+use coven_proto::client::ClientServiceClient;
+use coven_proto::{GetPairingStatusRequest, RequestPairingCodeRequest};
+use coven_ssh::SshAuthCredentials;
+use percent_encoding::{utf8_percent_encode, NON_ALPHANUMERIC};
+use qrcode::render::unicode;
+use qrcode::QrCode;
+use std::time::Duration;
+use tonic::{Code, Request, Status};
+
+use crate::link::{already_linked, derive_grpc_address, device_name, load_device_key, save_config};
+
+/// How often to ask the gateway whether the code was approved
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Link this device by pairing code: an admin approves the code with
+/// `coven admin pair approve <code>` (or by scanning the QR code in the
+/// app), and the gateway then accepts this device's key.
+pub async fn pair(
+    gateway: String,
+    name: Option<String>,
+    key_path: Option<String>,
+    key_passphrase: Option<String>,
+) -> Result<()> {
+    let gateway_grpc = derive_grpc_address(&gateway);
+    if already_linked(&gateway_grpc)? {
+        return Ok(());
+    }
+    let device_name = device_name(name);
+
+    println!("{}", "Coven Device Pairing".bold());
+    println!();
+
+    let device_key = load_device_key(key_path, key_passphrase)?;
+
+    println!(
+        "{} Requesting pairing code from {}...",
+        "[2/4]".dimmed(),
+        gateway_grpc
+    );
+    let channel =
+        coven_grpc::create_channel(&ChannelConfig::new(&gateway_grpc).without_keep_alive())
+            .await
+            .context("Failed to connect to gateway")?;
+    let key = device_key.key.clone();
+    let mut client =
+        ClientServiceClient::with_interceptor(channel, move |mut request: Request<()>| {
+            SshAuthCredentials::new(&key)
+                .and_then(|creds| creds.apply_to_request(&mut request))
+                .map_err(|e| Status::internal(format!("signing request: {}", e)))?;
+            Ok(request)
+        });
+    let code = client
+        .request_pairing_code(RequestPairingCodeRequest {
+            device_name: device_name.clone(),
+        })
+        .await
+        .map_err(|status| anyhow::anyhow!("gateway refused: {}", status.message()))?
+        .into_inner();
+
+    println!();
+    println!("{}", "━".repeat(50).dimmed());
+    println!();
+    println!("{}", render_qr(&pairing_uri(&gateway_grpc, &code.code))?);
+    println!("  Approve this device on the gateway with:");
+    println!();
+    println!(
+        "  {}",
+        format!("coven admin pair approve {} --principal <id>", code.code).cyan()
+    );
+    println!();
+    println!(
+        "  {}",
+        format!("  {}  ", code.code).on_white().black().bold()
+    );
+    println!();
+    println!("  Key:             {}", code.fingerprint.dimmed());
+    println!("  Code expires at: {}", code.expires_at.dimmed());
+    println!();
+    println!("{}", "━".repeat(50).dimmed());
+    println!();
+
+    println!("{} Waiting for approval...", "[3/4]".dimmed());
+    let (principal_id, token) = loop {
+        tokio::time::sleep(POLL_INTERVAL).await;
+
+        let status = match client
+            .get_pairing_status(GetPairingStatusRequest {
+                code: code.code.clone(),
+            })
+            .await
+        {
+            Ok(response) => response.into_inner(),
+            Err(status) if status.code() == Code::NotFound => {
+                bail!("Pairing code expired. Please try again.")
+            }
+            Err(status) => bail!("Failed to check status: {}", status.message()),
+        };
+        match status.status.as_str() {
+            "approved" => {
+                let principal_id = status
+                    .principal_id
+                    .context("No principal_id in approved response")?;
+                // Gateways that authenticate by key alone issue no token
+                break (principal_id, status.token.unwrap_or_default());
+            }
+            "expired" => bail!("Pairing code expired. Please try again."),
+            "pending" => {
+                print!(".");
+                std::io::Write::flush(&mut std::io::stdout())?;
+            }
+            other => bail!("Unexpected status: {}", other),
+        }
+    };
+
+    println!();
+    println!(
+        "  {} as {}",
+        "Approved!".green().bold(),
+        principal_id.bold()
+    );
+    println!();
+
+    println!("{} Saving configuration...", "[4/4]".dimmed());
+    save_config(gateway_grpc, token, principal_id, device_name, device_key)
+}
+
+/// What the QR code holds: the gateway and code, for an app to approve
+fn pairing_uri(gateway: &str, code: &str) -> String {
+    format!(
+        "coven://pair?gateway={}&code={}",
+        utf8_percent_encode(gateway, NON_ALPHANUMERIC),
+        utf8_percent_encode(code, NON_ALPHANUMERIC)
+    )
+}
+
+/// `data` as a QR code of Unicode half blocks, light on dark so it scans
+/// from a dark terminal
+fn render_qr(data: &str) -> Result<String> {
+    let qr = QrCode::new(data.as_bytes()).context("Failed to encode QR code")?;
+    Ok(qr
+        .render::<unicode::Dense1x2>()
+        .dark_color(unicode::Dense1x2::Light)
+        .light_color(unicode::Dense1x2::Dark)
+        .build())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pairing_uri_escapes_gateway() {
+        assert_eq!(
+            pairing_uri("http://gw.example.com:50051", "K7QM-3XWD"),
+            "coven://pair?gateway=http%3A%2F%2Fgw%2Eexample%2Ecom%3A50051&code=K7QM%2D3XWD"
+        );
+    }
+
+    #[test]
+    fn test_render_qr_is_unicode_blocks() {
+        let qr = render_qr(&pairing_uri("http://gw:50051", "K7QM-3XWD")).unwrap();
+        let lines: Vec<&str> = qr.lines().collect();
+        assert!(lines.len() > 10);
+        // Each character is two modules stacked, so a square code has half
+        // as many lines as columns
+        let width = lines[0].chars().count();
+        assert!(lines.iter().all(|l| l.chars().count() == width));
+        assert_eq!(lines.len(), width.div_ceil(2));
+        assert!(qr
+            .chars()
+            .all(|c| matches!(c, ' ' | '█' | '▀' | '▄' | '\n')));
+    }
+}
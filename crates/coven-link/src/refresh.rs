@@ -0,0 +1,395 @@
+// ABOUTME: Token expiry and renewal for linked devices
+// ABOUTME: Reads a token's expiry from its JWT claims and swaps it for a fresh one, signed with the device key, before it runs out
+
+use anyhow::{Context, Result};
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use chrono::{DateTime, Duration, Utc};
+use coven_grpc::ChannelConfig;
+use coven_proto::client::ClientServiceClient;
+use coven_proto::RefreshTokenRequest;
+use coven_ssh::SshAuthCredentials;
+use std::future::Future;
+use std::path::Path;
+use tonic::{Request, Status};
+
+use crate::config::{lock_file, ConfigFile, CovenConfig};
+use crate::rotate::grpc_url;
+
+/// How long before expiry a token is renewed, unless the config's
+/// `token_refresh_window_secs` says otherwise
+pub const DEFAULT_REFRESH_WINDOW: Duration = Duration::days(1);
+
+/// A token the gateway issued in exchange for an old one
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FreshToken {
+    pub token: String,
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+/// When `token` expires, from the `exp` claim of a JWT. None for tokens
+/// that aren't JWTs or don't expire.
+pub fn token_expiry(token: &str) -> Option<DateTime<Utc>> {
+    let mut parts = token.split('.');
+    let (_header, payload, _signature) = (parts.next()?, parts.next()?, parts.next()?);
+    if parts.next().is_some() {
+        return None;
+    }
+    let payload = URL_SAFE_NO_PAD.decode(payload.trim_end_matches('=')).ok()?;
+    let claims: serde_json::Value = serde_json::from_slice(&payload).ok()?;
+    DateTime::from_timestamp(claims.get("exp")?.as_i64()?, 0)
+}
+
+/// Whether a token expiring at `expires_at` is due for renewal at `now`
+pub fn needs_refresh(
+    expires_at: Option<DateTime<Utc>>,
+    window: Duration,
+    now: DateTime<Utc>,
+) -> bool {
+    expires_at.is_some_and(|expires_at| {
+        expires_at
+            .checked_sub_signed(window)
+            .is_none_or(|due| due <= now)
+    })
+}
+
+/// Renew the token of the active profile or link if it expires within the
+/// renewal window. Call before talking to the gateway; returns whether the
+/// token was renewed.
+pub async fn refresh_token_if_needed() -> Result<bool> {
+    let path = CovenConfig::config_path()?;
+    let profile = CovenConfig::active_profile()?;
+    let refreshed = refresh_in(&path, profile.as_deref(), Utc::now(), request_token).await?;
+    match refreshed {
+        Some(config) => {
+            if profile.is_none() {
+                CovenConfig::write_token_file(Some(&config.token))?;
+            }
+            Ok(true)
+        }
+        None => Ok(false),
+    }
+}
+
+/// Renew the token `profile` (or the active link) in the config at `path`
+/// uses, if it's due at `now`, by handing its connection to `exchange`.
+/// Processes renewing at once take turns, so only the first asks the
+/// gateway. The config is written atomically; returns the updated
+/// connection, or None if there was nothing to renew.
+pub async fn refresh_in<F, Fut>(
+    path: &Path,
+    profile: Option<&str>,
+    now: DateTime<Utc>,
+    exchange: F,
+) -> Result<Option<CovenConfig>>
+where
+    F: FnOnce(CovenConfig) -> Fut,
+    Fut: Future<Output = Result<FreshToken>>,
+{
+    if due(&ConfigFile::load_from(path)?, profile, now).is_none() {
+        return Ok(None);
+    }
+    let lock_path = path.to_path_buf();
+    let _lock = tokio::task::spawn_blocking(move || lock_file(&lock_path))
+        .await
+        .context("Failed to lock config")?
+        .context("Failed to lock config")?;
+    // Another process may have renewed it while we waited
+    let Some(config) = due(&ConfigFile::load_from(path)?, profile, now) else {
+        return Ok(None);
+    };
+
+    let gateway = config.gateway.clone();
+    let fresh = exchange(config).await.context("Failed to refresh token")?;
+
+    // Re-read, so whatever changed while the gateway answered is kept
+    let mut file = ConfigFile::load_from(path)?;
+    let mut current = file.resolve(profile)?;
+    if current.gateway != gateway {
+        return Ok(None);
+    }
+    current.token_expires_at = fresh.expires_at.or_else(|| token_expiry(&fresh.token));
+    current.token = fresh.token;
+    file.set(profile, &current);
+    file.save_to(path)?;
+    Ok(Some(current))
+}
+
+/// The connection `profile` uses in `file`, if its token is due for
+/// renewal at `now`
+fn due(file: &ConfigFile, profile: Option<&str>, now: DateTime<Utc>) -> Option<CovenConfig> {
+    // Not linked, or linked to a gateway that authenticates by key alone
+    let config = file.resolve(profile).ok()?;
+    if config.token.is_empty() {
+        return None;
+    }
+    let expires_at = config
+        .token_expires_at
+        .or_else(|| token_expiry(&config.token));
+    let window = file
+        .token_refresh_window_secs
+        .map_or(DEFAULT_REFRESH_WINDOW, |secs| {
+            i64::try_from(secs)
+                .ok()
+                .and_then(Duration::try_seconds)
+                .unwrap_or(Duration::MAX)
+        });
+    needs_refresh(expires_at, window, now).then_some(config)
+}
+
+/// Ask `config`'s gateway for a fresh token, signed with the device key
+async fn request_token(config: CovenConfig) -> Result<FreshToken> {
+    let key_path = CovenConfig::key_path().context("Failed to determine key path")?;
+    let passphrase = CovenConfig::passphrase_source()?;
+    let key = coven_ssh::load_key_with_passphrase(&key_path, passphrase.as_ref())
+        .context("Failed to load device key")?;
+
+    let channel = coven_grpc::create_channel(
+        &ChannelConfig::new(grpc_url(&config.gateway)).without_keep_alive(),
+    )
+    .await
+    .context("Failed to connect to gateway")?;
+    let mut client =
+        ClientServiceClient::with_interceptor(channel, move |mut request: Request<()>| {
+            SshAuthCredentials::new(&key)
+                .and_then(|creds| creds.apply_to_request(&mut request))
+                .map_err(|e| Status::internal(format!("signing request: {}", e)))?;
+            Ok(request)
+        });
+    let response = client
+        .refresh_token(RefreshTokenRequest {})
+        .await
+        .map_err(|status| anyhow::anyhow!("gateway refused: {}", status.message()))?
+        .into_inner();
+    let expires_at = response
+        .expires_at
+        .map(|at| DateTime::parse_from_rfc3339(&at).map(|at| at.with_timezone(&Utc)))
+        .transpose()
+        .context("Gateway sent an invalid expiry")?;
+    Ok(FreshToken {
+        token: response.token,
+        expires_at,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(rfc3339: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(rfc3339)
+            .unwrap()
+            .with_timezone(&Utc)
+    }
+
+    /// An unsigned JWT with `claims`; only the payload matters here
+    fn jwt(claims: &str) -> String {
+        format!(
+            "{}.{}.sig",
+            URL_SAFE_NO_PAD.encode(r#"{"alg":"HS256"}"#),
+            URL_SAFE_NO_PAD.encode(claims)
+        )
+    }
+
+    async fn unexpected_refresh(_config: CovenConfig) -> Result<FreshToken> {
+        panic!("the token isn't due for renewal")
+    }
+
+    const LINKED: &str = r#"
+[[links]]
+name = "gw"
+gateway = "http://gw:50051"
+token = "old"
+token_expires_at = "2026-01-02T00:00:00Z"
+principal_id = "p"
+device_name = "laptop"
+active = true
+
+[tui]
+theme = "dark"
+"#;
+
+    #[test]
+    fn test_token_expiry_reads_the_exp_claim() {
+        let token = jwt(r#"{"sub":"p","exp":1767312000}"#);
+        assert_eq!(token_expiry(&token), Some(at("2026-01-02T00:00:00Z")));
+        assert_eq!(token_expiry(&jwt(r#"{"sub":"p"}"#)), None);
+        assert_eq!(token_expiry("opaque-token"), None);
+        assert_eq!(token_expiry("a.!!!.c"), None);
+    }
+
+    #[test]
+    fn test_needs_refresh_within_the_window() {
+        let expires_at = Some(at("2026-01-02T00:00:00Z"));
+        let window = Duration::hours(1);
+        assert!(!needs_refresh(
+            expires_at,
+            window,
+            at("2026-01-01T22:59:59Z")
+        ));
+        assert!(needs_refresh(
+            expires_at,
+            window,
+            at("2026-01-01T23:00:00Z")
+        ));
+        assert!(needs_refresh(
+            expires_at,
+            window,
+            at("2026-01-03T00:00:00Z")
+        ));
+        assert!(!needs_refresh(None, window, at("2030-01-01T00:00:00Z")));
+    }
+
+    #[tokio::test]
+    async fn test_near_expiry_token_is_replaced_atomically() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        std::fs::write(&path, LINKED).unwrap();
+
+        // Two days out: nothing to do, and the gateway isn't asked
+        let refreshed = refresh_in(&path, None, at("2025-12-31T00:00:00Z"), unexpected_refresh)
+            .await
+            .unwrap();
+        assert!(refreshed.is_none());
+
+        // An hour out: the gateway swaps the token
+        let fresh = jwt(r#"{"sub":"p","exp":1767916800}"#);
+        let refreshed = refresh_in(&path, None, at("2026-01-01T23:00:00Z"), |config| {
+            assert_eq!(config.token, "old");
+            let token = fresh.clone();
+            async move {
+                Ok(FreshToken {
+                    token,
+                    expires_at: None,
+                })
+            }
+        })
+        .await
+        .unwrap()
+        .unwrap();
+        assert_eq!(refreshed.token, fresh);
+        assert_eq!(refreshed.token_expires_at, Some(at("2026-01-09T00:00:00Z")));
+
+        let file = ConfigFile::load_from(&path).unwrap();
+        let link = file.active_link().unwrap();
+        assert_eq!(link.token, fresh);
+        assert_eq!(link.token_expires_at, Some(at("2026-01-09T00:00:00Z")));
+        assert!(file.other.contains_key("tui"));
+        // Only the config itself is left, besides the lock; no temporary
+        // file, and nobody else can read it
+        let entries: Vec<_> = std::fs::read_dir(dir.path())
+            .unwrap()
+            .map(|e| e.unwrap().file_name())
+            .filter(|name| name != "config.toml.lock")
+            .collect();
+        assert_eq!(entries, ["config.toml"]);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_refreshes_ask_the_gateway_once() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        std::fs::write(&path, LINKED).unwrap();
+        let asked = &AtomicUsize::new(0);
+        let exchange = move |_config: CovenConfig| async move {
+            asked.fetch_add(1, Ordering::SeqCst);
+            tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+            Ok(FreshToken {
+                token: "new".to_string(),
+                expires_at: Some(at("2026-02-01T00:00:00Z")),
+            })
+        };
+
+        let now = at("2026-01-01T23:00:00Z");
+        let (first, second) = tokio::join!(
+            refresh_in(&path, None, now, exchange),
+            refresh_in(&path, None, now, exchange)
+        );
+        assert_eq!(asked.load(Ordering::SeqCst), 1);
+        let renewed: Vec<_> = [first.unwrap(), second.unwrap()]
+            .into_iter()
+            .flatten()
+            .collect();
+        assert_eq!(renewed.len(), 1);
+        assert_eq!(renewed[0].token, "new");
+    }
+
+    #[tokio::test]
+    async fn test_failed_refresh_leaves_the_config_alone() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        std::fs::write(&path, LINKED).unwrap();
+
+        let err = refresh_in(&path, None, at("2026-01-01T23:30:00Z"), |_| async {
+            Err::<FreshToken, _>(anyhow::anyhow!("gateway refused: unknown key"))
+        })
+        .await
+        .unwrap_err();
+        assert!(format!("{:#}", err).contains("unknown key"), "{:#}", err);
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), LINKED);
+    }
+
+    #[tokio::test]
+    async fn test_profile_window_and_keyless_links() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        std::fs::write(
+            &path,
+            r#"
+token_refresh_window_secs = 300
+
+[profiles.team]
+gateway = "http://team:50051"
+token = "team-old"
+token_expires_at = "2026-01-02T00:00:00Z"
+
+[profiles.keyonly]
+gateway = "http://local:50051"
+"#,
+        )
+        .unwrap();
+
+        // Outside the five minute window
+        let early = refresh_in(
+            &path,
+            Some("team"),
+            at("2026-01-01T23:00:00Z"),
+            unexpected_refresh,
+        )
+        .await
+        .unwrap();
+        assert!(early.is_none());
+
+        let refreshed = refresh_in(&path, Some("team"), at("2026-01-01T23:56:00Z"), |_| async {
+            Ok(FreshToken {
+                token: "team-new".to_string(),
+                expires_at: Some(at("2026-01-03T00:00:00Z")),
+            })
+        })
+        .await
+        .unwrap()
+        .unwrap();
+        assert_eq!(refreshed.token, "team-new");
+        let file = ConfigFile::load_from(&path).unwrap();
+        assert_eq!(file.profiles["team"].token.as_deref(), Some("team-new"));
+        assert!(file.links.is_empty());
+
+        // No token, nothing to renew
+        let keyonly = refresh_in(
+            &path,
+            Some("keyonly"),
+            at("2030-01-01T00:00:00Z"),
+            unexpected_refresh,
+        )
+        .await
+        .unwrap();
+        assert!(keyonly.is_none());
+    }
+}
@@ -0,0 +1,126 @@
+// ABOUTME: Key rotation for a linked device
+// ABOUTME: Registers a new SSH key with the gateway, signed by the current one, then switches to it
+
+use anyhow::{Context, Result};
+use colored::Colorize;
+use coven_grpc::ChannelConfig;
+use coven_proto::client::ClientServiceClient;
+use coven_proto::{RotateKeyRequest, RotateKeyResponse};
+use coven_ssh::{KeyRotation, SshAuthCredentials};
+use tonic::{Request, Status};
+
+use crate::config::CovenConfig;
+
+/// Replace the device key of the active profile. The gateway keeps
+/// accepting the old key for `grace_period_secs` (default: its own, a day
+/// for coven-serve), so other processes using it can catch up.
+pub async fn rotate(grace_period_secs: Option<u32>) -> Result<()> {
+    let config =
+        CovenConfig::load().context("Device not linked. Run 'coven link <gateway>' first.")?;
+    let key_path = CovenConfig::key_path().context("Failed to determine key path")?;
+    let passphrase = CovenConfig::passphrase_source()?;
+
+    println!("{}", "Coven Key Rotation".bold());
+    println!();
+    println!(
+        "{} Preparing a new SSH key next to {}...",
+        "[1/3]".dimmed(),
+        key_path.display()
+    );
+    let rotation = coven_ssh::rotate_key_with_passphrase(&key_path, passphrase.as_ref())
+        .context("Failed to prepare new SSH key")?;
+    let old_fingerprint = coven_ssh::compute_fingerprint(rotation.old_key.public_key())?;
+    let new_fingerprint = coven_ssh::compute_fingerprint(rotation.new_key.public_key())?;
+    println!("  Current: {}", old_fingerprint.dimmed());
+    println!("  New:     {}", new_fingerprint.dimmed());
+
+    println!(
+        "{} Registering the new key with {}...",
+        "[2/3]".dimmed(),
+        config.gateway
+    );
+    // On failure the new key stays next to the old one, so a retry
+    // registers the same key
+    let response = register(&config.gateway, &rotation, grace_period_secs)
+        .await
+        .context("Failed to register the new key; the current key is unchanged, so run 'coven link rotate' again to retry")?;
+
+    println!("{} Switching to the new key...", "[3/3]".dimmed());
+    let old_path = rotation
+        .commit()
+        .context("Failed to move the new key into place")?;
+
+    println!();
+    println!("{}", "Key rotated successfully!".green().bold());
+    println!();
+    println!("  Principal:  {}", response.principal_id);
+    println!("  Key:        {}", key_path.display());
+    println!(
+        "  Old key:    {} (accepted until {})",
+        old_path.display(),
+        response.old_keys_expire_at
+    );
+    println!();
+    println!("  Restart agents and clients using this key before the old one expires.");
+    Ok(())
+}
+
+/// Ask the gateway at `gateway` to switch to the rotation's new key, signed
+/// with the current one
+async fn register(
+    gateway: &str,
+    rotation: &KeyRotation,
+    grace_period_secs: Option<u32>,
+) -> Result<RotateKeyResponse> {
+    let channel =
+        coven_grpc::create_channel(&ChannelConfig::new(grpc_url(gateway)).without_keep_alive())
+            .await
+            .context("Failed to connect to gateway")?;
+    let old_key = rotation.old_key.clone();
+    let mut client =
+        ClientServiceClient::with_interceptor(channel, move |mut request: Request<()>| {
+            SshAuthCredentials::new(&old_key)
+                .and_then(|creds| creds.apply_to_request(&mut request))
+                .map_err(|e| Status::internal(format!("signing request: {}", e)))?;
+            Ok(request)
+        });
+
+    let proof = rotation
+        .proof()
+        .context("Failed to sign with the new key")?;
+    let response = client
+        .rotate_key(RotateKeyRequest {
+            new_public_key: proof.new_public_key,
+            timestamp: proof.timestamp,
+            new_key_signature: proof.signature,
+            grace_period_secs,
+        })
+        .await
+        .map_err(|status| anyhow::anyhow!("gateway refused: {}", status.message()))?;
+    Ok(response.into_inner())
+}
+
+/// The stored gateway address as a URL; configs written by old versions
+/// have no scheme
+pub(crate) fn grpc_url(gateway: &str) -> String {
+    if gateway.contains("://") {
+        gateway.to_string()
+    } else {
+        format!("http://{}", gateway)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_grpc_url() {
+        assert_eq!(grpc_url("localhost:50051"), "http://localhost:50051");
+        assert_eq!(
+            grpc_url("https://coven.example.com:443"),
+            "https://coven.example.com:443"
+        );
+        assert_eq!(grpc_url("unix:///run/coven.sock"), "unix:///run/coven.sock");
+    }
+}
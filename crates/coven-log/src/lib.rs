@@ -1,18 +1,92 @@
 // ABOUTME: Shared logging setup for all coven binaries
-// ABOUTME: Three functions: init() for stderr, init_file() for TUI, init_for() for bridges
+// ABOUTME: init() for stderr, init_file() for TUI, init_for() for bridges, init_structured() for JSON, plus -q/-v level mapping
+// ABOUTME: Every init installs a filter set_level() and SIGHUP can change at runtime
 
+#[cfg(feature = "otlp")]
+mod otlp;
+pub mod reload;
+pub mod rolling;
+pub mod structured;
+
+#[cfg(feature = "otlp")]
+pub use otlp::{grpc_server_span, init_otlp, init_otlp_for, inject_context, OtlpGuard};
+#[cfg(unix)]
+pub use reload::reload_on_sighup;
+pub use reload::{current_level, reload_level_file, set_level, LevelError};
+pub use rolling::{RollingFile, RotationPolicy};
+pub use structured::{init_structured, init_structured_with_level, json_requested};
+
+use tracing::Level;
 use tracing_subscriber::EnvFilter;
 
+/// Map `-q/--quiet` and repeated `-v/--verbose` flags to a log level:
+/// `-q` is WARN, no flag is INFO, `-v` is DEBUG, `-vv` and beyond is TRACE.
+/// Quiet wins if both are given.
+pub fn level_from_flags(quiet: bool, verbose: u8) -> Level {
+    match (quiet, verbose) {
+        (true, _) => Level::WARN,
+        (false, 0) => Level::INFO,
+        (false, 1) => Level::DEBUG,
+        (false, _) => Level::TRACE,
+    }
+}
+
+/// Use RUST_LOG when it is set, otherwise the given default filter.
+fn env_filter_or(default: impl FnOnce() -> EnvFilter) -> EnvFilter {
+    match std::env::var("RUST_LOG") {
+        Ok(directives) if !directives.trim().is_empty() => EnvFilter::new(directives),
+        _ => default(),
+    }
+}
+
 /// Standard logging to stderr. Default: INFO level, RUST_LOG override.
-/// Used by CLI and daemon binaries.
+/// Used by CLI and daemon binaries. With COVEN_LOG_FORMAT=json, logs JSON
+/// lines as `init_structured` does, named after the executable.
 pub fn init() {
-    let _ = tracing_subscriber::fmt()
-        .with_env_filter(EnvFilter::from_default_env().add_directive(tracing::Level::INFO.into()))
-        .try_init();
+    init_with_level(Level::INFO);
+}
+
+/// Logging to stderr at `level`, typically from `level_from_flags`.
+/// RUST_LOG still takes precedence when set.
+pub fn init_with_level(level: Level) {
+    if json_requested() {
+        structured::try_init_json(&process_name(), level_filter(level));
+        return;
+    }
+    let _ = reload::try_init(level_filter(level), tracing_subscriber::fmt::layer());
+}
+
+/// The executable's name, for tagging JSON logs
+fn process_name() -> String {
+    std::env::current_exe()
+        .ok()
+        .and_then(|exe| {
+            exe.file_stem()
+                .map(|stem| stem.to_string_lossy().into_owned())
+        })
+        .unwrap_or_else(|| "coven".to_string())
+}
+
+/// Everything at `level`, unless RUST_LOG says otherwise.
+fn level_filter(level: Level) -> EnvFilter {
+    env_filter_or(|| EnvFilter::default().add_directive(level.into()))
+}
+
+/// The named crate at `level` and everything else at WARN, unless RUST_LOG
+/// says otherwise.
+fn crate_filter(crate_name: &str, level: Level) -> EnvFilter {
+    env_filter_or(|| {
+        let directive = format!("{crate_name}={}", level.as_str().to_ascii_lowercase());
+        EnvFilter::default()
+            .add_directive(Level::WARN.into())
+            .add_directive(directive.parse().unwrap_or_else(|_| level.into()))
+    })
 }
 
 /// File-based logging for TUI apps. Default: WARN level, RUST_LOG override.
-/// Logs to ~/.config/coven/{app_name}/{app_name}.log
+/// Logs to ~/.config/coven/{app_name}/{app_name}.log, rotated at 10MB or
+/// daily with 5 archives kept; COVEN_LOG_MAX_SIZE, COVEN_LOG_KEEP and
+/// COVEN_LOG_COMPRESS adjust that (see `RotationPolicy::from_env`).
 /// If setup fails, prints a warning to stderr and continues without logging.
 pub fn init_file(app_name: &str) {
     if let Err(e) = init_file_inner(app_name) {
@@ -23,45 +97,67 @@ pub fn init_file(app_name: &str) {
 fn init_file_inner(app_name: &str) -> Result<(), Box<dyn std::error::Error>> {
     let config_dir = dirs::config_dir().ok_or("could not determine config directory")?;
     let log_dir = config_dir.join("coven").join(app_name);
-    std::fs::create_dir_all(&log_dir)?;
+    let log_file = RollingFile::open(&log_dir, app_name, RotationPolicy::from_env())?;
 
-    let log_file = std::fs::OpenOptions::new()
-        .create(true)
-        .append(true)
-        .open(log_dir.join(format!("{app_name}.log")))?;
-
-    tracing_subscriber::fmt()
-        .with_writer(log_file)
-        .with_env_filter(EnvFilter::from_default_env().add_directive(tracing::Level::WARN.into()))
-        .with_ansi(false)
-        .try_init()
-        .map_err(|e| format!("tracing already initialized: {e}"))?;
+    let filter = env_filter_or(|| EnvFilter::default().add_directive(Level::WARN.into()));
+    let layer = tracing_subscriber::fmt::layer()
+        .with_writer(std::sync::Mutex::new(log_file))
+        .with_ansi(false);
+    reload::try_init(filter, layer).map_err(|e| format!("tracing already initialized: {e}"))?;
 
     Ok(())
 }
 
 /// Crate-filtered logging to stderr. Default: INFO for named crate, WARN for everything else.
-/// Used by bridge binaries (matrix, slack, telegram).
+/// Used by bridge binaries (matrix, slack, telegram). With
+/// COVEN_LOG_FORMAT=json, logs JSON lines named after the crate.
 pub fn init_for(crate_name: &str) {
-    let directive = format!("{crate_name}=info");
-    let filter = EnvFilter::from_default_env()
-        .add_directive(tracing::Level::WARN.into())
-        .add_directive(
-            directive
-                .parse()
-                .unwrap_or_else(|_| tracing::Level::INFO.into()),
-        );
+    init_for_with_level(crate_name, Level::INFO);
+}
 
-    let _ = tracing_subscriber::fmt().with_env_filter(filter).try_init();
+/// Crate-filtered logging to stderr with the named crate at `level` and
+/// everything else at WARN. RUST_LOG still takes precedence when set.
+pub fn init_for_with_level(crate_name: &str, level: Level) {
+    if json_requested() {
+        structured::try_init_json(
+            &crate_name.replace('_', "-"),
+            crate_filter(crate_name, level),
+        );
+        return;
+    }
+    let _ = reload::try_init(
+        crate_filter(crate_name, level),
+        tracing_subscriber::fmt::layer(),
+    );
 }
 
 #[cfg(test)]
 mod tests {
+    use super::*;
+
     #[test]
     fn exports_init() {
         let _ = super::init as fn();
     }
 
+    #[test]
+    fn exports_init_with_level() {
+        let _ = super::init_with_level as fn(Level);
+        let _ = super::init_for_with_level as fn(&str, Level);
+        let _ = super::init_structured as fn(&str);
+    }
+
+    #[test]
+    fn level_from_flags_maps_verbosity() {
+        assert_eq!(level_from_flags(true, 0), Level::WARN);
+        assert_eq!(level_from_flags(false, 0), Level::INFO);
+        assert_eq!(level_from_flags(false, 1), Level::DEBUG);
+        assert_eq!(level_from_flags(false, 2), Level::TRACE);
+        assert_eq!(level_from_flags(false, 5), Level::TRACE);
+        // Quiet wins over verbose
+        assert_eq!(level_from_flags(true, 2), Level::WARN);
+    }
+
     #[test]
     fn exports_init_file() {
         let _ = super::init_file as fn(&str);
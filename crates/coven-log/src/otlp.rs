@@ -0,0 +1,192 @@
+// ABOUTME: Optional OTLP span export and W3C trace context propagation over gRPC metadata
+// ABOUTME: init_otlp() adds an exporter when OTEL_EXPORTER_OTLP_ENDPOINT is set; otherwise logs as usual
+
+use crate::structured::{json_layer, json_requested};
+use crate::{crate_filter, level_filter};
+use opentelemetry::propagation::{Extractor, Injector};
+use opentelemetry::trace::TracerProvider as _;
+use opentelemetry::{global, KeyValue};
+use opentelemetry_sdk::propagation::TraceContextPropagator;
+use opentelemetry_sdk::trace::TracerProvider;
+use opentelemetry_sdk::{runtime, Resource};
+use tonic::codegen::http;
+use tonic::metadata::MetadataMap;
+use tracing::Level;
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+use tracing_subscriber::prelude::*;
+use tracing_subscriber::{EnvFilter, Layer};
+
+/// Environment variable naming the OTLP collector (e.g. `http://localhost:4317`)
+const ENDPOINT_ENV: &str = "OTEL_EXPORTER_OTLP_ENDPOINT";
+
+/// Flushes buffered spans to the collector when dropped. Keep it alive in
+/// `main` for as long as the process should export spans.
+#[must_use = "spans are only flushed while the guard is alive"]
+pub struct OtlpGuard {
+    provider: Option<TracerProvider>,
+}
+
+impl Drop for OtlpGuard {
+    fn drop(&mut self) {
+        if let Some(provider) = self.provider.take() {
+            if let Err(e) = provider.shutdown() {
+                eprintln!("Warning: failed to flush traces: {e}");
+            }
+        }
+    }
+}
+
+/// Logging to stderr like `init_with_level` (JSON lines with
+/// COVEN_LOG_FORMAT=json), plus span export over OTLP to
+/// the collector in OTEL_EXPORTER_OTLP_ENDPOINT, tagged with `service_name`.
+/// Without that variable, or if the exporter can't be built, only logs.
+/// Must be called from within a Tokio runtime.
+pub fn init_otlp(service_name: &str, level: Level) -> OtlpGuard {
+    init_layers(service_name, level_filter(level))
+}
+
+/// Like `init_for_with_level`, plus span export named after the crate.
+pub fn init_otlp_for(crate_name: &str, level: Level) -> OtlpGuard {
+    init_layers(
+        &crate_name.replace('_', "-"),
+        crate_filter(crate_name, level),
+    )
+}
+
+fn init_layers(service_name: &str, filter: EnvFilter) -> OtlpGuard {
+    let provider = match std::env::var(ENDPOINT_ENV) {
+        Ok(endpoint) if !endpoint.trim().is_empty() => match build_provider(service_name) {
+            Ok(provider) => Some(provider),
+            Err(e) => {
+                eprintln!("Warning: failed to set up trace export to {endpoint}: {e}");
+                None
+            }
+        },
+        _ => None,
+    };
+
+    let otel_layer = provider
+        .as_ref()
+        .map(|p| tracing_opentelemetry::layer().with_tracer(p.tracer("coven")));
+    let (text, json) = if json_requested() {
+        (None, Some(json_layer(service_name, std::io::stderr)))
+    } else {
+        (Some(tracing_subscriber::fmt::layer()), None)
+    };
+    // Layer::and_then, not Option::and_then: every present layer stacks
+    let layers = Layer::and_then(text, json).and_then(otel_layer);
+    let _ = crate::reload::try_init(filter, layers);
+
+    OtlpGuard { provider }
+}
+
+fn build_provider(service_name: &str) -> Result<TracerProvider, Box<dyn std::error::Error>> {
+    // The exporter reads the endpoint and headers from the OTEL_* variables
+    let exporter = opentelemetry_otlp::SpanExporter::builder()
+        .with_tonic()
+        .build()?;
+    global::set_text_map_propagator(TraceContextPropagator::new());
+
+    Ok(TracerProvider::builder()
+        .with_batch_exporter(exporter, runtime::Tokio)
+        .with_resource(Resource::new([KeyValue::new(
+            "service.name",
+            service_name.to_string(),
+        )]))
+        .build())
+}
+
+/// Add the current span's trace context to outgoing gRPC metadata, so the
+/// server's spans join the caller's trace. A no-op until `init_otlp` has
+/// set up export.
+pub fn inject_context(metadata: &mut MetadataMap) {
+    let context = tracing::Span::current().context();
+    let mut headers = std::mem::take(metadata).into_headers();
+    global::get_text_map_propagator(|propagator| {
+        propagator.inject_context(&context, &mut HeaderInjector(&mut headers))
+    });
+    *metadata = MetadataMap::from_headers(headers);
+}
+
+/// Span for one incoming gRPC call, continuing the caller's trace when its
+/// metadata carries one. Pass to tonic's `Server::builder().trace_fn`.
+/// Handlers fill in `request_id` once they know it.
+pub fn grpc_server_span(request: &http::Request<()>) -> tracing::Span {
+    let parent = global::get_text_map_propagator(|propagator| {
+        propagator.extract(&HeaderExtractor(request.headers()))
+    });
+    let method = request.uri().path();
+    let span = tracing::info_span!(
+        "grpc.request",
+        otel.name = %method,
+        rpc.method = %method,
+        request_id = tracing::field::Empty,
+    );
+    span.set_parent(parent);
+    span
+}
+
+struct HeaderInjector<'a>(&'a mut http::HeaderMap);
+
+impl Injector for HeaderInjector<'_> {
+    fn set(&mut self, key: &str, value: String) {
+        if let (Ok(name), Ok(value)) = (
+            http::HeaderName::from_bytes(key.as_bytes()),
+            http::HeaderValue::from_str(&value),
+        ) {
+            self.0.insert(name, value);
+        }
+    }
+}
+
+struct HeaderExtractor<'a>(&'a http::HeaderMap);
+
+impl Extractor for HeaderExtractor<'_> {
+    fn get(&self, key: &str) -> Option<&str> {
+        self.0.get(key).and_then(|value| value.to_str().ok())
+    }
+
+    fn keys(&self) -> Vec<&str> {
+        self.0.keys().map(|name| name.as_str()).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TRACE_ID: &str = "4bf92f3577b34da6a3ce929d0e0e4736";
+
+    #[test]
+    fn server_span_continues_caller_trace() {
+        global::set_text_map_propagator(TraceContextPropagator::new());
+        let provider = TracerProvider::builder().build();
+        let subscriber = tracing_subscriber::registry()
+            .with(tracing_opentelemetry::layer().with_tracer(provider.tracer("test")));
+
+        tracing::subscriber::with_default(subscriber, || {
+            let request = http::Request::builder()
+                .uri("/coven.ClientService/SendMessage")
+                .header("traceparent", format!("00-{TRACE_ID}-00f067aa0ba902b7-01"))
+                .body(())
+                .unwrap();
+            let span = grpc_server_span(&request);
+            let _entered = span.enter();
+
+            // Calls made while handling the request carry the same trace
+            let mut metadata = MetadataMap::new();
+            inject_context(&mut metadata);
+            let traceparent = metadata.get("traceparent").unwrap().to_str().unwrap();
+            assert!(traceparent.starts_with(&format!("00-{TRACE_ID}-")));
+            assert!(!traceparent.contains("00f067aa0ba902b7"));
+        });
+    }
+
+    #[test]
+    fn inject_keeps_existing_metadata() {
+        let mut metadata = MetadataMap::new();
+        metadata.insert("authorization", "Bearer abc".parse().unwrap());
+        inject_context(&mut metadata);
+        assert_eq!(metadata.get("authorization").unwrap(), "Bearer abc");
+    }
+}
@@ -0,0 +1,299 @@
+// ABOUTME: Changing the log filter of a running process, so debugging doesn't need a restart
+// ABOUTME: set_level() swaps the filter the init functions installed; SIGHUP re-reads ~/.config/coven/log-level
+
+use std::fmt;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use tracing_subscriber::layer::{Layered, SubscriberExt};
+use tracing_subscriber::util::{SubscriberInitExt, TryInitError};
+use tracing_subscriber::{reload, EnvFilter, Layer, Registry};
+
+/// Environment variable naming the file SIGHUP reads a filter from, in
+/// place of ~/.config/coven/log-level
+pub const LEVEL_FILE_ENV: &str = "COVEN_LOG_LEVEL_FILE";
+
+/// The filter installed by whichever init function ran first
+static FILTER: OnceLock<ReloadableFilter> = OnceLock::new();
+
+/// Why a new filter wasn't applied. The current one stays either way.
+#[derive(Debug)]
+pub enum LevelError {
+    /// The directive doesn't parse as a RUST_LOG-style filter
+    Invalid { directive: String, reason: String },
+    /// Logging wasn't set up by coven-log, so there is no filter to change
+    NotInitialized,
+    /// The level file exists but couldn't be read
+    Read { path: PathBuf, source: io::Error },
+}
+
+impl fmt::Display for LevelError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Invalid { directive, reason } => {
+                write!(f, "invalid log filter {directive:?}: {reason}")
+            }
+            Self::NotInitialized => write!(f, "logging has no reloadable filter"),
+            Self::Read { path, source } => write!(f, "failed to read {}: {source}", path.display()),
+        }
+    }
+}
+
+impl std::error::Error for LevelError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Read { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+/// Parse `directive` ("debug", "coven_agent=trace,warn", ...) the way
+/// RUST_LOG is parsed, without applying it
+pub fn parse_filter(directive: &str) -> Result<EnvFilter, LevelError> {
+    let invalid = |reason: String| LevelError::Invalid {
+        directive: directive.to_string(),
+        reason,
+    };
+    let trimmed = directive.trim();
+    if trimmed.is_empty() {
+        return Err(invalid("empty filter".to_string()));
+    }
+    EnvFilter::builder()
+        .parse(trimmed)
+        .map_err(|e| invalid(e.to_string()))
+}
+
+/// Replace this process's log filter with `directive`. An invalid
+/// directive is rejected and the current filter kept.
+pub fn set_level(directive: &str) -> Result<(), LevelError> {
+    FILTER
+        .get()
+        .ok_or(LevelError::NotInitialized)?
+        .set(directive)
+}
+
+/// The filter currently applied, if coven-log set logging up
+pub fn current_level() -> Option<String> {
+    FILTER.get().and_then(ReloadableFilter::current)
+}
+
+/// The file SIGHUP reads a filter from: COVEN_LOG_LEVEL_FILE, or
+/// ~/.config/coven/log-level
+pub fn level_file() -> Option<PathBuf> {
+    match std::env::var_os(LEVEL_FILE_ENV) {
+        Some(path) if !path.is_empty() => Some(PathBuf::from(path)),
+        _ => dirs::config_dir().map(|dir| dir.join("coven").join("log-level")),
+    }
+}
+
+/// Apply the filter in `level_file()`, or go back to the filter logging
+/// started with if the file is missing or empty. Returns the filter now
+/// in effect.
+pub fn reload_level_file() -> Result<String, LevelError> {
+    let filter = FILTER.get().ok_or(LevelError::NotInitialized)?;
+    match level_file() {
+        Some(path) => filter.reload_from(&path),
+        None => filter.restore(),
+    }
+}
+
+/// Call `reload_level_file` on every SIGHUP, logging what it changed.
+/// Must be called from within a Tokio runtime.
+#[cfg(unix)]
+pub fn reload_on_sighup() {
+    use tokio::signal::unix::{signal, SignalKind};
+
+    let mut hangups = match signal(SignalKind::hangup()) {
+        Ok(hangups) => hangups,
+        Err(e) => {
+            tracing::warn!(error = %e, "Failed to listen for SIGHUP; log level is fixed");
+            return;
+        }
+    };
+    tokio::spawn(async move {
+        while hangups.recv().await.is_some() {
+            match reload_level_file() {
+                Ok(filter) => tracing::info!(%filter, "Reloaded log filter"),
+                Err(e) => tracing::warn!(error = %e, "Kept log filter"),
+            }
+        }
+    });
+}
+
+/// The global registry under a reloadable filter
+type Filtered = Layered<reload::Layer<EnvFilter, Registry>, Registry>;
+
+/// Make `layer`, under a reloadable `filter`, the global subscriber
+pub(crate) fn try_init<L>(filter: EnvFilter, layer: L) -> Result<(), TryInitError>
+where
+    L: Layer<Filtered> + Send + Sync + 'static,
+{
+    let (filter_layer, filter) = ReloadableFilter::new(filter);
+    tracing_subscriber::registry()
+        .with(filter_layer)
+        .with(layer)
+        .try_init()?;
+    let _ = FILTER.set(filter);
+    Ok(())
+}
+
+/// A filter that can be swapped, and the one it started as
+struct ReloadableFilter {
+    handle: reload::Handle<EnvFilter, Registry>,
+    initial: String,
+}
+
+impl ReloadableFilter {
+    fn new(filter: EnvFilter) -> (reload::Layer<EnvFilter, Registry>, Self) {
+        let initial = filter.to_string();
+        let (layer, handle) = reload::Layer::new(filter);
+        (layer, Self { handle, initial })
+    }
+
+    fn set(&self, directive: &str) -> Result<(), LevelError> {
+        let filter = parse_filter(directive)?;
+        self.handle
+            .reload(filter)
+            .map_err(|_| LevelError::NotInitialized)
+    }
+
+    fn current(&self) -> Option<String> {
+        self.handle.with_current(|filter| filter.to_string()).ok()
+    }
+
+    /// Go back to the filter logging started with
+    fn restore(&self) -> Result<String, LevelError> {
+        self.set(&self.initial)?;
+        Ok(self.initial.clone())
+    }
+
+    fn reload_from(&self, path: &Path) -> Result<String, LevelError> {
+        let directive = match std::fs::read_to_string(path) {
+            Ok(contents) if !contents.trim().is_empty() => contents.trim().to_string(),
+            Ok(_) => return self.restore(),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return self.restore(),
+            Err(source) => {
+                return Err(LevelError::Read {
+                    path: path.to_path_buf(),
+                    source,
+                })
+            }
+        };
+        self.set(&directive)?;
+        Ok(directive)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+    use tracing::Level;
+
+    /// Counts events that got past the filter
+    #[derive(Clone, Default)]
+    struct Seen(Arc<Mutex<Vec<Level>>>);
+
+    impl<S: tracing::Subscriber> Layer<S> for Seen {
+        fn on_event(
+            &self,
+            event: &tracing::Event<'_>,
+            _ctx: tracing_subscriber::layer::Context<'_, S>,
+        ) {
+            self.0.lock().unwrap().push(*event.metadata().level());
+        }
+    }
+
+    impl Seen {
+        fn take(&self) -> Vec<Level> {
+            std::mem::take(&mut *self.0.lock().unwrap())
+        }
+    }
+
+    /// Run `f` under a reloadable filter starting at `initial`
+    fn with_filter(initial: &str, f: impl FnOnce(&ReloadableFilter, &Seen)) {
+        let (layer, filter) = ReloadableFilter::new(parse_filter(initial).unwrap());
+        let seen = Seen::default();
+        let subscriber = tracing_subscriber::registry()
+            .with(layer)
+            .with(seen.clone());
+        tracing::subscriber::with_default(subscriber, || f(&filter, &seen));
+    }
+
+    fn log_each_level() {
+        tracing::debug!("debug");
+        tracing::info!("info");
+        tracing::warn!("warn");
+    }
+
+    #[test]
+    fn test_set_changes_what_is_logged() {
+        with_filter("info", |filter, seen| {
+            log_each_level();
+            assert_eq!(seen.take(), [Level::INFO, Level::WARN]);
+
+            filter.set("debug").unwrap();
+            log_each_level();
+            assert_eq!(seen.take(), [Level::DEBUG, Level::INFO, Level::WARN]);
+            assert_eq!(filter.current().as_deref(), Some("debug"));
+
+            filter.set(" warn ").unwrap();
+            log_each_level();
+            assert_eq!(seen.take(), [Level::WARN]);
+        });
+    }
+
+    #[test]
+    fn test_invalid_directives_keep_the_current_filter() {
+        with_filter("info", |filter, seen| {
+            for directive in ["", "  ", "coven_agent=loud", "[unclosed"] {
+                let err = filter.set(directive).unwrap_err();
+                assert!(
+                    matches!(err, LevelError::Invalid { .. }),
+                    "{directive:?}: {err}"
+                );
+            }
+            log_each_level();
+            assert_eq!(seen.take(), [Level::INFO, Level::WARN]);
+            assert_eq!(filter.current().as_deref(), Some("info"));
+        });
+    }
+
+    #[test]
+    fn test_level_file_sets_and_restores_the_filter() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("log-level");
+        with_filter("info", |filter, seen| {
+            std::fs::write(&path, "debug\n").unwrap();
+            assert_eq!(filter.reload_from(&path).unwrap(), "debug");
+            log_each_level();
+            assert_eq!(seen.take().len(), 3);
+
+            // A bad file is reported and changes nothing
+            std::fs::write(&path, "coven=loud").unwrap();
+            assert!(filter.reload_from(&path).is_err());
+            assert_eq!(filter.current().as_deref(), Some("debug"));
+
+            // Emptying or removing the file goes back to the startup filter
+            std::fs::write(&path, "").unwrap();
+            assert_eq!(filter.reload_from(&path).unwrap(), "info");
+            filter.set("trace").unwrap();
+            std::fs::remove_file(&path).unwrap();
+            assert_eq!(filter.reload_from(&path).unwrap(), "info");
+            log_each_level();
+            assert_eq!(seen.take(), [Level::INFO, Level::WARN]);
+        });
+    }
+
+    #[test]
+    fn test_parse_filter_accepts_rust_log_syntax() {
+        for directive in [
+            "debug",
+            "coven_agent=trace,warn",
+            "coven_serve[grpc.request]=debug",
+        ] {
+            assert!(parse_filter(directive).is_ok(), "{directive}");
+        }
+    }
+}
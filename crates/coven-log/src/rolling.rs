@@ -0,0 +1,496 @@
+// ABOUTME: Size- and age-based rotation for the log files init_file() writes
+// ABOUTME: Keeps a few numbered archives (optionally gzipped) and falls back to per-pid files when another instance holds the log
+
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+/// Environment variable with the size a log grows to before it's rotated,
+/// in bytes or with a K/M/G suffix (e.g. "10M")
+pub const MAX_SIZE_ENV: &str = "COVEN_LOG_MAX_SIZE";
+
+/// Environment variable with how many rotated logs to keep
+pub const KEEP_ENV: &str = "COVEN_LOG_KEEP";
+
+/// Environment variable that gzips rotated logs when set to 1 or true
+pub const COMPRESS_ENV: &str = "COVEN_LOG_COMPRESS";
+
+/// When a log file is rotated and what's kept of the old ones
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RotationPolicy {
+    /// Rotate once the file would grow past this many bytes
+    pub max_size: u64,
+    /// Rotate once the file is this old, even if it's small
+    pub max_age: Duration,
+    /// Rotated files to keep, as `<name>.1` (newest) to `<name>.<keep>`
+    pub keep: usize,
+    /// Gzip rotated files, as `<name>.1.gz` and so on
+    pub compress: bool,
+}
+
+impl Default for RotationPolicy {
+    fn default() -> Self {
+        Self {
+            max_size: 10 * 1024 * 1024,
+            max_age: Duration::from_secs(24 * 60 * 60),
+            keep: 5,
+            compress: false,
+        }
+    }
+}
+
+impl RotationPolicy {
+    /// The defaults, adjusted by COVEN_LOG_MAX_SIZE, COVEN_LOG_KEEP and
+    /// COVEN_LOG_COMPRESS. Values that don't parse are warned about and
+    /// ignored.
+    pub fn from_env() -> Self {
+        let mut policy = Self::default();
+        if let Ok(size) = std::env::var(MAX_SIZE_ENV) {
+            match parse_size(&size) {
+                Some(size) => policy.max_size = size,
+                None => eprintln!("Warning: ignoring {MAX_SIZE_ENV}={size}: not a size"),
+            }
+        }
+        if let Ok(keep) = std::env::var(KEEP_ENV) {
+            match keep.trim().parse() {
+                Ok(keep) => policy.keep = keep,
+                Err(_) => eprintln!("Warning: ignoring {KEEP_ENV}={keep}: not a count"),
+            }
+        }
+        if let Ok(compress) = std::env::var(COMPRESS_ENV) {
+            policy.compress = matches!(compress.trim(), "1" | "true" | "yes");
+        }
+        policy
+    }
+}
+
+/// Bytes in "10485760", "10M", "10MB" or "512k"
+fn parse_size(size: &str) -> Option<u64> {
+    let size = size.trim().to_ascii_uppercase();
+    let size = size.strip_suffix('B').unwrap_or(&size);
+    let (digits, multiplier) = match size.as_bytes().last()? {
+        b'K' => (&size[..size.len() - 1], 1024),
+        b'M' => (&size[..size.len() - 1], 1024 * 1024),
+        b'G' => (&size[..size.len() - 1], 1024 * 1024 * 1024),
+        _ => (size, 1),
+    };
+    let size: u64 = digits.trim().parse().ok()?;
+    size.checked_mul(multiplier).filter(|size| *size > 0)
+}
+
+/// A log file that rotates itself as it's written to. Only one process
+/// rotates a given log: an instance that finds the log held by another
+/// writes to `<app>.<pid>.log` instead. Those are deleted once no instance
+/// holds them and they've gone `max_age` without being written to.
+#[derive(Debug)]
+pub struct RollingFile {
+    path: PathBuf,
+    policy: RotationPolicy,
+    file: File,
+    size: u64,
+    started: SystemTime,
+    /// Held for the life of the writer so other instances keep off the log
+    _lock: File,
+}
+
+impl RollingFile {
+    /// Open `<dir>/<app_name>.log` for appending, or the per-pid log when
+    /// another process holds it
+    pub fn open(dir: &Path, app_name: &str, policy: RotationPolicy) -> io::Result<Self> {
+        fs::create_dir_all(dir)?;
+        if let Err(e) = prune_pid_logs(dir, app_name, policy.max_age, SystemTime::now()) {
+            eprintln!("Warning: failed to delete old logs: {e}");
+        }
+        let shared = dir.join(format!("{app_name}.log"));
+        let (path, lock) = match lock(&shared)? {
+            Some(lock) => (shared, lock),
+            None => {
+                let own = dir.join(format!("{app_name}.{}.log", std::process::id()));
+                let lock = lock(&own)?.ok_or_else(|| {
+                    io::Error::new(io::ErrorKind::WouldBlock, "log file is in use")
+                })?;
+                (own, lock)
+            }
+        };
+
+        let (file, size, started) = open_append(&path)?;
+        let rolling = Self {
+            path,
+            policy,
+            file,
+            size,
+            started,
+            _lock: lock,
+        };
+        rolling.prune()?;
+        Ok(rolling)
+    }
+
+    /// The file currently written to
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Rotate first if writing `incoming` more bytes at `now` would go past
+    /// the size limit, or the file is too old
+    fn rotate_if_needed(&mut self, incoming: u64, now: SystemTime) -> io::Result<()> {
+        let too_big = self.size > 0 && self.size + incoming > self.policy.max_size;
+        let too_old = now
+            .duration_since(self.started)
+            .is_ok_and(|age| age >= self.policy.max_age);
+        if too_big || (too_old && self.size > 0) {
+            self.rotate(now)?;
+        }
+        Ok(())
+    }
+
+    /// Move the current file to `<name>.1`, shifting older archives up and
+    /// dropping those past `keep`, and start a fresh file
+    fn rotate(&mut self, now: SystemTime) -> io::Result<()> {
+        self.file.flush()?;
+        let keep = self.policy.keep;
+        if keep == 0 {
+            fs::remove_file(&self.path)?;
+        } else {
+            for index in (1..keep).rev() {
+                for (from, to) in [
+                    (self.archive(index, false), self.archive(index + 1, false)),
+                    (self.archive(index, true), self.archive(index + 1, true)),
+                ] {
+                    if from.exists() {
+                        fs::rename(from, to)?;
+                    }
+                }
+            }
+            let newest = self.archive(1, false);
+            fs::rename(&self.path, &newest)?;
+            if self.policy.compress {
+                gzip(&newest, &self.archive(1, true))?;
+            }
+        }
+        self.prune()?;
+
+        let (file, size, _) = open_append(&self.path)?;
+        self.file = file;
+        self.size = size;
+        self.started = now;
+        Ok(())
+    }
+
+    /// `<name>.<index>`, or `<name>.<index>.gz`
+    fn archive(&self, index: usize, gzipped: bool) -> PathBuf {
+        let mut name = self.path.as_os_str().to_owned();
+        name.push(format!(".{index}"));
+        if gzipped {
+            name.push(".gz");
+        }
+        PathBuf::from(name)
+    }
+
+    /// Delete archives beyond `keep`, including ones left by a larger
+    /// `keep` earlier, and uncompressed twins of gzipped archives
+    fn prune(&self) -> io::Result<()> {
+        let (Some(dir), Some(name)) = (self.path.parent(), self.path.file_name()) else {
+            return Ok(());
+        };
+        let prefix = format!("{}.", name.to_string_lossy());
+        for entry in fs::read_dir(dir)? {
+            let entry = entry?;
+            let file_name = entry.file_name();
+            let Some(suffix) = file_name
+                .to_string_lossy()
+                .strip_prefix(&prefix)
+                .map(str::to_string)
+            else {
+                continue;
+            };
+            let gzipped = suffix.ends_with(".gz");
+            let Ok(index) = suffix.trim_end_matches(".gz").parse::<usize>() else {
+                continue;
+            };
+            let stale = index > self.policy.keep
+                || (!gzipped && self.policy.compress && self.archive(index, true).exists());
+            if stale {
+                fs::remove_file(entry.path())?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Write for RollingFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.rotate_if_needed(buf.len() as u64, SystemTime::now())?;
+        let written = self.file.write(buf)?;
+        self.size += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
+
+/// Delete the per-pid logs in `dir` that no instance holds and that haven't
+/// been written to within `max_age` of `now`, with their archives and locks
+fn prune_pid_logs(
+    dir: &Path,
+    app_name: &str,
+    max_age: Duration,
+    now: SystemTime,
+) -> io::Result<()> {
+    let prefix = format!("{app_name}.");
+    let names: Vec<String> = fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.file_name().to_string_lossy().into_owned())
+        .collect();
+    for name in &names {
+        let Some(pid) = name
+            .strip_prefix(&prefix)
+            .and_then(|rest| rest.strip_suffix(".log"))
+        else {
+            continue;
+        };
+        if pid.is_empty() || !pid.bytes().all(|b| b.is_ascii_digit()) {
+            continue;
+        }
+        let path = dir.join(name);
+        let idle = fs::metadata(&path)
+            .and_then(|metadata| metadata.modified())
+            .ok()
+            .and_then(|modified| now.duration_since(modified).ok())
+            .is_some_and(|age| age >= max_age);
+        // A running instance still holds its log
+        if !idle || lock(&path)?.is_none() {
+            continue;
+        }
+        let related = format!("{name}.");
+        for other in names.iter().filter(|other| other.starts_with(&related)) {
+            fs::remove_file(dir.join(other))?;
+        }
+        fs::remove_file(&path)?;
+    }
+    Ok(())
+}
+
+/// An exclusive lock on `<log>.lock`, or None if another process has it
+fn lock(log: &Path) -> io::Result<Option<File>> {
+    let mut name = log.as_os_str().to_owned();
+    name.push(".lock");
+    let file = OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(PathBuf::from(name))?;
+    match file.try_lock() {
+        Ok(()) => Ok(Some(file)),
+        Err(fs::TryLockError::WouldBlock) => Ok(None),
+        Err(fs::TryLockError::Error(e)) => Err(e),
+    }
+}
+
+/// `path` opened for appending, with its size and when it was started
+fn open_append(path: &Path) -> io::Result<(File, u64, SystemTime)> {
+    let file = OpenOptions::new().create(true).append(true).open(path)?;
+    let metadata = file.metadata()?;
+    let started = metadata
+        .created()
+        .or_else(|_| metadata.modified())
+        .unwrap_or_else(|_| SystemTime::now());
+    Ok((file, metadata.len(), started))
+}
+
+/// Compress `from` into `to` and remove `from`
+fn gzip(from: &Path, to: &Path) -> io::Result<()> {
+    let mut input = File::open(from)?;
+    let mut encoder =
+        flate2::write::GzEncoder::new(File::create(to)?, flate2::Compression::default());
+    io::copy(&mut input, &mut encoder)?;
+    encoder.finish()?.sync_all()?;
+    fs::remove_file(from)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+
+    fn policy(max_size: u64, keep: usize) -> RotationPolicy {
+        RotationPolicy {
+            max_size,
+            keep,
+            ..RotationPolicy::default()
+        }
+    }
+
+    fn names(dir: &Path) -> Vec<String> {
+        let mut names: Vec<String> = fs::read_dir(dir)
+            .unwrap()
+            .map(|e| e.unwrap().file_name().to_string_lossy().into_owned())
+            .filter(|name| !name.ends_with(".lock"))
+            .collect();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn test_parse_size() {
+        assert_eq!(parse_size("1048576"), Some(1024 * 1024));
+        assert_eq!(parse_size("10M"), Some(10 * 1024 * 1024));
+        assert_eq!(parse_size("10mb"), Some(10 * 1024 * 1024));
+        assert_eq!(parse_size(" 512K "), Some(512 * 1024));
+        assert_eq!(parse_size("1G"), Some(1024 * 1024 * 1024));
+        assert_eq!(parse_size("0"), None);
+        assert_eq!(parse_size("lots"), None);
+        assert_eq!(parse_size(""), None);
+    }
+
+    #[test]
+    fn test_rotates_when_the_next_line_would_not_fit() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut log = RollingFile::open(dir.path(), "app", policy(20, 3)).unwrap();
+
+        log.write_all(b"first line\n").unwrap();
+        log.write_all(b"second\n").unwrap();
+        assert_eq!(names(dir.path()), ["app.log"]);
+
+        // 18 bytes so far; another 11 would make 29
+        log.write_all(b"third line\n").unwrap();
+        assert_eq!(names(dir.path()), ["app.log", "app.log.1"]);
+        assert_eq!(
+            fs::read_to_string(dir.path().join("app.log.1")).unwrap(),
+            "first line\nsecond\n"
+        );
+        assert_eq!(
+            fs::read_to_string(dir.path().join("app.log")).unwrap(),
+            "third line\n"
+        );
+
+        // A line bigger than the limit still goes into a file of its own
+        log.write_all(b"a line far longer than twenty bytes\n")
+            .unwrap();
+        log.write_all(b"x\n").unwrap();
+        assert_eq!(
+            fs::read_to_string(dir.path().join("app.log.1")).unwrap(),
+            "a line far longer than twenty bytes\n"
+        );
+        assert_eq!(
+            fs::read_to_string(dir.path().join("app.log.2")).unwrap(),
+            "third line\n"
+        );
+    }
+
+    #[test]
+    fn test_rotates_old_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut log = RollingFile::open(dir.path(), "app", policy(1024, 3)).unwrap();
+        log.write_all(b"yesterday\n").unwrap();
+
+        let tomorrow = log.started + Duration::from_secs(24 * 60 * 60);
+        log.rotate_if_needed(6, tomorrow).unwrap();
+        assert_eq!(names(dir.path()), ["app.log", "app.log.1"]);
+
+        // The fresh file is timed from the rotation
+        log.write_all(b"today\n").unwrap();
+        log.rotate_if_needed(6, tomorrow + Duration::from_secs(60))
+            .unwrap();
+        assert_eq!(names(dir.path()), ["app.log", "app.log.1"]);
+    }
+
+    #[test]
+    fn test_keeps_only_the_newest_archives() {
+        let dir = tempfile::tempdir().unwrap();
+        // Left over from when more were kept
+        fs::write(dir.path().join("app.log.7"), "ancient").unwrap();
+        fs::write(dir.path().join("app.log.notes"), "not an archive").unwrap();
+
+        let mut log = RollingFile::open(dir.path(), "app", policy(4, 2)).unwrap();
+        assert!(!dir.path().join("app.log.7").exists());
+        for line in ["one\n", "two\n", "three\n", "four\n"] {
+            log.write_all(line.as_bytes()).unwrap();
+        }
+
+        assert_eq!(
+            names(dir.path()),
+            ["app.log", "app.log.1", "app.log.2", "app.log.notes"]
+        );
+        assert_eq!(
+            fs::read_to_string(dir.path().join("app.log.1")).unwrap(),
+            "three\n"
+        );
+        assert_eq!(
+            fs::read_to_string(dir.path().join("app.log.2")).unwrap(),
+            "two\n"
+        );
+    }
+
+    #[test]
+    fn test_gzips_archives() {
+        let dir = tempfile::tempdir().unwrap();
+        let policy = RotationPolicy {
+            compress: true,
+            ..policy(4, 2)
+        };
+        let mut log = RollingFile::open(dir.path(), "app", policy).unwrap();
+        for line in ["one\n", "two\n", "three\n"] {
+            log.write_all(line.as_bytes()).unwrap();
+        }
+
+        assert_eq!(
+            names(dir.path()),
+            ["app.log", "app.log.1.gz", "app.log.2.gz"]
+        );
+        let mut text = String::new();
+        flate2::read::GzDecoder::new(File::open(dir.path().join("app.log.1.gz")).unwrap())
+            .read_to_string(&mut text)
+            .unwrap();
+        assert_eq!(text, "two\n");
+    }
+
+    #[test]
+    fn test_second_instance_gets_its_own_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let first = RollingFile::open(dir.path(), "app", policy(1024, 1)).unwrap();
+        let second = RollingFile::open(dir.path(), "app", policy(1024, 1)).unwrap();
+        assert_eq!(first.path(), dir.path().join("app.log"));
+        assert_eq!(
+            second.path(),
+            dir.path().join(format!("app.{}.log", std::process::id()))
+        );
+
+        // Once the first lets go, the shared log is free again
+        drop(first);
+        let third = RollingFile::open(dir.path(), "app", policy(1024, 1)).unwrap();
+        assert_eq!(third.path(), dir.path().join("app.log"));
+    }
+
+    #[test]
+    fn test_prunes_idle_pid_logs() {
+        let dir = tempfile::tempdir().unwrap();
+        let old = SystemTime::now() - Duration::from_secs(2 * 24 * 60 * 60);
+        let write_old = |name: &str| {
+            let file = File::create(dir.path().join(name)).unwrap();
+            file.set_modified(old).unwrap();
+        };
+        // Left by an instance that has exited, with an archive
+        write_old("app.123.log");
+        write_old("app.123.log.1");
+        File::create(dir.path().join("app.123.log.lock")).unwrap();
+        // Still held by a running instance
+        write_old("app.456.log");
+        let held = lock(&dir.path().join("app.456.log")).unwrap().unwrap();
+        // Written to recently
+        fs::write(dir.path().join("app.789.log"), "recent").unwrap();
+        // Not a per-pid log
+        write_old("app.old.log");
+
+        let _log = RollingFile::open(dir.path(), "app", policy(1024, 1)).unwrap();
+        assert_eq!(
+            names(dir.path()),
+            ["app.456.log", "app.789.log", "app.log", "app.old.log"]
+        );
+        assert!(!dir.path().join("app.123.log.lock").exists());
+        drop(held);
+    }
+}
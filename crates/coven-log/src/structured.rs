@@ -0,0 +1,214 @@
+// ABOUTME: JSON structured logging for collectors that parse logs rather than read them
+// ABOUTME: init_structured() logs one JSON object per line; COVEN_LOG_FORMAT=json switches the other init functions over
+
+use std::fmt;
+use tracing::{Event, Level, Subscriber};
+use tracing_subscriber::fmt::format::{self, FormatEvent, FormatFields, JsonFields, Writer};
+use tracing_subscriber::fmt::FmtContext;
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::{EnvFilter, Layer};
+
+use crate::level_filter;
+
+/// Environment variable choosing the log format: `json` for JSON lines,
+/// anything else (or unset) for the usual text
+pub const FORMAT_ENV: &str = "COVEN_LOG_FORMAT";
+
+/// Whether COVEN_LOG_FORMAT asks for JSON logs
+pub fn json_requested() -> bool {
+    std::env::var(FORMAT_ENV).is_ok_and(|format| format.trim().eq_ignore_ascii_case("json"))
+}
+
+/// JSON logging to stderr at INFO, RUST_LOG override. Each line is an
+/// object with `timestamp`, `level`, `target`, `service`, the event's
+/// `fields`, and the `span`s it happened in, outermost first.
+pub fn init_structured(service_name: &str) {
+    init_structured_with_level(service_name, Level::INFO);
+}
+
+/// Like `init_structured`, at `level`.
+pub fn init_structured_with_level(service_name: &str, level: Level) {
+    try_init_json(service_name, level_filter(level));
+}
+
+pub(crate) fn try_init_json(service_name: &str, filter: EnvFilter) {
+    let _ = crate::reload::try_init(filter, json_layer(service_name, std::io::stderr));
+}
+
+/// A formatting layer writing JSON lines tagged with `service_name`
+pub(crate) fn json_layer<S, W>(service_name: &str, writer: W) -> impl Layer<S>
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+    W: for<'w> tracing_subscriber::fmt::MakeWriter<'w> + Send + Sync + 'static,
+{
+    tracing_subscriber::fmt::layer()
+        .fmt_fields(JsonFields::new())
+        .event_format(WithService::new(service_name))
+        .with_writer(writer)
+}
+
+/// The JSON event format with a `service` key up front
+struct WithService {
+    /// `service_name` as a JSON string, quotes and all
+    service: String,
+    inner: format::Format<format::Json>,
+}
+
+impl WithService {
+    fn new(service_name: &str) -> Self {
+        Self {
+            service: serde_json::Value::from(service_name).to_string(),
+            inner: format::format().json().with_span_list(true),
+        }
+    }
+}
+
+impl<S, N> FormatEvent<S, N> for WithService
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+    N: for<'a> FormatFields<'a> + 'static,
+{
+    fn format_event(
+        &self,
+        ctx: &FmtContext<'_, S, N>,
+        mut writer: Writer<'_>,
+        event: &Event<'_>,
+    ) -> fmt::Result {
+        let mut line = String::new();
+        self.inner
+            .format_event(ctx, Writer::new(&mut line), event)?;
+        match line.strip_prefix('{') {
+            Some(rest) => write!(writer, "{{\"service\":{},{}", self.service, rest),
+            None => writer.write_str(&line),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io;
+    use std::sync::{Arc, Mutex};
+    use tracing_subscriber::prelude::*;
+
+    /// Collects everything written to it, for reading back as JSON lines
+    #[derive(Clone, Default)]
+    struct Captured(Arc<Mutex<Vec<u8>>>);
+
+    impl io::Write for Captured {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl Captured {
+        fn lines(&self) -> Vec<serde_json::Value> {
+            String::from_utf8(self.0.lock().unwrap().clone())
+                .unwrap()
+                .lines()
+                .map(|line| serde_json::from_str(line).unwrap())
+                .collect()
+        }
+    }
+
+    fn capture(service_name: &str, f: impl FnOnce()) -> Vec<serde_json::Value> {
+        let captured = Captured::default();
+        let writer = captured.clone();
+        let subscriber =
+            tracing_subscriber::registry().with(json_layer(service_name, move || writer.clone()));
+        tracing::subscriber::with_default(subscriber, f);
+        captured.lines()
+    }
+
+    #[test]
+    fn json_lines_have_the_documented_shape() {
+        let lines = capture("coven-\"serve\"", || {
+            tracing::warn!(agent_id = "a-1", attempts = 3, "Agent reconnected");
+        });
+
+        let [line] = lines.as_slice() else {
+            panic!("expected one line, got {lines:?}");
+        };
+        assert_eq!(line["service"], "coven-\"serve\"");
+        assert_eq!(line["level"], "WARN");
+        assert_eq!(line["target"], module_path!());
+        assert!(line["timestamp"].is_string());
+        assert_eq!(line["fields"]["message"], "Agent reconnected");
+        assert_eq!(line["fields"]["agent_id"], "a-1");
+        assert_eq!(line["fields"]["attempts"], 3);
+    }
+
+    /// Records each new span's name and its parent's name
+    #[derive(Clone, Default)]
+    struct Parents(Arc<Mutex<Vec<(String, Option<String>)>>>);
+
+    impl<S> Layer<S> for Parents
+    where
+        S: Subscriber + for<'a> LookupSpan<'a>,
+    {
+        fn on_new_span(
+            &self,
+            _attrs: &tracing::span::Attributes<'_>,
+            id: &tracing::span::Id,
+            ctx: tracing_subscriber::layer::Context<'_, S>,
+        ) {
+            let span = ctx.span(id).unwrap();
+            let parent = span.parent().map(|parent| parent.name().to_string());
+            self.0
+                .lock()
+                .unwrap()
+                .push((span.name().to_string(), parent));
+        }
+    }
+
+    /// The server side of a call: handles it in its own span
+    fn handle(request_id: &str) -> &'static str {
+        let _span = tracing::info_span!("grpc.request", request_id).entered();
+        tracing::info!("handling");
+        "ok"
+    }
+
+    /// The client side of a call: makes it in its own span, with both ends
+    /// in one process
+    fn call(method: &str) -> &'static str {
+        let _span = tracing::info_span!("grpc.client", rpc.method = method).entered();
+        tracing::info!("sending");
+        handle("req-1")
+    }
+
+    #[test]
+    fn spans_nest_across_a_call() {
+        let captured = Captured::default();
+        let writer = captured.clone();
+        let parents = Parents::default();
+        let subscriber = tracing_subscriber::registry()
+            .with(json_layer("test", move || writer.clone()))
+            .with(parents.clone());
+        let reply = tracing::subscriber::with_default(subscriber, || call("SendMessage"));
+        assert_eq!(reply, "ok");
+
+        assert_eq!(
+            *parents.0.lock().unwrap(),
+            [
+                ("grpc.client".to_string(), None),
+                ("grpc.request".to_string(), Some("grpc.client".to_string())),
+            ]
+        );
+
+        let lines = captured.lines();
+        assert_eq!(lines.len(), 2, "{lines:?}");
+        assert_eq!(lines[0]["span"]["name"], "grpc.client");
+        assert_eq!(lines[0]["spans"].as_array().unwrap().len(), 1);
+
+        let spans = lines[1]["spans"].as_array().unwrap();
+        let names: Vec<_> = spans.iter().map(|span| &span["name"]).collect();
+        assert_eq!(names, ["grpc.client", "grpc.request"]);
+        assert_eq!(spans[0]["rpc.method"], "SendMessage");
+        assert_eq!(lines[1]["span"]["request_id"], "req-1");
+    }
+}
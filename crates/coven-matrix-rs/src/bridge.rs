@@ -1,24 +1,44 @@
 // ABOUTME: Core bridge logic that ties Matrix and Gateway clients together.
-// ABOUTME: Handles message routing, room bindings, command processing, and event streaming.
+// ABOUTME: Handles message routing, room bindings, command processing, event streaming, and agent-initiated messages.
 
 use crate::commands::{execute_command, Command, CommandContext};
 use crate::config::Config;
 use crate::error::Result;
 use crate::gateway::GatewayClient;
 use crate::matrix::{extract_text_content, MatrixClient};
+use crate::typing::{InFlightRequests, TypingRefresh};
 
+use coven_bridge_core::{
+    format_initiated, open_binding_store, route_initiated, send_notice, split_message,
+    BindingStore, ChannelIdentity, IdentityCache, MessageDeduplicator, OrderedDispatcher,
+    RequestOverrides, ResponseAccumulator, SenderIdentity, StoredBinding, DEFAULT_MAX_CONCURRENT,
+    IDENTITY_CACHE_TTL, INITIATED_RESUBSCRIBE_DELAY,
+};
 use coven_proto::client_stream_event::Payload;
+use coven_proto::AgentInitiatedEvent;
 use futures::StreamExt;
 use matrix_sdk::{
-    config::SyncSettings, ruma::events::room::message::OriginalSyncRoomMessageEvent,
-    ruma::OwnedRoomId, RoomMemberships, RoomState,
+    config::SyncSettings,
+    ruma::api::client::receipt::create_receipt::v3::ReceiptType,
+    ruma::events::receipt::ReceiptThread,
+    ruma::events::relation::Thread,
+    ruma::events::room::message::{
+        OriginalSyncRoomMessageEvent, Relation, Replacement, RoomMessageEventContent,
+    },
+    ruma::{EventId, OwnedEventId, OwnedRoomId, RoomId},
+    Client, RoomMemberships, RoomState,
 };
 use std::collections::HashMap;
 use std::sync::Arc;
+use std::time::Instant;
 use tokio::sync::RwLock;
 use tracing::{debug, error, info, warn};
 use uuid::Uuid;
 
+/// Longest message body the bridge sends in one event. Matrix events are
+/// capped at 65,535 bytes including JSON overhead, so bodies stay well below.
+const MAX_MESSAGE_LEN: usize = 32_000;
+
 /// Room binding information mapping a Matrix room to a gateway conversation.
 #[derive(Clone, Debug)]
 pub struct RoomBinding {
@@ -26,6 +46,30 @@ pub struct RoomBinding {
     pub conversation_key: String,
     /// The Matrix user who created this binding (used to scope visibility).
     pub owner: Option<String>,
+    /// Model/max_tokens settings applied to messages from this room
+    pub overrides: RequestOverrides,
+}
+
+impl RoomBinding {
+    /// Convert to the platform-neutral form used by the binding store.
+    pub fn to_stored(&self) -> StoredBinding {
+        StoredBinding {
+            target: self.room_id.to_string(),
+            conversation_key: self.conversation_key.clone(),
+            owner: self.owner.clone(),
+            overrides: self.overrides.clone(),
+        }
+    }
+
+    /// Convert from a stored binding, or None if the target isn't a room ID.
+    pub fn from_stored(stored: &StoredBinding) -> Option<Self> {
+        Some(Self {
+            room_id: OwnedRoomId::try_from(stored.target.as_str()).ok()?,
+            conversation_key: stored.conversation_key.clone(),
+            owner: stored.owner.clone(),
+            overrides: stored.overrides.clone(),
+        })
+    }
 }
 
 /// The Bridge ties together Matrix and Gateway clients to route messages.
@@ -34,6 +78,12 @@ pub struct Bridge {
     matrix: MatrixClient,
     gateway: Arc<RwLock<GatewayClient>>,
     bindings: Arc<RwLock<HashMap<OwnedRoomId, RoomBinding>>>,
+    store: Arc<dyn BindingStore>,
+    in_flight: InFlightRequests,
+    identities: Arc<IdentityCache>,
+    seen_events: Arc<MessageDeduplicator>,
+    /// Keeps messages in one room or thread in arrival order
+    dispatcher: OrderedDispatcher,
 }
 
 impl Bridge {
@@ -46,18 +96,48 @@ impl Bridge {
         let matrix = MatrixClient::login(&config.matrix).await?;
 
         // Connect to Gateway
-        let gateway =
-            GatewayClient::connect(&config.gateway.endpoint_uri(), config.gateway.token.clone())
-                .await?;
+        let gateway = GatewayClient::connect(
+            &config.gateway.endpoint_uri(),
+            config.gateway.token.clone(),
+            config.gateway.keep_alive()?,
+        )
+        .await?;
 
         // Do an initial sync to populate room list
         matrix.sync_once().await?;
 
+        // Restore bindings from the previous run
+        let store = open_binding_store(config.bindings_path().as_deref()).await?;
+        let mut bindings = HashMap::new();
+        for stored in store.load().await? {
+            match RoomBinding::from_stored(&stored) {
+                Some(binding) => {
+                    bindings.insert(binding.room_id.clone(), binding);
+                }
+                None => {
+                    warn!(binding_target = %stored.target, "Ignoring stored binding with invalid room ID")
+                }
+            }
+        }
+        info!(count = bindings.len(), "Loaded room bindings");
+
+        let dispatcher = OrderedDispatcher::new(
+            config
+                .bridge
+                .max_concurrent_messages
+                .unwrap_or(DEFAULT_MAX_CONCURRENT),
+        );
+
         Ok(Self {
             config,
             matrix,
             gateway: Arc::new(RwLock::new(gateway)),
-            bindings: Arc::new(RwLock::new(HashMap::new())),
+            bindings: Arc::new(RwLock::new(bindings)),
+            store,
+            in_flight: InFlightRequests::new(),
+            identities: Arc::new(IdentityCache::new("matrix", IDENTITY_CACHE_TTL)),
+            seen_events: Arc::new(MessageDeduplicator::default()),
+            dispatcher,
         })
     }
 
@@ -72,6 +152,7 @@ impl Bridge {
             room_id: room_id.clone(),
             conversation_key,
             owner,
+            overrides: RequestOverrides::default(),
         };
 
         info!(
@@ -80,12 +161,18 @@ impl Bridge {
             "Binding room to conversation"
         );
 
+        if let Err(e) = self.store.save(&binding.to_stored()).await {
+            warn!(error = %e, room_id = %room_id, "Failed to persist binding");
+        }
         self.bindings.write().await.insert(room_id, binding);
     }
 
     /// Unbind a Matrix room from any gateway conversation.
     pub async fn unbind_room(&self, room_id: &OwnedRoomId) -> Option<RoomBinding> {
         let binding = self.bindings.write().await.remove(room_id);
+        if let Err(e) = self.store.remove(room_id.as_str()).await {
+            warn!(error = %e, room_id = %room_id, "Failed to remove persisted binding");
+        }
         if let Some(ref b) = binding {
             info!(
                 room_id = %room_id,
@@ -108,18 +195,29 @@ impl Bridge {
         let client = self.matrix.client().clone();
         let user_id = self.matrix.user_id().clone();
         let bindings = Arc::clone(&self.bindings);
+        let store = Arc::clone(&self.store);
         let gateway = Arc::clone(&self.gateway);
+        let in_flight = self.in_flight.clone();
+        let identities = Arc::clone(&self.identities);
+        let seen_events = Arc::clone(&self.seen_events);
+        let dispatcher = self.dispatcher.clone();
         let config = self.config.clone();
 
-        // Set up the event handler for room messages
+        // Set up the event handler for room messages. Each message is handled
+        // in the background, after earlier ones in the same room or thread.
         client.add_event_handler(
             move |event: OriginalSyncRoomMessageEvent, room: matrix_sdk::Room| {
                 let bindings = Arc::clone(&bindings);
+                let store = Arc::clone(&store);
                 let gateway = Arc::clone(&gateway);
+                let in_flight = in_flight.clone();
+                let identities = Arc::clone(&identities);
+                let seen_events = Arc::clone(&seen_events);
                 let config = config.clone();
                 let user_id = user_id.clone();
 
-                async move {
+                let key = ordering_key(room.room_id(), &event.content);
+                dispatcher.submit(key, async move {
                     // Only process messages from joined rooms
                     if room.state() != RoomState::Joined {
                         return;
@@ -132,8 +230,15 @@ impl Bridge {
                         return;
                     }
 
+                    // Sync can replay events we've already seen (e.g. after a
+                    // limited timeline or a sync token reset)
+                    if seen_events.is_duplicate(event.event_id.as_str()) {
+                        debug!(event_id = %event.event_id, "Dropping duplicate event");
+                        return;
+                    }
+
                     // Extract text content
-                    let Some(text) = extract_text_content(&event) else {
+                    let Some(text) = extract_text_content(&event, config.bridge.ignore_bots) else {
                         debug!(room_id = %room_id, "Non-text message, ignoring");
                         return;
                     };
@@ -166,7 +271,9 @@ impl Bridge {
                     }
 
                     // Check if sender is allowed
-                    if !config.is_sender_allowed(event.sender.as_str()) {
+                    if !config.is_sender_allowed(event.sender.as_str())
+                        || config.is_sender_ignored(event.sender.as_str())
+                    {
                         debug!(sender = %event.sender, "Message from non-allowed sender, ignoring");
                         return;
                     }
@@ -228,7 +335,11 @@ impl Bridge {
                                             room_id: new_room_id.clone(),
                                             conversation_key: agent_id.clone(),
                                             owner: Some(event.sender.to_string()),
+                                            overrides: RequestOverrides::default(),
                                         };
+                                        if let Err(e) = store.save(&binding.to_stored()).await {
+                                            warn!(error = %e, room_id = %new_room_id, "Failed to persist binding");
+                                        }
                                         bindings.write().await.insert(new_room_id.clone(), binding);
 
                                         // Send success message in DM
@@ -294,6 +405,7 @@ impl Bridge {
                         let ctx = CommandContext {
                             gateway: &gateway,
                             bindings: &bindings,
+                            store: store.as_ref(),
                             room_id: &room_id,
                             sender: event.sender.as_str(),
                         };
@@ -352,13 +464,30 @@ impl Bridge {
                         "Message content preview"
                     );
 
+                    // Tell the agent who in the room is asking
+                    let sender = identities
+                        .resolve(event.sender.as_str(), || async {
+                            match room.get_member_no_sync(&event.sender).await {
+                                Ok(member) => member
+                                    .and_then(|m| m.display_name().map(String::from)),
+                                Err(e) => {
+                                    warn!(error = %e, sender = %event.sender, "Failed to look up room member");
+                                    None
+                                }
+                            }
+                        })
+                        .await;
+
                     // Process the message
                     if let Err(e) = process_message(
                         &room,
                         &binding,
                         &text,
+                        &sender,
+                        &event.event_id,
                         &gateway,
-                        config.bridge.typing_indicator,
+                        &in_flight,
+                        &config,
                     )
                     .await
                     {
@@ -386,10 +515,19 @@ impl Bridge {
                             error!(error = %send_err, "Failed to send error message to room");
                         }
                     }
-                }
+                });
+                async {}
             },
         );
 
+        // Relay messages agents send without being asked
+        tokio::spawn(run_initiated_listener(
+            client.clone(),
+            Arc::clone(&self.gateway),
+            Arc::clone(&self.bindings),
+            self.config.bridge.notifications_room.clone(),
+        ));
+
         // Start the sync loop
         let settings = SyncSettings::default().timeout(std::time::Duration::from_secs(30));
         client.sync(settings).await?;
@@ -402,6 +540,11 @@ impl Bridge {
         &self.matrix
     }
 
+    /// Get a reference to the binding store.
+    pub fn binding_store(&self) -> &Arc<dyn BindingStore> {
+        &self.store
+    }
+
     /// Get a reference to the Gateway client (locked).
     pub fn gateway_client(&self) -> &Arc<RwLock<GatewayClient>> {
         &self.gateway
@@ -409,43 +552,84 @@ impl Bridge {
 }
 
 /// Process a message from Matrix by sending to gateway and streaming response back.
+///
+/// While the request is in flight the room shows a typing notice, refreshed on
+/// every streamed event and on a timer so it doesn't lapse during long tool
+/// calls. The user's message gets a read receipt once the gateway accepts it.
 async fn process_message(
     room: &matrix_sdk::Room,
     binding: &RoomBinding,
     text: &str,
+    sender: &SenderIdentity,
+    event_id: &EventId,
     gateway: &Arc<RwLock<GatewayClient>>,
-    typing_indicator: bool,
+    in_flight: &InFlightRequests,
+    config: &Config,
 ) -> Result<()> {
-    let idempotency_key = Uuid::new_v4().to_string();
+    let room_id = room.room_id().to_owned();
+    let typing_enabled = config.typing_enabled();
+    let mut typing = TypingRefresh::default();
 
-    // Set typing indicator
-    if typing_indicator && room.state() == RoomState::Joined {
-        if let Err(e) = room.typing_notice(true).await {
-            warn!(error = %e, "Failed to set typing indicator");
+    in_flight.begin(&room_id).await;
+    if typing_enabled {
+        refresh_typing(room, &mut typing).await;
+    }
+
+    let result = stream_response(
+        room,
+        binding,
+        text,
+        sender,
+        event_id,
+        gateway,
+        config,
+        typing_enabled.then_some(&mut typing),
+    )
+    .await;
+
+    // Only clear the typing notice once no other request for this room is
+    // still streaming, otherwise we'd hide activity that is ongoing.
+    let room_idle = in_flight.finish(&room_id).await;
+    if typing_enabled && room_idle && room.state() == RoomState::Joined {
+        if let Err(e) = room.typing_notice(false).await {
+            warn!(error = %e, "Failed to clear typing indicator");
         }
     }
 
+    result
+}
+
+/// Send the message to the gateway and relay the streamed response to the room.
+async fn stream_response(
+    room: &matrix_sdk::Room,
+    binding: &RoomBinding,
+    text: &str,
+    sender: &SenderIdentity,
+    event_id: &EventId,
+    gateway: &Arc<RwLock<GatewayClient>>,
+    config: &Config,
+    mut typing: Option<&mut TypingRefresh>,
+) -> Result<()> {
+    let idempotency_key = Uuid::new_v4().to_string();
+    let mut channel = ChannelIdentity::new("matrix", room.room_id().as_str());
+    if let Some(name) = room.name() {
+        channel = channel.with_display_name(name);
+    }
+
     // Send message to gateway
-    let send_result = {
+    let response = {
         let mut gateway = gateway.write().await;
         gateway
             .send_message(
                 binding.conversation_key.clone(),
                 text.to_string(),
                 idempotency_key,
+                Some(sender),
+                Some(&channel),
+                None,
+                &binding.overrides,
             )
-            .await
-    };
-
-    let response = match send_result {
-        Ok(r) => r,
-        Err(e) => {
-            // Clear typing indicator on error
-            if typing_indicator && room.state() == RoomState::Joined {
-                let _ = room.typing_notice(false).await;
-            }
-            return Err(e);
-        }
+            .await?
     };
 
     debug!(
@@ -454,33 +638,66 @@ async fn process_message(
         "Message sent to gateway"
     );
 
+    // The agent is offline and the gateway will deliver the message later,
+    // or the gateway's content policy blocked it
+    if let Some(notice) = send_notice(&response) {
+        send_response_to_room(room, None, notice).await?;
+        return Ok(());
+    }
+
+    // The gateway has accepted the message, so mark it as read
+    if config.read_receipts_enabled() && room.state() == RoomState::Joined {
+        if let Err(e) = room
+            .send_single_receipt(
+                ReceiptType::Read,
+                ReceiptThread::Unthreaded,
+                event_id.to_owned(),
+            )
+            .await
+        {
+            warn!(error = %e, "Failed to send read receipt");
+        }
+    }
+
     // Stream events from gateway
-    let stream_result = {
+    let mut stream = {
         let mut gateway = gateway.write().await;
         gateway
             .stream_events(binding.conversation_key.clone())
-            .await
+            .await?
     };
 
-    let mut stream = match stream_result {
-        Ok(s) => s,
-        Err(e) => {
-            // Clear typing indicator on error
-            if typing_indicator && room.state() == RoomState::Joined {
-                let _ = room.typing_notice(false).await;
+    // Accumulate text chunks; with a stream interval configured, the
+    // partial response is sent once and then edited as it grows
+    let mut response = ResponseAccumulator::from_interval_ms(config.bridge.stream_interval_ms);
+    let mut live_event: Option<OwnedEventId> = None;
+    let mut has_sent_message = false;
+
+    loop {
+        // Wake up for the next typing refresh even if the stream is quiet
+        let next_event = match typing.as_deref() {
+            Some(refresh) => {
+                let deadline = refresh.next_deadline(Instant::now());
+                tokio::select! {
+                    event = stream.next() => Some(event),
+                    _ = tokio::time::sleep_until(deadline.into()) => None,
+                }
             }
-            return Err(e);
-        }
-    };
+            None => Some(stream.next().await),
+        };
 
-    // Accumulate text chunks for final message
-    let mut accumulated_text = String::new();
-    let mut has_sent_message = false;
+        if let Some(refresh) = typing.as_deref_mut() {
+            if refresh.is_due(Instant::now()) {
+                refresh_typing(room, refresh).await;
+            }
+        }
 
-    while let Some(event_result) = stream.next().await {
-        let event = match event_result {
-            Ok(e) => e,
-            Err(status) => {
+        let event = match next_event {
+            // Timer fired, nothing to process yet
+            None => continue,
+            Some(None) => break,
+            Some(Some(Ok(e))) => e,
+            Some(Some(Err(status))) => {
                 error!(error = %status, "Stream error");
                 break;
             }
@@ -489,12 +706,14 @@ async fn process_message(
         // Process the event payload
         match event.payload {
             Some(Payload::Text(chunk)) => {
-                accumulated_text.push_str(&chunk.content);
                 debug!(
                     chunk_len = chunk.content.len(),
-                    total_len = accumulated_text.len(),
+                    total_len = response.text().len() + chunk.content.len(),
                     "Received text chunk"
                 );
+                if let Some(partial) = response.push(&chunk.content, Instant::now()) {
+                    show_partial(room, &mut live_event, partial).await;
+                }
             }
             Some(Payload::Thinking(chunk)) => {
                 debug!(
@@ -525,13 +744,10 @@ async fn process_message(
             Some(Payload::Done(done)) => {
                 info!("Stream completed");
                 // Use the full response if available, otherwise use accumulated
-                let final_text = done
-                    .full_response
-                    .filter(|s| !s.is_empty())
-                    .unwrap_or_else(|| accumulated_text.clone());
+                let final_text = std::mem::take(&mut response).finish(done.full_response);
 
                 if !final_text.is_empty() && !has_sent_message {
-                    send_response_to_room(room, &final_text).await?;
+                    send_response_to_room(room, live_event.take(), &final_text).await?;
                     has_sent_message = true;
                 }
                 break;
@@ -540,7 +756,7 @@ async fn process_message(
                 error!(message = %error.message, "Stream error event");
                 if !has_sent_message {
                     let error_msg = format!("Error: {}", error.message);
-                    send_response_to_room(room, &error_msg).await?;
+                    send_response_to_room(room, None, &error_msg).await?;
                     has_sent_message = true;
                 }
                 break;
@@ -549,6 +765,10 @@ async fn process_message(
                 // Full event replay, typically for history - ignore in streaming context
                 debug!("Received full event (history replay)");
             }
+            Some(Payload::Queued(queued)) => {
+                // A message of its own, apart from the answer to come
+                send_response_to_room(room, None, &queued.notice).await?;
+            }
             Some(Payload::ToolApproval(approval)) => {
                 // Tool approval requests not supported in Matrix bridge - auto-deny
                 debug!(tool_name = %approval.tool_name, "Tool approval request (auto-denied in Matrix)");
@@ -566,29 +786,196 @@ async fn process_message(
         }
     }
 
-    // Clear typing indicator
-    if typing_indicator && room.state() == RoomState::Joined {
-        let _ = room.typing_notice(false).await;
-    }
-
     // If we accumulated text but didn't send yet (no Done event), send now
-    if !accumulated_text.is_empty() && !has_sent_message {
-        send_response_to_room(room, &accumulated_text).await?;
+    if !response.is_empty() && !has_sent_message {
+        send_response_to_room(room, live_event, response.text()).await?;
     }
 
     Ok(())
 }
 
-/// Send a response back to the Matrix room.
-async fn send_response_to_room(room: &matrix_sdk::Room, text: &str) -> Result<()> {
+/// Send (or re-send) the typing notice and record when it went out.
+async fn refresh_typing(room: &matrix_sdk::Room, refresh: &mut TypingRefresh) {
+    // Record the attempt even if it fails so a broken room can't spin the loop
+    refresh.mark_sent(Instant::now());
+    if room.state() != RoomState::Joined {
+        return;
+    }
+    if let Err(e) = room.typing_notice(true).await {
+        warn!(error = %e, "Failed to set typing indicator");
+    }
+}
+
+/// Show a partial response while it streams: send it the first time,
+/// then edit that event. Failures are logged; the final response is still
+/// delivered by `send_response_to_room`.
+async fn show_partial(room: &matrix_sdk::Room, live_event: &mut Option<OwnedEventId>, text: &str) {
+    if room.state() != RoomState::Joined {
+        return;
+    }
+    // Only the first message-sized piece is shown until the response ends
+    let Some(preview) = split_message(text, MAX_MESSAGE_LEN).into_iter().next() else {
+        return;
+    };
+    let content = match live_event {
+        Some(original) => replacement_content(original, &preview),
+        None => RoomMessageEventContent::text_plain(preview),
+    };
+    match room.send(content).await {
+        Ok(sent) => {
+            live_event.get_or_insert(sent.event_id);
+        }
+        Err(e) => warn!(error = %e, "Failed to show partial response"),
+    }
+}
+
+/// Build an `m.replace` edit of `original` with new plain-text body.
+fn replacement_content(original: &EventId, text: &str) -> RoomMessageEventContent {
+    // Clients without edit support show the fallback body
+    let mut content = RoomMessageEventContent::text_plain(format!("* {}", text));
+    content.relates_to = Some(Relation::Replacement(Replacement::new(
+        original.to_owned(),
+        RoomMessageEventContent::text_plain(text).into(),
+    )));
+    content
+}
+
+/// Deliver messages agents send on their own initiative for as long as the
+/// bridge runs, resubscribing whenever the gateway stream ends.
+async fn run_initiated_listener(
+    client: Client,
+    gateway: Arc<RwLock<GatewayClient>>,
+    bindings: Arc<RwLock<HashMap<OwnedRoomId, RoomBinding>>>,
+    notifications_room: Option<String>,
+) {
+    loop {
+        let stream_result = {
+            let mut gw = gateway.write().await;
+            gw.stream_agent_initiated().await
+        };
+
+        match stream_result {
+            Ok(mut stream) => {
+                info!("Listening for agent-initiated messages");
+                while let Some(event_result) = stream.next().await {
+                    match event_result {
+                        Ok(event) => {
+                            deliver_initiated(
+                                &client,
+                                &bindings,
+                                notifications_room.as_deref(),
+                                event,
+                            )
+                            .await
+                        }
+                        Err(status) => {
+                            warn!(error = %status, "Agent-initiated stream error");
+                            break;
+                        }
+                    }
+                }
+            }
+            Err(e) => {
+                warn!(error = %e, "Failed to subscribe to agent-initiated messages");
+            }
+        }
+
+        tokio::time::sleep(INITIATED_RESUBSCRIBE_DELAY).await;
+    }
+}
+
+/// Post an agent-initiated message to every room bound to the agent, or to
+/// the notifications room when none is.
+async fn deliver_initiated(
+    client: &Client,
+    bindings: &RwLock<HashMap<OwnedRoomId, RoomBinding>>,
+    notifications_room: Option<&str>,
+    event: AgentInitiatedEvent,
+) {
+    let deliveries = {
+        let bindings = bindings.read().await;
+        route_initiated(
+            &event,
+            bindings
+                .values()
+                .map(|b| (b.room_id.as_str(), b.conversation_key.as_str())),
+            notifications_room,
+        )
+    };
+
+    if deliveries.is_empty() {
+        info!(
+            agent_id = %event.agent_id,
+            message_id = %event.message_id,
+            "Dropping agent-initiated message: agent has no bound room and no notifications room is configured"
+        );
+        return;
+    }
+
+    let text = format_initiated(&event);
+    for delivery in deliveries {
+        let room = OwnedRoomId::try_from(delivery.target.as_str())
+            .ok()
+            .and_then(|room_id| client.get_room(&room_id));
+        let Some(room) = room.filter(|r| r.state() == RoomState::Joined) else {
+            warn!(room_id = %delivery.target, "Cannot deliver agent-initiated message to unknown or non-joined room");
+            continue;
+        };
+
+        for piece in split_message(&text, MAX_MESSAGE_LEN) {
+            let content = initiated_content(&piece, delivery.thread_id.as_deref());
+            if let Err(e) = room.send(content).await {
+                warn!(
+                    error = %e,
+                    room_id = %delivery.target,
+                    "Failed to deliver agent-initiated message"
+                );
+                break;
+            }
+        }
+    }
+}
+
+/// Key for handling messages in order: the thread for threaded messages,
+/// else the room.
+fn ordering_key(room_id: &RoomId, content: &RoomMessageEventContent) -> String {
+    match &content.relates_to {
+        Some(Relation::Thread(thread)) => format!("{}:{}", room_id, thread.event_id),
+        _ => room_id.to_string(),
+    }
+}
+
+/// Message content for an agent-initiated message, posted in the thread
+/// rooted at `thread_root` when that is a valid event ID.
+fn initiated_content(text: &str, thread_root: Option<&str>) -> RoomMessageEventContent {
+    let mut content = RoomMessageEventContent::text_plain(text);
+    if let Some(root) = thread_root.and_then(|id| OwnedEventId::try_from(id).ok()) {
+        content.relates_to = Some(Relation::Thread(Thread::plain(root.clone(), root)));
+    }
+    content
+}
+
+/// Send a response back to the Matrix room, split into messages that fit
+/// the event size limit. If a partial response is showing, it is edited to
+/// hold the first piece.
+async fn send_response_to_room(
+    room: &matrix_sdk::Room,
+    live_event: Option<OwnedEventId>,
+    text: &str,
+) -> Result<()> {
     if room.state() != RoomState::Joined {
         warn!(room_id = %room.room_id(), "Cannot send to non-joined room");
         return Ok(());
     }
 
-    let content =
-        matrix_sdk::ruma::events::room::message::RoomMessageEventContent::text_plain(text);
-    room.send(content).await?;
+    let mut live_event = live_event;
+    for piece in split_message(text, MAX_MESSAGE_LEN) {
+        let content = match live_event.take() {
+            Some(original) => replacement_content(&original, &piece),
+            None => RoomMessageEventContent::text_plain(piece),
+        };
+        room.send(content).await?;
+    }
 
     debug!(room_id = %room.room_id(), text_len = text.len(), "Sent response to room");
 
@@ -599,12 +986,38 @@ async fn send_response_to_room(room: &matrix_sdk::Room, text: &str) -> Result<()
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_initiated_content_threads_on_event_id() {
+        let content = initiated_content("hello", Some("$root:example.org"));
+        assert!(matches!(content.relates_to, Some(Relation::Thread(_))));
+
+        let content = initiated_content("hello", Some("1700000000.000100"));
+        assert!(content.relates_to.is_none());
+
+        let content = initiated_content("hello", None);
+        assert!(content.relates_to.is_none());
+    }
+
+    #[test]
+    fn test_ordering_key_separates_threads() {
+        let room_id = <&RoomId>::try_from("!room:example.org").unwrap();
+        let threaded = initiated_content("hi", Some("$root:example.org"));
+        let plain = initiated_content("hi", None);
+
+        assert_eq!(ordering_key(room_id, &plain), "!room:example.org");
+        assert_eq!(
+            ordering_key(room_id, &threaded),
+            "!room:example.org:$root:example.org"
+        );
+    }
+
     #[test]
     fn test_room_binding_clone() {
         let binding = RoomBinding {
             room_id: OwnedRoomId::try_from("!test:example.org").unwrap(),
             conversation_key: "test-conversation".to_string(),
             owner: Some("@user:example.org".to_string()),
+            overrides: RequestOverrides::default(),
         };
 
         let cloned = binding.clone();
@@ -612,4 +1025,36 @@ mod tests {
         assert_eq!(binding.conversation_key, cloned.conversation_key);
         assert_eq!(binding.owner, cloned.owner);
     }
+
+    #[test]
+    fn test_room_binding_stored_round_trip() {
+        let binding = RoomBinding {
+            room_id: OwnedRoomId::try_from("!test:example.org").unwrap(),
+            conversation_key: "agent-1".to_string(),
+            owner: Some("@user:example.org".to_string()),
+            overrides: RequestOverrides {
+                model: Some("claude-haiku-4-5".to_string()),
+                max_tokens: None,
+            },
+        };
+
+        let stored = binding.to_stored();
+        assert_eq!(stored.target, "!test:example.org");
+        let restored = RoomBinding::from_stored(&stored).unwrap();
+        assert_eq!(restored.room_id, binding.room_id);
+        assert_eq!(restored.conversation_key, binding.conversation_key);
+        assert_eq!(restored.owner, binding.owner);
+        assert_eq!(restored.overrides, binding.overrides);
+    }
+
+    #[test]
+    fn test_room_binding_from_invalid_stored_target() {
+        let stored = StoredBinding {
+            target: "not-a-room".to_string(),
+            conversation_key: "agent-1".to_string(),
+            owner: None,
+            overrides: RequestOverrides::default(),
+        };
+        assert!(RoomBinding::from_stored(&stored).is_none());
+    }
 }
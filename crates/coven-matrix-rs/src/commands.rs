@@ -1,22 +1,24 @@
 // ABOUTME: Handles !coven commands in Matrix rooms for binding management.
-// ABOUTME: Supports bind, unbind, status, and agents commands.
+// ABOUTME: Supports bind, unbind, status, set, and agents commands.
 
 use crate::bridge::RoomBinding;
 use crate::error::Result;
 use crate::gateway::GatewayClient;
+use coven_bridge_core::{BindingStore, RequestOverrides};
 use matrix_sdk::ruma::OwnedRoomId;
 use std::collections::HashMap;
 use std::sync::Arc;
 use tokio::sync::RwLock;
-use tracing::info;
+use tracing::{info, warn};
 
 pub enum Command {
-    Bind(String), // !coven bind <agent-id>
-    Unbind,       // !coven unbind
-    Status,       // !coven status
-    Agents,       // !coven agents
-    Rooms,        // !coven rooms (DM only - list user's bound rooms)
-    Help,         // !coven help
+    Bind(String),        // !coven bind <agent-id>
+    Unbind,              // !coven unbind
+    Status,              // !coven status
+    Set(String, String), // !coven set <model|max_tokens> <value|default>
+    Agents,              // !coven agents
+    Rooms,               // !coven rooms (DM only - list user's bound rooms)
+    Help,                // !coven help
     Unknown(String),
 }
 
@@ -41,6 +43,19 @@ impl Command {
             },
             "unbind" => Some(Command::Unbind),
             "status" => Some(Command::Status),
+            "set" => {
+                let args: Vec<&str> = parts
+                    .get(1)
+                    .map(|s| s.split_whitespace().collect())
+                    .unwrap_or_default();
+                match args.as_slice() {
+                    [key, value] => Some(Command::Set(key.to_string(), value.to_string())),
+                    _ => Some(Command::Unknown(
+                        "set (requires a setting and value, e.g., !coven set max_tokens 4096)"
+                            .to_string(),
+                    )),
+                }
+            }
             "agents" => Some(Command::Agents),
             "rooms" => Some(Command::Rooms),
             "help" => Some(Command::Help),
@@ -53,6 +68,7 @@ impl Command {
 pub struct CommandContext<'a> {
     pub gateway: &'a Arc<RwLock<GatewayClient>>,
     pub bindings: &'a Arc<RwLock<HashMap<OwnedRoomId, RoomBinding>>>,
+    pub store: &'a dyn BindingStore,
     pub room_id: &'a OwnedRoomId,
     /// The Matrix user who sent the command (used to set binding ownership).
     pub sender: &'a str,
@@ -65,7 +81,11 @@ pub async fn execute_command(command: Command, ctx: CommandContext<'_>) -> Resul
                 room_id: ctx.room_id.clone(),
                 conversation_key: agent_id.clone(),
                 owner: Some(ctx.sender.to_string()),
+                overrides: RequestOverrides::default(),
             };
+            if let Err(e) = ctx.store.save(&binding.to_stored()).await {
+                warn!(error = %e, room_id = %ctx.room_id, "Failed to persist binding");
+            }
             ctx.bindings
                 .write()
                 .await
@@ -78,6 +98,9 @@ pub async fn execute_command(command: Command, ctx: CommandContext<'_>) -> Resul
         }
         Command::Unbind => {
             let removed = ctx.bindings.write().await.remove(ctx.room_id);
+            if let Err(e) = ctx.store.remove(ctx.room_id.as_str()).await {
+                warn!(error = %e, room_id = %ctx.room_id, "Failed to remove persisted binding");
+            }
             info!(room_id = %ctx.room_id, "Room unbound via command");
             match removed {
                 Some(binding) => Ok(format!(
@@ -91,8 +114,10 @@ pub async fn execute_command(command: Command, ctx: CommandContext<'_>) -> Resul
             let bindings = ctx.bindings.read().await;
             match bindings.get(ctx.room_id) {
                 Some(binding) => Ok(format!(
-                    "📊 Status: Bound to agent `{}`\nRoom ID: {}",
-                    binding.conversation_key, ctx.room_id
+                    "📊 Status: Bound to agent `{}`\nRoom ID: {}\nSettings: {}",
+                    binding.conversation_key,
+                    ctx.room_id,
+                    binding.overrides.describe()
                 )),
                 None => Ok(format!(
                     "📊 Status: No agent bound to this room.\nRoom ID: {}\nUse `!coven bind <agent-id>` to bind an agent.",
@@ -100,6 +125,29 @@ pub async fn execute_command(command: Command, ctx: CommandContext<'_>) -> Resul
                 )),
             }
         }
+        Command::Set(key, value) => {
+            let mut bindings = ctx.bindings.write().await;
+            let Some(binding) = bindings.get_mut(ctx.room_id) else {
+                return Ok(
+                    "No agent bound to this room.\nUse `!coven bind <agent-id>` first.".to_string(),
+                );
+            };
+
+            // Validate against a copy so a bad value leaves the binding untouched
+            let mut overrides = binding.overrides.clone();
+            if let Err(e) = overrides.set(&key, &value) {
+                return Ok(format!("❌ {}", e));
+            }
+            binding.overrides = overrides;
+            if let Err(e) = ctx.store.save(&binding.to_stored()).await {
+                warn!(error = %e, room_id = %ctx.room_id, "Failed to persist binding");
+            }
+            info!(room_id = %ctx.room_id, setting = %key, value = %value, "Room overrides updated via command");
+            Ok(format!(
+                "⚙️ Settings for this room: {}",
+                binding.overrides.describe()
+            ))
+        }
         Command::Agents => {
             let mut gateway = ctx.gateway.write().await;
             let agents = gateway.list_agents().await?;
@@ -135,6 +183,9 @@ In a DM with me:
 
 In a bound room:
   !coven status     - Show current binding
+  !coven set model <name|default>
+  !coven set max_tokens <n|default>
+                    - Override agent settings for this room
   !coven unbind     - Unbind this room
   !coven help       - Show this help"#
             .to_string()),
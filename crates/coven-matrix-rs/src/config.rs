@@ -2,6 +2,7 @@
 // ABOUTME: Supports TOML config files with environment variable expansion.
 
 use crate::error::{BridgeError, Result};
+use coven_grpc::{KeepAliveConfig, KeepAliveSettings};
 use serde::Deserialize;
 use std::path::PathBuf;
 use tracing::warn;
@@ -44,6 +45,10 @@ pub struct GatewayConfig {
     /// If set, host/port/tls are derived from it. Prefer using host/port/tls directly.
     #[serde(default)]
     url: Option<String>,
+    /// HTTP/2 keep-alive pings to the gateway, as a `[gateway.keepalive]`
+    /// table (see `KeepAliveSettings`).
+    #[serde(default)]
+    pub keepalive: KeepAliveSettings,
 }
 
 fn default_gateway_port() -> u16 {
@@ -82,6 +87,13 @@ impl GatewayConfig {
         }
     }
 
+    /// Validated keep-alive config, or None when disabled.
+    pub fn keep_alive(&self) -> Result<Option<KeepAliveConfig>> {
+        self.keepalive
+            .to_config()
+            .map_err(|e| BridgeError::Config(format!("gateway.keepalive: {}", e)))
+    }
+
     /// Construct the gRPC endpoint URI from host/port/tls settings.
     pub fn endpoint_uri(&self) -> String {
         let scheme = if self.tls { "https" } else { "http" };
@@ -89,7 +101,7 @@ impl GatewayConfig {
     }
 }
 
-#[derive(Debug, Clone, Deserialize, Default)]
+#[derive(Debug, Clone, Deserialize)]
 pub struct BridgeConfig {
     /// Restrict to specific Matrix room IDs (empty = allow all rooms)
     #[serde(default)]
@@ -97,18 +109,71 @@ pub struct BridgeConfig {
     /// Restrict to specific Matrix user IDs (empty = allow all users)
     #[serde(default)]
     pub allowed_senders: Vec<String>,
+    /// Matrix user IDs whose messages are dropped, e.g. other bots in the
+    /// room. Matrix doesn't mark bot accounts, so list them here.
+    #[serde(default)]
+    pub ignore_senders: Vec<String>,
+    /// Drop `m.notice` messages, which bots send and the spec says must
+    /// never be answered automatically, so two bots can't answer each other.
+    #[serde(default = "default_ignore_bots")]
+    pub ignore_bots: bool,
     /// Only respond to messages with this prefix
     #[serde(default)]
     pub command_prefix: Option<String>,
     /// Show typing indicator while agent is responding
     #[serde(default = "default_typing_indicator")]
     pub typing_indicator: bool,
+    /// Suppress typing notices and read receipts so the bridge never
+    /// reveals agent activity in the room
+    #[serde(default)]
+    pub privacy_mode: bool,
+    /// Persist bindings to this file so they survive restarts
+    /// (`.json` for a JSON file, anything else for SQLite; unset = memory only).
+    #[serde(default)]
+    pub bindings_path: Option<String>,
+
+    /// Show the response while it streams, editing it at most once per this
+    /// many milliseconds (unset or 0 = send once the response is complete).
+    #[serde(default)]
+    pub stream_interval_ms: Option<u64>,
+
+    /// Room that receives agent-initiated messages from agents with no
+    /// bound room (unset = drop them with a log line).
+    #[serde(default)]
+    pub notifications_room: Option<String>,
+
+    /// Most messages handled at once across rooms (unset = 16). Messages in
+    /// one room or thread are always handled one at a time, in arrival order.
+    #[serde(default)]
+    pub max_concurrent_messages: Option<usize>,
+}
+
+impl Default for BridgeConfig {
+    fn default() -> Self {
+        Self {
+            allowed_rooms: Vec::new(),
+            allowed_senders: Vec::new(),
+            ignore_senders: Vec::new(),
+            ignore_bots: default_ignore_bots(),
+            command_prefix: None,
+            typing_indicator: default_typing_indicator(),
+            privacy_mode: false,
+            bindings_path: None,
+            stream_interval_ms: None,
+            notifications_room: None,
+            max_concurrent_messages: None,
+        }
+    }
 }
 
 fn default_typing_indicator() -> bool {
     true
 }
 
+fn default_ignore_bots() -> bool {
+    true
+}
+
 impl Config {
     pub fn load(path: Option<PathBuf>) -> Result<Self> {
         // Use Linux XDG-style path (~/.config) on all platforms for consistency with other coven tools
@@ -163,6 +228,7 @@ impl Config {
         if self.gateway.port == 0 {
             return Err(BridgeError::Config("gateway.port must be non-zero".into()));
         }
+        self.gateway.keep_alive()?;
         // Validate homeserver looks like a URL
         if !self.matrix.homeserver.starts_with("http://")
             && !self.matrix.homeserver.starts_with("https://")
@@ -183,4 +249,27 @@ impl Config {
         self.bridge.allowed_senders.is_empty()
             || self.bridge.allowed_senders.iter().any(|s| s == sender)
     }
+
+    /// Whether messages from `sender` are dropped even if it's allowed.
+    pub fn is_sender_ignored(&self, sender: &str) -> bool {
+        self.bridge.ignore_senders.iter().any(|s| s == sender)
+    }
+
+    /// Whether typing notices should be sent while the agent is responding.
+    pub fn typing_enabled(&self) -> bool {
+        self.bridge.typing_indicator && !self.bridge.privacy_mode
+    }
+
+    /// Whether read receipts should be sent once the gateway accepts a message.
+    pub fn read_receipts_enabled(&self) -> bool {
+        !self.bridge.privacy_mode
+    }
+
+    /// Resolved bindings store path, with `~` expanded.
+    pub fn bindings_path(&self) -> Option<PathBuf> {
+        self.bridge
+            .bindings_path
+            .as_deref()
+            .map(|p| PathBuf::from(shellexpand::tilde(p).into_owned()))
+    }
 }
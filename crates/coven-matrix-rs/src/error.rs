@@ -22,6 +22,9 @@ pub enum BridgeError {
 
     #[error("IO error: {0}")]
     Io(#[from] std::io::Error),
+
+    #[error("Binding store error: {0}")]
+    Store(String),
 }
 
 impl From<tonic::Status> for BridgeError {
@@ -48,4 +51,17 @@ impl From<matrix_sdk::ClientBuildError> for BridgeError {
     }
 }
 
+impl From<coven_bridge_core::BridgeCoreError> for BridgeError {
+    fn from(e: coven_bridge_core::BridgeCoreError) -> Self {
+        use coven_bridge_core::BridgeCoreError as Core;
+        match e {
+            Core::Config(msg) => BridgeError::Config(msg),
+            Core::Gateway(status) => BridgeError::Gateway(status),
+            Core::Connection(err) => BridgeError::Connection(err),
+            Core::Io(err) => BridgeError::Io(err),
+            other => BridgeError::Store(other.to_string()),
+        }
+    }
+}
+
 pub type Result<T> = std::result::Result<T, BridgeError>;
@@ -8,6 +8,7 @@ pub mod error;
 pub mod gateway;
 pub mod matrix;
 pub mod setup;
+pub mod typing;
 
 pub use bridge::Bridge;
 pub use config::Config;
@@ -15,6 +15,14 @@ struct Cli {
     /// Run interactive setup wizard
     #[arg(long)]
     setup: bool,
+
+    /// Only log warnings and errors
+    #[arg(short, long, global = true, conflicts_with = "verbose")]
+    quiet: bool,
+
+    /// Log more detail (-v for debug, -vv for trace)
+    #[arg(short, long, action = clap::ArgAction::Count, global = true)]
+    verbose: u8,
 }
 
 #[tokio::main]
@@ -27,7 +35,14 @@ async fn main() -> Result<()> {
     }
 
     // Initialize logging for normal operation
-    coven_log::init_for("coven_matrix_rs");
+    let level = coven_log::level_from_flags(cli.quiet, cli.verbose);
+    #[cfg(feature = "otlp")]
+    let _otlp = coven_log::init_otlp_for("coven_matrix_rs", level);
+    #[cfg(not(feature = "otlp"))]
+    coven_log::init_for_with_level("coven_matrix_rs", level);
+    // Re-read ~/.config/coven/log-level on SIGHUP
+    #[cfg(unix)]
+    coven_log::reload_on_sighup();
 
     coven_matrix_rs::run(cli.config).await
 }
@@ -210,10 +210,34 @@ impl MatrixClient {
     }
 }
 
-/// Extract text content from a Matrix message event
-pub fn extract_text_content(event: &OriginalSyncRoomMessageEvent) -> Option<String> {
-    match &event.content.msgtype {
+/// Extract text content from a Matrix message event. Notices are what bots
+/// send, so they're only read when `ignore_bots` is off.
+pub fn extract_text_content(
+    event: &OriginalSyncRoomMessageEvent,
+    ignore_bots: bool,
+) -> Option<String> {
+    message_text(&event.content, ignore_bots)
+}
+
+fn message_text(content: &RoomMessageEventContent, ignore_bots: bool) -> Option<String> {
+    match &content.msgtype {
         MessageType::Text(text) => Some(text.body.clone()),
+        MessageType::Notice(notice) if !ignore_bots => Some(notice.body.clone()),
         _ => None,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_notices_from_bots_are_ignored_unless_allowed() {
+        let text = RoomMessageEventContent::text_plain("hello");
+        let notice = RoomMessageEventContent::notice_plain("beep");
+
+        assert_eq!(message_text(&text, true).as_deref(), Some("hello"));
+        assert_eq!(message_text(&notice, true), None);
+        assert_eq!(message_text(&notice, false).as_deref(), Some("beep"));
+    }
+}
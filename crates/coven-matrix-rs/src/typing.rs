@@ -0,0 +1,191 @@
+// ABOUTME: Per-room in-flight request tracking and typing notice refresh timing.
+// ABOUTME: Keeps Matrix typing notices alive while an agent response is streaming.
+
+use matrix_sdk::ruma::OwnedRoomId;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+
+/// How often to re-send a typing notice while a request is in flight.
+/// matrix-sdk sends typing notices with a 4 second server-side timeout,
+/// so refresh comfortably inside that window.
+pub const TYPING_REFRESH_INTERVAL: Duration = Duration::from_secs(3);
+
+/// Tracks when the last typing notice was sent and when the next one is due.
+#[derive(Debug, Clone)]
+pub struct TypingRefresh {
+    interval: Duration,
+    last_sent: Option<Instant>,
+}
+
+impl TypingRefresh {
+    pub fn new(interval: Duration) -> Self {
+        Self {
+            interval,
+            last_sent: None,
+        }
+    }
+
+    /// Record that a typing notice was sent at `now`.
+    pub fn mark_sent(&mut self, now: Instant) {
+        self.last_sent = Some(now);
+    }
+
+    /// Whether a typing notice should be (re-)sent at `now`.
+    pub fn is_due(&self, now: Instant) -> bool {
+        match self.last_sent {
+            Some(last) => now.saturating_duration_since(last) >= self.interval,
+            None => true,
+        }
+    }
+
+    /// The instant at which the next refresh becomes due.
+    pub fn next_deadline(&self, now: Instant) -> Instant {
+        match self.last_sent {
+            Some(last) => last + self.interval,
+            None => now,
+        }
+    }
+}
+
+impl Default for TypingRefresh {
+    fn default() -> Self {
+        Self::new(TYPING_REFRESH_INTERVAL)
+    }
+}
+
+/// Counts in-flight gateway requests per room so the typing notice is only
+/// cleared once the last outstanding response for that room has finished.
+#[derive(Debug, Clone, Default)]
+pub struct InFlightRequests {
+    rooms: Arc<Mutex<HashMap<OwnedRoomId, usize>>>,
+}
+
+impl InFlightRequests {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register a new request for `room_id`. Returns the number of requests
+    /// now in flight for the room, including this one.
+    pub async fn begin(&self, room_id: &OwnedRoomId) -> usize {
+        let mut rooms = self.rooms.lock().await;
+        let count = rooms.entry(room_id.clone()).or_insert(0);
+        *count += 1;
+        *count
+    }
+
+    /// Mark a request for `room_id` as finished. Returns true if the room
+    /// has no requests left in flight.
+    pub async fn finish(&self, room_id: &OwnedRoomId) -> bool {
+        let mut rooms = self.rooms.lock().await;
+        match rooms.get_mut(room_id) {
+            Some(count) if *count > 1 => {
+                *count -= 1;
+                false
+            }
+            Some(_) => {
+                rooms.remove(room_id);
+                true
+            }
+            None => true,
+        }
+    }
+
+    /// Number of requests currently in flight for `room_id`.
+    pub async fn count(&self, room_id: &OwnedRoomId) -> usize {
+        self.rooms.lock().await.get(room_id).copied().unwrap_or(0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn room(id: &str) -> OwnedRoomId {
+        OwnedRoomId::try_from(id).unwrap()
+    }
+
+    #[test]
+    fn test_refresh_due_before_first_send() {
+        let refresh = TypingRefresh::new(Duration::from_secs(3));
+        let now = Instant::now();
+        assert!(refresh.is_due(now));
+        assert_eq!(refresh.next_deadline(now), now);
+    }
+
+    #[test]
+    fn test_refresh_not_due_within_interval() {
+        let mut refresh = TypingRefresh::new(Duration::from_secs(3));
+        let start = Instant::now();
+        refresh.mark_sent(start);
+
+        assert!(!refresh.is_due(start));
+        assert!(!refresh.is_due(start + Duration::from_millis(2999)));
+        assert_eq!(refresh.next_deadline(start), start + Duration::from_secs(3));
+    }
+
+    #[test]
+    fn test_refresh_due_after_interval() {
+        let mut refresh = TypingRefresh::new(Duration::from_secs(3));
+        let start = Instant::now();
+        refresh.mark_sent(start);
+
+        assert!(refresh.is_due(start + Duration::from_secs(3)));
+        assert!(refresh.is_due(start + Duration::from_secs(10)));
+
+        // Re-sending resets the window
+        let later = start + Duration::from_secs(4);
+        refresh.mark_sent(later);
+        assert!(!refresh.is_due(later + Duration::from_secs(1)));
+    }
+
+    #[test]
+    fn test_refresh_default_interval_within_typing_timeout() {
+        assert!(TYPING_REFRESH_INTERVAL < Duration::from_secs(4));
+    }
+
+    #[tokio::test]
+    async fn test_in_flight_single_request() {
+        let in_flight = InFlightRequests::new();
+        let room_id = room("!a:example.org");
+
+        assert_eq!(in_flight.begin(&room_id).await, 1);
+        assert_eq!(in_flight.count(&room_id).await, 1);
+        assert!(in_flight.finish(&room_id).await);
+        assert_eq!(in_flight.count(&room_id).await, 0);
+    }
+
+    #[tokio::test]
+    async fn test_in_flight_overlapping_requests() {
+        let in_flight = InFlightRequests::new();
+        let room_id = room("!a:example.org");
+
+        in_flight.begin(&room_id).await;
+        assert_eq!(in_flight.begin(&room_id).await, 2);
+
+        // First finish leaves the room busy, second clears it
+        assert!(!in_flight.finish(&room_id).await);
+        assert!(in_flight.finish(&room_id).await);
+    }
+
+    #[tokio::test]
+    async fn test_in_flight_rooms_are_independent() {
+        let in_flight = InFlightRequests::new();
+        let a = room("!a:example.org");
+        let b = room("!b:example.org");
+
+        in_flight.begin(&a).await;
+        in_flight.begin(&b).await;
+
+        assert!(in_flight.finish(&a).await);
+        assert_eq!(in_flight.count(&b).await, 1);
+    }
+
+    #[tokio::test]
+    async fn test_in_flight_finish_unknown_room() {
+        let in_flight = InFlightRequests::new();
+        assert!(in_flight.finish(&room("!none:example.org")).await);
+    }
+}
@@ -39,6 +39,14 @@ fn test_command_parsing() {
         Command::parse("!coven bind   "),
         Some(Command::Unknown(cmd)) if cmd.contains("requires agent-id")
     ));
+    assert!(matches!(
+        Command::parse("!coven set model claude-sonnet-4-20250514"),
+        Some(Command::Set(key, value)) if key == "model" && value == "claude-sonnet-4-20250514"
+    ));
+    assert!(matches!(
+        Command::parse("!coven set max_tokens"),
+        Some(Command::Unknown(cmd)) if cmd.contains("requires a setting")
+    ));
     assert!(Command::parse("hello world").is_none());
     assert!(Command::parse("/other command").is_none());
 }
@@ -60,6 +68,7 @@ token = "test-token"
 [bridge]
 allowed_rooms = ["!room1:matrix.org"]
 typing_indicator = false
+notifications_room = "!notify:matrix.org"
 "#;
 
     let mut file = NamedTempFile::new().unwrap();
@@ -75,6 +84,39 @@ typing_indicator = false
     assert_eq!(config.gateway.endpoint_uri(), "http://localhost:6666");
     assert_eq!(config.bridge.allowed_rooms.len(), 1);
     assert!(!config.bridge.typing_indicator);
+    assert_eq!(
+        config.bridge.notifications_room.as_deref(),
+        Some("!notify:matrix.org")
+    );
+}
+
+#[test]
+fn test_privacy_mode_disables_typing_and_receipts() {
+    let base = r#"
+[matrix]
+homeserver = "https://matrix.org"
+username = "@bot:matrix.org"
+password = "secret"
+
+[gateway]
+host = "localhost"
+port = 6666
+"#;
+
+    let mut file = NamedTempFile::new().unwrap();
+    file.write_all(base.as_bytes()).unwrap();
+    let config = Config::load(Some(file.path().to_path_buf())).unwrap();
+    assert!(!config.bridge.privacy_mode);
+    assert!(config.typing_enabled());
+    assert!(config.read_receipts_enabled());
+
+    let private = format!("{}\n[bridge]\nprivacy_mode = true\n", base);
+    let mut file = NamedTempFile::new().unwrap();
+    file.write_all(private.as_bytes()).unwrap();
+    let config = Config::load(Some(file.path().to_path_buf())).unwrap();
+    assert!(config.bridge.typing_indicator);
+    assert!(!config.typing_enabled());
+    assert!(!config.read_receipts_enabled());
 }
 
 #[test]
@@ -102,6 +144,35 @@ allowed_rooms = ["!allowed:matrix.org"]
     assert!(!config.is_room_allowed("!other:matrix.org"));
 }
 
+#[test]
+fn test_ignored_sender_check() {
+    let config_content = r#"
+[matrix]
+homeserver = "https://matrix.org"
+username = "@bot:matrix.org"
+password = "secret"
+
+[gateway]
+host = "localhost"
+port = 6666
+
+[bridge]
+ignore_senders = ["@otherbot:matrix.org"]
+"#;
+
+    let mut file = NamedTempFile::new().unwrap();
+    file.write_all(config_content.as_bytes()).unwrap();
+
+    let config = Config::load(Some(file.path().to_path_buf())).unwrap();
+
+    // Ignoring wins over the (empty = everyone) allowlist
+    assert!(config.is_sender_allowed("@otherbot:matrix.org"));
+    assert!(config.is_sender_ignored("@otherbot:matrix.org"));
+    assert!(!config.is_sender_ignored("@alice:matrix.org"));
+    // Notices from bots are dropped unless asked for
+    assert!(config.bridge.ignore_bots);
+}
+
 #[test]
 fn test_empty_allowed_rooms_allows_all() {
     let config_content = r#"
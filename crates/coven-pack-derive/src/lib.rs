@@ -0,0 +1,248 @@
+// ABOUTME: Derive macro generating JSON input schemas for coven-pack tools.
+// ABOUTME: Builds ToolInput impls from field types, doc comments, and serde attributes.
+
+//! `#[derive(ToolInput)]` for coven-pack. Use it through the `coven_pack`
+//! re-export rather than depending on this crate directly.
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{
+    parse_macro_input, parse_quote, Attribute, Data, DeriveInput, Expr, ExprLit, Fields, Lit,
+    LitStr, Meta, Token, Type,
+};
+
+/// Derive `coven_pack::ToolInput` for a struct with named fields.
+///
+/// - Each field becomes a property whose schema comes from its type's
+///   `ToolSchema` impl.
+/// - `///` doc comments become property descriptions.
+/// - Fields are required unless they are `Option<_>` or have `#[serde(default)]`.
+/// - `#[serde(rename = "...")]` renames the property; `#[serde(skip)]` omits it.
+/// - `#[tool(default = <value>)]` records a default value in the schema.
+/// - `#[tool(sensitive)]` marks the property `"sensitive": true`, so its
+///   value is redacted wherever tool inputs are logged;
+///   `#[tool(sensitive = false)]` opts a credential-looking name out.
+#[proc_macro_derive(ToolInput, attributes(tool))]
+pub fn derive_tool_input(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// What the schema needs to know about one struct field.
+struct FieldSpec {
+    name: String,
+    ty: Type,
+    description: Option<String>,
+    has_serde_default: bool,
+    default_value: Option<Expr>,
+    sensitive: Option<bool>,
+}
+
+fn expand(input: DeriveInput) -> syn::Result<TokenStream2> {
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(named) => &named.named,
+            _ => return Err(not_named_struct(&input)),
+        },
+        _ => return Err(not_named_struct(&input)),
+    };
+    let container_default = container_default(&input.attrs)?;
+
+    let mut specs = Vec::new();
+    for field in fields {
+        if let Some(mut spec) = field_spec(field)? {
+            spec.has_serde_default |= container_default;
+            specs.push(spec);
+        }
+    }
+
+    let sj = quote!(::coven_pack::__private::serde_json);
+    let properties = specs.iter().map(|spec| {
+        let FieldSpec {
+            name,
+            ty,
+            description,
+            has_serde_default,
+            default_value,
+            sensitive,
+        } = spec;
+        let description = description.as_ref().map(|d| {
+            quote! { property.insert("description".to_string(), #sj::Value::String(#d.to_string())); }
+        });
+        let default_value = default_value.as_ref().map(|value| {
+            quote! { property.insert("default".to_string(), #sj::json!(#value)); }
+        });
+        let sensitive = sensitive.map(|sensitive| {
+            quote! { property.insert("sensitive".to_string(), #sj::Value::Bool(#sensitive)); }
+        });
+        quote! {
+            {
+                let mut schema = <#ty as ::coven_pack::ToolSchema>::schema();
+                if let Some(property) = schema.as_object_mut() {
+                    #description
+                    #default_value
+                    #sensitive
+                }
+                properties.insert(#name.to_string(), schema);
+                if !#has_serde_default && !<#ty as ::coven_pack::ToolSchema>::OPTIONAL {
+                    required.push(#sj::Value::String(#name.to_string()));
+                }
+            }
+        }
+    });
+
+    // Generic structs need their field types to have schemas and the
+    // struct itself to be deserializable
+    let ident = &input.ident;
+    let mut generics = input.generics.clone();
+    let (_, ty_generics, _) = input.generics.split_for_impl();
+    let where_clause = generics.make_where_clause();
+    for spec in &specs {
+        let ty = &spec.ty;
+        where_clause
+            .predicates
+            .push(parse_quote!(#ty: ::coven_pack::ToolSchema));
+    }
+    where_clause.predicates.push(parse_quote!(
+        #ident #ty_generics: ::coven_pack::__private::serde::de::DeserializeOwned
+    ));
+    let (impl_generics, _, where_clause) = generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics ::coven_pack::ToolInput for #ident #ty_generics #where_clause {
+            #[allow(unused_mut)]
+            fn input_schema() -> #sj::Value {
+                let mut properties = #sj::Map::new();
+                let mut required: ::std::vec::Vec<#sj::Value> = ::std::vec::Vec::new();
+                #(#properties)*
+
+                let mut schema = #sj::Map::new();
+                schema.insert("type".to_string(), #sj::Value::String("object".to_string()));
+                schema.insert("properties".to_string(), #sj::Value::Object(properties));
+                if !required.is_empty() {
+                    schema.insert("required".to_string(), #sj::Value::Array(required));
+                }
+                #sj::Value::Object(schema)
+            }
+        }
+    })
+}
+
+fn not_named_struct(input: &DeriveInput) -> syn::Error {
+    syn::Error::new(
+        input.ident.span(),
+        "ToolInput can only be derived for structs with named fields",
+    )
+}
+
+/// Whether the struct has `#[serde(default)]`, making every field optional.
+/// Rejects container attributes that would change property names without
+/// the schema knowing.
+fn container_default(attrs: &[Attribute]) -> syn::Result<bool> {
+    let mut default = false;
+    for attr in attrs.iter().filter(|a| a.path().is_ident("serde")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("rename_all") {
+                return Err(meta.error(
+                    "ToolInput doesn't support #[serde(rename_all)]; rename fields individually",
+                ));
+            }
+            if meta.path.is_ident("default") {
+                default = true;
+            }
+            skip_meta_value(&meta)
+        })?;
+    }
+    Ok(default)
+}
+
+/// Collect a field's schema settings, or None if serde skips the field.
+fn field_spec(field: &syn::Field) -> syn::Result<Option<FieldSpec>> {
+    let ident = field
+        .ident
+        .as_ref()
+        .expect("named fields always have identifiers");
+    let mut name = ident.to_string().trim_start_matches("r#").to_string();
+    let mut docs = Vec::new();
+    let mut has_serde_default = false;
+    let mut default_value = None;
+    let mut sensitive = None;
+    let mut skip = false;
+
+    for attr in &field.attrs {
+        if attr.path().is_ident("doc") {
+            if let Meta::NameValue(nv) = &attr.meta {
+                if let Expr::Lit(ExprLit {
+                    lit: Lit::Str(doc), ..
+                }) = &nv.value
+                {
+                    let line = doc.value().trim().to_string();
+                    if !line.is_empty() {
+                        docs.push(line);
+                    }
+                }
+            }
+        } else if attr.path().is_ident("serde") {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("rename") && meta.input.peek(Token![=]) {
+                    name = meta.value()?.parse::<LitStr>()?.value();
+                    Ok(())
+                } else if meta.path.is_ident("default") {
+                    has_serde_default = true;
+                    skip_meta_value(&meta)
+                } else if meta.path.is_ident("skip") || meta.path.is_ident("skip_deserializing") {
+                    skip = true;
+                    Ok(())
+                } else if meta.path.is_ident("flatten") {
+                    Err(meta.error("ToolInput doesn't support #[serde(flatten)]"))
+                } else {
+                    skip_meta_value(&meta)
+                }
+            })?;
+        } else if attr.path().is_ident("tool") {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("default") {
+                    default_value = Some(meta.value()?.parse::<Expr>()?);
+                    Ok(())
+                } else if meta.path.is_ident("sensitive") {
+                    sensitive = Some(if meta.input.peek(Token![=]) {
+                        meta.value()?.parse::<syn::LitBool>()?.value
+                    } else {
+                        true
+                    });
+                    Ok(())
+                } else {
+                    Err(meta.error(
+                        "unsupported tool attribute, expected `default = <value>` or `sensitive`",
+                    ))
+                }
+            })?;
+        }
+    }
+
+    if skip {
+        return Ok(None);
+    }
+    Ok(Some(FieldSpec {
+        name,
+        ty: field.ty.clone(),
+        description: (!docs.is_empty()).then(|| docs.join(" ")),
+        has_serde_default,
+        default_value,
+        sensitive,
+    }))
+}
+
+/// Consume the value of a serde attribute this macro doesn't interpret, in
+/// either `key = value` or `key(...)` form.
+fn skip_meta_value(meta: &syn::meta::ParseNestedMeta) -> syn::Result<()> {
+    if meta.input.peek(Token![=]) {
+        meta.value()?.parse::<Expr>()?;
+    } else if meta.input.peek(syn::token::Paren) {
+        let _content;
+        syn::parenthesized!(_content in meta.input);
+    }
+    Ok(())
+}
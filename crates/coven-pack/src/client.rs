@@ -1,21 +1,132 @@
 // ABOUTME: PackClient for connecting to coven-gateway and serving tools.
-// ABOUTME: Handles registration, authentication, and tool execution request streaming.
-
-use crate::error::{PackError, ToolError};
+// ABOUTME: Handles connecting with retries, registration, manifest updates, authentication, tool request streaming, health reporting, secrets, and reconnecting after drops.
+
+use crate::config::PackConfig;
+use crate::error::PackError;
+use crate::executor::{
+    redactors_for, Redactors, ToolExecutor, DEFAULT_EXECUTION_TIMEOUT,
+    DEFAULT_MAX_CONCURRENT_EXECUTIONS,
+};
 use crate::handler::ToolHandler;
+use crate::health::{spawn_health_checks, DEFAULT_HEALTH_CHECK_INTERVAL};
+use crate::secrets::Secrets;
 use coven_grpc::{create_channel, ChannelConfig};
 use coven_proto::pack_service_client::PackServiceClient;
-use coven_proto::{ExecuteToolResponse, PackManifest};
-use coven_ssh::{load_key, PrivateKey, SshAuthCredentials};
+use coven_proto::{
+    ExecuteToolRequest, ExecuteToolResponse, PackManifest, PackSecrets, PackStatus,
+    PackToolProgress, PackWelcome, WatchSecretsRequest,
+};
+use coven_ssh::{load_key_with_passphrase, PassphraseSource, PrivateKey, SshAuthCredentials};
+use rand::Rng;
+use std::future::Future;
 use std::path::Path;
 use std::sync::Arc;
-use tokio::sync::RwLock;
+use std::time::Duration;
+use tokio::sync::{mpsc, RwLock};
 use tonic::transport::Channel;
+use tonic::Streaming;
 use tracing::{debug, error, info, warn};
 
-/// Credentials are refreshed when older than this many seconds.
-/// Gateway rejects signatures older than 5 minutes (300s), so refresh at 4 minutes.
-const CREDENTIAL_REFRESH_TTL_SECS: i64 = 240;
+/// Reason passed to `ToolHandler::on_closing` when the connection dropped
+/// and `run` is about to reconnect, so handlers can keep their resources.
+pub const RECONNECTING_REASON: &str = "reconnecting";
+
+/// Delay before the first reconnect attempt; doubles on each further attempt.
+const DEFAULT_RECONNECT_INITIAL_BACKOFF: Duration = Duration::from_secs(1);
+
+/// Upper bound on the delay between reconnect attempts.
+const DEFAULT_RECONNECT_MAX_BACKOFF: Duration = Duration::from_secs(30);
+
+/// Connection attempts `RetryPolicy::default()` makes before giving up.
+const DEFAULT_CONNECT_MAX_ATTEMPTS: u32 = 10;
+
+/// How long to wait for the gateway to send the pack's secrets after
+/// registering before serving without them.
+const SECRETS_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Connection state reported to the callback set with
+/// `PackClient::on_connection_state`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConnectionState {
+    /// The manifest is registered and tool requests are being served.
+    Registered,
+    /// The connection dropped; waiting `delay` before reconnect attempt
+    /// `attempt` (1-based, counted since the last successful registration).
+    Reconnecting { attempt: u32, delay: Duration },
+    /// `run` is returning; no further reconnects will be made.
+    Closed,
+}
+
+type StateCallback = Arc<dyn Fn(&ConnectionState) + Send + Sync>;
+
+/// How `PackClient::connect_with_retry` retries a gateway that isn't
+/// reachable yet.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Total connection attempts, including the first one.
+    pub max_attempts: u32,
+    /// Delay before the first retry; doubles on each subsequent retry.
+    pub initial_backoff: Duration,
+    /// Upper bound on the delay between retries.
+    pub max_backoff: Duration,
+}
+
+impl RetryPolicy {
+    /// A policy that never retries, like `PackClient::connect`.
+    pub fn none() -> Self {
+        Self {
+            max_attempts: 1,
+            ..Self::default()
+        }
+    }
+
+    /// Delay to wait before retry number `retry` (1-based), before jitter.
+    pub fn backoff(&self, retry: u32) -> Duration {
+        let factor = 2u32.saturating_pow(retry.saturating_sub(1));
+        self.initial_backoff
+            .saturating_mul(factor)
+            .min(self.max_backoff)
+    }
+}
+
+impl Default for RetryPolicy {
+    /// 10 attempts, backing off from 1s to 30s: a bit over two minutes for
+    /// the gateway to come up.
+    fn default() -> Self {
+        Self {
+            max_attempts: DEFAULT_CONNECT_MAX_ATTEMPTS,
+            initial_backoff: DEFAULT_RECONNECT_INITIAL_BACKOFF,
+            max_backoff: DEFAULT_RECONNECT_MAX_BACKOFF,
+        }
+    }
+}
+
+/// Why a registered session stopped serving requests.
+enum SessionEnd {
+    /// The gateway closed the request stream.
+    StreamClosed,
+    /// The stream or a result send failed.
+    Failed(PackError),
+}
+
+impl SessionEnd {
+    fn reason(&self) -> String {
+        match self {
+            SessionEnd::StreamClosed => "stream closed".to_string(),
+            SessionEnd::Failed(e) => e.to_string(),
+        }
+    }
+}
+
+/// Aborts the wrapped task when dropped, so a session's background work
+/// stops with it however the session ends.
+struct AbortOnDrop(tokio::task::JoinHandle<()>);
+
+impl Drop for AbortOnDrop {
+    fn drop(&mut self) {
+        self.0.abort();
+    }
+}
 
 /// Client for connecting a tool pack to coven-gateway.
 ///
@@ -23,7 +134,12 @@ const CREDENTIAL_REFRESH_TTL_SECS: i64 = 240;
 /// - SSH-based authentication with the gateway
 /// - Registering the pack's manifest (tools)
 /// - Receiving tool execution requests
-/// - Sending tool execution results
+/// - Executing requests concurrently, up to a configurable limit
+/// - Sending tool execution results, and progress reported while tools run
+/// - Reporting the handler's health check on an interval
+/// - Keeping the pack's gateway-managed secrets up to date
+/// - Updating the registered manifest when the pack's tools change
+/// - Reconnecting and re-registering when the gateway goes away
 ///
 /// # Example
 ///
@@ -55,7 +171,20 @@ const CREDENTIAL_REFRESH_TTL_SECS: i64 = 240;
 pub struct PackClient {
     channel: Channel,
     private_key: PrivateKey,
-    credentials: Arc<RwLock<SshAuthCredentials>>,
+    max_concurrent_executions: usize,
+    execution_timeout: Duration,
+    health_check_interval: Duration,
+    reconnect: bool,
+    max_reconnect_attempts: Option<u32>,
+    reconnect_initial_backoff: Duration,
+    reconnect_max_backoff: Duration,
+    state_callback: Option<StateCallback>,
+    /// Latest manifest passed to `run` or `update_manifest`, registered on
+    /// every reconnect
+    manifest: RwLock<Option<PackManifest>>,
+    /// Input redactors for the current manifest's tools, used when logging
+    redactors: Redactors,
+    secrets: Secrets,
 }
 
 impl PackClient {
@@ -70,15 +199,37 @@ impl PackClient {
     ///
     /// Returns an error if:
     /// - The SSH key cannot be loaded
-    /// - The key type is not supported (only ed25519 is supported)
+    /// - The key type is not supported (ed25519, RSA, or ECDSA P-256)
     /// - Connection to the gateway fails
     pub async fn connect(url: &str, ssh_key_path: &Path) -> Result<Self, PackError> {
-        // Load SSH key
-        let private_key =
-            load_key(ssh_key_path).map_err(|e| PackError::KeyLoadFailed(e.to_string()))?;
+        Self::connect_with_passphrase(url, ssh_key_path, None).await
+    }
+
+    /// Connect like `connect`, decrypting an encrypted SSH key with a
+    /// passphrase from `passphrase`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the passphrase can't be had or is wrong, or for
+    /// any reason `connect` would.
+    pub async fn connect_with_passphrase(
+        url: &str,
+        ssh_key_path: &Path,
+        passphrase: Option<&PassphraseSource>,
+    ) -> Result<Self, PackError> {
+        let private_key = load_key_with_passphrase(ssh_key_path, passphrase)
+            .map_err(|e| PackError::KeyLoadFailed(e.to_string()))?;
+        Self::connect_with_key(url, ssh_key_path, private_key).await
+    }
 
-        // Create auth credentials
-        let credentials = SshAuthCredentials::new(&private_key)?;
+    /// Connect with an already loaded (and decrypted) SSH key
+    async fn connect_with_key(
+        url: &str,
+        ssh_key_path: &Path,
+        private_key: PrivateKey,
+    ) -> Result<Self, PackError> {
+        // Fail now, not on the first request, if the key can't sign
+        SshAuthCredentials::new(&private_key)?;
 
         // Create gRPC channel
         let config = ChannelConfig::new(url);
@@ -93,38 +244,221 @@ impl PackClient {
         Ok(Self {
             channel,
             private_key,
-            credentials: Arc::new(RwLock::new(credentials)),
+            max_concurrent_executions: DEFAULT_MAX_CONCURRENT_EXECUTIONS,
+            execution_timeout: DEFAULT_EXECUTION_TIMEOUT,
+            health_check_interval: DEFAULT_HEALTH_CHECK_INTERVAL,
+            reconnect: true,
+            max_reconnect_attempts: None,
+            reconnect_initial_backoff: DEFAULT_RECONNECT_INITIAL_BACKOFF,
+            reconnect_max_backoff: DEFAULT_RECONNECT_MAX_BACKOFF,
+            state_callback: None,
+            manifest: RwLock::new(None),
+            redactors: Redactors::default(),
+            secrets: Secrets::new(),
         })
     }
 
-    /// Refresh credentials if they are stale.
+    /// Connect like `connect`, retrying with a jittered exponential backoff
+    /// while the gateway can't be reached, e.g. when the pack and gateway
+    /// start together. Key and credential errors are returned at once.
+    ///
+    /// # Errors
     ///
-    /// This should be called before sending any request to the gateway.
-    /// Credentials are refreshed if older than CREDENTIAL_REFRESH_TTL_SECS.
-    async fn refresh_credentials_if_stale(&self) -> Result<(), PackError> {
-        let creds = self.credentials.read().await;
-        if creds.is_stale(CREDENTIAL_REFRESH_TTL_SECS) {
-            drop(creds); // Release read lock before acquiring write lock
-            let mut creds = self.credentials.write().await;
-            // Double-check after acquiring write lock (another task may have refreshed)
-            if creds.is_stale(CREDENTIAL_REFRESH_TTL_SECS) {
-                let age = creds.age_secs();
-                *creds = SshAuthCredentials::new(&self.private_key)?;
-                debug!(old_age_secs = age, "Refreshed stale SSH credentials");
+    /// Returns the last connection error once `policy.max_attempts`
+    /// attempts have failed, or any other error from `connect`.
+    pub async fn connect_with_retry(
+        url: &str,
+        ssh_key_path: &Path,
+        policy: RetryPolicy,
+    ) -> Result<Self, PackError> {
+        Self::connect_with_passphrase_retry(url, ssh_key_path, None, policy).await
+    }
+
+    /// Connect like `connect_with_passphrase`, retrying like
+    /// `connect_with_retry`. The key is decrypted once, before the first attempt.
+    pub async fn connect_with_passphrase_retry(
+        url: &str,
+        ssh_key_path: &Path,
+        passphrase: Option<&PassphraseSource>,
+        policy: RetryPolicy,
+    ) -> Result<Self, PackError> {
+        // Decrypted once: a prompt or command source isn't asked again per attempt
+        let private_key = load_key_with_passphrase(ssh_key_path, passphrase)
+            .map_err(|e| PackError::KeyLoadFailed(e.to_string()))?;
+        let max_attempts = policy.max_attempts.max(1);
+        let mut attempt = 1;
+        loop {
+            info!(url = url, attempt, max_attempts, "Connecting to gateway");
+            match Self::connect_with_key(url, ssh_key_path, private_key.clone()).await {
+                Err(PackError::ConnectionFailed(reason)) if attempt < max_attempts => {
+                    let delay = jitter(policy.backoff(attempt));
+                    warn!(
+                        url = url,
+                        attempt,
+                        max_attempts,
+                        delay_ms = delay.as_millis() as u64,
+                        error = %reason,
+                        "Gateway not reachable, retrying"
+                    );
+                    tokio::time::sleep(delay).await;
+                    attempt += 1;
+                }
+                result => return result,
             }
         }
+    }
+
+    /// Connect using a loaded `PackConfig`, applying its settings with
+    /// `with_config`.
+    pub async fn connect_with_config(config: &PackConfig) -> Result<Self, PackError> {
+        Ok(Self::connect_with_passphrase(
+            &config.gateway_url,
+            &config.ssh_key_path,
+            config.ssh_key_passphrase.as_ref(),
+        )
+        .await?
+        .with_config(config))
+    }
+
+    /// Connect using a loaded `PackConfig` like `connect_with_config`,
+    /// retrying like `connect_with_retry`.
+    pub async fn connect_with_config_retry(
+        config: &PackConfig,
+        policy: RetryPolicy,
+    ) -> Result<Self, PackError> {
+        Ok(Self::connect_with_passphrase_retry(
+            &config.gateway_url,
+            &config.ssh_key_path,
+            config.ssh_key_passphrase.as_ref(),
+            policy,
+        )
+        .await?
+        .with_config(config))
+    }
+
+    /// Apply a `PackConfig`'s execution limits, health check interval, and
+    /// reconnect settings. Secrets the gateway sends are filled into
+    /// `config.secrets`.
+    pub fn with_config(self, config: &PackConfig) -> Self {
+        self.with_max_concurrent_executions(config.max_concurrent_executions)
+            .with_execution_timeout(config.execution_timeout)
+            .with_health_check_interval(config.health_check_interval)
+            .with_reconnect(config.reconnect)
+            .with_max_reconnect_attempts(config.max_reconnect_attempts)
+            .with_secrets(config.secrets.clone())
+    }
+
+    /// Set how many tool requests may execute at once (default 8, minimum 1).
+    pub fn with_max_concurrent_executions(mut self, max: usize) -> Self {
+        self.max_concurrent_executions = max.max(1);
+        self
+    }
+
+    /// Set how long a single tool execution may run before the gateway is
+    /// sent a timeout error for it (default 5 minutes).
+    pub fn with_execution_timeout(mut self, timeout: Duration) -> Self {
+        self.execution_timeout = timeout;
+        self
+    }
+
+    /// Set how often the handler's `health_check` runs and is reported to
+    /// the gateway while registered (default 30 seconds). The first check
+    /// runs right after each registration.
+    pub fn with_health_check_interval(mut self, interval: Duration) -> Self {
+        self.health_check_interval = interval;
+        self
+    }
+
+    /// Set whether `run` reconnects and re-registers after the connection
+    /// drops (default true). When false, `run` returns as soon as the
+    /// request stream ends.
+    pub fn with_reconnect(mut self, reconnect: bool) -> Self {
+        self.reconnect = reconnect;
+        self
+    }
+
+    /// Set how many consecutive reconnect attempts may fail before `run`
+    /// gives up (default None: retry forever). The count resets after each
+    /// successful registration.
+    pub fn with_max_reconnect_attempts(mut self, max: Option<u32>) -> Self {
+        self.max_reconnect_attempts = max;
+        self
+    }
+
+    /// Set the reconnect backoff: the delay before the first attempt, which
+    /// doubles per attempt up to `max` (defaults 1s and 30s). Each delay is
+    /// jittered down by up to half so restarted gateways aren't hit by every
+    /// pack at once.
+    pub fn with_reconnect_backoff(mut self, initial: Duration, max: Duration) -> Self {
+        self.reconnect_initial_backoff = initial;
+        self.reconnect_max_backoff = max;
+        self
+    }
+
+    /// Fill `secrets` with the values the gateway sends, instead of the
+    /// client's own handle.
+    pub fn with_secrets(mut self, secrets: Secrets) -> Self {
+        self.secrets = secrets;
+        self
+    }
+
+    /// The pack's gateway-managed secrets. Empty until the first
+    /// registration, then kept current while `run` is serving.
+    pub fn secrets(&self) -> Secrets {
+        self.secrets.clone()
+    }
+
+    /// Call `callback` on every connection state transition, e.g. to log
+    /// reconnects from a pack binary.
+    pub fn on_connection_state(
+        mut self,
+        callback: impl Fn(&ConnectionState) + Send + Sync + 'static,
+    ) -> Self {
+        self.state_callback = Some(Arc::new(callback));
+        self
+    }
+
+    fn set_state(&self, state: ConnectionState) {
+        if let Some(callback) = &self.state_callback {
+            callback(&state);
+        }
+    }
+
+    /// Delay before reconnect attempt `attempt` (1-based), before jitter.
+    fn reconnect_backoff(&self, attempt: u32) -> Duration {
+        let factor = 2u32.saturating_pow(attempt.saturating_sub(1));
+        self.reconnect_initial_backoff
+            .saturating_mul(factor)
+            .min(self.reconnect_max_backoff)
+    }
+
+    /// Sign `request` with a fresh nonce. The gateway rejects any
+    /// signature it has already seen, so no two requests can share one.
+    fn sign<T>(&self, request: &mut tonic::Request<T>) -> Result<(), PackError> {
+        SshAuthCredentials::new(&self.private_key)?.apply_to_request(request)?;
         Ok(())
     }
 
     /// Run the pack, handling tool execution requests.
     ///
     /// This method:
-    /// 1. Registers the pack's manifest with the gateway
+    /// 1. Registers the pack's manifest with the gateway and waits for its
+    ///    secrets, so they're in place before any tool runs
     /// 2. Receives tool execution requests via streaming
-    /// 3. Calls the handler for each request
-    /// 4. Sends results back to the gateway
+    /// 3. Calls the handler for each request, running up to the configured
+    ///    number of requests at once
+    /// 4. Sends each result back to the gateway as it completes
+    ///
+    /// When the stream ends or fails, in-flight executions are abandoned,
+    /// the handler's `on_closing` is called with `RECONNECTING_REASON`, and the
+    /// manifest is registered again after a jittered exponential backoff.
+    /// `on_registered` is called after every successful registration.
+    ///
+    /// With reconnect disabled, this method returns when the stream ends.
     ///
-    /// This method runs until the connection is closed or an error occurs.
+    /// To change the pack's tools while it runs, call `update_manifest` from
+    /// another task (e.g. with the client in an `Arc`); reconnects register
+    /// the updated manifest.
     ///
     /// # Arguments
     ///
@@ -134,19 +468,125 @@ impl PackClient {
     /// # Errors
     ///
     /// Returns an error if:
-    /// - Registration fails
-    /// - The stream is closed unexpectedly
-    /// - A fatal error occurs during tool execution
-    pub async fn run<H: ToolHandler>(
+    /// - The first registration fails
+    /// - Reconnecting fails more than the configured number of times
+    /// - Reconnect is disabled and the stream fails
+    pub async fn run<H: ToolHandler + 'static>(
         &self,
         manifest: PackManifest,
         handler: H,
     ) -> Result<(), PackError> {
         let handler = Arc::new(handler);
         let pack_id = manifest.pack_id.clone();
+        self.set_manifest(manifest.clone()).await;
+
+        // A bad key or rejected manifest won't fix itself, so the first
+        // registration fails fast
+        let mut stream = match self.register(&manifest).await {
+            Ok(stream) => stream,
+            Err(e) => {
+                self.set_state(ConnectionState::Closed);
+                return Err(e);
+            }
+        };
+
+        loop {
+            let secrets = self.watch_secrets(&pack_id).await;
+
+            info!(pack_id = %pack_id, "Pack registered, waiting for tool requests");
+            self.set_state(ConnectionState::Registered);
+
+            // Notify handler of successful registration
+            // The gateway doesn't send a PackWelcome in the current protocol,
+            // so we use the pack_id from the manifest
+            handler.on_registered(&pack_id, &[]).await;
+
+            let ended = self.serve(&pack_id, &handler, stream, secrets).await;
+
+            if !self.reconnect {
+                handler.on_closing(Some(&ended.reason())).await;
+                self.set_state(ConnectionState::Closed);
+                return match ended {
+                    SessionEnd::StreamClosed => Ok(()),
+                    SessionEnd::Failed(e) => Err(e),
+                };
+            }
+
+            warn!(pack_id = %pack_id, reason = %ended.reason(), "Gateway connection lost");
+            handler.on_closing(Some(RECONNECTING_REASON)).await;
+
+            let manifest = self.current_manifest(&manifest).await;
+            stream = match self.reregister(&manifest).await {
+                Ok(stream) => stream,
+                Err(e) => {
+                    handler.on_closing(Some(&e.to_string())).await;
+                    self.set_state(ConnectionState::Closed);
+                    return Err(e);
+                }
+            };
+        }
+    }
 
+    /// Replace the registered manifest, e.g. after the pack's tools changed.
+    /// The gateway applies it in place without dropping the connection or
+    /// in-flight executions, and tells connected agents about the new tools.
+    ///
+    /// The manifest is kept for re-registration even if sending it fails,
+    /// so a pack that is reconnecting picks it up when the gateway is back.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the pack isn't registered right now or the
+    /// gateway rejects the update.
+    pub async fn update_manifest(&self, manifest: PackManifest) -> Result<PackWelcome, PackError> {
         info!(
-            pack_id = %pack_id,
+            pack_id = %manifest.pack_id,
+            version = %manifest.version,
+            tools = manifest.tools.len(),
+            "Updating pack manifest"
+        );
+        self.set_manifest(manifest.clone()).await;
+
+        let mut client = PackServiceClient::new(self.channel.clone());
+
+        let mut request = tonic::Request::new(manifest);
+        self.sign(&mut request)?;
+
+        let response = client
+            .update_manifest(request)
+            .await
+            .map_err(|e| PackError::RegistrationRejected(e.to_string()))?;
+
+        let welcome = response.into_inner();
+        if !welcome.rejected_tools.is_empty() {
+            warn!(pack_id = %welcome.pack_id, rejected = ?welcome.rejected_tools, "Gateway rejected some updated tools");
+        }
+        Ok(welcome)
+    }
+
+    /// Keep `manifest` for re-registration and redact its tools' inputs
+    /// by its schemas from now on.
+    async fn set_manifest(&self, manifest: PackManifest) {
+        *self.redactors.write().unwrap_or_else(|e| e.into_inner()) = redactors_for(&manifest);
+        *self.manifest.write().await = Some(manifest);
+    }
+
+    /// The most recent manifest for this pack, falling back to `initial`.
+    async fn current_manifest(&self, initial: &PackManifest) -> PackManifest {
+        self.manifest
+            .read()
+            .await
+            .clone()
+            .unwrap_or_else(|| initial.clone())
+    }
+
+    /// Register the manifest and return the execution request stream.
+    async fn register(
+        &self,
+        manifest: &PackManifest,
+    ) -> Result<Streaming<ExecuteToolRequest>, PackError> {
+        info!(
+            pack_id = %manifest.pack_id,
             version = %manifest.version,
             tools = manifest.tools.len(),
             "Registering pack with gateway"
@@ -155,15 +595,9 @@ impl PackClient {
         // Create the gRPC client
         let mut client = PackServiceClient::new(self.channel.clone());
 
-        // Refresh credentials if stale before registration
-        self.refresh_credentials_if_stale().await?;
-
         // Create a request with auth credentials
-        let mut request = tonic::Request::new(manifest);
-        {
-            let creds = self.credentials.read().await;
-            creds.apply_to_request(&mut request)?;
-        }
+        let mut request = tonic::Request::new(manifest.clone());
+        self.sign(&mut request)?;
 
         // Register and get the execution request stream
         let response = client
@@ -171,89 +605,223 @@ impl PackClient {
             .await
             .map_err(|e| PackError::RegistrationRejected(e.to_string()))?;
 
-        let mut stream = response.into_inner();
-
-        info!(pack_id = %pack_id, "Pack registered, waiting for tool requests");
+        Ok(response.into_inner())
+    }
 
-        // Notify handler of successful registration
-        // The gateway doesn't send a PackWelcome in the current protocol,
-        // so we use the pack_id from the manifest
-        handler.on_registered(&pack_id, &[]).await;
+    /// Open the gateway's secrets stream and apply the first set it sends.
+    /// Returns the stream for later updates, or None if the gateway can't
+    /// send secrets; the pack then runs with whatever it had before.
+    async fn watch_secrets(&self, pack_id: &str) -> Option<Streaming<PackSecrets>> {
+        let opened = async {
+            let mut client = PackServiceClient::new(self.channel.clone());
+
+            // The gateway checks this signature against the pack's key and
+            // rejects replays, so sign it afresh on every reconnect
+            let mut request = tonic::Request::new(WatchSecretsRequest {
+                pack_id: pack_id.to_string(),
+            });
+            SshAuthCredentials::new(&self.private_key)
+                .and_then(|creds| creds.apply_to_request(&mut request))
+                .map_err(|e| tonic::Status::unauthenticated(e.to_string()))?;
+
+            let mut stream = client.watch_secrets(request).await?.into_inner();
+            let first = stream.message().await?;
+            Ok::<_, tonic::Status>((stream, first))
+        };
+
+        match tokio::time::timeout(SECRETS_TIMEOUT, opened).await {
+            Ok(Ok((stream, Some(secrets)))) => {
+                info!(pack_id = %pack_id, count = secrets.values.len(), "Received pack secrets");
+                self.secrets.replace(secrets.values);
+                Some(stream)
+            }
+            Ok(Ok((_, None))) => {
+                warn!(pack_id = %pack_id, "Gateway closed the secrets stream without sending any");
+                None
+            }
+            // Gateways from before managed secrets
+            Ok(Err(status)) if status.code() == tonic::Code::Unimplemented => {
+                debug!(pack_id = %pack_id, "Gateway doesn't manage pack secrets");
+                None
+            }
+            Ok(Err(status)) => {
+                warn!(pack_id = %pack_id, error = %status, "Failed to fetch pack secrets");
+                None
+            }
+            Err(_) => {
+                warn!(pack_id = %pack_id, "Timed out waiting for pack secrets");
+                None
+            }
+        }
+    }
 
-        // Process tool execution requests
+    /// Register again after a dropped connection, backing off between
+    /// attempts. The channel re-establishes its transport on demand, so a
+    /// successful registration means the gateway is back.
+    async fn reregister(
+        &self,
+        manifest: &PackManifest,
+    ) -> Result<Streaming<ExecuteToolRequest>, PackError> {
+        let mut attempt = 1;
         loop {
-            match stream.message().await {
-                Ok(Some(request)) => {
-                    let request_id = request.request_id.clone();
-                    let tool_name = request.tool_name.clone();
-
-                    info!(
-                        pack_id = %pack_id,
-                        request_id = %request_id,
-                        tool = %tool_name,
-                        "-> Tool execute"
-                    );
+            let delay = jitter(self.reconnect_backoff(attempt));
+            info!(
+                pack_id = %manifest.pack_id,
+                attempt,
+                delay_ms = delay.as_millis() as u64,
+                "Reconnecting to gateway"
+            );
+            self.set_state(ConnectionState::Reconnecting { attempt, delay });
+            tokio::time::sleep(delay).await;
+
+            match self.register(manifest).await {
+                Ok(stream) => return Ok(stream),
+                Err(e) => {
+                    warn!(pack_id = %manifest.pack_id, attempt, error = %e, "Reconnect failed");
+                    if self
+                        .max_reconnect_attempts
+                        .is_some_and(|max| attempt >= max)
+                    {
+                        error!(
+                            pack_id = %manifest.pack_id,
+                            attempts = attempt,
+                            "Giving up on reconnecting to gateway"
+                        );
+                        return Err(e);
+                    }
+                    attempt += 1;
+                }
+            }
+        }
+    }
 
-                    // Execute the tool
-                    let started = std::time::Instant::now();
-                    let result = handler.execute(&tool_name, &request.input_json).await;
-                    let elapsed = started.elapsed();
-
-                    // Build the response
-                    let response = match result {
-                        Ok(ref output) => {
-                            info!(
-                                pack_id = %pack_id,
-                                request_id = %request_id,
-                                tool = %tool_name,
-                                duration_ms = elapsed.as_millis() as u64,
-                                output_bytes = output.len(),
-                                "<- Tool result: success"
-                            );
-                            ExecuteToolResponse {
-                                request_id,
-                                result: Some(
-                                    coven_proto::execute_tool_response::Result::OutputJson(
-                                        output.clone(),
-                                    ),
-                                ),
-                            }
-                        }
-                        Err(e) => {
-                            warn!(
-                                pack_id = %pack_id,
-                                request_id = %request_id,
-                                tool = %tool_name,
-                                duration_ms = elapsed.as_millis() as u64,
-                                error = %e,
-                                "<- Tool result: error"
-                            );
-                            ExecuteToolResponse {
-                                request_id,
-                                result: Some(coven_proto::execute_tool_response::Result::Error(
-                                    format_tool_error(&e),
-                                )),
-                            }
-                        }
-                    };
-
-                    // Send the result back
-                    self.send_result(&pack_id, response).await?;
+    /// Serve execution requests from `stream` until it ends or fails.
+    async fn serve<H: ToolHandler + 'static>(
+        &self,
+        pack_id: &str,
+        handler: &Arc<H>,
+        mut stream: Streaming<ExecuteToolRequest>,
+        mut secrets: Option<Streaming<PackSecrets>>,
+    ) -> SessionEnd {
+        // Execute requests concurrently; when every slot is busy, further
+        // requests wait in the stream until one finishes
+        let (progress_tx, mut progress_rx) = mpsc::unbounded_channel();
+        let mut executor = ToolExecutor::new(
+            Arc::clone(handler),
+            pack_id,
+            self.max_concurrent_executions,
+            self.execution_timeout,
+        )
+        .with_progress(progress_tx)
+        .with_redactors(Arc::clone(&self.redactors));
+
+        // Health checks run beside the request loop so a slow check never
+        // holds up tool requests
+        let (status_tx, mut status_rx) = mpsc::channel(4);
+        let _health = AbortOnDrop(spawn_health_checks(
+            Arc::clone(handler),
+            pack_id.to_string(),
+            self.health_check_interval,
+            status_tx,
+        ));
+
+        loop {
+            tokio::select! {
+                message = stream.message(), if executor.has_capacity() => match message {
+                    Ok(Some(request)) => executor.spawn(request),
+                    Ok(None) => {
+                        info!(
+                            pack_id = %pack_id,
+                            abandoned = executor.in_flight(),
+                            "Stream closed by gateway"
+                        );
+                        return SessionEnd::StreamClosed;
+                    }
+                    Err(e) => {
+                        error!(
+                            pack_id = %pack_id,
+                            abandoned = executor.in_flight(),
+                            error = %e,
+                            "Stream error"
+                        );
+                        return SessionEnd::Failed(PackError::StreamError(e.to_string()));
+                    }
+                },
+                Some(response) = executor.next_completed() => {
+                    if let Err(e) = self.send_result(pack_id, response).await {
+                        return SessionEnd::Failed(e);
+                    }
                 }
-                Ok(None) => {
-                    info!(pack_id = %pack_id, "Stream closed by gateway");
-                    handler.on_closing(Some("stream closed")).await;
-                    break;
+                Some(progress) = progress_rx.recv() => {
+                    self.send_progress(pack_id, progress).await;
                 }
-                Err(e) => {
-                    error!(pack_id = %pack_id, error = %e, "Stream error");
-                    handler.on_closing(Some(&e.to_string())).await;
-                    return Err(PackError::StreamError(e.to_string()));
+                Some(status) = status_rx.recv() => {
+                    self.send_status(status).await;
                 }
+                update = next_secrets(secrets.as_mut()) => match update {
+                    Ok(Some(update)) => {
+                        info!(pack_id = %pack_id, count = update.values.len(), "Pack secrets updated");
+                        self.secrets.replace(update.values);
+                    }
+                    // Keep the last values; the next registration fetches them again
+                    Ok(None) => {
+                        debug!(pack_id = %pack_id, "Secrets stream closed");
+                        secrets = None;
+                    }
+                    Err(e) => {
+                        warn!(pack_id = %pack_id, error = %e, "Secrets stream failed");
+                        secrets = None;
+                    }
+                },
             }
         }
+    }
 
-        Ok(())
+    /// Send a running tool's progress to the gateway.
+    async fn send_progress(&self, pack_id: &str, progress: PackToolProgress) {
+        self.send_best_effort(
+            pack_id,
+            "tool progress",
+            progress,
+            |mut client, request| async move { client.tool_progress(request).await },
+        )
+        .await;
+    }
+
+    /// Report the pack's health to the gateway.
+    async fn send_status(&self, status: PackStatus) {
+        if !status.healthy {
+            warn!(pack_id = %status.pack_id, reason = %status.message, "Health check failed");
+        }
+
+        let pack_id = status.pack_id.clone();
+        self.send_best_effort(
+            &pack_id,
+            "health status",
+            status,
+            |mut client, request| async move { client.report_status(request).await },
+        )
+        .await;
+    }
+
+    /// Sign `message` and hand it to `send`. Progress and health reports are
+    /// best-effort: failures, including gateways too old to accept them, are
+    /// logged and otherwise ignored, as the next report follows soon.
+    async fn send_best_effort<T, F, Fut>(&self, pack_id: &str, what: &str, message: T, send: F)
+    where
+        F: FnOnce(PackServiceClient<Channel>, tonic::Request<T>) -> Fut,
+        Fut: Future<Output = Result<tonic::Response<()>, tonic::Status>>,
+    {
+        let mut request = tonic::Request::new(message);
+        if let Err(e) = self.sign(&mut request) {
+            debug!(pack_id = %pack_id, error = %e, "Dropping {}", what);
+            return;
+        }
+
+        let client = PackServiceClient::new(self.channel.clone());
+        if let Err(e) = send(client, request).await {
+            debug!(pack_id = %pack_id, error = %e, "Failed to send {}", what);
+        }
     }
 
     /// Send a tool execution result back to the gateway.
@@ -264,14 +832,8 @@ impl PackClient {
     ) -> Result<(), PackError> {
         let mut client = PackServiceClient::new(self.channel.clone());
 
-        // Refresh credentials if stale before sending result
-        self.refresh_credentials_if_stale().await?;
-
         let mut request = tonic::Request::new(response);
-        {
-            let creds = self.credentials.read().await;
-            creds.apply_to_request(&mut request)?;
-        }
+        self.sign(&mut request)?;
 
         client.tool_result(request).await.map_err(|e| {
             error!(pack_id = %pack_id, error = %e, "Failed to send tool result");
@@ -282,49 +844,51 @@ impl PackClient {
     }
 }
 
-/// Format a ToolError for transmission to the gateway.
-fn format_tool_error(error: &ToolError) -> String {
-    match error {
-        ToolError::UnknownTool(name) => format!("unknown tool: {}", name),
-        ToolError::InvalidInput(msg) => format!("invalid input: {}", msg),
-        ToolError::ExecutionFailed(msg) => format!("execution failed: {}", msg),
-        ToolError::Timeout => "execution timed out".to_string(),
-        ToolError::MissingCapability(cap) => format!("missing capability: {}", cap),
-        ToolError::Internal(msg) => format!("internal error: {}", msg),
+/// The next update from the secrets stream, or never if there is none.
+async fn next_secrets(
+    stream: Option<&mut Streaming<PackSecrets>>,
+) -> Result<Option<PackSecrets>, tonic::Status> {
+    match stream {
+        Some(stream) => stream.message().await,
+        None => std::future::pending().await,
     }
 }
 
+/// Randomly shorten `delay` by up to half, so packs that lost the same
+/// gateway don't all reconnect at the same instant.
+fn jitter(delay: Duration) -> Duration {
+    delay.mul_f64(rand::thread_rng().gen_range(0.5..=1.0))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
     #[test]
-    fn test_format_tool_error() {
-        assert_eq!(
-            format_tool_error(&ToolError::UnknownTool("search".to_string())),
-            "unknown tool: search"
-        );
-        assert_eq!(
-            format_tool_error(&ToolError::InvalidInput("missing field".to_string())),
-            "invalid input: missing field"
-        );
-        assert_eq!(
-            format_tool_error(&ToolError::ExecutionFailed(
-                "connection refused".to_string()
-            )),
-            "execution failed: connection refused"
-        );
-        assert_eq!(
-            format_tool_error(&ToolError::Timeout),
-            "execution timed out"
-        );
-        assert_eq!(
-            format_tool_error(&ToolError::MissingCapability("web".to_string())),
-            "missing capability: web"
-        );
-        assert_eq!(
-            format_tool_error(&ToolError::Internal("panic".to_string())),
-            "internal error: panic"
-        );
+    fn test_jitter_stays_within_half_to_full_delay() {
+        let delay = Duration::from_millis(1000);
+        for _ in 0..100 {
+            let jittered = jitter(delay);
+            assert!(jittered >= Duration::from_millis(500), "{:?}", jittered);
+            assert!(jittered <= delay, "{:?}", jittered);
+        }
+    }
+
+    #[test]
+    fn test_retry_policy_backoff_doubles_up_to_max() {
+        let policy = RetryPolicy {
+            max_attempts: 5,
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_millis(350),
+        };
+        assert_eq!(policy.backoff(1), Duration::from_millis(100));
+        assert_eq!(policy.backoff(2), Duration::from_millis(200));
+        assert_eq!(policy.backoff(3), Duration::from_millis(350));
+        assert_eq!(policy.backoff(40), Duration::from_millis(350));
+    }
+
+    #[test]
+    fn test_retry_policy_none_makes_one_attempt() {
+        assert_eq!(RetryPolicy::none().max_attempts, 1);
     }
 }
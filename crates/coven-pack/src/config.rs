@@ -1,9 +1,14 @@
 // ABOUTME: Configuration loading for coven-pack SDK with file, env, and default precedence.
-// ABOUTME: Resolves gateway URL from env vars, .env files, and ~/.config/coven/packs.toml.
+// ABOUTME: Resolves gateway URL, execution limits, health check interval, reconnect behavior, and secrets from env vars, .env files, and ~/.config/coven/packs.toml.
 
+use coven_ssh::PassphraseSource;
 use serde::Deserialize;
 use std::path::PathBuf;
+use std::time::Duration;
 
+use crate::executor::{DEFAULT_EXECUTION_TIMEOUT, DEFAULT_MAX_CONCURRENT_EXECUTIONS};
+use crate::health::DEFAULT_HEALTH_CHECK_INTERVAL;
+use crate::secrets::Secrets;
 use crate::PackError;
 
 /// Raw TOML structure for ~/.config/coven/packs.toml
@@ -11,6 +16,12 @@ use crate::PackError;
 struct PacksToml {
     server: Option<String>,
     port: Option<u16>,
+    ssh_key_passphrase: Option<String>,
+    max_concurrent_executions: Option<usize>,
+    execution_timeout_secs: Option<u64>,
+    health_check_interval_secs: Option<u64>,
+    reconnect: Option<bool>,
+    max_reconnect_attempts: Option<u32>,
 }
 
 /// Resolved pack configuration.
@@ -19,6 +30,21 @@ pub struct PackConfig {
     pub gateway_url: String,
     /// Path to SSH key: ~/.config/coven/packs/{pack_name}/id_ed25519
     pub ssh_key_path: PathBuf,
+    /// Where the passphrase for an encrypted SSH key comes from (default: the key isn't encrypted)
+    pub ssh_key_passphrase: Option<PassphraseSource>,
+    /// How many tool requests may execute at once (default 8)
+    pub max_concurrent_executions: usize,
+    /// How long a single tool execution may run (default 300s)
+    pub execution_timeout: Duration,
+    /// How often the handler's health check is run and reported (default 30s)
+    pub health_check_interval: Duration,
+    /// Whether to reconnect and re-register when the gateway connection drops (default true)
+    pub reconnect: bool,
+    /// Consecutive failed reconnect attempts before giving up (default: retry forever)
+    pub max_reconnect_attempts: Option<u32>,
+    /// Secrets the gateway manages for this pack, filled in once a client
+    /// made with `PackClient::connect_with_config` registers
+    pub secrets: Secrets,
 }
 
 impl PackConfig {
@@ -33,9 +59,28 @@ impl PackConfig {
     /// 1. `COVEN_SSH_KEY_PATH` (explicit path)
     /// 2. `PACK_SSH_KEY` (legacy compat)
     /// 3. Default: ~/.config/coven/packs/{pack_name}/id_ed25519
+    ///
+    /// An encrypted key's passphrase source comes from
+    /// `COVEN_SSH_KEY_PASSPHRASE`, then `ssh_key_passphrase` in packs.toml,
+    /// in the forms `PassphraseSource::parse` accepts (`env:NAME`,
+    /// `cmd:COMMAND`, `prompt`, `pass:PASSPHRASE`).
+    ///
+    /// Execution limits come from `COVEN_PACK_MAX_CONCURRENT_EXECUTIONS` and
+    /// `COVEN_PACK_EXECUTION_TIMEOUT_SECS`, then `max_concurrent_executions`
+    /// and `execution_timeout_secs` in packs.toml, then the defaults.
+    ///
+    /// The health check interval comes from
+    /// `COVEN_PACK_HEALTH_CHECK_INTERVAL_SECS`, then
+    /// `health_check_interval_secs` in packs.toml, then the default.
+    ///
+    /// Reconnect behavior comes from `COVEN_PACK_RECONNECT` and
+    /// `COVEN_PACK_MAX_RECONNECT_ATTEMPTS`, then `reconnect` and
+    /// `max_reconnect_attempts` in packs.toml. By default a pack reconnects
+    /// forever.
     pub fn load(pack_name: &str) -> Result<Self, PackError> {
         // Load .env from cwd (adds to env vars, so env lookups below catch both)
         let _ = dotenvy::dotenv();
+        let toml_config = load_packs_toml();
 
         // Resolve gateway URL: full URL env vars take priority over server/port construction
         let gateway_url = std::env::var("COVEN_GATEWAY_URL")
@@ -43,13 +88,10 @@ impl PackConfig {
             .filter(|s| !s.is_empty())
             .or_else(|| std::env::var("GATEWAY_ADDR").ok().filter(|s| !s.is_empty()))
             .unwrap_or_else(|| {
-                // Load packs.toml for server/port fallback
-                let toml_config = load_packs_toml();
-
                 let server = std::env::var("COVEN_SERVER")
                     .ok()
                     .filter(|s| !s.is_empty())
-                    .or(toml_config.server)
+                    .or(toml_config.server.clone())
                     .unwrap_or_else(|| "localhost".to_string());
 
                 let port = std::env::var("COVEN_PORT")
@@ -72,11 +114,88 @@ impl PackConfig {
                 PackError::ConfigError("could not determine config directory".to_string())
             })?;
 
+        let ssh_key_passphrase = std::env::var("COVEN_SSH_KEY_PASSPHRASE")
+            .ok()
+            .filter(|s| !s.is_empty())
+            .or(toml_config.ssh_key_passphrase.clone())
+            .map(|spec| PassphraseSource::parse(&spec))
+            .transpose()
+            .map_err(|e| PackError::ConfigError(e.to_string()))?;
+
+        // Zero would stall every request, so treat it like an unset value
+        let max_concurrent_executions = env_number("COVEN_PACK_MAX_CONCURRENT_EXECUTIONS")
+            .or(toml_config.max_concurrent_executions)
+            .filter(|n| *n > 0)
+            .unwrap_or(DEFAULT_MAX_CONCURRENT_EXECUTIONS);
+
+        let execution_timeout = env_number("COVEN_PACK_EXECUTION_TIMEOUT_SECS")
+            .or(toml_config.execution_timeout_secs)
+            .filter(|secs| *secs > 0)
+            .map(Duration::from_secs)
+            .unwrap_or(DEFAULT_EXECUTION_TIMEOUT);
+
+        let health_check_interval = env_number("COVEN_PACK_HEALTH_CHECK_INTERVAL_SECS")
+            .or(toml_config.health_check_interval_secs)
+            .filter(|secs| *secs > 0)
+            .map(Duration::from_secs)
+            .unwrap_or(DEFAULT_HEALTH_CHECK_INTERVAL);
+
+        let reconnect = env_bool("COVEN_PACK_RECONNECT")
+            .or(toml_config.reconnect)
+            .unwrap_or(true);
+
+        let max_reconnect_attempts =
+            env_number("COVEN_PACK_MAX_RECONNECT_ATTEMPTS").or(toml_config.max_reconnect_attempts);
+
         Ok(Self {
             gateway_url,
             ssh_key_path,
+            ssh_key_passphrase,
+            max_concurrent_executions,
+            execution_timeout,
+            health_check_interval,
+            reconnect,
+            max_reconnect_attempts,
+            secrets: Secrets::new(),
         })
     }
+
+    /// Look up a secret: the gateway's value if it has one, otherwise the
+    /// environment variable of the same name.
+    ///
+    /// The env fallback keeps packs working against gateways without
+    /// managed secrets and in local development.
+    pub fn secret(&self, key: &str) -> Option<String> {
+        self.secrets
+            .get(key)
+            .or_else(|| std::env::var(key).ok().filter(|v| !v.is_empty()))
+    }
+
+    /// Where `load(pack_name)` looks for the pack's SSH key when no path is
+    /// set in the environment
+    pub fn default_key_path(pack_name: &str) -> Option<PathBuf> {
+        default_pack_key_path(pack_name)
+    }
+}
+
+/// Parse a numeric env var, ignoring it when unset or malformed.
+fn env_number<T: std::str::FromStr>(key: &str) -> Option<T> {
+    std::env::var(key).ok().and_then(|v| v.trim().parse().ok())
+}
+
+/// Parse a boolean env var ("true"/"false", "1"/"0", "yes"/"no"), ignoring
+/// it when unset or malformed.
+fn env_bool(key: &str) -> Option<bool> {
+    match std::env::var(key)
+        .ok()?
+        .trim()
+        .to_ascii_lowercase()
+        .as_str()
+    {
+        "true" | "1" | "yes" => Some(true),
+        "false" | "0" | "no" => Some(false),
+        _ => None,
+    }
 }
 
 /// Load ~/.config/coven/packs.toml, returning defaults if file doesn't exist or can't be parsed.
@@ -326,6 +445,103 @@ mod tests {
         assert_eq!(config.port, Some(7777));
     }
 
+    #[test]
+    fn test_packs_toml_execution_limits() {
+        let toml_str = r#"
+            max_concurrent_executions = 2
+            execution_timeout_secs = 30
+        "#;
+        let config: PacksToml = toml::from_str(toml_str).unwrap();
+        assert_eq!(config.max_concurrent_executions, Some(2));
+        assert_eq!(config.execution_timeout_secs, Some(30));
+    }
+
+    #[test]
+    fn test_pack_config_execution_limits_from_env() {
+        with_env_vars(
+            &[
+                ("COVEN_PACK_MAX_CONCURRENT_EXECUTIONS", "3"),
+                ("COVEN_PACK_EXECUTION_TIMEOUT_SECS", "45"),
+            ],
+            || {
+                let config = PackConfig::load("test-pack").unwrap();
+                assert_eq!(config.max_concurrent_executions, 3);
+                assert_eq!(config.execution_timeout, Duration::from_secs(45));
+            },
+        );
+    }
+
+    #[test]
+    fn test_pack_config_zero_execution_limits_use_defaults() {
+        with_env_vars(
+            &[
+                ("COVEN_PACK_MAX_CONCURRENT_EXECUTIONS", "0"),
+                ("COVEN_PACK_EXECUTION_TIMEOUT_SECS", "0"),
+            ],
+            || {
+                let config = PackConfig::load("test-pack").unwrap();
+                assert_eq!(
+                    config.max_concurrent_executions,
+                    DEFAULT_MAX_CONCURRENT_EXECUTIONS
+                );
+                assert_eq!(config.execution_timeout, DEFAULT_EXECUTION_TIMEOUT);
+            },
+        );
+    }
+
+    #[test]
+    fn test_pack_config_health_check_interval() {
+        with_env_vars(&[("COVEN_PACK_HEALTH_CHECK_INTERVAL_SECS", "5")], || {
+            let config = PackConfig::load("test-pack").unwrap();
+            assert_eq!(config.health_check_interval, Duration::from_secs(5));
+        });
+        with_env_vars(&[("COVEN_PACK_HEALTH_CHECK_INTERVAL_SECS", "0")], || {
+            let config = PackConfig::load("test-pack").unwrap();
+            assert_eq!(config.health_check_interval, DEFAULT_HEALTH_CHECK_INTERVAL);
+        });
+
+        let toml_config: PacksToml = toml::from_str("health_check_interval_secs = 60").unwrap();
+        assert_eq!(toml_config.health_check_interval_secs, Some(60));
+    }
+
+    #[test]
+    fn test_packs_toml_reconnect_settings() {
+        let toml_str = r#"
+            reconnect = false
+            max_reconnect_attempts = 5
+        "#;
+        let config: PacksToml = toml::from_str(toml_str).unwrap();
+        assert_eq!(config.reconnect, Some(false));
+        assert_eq!(config.max_reconnect_attempts, Some(5));
+    }
+
+    #[test]
+    fn test_pack_config_reconnects_forever_by_default() {
+        without_env_vars(
+            &["COVEN_PACK_RECONNECT", "COVEN_PACK_MAX_RECONNECT_ATTEMPTS"],
+            || {
+                let config = PackConfig::load("test-pack").unwrap();
+                assert!(config.reconnect);
+                assert_eq!(config.max_reconnect_attempts, None);
+            },
+        );
+    }
+
+    #[test]
+    fn test_pack_config_reconnect_from_env() {
+        with_env_vars(
+            &[
+                ("COVEN_PACK_RECONNECT", "false"),
+                ("COVEN_PACK_MAX_RECONNECT_ATTEMPTS", "3"),
+            ],
+            || {
+                let config = PackConfig::load("test-pack").unwrap();
+                assert!(!config.reconnect);
+                assert_eq!(config.max_reconnect_attempts, Some(3));
+            },
+        );
+    }
+
     #[test]
     fn test_packs_toml_empty_deserialization() {
         let toml_str = "";
@@ -399,6 +615,47 @@ mod tests {
         });
     }
 
+    #[test]
+    fn test_pack_config_ssh_key_passphrase_env() {
+        with_env_vars(&[("COVEN_SSH_KEY_PASSPHRASE", "env:MY_PACK_PASS")], || {
+            let config = PackConfig::load("test-pack").unwrap();
+            assert!(matches!(
+                config.ssh_key_passphrase,
+                Some(PassphraseSource::Env(ref name)) if name == "MY_PACK_PASS"
+            ));
+        });
+        with_env_vars(&[("COVEN_SSH_KEY_PASSPHRASE", "hunter2")], || {
+            assert!(matches!(
+                PackConfig::load("test-pack"),
+                Err(PackError::ConfigError(_))
+            ));
+        });
+    }
+
+    #[test]
+    fn test_secret_prefers_gateway_value_over_env() {
+        with_env_vars(&[("COVEN_TEST_PACK_SECRET", "from-env")], || {
+            let config = PackConfig::load("test-pack").unwrap();
+            assert_eq!(
+                config.secret("COVEN_TEST_PACK_SECRET").as_deref(),
+                Some("from-env")
+            );
+
+            config.secrets.replace(std::collections::HashMap::from([(
+                "COVEN_TEST_PACK_SECRET".to_string(),
+                "from-gateway".to_string(),
+            )]));
+            assert_eq!(
+                config.secret("COVEN_TEST_PACK_SECRET").as_deref(),
+                Some("from-gateway")
+            );
+        });
+        without_env_vars(&["COVEN_TEST_PACK_SECRET"], || {
+            let config = PackConfig::load("test-pack").unwrap();
+            assert_eq!(config.secret("COVEN_TEST_PACK_SECRET"), None);
+        });
+    }
+
     #[test]
     fn test_config_dir_ignores_empty_xdg() {
         with_env_vars(&[("XDG_CONFIG_HOME", "")], || {
@@ -0,0 +1,93 @@
+// ABOUTME: ExecutionContext carries who a tool call is for alongside its progress reporter.
+// ABOUTME: Built by PackClient from each ExecuteToolRequest and handed to ToolHandler::execute_with_context.
+
+use crate::progress::ProgressReporter;
+use coven_proto::{ExecuteToolRequest, PackToolProgress};
+use tokio::sync::mpsc;
+
+/// Details of a single tool execution beyond its input.
+///
+/// Packs that keep data per agent (todos, notes, caches) use `agent_id` to
+/// keep one agent's data away from another's. Gateways that predate
+/// execution metadata leave both ids unset.
+#[derive(Debug, Clone)]
+pub struct ExecutionContext {
+    /// The gateway's id for this execution
+    pub request_id: String,
+    /// The agent that called the tool
+    pub agent_id: Option<String>,
+    /// The conversation thread the call belongs to, when the gateway knows it
+    pub thread_id: Option<String>,
+    /// Reporter for sending progress while the tool runs
+    pub progress: ProgressReporter,
+}
+
+impl ExecutionContext {
+    pub(crate) fn from_request(
+        request: &ExecuteToolRequest,
+        progress_tx: Option<mpsc::UnboundedSender<PackToolProgress>>,
+    ) -> Self {
+        Self {
+            request_id: request.request_id.clone(),
+            agent_id: request.agent_id.clone().filter(|id| !id.is_empty()),
+            thread_id: request.thread_id.clone().filter(|id| !id.is_empty()),
+            progress: ProgressReporter::new(request.request_id.clone(), progress_tx),
+        }
+    }
+
+    /// Set the calling agent, e.g. when exercising a handler in tests.
+    pub fn with_agent(mut self, agent_id: impl Into<String>) -> Self {
+        self.agent_id = Some(agent_id.into());
+        self
+    }
+
+    /// Set the conversation thread.
+    pub fn with_thread(mut self, thread_id: impl Into<String>) -> Self {
+        self.thread_id = Some(thread_id.into());
+        self
+    }
+}
+
+/// A context with no caller details and progress discarded, for calling a
+/// handler outside a `PackClient`.
+impl Default for ExecutionContext {
+    fn default() -> Self {
+        Self {
+            request_id: String::new(),
+            agent_id: None,
+            thread_id: None,
+            progress: ProgressReporter::disabled(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_request_reads_caller() {
+        let request = ExecuteToolRequest {
+            request_id: "req-1".to_string(),
+            tool_name: "todo_list".to_string(),
+            input_json: "{}".to_string(),
+            agent_id: Some("agent-a".to_string()),
+            thread_id: Some(String::new()),
+        };
+        let ctx = ExecutionContext::from_request(&request, None);
+        assert_eq!(ctx.request_id, "req-1");
+        assert_eq!(ctx.agent_id.as_deref(), Some("agent-a"));
+        // An empty id is the same as no id
+        assert!(ctx.thread_id.is_none());
+        assert_eq!(ctx.progress.request_id(), "req-1");
+    }
+
+    #[test]
+    fn test_builders() {
+        let ctx = ExecutionContext::default()
+            .with_agent("agent-b")
+            .with_thread("thread-1");
+        assert_eq!(ctx.agent_id.as_deref(), Some("agent-b"));
+        assert_eq!(ctx.thread_id.as_deref(), Some("thread-1"));
+    }
+}
@@ -0,0 +1,534 @@
+// ABOUTME: Bounded concurrent execution of tool requests for PackClient.
+// ABOUTME: Runs each request as a task with a timeout, logs redacted inputs, and yields responses tagged with their request id.
+
+use crate::context::ExecutionContext;
+use crate::error::ToolError;
+use crate::handler::ToolHandler;
+use coven_proto::redact::InputRedactor;
+use coven_proto::{ExecuteToolRequest, ExecuteToolResponse, PackManifest, PackToolProgress};
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc;
+use tokio::task::{Id, JoinSet};
+use tracing::{debug, error, info, warn};
+
+/// Default number of tool executions a pack runs at once.
+pub const DEFAULT_MAX_CONCURRENT_EXECUTIONS: usize = 8;
+
+/// Default time a single tool execution may take before it is abandoned.
+pub const DEFAULT_EXECUTION_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// Input redactors by tool name, replaced when the manifest changes.
+pub(crate) type Redactors = Arc<RwLock<HashMap<String, InputRedactor>>>;
+
+/// Input redactors for every tool in `manifest`.
+pub(crate) fn redactors_for(manifest: &PackManifest) -> HashMap<String, InputRedactor> {
+    manifest
+        .tools
+        .iter()
+        .map(|tool| (tool.name.clone(), tool.input_redactor()))
+        .collect()
+}
+
+/// Runs tool requests concurrently, up to a fixed limit.
+///
+/// Callers check `has_capacity` before taking another request off the
+/// stream, so requests beyond the limit wait in the stream rather than
+/// piling up as tasks.
+pub(crate) struct ToolExecutor<H> {
+    handler: Arc<H>,
+    pack_id: String,
+    max_concurrent: usize,
+    timeout: Duration,
+    tasks: JoinSet<ExecuteToolResponse>,
+    /// Request id of each running task, so a panicked task still gets a response
+    request_ids: HashMap<Id, String>,
+    /// Where handlers' progress reports go; None discards them
+    progress_tx: Option<mpsc::UnboundedSender<PackToolProgress>>,
+    /// How to redact each tool's input before it's logged
+    redactors: Redactors,
+}
+
+impl<H: ToolHandler + 'static> ToolExecutor<H> {
+    pub(crate) fn new(
+        handler: Arc<H>,
+        pack_id: impl Into<String>,
+        max_concurrent: usize,
+        timeout: Duration,
+    ) -> Self {
+        Self {
+            handler,
+            pack_id: pack_id.into(),
+            max_concurrent: max_concurrent.max(1),
+            timeout,
+            tasks: JoinSet::new(),
+            request_ids: HashMap::new(),
+            progress_tx: None,
+            redactors: Redactors::default(),
+        }
+    }
+
+    /// Redact logged inputs with these redactors. Tools without one get
+    /// the name heuristic only.
+    pub(crate) fn with_redactors(mut self, redactors: Redactors) -> Self {
+        self.redactors = redactors;
+        self
+    }
+
+    /// `request`'s input with sensitive values replaced, for logging.
+    fn redacted_input(&self, request: &ExecuteToolRequest) -> String {
+        let redactors = self.redactors.read().unwrap_or_else(|e| e.into_inner());
+        match redactors.get(&request.tool_name) {
+            Some(redactor) => redactor.redact(&request.input_json),
+            None => InputRedactor::new().redact(&request.input_json),
+        }
+    }
+
+    /// Send handlers' progress reports to `tx`.
+    pub(crate) fn with_progress(mut self, tx: mpsc::UnboundedSender<PackToolProgress>) -> Self {
+        self.progress_tx = Some(tx);
+        self
+    }
+
+    /// Whether another request can start now.
+    pub(crate) fn has_capacity(&self) -> bool {
+        self.tasks.len() < self.max_concurrent
+    }
+
+    /// Number of executions currently running.
+    pub(crate) fn in_flight(&self) -> usize {
+        self.tasks.len()
+    }
+
+    /// Start executing a request in the background.
+    pub(crate) fn spawn(&mut self, request: ExecuteToolRequest) {
+        let handler = Arc::clone(&self.handler);
+        let pack_id = self.pack_id.clone();
+        let timeout = self.timeout;
+        let request_id = request.request_id.clone();
+        let ctx = ExecutionContext::from_request(&request, self.progress_tx.clone());
+
+        info!(
+            pack_id = %pack_id,
+            request_id = %request.request_id,
+            tool = %request.tool_name,
+            agent_id = request.agent_id.as_deref().unwrap_or(""),
+            in_flight = self.tasks.len() + 1,
+            "-> Tool execute"
+        );
+        if tracing::enabled!(tracing::Level::DEBUG) {
+            debug!(
+                request_id = %request.request_id,
+                input = %self.redacted_input(&request),
+                "-> Tool input"
+            );
+        }
+
+        let handle = self
+            .tasks
+            .spawn(async move { execute(handler.as_ref(), &pack_id, request, ctx, timeout).await });
+        self.request_ids.insert(handle.id(), request_id);
+    }
+
+    /// Wait for the next execution to finish. Returns None when nothing is running.
+    pub(crate) async fn next_completed(&mut self) -> Option<ExecuteToolResponse> {
+        let joined = self.tasks.join_next_with_id().await?;
+        Some(match joined {
+            Ok((id, response)) => {
+                self.request_ids.remove(&id);
+                response
+            }
+            Err(e) => {
+                let request_id = self.request_ids.remove(&e.id()).unwrap_or_default();
+                error!(
+                    pack_id = %self.pack_id,
+                    request_id = %request_id,
+                    error = %e,
+                    "<- Tool execution task failed"
+                );
+                error_response(
+                    request_id,
+                    &ToolError::Internal(format!("tool execution task failed: {}", e)),
+                )
+            }
+        })
+    }
+}
+
+/// Run one request through the handler, bounded by `timeout`.
+async fn execute<H: ToolHandler>(
+    handler: &H,
+    pack_id: &str,
+    request: ExecuteToolRequest,
+    ctx: ExecutionContext,
+    timeout: Duration,
+) -> ExecuteToolResponse {
+    let started = Instant::now();
+    let result = tokio::time::timeout(
+        timeout,
+        handler.execute_with_context(&request.tool_name, &request.input_json, ctx),
+    )
+    .await
+    .unwrap_or(Err(ToolError::Timeout));
+    let elapsed = started.elapsed();
+
+    match result {
+        Ok(output) => {
+            info!(
+                pack_id = %pack_id,
+                request_id = %request.request_id,
+                tool = %request.tool_name,
+                duration_ms = elapsed.as_millis() as u64,
+                output_bytes = output.len(),
+                "<- Tool result: success"
+            );
+            ExecuteToolResponse {
+                request_id: request.request_id,
+                result: Some(coven_proto::execute_tool_response::Result::OutputJson(
+                    output,
+                )),
+            }
+        }
+        Err(e) => {
+            warn!(
+                pack_id = %pack_id,
+                request_id = %request.request_id,
+                tool = %request.tool_name,
+                duration_ms = elapsed.as_millis() as u64,
+                error = %e,
+                "<- Tool result: error"
+            );
+            error_response(request.request_id, &e)
+        }
+    }
+}
+
+fn error_response(request_id: String, error: &ToolError) -> ExecuteToolResponse {
+    ExecuteToolResponse {
+        request_id,
+        result: Some(coven_proto::execute_tool_response::Result::Error(
+            format_tool_error(error),
+        )),
+    }
+}
+
+/// Format a ToolError for transmission to the gateway.
+pub(crate) fn format_tool_error(error: &ToolError) -> String {
+    match error {
+        ToolError::UnknownTool(name) => format!("unknown tool: {}", name),
+        ToolError::InvalidInput(msg) => format!("invalid input: {}", msg),
+        ToolError::ExecutionFailed(msg) => format!("execution failed: {}", msg),
+        ToolError::Timeout => "execution timed out".to_string(),
+        ToolError::MissingCapability(cap) => format!("missing capability: {}", cap),
+        ToolError::Internal(msg) => format!("internal error: {}", msg),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::progress::ProgressReporter;
+    use async_trait::async_trait;
+    use coven_proto::execute_tool_response::Result as ToolResult;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// Sleeps for the number of milliseconds given as input, tracking how
+    /// many executions overlap.
+    #[derive(Default)]
+    struct SleepHandler {
+        running: AtomicUsize,
+        peak: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl ToolHandler for SleepHandler {
+        async fn execute(&self, tool_name: &str, input_json: &str) -> Result<String, ToolError> {
+            if tool_name == "panic" {
+                panic!("handler panicked");
+            }
+            let now = self.running.fetch_add(1, Ordering::SeqCst) + 1;
+            self.peak.fetch_max(now, Ordering::SeqCst);
+            let millis: u64 = input_json.parse().unwrap_or(0);
+            tokio::time::sleep(Duration::from_millis(millis)).await;
+            self.running.fetch_sub(1, Ordering::SeqCst);
+            Ok(format!(r#"{{"slept_ms": {}}}"#, millis))
+        }
+    }
+
+    fn request(id: &str, tool: &str, input: &str) -> ExecuteToolRequest {
+        ExecuteToolRequest {
+            request_id: id.to_string(),
+            tool_name: tool.to_string(),
+            input_json: input.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_limit_two_overlaps_two_and_queues_third() {
+        let handler = Arc::new(SleepHandler::default());
+        let mut executor = ToolExecutor::new(
+            Arc::clone(&handler),
+            "test-pack",
+            2,
+            DEFAULT_EXECUTION_TIMEOUT,
+        );
+
+        executor.spawn(request("slow", "sleep", "200"));
+        assert!(executor.has_capacity());
+        executor.spawn(request("fast", "sleep", "50"));
+        // A third request has to wait for a slot
+        assert!(!executor.has_capacity());
+        assert_eq!(executor.in_flight(), 2);
+
+        // The fast request finishes first even though it started second
+        let first = executor.next_completed().await.unwrap();
+        assert_eq!(first.request_id, "fast");
+        assert!(executor.has_capacity());
+        assert_eq!(handler.peak.load(Ordering::SeqCst), 2);
+
+        executor.spawn(request("queued", "sleep", "10"));
+        let mut rest = vec![
+            executor.next_completed().await.unwrap().request_id,
+            executor.next_completed().await.unwrap().request_id,
+        ];
+        rest.sort();
+        assert_eq!(rest, vec!["queued", "slow"]);
+        assert!(executor.next_completed().await.is_none());
+        assert_eq!(handler.peak.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_responses_keep_request_ids() {
+        let mut executor = ToolExecutor::new(
+            Arc::new(SleepHandler::default()),
+            "test-pack",
+            8,
+            DEFAULT_EXECUTION_TIMEOUT,
+        );
+        for i in 0..4 {
+            executor.spawn(request(
+                &format!("req-{}", i),
+                "sleep",
+                &((4 - i) * 10).to_string(),
+            ));
+        }
+
+        let mut seen = Vec::new();
+        while let Some(response) = executor.next_completed().await {
+            let expected = format!(
+                r#"{{"slept_ms": {}}}"#,
+                (4 - response.request_id[4..].parse::<u64>().unwrap()) * 10
+            );
+            assert_eq!(response.result, Some(ToolResult::OutputJson(expected)));
+            seen.push(response.request_id);
+        }
+        seen.sort();
+        assert_eq!(seen, vec!["req-0", "req-1", "req-2", "req-3"]);
+    }
+
+    #[tokio::test]
+    async fn test_timeout_returns_structured_error() {
+        let mut executor = ToolExecutor::new(
+            Arc::new(SleepHandler::default()),
+            "test-pack",
+            2,
+            Duration::from_millis(20),
+        );
+        executor.spawn(request("stuck", "sleep", "5000"));
+
+        let response = executor.next_completed().await.unwrap();
+        assert_eq!(response.request_id, "stuck");
+        assert_eq!(
+            response.result,
+            Some(ToolResult::Error("execution timed out".to_string()))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_panicking_handler_still_answers_request() {
+        let mut executor = ToolExecutor::new(
+            Arc::new(SleepHandler::default()),
+            "test-pack",
+            2,
+            DEFAULT_EXECUTION_TIMEOUT,
+        );
+        executor.spawn(request("boom", "panic", ""));
+
+        let response = executor.next_completed().await.unwrap();
+        assert_eq!(response.request_id, "boom");
+        assert!(matches!(
+            response.result,
+            Some(ToolResult::Error(ref msg)) if msg.starts_with("internal error: tool execution task failed")
+        ));
+    }
+
+    /// Answers with the calling agent's id.
+    struct WhoAmIHandler;
+
+    #[async_trait]
+    impl ToolHandler for WhoAmIHandler {
+        async fn execute(&self, _tool_name: &str, _input_json: &str) -> Result<String, ToolError> {
+            Ok("null".to_string())
+        }
+
+        async fn execute_with_context(
+            &self,
+            _tool_name: &str,
+            _input_json: &str,
+            ctx: ExecutionContext,
+        ) -> Result<String, ToolError> {
+            Ok(serde_json::to_string(&ctx.agent_id).unwrap())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_handler_sees_calling_agent() {
+        let mut executor = ToolExecutor::new(
+            Arc::new(WhoAmIHandler),
+            "test-pack",
+            2,
+            DEFAULT_EXECUTION_TIMEOUT,
+        );
+        executor.spawn(ExecuteToolRequest {
+            agent_id: Some("agent-a".to_string()),
+            ..request("who-1", "whoami", "{}")
+        });
+        executor.spawn(request("who-2", "whoami", "{}"));
+
+        let mut responses = vec![
+            executor.next_completed().await.unwrap(),
+            executor.next_completed().await.unwrap(),
+        ];
+        responses.sort_by(|a, b| a.request_id.cmp(&b.request_id));
+        assert_eq!(
+            responses[0].result,
+            Some(ToolResult::OutputJson(r#""agent-a""#.to_string()))
+        );
+        assert_eq!(
+            responses[1].result,
+            Some(ToolResult::OutputJson("null".to_string()))
+        );
+    }
+
+    /// Reports halfway progress before finishing.
+    struct ProgressHandler;
+
+    #[async_trait]
+    impl ToolHandler for ProgressHandler {
+        async fn execute(&self, _tool_name: &str, _input_json: &str) -> Result<String, ToolError> {
+            Ok("{}".to_string())
+        }
+
+        async fn execute_with_progress(
+            &self,
+            tool_name: &str,
+            input_json: &str,
+            progress: ProgressReporter,
+        ) -> Result<String, ToolError> {
+            progress.report(Some(50), "halfway");
+            self.execute(tool_name, input_json).await
+        }
+    }
+
+    #[tokio::test]
+    async fn test_progress_reports_carry_request_id() {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let mut executor = ToolExecutor::new(
+            Arc::new(ProgressHandler),
+            "test-pack",
+            2,
+            DEFAULT_EXECUTION_TIMEOUT,
+        )
+        .with_progress(tx);
+        executor.spawn(request("build-1", "build", "{}"));
+
+        let response = executor.next_completed().await.unwrap();
+        assert_eq!(response.request_id, "build-1");
+        let progress = rx.recv().await.unwrap();
+        assert_eq!(progress.request_id, "build-1");
+        assert_eq!(progress.percent, Some(50));
+        assert_eq!(progress.message, "halfway");
+    }
+
+    #[test]
+    fn test_zero_limit_still_runs_one() {
+        let executor = ToolExecutor::new(
+            Arc::new(SleepHandler::default()),
+            "test-pack",
+            0,
+            DEFAULT_EXECUTION_TIMEOUT,
+        );
+        assert!(executor.has_capacity());
+    }
+
+    #[test]
+    fn test_logged_input_is_redacted() {
+        let manifest = crate::ManifestBuilder::new("test-pack", "1.0.0")
+            .tool(
+                "login",
+                "Log in",
+                r#"{"type": "object", "properties": {"pin": {"type": "string", "sensitive": true}}}"#,
+                &[],
+            )
+            .build();
+        let redactors: Redactors = Arc::new(RwLock::new(redactors_for(&manifest)));
+        let executor = ToolExecutor::new(
+            Arc::new(SleepHandler::default()),
+            "test-pack",
+            1,
+            DEFAULT_EXECUTION_TIMEOUT,
+        )
+        .with_redactors(Arc::clone(&redactors));
+
+        // Schema annotations and the name heuristic both apply
+        let logged = executor.redacted_input(&request(
+            "req-1",
+            "login",
+            r#"{"user": "me", "pin": "1234", "password": "hunter2"}"#,
+        ));
+        assert!(logged.contains(r#""user":"me""#));
+        assert!(!logged.contains("1234"));
+        assert!(!logged.contains("hunter2"));
+
+        // Tools missing from the manifest still get the heuristic
+        let logged = executor.redacted_input(&request("req-2", "other", r#"{"api_key": "sk-1"}"#));
+        assert!(!logged.contains("sk-1"));
+
+        // Manifest updates are picked up
+        redactors.write().unwrap().clear();
+        let logged = executor.redacted_input(&request("req-3", "login", r#"{"pin": "1234"}"#));
+        assert!(logged.contains("1234"));
+    }
+
+    #[test]
+    fn test_format_tool_error() {
+        assert_eq!(
+            format_tool_error(&ToolError::UnknownTool("search".to_string())),
+            "unknown tool: search"
+        );
+        assert_eq!(
+            format_tool_error(&ToolError::InvalidInput("missing field".to_string())),
+            "invalid input: missing field"
+        );
+        assert_eq!(
+            format_tool_error(&ToolError::ExecutionFailed(
+                "connection refused".to_string()
+            )),
+            "execution failed: connection refused"
+        );
+        assert_eq!(
+            format_tool_error(&ToolError::Timeout),
+            "execution timed out"
+        );
+        assert_eq!(
+            format_tool_error(&ToolError::MissingCapability("web".to_string())),
+            "missing capability: web"
+        );
+        assert_eq!(
+            format_tool_error(&ToolError::Internal("panic".to_string())),
+            "internal error: panic"
+        );
+    }
+}
@@ -1,7 +1,10 @@
 // ABOUTME: ToolHandler trait for implementing tool execution logic.
 // ABOUTME: Packs implement this trait to define how their tools execute.
 
+use crate::context::ExecutionContext;
 use crate::error::ToolError;
+use crate::health::HealthStatus;
+use crate::progress::ProgressReporter;
 use async_trait::async_trait;
 
 /// Trait for handling tool execution requests.
@@ -66,6 +69,49 @@ pub trait ToolHandler: Send + Sync {
     /// - `ToolError::Timeout` - The tool took too long to execute
     async fn execute(&self, tool_name: &str, input_json: &str) -> Result<String, ToolError>;
 
+    /// Execute a tool, with a reporter for sending progress while it runs.
+    ///
+    /// Long-running tools override it and call `progress.report(...)` so the
+    /// agent sees they are still working; the default ignores the reporter
+    /// and calls `execute`.
+    async fn execute_with_progress(
+        &self,
+        tool_name: &str,
+        input_json: &str,
+        _progress: ProgressReporter,
+    ) -> Result<String, ToolError> {
+        self.execute(tool_name, input_json).await
+    }
+
+    /// Execute a tool knowing who called it.
+    ///
+    /// This is what the pack client calls. Packs that keep data per agent
+    /// override it and read `ctx.agent_id`; progress is reported through
+    /// `ctx.progress`. The default hands the reporter to
+    /// `execute_with_progress`, so handlers written against the older
+    /// methods keep working unchanged.
+    async fn execute_with_context(
+        &self,
+        tool_name: &str,
+        input_json: &str,
+        ctx: ExecutionContext,
+    ) -> Result<String, ToolError> {
+        self.execute_with_progress(tool_name, input_json, ctx.progress)
+            .await
+    }
+
+    /// Check whether the pack's dependencies (database, subprocess, remote
+    /// API) are working.
+    ///
+    /// The pack client calls this right after registering and then on an
+    /// interval, and reports the result to the gateway, which marks the
+    /// pack's tools degraded while it is unhealthy. Checks should be cheap;
+    /// one that takes longer than 10 seconds counts as unhealthy. The
+    /// default always reports healthy.
+    async fn health_check(&self) -> HealthStatus {
+        HealthStatus::Healthy
+    }
+
     /// Called when the pack successfully registers with the gateway.
     ///
     /// Override this method to perform any setup after registration completes.
@@ -185,6 +231,24 @@ mod tests {
             .await;
         handler.on_closing(None).await;
         handler.on_closing(Some("shutdown")).await;
+        assert_eq!(handler.health_check().await, HealthStatus::Healthy);
+    }
+
+    #[tokio::test]
+    async fn test_default_execute_with_progress_calls_execute() {
+        let handler = TestHandler;
+        let result = handler
+            .execute_with_progress("echo", "{}", ProgressReporter::disabled())
+            .await;
+        assert_eq!(result.unwrap(), "{}");
+    }
+
+    #[tokio::test]
+    async fn test_default_execute_with_context_calls_execute() {
+        let handler = TestHandler;
+        let ctx = ExecutionContext::default().with_agent("agent-a");
+        let result = handler.execute_with_context("echo", "{}", ctx).await;
+        assert_eq!(result.unwrap(), "{}");
     }
 
     #[tokio::test]
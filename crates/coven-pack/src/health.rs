@@ -0,0 +1,196 @@
+// ABOUTME: Pack health checks: the HealthStatus a handler reports and the loop that polls it.
+// ABOUTME: PackClient runs the loop while registered and forwards each result to the gateway.
+
+use crate::handler::ToolHandler;
+use coven_proto::PackStatus;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tokio::time::MissedTickBehavior;
+
+/// How often the handler's health check runs while the pack is registered.
+pub const DEFAULT_HEALTH_CHECK_INTERVAL: Duration = Duration::from_secs(30);
+
+/// A health check that hasn't answered within this long counts as unhealthy.
+const HEALTH_CHECK_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Result of `ToolHandler::health_check`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HealthStatus {
+    /// The pack's dependencies are working.
+    Healthy,
+    /// Something the tools depend on is broken; the reason is shown to
+    /// agents and in `coven admin packs`.
+    Unhealthy(String),
+}
+
+impl HealthStatus {
+    /// An unhealthy status with the given reason.
+    pub fn unhealthy(reason: impl Into<String>) -> Self {
+        Self::Unhealthy(reason.into())
+    }
+
+    pub fn is_healthy(&self) -> bool {
+        matches!(self, Self::Healthy)
+    }
+
+    pub(crate) fn to_status(&self, pack_id: &str) -> PackStatus {
+        match self {
+            Self::Healthy => PackStatus {
+                pack_id: pack_id.to_string(),
+                healthy: true,
+                message: String::new(),
+            },
+            Self::Unhealthy(reason) => PackStatus {
+                pack_id: pack_id.to_string(),
+                healthy: false,
+                message: reason.clone(),
+            },
+        }
+    }
+}
+
+/// Run `handler`'s health check now and then every `interval`, sending
+/// each result to `tx`. A check that takes longer than the timeout is
+/// reported as unhealthy. The task ends once `tx`'s receiver is dropped;
+/// abort it to stop sooner.
+pub(crate) fn spawn_health_checks<H: ToolHandler + 'static>(
+    handler: Arc<H>,
+    pack_id: String,
+    interval: Duration,
+    tx: mpsc::Sender<PackStatus>,
+) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        // A slow check delays the next one rather than triggering a burst
+        ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+        loop {
+            ticker.tick().await;
+            let status =
+                match tokio::time::timeout(HEALTH_CHECK_TIMEOUT, handler.health_check()).await {
+                    Ok(status) => status,
+                    Err(_) => HealthStatus::unhealthy(format!(
+                        "health check timed out after {}s",
+                        HEALTH_CHECK_TIMEOUT.as_secs()
+                    )),
+                };
+            if tx.send(status.to_status(&pack_id)).await.is_err() {
+                return;
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ToolError;
+    use async_trait::async_trait;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// Healthy for the first `healthy_checks` calls, then unhealthy.
+    struct FlakyHandler {
+        checks: AtomicUsize,
+        healthy_checks: usize,
+    }
+
+    #[async_trait]
+    impl ToolHandler for FlakyHandler {
+        async fn execute(&self, tool_name: &str, _input_json: &str) -> Result<String, ToolError> {
+            Err(ToolError::UnknownTool(tool_name.to_string()))
+        }
+
+        async fn health_check(&self) -> HealthStatus {
+            if self.checks.fetch_add(1, Ordering::SeqCst) < self.healthy_checks {
+                HealthStatus::Healthy
+            } else {
+                HealthStatus::unhealthy("database is locked")
+            }
+        }
+    }
+
+    struct HangingHandler;
+
+    #[async_trait]
+    impl ToolHandler for HangingHandler {
+        async fn execute(&self, tool_name: &str, _input_json: &str) -> Result<String, ToolError> {
+            Err(ToolError::UnknownTool(tool_name.to_string()))
+        }
+
+        async fn health_check(&self) -> HealthStatus {
+            std::future::pending().await
+        }
+    }
+
+    #[test]
+    fn test_status_to_proto() {
+        let healthy = HealthStatus::Healthy.to_status("pack");
+        assert!(healthy.healthy);
+        assert_eq!(healthy.pack_id, "pack");
+        assert!(healthy.message.is_empty());
+
+        let unhealthy = HealthStatus::unhealthy("MCP server exited").to_status("pack");
+        assert!(!unhealthy.healthy);
+        assert_eq!(unhealthy.message, "MCP server exited");
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_checks_run_immediately_then_every_interval() {
+        let handler = Arc::new(FlakyHandler {
+            checks: AtomicUsize::new(0),
+            healthy_checks: 2,
+        });
+        let (tx, mut rx) = mpsc::channel(8);
+        let interval = Duration::from_secs(30);
+        let task = spawn_health_checks(Arc::clone(&handler), "pack".into(), interval, tx);
+
+        let start = tokio::time::Instant::now();
+        assert!(rx.recv().await.unwrap().healthy);
+        assert_eq!(start.elapsed(), Duration::ZERO);
+
+        assert!(rx.recv().await.unwrap().healthy);
+        assert_eq!(start.elapsed(), interval);
+
+        let third = rx.recv().await.unwrap();
+        assert!(!third.healthy);
+        assert_eq!(third.message, "database is locked");
+        assert_eq!(start.elapsed(), interval * 2);
+
+        task.abort();
+        assert_eq!(handler.checks.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_hanging_check_reports_unhealthy() {
+        let (tx, mut rx) = mpsc::channel(8);
+        let task = spawn_health_checks(
+            Arc::new(HangingHandler),
+            "pack".into(),
+            DEFAULT_HEALTH_CHECK_INTERVAL,
+            tx,
+        );
+
+        let status = rx.recv().await.unwrap();
+        assert!(!status.healthy);
+        assert!(status.message.contains("timed out"));
+        task.abort();
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_checks_stop_when_receiver_dropped() {
+        let (tx, rx) = mpsc::channel(8);
+        let task = spawn_health_checks(
+            Arc::new(FlakyHandler {
+                checks: AtomicUsize::new(0),
+                healthy_checks: usize::MAX,
+            }),
+            "pack".into(),
+            Duration::from_secs(1),
+            tx,
+        );
+        drop(rx);
+        task.await.unwrap();
+    }
+}
@@ -1,5 +1,5 @@
 // ABOUTME: Rust SDK for building tool packs that connect to coven-gateway.
-// ABOUTME: Provides ManifestBuilder, ToolHandler trait, and PackClient for pack development.
+// ABOUTME: Provides ManifestBuilder, ToolHandler trait, typed tools, health checks, secrets, and PackClient for pack development.
 
 //! # coven-pack
 //!
@@ -43,19 +43,131 @@
 //!     client.run(manifest, MyHandler).await
 //! }
 //! ```
+//!
+//! ## Starting Before the Gateway
+//!
+//! `connect` fails at once if the gateway isn't listening. Packs started
+//! alongside the gateway can wait for it instead:
+//!
+//! ```ignore
+//! use coven_pack::RetryPolicy;
+//!
+//! let client = PackClient::connect_with_retry(
+//!     &config.gateway_url,
+//!     &config.ssh_key_path,
+//!     RetryPolicy::default(),
+//! )
+//! .await?
+//! .with_config(&config);
+//! ```
+//!
+//! ## Typed Tools
+//!
+//! Instead of matching on tool names and parsing JSON by hand, derive
+//! `ToolInput` on the input struct to generate its schema, and register
+//! plain async functions with `TypedHandler`:
+//!
+//! ```ignore
+//! use coven_pack::{ManifestBuilder, ToolError, ToolInput, TypedHandler};
+//! use serde::{Deserialize, Serialize};
+//! use std::sync::Arc;
+//!
+//! #[derive(Deserialize, ToolInput)]
+//! struct GreetInput {
+//!     /// Who to greet
+//!     name: String,
+//! }
+//!
+//! #[derive(Serialize)]
+//! struct GreetOutput {
+//!     message: String,
+//! }
+//!
+//! async fn greet(_: Arc<()>, input: GreetInput) -> Result<GreetOutput, ToolError> {
+//!     Ok(GreetOutput { message: format!("Hello, {}!", input.name) })
+//! }
+//!
+//! let manifest = ManifestBuilder::new("my-pack", "1.0.0")
+//!     .typed_tool::<GreetInput>("greet", "Greets the user", &[])
+//!     .build();
+//! let handler = TypedHandler::new().tool("greet", greet);
+//! ```
+//!
+//! ## Changing Tools at Runtime
+//!
+//! Packs whose tools aren't known up front (e.g. ones that wrap another tool
+//! server) can replace their manifest while `run` is serving requests.
+//! Connected agents see the new tools without either side reconnecting:
+//!
+//! ```ignore
+//! let client = Arc::new(PackClient::connect(&config.gateway_url, &config.ssh_key_path).await?);
+//! tokio::spawn({
+//!     let client = Arc::clone(&client);
+//!     async move { client.run(manifest, handler).await }
+//! });
+//!
+//! let updated = ManifestBuilder::new("my-pack", "1.0.1")
+//!     .tool("greet", "Greets the user", r#"{"type": "object"}"#, &[])
+//!     .tool("wave", "Waves at the user", r#"{"type": "object"}"#, &[])
+//!     .build();
+//! client.update_manifest(updated).await?;
+//! ```
+//!
+//! ## Secrets
+//!
+//! API keys and other credentials can live on the gateway instead of in the
+//! pack's environment. Operators set them with
+//! `coven admin secrets set <pack-id> <KEY> <value>`; the gateway stores them
+//! encrypted and sends them to the pack each time it registers, and again
+//! whenever one changes. Look them up when a tool runs so updates apply
+//! without a restart:
+//!
+//! ```ignore
+//! let config = PackConfig::load("github-pack")?;
+//! let client = PackClient::connect_with_config(&config).await?;
+//!
+//! // Later, inside a tool: the gateway's value, else the GITHUB_TOKEN env var
+//! let token = config.secret("GITHUB_TOKEN");
+//! ```
 
 mod client;
 mod config;
+mod context;
 mod error;
+mod executor;
 mod handler;
+mod health;
 mod manifest;
+mod progress;
+mod secrets;
+mod typed;
 
 // Re-export primary types
-pub use client::PackClient;
+pub use client::{ConnectionState, PackClient, RetryPolicy, RECONNECTING_REASON};
 pub use config::PackConfig;
+pub use context::ExecutionContext;
 pub use error::{PackError, ToolError};
+pub use executor::{DEFAULT_EXECUTION_TIMEOUT, DEFAULT_MAX_CONCURRENT_EXECUTIONS};
 pub use handler::{FnHandler, ToolHandler};
+pub use health::{HealthStatus, DEFAULT_HEALTH_CHECK_INTERVAL};
 pub use manifest::{ManifestBuilder, SchemaBuilder};
+pub use progress::ProgressReporter;
+pub use secrets::Secrets;
+pub use typed::{ToolInput, ToolSchema, TypedHandler};
+
+/// Derive macro for `ToolInput`.
+pub use coven_pack_derive::ToolInput;
 
 // Re-export proto types for convenience
-pub use coven_proto::{ExecuteToolRequest, ExecuteToolResponse, PackManifest, ToolDefinition};
+pub use coven_proto::redact::{InputRedactor, REDACTED};
+pub use coven_proto::{
+    ExecuteToolRequest, ExecuteToolResponse, PackManifest, PackStatus, PackToolProgress,
+    PackWelcome, ToolDefinition,
+};
+
+/// Dependencies of the `ToolInput` derive's generated code. Not public API.
+#[doc(hidden)]
+pub mod __private {
+    pub use serde;
+    pub use serde_json;
+}
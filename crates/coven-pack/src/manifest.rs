@@ -1,6 +1,7 @@
 // ABOUTME: Fluent builder for PackManifest construction.
 // ABOUTME: Provides ergonomic API for defining pack tools with JSON schemas.
 
+use crate::typed::ToolInput;
 use coven_proto::{PackManifest, ToolDefinition};
 
 /// Builder for constructing a `PackManifest`.
@@ -93,6 +94,40 @@ impl ManifestBuilder {
         )
     }
 
+    /// Add a tool whose input schema is generated from its input type.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use coven_pack::{ManifestBuilder, ToolInput};
+    /// use serde::Deserialize;
+    ///
+    /// #[derive(Deserialize, ToolInput)]
+    /// struct FetchInput {
+    ///     /// URL to fetch
+    ///     url: String,
+    /// }
+    ///
+    /// let manifest = ManifestBuilder::new("my-pack", "1.0.0")
+    ///     .typed_tool::<FetchInput>("fetch", "Fetch a URL", &["web"])
+    ///     .build();
+    ///
+    /// assert_eq!(manifest.tools[0].input_schema_json, FetchInput::input_schema_json());
+    /// ```
+    pub fn typed_tool<I: ToolInput>(
+        self,
+        name: impl Into<String>,
+        description: impl Into<String>,
+        required_capabilities: &[&str],
+    ) -> Self {
+        self.tool(
+            name,
+            description,
+            I::input_schema_json(),
+            required_capabilities,
+        )
+    }
+
     /// Add a tool to the manifest with a custom timeout.
     ///
     /// # Arguments
@@ -123,10 +158,42 @@ impl ManifestBuilder {
                 .map(|s| s.to_string())
                 .collect(),
             timeout_seconds,
+            confirm_message: None,
+            degraded_reason: None,
         });
         self
     }
 
+    /// Attach a confirmation message to the most recently added tool.
+    ///
+    /// The message is shown prominently in approval prompts (e.g., "This will
+    /// delete 3 files") instead of the raw input preview. Tools that declare a
+    /// confirmation message always require approval before executing.
+    ///
+    /// Has no effect if no tool has been added yet.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use coven_pack::ManifestBuilder;
+    ///
+    /// let manifest = ManifestBuilder::new("files", "1.0.0")
+    ///     .tool("delete", "Delete files", r#"{"type": "object"}"#, &["fs"])
+    ///     .confirm_message("This will permanently delete the selected files")
+    ///     .build();
+    ///
+    /// assert_eq!(
+    ///     manifest.tools[0].confirm_message.as_deref(),
+    ///     Some("This will permanently delete the selected files")
+    /// );
+    /// ```
+    pub fn confirm_message(mut self, message: impl Into<String>) -> Self {
+        if let Some(tool) = self.tools.last_mut() {
+            tool.confirm_message = Some(message.into());
+        }
+        self
+    }
+
     /// Add a pre-built ToolDefinition to the manifest.
     ///
     /// Useful when you have a ToolDefinition from another source.
@@ -241,6 +308,14 @@ impl SchemaBuilder {
         self
     }
 
+    /// Mark this property as holding a secret, so its value is redacted
+    /// wherever tool inputs are logged. Names like `password` or `api_key`
+    /// are redacted anyway; pass false to opt one of those out.
+    pub fn sensitive(mut self, sensitive: bool) -> Self {
+        self.value["sensitive"] = serde_json::Value::Bool(sensitive);
+        self
+    }
+
     /// Add a property to an object schema.
     pub fn property(mut self, name: impl Into<String>, schema: SchemaBuilder) -> Self {
         if let Some(props) = self.value.get_mut("properties") {
@@ -322,6 +397,8 @@ mod tests {
             input_schema_json: "{}".to_string(),
             required_capabilities: vec![],
             timeout_seconds: 45,
+            confirm_message: None,
+            degraded_reason: None,
         };
 
         let manifest = ManifestBuilder::new("pack", "1.0.0").add_tool(tool).build();
@@ -331,6 +408,30 @@ mod tests {
         assert_eq!(manifest.tools[0].timeout_seconds, 45);
     }
 
+    #[test]
+    fn test_manifest_builder_confirm_message() {
+        let manifest = ManifestBuilder::new("pack", "1.0.0")
+            .tool("safe", "Safe", "{}", &[])
+            .tool("delete", "Delete", "{}", &[])
+            .confirm_message("This will delete files")
+            .build();
+
+        assert!(manifest.tools[0].confirm_message.is_none());
+        assert_eq!(
+            manifest.tools[1].confirm_message.as_deref(),
+            Some("This will delete files")
+        );
+    }
+
+    #[test]
+    fn test_manifest_builder_confirm_message_without_tool() {
+        let manifest = ManifestBuilder::new("pack", "1.0.0")
+            .confirm_message("ignored")
+            .build();
+
+        assert!(manifest.tools.is_empty());
+    }
+
     #[test]
     fn test_manifest_builder_accessors() {
         let builder = ManifestBuilder::new("test", "1.0.0")
@@ -374,6 +475,16 @@ mod tests {
         assert_eq!(parsed["default"], 10);
     }
 
+    #[test]
+    fn test_schema_builder_sensitive() {
+        let schema = SchemaBuilder::object()
+            .property("pin", SchemaBuilder::string().sensitive(true))
+            .build();
+
+        let parsed: serde_json::Value = serde_json::from_str(&schema).unwrap();
+        assert_eq!(parsed["properties"]["pin"]["sensitive"], true);
+    }
+
     #[test]
     fn test_schema_builder_enum() {
         let schema = SchemaBuilder::string()
@@ -0,0 +1,91 @@
+// ABOUTME: ProgressReporter lets long-running tools report intermediate progress.
+// ABOUTME: Reports are queued for PackClient to send to the gateway while the tool keeps running.
+
+use coven_proto::PackToolProgress;
+use tokio::sync::mpsc;
+
+/// Reports progress of one tool execution back to the agent that called it.
+///
+/// Handed to `ToolHandler::execute_with_progress`. Reporting never blocks or
+/// fails; reports are delivered best-effort while the tool keeps running, and
+/// gateways that don't support progress simply drop them.
+///
+/// # Example
+///
+/// ```
+/// use coven_pack::ProgressReporter;
+///
+/// let progress = ProgressReporter::disabled();
+/// progress.report(Some(25), "Indexed 250 of 1000 files");
+/// progress.report(None, "Writing index");
+/// ```
+#[derive(Debug, Clone)]
+pub struct ProgressReporter {
+    request_id: String,
+    tx: Option<mpsc::UnboundedSender<PackToolProgress>>,
+}
+
+impl ProgressReporter {
+    pub(crate) fn new(
+        request_id: impl Into<String>,
+        tx: Option<mpsc::UnboundedSender<PackToolProgress>>,
+    ) -> Self {
+        Self {
+            request_id: request_id.into(),
+            tx,
+        }
+    }
+
+    /// A reporter that discards every report, for calling a handler outside
+    /// a `PackClient` (e.g., in tests).
+    pub fn disabled() -> Self {
+        Self::new(String::new(), None)
+    }
+
+    /// The gateway's id for the execution being reported on.
+    pub fn request_id(&self) -> &str {
+        &self.request_id
+    }
+
+    /// Report progress: `percent` complete (clamped to 100) when the tool can
+    /// estimate it, and a short human-readable status.
+    pub fn report(&self, percent: Option<u32>, message: impl Into<String>) {
+        if let Some(tx) = &self.tx {
+            // The receiver only goes away once the connection is gone
+            let _ = tx.send(PackToolProgress {
+                request_id: self.request_id.clone(),
+                percent: percent.map(|p| p.min(100)),
+                message: message.into(),
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_report_sends_with_request_id_and_clamps_percent() {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let progress = ProgressReporter::new("req-1", Some(tx));
+
+        progress.report(Some(40), "Compiling");
+        progress.report(Some(250), "Almost there");
+        progress.report(None, "Linking");
+
+        let first = rx.try_recv().unwrap();
+        assert_eq!(first.request_id, "req-1");
+        assert_eq!(first.percent, Some(40));
+        assert_eq!(first.message, "Compiling");
+        assert_eq!(rx.try_recv().unwrap().percent, Some(100));
+        assert_eq!(rx.try_recv().unwrap().percent, None);
+    }
+
+    #[test]
+    fn test_disabled_reporter_ignores_reports() {
+        let progress = ProgressReporter::disabled();
+        progress.report(Some(10), "ignored");
+        assert_eq!(progress.request_id(), "");
+    }
+}
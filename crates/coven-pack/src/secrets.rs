@@ -0,0 +1,90 @@
+// ABOUTME: Secrets handle for values the gateway manages on a pack's behalf.
+// ABOUTME: Filled when the pack registers and replaced whenever an operator changes a secret.
+
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+
+/// Secrets the gateway holds for this pack, set by operators with
+/// `coven admin secrets set`.
+///
+/// The handle is cheap to clone and always reflects the latest values the
+/// gateway sent, so tools should look secrets up when they run rather than
+/// copying them at startup. Values are never included in `Debug` output.
+#[derive(Clone, Default)]
+pub struct Secrets {
+    values: Arc<RwLock<HashMap<String, String>>>,
+}
+
+impl Secrets {
+    /// An empty set, filled in once the pack registers.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The current value of `key`, if the gateway has one.
+    pub fn get(&self, key: &str) -> Option<String> {
+        self.read().get(key).cloned()
+    }
+
+    /// Whether the gateway has a value for `key`.
+    pub fn contains(&self, key: &str) -> bool {
+        self.read().contains_key(key)
+    }
+
+    /// Names of the secrets the gateway has sent, sorted.
+    pub fn keys(&self) -> Vec<String> {
+        let mut keys: Vec<String> = self.read().keys().cloned().collect();
+        keys.sort();
+        keys
+    }
+
+    /// Replace every value with the set the gateway just sent.
+    pub(crate) fn replace(&self, values: HashMap<String, String>) {
+        *self.values.write().unwrap_or_else(|e| e.into_inner()) = values;
+    }
+
+    fn read(&self) -> std::sync::RwLockReadGuard<'_, HashMap<String, String>> {
+        self.values.read().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl std::fmt::Debug for Secrets {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Secrets")
+            .field("keys", &self.keys())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_replace_is_seen_by_every_clone() {
+        let secrets = Secrets::new();
+        let clone = secrets.clone();
+        assert_eq!(clone.get("TOKEN"), None);
+
+        secrets.replace(HashMap::from([("TOKEN".to_string(), "one".to_string())]));
+        assert_eq!(clone.get("TOKEN").as_deref(), Some("one"));
+        assert!(clone.contains("TOKEN"));
+
+        secrets.replace(HashMap::new());
+        assert!(!clone.contains("TOKEN"));
+    }
+
+    #[test]
+    fn test_debug_shows_keys_but_not_values() {
+        let secrets = Secrets::new();
+        secrets.replace(HashMap::from([
+            ("TOKEN".to_string(), "ghp_secret".to_string()),
+            ("API_KEY".to_string(), "sk-secret".to_string()),
+        ]));
+
+        let debug = format!("{:?}", secrets);
+        assert!(debug.contains("API_KEY") && debug.contains("TOKEN"));
+        assert!(!debug.contains("ghp_secret"));
+        assert!(!debug.contains("sk-secret"));
+    }
+}
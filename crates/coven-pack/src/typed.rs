@@ -0,0 +1,383 @@
+// ABOUTME: Typed tool support: schemas derived from input structs and a serde-based handler adapter.
+// ABOUTME: Defines ToolInput, ToolSchema, and TypedHandler so tools are plain async fns over typed input/output.
+
+use crate::context::ExecutionContext;
+use crate::error::ToolError;
+use crate::handler::ToolHandler;
+use crate::health::HealthStatus;
+use async_trait::async_trait;
+use futures::future::BoxFuture;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, HashMap};
+use std::future::Future;
+use std::sync::Arc;
+
+/// A tool's input type, with a JSON schema generated from its fields.
+///
+/// Derive it with `#[derive(ToolInput)]` alongside `Deserialize`; see the
+/// derive macro for how fields map to schema properties.
+///
+/// # Example
+///
+/// ```
+/// use coven_pack::ToolInput;
+/// use serde::Deserialize;
+///
+/// #[derive(Deserialize, ToolInput)]
+/// struct SearchInput {
+///     /// Search query
+///     query: String,
+///     /// Maximum results to return
+///     limit: Option<u32>,
+/// }
+///
+/// let schema = SearchInput::input_schema();
+/// assert_eq!(schema["properties"]["query"]["description"], "Search query");
+/// assert_eq!(schema["required"], serde_json::json!(["query"]));
+/// ```
+pub trait ToolInput: DeserializeOwned {
+    /// JSON Schema for this input as a value.
+    fn input_schema() -> Value;
+
+    /// JSON Schema for this input as a string, ready for a `ToolDefinition`.
+    fn input_schema_json() -> String {
+        Self::input_schema().to_string()
+    }
+}
+
+/// JSON Schema for a field type used in a `ToolInput`.
+pub trait ToolSchema {
+    /// Whether a property of this type may be left out of the input.
+    const OPTIONAL: bool = false;
+
+    /// Schema for a property of this type.
+    fn schema() -> Value;
+}
+
+macro_rules! impl_tool_schema {
+    ($json_type:literal: $($ty:ty),+) => {
+        $(
+            impl ToolSchema for $ty {
+                fn schema() -> Value {
+                    json!({"type": $json_type})
+                }
+            }
+        )+
+    };
+}
+
+impl_tool_schema!("string": String, char);
+impl_tool_schema!("boolean": bool);
+impl_tool_schema!("integer": i8, i16, i32, i64, isize, u8, u16, u32, u64, usize);
+impl_tool_schema!("number": f32, f64);
+
+impl<T: ToolSchema> ToolSchema for Option<T> {
+    const OPTIONAL: bool = true;
+
+    fn schema() -> Value {
+        T::schema()
+    }
+}
+
+impl<T: ToolSchema> ToolSchema for Vec<T> {
+    fn schema() -> Value {
+        json!({"type": "array", "items": T::schema()})
+    }
+}
+
+impl<T: ToolSchema> ToolSchema for HashMap<String, T> {
+    fn schema() -> Value {
+        json!({"type": "object", "additionalProperties": T::schema()})
+    }
+}
+
+impl<T: ToolSchema> ToolSchema for BTreeMap<String, T> {
+    fn schema() -> Value {
+        json!({"type": "object", "additionalProperties": T::schema()})
+    }
+}
+
+/// Arbitrary JSON; the schema places no constraints on it.
+impl ToolSchema for Value {
+    fn schema() -> Value {
+        json!({})
+    }
+}
+
+type ToolFn<S> = Box<
+    dyn Fn(Arc<S>, ExecutionContext, &str) -> BoxFuture<'static, Result<String, ToolError>>
+        + Send
+        + Sync,
+>;
+
+type HealthFn<S> = Box<dyn Fn(Arc<S>) -> BoxFuture<'static, HealthStatus> + Send + Sync>;
+
+/// A `ToolHandler` that routes each tool to an async function over typed
+/// input and output, handling the JSON conversion.
+///
+/// Input that fails to deserialize is rejected with `ToolError::InvalidInput`
+/// before the function runs. Every function receives the handler's shared
+/// state, which is `()` for stateless packs.
+///
+/// # Example
+///
+/// ```
+/// use coven_pack::{ToolError, ToolInput, TypedHandler};
+/// use serde::{Deserialize, Serialize};
+/// use std::sync::Arc;
+///
+/// #[derive(Deserialize, ToolInput)]
+/// struct GreetInput {
+///     /// Who to greet
+///     name: String,
+/// }
+///
+/// #[derive(Serialize)]
+/// struct GreetOutput {
+///     greeting: String,
+/// }
+///
+/// async fn greet(_: Arc<()>, input: GreetInput) -> Result<GreetOutput, ToolError> {
+///     Ok(GreetOutput {
+///         greeting: format!("Hello, {}!", input.name),
+///     })
+/// }
+///
+/// let handler = TypedHandler::new().tool("greet", greet);
+/// ```
+pub struct TypedHandler<S = ()> {
+    state: Arc<S>,
+    tools: HashMap<String, ToolFn<S>>,
+    health: Option<HealthFn<S>>,
+}
+
+impl TypedHandler<()> {
+    /// Create a handler for tools that need no shared state.
+    pub fn new() -> Self {
+        Self::with_state(())
+    }
+}
+
+impl Default for TypedHandler<()> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<S: Send + Sync + 'static> TypedHandler<S> {
+    /// Create a handler whose tools all receive `state`.
+    pub fn with_state(state: S) -> Self {
+        Self::with_shared_state(Arc::new(state))
+    }
+
+    /// Like `with_state`, for state the pack also uses outside tool calls,
+    /// e.g. from a background task.
+    pub fn with_shared_state(state: Arc<S>) -> Self {
+        Self {
+            state,
+            tools: HashMap::new(),
+            health: None,
+        }
+    }
+
+    /// Register the function that runs `name`. Registering a name twice
+    /// replaces the earlier function.
+    pub fn tool<I, O, F, Fut>(self, name: impl Into<String>, handler: F) -> Self
+    where
+        I: DeserializeOwned + Send + 'static,
+        O: Serialize + Send,
+        F: Fn(Arc<S>, I) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<O, ToolError>> + Send + 'static,
+    {
+        self.tool_with_context(name, move |state, _ctx, input| handler(state, input))
+    }
+
+    /// Like `tool`, for functions that also need the `ExecutionContext`,
+    /// e.g. to keep each agent's data separate.
+    pub fn tool_with_context<I, O, F, Fut>(mut self, name: impl Into<String>, handler: F) -> Self
+    where
+        I: DeserializeOwned + Send + 'static,
+        O: Serialize + Send,
+        F: Fn(Arc<S>, ExecutionContext, I) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<O, ToolError>> + Send + 'static,
+    {
+        let tool: ToolFn<S> = Box::new(move |state, ctx, input_json| {
+            let input: I = match serde_json::from_str(input_json) {
+                Ok(input) => input,
+                Err(e) => {
+                    return Box::pin(std::future::ready(Err(ToolError::InvalidInput(
+                        e.to_string(),
+                    ))))
+                }
+            };
+            let output = handler(state, ctx, input);
+            Box::pin(async move {
+                let output = output.await?;
+                serde_json::to_string(&output)
+                    .map_err(|e| ToolError::ExecutionFailed(e.to_string()))
+            })
+        });
+        self.tools.insert(name.into(), tool);
+        self
+    }
+
+    /// Use `check` as the handler's health check; it receives the shared
+    /// state. Without one the handler always reports healthy.
+    pub fn with_health_check<F, Fut>(mut self, check: F) -> Self
+    where
+        F: Fn(Arc<S>) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = HealthStatus> + Send + 'static,
+    {
+        self.health = Some(Box::new(move |state| Box::pin(check(state))));
+        self
+    }
+
+    /// Names of the registered tools.
+    pub fn tool_names(&self) -> impl Iterator<Item = &str> {
+        self.tools.keys().map(String::as_str)
+    }
+}
+
+#[async_trait]
+impl<S: Send + Sync + 'static> ToolHandler for TypedHandler<S> {
+    async fn execute(&self, tool_name: &str, input_json: &str) -> Result<String, ToolError> {
+        self.execute_with_context(tool_name, input_json, ExecutionContext::default())
+            .await
+    }
+
+    async fn execute_with_context(
+        &self,
+        tool_name: &str,
+        input_json: &str,
+        ctx: ExecutionContext,
+    ) -> Result<String, ToolError> {
+        let Some(tool) = self.tools.get(tool_name) else {
+            return Err(ToolError::UnknownTool(tool_name.to_string()));
+        };
+        tool(Arc::clone(&self.state), ctx, input_json).await
+    }
+
+    async fn health_check(&self) -> HealthStatus {
+        match &self.health {
+            Some(check) => check(Arc::clone(&self.state)).await,
+            None => HealthStatus::Healthy,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[derive(Deserialize)]
+    struct AddInput {
+        a: i64,
+        b: i64,
+    }
+
+    #[derive(Serialize)]
+    struct AddOutput {
+        sum: i64,
+    }
+
+    async fn add(calls: Arc<AtomicUsize>, input: AddInput) -> Result<AddOutput, ToolError> {
+        calls.fetch_add(1, Ordering::SeqCst);
+        Ok(AddOutput {
+            sum: input.a + input.b,
+        })
+    }
+
+    #[tokio::test]
+    async fn test_typed_handler_round_trips_json() {
+        let handler = TypedHandler::with_state(AtomicUsize::new(0)).tool("add", add);
+
+        let output = handler.execute("add", r#"{"a": 2, "b": 3}"#).await.unwrap();
+        assert_eq!(output, r#"{"sum":5}"#);
+        assert_eq!(handler.state.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_typed_handler_rejects_bad_input_without_calling() {
+        let handler = TypedHandler::with_state(AtomicUsize::new(0)).tool("add", add);
+
+        let err = handler.execute("add", r#"{"a": 2}"#).await.unwrap_err();
+        assert!(
+            matches!(err, ToolError::InvalidInput(ref msg) if msg.contains("missing field `b`"))
+        );
+        assert_eq!(handler.state.load(Ordering::SeqCst), 0);
+    }
+
+    #[tokio::test]
+    async fn test_typed_handler_unknown_tool() {
+        let handler = TypedHandler::new();
+        let err = handler.execute("missing", "{}").await.unwrap_err();
+        assert!(matches!(err, ToolError::UnknownTool(ref name) if name == "missing"));
+    }
+
+    #[tokio::test]
+    async fn test_typed_handler_passes_tool_errors_through() {
+        let handler = TypedHandler::new().tool("fail", |_, _: Value| async {
+            Err::<Value, _>(ToolError::ExecutionFailed("nope".to_string()))
+        });
+        let err = handler.execute("fail", "null").await.unwrap_err();
+        assert!(matches!(err, ToolError::ExecutionFailed(ref msg) if msg == "nope"));
+    }
+
+    #[tokio::test]
+    async fn test_typed_handler_passes_context() {
+        let handler = TypedHandler::new()
+            .tool_with_context("whoami", |_, ctx: ExecutionContext, _: Value| async move {
+                Ok::<_, ToolError>(ctx.agent_id)
+            });
+
+        let ctx = ExecutionContext::default().with_agent("agent-a");
+        let output = handler
+            .execute_with_context("whoami", "null", ctx)
+            .await
+            .unwrap();
+        assert_eq!(output, r#""agent-a""#);
+
+        // Plain execute runs with an empty context
+        assert_eq!(handler.execute("whoami", "null").await.unwrap(), "null");
+    }
+
+    #[tokio::test]
+    async fn test_typed_handler_health_check_uses_state() {
+        assert!(TypedHandler::new().health_check().await.is_healthy());
+
+        let handler =
+            TypedHandler::with_state(AtomicUsize::new(0)).with_health_check(|calls| async move {
+                if calls.fetch_add(1, Ordering::SeqCst) == 0 {
+                    HealthStatus::Healthy
+                } else {
+                    HealthStatus::unhealthy("connection refused")
+                }
+            });
+        assert!(handler.health_check().await.is_healthy());
+        assert_eq!(
+            handler.health_check().await,
+            HealthStatus::unhealthy("connection refused")
+        );
+    }
+
+    #[test]
+    fn test_tool_schema_types() {
+        assert_eq!(String::schema(), json!({"type": "string"}));
+        assert_eq!(u32::schema(), json!({"type": "integer"}));
+        assert_eq!(f64::schema(), json!({"type": "number"}));
+        assert_eq!(
+            <Option<Vec<bool>>>::schema(),
+            json!({"type": "array", "items": {"type": "boolean"}})
+        );
+        assert_eq!(
+            <HashMap<String, i64>>::schema(),
+            json!({"type": "object", "additionalProperties": {"type": "integer"}})
+        );
+        assert!(<Option<String>>::OPTIONAL);
+        assert!(!<Vec<String>>::OPTIONAL);
+    }
+}
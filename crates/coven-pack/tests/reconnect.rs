@@ -0,0 +1,396 @@
+// ABOUTME: Integration tests for PackClient reconnection against a mock gateway.
+// ABOUTME: The mock drops the request stream after registration and then refuses to re-register, or starts late.
+// ABOUTME: It also records each request's nonce, which must never repeat.
+
+use async_trait::async_trait;
+use coven_pack::{
+    ConnectionState, ManifestBuilder, PackClient, PackConfig, PackError, RetryPolicy, Secrets,
+    ToolError, ToolHandler, DEFAULT_EXECUTION_TIMEOUT, DEFAULT_HEALTH_CHECK_INTERVAL,
+    DEFAULT_MAX_CONCURRENT_EXECUTIONS,
+};
+use coven_proto::server::{PackService, PackServiceServer};
+use coven_proto::{
+    ExecuteToolRequest, ExecuteToolResponse, PackManifest, PackSecrets, PackStatus,
+    PackToolProgress, PackWelcome, WatchSecretsRequest,
+};
+use coven_ssh::PassphraseSource;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::net::TcpListener;
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::{ReceiverStream, TcpListenerStream};
+use tonic::{Request, Response, Status};
+
+type RequestSender = mpsc::Sender<Result<ExecuteToolRequest, Status>>;
+
+/// Gateway that scripts each registration:
+/// 1. closes the request stream right away
+/// 2. sends one tool request and closes the stream once the result arrives
+/// 3. and later: rejects the registration
+#[derive(Default)]
+struct MockGateway {
+    registrations: Arc<AtomicUsize>,
+    results: Arc<Mutex<Vec<ExecuteToolResponse>>>,
+    open_stream: Mutex<Option<RequestSender>>,
+    nonces: Arc<Mutex<Vec<String>>>,
+}
+
+impl MockGateway {
+    fn record_nonce<T>(&self, request: &Request<T>) {
+        let nonce = request.metadata().get("x-ssh-nonce").unwrap();
+        let nonce = nonce.to_str().unwrap().to_string();
+        self.nonces.lock().unwrap().push(nonce);
+    }
+}
+
+#[tonic::async_trait]
+impl PackService for MockGateway {
+    type RegisterStream = ReceiverStream<Result<ExecuteToolRequest, Status>>;
+    type WatchSecretsStream = ReceiverStream<Result<PackSecrets, Status>>;
+
+    async fn register(
+        &self,
+        request: Request<PackManifest>,
+    ) -> Result<Response<Self::RegisterStream>, Status> {
+        self.record_nonce(&request);
+        let registration = self.registrations.fetch_add(1, Ordering::SeqCst) + 1;
+        let (tx, rx) = mpsc::channel(4);
+        match registration {
+            1 => drop(tx),
+            2 => {
+                tx.send(Ok(ExecuteToolRequest {
+                    request_id: "req-1".to_string(),
+                    tool_name: "echo".to_string(),
+                    input_json: r#"{"message": "hi"}"#.to_string(),
+                    ..Default::default()
+                }))
+                .await
+                .unwrap();
+                *self.open_stream.lock().unwrap() = Some(tx);
+            }
+            _ => return Err(Status::unavailable("gateway restarting")),
+        }
+        Ok(Response::new(ReceiverStream::new(rx)))
+    }
+
+    async fn tool_result(
+        &self,
+        request: Request<ExecuteToolResponse>,
+    ) -> Result<Response<()>, Status> {
+        self.record_nonce(&request);
+        self.results.lock().unwrap().push(request.into_inner());
+        // Drop the connection now that the request was answered
+        self.open_stream.lock().unwrap().take();
+        Ok(Response::new(()))
+    }
+
+    async fn tool_progress(
+        &self,
+        _request: Request<PackToolProgress>,
+    ) -> Result<Response<()>, Status> {
+        Ok(Response::new(()))
+    }
+
+    async fn report_status(&self, _request: Request<PackStatus>) -> Result<Response<()>, Status> {
+        Ok(Response::new(()))
+    }
+
+    async fn update_manifest(
+        &self,
+        request: Request<PackManifest>,
+    ) -> Result<Response<PackWelcome>, Status> {
+        Ok(Response::new(PackWelcome {
+            pack_id: request.into_inner().pack_id,
+            rejected_tools: vec![],
+        }))
+    }
+    // Like a gateway from before managed secrets
+    async fn watch_secrets(
+        &self,
+        _request: Request<WatchSecretsRequest>,
+    ) -> Result<Response<Self::WatchSecretsStream>, Status> {
+        Err(Status::unimplemented("secrets"))
+    }
+}
+
+/// Records handler lifecycle callbacks.
+#[derive(Default)]
+struct RecordingHandler {
+    registered: AtomicUsize,
+    closing_reasons: Mutex<Vec<Option<String>>>,
+}
+
+#[async_trait]
+impl ToolHandler for RecordingHandler {
+    async fn execute(&self, tool_name: &str, input_json: &str) -> Result<String, ToolError> {
+        match tool_name {
+            "echo" => Ok(input_json.to_string()),
+            _ => Err(ToolError::UnknownTool(tool_name.to_string())),
+        }
+    }
+
+    async fn on_registered(&self, _pack_id: &str, _rejected_tools: &[String]) {
+        self.registered.fetch_add(1, Ordering::SeqCst);
+    }
+
+    async fn on_closing(&self, reason: Option<&str>) {
+        self.closing_reasons
+            .lock()
+            .unwrap()
+            .push(reason.map(str::to_string));
+    }
+}
+
+/// Start the mock gateway on a random local port, returning its URL.
+async fn start_gateway(gateway: MockGateway) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    serve(gateway, listener);
+    format!("http://{}", addr)
+}
+
+fn serve(gateway: MockGateway, listener: TcpListener) {
+    tokio::spawn(async move {
+        tonic::transport::Server::builder()
+            .add_service(PackServiceServer::new(gateway))
+            .serve_with_incoming(TcpListenerStream::new(listener))
+            .await
+            .unwrap();
+    });
+}
+
+fn fast_retries(max_attempts: u32) -> RetryPolicy {
+    RetryPolicy {
+        max_attempts,
+        initial_backoff: Duration::from_millis(20),
+        max_backoff: Duration::from_millis(50),
+    }
+}
+
+fn manifest() -> PackManifest {
+    ManifestBuilder::new("reconnect-pack", "0.1.0")
+        .tool("echo", "Echo", r#"{"type": "object"}"#, &[])
+        .build()
+}
+
+#[tokio::test]
+async fn test_reconnects_and_reregisters_after_drop() {
+    let gateway = MockGateway::default();
+    let registrations = Arc::clone(&gateway.registrations);
+    let results = Arc::clone(&gateway.results);
+    let nonces = Arc::clone(&gateway.nonces);
+    let url = start_gateway(gateway).await;
+
+    let key_dir = tempfile::tempdir().unwrap();
+    let key_path = key_dir.path().join("id_ed25519");
+    coven_ssh::load_or_generate_key(&key_path).unwrap();
+
+    let states = Arc::new(Mutex::new(Vec::new()));
+    let recorded = Arc::clone(&states);
+    let client = PackClient::connect(&url, &key_path)
+        .await
+        .unwrap()
+        .with_reconnect_backoff(Duration::from_millis(10), Duration::from_millis(40))
+        .with_max_reconnect_attempts(Some(2))
+        .on_connection_state(move |state| recorded.lock().unwrap().push(state.clone()));
+
+    let handler = Arc::new(RecordingHandler::default());
+    let err = tokio::time::timeout(
+        Duration::from_secs(10),
+        client.run(manifest(), SharedHandler(Arc::clone(&handler))),
+    )
+    .await
+    .expect("run should give up after the attempt limit")
+    .unwrap_err();
+    assert!(err.to_string().contains("gateway restarting"), "{}", err);
+
+    // Initial registration, one successful reconnect, then two refused attempts
+    assert_eq!(registrations.load(Ordering::SeqCst), 4);
+    assert_eq!(handler.registered.load(Ordering::SeqCst), 2);
+
+    // The request sent after re-registering was answered
+    let results = results.lock().unwrap();
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0].request_id, "req-1");
+
+    // Every request was signed afresh, or the gateway would refuse it as a replay
+    let mut nonces = nonces.lock().unwrap().clone();
+    assert_eq!(nonces.len(), 5);
+    nonces.sort();
+    nonces.dedup();
+    assert_eq!(nonces.len(), 5);
+
+    let reasons = handler.closing_reasons.lock().unwrap();
+    assert_eq!(reasons[0].as_deref(), Some("reconnecting"));
+    assert_eq!(reasons[1].as_deref(), Some("reconnecting"));
+    assert!(reasons[2]
+        .as_deref()
+        .unwrap()
+        .contains("gateway restarting"));
+
+    let states = states.lock().unwrap();
+    let kinds: Vec<&str> = states
+        .iter()
+        .map(|state| match state {
+            ConnectionState::Registered => "registered",
+            ConnectionState::Reconnecting { .. } => "reconnecting",
+            ConnectionState::Closed => "closed",
+        })
+        .collect();
+    assert_eq!(
+        kinds,
+        vec![
+            "registered",
+            "reconnecting",
+            "registered",
+            "reconnecting",
+            "reconnecting",
+            "closed"
+        ]
+    );
+    assert!(matches!(
+        states[4],
+        ConnectionState::Reconnecting { attempt: 2, .. }
+    ));
+}
+
+#[tokio::test]
+async fn test_without_reconnect_run_returns_when_stream_closes() {
+    let gateway = MockGateway::default();
+    let registrations = Arc::clone(&gateway.registrations);
+    let url = start_gateway(gateway).await;
+
+    let key_dir = tempfile::tempdir().unwrap();
+    let key_path = key_dir.path().join("id_ed25519");
+    coven_ssh::load_or_generate_key(&key_path).unwrap();
+
+    let client = PackClient::connect(&url, &key_path)
+        .await
+        .unwrap()
+        .with_reconnect(false);
+    let handler = Arc::new(RecordingHandler::default());
+    tokio::time::timeout(
+        Duration::from_secs(10),
+        client.run(manifest(), SharedHandler(Arc::clone(&handler))),
+    )
+    .await
+    .unwrap()
+    .unwrap();
+
+    assert_eq!(registrations.load(Ordering::SeqCst), 1);
+    assert_eq!(
+        *handler.closing_reasons.lock().unwrap(),
+        vec![Some("stream closed".to_string())]
+    );
+}
+
+/// Lets a test keep a reference to the handler it passes to `run`.
+struct SharedHandler(Arc<RecordingHandler>);
+
+#[async_trait]
+impl ToolHandler for SharedHandler {
+    async fn execute(&self, tool_name: &str, input_json: &str) -> Result<String, ToolError> {
+        self.0.execute(tool_name, input_json).await
+    }
+
+    async fn on_registered(&self, pack_id: &str, rejected_tools: &[String]) {
+        self.0.on_registered(pack_id, rejected_tools).await
+    }
+
+    async fn on_closing(&self, reason: Option<&str>) {
+        self.0.on_closing(reason).await
+    }
+}
+
+#[tokio::test]
+async fn test_connect_with_retry_waits_for_late_gateway() {
+    // Reserve a port, then leave it closed until after the first attempts
+    let addr = TcpListener::bind("127.0.0.1:0")
+        .await
+        .unwrap()
+        .local_addr()
+        .unwrap();
+    tokio::spawn(async move {
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        serve(
+            MockGateway::default(),
+            TcpListener::bind(addr).await.unwrap(),
+        );
+    });
+
+    let key_dir = tempfile::tempdir().unwrap();
+    let key_path = key_dir.path().join("id_ed25519");
+    coven_ssh::load_or_generate_key(&key_path).unwrap();
+
+    let url = format!("http://{}", addr);
+    PackClient::connect_with_retry(&url, &key_path, fast_retries(50))
+        .await
+        .expect("should connect once the gateway is listening");
+}
+
+#[tokio::test]
+async fn test_connect_with_retry_gives_up_after_max_attempts() {
+    let addr = TcpListener::bind("127.0.0.1:0")
+        .await
+        .unwrap()
+        .local_addr()
+        .unwrap();
+
+    let key_dir = tempfile::tempdir().unwrap();
+    let key_path = key_dir.path().join("id_ed25519");
+    coven_ssh::load_or_generate_key(&key_path).unwrap();
+
+    let url = format!("http://{}", addr);
+    let result = PackClient::connect_with_retry(&url, &key_path, fast_retries(3)).await;
+    assert!(matches!(result, Err(PackError::ConnectionFailed(_))));
+}
+
+#[tokio::test]
+async fn test_connect_with_retry_does_not_retry_key_errors() {
+    let key_dir = tempfile::tempdir().unwrap();
+    let missing = key_dir.path().join("missing_key");
+
+    let result = tokio::time::timeout(
+        Duration::from_secs(1),
+        PackClient::connect_with_retry("http://127.0.0.1:1", &missing, RetryPolicy::default()),
+    )
+    .await
+    .expect("key errors should not be retried");
+    assert!(matches!(result, Err(PackError::KeyLoadFailed(_))));
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn test_connect_with_retry_unlocks_the_key_once() {
+    let addr = TcpListener::bind("127.0.0.1:0")
+        .await
+        .unwrap()
+        .local_addr()
+        .unwrap();
+
+    let key_dir = tempfile::tempdir().unwrap();
+    let key_path = key_dir.path().join("id_ed25519");
+    coven_ssh::generate_key_encrypted(&key_path, "hunter2").unwrap();
+    // The command notes each time it's asked for the passphrase
+    let asked = key_dir.path().join("asked");
+    let config = PackConfig {
+        gateway_url: format!("http://{}", addr),
+        ssh_key_path: key_path,
+        ssh_key_passphrase: Some(PassphraseSource::Command(format!(
+            "echo . >> '{}'; echo hunter2",
+            asked.display()
+        ))),
+        max_concurrent_executions: DEFAULT_MAX_CONCURRENT_EXECUTIONS,
+        execution_timeout: DEFAULT_EXECUTION_TIMEOUT,
+        health_check_interval: DEFAULT_HEALTH_CHECK_INTERVAL,
+        reconnect: true,
+        max_reconnect_attempts: None,
+        secrets: Secrets::new(),
+    };
+
+    let result = PackClient::connect_with_config_retry(&config, fast_retries(3)).await;
+    assert!(matches!(result, Err(PackError::ConnectionFailed(_))));
+    let asked = std::fs::read_to_string(&asked).unwrap();
+    assert_eq!(asked.lines().count(), 1, "passphrase asked for per attempt");
+}
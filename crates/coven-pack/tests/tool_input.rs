@@ -0,0 +1,182 @@
+// ABOUTME: Integration tests for the ToolInput derive and typed tool handling.
+// ABOUTME: Checks generated schemas, serde attribute handling, and compile errors via trybuild.
+
+use coven_pack::{ManifestBuilder, SchemaBuilder, ToolError, ToolHandler, ToolInput, TypedHandler};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+#[derive(Debug, Deserialize, ToolInput)]
+#[allow(dead_code)]
+struct EverythingInput {
+    /// Name of the thing
+    name: String,
+    /// How many to make.
+    ///
+    /// Defaults to one.
+    count: Option<u32>,
+    ratio: f64,
+    enabled: bool,
+    /// Labels to attach
+    #[serde(default)]
+    labels: Vec<String>,
+    extra: HashMap<String, i64>,
+    #[serde(rename = "type")]
+    kind: String,
+    #[serde(skip)]
+    internal: u8,
+    /// Sort order
+    #[serde(default = "default_order")]
+    #[tool(default = "asc")]
+    order: String,
+}
+
+fn default_order() -> String {
+    "asc".to_string()
+}
+
+#[test]
+fn test_derived_schema() {
+    assert_eq!(
+        EverythingInput::input_schema(),
+        json!({
+            "type": "object",
+            "properties": {
+                "name": {"type": "string", "description": "Name of the thing"},
+                "count": {"type": "integer", "description": "How many to make. Defaults to one."},
+                "ratio": {"type": "number"},
+                "enabled": {"type": "boolean"},
+                "labels": {
+                    "type": "array",
+                    "items": {"type": "string"},
+                    "description": "Labels to attach"
+                },
+                "extra": {"type": "object", "additionalProperties": {"type": "integer"}},
+                "type": {"type": "string"},
+                "order": {"type": "string", "description": "Sort order", "default": "asc"}
+            },
+            "required": ["name", "ratio", "enabled", "extra", "type"]
+        })
+    );
+}
+
+#[derive(Deserialize, ToolInput)]
+#[allow(dead_code)]
+struct NoRequiredInput {
+    filter: Option<String>,
+}
+
+#[test]
+fn test_schema_without_required_fields_omits_required() {
+    assert_eq!(
+        NoRequiredInput::input_schema(),
+        json!({"type": "object", "properties": {"filter": {"type": "string"}}})
+    );
+}
+
+#[derive(Deserialize, ToolInput)]
+#[allow(dead_code)]
+struct SearchInput {
+    /// Search query
+    query: String,
+    /// Maximum results
+    limit: Option<i64>,
+}
+
+#[test]
+fn test_derived_schema_matches_schema_builder() {
+    let hand_written = SchemaBuilder::object()
+        .property("query", SchemaBuilder::string().description("Search query"))
+        .property(
+            "limit",
+            SchemaBuilder::integer().description("Maximum results"),
+        )
+        .required(&["query"])
+        .into_value();
+
+    assert_eq!(SearchInput::input_schema(), hand_written);
+}
+
+#[test]
+fn test_typed_tool_manifest_uses_derived_schema() {
+    let manifest = ManifestBuilder::new("pack", "1.0.0")
+        .typed_tool::<SearchInput>("search", "Search", &["web"])
+        .build();
+
+    let schema: serde_json::Value =
+        serde_json::from_str(&manifest.tools[0].input_schema_json).unwrap();
+    assert_eq!(schema, SearchInput::input_schema());
+    assert_eq!(manifest.tools[0].required_capabilities, vec!["web"]);
+}
+
+#[derive(Deserialize, ToolInput)]
+#[allow(dead_code)]
+struct LoginInput {
+    user: String,
+    #[tool(sensitive)]
+    pin: String,
+    /// Credential-looking name that isn't one
+    #[tool(sensitive = false)]
+    token_budget: u32,
+}
+
+#[test]
+fn test_sensitive_fields_are_marked_and_redacted() {
+    let schema = LoginInput::input_schema();
+    assert_eq!(schema["properties"]["pin"]["sensitive"], true);
+    assert_eq!(schema["properties"]["token_budget"]["sensitive"], false);
+    assert!(schema["properties"]["user"].get("sensitive").is_none());
+
+    let manifest = ManifestBuilder::new("pack", "1.0.0")
+        .typed_tool::<LoginInput>("login", "Log in", &[])
+        .build();
+    let redacted: serde_json::Value = serde_json::from_str(
+        &manifest.tools[0]
+            .input_redactor()
+            .redact(r#"{"user": "me", "pin": "1234", "token_budget": 5}"#),
+    )
+    .unwrap();
+    assert_eq!(
+        redacted,
+        json!({"user": "me", "pin": "***", "token_budget": 5})
+    );
+}
+
+#[derive(Serialize)]
+struct SearchOutput {
+    hits: Vec<String>,
+}
+
+async fn search(_: Arc<()>, input: SearchInput) -> Result<SearchOutput, ToolError> {
+    let limit = input.limit.unwrap_or(2) as usize;
+    Ok(SearchOutput {
+        hits: (0..limit)
+            .map(|i| format!("{}-{}", input.query, i))
+            .collect(),
+    })
+}
+
+#[tokio::test]
+async fn test_typed_handler_with_derived_input() {
+    let handler = TypedHandler::new().tool("search", search);
+
+    let output = handler
+        .execute("search", r#"{"query": "rust"}"#)
+        .await
+        .unwrap();
+    assert_eq!(output, r#"{"hits":["rust-0","rust-1"]}"#);
+
+    let err = handler
+        .execute("search", r#"{"limit": 1}"#)
+        .await
+        .unwrap_err();
+    assert!(matches!(err, ToolError::InvalidInput(_)));
+}
+
+#[test]
+fn test_derive_ui() {
+    let t = trybuild::TestCases::new();
+    t.pass("tests/ui/pass_*.rs");
+    t.compile_fail("tests/ui/fail_*.rs");
+}
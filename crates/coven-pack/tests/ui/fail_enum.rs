@@ -0,0 +1,10 @@
+use coven_pack::ToolInput;
+use serde::Deserialize;
+
+#[derive(Deserialize, ToolInput)]
+enum Choice {
+    A,
+    B,
+}
+
+fn main() {}
@@ -0,0 +1,10 @@
+use coven_pack::ToolInput;
+use serde::Deserialize;
+
+#[derive(Deserialize, ToolInput)]
+#[serde(rename_all = "camelCase")]
+struct Input {
+    due_date: String,
+}
+
+fn main() {}
@@ -0,0 +1,7 @@
+use coven_pack::ToolInput;
+use serde::Deserialize;
+
+#[derive(Deserialize, ToolInput)]
+struct Pair(String, i64);
+
+fn main() {}
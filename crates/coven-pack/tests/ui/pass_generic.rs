@@ -0,0 +1,16 @@
+use coven_pack::ToolInput;
+use serde::Deserialize;
+
+#[derive(Deserialize, ToolInput)]
+struct Wrapper<T> {
+    /// The wrapped value
+    value: T,
+    r#loop: Option<bool>,
+}
+
+fn main() {
+    let schema = Wrapper::<String>::input_schema();
+    assert_eq!(schema["properties"]["value"]["type"], "string");
+    assert!(schema["properties"]["loop"].is_object());
+    assert_eq!(schema["required"], serde_json::json!(["value"]));
+}
@@ -6,6 +6,11 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     tonic_build::configure()
         .build_server(true)
         .build_client(true)
+        // Lets tools print responses as JSON (e.g. coven admin --output json)
+        .type_attribute(".coven", "#[derive(serde::Serialize, serde::Deserialize)]")
+        // Hand-written Debug impls in lib.rs keep secret values out of logs
+        .skip_debug("coven.PackSecrets")
+        .skip_debug("coven.SetPackSecretRequest")
         .compile_protos(&["proto-src/coven.proto"], &["proto-src"])?;
 
     // Rerun if the proto file changes
@@ -11,6 +11,10 @@ pub mod coven {
 // Re-export commonly used types at crate root for convenience
 pub use coven::*;
 
+pub mod limits;
+pub mod overrides;
+pub mod redact;
+
 // Re-export client types under a client module
 pub mod client {
     pub use super::coven::admin_service_client::AdminServiceClient;
@@ -25,3 +29,103 @@ pub mod server {
     pub use super::coven::coven_control_server::{CovenControl, CovenControlServer};
     pub use super::coven::pack_service_server::{PackService, PackServiceServer};
 }
+
+/// Request metadata carrying the caller's current one-time code for admin
+/// RPCs. Gateways set it to "required" on errors a code would fix, so
+/// clients know to ask for one.
+pub const OTP_METADATA: &str = "x-coven-otp";
+
+impl AgentPresence {
+    /// Status shown for agents that haven't set one.
+    pub const DEFAULT_STATUS: &'static str = "available";
+
+    /// The default presence: "available" with no emoji.
+    pub fn available() -> Self {
+        Self {
+            status: Self::DEFAULT_STATUS.to_string(),
+            emoji: String::new(),
+        }
+    }
+
+    /// Presence as shown to people, emoji first when set
+    pub fn label(&self) -> String {
+        Self::format_label(&self.status, &self.emoji)
+    }
+
+    /// `label` for a status and emoji held outside an `AgentPresence`
+    pub fn format_label(status: &str, emoji: &str) -> String {
+        if emoji.is_empty() {
+            status.to_string()
+        } else {
+            format!("{} {}", emoji, status)
+        }
+    }
+}
+
+impl ForkThread {
+    /// Protocol feature of agents that handle `ForkThread`; the gateway
+    /// refuses forks for other agents rather than send one they'd drop.
+    pub const PROTOCOL_FEATURE: &'static str = "thread_fork";
+}
+
+impl ClientSendMessageResponse {
+    /// Status of a message the gateway's content policy blocked; `detail`
+    /// is the reply for the sender.
+    pub const STATUS_BLOCKED: &'static str = "blocked";
+
+    /// Reply for the sender of a blocked message when the gateway's policy
+    /// sets none.
+    pub const BLOCKED_NOTICE: &'static str =
+        "This message was blocked by the gateway's content policy.";
+
+    /// Why the gateway didn't pass the message on, when its content policy
+    /// blocked it.
+    pub fn blocked_reason(&self) -> Option<&str> {
+        (self.status == Self::STATUS_BLOCKED).then(|| {
+            self.detail
+                .as_deref()
+                .filter(|d| !d.is_empty())
+                .unwrap_or(Self::BLOCKED_NOTICE)
+        })
+    }
+}
+
+impl std::fmt::Debug for PackSecrets {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut keys: Vec<&String> = self.values.keys().collect();
+        keys.sort();
+        f.debug_struct("PackSecrets").field("keys", &keys).finish()
+    }
+}
+
+impl std::fmt::Debug for SetPackSecretRequest {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SetPackSecretRequest")
+            .field("pack_id", &self.pack_id)
+            .field("key", &self.key)
+            .field("value", &"<redacted>")
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_secret_values_are_not_in_debug_output() {
+        let secrets = PackSecrets {
+            values: [("API_KEY".to_string(), "hunter2".to_string())].into(),
+        };
+        let debug = format!("{:?}", secrets);
+        assert!(debug.contains("API_KEY"));
+        assert!(!debug.contains("hunter2"));
+
+        let request = SetPackSecretRequest {
+            pack_id: "github-pack".to_string(),
+            key: "API_KEY".to_string(),
+            value: "hunter2".to_string(),
+        };
+        assert!(!format!("{:?}", request).contains("hunter2"));
+    }
+}
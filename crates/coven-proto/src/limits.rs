@@ -0,0 +1,161 @@
+// ABOUTME: gRPC message size limits shared by coven clients and servers.
+// ABOUTME: Checks messages before they're sent, so an oversize one fails alone instead of resetting its stream.
+
+use crate::{agent_message, message_response::Event, AgentMessage, MessageResponse};
+use prost::Message;
+
+/// Largest message coven sends or accepts by default (16 MiB). tonic's own
+/// decode limit is 4 MiB, which a long tool result or file can exceed.
+pub const DEFAULT_MAX_MESSAGE_SIZE: usize = 16 * 1024 * 1024;
+
+/// Largest encoded messages a peer decodes and encodes. tonic fails a
+/// whole stream when one message breaks a limit, so senders check first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MessageLimits {
+    /// Largest message accepted from the other side, in bytes
+    pub max_decoding: usize,
+    /// Largest message sent to the other side, in bytes
+    pub max_encoding: usize,
+}
+
+impl Default for MessageLimits {
+    fn default() -> Self {
+        Self::new(DEFAULT_MAX_MESSAGE_SIZE)
+    }
+}
+
+impl MessageLimits {
+    /// The same limit, in bytes, both ways
+    pub fn new(max_size: usize) -> Self {
+        Self {
+            max_decoding: max_size,
+            max_encoding: max_size,
+        }
+    }
+
+    /// Fails when `msg` is larger than `max_encoding`
+    pub fn check_outgoing<M: Message>(&self, msg: &M) -> Result<(), MessageTooLarge> {
+        let size = msg.encoded_len();
+        if size > self.max_encoding {
+            return Err(MessageTooLarge {
+                size,
+                limit: self.max_encoding,
+            });
+        }
+        Ok(())
+    }
+
+    /// `msg`, or when it's a response event too large to send, an Error
+    /// event for the same request in its place. The gateway and the user
+    /// hear what happened and the stream carries on. Other messages are
+    /// returned unchanged.
+    pub fn fit_agent_message(&self, msg: AgentMessage) -> AgentMessage {
+        let Err(err) = self.check_outgoing(&msg) else {
+            return msg;
+        };
+        match msg.payload {
+            Some(agent_message::Payload::Response(response)) => AgentMessage {
+                payload: Some(agent_message::Payload::Response(MessageResponse {
+                    request_id: response.request_id,
+                    event: Some(Event::Error(format!(
+                        "{} event dropped: {}",
+                        event_kind(response.event.as_ref()),
+                        err
+                    ))),
+                })),
+            },
+            payload => AgentMessage { payload },
+        }
+    }
+}
+
+fn event_kind(event: Option<&Event>) -> &'static str {
+    match event {
+        Some(Event::Text(_)) => "Text",
+        Some(Event::ToolResult(_)) => "Tool result",
+        Some(Event::ToolUse(_)) => "Tool use",
+        Some(Event::File(_)) => "File",
+        Some(Event::Done(_)) => "Done",
+        _ => "Response",
+    }
+}
+
+/// A message broke a size limit
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MessageTooLarge {
+    /// Encoded size of the message, in bytes
+    pub size: usize,
+    /// The limit it broke, in bytes
+    pub limit: usize,
+}
+
+impl std::fmt::Display for MessageTooLarge {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "message is {} bytes, over the {}-byte limit",
+            self.size, self.limit
+        )
+    }
+}
+
+impl std::error::Error for MessageTooLarge {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text_response(text: String) -> AgentMessage {
+        AgentMessage {
+            payload: Some(agent_message::Payload::Response(MessageResponse {
+                request_id: "req-1".to_string(),
+                event: Some(Event::Text(text)),
+            })),
+        }
+    }
+
+    #[test]
+    fn test_check_outgoing() {
+        let limits = MessageLimits::new(64);
+        assert!(limits
+            .check_outgoing(&text_response("hi".to_string()))
+            .is_ok());
+
+        let err = limits
+            .check_outgoing(&text_response("x".repeat(100)))
+            .unwrap_err();
+        assert_eq!(err.limit, 64);
+        assert!(err.size > 100);
+        assert_eq!(
+            err.to_string(),
+            format!("message is {} bytes, over the 64-byte limit", err.size)
+        );
+    }
+
+    #[test]
+    fn test_fit_agent_message_replaces_oversize_response() {
+        let limits = MessageLimits::new(1024);
+        let small = text_response("hi".to_string());
+        assert_eq!(limits.fit_agent_message(small.clone()), small);
+
+        let fitted = limits.fit_agent_message(text_response("x".repeat(4096)));
+        let Some(agent_message::Payload::Response(response)) = fitted.payload else {
+            panic!("expected a response");
+        };
+        assert_eq!(response.request_id, "req-1");
+        match response.event {
+            Some(Event::Error(message)) => {
+                assert!(message.starts_with("Text event dropped: message is "));
+                assert!(message.ends_with("over the 1024-byte limit"));
+            }
+            other => panic!("expected an error event, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_default_is_larger_than_tonic_default() {
+        let limits = MessageLimits::default();
+        assert_eq!(limits.max_decoding, DEFAULT_MAX_MESSAGE_SIZE);
+        assert!(limits.max_encoding > 4 * 1024 * 1024);
+    }
+}
@@ -0,0 +1,214 @@
+// ABOUTME: Per-request overrides of the agent's model and max tokens, shared by bridges, gateway and agents.
+// ABOUTME: Parses and validates `/coven set` values, checks values arriving over the wire, and describes the current settings.
+
+use serde::{Deserialize, Serialize};
+
+/// Longest model name accepted as an override.
+pub const MAX_MODEL_LEN: usize = 100;
+
+/// Largest max_tokens accepted as an override.
+pub const MAX_TOKENS_LIMIT: u32 = 200_000;
+
+/// Settings applied to a request in place of the agent's defaults, e.g. set
+/// per chat by a bridge. Unset fields use the agent's configuration.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RequestOverrides {
+    /// Model to use instead of the configured one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    /// Response token limit to use instead of the configured one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_tokens: Option<u32>,
+}
+
+impl RequestOverrides {
+    /// True when the agent's defaults apply unchanged.
+    pub fn is_empty(&self) -> bool {
+        self.model.is_none() && self.max_tokens.is_none()
+    }
+
+    /// Apply a `/coven set <key> <value>` setting. A value of `default`
+    /// clears the override.
+    pub fn set(&mut self, key: &str, value: &str) -> Result<(), InvalidOverride> {
+        let value = value.trim();
+        let clear = value.eq_ignore_ascii_case("default");
+        match key.trim().to_ascii_lowercase().as_str() {
+            "model" => {
+                self.model = if clear {
+                    None
+                } else {
+                    validate_model(value)?;
+                    Some(value.to_string())
+                };
+            }
+            "max_tokens" | "max-tokens" => {
+                self.max_tokens = if clear {
+                    None
+                } else {
+                    Some(parse_max_tokens(value)?)
+                };
+            }
+            other => {
+                return Err(InvalidOverride(format!(
+                    "unknown setting '{}' (expected model or max_tokens)",
+                    other
+                )))
+            }
+        }
+        Ok(())
+    }
+
+    /// Check values that arrived from elsewhere, e.g. in a SendMessage.
+    pub fn validate(&self) -> Result<(), InvalidOverride> {
+        if let Some(model) = &self.model {
+            validate_model(model)?;
+        }
+        if let Some(max_tokens) = self.max_tokens {
+            if !(1..=MAX_TOKENS_LIMIT).contains(&max_tokens) {
+                return Err(max_tokens_error(max_tokens));
+            }
+        }
+        Ok(())
+    }
+
+    /// One-line summary for status messages.
+    pub fn describe(&self) -> String {
+        if self.is_empty() {
+            return "agent defaults".to_string();
+        }
+        let model = self.model.as_deref().unwrap_or("default");
+        let max_tokens = self
+            .max_tokens
+            .map(|n| n.to_string())
+            .unwrap_or_else(|| "default".to_string());
+        format!("model {}, max_tokens {}", model, max_tokens)
+    }
+}
+
+/// Check that a model name is plausible: ASCII letters, digits, and
+/// `.` `-` `_` `:` `/` `@`, starting with a letter or digit.
+pub fn validate_model(model: &str) -> Result<(), InvalidOverride> {
+    let invalid = |reason: &str| {
+        Err(InvalidOverride(format!(
+            "invalid model '{}': {}",
+            model, reason
+        )))
+    };
+
+    if model.is_empty() {
+        return invalid("must not be empty");
+    }
+    if model.len() > MAX_MODEL_LEN {
+        return invalid(&format!("longer than {} characters", MAX_MODEL_LEN));
+    }
+    if !model.starts_with(|c: char| c.is_ascii_alphanumeric()) {
+        return invalid("must start with a letter or digit");
+    }
+    if let Some(c) = model
+        .chars()
+        .find(|c| !c.is_ascii_alphanumeric() && !matches!(c, '.' | '-' | '_' | ':' | '/' | '@'))
+    {
+        return invalid(&format!("unexpected character '{}'", c));
+    }
+    Ok(())
+}
+
+/// Parse a max_tokens value between 1 and MAX_TOKENS_LIMIT.
+pub fn parse_max_tokens(value: &str) -> Result<u32, InvalidOverride> {
+    match value.trim().parse::<u32>() {
+        Ok(n) if (1..=MAX_TOKENS_LIMIT).contains(&n) => Ok(n),
+        _ => Err(max_tokens_error(value)),
+    }
+}
+
+fn max_tokens_error(value: impl std::fmt::Display) -> InvalidOverride {
+    InvalidOverride(format!(
+        "invalid max_tokens '{}': expected a number from 1 to {}",
+        value, MAX_TOKENS_LIMIT
+    ))
+}
+
+/// An override value that isn't accepted, with the reason
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InvalidOverride(pub String);
+
+impl std::fmt::Display for InvalidOverride {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for InvalidOverride {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_model() {
+        assert!(validate_model("claude-sonnet-4-20250514").is_ok());
+        assert!(validate_model("anthropic/claude-3.5-haiku@latest").is_ok());
+        assert!(validate_model("").is_err());
+        assert!(validate_model("-rm").is_err());
+        assert!(validate_model("model name").is_err());
+        assert!(validate_model("model;drop").is_err());
+        assert!(validate_model(&"m".repeat(MAX_MODEL_LEN + 1)).is_err());
+    }
+
+    #[test]
+    fn test_parse_max_tokens_bounds() {
+        assert_eq!(parse_max_tokens("4096").unwrap(), 4096);
+        assert_eq!(parse_max_tokens(" 1 ").unwrap(), 1);
+        assert!(parse_max_tokens("0").is_err());
+        assert!(parse_max_tokens("200001").is_err());
+        assert!(parse_max_tokens("lots").is_err());
+    }
+
+    #[test]
+    fn test_set_and_clear() {
+        let mut overrides = RequestOverrides::default();
+        assert_eq!(overrides.describe(), "agent defaults");
+
+        overrides.set("model", "claude-haiku-4-5").unwrap();
+        overrides.set("max-tokens", "2048").unwrap();
+        assert_eq!(overrides.model.as_deref(), Some("claude-haiku-4-5"));
+        assert_eq!(overrides.max_tokens, Some(2048));
+        assert_eq!(
+            overrides.describe(),
+            "model claude-haiku-4-5, max_tokens 2048"
+        );
+
+        overrides.set("MODEL", "default").unwrap();
+        assert_eq!(overrides.describe(), "model default, max_tokens 2048");
+        overrides.set("max_tokens", "default").unwrap();
+        assert!(overrides.is_empty());
+    }
+
+    #[test]
+    fn test_validate_checks_received_values() {
+        assert!(RequestOverrides::default().validate().is_ok());
+        let overrides = |model: Option<&str>, max_tokens: Option<u32>| RequestOverrides {
+            model: model.map(String::from),
+            max_tokens,
+        };
+        assert!(overrides(Some("claude-haiku-4-5"), Some(2048))
+            .validate()
+            .is_ok());
+        assert!(overrides(Some("bad model"), None).validate().is_err());
+        assert!(overrides(None, Some(0)).validate().is_err());
+        assert!(overrides(None, Some(MAX_TOKENS_LIMIT + 1))
+            .validate()
+            .is_err());
+    }
+
+    #[test]
+    fn test_set_rejects_bad_input_without_changing() {
+        let mut overrides = RequestOverrides {
+            model: Some("claude-haiku-4-5".to_string()),
+            max_tokens: None,
+        };
+        assert!(overrides.set("model", "bad model").is_err());
+        assert!(overrides.set("temperature", "0.2").is_err());
+        assert_eq!(overrides.model.as_deref(), Some("claude-haiku-4-5"));
+    }
+}
@@ -0,0 +1,309 @@
+// ABOUTME: Redaction of sensitive tool input fields before inputs are logged or stored
+// ABOUTME: Fields are sensitive when the tool's schema marks them or their name looks like a credential
+
+use serde_json::Value;
+use std::collections::HashSet;
+
+/// Replacement for a redacted value
+pub const REDACTED: &str = "***";
+
+/// Words that make a field name look like a credential, e.g. `password`,
+/// `GITHUB_TOKEN`, or `clientSecret`
+const SENSITIVE_WORDS: &[&str] = &[
+    "password",
+    "passwd",
+    "passphrase",
+    "secret",
+    "token",
+    "apikey",
+    "authorization",
+    "credential",
+    "credentials",
+];
+
+/// Word pairs that do the same, e.g. `api_key` or `privateKey`
+const SENSITIVE_PAIRS: &[(&str, &str)] = &[
+    ("api", "key"),
+    ("private", "key"),
+    ("access", "key"),
+    ("secret", "key"),
+];
+
+/// Whether a field name looks like it holds a credential.
+///
+/// Names are split into words on `_`, `-`, `.`, spaces, and camelCase
+/// boundaries, so `max_tokens` and `tokenizer` are not matched but
+/// `access_token` and `apiKey` are.
+pub fn is_sensitive_name(name: &str) -> bool {
+    let words = split_words(name);
+    words.iter().any(|w| SENSITIVE_WORDS.contains(&w.as_str()))
+        || words
+            .windows(2)
+            .any(|pair| SENSITIVE_PAIRS.contains(&(pair[0].as_str(), pair[1].as_str())))
+}
+
+fn split_words(name: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut current = String::new();
+    let mut prev_lower = false;
+    for c in name.chars() {
+        if !c.is_alphanumeric() {
+            if !current.is_empty() {
+                words.push(std::mem::take(&mut current));
+            }
+            prev_lower = false;
+            continue;
+        }
+        if c.is_uppercase() && prev_lower && !current.is_empty() {
+            words.push(std::mem::take(&mut current));
+        }
+        prev_lower = c.is_lowercase() || c.is_ascii_digit();
+        current.extend(c.to_lowercase());
+    }
+    if !current.is_empty() {
+        words.push(current);
+    }
+    words
+}
+
+/// Redacts one tool's inputs.
+///
+/// A property is redacted when its schema has `"sensitive": true`, or when
+/// its name looks like a credential (see `is_sensitive_name`) and its
+/// schema doesn't say `"sensitive": false`. Names apply at any depth of
+/// the input.
+#[derive(Debug, Clone, Default)]
+pub struct InputRedactor {
+    /// Marked `"sensitive": true`
+    sensitive: HashSet<String>,
+    /// Marked `"sensitive": false`, overriding the name heuristic
+    exempt: HashSet<String>,
+}
+
+impl InputRedactor {
+    /// A redactor that only applies the name heuristic, for tools whose
+    /// schema isn't known.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A redactor for inputs matching `input_schema_json`. An unparseable
+    /// schema falls back to the name heuristic.
+    pub fn from_schema(input_schema_json: &str) -> Self {
+        let mut redactor = Self::default();
+        if let Ok(schema) = serde_json::from_str::<Value>(input_schema_json) {
+            redactor.collect(&schema);
+        }
+        redactor
+    }
+
+    fn collect(&mut self, schema: &Value) {
+        if let Some(properties) = schema.get("properties").and_then(Value::as_object) {
+            for (name, property) in properties {
+                match property.get("sensitive").and_then(Value::as_bool) {
+                    Some(true) => {
+                        self.sensitive.insert(name.clone());
+                    }
+                    Some(false) => {
+                        self.exempt.insert(name.clone());
+                    }
+                    None => {}
+                }
+                self.collect(property);
+            }
+        }
+        if let Some(items) = schema.get("items") {
+            self.collect(items);
+        }
+    }
+
+    /// Whether values under `name` are redacted.
+    pub fn is_sensitive(&self, name: &str) -> bool {
+        self.sensitive.contains(name) || (!self.exempt.contains(name) && is_sensitive_name(name))
+    }
+
+    /// Redact sensitive values in a JSON tool input. Input that isn't JSON
+    /// can't be inspected, so all of it is replaced.
+    pub fn redact(&self, input_json: &str) -> String {
+        match serde_json::from_str::<Value>(input_json) {
+            Ok(mut value) => {
+                self.redact_value(&mut value);
+                value.to_string()
+            }
+            Err(_) => REDACTED.to_string(),
+        }
+    }
+
+    /// Redact sensitive values in place.
+    pub fn redact_value(&self, value: &mut Value) {
+        match value {
+            Value::Object(map) => {
+                for (name, field) in map.iter_mut() {
+                    if self.is_sensitive(name) && !field.is_null() {
+                        *field = Value::String(REDACTED.to_string());
+                    } else {
+                        self.redact_value(field);
+                    }
+                }
+            }
+            Value::Array(items) => {
+                for item in items {
+                    self.redact_value(item);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+impl InputRedactor {
+    /// One-line summary of a tool input for logs and event history:
+    /// redacted, and cut to `max_chars` characters.
+    pub fn summary(&self, input: &Value, max_chars: usize) -> String {
+        let mut input = input.clone();
+        self.redact_value(&mut input);
+        let text = input.to_string();
+        if text.chars().count() <= max_chars {
+            return text;
+        }
+        let cut: String = text.chars().take(max_chars.saturating_sub(3)).collect();
+        format!("{}...", cut)
+    }
+}
+
+impl crate::ToolDefinition {
+    /// A redactor for this tool's inputs, built from its input schema.
+    pub fn input_redactor(&self) -> InputRedactor {
+        InputRedactor::from_schema(&self.input_schema_json)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_name_heuristic() {
+        for name in [
+            "password",
+            "db_password",
+            "GITHUB_TOKEN",
+            "access_token",
+            "clientSecret",
+            "api_key",
+            "apiKey",
+            "API-KEY",
+            "apikey",
+            "private_key",
+            "Authorization",
+            "credentials",
+        ] {
+            assert!(is_sensitive_name(name), "{name}");
+        }
+        for name in [
+            "max_tokens",
+            "tokenizer",
+            "key",
+            "keyword",
+            "author",
+            "secretary",
+            "title",
+        ] {
+            assert!(!is_sensitive_name(name), "{name}");
+        }
+    }
+
+    #[test]
+    fn test_redacts_heuristic_names_without_schema() {
+        let redacted = InputRedactor::new()
+            .redact(r#"{"url": "https://example.com", "api_key": "sk-123", "max_tokens": 10}"#);
+        let value: Value = serde_json::from_str(&redacted).unwrap();
+        assert_eq!(
+            value,
+            json!({"url": "https://example.com", "api_key": "***", "max_tokens": 10})
+        );
+    }
+
+    #[test]
+    fn test_schema_annotations_add_and_exempt_fields() {
+        let schema = json!({
+            "type": "object",
+            "properties": {
+                "pin": {"type": "string", "sensitive": true},
+                "count": {"type": "integer"},
+                "reset_token": {"type": "boolean", "sensitive": false},
+                "accounts": {
+                    "type": "array",
+                    "items": {
+                        "type": "object",
+                        "properties": {"iban": {"type": "string", "sensitive": true}}
+                    }
+                }
+            }
+        });
+        let redactor = InputRedactor::from_schema(&schema.to_string());
+        let redacted = redactor.redact(
+            &json!({
+                "pin": "1234",
+                "count": 3,
+                "reset_token": true,
+                "accounts": [{"iban": "DE89", "name": "Main"}]
+            })
+            .to_string(),
+        );
+        let value: Value = serde_json::from_str(&redacted).unwrap();
+        assert_eq!(
+            value,
+            json!({
+                "pin": "***",
+                "count": 3,
+                "reset_token": true,
+                "accounts": [{"iban": "***", "name": "Main"}]
+            })
+        );
+    }
+
+    #[test]
+    fn test_nested_objects_and_nulls() {
+        let redacted = InputRedactor::new().redact(
+            r#"{"auth": {"password": "hunter2", "user": "me"}, "token": null, "headers": [{"Authorization": "Bearer x"}]}"#,
+        );
+        assert!(!redacted.contains("hunter2"));
+        assert!(!redacted.contains("Bearer x"));
+        assert!(redacted.contains(r#""user":"me""#));
+        assert!(redacted.contains(r#""token":null"#));
+    }
+
+    #[test]
+    fn test_non_json_input_is_fully_redacted() {
+        assert_eq!(InputRedactor::new().redact("password=hunter2"), REDACTED);
+    }
+
+    #[test]
+    fn test_summary_is_redacted_and_cut() {
+        let input = json!({"password": "hunter2", "query": "x".repeat(100)});
+        let summary = InputRedactor::new().summary(&input, 40);
+        assert!(!summary.contains("hunter2"));
+        assert_eq!(summary.chars().count(), 40);
+        assert!(summary.ends_with("..."));
+        assert_eq!(
+            InputRedactor::new().summary(&json!({"q": "hi"}), 40),
+            r#"{"q":"hi"}"#
+        );
+    }
+
+    #[test]
+    fn test_tool_definition_redactor() {
+        let tool = crate::ToolDefinition {
+            name: "login".to_string(),
+            input_schema_json: r#"{"properties": {"otp": {"type": "string", "sensitive": true}}}"#
+                .to_string(),
+            ..Default::default()
+        };
+        assert_eq!(
+            tool.input_redactor().redact(r#"{"otp": "123456"}"#),
+            r#"{"otp":"***"}"#
+        );
+    }
+}
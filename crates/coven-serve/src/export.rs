@@ -0,0 +1,272 @@
+// ABOUTME: Conversation export for compliance dumps, streamed by AdminService.ExportThreads
+// ABOUTME: Pages through the store, turning threads, messages, tool calls and usage into JSON Lines records
+
+use crate::store::{ExportFilter, Message, Store, ThreadSummary, TokenUsage, ToolCall};
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use coven_proto::ExportRecord;
+use regex::Regex;
+use serde::Serialize;
+use tokio::sync::mpsc;
+use tonic::Status;
+
+/// Rows read from the store per query
+const PAGE_SIZE: i64 = 200;
+
+/// States a thread can be exported by: "open" when its last message
+/// awaits a reply, "answered" when the agent had the last word, and
+/// "empty" when it has no messages
+pub const THREAD_STATES: &[&str] = &["open", "answered", "empty"];
+
+/// One line of an export
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Record {
+    Thread {
+        thread_id: String,
+        agent_id: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        title: Option<String>,
+        state: &'static str,
+        participants: Vec<String>,
+        created_at: DateTime<Utc>,
+        updated_at: DateTime<Utc>,
+    },
+    Message {
+        thread_id: String,
+        id: String,
+        direction: String,
+        author: String,
+        content: String,
+        message_type: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        reply_to_message_id: Option<String>,
+        created_at: DateTime<Utc>,
+    },
+    ToolCall {
+        thread_id: String,
+        request_id: String,
+        tool_id: String,
+        name: String,
+        /// Unset when redacted
+        input: Option<serde_json::Value>,
+        input_redacted: bool,
+        output: Option<String>,
+        is_error: bool,
+        created_at: DateTime<Utc>,
+        completed_at: Option<DateTime<Utc>>,
+    },
+    Usage {
+        thread_id: String,
+        request_id: String,
+        input_tokens: i64,
+        output_tokens: i64,
+        cache_read_tokens: i64,
+        cache_write_tokens: i64,
+        thinking_tokens: i64,
+        created_at: DateTime<Utc>,
+    },
+}
+
+/// Drops tool inputs matching any of the gateway's redaction patterns
+/// before they're exported
+#[derive(Debug, Default)]
+pub struct Redactor {
+    patterns: Vec<Regex>,
+}
+
+impl Redactor {
+    pub fn new(patterns: &[String]) -> Result<Self> {
+        let patterns = patterns
+            .iter()
+            .map(|p| Regex::new(p).with_context(|| format!("redaction pattern {}", p)))
+            .collect::<Result<_>>()?;
+        Ok(Self { patterns })
+    }
+
+    /// `input_json` as exported, or None if a pattern matches it. Input
+    /// that isn't JSON is exported as a string.
+    fn input(&self, input_json: &str) -> Option<serde_json::Value> {
+        if self.patterns.iter().any(|p| p.is_match(input_json)) {
+            return None;
+        }
+        Some(
+            serde_json::from_str(input_json)
+                .unwrap_or_else(|_| serde_json::Value::String(input_json.to_string())),
+        )
+    }
+}
+
+/// The state `THREAD_STATES` names for a thread whose newest message went
+/// in `last_direction`
+fn thread_state(last_direction: Option<&str>) -> &'static str {
+    match last_direction {
+        None => "empty",
+        Some("inbound") => "open",
+        Some(_) => "answered",
+    }
+}
+
+/// Send every record `filter` covers to `tx`, a thread's record followed by
+/// its own, leaving out threads not in `state` if one is given. Stops early
+/// if the receiver goes away.
+pub async fn run(
+    store: &Store,
+    filter: &ExportFilter,
+    state: Option<&str>,
+    redactor: &Redactor,
+    tx: &mpsc::Sender<Result<ExportRecord, Status>>,
+) -> Result<()> {
+    let send = |record: Record| async move {
+        let json = serde_json::to_string(&record)?;
+        anyhow::Ok(tx.send(Ok(ExportRecord { json })).await.is_ok())
+    };
+
+    let mut after: Option<String> = None;
+    loop {
+        let threads = store
+            .export_threads(filter, after.as_deref(), PAGE_SIZE)
+            .await?;
+        let Some(last) = threads.last() else {
+            return Ok(());
+        };
+        after = Some(last.conversation.id.clone());
+
+        for thread in threads {
+            let current = thread_state(thread.last_direction.as_deref());
+            if state.is_some_and(|s| s != current) {
+                continue;
+            }
+            if !send(thread_record(&thread, current)).await? {
+                return Ok(());
+            }
+            let id = &thread.conversation.id;
+
+            let mut row = 0;
+            loop {
+                let page = store.export_messages(id, filter, row, PAGE_SIZE).await?;
+                let Some((rowid, _)) = page.last() else {
+                    break;
+                };
+                row = *rowid;
+                for (_, message) in page {
+                    if !send(message_record(message)).await? {
+                        return Ok(());
+                    }
+                }
+            }
+
+            let mut row = 0;
+            loop {
+                let page = store.export_tool_calls(id, filter, row, PAGE_SIZE).await?;
+                let Some((rowid, _)) = page.last() else {
+                    break;
+                };
+                row = *rowid;
+                for (_, call) in page {
+                    if !send(tool_call_record(id, call, redactor)).await? {
+                        return Ok(());
+                    }
+                }
+            }
+
+            let mut row = 0;
+            loop {
+                let page = store.export_usage(id, filter, row, PAGE_SIZE).await?;
+                let Some((rowid, _)) = page.last() else {
+                    break;
+                };
+                row = *rowid;
+                for (_, usage) in page {
+                    if !send(usage_record(id, usage)).await? {
+                        return Ok(());
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn thread_record(thread: &ThreadSummary, state: &'static str) -> Record {
+    Record::Thread {
+        thread_id: thread.conversation.id.clone(),
+        agent_id: thread.conversation.agent_id.clone(),
+        title: thread.conversation.title.clone(),
+        state,
+        participants: thread.participants.clone(),
+        created_at: thread.conversation.created_at,
+        updated_at: thread.conversation.updated_at,
+    }
+}
+
+fn message_record(message: Message) -> Record {
+    Record::Message {
+        thread_id: message.conversation_id,
+        id: message.id,
+        direction: message.direction,
+        author: message.author,
+        content: message.content,
+        message_type: message.message_type,
+        reply_to_message_id: message.reply_to_message_id,
+        created_at: message.created_at,
+    }
+}
+
+fn tool_call_record(thread_id: &str, call: ToolCall, redactor: &Redactor) -> Record {
+    let input = redactor.input(&call.input_json);
+    Record::ToolCall {
+        thread_id: thread_id.to_string(),
+        request_id: call.request_id,
+        tool_id: call.tool_id,
+        name: call.tool_name,
+        input_redacted: input.is_none(),
+        input,
+        output: call.output,
+        is_error: call.is_error,
+        created_at: call.created_at,
+        completed_at: call.completed_at,
+    }
+}
+
+fn usage_record(thread_id: &str, usage: TokenUsage) -> Record {
+    Record::Usage {
+        thread_id: thread_id.to_string(),
+        request_id: usage.request_id,
+        input_tokens: usage.input_tokens,
+        output_tokens: usage.output_tokens,
+        cache_read_tokens: usage.cache_read_tokens,
+        cache_write_tokens: usage.cache_write_tokens,
+        thinking_tokens: usage.thinking_tokens,
+        created_at: usage.created_at,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_redactor_drops_matching_inputs() {
+        let redactor = Redactor::new(&["(?i)password".to_string()]).unwrap();
+        assert_eq!(redactor.input(r#"{"password": "hunter2"}"#), None);
+        assert_eq!(
+            redactor.input(r#"{"path": "/tmp"}"#),
+            Some(serde_json::json!({ "path": "/tmp" }))
+        );
+        assert_eq!(
+            redactor.input("not json"),
+            Some(serde_json::Value::String("not json".to_string()))
+        );
+        assert!(Redactor::new(&["[unclosed".to_string()]).is_err());
+    }
+
+    #[test]
+    fn test_thread_state() {
+        assert_eq!(thread_state(None), "empty");
+        assert_eq!(thread_state(Some("inbound")), "open");
+        assert_eq!(thread_state(Some("outbound")), "answered");
+        for state in ["empty", "open", "answered"] {
+            assert!(THREAD_STATES.contains(&state));
+        }
+    }
+}
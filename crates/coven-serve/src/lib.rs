@@ -1,20 +1,72 @@
-// ABOUTME: Local gateway server for coven - "super trusted" mode without authentication
+// ABOUTME: Local gateway server for coven - "super trusted" mode, with optional role checks
 // ABOUTME: Implements CovenControl (agents), ClientService (TUI), and PackService (packs)
 
+pub mod export;
+pub mod moderation;
+pub mod pairing;
+pub mod push;
+pub mod roles;
+pub mod secrets;
 pub mod server;
 pub mod services;
 pub mod store;
+pub mod tokens;
+pub mod totp;
+pub mod webhook;
+
+pub use coven_proto::limits::MessageLimits;
+pub use moderation::ModerationConfig;
+pub use roles::RolesConfig;
+pub use server::{ListenAddr, RunningServer, Server, UNIX_SCHEME};
+pub use webhook::WebhookConfig;
 
 use anyhow::Result;
 use std::path::PathBuf;
+use std::time::Duration;
+
+/// How long tool calls and token usage are kept unless configured
+pub const DEFAULT_ACTIVITY_RETENTION: Duration = Duration::from_secs(30 * 24 * 60 * 60);
 
 /// Configuration for the local gateway server
 #[derive(Debug, Clone)]
 pub struct ServeConfig {
-    /// gRPC listen address (default: 127.0.0.1:50051)
+    /// gRPC listen address (default: 127.0.0.1:50051), or
+    /// `unix:///path/to.sock` to listen on a Unix domain socket
     pub grpc_addr: String,
+    /// Permissions for the Unix socket file (default: 0o600, owner only)
+    pub socket_mode: u32,
     /// SQLite database path (default: ~/.coven/local.db)
     pub db_path: PathBuf,
+    /// How long a write waits on a locked database before failing (default: 5 seconds)
+    pub db_busy_timeout: Duration,
+    /// Queue messages for offline agents instead of rejecting them (default: off)
+    pub dead_letter: Option<DeadLetterConfig>,
+    /// Master key that encrypts pack secrets, created on first start
+    /// (default: `secrets.key` next to the database)
+    pub secrets_key_path: Option<PathBuf>,
+    /// Largest gRPC message accepted or sent on any service (default: 16 MiB)
+    pub message_limits: MessageLimits,
+    /// Let admins tail all traffic through the gateway, content included
+    /// (default: off)
+    pub enable_tail: bool,
+    /// Webhook that receives a POST for each agent-initiated message and
+    /// tool approval request, with the registered push tokens (default: none)
+    pub push_webhook: Option<String>,
+    /// Check callers' roles before admin RPCs and gated tool approvals
+    /// (default: none, every client acts as the local owner)
+    pub roles: Option<RolesConfig>,
+    /// Filter messages through a denylist, length cap, or moderation
+    /// endpoint before they reach agents (default: none, nothing is checked)
+    pub moderation: Option<ModerationConfig>,
+    /// Regexes; exported tool calls whose input matches any of them are
+    /// exported without it (default: none, inputs are exported as is)
+    pub export_redactions: Vec<String>,
+    /// Webhooks sent agent connects and disconnects, errors, agent-initiated
+    /// messages and long-pending tool approvals (default: none)
+    pub webhooks: Vec<WebhookConfig>,
+    /// How long agents' tool calls, token usage and resolved tool
+    /// approvals are kept before they're pruned (default: 30 days)
+    pub activity_retention: Duration,
 }
 
 impl Default for ServeConfig {
@@ -25,7 +77,59 @@ impl Default for ServeConfig {
 
         Self {
             grpc_addr: "127.0.0.1:50051".to_string(),
+            socket_mode: 0o600,
             db_path,
+            db_busy_timeout: store::DEFAULT_BUSY_TIMEOUT,
+            dead_letter: None,
+            secrets_key_path: None,
+            message_limits: MessageLimits::default(),
+            enable_tail: false,
+            push_webhook: None,
+            roles: None,
+            moderation: None,
+            export_redactions: Vec::new(),
+            webhooks: Vec::new(),
+            activity_retention: DEFAULT_ACTIVITY_RETENTION,
+        }
+    }
+}
+
+impl ServeConfig {
+    /// Where the pack secrets master key lives
+    pub fn secrets_key_path(&self) -> PathBuf {
+        self.secrets_key_path.clone().unwrap_or_else(|| {
+            self.db_path
+                .parent()
+                .unwrap_or_else(|| std::path::Path::new("."))
+                .join("secrets.key")
+        })
+    }
+
+    /// JSON Lines file of webhook payloads that couldn't be delivered,
+    /// next to the database
+    pub fn webhook_dead_letter_path(&self) -> PathBuf {
+        self.db_path
+            .parent()
+            .unwrap_or_else(|| std::path::Path::new("."))
+            .join("webhook-dead-letters.jsonl")
+    }
+}
+
+/// Dead-letter queue settings for messages sent while their agent is offline
+#[derive(Debug, Clone)]
+pub struct DeadLetterConfig {
+    /// How long a queued message stays deliverable (default: 24 hours)
+    pub ttl: Duration,
+    /// Maximum queued messages per agent; the oldest are dropped first.
+    /// 0 keeps every message (default: 100)
+    pub max_per_agent: usize,
+}
+
+impl Default for DeadLetterConfig {
+    fn default() -> Self {
+        Self {
+            ttl: Duration::from_secs(24 * 60 * 60),
+            max_per_agent: 100,
         }
     }
 }
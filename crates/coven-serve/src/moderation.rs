@@ -0,0 +1,434 @@
+// ABOUTME: Optional content filter for the local gateway, off unless a moderation file is given
+// ABOUTME: Checks messages against a regex denylist, a length cap, and an external endpoint before routing
+
+use anyhow::{Context, Result};
+use coven_proto::{message_response, ClientSendMessageResponse, MessageResponse};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tracing::warn;
+
+/// How long the moderation endpoint may take to answer
+const ENDPOINT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Longest redacted excerpt of a blocked message put in the log, in characters
+const LOG_EXCERPT_CHARS: usize = 80;
+
+/// Streamed text kept from earlier chunks of a response, in characters, so
+/// a denied phrase split across chunks is still caught
+const STREAM_OVERLAP_CHARS: usize = 1024;
+
+/// Reply to the sender of a blocked message unless the file sets its own
+pub const DEFAULT_POLICY_MESSAGE: &str = ClientSendMessageResponse::BLOCKED_NOTICE;
+
+/// Stands in for agent text the outbound filter blocked
+pub const REMOVED_NOTICE: &str = "[removed by content policy]";
+
+/// What the gateway lets through. Loaded from TOML:
+///
+/// ```toml
+/// deny_patterns = ["(?i)ignore (all )?previous instructions"]
+/// max_length = 4000
+/// endpoint = "http://127.0.0.1:9000/moderate"
+/// filter_outbound = true
+/// policy_message = "Sorry, I can't pass that on."
+/// ```
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ModerationConfig {
+    /// Regexes; a message matching any of them is blocked
+    pub deny_patterns: Vec<String>,
+    /// Longest message accepted from a client, in characters (default: no cap)
+    pub max_length: Option<usize>,
+    /// URL each inbound message is POSTed to as a `ModerationRequest`; it
+    /// answers with a `ModerationVerdict` (default: none)
+    pub endpoint: Option<String>,
+    /// Block messages when the endpoint fails or times out (default: let
+    /// them through)
+    pub fail_closed: bool,
+    /// Also check agents' replies and agent-initiated messages against
+    /// `deny_patterns` (default: off)
+    pub filter_outbound: bool,
+    /// Reply to the sender of a blocked message
+    pub policy_message: String,
+}
+
+impl Default for ModerationConfig {
+    fn default() -> Self {
+        Self {
+            deny_patterns: Vec::new(),
+            max_length: None,
+            endpoint: None,
+            fail_closed: false,
+            filter_outbound: false,
+            policy_message: DEFAULT_POLICY_MESSAGE.to_string(),
+        }
+    }
+}
+
+impl ModerationConfig {
+    /// Read a moderation file
+    pub fn load(path: &Path) -> Result<Self> {
+        let text = std::fs::read_to_string(path)
+            .with_context(|| format!("reading moderation file: {}", path.display()))?;
+        let config: Self = toml::from_str(&text)
+            .with_context(|| format!("parsing moderation file: {}", path.display()))?;
+        // Catch bad patterns at startup rather than on the first message
+        ContentFilter::new(config.clone())
+            .with_context(|| format!("in moderation file: {}", path.display()))?;
+        Ok(config)
+    }
+}
+
+/// Body posted to the moderation endpoint
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ModerationRequest {
+    pub agent_id: String,
+    /// Platform ID of the sender when a bridge relayed the message, else `user`
+    pub sender: String,
+    pub content: String,
+}
+
+/// The moderation endpoint's answer
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ModerationVerdict {
+    pub allowed: bool,
+    /// Why the message was blocked, for the log
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
+
+/// Checks messages against a `ModerationConfig`
+#[derive(Debug)]
+pub struct ContentFilter {
+    config: ModerationConfig,
+    /// `config.deny_patterns`, compiled
+    patterns: Vec<Regex>,
+    http: reqwest::Client,
+    /// End of the text streamed so far, by agent and request
+    stream_tails: Mutex<HashMap<(String, String), String>>,
+}
+
+impl ContentFilter {
+    pub fn new(config: ModerationConfig) -> Result<Arc<Self>> {
+        let patterns = config
+            .deny_patterns
+            .iter()
+            .map(|p| Regex::new(p).with_context(|| format!("deny pattern {}", p)))
+            .collect::<Result<_>>()?;
+        Ok(Arc::new(Self {
+            config,
+            patterns,
+            http: reqwest::Client::new(),
+            stream_tails: Mutex::new(HashMap::new()),
+        }))
+    }
+
+    /// Reply to the sender of a blocked message
+    pub fn policy_message(&self) -> &str {
+        &self.config.policy_message
+    }
+
+    /// Why a client's message to `agent_id` must not reach it, or None to
+    /// let it through. Blocked messages are logged, redacted.
+    pub async fn check_inbound(
+        &self,
+        agent_id: &str,
+        sender: &str,
+        content: &str,
+    ) -> Option<String> {
+        let reason = match self.config.max_length {
+            Some(max) if content.chars().count() > max => {
+                Some(format!("longer than {} characters", max))
+            }
+            _ => self.denied(content),
+        };
+        let reason = match reason {
+            Some(reason) => Some(reason),
+            None => self.ask_endpoint(agent_id, sender, content).await,
+        };
+        if let Some(reason) = &reason {
+            log_blocked("inbound", agent_id, reason, content);
+        }
+        reason
+    }
+
+    /// Why agent text must not reach clients, or None to let it through.
+    /// Only the deny patterns apply, and only with `filter_outbound`.
+    pub fn check_outbound(&self, agent_id: &str, content: &str) -> Option<String> {
+        if !self.config.filter_outbound {
+            return None;
+        }
+        let reason = self.denied(content)?;
+        log_blocked("outbound", agent_id, &reason, content);
+        Some(reason)
+    }
+
+    /// Replace blocked text in an agent's response with `REMOVED_NOTICE`.
+    /// Each streamed chunk is checked together with the end of the text
+    /// before it, so a phrase split across chunks is caught; the full
+    /// response is checked again when it's done.
+    pub fn filter_response(&self, agent_id: &str, response: &mut MessageResponse) {
+        use message_response::Event;
+        if !self.config.filter_outbound {
+            return;
+        }
+        let key = (agent_id.to_string(), response.request_id.clone());
+        match &mut response.event {
+            Some(Event::Text(text)) => {
+                let mut tails = self.stream_tails.lock().unwrap();
+                let tail = tails.entry(key).or_default();
+                let window = format!("{}{}", tail, text);
+                if self.check_outbound(agent_id, &window).is_some() {
+                    *text = REMOVED_NOTICE.to_string();
+                    // What's left is what was already let through
+                    tail.clear();
+                } else {
+                    let skip = window.chars().count().saturating_sub(STREAM_OVERLAP_CHARS);
+                    *tail = window.chars().skip(skip).collect();
+                }
+            }
+            Some(Event::Done(done)) => {
+                self.stream_tails.lock().unwrap().remove(&key);
+                if self.check_outbound(agent_id, &done.full_response).is_some() {
+                    done.full_response = REMOVED_NOTICE.to_string();
+                }
+            }
+            Some(Event::Error(_)) | Some(Event::Cancelled(_)) => {
+                self.stream_tails.lock().unwrap().remove(&key);
+            }
+            _ => {}
+        }
+    }
+
+    /// Drop what's kept of `agent_id`'s unfinished responses, once it
+    /// disconnects and can't finish them
+    pub fn forget_agent(&self, agent_id: &str) {
+        self.stream_tails
+            .lock()
+            .unwrap()
+            .retain(|(agent, _), _| agent != agent_id);
+    }
+
+    fn denied(&self, content: &str) -> Option<String> {
+        self.patterns
+            .iter()
+            .find(|p| p.is_match(content))
+            .map(|p| format!("matches deny pattern {}", p.as_str()))
+    }
+
+    /// Ask the endpoint, if there is one, whether to block `content`
+    async fn ask_endpoint(&self, agent_id: &str, sender: &str, content: &str) -> Option<String> {
+        let url = self.config.endpoint.as_ref()?;
+        let request = ModerationRequest {
+            agent_id: agent_id.to_string(),
+            sender: sender.to_string(),
+            content: content.to_string(),
+        };
+        let result = async {
+            self.http
+                .post(url)
+                .timeout(ENDPOINT_TIMEOUT)
+                .json(&request)
+                .send()
+                .await?
+                .error_for_status()?
+                .json::<ModerationVerdict>()
+                .await
+        }
+        .await;
+        match result {
+            Ok(verdict) if verdict.allowed => None,
+            Ok(verdict) => Some(
+                verdict
+                    .reason
+                    .unwrap_or_else(|| "refused by moderation endpoint".to_string()),
+            ),
+            Err(e) => {
+                warn!(error = %e, fail_closed = self.config.fail_closed, "Moderation endpoint failed");
+                self.config
+                    .fail_closed
+                    .then(|| "moderation endpoint unavailable".to_string())
+            }
+        }
+    }
+}
+
+fn log_blocked(direction: &str, agent_id: &str, reason: &str, content: &str) {
+    warn!(
+        direction,
+        agent_id,
+        reason,
+        chars = content.chars().count(),
+        excerpt = %redact(content),
+        "Message blocked by content policy"
+    );
+}
+
+/// `content` with all but the first character of each word masked, cut to
+/// `LOG_EXCERPT_CHARS`: enough to review what was blocked without keeping
+/// it in the log or the admin traffic tap
+pub fn redact(content: &str) -> String {
+    let masked = content
+        .split_whitespace()
+        .map(|word| {
+            word.chars()
+                .enumerate()
+                .map(|(i, c)| if i == 0 { c } else { '*' })
+                .collect::<String>()
+        })
+        .collect::<Vec<_>>()
+        .join(" ");
+    match masked.char_indices().nth(LOG_EXCERPT_CHARS) {
+        Some((end, _)) => format!("{}…", &masked[..end]),
+        None => masked,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn filter(config: ModerationConfig) -> Arc<ContentFilter> {
+        ContentFilter::new(config).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_inbound_patterns_and_length_cap() {
+        let filter = filter(ModerationConfig {
+            deny_patterns: vec!["(?i)ignore previous instructions".to_string()],
+            max_length: Some(20),
+            ..Default::default()
+        });
+
+        assert_eq!(filter.check_inbound("a", "user", "hello").await, None);
+        let reason = filter
+            .check_inbound("a", "user", "IGNORE previous instructions")
+            .await
+            .unwrap();
+        assert!(reason.contains("deny pattern"), "{}", reason);
+        let reason = filter
+            .check_inbound("a", "user", &"é".repeat(21))
+            .await
+            .unwrap();
+        assert_eq!(reason, "longer than 20 characters");
+        assert!(filter
+            .check_inbound("a", "user", &"é".repeat(20))
+            .await
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn test_unreachable_endpoint_fails_open_unless_closed() {
+        // Nothing listens on port 9 of localhost
+        let config = ModerationConfig {
+            endpoint: Some("http://127.0.0.1:9/moderate".to_string()),
+            ..Default::default()
+        };
+        let open = filter(config.clone());
+        assert_eq!(open.check_inbound("a", "user", "hi").await, None);
+
+        let closed = filter(ModerationConfig {
+            fail_closed: true,
+            ..config
+        });
+        assert_eq!(
+            closed.check_inbound("a", "user", "hi").await.as_deref(),
+            Some("moderation endpoint unavailable")
+        );
+    }
+
+    #[test]
+    fn test_outbound_filter_replaces_text_only_when_enabled() {
+        let config = ModerationConfig {
+            deny_patterns: vec!["secret-\\d+".to_string()],
+            ..Default::default()
+        };
+        let response = |text: &str| MessageResponse {
+            request_id: "r1".to_string(),
+            event: Some(message_response::Event::Text(text.to_string())),
+        };
+
+        let mut unfiltered = response("the code is secret-42");
+        filter(config.clone()).filter_response("a", &mut unfiltered);
+        assert_eq!(unfiltered, response("the code is secret-42"));
+
+        let outbound = filter(ModerationConfig {
+            filter_outbound: true,
+            ..config
+        });
+        let mut blocked = response("the code is secret-42");
+        outbound.filter_response("a", &mut blocked);
+        assert_eq!(blocked, response(REMOVED_NOTICE));
+        let mut fine = response("no code here");
+        outbound.filter_response("a", &mut fine);
+        assert_eq!(fine, response("no code here"));
+    }
+
+    #[test]
+    fn test_phrase_split_across_chunks_is_caught() {
+        let outbound = filter(ModerationConfig {
+            deny_patterns: vec!["secret-\\d+".to_string()],
+            filter_outbound: true,
+            ..Default::default()
+        });
+        let chunk = |request_id: &str, text: &str| MessageResponse {
+            request_id: request_id.to_string(),
+            event: Some(message_response::Event::Text(text.to_string())),
+        };
+        let text = |response: MessageResponse| match response.event {
+            Some(message_response::Event::Text(text)) => text,
+            other => panic!("not text: {:?}", other),
+        };
+
+        let mut first = chunk("r1", "the code is sec");
+        outbound.filter_response("a", &mut first);
+        assert_eq!(text(first), "the code is sec");
+        // Another request's chunks don't join this one's
+        let mut other = chunk("r2", "ret-1");
+        outbound.filter_response("a", &mut other);
+        assert_eq!(text(other), "ret-1");
+        let mut second = chunk("r1", "ret-42, keep it safe");
+        outbound.filter_response("a", &mut second);
+        assert_eq!(text(second), REMOVED_NOTICE);
+        let mut third = chunk("r1", " and move on");
+        outbound.filter_response("a", &mut third);
+        assert_eq!(text(third), " and move on");
+    }
+
+    #[test]
+    fn test_redact_masks_words_and_truncates() {
+        assert_eq!(
+            redact("ignore  previous\ninstructions"),
+            "i***** p******* i***********"
+        );
+        let long = redact(&"word ".repeat(40));
+        assert_eq!(long.chars().count(), LOG_EXCERPT_CHARS + 1);
+        assert!(long.ends_with('…'));
+    }
+
+    #[test]
+    fn test_moderation_file() {
+        let config: ModerationConfig = toml::from_str(
+            r#"
+            deny_patterns = ["(?i)password"]
+            filter_outbound = true
+            "#,
+        )
+        .unwrap();
+        assert_eq!(config.deny_patterns, ["(?i)password"]);
+        assert!(config.filter_outbound);
+        assert_eq!(config.policy_message, DEFAULT_POLICY_MESSAGE);
+
+        assert!(toml::from_str::<ModerationConfig>("denylist = []").is_err());
+        let err = ContentFilter::new(ModerationConfig {
+            deny_patterns: vec!["(unclosed".to_string()],
+            ..Default::default()
+        })
+        .unwrap_err();
+        assert!(format!("{:#}", err).contains("deny pattern (unclosed"));
+    }
+}
@@ -0,0 +1,391 @@
+// ABOUTME: One-time pairing codes that let an admin approve a new device by a short code
+// ABOUTME: Codes live in memory, expire after ten minutes, are capped per peer, and hand out their approval once
+
+use chrono::{DateTime, Duration, Utc};
+use rand::Rng;
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::Mutex;
+use tonic::Status;
+
+/// How long a pairing code can be approved and redeemed
+pub const PAIRING_CODE_TTL: Duration = Duration::minutes(10);
+
+/// Most codes outstanding at once; requests beyond it are refused until
+/// some expire or are redeemed
+const MAX_OUTSTANDING: usize = 1000;
+
+/// Most codes outstanding at once from one address. Any fresh key can ask
+/// for a code, so without this one host could use up `MAX_OUTSTANDING`.
+const MAX_PER_PEER: usize = 3;
+
+/// Characters a code is made of: no 0/O or 1/I, which read alike
+const CODE_ALPHABET: &[u8] = b"ABCDEFGHJKLMNPQRSTUVWXYZ23456789";
+
+/// Characters in a code, not counting the dash in the middle
+const CODE_LEN: usize = 8;
+
+/// A device waiting for, or granted, approval
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Pairing {
+    /// Normalized code, as `normalize_code` returns it
+    pub code: String,
+    /// Fingerprint of the key that requested the code
+    pub fingerprint: String,
+    pub device_name: String,
+    /// Address the request came from; None over a Unix socket
+    pub peer: Option<IpAddr>,
+    pub expires_at: DateTime<Utc>,
+    /// Principal an admin approved the device as, once one has
+    pub approved_as: Option<String>,
+    /// Whether the device's key has been granted to `approved_as`. The
+    /// device isn't told it was approved until it has.
+    pub granted: bool,
+}
+
+impl Pairing {
+    /// The code as shown to people: two groups of four, e.g. "K7QM-3XWD"
+    pub fn display_code(&self) -> String {
+        let (first, second) = self.code.split_at(self.code.len() / 2);
+        format!("{}-{}", first, second)
+    }
+}
+
+/// Where a device's code stands, as reported to the device
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PairingState {
+    Pending,
+    /// Approved as this principal. Reported once; the code is gone after.
+    Approved {
+        principal_id: String,
+    },
+    Expired,
+}
+
+impl PairingState {
+    pub fn label(&self) -> &'static str {
+        match self {
+            PairingState::Pending => "pending",
+            PairingState::Approved { .. } => "approved",
+            PairingState::Expired => "expired",
+        }
+    }
+}
+
+/// Outstanding pairing codes, by normalized code
+#[derive(Debug)]
+pub struct PairingCodes {
+    ttl: Duration,
+    codes: Mutex<HashMap<String, Pairing>>,
+}
+
+impl Default for PairingCodes {
+    fn default() -> Self {
+        Self::new(PAIRING_CODE_TTL)
+    }
+}
+
+/// Upper-case `code` and drop dashes and whitespace, so "k7qm 3xwd" and
+/// "K7QM-3XWD" are the same code
+pub fn normalize_code(code: &str) -> String {
+    code.chars()
+        .filter(|c| *c != '-' && !c.is_whitespace())
+        .map(|c| c.to_ascii_uppercase())
+        .collect()
+}
+
+fn not_found() -> Status {
+    Status::not_found("unknown or expired pairing code")
+}
+
+impl PairingCodes {
+    /// Codes that expire `ttl` after they're issued
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            codes: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Issue a code for the device with key `fingerprint`, asking from
+    /// `peer`. A code the same key asked for earlier is replaced, so only
+    /// the newest one works.
+    pub fn request(
+        &self,
+        fingerprint: &str,
+        device_name: &str,
+        peer: Option<IpAddr>,
+        now: DateTime<Utc>,
+    ) -> Result<Pairing, Status> {
+        let mut codes = self.codes.lock().unwrap();
+        codes.retain(|_, p| p.expires_at > now && p.fingerprint != fingerprint);
+        if codes.values().filter(|p| p.peer == peer).count() >= MAX_PER_PEER {
+            return Err(Status::resource_exhausted(
+                "too many pairing codes outstanding from this address; approve or let one expire first",
+            ));
+        }
+        if codes.len() >= MAX_OUTSTANDING {
+            return Err(Status::resource_exhausted(
+                "too many pairing codes outstanding; try again in a few minutes",
+            ));
+        }
+
+        let mut rng = rand::thread_rng();
+        let code = loop {
+            let code: String = (0..CODE_LEN)
+                .map(|_| CODE_ALPHABET[rng.gen_range(0..CODE_ALPHABET.len())] as char)
+                .collect();
+            if !codes.contains_key(&code) {
+                break code;
+            }
+        };
+        let pairing = Pairing {
+            code: code.clone(),
+            fingerprint: fingerprint.to_string(),
+            device_name: device_name.to_string(),
+            peer,
+            expires_at: now + self.ttl,
+            approved_as: None,
+            granted: false,
+        };
+        codes.insert(code, pairing.clone());
+        Ok(pairing)
+    }
+
+    /// The pending pairing for `code`, for an admin about to approve it
+    pub fn pending(&self, code: &str, now: DateTime<Utc>) -> Result<Pairing, Status> {
+        let codes = self.codes.lock().unwrap();
+        match codes.get(&normalize_code(code)) {
+            Some(p) if p.expires_at <= now => Err(not_found()),
+            Some(p) if p.approved_as.is_some() => Err(Status::failed_precondition(
+                "pairing code was already approved",
+            )),
+            Some(p) => Ok(p.clone()),
+            None => Err(not_found()),
+        }
+    }
+
+    /// Record that `code` was approved as `principal_id`, before its key is
+    /// granted, so two admins can't both grant it. Each code is approved at
+    /// most once; `granted` then lets the device know, or `release` undoes
+    /// an approval whose grant failed.
+    pub fn approve(
+        &self,
+        code: &str,
+        principal_id: &str,
+        now: DateTime<Utc>,
+    ) -> Result<Pairing, Status> {
+        let mut codes = self.codes.lock().unwrap();
+        let pairing = codes
+            .get_mut(&normalize_code(code))
+            .filter(|p| p.expires_at > now)
+            .ok_or_else(not_found)?;
+        if pairing.approved_as.is_some() {
+            return Err(Status::failed_precondition(
+                "pairing code was already approved",
+            ));
+        }
+        pairing.approved_as = Some(principal_id.to_string());
+        Ok(pairing.clone())
+    }
+
+    /// Record that the key of the approved `code` was granted, so the
+    /// device's next status check reports the approval
+    pub fn granted(&self, code: &str) {
+        if let Some(pairing) = self.codes.lock().unwrap().get_mut(&normalize_code(code)) {
+            pairing.granted = true;
+        }
+    }
+
+    /// Undo the approval of `code`, whose key couldn't be granted, so it
+    /// can be approved again
+    pub fn release(&self, code: &str) {
+        if let Some(pairing) = self.codes.lock().unwrap().get_mut(&normalize_code(code)) {
+            pairing.approved_as = None;
+        }
+    }
+
+    /// Where `code` stands, for the device with key `fingerprint` that
+    /// requested it. Other keys get NotFound, as for an unknown code. An
+    /// approval is reported once, and the code can't be used after that.
+    pub fn status(
+        &self,
+        code: &str,
+        fingerprint: &str,
+        now: DateTime<Utc>,
+    ) -> Result<PairingState, Status> {
+        let code = normalize_code(code);
+        let mut codes = self.codes.lock().unwrap();
+        let pairing = codes
+            .get(&code)
+            .filter(|p| p.fingerprint == fingerprint)
+            .ok_or_else(not_found)?;
+        let state = match &pairing.approved_as {
+            Some(principal_id) if pairing.granted => PairingState::Approved {
+                principal_id: principal_id.clone(),
+            },
+            // Being granted; expiring now would strand the approval
+            Some(_) => return Ok(PairingState::Pending),
+            None if pairing.expires_at <= now => PairingState::Expired,
+            None => return Ok(PairingState::Pending),
+        };
+        codes.remove(&code);
+        Ok(state)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn now() -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339("2026-01-02T03:04:05Z")
+            .unwrap()
+            .with_timezone(&Utc)
+    }
+
+    #[test]
+    fn test_codes_are_short_and_unambiguous() {
+        let codes = PairingCodes::default();
+        let pairing = codes.request("fp-1", "laptop", None, now()).unwrap();
+        assert_eq!(pairing.code.len(), CODE_LEN);
+        assert!(pairing.code.bytes().all(|c| CODE_ALPHABET.contains(&c)));
+        assert_eq!(pairing.expires_at, now() + PAIRING_CODE_TTL);
+
+        let shown = pairing.display_code();
+        assert_eq!(shown.len(), CODE_LEN + 1);
+        assert_eq!(normalize_code(&shown), pairing.code);
+        assert_eq!(normalize_code(" k7qm-3x wd"), "K7QM3XWD");
+    }
+
+    #[test]
+    fn test_approval_is_redeemed_once() {
+        let codes = PairingCodes::default();
+        let pairing = codes.request("fp-1", "laptop", None, now()).unwrap();
+        let code = pairing.display_code().to_lowercase();
+
+        assert_eq!(
+            codes.status(&code, "fp-1", now()).unwrap(),
+            PairingState::Pending
+        );
+        // Only the requesting key can see the code
+        assert_eq!(
+            codes.status(&code, "fp-2", now()).unwrap_err().code(),
+            tonic::Code::NotFound
+        );
+
+        assert_eq!(codes.pending(&code, now()).unwrap(), pairing);
+        let approved = codes.approve(&code, "device:laptop", now()).unwrap();
+        assert_eq!(approved.approved_as.as_deref(), Some("device:laptop"));
+        let again = codes.approve(&code, "harper", now()).unwrap_err();
+        assert_eq!(again.code(), tonic::Code::FailedPrecondition);
+        assert_eq!(
+            codes.pending(&code, now()).unwrap_err().code(),
+            tonic::Code::FailedPrecondition
+        );
+
+        // The device waits until the key is granted
+        assert_eq!(
+            codes.status(&code, "fp-1", now()).unwrap(),
+            PairingState::Pending
+        );
+        codes.granted(&code);
+
+        assert_eq!(
+            codes.status(&code, "fp-1", now()).unwrap(),
+            PairingState::Approved {
+                principal_id: "device:laptop".to_string()
+            }
+        );
+        assert_eq!(
+            codes.status(&code, "fp-1", now()).unwrap_err().code(),
+            tonic::Code::NotFound
+        );
+    }
+
+    #[test]
+    fn test_released_approval_can_be_approved_again() {
+        let codes = PairingCodes::default();
+        let pairing = codes.request("fp-1", "laptop", None, now()).unwrap();
+        codes.approve(&pairing.code, "harper", now()).unwrap();
+        codes.release(&pairing.code);
+
+        assert_eq!(
+            codes.status(&pairing.code, "fp-1", now()).unwrap(),
+            PairingState::Pending
+        );
+        codes.approve(&pairing.code, "guest", now()).unwrap();
+        codes.granted(&pairing.code);
+        assert_eq!(
+            codes.status(&pairing.code, "fp-1", now()).unwrap(),
+            PairingState::Approved {
+                principal_id: "guest".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn test_codes_are_capped_per_peer() {
+        let codes = PairingCodes::default();
+        let peer: IpAddr = "203.0.113.7".parse().unwrap();
+        for i in 0..MAX_PER_PEER {
+            codes
+                .request(&format!("fp-{i}"), "bot", Some(peer), now())
+                .unwrap();
+        }
+        let err = codes
+            .request("fp-new", "bot", Some(peer), now())
+            .unwrap_err();
+        assert_eq!(err.code(), tonic::Code::ResourceExhausted);
+
+        // Other addresses are unaffected, and the cap lifts as codes expire
+        let other: IpAddr = "198.51.100.1".parse().unwrap();
+        assert!(codes
+            .request("fp-other", "phone", Some(other), now())
+            .is_ok());
+        let later = now() + PAIRING_CODE_TTL;
+        assert!(codes.request("fp-new", "bot", Some(peer), later).is_ok());
+    }
+
+    #[test]
+    fn test_codes_expire() {
+        let codes = PairingCodes::new(Duration::minutes(10));
+        let pairing = codes.request("fp-1", "laptop", None, now()).unwrap();
+        let almost = now() + Duration::minutes(10) - Duration::seconds(1);
+        let expired = now() + Duration::minutes(10);
+
+        assert!(codes.pending(&pairing.code, almost).is_ok());
+        assert_eq!(
+            codes.pending(&pairing.code, expired).unwrap_err().code(),
+            tonic::Code::NotFound
+        );
+        assert_eq!(
+            codes
+                .approve(&pairing.code, "p", expired)
+                .unwrap_err()
+                .code(),
+            tonic::Code::NotFound
+        );
+
+        // The device hears once that its code expired
+        assert_eq!(
+            codes.status(&pairing.code, "fp-1", expired).unwrap(),
+            PairingState::Expired
+        );
+        assert!(codes.status(&pairing.code, "fp-1", expired).is_err());
+    }
+
+    #[test]
+    fn test_new_request_replaces_old_code_and_purges_expired() {
+        let codes = PairingCodes::new(Duration::minutes(10));
+        let first = codes.request("fp-1", "laptop", None, now()).unwrap();
+        let stale = codes.request("fp-2", "phone", None, now()).unwrap();
+
+        let later = now() + Duration::minutes(11);
+        let second = codes.request("fp-1", "laptop", None, later).unwrap();
+        assert_ne!(first.code, second.code);
+        assert!(codes.pending(&first.code, later).is_err());
+        assert!(codes.status(&stale.code, "fp-2", later).is_err());
+        assert_eq!(codes.codes.lock().unwrap().len(), 1);
+    }
+}
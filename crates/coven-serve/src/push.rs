@@ -0,0 +1,216 @@
+// ABOUTME: Push notification delivery for the local gateway
+// ABOUTME: Posts agent-initiated messages and pending tool approvals to a webhook with the registered device tokens
+
+use crate::services::control::ControlState;
+use crate::store::{PushToken, Store};
+use coven_proto::{message_response, AgentInitiatedEvent};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::task::JoinHandle;
+use tracing::{debug, warn};
+
+/// How long one webhook delivery may take
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Longest message preview put in a notification, in characters
+const PREVIEW_CHARS: usize = 200;
+
+/// Body posted to the push webhook. The webhook owns the APNs and FCM
+/// credentials and fans the notification out to `targets`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PushNotification {
+    /// `agent_message` or `tool_approval`
+    pub kind: String,
+    pub agent_id: String,
+    /// Agent message ID, or the tool approval ID to answer
+    pub id: String,
+    pub title: String,
+    pub body: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub thread_id: Option<String>,
+    pub targets: Vec<PushTarget>,
+}
+
+/// A device the notification should reach
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PushTarget {
+    pub principal_id: String,
+    pub platform: String,
+    pub token: String,
+}
+
+impl From<PushToken> for PushTarget {
+    fn from(token: PushToken) -> Self {
+        Self {
+            principal_id: token.principal_id,
+            platform: token.platform,
+            token: token.token,
+        }
+    }
+}
+
+/// Sends a push for every agent-initiated message and tool approval request
+/// to the webhook at `url`
+#[derive(Clone)]
+pub struct PushNotifier {
+    store: Store,
+    url: String,
+    http: reqwest::Client,
+}
+
+impl PushNotifier {
+    pub fn new(store: Store, url: impl Into<String>) -> Self {
+        Self {
+            store,
+            url: url.into(),
+            http: reqwest::Client::new(),
+        }
+    }
+
+    /// Follow the gateway's agent traffic until it shuts down
+    pub fn spawn(self, control: &ControlState) -> JoinHandle<()> {
+        let mut initiated = control.subscribe_initiated();
+        let mut responses = control.subscribe_responses();
+        tokio::spawn(async move {
+            loop {
+                let notification = tokio::select! {
+                    event = initiated.recv() => match event {
+                        Ok(event) => agent_message(event),
+                        Err(RecvError::Lagged(n)) => {
+                            warn!(skipped = n, "Push notifier fell behind agent messages");
+                            continue;
+                        }
+                        Err(RecvError::Closed) => break,
+                    },
+                    response = responses.recv() => match response {
+                        Ok(response) => match response.response.event {
+                            Some(message_response::Event::ToolApprovalRequest(request)) => {
+                                tool_approval(response.agent_id, request)
+                            }
+                            _ => continue,
+                        },
+                        Err(RecvError::Lagged(n)) => {
+                            warn!(skipped = n, "Push notifier fell behind agent responses");
+                            continue;
+                        }
+                        Err(RecvError::Closed) => break,
+                    },
+                };
+                // Deliver in the background so a slow webhook never holds up
+                // the broadcast receivers
+                let notifier = self.clone();
+                tokio::spawn(async move { notifier.deliver(notification).await });
+            }
+        })
+    }
+
+    /// Post `notification` to every registered device. Failures are logged:
+    /// a missed push never affects the message itself.
+    async fn deliver(&self, mut notification: PushNotification) {
+        let tokens = match self.store.list_push_tokens(None).await {
+            Ok(tokens) => tokens,
+            Err(e) => {
+                warn!(error = %e, "Failed to load push tokens");
+                return;
+            }
+        };
+        if tokens.is_empty() {
+            return;
+        }
+        notification.targets = tokens.into_iter().map(PushTarget::from).collect();
+
+        let result = self
+            .http
+            .post(&self.url)
+            .timeout(WEBHOOK_TIMEOUT)
+            .json(&notification)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status());
+        match result {
+            Ok(_) => debug!(
+                kind = %notification.kind,
+                id = %notification.id,
+                devices = notification.targets.len(),
+                "Push notification sent"
+            ),
+            Err(e) => warn!(
+                kind = %notification.kind,
+                id = %notification.id,
+                error = %e,
+                "Push webhook failed"
+            ),
+        }
+    }
+}
+
+fn agent_message(event: AgentInitiatedEvent) -> PushNotification {
+    let title = if event.agent_name.is_empty() {
+        event.agent_id.clone()
+    } else {
+        event.agent_name
+    };
+    PushNotification {
+        kind: "agent_message".to_string(),
+        agent_id: event.agent_id,
+        id: event.message_id,
+        title,
+        body: preview(&event.content),
+        thread_id: event.thread_id,
+        targets: Vec::new(),
+    }
+}
+
+fn tool_approval(agent_id: String, request: coven_proto::ToolApprovalRequest) -> PushNotification {
+    let body = request
+        .confirm_message
+        .unwrap_or_else(|| format!("Allow {}?", request.name));
+    PushNotification {
+        kind: "tool_approval".to_string(),
+        title: format!("{} needs approval", agent_id),
+        agent_id,
+        id: request.id,
+        body: preview(&body),
+        thread_id: None,
+        targets: Vec::new(),
+    }
+}
+
+/// First `PREVIEW_CHARS` characters of `text`, with an ellipsis if cut
+fn preview(text: &str) -> String {
+    match text.char_indices().nth(PREVIEW_CHARS) {
+        Some((end, _)) => format!("{}…", &text[..end]),
+        None => text.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_preview_truncates_on_char_boundary() {
+        assert_eq!(preview("short"), "short");
+        let long = "é".repeat(PREVIEW_CHARS + 5);
+        let cut = preview(&long);
+        assert_eq!(cut.chars().count(), PREVIEW_CHARS + 1);
+        assert!(cut.ends_with('…'));
+    }
+
+    #[test]
+    fn test_tool_approval_uses_confirm_message() {
+        let push = tool_approval(
+            "agent-1".to_string(),
+            coven_proto::ToolApprovalRequest {
+                id: "tool-1".to_string(),
+                name: "bash".to_string(),
+                input_json: "{}".to_string(),
+                confirm_message: None,
+            },
+        );
+        assert_eq!(push.kind, "tool_approval");
+        assert_eq!(push.id, "tool-1");
+        assert_eq!(push.body, "Allow bash?");
+    }
+}
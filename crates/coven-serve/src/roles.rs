@@ -0,0 +1,824 @@
+// ABOUTME: Role-based authorization for the local gateway, off unless a roles file is given
+// ABOUTME: Identifies callers by their signed SSH key, or a token issued for one, and checks roles before admin RPCs and tool approvals
+// ABOUTME: Key rotations register new fingerprints for a principal, with a grace period for the old ones
+// ABOUTME: Optionally demands a TOTP code from the caller's enrolled secret before destructive admin RPCs
+
+use crate::pairing::PairingCodes;
+use crate::secrets::MasterKey;
+use crate::store::{KeyGrant, Store};
+use crate::tokens::{bearer_token, IssuedToken, TokenIssuer};
+use crate::totp;
+use anyhow::{Context, Result};
+use chrono::Utc;
+use coven_proto::RotateKeyResponse;
+use coven_ssh::{
+    compute_fingerprint, verify_request, NonceCache, PublicKey, SshError,
+    DEFAULT_ROTATION_GRACE_SECS, DEFAULT_SKEW_TOLERANCE,
+};
+use serde::Deserialize;
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use std::sync::{Arc, Mutex, OnceLock, RwLock};
+use std::time::{Duration, Instant};
+use tonic::metadata::MetadataMap;
+use tonic::{Request, Status};
+
+pub const OWNER: &str = "owner";
+pub const MEMBER: &str = "member";
+
+/// Longest grace period a key rotation may give the old keys
+pub const MAX_ROTATION_GRACE_SECS: u32 = 7 * 24 * 60 * 60;
+
+/// Wrong one-time codes a principal may send before it's locked out
+const MAX_OTP_FAILURES: u32 = 5;
+
+/// How long a principal's wrong codes count against it, and how long the
+/// lockout lasts
+const OTP_LOCKOUT: Duration = Duration::from_secs(5 * 60);
+
+/// Who holds which roles, and what each role may do. Loaded from TOML:
+///
+/// ```toml
+/// default_roles = ["member"]
+/// admin_roles = ["owner"]
+/// signature_skew_secs = 120
+/// require_otp = false
+///
+/// [tool_roles]
+/// deploy = ["owner"]
+///
+/// [[principals]]
+/// name = "Harper"
+/// public_key = "ssh-ed25519 AAAAC3Nza... harper@laptop"
+/// roles = ["owner"]
+/// ```
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RolesConfig {
+    /// Roles of a signed caller whose key isn't listed in `principals`
+    /// (default: member). Unsigned callers have none.
+    pub default_roles: Vec<String>,
+    /// Admin RPCs require one of these roles (default: owner)
+    pub admin_roles: Vec<String>,
+    /// Approving a tool with one of these names requires one of its roles;
+    /// unlisted tools can be approved by anyone
+    pub tool_roles: HashMap<String, Vec<String>>,
+    /// Principals with roles of their own, by SSH key
+    pub principals: Vec<PrincipalRoles>,
+    /// How far a signature's timestamp may be from the gateway's clock,
+    /// either way (default: 120)
+    pub signature_skew_secs: u64,
+    /// Destructive admin RPCs need a one-time code from the caller's
+    /// enrolled TOTP secret (default: off)
+    pub require_otp: bool,
+}
+
+impl Default for RolesConfig {
+    fn default() -> Self {
+        Self {
+            default_roles: vec![MEMBER.to_string()],
+            admin_roles: vec![OWNER.to_string()],
+            tool_roles: HashMap::new(),
+            principals: Vec::new(),
+            signature_skew_secs: DEFAULT_SKEW_TOLERANCE.as_secs(),
+            require_otp: false,
+        }
+    }
+}
+
+/// One principal's entry in the roles file
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PrincipalRoles {
+    pub name: String,
+    /// OpenSSH public key, as in a `.pub` file
+    pub public_key: String,
+    pub roles: Vec<String>,
+}
+
+impl RolesConfig {
+    /// Read a roles file
+    pub fn load(path: &Path) -> Result<Self> {
+        let text = std::fs::read_to_string(path)
+            .with_context(|| format!("reading roles file: {}", path.display()))?;
+        let config: Self = toml::from_str(&text)
+            .with_context(|| format!("parsing roles file: {}", path.display()))?;
+        // Catch bad keys at startup rather than on the first request
+        Authorizer::new(config.clone())
+            .with_context(|| format!("in roles file: {}", path.display()))?;
+        Ok(config)
+    }
+}
+
+/// The principal behind a request
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Caller {
+    pub principal_id: String,
+    pub display_name: String,
+    pub roles: Vec<String>,
+    /// Fingerprint of the key that signed the request; None when unsigned
+    pub key_fingerprint: Option<String>,
+}
+
+impl Caller {
+    /// A caller that didn't sign its request
+    fn anonymous() -> Self {
+        Self {
+            principal_id: "anonymous".to_string(),
+            display_name: "Anonymous".to_string(),
+            roles: Vec::new(),
+            key_fingerprint: None,
+        }
+    }
+
+    /// Whether the caller holds any of `roles`
+    pub fn has_any(&self, roles: &[String]) -> bool {
+        roles.iter().any(|r| self.roles.contains(r))
+    }
+}
+
+/// Checks requests against a `RolesConfig`
+#[derive(Debug)]
+pub struct Authorizer {
+    config: RolesConfig,
+    /// `config.principals` with parsed keys
+    principals: Vec<(PublicKey, PrincipalRoles)>,
+    /// Keys registered by rotations, by fingerprint. They take precedence
+    /// over the roles file, so a rotated-out key stops working there too.
+    grants: RwLock<HashMap<String, KeyGrant>>,
+    /// Nonces of signatures already accepted, so none is accepted twice
+    nonces: NonceCache,
+    /// Codes of devices waiting to be paired with a principal
+    pairings: PairingCodes,
+    /// TOTP secrets by principal, for one-time codes
+    otp_secrets: RwLock<HashMap<String, Vec<u8>>>,
+    /// Encrypts TOTP secrets at rest; set when they're loaded
+    otp_key: OnceLock<MasterKey>,
+    /// Codes accepted and refused, by principal
+    otp_attempts: Mutex<HashMap<String, OtpAttempts>>,
+    /// Signs and checks bearer tokens; set once the master key is loaded
+    tokens: OnceLock<TokenIssuer>,
+    /// IDs of tokens revoked before they expired
+    revoked_tokens: RwLock<HashSet<String>>,
+}
+
+/// A principal's recent one-time codes
+#[derive(Debug, Default)]
+struct OtpAttempts {
+    /// Step of the last code accepted; codes up to it are used up
+    used_through: Option<i64>,
+    /// Wrong codes since `failing_since`
+    failures: u32,
+    failing_since: Option<Instant>,
+}
+
+impl Authorizer {
+    pub fn new(config: RolesConfig) -> Result<Arc<Self>> {
+        let principals = config
+            .principals
+            .iter()
+            .map(|p| {
+                let key = PublicKey::from_openssh(p.public_key.trim())
+                    .with_context(|| format!("public key of principal {}", p.name))?;
+                Ok((key, p.clone()))
+            })
+            .collect::<Result<_>>()?;
+        Ok(Arc::new(Self {
+            config,
+            principals,
+            grants: RwLock::new(HashMap::new()),
+            nonces: NonceCache::default(),
+            pairings: PairingCodes::default(),
+            otp_secrets: RwLock::new(HashMap::new()),
+            otp_key: OnceLock::new(),
+            otp_attempts: Mutex::new(HashMap::new()),
+            tokens: OnceLock::new(),
+            revoked_tokens: RwLock::new(HashSet::new()),
+        }))
+    }
+
+    /// Outstanding device pairing codes
+    pub fn pairings(&self) -> &PairingCodes {
+        &self.pairings
+    }
+
+    /// Replace the key grants with those stored
+    pub fn load_grants(&self, grants: Vec<KeyGrant>) {
+        *self.grants.write().unwrap() = grants
+            .into_iter()
+            .map(|g| (g.fingerprint.clone(), g))
+            .collect();
+    }
+
+    /// Replace the TOTP secrets with those stored, decrypted with `key`,
+    /// which also encrypts the ones enrolled from now on. Secrets older
+    /// gateways stored in the clear are encrypted in place.
+    pub async fn load_otp_secrets(&self, store: &Store, key: MasterKey) -> Result<()> {
+        let mut secrets = HashMap::new();
+        for stored in store.list_otp_secrets().await? {
+            let secret = match &stored.nonce {
+                Some(nonce) => key.decrypt_otp(&stored.principal_id, nonce, &stored.ciphertext)?,
+                None => {
+                    let (nonce, ciphertext) =
+                        key.encrypt_otp(&stored.principal_id, &stored.ciphertext)?;
+                    store
+                        .set_otp_secret(&stored.principal_id, &nonce, &ciphertext)
+                        .await?;
+                    stored.ciphertext
+                }
+            };
+            secrets.insert(stored.principal_id, secret);
+        }
+        *self.otp_secrets.write().unwrap() = secrets;
+        let _ = self.otp_key.set(key);
+        Ok(())
+    }
+
+    /// Issue and accept bearer tokens signed with `key`
+    pub fn issue_tokens_with(&self, key: MasterKey) {
+        let _ = self.tokens.set(TokenIssuer::new(key));
+    }
+
+    /// Replace the revoked token IDs with those stored
+    pub fn load_revoked_tokens(&self, ids: Vec<String>) {
+        *self.revoked_tokens.write().unwrap() = ids.into_iter().collect();
+    }
+
+    /// Refuse the token `id` from now on, even before it expires
+    pub fn revoke_token(&self, id: &str) {
+        self.revoked_tokens.write().unwrap().insert(id.to_string());
+    }
+
+    /// A token for `caller`, who must have signed with a key: a token can't
+    /// renew itself, or a stolen one would never run out
+    pub fn issue_token(&self, caller: &Caller) -> Result<IssuedToken, Status> {
+        if caller.key_fingerprint.is_none() {
+            return Err(Status::unauthenticated(
+                "sign the request with the device key to get a token",
+            ));
+        }
+        let issuer = self
+            .tokens
+            .get()
+            .ok_or_else(|| Status::unavailable("the gateway isn't issuing tokens yet"))?;
+        Ok(issuer.issue(&caller.principal_id, Utc::now()))
+    }
+
+    /// Identify the caller from the request's SSH signature headers, or
+    /// failing those its bearer token. A bad, expired or replayed signature
+    /// or a bad, expired or revoked token is an error; neither at all makes
+    /// an anonymous caller.
+    pub fn caller(&self, metadata: &MetadataMap) -> Result<Caller, Status> {
+        let key = verify_request(
+            metadata,
+            None,
+            Duration::from_secs(self.config.signature_skew_secs),
+            &self.nonces,
+        )
+        .map_err(signature_refused)?;
+        let Some(key) = key else {
+            return match (bearer_token(metadata), self.tokens.get()) {
+                (Some(token), Some(issuer)) => {
+                    let token = issuer.verify(token, Utc::now())?;
+                    if self.revoked_tokens.read().unwrap().contains(&token.id) {
+                        return Err(Status::unauthenticated(
+                            "token revoked; run 'coven link' to get a new one",
+                        ));
+                    }
+                    Ok(self.principal(&token.principal_id, None))
+                }
+                _ => Ok(Caller::anonymous()),
+            };
+        };
+        let fingerprint =
+            compute_fingerprint(&key).map_err(|e| Status::unauthenticated(e.to_string()))?;
+
+        let grant = self.grants.read().unwrap().get(&fingerprint).cloned();
+        if let Some(grant) = grant {
+            if grant.expires_at.is_some_and(|t| t <= Utc::now()) {
+                return Err(Status::unauthenticated(format!(
+                    "key {} was rotated out of {}; sign with its replacement",
+                    &fingerprint[..16],
+                    grant.principal_id
+                )));
+            }
+            return Ok(self.principal(&grant.principal_id, Some(fingerprint)));
+        }
+        match self
+            .principals
+            .iter()
+            .find(|(known, _)| known.key_data() == key.key_data())
+        {
+            Some((_, principal)) => Ok(Caller {
+                principal_id: principal.name.clone(),
+                display_name: principal.name.clone(),
+                roles: principal.roles.clone(),
+                key_fingerprint: Some(fingerprint),
+            }),
+            None => Ok(self.principal(&format!("key:{}", &fingerprint[..16]), Some(fingerprint))),
+        }
+    }
+
+    /// `principal_id` signing with the key `fingerprint`, or presenting a
+    /// token. Principals outside the roles file have the default roles.
+    fn principal(&self, principal_id: &str, fingerprint: Option<String>) -> Caller {
+        match self.principals.iter().find(|(_, p)| p.name == principal_id) {
+            Some((_, principal)) => Caller {
+                principal_id: principal.name.clone(),
+                display_name: principal.name.clone(),
+                roles: principal.roles.clone(),
+                key_fingerprint: fingerprint,
+            },
+            None => Caller {
+                principal_id: principal_id.to_string(),
+                display_name: "Unknown key".to_string(),
+                roles: self.config.default_roles.clone(),
+                key_fingerprint: fingerprint,
+            },
+        }
+    }
+
+    /// Which principal the key `fingerprint` belongs to, if any, counting
+    /// rotated-out keys
+    fn owner_of(&self, fingerprint: &str) -> Option<String> {
+        if let Some(grant) = self.grants.read().unwrap().get(fingerprint) {
+            return Some(grant.principal_id.clone());
+        }
+        self.principals
+            .iter()
+            .find(|(key, _)| compute_fingerprint(key).is_ok_and(|fp| fp == fingerprint))
+            .map(|(_, p)| p.name.clone())
+    }
+
+    /// Fingerprints of the keys `principal_id` can sign with now, or None if
+    /// the gateway doesn't know the principal
+    pub fn active_fingerprints(&self, principal_id: &str) -> Option<Vec<String>> {
+        let grants = self.grants.read().unwrap();
+        let known = grants.values().any(|g| g.principal_id == principal_id)
+            || self.principals.iter().any(|(_, p)| p.name == principal_id);
+        if !known {
+            return None;
+        }
+        let now = Utc::now();
+        let from_file = self
+            .principals
+            .iter()
+            .filter(|(_, p)| p.name == principal_id)
+            .filter_map(|(key, _)| compute_fingerprint(key).ok())
+            .filter(|fp| !grants.contains_key(fp));
+        let granted = grants
+            .values()
+            .filter(|g| g.principal_id == principal_id)
+            .filter(|g| !g.expires_at.is_some_and(|t| t <= now))
+            .map(|g| g.fingerprint.clone());
+        let mut fingerprints: Vec<String> = from_file.chain(granted).collect();
+        fingerprints.sort();
+        fingerprints.dedup();
+        Some(fingerprints)
+    }
+
+    /// Make `new_fingerprint` a key of `principal_id`, leaving
+    /// `old_fingerprints` working for `grace_secs` (default: a day) so
+    /// clients still using them can switch over.
+    pub async fn rotate(
+        &self,
+        store: &Store,
+        principal_id: &str,
+        old_fingerprints: Vec<String>,
+        new_fingerprint: &str,
+        grace_secs: Option<u32>,
+    ) -> Result<RotateKeyResponse, Status> {
+        let new_fingerprint = new_fingerprint.trim().to_ascii_lowercase();
+        if new_fingerprint.len() != 64 || !new_fingerprint.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(Status::invalid_argument(
+                "fingerprint must be a SHA-256 fingerprint: 64 hex characters",
+            ));
+        }
+        let grace_secs = grace_secs.unwrap_or(DEFAULT_ROTATION_GRACE_SECS);
+        if grace_secs > MAX_ROTATION_GRACE_SECS {
+            return Err(Status::invalid_argument(format!(
+                "grace period is at most {} seconds",
+                MAX_ROTATION_GRACE_SECS
+            )));
+        }
+        match self.owner_of(&new_fingerprint) {
+            Some(owner) if owner != principal_id => {
+                return Err(Status::already_exists(format!(
+                    "key {} already belongs to {}",
+                    &new_fingerprint[..16],
+                    owner
+                )));
+            }
+            _ => {}
+        }
+
+        // Rotating to a key the principal already has is a no-op for it
+        let old_fingerprints: Vec<String> = old_fingerprints
+            .into_iter()
+            .filter(|fp| *fp != new_fingerprint)
+            .collect();
+        let expires_at = Utc::now() + chrono::Duration::seconds(grace_secs.into());
+        store
+            .rotate_key(
+                principal_id,
+                &old_fingerprints,
+                &new_fingerprint,
+                expires_at,
+            )
+            .await
+            .map_err(|e| Status::internal(format!("database error: {}", e)))?;
+        let grants = store
+            .list_key_grants()
+            .await
+            .map_err(|e| Status::internal(format!("database error: {}", e)))?;
+        self.load_grants(grants);
+
+        Ok(RotateKeyResponse {
+            principal_id: principal_id.to_string(),
+            new_fingerprint,
+            old_fingerprints,
+            old_keys_expire_at: expires_at.to_rfc3339(),
+        })
+    }
+
+    /// Make `fingerprint` a key of `principal_id` alongside its current
+    /// ones, as when approving a paired device
+    pub async fn grant(
+        &self,
+        store: &Store,
+        principal_id: &str,
+        fingerprint: &str,
+    ) -> Result<(), Status> {
+        self.rotate(store, principal_id, Vec::new(), fingerprint, Some(0))
+            .await
+            .map(|_| ())
+    }
+
+    /// The caller, if allowed to make admin RPCs
+    pub fn require_admin(&self, metadata: &MetadataMap) -> Result<Caller, Status> {
+        let caller = self.caller(metadata)?;
+        if !caller.has_any(&self.config.admin_roles) {
+            return Err(denied(&caller, "admin RPCs", &self.config.admin_roles));
+        }
+        Ok(caller)
+    }
+
+    /// When the roles file sets `require_otp`, check the one-time code in
+    /// `metadata` against `caller`'s TOTP secret. Guards destructive admin
+    /// RPCs.
+    pub fn check_otp(&self, caller: &Caller, metadata: &MetadataMap) -> Result<(), Status> {
+        if !self.config.require_otp {
+            return Ok(());
+        }
+        let secret = self
+            .otp_secrets
+            .read()
+            .unwrap()
+            .get(&caller.principal_id)
+            .cloned()
+            .ok_or_else(|| {
+                Status::failed_precondition(format!(
+                    "this call needs a one-time code, but {} has no TOTP secret; enroll one with `coven admin otp enroll`",
+                    caller.principal_id
+                ))
+            })?;
+        self.check_code(&caller.principal_id, &secret, totp::code_from(metadata))
+    }
+
+    /// Give `principal_id` (default: `caller`) a new TOTP secret and return
+    /// whose it is and the secret. This takes a current `code` from the
+    /// caller's own secret, so a stolen key alone can't enroll or replace
+    /// one. The exception is the first admin on a gateway where nobody has
+    /// a secret yet; everyone after is enrolled by an admin who has one.
+    pub async fn enroll_otp(
+        &self,
+        store: &Store,
+        caller: &Caller,
+        principal_id: Option<&str>,
+        code: Option<&str>,
+    ) -> Result<(String, Vec<u8>), Status> {
+        let target = principal_id
+            .filter(|p| !p.is_empty())
+            .unwrap_or(&caller.principal_id)
+            .to_string();
+        let (own, anyone_enrolled) = {
+            let secrets = self.otp_secrets.read().unwrap();
+            (
+                secrets.get(&caller.principal_id).cloned(),
+                !secrets.is_empty(),
+            )
+        };
+        match own {
+            Some(own) => self.check_code(&caller.principal_id, &own, code)?,
+            None if target != caller.principal_id => {
+                return Err(Status::failed_precondition(format!(
+                    "enrolling {} takes a code from your own TOTP secret, and {} has none",
+                    target, caller.principal_id
+                )))
+            }
+            None if anyone_enrolled => {
+                return Err(Status::failed_precondition(format!(
+                    "other admins already have TOTP secrets; ask one to run `coven admin otp enroll --for {}`",
+                    caller.principal_id
+                )))
+            }
+            None => {}
+        }
+
+        let key = self
+            .otp_key
+            .get()
+            .ok_or_else(|| Status::internal("one-time code secrets aren't loaded"))?;
+        let secret = totp::generate_secret();
+        let (nonce, ciphertext) = key
+            .encrypt_otp(&target, &secret)
+            .map_err(|e| Status::internal(e.to_string()))?;
+        store
+            .set_otp_secret(&target, &nonce, &ciphertext)
+            .await
+            .map_err(|e| Status::internal(format!("database error: {}", e)))?;
+        self.otp_secrets
+            .write()
+            .unwrap()
+            .insert(target.clone(), secret.clone());
+        // Steps used with the old secret say nothing about the new one
+        self.otp_attempts.lock().unwrap().remove(&target);
+        Ok((target, secret))
+    }
+
+    /// Check `principal_id`'s one-time `code`, accepting each code once and
+    /// locking the principal out for a while after too many wrong ones
+    fn check_code(
+        &self,
+        principal_id: &str,
+        secret: &[u8],
+        code: Option<&str>,
+    ) -> Result<(), Status> {
+        let mut attempts = self.otp_attempts.lock().unwrap();
+        let attempts = attempts.entry(principal_id.to_string()).or_default();
+        if let Some(since) = attempts.failing_since {
+            let elapsed = since.elapsed();
+            if elapsed >= OTP_LOCKOUT {
+                attempts.failures = 0;
+                attempts.failing_since = None;
+            } else if attempts.failures >= MAX_OTP_FAILURES {
+                return Err(Status::resource_exhausted(format!(
+                    "too many wrong one-time codes for {}; try again in {}s",
+                    principal_id,
+                    (OTP_LOCKOUT - elapsed).as_secs() + 1
+                )));
+            }
+        }
+        match totp::check(secret, code, attempts.used_through) {
+            Ok(step) => {
+                attempts.used_through = Some(step);
+                attempts.failures = 0;
+                attempts.failing_since = None;
+                Ok(())
+            }
+            Err(status) => {
+                if code.is_some() {
+                    attempts.failures += 1;
+                    attempts.failing_since.get_or_insert_with(Instant::now);
+                }
+                Err(status)
+            }
+        }
+    }
+
+    /// Whether `caller` may approve `tool_name`
+    pub fn check_tool(&self, caller: &Caller, tool_name: &str) -> Result<(), Status> {
+        match self.config.tool_roles.get(tool_name) {
+            Some(roles) if !caller.has_any(roles) => {
+                Err(denied(caller, &format!("approving {}", tool_name), roles))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Interceptor for the admin service: admits only admins when there is an
+/// authorizer, and everyone in trusted mode. Admitted admins are left in
+/// the request's extensions for the handlers, since a signature can only
+/// be checked once.
+pub fn admin_interceptor(
+    authorizer: Option<Arc<Authorizer>>,
+) -> impl FnMut(Request<()>) -> Result<Request<()>, Status> + Clone {
+    move |mut request: Request<()>| {
+        if let Some(authorizer) = &authorizer {
+            let caller = authorizer.require_admin(request.metadata())?;
+            request.extensions_mut().insert(caller);
+        }
+        Ok(request)
+    }
+}
+
+/// Answer to key rotation RPCs when callers aren't identified by key
+pub fn rotation_needs_roles() -> Status {
+    Status::failed_precondition(
+        "key rotation needs a roles file: without one the gateway doesn't identify callers by key",
+    )
+}
+
+/// Answer to one-time code RPCs when callers aren't identified by key
+pub fn otp_needs_roles() -> Status {
+    Status::failed_precondition(
+        "one-time codes need a roles file: without one the gateway doesn't identify callers by key",
+    )
+}
+
+/// Answer to token RPCs when callers aren't identified by key
+pub fn tokens_need_roles() -> Status {
+    Status::failed_precondition(
+        "tokens need a roles file: without one every client is trusted and needs none",
+    )
+}
+
+/// Answer to pairing RPCs when callers aren't identified by key
+pub fn pairing_needs_roles() -> Status {
+    Status::failed_precondition(
+        "pairing needs a roles file: without one every client is trusted and can link directly",
+    )
+}
+
+/// Answer to a request whose signature didn't verify. A full nonce cache
+/// is the gateway's problem, not the caller's, so it's worth retrying.
+pub fn signature_refused(err: SshError) -> Status {
+    match err {
+        SshError::NonceCacheFull => Status::unavailable(err.to_string()),
+        err => Status::unauthenticated(err.to_string()),
+    }
+}
+
+fn denied(caller: &Caller, what: &str, roles: &[String]) -> Status {
+    let has = if caller.roles.is_empty() {
+        "none".to_string()
+    } else {
+        caller.roles.join(", ")
+    };
+    Status::permission_denied(format!(
+        "{} requires one of the roles: {}; {} has: {}",
+        what,
+        roles.join(", "),
+        caller.principal_id,
+        has
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use coven_ssh::{PrivateKey, SshAuthCredentials};
+
+    fn key() -> PrivateKey {
+        let dir = tempfile::tempdir().unwrap();
+        coven_ssh::generate_key(&dir.path().join("id_ed25519")).unwrap()
+    }
+
+    fn signed(key: &PrivateKey) -> MetadataMap {
+        let mut request = Request::new(());
+        SshAuthCredentials::new(key)
+            .unwrap()
+            .apply_to_request(&mut request)
+            .unwrap();
+        request.metadata().clone()
+    }
+
+    fn authorizer(owner: &PrivateKey) -> Arc<Authorizer> {
+        Authorizer::new(RolesConfig {
+            tool_roles: HashMap::from([("deploy".to_string(), vec![OWNER.to_string()])]),
+            principals: vec![PrincipalRoles {
+                name: "harper".to_string(),
+                public_key: owner.public_key().to_openssh().unwrap(),
+                roles: vec![OWNER.to_string()],
+            }],
+            ..Default::default()
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn test_callers_get_their_roles() {
+        let owner = key();
+        let authz = authorizer(&owner);
+
+        let caller = authz.caller(&signed(&owner)).unwrap();
+        assert_eq!(caller.principal_id, "harper");
+        assert_eq!(caller.roles, [OWNER]);
+
+        let stranger = authz.caller(&signed(&key())).unwrap();
+        assert!(stranger.principal_id.starts_with("key:"));
+        assert_eq!(stranger.roles, [MEMBER]);
+
+        let anonymous = authz.caller(&MetadataMap::new()).unwrap();
+        assert_eq!(anonymous, Caller::anonymous());
+    }
+
+    #[test]
+    fn test_bad_signature_is_unauthenticated() {
+        let owner = key();
+        let authz = authorizer(&owner);
+        let mut metadata = signed(&owner);
+        metadata.insert("x-ssh-nonce", "0000".parse().unwrap());
+        let err = authz.caller(&metadata).unwrap_err();
+        assert_eq!(err.code(), tonic::Code::Unauthenticated);
+    }
+
+    #[test]
+    fn test_replayed_signature_is_unauthenticated() {
+        let owner = key();
+        let authz = authorizer(&owner);
+        let metadata = signed(&owner);
+        assert_eq!(authz.caller(&metadata).unwrap().principal_id, "harper");
+
+        let err = authz.caller(&metadata).unwrap_err();
+        assert_eq!(err.code(), tonic::Code::Unauthenticated);
+        assert!(err.message().contains("replayed"));
+
+        // A client clock outside the tolerance gets a distinct error
+        let timestamp = coven_ssh::current_timestamp() - 121;
+        let nonce = coven_ssh::generate_nonce();
+        let mut metadata = signed(&owner);
+        let signature =
+            coven_ssh::sign_message(&owner, &format!("{}|{}", timestamp, nonce)).unwrap();
+        metadata.insert("x-ssh-timestamp", timestamp.to_string().parse().unwrap());
+        metadata.insert("x-ssh-nonce", nonce.parse().unwrap());
+        metadata.insert("x-ssh-signature", signature.parse().unwrap());
+        let err = authz.caller(&metadata).unwrap_err();
+        assert!(err.message().contains("expired"), "{}", err.message());
+    }
+
+    #[test]
+    fn test_admin_and_tool_checks() {
+        let owner = key();
+        let authz = authorizer(&owner);
+        let member = authz.caller(&signed(&key())).unwrap();
+
+        assert!(authz.require_admin(&signed(&owner)).is_ok());
+        let err = authz.require_admin(&signed(&key())).unwrap_err();
+        assert_eq!(err.code(), tonic::Code::PermissionDenied);
+        assert!(err.message().contains("requires one of the roles: owner"));
+
+        assert!(authz.check_tool(&member, "read_file").is_ok());
+        let err = authz.check_tool(&member, "deploy").unwrap_err();
+        assert_eq!(err.code(), tonic::Code::PermissionDenied);
+        let owner = authz.caller(&signed(&owner)).unwrap();
+        assert!(authz.check_tool(&owner, "deploy").is_ok());
+    }
+
+    #[test]
+    fn test_key_grants_override_roles_file() {
+        let owner = key();
+        let authz = authorizer(&owner);
+        let new = key();
+        let fp = |k: &PrivateKey| compute_fingerprint(k.public_key()).unwrap();
+        let grant = |k: &PrivateKey, expires_at| KeyGrant {
+            fingerprint: fp(k),
+            principal_id: "harper".to_string(),
+            expires_at,
+            created_at: Utc::now(),
+        };
+        assert_eq!(authz.active_fingerprints("harper"), Some(vec![fp(&owner)]));
+
+        authz.load_grants(vec![
+            grant(&owner, Some(Utc::now() - chrono::Duration::seconds(1))),
+            grant(&new, None),
+        ]);
+        let caller = authz.caller(&signed(&new)).unwrap();
+        assert_eq!(caller.principal_id, "harper");
+        assert_eq!(caller.roles, [OWNER]);
+        assert_eq!(caller.key_fingerprint, Some(fp(&new)));
+
+        let err = authz.caller(&signed(&owner)).unwrap_err();
+        assert_eq!(err.code(), tonic::Code::Unauthenticated);
+        assert!(err.message().contains("rotated out of harper"));
+        assert_eq!(authz.active_fingerprints("harper"), Some(vec![fp(&new)]));
+        assert_eq!(authz.active_fingerprints("nobody"), None);
+    }
+
+    #[test]
+    fn test_roles_file() {
+        let config: RolesConfig = toml::from_str(
+            r#"
+            [tool_roles]
+            deploy = ["owner"]
+            "#,
+        )
+        .unwrap();
+        assert_eq!(config.default_roles, [MEMBER]);
+        assert_eq!(config.admin_roles, [OWNER]);
+        assert_eq!(config.tool_roles["deploy"], [OWNER]);
+
+        assert!(toml::from_str::<RolesConfig>("admins = []").is_err());
+        let bad_key = RolesConfig {
+            principals: vec![PrincipalRoles {
+                name: "harper".to_string(),
+                public_key: "ssh-ed25519 nope".to_string(),
+                roles: vec![],
+            }],
+            ..Default::default()
+        };
+        let err = Authorizer::new(bad_key).unwrap_err();
+        assert!(format!("{:#}", err).contains("principal harper"));
+    }
+}
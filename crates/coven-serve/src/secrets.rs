@@ -0,0 +1,421 @@
+// ABOUTME: Gateway-managed pack secrets, encrypted at rest with the gateway's master key (as are TOTP secrets, and tokens are signed with it)
+// ABOUTME: Values are only decrypted to hand to the owning pack; changes are broadcast to watchers
+
+use crate::store::{EncryptedSecret, Store};
+use anyhow::{anyhow, bail, Context, Result};
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng, Payload};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use chrono::Utc;
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Arc;
+use tokio::sync::broadcast;
+use tracing::info;
+
+/// Length of the master key file in bytes
+const MASTER_KEY_LEN: usize = 32;
+
+/// Key that encrypts every pack secret and TOTP secret in the store, and
+/// signs the bearer tokens the gateway issues
+#[derive(Clone)]
+pub struct MasterKey {
+    cipher: ChaCha20Poly1305,
+    /// Derived from the master key, so tokens and secrets share no key
+    token_key: [u8; 32],
+}
+
+impl std::fmt::Debug for MasterKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("MasterKey(<redacted>)")
+    }
+}
+
+impl MasterKey {
+    /// Load the key at `path`, generating and saving a new one (readable
+    /// only by the owner) if the file doesn't exist.
+    ///
+    /// Losing this file makes every stored secret unreadable.
+    pub fn load_or_generate(path: &Path) -> Result<Self> {
+        if path.exists() {
+            let bytes = std::fs::read(path)
+                .with_context(|| format!("reading secrets key: {}", path.display()))?;
+            if bytes.len() != MASTER_KEY_LEN {
+                bail!(
+                    "secrets key {} is {} bytes, expected {}",
+                    path.display(),
+                    bytes.len(),
+                    MASTER_KEY_LEN
+                );
+            }
+            return Ok(Self::from_bytes(&bytes));
+        }
+
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)
+                .with_context(|| format!("creating directory: {}", parent.display()))?;
+        }
+        let key = ChaCha20Poly1305::generate_key(&mut OsRng);
+        write_private(path, &key)
+            .with_context(|| format!("writing secrets key: {}", path.display()))?;
+        info!(path = %path.display(), "Generated new secrets key");
+        Ok(Self::from_bytes(&key))
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Self {
+        let mut derive =
+            Hmac::<Sha256>::new_from_slice(bytes).expect("HMAC takes keys of any length");
+        derive.update(b"coven token signing");
+        Self {
+            cipher: ChaCha20Poly1305::new(Key::from_slice(bytes)),
+            token_key: derive.finalize().into_bytes().into(),
+        }
+    }
+
+    /// Signature of a bearer token's header and claims
+    pub fn sign_token(&self, signing_input: &[u8]) -> Vec<u8> {
+        self.token_mac(signing_input)
+            .finalize()
+            .into_bytes()
+            .to_vec()
+    }
+
+    /// Whether `signature` is `sign_token(signing_input)`, compared in
+    /// constant time
+    pub fn verify_token(&self, signing_input: &[u8], signature: &[u8]) -> bool {
+        self.token_mac(signing_input)
+            .verify_slice(signature)
+            .is_ok()
+    }
+
+    fn token_mac(&self, signing_input: &[u8]) -> Hmac<Sha256> {
+        let mut mac =
+            Hmac::<Sha256>::new_from_slice(&self.token_key).expect("HMAC takes keys of any length");
+        mac.update(signing_input);
+        mac
+    }
+
+    /// Encrypt `value`, bound to its pack and key so a stored ciphertext
+    /// can't be moved to another row.
+    pub fn encrypt(&self, pack_id: &str, key: &str, value: &str) -> Result<(Vec<u8>, Vec<u8>)> {
+        self.seal(&associated_data(pack_id, key), value.as_bytes())
+            .map_err(|_| anyhow!("encrypting secret {}/{}", pack_id, key))
+    }
+
+    /// Decrypt a stored secret. Fails if it was encrypted with another key
+    /// or for another pack or key name.
+    pub fn decrypt(&self, secret: &EncryptedSecret) -> Result<String> {
+        let plaintext = self
+            .open(
+                &associated_data(&secret.pack_id, &secret.key),
+                &secret.nonce,
+                &secret.ciphertext,
+            )
+            .map_err(|_| {
+                anyhow!(
+                    "secret {}/{} can't be decrypted with this gateway's key",
+                    secret.pack_id,
+                    secret.key
+                )
+            })?;
+        String::from_utf8(plaintext)
+            .map_err(|_| anyhow!("secret {}/{} isn't valid UTF-8", secret.pack_id, secret.key))
+    }
+
+    /// Encrypt a principal's TOTP secret, bound to that principal
+    pub fn encrypt_otp(&self, principal_id: &str, secret: &[u8]) -> Result<(Vec<u8>, Vec<u8>)> {
+        self.seal(&otp_associated_data(principal_id), secret)
+            .map_err(|_| anyhow!("encrypting TOTP secret of {}", principal_id))
+    }
+
+    /// Decrypt a TOTP secret from `encrypt_otp`
+    pub fn decrypt_otp(
+        &self,
+        principal_id: &str,
+        nonce: &[u8],
+        ciphertext: &[u8],
+    ) -> Result<Vec<u8>> {
+        self.open(&otp_associated_data(principal_id), nonce, ciphertext)
+            .map_err(|_| {
+                anyhow!(
+                    "TOTP secret of {} can't be decrypted with this gateway's key",
+                    principal_id
+                )
+            })
+    }
+
+    fn seal(&self, aad: &[u8], msg: &[u8]) -> Result<(Vec<u8>, Vec<u8>), chacha20poly1305::Error> {
+        let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let ciphertext = self.cipher.encrypt(&nonce, Payload { msg, aad })?;
+        Ok((nonce.to_vec(), ciphertext))
+    }
+
+    fn open(
+        &self,
+        aad: &[u8],
+        nonce: &[u8],
+        msg: &[u8],
+    ) -> Result<Vec<u8>, chacha20poly1305::Error> {
+        if nonce.len() != 12 {
+            return Err(chacha20poly1305::Error);
+        }
+        self.cipher
+            .decrypt(Nonce::from_slice(nonce), Payload { msg, aad })
+    }
+}
+
+fn associated_data(pack_id: &str, key: &str) -> Vec<u8> {
+    format!("{}\0{}", pack_id, key).into_bytes()
+}
+
+/// Ends with a NUL, which a pack secret's never does since key names are
+/// non-empty and NUL-free, so one can't pass for the other
+fn otp_associated_data(principal_id: &str) -> Vec<u8> {
+    format!("otp\0{}\0", principal_id).into_bytes()
+}
+
+#[cfg(unix)]
+fn write_private(path: &Path, bytes: &[u8]) -> std::io::Result<()> {
+    use std::io::Write;
+    use std::os::unix::fs::OpenOptionsExt;
+    let mut file = std::fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .mode(0o600)
+        .open(path)?;
+    file.write_all(bytes)
+}
+
+#[cfg(not(unix))]
+fn write_private(path: &Path, bytes: &[u8]) -> std::io::Result<()> {
+    std::fs::write(path, bytes)
+}
+
+/// Check a secret name: letters, digits, `_`, `-` and `.`, like an
+/// environment variable.
+pub fn validate_key(key: &str) -> Result<()> {
+    if key.is_empty() {
+        bail!("secret key must not be empty");
+    }
+    if !key
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.'))
+    {
+        bail!(
+            "secret key '{}' may only contain letters, digits, '_', '-' and '.'",
+            key
+        );
+    }
+    Ok(())
+}
+
+/// Pack secrets in the store, with change notifications for connected packs
+pub struct SecretVault {
+    store: Store,
+    key: MasterKey,
+    /// Pack ID whose secrets just changed
+    changed: broadcast::Sender<String>,
+}
+
+impl SecretVault {
+    pub fn new(store: Store, key: MasterKey) -> Arc<Self> {
+        let (changed, _) = broadcast::channel(16);
+        Arc::new(Self {
+            store,
+            key,
+            changed,
+        })
+    }
+
+    /// Store `value` for `pack_id`, replacing any existing value. Returns
+    /// true if the key is new.
+    pub async fn set(&self, pack_id: &str, key: &str, value: &str) -> Result<bool> {
+        validate_key(key)?;
+        let (nonce, ciphertext) = self.key.encrypt(pack_id, key, value)?;
+        let created = self
+            .store
+            .upsert_pack_secret(&EncryptedSecret {
+                pack_id: pack_id.to_string(),
+                key: key.to_string(),
+                nonce,
+                ciphertext,
+                updated_at: Utc::now(),
+            })
+            .await?;
+        info!(pack_id = %pack_id, key = %key, created, "Pack secret set");
+        let _ = self.changed.send(pack_id.to_string());
+        Ok(created)
+    }
+
+    /// Remove a secret. Returns true if it existed.
+    pub async fn delete(&self, pack_id: &str, key: &str) -> Result<bool> {
+        let deleted = self.store.delete_pack_secret(pack_id, key).await?;
+        if deleted {
+            info!(pack_id = %pack_id, key = %key, "Pack secret deleted");
+            let _ = self.changed.send(pack_id.to_string());
+        }
+        Ok(deleted)
+    }
+
+    /// A pack's secrets without their values, ordered by key
+    pub async fn list(&self, pack_id: &str) -> Result<Vec<EncryptedSecret>> {
+        self.store.list_pack_secrets(pack_id).await
+    }
+
+    /// A pack's decrypted secrets, to send to that pack
+    pub async fn values(&self, pack_id: &str) -> Result<HashMap<String, String>> {
+        self.store
+            .list_pack_secrets(pack_id)
+            .await?
+            .iter()
+            .map(|secret| Ok((secret.key.clone(), self.key.decrypt(secret)?)))
+            .collect()
+    }
+
+    /// Receive the ID of each pack whose secrets change
+    pub fn subscribe(&self) -> broadcast::Receiver<String> {
+        self.changed.subscribe()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    async fn vault(dir: &TempDir) -> Arc<SecretVault> {
+        let store = Store::open(&dir.path().join("test.db")).await.unwrap();
+        let key = MasterKey::load_or_generate(&dir.path().join("secrets.key")).unwrap();
+        SecretVault::new(store, key)
+    }
+
+    #[tokio::test]
+    async fn test_values_are_encrypted_at_rest() {
+        let dir = TempDir::new().unwrap();
+        let vault = vault(&dir).await;
+
+        assert!(vault
+            .set("github-pack", "TOKEN", "ghp_secret")
+            .await
+            .unwrap());
+        let stored = vault.list("github-pack").await.unwrap();
+        assert_eq!(stored.len(), 1);
+        assert!(!stored[0]
+            .ciphertext
+            .windows(b"ghp_secret".len())
+            .any(|w| w == b"ghp_secret"));
+
+        let values = vault.values("github-pack").await.unwrap();
+        assert_eq!(values["TOKEN"], "ghp_secret");
+    }
+
+    #[tokio::test]
+    async fn test_set_replaces_and_delete_removes() {
+        let dir = TempDir::new().unwrap();
+        let vault = vault(&dir).await;
+        let mut changes = vault.subscribe();
+
+        assert!(vault.set("github-pack", "TOKEN", "old").await.unwrap());
+        assert!(!vault.set("github-pack", "TOKEN", "new").await.unwrap());
+        assert_eq!(vault.values("github-pack").await.unwrap()["TOKEN"], "new");
+        assert_eq!(changes.recv().await.unwrap(), "github-pack");
+        assert_eq!(changes.recv().await.unwrap(), "github-pack");
+
+        assert!(vault.delete("github-pack", "TOKEN").await.unwrap());
+        assert!(!vault.delete("github-pack", "TOKEN").await.unwrap());
+        assert_eq!(changes.recv().await.unwrap(), "github-pack");
+        assert!(changes.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_pack_without_secrets_gets_none() {
+        let dir = TempDir::new().unwrap();
+        let vault = vault(&dir).await;
+        vault
+            .set("github-pack", "TOKEN", "ghp_secret")
+            .await
+            .unwrap();
+
+        assert!(vault.values("other-pack").await.unwrap().is_empty());
+        assert!(vault.list("other-pack").await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_key_file_is_reused_across_restarts() {
+        let dir = TempDir::new().unwrap();
+        vault(&dir)
+            .await
+            .set("github-pack", "TOKEN", "ghp_secret")
+            .await
+            .unwrap();
+
+        // Same key file: readable
+        let reopened = vault(&dir).await;
+        assert_eq!(
+            reopened.values("github-pack").await.unwrap()["TOKEN"],
+            "ghp_secret"
+        );
+
+        // Another key: not readable
+        let other = TempDir::new().unwrap();
+        let store = Store::open(&dir.path().join("test.db")).await.unwrap();
+        let key = MasterKey::load_or_generate(&other.path().join("secrets.key")).unwrap();
+        assert!(SecretVault::new(store, key)
+            .values("github-pack")
+            .await
+            .is_err());
+    }
+
+    #[test]
+    fn test_ciphertext_is_bound_to_pack_and_key() {
+        let dir = TempDir::new().unwrap();
+        let key = MasterKey::load_or_generate(&dir.path().join("secrets.key")).unwrap();
+        let (nonce, ciphertext) = key.encrypt("github-pack", "TOKEN", "ghp_secret").unwrap();
+
+        let secret = |pack_id: &str, name: &str| EncryptedSecret {
+            pack_id: pack_id.to_string(),
+            key: name.to_string(),
+            nonce: nonce.clone(),
+            ciphertext: ciphertext.clone(),
+            updated_at: Utc::now(),
+        };
+        assert_eq!(
+            key.decrypt(&secret("github-pack", "TOKEN")).unwrap(),
+            "ghp_secret"
+        );
+        assert!(key.decrypt(&secret("other-pack", "TOKEN")).is_err());
+        assert!(key.decrypt(&secret("github-pack", "OTHER")).is_err());
+    }
+
+    #[test]
+    fn test_otp_secret_is_bound_to_principal() {
+        let dir = TempDir::new().unwrap();
+        let key = MasterKey::load_or_generate(&dir.path().join("secrets.key")).unwrap();
+        let (nonce, ciphertext) = key.encrypt_otp("owner", b"12345678901234567890").unwrap();
+
+        assert_eq!(
+            key.decrypt_otp("owner", &nonce, &ciphertext).unwrap(),
+            b"12345678901234567890"
+        );
+        assert!(key.decrypt_otp("intruder", &nonce, &ciphertext).is_err());
+    }
+
+    #[test]
+    fn test_key_file_length_is_checked() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("secrets.key");
+        std::fs::write(&path, b"too short").unwrap();
+        let err = MasterKey::load_or_generate(&path).unwrap_err();
+        assert!(err.to_string().contains("expected 32"));
+    }
+
+    #[test]
+    fn test_validate_key() {
+        assert!(validate_key("GITHUB_TOKEN").is_ok());
+        assert!(validate_key("api.key-2").is_ok());
+        assert!(validate_key("").is_err());
+        assert!(validate_key("HAS SPACE").is_err());
+        assert!(validate_key("a=b").is_err());
+    }
+}
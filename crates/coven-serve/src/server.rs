@@ -1,62 +1,489 @@
 // ABOUTME: gRPC server setup and lifecycle for local gateway
-// ABOUTME: Combines CovenControl, ClientService, and PackService into a single server
+// ABOUTME: Combines CovenControl, ClientService, PackService, and AdminService into a single server
 
+use crate::export::Redactor;
+use crate::moderation::ContentFilter;
+use crate::push::PushNotifier;
+use crate::roles::{admin_interceptor, Authorizer};
+use crate::secrets::{MasterKey, SecretVault};
+use crate::services::admin::AdminServiceImpl;
 use crate::services::client::ClientServiceImpl;
 use crate::services::control::{ControlState, CovenControlService};
 use crate::services::pack::{PackServiceImpl, PackState};
+use crate::services::shutdown::Shutdown;
 use crate::store::Store;
+use crate::webhook::WebhookDispatcher;
 use crate::ServeConfig;
 use anyhow::{Context, Result};
-use coven_proto::server::{ClientServiceServer, CovenControlServer, PackServiceServer};
+use coven_proto::server::{
+    AdminServiceServer, ClientServiceServer, CovenControlServer, PackServiceServer,
+};
+use std::fmt;
+use std::future::Future;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::net::TcpListener;
 use tokio::signal;
-use tonic::transport::Server;
-use tracing::info;
+use tokio::sync::oneshot;
+use tokio::task::JoinHandle;
+use tokio_stream::wrappers::TcpListenerStream;
+use tonic::service::interceptor::InterceptedService;
+use tracing::{info, warn};
 
-/// Run the local gateway server
-pub async fn run(config: ServeConfig) -> Result<()> {
-    info!("Starting local gateway server");
-    info!("  gRPC address: {}", config.grpc_addr);
-    info!("  Database: {}", config.db_path.display());
+/// How often recorded tool calls, token usage and resolved tool approvals
+/// past retention are pruned
+const ACTIVITY_PRUNE_INTERVAL: std::time::Duration = std::time::Duration::from_secs(60 * 60);
+
+/// Prefix of `grpc_addr` values that name a Unix domain socket
+pub use coven_grpc::UNIX_SCHEME;
+
+/// Where a running gateway accepts connections
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ListenAddr {
+    Tcp(SocketAddr),
+    /// A Unix domain socket, for single-host setups that shouldn't open a
+    /// port at all
+    Unix(PathBuf),
+}
+
+impl ListenAddr {
+    /// URL clients connect to: `http://host:port` or `unix:///path`
+    pub fn url(&self) -> String {
+        match self {
+            ListenAddr::Tcp(addr) => format!("http://{}", addr),
+            ListenAddr::Unix(path) => format!("{}{}", UNIX_SCHEME, path.display()),
+        }
+    }
+}
+
+impl fmt::Display for ListenAddr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ListenAddr::Tcp(addr) => write!(f, "{}", addr),
+            ListenAddr::Unix(path) => write!(f, "{}{}", UNIX_SCHEME, path.display()),
+        }
+    }
+}
+
+/// A bound listener of either kind
+enum Listener {
+    Tcp(TcpListener),
+    #[cfg(unix)]
+    Unix(tokio::net::UnixListener),
+}
+
+/// Prune tool calls and token usage older than `retention`, now and every
+/// `ACTIVITY_PRUNE_INTERVAL`
+fn spawn_activity_pruning(store: Store, retention: std::time::Duration) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(ACTIVITY_PRUNE_INTERVAL);
+        loop {
+            interval.tick().await;
+            let Some(before) = chrono::Duration::from_std(retention)
+                .ok()
+                .and_then(|retention| chrono::Utc::now().checked_sub_signed(retention))
+            else {
+                return;
+            };
+            match store.prune_activity(before).await {
+                Ok(0) => {}
+                Ok(pruned) => info!(
+                    pruned,
+                    "Pruned tool calls, token usage and approvals past retention"
+                ),
+                Err(e) => {
+                    warn!(error = %e, "Failed to prune tool calls, token usage and approvals")
+                }
+            }
+        }
+    })
+}
 
-    // Open database
-    let store = Store::open(&config.db_path)
+async fn bind(config: &ServeConfig) -> Result<(Listener, ListenAddr)> {
+    if let Some(path) = config.grpc_addr.strip_prefix(UNIX_SCHEME) {
+        return bind_unix(Path::new(path), config.socket_mode);
+    }
+    let listener = TcpListener::bind(&config.grpc_addr)
         .await
-        .context("opening database")?;
+        .with_context(|| format!("binding gRPC address {}", config.grpc_addr))?;
+    let local_addr = listener.local_addr().context("reading bound address")?;
+    Ok((Listener::Tcp(listener), ListenAddr::Tcp(local_addr)))
+}
+
+/// Bind a Unix socket at `path` with permissions `mode`, replacing a stale
+/// socket file left by a gateway that didn't shut down cleanly.
+///
+/// The socket is bound in a private 0700 directory next to `path`, given
+/// `mode` there, and only then renamed into place, so it's never reachable
+/// with looser permissions.
+#[cfg(unix)]
+fn bind_unix(path: &Path, mode: u32) -> Result<(Listener, ListenAddr)> {
+    use std::os::unix::fs::{DirBuilderExt, PermissionsExt};
+
+    if path.exists() {
+        if std::os::unix::net::UnixStream::connect(path).is_ok() {
+            anyhow::bail!("another gateway is already listening on {}", path.display());
+        }
+        std::fs::remove_file(path)
+            .with_context(|| format!("removing stale socket {}", path.display()))?;
+    }
+    let parent = path
+        .parent()
+        .filter(|p| !p.as_os_str().is_empty())
+        .unwrap_or(Path::new("."));
+    std::fs::create_dir_all(parent).with_context(|| format!("creating {}", parent.display()))?;
+
+    // Kept short: socket paths are limited to about 100 bytes
+    static STAGED: std::sync::atomic::AtomicUsize = std::sync::atomic::AtomicUsize::new(0);
+    let n = STAGED.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+    let staging = parent.join(format!(".sock-{}-{}", std::process::id(), n));
+    let _ = std::fs::remove_dir_all(&staging);
+    std::fs::DirBuilder::new()
+        .mode(0o700)
+        .create(&staging)
+        .with_context(|| format!("creating {}", staging.display()))?;
+    let staged = staging.join("s");
+    let bound = (|| -> Result<tokio::net::UnixListener> {
+        let listener = tokio::net::UnixListener::bind(&staged)
+            .with_context(|| format!("binding Unix socket {}", path.display()))?;
+        std::fs::set_permissions(&staged, std::fs::Permissions::from_mode(mode))
+            .with_context(|| format!("setting permissions on {}", path.display()))?;
+        std::fs::rename(&staged, path)
+            .with_context(|| format!("moving socket into place at {}", path.display()))?;
+        Ok(listener)
+    })();
+    let _ = std::fs::remove_dir_all(&staging);
+
+    Ok((Listener::Unix(bound?), ListenAddr::Unix(path.to_path_buf())))
+}
+
+#[cfg(not(unix))]
+fn bind_unix(path: &Path, _mode: u32) -> Result<(Listener, ListenAddr)> {
+    anyhow::bail!(
+        "cannot listen on {}: Unix domain sockets are not supported on this platform",
+        path.display()
+    )
+}
+
+/// The local gateway, run in-process on a background task
+pub struct Server;
+
+impl Server {
+    /// Open the database, bind `config.grpc_addr` and start serving.
+    ///
+    /// Binding port 0 picks a free port; `RunningServer::local_addr` reports
+    /// the one actually bound. A `unix:///path` address listens on a Unix
+    /// socket instead, removed again when the server stops. The server stops
+    /// on `RunningServer::shutdown` or when the handle is dropped.
+    pub async fn start(config: ServeConfig) -> Result<RunningServer> {
+        // Open database
+        let store = Store::open_with_busy_timeout(&config.db_path, config.db_busy_timeout)
+            .await
+            .context("opening database")?;
+        let master_key = MasterKey::load_or_generate(&config.secrets_key_path())
+            .context("loading secrets key")?;
+        let secrets = SecretVault::new(store.clone(), master_key.clone());
+        let authorizer = config
+            .roles
+            .clone()
+            .map(Authorizer::new)
+            .transpose()
+            .context("loading roles")?;
+        if let Some(authorizer) = &authorizer {
+            authorizer.load_grants(
+                store
+                    .list_key_grants()
+                    .await
+                    .context("loading key grants")?,
+            );
+            authorizer.issue_tokens_with(master_key.clone());
+            authorizer.load_revoked_tokens(
+                store
+                    .list_revoked_tokens(chrono::Utc::now())
+                    .await
+                    .context("loading revoked tokens")?,
+            );
+            authorizer
+                .load_otp_secrets(&store, master_key)
+                .await
+                .context("loading one-time code secrets")?;
+        }
+        let filter = config
+            .moderation
+            .clone()
+            .map(ContentFilter::new)
+            .transpose()
+            .context("loading moderation rules")?;
+        let redactor =
+            Redactor::new(&config.export_redactions).context("loading export redactions")?;
 
-    // Create shared state
-    let control_state = ControlState::new(store.clone());
-    let pack_state = PackState::new(store.clone());
+        // Create shared state
+        let control_state = ControlState::with_limits(
+            store.clone(),
+            config.dead_letter.clone(),
+            config.message_limits,
+        );
+        let pack_state = PackState::new(store.clone());
 
-    // Create services
-    let control_service = CovenControlService::new(control_state.clone());
-    let client_service = ClientServiceImpl::new(store.clone(), control_state.clone());
-    let pack_service = PackServiceImpl::new(pack_state.clone());
+        // Create services
+        let stop_streams = Shutdown::default();
+        let mut control_service = CovenControlService::new(control_state.clone())
+            .with_packs(pack_state.clone())
+            .with_shutdown(stop_streams.clone());
+        let mut client_service = ClientServiceImpl::new(store.clone(), control_state.clone())
+            .with_shutdown(stop_streams.clone());
+        if let Some(authorizer) = &authorizer {
+            client_service = client_service.with_authorizer(authorizer.clone());
+        }
+        if let Some(filter) = &filter {
+            control_service = control_service.with_filter(filter.clone());
+            client_service = client_service.with_filter(filter.clone());
+        }
+        let mut pack_service = PackServiceImpl::new(pack_state.clone())
+            .with_secrets(secrets.clone())
+            .with_shutdown(stop_streams.clone());
+        if let Some(authorizer) = &authorizer {
+            pack_service = pack_service.with_authorizer(authorizer.clone());
+        }
+        let mut admin_service = AdminServiceImpl::new(store.clone(), control_state.clone())
+            .with_packs(pack_state.clone())
+            .with_secrets(secrets)
+            .with_redactor(Arc::new(redactor))
+            .with_shutdown(stop_streams.clone());
+        if let Some(authorizer) = &authorizer {
+            admin_service = admin_service.with_authorizer(authorizer.clone());
+        }
+        if config.enable_tail {
+            admin_service = admin_service.with_tail();
+        }
 
-    // Parse address
-    let addr = config.grpc_addr.parse().context("parsing gRPC address")?;
+        let (listener, listen_addr) = bind(&config).await?;
+        let pruning = spawn_activity_pruning(store.clone(), config.activity_retention);
+        let push = config
+            .push_webhook
+            .as_ref()
+            .map(|url| PushNotifier::new(store.clone(), url.clone()).spawn(&control_state));
+        let webhooks = (!config.webhooks.is_empty()).then(|| {
+            WebhookDispatcher::new(
+                store.clone(),
+                config.webhooks.clone(),
+                config.webhook_dead_letter_path(),
+            )
+            .spawn(&control_state)
+        });
+        info!("Local gateway listening on {}", listen_addr);
+
+        let limits = config.message_limits;
+        let socket_file = match &listen_addr {
+            ListenAddr::Unix(path) => Some(path.clone()),
+            ListenAddr::Tcp(_) => None,
+        };
+        let (shutdown_tx, shutdown_rx) = oneshot::channel::<()>();
+        let task = tokio::spawn(async move {
+            let builder = tonic::transport::Server::builder();
+            #[cfg(feature = "otlp")]
+            let builder = builder.trace_fn(coven_log::grpc_server_span);
+            let router = builder
+                .add_service(
+                    CovenControlServer::new(control_service)
+                        .max_decoding_message_size(limits.max_decoding)
+                        .max_encoding_message_size(limits.max_encoding),
+                )
+                .add_service(
+                    ClientServiceServer::new(client_service)
+                        .max_decoding_message_size(limits.max_decoding)
+                        .max_encoding_message_size(limits.max_encoding),
+                )
+                .add_service(
+                    PackServiceServer::new(pack_service)
+                        .max_decoding_message_size(limits.max_decoding)
+                        .max_encoding_message_size(limits.max_encoding),
+                )
+                .add_service(InterceptedService::new(
+                    AdminServiceServer::new(admin_service)
+                        .max_decoding_message_size(limits.max_decoding)
+                        .max_encoding_message_size(limits.max_encoding),
+                    admin_interceptor(authorizer),
+                ));
+
+            // A dropped sender also stops the server. Graceful shutdown
+            // waits for open calls, so end the streams agents, clients and
+            // packs hold open rather than wait on them forever.
+            let shutdown = async move {
+                let _ = shutdown_rx.await;
+                stop_streams.trigger();
+            };
+            let result = match listener {
+                Listener::Tcp(listener) => {
+                    router
+                        .serve_with_incoming_shutdown(TcpListenerStream::new(listener), shutdown)
+                        .await
+                }
+                #[cfg(unix)]
+                Listener::Unix(listener) => {
+                    router
+                        .serve_with_incoming_shutdown(
+                            tokio_stream::wrappers::UnixListenerStream::new(listener),
+                            shutdown,
+                        )
+                        .await
+                }
+            };
+            pruning.abort();
+            if let Some(push) = push {
+                push.abort();
+            }
+            if let Some(webhooks) = webhooks {
+                webhooks.abort();
+            }
+            if let Some(path) = socket_file {
+                let _ = std::fs::remove_file(path);
+            }
+            result.context("running gRPC server")
+        });
+
+        Ok(RunningServer {
+            listen_addr,
+            shutdown: Some(shutdown_tx),
+            task,
+        })
+    }
+}
+
+/// Handle to a gateway started with `Server::start`
+pub struct RunningServer {
+    listen_addr: ListenAddr,
+    shutdown: Option<oneshot::Sender<()>>,
+    task: JoinHandle<Result<()>>,
+}
+
+impl RunningServer {
+    /// Where the gRPC server accepts connections
+    pub fn listen_addr(&self) -> &ListenAddr {
+        &self.listen_addr
+    }
+
+    /// The TCP address the gRPC server is bound to, or None when it
+    /// listens on a Unix socket
+    pub fn local_addr(&self) -> Option<SocketAddr> {
+        match &self.listen_addr {
+            ListenAddr::Tcp(addr) => Some(*addr),
+            ListenAddr::Unix(_) => None,
+        }
+    }
+
+    /// URL clients connect to, e.g. `http://127.0.0.1:50051` or
+    /// `unix:///run/coven/gateway.sock`
+    pub fn url(&self) -> String {
+        self.listen_addr.url()
+    }
+
+    /// Stop accepting connections, close open streams, and wait for the
+    /// server task to finish.
+    pub async fn shutdown(mut self) -> Result<()> {
+        if let Some(tx) = self.shutdown.take() {
+            let _ = tx.send(());
+        }
+        (&mut self.task)
+            .await
+            .context("gRPC server task panicked")?
+    }
+
+    /// Serve until `signal` completes, then shut down. Returns early if the
+    /// server stops on its own.
+    pub async fn run_until(mut self, signal: impl Future<Output = ()>) -> Result<()> {
+        let stopped = tokio::select! {
+            _ = signal => None,
+            result = &mut self.task => Some(result),
+        };
+        match stopped {
+            None => self.shutdown().await,
+            Some(result) => result.context("gRPC server task panicked")?,
+        }
+    }
+}
+
+impl Drop for RunningServer {
+    fn drop(&mut self) {
+        if let Some(tx) = self.shutdown.take() {
+            let _ = tx.send(());
+        }
+    }
+}
+
+/// Run the local gateway server until Ctrl+C or SIGTERM
+pub async fn run(config: ServeConfig) -> Result<()> {
+    info!("Starting local gateway server");
+    info!("  gRPC address: {}", config.grpc_addr);
+    info!("  Database: {}", config.db_path.display());
+    info!("  Secrets key: {}", config.secrets_key_path().display());
+    if let Some(dead_letter) = &config.dead_letter {
+        if dead_letter.max_per_agent == 0 {
+            info!(
+                "  Dead-letter queue: on (ttl {:?}, no limit per agent)",
+                dead_letter.ttl
+            );
+        } else {
+            info!(
+                "  Dead-letter queue: on (ttl {:?}, max {} per agent)",
+                dead_letter.ttl, dead_letter.max_per_agent
+            );
+        }
+    }
+    if config.enable_tail {
+        info!("  Traffic tail: on (admins can read all message content)");
+    }
+    if let Some(url) = &config.push_webhook {
+        info!("  Push webhook: {}", url);
+    }
+    if !config.webhooks.is_empty() {
+        info!(
+            "  Webhooks: {} (dead letters in {})",
+            config.webhooks.len(),
+            config.webhook_dead_letter_path().display()
+        );
+    }
+    if let Some(moderation) = &config.moderation {
+        info!(
+            "  Moderation: on ({} deny patterns, max length {}, endpoint {}, outbound {})",
+            moderation.deny_patterns.len(),
+            moderation
+                .max_length
+                .map_or("none".to_string(), |max| max.to_string()),
+            moderation.endpoint.as_deref().unwrap_or("none"),
+            if moderation.filter_outbound {
+                "on"
+            } else {
+                "off"
+            }
+        );
+    }
+    if let Some(roles) = &config.roles {
+        info!(
+            "  Roles: on ({} principals, admin requires {})",
+            roles.principals.len(),
+            roles.admin_roles.join(" or ")
+        );
+    }
+
+    let server = Server::start(config.clone()).await?;
+    let addr = server.listen_addr().clone();
 
-    info!("Local gateway listening on {}", addr);
     println!();
     println!("Local coven gateway running!");
-    println!("  gRPC: {}", config.grpc_addr);
+    println!("  gRPC: {}", addr);
     println!("  Database: {}", config.db_path.display());
     println!();
     println!("Connect agents with:");
-    println!("  coven agent run --server http://{}", config.grpc_addr);
+    println!("  coven agent run --server {}", server.url());
     println!();
     println!("Use TUI with:");
-    println!("  COVEN_GATEWAY={} coven chat", config.grpc_addr);
+    println!("  COVEN_GATEWAY={} coven chat", addr);
     println!();
     println!("Press Ctrl+C to stop");
 
-    // Build and run server with graceful shutdown
-    Server::builder()
-        .add_service(CovenControlServer::new(control_service))
-        .add_service(ClientServiceServer::new(client_service))
-        .add_service(PackServiceServer::new(pack_service))
-        .serve_with_shutdown(addr, shutdown_signal())
-        .await
-        .context("running gRPC server")?;
+    server.run_until(shutdown_signal()).await?;
 
     info!("Server shut down gracefully");
     println!("\nServer stopped.");
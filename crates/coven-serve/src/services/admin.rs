@@ -0,0 +1,804 @@
+// ABOUTME: AdminService gRPC implementation for the local gateway
+// ABOUTME: Manages the dead-letter queue, packs, pack secrets, agent details, traffic tails, push tokens, agent log levels, conversation exports, key rotations, device pairing, one-time code enrollment, and listing and revoking tokens; bindings and principals don't exist in local mode
+
+use super::control::{traffic_event, ControlState};
+use super::pack::PackState;
+use crate::export::{self, Redactor, THREAD_STATES};
+use crate::roles::{
+    otp_needs_roles, pairing_needs_roles, rotation_needs_roles, tokens_need_roles, Authorizer,
+    Caller,
+};
+use crate::secrets::SecretVault;
+use crate::services::shutdown::Shutdown;
+use crate::store::{DeadLetter, ExportFilter, Message, Store};
+use crate::totp;
+use chrono::{DateTime, Utc};
+use coven_proto::server::AdminService;
+use coven_proto::{
+    ActiveRequest, AgentActivity, AgentConnection, AgentInfo, ApprovePairingRequest,
+    ApprovePairingResponse, Binding, CreateBindingRequest, CreatePrincipalRequest,
+    CreateTokenRequest, CreateTokenResponse, DeleteBindingRequest, DeleteBindingResponse,
+    DeletePackSecretRequest, DeletePackSecretResponse, DeletePrincipalRequest,
+    DeletePrincipalResponse, EnrollOtpRequest, EnrollOtpResponse, ExportRecord,
+    ExportThreadsRequest, GetAgentRequest, GetAgentResponse, ListBindingsRequest,
+    ListBindingsResponse, ListDeadLettersRequest, ListDeadLettersResponse, ListPackSecretsRequest,
+    ListPackSecretsResponse, ListPacksRequest, ListPacksResponse, ListPrincipalsRequest,
+    ListPrincipalsResponse, ListPushTokensRequest, ListPushTokensResponse,
+    ListRecentChannelsRequest, ListRecentChannelsResponse, ListTokensRequest, ListTokensResponse,
+    PackSecretInfo, Principal, PurgeDeadLettersRequest, PurgeDeadLettersResponse,
+    ReplayDeadLettersRequest, ReplayDeadLettersResponse, RevokeTokenRequest, RevokeTokenResponse,
+    RotateKeyResponse, RotatePrincipalKeyRequest, SetAgentLogLevelRequest,
+    SetAgentLogLevelResponse, SetPackSecretRequest, SetPackSecretResponse, TailTrafficRequest,
+    TokenInfo, TrafficEvent, UpdateBindingRequest, UpdatePrincipalRequest,
+};
+use std::pin::Pin;
+use std::sync::Arc;
+use tokio::sync::{broadcast, mpsc};
+use tokio_stream::wrappers::ReceiverStream;
+use tonic::{Request, Response, Status};
+use tracing::info;
+
+/// Recent activity entries `get_agent` returns when the request sets none
+const DEFAULT_ACTIVITY_LIMIT: i64 = 10;
+
+/// Most recent activity entries `get_agent` will return
+const MAX_ACTIVITY_LIMIT: i64 = 100;
+
+/// Channels `list_recent_channels` returns when the request sets no limit
+const DEFAULT_CHANNEL_LIMIT: i64 = 20;
+
+/// Most channels `list_recent_channels` will return
+const MAX_CHANNEL_LIMIT: i64 = 200;
+
+/// Longest activity preview; longer first lines are cut off
+const PREVIEW_CHARS: usize = 80;
+
+/// AdminService implementation
+pub struct AdminServiceImpl {
+    store: Store,
+    control: Arc<ControlState>,
+    packs: Option<Arc<PackState>>,
+    secrets: Option<Arc<SecretVault>>,
+    authorizer: Option<Arc<Authorizer>>,
+    tail: bool,
+    redactor: Arc<Redactor>,
+    shutdown: Shutdown,
+}
+
+impl AdminServiceImpl {
+    pub fn new(store: Store, control: Arc<ControlState>) -> Self {
+        Self {
+            store,
+            control,
+            packs: None,
+            secrets: None,
+            authorizer: None,
+            tail: false,
+            redactor: Arc::default(),
+            shutdown: Shutdown::default(),
+        }
+    }
+
+    /// Report on these packs in `list_packs`.
+    pub fn with_packs(mut self, packs: Arc<PackState>) -> Self {
+        self.packs = Some(packs);
+        self
+    }
+
+    /// Manage pack secrets in this vault.
+    pub fn with_secrets(mut self, secrets: Arc<SecretVault>) -> Self {
+        self.secrets = Some(secrets);
+        self
+    }
+
+    /// Rotate the keys of principals `authorizer` knows.
+    pub fn with_authorizer(mut self, authorizer: Arc<Authorizer>) -> Self {
+        self.authorizer = Some(authorizer);
+        self
+    }
+
+    /// Let admins watch all traffic with `tail_traffic`. Off by default,
+    /// since the feed carries message content.
+    pub fn with_tail(mut self) -> Self {
+        self.tail = true;
+        self
+    }
+
+    /// Drop tool inputs `redactor` matches from exports.
+    pub fn with_redactor(mut self, redactor: Arc<Redactor>) -> Self {
+        self.redactor = redactor;
+        self
+    }
+
+    /// End this service's open streams when `shutdown` is triggered.
+    pub fn with_shutdown(mut self, shutdown: Shutdown) -> Self {
+        self.shutdown = shutdown;
+        self
+    }
+
+    /// Check the one-time code on a destructive call, when the roles file
+    /// asks for one
+    fn second_factor<T>(&self, request: &Request<T>) -> Result<(), Status> {
+        match &self.authorizer {
+            Some(authorizer) => authorizer.check_otp(caller(request)?, request.metadata()),
+            None => Ok(()),
+        }
+    }
+
+    fn vault(&self) -> Result<&SecretVault, Status> {
+        self.secrets
+            .as_deref()
+            .ok_or_else(|| Status::unimplemented("pack secrets are not enabled on this gateway"))
+    }
+}
+
+fn require(field: &str, value: &str) -> Result<(), Status> {
+    if value.is_empty() {
+        return Err(Status::invalid_argument(format!("{} is required", field)));
+    }
+    Ok(())
+}
+
+/// An optional ISO-8601 request field
+fn parse_time(field: &str, value: Option<&str>) -> Result<Option<DateTime<Utc>>, Status> {
+    value
+        .map(|v| {
+            DateTime::parse_from_rfc3339(v)
+                .map(|t| t.with_timezone(&Utc))
+                .map_err(|e| Status::invalid_argument(format!("{} {:?}: {}", field, v, e)))
+        })
+        .transpose()
+}
+
+/// The admin the interceptor admitted
+fn caller<T>(request: &Request<T>) -> Result<&Caller, Status> {
+    request
+        .extensions()
+        .get::<Caller>()
+        .ok_or_else(|| Status::internal("admin call without an identified caller"))
+}
+
+fn not_in_local_mode(what: &str) -> Status {
+    Status::unimplemented(format!("{} are not supported by the local gateway", what))
+}
+
+fn to_proto(letter: DeadLetter) -> coven_proto::DeadLetter {
+    coven_proto::DeadLetter {
+        id: letter.id,
+        agent_id: letter.agent_id,
+        sender: letter.sender,
+        content: letter.content,
+        sender_display: letter.sender_display,
+        created_at: letter.created_at.to_rfc3339(),
+        expires_at: letter.expires_at.to_rfc3339(),
+    }
+}
+
+fn to_activity(msg: Message) -> AgentActivity {
+    AgentActivity {
+        thread_id: msg.conversation_id,
+        direction: msg.direction,
+        author: msg.author,
+        message_type: msg.message_type,
+        preview: preview(&msg.content),
+        timestamp: msg.created_at.to_rfc3339(),
+    }
+}
+
+/// `event` as a tail asked for it: None when it's for another agent, and
+/// without content when redacted. Dropped-event notices always pass.
+fn filter_traffic(
+    mut event: TrafficEvent,
+    agent_id: Option<&str>,
+    redact: bool,
+) -> Option<TrafficEvent> {
+    if let Some(agent_id) = agent_id {
+        if event.kind != "dropped" && event.agent_id != agent_id {
+            return None;
+        }
+    }
+    if redact {
+        event.content = None;
+    }
+    Some(event)
+}
+
+/// First line of `content`, with `…` if anything was left out
+fn preview(content: &str) -> String {
+    let mut lines = content.trim().lines();
+    let first = lines.next().unwrap_or_default();
+    let mut preview: String = first.chars().take(PREVIEW_CHARS).collect();
+    if preview.len() < first.len() || lines.next().is_some() {
+        preview.push('…');
+    }
+    preview
+}
+
+#[tonic::async_trait]
+impl AdminService for AdminServiceImpl {
+    async fn list_bindings(
+        &self,
+        _request: Request<ListBindingsRequest>,
+    ) -> Result<Response<ListBindingsResponse>, Status> {
+        Err(not_in_local_mode("bindings"))
+    }
+
+    async fn create_binding(
+        &self,
+        _request: Request<CreateBindingRequest>,
+    ) -> Result<Response<Binding>, Status> {
+        Err(not_in_local_mode("bindings"))
+    }
+
+    async fn update_binding(
+        &self,
+        _request: Request<UpdateBindingRequest>,
+    ) -> Result<Response<Binding>, Status> {
+        Err(not_in_local_mode("bindings"))
+    }
+
+    async fn delete_binding(
+        &self,
+        _request: Request<DeleteBindingRequest>,
+    ) -> Result<Response<DeleteBindingResponse>, Status> {
+        Err(not_in_local_mode("bindings"))
+    }
+
+    async fn list_recent_channels(
+        &self,
+        request: Request<ListRecentChannelsRequest>,
+    ) -> Result<Response<ListRecentChannelsResponse>, Status> {
+        let req = request.into_inner();
+        let limit = match req.limit {
+            0 => DEFAULT_CHANNEL_LIMIT,
+            n => i64::from(n).clamp(1, MAX_CHANNEL_LIMIT),
+        };
+        let channels = self
+            .store
+            .list_recent_channels(req.frontend.as_deref(), limit)
+            .await
+            .map_err(|e| Status::internal(format!("database error: {}", e)))?;
+
+        // Bridges keep their own bindings here, so to the local gateway
+        // every channel is unbound and `unbound_only` filters nothing
+        let channels = channels
+            .into_iter()
+            .map(|channel| coven_proto::RecentChannel {
+                frontend: channel.frontend,
+                channel_id: channel.channel_id,
+                last_message_at: channel.last_message_at.to_rfc3339(),
+                display_name: channel.display_name,
+                bound_agent_id: None,
+            })
+            .collect();
+        Ok(Response::new(ListRecentChannelsResponse { channels }))
+    }
+
+    async fn create_token(
+        &self,
+        _request: Request<CreateTokenRequest>,
+    ) -> Result<Response<CreateTokenResponse>, Status> {
+        Err(Status::unimplemented(
+            "the local gateway issues tokens only to devices signing with their key; run 'coven link' on the device",
+        ))
+    }
+
+    async fn list_tokens(
+        &self,
+        request: Request<ListTokensRequest>,
+    ) -> Result<Response<ListTokensResponse>, Status> {
+        if self.authorizer.is_none() {
+            return Err(tokens_need_roles());
+        }
+        let principal_id = request.into_inner().principal_id;
+        let tokens = self
+            .store
+            .list_tokens(principal_id.as_deref(), Utc::now())
+            .await
+            .map_err(|e| Status::internal(format!("database error: {}", e)))?;
+        Ok(Response::new(ListTokensResponse {
+            tokens: tokens
+                .into_iter()
+                .map(|token| TokenInfo {
+                    id: token.id,
+                    principal_id: token.principal_id,
+                    issued_at: token.issued_at.to_rfc3339(),
+                    expires_at: token.expires_at.to_rfc3339(),
+                    last_used_at: None,
+                })
+                .collect(),
+        }))
+    }
+
+    async fn revoke_token(
+        &self,
+        request: Request<RevokeTokenRequest>,
+    ) -> Result<Response<RevokeTokenResponse>, Status> {
+        let Some(authorizer) = &self.authorizer else {
+            return Err(tokens_need_roles());
+        };
+        self.second_factor(&request)?;
+        let token_id = request.into_inner().token_id;
+        require("token_id", &token_id)?;
+        let revoked = self
+            .store
+            .revoke_token(&token_id, Utc::now())
+            .await
+            .map_err(|e| Status::internal(format!("database error: {}", e)))?;
+        if !revoked {
+            return Err(Status::not_found(format!(
+                "no unexpired, unrevoked token {}",
+                token_id
+            )));
+        }
+        authorizer.revoke_token(&token_id);
+        info!(token_id = %token_id, "Token revoked");
+        Ok(Response::new(RevokeTokenResponse {}))
+    }
+
+    async fn list_principals(
+        &self,
+        _request: Request<ListPrincipalsRequest>,
+    ) -> Result<Response<ListPrincipalsResponse>, Status> {
+        Err(not_in_local_mode("principals"))
+    }
+
+    async fn create_principal(
+        &self,
+        _request: Request<CreatePrincipalRequest>,
+    ) -> Result<Response<Principal>, Status> {
+        Err(not_in_local_mode("principals"))
+    }
+
+    async fn update_principal(
+        &self,
+        _request: Request<UpdatePrincipalRequest>,
+    ) -> Result<Response<Principal>, Status> {
+        Err(not_in_local_mode("principals"))
+    }
+
+    async fn delete_principal(
+        &self,
+        _request: Request<DeletePrincipalRequest>,
+    ) -> Result<Response<DeletePrincipalResponse>, Status> {
+        Err(not_in_local_mode("principals"))
+    }
+
+    async fn rotate_principal_key(
+        &self,
+        request: Request<RotatePrincipalKeyRequest>,
+    ) -> Result<Response<RotateKeyResponse>, Status> {
+        let authorizer = self.authorizer.as_ref().ok_or_else(rotation_needs_roles)?;
+        let req = request.into_inner();
+        require("id", &req.id)?;
+        require("new_pubkey_fp", &req.new_pubkey_fp)?;
+        let old_fingerprints = authorizer
+            .active_fingerprints(&req.id)
+            .ok_or_else(|| Status::not_found(format!("principal not found: {}", req.id)))?;
+
+        let response = authorizer
+            .rotate(
+                &self.store,
+                &req.id,
+                old_fingerprints,
+                &req.new_pubkey_fp,
+                req.grace_period_secs,
+            )
+            .await?;
+        info!(
+            principal = %response.principal_id,
+            new_fingerprint = %response.new_fingerprint,
+            old_keys_expire_at = %response.old_keys_expire_at,
+            "Key rotated by admin"
+        );
+        Ok(Response::new(response))
+    }
+
+    async fn approve_pairing(
+        &self,
+        request: Request<ApprovePairingRequest>,
+    ) -> Result<Response<ApprovePairingResponse>, Status> {
+        let authorizer = self.authorizer.as_ref().ok_or_else(pairing_needs_roles)?;
+        let req = request.into_inner();
+        require("code", &req.code)?;
+        // The approver names the principal: a device naming itself could
+        // pick an existing one and take its roles
+        let principal_id = req.principal_id.trim().to_string();
+        require("principal_id", &principal_id)?;
+
+        // Claim the code before granting, so it's granted at most once
+        let pairing = authorizer
+            .pairings()
+            .approve(&req.code, &principal_id, Utc::now())?;
+        if let Err(e) = authorizer
+            .grant(&self.store, &principal_id, &pairing.fingerprint)
+            .await
+        {
+            authorizer.pairings().release(&req.code);
+            return Err(e);
+        }
+        // The key works from here on; the device learns its principal, and
+        // gets a token, on its next status check
+        authorizer.pairings().granted(&req.code);
+        info!(
+            principal = %principal_id,
+            device = %pairing.device_name,
+            fingerprint = %pairing.fingerprint,
+            "Device paired"
+        );
+        Ok(Response::new(ApprovePairingResponse {
+            principal_id,
+            device_name: pairing.device_name,
+            fingerprint: pairing.fingerprint,
+        }))
+    }
+
+    async fn list_dead_letters(
+        &self,
+        request: Request<ListDeadLettersRequest>,
+    ) -> Result<Response<ListDeadLettersResponse>, Status> {
+        let req = request.into_inner();
+        let letters = self
+            .store
+            .list_dead_letters(req.agent_id.as_deref())
+            .await
+            .map_err(|e| Status::internal(format!("database error: {}", e)))?;
+
+        Ok(Response::new(ListDeadLettersResponse {
+            dead_letters: letters.into_iter().map(to_proto).collect(),
+        }))
+    }
+
+    async fn replay_dead_letters(
+        &self,
+        request: Request<ReplayDeadLettersRequest>,
+    ) -> Result<Response<ReplayDeadLettersResponse>, Status> {
+        let req = request.into_inner();
+        if req.agent_id.is_empty() {
+            return Err(Status::invalid_argument("agent_id is required"));
+        }
+
+        let (delivered, remaining) = self.control.replay_dead_letters(&req.agent_id).await?;
+
+        Ok(Response::new(ReplayDeadLettersResponse {
+            delivered: delivered as i32,
+            remaining: remaining as i32,
+        }))
+    }
+
+    async fn purge_dead_letters(
+        &self,
+        request: Request<PurgeDeadLettersRequest>,
+    ) -> Result<Response<PurgeDeadLettersResponse>, Status> {
+        self.second_factor(&request)?;
+        let req = request.into_inner();
+        let purged = self
+            .store
+            .purge_dead_letters(req.agent_id.as_deref())
+            .await
+            .map_err(|e| Status::internal(format!("database error: {}", e)))?;
+
+        info!(agent_id = ?req.agent_id, purged, "Purged dead letters");
+
+        Ok(Response::new(PurgeDeadLettersResponse {
+            purged: purged as i32,
+        }))
+    }
+
+    async fn list_packs(
+        &self,
+        _request: Request<ListPacksRequest>,
+    ) -> Result<Response<ListPacksResponse>, Status> {
+        let packs = match &self.packs {
+            Some(packs) => packs.list_packs().await,
+            None => vec![],
+        };
+        Ok(Response::new(ListPacksResponse { packs }))
+    }
+
+    async fn set_pack_secret(
+        &self,
+        request: Request<SetPackSecretRequest>,
+    ) -> Result<Response<SetPackSecretResponse>, Status> {
+        let req = request.into_inner();
+        require("pack_id", &req.pack_id)?;
+        crate::secrets::validate_key(&req.key)
+            .map_err(|e| Status::invalid_argument(e.to_string()))?;
+
+        let created = self
+            .vault()?
+            .set(&req.pack_id, &req.key, &req.value)
+            .await
+            .map_err(|e| Status::internal(format!("database error: {}", e)))?;
+
+        Ok(Response::new(SetPackSecretResponse { created }))
+    }
+
+    async fn delete_pack_secret(
+        &self,
+        request: Request<DeletePackSecretRequest>,
+    ) -> Result<Response<DeletePackSecretResponse>, Status> {
+        self.second_factor(&request)?;
+        let req = request.into_inner();
+        require("pack_id", &req.pack_id)?;
+        require("key", &req.key)?;
+
+        let deleted = self
+            .vault()?
+            .delete(&req.pack_id, &req.key)
+            .await
+            .map_err(|e| Status::internal(format!("database error: {}", e)))?;
+
+        Ok(Response::new(DeletePackSecretResponse { deleted }))
+    }
+
+    async fn list_pack_secrets(
+        &self,
+        request: Request<ListPackSecretsRequest>,
+    ) -> Result<Response<ListPackSecretsResponse>, Status> {
+        let req = request.into_inner();
+        require("pack_id", &req.pack_id)?;
+
+        let secrets = self
+            .vault()?
+            .list(&req.pack_id)
+            .await
+            .map_err(|e| Status::internal(format!("database error: {}", e)))?;
+
+        Ok(Response::new(ListPackSecretsResponse {
+            secrets: secrets
+                .into_iter()
+                .map(|secret| PackSecretInfo {
+                    key: secret.key,
+                    updated_at: secret.updated_at.to_rfc3339(),
+                })
+                .collect(),
+        }))
+    }
+
+    async fn get_agent(
+        &self,
+        request: Request<GetAgentRequest>,
+    ) -> Result<Response<GetAgentResponse>, Status> {
+        let req = request.into_inner();
+        require("agent_id", &req.agent_id)?;
+        let db_error = |e: anyhow::Error| Status::internal(format!("database error: {}", e));
+
+        let agent = self
+            .store
+            .get_agent(&req.agent_id)
+            .await
+            .map_err(db_error)?
+            .ok_or_else(|| Status::not_found(format!("unknown agent: {}", req.agent_id)))?;
+        let limit = match req.activity_limit {
+            0 => DEFAULT_ACTIVITY_LIMIT,
+            n => i64::from(n).clamp(1, MAX_ACTIVITY_LIMIT),
+        };
+        let recent = self
+            .store
+            .recent_agent_messages(&agent.id, limit)
+            .await
+            .map_err(db_error)?;
+        let queued = self
+            .store
+            .count_dead_letters(&agent.id)
+            .await
+            .map_err(db_error)?;
+
+        // The store's flag can lag a crash; the live connection is the truth
+        let live = self.control.connection_info(&agent.id).await;
+        let connection = live.as_ref().map(|live| AgentConnection {
+            connected_since: Some(live.connected_at.to_rfc3339()),
+            last_heartbeat: live.last_heartbeat.map(|t| t.to_rfc3339()),
+            heartbeat_latency_ms: live.heartbeat_latency_ms,
+            capabilities: live.capabilities.clone(),
+            protocol_features: live.protocol_features.clone(),
+            active_requests: live
+                .active_requests
+                .iter()
+                .map(|r| ActiveRequest {
+                    request_id: r.request_id.clone(),
+                    thread_id: r.thread_id.clone(),
+                    started_at: r.started_at.to_rfc3339(),
+                })
+                .collect(),
+            recent_errors: live.recent_errors as i32,
+            budget: live.budget.clone(),
+        });
+
+        Ok(Response::new(GetAgentResponse {
+            agent: Some(AgentInfo {
+                connected: live.is_some(),
+                metadata: live.map(|live| live.metadata),
+                id: agent.id,
+                name: agent.name,
+                backend: agent.backend,
+                working_dir: agent.working_dir,
+            }),
+            connection: Some(connection.unwrap_or_default()),
+            last_seen: agent.last_seen.map(|t| t.to_rfc3339()),
+            queued_messages: queued as i32,
+            recent_activity: recent.into_iter().map(to_activity).collect(),
+        }))
+    }
+
+    type TailTrafficStream =
+        Pin<Box<dyn futures::Stream<Item = Result<TrafficEvent, Status>> + Send>>;
+
+    async fn tail_traffic(
+        &self,
+        request: Request<TailTrafficRequest>,
+    ) -> Result<Response<Self::TailTrafficStream>, Status> {
+        if !self.tail {
+            return Err(Status::failed_precondition(
+                "traffic tail is disabled; start the gateway with --enable-tail",
+            ));
+        }
+        let req = request.into_inner();
+        info!(agent_id = ?req.agent_id, redact = req.redact, "Admin is tailing traffic");
+
+        let mut traffic = self.control.subscribe_traffic();
+        let (tx, rx) = mpsc::channel(64);
+        tokio::spawn(async move {
+            loop {
+                let received = tokio::select! {
+                    // Unsubscribe as soon as the admin goes away
+                    _ = tx.closed() => break,
+                    received = traffic.recv() => received,
+                };
+                let event = match received {
+                    Ok(event) => event,
+                    Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                        let mut event = traffic_event("dropped", "");
+                        event.detail = Some(format!("{} events skipped", skipped));
+                        event
+                    }
+                    Err(broadcast::error::RecvError::Closed) => break,
+                };
+                let Some(event) = filter_traffic(event, req.agent_id.as_deref(), req.redact) else {
+                    continue;
+                };
+                if tx.send(Ok(event)).await.is_err() {
+                    break;
+                }
+            }
+        });
+        Ok(Response::new(
+            self.shutdown.until_shutdown(ReceiverStream::new(rx)),
+        ))
+    }
+
+    async fn list_push_tokens(
+        &self,
+        request: Request<ListPushTokensRequest>,
+    ) -> Result<Response<ListPushTokensResponse>, Status> {
+        let req = request.into_inner();
+        let tokens = self
+            .store
+            .list_push_tokens(req.principal_id.as_deref())
+            .await
+            .map_err(|e| Status::internal(format!("database error: {}", e)))?;
+
+        Ok(Response::new(ListPushTokensResponse {
+            tokens: tokens
+                .into_iter()
+                .map(|t| coven_proto::PushToken {
+                    principal_id: t.principal_id,
+                    platform: t.platform,
+                    token: t.token,
+                    created_at: t.created_at.to_rfc3339(),
+                    updated_at: t.updated_at.to_rfc3339(),
+                })
+                .collect(),
+        }))
+    }
+
+    async fn enroll_otp(
+        &self,
+        request: Request<EnrollOtpRequest>,
+    ) -> Result<Response<EnrollOtpResponse>, Status> {
+        let authorizer = self.authorizer.as_ref().ok_or_else(otp_needs_roles)?;
+        let caller = caller(&request)?.clone();
+        let req = request.into_inner();
+        let (principal_id, secret) = authorizer
+            .enroll_otp(
+                &self.store,
+                &caller,
+                req.principal_id.as_deref(),
+                req.code.as_deref(),
+            )
+            .await?;
+        info!(principal = %principal_id, by = %caller.principal_id, "TOTP secret enrolled");
+        Ok(Response::new(EnrollOtpResponse {
+            uri: totp::otpauth_uri(&principal_id, &secret),
+            secret: totp::encode_secret(&secret),
+            principal_id,
+        }))
+    }
+
+    async fn set_agent_log_level(
+        &self,
+        request: Request<SetAgentLogLevelRequest>,
+    ) -> Result<Response<SetAgentLogLevelResponse>, Status> {
+        let req = request.into_inner();
+        require("agent_id", &req.agent_id)?;
+        // Agents keep their filter on a bad one, so catch it while the caller can hear
+        coven_log::reload::parse_filter(&req.filter)
+            .map_err(|e| Status::invalid_argument(e.to_string()))?;
+        self.control
+            .set_log_level(&req.agent_id, req.filter.trim())
+            .await?;
+        Ok(Response::new(SetAgentLogLevelResponse {}))
+    }
+
+    type ExportThreadsStream =
+        Pin<Box<dyn futures::Stream<Item = Result<ExportRecord, Status>> + Send>>;
+
+    async fn export_threads(
+        &self,
+        request: Request<ExportThreadsRequest>,
+    ) -> Result<Response<Self::ExportThreadsStream>, Status> {
+        let req = request.into_inner();
+        let filter = ExportFilter {
+            agent_id: req.agent_id,
+            since: parse_time("since", req.since.as_deref())?,
+            until: parse_time("until", req.until.as_deref())?,
+        };
+        if let Some(state) = &req.state {
+            if !THREAD_STATES.contains(&state.as_str()) {
+                return Err(Status::invalid_argument(format!(
+                    "unknown thread state {:?}; expected one of {}",
+                    state,
+                    THREAD_STATES.join(", ")
+                )));
+            }
+        }
+        info!(agent_id = ?filter.agent_id, since = ?filter.since, until = ?filter.until, state = ?req.state, "Admin is exporting threads");
+
+        let store = self.store.clone();
+        let redactor = self.redactor.clone();
+        let (tx, rx) = mpsc::channel(64);
+        tokio::spawn(async move {
+            if let Err(e) = export::run(&store, &filter, req.state.as_deref(), &redactor, &tx).await
+            {
+                let _ = tx
+                    .send(Err(Status::internal(format!("export failed: {}", e))))
+                    .await;
+            }
+        });
+        Ok(Response::new(Box::pin(ReceiverStream::new(rx))))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_preview_keeps_first_line() {
+        assert_eq!(preview("  hello there  "), "hello there");
+        assert_eq!(preview("first\nsecond"), "first…");
+        let long = "é".repeat(PREVIEW_CHARS + 5);
+        let cut = preview(&long);
+        assert_eq!(cut.chars().count(), PREVIEW_CHARS + 1);
+        assert!(cut.ends_with('…'));
+    }
+
+    #[test]
+    fn test_filter_traffic_by_agent_and_redaction() {
+        let mut event = traffic_event("inbound", "agent-1");
+        event.content = Some("secret plans".to_string());
+        event.content_bytes = 12;
+
+        assert!(filter_traffic(event.clone(), Some("agent-2"), false).is_none());
+        let kept = filter_traffic(event.clone(), Some("agent-1"), false).unwrap();
+        assert_eq!(kept.content.as_deref(), Some("secret plans"));
+
+        let redacted = filter_traffic(event, None, true).unwrap();
+        assert_eq!(redacted.content, None);
+        assert_eq!(redacted.content_bytes, 12);
+
+        let dropped = traffic_event("dropped", "");
+        assert!(filter_traffic(dropped, Some("agent-1"), true).is_some());
+    }
+}
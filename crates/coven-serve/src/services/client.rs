@@ -1,17 +1,34 @@
 // ABOUTME: ClientService gRPC implementation for TUI/client connections
-// ABOUTME: Handles listing agents, sending messages, and streaming responses
+// ABOUTME: Handles listing agents and threads, sending messages, forking conversations, and streaming responses and agent-initiated messages
 
 use super::control::{ControlState, OutboundMessage};
-use crate::store::{Message, Store};
+use crate::moderation::{self, ContentFilter};
+use crate::pairing::PairingState;
+use crate::roles::{
+    pairing_needs_roles, rotation_needs_roles, tokens_need_roles, Authorizer, Caller, OWNER,
+};
+use crate::services::shutdown::Shutdown;
+use crate::store::{Conversation, Message, Store, TokenRecord, ToolApproval, PUSH_PLATFORMS};
+use crate::tokens::IssuedToken;
 use chrono::Utc;
+use coven_proto::overrides::RequestOverrides;
 use coven_proto::server::ClientService;
 use coven_proto::{
-    client_stream_event, AgentInfo, ApproveToolRequest, ApproveToolResponse,
-    ClientSendMessageRequest, ClientSendMessageResponse, ClientStreamEvent, GetEventsRequest,
-    GetEventsResponse, ListAgentsRequest, ListAgentsResponse, MeResponse, RegisterAgentRequest,
-    RegisterAgentResponse, RegisterClientRequest, RegisterClientResponse, StreamDone, StreamError,
-    StreamEventsRequest, TextChunk, ThinkingChunk,
+    client_stream_event, AgentInfo, AgentInitiatedEvent, ApproveToolRequest, ApproveToolResponse,
+    ClientSendMessageRequest, ClientSendMessageResponse, ClientStreamEvent, ForkThreadRequest,
+    ForkThreadResponse, ForkedMessage, GetApprovalHistoryRequest, GetApprovalHistoryResponse,
+    GetEventsRequest, GetEventsResponse, GetPairingStatusRequest, ListAgentsRequest,
+    ListAgentsResponse, ListPendingApprovalsRequest, ListPendingApprovalsResponse,
+    ListThreadsRequest, ListThreadsResponse, MeResponse, PairingCode, PairingStatus,
+    RefreshTokenRequest, RefreshTokenResponse, RegisterAgentRequest, RegisterAgentResponse,
+    RegisterClientRequest, RegisterClientResponse, RegisterPushTokenRequest,
+    RegisterPushTokenResponse, RequestPairingCodeRequest, RotateKeyRequest, RotateKeyResponse,
+    StreamAgentInitiatedRequest, StreamDone, StreamError, StreamEventsRequest, TextChunk,
+    ThinkingChunk, ThreadInfo, ToolApprovalRecord, ToolSummary, UnregisterPushTokenRequest,
+    UnregisterPushTokenResponse, VersionResponse,
 };
+use coven_ssh::{compute_fingerprint, RotationProof, MAX_SIGNATURE_AGE_SECS};
+use std::collections::HashMap;
 use std::pin::Pin;
 use std::sync::Arc;
 use tokio::sync::mpsc;
@@ -20,33 +37,156 @@ use tonic::{Request, Response, Status};
 use tracing::{debug, info, warn};
 use uuid::Uuid;
 
+/// The one principal in local mode without roles; every client acts as it
+pub const LOCAL_PRINCIPAL: &str = "local-user";
+
+/// Longest device token accepted; APNs and FCM tokens are far shorter
+const MAX_PUSH_TOKEN_LEN: usize = 4096;
+
+/// Send status of a message the content filter blocked
+pub const SEND_STATUS_BLOCKED: &str = ClientSendMessageResponse::STATUS_BLOCKED;
+
+/// Approval history records returned when the request sets no limit
+const DEFAULT_APPROVAL_HISTORY: i32 = 50;
+
+/// Longest device name a pairing request may carry
+const MAX_DEVICE_NAME_LEN: usize = 128;
+
 /// ClientService implementation
 pub struct ClientServiceImpl {
     store: Store,
     control: Arc<ControlState>,
+    authorizer: Option<Arc<Authorizer>>,
+    filter: Option<Arc<ContentFilter>>,
+    shutdown: Shutdown,
 }
 
 impl ClientServiceImpl {
     pub fn new(store: Store, control: Arc<ControlState>) -> Self {
-        Self { store, control }
+        Self {
+            store,
+            control,
+            authorizer: None,
+            filter: None,
+            shutdown: Shutdown::default(),
+        }
+    }
+
+    /// Check messages with `filter` before routing them, answering blocked
+    /// ones with its policy message.
+    pub fn with_filter(mut self, filter: Arc<ContentFilter>) -> Self {
+        self.filter = Some(filter);
+        self
+    }
+
+    /// Identify callers and check their roles with `authorizer`, instead of
+    /// treating every client as the local owner.
+    pub fn with_authorizer(mut self, authorizer: Arc<Authorizer>) -> Self {
+        self.authorizer = Some(authorizer);
+        self
+    }
+
+    /// End this service's open streams when `shutdown` is triggered.
+    pub fn with_shutdown(mut self, shutdown: Shutdown) -> Self {
+        self.shutdown = shutdown;
+        self
+    }
+
+    /// The authorizer and the key signing a pairing request. Pairing
+    /// identifies devices by key, so it needs roles and a signed request.
+    fn pairing_key<T>(
+        &self,
+        request: &Request<T>,
+    ) -> Result<(&Authorizer, Caller, String), Status> {
+        let Some(authorizer) = &self.authorizer else {
+            return Err(pairing_needs_roles());
+        };
+        let caller = authorizer.caller(request.metadata())?;
+        let fingerprint = caller.key_fingerprint.clone().ok_or_else(|| {
+            Status::unauthenticated("sign pairing requests with the device's SSH key")
+        })?;
+        Ok((authorizer, caller, fingerprint))
+    }
+
+    /// A token for `caller`, recorded so it can be listed and revoked
+    async fn issue_token(
+        &self,
+        authorizer: &Authorizer,
+        caller: &Caller,
+    ) -> Result<IssuedToken, Status> {
+        let issued = authorizer.issue_token(caller)?;
+        self.store
+            .record_token(&TokenRecord {
+                id: issued.id.clone(),
+                principal_id: caller.principal_id.clone(),
+                issued_at: issued.issued_at,
+                expires_at: issued.expires_at,
+            })
+            .await
+            .map_err(|e| Status::internal(format!("database error: {}", e)))?;
+        info!(principal = %caller.principal_id, token_id = %issued.id, expires_at = %issued.expires_at, "Token issued");
+        Ok(issued)
+    }
+
+    /// Who is making `request`
+    fn caller<T>(&self, request: &Request<T>) -> Result<Caller, Status> {
+        match &self.authorizer {
+            Some(authorizer) => authorizer.caller(request.metadata()),
+            None => Ok(Caller {
+                principal_id: LOCAL_PRINCIPAL.to_string(),
+                display_name: "Local User".to_string(),
+                roles: vec![OWNER.to_string()],
+                key_fingerprint: None,
+            }),
+        }
+    }
+
+    /// The agent and conversation a conversation key names. A key is a
+    /// conversation ID, or an agent ID for that agent's main conversation,
+    /// which may not exist yet.
+    async fn resolve_conversation(
+        &self,
+        key: &str,
+    ) -> Result<(String, Option<Conversation>), Status> {
+        let conversation = self
+            .store
+            .get_conversation(key)
+            .await
+            .map_err(|e| Status::internal(format!("database error: {}", e)))?;
+        Ok(match conversation {
+            Some(conversation) => (conversation.agent_id.clone(), Some(conversation)),
+            None => (key.to_string(), None),
+        })
     }
 }
 
 #[tonic::async_trait]
 impl ClientService for ClientServiceImpl {
-    async fn get_me(&self, _request: Request<()>) -> Result<Response<MeResponse>, Status> {
-        // In local mode, everyone is admin
+    async fn get_me(&self, request: Request<()>) -> Result<Response<MeResponse>, Status> {
+        // Without roles, everyone is the owner
+        let caller = self.caller(&request)?;
         Ok(Response::new(MeResponse {
-            principal_id: "local-user".to_string(),
+            principal_id: caller.principal_id,
             principal_type: "client".to_string(),
-            display_name: "Local User".to_string(),
+            display_name: caller.display_name,
             status: "approved".to_string(),
-            roles: vec!["owner".to_string()],
+            roles: caller.roles,
             member_id: None,
             member_display_name: None,
         }))
     }
 
+    async fn get_version(
+        &self,
+        _request: Request<()>,
+    ) -> Result<Response<VersionResponse>, Status> {
+        Ok(Response::new(VersionResponse {
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            component: "coven-serve".to_string(),
+            server_time_ms: Some(Utc::now().timestamp_millis()),
+        }))
+    }
+
     async fn get_events(
         &self,
         request: Request<GetEventsRequest>,
@@ -54,12 +194,19 @@ impl ClientService for ClientServiceImpl {
         let req = request.into_inner();
         let conversation_id = &req.conversation_key;
 
-        let limit = req.limit.unwrap_or(100).min(500) as i64;
-        let messages = self
+        // The cursor is the ID of the last event of the previous page. One
+        // extra row tells whether there is another page.
+        let limit = req.limit.unwrap_or(100).clamp(1, 500) as usize;
+        let mut messages = self
             .store
-            .get_messages(conversation_id, limit)
+            .get_messages_after(conversation_id, req.cursor.as_deref(), limit as i64 + 1)
             .await
             .map_err(|e| Status::internal(format!("database error: {}", e)))?;
+        let has_more = messages.len() > limit;
+        messages.truncate(limit);
+        let next_cursor = has_more
+            .then(|| messages.last().map(|m| m.id.clone()))
+            .flatten();
 
         let events = messages
             .into_iter()
@@ -75,13 +222,14 @@ impl ClientService for ClientServiceImpl {
                 raw_payload_ref: None,
                 actor_principal_id: None,
                 actor_member_id: None,
+                reply_to_message_id: m.reply_to_message_id,
             })
             .collect();
 
         Ok(Response::new(GetEventsResponse {
             events,
-            next_cursor: None,
-            has_more: false,
+            next_cursor,
+            has_more,
         }))
     }
 
@@ -90,22 +238,83 @@ impl ClientService for ClientServiceImpl {
         request: Request<ClientSendMessageRequest>,
     ) -> Result<Response<ClientSendMessageResponse>, Status> {
         let req = request.into_inner();
-        let agent_id = &req.conversation_key;
+        let (agent_id, conversation) = self.resolve_conversation(&req.conversation_key).await?;
+        let agent_id = &agent_id;
 
-        // Check if agent is connected
-        if !self.control.is_connected(agent_id).await {
-            return Err(Status::not_found(format!(
-                "agent not connected: {}",
-                agent_id
-            )));
+        // Remember which bridge channel this came from, so admins can pick
+        // it when creating a binding
+        if let (Some(frontend), Some(channel_id)) = (&req.channel_frontend, &req.channel_id) {
+            if let Err(e) = self
+                .store
+                .record_channel(frontend, channel_id, req.channel_name.as_deref())
+                .await
+            {
+                warn!(frontend = %frontend, channel_id = %channel_id, error = %e, "Failed to record channel");
+            }
         }
 
-        // Get or create conversation
-        let conversation = self
-            .store
-            .get_or_create_conversation(agent_id)
-            .await
-            .map_err(|e| Status::internal(format!("database error: {}", e)))?;
+        // Overrides reach the agent's backend as they are, so bad ones stop here
+        let overrides = RequestOverrides {
+            model: req.model.clone(),
+            max_tokens: req.max_tokens,
+        };
+        if let Err(e) = overrides.validate() {
+            let status = Status::invalid_argument(e.to_string());
+            self.control.tap_rejected(agent_id, &req.content, &status);
+            return Err(status);
+        }
+
+        // A blocked message never reaches the agent or the database; the
+        // sender gets the policy message instead
+        if let Some(filter) = &self.filter {
+            let sender = req.sender_platform_id.as_deref().unwrap_or("user");
+            if let Some(reason) = filter.check_inbound(agent_id, sender, &req.content).await {
+                let status = Status::permission_denied(format!("blocked: {}", reason));
+                self.control
+                    .tap_rejected(agent_id, &moderation::redact(&req.content), &status);
+                return Ok(Response::new(ClientSendMessageResponse {
+                    status: SEND_STATUS_BLOCKED.to_string(),
+                    message_id: String::new(),
+                    detail: Some(filter.policy_message().to_string()),
+                }));
+            }
+        }
+
+        // Offline agents are an error unless the dead-letter queue is on,
+        // in which case messages for known agents wait for them to reconnect
+        let connected = self.control.is_connected(agent_id).await;
+        if !connected {
+            let known = self.control.dead_letter_enabled()
+                && self
+                    .store
+                    .get_agent(agent_id)
+                    .await
+                    .map_err(|e| Status::internal(format!("database error: {}", e)))?
+                    .is_some();
+            if !known {
+                let status = Status::not_found(format!("agent not connected: {}", agent_id));
+                self.control.tap_rejected(agent_id, &req.content, &status);
+                return Err(status);
+            }
+            // The dead-letter queue keeps text only, so files would be lost
+            if !req.attachments.is_empty() {
+                let status = Status::failed_precondition(format!(
+                    "agent not connected: {}; attachments can't be queued",
+                    agent_id
+                ));
+                self.control.tap_rejected(agent_id, &req.content, &status);
+                return Err(status);
+            }
+        }
+
+        let conversation = match conversation {
+            Some(conversation) => conversation,
+            None => self
+                .store
+                .get_or_create_conversation(agent_id)
+                .await
+                .map_err(|e| Status::internal(format!("database error: {}", e)))?,
+        };
 
         // Generate request ID
         let request_id = if req.idempotency_key.is_empty() {
@@ -113,38 +322,86 @@ impl ClientService for ClientServiceImpl {
         } else {
             req.idempotency_key.clone()
         };
+        tracing::Span::current().record("request_id", request_id.as_str());
 
-        // Save inbound message
+        // Attribute the message to the chat user when a bridge relayed it
+        let author = req
+            .sender_display
+            .clone()
+            .unwrap_or_else(|| "user".to_string());
+        let sender = req
+            .sender_platform_id
+            .clone()
+            .unwrap_or_else(|| "user".to_string());
+
+        let outbound = OutboundMessage {
+            agent_id: agent_id.clone(),
+            request_id: request_id.clone(),
+            thread_id: conversation.id.clone(),
+            sender,
+            content: req.content,
+            sender_display: req.sender_display,
+            sender_platform_id: req.sender_platform_id,
+            sender_platform: req.sender_platform,
+            model: req.model,
+            max_tokens: req.max_tokens,
+            reply_to_message_id: req.reply_to_message_id,
+            attachments: req.attachments,
+        };
+        // Refuse a message the agent could never receive before saving it
+        self.control
+            .check_deliverable(&outbound)
+            .map_err(|status| {
+                self.control
+                    .tap_rejected(agent_id, &outbound.content, &status);
+                status
+            })?;
+
+        // Save inbound message. The request ID is also the stored message's
+        // ID, so senders and agents can name it in reply_to_message_id. A
+        // retried send finds its message already saved and isn't delivered
+        // twice, even when the retry races the original.
         let msg = Message {
-            id: Uuid::new_v4().to_string(),
+            id: request_id.clone(),
             conversation_id: conversation.id.clone(),
             direction: "inbound".to_string(),
-            author: "user".to_string(),
-            content: req.content.clone(),
+            author,
+            content: outbound.content.clone(),
             message_type: "message".to_string(),
+            reply_to_message_id: outbound.reply_to_message_id.clone(),
             created_at: Utc::now(),
         };
-        self.store
-            .save_message(&msg)
+        let saved = self
+            .store
+            .save_message_once(&msg)
             .await
             .map_err(|e| Status::internal(format!("database error: {}", e)))?;
+        if !saved {
+            return Ok(Response::new(ClientSendMessageResponse {
+                status: "duplicate".to_string(),
+                message_id: String::new(),
+                detail: None,
+            }));
+        }
 
-        // Send to agent
-        self.control
-            .send_to_agent(OutboundMessage {
-                agent_id: agent_id.clone(),
-                request_id: request_id.clone(),
-                thread_id: conversation.id.clone(),
-                sender: "user".to_string(),
-                content: req.content,
-            })
-            .await?;
+        if !connected {
+            self.control.queue_dead_letter(outbound).await?;
+            return Ok(Response::new(ClientSendMessageResponse {
+                status: "queued".to_string(),
+                message_id: request_id,
+                detail: None,
+            }));
+        }
+
+        // Send to agent, after anything still queued for it
+        self.control.deliver(outbound).await?;
 
         info!(agent_id = %agent_id, request_id = %request_id, "Message sent to agent");
 
         Ok(Response::new(ClientSendMessageResponse {
             status: "accepted".to_string(),
             message_id: request_id,
+            detail: None,
         }))
     }
 
@@ -156,28 +413,49 @@ impl ClientService for ClientServiceImpl {
         request: Request<StreamEventsRequest>,
     ) -> Result<Response<Self::StreamEventsStream>, Status> {
         let req = request.into_inner();
-        let agent_id = req.conversation_key.clone();
+        let (agent_id, conversation) = self.resolve_conversation(&req.conversation_key).await?;
+        let conversation_id = conversation.map_or_else(|| agent_id.clone(), |c| c.id);
+        let streaming = req.stream.unwrap_or(true);
 
         // Subscribe to agent responses
         let mut response_rx = self.control.subscribe_responses();
         let (tx, rx) = mpsc::channel(32);
 
         let store = self.store.clone();
+        let filter = self.filter.clone();
 
         // Spawn task to filter and forward responses
         tokio::spawn(async move {
+            // Requests in this conversation can run at once; each has its own
+            let mut so_far: HashMap<String, ResponseSoFar> = HashMap::new();
             loop {
                 match response_rx.recv().await {
                     Ok(resp) => {
-                        // Only forward responses for this agent
-                        if resp.agent_id != agent_id {
+                        // Only forward responses in this conversation
+                        if resp.agent_id != agent_id || resp.thread_id != conversation_id {
                             continue;
                         }
+                        match &resp.response.event {
+                            // The response ended without a Done
+                            Some(
+                                coven_proto::message_response::Event::Error(_)
+                                | coven_proto::message_response::Event::Cancelled(_),
+                            ) => {
+                                so_far.remove(&resp.request_id);
+                            }
+                            Some(event) => {
+                                so_far
+                                    .entry(resp.request_id.clone())
+                                    .or_default()
+                                    .record(event);
+                            }
+                            None => {}
+                        }
 
                         let event = match &resp.response.event {
                             Some(coven_proto::message_response::Event::Text(text)) => {
                                 ClientStreamEvent {
-                                    conversation_key: agent_id.clone(),
+                                    conversation_key: conversation_id.clone(),
                                     timestamp: Utc::now().to_rfc3339(),
                                     payload: Some(client_stream_event::Payload::Text(TextChunk {
                                         content: text.clone(),
@@ -186,7 +464,7 @@ impl ClientService for ClientServiceImpl {
                             }
                             Some(coven_proto::message_response::Event::Thinking(text)) => {
                                 ClientStreamEvent {
-                                    conversation_key: agent_id.clone(),
+                                    conversation_key: conversation_id.clone(),
                                     timestamp: Utc::now().to_rfc3339(),
                                     payload: Some(client_stream_event::Payload::Thinking(
                                         ThinkingChunk {
@@ -197,7 +475,7 @@ impl ClientService for ClientServiceImpl {
                             }
                             Some(coven_proto::message_response::Event::ToolUse(tool)) => {
                                 ClientStreamEvent {
-                                    conversation_key: agent_id.clone(),
+                                    conversation_key: conversation_id.clone(),
                                     timestamp: Utc::now().to_rfc3339(),
                                     payload: Some(client_stream_event::Payload::ToolUse(
                                         tool.clone(),
@@ -206,7 +484,7 @@ impl ClientService for ClientServiceImpl {
                             }
                             Some(coven_proto::message_response::Event::ToolResult(result)) => {
                                 ClientStreamEvent {
-                                    conversation_key: agent_id.clone(),
+                                    conversation_key: conversation_id.clone(),
                                     timestamp: Utc::now().to_rfc3339(),
                                     payload: Some(client_stream_event::Payload::ToolResult(
                                         result.clone(),
@@ -216,7 +494,7 @@ impl ClientService for ClientServiceImpl {
                             Some(coven_proto::message_response::Event::ToolApprovalRequest(
                                 approval,
                             )) => ClientStreamEvent {
-                                conversation_key: agent_id.clone(),
+                                conversation_key: conversation_id.clone(),
                                 timestamp: Utc::now().to_rfc3339(),
                                 payload: Some(client_stream_event::Payload::ToolApproval(
                                     coven_proto::ClientToolApprovalRequest {
@@ -225,35 +503,41 @@ impl ClientService for ClientServiceImpl {
                                         tool_id: approval.id.clone(),
                                         tool_name: approval.name.clone(),
                                         input_json: approval.input_json.clone(),
+                                        confirm_message: approval.confirm_message.clone(),
                                     },
                                 )),
                             },
                             Some(coven_proto::message_response::Event::Done(done)) => {
+                                let done = so_far
+                                    .remove(&resp.request_id)
+                                    .unwrap_or_default()
+                                    .done(&done.full_response, filter.as_deref(), &agent_id);
+
                                 // Save the complete response to store
-                                if !done.full_response.is_empty() {
+                                let full_response = done.full_response.as_deref().unwrap_or("");
+                                if !full_response.is_empty() {
                                     let msg = Message {
                                         id: Uuid::new_v4().to_string(),
-                                        conversation_id: agent_id.clone(),
+                                        conversation_id: conversation_id.clone(),
                                         direction: "outbound".to_string(),
                                         author: "agent".to_string(),
-                                        content: done.full_response.clone(),
+                                        content: full_response.to_string(),
                                         message_type: "message".to_string(),
+                                        reply_to_message_id: None,
                                         created_at: Utc::now(),
                                     };
                                     let _ = store.save_message(&msg).await;
                                 }
 
                                 ClientStreamEvent {
-                                    conversation_key: agent_id.clone(),
+                                    conversation_key: conversation_id.clone(),
                                     timestamp: Utc::now().to_rfc3339(),
-                                    payload: Some(client_stream_event::Payload::Done(StreamDone {
-                                        full_response: Some(done.full_response.clone()),
-                                    })),
+                                    payload: Some(client_stream_event::Payload::Done(done)),
                                 }
                             }
                             Some(coven_proto::message_response::Event::Error(err)) => {
                                 ClientStreamEvent {
-                                    conversation_key: agent_id.clone(),
+                                    conversation_key: conversation_id.clone(),
                                     timestamp: Utc::now().to_rfc3339(),
                                     payload: Some(client_stream_event::Payload::Error(
                                         StreamError {
@@ -265,22 +549,34 @@ impl ClientService for ClientServiceImpl {
                             }
                             Some(coven_proto::message_response::Event::Usage(usage)) => {
                                 ClientStreamEvent {
-                                    conversation_key: agent_id.clone(),
+                                    conversation_key: conversation_id.clone(),
                                     timestamp: Utc::now().to_rfc3339(),
                                     payload: Some(client_stream_event::Payload::Usage(*usage)),
                                 }
                             }
                             Some(coven_proto::message_response::Event::ToolState(state)) => {
                                 ClientStreamEvent {
-                                    conversation_key: agent_id.clone(),
+                                    conversation_key: conversation_id.clone(),
                                     timestamp: Utc::now().to_rfc3339(),
                                     payload: Some(client_stream_event::Payload::ToolState(
                                         state.clone(),
                                     )),
                                 }
                             }
+                            Some(coven_proto::message_response::Event::Queued(queued)) => {
+                                ClientStreamEvent {
+                                    conversation_key: conversation_id.clone(),
+                                    timestamp: Utc::now().to_rfc3339(),
+                                    payload: Some(client_stream_event::Payload::Queued(
+                                        queued.clone(),
+                                    )),
+                                }
+                            }
                             _ => continue,
                         };
+                        if !streaming && !sent_when_buffered(&event) {
+                            continue;
+                        }
 
                         if tx.send(Ok(event)).await.is_err() {
                             break;
@@ -298,34 +594,89 @@ impl ClientService for ClientServiceImpl {
         });
 
         let stream = ReceiverStream::new(rx);
-        Ok(Response::new(Box::pin(stream)))
+        Ok(Response::new(self.shutdown.until_shutdown(stream)))
+    }
+
+    type StreamAgentInitiatedStream =
+        Pin<Box<dyn futures::Stream<Item = Result<AgentInitiatedEvent, Status>> + Send>>;
+
+    async fn stream_agent_initiated(
+        &self,
+        request: Request<StreamAgentInitiatedRequest>,
+    ) -> Result<Response<Self::StreamAgentInitiatedStream>, Status> {
+        let agent_ids = request.into_inner().agent_ids;
+
+        let mut initiated_rx = self.control.subscribe_initiated();
+        let (tx, rx) = mpsc::channel(32);
+
+        tokio::spawn(async move {
+            loop {
+                match initiated_rx.recv().await {
+                    Ok(event) => {
+                        if !agent_ids.is_empty() && !agent_ids.contains(&event.agent_id) {
+                            continue;
+                        }
+                        if tx.send(Ok(event)).await.is_err() {
+                            break;
+                        }
+                    }
+                    Err(tokio::sync::broadcast::error::RecvError::Lagged(n)) => {
+                        warn!(missed = n, "Agent-initiated stream lagged, missed messages");
+                        continue;
+                    }
+                    Err(tokio::sync::broadcast::error::RecvError::Closed) => {
+                        break;
+                    }
+                }
+            }
+        });
+
+        let stream = ReceiverStream::new(rx);
+        Ok(Response::new(self.shutdown.until_shutdown(stream)))
     }
 
     async fn list_agents(
         &self,
         _request: Request<ListAgentsRequest>,
     ) -> Result<Response<ListAgentsResponse>, Status> {
-        let agents = self
-            .store
-            .list_agents()
-            .await
-            .map_err(|e| Status::internal(format!("database error: {}", e)))?;
+        let agents = list_agent_infos(&self.store, &self.control).await?;
+        Ok(Response::new(ListAgentsResponse { agents }))
+    }
 
-        let agent_infos: Vec<AgentInfo> = agents
-            .into_iter()
-            .map(|a| AgentInfo {
-                id: a.id.clone(),
-                name: a.name,
-                backend: a.backend,
-                working_dir: a.working_dir,
-                connected: a.connected,
-                metadata: None,
-            })
-            .collect();
+    type WatchAgentsStream =
+        Pin<Box<dyn futures::Stream<Item = Result<ListAgentsResponse, Status>> + Send>>;
 
-        Ok(Response::new(ListAgentsResponse {
-            agents: agent_infos,
-        }))
+    async fn watch_agents(
+        &self,
+        _request: Request<ListAgentsRequest>,
+    ) -> Result<Response<Self::WatchAgentsStream>, Status> {
+        // Subscribe before the first list so no change is missed in between
+        let mut changes = self.control.subscribe_agent_changes();
+        let agents = list_agent_infos(&self.store, &self.control).await?;
+        let (tx, rx) = mpsc::channel(8);
+        let _ = tx.send(Ok(ListAgentsResponse { agents })).await;
+
+        let store = self.store.clone();
+        let control = self.control.clone();
+        tokio::spawn(async move {
+            loop {
+                match changes.recv().await {
+                    // Missed notifications don't matter; only the latest list is sent
+                    Ok(()) | Err(tokio::sync::broadcast::error::RecvError::Lagged(_)) => {}
+                    Err(tokio::sync::broadcast::error::RecvError::Closed) => break,
+                }
+                let update = list_agent_infos(&store, &control)
+                    .await
+                    .map(|agents| ListAgentsResponse { agents });
+                if tx.send(update).await.is_err() {
+                    break;
+                }
+            }
+        });
+
+        Ok(Response::new(
+            self.shutdown.until_shutdown(ReceiverStream::new(rx)),
+        ))
     }
 
     async fn register_agent(
@@ -352,32 +703,444 @@ impl ClientService for ClientServiceImpl {
         }))
     }
 
+    async fn fork_thread(
+        &self,
+        request: Request<ForkThreadRequest>,
+    ) -> Result<Response<ForkThreadResponse>, Status> {
+        let req = request.into_inner();
+        let conversation = self
+            .store
+            .get_conversation(&req.conversation_key)
+            .await
+            .map_err(|e| Status::internal(format!("database error: {}", e)))?
+            .ok_or_else(|| {
+                Status::not_found(format!("conversation not found: {}", req.conversation_key))
+            })?;
+
+        // Checked first, so a fork the agent can't take isn't stored
+        self.control.check_can_fork(&conversation.agent_id).await?;
+
+        let (fork, copied) = self
+            .store
+            .fork_conversation(&conversation.id, &req.message_id)
+            .await
+            .map_err(|e| Status::internal(format!("database error: {}", e)))?
+            .ok_or_else(|| {
+                Status::not_found(format!(
+                    "message {} not found in conversation {}",
+                    req.message_id, conversation.id
+                ))
+            })?;
+        // The agent starts the fork from the gateway's record rather than
+        // its own, which needn't line up message for message
+        let history = copied
+            .into_iter()
+            .filter(|m| m.message_type == "message")
+            .map(|m| ForkedMessage {
+                role: if m.direction == "inbound" {
+                    "user"
+                } else {
+                    "assistant"
+                }
+                .to_string(),
+                content: m.content,
+            })
+            .collect();
+        self.control
+            .fork_thread(&conversation.agent_id, &conversation.id, &fork.id, history)
+            .await?;
+
+        info!(agent_id = %conversation.agent_id, conversation_id = %conversation.id, fork_id = %fork.id, "Conversation forked");
+        Ok(Response::new(ForkThreadResponse {
+            conversation_key: fork.id,
+        }))
+    }
+
+    async fn list_threads(
+        &self,
+        request: Request<ListThreadsRequest>,
+    ) -> Result<Response<ListThreadsResponse>, Status> {
+        let req = request.into_inner();
+        let threads = self
+            .store
+            .list_conversations(&req.agent_id)
+            .await
+            .map_err(|e| Status::internal(format!("database error: {}", e)))?
+            .into_iter()
+            .map(|c| ThreadInfo {
+                conversation_key: c.id,
+                title: c.title,
+                updated_at: c.updated_at.to_rfc3339(),
+            })
+            .collect();
+        Ok(Response::new(ListThreadsResponse { threads }))
+    }
+
     async fn approve_tool(
         &self,
         request: Request<ApproveToolRequest>,
     ) -> Result<Response<ApproveToolResponse>, Status> {
+        let caller = self.caller(&request)?;
         let req = request.into_inner();
         debug!(
             agent_id = %req.agent_id,
             tool_id = %req.tool_id,
             approved = req.approved,
+            principal = %caller.principal_id,
             "Tool approval request"
         );
 
+        // Anyone may deny a tool, but approving one can require a role. A
+        // tool we can't name can't be checked, so it can't be approved.
+        if req.approved {
+            if let Some(authorizer) = &self.authorizer {
+                let pending = self
+                    .store
+                    .list_pending_approvals(&req.agent_id)
+                    .await
+                    .map_err(|e| Status::internal(format!("database error: {}", e)))?;
+                let Some(approval) = pending.iter().find(|a| a.tool_id == req.tool_id) else {
+                    return Err(Status::not_found(format!(
+                        "no pending approval for tool {} on agent {}",
+                        req.tool_id, req.agent_id
+                    )));
+                };
+                authorizer.check_tool(&caller, &approval.tool_name)?;
+            }
+        }
+
         // Forward approval to agent
-        match self
+        if let Err(e) = self
             .control
             .approve_tool(&req.agent_id, &req.tool_id, req.approved, req.approve_all)
             .await
         {
-            Ok(()) => Ok(Response::new(ApproveToolResponse {
-                success: true,
-                error: None,
-            })),
-            Err(e) => Ok(Response::new(ApproveToolResponse {
+            return Ok(Response::new(ApproveToolResponse {
                 success: false,
                 error: Some(e.message().to_string()),
-            })),
+            }));
+        }
+
+        // Recorded only once the agent has the answer, so a failed forward
+        // leaves the approval pending
+        let decision = match (req.approved, req.approve_all) {
+            (true, true) => "approved_all",
+            (true, false) => "approved",
+            (false, _) => "denied",
+        };
+        if let Err(e) = self
+            .store
+            .record_approval_decision(&req.agent_id, &req.tool_id, decision, &caller.principal_id)
+            .await
+        {
+            warn!(agent_id = %req.agent_id, tool_id = %req.tool_id, error = %e, "Failed to record tool approval decision");
+        }
+        Ok(Response::new(ApproveToolResponse {
+            success: true,
+            error: None,
+        }))
+    }
+
+    async fn register_push_token(
+        &self,
+        request: Request<RegisterPushTokenRequest>,
+    ) -> Result<Response<RegisterPushTokenResponse>, Status> {
+        let req = request.into_inner();
+        if !PUSH_PLATFORMS.contains(&req.platform.as_str()) {
+            return Err(Status::invalid_argument(format!(
+                "unknown push platform '{}' (expected one of: {})",
+                req.platform,
+                PUSH_PLATFORMS.join(", ")
+            )));
+        }
+        let token = req.token.trim();
+        if token.is_empty() || token.len() > MAX_PUSH_TOKEN_LEN {
+            return Err(Status::invalid_argument(format!(
+                "push token must be 1 to {} bytes",
+                MAX_PUSH_TOKEN_LEN
+            )));
+        }
+
+        let created = self
+            .store
+            .upsert_push_token(LOCAL_PRINCIPAL, &req.platform, token)
+            .await
+            .map_err(|e| Status::internal(format!("database error: {}", e)))?;
+        info!(platform = %req.platform, created, "Push token registered");
+        Ok(Response::new(RegisterPushTokenResponse { created }))
+    }
+
+    async fn unregister_push_token(
+        &self,
+        request: Request<UnregisterPushTokenRequest>,
+    ) -> Result<Response<UnregisterPushTokenResponse>, Status> {
+        let token = request.into_inner().token;
+        let removed = self
+            .store
+            .delete_push_token(LOCAL_PRINCIPAL, token.trim())
+            .await
+            .map_err(|e| Status::internal(format!("database error: {}", e)))?;
+        Ok(Response::new(UnregisterPushTokenResponse { removed }))
+    }
+
+    async fn rotate_key(
+        &self,
+        request: Request<RotateKeyRequest>,
+    ) -> Result<Response<RotateKeyResponse>, Status> {
+        let Some(authorizer) = &self.authorizer else {
+            return Err(rotation_needs_roles());
+        };
+        let caller = authorizer.caller(request.metadata())?;
+        let Some(old_fingerprint) = caller.key_fingerprint else {
+            return Err(Status::unauthenticated(
+                "sign the request with the key being rotated",
+            ));
+        };
+        let req = request.into_inner();
+        let proof = RotationProof {
+            new_public_key: req.new_public_key,
+            timestamp: req.timestamp,
+            signature: req.new_key_signature,
+        };
+        let new_key = proof
+            .verify(&old_fingerprint, MAX_SIGNATURE_AGE_SECS)
+            .map_err(|e| Status::invalid_argument(format!("new key: {}", e)))?;
+        let new_fingerprint =
+            compute_fingerprint(&new_key).map_err(|e| Status::invalid_argument(e.to_string()))?;
+
+        let response = authorizer
+            .rotate(
+                &self.store,
+                &caller.principal_id,
+                vec![old_fingerprint],
+                &new_fingerprint,
+                req.grace_period_secs,
+            )
+            .await?;
+        info!(
+            principal = %response.principal_id,
+            new_fingerprint = %response.new_fingerprint,
+            old_keys_expire_at = %response.old_keys_expire_at,
+            "Key rotated"
+        );
+        Ok(Response::new(response))
+    }
+
+    async fn request_pairing_code(
+        &self,
+        request: Request<RequestPairingCodeRequest>,
+    ) -> Result<Response<PairingCode>, Status> {
+        let (authorizer, _, fingerprint) = self.pairing_key(&request)?;
+        let peer = request.remote_addr().map(|addr| addr.ip());
+        let device_name = request.into_inner().device_name.trim().to_string();
+        if device_name.is_empty() || device_name.len() > MAX_DEVICE_NAME_LEN {
+            return Err(Status::invalid_argument(format!(
+                "device name must be 1 to {} bytes",
+                MAX_DEVICE_NAME_LEN
+            )));
+        }
+
+        let pairing =
+            authorizer
+                .pairings()
+                .request(&fingerprint, &device_name, peer, Utc::now())?;
+        info!(
+            device = %device_name,
+            fingerprint = %fingerprint,
+            expires_at = %pairing.expires_at.to_rfc3339(),
+            "Pairing code issued"
+        );
+        Ok(Response::new(PairingCode {
+            code: pairing.display_code(),
+            expires_at: pairing.expires_at.to_rfc3339(),
+            fingerprint,
+        }))
+    }
+
+    async fn get_pairing_status(
+        &self,
+        request: Request<GetPairingStatusRequest>,
+    ) -> Result<Response<PairingStatus>, Status> {
+        let (authorizer, caller, fingerprint) = self.pairing_key(&request)?;
+        let code = request.into_inner().code;
+        let state = authorizer
+            .pairings()
+            .status(&code, &fingerprint, Utc::now())?;
+        let PairingState::Approved { principal_id } = &state else {
+            return Ok(Response::new(PairingStatus {
+                status: state.label().to_string(),
+                principal_id: None,
+                token: None,
+            }));
+        };
+
+        // The key was granted before the approval was reported, so the
+        // caller is already the approved principal. Without a token the
+        // device still signs with its key, and can ask for one later.
+        let token = match self.issue_token(authorizer, &caller).await {
+            Ok(issued) => Some(issued.token),
+            Err(e) => {
+                warn!(principal = %principal_id, error = %e.message(), "Paired device gets no token");
+                None
+            }
+        };
+        Ok(Response::new(PairingStatus {
+            status: state.label().to_string(),
+            principal_id: Some(principal_id.clone()),
+            token,
+        }))
+    }
+
+    async fn refresh_token(
+        &self,
+        request: Request<RefreshTokenRequest>,
+    ) -> Result<Response<RefreshTokenResponse>, Status> {
+        let Some(authorizer) = &self.authorizer else {
+            return Err(tokens_need_roles());
+        };
+        let caller = authorizer.caller(request.metadata())?;
+        let issued = self.issue_token(authorizer, &caller).await?;
+        Ok(Response::new(RefreshTokenResponse {
+            token: issued.token,
+            expires_at: Some(issued.expires_at.to_rfc3339()),
+        }))
+    }
+
+    async fn list_pending_approvals(
+        &self,
+        request: Request<ListPendingApprovalsRequest>,
+    ) -> Result<Response<ListPendingApprovalsResponse>, Status> {
+        let agent_id = request.into_inner().agent_id;
+        let approvals = self
+            .store
+            .list_pending_approvals(&agent_id)
+            .await
+            .map_err(|e| Status::internal(format!("database error: {}", e)))?;
+        Ok(Response::new(ListPendingApprovalsResponse {
+            approvals: approvals.into_iter().map(approval_record).collect(),
+        }))
+    }
+
+    async fn get_approval_history(
+        &self,
+        request: Request<GetApprovalHistoryRequest>,
+    ) -> Result<Response<GetApprovalHistoryResponse>, Status> {
+        let req = request.into_inner();
+        let limit = match req.limit {
+            0 => DEFAULT_APPROVAL_HISTORY,
+            n => n.clamp(1, 500),
+        };
+        let approvals = self
+            .store
+            .approval_history(&req.agent_id, limit as i64)
+            .await
+            .map_err(|e| Status::internal(format!("database error: {}", e)))?;
+        Ok(Response::new(GetApprovalHistoryResponse {
+            approvals: approvals.into_iter().map(approval_record).collect(),
+        }))
+    }
+}
+
+/// What one of an agent's responses has produced so far, for the
+/// StreamDone that ends it
+#[derive(Debug, Default)]
+struct ResponseSoFar {
+    text: String,
+    tools: Vec<ToolSummary>,
+}
+
+impl ResponseSoFar {
+    fn record(&mut self, event: &coven_proto::message_response::Event) {
+        use coven_proto::message_response::Event;
+        match event {
+            Event::Text(text) => self.text.push_str(text),
+            Event::ToolUse(tool) => self.tools.push(ToolSummary {
+                id: tool.id.clone(),
+                name: tool.name.clone(),
+                is_error: false,
+                completed: false,
+            }),
+            Event::ToolResult(result) => {
+                if let Some(tool) = self.tools.iter_mut().rev().find(|t| t.id == result.id) {
+                    tool.is_error = result.is_error;
+                    tool.completed = true;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// The StreamDone for a response the agent finished with
+    /// `full_response`, or with the text it streamed when that's empty.
+    /// `full_response` has been through `filter` already; the streamed text
+    /// was only checked chunk by chunk, so it's checked again as a whole.
+    fn done(
+        self,
+        full_response: &str,
+        filter: Option<&ContentFilter>,
+        agent_id: &str,
+    ) -> StreamDone {
+        let text = if !full_response.is_empty() {
+            full_response.to_string()
+        } else if filter.is_some_and(|f| f.check_outbound(agent_id, &self.text).is_some()) {
+            moderation::REMOVED_NOTICE.to_string()
+        } else {
+            self.text
+        };
+        StreamDone {
+            full_response: Some(text),
+            tools: self.tools,
         }
     }
 }
+
+/// Whether a subscriber that asked not to stream still gets `event`: the
+/// final Done, errors, approvals the agent is waiting on, and queue notices
+fn sent_when_buffered(event: &ClientStreamEvent) -> bool {
+    matches!(
+        event.payload,
+        Some(
+            client_stream_event::Payload::Done(_)
+                | client_stream_event::Payload::Error(_)
+                | client_stream_event::Payload::ToolApproval(_)
+                | client_stream_event::Payload::Queued(_)
+        )
+    )
+}
+
+fn approval_record(approval: ToolApproval) -> ToolApprovalRecord {
+    ToolApprovalRecord {
+        agent_id: approval.agent_id,
+        request_id: approval.request_id,
+        tool_id: approval.tool_id,
+        tool_name: approval.tool_name,
+        input_json: approval.input_json,
+        confirm_message: approval.confirm_message,
+        requested_at: approval.requested_at.to_rfc3339(),
+        resolved_at: approval.resolved_at.map(|t| t.to_rfc3339()),
+        decision: approval.decision,
+        resolved_by: approval.resolved_by,
+    }
+}
+
+/// Every known agent, with live metadata for the connected ones
+async fn list_agent_infos(store: &Store, control: &ControlState) -> Result<Vec<AgentInfo>, Status> {
+    let agents = store
+        .list_agents()
+        .await
+        .map_err(|e| Status::internal(format!("database error: {}", e)))?;
+    // Only connected agents have live metadata (and a presence)
+    let mut metadata = control.connected_metadata().await;
+
+    Ok(agents
+        .into_iter()
+        .map(|a| AgentInfo {
+            metadata: metadata.remove(&a.id),
+            id: a.id,
+            name: a.name,
+            backend: a.backend,
+            working_dir: a.working_dir,
+            connected: a.connected,
+        })
+        .collect())
+}
@@ -1,17 +1,26 @@
 // ABOUTME: CovenControl gRPC service implementation for agent connections
-// ABOUTME: Handles agent registration, heartbeats, and message routing
+// ABOUTME: Handles agent registration, heartbeats, connection details, presence, message routing, dead-letter replay, agent-initiated messages, tool approval records, tool call and usage records, pack tool calls, pack tool list pushes, agent log level changes, thread forks, outbound content filtering, connect/disconnect events, and the admin traffic tap
 
-use crate::store::{Agent, Store};
-use chrono::Utc;
+use crate::moderation::{ContentFilter, REMOVED_NOTICE};
+use crate::services::pack::PackState;
+use crate::services::shutdown::Shutdown;
+use crate::store::{Agent, DeadLetter, Store, TokenUsage, ToolApproval, ToolCall};
+use crate::DeadLetterConfig;
+use chrono::{DateTime, Utc};
+use coven_proto::limits::MessageLimits;
+use coven_proto::redact::InputRedactor;
 use coven_proto::server::CovenControl;
 use coven_proto::{
-    AgentMessage, MessageResponse, SendMessage, ServerMessage, ToolApprovalResponse, Welcome,
+    pack_tool_result, AgentInitiatedEvent, AgentMessage, AgentMetadata, AgentPresence,
+    AvailableTools, ExecutePackTool, FileAttachment, ForkThread, ForkedMessage, MessageResponse,
+    PackToolProgress, PackToolResult, SendMessage, ServerMessage, SetLogLevel,
+    ToolApprovalResponse, TrafficEvent, Welcome,
 };
 use futures::StreamExt;
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
 use std::pin::Pin;
 use std::sync::Arc;
-use tokio::sync::{broadcast, mpsc, RwLock};
+use tokio::sync::{broadcast, mpsc, Mutex, RwLock};
 use tokio_stream::wrappers::ReceiverStream;
 use tonic::{Request, Response, Status, Streaming};
 use tracing::{debug, error, info, warn};
@@ -25,6 +34,59 @@ pub struct OutboundMessage {
     pub thread_id: String,
     pub sender: String,
     pub content: String,
+    /// Identity of the chat user behind the message, when relayed by a bridge
+    pub sender_display: Option<String>,
+    pub sender_platform_id: Option<String>,
+    pub sender_platform: Option<String>,
+    /// Per-request overrides of the agent's model and max tokens
+    pub model: Option<String>,
+    pub max_tokens: Option<u32>,
+    /// Earlier message in the thread this one replies to
+    pub reply_to_message_id: Option<String>,
+    /// Files sent along with the message
+    pub attachments: Vec<FileAttachment>,
+}
+
+impl From<DeadLetter> for OutboundMessage {
+    fn from(letter: DeadLetter) -> Self {
+        Self {
+            agent_id: letter.agent_id,
+            request_id: letter.id,
+            thread_id: letter.thread_id,
+            sender: letter.sender,
+            content: letter.content,
+            sender_display: letter.sender_display,
+            sender_platform_id: letter.sender_platform_id,
+            sender_platform: letter.sender_platform,
+            model: letter.model,
+            max_tokens: letter.max_tokens,
+            reply_to_message_id: letter.reply_to_message_id,
+            // Messages with attachments are never queued
+            attachments: vec![],
+        }
+    }
+}
+
+impl From<OutboundMessage> for ServerMessage {
+    fn from(msg: OutboundMessage) -> Self {
+        ServerMessage {
+            payload: Some(coven_proto::server_message::Payload::SendMessage(
+                SendMessage {
+                    request_id: msg.request_id,
+                    thread_id: msg.thread_id,
+                    sender: msg.sender,
+                    content: msg.content,
+                    attachments: msg.attachments,
+                    sender_display: msg.sender_display,
+                    sender_platform_id: msg.sender_platform_id,
+                    sender_platform: msg.sender_platform,
+                    model: msg.model,
+                    max_tokens: msg.max_tokens,
+                    reply_to_message_id: msg.reply_to_message_id,
+                },
+            )),
+        }
+    }
 }
 
 /// Response from an agent
@@ -32,18 +94,143 @@ pub struct OutboundMessage {
 pub struct AgentResponse {
     pub agent_id: String,
     pub request_id: String,
+    /// Thread of the request being answered, or the agent ID if the
+    /// gateway didn't send that request
+    pub thread_id: String,
     pub response: MessageResponse,
 }
 
+/// An agent joining or leaving the gateway
+#[derive(Debug, Clone, PartialEq)]
+pub enum AgentLifecycle {
+    Connected {
+        agent_id: String,
+        name: String,
+    },
+    Disconnected {
+        agent_id: String,
+        name: String,
+        /// Why the stream broke; None when the agent closed it cleanly
+        error: Option<String>,
+    },
+}
+
 /// Connected agent handle
 struct ConnectedAgent {
     #[allow(dead_code)]
     id: String,
     #[allow(dead_code)]
     name: String,
+    /// Metadata from registration, with the latest presence applied
+    metadata: AgentMetadata,
+    connected_at: DateTime<Utc>,
+    last_heartbeat: Option<DateTime<Utc>>,
+    heartbeat_latency_ms: Option<i64>,
+    capabilities: Vec<String>,
+    protocol_features: Vec<String>,
+    /// Requests sent but not yet answered with Done or Error, by request ID
+    active: HashMap<String, ActiveRequestInfo>,
+    /// When the agent reported errors, oldest first, within `ERROR_WINDOW`
+    errors: VecDeque<DateTime<Utc>>,
+    /// Latest budget spend the agent reported, if it has a budget
+    budget: Option<BudgetStatus>,
     tx: mpsc::Sender<ServerMessage>,
 }
 
+/// A message an agent is still working on
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ActiveRequestInfo {
+    pub request_id: String,
+    pub thread_id: String,
+    pub started_at: DateTime<Utc>,
+}
+
+/// How a connected agent is doing, as shown by `GetAgent`
+#[derive(Debug, Clone)]
+pub struct AgentConnectionInfo {
+    pub metadata: AgentMetadata,
+    pub connected_at: DateTime<Utc>,
+    pub last_heartbeat: Option<DateTime<Utc>>,
+    /// When the last heartbeat arrived minus the time the agent stamped on
+    /// it: network delay plus any clock skew between the two hosts
+    pub heartbeat_latency_ms: Option<i64>,
+    pub capabilities: Vec<String>,
+    pub protocol_features: Vec<String>,
+    /// Oldest first
+    pub active_requests: Vec<ActiveRequestInfo>,
+    /// Error events within `ERROR_WINDOW`
+    pub recent_errors: usize,
+    /// Latest budget spend the agent reported
+    pub budget: Option<BudgetStatus>,
+}
+
+/// Traffic events buffered per tail before a slow one starts dropping them
+const TRAFFIC_BUFFER: usize = 1024;
+
+/// A traffic event of `kind` for `agent_id`, stamped now
+pub fn traffic_event(kind: &str, agent_id: &str) -> TrafficEvent {
+    TrafficEvent {
+        timestamp: Utc::now().to_rfc3339(),
+        kind: kind.to_string(),
+        agent_id: agent_id.to_string(),
+        ..Default::default()
+    }
+}
+
+fn with_content(mut event: TrafficEvent, content: &str) -> TrafficEvent {
+    event.content_bytes = content.len() as u64;
+    event.content = Some(content.to_string());
+    event
+}
+
+/// A traffic event describing `msg` on its way to its agent
+fn message_event(kind: &str, msg: &OutboundMessage) -> TrafficEvent {
+    let mut event = with_content(traffic_event(kind, &msg.agent_id), &msg.content);
+    event.request_id = msg.request_id.clone();
+    event.thread_id = msg.thread_id.clone();
+    event.sender = msg.sender.clone();
+    event
+}
+
+/// How far back an agent's errors count as recent
+const ERROR_WINDOW: chrono::Duration = chrono::Duration::hours(1);
+
+/// Longest presence status or emoji kept; anything past it is cut off
+const MAX_PRESENCE_CHARS: usize = 64;
+
+/// Tidy an agent-supplied presence for display. An empty status means
+/// the agent hasn't set one, so it shows as available.
+fn normalize_presence(presence: Option<AgentPresence>) -> AgentPresence {
+    let presence = presence.unwrap_or_default();
+    let clean = |s: &str| {
+        s.trim()
+            .chars()
+            .take(MAX_PRESENCE_CHARS)
+            .collect::<String>()
+    };
+    let status = clean(&presence.status);
+    AgentPresence {
+        status: if status.is_empty() {
+            AgentPresence::DEFAULT_STATUS.to_string()
+        } else {
+            status
+        },
+        emoji: clean(&presence.emoji),
+    }
+}
+
+/// A tool's output as recorded: JSON output has credential-like fields
+/// redacted like inputs, while plain text is kept as is
+fn redact_output(output: &str) -> String {
+    match serde_json::from_str::<serde_json::Value>(output) {
+        Ok(mut value) if value.is_object() || value.is_array() => {
+            InputRedactor::new().redact_value(&mut value);
+            value.to_string()
+        }
+        _ => output.to_string(),
+    }
+}
+
 /// Shared state for the control service
 pub struct ControlState {
     pub store: Store,
@@ -54,48 +241,401 @@ pub struct ControlState {
     outbound_tx: broadcast::Sender<OutboundMessage>,
     /// Channel for receiving responses from agents
     response_tx: broadcast::Sender<AgentResponse>,
+    /// Channel for messages agents send on their own initiative
+    initiated_tx: broadcast::Sender<AgentInitiatedEvent>,
+    /// Notified when an agent connects, disconnects, or changes presence
+    agents_changed: broadcast::Sender<()>,
+    /// Which agent connected or disconnected, and how
+    lifecycle_tx: broadcast::Sender<AgentLifecycle>,
+    /// Copy of the traffic through the gateway, for admin tails. Events are
+    /// only built while someone is subscribed.
+    traffic_tx: broadcast::Sender<TrafficEvent>,
+    /// Dead-letter settings; None rejects messages for offline agents
+    dead_letter: Option<DeadLetterConfig>,
+    /// Per-agent locks held while delivering, so a queued message is never
+    /// delivered twice and new messages wait for the queue to drain
+    delivery_locks: std::sync::Mutex<HashMap<String, Arc<Mutex<()>>>>,
+    /// Size limits of the agent streams
+    limits: MessageLimits,
 }
 
 impl ControlState {
-    pub fn new(store: Store) -> Arc<Self> {
+    pub fn new(store: Store, dead_letter: Option<DeadLetterConfig>) -> Arc<Self> {
+        Self::with_limits(store, dead_letter, MessageLimits::default())
+    }
+
+    /// Like `new`, refusing to send agents messages larger than `limits`
+    pub fn with_limits(
+        store: Store,
+        dead_letter: Option<DeadLetterConfig>,
+        limits: MessageLimits,
+    ) -> Arc<Self> {
         let (outbound_tx, _) = broadcast::channel(256);
         let (response_tx, _) = broadcast::channel(256);
+        let (initiated_tx, _) = broadcast::channel(256);
+        let (agents_changed, _) = broadcast::channel(16);
+        let (lifecycle_tx, _) = broadcast::channel(256);
+        let (traffic_tx, _) = broadcast::channel(TRAFFIC_BUFFER);
 
         Arc::new(Self {
             store,
             agents: RwLock::new(HashMap::new()),
             outbound_tx,
             response_tx,
+            initiated_tx,
+            agents_changed,
+            lifecycle_tx,
+            traffic_tx,
+            dead_letter,
+            delivery_locks: std::sync::Mutex::new(HashMap::new()),
+            limits,
         })
     }
 
     /// Send a message to an agent
     pub async fn send_to_agent(&self, msg: OutboundMessage) -> Result<(), Status> {
-        let agents = self.agents.read().await;
-        if let Some(agent) = agents.get(&msg.agent_id) {
-            let server_msg = ServerMessage {
-                payload: Some(coven_proto::server_message::Payload::SendMessage(
-                    SendMessage {
-                        request_id: msg.request_id,
-                        thread_id: msg.thread_id,
-                        sender: msg.sender,
-                        content: msg.content,
-                        attachments: vec![],
-                    },
-                )),
-            };
-            agent
-                .tx
-                .send(server_msg)
+        let tx = self
+            .agents
+            .read()
+            .await
+            .get(&msg.agent_id)
+            .map(|agent| agent.tx.clone());
+        let Some(tx) = tx else {
+            return Err(Status::not_found(format!(
+                "agent not connected: {}",
+                msg.agent_id
+            )));
+        };
+
+        let agent_id = msg.agent_id.clone();
+        let request = ActiveRequestInfo {
+            request_id: msg.request_id.clone(),
+            thread_id: msg.thread_id.clone(),
+            started_at: Utc::now(),
+        };
+        let event = self.is_tapped().then(|| message_event("inbound", &msg));
+        let server_msg = ServerMessage::from(msg);
+        self.check_size(&agent_id, &server_msg)?;
+
+        // Tracked before sending, so even the quickest answer finds its thread
+        let request_id = request.request_id.clone();
+        if let Some(agent) = self.agents.write().await.get_mut(&agent_id) {
+            agent.active.insert(request_id.clone(), request);
+        }
+        if tx.send(server_msg).await.is_err() {
+            if let Some(agent) = self.agents.write().await.get_mut(&agent_id) {
+                agent.active.remove(&request_id);
+            }
+            return Err(Status::internal("failed to send to agent"));
+        }
+        if let Some(event) = event {
+            self.tap(event);
+        }
+        Ok(())
+    }
+
+    /// Receive a copy of the traffic through the gateway from now on
+    pub fn subscribe_traffic(&self) -> broadcast::Receiver<TrafficEvent> {
+        self.traffic_tx.subscribe()
+    }
+
+    /// Whether anyone is tailing traffic
+    fn is_tapped(&self) -> bool {
+        self.traffic_tx.receiver_count() > 0
+    }
+
+    fn tap(&self, event: TrafficEvent) {
+        // No receivers just means nobody is tailing
+        let _ = self.traffic_tx.send(event);
+    }
+
+    /// Report a message refused before it reached routing
+    pub fn tap_rejected(&self, agent_id: &str, content: &str, reason: &Status) {
+        if self.is_tapped() {
+            let mut event = with_content(traffic_event("rejected", agent_id), content);
+            event.detail = Some(reason.message().to_string());
+            self.tap(event);
+        }
+    }
+
+    /// Persist tool approval requests, and resolve pending ones when the
+    /// agent reports the tool moved on without an answer from a client
+    /// (e.g. timed out, or approved by an earlier "approve all").
+    async fn record_approval(&self, agent_id: &str, response: &MessageResponse) {
+        use coven_proto::message_response::Event;
+        use coven_proto::ToolState;
+        let result = match &response.event {
+            Some(Event::ToolApprovalRequest(request)) => {
+                self.store
+                    .record_approval_request(&ToolApproval {
+                        agent_id: agent_id.to_string(),
+                        tool_id: request.id.clone(),
+                        request_id: response.request_id.clone(),
+                        tool_name: request.name.clone(),
+                        input_json: InputRedactor::new().redact(&request.input_json),
+                        confirm_message: request.confirm_message.clone(),
+                        requested_at: Utc::now(),
+                        resolved_at: None,
+                        decision: None,
+                        resolved_by: None,
+                    })
+                    .await
+            }
+            Some(Event::ToolState(update)) => {
+                let decision = match update.state() {
+                    ToolState::Running | ToolState::Completed | ToolState::Failed => "approved",
+                    ToolState::Denied => "denied",
+                    ToolState::Timeout => "timeout",
+                    ToolState::Cancelled => "cancelled",
+                    _ => return,
+                };
+                self.store
+                    .resolve_approval(agent_id, &update.id, decision, None)
+                    .await
+                    .map(|_| ())
+            }
+            _ => return,
+        };
+        if let Err(e) = result {
+            warn!(agent_id = %agent_id, error = %e, "Failed to record tool approval");
+        }
+    }
+
+    /// Persist the tool calls and token usage in an agent's response, so
+    /// exports have the whole conversation and not only its text
+    async fn record_activity(&self, agent_id: &str, thread_id: &str, response: &MessageResponse) {
+        use coven_proto::message_response::Event;
+        let result = match &response.event {
+            Some(Event::ToolUse(tool)) => {
+                self.store
+                    .record_tool_call(&ToolCall {
+                        agent_id: agent_id.to_string(),
+                        thread_id: thread_id.to_string(),
+                        tool_id: tool.id.clone(),
+                        request_id: response.request_id.clone(),
+                        tool_name: tool.name.clone(),
+                        // Credentials never reach the database
+                        input_json: InputRedactor::new().redact(&tool.input_json),
+                        output: None,
+                        is_error: false,
+                        created_at: Utc::now(),
+                        completed_at: None,
+                    })
+                    .await
+            }
+            Some(Event::ToolResult(result)) => self
+                .store
+                .record_tool_result(
+                    agent_id,
+                    &result.id,
+                    &redact_output(&result.output),
+                    result.is_error,
+                )
                 .await
-                .map_err(|_| Status::internal("failed to send to agent"))?;
-            Ok(())
-        } else {
-            Err(Status::not_found(format!(
+                .map(|_| ()),
+            Some(Event::Usage(usage)) => {
+                self.store
+                    .record_usage(&TokenUsage {
+                        agent_id: agent_id.to_string(),
+                        thread_id: thread_id.to_string(),
+                        request_id: response.request_id.clone(),
+                        input_tokens: usage.input_tokens.into(),
+                        output_tokens: usage.output_tokens.into(),
+                        cache_read_tokens: usage.cache_read_tokens.into(),
+                        cache_write_tokens: usage.cache_write_tokens.into(),
+                        thinking_tokens: usage.thinking_tokens.into(),
+                        created_at: Utc::now(),
+                    })
+                    .await
+            }
+            _ => return,
+        };
+        if let Err(e) = result {
+            warn!(agent_id = %agent_id, error = %e, "Failed to record agent activity");
+        }
+    }
+
+    /// Track an agent's response: Done and Error finish its request, Error
+    /// counts toward the agent's recent errors, and Budget replaces its
+    /// reported spend.
+    async fn record_response(&self, agent_id: &str, response: &MessageResponse) {
+        use coven_proto::message_response::Event;
+        let (kind, content) = match &response.event {
+            Some(Event::Done(done)) => ("response", &done.full_response),
+            Some(Event::Error(error)) => ("error", error),
+            Some(Event::Budget(budget)) => {
+                if let Some(agent) = self.agents.write().await.get_mut(agent_id) {
+                    agent.budget = Some(budget.clone());
+                }
+                return;
+            }
+            _ => return,
+        };
+        let is_error = kind == "error";
+        let mut started_at = None;
+        if let Some(agent) = self.agents.write().await.get_mut(agent_id) {
+            started_at = agent
+                .active
+                .remove(&response.request_id)
+                .map(|request| request.started_at);
+            if is_error {
+                let now = Utc::now();
+                agent.errors.push_back(now);
+                while agent
+                    .errors
+                    .front()
+                    .is_some_and(|t| now - *t > ERROR_WINDOW)
+                {
+                    agent.errors.pop_front();
+                }
+            }
+        }
+
+        if self.is_tapped() {
+            let mut event = with_content(traffic_event(kind, agent_id), content);
+            event.request_id = response.request_id.clone();
+            event.latency_ms = started_at.map(|started| (Utc::now() - started).num_milliseconds());
+            self.tap(event);
+        }
+    }
+
+    /// Fails with `ResourceExhausted` when `msg` is too large to send to
+    /// its agent. Sending it anyway would reset the agent's stream.
+    pub fn check_deliverable(&self, msg: &OutboundMessage) -> Result<(), Status> {
+        self.check_size(&msg.agent_id, &ServerMessage::from(msg.clone()))
+    }
+
+    fn check_size(&self, agent_id: &str, msg: &ServerMessage) -> Result<(), Status> {
+        self.limits.check_outgoing(msg).map_err(|e| {
+            Status::resource_exhausted(format!(
+                "message too large to deliver to agent {}: {}",
+                agent_id, e
+            ))
+        })
+    }
+
+    /// Whether messages for offline agents are queued
+    pub fn dead_letter_enabled(&self) -> bool {
+        self.dead_letter.is_some()
+    }
+
+    /// Queue a message for an agent that is offline, to be delivered when
+    /// it reconnects.
+    pub async fn queue_dead_letter(&self, msg: OutboundMessage) -> Result<(), Status> {
+        let Some(config) = &self.dead_letter else {
+            return Err(Status::failed_precondition("dead-letter queue is disabled"));
+        };
+
+        if self.is_tapped() {
+            self.tap(message_event("queued", &msg));
+        }
+
+        let now = Utc::now();
+        let expires_at = chrono::Duration::from_std(config.ttl)
+            .ok()
+            .and_then(|ttl| now.checked_add_signed(ttl))
+            .unwrap_or_else(|| now + chrono::Duration::days(365));
+        let letter = DeadLetter {
+            id: msg.request_id,
+            agent_id: msg.agent_id,
+            thread_id: msg.thread_id,
+            sender: msg.sender,
+            content: msg.content,
+            sender_display: msg.sender_display,
+            sender_platform_id: msg.sender_platform_id,
+            sender_platform: msg.sender_platform,
+            reply_to_message_id: msg.reply_to_message_id,
+            model: msg.model,
+            max_tokens: msg.max_tokens,
+            created_at: now,
+            expires_at,
+        };
+
+        let dropped = self
+            .store
+            .enqueue_dead_letter(&letter, config.max_per_agent)
+            .await
+            .map_err(|e| Status::internal(format!("database error: {}", e)))?;
+        if dropped > 0 {
+            warn!(agent_id = %letter.agent_id, dropped, "Dead-letter queue full, dropped oldest messages");
+        }
+        info!(agent_id = %letter.agent_id, request_id = %letter.id, "Queued message for offline agent");
+        Ok(())
+    }
+
+    fn delivery_lock(&self, agent_id: &str) -> Arc<Mutex<()>> {
+        self.delivery_locks
+            .lock()
+            .expect("delivery locks poisoned")
+            .entry(agent_id.to_string())
+            .or_default()
+            .clone()
+    }
+
+    /// Send a message to a connected agent after anything still queued for
+    /// it, so a message sent while a replay is running can't overtake it.
+    pub async fn deliver(&self, msg: OutboundMessage) -> Result<(), Status> {
+        if !self.dead_letter_enabled() {
+            return self.send_to_agent(msg).await;
+        }
+        let lock = self.delivery_lock(&msg.agent_id);
+        let _guard = lock.lock().await;
+        let (_, remaining) = self.replay_queued(&msg.agent_id).await?;
+        if remaining > 0 {
+            return Err(Status::not_found(format!(
                 "agent not connected: {}",
                 msg.agent_id
-            )))
+            )));
         }
+        self.send_to_agent(msg).await
+    }
+
+    /// Deliver an agent's queued messages in order. Returns how many were
+    /// delivered and how many are still queued (if the agent went away).
+    pub async fn replay_dead_letters(&self, agent_id: &str) -> Result<(usize, usize), Status> {
+        if !self.is_connected(agent_id).await {
+            return Err(Status::not_found(format!(
+                "agent not connected: {}",
+                agent_id
+            )));
+        }
+        let lock = self.delivery_lock(agent_id);
+        let _guard = lock.lock().await;
+
+        if let Err(e) = self.store.purge_expired_dead_letters().await {
+            warn!(error = %e, "Failed to purge expired dead letters");
+        }
+        self.replay_queued(agent_id).await
+    }
+
+    /// Send the agent's queued messages, oldest first. The caller holds the
+    /// agent's delivery lock.
+    async fn replay_queued(&self, agent_id: &str) -> Result<(usize, usize), Status> {
+        let letters = self
+            .store
+            .list_dead_letters(Some(agent_id))
+            .await
+            .map_err(|e| Status::internal(format!("database error: {}", e)))?;
+
+        let total = letters.len();
+        let mut delivered = 0;
+        for letter in letters {
+            let id = letter.id.clone();
+            if let Err(e) = self.send_to_agent(letter.into()).await {
+                // The agent went away mid-replay; the rest stay queued
+                warn!(agent_id = %agent_id, error = %e, "Stopped replaying queued messages");
+                break;
+            }
+            if let Err(e) = self.store.delete_dead_letter(&id).await {
+                warn!(error = %e, request_id = %id, "Failed to remove delivered dead letter");
+            }
+            delivered += 1;
+        }
+
+        if delivered > 0 {
+            info!(agent_id = %agent_id, delivered, "Delivered queued messages");
+        }
+        Ok((delivered, total - delivered))
     }
 
     /// Subscribe to responses from agents
@@ -103,6 +643,27 @@ impl ControlState {
         self.response_tx.subscribe()
     }
 
+    /// Subscribe to messages agents send on their own initiative
+    pub fn subscribe_initiated(&self) -> broadcast::Receiver<AgentInitiatedEvent> {
+        self.initiated_tx.subscribe()
+    }
+
+    /// Get notified when agents connect, disconnect, or change presence.
+    /// Notifications carry no data; re-read the agent list on each one.
+    pub fn subscribe_agent_changes(&self) -> broadcast::Receiver<()> {
+        self.agents_changed.subscribe()
+    }
+
+    /// Subscribe to agents connecting and disconnecting
+    pub fn subscribe_lifecycle(&self) -> broadcast::Receiver<AgentLifecycle> {
+        self.lifecycle_tx.subscribe()
+    }
+
+    fn notify_agents_changed(&self) {
+        // No receivers just means nobody is watching
+        let _ = self.agents_changed.send(());
+    }
+
     /// List connected agent IDs
     pub async fn list_connected(&self) -> Vec<String> {
         self.agents.read().await.keys().cloned().collect()
@@ -113,6 +674,66 @@ impl ControlState {
         self.agents.read().await.contains_key(agent_id)
     }
 
+    /// Metadata of each connected agent, including its current presence
+    pub async fn connected_metadata(&self) -> HashMap<String, AgentMetadata> {
+        self.agents
+            .read()
+            .await
+            .iter()
+            .map(|(id, agent)| (id.clone(), agent.metadata.clone()))
+            .collect()
+    }
+
+    /// Connection details of an agent, or None if it isn't connected
+    pub async fn connection_info(&self, agent_id: &str) -> Option<AgentConnectionInfo> {
+        self.agents.read().await.get(agent_id).map(|agent| {
+            let mut active_requests: Vec<_> = agent.active.values().cloned().collect();
+            active_requests.sort_by_key(|r| r.started_at);
+            let since = Utc::now() - ERROR_WINDOW;
+            AgentConnectionInfo {
+                metadata: agent.metadata.clone(),
+                connected_at: agent.connected_at,
+                last_heartbeat: agent.last_heartbeat,
+                heartbeat_latency_ms: agent.heartbeat_latency_ms,
+                capabilities: agent.capabilities.clone(),
+                protocol_features: agent.protocol_features.clone(),
+                active_requests,
+                recent_errors: agent.errors.iter().filter(|t| **t > since).count(),
+                budget: agent.budget.clone(),
+            }
+        })
+    }
+
+    /// Thread of a request an agent is still working on
+    async fn thread_of(&self, agent_id: &str, request_id: &str) -> Option<String> {
+        self.agents
+            .read()
+            .await
+            .get(agent_id)?
+            .active
+            .get(request_id)
+            .map(|request| request.thread_id.clone())
+    }
+
+    /// Record a heartbeat from a connected agent, stamped `timestamp_ms`
+    /// (Unix millis, 0 if the agent didn't set it) by its clock
+    async fn record_heartbeat(&self, agent_id: &str, timestamp_ms: i64) {
+        if let Some(agent) = self.agents.write().await.get_mut(agent_id) {
+            let now = Utc::now();
+            agent.last_heartbeat = Some(now);
+            agent.heartbeat_latency_ms =
+                (timestamp_ms > 0).then(|| now.timestamp_millis() - timestamp_ms);
+        }
+    }
+
+    /// Record a presence update from a connected agent
+    async fn set_presence(&self, agent_id: &str, presence: AgentPresence) {
+        if let Some(agent) = self.agents.write().await.get_mut(agent_id) {
+            agent.metadata.presence = Some(normalize_presence(Some(presence)));
+        }
+        self.notify_agents_changed();
+    }
+
     /// Forward tool approval to an agent
     pub async fn approve_tool(
         &self,
@@ -146,16 +767,121 @@ impl ControlState {
             )))
         }
     }
+
+    /// Ask a connected agent to replace its log filter with `filter`
+    pub async fn set_log_level(&self, agent_id: &str, filter: &str) -> Result<(), Status> {
+        let tx = self
+            .agents
+            .read()
+            .await
+            .get(agent_id)
+            .map(|agent| agent.tx.clone())
+            .ok_or_else(|| Status::not_found(format!("agent not connected: {}", agent_id)))?;
+        let server_msg = ServerMessage {
+            payload: Some(coven_proto::server_message::Payload::SetLogLevel(
+                SetLogLevel {
+                    filter: filter.to_string(),
+                },
+            )),
+        };
+        tx.send(server_msg)
+            .await
+            .map_err(|_| Status::internal("failed to send log level to agent"))?;
+        info!(agent_id = %agent_id, filter = %filter, "Agent log level change sent");
+        Ok(())
+    }
+
+    /// Fail unless `agent_id` is connected and handles thread forks.
+    /// Agents without the feature would drop a fork, leaving a thread the
+    /// gateway has but the agent doesn't.
+    pub async fn check_can_fork(&self, agent_id: &str) -> Result<(), Status> {
+        let agents = self.agents.read().await;
+        let agent = agents.get(agent_id).ok_or_else(|| {
+            Status::failed_precondition(format!("agent not connected: {}", agent_id))
+        })?;
+        if !agent
+            .protocol_features
+            .iter()
+            .any(|f| f == ForkThread::PROTOCOL_FEATURE)
+        {
+            return Err(Status::failed_precondition(format!(
+                "agent {} can't fork threads",
+                agent_id
+            )));
+        }
+        Ok(())
+    }
+
+    /// Ask a connected agent to fork `thread_id` into `new_thread_id`,
+    /// starting with `history`
+    pub async fn fork_thread(
+        &self,
+        agent_id: &str,
+        thread_id: &str,
+        new_thread_id: &str,
+        history: Vec<ForkedMessage>,
+    ) -> Result<(), Status> {
+        let tx = self
+            .agents
+            .read()
+            .await
+            .get(agent_id)
+            .map(|agent| agent.tx.clone())
+            .ok_or_else(|| Status::not_found(format!("agent not connected: {}", agent_id)))?;
+        let server_msg = ServerMessage {
+            payload: Some(coven_proto::server_message::Payload::ForkThread(
+                ForkThread {
+                    thread_id: thread_id.to_string(),
+                    new_thread_id: new_thread_id.to_string(),
+                    history,
+                },
+            )),
+        };
+        tx.send(server_msg)
+            .await
+            .map_err(|_| Status::internal("failed to send thread fork to agent"))?;
+        info!(agent_id = %agent_id, thread_id = %thread_id, new_thread_id = %new_thread_id, "Thread fork sent");
+        Ok(())
+    }
 }
 
 /// CovenControl service implementation
 pub struct CovenControlService {
     state: Arc<ControlState>,
+    /// Connected packs, for serving agents' pack tool calls
+    packs: Option<Arc<PackState>>,
+    /// Content filter for agents' replies
+    filter: Option<Arc<ContentFilter>>,
+    shutdown: Shutdown,
 }
 
 impl CovenControlService {
     pub fn new(state: Arc<ControlState>) -> Self {
-        Self { state }
+        Self {
+            state,
+            packs: None,
+            filter: None,
+            shutdown: Shutdown::default(),
+        }
+    }
+
+    /// Serve agents' pack tool calls from these packs.
+    pub fn with_packs(mut self, packs: Arc<PackState>) -> Self {
+        self.packs = Some(packs);
+        self
+    }
+
+    /// Check agents' replies and agent-initiated messages with `filter`
+    /// before passing them on.
+    pub fn with_filter(mut self, filter: Arc<ContentFilter>) -> Self {
+        self.filter = Some(filter);
+        self
+    }
+
+    /// End this service's open streams when `shutdown` is triggered.
+    pub fn with_shutdown(mut self, shutdown: Shutdown) -> Self {
+        self.shutdown = shutdown;
+        self
     }
 
     pub fn state(&self) -> Arc<ControlState> {
@@ -232,10 +958,38 @@ impl CovenControl for CovenControlService {
                 ConnectedAgent {
                     id: agent_id.clone(),
                     name: agent_name.clone(),
+                    metadata: AgentMetadata {
+                        presence: Some(normalize_presence(
+                            metadata.and_then(|m| m.presence.clone()),
+                        )),
+                        ..metadata.cloned().unwrap_or_default()
+                    },
+                    connected_at: Utc::now(),
+                    last_heartbeat: None,
+                    heartbeat_latency_ms: None,
+                    capabilities: register.capabilities.clone(),
+                    protocol_features: register.protocol_features.clone(),
+                    active: HashMap::new(),
+                    errors: VecDeque::new(),
+                    budget: None,
                     tx: tx.clone(),
                 },
             );
         }
+        self.state.notify_agents_changed();
+
+        // Tools from packs connected right now; later changes are pushed
+        // as they happen. Subscribe first so none are missed in between.
+        let tool_changes = self.packs.as_ref().map(|packs| packs.subscribe_tools());
+        let available_tools = match &self.packs {
+            Some(packs) => packs
+                .list_tools()
+                .await
+                .into_iter()
+                .map(|(_, tool)| tool)
+                .collect(),
+            None => vec![],
+        };
 
         // Send welcome message
         let welcome = ServerMessage {
@@ -244,7 +998,7 @@ impl CovenControl for CovenControlService {
                 agent_id: agent_id.clone(),
                 instance_id: agent_id[..8.min(agent_id.len())].to_string(),
                 principal_id: agent_id.clone(), // No principals in local mode
-                available_tools: vec![],        // TODO: populate from packs
+                available_tools,
                 mcp_token: String::new(),
                 mcp_endpoint: String::new(),
                 secrets: HashMap::new(),
@@ -255,34 +1009,128 @@ impl CovenControl for CovenControlService {
             .map_err(|_| Status::internal("failed to send welcome"))?;
 
         info!(agent_id = %agent_id, "Agent registered");
+        // No subscribers just means no webhook is listening
+        let _ = self.state.lifecycle_tx.send(AgentLifecycle::Connected {
+            agent_id: agent_id.clone(),
+            name: agent_name.clone(),
+        });
+
+        // Deliver anything that arrived while the agent was offline
+        if self.state.dead_letter_enabled() {
+            let state = self.state.clone();
+            let agent_id = agent_id.clone();
+            tokio::spawn(async move {
+                if let Err(e) = state.replay_dead_letters(&agent_id).await {
+                    warn!(agent_id = %agent_id, error = %e, "Failed to replay queued messages");
+                }
+            });
+        }
+
+        let tool_updates = self
+            .packs
+            .clone()
+            .zip(tool_changes)
+            .map(|(packs, changes)| tokio::spawn(push_tool_updates(packs, changes, tx.clone())));
 
         // Clone state for the inbound handler
         let state = self.state.clone();
+        let packs = self.packs.clone();
+        let filter = self.filter.clone();
+        let agent_tx = tx.clone();
         let agent_id_clone = agent_id.clone();
+        let agent_name_clone = agent_name.clone();
 
         // Spawn task to handle inbound messages from agent
         tokio::spawn(async move {
+            let mut stream_error = None;
             while let Some(result) = inbound.next().await {
                 match result {
                     Ok(msg) => {
                         if let Some(payload) = msg.payload {
                             match payload {
-                                coven_proto::agent_message::Payload::Heartbeat(_) => {
+                                coven_proto::agent_message::Payload::Heartbeat(heartbeat) => {
                                     debug!(agent_id = %agent_id_clone, "Heartbeat received");
+                                    state
+                                        .record_heartbeat(&agent_id_clone, heartbeat.timestamp_ms)
+                                        .await;
                                     // Update last_seen
                                     let _ = state
                                         .store
                                         .set_agent_connected(&agent_id_clone, true)
                                         .await;
                                 }
-                                coven_proto::agent_message::Payload::Response(resp) => {
+                                coven_proto::agent_message::Payload::Response(mut resp) => {
                                     debug!(agent_id = %agent_id_clone, request_id = %resp.request_id, "Response received");
+                                    if let Some(filter) = &filter {
+                                        filter.filter_response(&agent_id_clone, &mut resp);
+                                    }
+                                    // Looked up first, as a finished request is forgotten
+                                    let thread_id = state
+                                        .thread_of(&agent_id_clone, &resp.request_id)
+                                        .await
+                                        .unwrap_or_else(|| agent_id_clone.clone());
+                                    state.record_response(&agent_id_clone, &resp).await;
+                                    state.record_approval(&agent_id_clone, &resp).await;
+                                    state
+                                        .record_activity(&agent_id_clone, &thread_id, &resp)
+                                        .await;
                                     let _ = state.response_tx.send(AgentResponse {
                                         agent_id: agent_id_clone.clone(),
                                         request_id: resp.request_id.clone(),
+                                        thread_id,
                                         response: resp,
                                     });
                                 }
+                                coven_proto::agent_message::Payload::AgentInitiated(mut msg) => {
+                                    debug!(agent_id = %agent_id_clone, message_id = %msg.message_id, "Agent-initiated message received");
+                                    if filter.as_ref().is_some_and(|f| {
+                                        f.check_outbound(&agent_id_clone, &msg.content).is_some()
+                                    }) {
+                                        msg.content = REMOVED_NOTICE.to_string();
+                                    }
+                                    // No subscribers just means no bridge is listening
+                                    let _ = state.initiated_tx.send(AgentInitiatedEvent {
+                                        agent_id: agent_id_clone.clone(),
+                                        agent_name: agent_name_clone.clone(),
+                                        message_id: msg.message_id,
+                                        content: msg.content,
+                                        thread_id: msg.thread_id,
+                                        timestamp: Utc::now().to_rfc3339(),
+                                    });
+                                }
+                                coven_proto::agent_message::Payload::UpdatePresence(presence) => {
+                                    debug!(agent_id = %agent_id_clone, status = %presence.status, "Presence updated");
+                                    state.set_presence(&agent_id_clone, presence).await;
+                                }
+                                coven_proto::agent_message::Payload::ThreadTitle(titled) => {
+                                    debug!(agent_id = %agent_id_clone, thread_id = %titled.thread_id, "Thread titled");
+                                    match state
+                                        .store
+                                        .set_conversation_title(
+                                            &agent_id_clone,
+                                            &titled.thread_id,
+                                            titled.title.as_deref(),
+                                        )
+                                        .await
+                                    {
+                                        Ok(true) => {}
+                                        Ok(false) => {
+                                            debug!(agent_id = %agent_id_clone, thread_id = %titled.thread_id, "No conversation for titled thread")
+                                        }
+                                        Err(e) => {
+                                            warn!(agent_id = %agent_id_clone, error = %e, "Failed to store thread title")
+                                        }
+                                    }
+                                }
+                                coven_proto::agent_message::Payload::ExecutePackTool(call) => {
+                                    debug!(agent_id = %agent_id_clone, request_id = %call.request_id, tool = %call.tool_name, "Pack tool call received");
+                                    tokio::spawn(run_pack_tool(
+                                        packs.clone(),
+                                        agent_id_clone.clone(),
+                                        agent_tx.clone(),
+                                        call,
+                                    ));
+                                }
                                 _ => {
                                     debug!(agent_id = %agent_id_clone, "Other message received");
                                 }
@@ -291,6 +1139,7 @@ impl CovenControl for CovenControlService {
                     }
                     Err(e) => {
                         warn!(agent_id = %agent_id_clone, error = %e, "Stream error");
+                        stream_error = Some(e.message().to_string());
                         break;
                     }
                 }
@@ -298,6 +1147,12 @@ impl CovenControl for CovenControlService {
 
             // Agent disconnected
             info!(agent_id = %agent_id_clone, "Agent disconnected");
+            if let Some(tool_updates) = tool_updates {
+                tool_updates.abort();
+            }
+            if let Some(filter) = &filter {
+                filter.forget_agent(&agent_id_clone);
+            }
             {
                 let mut agents = state.agents.write().await;
                 agents.remove(&agent_id_clone);
@@ -306,10 +1161,179 @@ impl CovenControl for CovenControlService {
                 .store
                 .set_agent_connected(&agent_id_clone, false)
                 .await;
+            // A reconnected agent starts over, so nothing can answer these
+            match state.store.expire_pending_approvals(&agent_id_clone).await {
+                Ok(0) => {}
+                Ok(expired) => {
+                    debug!(agent_id = %agent_id_clone, expired, "Expired pending tool approvals")
+                }
+                Err(e) => {
+                    warn!(agent_id = %agent_id_clone, error = %e, "Failed to expire tool approvals")
+                }
+            }
+            state.notify_agents_changed();
+            let _ = state.lifecycle_tx.send(AgentLifecycle::Disconnected {
+                agent_id: agent_id_clone,
+                name: agent_name_clone,
+                error: stream_error,
+            });
         });
 
         // Return stream of outbound messages
         let stream = ReceiverStream::new(rx).map(Ok);
-        Ok(Response::new(Box::pin(stream)))
+        Ok(Response::new(self.shutdown.until_shutdown(stream)))
+    }
+}
+
+/// Send an agent the full pack tool list each time it changes, until the
+/// agent goes away.
+async fn push_tool_updates(
+    packs: Arc<PackState>,
+    mut changes: broadcast::Receiver<()>,
+    agent_tx: mpsc::Sender<ServerMessage>,
+) {
+    loop {
+        match changes.recv().await {
+            // Missed notifications don't matter; only the latest list is sent
+            Ok(()) | Err(broadcast::error::RecvError::Lagged(_)) => {}
+            Err(broadcast::error::RecvError::Closed) => return,
+        }
+        let tools = packs
+            .list_tools()
+            .await
+            .into_iter()
+            .map(|(_, tool)| tool)
+            .collect();
+        let msg = ServerMessage {
+            payload: Some(coven_proto::server_message::Payload::AvailableTools(
+                AvailableTools { tools },
+            )),
+        };
+        if agent_tx.send(msg).await.is_err() {
+            return;
+        }
+    }
+}
+
+/// Execute a pack tool for an agent, forwarding the pack's progress while it
+/// runs and then the result, all tagged with the agent's request id.
+async fn run_pack_tool(
+    packs: Option<Arc<PackState>>,
+    agent_id: String,
+    agent_tx: mpsc::Sender<ServerMessage>,
+    call: ExecutePackTool,
+) {
+    let result = match packs {
+        Some(packs) => {
+            let (progress_tx, progress_rx) = mpsc::unbounded_channel();
+            let forward = tokio::spawn(forward_progress(
+                agent_tx.clone(),
+                call.request_id.clone(),
+                progress_rx,
+            ));
+
+            let response = packs
+                .execute_tool(
+                    &call.tool_name,
+                    &call.input_json,
+                    Some(&agent_id),
+                    Some(progress_tx),
+                )
+                .await;
+            // The progress channel closes once the execution is finished, so
+            // this delivers any queued progress before the result
+            let _ = forward.await;
+
+            match response {
+                Ok(response) => match response.result {
+                    Some(coven_proto::execute_tool_response::Result::OutputJson(output)) => {
+                        pack_tool_result::Result::OutputJson(output)
+                    }
+                    Some(coven_proto::execute_tool_response::Result::Error(error)) => {
+                        pack_tool_result::Result::Error(error)
+                    }
+                    None => pack_tool_result::Result::Error("empty result from pack".to_string()),
+                },
+                Err(status) => pack_tool_result::Result::Error(status.message().to_string()),
+            }
+        }
+        None => pack_tool_result::Result::Error("no packs available".to_string()),
+    };
+
+    let msg = ServerMessage {
+        payload: Some(coven_proto::server_message::Payload::PackToolResult(
+            PackToolResult {
+                request_id: call.request_id,
+                result: Some(result),
+            },
+        )),
+    };
+    if agent_tx.send(msg).await.is_err() {
+        debug!("Agent disconnected before pack tool result");
+    }
+}
+
+/// Relay a pack's progress reports to the calling agent, tagged with the
+/// agent's request id, until the execution finishes or the agent goes away.
+async fn forward_progress(
+    agent_tx: mpsc::Sender<ServerMessage>,
+    request_id: String,
+    mut progress_rx: mpsc::UnboundedReceiver<PackToolProgress>,
+) {
+    while let Some(mut progress) = progress_rx.recv().await {
+        progress.request_id = request_id.clone();
+        let msg = ServerMessage {
+            payload: Some(coven_proto::server_message::Payload::PackToolProgress(
+                progress,
+            )),
+        };
+        if agent_tx.send(msg).await.is_err() {
+            return;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn presence(status: &str, emoji: &str) -> AgentPresence {
+        AgentPresence {
+            status: status.to_string(),
+            emoji: emoji.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_normalize_presence_defaults_to_available() {
+        assert_eq!(normalize_presence(None), AgentPresence::available());
+        assert_eq!(
+            normalize_presence(Some(presence("  ", "🍕"))),
+            presence("available", "🍕")
+        );
+    }
+
+    #[test]
+    fn test_normalize_presence_trims_and_caps() {
+        assert_eq!(
+            normalize_presence(Some(presence(" busy ", " 🔥 "))),
+            presence("busy", "🔥")
+        );
+        let long = "x".repeat(MAX_PRESENCE_CHARS * 2);
+        let normalized = normalize_presence(Some(presence(&long, "")));
+        assert_eq!(normalized.status.chars().count(), MAX_PRESENCE_CHARS);
+    }
+
+    #[test]
+    fn test_redact_output_hides_credential_fields_only() {
+        let redacted: serde_json::Value =
+            serde_json::from_str(&redact_output(r#"{"user":"harper","api_key":"sk-123"}"#))
+                .unwrap();
+        assert_eq!(
+            redacted,
+            serde_json::json!({"user": "harper", "api_key": "***"})
+        );
+        assert_eq!(redact_output("token: sk-123"), "token: sk-123");
+        assert_eq!(redact_output("42"), "42");
     }
 }
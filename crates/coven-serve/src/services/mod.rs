@@ -1,10 +1,14 @@
 // ABOUTME: gRPC service implementations for the local gateway
-// ABOUTME: CovenControl (agents), ClientService (TUI), PackService (packs)
+// ABOUTME: CovenControl (agents), ClientService (TUI), PackService (packs), AdminService (dead letters)
 
+pub mod admin;
 pub mod client;
 pub mod control;
 pub mod pack;
+pub mod shutdown;
 
+pub use admin::AdminServiceImpl;
 pub use client::ClientServiceImpl;
 pub use control::CovenControlService;
 pub use pack::PackServiceImpl;
+pub use shutdown::Shutdown;
@@ -1,34 +1,77 @@
 // ABOUTME: PackService gRPC implementation for tool pack connections
-// ABOUTME: Handles pack registration and tool execution routing
+// ABOUTME: Handles pack registration and manifest updates, tool execution routing, progress forwarding, pack health, and secrets
 
+use crate::roles::{signature_refused, Authorizer};
+use crate::secrets::SecretVault;
+use crate::services::shutdown::Shutdown;
 use crate::store::{Pack, Store};
-use chrono::Utc;
+use chrono::{DateTime, Utc};
 use coven_proto::server::PackService;
-use coven_proto::{ExecuteToolRequest, ExecuteToolResponse, PackManifest, ToolDefinition};
+use coven_proto::{
+    ExecuteToolRequest, ExecuteToolResponse, PackInfo, PackManifest, PackSecrets, PackStatus,
+    PackToolProgress, PackWelcome, ToolDefinition, WatchSecretsRequest,
+};
+use coven_ssh::{compute_fingerprint, verify_request, NonceCache, DEFAULT_SKEW_TOLERANCE};
 use futures::Stream;
 use std::collections::HashMap;
 use std::pin::Pin;
 use std::sync::Arc;
 use std::task::{Context, Poll};
-use tokio::sync::{mpsc, oneshot, RwLock};
+use tokio::sync::{broadcast, mpsc, oneshot, RwLock};
 use tokio_stream::wrappers::ReceiverStream;
+use tonic::metadata::MetadataMap;
 use tonic::{Request, Response, Status};
 use tracing::{debug, error, info, warn};
 use uuid::Uuid;
 
 /// Connected pack handle
 struct ConnectedPack {
-    #[allow(dead_code)]
     id: String,
-    #[allow(dead_code)]
     version: String,
     tools: Vec<ToolDefinition>,
     tx: mpsc::Sender<ExecuteToolRequest>,
+    health: PackHealth,
+}
+
+/// Last health status a pack reported. Packs count as healthy until they
+/// report otherwise, since older packs never report.
+#[derive(Default)]
+struct PackHealth {
+    unhealthy_reason: Option<String>,
+    reported_at: Option<DateTime<Utc>>,
+}
+
+impl ConnectedPack {
+    /// The pack's tools, marked degraded while the pack is unhealthy. A
+    /// healthy pack can still mark individual tools degraded in its manifest.
+    fn advertised_tools(&self) -> impl Iterator<Item = ToolDefinition> + '_ {
+        self.tools.iter().map(|tool| ToolDefinition {
+            degraded_reason: self
+                .health
+                .unhealthy_reason
+                .clone()
+                .or_else(|| tool.degraded_reason.clone()),
+            ..tool.clone()
+        })
+    }
+
+    fn info(&self) -> PackInfo {
+        PackInfo {
+            pack_id: self.id.clone(),
+            version: self.version.clone(),
+            tools: self.tools.iter().map(|t| t.name.clone()).collect(),
+            healthy: self.health.unhealthy_reason.is_none(),
+            status_message: self.health.unhealthy_reason.clone().unwrap_or_default(),
+            last_status_at: self.health.reported_at.map(|at| at.to_rfc3339()),
+        }
+    }
 }
 
 /// Pending tool execution
 struct PendingExecution {
     response_tx: oneshot::Sender<ExecuteToolResponse>,
+    /// Where the pack's progress reports for this execution go, if anywhere
+    progress_tx: Option<mpsc::UnboundedSender<PackToolProgress>>,
 }
 
 /// Stream wrapper that triggers cleanup when the pack disconnects
@@ -70,50 +113,153 @@ pub struct PackState {
     packs: RwLock<HashMap<String, ConnectedPack>>,
     /// Pending tool executions: request_id -> sender
     pending: RwLock<HashMap<String, PendingExecution>>,
+    /// Signalled whenever the set of available tools changes
+    tools_changed: broadcast::Sender<()>,
 }
 
 impl PackState {
     pub fn new(store: Store) -> Arc<Self> {
+        let (tools_changed, _) = broadcast::channel(16);
         Arc::new(Self {
             store,
             packs: RwLock::new(HashMap::new()),
             pending: RwLock::new(HashMap::new()),
+            tools_changed,
         })
     }
 
-    /// Get all available tools from all connected packs
+    /// Get notified when packs connect, disconnect, or change their tools.
+    /// Notifications carry no data; re-read `list_tools` on each one.
+    pub fn subscribe_tools(&self) -> broadcast::Receiver<()> {
+        self.tools_changed.subscribe()
+    }
+
+    fn notify_tools_changed(&self) {
+        // No receivers just means no agents are connected
+        let _ = self.tools_changed.send(());
+    }
+
+    /// Get all available tools from all connected packs. Tools of packs
+    /// that last reported themselves unhealthy carry a `degraded_reason`.
     pub async fn list_tools(&self) -> Vec<(String, ToolDefinition)> {
         let packs = self.packs.read().await;
         let mut tools = Vec::new();
         for (pack_id, pack) in packs.iter() {
-            for tool in &pack.tools {
-                tools.push((pack_id.clone(), tool.clone()));
+            for tool in pack.advertised_tools() {
+                tools.push((pack_id.clone(), tool));
             }
         }
         tools
     }
 
-    /// Execute a tool on a pack
+    /// Connected packs and their health, sorted by pack ID
+    pub async fn list_packs(&self) -> Vec<PackInfo> {
+        let packs = self.packs.read().await;
+        let mut infos: Vec<PackInfo> = packs.values().map(ConnectedPack::info).collect();
+        infos.sort_by(|a, b| a.pack_id.cmp(&b.pack_id));
+        infos
+    }
+
+    /// Record a pack's health report. Reports from packs that aren't
+    /// connected are ignored.
+    pub async fn handle_status(&self, status: PackStatus) {
+        let mut packs = self.packs.write().await;
+        let Some(pack) = packs.get_mut(&status.pack_id) else {
+            debug!(pack_id = %status.pack_id, "Dropping status for unknown pack");
+            return;
+        };
+
+        let reason = (!status.healthy).then(|| status.message.clone());
+        match (&pack.health.unhealthy_reason, &reason) {
+            (None, Some(reason)) => {
+                warn!(pack_id = %status.pack_id, reason = %reason, "Pack reported unhealthy; marking its tools degraded")
+            }
+            (Some(_), None) => info!(pack_id = %status.pack_id, "Pack recovered"),
+            _ => {}
+        }
+        pack.health = PackHealth {
+            unhealthy_reason: reason,
+            reported_at: Some(Utc::now()),
+        };
+    }
+
+    /// Replace a connected pack's manifest in place, keeping its request
+    /// stream and pending executions. Agents are notified if its tools
+    /// changed.
+    pub async fn update_manifest(&self, manifest: PackManifest) -> Result<PackWelcome, Status> {
+        let changed = {
+            let mut packs = self.packs.write().await;
+            let Some(pack) = packs.get_mut(&manifest.pack_id) else {
+                return Err(Status::not_found(format!(
+                    "pack not connected: {}",
+                    manifest.pack_id
+                )));
+            };
+            let changed = pack.tools != manifest.tools;
+            pack.version = manifest.version.clone();
+            pack.tools = manifest.tools;
+            changed
+        };
+
+        let pack = Pack {
+            id: manifest.pack_id.clone(),
+            version: manifest.version.clone(),
+            connected: true,
+            connected_at: Some(Utc::now()),
+        };
+        if let Err(e) = self.store.upsert_pack(&pack).await {
+            error!(pack_id = %manifest.pack_id, error = %e, "Failed to save pack");
+        }
+
+        if changed {
+            info!(pack_id = %manifest.pack_id, version = %manifest.version, "Pack tools updated");
+            self.notify_tools_changed();
+        }
+
+        Ok(PackWelcome {
+            pack_id: manifest.pack_id,
+            rejected_tools: vec![],
+        })
+    }
+
+    /// Execute a tool on a pack on behalf of `agent_id`, which the pack may
+    /// use to keep agents' data apart. Progress the pack reports while the
+    /// tool runs is sent to `progress_tx`, which is dropped once the
+    /// execution finishes.
     pub async fn execute_tool(
         &self,
         tool_name: &str,
         input_json: &str,
+        agent_id: Option<&str>,
+        progress_tx: Option<mpsc::UnboundedSender<PackToolProgress>>,
     ) -> Result<ExecuteToolResponse, Status> {
         // Find which pack has this tool and extract the sender
         // Release the lock before any async operations to avoid race conditions
-        let tx = {
+        let (tx, pack_id, redactor) = {
             let packs = self.packs.read().await;
-            let pack = packs
-                .values()
-                .find(|p| p.tools.iter().any(|t| t.name == tool_name));
-
-            match pack {
-                Some(p) => p.tx.clone(),
+            let found = packs.values().find_map(|p| {
+                p.tools
+                    .iter()
+                    .find(|t| t.name == tool_name)
+                    .map(|tool| (p, tool))
+            });
+
+            match found {
+                Some((p, tool)) => (p.tx.clone(), p.id.clone(), tool.input_redactor()),
                 None => return Err(Status::not_found(format!("tool not found: {}", tool_name))),
             }
         };
 
         let request_id = Uuid::new_v4().to_string();
+        // Inputs can carry credentials; only the redacted form is logged
+        debug!(
+            pack_id = %pack_id,
+            request_id = %request_id,
+            tool = %tool_name,
+            agent_id = agent_id.unwrap_or(""),
+            input = %redactor.redact(input_json),
+            "Routing tool call to pack"
+        );
 
         // Create oneshot channel for response
         let (response_tx, response_rx) = oneshot::channel();
@@ -121,7 +267,13 @@ impl PackState {
         // Store pending execution
         {
             let mut pending = self.pending.write().await;
-            pending.insert(request_id.clone(), PendingExecution { response_tx });
+            pending.insert(
+                request_id.clone(),
+                PendingExecution {
+                    response_tx,
+                    progress_tx,
+                },
+            );
         }
 
         // Send request to pack (lock is already released)
@@ -129,6 +281,9 @@ impl PackState {
             tool_name: tool_name.to_string(),
             input_json: input_json.to_string(),
             request_id: request_id.clone(),
+            agent_id: agent_id.map(str::to_string),
+            // Agents don't say which thread a pack tool call belongs to
+            thread_id: None,
         };
 
         if tx.send(request).await.is_err() {
@@ -159,6 +314,24 @@ impl PackState {
         }
     }
 
+    /// Handle a progress report from a pack, forwarding it to whoever is
+    /// waiting on that execution. Reports for finished or unknown
+    /// executions are dropped.
+    pub async fn handle_tool_progress(&self, progress: PackToolProgress) {
+        let pending = self.pending.read().await;
+        match pending
+            .get(&progress.request_id)
+            .and_then(|execution| execution.progress_tx.as_ref())
+        {
+            Some(tx) => {
+                let _ = tx.send(progress);
+            }
+            None => {
+                debug!(request_id = %progress.request_id, "Dropping progress for unknown execution");
+            }
+        }
+    }
+
     /// Cleanup when pack disconnects
     pub async fn disconnect_pack(&self, pack_id: &str) {
         info!(pack_id = %pack_id, "Pack disconnecting");
@@ -174,9 +347,12 @@ impl PackState {
         };
 
         // Remove the pack
-        {
+        let removed = {
             let mut packs = self.packs.write().await;
-            packs.remove(pack_id);
+            packs.remove(pack_id).is_some()
+        };
+        if removed {
+            self.notify_tools_changed();
         }
 
         // Note: We can't easily clean up pending executions tied to this pack
@@ -198,11 +374,88 @@ impl PackState {
 /// PackService implementation
 pub struct PackServiceImpl {
     state: Arc<PackState>,
+    secrets: Option<Arc<SecretVault>>,
+    authorizer: Option<Arc<Authorizer>>,
+    /// Replay protection for signed `watch_secrets` calls without a roles file
+    nonces: NonceCache,
+    shutdown: Shutdown,
 }
 
 impl PackServiceImpl {
     pub fn new(state: Arc<PackState>) -> Self {
-        Self { state }
+        Self {
+            state,
+            secrets: None,
+            authorizer: None,
+            nonces: NonceCache::default(),
+            shutdown: Shutdown::default(),
+        }
+    }
+
+    /// Hand packs their secrets from this vault in `watch_secrets`.
+    pub fn with_secrets(mut self, secrets: Arc<SecretVault>) -> Self {
+        self.secrets = Some(secrets);
+        self
+    }
+
+    /// Identify packs through the roles file, so a principal named after a
+    /// pack can read its secrets with any of its keys.
+    pub fn with_authorizer(mut self, authorizer: Arc<Authorizer>) -> Self {
+        self.authorizer = Some(authorizer);
+        self
+    }
+
+    /// End this service's open streams when `shutdown` is triggered.
+    pub fn with_shutdown(mut self, shutdown: Shutdown) -> Self {
+        self.shutdown = shutdown;
+        self
+    }
+
+    /// Check that the request was signed by `pack_id` itself: a roles file
+    /// principal of that name, or else the key the pack first fetched its
+    /// secrets with, which is pinned to the pack from then on.
+    async fn authenticate_pack(&self, pack_id: &str, metadata: &MetadataMap) -> Result<(), Status> {
+        let (principal_id, fingerprint) = match &self.authorizer {
+            Some(authorizer) => {
+                let caller = authorizer.caller(metadata)?;
+                (Some(caller.principal_id), caller.key_fingerprint)
+            }
+            None => {
+                let key = verify_request(metadata, None, DEFAULT_SKEW_TOLERANCE, &self.nonces)
+                    .map_err(signature_refused)?;
+                let fingerprint = key
+                    .map(|key| compute_fingerprint(&key))
+                    .transpose()
+                    .map_err(|e| Status::unauthenticated(e.to_string()))?;
+                (None, fingerprint)
+            }
+        };
+        let Some(fingerprint) = fingerprint else {
+            return Err(Status::unauthenticated(
+                "pack secrets need a request signed with the pack's key",
+            ));
+        };
+        if principal_id.as_deref() == Some(pack_id) {
+            return Ok(());
+        }
+
+        let pinned = self
+            .state
+            .store
+            .pin_pack_key(pack_id, &fingerprint)
+            .await
+            .map_err(|e| {
+                error!(pack_id = %pack_id, error = %e, "Failed to check pack key");
+                Status::internal("failed to check pack key")
+            })?;
+        if !pinned {
+            warn!(pack_id = %pack_id, key = %&fingerprint[..16], "Refusing pack secrets to a different key");
+            return Err(Status::permission_denied(format!(
+                "pack {} belongs to a different key",
+                pack_id
+            )));
+        }
+        Ok(())
     }
 
     pub fn state(&self) -> Arc<PackState> {
@@ -214,6 +467,8 @@ impl PackServiceImpl {
 impl PackService for PackServiceImpl {
     type RegisterStream =
         Pin<Box<dyn futures::Stream<Item = Result<ExecuteToolRequest, Status>> + Send>>;
+    type WatchSecretsStream =
+        Pin<Box<dyn futures::Stream<Item = Result<PackSecrets, Status>> + Send>>;
 
     async fn register(
         &self,
@@ -249,11 +504,13 @@ impl PackService for PackServiceImpl {
                     version,
                     tools: manifest.tools,
                     tx,
+                    health: PackHealth::default(),
                 },
             );
         }
 
         info!(pack_id = %pack_id, "Pack registered");
+        self.state.notify_tools_changed();
 
         // Return stream with cleanup on disconnect
         let stream = PackStream {
@@ -261,7 +518,7 @@ impl PackService for PackServiceImpl {
             pack_id,
             state: self.state.clone(),
         };
-        Ok(Response::new(Box::pin(stream)))
+        Ok(Response::new(self.shutdown.until_shutdown(stream)))
     }
 
     async fn tool_result(
@@ -273,4 +530,92 @@ impl PackService for PackServiceImpl {
         self.state.handle_tool_result(response).await;
         Ok(Response::new(()))
     }
+
+    async fn tool_progress(
+        &self,
+        request: Request<PackToolProgress>,
+    ) -> Result<Response<()>, Status> {
+        let progress = request.into_inner();
+        debug!(request_id = %progress.request_id, percent = ?progress.percent, "Tool progress received");
+        self.state.handle_tool_progress(progress).await;
+        Ok(Response::new(()))
+    }
+
+    async fn report_status(&self, request: Request<PackStatus>) -> Result<Response<()>, Status> {
+        let status = request.into_inner();
+        debug!(pack_id = %status.pack_id, healthy = status.healthy, "Pack status received");
+        self.state.handle_status(status).await;
+        Ok(Response::new(()))
+    }
+
+    async fn update_manifest(
+        &self,
+        request: Request<PackManifest>,
+    ) -> Result<Response<PackWelcome>, Status> {
+        let manifest = request.into_inner();
+        debug!(pack_id = %manifest.pack_id, tools = manifest.tools.len(), "Manifest update received");
+        let welcome = self.state.update_manifest(manifest).await?;
+        Ok(Response::new(welcome))
+    }
+
+    async fn watch_secrets(
+        &self,
+        request: Request<WatchSecretsRequest>,
+    ) -> Result<Response<Self::WatchSecretsStream>, Status> {
+        let pack_id = request.get_ref().pack_id.clone();
+        if pack_id.is_empty() {
+            return Err(Status::invalid_argument("pack_id is required"));
+        }
+        self.authenticate_pack(&pack_id, request.metadata()).await?;
+
+        let (tx, rx) = mpsc::channel::<Result<PackSecrets, Status>>(4);
+        let Some(vault) = self.secrets.clone() else {
+            // No vault configured: the pack has no secrets, now or later
+            let _ = tx.send(Ok(PackSecrets::default())).await;
+            return Ok(Response::new(Box::pin(ReceiverStream::new(rx))));
+        };
+
+        // Subscribe before the first read so no change slips between them
+        let mut changes = vault.subscribe();
+        let values = vault.values(&pack_id).await.map_err(|e| {
+            error!(pack_id = %pack_id, error = %e, "Failed to read pack secrets");
+            Status::internal("failed to read pack secrets")
+        })?;
+        debug!(pack_id = %pack_id, count = values.len(), "Sending pack secrets");
+        let _ = tx.send(Ok(PackSecrets { values })).await;
+
+        tokio::spawn(async move {
+            loop {
+                let changed = tokio::select! {
+                    _ = tx.closed() => return,
+                    changed = changes.recv() => changed,
+                };
+                match changed {
+                    Ok(id) if id != pack_id => continue,
+                    // Missed notifications might include ours; resend to be safe
+                    Ok(_) | Err(broadcast::error::RecvError::Lagged(_)) => {}
+                    Err(broadcast::error::RecvError::Closed) => return,
+                }
+
+                let update = match vault.values(&pack_id).await {
+                    Ok(values) => {
+                        debug!(pack_id = %pack_id, count = values.len(), "Pushing updated pack secrets");
+                        Ok(PackSecrets { values })
+                    }
+                    Err(e) => {
+                        error!(pack_id = %pack_id, error = %e, "Failed to read pack secrets");
+                        Err(Status::internal("failed to read pack secrets"))
+                    }
+                };
+                let failed = update.is_err();
+                if tx.send(update).await.is_err() || failed {
+                    return;
+                }
+            }
+        });
+
+        Ok(Response::new(
+            self.shutdown.until_shutdown(ReceiverStream::new(rx)),
+        ))
+    }
 }
@@ -0,0 +1,44 @@
+// ABOUTME: Shutdown signal shared by the gRPC services' long-lived server streams
+// ABOUTME: Ends agent, client, and pack streams so graceful shutdown isn't held open by them
+
+use futures::{Stream, StreamExt};
+use std::pin::Pin;
+use tokio_util::sync::CancellationToken;
+
+/// Ends the services' open server streams when the gateway stops.
+///
+/// Graceful shutdown waits for every in-flight call to finish, and the
+/// streams agents, clients, and packs hold open never finish on their own.
+/// Services bound those streams with `until_shutdown`; the server calls
+/// `trigger` once it stops accepting connections.
+#[derive(Debug, Clone, Default)]
+pub struct Shutdown(CancellationToken);
+
+impl Shutdown {
+    /// End every stream bound to this signal.
+    pub fn trigger(&self) {
+        self.0.cancel();
+    }
+
+    /// `stream`, ending early when the gateway shuts down.
+    pub fn until_shutdown<S>(&self, stream: S) -> Pin<Box<dyn Stream<Item = S::Item> + Send>>
+    where
+        S: Stream + Send + 'static,
+    {
+        Box::pin(stream.take_until(self.0.clone().cancelled_owned()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_trigger_ends_bound_streams() {
+        let shutdown = Shutdown::default();
+        let mut stream = shutdown.until_shutdown(futures::stream::pending::<()>());
+
+        shutdown.trigger();
+        assert_eq!(stream.next().await, None);
+    }
+}
@@ -1,10 +1,21 @@
 // ABOUTME: SQLite persistence for local gateway - simplified schema for super-trusted mode
-// ABOUTME: Stores agents, conversations, and messages without auth/principal complexity
+// ABOUTME: Stores agents, conversations, messages, and dead letters without auth/principal complexity
 
 use anyhow::{Context, Result};
-use chrono::{DateTime, Utc};
-use sqlx::{sqlite::SqlitePoolOptions, Pool, Row, Sqlite};
+use chrono::{DateTime, SecondsFormat, Utc};
+use sqlx::sqlite::{SqliteConnectOptions, SqliteJournalMode, SqlitePoolOptions, SqliteSynchronous};
+use sqlx::{Pool, Row, Sqlite};
+use std::collections::HashMap;
 use std::path::Path;
+use std::time::Duration;
+use uuid::Uuid;
+
+/// How long a connection waits on a locked database before failing with
+/// SQLITE_BUSY, unless the caller picks its own timeout
+pub const DEFAULT_BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Push notification platforms a device token can belong to
+pub const PUSH_PLATFORMS: &[&str] = &["apns", "fcm"];
 
 /// Local gateway store backed by SQLite
 #[derive(Clone)]
@@ -31,6 +42,8 @@ pub struct Conversation {
     pub agent_id: String,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
+    /// Title the agent gave the thread, once it has one
+    pub title: Option<String>,
 }
 
 /// Message in a conversation
@@ -42,7 +55,40 @@ pub struct Message {
     pub author: String,
     pub content: String,
     pub message_type: String, // "message", "tool_use", "tool_result", "thinking"
+    /// Earlier message in the conversation this one replies to
+    pub reply_to_message_id: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Inbound message queued while its agent was offline
+#[derive(Debug, Clone)]
+pub struct DeadLetter {
+    /// Also used as the request ID when the message is finally delivered
+    pub id: String,
+    pub agent_id: String,
+    pub thread_id: String,
+    pub sender: String,
+    pub content: String,
+    pub sender_display: Option<String>,
+    pub sender_platform_id: Option<String>,
+    pub sender_platform: Option<String>,
+    pub reply_to_message_id: Option<String>,
+    /// Per-request overrides the message was sent with
+    pub model: Option<String>,
+    pub max_tokens: Option<u32>,
     pub created_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+}
+
+/// Fixed-width RFC 3339 timestamp so stored values compare correctly as text
+fn sortable_timestamp(t: DateTime<Utc>) -> String {
+    t.to_rfc3339_opts(SecondsFormat::Micros, true)
+}
+
+fn parse_timestamp(s: &str) -> DateTime<Utc> {
+    DateTime::parse_from_rfc3339(s)
+        .map(|dt| dt.with_timezone(&Utc))
+        .unwrap_or_else(|_| Utc::now())
 }
 
 /// Pack registration
@@ -54,19 +100,188 @@ pub struct Pack {
     pub connected_at: Option<DateTime<Utc>>,
 }
 
+/// A pack secret as stored: the value is encrypted with the gateway's
+/// master key and only `secrets::SecretVault` can read it
+#[derive(Clone)]
+pub struct EncryptedSecret {
+    pub pack_id: String,
+    pub key: String,
+    pub nonce: Vec<u8>,
+    pub ciphertext: Vec<u8>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// A device registered for push notifications
+#[derive(Debug, Clone)]
+pub struct PushToken {
+    pub token: String,
+    pub principal_id: String,
+    /// One of `PUSH_PLATFORMS`
+    pub platform: String,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// A bridge channel the gateway has received a message from
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecentChannel {
+    pub frontend: String,
+    pub channel_id: String,
+    /// Channel name, if the bridge reported one
+    pub display_name: Option<String>,
+    pub last_message_at: DateTime<Utc>,
+}
+
+/// An SSH key that authenticates as a principal by fingerprint, registered
+/// by a key rotation
+#[derive(Debug, Clone, PartialEq)]
+pub struct KeyGrant {
+    /// SHA-256 fingerprint, hex
+    pub fingerprint: String,
+    pub principal_id: String,
+    /// When the key stops authenticating; None until it's rotated out
+    pub expires_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// A bearer token the gateway issued; the token itself isn't kept
+#[derive(Debug, Clone, PartialEq)]
+pub struct TokenRecord {
+    /// The token's jti
+    pub id: String,
+    pub principal_id: String,
+    pub issued_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+}
+
+/// A principal's TOTP secret, for one-time codes on destructive admin RPCs,
+/// encrypted with the gateway's master key
+#[derive(Debug, Clone, PartialEq)]
+pub struct OtpSecret {
+    pub principal_id: String,
+    /// None for secrets stored in the clear by older gateways
+    pub nonce: Option<Vec<u8>>,
+    pub ciphertext: Vec<u8>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// A tool approval an agent asked for, and how it was resolved
+#[derive(Debug, Clone)]
+pub struct ToolApproval {
+    pub agent_id: String,
+    /// Tool invocation ID, unique per agent
+    pub tool_id: String,
+    /// Message request the tool call belongs to
+    pub request_id: String,
+    pub tool_name: String,
+    pub input_json: String,
+    pub confirm_message: Option<String>,
+    pub requested_at: DateTime<Utc>,
+    /// None while still pending
+    pub resolved_at: Option<DateTime<Utc>>,
+    /// One of `APPROVAL_DECISIONS` once resolved
+    pub decision: Option<String>,
+    /// Principal that decided; None when the agent resolved it on its own
+    /// (timed out, cancelled) or went away
+    pub resolved_by: Option<String>,
+}
+
+/// A tool an agent called while answering a message
+#[derive(Debug, Clone, PartialEq)]
+pub struct ToolCall {
+    pub agent_id: String,
+    /// Conversation of the request; the agent ID for calls recorded before
+    /// conversations could be forked, when each agent had one
+    pub thread_id: String,
+    /// Tool invocation ID, unique per agent
+    pub tool_id: String,
+    /// Message request the call belongs to
+    pub request_id: String,
+    pub tool_name: String,
+    pub input_json: String,
+    /// None until the tool reports its result
+    pub output: Option<String>,
+    pub is_error: bool,
+    pub created_at: DateTime<Utc>,
+    pub completed_at: Option<DateTime<Utc>>,
+}
+
+/// Tokens one usage update from an agent reported
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TokenUsage {
+    pub agent_id: String,
+    /// Conversation of the request, as for `ToolCall::thread_id`
+    pub thread_id: String,
+    pub request_id: String,
+    pub input_tokens: i64,
+    pub output_tokens: i64,
+    pub cache_read_tokens: i64,
+    pub cache_write_tokens: i64,
+    pub thinking_tokens: i64,
+    pub created_at: DateTime<Utc>,
+}
+
+/// What an export covers: one agent's records, or everyone's, within a
+/// time range
+#[derive(Debug, Clone, Default)]
+pub struct ExportFilter {
+    pub agent_id: Option<String>,
+    /// Records from before this are left out
+    pub since: Option<DateTime<Utc>>,
+    /// Records from this time on are left out
+    pub until: Option<DateTime<Utc>>,
+}
+
+/// A conversation as exported: who took part and who spoke last
+#[derive(Debug, Clone)]
+pub struct ThreadSummary {
+    pub conversation: Conversation,
+    /// Distinct message authors, in order of first appearance
+    pub participants: Vec<String>,
+    /// Direction of the newest message, None when there are none
+    pub last_direction: Option<String>,
+}
+
+/// How a tool approval can end
+pub const APPROVAL_DECISIONS: &[&str] = &[
+    "approved",
+    "approved_all",
+    "denied",
+    "timeout",
+    "cancelled",
+    "expired",
+];
+
 impl Store {
     /// Open or create the store at the given path
     pub async fn open(path: &Path) -> Result<Self> {
+        Self::open_with_busy_timeout(path, DEFAULT_BUSY_TIMEOUT).await
+    }
+
+    /// Open or create the store, waiting up to `busy_timeout` for a locked
+    /// database before giving up.
+    ///
+    /// Every pooled connection gets the same pragmas: `journal_mode = WAL` so
+    /// readers don't block the writer, `synchronous = NORMAL` (safe under WAL,
+    /// and far fewer fsyncs than FULL), `foreign_keys = ON`, and
+    /// `busy_timeout` so concurrent writers queue instead of failing.
+    pub async fn open_with_busy_timeout(path: &Path, busy_timeout: Duration) -> Result<Self> {
         // Ensure parent directory exists
         if let Some(parent) = path.parent() {
             std::fs::create_dir_all(parent)
                 .with_context(|| format!("creating directory: {}", parent.display()))?;
         }
 
-        let url = format!("sqlite:{}?mode=rwc", path.display());
+        let options = SqliteConnectOptions::new()
+            .filename(path)
+            .create_if_missing(true)
+            .journal_mode(SqliteJournalMode::Wal)
+            .synchronous(SqliteSynchronous::Normal)
+            .foreign_keys(true)
+            .busy_timeout(busy_timeout);
         let pool = SqlitePoolOptions::new()
             .max_connections(5)
-            .connect(&url)
+            .connect_with(options)
             .await
             .with_context(|| format!("opening database: {}", path.display()))?;
 
@@ -77,14 +292,6 @@ impl Store {
 
     /// Initialize database schema
     async fn init_schema(&self) -> Result<()> {
-        // Enable WAL mode for better concurrency
-        sqlx::query("PRAGMA journal_mode = WAL")
-            .execute(&self.pool)
-            .await?;
-        sqlx::query("PRAGMA foreign_keys = ON")
-            .execute(&self.pool)
-            .await?;
-
         sqlx::query(
             r#"
             CREATE TABLE IF NOT EXISTS agents (
@@ -101,7 +308,8 @@ impl Store {
                 id TEXT PRIMARY KEY,
                 agent_id TEXT NOT NULL,
                 created_at TEXT NOT NULL,
-                updated_at TEXT NOT NULL
+                updated_at TEXT NOT NULL,
+                title TEXT
             );
             CREATE INDEX IF NOT EXISTS idx_conversations_agent ON conversations(agent_id);
 
@@ -112,6 +320,7 @@ impl Store {
                 author TEXT NOT NULL,
                 content TEXT NOT NULL,
                 message_type TEXT NOT NULL DEFAULT 'message',
+                reply_to_message_id TEXT,
                 created_at TEXT NOT NULL,
                 FOREIGN KEY (conversation_id) REFERENCES conversations(id)
             );
@@ -123,12 +332,198 @@ impl Store {
                 connected INTEGER NOT NULL DEFAULT 0,
                 connected_at TEXT
             );
+
+            CREATE TABLE IF NOT EXISTS pack_secrets (
+                pack_id TEXT NOT NULL,
+                key TEXT NOT NULL,
+                nonce BLOB NOT NULL,
+                ciphertext BLOB NOT NULL,
+                updated_at TEXT NOT NULL,
+                PRIMARY KEY (pack_id, key)
+            );
+
+            CREATE TABLE IF NOT EXISTS pack_keys (
+                pack_id TEXT PRIMARY KEY,
+                key_fingerprint TEXT NOT NULL,
+                pinned_at TEXT NOT NULL
+            );
+
+            CREATE TABLE IF NOT EXISTS dead_letters (
+                id TEXT PRIMARY KEY,
+                agent_id TEXT NOT NULL,
+                thread_id TEXT NOT NULL,
+                sender TEXT NOT NULL,
+                content TEXT NOT NULL,
+                sender_display TEXT,
+                sender_platform_id TEXT,
+                sender_platform TEXT,
+                reply_to_message_id TEXT,
+                model TEXT,
+                max_tokens INTEGER,
+                created_at TEXT NOT NULL,
+                expires_at TEXT NOT NULL
+            );
+            CREATE INDEX IF NOT EXISTS idx_dead_letters_agent ON dead_letters(agent_id, created_at);
+
+            CREATE TABLE IF NOT EXISTS push_tokens (
+                token TEXT PRIMARY KEY,
+                principal_id TEXT NOT NULL,
+                platform TEXT NOT NULL,
+                created_at TEXT NOT NULL,
+                updated_at TEXT NOT NULL
+            );
+            CREATE INDEX IF NOT EXISTS idx_push_tokens_principal ON push_tokens(principal_id);
+
+            CREATE TABLE IF NOT EXISTS recent_channels (
+                frontend TEXT NOT NULL,
+                channel_id TEXT NOT NULL,
+                display_name TEXT,
+                last_message_at TEXT NOT NULL,
+                PRIMARY KEY (frontend, channel_id)
+            );
+
+            CREATE TABLE IF NOT EXISTS tool_approvals (
+                agent_id TEXT NOT NULL,
+                tool_id TEXT NOT NULL,
+                request_id TEXT NOT NULL,
+                tool_name TEXT NOT NULL,
+                input_json TEXT NOT NULL,
+                confirm_message TEXT,
+                requested_at TEXT NOT NULL,
+                resolved_at TEXT,
+                decision TEXT,
+                resolved_by TEXT,
+                PRIMARY KEY (agent_id, tool_id)
+            );
+            CREATE INDEX IF NOT EXISTS idx_tool_approvals_pending ON tool_approvals(agent_id, resolved_at, requested_at);
+
+            CREATE TABLE IF NOT EXISTS key_grants (
+                fingerprint TEXT PRIMARY KEY,
+                principal_id TEXT NOT NULL,
+                expires_at TEXT,
+                created_at TEXT NOT NULL
+            );
+
+            CREATE TABLE IF NOT EXISTS issued_tokens (
+                id TEXT PRIMARY KEY,
+                principal_id TEXT NOT NULL,
+                issued_at TEXT NOT NULL,
+                expires_at TEXT NOT NULL,
+                revoked_at TEXT
+            );
+
+            CREATE TABLE IF NOT EXISTS otp_secrets (
+                principal_id TEXT PRIMARY KEY,
+                secret BLOB NOT NULL,
+                created_at TEXT NOT NULL,
+                nonce BLOB
+            );
+
+            CREATE TABLE IF NOT EXISTS tool_calls (
+                agent_id TEXT NOT NULL,
+                tool_id TEXT NOT NULL,
+                request_id TEXT NOT NULL,
+                tool_name TEXT NOT NULL,
+                input_json TEXT NOT NULL,
+                output TEXT,
+                is_error INTEGER NOT NULL DEFAULT 0,
+                created_at TEXT NOT NULL,
+                completed_at TEXT,
+                thread_id TEXT,
+                PRIMARY KEY (agent_id, tool_id)
+            );
+
+            CREATE TABLE IF NOT EXISTS token_usage (
+                agent_id TEXT NOT NULL,
+                request_id TEXT NOT NULL,
+                input_tokens INTEGER NOT NULL,
+                output_tokens INTEGER NOT NULL,
+                cache_read_tokens INTEGER NOT NULL,
+                cache_write_tokens INTEGER NOT NULL,
+                thinking_tokens INTEGER NOT NULL,
+                created_at TEXT NOT NULL,
+                thread_id TEXT
+            );
+            CREATE INDEX IF NOT EXISTS idx_token_usage_agent ON token_usage(agent_id);
             "#,
         )
         .execute(&self.pool)
         .await
         .context("initializing schema")?;
 
+        // Databases created before replies existed lack the column
+        for table in ["messages", "dead_letters"] {
+            let columns: Vec<String> =
+                sqlx::query_scalar(&format!("SELECT name FROM pragma_table_info('{}')", table))
+                    .fetch_all(&self.pool)
+                    .await?;
+            if !columns.iter().any(|c| c == "reply_to_message_id") {
+                sqlx::query(&format!(
+                    "ALTER TABLE {} ADD COLUMN reply_to_message_id TEXT",
+                    table
+                ))
+                .execute(&self.pool)
+                .await
+                .with_context(|| format!("migrating {}", table))?;
+            }
+        }
+
+        // Databases from before conversations could be forked record tool
+        // calls and usage by agent only
+        for table in ["tool_calls", "token_usage"] {
+            let columns: Vec<String> =
+                sqlx::query_scalar(&format!("SELECT name FROM pragma_table_info('{}')", table))
+                    .fetch_all(&self.pool)
+                    .await?;
+            if !columns.iter().any(|c| c == "thread_id") {
+                sqlx::query(&format!("ALTER TABLE {} ADD COLUMN thread_id TEXT", table))
+                    .execute(&self.pool)
+                    .await
+                    .with_context(|| format!("migrating {}", table))?;
+            }
+        }
+
+        // Databases from before queued messages kept their overrides lack these
+        let columns: Vec<String> =
+            sqlx::query_scalar("SELECT name FROM pragma_table_info('dead_letters')")
+                .fetch_all(&self.pool)
+                .await?;
+        for (column, kind) in [("model", "TEXT"), ("max_tokens", "INTEGER")] {
+            if !columns.iter().any(|c| c == column) {
+                sqlx::query(&format!(
+                    "ALTER TABLE dead_letters ADD COLUMN {} {}",
+                    column, kind
+                ))
+                .execute(&self.pool)
+                .await
+                .context("migrating dead_letters")?;
+            }
+        }
+
+        // Databases from before thread titles lack the column
+        let columns: Vec<String> =
+            sqlx::query_scalar("SELECT name FROM pragma_table_info('conversations')")
+                .fetch_all(&self.pool)
+                .await?;
+        if !columns.iter().any(|c| c == "title") {
+            sqlx::query("ALTER TABLE conversations ADD COLUMN title TEXT")
+                .execute(&self.pool)
+                .await
+                .context("migrating conversations")?;
+        }
+
+        // Databases from before TOTP secrets were encrypted lack the nonce
+        let columns: Vec<String> =
+            sqlx::query_scalar("SELECT name FROM pragma_table_info('otp_secrets')")
+                .fetch_all(&self.pool)
+                .await?;
+        if !columns.iter().any(|c| c == "nonce") {
+            sqlx::query("ALTER TABLE otp_secrets ADD COLUMN nonce BLOB")
+                .execute(&self.pool)
+                .await
+                .context("migrating otp_secrets")?;
+        }
+
         Ok(())
     }
 
@@ -263,6 +658,7 @@ impl Store {
             agent_id: agent_id.to_string(),
             created_at: now,
             updated_at: now,
+            title: None,
         })
     }
 
@@ -270,7 +666,7 @@ impl Store {
     pub async fn get_or_create_conversation(&self, agent_id: &str) -> Result<Conversation> {
         // Try to get existing
         let row = sqlx::query(
-            "SELECT id, agent_id, created_at, updated_at FROM conversations WHERE agent_id = ? ORDER BY updated_at DESC LIMIT 1",
+            "SELECT id, agent_id, created_at, updated_at, title FROM conversations WHERE agent_id = ? ORDER BY updated_at DESC LIMIT 1",
         )
         .bind(agent_id)
         .fetch_optional(&self.pool)
@@ -288,6 +684,7 @@ impl Store {
                 updated_at: DateTime::parse_from_rfc3339(&updated_str)
                     .map(|dt| dt.with_timezone(&Utc))
                     .unwrap_or_else(|_| Utc::now()),
+                title: row.get("title"),
             });
         }
 
@@ -295,6 +692,142 @@ impl Store {
         self.create_conversation(agent_id).await
     }
 
+    /// Get a conversation by ID
+    pub async fn get_conversation(&self, id: &str) -> Result<Option<Conversation>> {
+        let row = sqlx::query(
+            "SELECT id, agent_id, created_at, updated_at, title FROM conversations WHERE id = ?",
+        )
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row.map(|row| {
+            let created_str: String = row.get("created_at");
+            let updated_str: String = row.get("updated_at");
+            Conversation {
+                id: row.get("id"),
+                agent_id: row.get("agent_id"),
+                created_at: DateTime::parse_from_rfc3339(&created_str)
+                    .map(|dt| dt.with_timezone(&Utc))
+                    .unwrap_or_else(|_| Utc::now()),
+                updated_at: DateTime::parse_from_rfc3339(&updated_str)
+                    .map(|dt| dt.with_timezone(&Utc))
+                    .unwrap_or_else(|_| Utc::now()),
+                title: row.get("title"),
+            }
+        }))
+    }
+
+    /// An agent's conversations, most recently active first
+    pub async fn list_conversations(&self, agent_id: &str) -> Result<Vec<Conversation>> {
+        let rows = sqlx::query(
+            "SELECT id, agent_id, created_at, updated_at, title FROM conversations WHERE agent_id = ? ORDER BY updated_at DESC",
+        )
+        .bind(agent_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .iter()
+            .map(|row| Conversation {
+                id: row.get("id"),
+                agent_id: row.get("agent_id"),
+                created_at: parse_timestamp(row.get("created_at")),
+                updated_at: parse_timestamp(row.get("updated_at")),
+                title: row.get("title"),
+            })
+            .collect())
+    }
+
+    /// Set or clear the title of `agent_id`'s conversation `conversation_id`.
+    /// Returns false if the agent has no such conversation.
+    pub async fn set_conversation_title(
+        &self,
+        agent_id: &str,
+        conversation_id: &str,
+        title: Option<&str>,
+    ) -> Result<bool> {
+        let result =
+            sqlx::query("UPDATE conversations SET title = ? WHERE id = ? AND agent_id = ?")
+                .bind(title)
+                .bind(conversation_id)
+                .bind(agent_id)
+                .execute(&self.pool)
+                .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Copy a conversation's messages up to and including `message_id`
+    /// into a new conversation with the same agent. The copies get new IDs,
+    /// with replies pointing at the copies. Returns the new conversation
+    /// and the messages copied, oldest first, or None if the conversation
+    /// has no such message.
+    pub async fn fork_conversation(
+        &self,
+        conversation_id: &str,
+        message_id: &str,
+    ) -> Result<Option<(Conversation, Vec<Message>)>> {
+        let Some(conversation) = self.get_conversation(conversation_id).await? else {
+            return Ok(None);
+        };
+        let mut copied = self.get_messages(conversation_id, i64::MAX).await?;
+        let Some(last) = copied.iter().position(|m| m.id == message_id) else {
+            return Ok(None);
+        };
+        copied.truncate(last + 1);
+
+        let now = Utc::now();
+        let fork = Conversation {
+            id: Uuid::new_v4().to_string(),
+            agent_id: conversation.agent_id,
+            created_at: now,
+            updated_at: now,
+            title: conversation.title,
+        };
+        let new_ids: HashMap<&str, String> = copied
+            .iter()
+            .map(|m| (m.id.as_str(), Uuid::new_v4().to_string()))
+            .collect();
+
+        let mut tx = self.pool.begin().await?;
+        sqlx::query(
+            "INSERT INTO conversations (id, agent_id, created_at, updated_at, title) VALUES (?, ?, ?, ?, ?)",
+        )
+        .bind(&fork.id)
+        .bind(&fork.agent_id)
+        .bind(now.to_rfc3339())
+        .bind(now.to_rfc3339())
+        .bind(&fork.title)
+        .execute(&mut *tx)
+        .await?;
+        for message in &copied {
+            let reply_to = message
+                .reply_to_message_id
+                .as_deref()
+                .and_then(|id| new_ids.get(id));
+            sqlx::query(
+                r#"
+                INSERT INTO messages (id, conversation_id, direction, author, content, message_type,
+                                      reply_to_message_id, created_at)
+                VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+                "#,
+            )
+            .bind(&new_ids[message.id.as_str()])
+            .bind(&fork.id)
+            .bind(&message.direction)
+            .bind(&message.author)
+            .bind(&message.content)
+            .bind(&message.message_type)
+            .bind(reply_to)
+            .bind(message.created_at.to_rfc3339())
+            .execute(&mut *tx)
+            .await?;
+        }
+        tx.commit().await?;
+
+        Ok(Some((fork, copied)))
+    }
+
     /// Touch conversation (update updated_at)
     pub async fn touch_conversation(&self, conversation_id: &str) -> Result<()> {
         let now = Utc::now().to_rfc3339();
@@ -312,8 +845,9 @@ impl Store {
     pub async fn save_message(&self, msg: &Message) -> Result<()> {
         sqlx::query(
             r#"
-            INSERT INTO messages (id, conversation_id, direction, author, content, message_type, created_at)
-            VALUES (?, ?, ?, ?, ?, ?, ?)
+            INSERT INTO messages (id, conversation_id, direction, author, content, message_type,
+                                  reply_to_message_id, created_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?)
             "#,
         )
         .bind(&msg.id)
@@ -322,6 +856,7 @@ impl Store {
         .bind(&msg.author)
         .bind(&msg.content)
         .bind(&msg.message_type)
+        .bind(&msg.reply_to_message_id)
         .bind(msg.created_at.to_rfc3339())
         .execute(&self.pool)
         .await?;
@@ -332,18 +867,62 @@ impl Store {
         Ok(())
     }
 
+    /// Save a message unless one with its ID is already saved, in a single
+    /// insert so concurrent retries can't both succeed. Returns whether it
+    /// was saved.
+    pub async fn save_message_once(&self, msg: &Message) -> Result<bool> {
+        let result = sqlx::query(
+            r#"
+            INSERT OR IGNORE INTO messages (id, conversation_id, direction, author, content,
+                                            message_type, reply_to_message_id, created_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(&msg.id)
+        .bind(&msg.conversation_id)
+        .bind(&msg.direction)
+        .bind(&msg.author)
+        .bind(&msg.content)
+        .bind(&msg.message_type)
+        .bind(&msg.reply_to_message_id)
+        .bind(msg.created_at.to_rfc3339())
+        .execute(&self.pool)
+        .await?;
+        if result.rows_affected() == 0 {
+            return Ok(false);
+        }
+
+        self.touch_conversation(&msg.conversation_id).await?;
+        Ok(true)
+    }
+
     /// Get messages for a conversation
     pub async fn get_messages(&self, conversation_id: &str, limit: i64) -> Result<Vec<Message>> {
+        self.get_messages_after(conversation_id, None, limit).await
+    }
+
+    /// Get up to `limit` messages for a conversation that come after the
+    /// message with ID `after`, for paging through long histories. An
+    /// unknown `after` ID yields no messages.
+    pub async fn get_messages_after(
+        &self,
+        conversation_id: &str,
+        after: Option<&str>,
+        limit: i64,
+    ) -> Result<Vec<Message>> {
         let rows = sqlx::query(
             r#"
-            SELECT id, conversation_id, direction, author, content, message_type, created_at
+            SELECT id, conversation_id, direction, author, content, message_type,
+                   reply_to_message_id, created_at
             FROM messages
-            WHERE conversation_id = ?
-            ORDER BY created_at ASC
-            LIMIT ?
+            WHERE conversation_id = ?1
+              AND (?2 IS NULL OR (created_at, id) > (SELECT created_at, id FROM messages WHERE id = ?2))
+            ORDER BY created_at ASC, id ASC
+            LIMIT ?3
             "#,
         )
         .bind(conversation_id)
+        .bind(after)
         .bind(limit)
         .fetch_all(&self.pool)
         .await?;
@@ -358,6 +937,7 @@ impl Store {
                 author: row.get("author"),
                 content: row.get("content"),
                 message_type: row.get("message_type"),
+                reply_to_message_id: row.get("reply_to_message_id"),
                 created_at: DateTime::parse_from_rfc3339(&created_str)
                     .map(|dt| dt.with_timezone(&Utc))
                     .unwrap_or_else(|_| Utc::now()),
@@ -366,6 +946,176 @@ impl Store {
         Ok(messages)
     }
 
+    /// The `limit` most recent messages across an agent's conversations,
+    /// newest first
+    pub async fn recent_agent_messages(&self, agent_id: &str, limit: i64) -> Result<Vec<Message>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT m.id, m.conversation_id, m.direction, m.author, m.content, m.message_type,
+                   m.reply_to_message_id, m.created_at
+            FROM messages m
+            JOIN conversations c ON c.id = m.conversation_id
+            WHERE c.agent_id = ?
+            ORDER BY m.created_at DESC, m.rowid DESC
+            LIMIT ?
+            "#,
+        )
+        .bind(agent_id)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| Message {
+                id: row.get("id"),
+                conversation_id: row.get("conversation_id"),
+                direction: row.get("direction"),
+                author: row.get("author"),
+                content: row.get("content"),
+                message_type: row.get("message_type"),
+                reply_to_message_id: row.get("reply_to_message_id"),
+                created_at: parse_timestamp(&row.get::<String, _>("created_at")),
+            })
+            .collect())
+    }
+
+    // --- Dead-letter operations ---
+
+    /// Queue a message for an offline agent, then drop the agent's oldest
+    /// queued messages beyond `max_per_agent`; 0 keeps them all. Returns how
+    /// many were dropped. Re-queuing an existing ID (a retried send) is a no-op.
+    pub async fn enqueue_dead_letter(
+        &self,
+        letter: &DeadLetter,
+        max_per_agent: usize,
+    ) -> Result<u64> {
+        sqlx::query(
+            r#"
+            INSERT OR IGNORE INTO dead_letters (id, agent_id, thread_id, sender, content, sender_display,
+                                      sender_platform_id, sender_platform, reply_to_message_id,
+                                      model, max_tokens, created_at, expires_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(&letter.id)
+        .bind(&letter.agent_id)
+        .bind(&letter.thread_id)
+        .bind(&letter.sender)
+        .bind(&letter.content)
+        .bind(&letter.sender_display)
+        .bind(&letter.sender_platform_id)
+        .bind(&letter.sender_platform)
+        .bind(&letter.reply_to_message_id)
+        .bind(&letter.model)
+        .bind(letter.max_tokens)
+        .bind(sortable_timestamp(letter.created_at))
+        .bind(sortable_timestamp(letter.expires_at))
+        .execute(&self.pool)
+        .await?;
+        if max_per_agent == 0 {
+            return Ok(0);
+        }
+
+        let dropped = sqlx::query(
+            r#"
+            DELETE FROM dead_letters
+            WHERE agent_id = ? AND id NOT IN (
+                SELECT id FROM dead_letters
+                WHERE agent_id = ?
+                ORDER BY created_at DESC, rowid DESC
+                LIMIT ?
+            )
+            "#,
+        )
+        .bind(&letter.agent_id)
+        .bind(&letter.agent_id)
+        .bind(max_per_agent as i64)
+        .execute(&self.pool)
+        .await?
+        .rows_affected();
+
+        Ok(dropped)
+    }
+
+    /// List unexpired dead letters, oldest first, optionally for one agent
+    pub async fn list_dead_letters(&self, agent_id: Option<&str>) -> Result<Vec<DeadLetter>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT id, agent_id, thread_id, sender, content, sender_display,
+                   sender_platform_id, sender_platform, reply_to_message_id, model, max_tokens,
+                   created_at, expires_at
+            FROM dead_letters
+            WHERE (? IS NULL OR agent_id = ?) AND expires_at > ?
+            ORDER BY created_at ASC, rowid ASC
+            "#,
+        )
+        .bind(agent_id)
+        .bind(agent_id)
+        .bind(sortable_timestamp(Utc::now()))
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| DeadLetter {
+                id: row.get("id"),
+                agent_id: row.get("agent_id"),
+                thread_id: row.get("thread_id"),
+                sender: row.get("sender"),
+                content: row.get("content"),
+                sender_display: row.get("sender_display"),
+                sender_platform_id: row.get("sender_platform_id"),
+                sender_platform: row.get("sender_platform"),
+                reply_to_message_id: row.get("reply_to_message_id"),
+                model: row.get("model"),
+                max_tokens: row.get("max_tokens"),
+                created_at: parse_timestamp(&row.get::<String, _>("created_at")),
+                expires_at: parse_timestamp(&row.get::<String, _>("expires_at")),
+            })
+            .collect())
+    }
+
+    /// Count unexpired dead letters queued for an agent
+    pub async fn count_dead_letters(&self, agent_id: &str) -> Result<u64> {
+        let count: i64 = sqlx::query_scalar(
+            "SELECT COUNT(*) FROM dead_letters WHERE agent_id = ? AND expires_at > ?",
+        )
+        .bind(agent_id)
+        .bind(sortable_timestamp(Utc::now()))
+        .fetch_one(&self.pool)
+        .await?;
+        Ok(count as u64)
+    }
+
+    /// Remove a dead letter once it has been delivered
+    pub async fn delete_dead_letter(&self, id: &str) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM dead_letters WHERE id = ?")
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Remove queued messages for one agent, or for every agent
+    pub async fn purge_dead_letters(&self, agent_id: Option<&str>) -> Result<u64> {
+        let result = sqlx::query("DELETE FROM dead_letters WHERE ? IS NULL OR agent_id = ?")
+            .bind(agent_id)
+            .bind(agent_id)
+            .execute(&self.pool)
+            .await?;
+        Ok(result.rows_affected())
+    }
+
+    /// Remove dead letters whose TTL has passed
+    pub async fn purge_expired_dead_letters(&self) -> Result<u64> {
+        let result = sqlx::query("DELETE FROM dead_letters WHERE expires_at <= ?")
+            .bind(sortable_timestamp(Utc::now()))
+            .execute(&self.pool)
+            .await?;
+        Ok(result.rows_affected())
+    }
+
     // --- Pack operations ---
 
     /// Register or update a pack
@@ -428,103 +1178,1874 @@ impl Store {
         }
         Ok(packs)
     }
-}
+
+    /// Pin `pack_id` to the key `fingerprint` unless another key already
+    /// holds it. Returns whether `fingerprint` is the pack's key.
+    pub async fn pin_pack_key(&self, pack_id: &str, fingerprint: &str) -> Result<bool> {
+        sqlx::query(
+            "INSERT OR IGNORE INTO pack_keys (pack_id, key_fingerprint, pinned_at) VALUES (?, ?, ?)",
+        )
+        .bind(pack_id)
+        .bind(fingerprint)
+        .bind(Utc::now().to_rfc3339())
+        .execute(&self.pool)
+        .await?;
+        let pinned: String =
+            sqlx::query_scalar("SELECT key_fingerprint FROM pack_keys WHERE pack_id = ?")
+                .bind(pack_id)
+                .fetch_one(&self.pool)
+                .await?;
+        Ok(pinned == fingerprint)
+    }
+
+    // --- Pack secret operations ---
+
+    /// Insert or replace a pack secret. Returns true if it didn't exist before.
+    pub async fn upsert_pack_secret(&self, secret: &EncryptedSecret) -> Result<bool> {
+        let mut tx = self.pool.begin().await?;
+        let existed = sqlx::query("SELECT 1 FROM pack_secrets WHERE pack_id = ? AND key = ?")
+            .bind(&secret.pack_id)
+            .bind(&secret.key)
+            .fetch_optional(&mut *tx)
+            .await?
+            .is_some();
+        sqlx::query(
+            r#"
+            INSERT INTO pack_secrets (pack_id, key, nonce, ciphertext, updated_at)
+            VALUES (?, ?, ?, ?, ?)
+            ON CONFLICT(pack_id, key) DO UPDATE SET
+                nonce = excluded.nonce,
+                ciphertext = excluded.ciphertext,
+                updated_at = excluded.updated_at
+            "#,
+        )
+        .bind(&secret.pack_id)
+        .bind(&secret.key)
+        .bind(&secret.nonce)
+        .bind(&secret.ciphertext)
+        .bind(secret.updated_at.to_rfc3339())
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await?;
+        Ok(!existed)
+    }
+
+    /// A pack's secrets, ordered by key
+    pub async fn list_pack_secrets(&self, pack_id: &str) -> Result<Vec<EncryptedSecret>> {
+        let rows = sqlx::query(
+            "SELECT pack_id, key, nonce, ciphertext, updated_at FROM pack_secrets \
+             WHERE pack_id = ? ORDER BY key",
+        )
+        .bind(pack_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| EncryptedSecret {
+                pack_id: row.get("pack_id"),
+                key: row.get("key"),
+                nonce: row.get("nonce"),
+                ciphertext: row.get("ciphertext"),
+                updated_at: parse_timestamp(row.get("updated_at")),
+            })
+            .collect())
+    }
+
+    /// Delete a pack secret. Returns true if it existed.
+    pub async fn delete_pack_secret(&self, pack_id: &str, key: &str) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM pack_secrets WHERE pack_id = ? AND key = ?")
+            .bind(pack_id)
+            .bind(key)
+            .execute(&self.pool)
+            .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    // --- Push token operations ---
+
+    /// Register a device token for a principal. A token already registered
+    /// is moved to this principal and platform. Returns true if it is new.
+    pub async fn upsert_push_token(
+        &self,
+        principal_id: &str,
+        platform: &str,
+        token: &str,
+    ) -> Result<bool> {
+        let now = sortable_timestamp(Utc::now());
+        let mut tx = self.pool.begin().await?;
+        let existed = sqlx::query("SELECT 1 FROM push_tokens WHERE token = ?")
+            .bind(token)
+            .fetch_optional(&mut *tx)
+            .await?
+            .is_some();
+        sqlx::query(
+            r#"
+            INSERT INTO push_tokens (token, principal_id, platform, created_at, updated_at)
+            VALUES (?, ?, ?, ?, ?)
+            ON CONFLICT(token) DO UPDATE SET
+                principal_id = excluded.principal_id,
+                platform = excluded.platform,
+                updated_at = excluded.updated_at
+            "#,
+        )
+        .bind(token)
+        .bind(principal_id)
+        .bind(platform)
+        .bind(&now)
+        .bind(&now)
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await?;
+        Ok(!existed)
+    }
+
+    /// Remove one of a principal's device tokens. Returns true if it existed.
+    pub async fn delete_push_token(&self, principal_id: &str, token: &str) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM push_tokens WHERE principal_id = ? AND token = ?")
+            .bind(principal_id)
+            .bind(token)
+            .execute(&self.pool)
+            .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Device tokens for one principal, or for everyone, oldest first
+    pub async fn list_push_tokens(&self, principal_id: Option<&str>) -> Result<Vec<PushToken>> {
+        let rows = sqlx::query(
+            "SELECT token, principal_id, platform, created_at, updated_at FROM push_tokens \
+             WHERE ? IS NULL OR principal_id = ? ORDER BY created_at, token",
+        )
+        .bind(principal_id)
+        .bind(principal_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| PushToken {
+                token: row.get("token"),
+                principal_id: row.get("principal_id"),
+                platform: row.get("platform"),
+                created_at: parse_timestamp(row.get("created_at")),
+                updated_at: parse_timestamp(row.get("updated_at")),
+            })
+            .collect())
+    }
+
+    // --- Recent channel operations ---
+
+    /// Note that a message just arrived from a bridge channel. A name the
+    /// bridge didn't send this time keeps the one it sent before.
+    pub async fn record_channel(
+        &self,
+        frontend: &str,
+        channel_id: &str,
+        display_name: Option<&str>,
+    ) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO recent_channels (frontend, channel_id, display_name, last_message_at)
+            VALUES (?, ?, ?, ?)
+            ON CONFLICT(frontend, channel_id) DO UPDATE SET
+                display_name = COALESCE(excluded.display_name, recent_channels.display_name),
+                last_message_at = excluded.last_message_at
+            "#,
+        )
+        .bind(frontend)
+        .bind(channel_id)
+        .bind(display_name)
+        .bind(sortable_timestamp(Utc::now()))
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Up to `limit` channels on one frontend, or on all of them, most
+    /// recently active first
+    pub async fn list_recent_channels(
+        &self,
+        frontend: Option<&str>,
+        limit: i64,
+    ) -> Result<Vec<RecentChannel>> {
+        let rows = sqlx::query(
+            "SELECT frontend, channel_id, display_name, last_message_at FROM recent_channels \
+             WHERE ? IS NULL OR frontend = ? \
+             ORDER BY last_message_at DESC, frontend, channel_id LIMIT ?",
+        )
+        .bind(frontend)
+        .bind(frontend)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| RecentChannel {
+                frontend: row.get("frontend"),
+                channel_id: row.get("channel_id"),
+                display_name: row.get("display_name"),
+                last_message_at: parse_timestamp(row.get("last_message_at")),
+            })
+            .collect())
+    }
+
+    // --- Key grant operations ---
+
+    /// Switch a principal to the key `new_fingerprint`: it authenticates as
+    /// the principal from now on, and the `old_fingerprints` only until
+    /// `old_expire_at`. An old key already due to expire sooner keeps its
+    /// earlier expiry.
+    pub async fn rotate_key(
+        &self,
+        principal_id: &str,
+        old_fingerprints: &[String],
+        new_fingerprint: &str,
+        old_expire_at: DateTime<Utc>,
+    ) -> Result<()> {
+        let now = sortable_timestamp(Utc::now());
+        let mut tx = self.pool.begin().await?;
+        sqlx::query(
+            r#"
+            INSERT INTO key_grants (fingerprint, principal_id, expires_at, created_at)
+            VALUES (?, ?, NULL, ?)
+            ON CONFLICT(fingerprint) DO UPDATE SET
+                principal_id = excluded.principal_id,
+                expires_at = NULL
+            "#,
+        )
+        .bind(new_fingerprint)
+        .bind(principal_id)
+        .bind(&now)
+        .execute(&mut *tx)
+        .await?;
+        for fingerprint in old_fingerprints {
+            sqlx::query(
+                r#"
+                INSERT INTO key_grants (fingerprint, principal_id, expires_at, created_at)
+                VALUES (?, ?, ?, ?)
+                ON CONFLICT(fingerprint) DO UPDATE SET
+                    expires_at = CASE
+                        WHEN key_grants.expires_at IS NOT NULL
+                            AND key_grants.expires_at < excluded.expires_at
+                        THEN key_grants.expires_at
+                        ELSE excluded.expires_at
+                    END
+                "#,
+            )
+            .bind(fingerprint)
+            .bind(principal_id)
+            .bind(sortable_timestamp(old_expire_at))
+            .bind(&now)
+            .execute(&mut *tx)
+            .await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Every key grant, oldest first, expired ones included: an expired key
+    /// must keep failing rather than fall back to the roles file
+    pub async fn list_key_grants(&self) -> Result<Vec<KeyGrant>> {
+        let rows = sqlx::query(
+            "SELECT fingerprint, principal_id, expires_at, created_at FROM key_grants \
+             ORDER BY created_at, fingerprint",
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| KeyGrant {
+                fingerprint: row.get("fingerprint"),
+                principal_id: row.get("principal_id"),
+                expires_at: row
+                    .get::<Option<String>, _>("expires_at")
+                    .map(|t| parse_timestamp(&t)),
+                created_at: parse_timestamp(row.get("created_at")),
+            })
+            .collect())
+    }
+
+    // --- Issued token operations ---
+
+    /// Note a token that was issued, so it can be listed and revoked
+    pub async fn record_token(&self, token: &TokenRecord) -> Result<()> {
+        sqlx::query(
+            "INSERT INTO issued_tokens (id, principal_id, issued_at, expires_at) \
+             VALUES (?, ?, ?, ?)",
+        )
+        .bind(&token.id)
+        .bind(&token.principal_id)
+        .bind(sortable_timestamp(token.issued_at))
+        .bind(sortable_timestamp(token.expires_at))
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Tokens neither expired nor revoked at `now`, newest first, of
+    /// `principal_id` or of everyone
+    pub async fn list_tokens(
+        &self,
+        principal_id: Option<&str>,
+        now: DateTime<Utc>,
+    ) -> Result<Vec<TokenRecord>> {
+        let rows = sqlx::query(
+            "SELECT id, principal_id, issued_at, expires_at FROM issued_tokens \
+             WHERE revoked_at IS NULL AND expires_at > ? AND (? IS NULL OR principal_id = ?) \
+             ORDER BY issued_at DESC, id",
+        )
+        .bind(sortable_timestamp(now))
+        .bind(principal_id)
+        .bind(principal_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| TokenRecord {
+                id: row.get("id"),
+                principal_id: row.get("principal_id"),
+                issued_at: parse_timestamp(row.get("issued_at")),
+                expires_at: parse_timestamp(row.get("expires_at")),
+            })
+            .collect())
+    }
+
+    /// Revoke the token `id`. Returns false if there's no such token, or
+    /// it's already revoked or expired.
+    pub async fn revoke_token(&self, id: &str, now: DateTime<Utc>) -> Result<bool> {
+        let now = sortable_timestamp(now);
+        let result = sqlx::query(
+            "UPDATE issued_tokens SET revoked_at = ? \
+             WHERE id = ? AND revoked_at IS NULL AND expires_at > ?",
+        )
+        .bind(&now)
+        .bind(id)
+        .bind(&now)
+        .execute(&self.pool)
+        .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// IDs of revoked tokens that haven't expired at `now`; expired ones are
+    /// refused anyway
+    pub async fn list_revoked_tokens(&self, now: DateTime<Utc>) -> Result<Vec<String>> {
+        let ids = sqlx::query_scalar(
+            "SELECT id FROM issued_tokens WHERE revoked_at IS NOT NULL AND expires_at > ? \
+             ORDER BY id",
+        )
+        .bind(sortable_timestamp(now))
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(ids)
+    }
+
+    // --- One-time code secrets ---
+
+    /// Store `principal_id`'s encrypted TOTP secret, replacing any it had
+    pub async fn set_otp_secret(
+        &self,
+        principal_id: &str,
+        nonce: &[u8],
+        ciphertext: &[u8],
+    ) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO otp_secrets (principal_id, secret, nonce, created_at)
+            VALUES (?, ?, ?, ?)
+            ON CONFLICT(principal_id) DO UPDATE SET
+                secret = excluded.secret,
+                nonce = excluded.nonce,
+                created_at = excluded.created_at
+            "#,
+        )
+        .bind(principal_id)
+        .bind(ciphertext)
+        .bind(nonce)
+        .bind(sortable_timestamp(Utc::now()))
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Every enrolled TOTP secret
+    pub async fn list_otp_secrets(&self) -> Result<Vec<OtpSecret>> {
+        let rows = sqlx::query(
+            "SELECT principal_id, secret, nonce, created_at FROM otp_secrets ORDER BY principal_id",
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| OtpSecret {
+                principal_id: row.get("principal_id"),
+                nonce: row.get("nonce"),
+                ciphertext: row.get("secret"),
+                created_at: parse_timestamp(row.get("created_at")),
+            })
+            .collect())
+    }
+
+    // --- Tool approval operations ---
+
+    /// Record that an agent is waiting for approval to run a tool. A repeated
+    /// request for the same tool keeps the original record.
+    pub async fn record_approval_request(&self, approval: &ToolApproval) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT OR IGNORE INTO tool_approvals
+                (agent_id, tool_id, request_id, tool_name, input_json, confirm_message, requested_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(&approval.agent_id)
+        .bind(&approval.tool_id)
+        .bind(&approval.request_id)
+        .bind(&approval.tool_name)
+        .bind(&approval.input_json)
+        .bind(&approval.confirm_message)
+        .bind(sortable_timestamp(approval.requested_at))
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Resolve a pending approval. Returns false if it isn't pending, so the
+    /// first resolution wins.
+    pub async fn resolve_approval(
+        &self,
+        agent_id: &str,
+        tool_id: &str,
+        decision: &str,
+        resolved_by: Option<&str>,
+    ) -> Result<bool> {
+        let result = sqlx::query(
+            "UPDATE tool_approvals SET resolved_at = ?, decision = ?, resolved_by = ? \
+             WHERE agent_id = ? AND tool_id = ? AND resolved_at IS NULL",
+        )
+        .bind(sortable_timestamp(Utc::now()))
+        .bind(decision)
+        .bind(resolved_by)
+        .bind(agent_id)
+        .bind(tool_id)
+        .execute(&self.pool)
+        .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Mark every approval still pending for an agent as expired; nothing
+    /// can answer them once the agent is gone. Returns how many there were.
+    pub async fn expire_pending_approvals(&self, agent_id: &str) -> Result<u64> {
+        let result = sqlx::query(
+            "UPDATE tool_approvals SET resolved_at = ?, decision = 'expired' \
+             WHERE agent_id = ? AND resolved_at IS NULL",
+        )
+        .bind(sortable_timestamp(Utc::now()))
+        .bind(agent_id)
+        .execute(&self.pool)
+        .await?;
+        Ok(result.rows_affected())
+    }
+
+    /// Approvals an agent is still waiting on, oldest first
+    pub async fn list_pending_approvals(&self, agent_id: &str) -> Result<Vec<ToolApproval>> {
+        let rows = sqlx::query(
+            "SELECT agent_id, tool_id, request_id, tool_name, input_json, confirm_message, \
+             requested_at, resolved_at, decision, resolved_by FROM tool_approvals \
+             WHERE agent_id = ? AND resolved_at IS NULL \
+             ORDER BY requested_at ASC, rowid ASC",
+        )
+        .bind(agent_id)
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(rows.iter().map(approval_from_row).collect())
+    }
+
+    /// Record a client's `decision` on an approval once it reached the
+    /// agent. The agent may already have reported the tool running (or
+    /// refused), resolving it the same way without a decider; the client is
+    /// then recorded as the one who decided. Returns false if the approval
+    /// was resolved otherwise, e.g. it timed out first.
+    pub async fn record_approval_decision(
+        &self,
+        agent_id: &str,
+        tool_id: &str,
+        decision: &str,
+        resolved_by: &str,
+    ) -> Result<bool> {
+        // What the agent reports for a tool a client let run or refused
+        let reported = if decision == "denied" {
+            "denied"
+        } else {
+            "approved"
+        };
+        let result = sqlx::query(
+            "UPDATE tool_approvals SET resolved_at = COALESCE(resolved_at, ?), decision = ?, \
+             resolved_by = ? WHERE agent_id = ? AND tool_id = ? AND resolved_by IS NULL \
+             AND (resolved_at IS NULL OR decision = ?)",
+        )
+        .bind(sortable_timestamp(Utc::now()))
+        .bind(decision)
+        .bind(resolved_by)
+        .bind(agent_id)
+        .bind(tool_id)
+        .bind(reported)
+        .execute(&self.pool)
+        .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// An agent's resolved approvals, most recently resolved first
+    pub async fn approval_history(&self, agent_id: &str, limit: i64) -> Result<Vec<ToolApproval>> {
+        let rows = sqlx::query(
+            "SELECT agent_id, tool_id, request_id, tool_name, input_json, confirm_message, \
+             requested_at, resolved_at, decision, resolved_by FROM tool_approvals \
+             WHERE agent_id = ? AND resolved_at IS NOT NULL \
+             ORDER BY resolved_at DESC, rowid DESC LIMIT ?",
+        )
+        .bind(agent_id)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(rows.iter().map(approval_from_row).collect())
+    }
+
+    // --- Tool calls and token usage ---
+
+    /// Record a tool call an agent started. A repeated report of the same
+    /// call keeps the original record.
+    pub async fn record_tool_call(&self, call: &ToolCall) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT OR IGNORE INTO tool_calls (agent_id, tool_id, request_id, tool_name,
+                                              input_json, output, is_error, created_at, completed_at,
+                                              thread_id)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(&call.agent_id)
+        .bind(&call.tool_id)
+        .bind(&call.request_id)
+        .bind(&call.tool_name)
+        .bind(&call.input_json)
+        .bind(&call.output)
+        .bind(call.is_error)
+        .bind(sortable_timestamp(call.created_at))
+        .bind(call.completed_at.map(sortable_timestamp))
+        .bind(&call.thread_id)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Attach a tool's result to its call. Returns false if the call was
+    /// never recorded.
+    pub async fn record_tool_result(
+        &self,
+        agent_id: &str,
+        tool_id: &str,
+        output: &str,
+        is_error: bool,
+    ) -> Result<bool> {
+        let result = sqlx::query(
+            "UPDATE tool_calls SET output = ?, is_error = ?, completed_at = ? \
+             WHERE agent_id = ? AND tool_id = ?",
+        )
+        .bind(output)
+        .bind(is_error)
+        .bind(sortable_timestamp(Utc::now()))
+        .bind(agent_id)
+        .bind(tool_id)
+        .execute(&self.pool)
+        .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Record a token usage update from an agent
+    pub async fn record_usage(&self, usage: &TokenUsage) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO token_usage (agent_id, request_id, input_tokens, output_tokens,
+                                     cache_read_tokens, cache_write_tokens, thinking_tokens,
+                                     created_at, thread_id)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(&usage.agent_id)
+        .bind(&usage.request_id)
+        .bind(usage.input_tokens)
+        .bind(usage.output_tokens)
+        .bind(usage.cache_read_tokens)
+        .bind(usage.cache_write_tokens)
+        .bind(usage.thinking_tokens)
+        .bind(sortable_timestamp(usage.created_at))
+        .bind(&usage.thread_id)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Remove tool calls and token usage recorded, and tool approvals
+    /// resolved, before `before`. Returns how many rows went.
+    pub async fn prune_activity(&self, before: DateTime<Utc>) -> Result<u64> {
+        let before = sortable_timestamp(before);
+        let calls = sqlx::query("DELETE FROM tool_calls WHERE created_at < ?")
+            .bind(&before)
+            .execute(&self.pool)
+            .await?;
+        let usage = sqlx::query("DELETE FROM token_usage WHERE created_at < ?")
+            .bind(&before)
+            .execute(&self.pool)
+            .await?;
+        let approvals = sqlx::query(
+            "DELETE FROM tool_approvals WHERE resolved_at IS NOT NULL AND resolved_at < ?",
+        )
+        .bind(&before)
+        .execute(&self.pool)
+        .await?;
+        Ok(calls.rows_affected() + usage.rows_affected() + approvals.rows_affected())
+    }
+
+    // --- Export ---
+    //
+    // Each query returns one page, after the last ID or rowid of the page
+    // before, so an export never holds more than a page in memory.
+
+    /// Up to `limit` conversations `filter` covers, by ID, after the one
+    /// with ID `after`. A conversation is covered if it was active at some
+    /// point in the filter's time range.
+    pub async fn export_threads(
+        &self,
+        filter: &ExportFilter,
+        after: Option<&str>,
+        limit: i64,
+    ) -> Result<Vec<ThreadSummary>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT c.id, c.agent_id, c.created_at, c.updated_at, c.title,
+                   (SELECT json_group_array(author) FROM (
+                        SELECT author FROM messages WHERE conversation_id = c.id
+                        GROUP BY author ORDER BY MIN(rowid))) AS participants,
+                   (SELECT direction FROM messages WHERE conversation_id = c.id
+                    ORDER BY rowid DESC LIMIT 1) AS last_direction
+            FROM conversations c
+            WHERE (?1 IS NULL OR c.agent_id = ?1)
+              AND (?2 IS NULL OR julianday(c.updated_at) >= julianday(?2))
+              AND (?3 IS NULL OR julianday(c.created_at) < julianday(?3))
+              AND (?4 IS NULL OR c.id > ?4)
+            ORDER BY c.id
+            LIMIT ?5
+            "#,
+        )
+        .bind(&filter.agent_id)
+        .bind(filter.since.map(sortable_timestamp))
+        .bind(filter.until.map(sortable_timestamp))
+        .bind(after)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.iter()
+            .map(|row| {
+                let participants: String = row.get("participants");
+                Ok(ThreadSummary {
+                    conversation: Conversation {
+                        id: row.get("id"),
+                        agent_id: row.get("agent_id"),
+                        created_at: parse_timestamp(row.get("created_at")),
+                        updated_at: parse_timestamp(row.get("updated_at")),
+                        title: row.get("title"),
+                    },
+                    participants: serde_json::from_str(&participants)
+                        .context("reading thread participants")?,
+                    last_direction: row.get("last_direction"),
+                })
+            })
+            .collect()
+    }
+
+    /// Up to `limit` of a conversation's messages in `filter`'s time range,
+    /// oldest first, with their rowids, after rowid `after`
+    pub async fn export_messages(
+        &self,
+        conversation_id: &str,
+        filter: &ExportFilter,
+        after: i64,
+        limit: i64,
+    ) -> Result<Vec<(i64, Message)>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT rowid, id, conversation_id, direction, author, content, message_type,
+                   reply_to_message_id, created_at
+            FROM messages
+            WHERE conversation_id = ?1 AND rowid > ?2
+              AND (?3 IS NULL OR julianday(created_at) >= julianday(?3))
+              AND (?4 IS NULL OR julianday(created_at) < julianday(?4))
+            ORDER BY rowid
+            LIMIT ?5
+            "#,
+        )
+        .bind(conversation_id)
+        .bind(after)
+        .bind(filter.since.map(sortable_timestamp))
+        .bind(filter.until.map(sortable_timestamp))
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| {
+                let message = Message {
+                    id: row.get("id"),
+                    conversation_id: row.get("conversation_id"),
+                    direction: row.get("direction"),
+                    author: row.get("author"),
+                    content: row.get("content"),
+                    message_type: row.get("message_type"),
+                    reply_to_message_id: row.get("reply_to_message_id"),
+                    created_at: parse_timestamp(&row.get::<String, _>("created_at")),
+                };
+                (row.get("rowid"), message)
+            })
+            .collect())
+    }
+
+    /// Up to `limit` of a conversation's tool calls in `filter`'s time range,
+    /// oldest first, with their rowids, after rowid `after`
+    pub async fn export_tool_calls(
+        &self,
+        thread_id: &str,
+        filter: &ExportFilter,
+        after: i64,
+        limit: i64,
+    ) -> Result<Vec<(i64, ToolCall)>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT rowid, agent_id, COALESCE(thread_id, agent_id) AS thread_id, tool_id,
+                   request_id, tool_name, input_json, output, is_error, created_at, completed_at
+            FROM tool_calls
+            WHERE COALESCE(thread_id, agent_id) = ?1 AND rowid > ?2
+              AND (?3 IS NULL OR created_at >= ?3)
+              AND (?4 IS NULL OR created_at < ?4)
+            ORDER BY rowid
+            LIMIT ?5
+            "#,
+        )
+        .bind(thread_id)
+        .bind(after)
+        .bind(filter.since.map(sortable_timestamp))
+        .bind(filter.until.map(sortable_timestamp))
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| {
+                let call = ToolCall {
+                    agent_id: row.get("agent_id"),
+                    thread_id: row.get("thread_id"),
+                    tool_id: row.get("tool_id"),
+                    request_id: row.get("request_id"),
+                    tool_name: row.get("tool_name"),
+                    input_json: row.get("input_json"),
+                    output: row.get("output"),
+                    is_error: row.get("is_error"),
+                    created_at: parse_timestamp(row.get("created_at")),
+                    completed_at: row
+                        .get::<Option<String>, _>("completed_at")
+                        .map(|t| parse_timestamp(&t)),
+                };
+                (row.get("rowid"), call)
+            })
+            .collect())
+    }
+
+    /// Up to `limit` of a conversation's usage updates in `filter`'s time range,
+    /// oldest first, with their rowids, after rowid `after`
+    pub async fn export_usage(
+        &self,
+        thread_id: &str,
+        filter: &ExportFilter,
+        after: i64,
+        limit: i64,
+    ) -> Result<Vec<(i64, TokenUsage)>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT rowid, agent_id, COALESCE(thread_id, agent_id) AS thread_id, request_id,
+                   input_tokens, output_tokens, cache_read_tokens, cache_write_tokens,
+                   thinking_tokens, created_at
+            FROM token_usage
+            WHERE COALESCE(thread_id, agent_id) = ?1 AND rowid > ?2
+              AND (?3 IS NULL OR created_at >= ?3)
+              AND (?4 IS NULL OR created_at < ?4)
+            ORDER BY rowid
+            LIMIT ?5
+            "#,
+        )
+        .bind(thread_id)
+        .bind(after)
+        .bind(filter.since.map(sortable_timestamp))
+        .bind(filter.until.map(sortable_timestamp))
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| {
+                let usage = TokenUsage {
+                    agent_id: row.get("agent_id"),
+                    thread_id: row.get("thread_id"),
+                    request_id: row.get("request_id"),
+                    input_tokens: row.get("input_tokens"),
+                    output_tokens: row.get("output_tokens"),
+                    cache_read_tokens: row.get("cache_read_tokens"),
+                    cache_write_tokens: row.get("cache_write_tokens"),
+                    thinking_tokens: row.get("thinking_tokens"),
+                    created_at: parse_timestamp(row.get("created_at")),
+                };
+                (row.get("rowid"), usage)
+            })
+            .collect())
+    }
+}
+
+fn approval_from_row(row: &sqlx::sqlite::SqliteRow) -> ToolApproval {
+    ToolApproval {
+        agent_id: row.get("agent_id"),
+        tool_id: row.get("tool_id"),
+        request_id: row.get("request_id"),
+        tool_name: row.get("tool_name"),
+        input_json: row.get("input_json"),
+        confirm_message: row.get("confirm_message"),
+        requested_at: parse_timestamp(&row.get::<String, _>("requested_at")),
+        resolved_at: row
+            .get::<Option<String>, _>("resolved_at")
+            .map(|t| parse_timestamp(&t)),
+        decision: row.get("decision"),
+        resolved_by: row.get("resolved_by"),
+    }
+}
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use tempfile::TempDir;
-    use uuid::Uuid;
 
-    async fn test_store() -> (Store, TempDir) {
+    async fn test_store() -> (Store, TempDir) {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("test.db");
+        let store = Store::open(&path).await.unwrap();
+        (store, dir)
+    }
+
+    #[tokio::test]
+    async fn test_agent_crud() {
+        let (store, _dir): (Store, TempDir) = test_store().await;
+
+        // Create agent
+        let agent = Agent {
+            id: "test-agent".to_string(),
+            name: "Test Agent".to_string(),
+            backend: "mux".to_string(),
+            working_dir: "/tmp".to_string(),
+            connected: true,
+            connected_at: Some(Utc::now()),
+            last_seen: Some(Utc::now()),
+        };
+        store.upsert_agent(&agent).await.unwrap();
+
+        // List agents
+        let agents = store.list_agents().await.unwrap();
+        assert_eq!(agents.len(), 1);
+        assert_eq!(agents[0].id, "test-agent");
+        assert!(agents[0].connected);
+
+        // Get agent
+        let fetched = store.get_agent("test-agent").await.unwrap().unwrap();
+        assert_eq!(fetched.name, "Test Agent");
+
+        // Disconnect
+        store
+            .set_agent_connected("test-agent", false)
+            .await
+            .unwrap();
+        let fetched = store.get_agent("test-agent").await.unwrap().unwrap();
+        assert!(!fetched.connected);
+    }
+
+    #[tokio::test]
+    async fn test_conversation_and_messages() {
+        let (store, _dir): (Store, TempDir) = test_store().await;
+
+        // Create agent first
+        let agent = Agent {
+            id: "agent-1".to_string(),
+            name: "Agent 1".to_string(),
+            backend: "mux".to_string(),
+            working_dir: "/tmp".to_string(),
+            connected: true,
+            connected_at: Some(Utc::now()),
+            last_seen: Some(Utc::now()),
+        };
+        store.upsert_agent(&agent).await.unwrap();
+
+        // Get or create conversation
+        let conv = store.get_or_create_conversation("agent-1").await.unwrap();
+        assert_eq!(conv.agent_id, "agent-1");
+
+        // Save messages
+        let msg1 = Message {
+            id: Uuid::new_v4().to_string(),
+            conversation_id: conv.id.clone(),
+            direction: "inbound".to_string(),
+            author: "user".to_string(),
+            content: "Hello".to_string(),
+            message_type: "message".to_string(),
+            reply_to_message_id: None,
+            created_at: Utc::now(),
+        };
+        store.save_message(&msg1).await.unwrap();
+
+        let msg2 = Message {
+            id: Uuid::new_v4().to_string(),
+            conversation_id: conv.id.clone(),
+            direction: "outbound".to_string(),
+            author: "agent".to_string(),
+            content: "Hi there!".to_string(),
+            message_type: "message".to_string(),
+            reply_to_message_id: None,
+            created_at: Utc::now(),
+        };
+        store.save_message(&msg2).await.unwrap();
+
+        // Get messages
+        let messages = store.get_messages(&conv.id, 100).await.unwrap();
+        assert_eq!(messages.len(), 2);
+        assert_eq!(messages[0].content, "Hello");
+        assert_eq!(messages[1].content, "Hi there!");
+    }
+
+    #[tokio::test]
+    async fn test_reply_to_round_trips() {
+        let (store, _dir) = test_store().await;
+        let conv = store.get_or_create_conversation("agent-1").await.unwrap();
+
+        for (id, reply_to) in [("msg-1", None), ("msg-2", Some("msg-1"))] {
+            store
+                .save_message(&Message {
+                    id: id.to_string(),
+                    conversation_id: conv.id.clone(),
+                    direction: "inbound".to_string(),
+                    author: "user".to_string(),
+                    content: format!("{} content", id),
+                    message_type: "message".to_string(),
+                    reply_to_message_id: reply_to.map(str::to_string),
+                    created_at: Utc::now(),
+                })
+                .await
+                .unwrap();
+        }
+
+        let messages = store.get_messages(&conv.id, 100).await.unwrap();
+        assert_eq!(messages[0].reply_to_message_id, None);
+        assert_eq!(messages[1].reply_to_message_id.as_deref(), Some("msg-1"));
+
+        // Saving the same ID again is a no-op
+        let again = Message {
+            content: "retried".to_string(),
+            ..messages[1].clone()
+        };
+        assert!(!store.save_message_once(&again).await.unwrap());
+        let messages = store.get_messages(&conv.id, 100).await.unwrap();
+        assert_eq!(messages.len(), 2);
+        assert_eq!(messages[1].content, "msg-2 content");
+    }
+
+    #[tokio::test]
+    async fn test_tool_calls_and_usage_are_exported_by_thread() {
+        let (store, _dir) = test_store().await;
+        for thread_id in ["agent-1", "fork-1"] {
+            store
+                .record_tool_call(&ToolCall {
+                    agent_id: "agent-1".to_string(),
+                    thread_id: thread_id.to_string(),
+                    tool_id: format!("tool-{}", thread_id),
+                    request_id: "req-1".to_string(),
+                    tool_name: "Read".to_string(),
+                    input_json: "{}".to_string(),
+                    output: None,
+                    is_error: false,
+                    created_at: Utc::now(),
+                    completed_at: None,
+                })
+                .await
+                .unwrap();
+            store
+                .record_usage(&TokenUsage {
+                    agent_id: "agent-1".to_string(),
+                    thread_id: thread_id.to_string(),
+                    input_tokens: 10,
+                    created_at: Utc::now(),
+                    ..Default::default()
+                })
+                .await
+                .unwrap();
+        }
+        // Rows from before forks belong to the agent's own thread
+        sqlx::query("UPDATE tool_calls SET thread_id = NULL WHERE thread_id = 'agent-1'")
+            .execute(&store.pool)
+            .await
+            .unwrap();
+
+        let filter = ExportFilter::default();
+        for thread_id in ["agent-1", "fork-1"] {
+            let calls = store
+                .export_tool_calls(thread_id, &filter, 0, 10)
+                .await
+                .unwrap();
+            assert_eq!(calls.len(), 1, "{}", thread_id);
+            assert_eq!(calls[0].1.thread_id, thread_id);
+            assert_eq!(calls[0].1.tool_id, format!("tool-{}", thread_id));
+            let usage = store.export_usage(thread_id, &filter, 0, 10).await.unwrap();
+            assert_eq!(usage.len(), 1, "{}", thread_id);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_prune_activity_drops_old_rows() {
+        let (store, _dir) = test_store().await;
+        let now = Utc::now();
+        for (tool_id, age) in [("old", 40), ("new", 1)] {
+            let created_at = now - chrono::Duration::days(age);
+            store
+                .record_tool_call(&ToolCall {
+                    agent_id: "agent-1".to_string(),
+                    thread_id: "agent-1".to_string(),
+                    tool_id: tool_id.to_string(),
+                    request_id: "req-1".to_string(),
+                    tool_name: "Read".to_string(),
+                    input_json: "{}".to_string(),
+                    output: None,
+                    is_error: false,
+                    created_at,
+                    completed_at: None,
+                })
+                .await
+                .unwrap();
+            store
+                .record_usage(&TokenUsage {
+                    agent_id: "agent-1".to_string(),
+                    thread_id: "agent-1".to_string(),
+                    created_at,
+                    ..Default::default()
+                })
+                .await
+                .unwrap();
+        }
+
+        let pruned = store
+            .prune_activity(now - chrono::Duration::days(30))
+            .await
+            .unwrap();
+        assert_eq!(pruned, 2);
+        let filter = ExportFilter::default();
+        let calls = store
+            .export_tool_calls("agent-1", &filter, 0, 10)
+            .await
+            .unwrap();
+        assert_eq!(calls.len(), 1);
+        assert_eq!(calls[0].1.tool_id, "new");
+        let usage = store.export_usage("agent-1", &filter, 0, 10).await.unwrap();
+        assert_eq!(usage.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_fork_conversation_copies_up_to_message() {
+        let (store, _dir) = test_store().await;
+        let conv = store.get_or_create_conversation("agent-1").await.unwrap();
+
+        for (id, reply_to) in [("msg-1", None), ("msg-2", Some("msg-1")), ("msg-3", None)] {
+            store
+                .save_message(&Message {
+                    id: id.to_string(),
+                    conversation_id: conv.id.clone(),
+                    direction: "inbound".to_string(),
+                    author: "user".to_string(),
+                    content: format!("{} content", id),
+                    message_type: "message".to_string(),
+                    reply_to_message_id: reply_to.map(str::to_string),
+                    created_at: Utc::now(),
+                })
+                .await
+                .unwrap();
+        }
+
+        let (fork, copied) = store
+            .fork_conversation(&conv.id, "msg-2")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(copied.len(), 2);
+        assert_eq!(copied[1].id, "msg-2");
+        assert_eq!(fork.agent_id, "agent-1");
+        assert_ne!(fork.id, conv.id);
+
+        let messages = store.get_messages(&fork.id, 100).await.unwrap();
+        let contents: Vec<_> = messages.iter().map(|m| m.content.as_str()).collect();
+        assert_eq!(contents, ["msg-1 content", "msg-2 content"]);
+        assert_eq!(
+            messages[1].reply_to_message_id.as_deref(),
+            Some(messages[0].id.as_str())
+        );
+        assert_eq!(store.get_messages(&conv.id, 100).await.unwrap().len(), 3);
+
+        assert!(store
+            .fork_conversation(&conv.id, "msg-9")
+            .await
+            .unwrap()
+            .is_none());
+        assert!(store
+            .fork_conversation("missing", "msg-1")
+            .await
+            .unwrap()
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn test_thread_titles_are_listed_and_kept_by_forks() {
+        let (store, _dir) = test_store().await;
+        let conv = store.get_or_create_conversation("agent-1").await.unwrap();
+        assert_eq!(conv.title, None);
+        store
+            .save_message(&Message {
+                id: "msg-1".to_string(),
+                conversation_id: conv.id.clone(),
+                direction: "inbound".to_string(),
+                author: "user".to_string(),
+                content: "why did the deploy fail?".to_string(),
+                message_type: "message".to_string(),
+                reply_to_message_id: None,
+                created_at: Utc::now(),
+            })
+            .await
+            .unwrap();
+
+        // Only the agent the conversation belongs to can title it
+        assert!(!store
+            .set_conversation_title("agent-2", &conv.id, Some("Mine"))
+            .await
+            .unwrap());
+        assert!(store
+            .set_conversation_title("agent-1", &conv.id, Some("Deploy failures"))
+            .await
+            .unwrap());
+
+        let (fork, _) = store
+            .fork_conversation(&conv.id, "msg-1")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(fork.title.as_deref(), Some("Deploy failures"));
+        store.touch_conversation(&fork.id).await.unwrap();
+
+        let listed = store.list_conversations("agent-1").await.unwrap();
+        let ids: Vec<_> = listed.iter().map(|c| c.id.as_str()).collect();
+        assert_eq!(ids, [fork.id.as_str(), conv.id.as_str()]);
+        assert!(listed
+            .iter()
+            .all(|c| c.title.as_deref() == Some("Deploy failures")));
+        assert!(store
+            .list_conversations("agent-2")
+            .await
+            .unwrap()
+            .is_empty());
+
+        store
+            .set_conversation_title("agent-1", &conv.id, None)
+            .await
+            .unwrap();
+        let conv = store.get_conversation(&conv.id).await.unwrap().unwrap();
+        assert_eq!(conv.title, None);
+    }
+
+    #[tokio::test]
+    async fn test_open_adds_reply_column_to_old_database() {
         let dir = TempDir::new().unwrap();
-        let path = dir.path().join("test.db");
+        let path = dir.path().join("old.db");
+        {
+            let options = SqliteConnectOptions::new()
+                .filename(&path)
+                .create_if_missing(true);
+            let pool = SqlitePoolOptions::new()
+                .connect_with(options)
+                .await
+                .unwrap();
+            sqlx::query(
+                r#"
+                CREATE TABLE messages (
+                    id TEXT PRIMARY KEY,
+                    conversation_id TEXT NOT NULL,
+                    direction TEXT NOT NULL,
+                    author TEXT NOT NULL,
+                    content TEXT NOT NULL,
+                    message_type TEXT NOT NULL DEFAULT 'message',
+                    created_at TEXT NOT NULL
+                );
+                INSERT INTO messages VALUES ('old-1', 'agent-1', 'inbound', 'user', 'hi', 'message', '2026-01-01T00:00:00Z');
+                "#,
+            )
+            .execute(&pool)
+            .await
+            .unwrap();
+            pool.close().await;
+        }
+
         let store = Store::open(&path).await.unwrap();
-        (store, dir)
+        let messages = store.get_messages("agent-1", 10).await.unwrap();
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0].reply_to_message_id, None);
     }
 
     #[tokio::test]
-    async fn test_agent_crud() {
+    async fn test_get_messages_after_pages_through_history() {
+        let (store, _dir) = test_store().await;
+
+        let start = Utc::now();
+        for i in 0..5 {
+            store
+                .save_message(&Message {
+                    id: format!("msg-{}", i),
+                    conversation_id: "agent-1".to_string(),
+                    direction: "inbound".to_string(),
+                    author: "user".to_string(),
+                    content: format!("message {}", i),
+                    message_type: "message".to_string(),
+                    reply_to_message_id: None,
+                    created_at: start + chrono::Duration::seconds(i),
+                })
+                .await
+                .unwrap();
+        }
+
+        let mut pages = Vec::new();
+        let mut after: Option<String> = None;
+        loop {
+            let page = store
+                .get_messages_after("agent-1", after.as_deref(), 2)
+                .await
+                .unwrap();
+            let Some(last) = page.last() else { break };
+            after = Some(last.id.clone());
+            pages.push(page.iter().map(|m| m.id.clone()).collect::<Vec<_>>());
+        }
+        assert_eq!(
+            pages,
+            vec![
+                vec!["msg-0", "msg-1"],
+                vec!["msg-2", "msg-3"],
+                vec!["msg-4"]
+            ]
+        );
+
+        assert!(store
+            .get_messages_after("agent-1", Some("missing"), 10)
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_recent_agent_messages_newest_first() {
+        let (store, _dir) = test_store().await;
+        store.get_or_create_conversation("agent-1").await.unwrap();
+        store.get_or_create_conversation("agent-2").await.unwrap();
+
+        let start = Utc::now();
+        for (i, agent_id) in ["agent-1", "agent-2", "agent-1", "agent-1"]
+            .into_iter()
+            .enumerate()
+        {
+            store
+                .save_message(&Message {
+                    id: format!("msg-{}", i),
+                    conversation_id: agent_id.to_string(),
+                    direction: "inbound".to_string(),
+                    author: "user".to_string(),
+                    content: format!("message {}", i),
+                    message_type: "message".to_string(),
+                    reply_to_message_id: None,
+                    created_at: start + chrono::Duration::seconds(i as i64),
+                })
+                .await
+                .unwrap();
+        }
+
+        let ids: Vec<_> = store
+            .recent_agent_messages("agent-1", 2)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|m| m.id)
+            .collect();
+        assert_eq!(ids, vec!["msg-3", "msg-2"]);
+        assert!(store
+            .recent_agent_messages("unknown", 10)
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    fn dead_letter(id: &str, agent_id: &str, ttl: chrono::Duration) -> DeadLetter {
+        let now = Utc::now();
+        DeadLetter {
+            id: id.to_string(),
+            agent_id: agent_id.to_string(),
+            thread_id: agent_id.to_string(),
+            sender: "U123".to_string(),
+            content: format!("message {}", id),
+            sender_display: Some("Alice".to_string()),
+            sender_platform_id: Some("U123".to_string()),
+            sender_platform: Some("slack".to_string()),
+            reply_to_message_id: None,
+            model: None,
+            max_tokens: None,
+            created_at: now,
+            expires_at: now + ttl,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_dead_letter_queue_round_trip() {
         let (store, _dir): (Store, TempDir) = test_store().await;
+        let ttl = chrono::Duration::hours(1);
 
-        // Create agent
-        let agent = Agent {
-            id: "test-agent".to_string(),
-            name: "Test Agent".to_string(),
-            backend: "mux".to_string(),
-            working_dir: "/tmp".to_string(),
-            connected: true,
-            connected_at: Some(Utc::now()),
-            last_seen: Some(Utc::now()),
+        store
+            .enqueue_dead_letter(&dead_letter("m1", "agent-1", ttl), 10)
+            .await
+            .unwrap();
+        let with_overrides = DeadLetter {
+            model: Some("claude-haiku-4-5".to_string()),
+            max_tokens: Some(1024),
+            ..dead_letter("m2", "agent-1", ttl)
         };
-        store.upsert_agent(&agent).await.unwrap();
+        store
+            .enqueue_dead_letter(&with_overrides, 10)
+            .await
+            .unwrap();
+        store
+            .enqueue_dead_letter(&dead_letter("m3", "agent-2", ttl), 10)
+            .await
+            .unwrap();
 
-        // List agents
-        let agents = store.list_agents().await.unwrap();
-        assert_eq!(agents.len(), 1);
-        assert_eq!(agents[0].id, "test-agent");
-        assert!(agents[0].connected);
+        let queued = store.list_dead_letters(Some("agent-1")).await.unwrap();
+        let ids: Vec<_> = queued.iter().map(|l| l.id.as_str()).collect();
+        assert_eq!(ids, vec!["m1", "m2"]);
+        assert_eq!(queued[0].sender_display.as_deref(), Some("Alice"));
+        assert_eq!(queued[0].model, None);
+        assert_eq!(queued[1].model.as_deref(), Some("claude-haiku-4-5"));
+        assert_eq!(queued[1].max_tokens, Some(1024));
+        assert_eq!(store.list_dead_letters(None).await.unwrap().len(), 3);
+        assert_eq!(store.count_dead_letters("agent-1").await.unwrap(), 2);
+        assert_eq!(store.count_dead_letters("agent-3").await.unwrap(), 0);
 
-        // Get agent
-        let fetched = store.get_agent("test-agent").await.unwrap().unwrap();
-        assert_eq!(fetched.name, "Test Agent");
+        assert!(store.delete_dead_letter("m1").await.unwrap());
+        assert!(!store.delete_dead_letter("m1").await.unwrap());
 
-        // Disconnect
+        assert_eq!(store.purge_dead_letters(Some("agent-1")).await.unwrap(), 1);
+        assert_eq!(store.purge_dead_letters(None).await.unwrap(), 1);
+        assert!(store.list_dead_letters(None).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_dead_letter_queue_is_bounded_per_agent() {
+        let (store, _dir): (Store, TempDir) = test_store().await;
+        let ttl = chrono::Duration::hours(1);
+
+        for id in ["m1", "m2", "m3"] {
+            store
+                .enqueue_dead_letter(&dead_letter(id, "agent-1", ttl), 2)
+                .await
+                .unwrap();
+        }
         store
-            .set_agent_connected("test-agent", false)
+            .enqueue_dead_letter(&dead_letter("other", "agent-2", ttl), 2)
             .await
             .unwrap();
-        let fetched = store.get_agent("test-agent").await.unwrap().unwrap();
-        assert!(!fetched.connected);
+
+        // The oldest message for agent-1 was dropped; agent-2 is unaffected
+        let ids: Vec<_> = store
+            .list_dead_letters(Some("agent-1"))
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|l| l.id)
+            .collect();
+        assert_eq!(ids, vec!["m2", "m3"]);
+        assert_eq!(
+            store
+                .list_dead_letters(Some("agent-2"))
+                .await
+                .unwrap()
+                .len(),
+            1
+        );
     }
 
     #[tokio::test]
-    async fn test_conversation_and_messages() {
+    async fn test_dead_letter_queue_limit_of_zero_keeps_everything() {
         let (store, _dir): (Store, TempDir) = test_store().await;
+        let ttl = chrono::Duration::hours(1);
 
-        // Create agent first
-        let agent = Agent {
-            id: "agent-1".to_string(),
-            name: "Agent 1".to_string(),
-            backend: "mux".to_string(),
-            working_dir: "/tmp".to_string(),
-            connected: true,
-            connected_at: Some(Utc::now()),
-            last_seen: Some(Utc::now()),
+        for id in ["m1", "m2", "m3"] {
+            let dropped = store
+                .enqueue_dead_letter(&dead_letter(id, "agent-1", ttl), 0)
+                .await
+                .unwrap();
+            assert_eq!(dropped, 0);
+        }
+        assert_eq!(store.count_dead_letters("agent-1").await.unwrap(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_expired_dead_letters_are_hidden_and_purged() {
+        let (store, _dir): (Store, TempDir) = test_store().await;
+
+        store
+            .enqueue_dead_letter(
+                &dead_letter("old", "agent-1", chrono::Duration::seconds(-1)),
+                10,
+            )
+            .await
+            .unwrap();
+        store
+            .enqueue_dead_letter(
+                &dead_letter("new", "agent-1", chrono::Duration::hours(1)),
+                10,
+            )
+            .await
+            .unwrap();
+
+        let queued = store.list_dead_letters(Some("agent-1")).await.unwrap();
+        assert_eq!(queued.len(), 1);
+        assert_eq!(queued[0].id, "new");
+        assert_eq!(store.count_dead_letters("agent-1").await.unwrap(), 1);
+
+        assert_eq!(store.purge_expired_dead_letters().await.unwrap(), 1);
+        assert_eq!(store.purge_dead_letters(None).await.unwrap(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_push_token_crud() {
+        let (store, _dir) = test_store().await;
+
+        assert!(store
+            .upsert_push_token("alice", "apns", "token-1")
+            .await
+            .unwrap());
+        assert!(store
+            .upsert_push_token("bob", "fcm", "token-2")
+            .await
+            .unwrap());
+        // Registering again updates instead of duplicating
+        assert!(!store
+            .upsert_push_token("alice", "apns", "token-1")
+            .await
+            .unwrap());
+
+        let all = store.list_push_tokens(None).await.unwrap();
+        assert_eq!(all.len(), 2);
+        let alice = store.list_push_tokens(Some("alice")).await.unwrap();
+        assert_eq!(alice.len(), 1);
+        assert_eq!(alice[0].token, "token-1");
+        assert_eq!(alice[0].platform, "apns");
+        assert!(alice[0].updated_at >= alice[0].created_at);
+
+        // Only the owner can remove a token
+        assert!(!store.delete_push_token("alice", "token-2").await.unwrap());
+        assert!(store.delete_push_token("bob", "token-2").await.unwrap());
+        assert!(!store.delete_push_token("bob", "token-2").await.unwrap());
+        assert_eq!(store.list_push_tokens(None).await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_recent_channels() {
+        let (store, _dir) = test_store().await;
+
+        store
+            .record_channel("slack", "C1", Some("#general"))
+            .await
+            .unwrap();
+        store.record_channel("telegram", "42", None).await.unwrap();
+        // A later message without a name keeps the name and moves it up
+        store.record_channel("slack", "C1", None).await.unwrap();
+
+        let all = store.list_recent_channels(None, 10).await.unwrap();
+        assert_eq!(all.len(), 2);
+        assert_eq!(all[0].channel_id, "C1");
+        assert_eq!(all[0].display_name.as_deref(), Some("#general"));
+        assert!(all[0].last_message_at >= all[1].last_message_at);
+
+        let telegram = store
+            .list_recent_channels(Some("telegram"), 10)
+            .await
+            .unwrap();
+        assert_eq!(telegram.len(), 1);
+        assert_eq!(telegram[0].channel_id, "42");
+        assert_eq!(telegram[0].display_name, None);
+        assert_eq!(store.list_recent_channels(None, 1).await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_key_rotation_grants() {
+        let (store, _dir) = test_store().await;
+        let soon = Utc::now() + chrono::Duration::hours(1);
+        let later = Utc::now() + chrono::Duration::hours(24);
+
+        store
+            .rotate_key("alice", &["fp-1".to_string()], "fp-2", soon)
+            .await
+            .unwrap();
+        let grants = store.list_key_grants().await.unwrap();
+        assert_eq!(grants.len(), 2);
+        let old = grants.iter().find(|g| g.fingerprint == "fp-1").unwrap();
+        assert_eq!(old.principal_id, "alice");
+        assert_eq!(old.expires_at.unwrap().timestamp(), soon.timestamp());
+        let new = grants.iter().find(|g| g.fingerprint == "fp-2").unwrap();
+        assert_eq!(new.expires_at, None);
+
+        // Rotating again retires fp-2, but can't extend fp-1's grace period
+        store
+            .rotate_key(
+                "alice",
+                &["fp-1".to_string(), "fp-2".to_string()],
+                "fp-3",
+                later,
+            )
+            .await
+            .unwrap();
+        let grants = store.list_key_grants().await.unwrap();
+        let expiry = |fp: &str| {
+            grants
+                .iter()
+                .find(|g| g.fingerprint == fp)
+                .unwrap()
+                .expires_at
+                .map(|t| t.timestamp())
         };
-        store.upsert_agent(&agent).await.unwrap();
+        assert_eq!(expiry("fp-1"), Some(soon.timestamp()));
+        assert_eq!(expiry("fp-2"), Some(later.timestamp()));
+        assert_eq!(expiry("fp-3"), None);
 
-        // Get or create conversation
-        let conv = store.get_or_create_conversation("agent-1").await.unwrap();
-        assert_eq!(conv.agent_id, "agent-1");
+        // Rotating back to a retired key makes it permanent again
+        store
+            .rotate_key("alice", &["fp-3".to_string()], "fp-1", soon)
+            .await
+            .unwrap();
+        let grants = store.list_key_grants().await.unwrap();
+        let fp1 = grants.iter().find(|g| g.fingerprint == "fp-1").unwrap();
+        assert_eq!(fp1.expires_at, None);
+    }
 
-        // Save messages
-        let msg1 = Message {
-            id: Uuid::new_v4().to_string(),
-            conversation_id: conv.id.clone(),
-            direction: "inbound".to_string(),
-            author: "user".to_string(),
-            content: "Hello".to_string(),
-            message_type: "message".to_string(),
-            created_at: Utc::now(),
+    #[tokio::test]
+    async fn test_tokens_listed_until_revoked_or_expired() {
+        let (store, _dir) = test_store().await;
+        let now = Utc::now();
+        let token = |id: &str, principal_id: &str, expires_at| TokenRecord {
+            id: id.to_string(),
+            principal_id: principal_id.to_string(),
+            issued_at: now - chrono::Duration::hours(1),
+            expires_at,
         };
-        store.save_message(&msg1).await.unwrap();
+        let later = now + chrono::Duration::days(1);
+        for record in [
+            token("t-1", "alice", later),
+            token("t-2", "bob", later),
+            token("t-old", "alice", now - chrono::Duration::minutes(1)),
+        ] {
+            store.record_token(&record).await.unwrap();
+        }
 
-        let msg2 = Message {
-            id: Uuid::new_v4().to_string(),
-            conversation_id: conv.id.clone(),
-            direction: "outbound".to_string(),
-            author: "agent".to_string(),
-            content: "Hi there!".to_string(),
-            message_type: "message".to_string(),
-            created_at: Utc::now(),
+        let ids = |tokens: Vec<TokenRecord>| tokens.into_iter().map(|t| t.id).collect::<Vec<_>>();
+        assert_eq!(
+            ids(store.list_tokens(None, now).await.unwrap()),
+            ["t-1", "t-2"]
+        );
+        assert_eq!(
+            ids(store.list_tokens(Some("alice"), now).await.unwrap()),
+            ["t-1"]
+        );
+
+        assert!(store.revoke_token("t-1", now).await.unwrap());
+        assert!(!store.revoke_token("t-1", now).await.unwrap());
+        assert!(!store.revoke_token("t-old", now).await.unwrap());
+        assert!(!store.revoke_token("nope", now).await.unwrap());
+        assert_eq!(ids(store.list_tokens(None, now).await.unwrap()), ["t-2"]);
+        assert_eq!(store.list_revoked_tokens(now).await.unwrap(), ["t-1"]);
+        // Once it would have expired anyway, it's no longer worth keeping
+        assert!(store.list_revoked_tokens(later).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_pragmas_applied_to_every_connection() {
+        let (store, _dir): (Store, TempDir) = test_store().await;
+
+        // Hold several connections at once so the check isn't just the first one
+        let mut conns = Vec::new();
+        for _ in 0..3 {
+            conns.push(store.pool.acquire().await.unwrap());
+        }
+        for conn in conns.iter_mut() {
+            let mode: String = sqlx::query_scalar("PRAGMA journal_mode")
+                .fetch_one(&mut **conn)
+                .await
+                .unwrap();
+            assert_eq!(mode, "wal");
+            let synchronous: i64 = sqlx::query_scalar("PRAGMA synchronous")
+                .fetch_one(&mut **conn)
+                .await
+                .unwrap();
+            assert_eq!(synchronous, 1); // NORMAL
+            let timeout: i64 = sqlx::query_scalar("PRAGMA busy_timeout")
+                .fetch_one(&mut **conn)
+                .await
+                .unwrap();
+            assert_eq!(timeout, DEFAULT_BUSY_TIMEOUT.as_millis() as i64);
+            let foreign_keys: i64 = sqlx::query_scalar("PRAGMA foreign_keys")
+                .fetch_one(&mut **conn)
+                .await
+                .unwrap();
+            assert_eq!(foreign_keys, 1);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_writers_do_not_hit_lock_errors() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("test.db");
+        // Two stores on one file means two pools competing for the write lock,
+        // the same as the gateway and an admin command running side by side
+        let first = Store::open(&path).await.unwrap();
+        let second = Store::open(&path).await.unwrap();
+        for (i, store) in [&first, &second].into_iter().enumerate() {
+            let agent_id = format!("agent-{i}");
+            store
+                .upsert_agent(&Agent {
+                    id: agent_id.clone(),
+                    name: agent_id.clone(),
+                    backend: "mux".to_string(),
+                    working_dir: "/tmp".to_string(),
+                    connected: true,
+                    connected_at: Some(Utc::now()),
+                    last_seen: Some(Utc::now()),
+                })
+                .await
+                .unwrap();
+            store.get_or_create_conversation(&agent_id).await.unwrap();
+        }
+
+        let mut writers = Vec::new();
+        for i in 0..40 {
+            let store = if i % 2 == 0 {
+                first.clone()
+            } else {
+                second.clone()
+            };
+            let conversation_id = format!("agent-{}", i % 2);
+            writers.push(tokio::spawn(async move {
+                for n in 0..10 {
+                    store
+                        .save_message(&Message {
+                            id: Uuid::new_v4().to_string(),
+                            conversation_id: conversation_id.clone(),
+                            direction: "inbound".to_string(),
+                            author: format!("writer-{i}"),
+                            content: format!("message {n}"),
+                            message_type: "message".to_string(),
+                            reply_to_message_id: None,
+                            created_at: Utc::now(),
+                        })
+                        .await?;
+                    store.touch_conversation(&conversation_id).await?;
+                }
+                anyhow::Ok(())
+            }));
+        }
+        for writer in writers {
+            writer.await.unwrap().expect("writer hit a database error");
+        }
+
+        let total = first.get_messages("agent-0", 1000).await.unwrap().len()
+            + second.get_messages("agent-1", 1000).await.unwrap().len();
+        assert_eq!(total, 400);
+    }
+
+    fn approval(agent_id: &str, tool_id: &str) -> ToolApproval {
+        ToolApproval {
+            agent_id: agent_id.to_string(),
+            tool_id: tool_id.to_string(),
+            request_id: "req-1".to_string(),
+            tool_name: "bash".to_string(),
+            input_json: r#"{"command":"ls"}"#.to_string(),
+            confirm_message: None,
+            requested_at: Utc::now(),
+            resolved_at: None,
+            decision: None,
+            resolved_by: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_tool_approval_lifecycle() {
+        let (store, _dir) = test_store().await;
+
+        for tool_id in ["tool-1", "tool-2", "tool-3"] {
+            store
+                .record_approval_request(&approval("agent-1", tool_id))
+                .await
+                .unwrap();
+        }
+        store
+            .record_approval_request(&approval("agent-2", "tool-1"))
+            .await
+            .unwrap();
+        // A repeated request doesn't duplicate the record
+        store
+            .record_approval_request(&approval("agent-1", "tool-1"))
+            .await
+            .unwrap();
+
+        let pending = store.list_pending_approvals("agent-1").await.unwrap();
+        let ids: Vec<&str> = pending.iter().map(|a| a.tool_id.as_str()).collect();
+        assert_eq!(ids, ["tool-1", "tool-2", "tool-3"]);
+
+        assert!(store
+            .resolve_approval("agent-1", "tool-1", "approved", Some("local-user"))
+            .await
+            .unwrap());
+        // The first resolution wins
+        assert!(!store
+            .resolve_approval("agent-1", "tool-1", "timeout", None)
+            .await
+            .unwrap());
+        assert!(store
+            .resolve_approval("agent-1", "tool-2", "denied", Some("local-user"))
+            .await
+            .unwrap());
+
+        let pending = store.list_pending_approvals("agent-1").await.unwrap();
+        assert_eq!(pending.len(), 1);
+        assert_eq!(pending[0].tool_id, "tool-3");
+
+        let history = store.approval_history("agent-1", 10).await.unwrap();
+        let decided: Vec<(&str, Option<&str>)> = history
+            .iter()
+            .map(|a| (a.tool_id.as_str(), a.decision.as_deref()))
+            .collect();
+        assert_eq!(
+            decided,
+            [("tool-2", Some("denied")), ("tool-1", Some("approved"))]
+        );
+        assert_eq!(history[1].resolved_by.as_deref(), Some("local-user"));
+        assert!(history[1].resolved_at.is_some());
+        assert_eq!(store.approval_history("agent-1", 1).await.unwrap().len(), 1);
+
+        // A departed agent's pending approvals expire; other agents' don't
+        assert_eq!(store.expire_pending_approvals("agent-1").await.unwrap(), 1);
+        assert!(store
+            .list_pending_approvals("agent-1")
+            .await
+            .unwrap()
+            .is_empty());
+        let history = store.approval_history("agent-1", 10).await.unwrap();
+        assert_eq!(history[0].decision.as_deref(), Some("expired"));
+        assert_eq!(history[0].resolved_by, None);
+        assert_eq!(
+            store.list_pending_approvals("agent-2").await.unwrap().len(),
+            1
+        );
+    }
+
+    #[tokio::test]
+    async fn test_client_decision_is_attributed_after_the_agent_reports() {
+        let (store, _dir) = test_store().await;
+        for tool_id in ["ran", "timed-out", "pending"] {
+            store
+                .record_approval_request(&approval("agent-1", tool_id))
+                .await
+                .unwrap();
+        }
+        // The agent reports running the tool before the decision is recorded
+        store
+            .resolve_approval("agent-1", "ran", "approved", None)
+            .await
+            .unwrap();
+        assert!(store
+            .record_approval_decision("agent-1", "ran", "approved_all", "harper")
+            .await
+            .unwrap());
+        // ...but a tool that timed out first stays timed out
+        store
+            .resolve_approval("agent-1", "timed-out", "timeout", None)
+            .await
+            .unwrap();
+        assert!(!store
+            .record_approval_decision("agent-1", "timed-out", "approved", "harper")
+            .await
+            .unwrap());
+        assert!(store
+            .record_approval_decision("agent-1", "pending", "denied", "harper")
+            .await
+            .unwrap());
+
+        let history = store.approval_history("agent-1", 10).await.unwrap();
+        let decided = |tool_id: &str| {
+            let a = history.iter().find(|a| a.tool_id == tool_id).unwrap();
+            (a.decision.clone().unwrap(), a.resolved_by.clone())
         };
-        store.save_message(&msg2).await.unwrap();
+        assert_eq!(
+            decided("ran"),
+            ("approved_all".to_string(), Some("harper".to_string()))
+        );
+        assert_eq!(decided("timed-out"), ("timeout".to_string(), None));
+        assert_eq!(
+            decided("pending"),
+            ("denied".to_string(), Some("harper".to_string()))
+        );
+    }
 
-        // Get messages
-        let messages = store.get_messages(&conv.id, 100).await.unwrap();
-        assert_eq!(messages.len(), 2);
-        assert_eq!(messages[0].content, "Hello");
-        assert_eq!(messages[1].content, "Hi there!");
+    #[tokio::test]
+    async fn test_prune_activity_drops_resolved_approvals() {
+        let (store, _dir) = test_store().await;
+        for tool_id in ["resolved", "pending"] {
+            store
+                .record_approval_request(&approval("agent-1", tool_id))
+                .await
+                .unwrap();
+        }
+        store
+            .resolve_approval("agent-1", "resolved", "denied", None)
+            .await
+            .unwrap();
+
+        let pruned = store
+            .prune_activity(Utc::now() + chrono::Duration::seconds(1))
+            .await
+            .unwrap();
+        assert_eq!(pruned, 1);
+        assert!(store
+            .approval_history("agent-1", 10)
+            .await
+            .unwrap()
+            .is_empty());
+        assert_eq!(
+            store.list_pending_approvals("agent-1").await.unwrap().len(),
+            1
+        );
     }
 }
@@ -0,0 +1,169 @@
+// ABOUTME: Bearer tokens the local gateway issues to principals it knows by key
+// ABOUTME: HS256 JWTs signed with a key derived from the master key, renewed through RefreshToken, revocable by ID
+
+use crate::secrets::MasterKey;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use tonic::metadata::MetadataMap;
+use tonic::Status;
+use uuid::Uuid;
+
+/// How long an issued token authenticates its principal
+pub const TOKEN_LIFETIME: Duration = Duration::days(30);
+
+/// JOSE header of every token issued
+const HEADER: &str = r#"{"alg":"HS256","typ":"JWT"}"#;
+
+/// Hex digits in a token's ID
+const TOKEN_ID_LEN: usize = 12;
+
+/// A token, its ID and when it stops working
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IssuedToken {
+    pub token: String,
+    /// Lists and revokes the token without showing it (its jti)
+    pub id: String,
+    pub issued_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+}
+
+/// Who a valid token was issued to, and which token it is
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VerifiedToken {
+    pub principal_id: String,
+    pub id: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Claims {
+    sub: String,
+    jti: String,
+    iat: i64,
+    exp: i64,
+}
+
+/// Issues and checks tokens with the gateway's master key
+#[derive(Debug, Clone)]
+pub struct TokenIssuer {
+    key: MasterKey,
+}
+
+impl TokenIssuer {
+    pub fn new(key: MasterKey) -> Self {
+        Self { key }
+    }
+
+    /// A token for `principal_id`, good for `TOKEN_LIFETIME` from `now`
+    pub fn issue(&self, principal_id: &str, now: DateTime<Utc>) -> IssuedToken {
+        let expires_at = now + TOKEN_LIFETIME;
+        let id = Uuid::new_v4().simple().to_string()[..TOKEN_ID_LEN].to_string();
+        let claims = Claims {
+            sub: principal_id.to_string(),
+            jti: id.clone(),
+            iat: now.timestamp(),
+            exp: expires_at.timestamp(),
+        };
+        let claims = serde_json::to_vec(&claims).expect("claims serialize");
+        let signing_input = format!(
+            "{}.{}",
+            URL_SAFE_NO_PAD.encode(HEADER),
+            URL_SAFE_NO_PAD.encode(claims)
+        );
+        let signature = URL_SAFE_NO_PAD.encode(self.key.sign_token(signing_input.as_bytes()));
+        // Whole seconds, as the token states them
+        let whole = |t: DateTime<Utc>| DateTime::from_timestamp(t.timestamp(), 0).unwrap_or(t);
+        IssuedToken {
+            token: format!("{}.{}", signing_input, signature),
+            id,
+            issued_at: whole(now),
+            expires_at: whole(expires_at),
+        }
+    }
+
+    /// Whom `token` was issued to, if this gateway signed it and it hasn't
+    /// expired at `now`. Revocation is the caller's to check.
+    pub fn verify(&self, token: &str, now: DateTime<Utc>) -> Result<VerifiedToken, Status> {
+        let invalid = || Status::unauthenticated("invalid token");
+        let (signing_input, signature) = token.rsplit_once('.').ok_or_else(invalid)?;
+        let signature = URL_SAFE_NO_PAD.decode(signature).map_err(|_| invalid())?;
+        if !self.key.verify_token(signing_input.as_bytes(), &signature) {
+            return Err(invalid());
+        }
+        let (header, claims) = signing_input.split_once('.').ok_or_else(invalid)?;
+        if URL_SAFE_NO_PAD.decode(header).map_err(|_| invalid())? != HEADER.as_bytes() {
+            return Err(invalid());
+        }
+        let claims = URL_SAFE_NO_PAD.decode(claims).map_err(|_| invalid())?;
+        let claims: Claims = serde_json::from_slice(&claims).map_err(|_| invalid())?;
+        if claims.exp <= now.timestamp() {
+            return Err(Status::unauthenticated(
+                "token expired; run 'coven link' to get a new one",
+            ));
+        }
+        Ok(VerifiedToken {
+            principal_id: claims.sub,
+            id: claims.jti,
+        })
+    }
+}
+
+/// The bearer token in a request's `authorization` header, if any
+pub fn bearer_token(metadata: &MetadataMap) -> Option<&str> {
+    metadata
+        .get("authorization")?
+        .to_str()
+        .ok()?
+        .strip_prefix("Bearer ")
+        .map(str::trim)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn issuer() -> TokenIssuer {
+        let dir = tempfile::tempdir().unwrap();
+        TokenIssuer::new(MasterKey::load_or_generate(&dir.path().join("key")).unwrap())
+    }
+
+    #[test]
+    fn test_issued_tokens_verify_until_they_expire() {
+        let issuer = issuer();
+        let now = Utc::now();
+        let issued = issuer.issue("harper", now);
+        assert_eq!(
+            issued.expires_at.timestamp(),
+            (now + TOKEN_LIFETIME).timestamp()
+        );
+        let verified = issuer.verify(&issued.token, now).unwrap();
+        assert_eq!(verified.principal_id, "harper");
+        assert_eq!(verified.id, issued.id);
+        assert_eq!(issued.id.len(), TOKEN_ID_LEN);
+        assert_ne!(issuer.issue("harper", now).id, issued.id);
+
+        let err = issuer.verify(&issued.token, issued.expires_at).unwrap_err();
+        assert!(err.message().contains("expired"), "{}", err.message());
+    }
+
+    #[test]
+    fn test_tokens_from_other_keys_or_tampered_are_refused() {
+        let issuer = issuer();
+        let now = Utc::now();
+        let other = self::issuer().issue("harper", now);
+        assert!(issuer.verify(&other.token, now).is_err());
+
+        let issued = issuer.issue("guest", now);
+        let (_, rest) = issued.token.split_once('.').unwrap();
+        let (_, signature) = rest.split_once('.').unwrap();
+        let forged = format!(
+            "{}.{}.{}",
+            URL_SAFE_NO_PAD.encode(HEADER),
+            URL_SAFE_NO_PAD.encode(r#"{"sub":"harper","jti":"x","iat":0,"exp":9999999999}"#),
+            signature
+        );
+        assert!(issuer.verify(&forged, now).is_err());
+        assert!(issuer.verify("not-a-token", now).is_err());
+    }
+}
@@ -0,0 +1,205 @@
+// ABOUTME: Time-based one-time codes (RFC 6238), a second factor for destructive admin RPCs
+// ABOUTME: Generates and encodes per-principal secrets and checks the code a request carries in its metadata
+
+use coven_proto::OTP_METADATA;
+use hmac::{Hmac, Mac};
+use rand::RngCore;
+use sha1::Sha1;
+use tonic::metadata::MetadataMap;
+use tonic::Status;
+
+/// Seconds each code is valid for
+const STEP_SECS: i64 = 30;
+
+/// Digits in a code
+const DIGITS: u32 = 6;
+
+/// Secret length in bytes, the size of a SHA-1 digest as RFC 4226 advises
+const SECRET_LEN: usize = 20;
+
+/// Steps either side of now whose codes are still accepted, for clock
+/// skew and slow typing
+const SKEW_STEPS: i64 = 1;
+
+const BASE32_ALPHABET: &[u8; 32] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+
+/// A new random secret
+pub fn generate_secret() -> Vec<u8> {
+    let mut secret = vec![0; SECRET_LEN];
+    rand::thread_rng().fill_bytes(&mut secret);
+    secret
+}
+
+/// `secret` in unpadded base32, the form authenticator apps take
+pub fn encode_secret(secret: &[u8]) -> String {
+    let mut encoded = String::with_capacity(secret.len().div_ceil(5) * 8);
+    for chunk in secret.chunks(5) {
+        let mut block = [0u8; 8];
+        block[3..3 + chunk.len()].copy_from_slice(chunk);
+        let bits = u64::from_be_bytes(block);
+        for i in 0..(chunk.len() * 8).div_ceil(5) {
+            let index = (bits >> (35 - i * 5)) & 0x1f;
+            encoded.push(char::from(BASE32_ALPHABET[index as usize]));
+        }
+    }
+    encoded
+}
+
+/// Link an authenticator app can import `secret` from, labelled with
+/// `principal_id`
+pub fn otpauth_uri(principal_id: &str, secret: &[u8]) -> String {
+    format!(
+        "otpauth://totp/coven:{}?secret={}&issuer=coven&digits={}&period={}",
+        percent_encode(principal_id),
+        encode_secret(secret),
+        DIGITS,
+        STEP_SECS
+    )
+}
+
+fn percent_encode(text: &str) -> String {
+    text.bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                char::from(b).to_string()
+            }
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+/// The code for `secret` at `unix_secs`
+pub fn code_at(secret: &[u8], unix_secs: i64) -> String {
+    let counter = unix_secs.div_euclid(STEP_SECS) as u64;
+    let mut mac = Hmac::<Sha1>::new_from_slice(secret).expect("HMAC takes keys of any length");
+    mac.update(&counter.to_be_bytes());
+    let digest = mac.finalize().into_bytes();
+
+    // Dynamic truncation, RFC 4226 section 5.3
+    let offset = usize::from(digest[digest.len() - 1] & 0x0f);
+    let value = u32::from_be_bytes([
+        digest[offset] & 0x7f,
+        digest[offset + 1],
+        digest[offset + 2],
+        digest[offset + 3],
+    ]);
+    format!(
+        "{:0width$}",
+        value % 10u32.pow(DIGITS),
+        width = DIGITS as usize
+    )
+}
+
+/// The step whose code for `secret` is `code`, if it's the step of
+/// `unix_secs` give or take one and later than `used_through`
+pub fn verify(secret: &[u8], code: &str, unix_secs: i64, used_through: Option<i64>) -> Option<i64> {
+    let code: String = code.chars().filter(|c| !c.is_whitespace()).collect();
+    let now = unix_secs.div_euclid(STEP_SECS);
+    (now - SKEW_STEPS..=now + SKEW_STEPS)
+        .filter(|step| used_through.is_none_or(|used| *step > used))
+        .find(|step| code_at(secret, step * STEP_SECS) == code)
+}
+
+/// Check `code` against `secret` now, refusing codes from steps up to
+/// `used_through` so none is accepted twice. Returns the code's step, for
+/// the next call's `used_through`. Failures tell the client a (new) code
+/// would help.
+pub fn check(secret: &[u8], code: Option<&str>, used_through: Option<i64>) -> Result<i64, Status> {
+    let now = chrono::Utc::now().timestamp();
+    match code {
+        None => Err(required("this call needs a one-time code")),
+        Some(code) => verify(secret, code, now, used_through)
+            .ok_or_else(|| required("one-time code is wrong, expired or already used")),
+    }
+}
+
+/// The code a request carries in its metadata
+pub fn code_from(metadata: &MetadataMap) -> Option<&str> {
+    metadata
+        .get(OTP_METADATA)
+        .and_then(|value| value.to_str().ok())
+}
+
+fn required(message: &str) -> Status {
+    let mut status = Status::unauthenticated(message);
+    status
+        .metadata_mut()
+        .insert(OTP_METADATA, "required".parse().unwrap());
+    status
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The SHA-1 seed from RFC 6238's test vectors
+    const RFC_SECRET: &[u8] = b"12345678901234567890";
+
+    #[test]
+    fn test_rfc_6238_vectors() {
+        // The RFC lists eight digits; codes here are their last six
+        assert_eq!(code_at(RFC_SECRET, 59), "287082");
+        assert_eq!(code_at(RFC_SECRET, 1111111109), "081804");
+        assert_eq!(code_at(RFC_SECRET, 1111111111), "050471");
+        assert_eq!(code_at(RFC_SECRET, 1234567890), "005924");
+        assert_eq!(code_at(RFC_SECRET, 2000000000), "279037");
+    }
+
+    #[test]
+    fn test_verify_allows_a_step_of_skew() {
+        let now = 1111111109;
+        let step = now / STEP_SECS;
+        assert_eq!(verify(RFC_SECRET, "081804", now, None), Some(step));
+        assert_eq!(verify(RFC_SECRET, "081 804", now + 30, None), Some(step));
+        assert_eq!(verify(RFC_SECRET, "081804", now - 30, None), Some(step));
+        assert_eq!(verify(RFC_SECRET, "081804", now + 90, None), None);
+        assert_eq!(verify(RFC_SECRET, "000000", now, None), None);
+        assert_eq!(verify(RFC_SECRET, "", now, None), None);
+    }
+
+    #[test]
+    fn test_verify_refuses_used_steps() {
+        let now = 1111111109;
+        let step = now / STEP_SECS;
+        assert_eq!(verify(RFC_SECRET, "081804", now, Some(step)), None);
+        assert_eq!(verify(RFC_SECRET, "081804", now + 30, Some(step + 1)), None);
+        assert_eq!(
+            verify(RFC_SECRET, "081804", now, Some(step - 1)),
+            Some(step)
+        );
+    }
+
+    #[test]
+    fn test_encode_secret_is_base32() {
+        assert_eq!(
+            encode_secret(RFC_SECRET),
+            "GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ"
+        );
+        assert_eq!(encode_secret(b"f"), "MY");
+        assert_eq!(encode_secret(b"foobar"), "MZXW6YTBOI");
+        assert_eq!(generate_secret().len(), SECRET_LEN);
+    }
+
+    #[test]
+    fn test_otpauth_uri() {
+        assert_eq!(
+            otpauth_uri("device:my phone", b"foobar"),
+            "otpauth://totp/coven:device%3Amy%20phone?secret=MZXW6YTBOI&issuer=coven&digits=6&period=30"
+        );
+    }
+
+    #[test]
+    fn test_missing_or_wrong_code_asks_for_one() {
+        let secret = generate_secret();
+        for err in [
+            check(&secret, None, None).unwrap_err(),
+            check(&secret, Some("nope"), None).unwrap_err(),
+        ] {
+            assert_eq!(err.code(), tonic::Code::Unauthenticated);
+            assert_eq!(err.metadata().get(OTP_METADATA).unwrap(), "required");
+        }
+        let code = code_at(&secret, chrono::Utc::now().timestamp());
+        let step = check(&secret, Some(&code), None).unwrap();
+        assert!(check(&secret, Some(&code), Some(step)).is_err());
+    }
+}
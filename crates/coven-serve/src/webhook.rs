@@ -0,0 +1,769 @@
+// ABOUTME: Webhook notifications for gateway events, configured by a webhooks file
+// ABOUTME: Queues signed JSON payloads per webhook, retries with backoff, and logs undeliverable ones as dead letters
+
+use crate::services::control::{AgentLifecycle, ControlState};
+use crate::store::Store;
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Utc};
+use coven_proto::{message_response, AgentInitiatedEvent, ToolApprovalRequest};
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::{mpsc, Mutex};
+use tokio::task::{JoinHandle, JoinSet};
+use tracing::{debug, warn};
+use uuid::Uuid;
+
+/// How long one delivery attempt may take
+const DELIVERY_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Payloads waiting per webhook before new ones go straight to the dead
+/// letters
+const QUEUE_SIZE: usize = 256;
+
+/// Longest message preview put in a payload, in characters
+const PREVIEW_CHARS: usize = 200;
+
+/// Header naming the event a payload is for
+pub const EVENT_HEADER: &str = "x-coven-event";
+/// Header with the payload's ID, the same on every attempt
+pub const DELIVERY_HEADER: &str = "x-coven-delivery";
+/// Header with the Unix time the signature was made at
+pub const TIMESTAMP_HEADER: &str = "x-coven-timestamp";
+/// Header with `sha256=<hex>`, when the webhook has a secret
+pub const SIGNATURE_HEADER: &str = "x-coven-signature";
+
+/// Gateway events a webhook can subscribe to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EventKind {
+    AgentConnected,
+    AgentDisconnected,
+    ApprovalPendingTimeout,
+    ErrorEvent,
+    AgentInitiated,
+}
+
+impl EventKind {
+    pub const ALL: [EventKind; 5] = [
+        EventKind::AgentConnected,
+        EventKind::AgentDisconnected,
+        EventKind::ApprovalPendingTimeout,
+        EventKind::ErrorEvent,
+        EventKind::AgentInitiated,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            EventKind::AgentConnected => "agent_connected",
+            EventKind::AgentDisconnected => "agent_disconnected",
+            EventKind::ApprovalPendingTimeout => "approval_pending_timeout",
+            EventKind::ErrorEvent => "error_event",
+            EventKind::AgentInitiated => "agent_initiated",
+        }
+    }
+}
+
+impl fmt::Display for EventKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// How often a failed delivery is retried
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RetryPolicy {
+    /// Attempts in all, the first included
+    pub max_attempts: u32,
+    /// Wait before the first retry, doubling for each one after
+    pub initial_backoff_ms: u64,
+    /// Longest wait between attempts
+    pub max_backoff_ms: u64,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 5,
+            initial_backoff_ms: 1_000,
+            max_backoff_ms: 60_000,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Wait after failed attempt number `attempt` (1-based)
+    fn backoff(&self, attempt: u32) -> Duration {
+        let factor = 2u64.saturating_pow(attempt.saturating_sub(1));
+        Duration::from_millis(
+            self.initial_backoff_ms
+                .saturating_mul(factor)
+                .min(self.max_backoff_ms),
+        )
+    }
+}
+
+/// One webhook. Loaded from a TOML file of `[[webhook]]` tables:
+///
+/// ```toml
+/// [[webhook]]
+/// url = "https://hooks.slack.com/services/T000/B000/XXXX"
+/// events = ["agent_disconnected", "approval_pending_timeout"]
+/// secret = "shared-signing-secret"
+/// approval_timeout_secs = 600
+///
+/// [webhook.retry]
+/// max_attempts = 5
+/// initial_backoff_ms = 1000
+/// max_backoff_ms = 60000
+/// ```
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct WebhookConfig {
+    /// URL each payload is POSTed to
+    pub url: String,
+    /// Events to send (default: all of them)
+    #[serde(default)]
+    pub events: Vec<EventKind>,
+    /// Key for the `x-coven-signature` HMAC (default: payloads are unsigned)
+    #[serde(default)]
+    pub secret: Option<String>,
+    /// How long a tool approval waits unanswered before
+    /// `approval_pending_timeout` is sent (default: 10 minutes)
+    #[serde(default = "default_approval_timeout_secs")]
+    pub approval_timeout_secs: u64,
+    #[serde(default)]
+    pub retry: RetryPolicy,
+}
+
+fn default_approval_timeout_secs() -> u64 {
+    10 * 60
+}
+
+/// The layout of a webhooks file
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct WebhooksFile {
+    #[serde(default)]
+    webhook: Vec<WebhookConfig>,
+}
+
+impl WebhookConfig {
+    /// A webhook for `url` sending every event, with the defaults
+    pub fn new(url: impl Into<String>) -> Self {
+        Self {
+            url: url.into(),
+            events: Vec::new(),
+            secret: None,
+            approval_timeout_secs: default_approval_timeout_secs(),
+            retry: RetryPolicy::default(),
+        }
+    }
+
+    /// Read a webhooks file
+    pub fn load(path: &Path) -> Result<Vec<Self>> {
+        let text = std::fs::read_to_string(path)
+            .with_context(|| format!("reading webhooks file: {}", path.display()))?;
+        let file: WebhooksFile = toml::from_str(&text)
+            .with_context(|| format!("parsing webhooks file: {}", path.display()))?;
+        for webhook in &file.webhook {
+            webhook
+                .validate()
+                .with_context(|| format!("in webhooks file: {}", path.display()))?;
+        }
+        Ok(file.webhook)
+    }
+
+    fn validate(&self) -> Result<()> {
+        let url = reqwest::Url::parse(&self.url)
+            .with_context(|| format!("invalid webhook URL {:?}", self.url))?;
+        if !matches!(url.scheme(), "http" | "https") {
+            bail!("webhook URL {:?} must be http or https", self.url);
+        }
+        if self.retry.max_attempts == 0 {
+            bail!(
+                "webhook {}: retry.max_attempts must be at least 1",
+                self.url
+            );
+        }
+        if self.secret.as_deref().is_some_and(str::is_empty) {
+            bail!("webhook {}: secret is empty", self.url);
+        }
+        Ok(())
+    }
+
+    /// Whether `kind` is one of the events this webhook wants
+    pub fn wants(&self, kind: EventKind) -> bool {
+        self.events.is_empty() || self.events.contains(&kind)
+    }
+
+    fn approval_timeout(&self) -> Duration {
+        Duration::from_secs(self.approval_timeout_secs)
+    }
+}
+
+/// Body POSTed to a webhook
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WebhookPayload {
+    /// Unique per event; repeated on retries, so receivers can drop duplicates
+    pub id: String,
+    pub timestamp: DateTime<Utc>,
+    pub agent_id: String,
+    /// One-line summary, which Slack-compatible webhooks show as is
+    pub text: String,
+    #[serde(flatten)]
+    pub details: EventDetails,
+}
+
+/// What happened, tagged by `event`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum EventDetails {
+    AgentConnected {
+        agent_name: String,
+    },
+    AgentDisconnected {
+        agent_name: String,
+        /// The connection broke rather than being closed by the agent
+        unexpected: bool,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        reason: Option<String>,
+    },
+    ApprovalPendingTimeout {
+        tool_id: String,
+        request_id: String,
+        tool_name: String,
+        pending_secs: u64,
+    },
+    ErrorEvent {
+        request_id: String,
+        message: String,
+    },
+    AgentInitiated {
+        agent_name: String,
+        message_id: String,
+        content: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        thread_id: Option<String>,
+    },
+}
+
+impl EventDetails {
+    pub fn kind(&self) -> EventKind {
+        match self {
+            EventDetails::AgentConnected { .. } => EventKind::AgentConnected,
+            EventDetails::AgentDisconnected { .. } => EventKind::AgentDisconnected,
+            EventDetails::ApprovalPendingTimeout { .. } => EventKind::ApprovalPendingTimeout,
+            EventDetails::ErrorEvent { .. } => EventKind::ErrorEvent,
+            EventDetails::AgentInitiated { .. } => EventKind::AgentInitiated,
+        }
+    }
+}
+
+impl WebhookPayload {
+    /// A new event for `agent_id`, stamped now
+    pub fn new(agent_id: impl Into<String>, details: EventDetails) -> Self {
+        let agent_id = agent_id.into();
+        let text = summary(&agent_id, &details);
+        Self {
+            id: Uuid::new_v4().to_string(),
+            timestamp: Utc::now(),
+            agent_id,
+            text,
+            details,
+        }
+    }
+}
+
+fn summary(agent_id: &str, details: &EventDetails) -> String {
+    match details {
+        EventDetails::AgentConnected { .. } => format!("{} connected", agent_id),
+        EventDetails::AgentDisconnected {
+            unexpected: true,
+            reason,
+            ..
+        } => match reason {
+            Some(reason) => format!("{} disconnected unexpectedly: {}", agent_id, reason),
+            None => format!("{} disconnected unexpectedly", agent_id),
+        },
+        EventDetails::AgentDisconnected { .. } => format!("{} disconnected", agent_id),
+        EventDetails::ApprovalPendingTimeout {
+            tool_name,
+            pending_secs,
+            ..
+        } => format!(
+            "{} has waited {} minute(s) for approval to run {}",
+            agent_id,
+            pending_secs / 60,
+            tool_name
+        ),
+        EventDetails::ErrorEvent { message, .. } => {
+            format!("{} reported an error: {}", agent_id, preview(message))
+        }
+        EventDetails::AgentInitiated { content, .. } => {
+            format!("{}: {}", agent_id, preview(content))
+        }
+    }
+}
+
+/// `sha256=<hex>`, the HMAC-SHA256 under `secret` of `<timestamp>.<body>`
+pub fn sign(secret: &str, timestamp: i64, body: &[u8]) -> String {
+    let mac = hmac(secret, timestamp, body);
+    format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+}
+
+/// Whether `signature` is what `sign` gives for this body, compared in
+/// constant time. For receivers checking a payload came from the gateway.
+pub fn verify(secret: &str, timestamp: i64, body: &[u8], signature: &str) -> bool {
+    let Some(Ok(expected)) = signature.strip_prefix("sha256=").map(hex::decode) else {
+        return false;
+    };
+    hmac(secret, timestamp, body)
+        .verify_slice(&expected)
+        .is_ok()
+}
+
+fn hmac(secret: &str, timestamp: i64, body: &[u8]) -> Hmac<Sha256> {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC takes keys of any length");
+    mac.update(timestamp.to_string().as_bytes());
+    mac.update(b".");
+    mac.update(body);
+    mac
+}
+
+/// A payload that could not be delivered, as logged
+#[derive(Debug, Serialize)]
+struct DeadLetter<'a> {
+    failed_at: DateTime<Utc>,
+    url: &'a str,
+    attempts: u32,
+    error: &'a str,
+    payload: &'a WebhookPayload,
+}
+
+/// JSON Lines file of payloads no webhook took
+struct DeadLetterLog {
+    path: PathBuf,
+    /// Keeps concurrent appends from interleaving
+    lock: Mutex<()>,
+}
+
+impl DeadLetterLog {
+    async fn append(&self, url: &str, attempts: u32, error: &str, payload: &WebhookPayload) {
+        warn!(
+            event = %payload.details.kind(),
+            id = %payload.id,
+            attempts,
+            error,
+            "Webhook delivery failed; logged as a dead letter"
+        );
+        let record = DeadLetter {
+            failed_at: Utc::now(),
+            url,
+            attempts,
+            error,
+            payload,
+        };
+        let result = async {
+            let mut line = serde_json::to_vec(&record)?;
+            line.push(b'\n');
+            let _guard = self.lock.lock().await;
+            if let Some(parent) = self.path.parent() {
+                tokio::fs::create_dir_all(parent).await?;
+            }
+            let mut file = tokio::fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&self.path)
+                .await?;
+            file.write_all(&line).await?;
+            anyhow::Ok(())
+        }
+        .await;
+        if let Err(e) = result {
+            warn!(path = %self.path.display(), error = %e, "Failed to write webhook dead letter");
+        }
+    }
+}
+
+/// A webhook and the queue its worker delivers from
+struct Webhook {
+    config: WebhookConfig,
+    queue: mpsc::Sender<WebhookPayload>,
+}
+
+/// Sends gateway events to the configured webhooks
+pub struct WebhookDispatcher {
+    store: Store,
+    webhooks: Vec<WebhookConfig>,
+    dead_letters: Arc<DeadLetterLog>,
+    http: reqwest::Client,
+}
+
+impl WebhookDispatcher {
+    /// Deliver to `webhooks`, logging payloads that can't be delivered to
+    /// the JSON Lines file at `dead_letter_path`
+    pub fn new(store: Store, webhooks: Vec<WebhookConfig>, dead_letter_path: PathBuf) -> Self {
+        Self {
+            store,
+            webhooks,
+            dead_letters: Arc::new(DeadLetterLog {
+                path: dead_letter_path,
+                lock: Mutex::new(()),
+            }),
+            http: reqwest::Client::new(),
+        }
+    }
+
+    /// Follow the gateway's events until it shuts down. Aborting the
+    /// returned task stops delivery, retries included.
+    pub fn spawn(self, control: &ControlState) -> JoinHandle<()> {
+        let mut lifecycle = control.subscribe_lifecycle();
+        let mut responses = control.subscribe_responses();
+        let mut initiated = control.subscribe_initiated();
+        tokio::spawn(async move {
+            // Owns every worker and approval timer, so they stop with this task
+            let mut tasks = JoinSet::new();
+            let webhooks: Vec<Arc<Webhook>> = self
+                .webhooks
+                .iter()
+                .map(|config| {
+                    let (queue, rx) = mpsc::channel(QUEUE_SIZE);
+                    tasks.spawn(deliver_queued(
+                        self.http.clone(),
+                        config.clone(),
+                        rx,
+                        self.dead_letters.clone(),
+                    ));
+                    Arc::new(Webhook {
+                        config: config.clone(),
+                        queue,
+                    })
+                })
+                .collect();
+
+            loop {
+                while tasks.try_join_next().is_some() {}
+                let payload = tokio::select! {
+                    event = lifecycle.recv() => match event {
+                        Ok(event) => lifecycle_payload(event),
+                        Err(RecvError::Lagged(n)) => {
+                            warn!(skipped = n, "Webhooks fell behind agent connections");
+                            continue;
+                        }
+                        Err(RecvError::Closed) => break,
+                    },
+                    response = responses.recv() => match response {
+                        Ok(response) => match response.response.event {
+                            Some(message_response::Event::Error(message)) => {
+                                WebhookPayload::new(
+                                    response.agent_id,
+                                    EventDetails::ErrorEvent {
+                                        request_id: response.request_id,
+                                        message,
+                                    },
+                                )
+                            }
+                            Some(message_response::Event::ToolApprovalRequest(request)) => {
+                                for webhook in &webhooks {
+                                    if webhook.config.wants(EventKind::ApprovalPendingTimeout) {
+                                        tasks.spawn(watch_approval(
+                                            self.store.clone(),
+                                            webhook.clone(),
+                                            self.dead_letters.clone(),
+                                            response.agent_id.clone(),
+                                            response.request_id.clone(),
+                                            request.clone(),
+                                        ));
+                                    }
+                                }
+                                continue;
+                            }
+                            _ => continue,
+                        },
+                        Err(RecvError::Lagged(n)) => {
+                            warn!(skipped = n, "Webhooks fell behind agent responses");
+                            continue;
+                        }
+                        Err(RecvError::Closed) => break,
+                    },
+                    event = initiated.recv() => match event {
+                        Ok(event) => initiated_payload(event),
+                        Err(RecvError::Lagged(n)) => {
+                            warn!(skipped = n, "Webhooks fell behind agent messages");
+                            continue;
+                        }
+                        Err(RecvError::Closed) => break,
+                    },
+                };
+                for webhook in &webhooks {
+                    if webhook.config.wants(payload.details.kind()) {
+                        enqueue(webhook, payload.clone(), &self.dead_letters).await;
+                    }
+                }
+            }
+        })
+    }
+}
+
+fn lifecycle_payload(event: AgentLifecycle) -> WebhookPayload {
+    match event {
+        AgentLifecycle::Connected { agent_id, name } => {
+            WebhookPayload::new(agent_id, EventDetails::AgentConnected { agent_name: name })
+        }
+        AgentLifecycle::Disconnected {
+            agent_id,
+            name,
+            error,
+        } => WebhookPayload::new(
+            agent_id,
+            EventDetails::AgentDisconnected {
+                agent_name: name,
+                unexpected: error.is_some(),
+                reason: error,
+            },
+        ),
+    }
+}
+
+fn initiated_payload(event: AgentInitiatedEvent) -> WebhookPayload {
+    WebhookPayload::new(
+        event.agent_id,
+        EventDetails::AgentInitiated {
+            agent_name: event.agent_name,
+            message_id: event.message_id,
+            content: preview(&event.content),
+            thread_id: event.thread_id,
+        },
+    )
+}
+
+/// Queue `payload` for `webhook`, or log it as a dead letter if the queue
+/// is full
+async fn enqueue(webhook: &Webhook, payload: WebhookPayload, dead_letters: &DeadLetterLog) {
+    if let Err(e) = webhook.queue.try_send(payload) {
+        let payload = match e {
+            mpsc::error::TrySendError::Full(payload)
+            | mpsc::error::TrySendError::Closed(payload) => payload,
+        };
+        dead_letters
+            .append(&webhook.config.url, 0, "delivery queue full", &payload)
+            .await;
+    }
+}
+
+/// Send `approval_pending_timeout` if the approval is still pending once
+/// the webhook's timeout has passed
+async fn watch_approval(
+    store: Store,
+    webhook: Arc<Webhook>,
+    dead_letters: Arc<DeadLetterLog>,
+    agent_id: String,
+    request_id: String,
+    request: ToolApprovalRequest,
+) {
+    let timeout = webhook.config.approval_timeout();
+    tokio::time::sleep(timeout).await;
+    let pending = match store.list_pending_approvals(&agent_id).await {
+        Ok(pending) => pending,
+        Err(e) => {
+            warn!(agent_id = %agent_id, error = %e, "Failed to check pending approvals");
+            return;
+        }
+    };
+    if !pending
+        .iter()
+        .any(|approval| approval.tool_id == request.id)
+    {
+        return;
+    }
+    let payload = WebhookPayload::new(
+        agent_id,
+        EventDetails::ApprovalPendingTimeout {
+            tool_id: request.id,
+            request_id,
+            tool_name: request.name,
+            pending_secs: timeout.as_secs(),
+        },
+    );
+    enqueue(&webhook, payload, &dead_letters).await;
+}
+
+/// Deliver each queued payload in turn, so a webhook sees events in order
+async fn deliver_queued(
+    http: reqwest::Client,
+    config: WebhookConfig,
+    mut queue: mpsc::Receiver<WebhookPayload>,
+    dead_letters: Arc<DeadLetterLog>,
+) {
+    while let Some(payload) = queue.recv().await {
+        if let Err((attempts, error)) = deliver(&http, &config, &payload).await {
+            dead_letters
+                .append(&config.url, attempts, &error, &payload)
+                .await;
+        }
+    }
+}
+
+/// POST `payload` until it's accepted or `config.retry` gives up. Returns
+/// the attempts made and the last error on failure.
+async fn deliver(
+    http: &reqwest::Client,
+    config: &WebhookConfig,
+    payload: &WebhookPayload,
+) -> Result<(), (u32, String)> {
+    let body = serde_json::to_vec(payload).map_err(|e| (0, e.to_string()))?;
+    let mut attempt = 0;
+    loop {
+        attempt += 1;
+        let timestamp = Utc::now().timestamp();
+        let mut request = http
+            .post(&config.url)
+            .timeout(DELIVERY_TIMEOUT)
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .header(EVENT_HEADER, payload.details.kind().as_str())
+            .header(DELIVERY_HEADER, &payload.id)
+            .header(TIMESTAMP_HEADER, timestamp.to_string());
+        if let Some(secret) = &config.secret {
+            request = request.header(SIGNATURE_HEADER, sign(secret, timestamp, &body));
+        }
+        let (error, retryable) = match request.body(body.clone()).send().await {
+            Ok(response) if response.status().is_success() => {
+                debug!(
+                    event = %payload.details.kind(),
+                    id = %payload.id,
+                    attempt,
+                    "Webhook delivered"
+                );
+                return Ok(());
+            }
+            Ok(response) => {
+                let status = response.status();
+                // Other client errors won't go away by sending the same request again
+                let retryable = status.is_server_error()
+                    || status == reqwest::StatusCode::REQUEST_TIMEOUT
+                    || status == reqwest::StatusCode::TOO_MANY_REQUESTS;
+                (format!("webhook answered {}", status), retryable)
+            }
+            Err(e) => (e.to_string(), true),
+        };
+        if !retryable || attempt >= config.retry.max_attempts {
+            return Err((attempt, error));
+        }
+        debug!(
+            event = %payload.details.kind(),
+            id = %payload.id,
+            attempt,
+            error = %error,
+            "Webhook delivery failed; retrying"
+        );
+        tokio::time::sleep(config.retry.backoff(attempt)).await;
+    }
+}
+
+/// First `PREVIEW_CHARS` characters of `text`, with an ellipsis if cut
+fn preview(text: &str) -> String {
+    match text.char_indices().nth(PREVIEW_CHARS) {
+        Some((end, _)) => format!("{}…", &text[..end]),
+        None => text.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backoff_doubles_up_to_the_cap() {
+        let retry = RetryPolicy {
+            max_attempts: 10,
+            initial_backoff_ms: 100,
+            max_backoff_ms: 1_000,
+        };
+        let waits: Vec<u64> = (1..=6)
+            .map(|attempt| retry.backoff(attempt).as_millis() as u64)
+            .collect();
+        assert_eq!(waits, [100, 200, 400, 800, 1_000, 1_000]);
+        assert_eq!(retry.backoff(200), Duration::from_millis(1_000));
+    }
+
+    #[test]
+    fn test_sign_and_verify() {
+        let signature = sign("secret", 1_700_000_000, b"{}");
+        assert!(signature.starts_with("sha256="));
+        assert!(verify("secret", 1_700_000_000, b"{}", &signature));
+        assert!(!verify("other", 1_700_000_000, b"{}", &signature));
+        assert!(!verify("secret", 1_700_000_001, b"{}", &signature));
+        assert!(!verify("secret", 1_700_000_000, b"{ }", &signature));
+        assert!(!verify("secret", 1_700_000_000, b"{}", "sha256=zz"));
+    }
+
+    #[test]
+    fn test_payload_schema() {
+        let payload = WebhookPayload::new(
+            "agent-1",
+            EventDetails::AgentDisconnected {
+                agent_name: "Agent One".to_string(),
+                unexpected: true,
+                reason: Some("connection reset".to_string()),
+            },
+        );
+        let json = serde_json::to_value(&payload).unwrap();
+        assert_eq!(json["event"], "agent_disconnected");
+        assert_eq!(json["agent_id"], "agent-1");
+        assert_eq!(json["unexpected"], true);
+        assert_eq!(
+            json["text"],
+            "agent-1 disconnected unexpectedly: connection reset"
+        );
+        let parsed: WebhookPayload = serde_json::from_value(json).unwrap();
+        assert_eq!(parsed, payload);
+    }
+
+    #[test]
+    fn test_load_webhooks_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("webhooks.toml");
+        std::fs::write(
+            &path,
+            r#"
+            [[webhook]]
+            url = "https://hooks.example.com/a"
+            events = ["agent_disconnected", "approval_pending_timeout"]
+            secret = "s3cret"
+
+            [webhook.retry]
+            max_attempts = 3
+
+            [[webhook]]
+            url = "http://127.0.0.1:9000/all"
+            "#,
+        )
+        .unwrap();
+        let webhooks = WebhookConfig::load(&path).unwrap();
+        assert_eq!(webhooks.len(), 2);
+        assert!(webhooks[0].wants(EventKind::AgentDisconnected));
+        assert!(!webhooks[0].wants(EventKind::AgentConnected));
+        assert_eq!(webhooks[0].retry.max_attempts, 3);
+        assert_eq!(webhooks[0].retry.initial_backoff_ms, 1_000);
+        assert_eq!(webhooks[0].approval_timeout(), Duration::from_secs(600));
+        assert!(EventKind::ALL.iter().all(|kind| webhooks[1].wants(*kind)));
+
+        for bad in [
+            "[[webhook]]\nurl = \"ftp://example.com\"\n",
+            "[[webhook]]\nurl = \"http://example.com\"\nevents = [\"agent_sneezed\"]\n",
+            "[[webhook]]\nurl = \"http://example.com\"\n[webhook.retry]\nmax_attempts = 0\n",
+        ] {
+            std::fs::write(&path, bad).unwrap();
+            assert!(WebhookConfig::load(&path).is_err(), "{bad}");
+        }
+    }
+}
@@ -0,0 +1,342 @@
+// ABOUTME: End-to-end test of the admin GetAgent RPC against the local gateway.
+// ABOUTME: A fake agent registers, heartbeats and answers; GetAgent reports its metadata, connection, work and activity.
+
+use chrono::Utc;
+use coven_proto::client::{AdminServiceClient, CovenControlClient};
+use coven_proto::server::{AdminServiceServer, CovenControlServer};
+use coven_proto::{
+    agent_message, message_response, AgentMessage, AgentMetadata, GetAgentRequest,
+    GetAgentResponse, Heartbeat, MessageResponse, RegisterAgent,
+};
+use coven_serve::services::admin::AdminServiceImpl;
+use coven_serve::services::control::{ControlState, CovenControlService, OutboundMessage};
+use coven_serve::store::{Message, Store};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::net::TcpListener;
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::{ReceiverStream, TcpListenerStream};
+use tonic::transport::Channel;
+
+/// Poll GetAgent until `done` accepts the response.
+async fn wait_for_agent(
+    admin: &mut AdminServiceClient<Channel>,
+    request: GetAgentRequest,
+    done: impl Fn(&GetAgentResponse) -> bool,
+) -> GetAgentResponse {
+    for _ in 0..200 {
+        if let Ok(response) = admin.get_agent(request.clone()).await {
+            let response = response.into_inner();
+            if done(&response) {
+                return response;
+            }
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    panic!("timed out waiting for agent {}", request.agent_id);
+}
+
+/// Start a local gateway with the control and admin services, returning
+/// its URL, store and shared state.
+async fn start_gateway(dir: &std::path::Path) -> (String, Store, Arc<ControlState>) {
+    let store = Store::open(&dir.join("gateway.db")).await.unwrap();
+    let control_state = ControlState::new(store.clone(), None);
+
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    {
+        let store = store.clone();
+        let control_state = control_state.clone();
+        tokio::spawn(async move {
+            tonic::transport::Server::builder()
+                .add_service(CovenControlServer::new(CovenControlService::new(
+                    control_state.clone(),
+                )))
+                .add_service(AdminServiceServer::new(AdminServiceImpl::new(
+                    store,
+                    control_state,
+                )))
+                .serve_with_incoming(TcpListenerStream::new(listener))
+                .await
+                .unwrap();
+        });
+    }
+    (url, store, control_state)
+}
+
+fn agent_request() -> GetAgentRequest {
+    GetAgentRequest {
+        agent_id: "agent-1".to_string(),
+        activity_limit: 0,
+    }
+}
+
+#[tokio::test]
+async fn test_get_agent_reports_connection_and_activity() {
+    let dir = tempfile::tempdir().unwrap();
+    let (url, store, _control_state) = start_gateway(dir.path()).await;
+
+    let (agent_tx, agent_rx) = mpsc::channel(8);
+    agent_tx
+        .send(AgentMessage {
+            payload: Some(agent_message::Payload::Register(RegisterAgent {
+                agent_id: "agent-1".to_string(),
+                name: "Agent One".to_string(),
+                metadata: Some(AgentMetadata {
+                    working_directory: "/src/coven".to_string(),
+                    hostname: "build-box".to_string(),
+                    workspaces: vec!["coven".to_string()],
+                    backend: "direct".to_string(),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            })),
+        })
+        .await
+        .unwrap();
+    let mut control = CovenControlClient::connect(url.clone()).await.unwrap();
+    let _inbound = control
+        .agent_stream(ReceiverStream::new(agent_rx))
+        .await
+        .unwrap();
+
+    store.get_or_create_conversation("agent-1").await.unwrap();
+    for (i, content) in ["hello", "hi there\nhow can I help?"].iter().enumerate() {
+        store
+            .save_message(&Message {
+                id: format!("msg-{}", i),
+                conversation_id: "agent-1".to_string(),
+                direction: if i == 0 { "inbound" } else { "outbound" }.to_string(),
+                author: if i == 0 { "user" } else { "agent" }.to_string(),
+                content: content.to_string(),
+                message_type: "message".to_string(),
+                reply_to_message_id: None,
+                created_at: Utc::now() + chrono::Duration::seconds(i as i64),
+            })
+            .await
+            .unwrap();
+    }
+
+    agent_tx
+        .send(AgentMessage {
+            payload: Some(agent_message::Payload::Heartbeat(Heartbeat {
+                timestamp_ms: Utc::now().timestamp_millis(),
+            })),
+        })
+        .await
+        .unwrap();
+
+    let mut admin = AdminServiceClient::connect(url).await.unwrap();
+    let request = agent_request();
+    let detail = wait_for_agent(&mut admin, request.clone(), |r| {
+        r.connection
+            .as_ref()
+            .is_some_and(|c| c.last_heartbeat.is_some())
+    })
+    .await;
+
+    let agent = detail.agent.unwrap();
+    assert!(agent.connected);
+    assert_eq!(agent.working_dir, "/src/coven");
+    assert_eq!(agent.metadata.unwrap().hostname, "build-box");
+    let connection = detail.connection.unwrap();
+    assert!(connection.connected_since.is_some());
+    assert!(connection.heartbeat_latency_ms.is_some());
+    assert_eq!(detail.queued_messages, 0);
+
+    let previews: Vec<_> = detail
+        .recent_activity
+        .iter()
+        .map(|a| a.preview.as_str())
+        .collect();
+    assert_eq!(previews, vec!["hi there…", "hello"]);
+    assert_eq!(detail.recent_activity[0].thread_id, "agent-1");
+
+    // Once the agent goes away only the stored details are left
+    drop(agent_tx);
+    let detail = wait_for_agent(&mut admin, request, |r| {
+        r.agent.as_ref().is_some_and(|a| !a.connected)
+    })
+    .await;
+    assert_eq!(detail.connection, Some(Default::default()));
+    assert!(detail.agent.unwrap().metadata.is_none());
+    assert!(detail.last_seen.is_some());
+
+    let status = admin
+        .get_agent(GetAgentRequest {
+            agent_id: "missing".to_string(),
+            activity_limit: 0,
+        })
+        .await
+        .unwrap_err();
+    assert_eq!(status.code(), tonic::Code::NotFound);
+}
+
+#[tokio::test]
+async fn test_get_agent_reports_capabilities_work_and_errors() {
+    let dir = tempfile::tempdir().unwrap();
+    let (url, _store, control_state) = start_gateway(dir.path()).await;
+
+    let (agent_tx, agent_rx) = mpsc::channel(8);
+    agent_tx
+        .send(AgentMessage {
+            payload: Some(agent_message::Payload::Register(RegisterAgent {
+                agent_id: "agent-1".to_string(),
+                name: "Agent One".to_string(),
+                capabilities: vec!["base".to_string(), "chat".to_string()],
+                protocol_features: vec!["pack_tools".to_string()],
+                ..Default::default()
+            })),
+        })
+        .await
+        .unwrap();
+    let mut control = CovenControlClient::connect(url.clone()).await.unwrap();
+    let _inbound = control
+        .agent_stream(ReceiverStream::new(agent_rx))
+        .await
+        .unwrap();
+
+    let mut admin = AdminServiceClient::connect(url).await.unwrap();
+    let detail = wait_for_agent(&mut admin, agent_request(), |r| {
+        r.agent.as_ref().is_some_and(|a| a.connected)
+    })
+    .await;
+    let connection = detail.connection.unwrap();
+    assert_eq!(connection.capabilities, vec!["base", "chat"]);
+    assert_eq!(connection.protocol_features, vec!["pack_tools"]);
+    assert!(connection.active_requests.is_empty());
+    assert_eq!(connection.recent_errors, 0);
+
+    // A message the agent hasn't answered shows up as active work
+    for request_id in ["req-1", "req-2"] {
+        control_state
+            .send_to_agent(OutboundMessage {
+                agent_id: "agent-1".to_string(),
+                request_id: request_id.to_string(),
+                thread_id: "thread-1".to_string(),
+                sender: "user".to_string(),
+                content: "hello".to_string(),
+                sender_display: None,
+                sender_platform_id: None,
+                sender_platform: None,
+                model: None,
+                max_tokens: None,
+                reply_to_message_id: None,
+                attachments: vec![],
+            })
+            .await
+            .unwrap();
+    }
+    let detail = admin.get_agent(agent_request()).await.unwrap().into_inner();
+    let active: Vec<_> = detail
+        .connection
+        .unwrap()
+        .active_requests
+        .into_iter()
+        .map(|r| (r.request_id, r.thread_id))
+        .collect();
+    assert_eq!(
+        active,
+        vec![
+            ("req-1".to_string(), "thread-1".to_string()),
+            ("req-2".to_string(), "thread-1".to_string())
+        ]
+    );
+
+    // An error finishes its request and counts as a recent error
+    agent_tx
+        .send(AgentMessage {
+            payload: Some(agent_message::Payload::Response(MessageResponse {
+                request_id: "req-1".to_string(),
+                event: Some(message_response::Event::Error("boom".to_string())),
+            })),
+        })
+        .await
+        .unwrap();
+    let detail = wait_for_agent(&mut admin, agent_request(), |r| {
+        r.connection.as_ref().is_some_and(|c| c.recent_errors == 1)
+    })
+    .await;
+    let connection = detail.connection.unwrap();
+    assert_eq!(connection.active_requests.len(), 1);
+    assert_eq!(connection.active_requests[0].request_id, "req-2");
+}
+
+#[tokio::test]
+async fn test_an_immediate_answer_leaves_no_active_request() {
+    let dir = tempfile::tempdir().unwrap();
+    let (url, _store, control_state) = start_gateway(dir.path()).await;
+
+    let (agent_tx, agent_rx) = mpsc::channel(8);
+    agent_tx
+        .send(AgentMessage {
+            payload: Some(agent_message::Payload::Register(RegisterAgent {
+                agent_id: "agent-1".to_string(),
+                name: "Agent One".to_string(),
+                ..Default::default()
+            })),
+        })
+        .await
+        .unwrap();
+    let mut control = CovenControlClient::connect(url.clone()).await.unwrap();
+    let mut inbound = control
+        .agent_stream(ReceiverStream::new(agent_rx))
+        .await
+        .unwrap()
+        .into_inner();
+
+    let mut admin = AdminServiceClient::connect(url).await.unwrap();
+    wait_for_agent(&mut admin, agent_request(), |r| {
+        r.agent.as_ref().is_some_and(|a| a.connected)
+    })
+    .await;
+
+    // The agent answers each message the moment it arrives
+    tokio::spawn(async move {
+        while let Ok(Some(message)) = inbound.message().await {
+            if let Some(coven_proto::server_message::Payload::SendMessage(send)) = message.payload {
+                let done = MessageResponse {
+                    request_id: send.request_id,
+                    event: Some(message_response::Event::Done(Default::default())),
+                };
+                let done = AgentMessage {
+                    payload: Some(agent_message::Payload::Response(done)),
+                };
+                if agent_tx.send(done).await.is_err() {
+                    break;
+                }
+            }
+        }
+    });
+
+    let mut responses = control_state.subscribe_responses();
+    for i in 0..20 {
+        control_state
+            .send_to_agent(OutboundMessage {
+                agent_id: "agent-1".to_string(),
+                request_id: format!("req-{}", i),
+                thread_id: "thread-1".to_string(),
+                sender: "user".to_string(),
+                content: "hello".to_string(),
+                sender_display: None,
+                sender_platform_id: None,
+                sender_platform: None,
+                model: None,
+                max_tokens: None,
+                reply_to_message_id: None,
+                attachments: vec![],
+            })
+            .await
+            .unwrap();
+    }
+    for _ in 0..20 {
+        tokio::time::timeout(Duration::from_secs(5), responses.recv())
+            .await
+            .expect("answer")
+            .unwrap();
+    }
+
+    // Each request was tracked before it went out, so its answer found it
+    let detail = wait_for_agent(&mut admin, agent_request(), |r| r.connection.is_some()).await;
+    assert!(detail.connection.unwrap().active_requests.is_empty());
+}
@@ -0,0 +1,115 @@
+// ABOUTME: End-to-end test of the admin SetAgentLogLevel RPC against the local gateway.
+// ABOUTME: A fake agent registers and is sent valid filters; bad filters and offline agents are refused.
+
+use coven_proto::client::{AdminServiceClient, CovenControlClient};
+use coven_proto::server::{AdminServiceServer, CovenControlServer};
+use coven_proto::{
+    agent_message, server_message, AgentMessage, RegisterAgent, ServerMessage,
+    SetAgentLogLevelRequest,
+};
+use coven_serve::services::admin::AdminServiceImpl;
+use coven_serve::services::control::{ControlState, CovenControlService};
+use coven_serve::store::Store;
+use std::time::Duration;
+use tokio::net::TcpListener;
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::{ReceiverStream, TcpListenerStream};
+use tonic::{Code, Streaming};
+
+async fn next_message(inbound: &mut Streaming<ServerMessage>) -> server_message::Payload {
+    tokio::time::timeout(Duration::from_secs(10), inbound.message())
+        .await
+        .expect("timed out waiting for the gateway")
+        .unwrap()
+        .expect("gateway closed the stream")
+        .payload
+        .unwrap()
+}
+
+fn set_level(agent_id: &str, filter: &str) -> SetAgentLogLevelRequest {
+    SetAgentLogLevelRequest {
+        agent_id: agent_id.to_string(),
+        filter: filter.to_string(),
+    }
+}
+
+#[tokio::test]
+async fn test_log_level_reaches_the_agent() {
+    let dir = tempfile::tempdir().unwrap();
+    let store = Store::open(&dir.path().join("gateway.db")).await.unwrap();
+    let control_state = ControlState::new(store.clone(), None);
+
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    tokio::spawn(async move {
+        tonic::transport::Server::builder()
+            .add_service(CovenControlServer::new(CovenControlService::new(
+                control_state.clone(),
+            )))
+            .add_service(AdminServiceServer::new(AdminServiceImpl::new(
+                store,
+                control_state,
+            )))
+            .serve_with_incoming(TcpListenerStream::new(listener))
+            .await
+            .unwrap();
+    });
+
+    let (agent_tx, agent_rx) = mpsc::channel(8);
+    agent_tx
+        .send(AgentMessage {
+            payload: Some(agent_message::Payload::Register(RegisterAgent {
+                agent_id: "agent-1".to_string(),
+                name: "Agent One".to_string(),
+                ..Default::default()
+            })),
+        })
+        .await
+        .unwrap();
+    let mut control = CovenControlClient::connect(url.clone()).await.unwrap();
+    let mut inbound = control
+        .agent_stream(ReceiverStream::new(agent_rx))
+        .await
+        .unwrap()
+        .into_inner();
+    assert!(matches!(
+        next_message(&mut inbound).await,
+        server_message::Payload::Welcome(_)
+    ));
+
+    let mut admin = AdminServiceClient::connect(url).await.unwrap();
+    admin
+        .set_agent_log_level(set_level("agent-1", " coven_agent=debug,warn "))
+        .await
+        .unwrap();
+    let server_message::Payload::SetLogLevel(sent) = next_message(&mut inbound).await else {
+        panic!("expected a log level change");
+    };
+    assert_eq!(sent.filter, "coven_agent=debug,warn");
+
+    // Filters are checked before they're sent, so the agent never sees these
+    for filter in ["", "coven_agent=loud"] {
+        let err = admin
+            .set_agent_log_level(set_level("agent-1", filter))
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), Code::InvalidArgument, "{filter:?}");
+    }
+
+    let err = admin
+        .set_agent_log_level(set_level("agent-2", "debug"))
+        .await
+        .unwrap_err();
+    assert_eq!(err.code(), Code::NotFound);
+
+    // Still connected, with nothing else queued
+    admin
+        .set_agent_log_level(set_level("agent-1", "info"))
+        .await
+        .unwrap();
+    let server_message::Payload::SetLogLevel(sent) = next_message(&mut inbound).await else {
+        panic!("expected a log level change");
+    };
+    assert_eq!(sent.filter, "info");
+    drop(agent_tx);
+}
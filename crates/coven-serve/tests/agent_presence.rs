@@ -0,0 +1,178 @@
+// ABOUTME: End-to-end test of agent presence through the local gateway.
+// ABOUTME: A fake agent registers, changes its status, and clients see it in ListAgents and WatchAgents.
+
+use coven_proto::client::{ClientServiceClient, CovenControlClient};
+use coven_proto::server::{ClientServiceServer, CovenControlServer};
+use coven_proto::{
+    agent_message, AgentMessage, AgentPresence, ListAgentsRequest, ListAgentsResponse,
+    RegisterAgent, ServerMessage,
+};
+use coven_serve::services::client::ClientServiceImpl;
+use coven_serve::services::control::{ControlState, CovenControlService};
+use coven_serve::store::Store;
+use std::time::Duration;
+use tokio::net::TcpListener;
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::{ReceiverStream, TcpListenerStream};
+use tonic::transport::Channel;
+
+/// Poll ListAgents until the agent's presence matches `expected`.
+async fn wait_for_presence(
+    client: &mut ClientServiceClient<Channel>,
+    agent_id: &str,
+    expected: &AgentPresence,
+) {
+    for _ in 0..200 {
+        let agents = client
+            .list_agents(ListAgentsRequest { workspace: None })
+            .await
+            .unwrap()
+            .into_inner()
+            .agents;
+        let presence = agents
+            .into_iter()
+            .find(|a| a.id == agent_id)
+            .and_then(|a| a.metadata)
+            .and_then(|m| m.presence);
+        if presence.as_ref() == Some(expected) {
+            return;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    panic!("timed out waiting for presence {:?}", expected);
+}
+
+/// Start a local gateway with the control and client services, returning its URL.
+async fn start_gateway(dir: &std::path::Path) -> String {
+    let store = Store::open(&dir.join("gateway.db")).await.unwrap();
+    let control_state = ControlState::new(store.clone(), None);
+
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    tokio::spawn(async move {
+        tonic::transport::Server::builder()
+            .add_service(CovenControlServer::new(CovenControlService::new(
+                control_state.clone(),
+            )))
+            .add_service(ClientServiceServer::new(ClientServiceImpl::new(
+                store,
+                control_state,
+            )))
+            .serve_with_incoming(TcpListenerStream::new(listener))
+            .await
+            .unwrap();
+    });
+    url
+}
+
+/// Register a fake agent without a presence. The agent stays connected
+/// until the returned sender is dropped.
+async fn connect_agent(
+    url: &str,
+    agent_id: &str,
+) -> (mpsc::Sender<AgentMessage>, tonic::Streaming<ServerMessage>) {
+    let (agent_tx, agent_rx) = mpsc::channel(8);
+    agent_tx
+        .send(AgentMessage {
+            payload: Some(agent_message::Payload::Register(RegisterAgent {
+                agent_id: agent_id.to_string(),
+                name: "Agent One".to_string(),
+                ..Default::default()
+            })),
+        })
+        .await
+        .unwrap();
+    let mut control = CovenControlClient::connect(url.to_string()).await.unwrap();
+    let inbound = control
+        .agent_stream(ReceiverStream::new(agent_rx))
+        .await
+        .unwrap()
+        .into_inner();
+    (agent_tx, inbound)
+}
+
+/// Read WatchAgents updates until one satisfies `matches`.
+async fn wait_for_update(
+    updates: &mut tonic::Streaming<ListAgentsResponse>,
+    matches: impl Fn(&ListAgentsResponse) -> bool,
+) {
+    let wait = async {
+        while let Some(update) = updates.message().await.unwrap() {
+            if matches(&update) {
+                return;
+            }
+        }
+        panic!("WatchAgents stream ended");
+    };
+    tokio::time::timeout(Duration::from_secs(5), wait)
+        .await
+        .expect("timed out waiting for an agent update");
+}
+
+#[tokio::test]
+async fn test_presence_defaults_to_available_and_updates() {
+    let dir = tempfile::tempdir().unwrap();
+    let url = start_gateway(dir.path()).await;
+    let (agent_tx, _inbound) = connect_agent(&url, "agent-1").await;
+
+    let mut client = ClientServiceClient::connect(url).await.unwrap();
+    wait_for_presence(&mut client, "agent-1", &AgentPresence::available()).await;
+
+    // The agent goes busy
+    let busy = AgentPresence {
+        status: "busy".to_string(),
+        emoji: "🔥".to_string(),
+    };
+    agent_tx
+        .send(AgentMessage {
+            payload: Some(agent_message::Payload::UpdatePresence(busy.clone())),
+        })
+        .await
+        .unwrap();
+    wait_for_presence(&mut client, "agent-1", &busy).await;
+}
+
+#[tokio::test]
+async fn test_watch_agents_streams_changes() {
+    let dir = tempfile::tempdir().unwrap();
+    let url = start_gateway(dir.path()).await;
+    let mut client = ClientServiceClient::connect(url.clone()).await.unwrap();
+    let mut updates = client
+        .watch_agents(ListAgentsRequest { workspace: None })
+        .await
+        .unwrap()
+        .into_inner();
+
+    // The first update is the list as it stands
+    let first = updates.message().await.unwrap().unwrap();
+    assert!(first.agents.is_empty());
+
+    let (agent_tx, _inbound) = connect_agent(&url, "agent-1").await;
+    wait_for_update(&mut updates, |u| {
+        u.agents.iter().any(|a| a.id == "agent-1" && a.connected)
+    })
+    .await;
+
+    let busy = AgentPresence {
+        status: "busy".to_string(),
+        emoji: String::new(),
+    };
+    agent_tx
+        .send(AgentMessage {
+            payload: Some(agent_message::Payload::UpdatePresence(busy.clone())),
+        })
+        .await
+        .unwrap();
+    wait_for_update(&mut updates, |u| {
+        u.agents
+            .iter()
+            .any(|a| a.metadata.as_ref().and_then(|m| m.presence.as_ref()) == Some(&busy))
+    })
+    .await;
+
+    drop(agent_tx);
+    wait_for_update(&mut updates, |u| {
+        u.agents.iter().any(|a| a.id == "agent-1" && !a.connected)
+    })
+    .await;
+}
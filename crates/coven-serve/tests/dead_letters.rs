@@ -0,0 +1,120 @@
+// ABOUTME: End-to-end test of replaying the dead-letter queue through the local gateway.
+// ABOUTME: Messages queued while an agent was offline reach it, overrides intact, before anything sent after it reconnects.
+
+use coven_proto::client::CovenControlClient;
+use coven_proto::server::CovenControlServer;
+use coven_proto::{agent_message, server_message, AgentMessage, RegisterAgent, ServerMessage};
+use coven_serve::services::control::{ControlState, CovenControlService, OutboundMessage};
+use coven_serve::store::Store;
+use coven_serve::DeadLetterConfig;
+use std::time::Duration;
+use tokio::net::TcpListener;
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::{ReceiverStream, TcpListenerStream};
+use tonic::Streaming;
+
+const AGENT_ID: &str = "agent-1";
+
+async fn next_message(inbound: &mut Streaming<ServerMessage>) -> server_message::Payload {
+    tokio::time::timeout(Duration::from_secs(10), inbound.message())
+        .await
+        .expect("timed out waiting for the gateway")
+        .unwrap()
+        .expect("gateway closed the stream")
+        .payload
+        .unwrap()
+}
+
+fn message(request_id: &str) -> OutboundMessage {
+    OutboundMessage {
+        agent_id: AGENT_ID.to_string(),
+        request_id: request_id.to_string(),
+        thread_id: "thread-1".to_string(),
+        sender: "user".to_string(),
+        content: format!("message {}", request_id),
+        sender_display: None,
+        sender_platform_id: None,
+        sender_platform: None,
+        model: None,
+        max_tokens: None,
+        reply_to_message_id: None,
+        attachments: vec![],
+    }
+}
+
+#[tokio::test]
+async fn test_queued_messages_arrive_before_new_ones() {
+    let dir = tempfile::tempdir().unwrap();
+    let store = Store::open(&dir.path().join("gateway.db")).await.unwrap();
+    let control_state = ControlState::new(store, Some(DeadLetterConfig::default()));
+
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    {
+        let control_state = control_state.clone();
+        tokio::spawn(async move {
+            tonic::transport::Server::builder()
+                .add_service(CovenControlServer::new(CovenControlService::new(
+                    control_state,
+                )))
+                .serve_with_incoming(TcpListenerStream::new(listener))
+                .await
+                .unwrap();
+        });
+    }
+
+    // Queued while the agent is offline, the first with overrides
+    control_state
+        .queue_dead_letter(OutboundMessage {
+            model: Some("claude-haiku-4-5".to_string()),
+            max_tokens: Some(1024),
+            ..message("queued-1")
+        })
+        .await
+        .unwrap();
+    for request_id in ["queued-2", "queued-3"] {
+        control_state
+            .queue_dead_letter(message(request_id))
+            .await
+            .unwrap();
+    }
+
+    let (agent_tx, agent_rx) = mpsc::channel(8);
+    agent_tx
+        .send(AgentMessage {
+            payload: Some(agent_message::Payload::Register(RegisterAgent {
+                agent_id: AGENT_ID.to_string(),
+                name: "Agent One".to_string(),
+                ..Default::default()
+            })),
+        })
+        .await
+        .unwrap();
+    let mut control = CovenControlClient::connect(url).await.unwrap();
+    let mut inbound = control
+        .agent_stream(ReceiverStream::new(agent_rx))
+        .await
+        .unwrap()
+        .into_inner();
+    assert!(matches!(
+        next_message(&mut inbound).await,
+        server_message::Payload::Welcome(_)
+    ));
+
+    // Sent as soon as the agent is back, racing the replay on connect
+    control_state.deliver(message("new")).await.unwrap();
+
+    let mut received = Vec::new();
+    while received.len() < 4 {
+        if let server_message::Payload::SendMessage(sent) = next_message(&mut inbound).await {
+            received.push(sent);
+        }
+    }
+    let ids: Vec<_> = received.iter().map(|m| m.request_id.as_str()).collect();
+    assert_eq!(ids, vec!["queued-1", "queued-2", "queued-3", "new"]);
+
+    // Replayed messages keep the overrides they were sent with
+    assert_eq!(received[0].model.as_deref(), Some("claude-haiku-4-5"));
+    assert_eq!(received[0].max_tokens, Some(1024));
+    assert_eq!(received[1].model, None);
+}
@@ -0,0 +1,219 @@
+// ABOUTME: Tests for running the local gateway in-process with Server::start.
+// ABOUTME: Starts real gateways on ephemeral ports, runs a client flow, and shuts them down.
+
+use coven_proto::client::{ClientServiceClient, CovenControlClient};
+use coven_proto::{
+    agent_message, server_message, AgentMessage, ListAgentsRequest, RegisterAgent,
+    StreamEventsRequest,
+};
+use coven_serve::{ServeConfig, Server};
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
+
+fn config(dir: &tempfile::TempDir) -> ServeConfig {
+    ServeConfig {
+        grpc_addr: "127.0.0.1:0".to_string(),
+        db_path: dir.path().join("gateway.db"),
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn test_embedded_gateway_serves_clients_and_shuts_down() {
+    let dir = tempfile::tempdir().unwrap();
+    let server = Server::start(config(&dir)).await.unwrap();
+    assert_ne!(server.local_addr().unwrap().port(), 0);
+    let url = server.url();
+
+    // An agent connects and is welcomed
+    let (agent_tx, agent_rx) = mpsc::channel(8);
+    agent_tx
+        .send(AgentMessage {
+            payload: Some(agent_message::Payload::Register(RegisterAgent {
+                agent_id: "agent-1".to_string(),
+                name: "Agent One".to_string(),
+                ..Default::default()
+            })),
+        })
+        .await
+        .unwrap();
+    let mut control = CovenControlClient::connect(url.clone()).await.unwrap();
+    let mut inbound = control
+        .agent_stream(ReceiverStream::new(agent_rx))
+        .await
+        .unwrap()
+        .into_inner();
+    let welcome = tokio::time::timeout(Duration::from_secs(10), inbound.message())
+        .await
+        .unwrap()
+        .unwrap()
+        .unwrap();
+    assert!(matches!(
+        welcome.payload,
+        Some(server_message::Payload::Welcome(_))
+    ));
+
+    // A client sees it
+    let mut client = ClientServiceClient::connect(url.clone()).await.unwrap();
+    let agents = client
+        .list_agents(ListAgentsRequest { workspace: None })
+        .await
+        .unwrap()
+        .into_inner()
+        .agents;
+    assert_eq!(agents.len(), 1);
+    assert_eq!(agents[0].id, "agent-1");
+
+    // Shutting down frees the port
+    drop((agent_tx, inbound, control, client));
+    tokio::time::timeout(Duration::from_secs(10), server.shutdown())
+        .await
+        .expect("shutdown hung")
+        .unwrap();
+    assert!(ClientServiceClient::connect(url).await.is_err());
+}
+
+#[tokio::test]
+async fn test_shutdown_ends_open_streams() {
+    let dir = tempfile::tempdir().unwrap();
+    let server = Server::start(config(&dir)).await.unwrap();
+    let url = server.url();
+
+    // An agent and a client stay connected through the shutdown
+    let (agent_tx, agent_rx) = mpsc::channel(8);
+    agent_tx
+        .send(AgentMessage {
+            payload: Some(agent_message::Payload::Register(RegisterAgent {
+                agent_id: "agent-1".to_string(),
+                name: "Agent One".to_string(),
+                ..Default::default()
+            })),
+        })
+        .await
+        .unwrap();
+    let mut control = CovenControlClient::connect(url.clone()).await.unwrap();
+    let mut inbound = control
+        .agent_stream(ReceiverStream::new(agent_rx))
+        .await
+        .unwrap()
+        .into_inner();
+    inbound.message().await.unwrap().unwrap();
+    let mut client = ClientServiceClient::connect(url).await.unwrap();
+    let mut events = client
+        .stream_events(StreamEventsRequest {
+            conversation_key: "agent-1".to_string(),
+            ..Default::default()
+        })
+        .await
+        .unwrap()
+        .into_inner();
+
+    tokio::time::timeout(Duration::from_secs(10), server.shutdown())
+        .await
+        .expect("shutdown hung on open streams")
+        .unwrap();
+    assert!(!matches!(inbound.message().await, Ok(Some(_))));
+    assert!(!matches!(events.message().await, Ok(Some(_))));
+    drop(agent_tx);
+}
+
+#[tokio::test]
+async fn test_ephemeral_ports_are_distinct() {
+    let (first_dir, second_dir) = (tempfile::tempdir().unwrap(), tempfile::tempdir().unwrap());
+    let first = Server::start(config(&first_dir)).await.unwrap();
+    let second = Server::start(config(&second_dir)).await.unwrap();
+    assert_ne!(first.local_addr(), second.local_addr());
+
+    first.shutdown().await.unwrap();
+    second.shutdown().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_dropping_the_handle_stops_the_server() {
+    let dir = tempfile::tempdir().unwrap();
+    let server = Server::start(config(&dir)).await.unwrap();
+    let url = server.url();
+    drop(server);
+
+    let mut refused = false;
+    for _ in 0..100 {
+        if ClientServiceClient::connect(url.clone()).await.is_err() {
+            refused = true;
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    assert!(refused, "server still accepting connections after drop");
+}
+
+#[tokio::test]
+async fn test_start_reports_bind_errors() {
+    let dir = tempfile::tempdir().unwrap();
+    let server = Server::start(config(&dir)).await.unwrap();
+
+    let taken = ServeConfig {
+        grpc_addr: server.local_addr().unwrap().to_string(),
+        ..config(&dir)
+    };
+    let err = Server::start(taken).await.err().unwrap();
+    assert!(err.to_string().starts_with("binding gRPC address"));
+
+    server.shutdown().await.unwrap();
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn test_unix_socket_listener() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let dir = tempfile::tempdir().unwrap();
+    let socket = dir.path().join("run").join("gateway.sock");
+    // A socket file left behind by a gateway that crashed
+    std::fs::create_dir_all(socket.parent().unwrap()).unwrap();
+    drop(std::os::unix::net::UnixListener::bind(&socket).unwrap());
+
+    let server = Server::start(ServeConfig {
+        grpc_addr: format!("unix://{}", socket.display()),
+        socket_mode: 0o660,
+        ..config(&dir)
+    })
+    .await
+    .unwrap();
+    assert_eq!(server.url(), format!("unix://{}", socket.display()));
+    let mode = std::fs::metadata(&socket).unwrap().permissions().mode();
+    assert_eq!(mode & 0o777, 0o660);
+    assert_eq!(server.local_addr(), None);
+    // Nothing is left of the directory the socket was bound in
+    let entries: Vec<_> = std::fs::read_dir(socket.parent().unwrap())
+        .unwrap()
+        .map(|e| e.unwrap().file_name())
+        .collect();
+    assert_eq!(entries, vec![std::ffi::OsString::from("gateway.sock")]);
+
+    // A second gateway can't take over a live socket
+    let err = Server::start(ServeConfig {
+        grpc_addr: server.url(),
+        ..config(&dir)
+    })
+    .await
+    .err()
+    .unwrap();
+    assert!(err.to_string().contains("already listening"), "{}", err);
+
+    let channel = coven_grpc::create_channel(&coven_grpc::ChannelConfig::new(server.url()))
+        .await
+        .unwrap();
+    let mut client = ClientServiceClient::new(channel);
+    let agents = client
+        .list_agents(ListAgentsRequest { workspace: None })
+        .await
+        .unwrap()
+        .into_inner()
+        .agents;
+    assert!(agents.is_empty());
+
+    drop(client);
+    server.shutdown().await.unwrap();
+    assert!(!socket.exists(), "socket file removed on shutdown");
+}
@@ -0,0 +1,275 @@
+// ABOUTME: Tests the admin ExportThreads RPC against a seeded local gateway store.
+// ABOUTME: Checks record counts across pages, agent/time/state filters, and server-side tool input redaction.
+
+use chrono::{Duration, Utc};
+use coven_proto::client::AdminServiceClient;
+use coven_proto::server::AdminServiceServer;
+use coven_proto::ExportThreadsRequest;
+use coven_serve::export::Redactor;
+use coven_serve::services::admin::AdminServiceImpl;
+use coven_serve::services::control::ControlState;
+use coven_serve::store::{Message, Store, TokenUsage, ToolCall};
+use serde_json::Value;
+use std::sync::Arc;
+use tokio::net::TcpListener;
+use tokio_stream::wrappers::TcpListenerStream;
+use tonic::transport::Channel;
+use tonic::Code;
+
+/// Messages in the busy thread, enough to take several pages
+const BUSY_MESSAGES: usize = 450;
+
+fn message(conversation_id: &str, n: usize, direction: &str, age: Duration) -> Message {
+    Message {
+        id: format!("{}-{}", conversation_id, n),
+        conversation_id: conversation_id.to_string(),
+        direction: direction.to_string(),
+        author: if direction == "inbound" {
+            "alice"
+        } else {
+            "agent"
+        }
+        .to_string(),
+        content: format!("message {}", n),
+        message_type: "message".to_string(),
+        reply_to_message_id: None,
+        created_at: Utc::now() - age,
+    }
+}
+
+/// agent-busy: a long answered thread, with one message from 40 days ago,
+/// two tool calls and a usage update. agent-open: waiting on a reply.
+/// agent-empty: no messages.
+async fn seed(store: &Store) {
+    let busy = store
+        .get_or_create_conversation("agent-busy")
+        .await
+        .unwrap();
+    store
+        .save_message(&message(&busy.id, 0, "inbound", Duration::days(40)))
+        .await
+        .unwrap();
+    for n in 1..BUSY_MESSAGES {
+        let direction = if n % 2 == 1 { "inbound" } else { "outbound" };
+        store
+            .save_message(&message(&busy.id, n, direction, Duration::zero()))
+            .await
+            .unwrap();
+    }
+    for (tool_id, input) in [
+        ("tool-1", r#"{"path": "/tmp/notes.txt"}"#),
+        ("tool-2", r#"{"password": "hunter2"}"#),
+    ] {
+        store
+            .record_tool_call(&ToolCall {
+                agent_id: "agent-busy".to_string(),
+                thread_id: busy.id.clone(),
+                tool_id: tool_id.to_string(),
+                request_id: "req-1".to_string(),
+                tool_name: "Read".to_string(),
+                input_json: input.to_string(),
+                output: None,
+                is_error: false,
+                created_at: Utc::now(),
+                completed_at: None,
+            })
+            .await
+            .unwrap();
+    }
+    assert!(store
+        .record_tool_result("agent-busy", "tool-1", "notes", false)
+        .await
+        .unwrap());
+    store
+        .record_usage(&TokenUsage {
+            agent_id: "agent-busy".to_string(),
+            thread_id: busy.id.clone(),
+            request_id: "req-1".to_string(),
+            input_tokens: 120,
+            output_tokens: 30,
+            created_at: Utc::now(),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+
+    let open = store
+        .get_or_create_conversation("agent-open")
+        .await
+        .unwrap();
+    store
+        .save_message(&message(&open.id, 0, "inbound", Duration::zero()))
+        .await
+        .unwrap();
+
+    store
+        .get_or_create_conversation("agent-empty")
+        .await
+        .unwrap();
+}
+
+async fn start() -> (AdminServiceClient<Channel>, tempfile::TempDir) {
+    let dir = tempfile::tempdir().unwrap();
+    let store = Store::open(&dir.path().join("gateway.db")).await.unwrap();
+    seed(&store).await;
+    let redactor = Redactor::new(&["(?i)password".to_string()]).unwrap();
+    let admin = AdminServiceImpl::new(store.clone(), ControlState::new(store, None))
+        .with_redactor(Arc::new(redactor));
+
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    tokio::spawn(async move {
+        tonic::transport::Server::builder()
+            .add_service(AdminServiceServer::new(admin))
+            .serve_with_incoming(TcpListenerStream::new(listener))
+            .await
+            .unwrap();
+    });
+    (AdminServiceClient::connect(url).await.unwrap(), dir)
+}
+
+async fn export(
+    client: &mut AdminServiceClient<Channel>,
+    request: ExportThreadsRequest,
+) -> Vec<Value> {
+    let mut stream = client.export_threads(request).await.unwrap().into_inner();
+    let mut records = Vec::new();
+    while let Some(record) = stream.message().await.unwrap() {
+        records.push(serde_json::from_str(&record.json).unwrap());
+    }
+    records
+}
+
+fn of_type<'a>(records: &'a [Value], kind: &str) -> Vec<&'a Value> {
+    records.iter().filter(|r| r["type"] == kind).collect()
+}
+
+#[tokio::test]
+async fn test_export_streams_every_record() {
+    let (mut client, _dir) = start().await;
+    let records = export(&mut client, ExportThreadsRequest::default()).await;
+
+    let threads = of_type(&records, "thread");
+    let ids: Vec<_> = threads
+        .iter()
+        .map(|t| t["thread_id"].as_str().unwrap())
+        .collect();
+    assert_eq!(ids, ["agent-busy", "agent-empty", "agent-open"]);
+    let states: Vec<_> = threads
+        .iter()
+        .map(|t| t["state"].as_str().unwrap())
+        .collect();
+    assert_eq!(states, ["answered", "empty", "open"]);
+    assert_eq!(
+        threads[0]["participants"],
+        serde_json::json!(["alice", "agent"])
+    );
+
+    assert_eq!(of_type(&records, "message").len(), BUSY_MESSAGES + 1);
+    assert_eq!(of_type(&records, "tool_call").len(), 2);
+    assert_eq!(of_type(&records, "usage").len(), 1);
+
+    // Each thread's records follow it, messages in order across pages
+    let busy_messages: Vec<_> = records
+        .iter()
+        .skip(1)
+        .take(BUSY_MESSAGES)
+        .map(|r| r["content"].as_str().unwrap().to_string())
+        .collect();
+    let expected: Vec<_> = (0..BUSY_MESSAGES)
+        .map(|n| format!("message {}", n))
+        .collect();
+    assert_eq!(busy_messages, expected);
+}
+
+#[tokio::test]
+async fn test_export_redacts_tool_inputs() {
+    let (mut client, _dir) = start().await;
+    let records = export(&mut client, ExportThreadsRequest::default()).await;
+
+    let calls = of_type(&records, "tool_call");
+    assert_eq!(
+        calls[0]["input"],
+        serde_json::json!({ "path": "/tmp/notes.txt" })
+    );
+    assert_eq!(calls[0]["input_redacted"], false);
+    assert_eq!(calls[0]["output"], "notes");
+    assert_eq!(calls[1]["input"], Value::Null);
+    assert_eq!(calls[1]["input_redacted"], true);
+    assert!(!records.iter().any(|r| r.to_string().contains("hunter2")));
+}
+
+#[tokio::test]
+async fn test_export_filters() {
+    let (mut client, _dir) = start().await;
+
+    let records = export(
+        &mut client,
+        ExportThreadsRequest {
+            agent_id: Some("agent-open".to_string()),
+            ..Default::default()
+        },
+    )
+    .await;
+    assert_eq!(records.len(), 2);
+    assert_eq!(records[0]["thread_id"], "agent-open");
+    assert_eq!(records[1]["type"], "message");
+
+    // The 40-day-old message falls outside the last 30 days
+    let since = (Utc::now() - Duration::days(30)).to_rfc3339();
+    let records = export(
+        &mut client,
+        ExportThreadsRequest {
+            agent_id: Some("agent-busy".to_string()),
+            since: Some(since),
+            ..Default::default()
+        },
+    )
+    .await;
+    assert_eq!(of_type(&records, "message").len(), BUSY_MESSAGES - 1);
+    assert_eq!(of_type(&records, "tool_call").len(), 2);
+
+    // Nothing is older than a year ago
+    let until = (Utc::now() - Duration::days(365)).to_rfc3339();
+    let records = export(
+        &mut client,
+        ExportThreadsRequest {
+            until: Some(until),
+            ..Default::default()
+        },
+    )
+    .await;
+    assert!(records.is_empty());
+
+    for (state, thread) in [("open", "agent-open"), ("empty", "agent-empty")] {
+        let records = export(
+            &mut client,
+            ExportThreadsRequest {
+                state: Some(state.to_string()),
+                ..Default::default()
+            },
+        )
+        .await;
+        let threads = of_type(&records, "thread");
+        assert_eq!(threads.len(), 1, "{state}");
+        assert_eq!(threads[0]["thread_id"], thread);
+    }
+}
+
+#[tokio::test]
+async fn test_export_rejects_bad_filters() {
+    let (mut client, _dir) = start().await;
+    for request in [
+        ExportThreadsRequest {
+            state: Some("archived".to_string()),
+            ..Default::default()
+        },
+        ExportThreadsRequest {
+            since: Some("30d".to_string()),
+            ..Default::default()
+        },
+    ] {
+        let err = client.export_threads(request).await.unwrap_err();
+        assert_eq!(err.code(), Code::InvalidArgument, "{}", err.message());
+    }
+}
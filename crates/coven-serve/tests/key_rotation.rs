@@ -0,0 +1,249 @@
+// ABOUTME: Tests SSH key rotation on the local gateway with a roles file.
+// ABOUTME: The old key signs RotateKey and keeps working through the grace period, then stops.
+
+use coven_proto::client::{AdminServiceClient, ClientServiceClient};
+use coven_proto::{RotateKeyRequest, RotatePrincipalKeyRequest};
+use coven_serve::roles::{PrincipalRoles, MEMBER, OWNER};
+use coven_serve::{RolesConfig, ServeConfig, Server};
+use coven_ssh::{compute_fingerprint, PrivateKey, RotationProof, SshAuthCredentials};
+use std::path::Path;
+use std::time::Duration;
+use tonic::service::interceptor::InterceptedService;
+use tonic::transport::Channel;
+use tonic::{Code, Request, Status};
+
+type Signer = Box<dyn FnMut(Request<()>) -> Result<Request<()>, Status> + Send>;
+
+/// Signs every request with `key`
+fn signer(key: PrivateKey) -> Signer {
+    Box::new(move |mut request: Request<()>| {
+        SshAuthCredentials::new(&key)
+            .and_then(|creds| creds.apply_to_request(&mut request))
+            .map_err(|e| Status::internal(e.to_string()))?;
+        Ok(request)
+    })
+}
+
+async fn channel(url: &str) -> Channel {
+    Channel::from_shared(url.to_string())
+        .unwrap()
+        .connect()
+        .await
+        .unwrap()
+}
+
+async fn client(
+    url: &str,
+    key: &PrivateKey,
+) -> ClientServiceClient<InterceptedService<Channel, Signer>> {
+    ClientServiceClient::with_interceptor(channel(url).await, signer(key.clone()))
+}
+
+async fn admin(
+    url: &str,
+    key: &PrivateKey,
+) -> AdminServiceClient<InterceptedService<Channel, Signer>> {
+    AdminServiceClient::with_interceptor(channel(url).await, signer(key.clone()))
+}
+
+/// Who the gateway thinks `key` belongs to, or the error it gives
+async fn whoami(url: &str, key: &PrivateKey) -> Result<String, Status> {
+    let me = client(url, key).await.get_me(()).await?.into_inner();
+    Ok(me.principal_id)
+}
+
+/// A gateway where `owner` is an owner and `agent` the member "agent-bot"
+async fn start(dir: &Path, owner: &PrivateKey, agent: &PrivateKey) -> coven_serve::RunningServer {
+    let principal = |name: &str, key: &PrivateKey, role: &str| PrincipalRoles {
+        name: name.to_string(),
+        public_key: key.public_key().to_openssh().unwrap(),
+        roles: vec![role.to_string()],
+    };
+    Server::start(ServeConfig {
+        grpc_addr: "127.0.0.1:0".to_string(),
+        db_path: dir.join("gateway.db"),
+        roles: Some(RolesConfig {
+            principals: vec![
+                principal("owner", owner, OWNER),
+                principal("agent-bot", agent, MEMBER),
+            ],
+            ..Default::default()
+        }),
+        ..Default::default()
+    })
+    .await
+    .unwrap()
+}
+
+fn rotate_request(proof: RotationProof, grace_period_secs: Option<u32>) -> RotateKeyRequest {
+    RotateKeyRequest {
+        new_public_key: proof.new_public_key,
+        timestamp: proof.timestamp,
+        new_key_signature: proof.signature,
+        grace_period_secs,
+    }
+}
+
+#[tokio::test]
+async fn test_both_keys_work_during_grace_period_then_old_expires() {
+    let dir = tempfile::tempdir().unwrap();
+    let owner = coven_ssh::generate_key(&dir.path().join("owner_key")).unwrap();
+    let key_path = dir.path().join("agent_key");
+    let old = coven_ssh::generate_key(&key_path).unwrap();
+    let server = start(dir.path(), &owner, &old).await;
+    let url = server.url();
+
+    // Signed with the old key, proven with the new one
+    let rotation = coven_ssh::rotate_key(&key_path).unwrap();
+    let new = rotation.new_key.clone();
+    let response = client(&url, &old)
+        .await
+        .rotate_key(rotate_request(rotation.proof().unwrap(), Some(1)))
+        .await
+        .unwrap()
+        .into_inner();
+    assert_eq!(response.principal_id, "agent-bot");
+    assert_eq!(
+        response.new_fingerprint,
+        compute_fingerprint(new.public_key()).unwrap()
+    );
+    assert_eq!(
+        response.old_fingerprints,
+        [compute_fingerprint(old.public_key()).unwrap()]
+    );
+    rotation.commit().unwrap();
+
+    // Within the grace period both keys are the same principal
+    assert_eq!(whoami(&url, &old).await.unwrap(), "agent-bot");
+    let me = client(&url, &new)
+        .await
+        .get_me(())
+        .await
+        .unwrap()
+        .into_inner();
+    assert_eq!(me.principal_id, "agent-bot");
+    assert_eq!(me.roles, [MEMBER]);
+
+    tokio::time::sleep(Duration::from_millis(1200)).await;
+    let err = whoami(&url, &old).await.unwrap_err();
+    assert_eq!(err.code(), Code::Unauthenticated);
+    assert!(err.message().contains("rotated out of agent-bot"));
+    assert_eq!(whoami(&url, &new).await.unwrap(), "agent-bot");
+
+    // The rotation outlives the gateway, even though the roles file still
+    // lists the old key
+    server.shutdown().await.unwrap();
+    let server = start(dir.path(), &owner, &old).await;
+    let url = server.url();
+    assert_eq!(whoami(&url, &new).await.unwrap(), "agent-bot");
+    assert_eq!(
+        whoami(&url, &old).await.unwrap_err().code(),
+        Code::Unauthenticated
+    );
+    server.shutdown().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_rotation_needs_proof_of_the_new_key() {
+    let dir = tempfile::tempdir().unwrap();
+    let owner = coven_ssh::generate_key(&dir.path().join("owner_key")).unwrap();
+    let key_path = dir.path().join("agent_key");
+    let old = coven_ssh::generate_key(&key_path).unwrap();
+    let server = start(dir.path(), &owner, &old).await;
+    let url = server.url();
+
+    // A proof made for another key's rotation doesn't carry over
+    let rotation = coven_ssh::rotate_key(&key_path).unwrap();
+    let proof = RotationProof::new(owner.public_key(), &rotation.new_key).unwrap();
+    let err = client(&url, &old)
+        .await
+        .rotate_key(rotate_request(proof, None))
+        .await
+        .unwrap_err();
+    assert_eq!(err.code(), Code::InvalidArgument);
+
+    // Nor can a principal take over a key that belongs to someone else
+    let proof = RotationProof::new(old.public_key(), &owner).unwrap();
+    let err = client(&url, &old)
+        .await
+        .rotate_key(rotate_request(proof, None))
+        .await
+        .unwrap_err();
+    assert_eq!(err.code(), Code::AlreadyExists);
+
+    // Unsigned callers have no key to rotate
+    let mut unsigned = ClientServiceClient::connect(url.clone()).await.unwrap();
+    let err = unsigned
+        .rotate_key(rotate_request(rotation.proof().unwrap(), None))
+        .await
+        .unwrap_err();
+    assert_eq!(err.code(), Code::Unauthenticated);
+
+    assert_eq!(whoami(&url, &old).await.unwrap(), "agent-bot");
+    server.shutdown().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_admin_rotation_without_grace_period_cuts_old_key_off() {
+    let dir = tempfile::tempdir().unwrap();
+    let owner = coven_ssh::generate_key(&dir.path().join("owner_key")).unwrap();
+    let old = coven_ssh::generate_key(&dir.path().join("agent_key")).unwrap();
+    let new = coven_ssh::generate_key(&dir.path().join("new_key")).unwrap();
+    let server = start(dir.path(), &owner, &old).await;
+    let url = server.url();
+    let new_fingerprint = compute_fingerprint(new.public_key()).unwrap();
+
+    // Only admins rotate other principals' keys
+    let err = admin(&url, &old)
+        .await
+        .rotate_principal_key(RotatePrincipalKeyRequest {
+            id: "agent-bot".to_string(),
+            new_pubkey_fp: new_fingerprint.clone(),
+            grace_period_secs: Some(0),
+        })
+        .await
+        .unwrap_err();
+    assert_eq!(err.code(), Code::PermissionDenied);
+
+    let mut as_owner = admin(&url, &owner).await;
+    let err = as_owner
+        .rotate_principal_key(RotatePrincipalKeyRequest {
+            id: "nobody".to_string(),
+            new_pubkey_fp: new_fingerprint.clone(),
+            grace_period_secs: None,
+        })
+        .await
+        .unwrap_err();
+    assert_eq!(err.code(), Code::NotFound);
+    let err = as_owner
+        .rotate_principal_key(RotatePrincipalKeyRequest {
+            id: "agent-bot".to_string(),
+            new_pubkey_fp: "not-a-fingerprint".to_string(),
+            grace_period_secs: None,
+        })
+        .await
+        .unwrap_err();
+    assert_eq!(err.code(), Code::InvalidArgument);
+
+    let response = as_owner
+        .rotate_principal_key(RotatePrincipalKeyRequest {
+            id: "agent-bot".to_string(),
+            new_pubkey_fp: new_fingerprint.to_uppercase(),
+            grace_period_secs: Some(0),
+        })
+        .await
+        .unwrap()
+        .into_inner();
+    assert_eq!(response.new_fingerprint, new_fingerprint);
+    assert_eq!(
+        response.old_fingerprints,
+        [compute_fingerprint(old.public_key()).unwrap()]
+    );
+
+    assert_eq!(
+        whoami(&url, &old).await.unwrap_err().code(),
+        Code::Unauthenticated
+    );
+    assert_eq!(whoami(&url, &new).await.unwrap(), "agent-bot");
+    server.shutdown().await.unwrap();
+}
@@ -0,0 +1,108 @@
+// ABOUTME: End-to-end test of the gateway's message size limits.
+// ABOUTME: An oversize message or attachment is refused with ResourceExhausted and the agent's stream keeps working.
+
+use coven_proto::client::{ClientServiceClient, CovenControlClient};
+use coven_proto::limits::MessageLimits;
+use coven_proto::{
+    agent_message, server_message, AgentMessage, ClientSendMessageRequest, FileAttachment,
+    RegisterAgent,
+};
+use coven_serve::{ServeConfig, Server};
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
+
+#[tokio::test]
+async fn test_oversize_message_is_refused_without_dropping_agent() {
+    let dir = tempfile::tempdir().unwrap();
+    let server = Server::start(ServeConfig {
+        grpc_addr: "127.0.0.1:0".to_string(),
+        db_path: dir.path().join("gateway.db"),
+        message_limits: MessageLimits {
+            max_decoding: 1024 * 1024,
+            max_encoding: 64 * 1024,
+        },
+        ..Default::default()
+    })
+    .await
+    .unwrap();
+
+    // Register a fake agent and wait for its welcome
+    let (agent_tx, agent_rx) = mpsc::channel(8);
+    agent_tx
+        .send(AgentMessage {
+            payload: Some(agent_message::Payload::Register(RegisterAgent {
+                agent_id: "agent-1".to_string(),
+                name: "Agent One".to_string(),
+                ..Default::default()
+            })),
+        })
+        .await
+        .unwrap();
+    let mut control = CovenControlClient::connect(server.url()).await.unwrap();
+    let mut inbound = control
+        .agent_stream(ReceiverStream::new(agent_rx))
+        .await
+        .unwrap()
+        .into_inner();
+    let welcome = inbound.message().await.unwrap().unwrap();
+    assert!(matches!(
+        welcome.payload,
+        Some(server_message::Payload::Welcome(_))
+    ));
+
+    let mut client = ClientServiceClient::connect(server.url()).await.unwrap();
+    let err = client
+        .send_message(ClientSendMessageRequest {
+            conversation_key: "agent-1".to_string(),
+            content: "x".repeat(100 * 1024),
+            ..Default::default()
+        })
+        .await
+        .unwrap_err();
+    assert_eq!(err.code(), tonic::Code::ResourceExhausted);
+    assert!(err.message().contains("agent-1"), "{}", err.message());
+
+    // Attachments count toward the limit too
+    let attachment = |len: usize| FileAttachment {
+        filename: "notes.txt".to_string(),
+        mime_type: "text/plain".to_string(),
+        data: vec![b'x'; len],
+    };
+    let err = client
+        .send_message(ClientSendMessageRequest {
+            conversation_key: "agent-1".to_string(),
+            content: "see attached".to_string(),
+            attachments: vec![attachment(100 * 1024)],
+            ..Default::default()
+        })
+        .await
+        .unwrap_err();
+    assert_eq!(err.code(), tonic::Code::ResourceExhausted);
+
+    // The agent is still connected and gets the next message, attachment included
+    client
+        .send_message(ClientSendMessageRequest {
+            conversation_key: "agent-1".to_string(),
+            content: "hello".to_string(),
+            attachments: vec![attachment(16)],
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+    let msg = tokio::time::timeout(Duration::from_secs(5), inbound.message())
+        .await
+        .unwrap()
+        .unwrap()
+        .unwrap();
+    match msg.payload {
+        Some(server_message::Payload::SendMessage(send)) => {
+            assert_eq!(send.content, "hello");
+            assert_eq!(send.attachments, vec![attachment(16)]);
+        }
+        other => panic!("expected SendMessage, got {:?}", other),
+    }
+
+    drop(agent_tx);
+    server.shutdown().await.unwrap();
+}
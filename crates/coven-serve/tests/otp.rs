@@ -0,0 +1,307 @@
+// ABOUTME: Tests one-time codes guarding destructive admin RPCs on the local gateway.
+// ABOUTME: An owner enrolls a TOTP secret, then purges only with a current, unused code; reads need none.
+
+use coven_proto::client::AdminServiceClient;
+use coven_proto::{
+    DeletePackSecretRequest, EnrollOtpRequest, ListDeadLettersRequest, PurgeDeadLettersRequest,
+    OTP_METADATA,
+};
+use coven_serve::roles::{PrincipalRoles, OWNER};
+use coven_serve::totp::code_at;
+use coven_serve::{RolesConfig, ServeConfig, Server};
+use coven_ssh::{PrivateKey, SshAuthCredentials};
+use std::path::Path;
+use tonic::service::interceptor::InterceptedService;
+use tonic::transport::Channel;
+use tonic::{Code, Request, Status};
+
+type Signer = Box<dyn FnMut(Request<()>) -> Result<Request<()>, Status> + Send>;
+
+type Admin = AdminServiceClient<InterceptedService<Channel, Signer>>;
+
+/// Signs every request with `key`
+fn signer(key: PrivateKey) -> Signer {
+    Box::new(move |mut request: Request<()>| {
+        SshAuthCredentials::new(&key)
+            .and_then(|creds| creds.apply_to_request(&mut request))
+            .map_err(|e| Status::internal(e.to_string()))?;
+        Ok(request)
+    })
+}
+
+async fn admin(url: &str, key: &PrivateKey) -> Admin {
+    let channel = Channel::from_shared(url.to_string())
+        .unwrap()
+        .connect()
+        .await
+        .unwrap();
+    AdminServiceClient::with_interceptor(channel, signer(key.clone()))
+}
+
+/// A gateway where `owner` is an owner, requiring codes if `require_otp`
+async fn start(dir: &Path, owner: &PrivateKey, require_otp: bool) -> coven_serve::RunningServer {
+    start_with_owners(dir, &[("owner", owner)], require_otp).await
+}
+
+/// A gateway where each of `owners` is an owner with the given name
+async fn start_with_owners(
+    dir: &Path,
+    owners: &[(&str, &PrivateKey)],
+    require_otp: bool,
+) -> coven_serve::RunningServer {
+    Server::start(ServeConfig {
+        grpc_addr: "127.0.0.1:0".to_string(),
+        db_path: dir.join("gateway.db"),
+        roles: Some(RolesConfig {
+            principals: owners
+                .iter()
+                .map(|(name, key)| PrincipalRoles {
+                    name: name.to_string(),
+                    public_key: key.public_key().to_openssh().unwrap(),
+                    roles: vec![OWNER.to_string()],
+                })
+                .collect(),
+            require_otp,
+            ..Default::default()
+        }),
+        ..Default::default()
+    })
+    .await
+    .unwrap()
+}
+
+fn with_code<T>(message: T, code: &str) -> Request<T> {
+    let mut request = Request::new(message);
+    request
+        .metadata_mut()
+        .insert(OTP_METADATA, code.parse().unwrap());
+    request
+}
+
+fn purge() -> PurgeDeadLettersRequest {
+    PurgeDeadLettersRequest { agent_id: None }
+}
+
+/// The current code for a base32 `secret` from `EnrollOtp`
+fn current_code(secret: &str) -> String {
+    code_at(&decode_base32(secret), chrono::Utc::now().timestamp())
+}
+
+/// The next step's code, accepted now (within the clock skew) though the
+/// current one was just used
+fn next_code(secret: &str) -> String {
+    code_at(&decode_base32(secret), chrono::Utc::now().timestamp() + 30)
+}
+
+fn enroll(code: Option<String>, principal_id: Option<&str>) -> EnrollOtpRequest {
+    EnrollOtpRequest {
+        code,
+        principal_id: principal_id.map(String::from),
+    }
+}
+
+fn decode_base32(encoded: &str) -> Vec<u8> {
+    const ALPHABET: &str = "ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+    let mut bits = 0u64;
+    let mut count = 0;
+    let mut bytes = Vec::new();
+    for c in encoded.chars() {
+        bits = (bits << 5) | ALPHABET.find(c).unwrap() as u64;
+        count += 5;
+        if count >= 8 {
+            count -= 8;
+            bytes.push((bits >> count) as u8);
+        }
+    }
+    bytes
+}
+
+#[tokio::test]
+async fn test_destructive_calls_need_a_current_code() {
+    let dir = tempfile::tempdir().unwrap();
+    let owner = coven_ssh::generate_key(&dir.path().join("owner_key")).unwrap();
+    let server = start(dir.path(), &owner, true).await;
+    let url = server.url();
+
+    // Not enrolled yet: nothing a code could fix
+    let err = admin(&url, &owner)
+        .await
+        .purge_dead_letters(purge())
+        .await
+        .unwrap_err();
+    assert_eq!(err.code(), Code::FailedPrecondition, "{}", err.message());
+    assert!(err.message().contains("otp enroll"), "{}", err.message());
+
+    let enrolled = admin(&url, &owner)
+        .await
+        .enroll_otp(enroll(None, None))
+        .await
+        .unwrap()
+        .into_inner();
+    assert_eq!(enrolled.principal_id, "owner");
+    assert!(enrolled
+        .uri
+        .starts_with("otpauth://totp/coven:owner?secret="));
+
+    // Without a code, or with a wrong one, the client is asked for one
+    for request in [Request::new(purge()), with_code(purge(), "000000")] {
+        let err = admin(&url, &owner)
+            .await
+            .purge_dead_letters(request)
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), Code::Unauthenticated, "{}", err.message());
+        assert_eq!(err.metadata().get(OTP_METADATA).unwrap(), "required");
+    }
+    let err = admin(&url, &owner)
+        .await
+        .delete_pack_secret(DeletePackSecretRequest {
+            pack_id: "pack".to_string(),
+            key: "API_KEY".to_string(),
+        })
+        .await
+        .unwrap_err();
+    assert_eq!(err.code(), Code::Unauthenticated);
+
+    let code = current_code(&enrolled.secret);
+    let purged = admin(&url, &owner)
+        .await
+        .purge_dead_letters(with_code(purge(), &code))
+        .await
+        .unwrap()
+        .into_inner();
+    assert_eq!(purged.purged, 0);
+
+    // Reads are unaffected
+    admin(&url, &owner)
+        .await
+        .list_dead_letters(ListDeadLettersRequest { agent_id: None })
+        .await
+        .unwrap();
+
+    // A code is accepted once
+    let err = admin(&url, &owner)
+        .await
+        .purge_dead_letters(with_code(purge(), &code))
+        .await
+        .unwrap_err();
+    assert_eq!(err.code(), Code::Unauthenticated, "{}", err.message());
+
+    // Replacing the secret takes an unused code from the current one
+    for code in [None, Some(code)] {
+        let err = admin(&url, &owner)
+            .await
+            .enroll_otp(enroll(code, None))
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), Code::Unauthenticated);
+    }
+    let replaced = admin(&url, &owner)
+        .await
+        .enroll_otp(enroll(Some(next_code(&enrolled.secret)), None))
+        .await
+        .unwrap()
+        .into_inner();
+    assert_ne!(replaced.secret, enrolled.secret);
+
+    server.shutdown().await.unwrap();
+
+    // Secrets outlive restarts
+    let server = start(dir.path(), &owner, true).await;
+    admin(&server.url(), &owner)
+        .await
+        .purge_dead_letters(with_code(purge(), &current_code(&replaced.secret)))
+        .await
+        .unwrap();
+    server.shutdown().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_wrong_codes_lock_the_principal_out() {
+    let dir = tempfile::tempdir().unwrap();
+    let owner = coven_ssh::generate_key(&dir.path().join("owner_key")).unwrap();
+    let server = start(dir.path(), &owner, true).await;
+    let url = server.url();
+
+    let enrolled = admin(&url, &owner)
+        .await
+        .enroll_otp(enroll(None, None))
+        .await
+        .unwrap()
+        .into_inner();
+    for _ in 0..5 {
+        let err = admin(&url, &owner)
+            .await
+            .purge_dead_letters(with_code(purge(), "000000"))
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), Code::Unauthenticated);
+    }
+
+    // Even the right code is refused until the lockout ends
+    let err = admin(&url, &owner)
+        .await
+        .purge_dead_letters(with_code(purge(), &current_code(&enrolled.secret)))
+        .await
+        .unwrap_err();
+    assert_eq!(err.code(), Code::ResourceExhausted, "{}", err.message());
+
+    server.shutdown().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_later_admins_are_enrolled_by_an_enrolled_one() {
+    let dir = tempfile::tempdir().unwrap();
+    let first = coven_ssh::generate_key(&dir.path().join("first_key")).unwrap();
+    let second = coven_ssh::generate_key(&dir.path().join("second_key")).unwrap();
+    let server =
+        start_with_owners(dir.path(), &[("first", &first), ("second", &second)], true).await;
+    let url = server.url();
+
+    // The first admin on the gateway enrolls without a code
+    let enrolled = admin(&url, &first)
+        .await
+        .enroll_otp(enroll(None, None))
+        .await
+        .unwrap()
+        .into_inner();
+
+    // After that, a stolen admin key can't enroll itself
+    let err = admin(&url, &second)
+        .await
+        .enroll_otp(enroll(None, None))
+        .await
+        .unwrap_err();
+    assert_eq!(err.code(), Code::FailedPrecondition, "{}", err.message());
+    assert!(err.message().contains("--for second"), "{}", err.message());
+
+    let theirs = admin(&url, &first)
+        .await
+        .enroll_otp(enroll(Some(current_code(&enrolled.secret)), Some("second")))
+        .await
+        .unwrap()
+        .into_inner();
+    assert_eq!(theirs.principal_id, "second");
+    admin(&url, &second)
+        .await
+        .purge_dead_letters(with_code(purge(), &current_code(&theirs.secret)))
+        .await
+        .unwrap();
+
+    server.shutdown().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_codes_are_off_by_default() {
+    let dir = tempfile::tempdir().unwrap();
+    let owner = coven_ssh::generate_key(&dir.path().join("owner_key")).unwrap();
+    let server = start(dir.path(), &owner, false).await;
+
+    admin(&server.url(), &owner)
+        .await
+        .purge_dead_letters(purge())
+        .await
+        .unwrap();
+
+    server.shutdown().await.unwrap();
+}
@@ -0,0 +1,221 @@
+// ABOUTME: End-to-end test of pack health reporting through the local gateway.
+// ABOUTME: A fake pack turns unhealthy and back; agents and admins see its tools degraded meanwhile.
+
+use async_trait::async_trait;
+use coven_pack::{HealthStatus, ManifestBuilder, PackClient, ToolError, ToolHandler};
+use coven_proto::client::{AdminServiceClient, CovenControlClient};
+use coven_proto::server::{AdminServiceServer, CovenControlServer, PackServiceServer};
+use coven_proto::{agent_message, server_message, AgentMessage, ListPacksRequest, RegisterAgent};
+use coven_serve::services::admin::AdminServiceImpl;
+use coven_serve::services::control::{ControlState, CovenControlService};
+use coven_serve::services::pack::{PackServiceImpl, PackState};
+use coven_serve::store::Store;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::net::TcpListener;
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::{ReceiverStream, TcpListenerStream};
+
+const REBUILDING: &str = "search index is rebuilding";
+
+/// Pack whose health is flipped by the test.
+struct SearchPack {
+    healthy: Arc<AtomicBool>,
+}
+
+#[async_trait]
+impl ToolHandler for SearchPack {
+    async fn execute(&self, tool_name: &str, _input_json: &str) -> Result<String, ToolError> {
+        match tool_name {
+            "search" => Ok(r#"{"hits":[]}"#.to_string()),
+            _ => Err(ToolError::UnknownTool(tool_name.to_string())),
+        }
+    }
+
+    async fn health_check(&self) -> HealthStatus {
+        if self.healthy.load(Ordering::SeqCst) {
+            HealthStatus::Healthy
+        } else {
+            HealthStatus::unhealthy(REBUILDING)
+        }
+    }
+}
+
+/// Poll the gateway's advertised tools until `done` holds for them.
+async fn wait_for_tools(
+    pack_state: &PackState,
+    done: impl Fn(&[(String, coven_proto::ToolDefinition)]) -> bool,
+) {
+    for _ in 0..200 {
+        let tools = pack_state.list_tools().await;
+        if done(&tools) {
+            return;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    panic!("timed out waiting for pack tools to change");
+}
+
+#[tokio::test]
+async fn test_unhealthy_pack_tools_are_degraded() {
+    let dir = tempfile::tempdir().unwrap();
+    let store = Store::open(&dir.path().join("gateway.db")).await.unwrap();
+    let pack_state = PackState::new(store.clone());
+    let control_state = ControlState::new(store.clone(), None);
+
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    {
+        let pack_state = pack_state.clone();
+        tokio::spawn(async move {
+            tonic::transport::Server::builder()
+                .add_service(CovenControlServer::new(
+                    CovenControlService::new(control_state.clone()).with_packs(pack_state.clone()),
+                ))
+                .add_service(PackServiceServer::new(PackServiceImpl::new(
+                    pack_state.clone(),
+                )))
+                .add_service(AdminServiceServer::new(
+                    AdminServiceImpl::new(store, control_state).with_packs(pack_state),
+                ))
+                .serve_with_incoming(TcpListenerStream::new(listener))
+                .await
+                .unwrap();
+        });
+    }
+
+    // Connect a pack that starts out unhealthy
+    let healthy = Arc::new(AtomicBool::new(false));
+    let key_path = dir.path().join("pack_key");
+    coven_ssh::load_or_generate_key(&key_path).unwrap();
+    let pack = PackClient::connect(&url, &key_path)
+        .await
+        .unwrap()
+        .with_health_check_interval(Duration::from_millis(50));
+    let manifest = ManifestBuilder::new("search-pack", "0.2.0")
+        .tool("search", "Search documents", r#"{"type": "object"}"#, &[])
+        .build();
+    let handler = SearchPack {
+        healthy: Arc::clone(&healthy),
+    };
+    tokio::spawn(async move { pack.run(manifest, handler).await });
+
+    wait_for_tools(&pack_state, |tools| {
+        tools.iter().any(|(_, tool)| tool.degraded_reason.is_some())
+    })
+    .await;
+
+    // An agent connecting now is offered the tool marked degraded
+    let (agent_tx, agent_rx) = mpsc::channel(8);
+    agent_tx
+        .send(AgentMessage {
+            payload: Some(agent_message::Payload::Register(RegisterAgent {
+                agent_id: "agent-1".to_string(),
+                name: "Agent One".to_string(),
+                ..Default::default()
+            })),
+        })
+        .await
+        .unwrap();
+    let mut control = CovenControlClient::connect(url.clone()).await.unwrap();
+    let mut inbound = control
+        .agent_stream(ReceiverStream::new(agent_rx))
+        .await
+        .unwrap()
+        .into_inner();
+    let welcome = tokio::time::timeout(Duration::from_secs(10), inbound.message())
+        .await
+        .expect("timed out waiting for welcome")
+        .unwrap()
+        .and_then(|message| message.payload);
+    let Some(server_message::Payload::Welcome(welcome)) = welcome else {
+        panic!("expected welcome");
+    };
+    assert_eq!(welcome.available_tools.len(), 1);
+    assert_eq!(welcome.available_tools[0].name, "search");
+    assert_eq!(
+        welcome.available_tools[0].degraded_reason.as_deref(),
+        Some(REBUILDING)
+    );
+
+    // Admins see the pack's health
+    let mut admin = AdminServiceClient::connect(url).await.unwrap();
+    let packs = admin
+        .list_packs(ListPacksRequest {})
+        .await
+        .unwrap()
+        .into_inner()
+        .packs;
+    assert_eq!(packs.len(), 1);
+    assert_eq!(packs[0].pack_id, "search-pack");
+    assert_eq!(packs[0].version, "0.2.0");
+    assert_eq!(packs[0].tools, vec!["search"]);
+    assert!(!packs[0].healthy);
+    assert_eq!(packs[0].status_message, REBUILDING);
+    assert!(packs[0].last_status_at.is_some());
+
+    // Once the pack recovers, the mark goes away
+    healthy.store(true, Ordering::SeqCst);
+    wait_for_tools(&pack_state, |tools| {
+        tools.iter().all(|(_, tool)| tool.degraded_reason.is_none())
+    })
+    .await;
+    let packs = admin
+        .list_packs(ListPacksRequest {})
+        .await
+        .unwrap()
+        .into_inner()
+        .packs;
+    assert!(packs[0].healthy);
+    assert!(packs[0].status_message.is_empty());
+}
+
+#[tokio::test]
+async fn test_healthy_pack_can_mark_single_tools_degraded() {
+    let dir = tempfile::tempdir().unwrap();
+    let store = Store::open(&dir.path().join("gateway.db")).await.unwrap();
+    let pack_state = PackState::new(store);
+
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    {
+        let pack_state = pack_state.clone();
+        tokio::spawn(async move {
+            tonic::transport::Server::builder()
+                .add_service(PackServiceServer::new(PackServiceImpl::new(pack_state)))
+                .serve_with_incoming(TcpListenerStream::new(listener))
+                .await
+                .unwrap();
+        });
+    }
+
+    // A pack fronting several backends, one of which is down
+    let key_path = dir.path().join("pack_key");
+    coven_ssh::load_or_generate_key(&key_path).unwrap();
+    let pack = PackClient::connect(&url, &key_path)
+        .await
+        .unwrap()
+        .with_health_check_interval(Duration::from_millis(50));
+    let mut manifest = ManifestBuilder::new("search-pack", "0.2.0")
+        .tool("search", "Search documents", r#"{"type": "object"}"#, &[])
+        .tool("reindex", "Rebuild the index", r#"{"type": "object"}"#, &[])
+        .build();
+    manifest.tools[1].degraded_reason = Some(REBUILDING.to_string());
+    let handler = SearchPack {
+        healthy: Arc::new(AtomicBool::new(true)),
+    };
+    tokio::spawn(async move { pack.run(manifest, handler).await });
+
+    wait_for_tools(&pack_state, |tools| tools.len() == 2).await;
+    // Health reports don't clear the pack's own marks
+    tokio::time::sleep(Duration::from_millis(150)).await;
+    let tools = pack_state.list_tools().await;
+    for (_, tool) in &tools {
+        match tool.name.as_str() {
+            "search" => assert_eq!(tool.degraded_reason, None),
+            "reindex" => assert_eq!(tool.degraded_reason.as_deref(), Some(REBUILDING)),
+            other => panic!("unexpected tool {other}"),
+        }
+    }
+}
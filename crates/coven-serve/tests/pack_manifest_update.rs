@@ -0,0 +1,199 @@
+// ABOUTME: End-to-end test of pack manifest updates through the local gateway.
+// ABOUTME: A fake pack adds and removes a tool mid-session; a connected agent is told and can call it.
+
+use async_trait::async_trait;
+use coven_pack::{ManifestBuilder, PackClient, PackManifest, ToolError, ToolHandler};
+use coven_proto::client::CovenControlClient;
+use coven_proto::server::{CovenControlServer, PackServiceServer};
+use coven_proto::{
+    agent_message, pack_tool_result, server_message, AgentMessage, ExecutePackTool, RegisterAgent,
+    ServerMessage,
+};
+use coven_serve::services::control::{ControlState, CovenControlService};
+use coven_serve::services::pack::{PackServiceImpl, PackState};
+use coven_serve::store::Store;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::net::TcpListener;
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::{ReceiverStream, TcpListenerStream};
+use tonic::Streaming;
+
+/// Pack that can serve "search" from the start and "create_issue" once the
+/// wrapped service has been authenticated.
+struct IssuesPack;
+
+#[async_trait]
+impl ToolHandler for IssuesPack {
+    async fn execute(&self, tool_name: &str, _input_json: &str) -> Result<String, ToolError> {
+        match tool_name {
+            "search" => Ok(r#"{"issues":[]}"#.to_string()),
+            "create_issue" => Ok(r#"{"id":42}"#.to_string()),
+            _ => Err(ToolError::UnknownTool(tool_name.to_string())),
+        }
+    }
+}
+
+fn manifest(version: &str, tools: &[&str]) -> PackManifest {
+    tools
+        .iter()
+        .fold(
+            ManifestBuilder::new("issues-pack", version),
+            |builder, name| builder.tool(*name, "Issue tracker tool", r#"{"type": "object"}"#, &[]),
+        )
+        .build()
+}
+
+async fn next_message(inbound: &mut Streaming<ServerMessage>) -> server_message::Payload {
+    tokio::time::timeout(Duration::from_secs(10), inbound.message())
+        .await
+        .expect("timed out waiting for the gateway")
+        .unwrap()
+        .expect("gateway closed the stream")
+        .payload
+        .unwrap()
+}
+
+/// Names of the tools in the next pushed tool list.
+async fn next_tool_names(inbound: &mut Streaming<ServerMessage>) -> Vec<String> {
+    let server_message::Payload::AvailableTools(update) = next_message(inbound).await else {
+        panic!("expected an available tools update");
+    };
+    update.tools.into_iter().map(|tool| tool.name).collect()
+}
+
+#[tokio::test]
+async fn test_tool_added_mid_session_reaches_agent() {
+    let dir = tempfile::tempdir().unwrap();
+    let store = Store::open(&dir.path().join("gateway.db")).await.unwrap();
+    let pack_state = PackState::new(store.clone());
+    let control_state = ControlState::new(store, None);
+
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    {
+        let pack_state = pack_state.clone();
+        tokio::spawn(async move {
+            tonic::transport::Server::builder()
+                .add_service(CovenControlServer::new(
+                    CovenControlService::new(control_state).with_packs(pack_state.clone()),
+                ))
+                .add_service(PackServiceServer::new(PackServiceImpl::new(pack_state)))
+                .serve_with_incoming(TcpListenerStream::new(listener))
+                .await
+                .unwrap();
+        });
+    }
+
+    // Connect the pack with only its first tool and wait until it's offered
+    let key_path = dir.path().join("pack_key");
+    coven_ssh::load_or_generate_key(&key_path).unwrap();
+    let pack = Arc::new(PackClient::connect(&url, &key_path).await.unwrap());
+    {
+        let pack = Arc::clone(&pack);
+        tokio::spawn(async move { pack.run(manifest("1.0.0", &["search"]), IssuesPack).await });
+    }
+    for _ in 0..100 {
+        if !pack_state.list_tools().await.is_empty() {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+
+    // Connect an agent
+    let (agent_tx, agent_rx) = mpsc::channel(8);
+    agent_tx
+        .send(AgentMessage {
+            payload: Some(agent_message::Payload::Register(RegisterAgent {
+                agent_id: "agent-1".to_string(),
+                name: "Agent One".to_string(),
+                ..Default::default()
+            })),
+        })
+        .await
+        .unwrap();
+    let mut control = CovenControlClient::connect(url).await.unwrap();
+    let mut inbound = control
+        .agent_stream(ReceiverStream::new(agent_rx))
+        .await
+        .unwrap()
+        .into_inner();
+
+    let server_message::Payload::Welcome(welcome) = next_message(&mut inbound).await else {
+        panic!("expected welcome");
+    };
+    let names: Vec<&str> = welcome
+        .available_tools
+        .iter()
+        .map(|tool| tool.name.as_str())
+        .collect();
+    assert_eq!(names, vec!["search"]);
+
+    // The pack gains a tool without reconnecting
+    let welcome = pack
+        .update_manifest(manifest("1.0.1", &["search", "create_issue"]))
+        .await
+        .unwrap();
+    assert_eq!(welcome.pack_id, "issues-pack");
+    assert!(welcome.rejected_tools.is_empty());
+    assert_eq!(
+        next_tool_names(&mut inbound).await,
+        vec!["search", "create_issue"]
+    );
+
+    // The agent can call the new tool right away
+    agent_tx
+        .send(AgentMessage {
+            payload: Some(agent_message::Payload::ExecutePackTool(ExecutePackTool {
+                request_id: "call-1".to_string(),
+                tool_name: "create_issue".to_string(),
+                input_json: "{}".to_string(),
+            })),
+        })
+        .await
+        .unwrap();
+    let server_message::Payload::PackToolResult(result) = next_message(&mut inbound).await else {
+        panic!("expected the tool result");
+    };
+    assert_eq!(result.request_id, "call-1");
+    assert_eq!(
+        result.result,
+        Some(pack_tool_result::Result::OutputJson(
+            r#"{"id":42}"#.to_string()
+        ))
+    );
+
+    // Removing a tool is pushed the same way
+    pack.update_manifest(manifest("1.0.2", &["create_issue"]))
+        .await
+        .unwrap();
+    assert_eq!(next_tool_names(&mut inbound).await, vec!["create_issue"]);
+
+    // An unchanged tool list isn't pushed again
+    pack.update_manifest(manifest("1.0.3", &["create_issue"]))
+        .await
+        .unwrap();
+    assert!(
+        tokio::time::timeout(Duration::from_millis(200), inbound.message())
+            .await
+            .is_err(),
+        "unexpected message after a no-op manifest update"
+    );
+
+    let packs = pack_state.list_packs().await;
+    assert_eq!(packs[0].version, "1.0.3");
+    assert_eq!(packs[0].tools, vec!["create_issue"]);
+}
+
+#[tokio::test]
+async fn test_update_for_unregistered_pack_is_rejected() {
+    let dir = tempfile::tempdir().unwrap();
+    let store = Store::open(&dir.path().join("gateway.db")).await.unwrap();
+    let pack_state = PackState::new(store);
+
+    let err = pack_state
+        .update_manifest(manifest("1.0.0", &["search"]))
+        .await
+        .unwrap_err();
+    assert_eq!(err.code(), tonic::Code::NotFound);
+}
@@ -0,0 +1,160 @@
+// ABOUTME: End-to-end test of pack tool progress through the local gateway.
+// ABOUTME: A fake pack reports progress while a fake agent calls its tool over CovenControl.
+
+use async_trait::async_trait;
+use coven_pack::{ManifestBuilder, PackClient, ProgressReporter, ToolError, ToolHandler};
+use coven_proto::client::CovenControlClient;
+use coven_proto::server::{CovenControlServer, PackServiceServer};
+use coven_proto::{
+    agent_message, pack_tool_result, server_message, AgentMessage, ExecutePackTool, RegisterAgent,
+    ServerMessage,
+};
+use coven_serve::services::control::{ControlState, CovenControlService};
+use coven_serve::services::pack::{PackServiceImpl, PackState};
+use coven_serve::store::Store;
+use std::sync::Mutex;
+use std::time::Duration;
+use tokio::net::TcpListener;
+use tokio::sync::{mpsc, oneshot};
+use tokio_stream::wrappers::{ReceiverStream, TcpListenerStream};
+use tonic::Streaming;
+
+/// Pack whose "build" tool reports progress, then finishes once released.
+struct BuildPack {
+    release: Mutex<Option<oneshot::Receiver<()>>>,
+}
+
+#[async_trait]
+impl ToolHandler for BuildPack {
+    async fn execute(&self, tool_name: &str, _input_json: &str) -> Result<String, ToolError> {
+        match tool_name {
+            "build" => Ok(r#"{"built":true}"#.to_string()),
+            _ => Err(ToolError::UnknownTool(tool_name.to_string())),
+        }
+    }
+
+    async fn execute_with_progress(
+        &self,
+        tool_name: &str,
+        input_json: &str,
+        progress: ProgressReporter,
+    ) -> Result<String, ToolError> {
+        progress.report(Some(50), "Compiled 20 of 40 crates");
+        // Finish only after the agent has seen the report
+        let release = self.release.lock().unwrap().take();
+        if let Some(release) = release {
+            let _ = release.await;
+        }
+        self.execute(tool_name, input_json).await
+    }
+}
+
+async fn next_message(inbound: &mut Streaming<ServerMessage>) -> server_message::Payload {
+    tokio::time::timeout(Duration::from_secs(10), inbound.message())
+        .await
+        .expect("timed out waiting for the gateway")
+        .unwrap()
+        .expect("gateway closed the stream")
+        .payload
+        .unwrap()
+}
+
+#[tokio::test]
+async fn test_pack_progress_reaches_agent_before_result() {
+    let dir = tempfile::tempdir().unwrap();
+    let store = Store::open(&dir.path().join("gateway.db")).await.unwrap();
+    let pack_state = PackState::new(store.clone());
+    let control_state = ControlState::new(store, None);
+
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    {
+        let pack_state = pack_state.clone();
+        tokio::spawn(async move {
+            tonic::transport::Server::builder()
+                .add_service(CovenControlServer::new(
+                    CovenControlService::new(control_state).with_packs(pack_state.clone()),
+                ))
+                .add_service(PackServiceServer::new(PackServiceImpl::new(pack_state)))
+                .serve_with_incoming(TcpListenerStream::new(listener))
+                .await
+                .unwrap();
+        });
+    }
+
+    // Connect the pack and wait until its tool is offered
+    let key_path = dir.path().join("pack_key");
+    coven_ssh::load_or_generate_key(&key_path).unwrap();
+    let pack = PackClient::connect(&url, &key_path).await.unwrap();
+    let manifest = ManifestBuilder::new("build-pack", "0.1.0")
+        .tool("build", "Build the project", r#"{"type": "object"}"#, &[])
+        .build();
+    let (release_tx, release_rx) = oneshot::channel();
+    let build_pack = BuildPack {
+        release: Mutex::new(Some(release_rx)),
+    };
+    let mut tools_changed = pack_state.subscribe_tools();
+    tokio::spawn(async move { pack.run(manifest, build_pack).await });
+    while pack_state.list_tools().await.is_empty() {
+        tokio::time::timeout(Duration::from_secs(10), tools_changed.recv())
+            .await
+            .expect("timed out waiting for the pack")
+            .unwrap();
+    }
+
+    // Connect an agent and call the tool
+    let (agent_tx, agent_rx) = mpsc::channel(8);
+    agent_tx
+        .send(AgentMessage {
+            payload: Some(agent_message::Payload::Register(RegisterAgent {
+                agent_id: "agent-1".to_string(),
+                name: "Agent One".to_string(),
+                ..Default::default()
+            })),
+        })
+        .await
+        .unwrap();
+    let mut control = CovenControlClient::connect(url).await.unwrap();
+    let mut inbound = control
+        .agent_stream(ReceiverStream::new(agent_rx))
+        .await
+        .unwrap()
+        .into_inner();
+
+    let server_message::Payload::Welcome(welcome) = next_message(&mut inbound).await else {
+        panic!("expected welcome");
+    };
+    assert_eq!(welcome.available_tools.len(), 1);
+    assert_eq!(welcome.available_tools[0].name, "build");
+
+    agent_tx
+        .send(AgentMessage {
+            payload: Some(agent_message::Payload::ExecutePackTool(ExecutePackTool {
+                request_id: "call-1".to_string(),
+                tool_name: "build".to_string(),
+                input_json: "{}".to_string(),
+            })),
+        })
+        .await
+        .unwrap();
+
+    let server_message::Payload::PackToolProgress(progress) = next_message(&mut inbound).await
+    else {
+        panic!("expected progress before the result");
+    };
+    assert_eq!(progress.request_id, "call-1");
+    assert_eq!(progress.percent, Some(50));
+    assert_eq!(progress.message, "Compiled 20 of 40 crates");
+    release_tx.send(()).unwrap();
+
+    let server_message::Payload::PackToolResult(result) = next_message(&mut inbound).await else {
+        panic!("expected the tool result");
+    };
+    assert_eq!(result.request_id, "call-1");
+    assert_eq!(
+        result.result,
+        Some(pack_tool_result::Result::OutputJson(
+            r#"{"built":true}"#.to_string()
+        ))
+    );
+}
@@ -0,0 +1,254 @@
+// ABOUTME: End-to-end test of gateway-managed pack secrets through the local gateway.
+// ABOUTME: An admin sets, changes, and deletes secrets; a connected pack sees each change.
+
+use async_trait::async_trait;
+use coven_pack::{ManifestBuilder, PackClient, Secrets, ToolError, ToolHandler};
+use coven_proto::client::AdminServiceClient;
+use coven_proto::pack_service_client::PackServiceClient;
+use coven_proto::{
+    DeletePackSecretRequest, ListPackSecretsRequest, SetPackSecretRequest, WatchSecretsRequest,
+};
+use coven_serve::{ServeConfig, Server};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tonic::transport::Channel;
+
+/// Pack that records the token it could see when it registered.
+struct TokenPack {
+    secrets: Secrets,
+    token_at_registration: Arc<Mutex<Option<Option<String>>>>,
+}
+
+#[async_trait]
+impl ToolHandler for TokenPack {
+    async fn execute(&self, tool_name: &str, _input_json: &str) -> Result<String, ToolError> {
+        Err(ToolError::UnknownTool(tool_name.to_string()))
+    }
+
+    async fn on_registered(&self, _pack_id: &str, _rejected_tools: &[String]) {
+        *self.token_at_registration.lock().unwrap() = Some(self.secrets.get("GITHUB_TOKEN"));
+    }
+}
+
+async fn set_secret(
+    admin: &mut AdminServiceClient<Channel>,
+    pack_id: &str,
+    key: &str,
+    value: &str,
+) -> bool {
+    admin
+        .set_pack_secret(SetPackSecretRequest {
+            pack_id: pack_id.to_string(),
+            key: key.to_string(),
+            value: value.to_string(),
+        })
+        .await
+        .unwrap()
+        .into_inner()
+        .created
+}
+
+/// Wait until `check` passes on the pack's secrets.
+async fn wait_for(secrets: &Secrets, check: impl Fn(&Secrets) -> bool) {
+    for _ in 0..250 {
+        if check(secrets) {
+            return;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    panic!("pack secrets never updated: {:?}", secrets);
+}
+
+/// Start a pack on `url` that fills `secrets`.
+async fn start_pack(
+    url: &str,
+    dir: &tempfile::TempDir,
+    pack_id: &str,
+    secrets: &Secrets,
+) -> Arc<Mutex<Option<Option<String>>>> {
+    let key_path = dir.path().join(format!("{}_key", pack_id));
+    coven_ssh::load_or_generate_key(&key_path).unwrap();
+    let client = PackClient::connect(url, &key_path)
+        .await
+        .unwrap()
+        .with_secrets(secrets.clone());
+
+    let token_at_registration = Arc::new(Mutex::new(None));
+    let handler = TokenPack {
+        secrets: secrets.clone(),
+        token_at_registration: Arc::clone(&token_at_registration),
+    };
+    let manifest = ManifestBuilder::new(pack_id, "1.0.0")
+        .tool("noop", "Does nothing", r#"{"type": "object"}"#, &[])
+        .build();
+    tokio::spawn(async move { client.run(manifest, handler).await });
+    token_at_registration
+}
+
+#[tokio::test]
+async fn test_pack_receives_secrets_and_updates() {
+    let dir = tempfile::tempdir().unwrap();
+    let server = Server::start(ServeConfig {
+        grpc_addr: "127.0.0.1:0".to_string(),
+        db_path: dir.path().join("gateway.db"),
+        ..Default::default()
+    })
+    .await
+    .unwrap();
+    let url = server.url();
+    let mut admin = AdminServiceClient::connect(url.clone()).await.unwrap();
+
+    // Set before the pack connects: it's in place before on_registered
+    assert!(set_secret(&mut admin, "github-pack", "GITHUB_TOKEN", "ghp_first").await);
+    let secrets = Secrets::new();
+    let token_at_registration = start_pack(&url, &dir, "github-pack", &secrets).await;
+    wait_for(&secrets, |s| {
+        s.get("GITHUB_TOKEN").as_deref() == Some("ghp_first")
+    })
+    .await;
+    for _ in 0..250 {
+        if token_at_registration.lock().unwrap().is_some() {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    assert_eq!(
+        *token_at_registration.lock().unwrap(),
+        Some(Some("ghp_first".to_string()))
+    );
+
+    // Changes are pushed without reconnecting
+    assert!(!set_secret(&mut admin, "github-pack", "GITHUB_TOKEN", "ghp_second").await);
+    wait_for(&secrets, |s| {
+        s.get("GITHUB_TOKEN").as_deref() == Some("ghp_second")
+    })
+    .await;
+    assert!(set_secret(&mut admin, "github-pack", "WEBHOOK_SECRET", "whsec").await);
+    wait_for(&secrets, |s| s.contains("WEBHOOK_SECRET")).await;
+
+    // Other packs' secrets don't leak in
+    assert!(set_secret(&mut admin, "other-pack", "OTHER_TOKEN", "nope").await);
+
+    // Listing shows names only
+    let listed = admin
+        .list_pack_secrets(ListPackSecretsRequest {
+            pack_id: "github-pack".to_string(),
+        })
+        .await
+        .unwrap()
+        .into_inner()
+        .secrets;
+    let keys: Vec<&str> = listed.iter().map(|s| s.key.as_str()).collect();
+    assert_eq!(keys, ["GITHUB_TOKEN", "WEBHOOK_SECRET"]);
+    assert!(!format!("{:?}", listed).contains("ghp_second"));
+
+    // Deleting removes it from the pack
+    let deleted = admin
+        .delete_pack_secret(DeletePackSecretRequest {
+            pack_id: "github-pack".to_string(),
+            key: "GITHUB_TOKEN".to_string(),
+        })
+        .await
+        .unwrap()
+        .into_inner()
+        .deleted;
+    assert!(deleted);
+    wait_for(&secrets, |s| !s.contains("GITHUB_TOKEN")).await;
+    assert_eq!(secrets.keys(), ["WEBHOOK_SECRET"]);
+}
+
+#[tokio::test]
+async fn test_pack_without_secrets_registers_with_none() {
+    let dir = tempfile::tempdir().unwrap();
+    let server = Server::start(ServeConfig {
+        grpc_addr: "127.0.0.1:0".to_string(),
+        db_path: dir.path().join("gateway.db"),
+        ..Default::default()
+    })
+    .await
+    .unwrap();
+
+    let secrets = Secrets::new();
+    let token_at_registration = start_pack(&server.url(), &dir, "plain-pack", &secrets).await;
+    for _ in 0..250 {
+        if token_at_registration.lock().unwrap().is_some() {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    assert_eq!(*token_at_registration.lock().unwrap(), Some(None));
+    assert!(secrets.keys().is_empty());
+}
+
+#[tokio::test]
+async fn test_set_rejects_bad_keys() {
+    let dir = tempfile::tempdir().unwrap();
+    let server = Server::start(ServeConfig {
+        grpc_addr: "127.0.0.1:0".to_string(),
+        db_path: dir.path().join("gateway.db"),
+        ..Default::default()
+    })
+    .await
+    .unwrap();
+    let mut admin = AdminServiceClient::connect(server.url()).await.unwrap();
+
+    for (pack_id, key) in [
+        ("", "TOKEN"),
+        ("github-pack", ""),
+        ("github-pack", "HAS SPACE"),
+    ] {
+        let status = admin
+            .set_pack_secret(SetPackSecretRequest {
+                pack_id: pack_id.to_string(),
+                key: key.to_string(),
+                value: "value".to_string(),
+            })
+            .await
+            .unwrap_err();
+        assert_eq!(
+            status.code(),
+            tonic::Code::InvalidArgument,
+            "{pack_id}/{key}"
+        );
+    }
+}
+
+#[tokio::test]
+async fn test_secrets_go_only_to_the_pack_key() {
+    let dir = tempfile::tempdir().unwrap();
+    let server = Server::start(ServeConfig {
+        grpc_addr: "127.0.0.1:0".to_string(),
+        db_path: dir.path().join("gateway.db"),
+        ..Default::default()
+    })
+    .await
+    .unwrap();
+    let url = server.url();
+    let mut admin = AdminServiceClient::connect(url.clone()).await.unwrap();
+    assert!(set_secret(&mut admin, "github-pack", "GITHUB_TOKEN", "ghp_first").await);
+
+    let secrets = Secrets::new();
+    start_pack(&url, &dir, "github-pack", &secrets).await;
+    wait_for(&secrets, |s| s.contains("GITHUB_TOKEN")).await;
+
+    let mut packs = PackServiceClient::connect(url).await.unwrap();
+    let watch = || {
+        tonic::Request::new(WatchSecretsRequest {
+            pack_id: "github-pack".to_string(),
+        })
+    };
+
+    // Unsigned
+    let status = packs.watch_secrets(watch()).await.unwrap_err();
+    assert_eq!(status.code(), tonic::Code::Unauthenticated);
+
+    // Signed, but not with the key the pack fetched its secrets with
+    let other = coven_ssh::load_or_generate_key(&dir.path().join("other_key")).unwrap();
+    let mut request = watch();
+    coven_ssh::SshAuthCredentials::new(&other)
+        .unwrap()
+        .apply_to_request(&mut request)
+        .unwrap();
+    let status = packs.watch_secrets(request).await.unwrap_err();
+    assert_eq!(status.code(), tonic::Code::PermissionDenied);
+}
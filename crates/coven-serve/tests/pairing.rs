@@ -0,0 +1,260 @@
+// ABOUTME: Tests device pairing on the local gateway with a roles file.
+// ABOUTME: A device requests a code, an owner approves it as a principal they name, and the device gets its key granted and a token.
+
+use coven_proto::client::{AdminServiceClient, ClientServiceClient};
+use coven_proto::{ApprovePairingRequest, GetPairingStatusRequest, RequestPairingCodeRequest};
+use coven_serve::roles::{PrincipalRoles, MEMBER, OWNER};
+use coven_serve::{RolesConfig, ServeConfig, Server};
+use coven_ssh::{compute_fingerprint, PrivateKey, SshAuthCredentials};
+use std::path::Path;
+use tonic::service::interceptor::InterceptedService;
+use tonic::transport::Channel;
+use tonic::{Code, Request, Status};
+
+type Signer = Box<dyn FnMut(Request<()>) -> Result<Request<()>, Status> + Send>;
+
+/// Signs every request with `key`
+fn signer(key: PrivateKey) -> Signer {
+    Box::new(move |mut request: Request<()>| {
+        SshAuthCredentials::new(&key)
+            .and_then(|creds| creds.apply_to_request(&mut request))
+            .map_err(|e| Status::internal(e.to_string()))?;
+        Ok(request)
+    })
+}
+
+async fn channel(url: &str) -> Channel {
+    Channel::from_shared(url.to_string())
+        .unwrap()
+        .connect()
+        .await
+        .unwrap()
+}
+
+async fn client(
+    url: &str,
+    key: &PrivateKey,
+) -> ClientServiceClient<InterceptedService<Channel, Signer>> {
+    ClientServiceClient::with_interceptor(channel(url).await, signer(key.clone()))
+}
+
+async fn admin(
+    url: &str,
+    key: &PrivateKey,
+) -> AdminServiceClient<InterceptedService<Channel, Signer>> {
+    AdminServiceClient::with_interceptor(channel(url).await, signer(key.clone()))
+}
+
+/// A gateway where `owner` is an owner
+async fn start(dir: &Path, owner: &PrivateKey) -> coven_serve::RunningServer {
+    Server::start(ServeConfig {
+        grpc_addr: "127.0.0.1:0".to_string(),
+        db_path: dir.join("gateway.db"),
+        roles: Some(RolesConfig {
+            principals: vec![PrincipalRoles {
+                name: "owner".to_string(),
+                public_key: owner.public_key().to_openssh().unwrap(),
+                roles: vec![OWNER.to_string()],
+            }],
+            ..Default::default()
+        }),
+        ..Default::default()
+    })
+    .await
+    .unwrap()
+}
+
+async fn status(url: &str, key: &PrivateKey, code: &str) -> Result<String, Status> {
+    let status = client(url, key)
+        .await
+        .get_pairing_status(GetPairingStatusRequest {
+            code: code.to_string(),
+        })
+        .await?
+        .into_inner();
+    Ok(status.status)
+}
+
+#[tokio::test]
+async fn test_approved_device_signs_as_its_principal() {
+    let dir = tempfile::tempdir().unwrap();
+    let owner = coven_ssh::generate_key(&dir.path().join("owner_key")).unwrap();
+    let device = coven_ssh::generate_key(&dir.path().join("device_key")).unwrap();
+    let server = start(dir.path(), &owner).await;
+    let url = server.url();
+
+    let code = client(&url, &device)
+        .await
+        .request_pairing_code(RequestPairingCodeRequest {
+            device_name: "phone".to_string(),
+        })
+        .await
+        .unwrap()
+        .into_inner();
+    assert_eq!(
+        code.fingerprint,
+        compute_fingerprint(device.public_key()).unwrap()
+    );
+    assert_eq!(status(&url, &device, &code.code).await.unwrap(), "pending");
+
+    // Only admins approve, and each code once
+    let request = ApprovePairingRequest {
+        code: code.code.to_lowercase(),
+        principal_id: "device:phone".to_string(),
+    };
+    let err = admin(&url, &device)
+        .await
+        .approve_pairing(request.clone())
+        .await
+        .unwrap_err();
+    assert_eq!(err.code(), Code::PermissionDenied);
+    let approved = admin(&url, &owner)
+        .await
+        .approve_pairing(request.clone())
+        .await
+        .unwrap()
+        .into_inner();
+    assert_eq!(approved.principal_id, "device:phone");
+    assert_eq!(approved.fingerprint, code.fingerprint);
+    let err = admin(&url, &owner)
+        .await
+        .approve_pairing(request)
+        .await
+        .unwrap_err();
+    assert_eq!(err.code(), Code::FailedPrecondition);
+
+    // The device hears about the approval once, then the code is gone
+    let status_response = client(&url, &device)
+        .await
+        .get_pairing_status(GetPairingStatusRequest {
+            code: code.code.clone(),
+        })
+        .await
+        .unwrap()
+        .into_inner();
+    assert_eq!(status_response.status, "approved");
+    assert_eq!(
+        status_response.principal_id.as_deref(),
+        Some("device:phone")
+    );
+    assert_eq!(
+        status(&url, &device, &code.code).await.unwrap_err().code(),
+        Code::NotFound
+    );
+
+    // The token handed out with the approval stands in for the key
+    let token = status_response.token.expect("approved device gets a token");
+    let mut with_token =
+        ClientServiceClient::with_interceptor(channel(&url).await, move |mut r: Request<()>| {
+            let value = format!("Bearer {}", token).parse().unwrap();
+            r.metadata_mut().insert("authorization", value);
+            Ok::<_, Status>(r)
+        });
+    let me = with_token.get_me(()).await.unwrap().into_inner();
+    assert_eq!(me.principal_id, "device:phone");
+
+    let me = client(&url, &device)
+        .await
+        .get_me(())
+        .await
+        .unwrap()
+        .into_inner();
+    assert_eq!(me.principal_id, "device:phone");
+    assert_eq!(me.roles, [MEMBER]);
+    server.shutdown().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_pairing_into_an_existing_principal() {
+    let dir = tempfile::tempdir().unwrap();
+    let owner = coven_ssh::generate_key(&dir.path().join("owner_key")).unwrap();
+    let laptop = coven_ssh::generate_key(&dir.path().join("laptop_key")).unwrap();
+    let server = start(dir.path(), &owner).await;
+    let url = server.url();
+
+    let code = client(&url, &laptop)
+        .await
+        .request_pairing_code(RequestPairingCodeRequest {
+            device_name: "laptop".to_string(),
+        })
+        .await
+        .unwrap()
+        .into_inner()
+        .code;
+    // Another key can't poll the code, nor can anyone approve a made-up one
+    assert_eq!(
+        status(&url, &owner, &code).await.unwrap_err().code(),
+        Code::NotFound
+    );
+    let mut as_owner = admin(&url, &owner).await;
+    let err = as_owner
+        .approve_pairing(ApprovePairingRequest {
+            code: "AAAA-AAAA".to_string(),
+            principal_id: "owner".to_string(),
+        })
+        .await
+        .unwrap_err();
+    assert_eq!(err.code(), Code::NotFound);
+    // The approver has to say who the device is
+    let err = as_owner
+        .approve_pairing(ApprovePairingRequest {
+            code: code.clone(),
+            principal_id: " ".to_string(),
+        })
+        .await
+        .unwrap_err();
+    assert_eq!(err.code(), Code::InvalidArgument);
+    assert_eq!(status(&url, &laptop, &code).await.unwrap(), "pending");
+
+    as_owner
+        .approve_pairing(ApprovePairingRequest {
+            code: code.clone(),
+            principal_id: "owner".to_string(),
+        })
+        .await
+        .unwrap();
+    assert_eq!(status(&url, &laptop, &code).await.unwrap(), "approved");
+    let me = client(&url, &laptop)
+        .await
+        .get_me(())
+        .await
+        .unwrap()
+        .into_inner();
+    assert_eq!(me.principal_id, "owner");
+    assert_eq!(me.roles, [OWNER]);
+    server.shutdown().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_pairing_needs_a_signed_request_and_roles() {
+    let dir = tempfile::tempdir().unwrap();
+    let owner = coven_ssh::generate_key(&dir.path().join("owner_key")).unwrap();
+    let server = start(dir.path(), &owner).await;
+
+    let mut unsigned = ClientServiceClient::connect(server.url()).await.unwrap();
+    let err = unsigned
+        .request_pairing_code(RequestPairingCodeRequest {
+            device_name: "phone".to_string(),
+        })
+        .await
+        .unwrap_err();
+    assert_eq!(err.code(), Code::Unauthenticated);
+    server.shutdown().await.unwrap();
+
+    let trusted = Server::start(ServeConfig {
+        grpc_addr: "127.0.0.1:0".to_string(),
+        db_path: dir.path().join("trusted.db"),
+        ..Default::default()
+    })
+    .await
+    .unwrap();
+    let err = client(&trusted.url(), &owner)
+        .await
+        .request_pairing_code(RequestPairingCodeRequest {
+            device_name: "phone".to_string(),
+        })
+        .await
+        .unwrap_err();
+    assert_eq!(err.code(), Code::FailedPrecondition);
+    trusted.shutdown().await.unwrap();
+}
@@ -0,0 +1,242 @@
+// ABOUTME: Tests push token registration and webhook delivery against the local gateway.
+// ABOUTME: A tiny HTTP server stands in for the push webhook and records what it is sent.
+
+use coven_proto::client::{AdminServiceClient, ClientServiceClient, CovenControlClient};
+use coven_proto::{
+    agent_message, message_response, server_message, AgentInitiated, AgentMessage,
+    ListPushTokensRequest, MessageResponse, RegisterAgent, RegisterPushTokenRequest,
+    ToolApprovalRequest, UnregisterPushTokenRequest,
+};
+use coven_serve::push::PushNotification;
+use coven_serve::{ServeConfig, Server};
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
+
+fn config(dir: &tempfile::TempDir, push_webhook: Option<String>) -> ServeConfig {
+    ServeConfig {
+        grpc_addr: "127.0.0.1:0".to_string(),
+        db_path: dir.path().join("gateway.db"),
+        push_webhook,
+        ..Default::default()
+    }
+}
+
+fn register(platform: &str, token: &str) -> RegisterPushTokenRequest {
+    RegisterPushTokenRequest {
+        platform: platform.to_string(),
+        token: token.to_string(),
+    }
+}
+
+/// Accept webhook POSTs, answer 200, and pass each parsed body on
+async fn start_webhook() -> (String, mpsc::Receiver<PushNotification>) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}/push", listener.local_addr().unwrap());
+    let (tx, rx) = mpsc::channel(8);
+    tokio::spawn(async move {
+        loop {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut request = Vec::new();
+            let mut buf = [0u8; 4096];
+            let body = loop {
+                let n = socket.read(&mut buf).await.unwrap();
+                assert!(n > 0, "webhook request ended early");
+                request.extend_from_slice(&buf[..n]);
+                let Some(end) = request.windows(4).position(|w| w == b"\r\n\r\n") else {
+                    continue;
+                };
+                let head = String::from_utf8_lossy(&request[..end]).to_lowercase();
+                let length: usize = head
+                    .lines()
+                    .find_map(|line| line.strip_prefix("content-length:"))
+                    .map(|v| v.trim().parse().unwrap())
+                    .unwrap_or(0);
+                if request.len() >= end + 4 + length {
+                    break request[end + 4..end + 4 + length].to_vec();
+                }
+            };
+            socket
+                .write_all(b"HTTP/1.1 200 OK\r\ncontent-length: 0\r\nconnection: close\r\n\r\n")
+                .await
+                .unwrap();
+            tx.send(serde_json::from_slice(&body).unwrap())
+                .await
+                .unwrap();
+        }
+    });
+    (url, rx)
+}
+
+#[tokio::test]
+async fn test_push_token_register_list_unregister() {
+    let dir = tempfile::tempdir().unwrap();
+    let server = Server::start(config(&dir, None)).await.unwrap();
+    let mut client = ClientServiceClient::connect(server.url()).await.unwrap();
+    let mut admin = AdminServiceClient::connect(server.url()).await.unwrap();
+
+    let first = client
+        .register_push_token(register("apns", "device-1"))
+        .await
+        .unwrap()
+        .into_inner();
+    assert!(first.created);
+    let again = client
+        .register_push_token(register("apns", "device-1"))
+        .await
+        .unwrap()
+        .into_inner();
+    assert!(!again.created);
+    client
+        .register_push_token(register("fcm", "device-2"))
+        .await
+        .unwrap();
+
+    let err = client
+        .register_push_token(register("sms", "device-3"))
+        .await
+        .unwrap_err();
+    assert_eq!(err.code(), tonic::Code::InvalidArgument);
+    let err = client
+        .register_push_token(register("fcm", "  "))
+        .await
+        .unwrap_err();
+    assert_eq!(err.code(), tonic::Code::InvalidArgument);
+
+    let tokens = admin
+        .list_push_tokens(ListPushTokensRequest {
+            principal_id: Some("local-user".to_string()),
+        })
+        .await
+        .unwrap()
+        .into_inner()
+        .tokens;
+    let listed: Vec<(&str, &str)> = tokens
+        .iter()
+        .map(|t| (t.platform.as_str(), t.token.as_str()))
+        .collect();
+    assert_eq!(listed, [("apns", "device-1"), ("fcm", "device-2")]);
+
+    let removed = client
+        .unregister_push_token(UnregisterPushTokenRequest {
+            token: "device-1".to_string(),
+        })
+        .await
+        .unwrap()
+        .into_inner();
+    assert!(removed.removed);
+    let removed = client
+        .unregister_push_token(UnregisterPushTokenRequest {
+            token: "device-1".to_string(),
+        })
+        .await
+        .unwrap()
+        .into_inner();
+    assert!(!removed.removed);
+
+    let tokens = admin
+        .list_push_tokens(ListPushTokensRequest { principal_id: None })
+        .await
+        .unwrap()
+        .into_inner()
+        .tokens;
+    assert_eq!(tokens.len(), 1);
+    assert_eq!(tokens[0].token, "device-2");
+
+    server.shutdown().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_agent_messages_and_approvals_are_pushed() {
+    let dir = tempfile::tempdir().unwrap();
+    let (webhook, mut pushes) = start_webhook().await;
+    let server = Server::start(config(&dir, Some(webhook))).await.unwrap();
+    let url = server.url();
+
+    let mut client = ClientServiceClient::connect(url.clone()).await.unwrap();
+    client
+        .register_push_token(register("apns", "device-1"))
+        .await
+        .unwrap();
+
+    let (agent_tx, agent_rx) = mpsc::channel(8);
+    agent_tx
+        .send(AgentMessage {
+            payload: Some(agent_message::Payload::Register(RegisterAgent {
+                agent_id: "agent-1".to_string(),
+                name: "Agent One".to_string(),
+                ..Default::default()
+            })),
+        })
+        .await
+        .unwrap();
+    let mut control = CovenControlClient::connect(url).await.unwrap();
+    let mut inbound = control
+        .agent_stream(ReceiverStream::new(agent_rx))
+        .await
+        .unwrap()
+        .into_inner();
+    let welcome = inbound.message().await.unwrap().unwrap();
+    assert!(matches!(
+        welcome.payload,
+        Some(server_message::Payload::Welcome(_))
+    ));
+
+    agent_tx
+        .send(AgentMessage {
+            payload: Some(agent_message::Payload::AgentInitiated(AgentInitiated {
+                message_id: "msg-1".to_string(),
+                content: "Build finished".to_string(),
+                thread_id: None,
+            })),
+        })
+        .await
+        .unwrap();
+    agent_tx
+        .send(AgentMessage {
+            payload: Some(agent_message::Payload::Response(MessageResponse {
+                request_id: "req-1".to_string(),
+                event: Some(message_response::Event::ToolApprovalRequest(
+                    ToolApprovalRequest {
+                        id: "tool-1".to_string(),
+                        name: "bash".to_string(),
+                        input_json: r#"{"command":"rm -rf build"}"#.to_string(),
+                        confirm_message: Some("Delete the build directory?".to_string()),
+                    },
+                )),
+            })),
+        })
+        .await
+        .unwrap();
+
+    let mut received = Vec::new();
+    for _ in 0..2 {
+        let push = tokio::time::timeout(Duration::from_secs(10), pushes.recv())
+            .await
+            .expect("push in time")
+            .unwrap();
+        received.push(push);
+    }
+    // Deliveries run concurrently, so they may arrive in either order
+    received.sort_by(|a, b| a.kind.cmp(&b.kind));
+
+    let message = &received[0];
+    assert_eq!(message.kind, "agent_message");
+    assert_eq!(message.agent_id, "agent-1");
+    assert_eq!(message.id, "msg-1");
+    assert_eq!(message.title, "Agent One");
+    assert_eq!(message.body, "Build finished");
+    assert_eq!(message.targets.len(), 1);
+    assert_eq!(message.targets[0].platform, "apns");
+    assert_eq!(message.targets[0].token, "device-1");
+
+    let approval = &received[1];
+    assert_eq!(approval.kind, "tool_approval");
+    assert_eq!(approval.id, "tool-1");
+    assert_eq!(approval.body, "Delete the build directory?");
+    assert_eq!(approval.targets, message.targets);
+
+    server.shutdown().await.unwrap();
+}
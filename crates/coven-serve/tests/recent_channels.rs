@@ -0,0 +1,104 @@
+// ABOUTME: End-to-end test of recording bridge channels for the binding picker.
+// ABOUTME: Messages relayed with a channel show up in ListRecentChannels, most recent first.
+
+use coven_proto::client::{AdminServiceClient, ClientServiceClient, CovenControlClient};
+use coven_proto::{
+    agent_message, server_message, AgentMessage, ClientSendMessageRequest,
+    ListRecentChannelsRequest, RegisterAgent,
+};
+use coven_serve::{ServeConfig, Server};
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
+
+fn relayed(frontend: &str, channel_id: &str, name: Option<&str>) -> ClientSendMessageRequest {
+    ClientSendMessageRequest {
+        conversation_key: "agent-1".to_string(),
+        content: "hello".to_string(),
+        channel_frontend: Some(frontend.to_string()),
+        channel_id: Some(channel_id.to_string()),
+        channel_name: name.map(str::to_string),
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn test_relayed_messages_record_their_channel() {
+    let dir = tempfile::tempdir().unwrap();
+    let server = Server::start(ServeConfig {
+        grpc_addr: "127.0.0.1:0".to_string(),
+        db_path: dir.path().join("gateway.db"),
+        ..Default::default()
+    })
+    .await
+    .unwrap();
+
+    let (agent_tx, agent_rx) = mpsc::channel(8);
+    agent_tx
+        .send(AgentMessage {
+            payload: Some(agent_message::Payload::Register(RegisterAgent {
+                agent_id: "agent-1".to_string(),
+                name: "Agent One".to_string(),
+                ..Default::default()
+            })),
+        })
+        .await
+        .unwrap();
+    let mut control = CovenControlClient::connect(server.url()).await.unwrap();
+    let mut inbound = control
+        .agent_stream(ReceiverStream::new(agent_rx))
+        .await
+        .unwrap()
+        .into_inner();
+    let welcome = inbound.message().await.unwrap().unwrap();
+    assert!(matches!(
+        welcome.payload,
+        Some(server_message::Payload::Welcome(_))
+    ));
+
+    let mut client = ClientServiceClient::connect(server.url()).await.unwrap();
+    client
+        .send_message(relayed("slack", "C1", Some("#general")))
+        .await
+        .unwrap();
+    client
+        .send_message(relayed("telegram", "42", None))
+        .await
+        .unwrap();
+    // Messages without a channel, e.g. from the TUI, record nothing
+    client
+        .send_message(ClientSendMessageRequest {
+            conversation_key: "agent-1".to_string(),
+            content: "from the terminal".to_string(),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+
+    let mut admin = AdminServiceClient::connect(server.url()).await.unwrap();
+    let channels = admin
+        .list_recent_channels(ListRecentChannelsRequest::default())
+        .await
+        .unwrap()
+        .into_inner()
+        .channels;
+    let ids: Vec<&str> = channels.iter().map(|c| c.channel_id.as_str()).collect();
+    assert_eq!(ids, vec!["42", "C1"]);
+
+    let slack = admin
+        .list_recent_channels(ListRecentChannelsRequest {
+            frontend: Some("slack".to_string()),
+            unbound_only: true,
+            limit: 0,
+        })
+        .await
+        .unwrap()
+        .into_inner()
+        .channels;
+    assert_eq!(slack.len(), 1);
+    assert_eq!(slack[0].channel_id, "C1");
+    assert_eq!(slack[0].display_name.as_deref(), Some("#general"));
+    assert_eq!(slack[0].bound_agent_id, None);
+
+    drop(agent_tx);
+    server.shutdown().await.unwrap();
+}
@@ -0,0 +1,97 @@
+// ABOUTME: End-to-end test of per-request model and max_tokens overrides.
+// ABOUTME: Invalid overrides are refused with InvalidArgument; valid ones reach the agent unchanged.
+
+use coven_proto::client::{ClientServiceClient, CovenControlClient};
+use coven_proto::{
+    agent_message, server_message, AgentMessage, ClientSendMessageRequest, RegisterAgent,
+};
+use coven_serve::{ServeConfig, Server};
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
+
+#[tokio::test]
+async fn test_invalid_overrides_are_refused() {
+    let dir = tempfile::tempdir().unwrap();
+    let server = Server::start(ServeConfig {
+        grpc_addr: "127.0.0.1:0".to_string(),
+        db_path: dir.path().join("gateway.db"),
+        ..Default::default()
+    })
+    .await
+    .unwrap();
+
+    // Register a fake agent and wait for its welcome
+    let (agent_tx, agent_rx) = mpsc::channel(8);
+    agent_tx
+        .send(AgentMessage {
+            payload: Some(agent_message::Payload::Register(RegisterAgent {
+                agent_id: "agent-1".to_string(),
+                name: "Agent One".to_string(),
+                ..Default::default()
+            })),
+        })
+        .await
+        .unwrap();
+    let mut control = CovenControlClient::connect(server.url()).await.unwrap();
+    let mut inbound = control
+        .agent_stream(ReceiverStream::new(agent_rx))
+        .await
+        .unwrap()
+        .into_inner();
+    let welcome = inbound.message().await.unwrap().unwrap();
+    assert!(matches!(
+        welcome.payload,
+        Some(server_message::Payload::Welcome(_))
+    ));
+
+    let mut client = ClientServiceClient::connect(server.url()).await.unwrap();
+    let err = client
+        .send_message(ClientSendMessageRequest {
+            conversation_key: "agent-1".to_string(),
+            content: "hello".to_string(),
+            max_tokens: Some(0),
+            ..Default::default()
+        })
+        .await
+        .unwrap_err();
+    assert_eq!(err.code(), tonic::Code::InvalidArgument);
+
+    let err = client
+        .send_message(ClientSendMessageRequest {
+            conversation_key: "agent-1".to_string(),
+            content: "hello".to_string(),
+            model: Some("bad model".to_string()),
+            ..Default::default()
+        })
+        .await
+        .unwrap_err();
+    assert_eq!(err.code(), tonic::Code::InvalidArgument);
+
+    // Valid overrides pass through to the agent
+    client
+        .send_message(ClientSendMessageRequest {
+            conversation_key: "agent-1".to_string(),
+            content: "hello".to_string(),
+            model: Some("claude-haiku-4-5".to_string()),
+            max_tokens: Some(1024),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+    let msg = tokio::time::timeout(Duration::from_secs(5), inbound.message())
+        .await
+        .unwrap()
+        .unwrap()
+        .unwrap();
+    match msg.payload {
+        Some(server_message::Payload::SendMessage(send)) => {
+            assert_eq!(send.model.as_deref(), Some("claude-haiku-4-5"));
+            assert_eq!(send.max_tokens, Some(1024));
+        }
+        other => panic!("expected SendMessage, got {:?}", other),
+    }
+
+    drop(agent_tx);
+    server.shutdown().await.unwrap();
+}
@@ -0,0 +1,333 @@
+// ABOUTME: Tests that clients choose between streamed and buffered agent responses.
+// ABOUTME: A fake agent answers while one subscriber streams and another waits for the whole response.
+// ABOUTME: Concurrent requests keep their text apart, and streamed text is moderated as a whole.
+
+use coven_proto::client::{ClientServiceClient, CovenControlClient};
+use coven_proto::{
+    agent_message, client_stream_event, message_response, server_message, AgentMessage,
+    ClientStreamEvent, Done, MessageResponse, RegisterAgent, ServerMessage, StreamEventsRequest,
+    ToolResult, ToolSummary, ToolUse,
+};
+use coven_serve::{ModerationConfig, ServeConfig, Server};
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
+use tonic::Streaming;
+
+const AGENT_ID: &str = "agent-1";
+
+/// Connect a fake agent, returning its outbound sender and inbound stream
+async fn connect_agent(url: &str) -> (mpsc::Sender<AgentMessage>, Streaming<ServerMessage>) {
+    let (agent_tx, agent_rx) = mpsc::channel(8);
+    agent_tx
+        .send(AgentMessage {
+            payload: Some(agent_message::Payload::Register(RegisterAgent {
+                agent_id: AGENT_ID.to_string(),
+                name: "Agent One".to_string(),
+                ..Default::default()
+            })),
+        })
+        .await
+        .unwrap();
+    let mut control = CovenControlClient::connect(url.to_string()).await.unwrap();
+    let mut inbound = control
+        .agent_stream(ReceiverStream::new(agent_rx))
+        .await
+        .unwrap()
+        .into_inner();
+    let welcome = inbound.message().await.unwrap().unwrap();
+    assert!(matches!(
+        welcome.payload,
+        Some(server_message::Payload::Welcome(_))
+    ));
+    (agent_tx, inbound)
+}
+
+async fn respond(agent_tx: &mpsc::Sender<AgentMessage>, event: message_response::Event) {
+    respond_to(agent_tx, "req-1", event).await;
+}
+
+async fn respond_to(
+    agent_tx: &mpsc::Sender<AgentMessage>,
+    request_id: &str,
+    event: message_response::Event,
+) {
+    agent_tx
+        .send(AgentMessage {
+            payload: Some(agent_message::Payload::Response(MessageResponse {
+                request_id: request_id.to_string(),
+                event: Some(event),
+            })),
+        })
+        .await
+        .unwrap();
+}
+
+fn text(content: &str) -> message_response::Event {
+    message_response::Event::Text(content.to_string())
+}
+
+/// A Done that leaves the full response to the streamed text
+fn done() -> message_response::Event {
+    message_response::Event::Done(Done {
+        full_response: String::new(),
+    })
+}
+
+/// The full response in the next Done on `events`
+async fn full_response(events: &mut Streaming<ClientStreamEvent>) -> String {
+    let payloads = until_done(events).await;
+    let [client_stream_event::Payload::Done(done)] = payloads.as_slice() else {
+        panic!("expected only Done, got {:?}", payloads);
+    };
+    done.full_response.clone().unwrap()
+}
+
+async fn subscribe(url: &str, stream: Option<bool>) -> Streaming<ClientStreamEvent> {
+    let mut client = ClientServiceClient::connect(url.to_string()).await.unwrap();
+    client
+        .stream_events(StreamEventsRequest {
+            conversation_key: AGENT_ID.to_string(),
+            since_event_id: None,
+            stream,
+        })
+        .await
+        .unwrap()
+        .into_inner()
+}
+
+/// Payloads of `events` through the first Done
+async fn until_done(
+    events: &mut Streaming<ClientStreamEvent>,
+) -> Vec<client_stream_event::Payload> {
+    let mut payloads = Vec::new();
+    loop {
+        let event = tokio::time::timeout(Duration::from_secs(5), events.message())
+            .await
+            .expect("event in time")
+            .unwrap()
+            .unwrap();
+        let payload = event.payload.unwrap();
+        let done = matches!(payload, client_stream_event::Payload::Done(_));
+        payloads.push(payload);
+        if done {
+            return payloads;
+        }
+    }
+}
+
+/// The agent reads a file, fails to run a command, and says so
+async fn answer(agent_tx: &mpsc::Sender<AgentMessage>, full_response: &str) {
+    respond(
+        agent_tx,
+        message_response::Event::Text("Let me ".to_string()),
+    )
+    .await;
+    respond(
+        agent_tx,
+        message_response::Event::ToolUse(ToolUse {
+            id: "tool-1".to_string(),
+            name: "read_file".to_string(),
+            input_json: r#"{"path":"notes.md"}"#.to_string(),
+        }),
+    )
+    .await;
+    respond(
+        agent_tx,
+        message_response::Event::ToolResult(ToolResult {
+            id: "tool-1".to_string(),
+            output: "# Notes".to_string(),
+            is_error: false,
+        }),
+    )
+    .await;
+    respond(
+        agent_tx,
+        message_response::Event::ToolUse(ToolUse {
+            id: "tool-2".to_string(),
+            name: "bash".to_string(),
+            input_json: r#"{"command":"make"}"#.to_string(),
+        }),
+    )
+    .await;
+    respond(
+        agent_tx,
+        message_response::Event::ToolResult(ToolResult {
+            id: "tool-2".to_string(),
+            output: "make: not found".to_string(),
+            is_error: true,
+        }),
+    )
+    .await;
+    respond(
+        agent_tx,
+        message_response::Event::Text("check.".to_string()),
+    )
+    .await;
+    respond(
+        agent_tx,
+        message_response::Event::Done(Done {
+            full_response: full_response.to_string(),
+        }),
+    )
+    .await;
+}
+
+fn tool(id: &str, name: &str, is_error: bool) -> ToolSummary {
+    ToolSummary {
+        id: id.to_string(),
+        name: name.to_string(),
+        is_error,
+        completed: true,
+    }
+}
+
+#[tokio::test]
+async fn test_streaming_and_buffered_subscribers() {
+    let dir = tempfile::tempdir().unwrap();
+    let server = Server::start(ServeConfig {
+        grpc_addr: "127.0.0.1:0".to_string(),
+        db_path: dir.path().join("gateway.db"),
+        ..Default::default()
+    })
+    .await
+    .unwrap();
+    let url = server.url();
+
+    let (agent_tx, _inbound) = connect_agent(&url).await;
+    let mut streamed = subscribe(&url, None).await;
+    let mut buffered = subscribe(&url, Some(false)).await;
+    answer(&agent_tx, "Let me check.").await;
+
+    // Streaming, the default, delivers every event as it happens
+    let payloads = until_done(&mut streamed).await;
+    assert_eq!(payloads.len(), 7, "{payloads:?}");
+    assert!(matches!(
+        &payloads[0],
+        client_stream_event::Payload::Text(chunk) if chunk.content == "Let me "
+    ));
+    assert!(matches!(
+        &payloads[1],
+        client_stream_event::Payload::ToolUse(tool) if tool.name == "read_file"
+    ));
+
+    // Buffered, only the end arrives, with everything in it
+    let payloads = until_done(&mut buffered).await;
+    let [client_stream_event::Payload::Done(done)] = payloads.as_slice() else {
+        panic!("expected only Done, got {:?}", payloads);
+    };
+    assert_eq!(done.full_response.as_deref(), Some("Let me check."));
+    assert_eq!(
+        done.tools,
+        [
+            tool("tool-1", "read_file", false),
+            tool("tool-2", "bash", true)
+        ]
+    );
+
+    // Each response starts afresh; an agent that doesn't repeat its text
+    // in Done gets the streamed text as the full response
+    respond(
+        &agent_tx,
+        message_response::Event::Text("Done.".to_string()),
+    )
+    .await;
+    respond(
+        &agent_tx,
+        message_response::Event::Done(Done {
+            full_response: String::new(),
+        }),
+    )
+    .await;
+    let payloads = until_done(&mut buffered).await;
+    let [client_stream_event::Payload::Done(done)] = payloads.as_slice() else {
+        panic!("expected only Done, got {:?}", payloads);
+    };
+    assert_eq!(done.full_response.as_deref(), Some("Done."));
+    assert!(done.tools.is_empty());
+
+    // Errors aren't held back
+    respond(
+        &agent_tx,
+        message_response::Event::Text("Partial".to_string()),
+    )
+    .await;
+    respond(
+        &agent_tx,
+        message_response::Event::Error("agent crashed".to_string()),
+    )
+    .await;
+    let event = tokio::time::timeout(Duration::from_secs(5), buffered.message())
+        .await
+        .unwrap()
+        .unwrap()
+        .unwrap();
+    assert!(matches!(
+        event.payload,
+        Some(client_stream_event::Payload::Error(err)) if err.message == "agent crashed"
+    ));
+
+    server.shutdown().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_concurrent_requests_keep_their_text_apart() {
+    let dir = tempfile::tempdir().unwrap();
+    let server = Server::start(ServeConfig {
+        grpc_addr: "127.0.0.1:0".to_string(),
+        db_path: dir.path().join("gateway.db"),
+        ..Default::default()
+    })
+    .await
+    .unwrap();
+    let url = server.url();
+
+    let (agent_tx, _inbound) = connect_agent(&url).await;
+    let mut buffered = subscribe(&url, Some(false)).await;
+    respond_to(&agent_tx, "req-1", text("First ")).await;
+    respond_to(&agent_tx, "req-2", text("Second ")).await;
+    respond_to(&agent_tx, "req-1", text("answer.")).await;
+    respond_to(&agent_tx, "req-2", text("answer.")).await;
+    respond_to(&agent_tx, "req-2", done()).await;
+    respond_to(&agent_tx, "req-1", done()).await;
+
+    assert_eq!(full_response(&mut buffered).await, "Second answer.");
+    assert_eq!(full_response(&mut buffered).await, "First answer.");
+
+    server.shutdown().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_streamed_text_is_moderated_as_a_whole() {
+    let dir = tempfile::tempdir().unwrap();
+    let server = Server::start(ServeConfig {
+        grpc_addr: "127.0.0.1:0".to_string(),
+        db_path: dir.path().join("gateway.db"),
+        moderation: Some(ModerationConfig {
+            deny_patterns: vec!["(?s)alpha.*omega".to_string()],
+            filter_outbound: true,
+            ..Default::default()
+        }),
+        ..Default::default()
+    })
+    .await
+    .unwrap();
+    let url = server.url();
+
+    let (agent_tx, _inbound) = connect_agent(&url).await;
+    let mut buffered = subscribe(&url, Some(false)).await;
+
+    // Too far apart for any chunk to be refused on its own...
+    respond(&agent_tx, text("alpha ")).await;
+    respond(&agent_tx, text(&"x".repeat(2000))).await;
+    respond(&agent_tx, text(" omega")).await;
+    respond(&agent_tx, done()).await;
+
+    // ...but the response they make up is
+    assert_eq!(
+        full_response(&mut buffered).await,
+        "[removed by content policy]"
+    );
+
+    server.shutdown().await.unwrap();
+}
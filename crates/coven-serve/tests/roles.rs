@@ -0,0 +1,259 @@
+// ABOUTME: Tests role-based authorization on the local gateway when it has a roles file.
+// ABOUTME: Members are refused admin RPCs and gated tool approvals that owners are allowed.
+
+use coven_proto::client::{AdminServiceClient, ClientServiceClient, CovenControlClient};
+use coven_proto::{
+    agent_message, message_response, server_message, AgentMessage, ApproveToolRequest,
+    CreatePrincipalRequest, ListDeadLettersRequest, ListPendingApprovalsRequest, MessageResponse,
+    RegisterAgent, ServerMessage, ToolApprovalRequest,
+};
+use coven_serve::roles::{PrincipalRoles, OWNER};
+use coven_serve::{RolesConfig, ServeConfig, Server};
+use coven_ssh::{PrivateKey, SshAuthCredentials};
+use std::collections::HashMap;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
+use tonic::service::interceptor::InterceptedService;
+use tonic::transport::Channel;
+use tonic::{Code, Request, Status, Streaming};
+
+const AGENT_ID: &str = "agent-1";
+
+type Signer = Box<dyn FnMut(Request<()>) -> Result<Request<()>, Status> + Send>;
+
+/// Signs every request with `key`
+fn signer(key: PrivateKey) -> Signer {
+    Box::new(move |mut request: Request<()>| {
+        SshAuthCredentials::new(&key)
+            .and_then(|creds| creds.apply_to_request(&mut request))
+            .map_err(|e| Status::internal(e.to_string()))?;
+        Ok(request)
+    })
+}
+
+async fn admin(
+    url: &str,
+    key: PrivateKey,
+) -> AdminServiceClient<InterceptedService<Channel, Signer>> {
+    let channel = Channel::from_shared(url.to_string())
+        .unwrap()
+        .connect()
+        .await
+        .unwrap();
+    AdminServiceClient::with_interceptor(channel, signer(key))
+}
+
+async fn client(
+    url: &str,
+    key: PrivateKey,
+) -> ClientServiceClient<InterceptedService<Channel, Signer>> {
+    let channel = Channel::from_shared(url.to_string())
+        .unwrap()
+        .connect()
+        .await
+        .unwrap();
+    ClientServiceClient::with_interceptor(channel, signer(key))
+}
+
+/// A gateway where `owner` is an owner and any other key a member, and
+/// approving `deploy` takes an owner
+async fn start(dir: &tempfile::TempDir, owner: &PrivateKey) -> coven_serve::RunningServer {
+    Server::start(ServeConfig {
+        grpc_addr: "127.0.0.1:0".to_string(),
+        db_path: dir.path().join("gateway.db"),
+        roles: Some(RolesConfig {
+            tool_roles: HashMap::from([("deploy".to_string(), vec![OWNER.to_string()])]),
+            principals: vec![PrincipalRoles {
+                name: "owner".to_string(),
+                public_key: owner.public_key().to_openssh().unwrap(),
+                roles: vec![OWNER.to_string()],
+            }],
+            ..Default::default()
+        }),
+        ..Default::default()
+    })
+    .await
+    .unwrap()
+}
+
+fn keys(dir: &tempfile::TempDir) -> (PrivateKey, PrivateKey) {
+    (
+        coven_ssh::generate_key(&dir.path().join("owner_key")).unwrap(),
+        coven_ssh::generate_key(&dir.path().join("member_key")).unwrap(),
+    )
+}
+
+#[tokio::test]
+async fn test_member_is_denied_admin_rpcs_and_owner_is_allowed() {
+    let dir = tempfile::tempdir().unwrap();
+    let (owner, member) = keys(&dir);
+    let server = start(&dir, &owner).await;
+    let url = server.url();
+
+    let mut as_member = admin(&url, member.clone()).await;
+    let err = as_member
+        .list_dead_letters(ListDeadLettersRequest { agent_id: None })
+        .await
+        .unwrap_err();
+    assert_eq!(err.code(), Code::PermissionDenied);
+    assert!(err.message().contains("requires one of the roles: owner"));
+    let err = as_member
+        .create_principal(CreatePrincipalRequest::default())
+        .await
+        .unwrap_err();
+    assert_eq!(err.code(), Code::PermissionDenied);
+
+    // Unsigned callers have no roles at all
+    let mut unsigned = AdminServiceClient::connect(url.clone()).await.unwrap();
+    let err = unsigned
+        .list_dead_letters(ListDeadLettersRequest { agent_id: None })
+        .await
+        .unwrap_err();
+    assert_eq!(err.code(), Code::PermissionDenied);
+
+    let mut as_owner = admin(&url, owner.clone()).await;
+    as_owner
+        .list_dead_letters(ListDeadLettersRequest { agent_id: None })
+        .await
+        .unwrap();
+    // Gets past the role check to the local gateway's own answer
+    let err = as_owner
+        .create_principal(CreatePrincipalRequest::default())
+        .await
+        .unwrap_err();
+    assert_eq!(err.code(), Code::Unimplemented);
+
+    // GetMe reports who the gateway thinks each caller is
+    let me = client(&url, owner)
+        .await
+        .get_me(())
+        .await
+        .unwrap()
+        .into_inner();
+    assert_eq!(
+        (me.principal_id.as_str(), me.roles),
+        ("owner", vec![OWNER.to_string()])
+    );
+    let me = client(&url, member)
+        .await
+        .get_me(())
+        .await
+        .unwrap()
+        .into_inner();
+    assert!(me.principal_id.starts_with("key:"));
+    assert_eq!(me.roles, ["member"]);
+
+    server.shutdown().await.unwrap();
+}
+
+/// Connect a fake agent that asks to run `deploy`
+async fn agent_asking_to_deploy(
+    url: &str,
+) -> (mpsc::Sender<AgentMessage>, Streaming<ServerMessage>) {
+    let (agent_tx, agent_rx) = mpsc::channel(8);
+    agent_tx
+        .send(AgentMessage {
+            payload: Some(agent_message::Payload::Register(RegisterAgent {
+                agent_id: AGENT_ID.to_string(),
+                name: "Agent One".to_string(),
+                ..Default::default()
+            })),
+        })
+        .await
+        .unwrap();
+    let mut control = CovenControlClient::connect(url.to_string()).await.unwrap();
+    let mut inbound = control
+        .agent_stream(ReceiverStream::new(agent_rx))
+        .await
+        .unwrap()
+        .into_inner();
+    let welcome = inbound.message().await.unwrap().unwrap();
+    assert!(matches!(
+        welcome.payload,
+        Some(server_message::Payload::Welcome(_))
+    ));
+    agent_tx
+        .send(AgentMessage {
+            payload: Some(agent_message::Payload::Response(MessageResponse {
+                request_id: "req-1".to_string(),
+                event: Some(message_response::Event::ToolApprovalRequest(
+                    ToolApprovalRequest {
+                        id: "tool-1".to_string(),
+                        name: "deploy".to_string(),
+                        input_json: "{}".to_string(),
+                        confirm_message: None,
+                    },
+                )),
+            })),
+        })
+        .await
+        .unwrap();
+    (agent_tx, inbound)
+}
+
+#[tokio::test]
+async fn test_gated_tool_needs_its_role_to_approve() {
+    let dir = tempfile::tempdir().unwrap();
+    let (owner, member) = keys(&dir);
+    let server = start(&dir, &owner).await;
+    let url = server.url();
+
+    let (_agent_tx, mut inbound) = agent_asking_to_deploy(&url).await;
+    let mut as_member = client(&url, member).await;
+    for _ in 0..100 {
+        let pending = as_member
+            .list_pending_approvals(ListPendingApprovalsRequest {
+                agent_id: AGENT_ID.to_string(),
+            })
+            .await
+            .unwrap()
+            .into_inner()
+            .approvals;
+        if !pending.is_empty() {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+
+    let approve = ApproveToolRequest {
+        agent_id: AGENT_ID.to_string(),
+        tool_id: "tool-1".to_string(),
+        approved: true,
+        approve_all: false,
+    };
+
+    // A tool the gateway can't find can't have its role checked
+    let err = client(&url, owner.clone())
+        .await
+        .approve_tool(ApproveToolRequest {
+            tool_id: "tool-unknown".to_string(),
+            ..approve.clone()
+        })
+        .await
+        .unwrap_err();
+    assert_eq!(err.code(), Code::NotFound);
+
+    let err = as_member.approve_tool(approve.clone()).await.unwrap_err();
+    assert_eq!(err.code(), Code::PermissionDenied);
+    assert!(err.message().contains("approving deploy"));
+
+    let answer = client(&url, owner)
+        .await
+        .approve_tool(approve)
+        .await
+        .unwrap()
+        .into_inner();
+    assert!(answer.success);
+    let forwarded = tokio::time::timeout(Duration::from_secs(5), inbound.message())
+        .await
+        .unwrap()
+        .unwrap()
+        .unwrap();
+    assert!(matches!(
+        forwarded.payload,
+        Some(server_message::Payload::ToolApproval(ref a)) if a.id == "tool-1" && a.approved
+    ));
+
+    server.shutdown().await.unwrap();
+}
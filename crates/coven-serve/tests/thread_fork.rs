@@ -0,0 +1,283 @@
+// ABOUTME: End-to-end test of forking a conversation through the local gateway.
+// ABOUTME: A fake agent is sent the fork's history, and the fork's messages and answers stay apart from the original's.
+
+use coven_proto::client::{ClientServiceClient, CovenControlClient};
+use coven_proto::{
+    agent_message, client_stream_event, message_response, server_message, AgentMessage,
+    ClientSendMessageRequest, ClientStreamEvent, Done, ForkThread, ForkThreadRequest,
+    GetEventsRequest, MessageResponse, RegisterAgent, ServerMessage, StreamEventsRequest,
+};
+use coven_serve::{ServeConfig, Server};
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
+use tonic::transport::Channel;
+use tonic::{Code, Streaming};
+
+const AGENT_ID: &str = "agent-1";
+
+async fn next_message(inbound: &mut Streaming<ServerMessage>) -> server_message::Payload {
+    tokio::time::timeout(Duration::from_secs(10), inbound.message())
+        .await
+        .expect("timed out waiting for the gateway")
+        .unwrap()
+        .expect("gateway closed the stream")
+        .payload
+        .unwrap()
+}
+
+/// Connect a fake agent, returning its outbound sender and inbound stream.
+/// It handles forks if `forks`.
+async fn connect_agent(
+    url: &str,
+    forks: bool,
+) -> (mpsc::Sender<AgentMessage>, Streaming<ServerMessage>) {
+    let (agent_tx, agent_rx) = mpsc::channel(8);
+    let protocol_features = if forks {
+        vec![ForkThread::PROTOCOL_FEATURE.to_string()]
+    } else {
+        Vec::new()
+    };
+    agent_tx
+        .send(AgentMessage {
+            payload: Some(agent_message::Payload::Register(RegisterAgent {
+                agent_id: AGENT_ID.to_string(),
+                name: "Agent One".to_string(),
+                protocol_features,
+                ..Default::default()
+            })),
+        })
+        .await
+        .unwrap();
+    let mut control = CovenControlClient::connect(url.to_string()).await.unwrap();
+    let mut inbound = control
+        .agent_stream(ReceiverStream::new(agent_rx))
+        .await
+        .unwrap()
+        .into_inner();
+    assert!(matches!(
+        next_message(&mut inbound).await,
+        server_message::Payload::Welcome(_)
+    ));
+    (agent_tx, inbound)
+}
+
+/// Send `content` to a conversation, returning the message ID
+async fn send(client: &mut ClientServiceClient<Channel>, key: &str, content: &str) -> String {
+    client
+        .send_message(ClientSendMessageRequest {
+            conversation_key: key.to_string(),
+            content: content.to_string(),
+            idempotency_key: format!("{}-{}", key, content),
+            ..Default::default()
+        })
+        .await
+        .unwrap()
+        .into_inner()
+        .message_id
+}
+
+async fn answer(agent_tx: &mpsc::Sender<AgentMessage>, request_id: &str, full_response: &str) {
+    agent_tx
+        .send(AgentMessage {
+            payload: Some(agent_message::Payload::Response(MessageResponse {
+                request_id: request_id.to_string(),
+                event: Some(message_response::Event::Done(Done {
+                    full_response: full_response.to_string(),
+                })),
+            })),
+        })
+        .await
+        .unwrap();
+}
+
+async fn subscribe(
+    client: &mut ClientServiceClient<Channel>,
+    key: &str,
+) -> Streaming<ClientStreamEvent> {
+    client
+        .stream_events(StreamEventsRequest {
+            conversation_key: key.to_string(),
+            since_event_id: None,
+            stream: None,
+        })
+        .await
+        .unwrap()
+        .into_inner()
+}
+
+/// The full response of the next event on `events`, which must be Done
+async fn next_done(events: &mut Streaming<ClientStreamEvent>) -> (String, String) {
+    let event = tokio::time::timeout(Duration::from_secs(5), events.message())
+        .await
+        .expect("event in time")
+        .unwrap()
+        .unwrap();
+    let Some(client_stream_event::Payload::Done(done)) = event.payload else {
+        panic!("expected Done, got {:?}", event.payload);
+    };
+    (
+        event.conversation_key,
+        done.full_response.unwrap_or_default(),
+    )
+}
+
+async fn contents(client: &mut ClientServiceClient<Channel>, key: &str) -> Vec<String> {
+    client
+        .get_events(GetEventsRequest {
+            conversation_key: key.to_string(),
+            ..Default::default()
+        })
+        .await
+        .unwrap()
+        .into_inner()
+        .events
+        .into_iter()
+        .map(|event| event.text.unwrap_or_default())
+        .collect()
+}
+
+#[tokio::test]
+async fn test_fork_continues_apart_from_the_original() {
+    let dir = tempfile::tempdir().unwrap();
+    let server = Server::start(ServeConfig {
+        grpc_addr: "127.0.0.1:0".to_string(),
+        db_path: dir.path().join("gateway.db"),
+        ..Default::default()
+    })
+    .await
+    .unwrap();
+    let url = server.url();
+
+    let (agent_tx, mut inbound) = connect_agent(&url, true).await;
+    let mut client = ClientServiceClient::connect(url.clone()).await.unwrap();
+    let mut original = subscribe(&mut client, AGENT_ID).await;
+
+    // Two exchanges in the agent's conversation
+    for (content, reply) in [("first", "one"), ("second", "two")] {
+        let id = send(&mut client, AGENT_ID, content).await;
+        let server_message::Payload::SendMessage(sent) = next_message(&mut inbound).await else {
+            panic!("expected a message");
+        };
+        assert_eq!(sent.thread_id, AGENT_ID);
+        answer(&agent_tx, &id, reply).await;
+        assert_eq!(
+            next_done(&mut original).await,
+            (AGENT_ID.to_string(), reply.to_string())
+        );
+    }
+    let events = client
+        .get_events(GetEventsRequest {
+            conversation_key: AGENT_ID.to_string(),
+            ..Default::default()
+        })
+        .await
+        .unwrap()
+        .into_inner()
+        .events;
+    assert_eq!(events.len(), 4);
+
+    // Fork after the first answer
+    let fork = client
+        .fork_thread(ForkThreadRequest {
+            conversation_key: AGENT_ID.to_string(),
+            message_id: events[1].id.clone(),
+        })
+        .await
+        .unwrap()
+        .into_inner()
+        .conversation_key;
+    let server_message::Payload::ForkThread(sent) = next_message(&mut inbound).await else {
+        panic!("expected a thread fork");
+    };
+    assert_eq!(sent.thread_id, AGENT_ID);
+    assert_eq!(sent.new_thread_id, fork);
+    let history: Vec<_> = sent
+        .history
+        .iter()
+        .map(|m| (m.role.as_str(), m.content.as_str()))
+        .collect();
+    assert_eq!(history, [("user", "first"), ("assistant", "one")]);
+    assert_eq!(contents(&mut client, &fork).await, ["first", "one"]);
+
+    // Messages to the fork reach the agent on the fork's thread, and its
+    // answers are streamed and saved there only
+    let mut forked = subscribe(&mut client, &fork).await;
+    let id = send(&mut client, &fork, "instead").await;
+    let server_message::Payload::SendMessage(sent) = next_message(&mut inbound).await else {
+        panic!("expected a message");
+    };
+    assert_eq!(sent.thread_id, fork);
+    answer(&agent_tx, &id, "three").await;
+    assert_eq!(
+        next_done(&mut forked).await,
+        (fork.clone(), "three".to_string())
+    );
+    assert_eq!(
+        contents(&mut client, &fork).await,
+        ["first", "one", "instead", "three"]
+    );
+    assert_eq!(
+        contents(&mut client, AGENT_ID).await,
+        ["first", "one", "second", "two"]
+    );
+
+    let err = client
+        .fork_thread(ForkThreadRequest {
+            conversation_key: AGENT_ID.to_string(),
+            message_id: "missing".to_string(),
+        })
+        .await
+        .unwrap_err();
+    assert_eq!(err.code(), Code::NotFound);
+
+    // The agent forks its own copy, so it has to be connected
+    drop(agent_tx);
+    drop(inbound);
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    let err = client
+        .fork_thread(ForkThreadRequest {
+            conversation_key: AGENT_ID.to_string(),
+            message_id: events[1].id.clone(),
+        })
+        .await
+        .unwrap_err();
+    assert_eq!(err.code(), Code::FailedPrecondition);
+
+    server.shutdown().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_agents_that_cant_fork_are_refused() {
+    let dir = tempfile::tempdir().unwrap();
+    let server = Server::start(ServeConfig {
+        grpc_addr: "127.0.0.1:0".to_string(),
+        db_path: dir.path().join("gateway.db"),
+        ..Default::default()
+    })
+    .await
+    .unwrap();
+    let url = server.url();
+
+    let (agent_tx, mut inbound) = connect_agent(&url, false).await;
+    let mut client = ClientServiceClient::connect(url.clone()).await.unwrap();
+    let mut original = subscribe(&mut client, AGENT_ID).await;
+    let id = send(&mut client, AGENT_ID, "first").await;
+    let server_message::Payload::SendMessage(_) = next_message(&mut inbound).await else {
+        panic!("expected a message");
+    };
+    answer(&agent_tx, &id, "one").await;
+    next_done(&mut original).await;
+
+    let err = client
+        .fork_thread(ForkThreadRequest {
+            conversation_key: AGENT_ID.to_string(),
+            message_id: id,
+        })
+        .await
+        .unwrap_err();
+    assert_eq!(err.code(), Code::FailedPrecondition);
+    assert!(err.message().contains("can't fork"), "{}", err.message());
+
+    server.shutdown().await.unwrap();
+}
@@ -0,0 +1,237 @@
+// ABOUTME: Tests RefreshToken, ListTokens and RevokeToken on the local gateway with a roles file.
+// ABOUTME: A key-signed request gets a token for the key's principal; tokens can't renew themselves, and a revoked one fails its next call.
+
+use coven_proto::client::{AdminServiceClient, ClientServiceClient};
+use coven_proto::{ListTokensRequest, RefreshTokenRequest, RevokeTokenRequest};
+use coven_serve::roles::{PrincipalRoles, OWNER};
+use coven_serve::{RolesConfig, ServeConfig, Server};
+use coven_ssh::{PrivateKey, SshAuthCredentials};
+use std::path::Path;
+use tonic::service::interceptor::InterceptedService;
+use tonic::transport::Channel;
+use tonic::{Code, Request, Status};
+
+type Auth = Box<dyn FnMut(Request<()>) -> Result<Request<()>, Status> + Send>;
+
+/// Signs every request with `key`
+fn signer(key: PrivateKey) -> Auth {
+    Box::new(move |mut request: Request<()>| {
+        SshAuthCredentials::new(&key)
+            .and_then(|creds| creds.apply_to_request(&mut request))
+            .map_err(|e| Status::internal(e.to_string()))?;
+        Ok(request)
+    })
+}
+
+/// Sends `token` as the bearer token of every request
+fn bearer(token: String) -> Auth {
+    Box::new(move |mut request: Request<()>| {
+        let value = format!("Bearer {}", token).parse().unwrap();
+        request.metadata_mut().insert("authorization", value);
+        Ok(request)
+    })
+}
+
+async fn client(url: &str, auth: Auth) -> ClientServiceClient<InterceptedService<Channel, Auth>> {
+    let channel = Channel::from_shared(url.to_string())
+        .unwrap()
+        .connect()
+        .await
+        .unwrap();
+    ClientServiceClient::with_interceptor(channel, auth)
+}
+
+async fn admin(url: &str, auth: Auth) -> AdminServiceClient<InterceptedService<Channel, Auth>> {
+    let channel = Channel::from_shared(url.to_string())
+        .unwrap()
+        .connect()
+        .await
+        .unwrap();
+    AdminServiceClient::with_interceptor(channel, auth)
+}
+
+/// The ID (jti) a token carries
+fn token_id(token: &str) -> String {
+    use base64::Engine;
+    let claims = token.split('.').nth(1).unwrap();
+    let claims = base64::engine::general_purpose::URL_SAFE_NO_PAD
+        .decode(claims)
+        .unwrap();
+    let claims: serde_json::Value = serde_json::from_slice(&claims).unwrap();
+    claims["jti"].as_str().unwrap().to_string()
+}
+
+/// Roles with `owner`'s key as the owner
+fn owner_roles(owner: &PrivateKey) -> RolesConfig {
+    RolesConfig {
+        principals: vec![PrincipalRoles {
+            name: "harper".to_string(),
+            public_key: owner.public_key().to_openssh().unwrap(),
+            roles: vec![OWNER.to_string()],
+        }],
+        ..Default::default()
+    }
+}
+
+async fn start(dir: &Path, roles: Option<RolesConfig>) -> coven_serve::RunningServer {
+    Server::start(ServeConfig {
+        grpc_addr: "127.0.0.1:0".to_string(),
+        db_path: dir.join("gateway.db"),
+        roles,
+        ..Default::default()
+    })
+    .await
+    .unwrap()
+}
+
+#[tokio::test]
+async fn test_signed_request_gets_a_token_for_its_principal() {
+    let dir = tempfile::tempdir().unwrap();
+    let owner = coven_ssh::generate_key(&dir.path().join("owner_key")).unwrap();
+    let server = start(dir.path(), Some(owner_roles(&owner))).await;
+    let url = server.url();
+
+    let fresh = client(&url, signer(owner.clone()))
+        .await
+        .refresh_token(RefreshTokenRequest {})
+        .await
+        .unwrap()
+        .into_inner();
+    let expires_at = chrono::DateTime::parse_from_rfc3339(&fresh.expires_at.unwrap()).unwrap();
+    assert!(expires_at > chrono::Utc::now() + chrono::Duration::days(29));
+
+    // The token stands in for the key
+    let me = client(&url, bearer(fresh.token.clone()))
+        .await
+        .get_me(())
+        .await
+        .unwrap()
+        .into_inner();
+    assert_eq!(me.principal_id, "harper");
+
+    // ...but can't be swapped for another
+    let err = client(&url, bearer(fresh.token))
+        .await
+        .refresh_token(RefreshTokenRequest {})
+        .await
+        .unwrap_err();
+    assert_eq!(err.code(), Code::Unauthenticated);
+
+    // A token this gateway didn't sign is refused outright
+    let err = client(&url, bearer("a.b.c".to_string()))
+        .await
+        .get_me(())
+        .await
+        .unwrap_err();
+    assert_eq!(err.code(), Code::Unauthenticated);
+}
+
+#[tokio::test]
+async fn test_trusted_mode_issues_no_tokens() {
+    let dir = tempfile::tempdir().unwrap();
+    let key = coven_ssh::generate_key(&dir.path().join("key")).unwrap();
+    let server = start(dir.path(), None).await;
+
+    let err = client(&server.url(), signer(key))
+        .await
+        .refresh_token(RefreshTokenRequest {})
+        .await
+        .unwrap_err();
+    assert_eq!(err.code(), Code::FailedPrecondition);
+}
+
+#[tokio::test]
+async fn test_revoked_token_fails_its_next_call() {
+    let dir = tempfile::tempdir().unwrap();
+    let owner = coven_ssh::generate_key(&dir.path().join("owner_key")).unwrap();
+    let server = start(dir.path(), Some(owner_roles(&owner))).await;
+    let url = server.url();
+
+    let mut tokens = Vec::new();
+    for _ in 0..2 {
+        tokens.push(
+            client(&url, signer(owner.clone()))
+                .await
+                .refresh_token(RefreshTokenRequest {})
+                .await
+                .unwrap()
+                .into_inner()
+                .token,
+        );
+    }
+    let (leaked, kept) = (tokens[0].clone(), tokens[1].clone());
+    client(&url, bearer(leaked.clone()))
+        .await
+        .get_me(())
+        .await
+        .unwrap();
+
+    // The owner sees both, by ID only
+    let listed = admin(&url, signer(owner.clone()))
+        .await
+        .list_tokens(ListTokensRequest { principal_id: None })
+        .await
+        .unwrap()
+        .into_inner()
+        .tokens;
+    assert_eq!(listed.len(), 2, "{listed:?}");
+    assert!(listed.iter().all(|t| t.principal_id == "harper"));
+    let leaked_id = token_id(&leaked);
+    assert!(listed.iter().any(|t| t.id == leaked_id));
+
+    admin(&url, signer(owner.clone()))
+        .await
+        .revoke_token(RevokeTokenRequest {
+            token_id: leaked_id.clone(),
+        })
+        .await
+        .unwrap();
+
+    // The revoked token is refused at once; the other still works
+    let err = client(&url, bearer(leaked.clone()))
+        .await
+        .get_me(())
+        .await
+        .unwrap_err();
+    assert_eq!(err.code(), Code::Unauthenticated);
+    assert!(err.message().contains("revoked"), "{}", err.message());
+    client(&url, bearer(kept.clone()))
+        .await
+        .get_me(())
+        .await
+        .unwrap();
+
+    // Revoking it again is an error, and it's gone from the list
+    let err = admin(&url, signer(owner.clone()))
+        .await
+        .revoke_token(RevokeTokenRequest {
+            token_id: leaked_id.clone(),
+        })
+        .await
+        .unwrap_err();
+    assert_eq!(err.code(), Code::NotFound);
+    let listed = admin(&url, signer(owner.clone()))
+        .await
+        .list_tokens(ListTokensRequest { principal_id: None })
+        .await
+        .unwrap()
+        .into_inner()
+        .tokens;
+    assert_eq!(listed.len(), 1);
+    assert_ne!(listed[0].id, leaked_id);
+
+    // ...and it stays revoked across a restart
+    server.shutdown().await.unwrap();
+    let server = start(dir.path(), Some(owner_roles(&owner))).await;
+    let err = client(&server.url(), bearer(leaked))
+        .await
+        .get_me(())
+        .await
+        .unwrap_err();
+    assert_eq!(err.code(), Code::Unauthenticated);
+    client(&server.url(), bearer(kept))
+        .await
+        .get_me(())
+        .await
+        .unwrap();
+}
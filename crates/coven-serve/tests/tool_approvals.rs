@@ -0,0 +1,211 @@
+// ABOUTME: Tests that the local gateway records tool approval requests and how they end.
+// ABOUTME: A fake agent asks for approvals while a client lists, answers, and reads their history.
+
+use coven_proto::client::{ClientServiceClient, CovenControlClient};
+use coven_proto::{
+    agent_message, message_response, server_message, AgentMessage, ApproveToolRequest,
+    GetApprovalHistoryRequest, ListPendingApprovalsRequest, MessageResponse, RegisterAgent,
+    ServerMessage, ToolApprovalRecord, ToolApprovalRequest, ToolState, ToolStateUpdate,
+};
+use coven_serve::{ServeConfig, Server};
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
+use tonic::transport::Channel;
+use tonic::Streaming;
+
+const AGENT_ID: &str = "agent-1";
+
+/// Connect a fake agent, returning its outbound sender and inbound stream
+async fn connect_agent(url: &str) -> (mpsc::Sender<AgentMessage>, Streaming<ServerMessage>) {
+    let (agent_tx, agent_rx) = mpsc::channel(8);
+    agent_tx
+        .send(AgentMessage {
+            payload: Some(agent_message::Payload::Register(RegisterAgent {
+                agent_id: AGENT_ID.to_string(),
+                name: "Agent One".to_string(),
+                ..Default::default()
+            })),
+        })
+        .await
+        .unwrap();
+    let mut control = CovenControlClient::connect(url.to_string()).await.unwrap();
+    let mut inbound = control
+        .agent_stream(ReceiverStream::new(agent_rx))
+        .await
+        .unwrap()
+        .into_inner();
+    let welcome = inbound.message().await.unwrap().unwrap();
+    assert!(matches!(
+        welcome.payload,
+        Some(server_message::Payload::Welcome(_))
+    ));
+    (agent_tx, inbound)
+}
+
+async fn respond(agent_tx: &mpsc::Sender<AgentMessage>, event: message_response::Event) {
+    agent_tx
+        .send(AgentMessage {
+            payload: Some(agent_message::Payload::Response(MessageResponse {
+                request_id: "req-1".to_string(),
+                event: Some(event),
+            })),
+        })
+        .await
+        .unwrap();
+}
+
+fn approval_request(tool_id: &str, name: &str) -> message_response::Event {
+    message_response::Event::ToolApprovalRequest(ToolApprovalRequest {
+        id: tool_id.to_string(),
+        name: name.to_string(),
+        input_json: r#"{"path":"/tmp/x","api_key":"sk-123"}"#.to_string(),
+        confirm_message: None,
+    })
+}
+
+async fn pending(client: &mut ClientServiceClient<Channel>) -> Vec<ToolApprovalRecord> {
+    client
+        .list_pending_approvals(ListPendingApprovalsRequest {
+            agent_id: AGENT_ID.to_string(),
+        })
+        .await
+        .unwrap()
+        .into_inner()
+        .approvals
+}
+
+async fn history(client: &mut ClientServiceClient<Channel>) -> Vec<ToolApprovalRecord> {
+    client
+        .get_approval_history(GetApprovalHistoryRequest {
+            agent_id: AGENT_ID.to_string(),
+            limit: 0,
+        })
+        .await
+        .unwrap()
+        .into_inner()
+        .approvals
+}
+
+/// Poll the pending list until it has `count` entries; the gateway records
+/// agent messages in the background
+async fn wait_for_pending(
+    client: &mut ClientServiceClient<Channel>,
+    count: usize,
+) -> Vec<ToolApprovalRecord> {
+    for _ in 0..100 {
+        let approvals = pending(client).await;
+        if approvals.len() == count {
+            return approvals;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    panic!("expected {} pending approvals", count);
+}
+
+#[tokio::test]
+async fn test_approvals_are_listed_until_resolved() {
+    let dir = tempfile::tempdir().unwrap();
+    let server = Server::start(ServeConfig {
+        grpc_addr: "127.0.0.1:0".to_string(),
+        db_path: dir.path().join("gateway.db"),
+        ..Default::default()
+    })
+    .await
+    .unwrap();
+    let url = server.url();
+
+    let (agent_tx, mut inbound) = connect_agent(&url).await;
+    respond(&agent_tx, approval_request("tool-1", "write_file")).await;
+    respond(&agent_tx, approval_request("tool-2", "bash")).await;
+
+    // A client that starts listening later still sees both, oldest first
+    let mut client = ClientServiceClient::connect(url.clone()).await.unwrap();
+    let approvals = wait_for_pending(&mut client, 2).await;
+    assert_eq!(approvals[0].tool_id, "tool-1");
+    assert_eq!(approvals[0].tool_name, "write_file");
+    assert_eq!(approvals[0].request_id, "req-1");
+    // Stored with credentials redacted
+    assert_eq!(
+        approvals[0].input_json,
+        r#"{"api_key":"***","path":"/tmp/x"}"#
+    );
+    assert!(approvals[0].resolved_at.is_none());
+    assert_eq!(approvals[1].tool_id, "tool-2");
+
+    // The client denies one; the agent gets the answer
+    let answer = client
+        .approve_tool(ApproveToolRequest {
+            agent_id: AGENT_ID.to_string(),
+            tool_id: "tool-1".to_string(),
+            approved: false,
+            approve_all: false,
+        })
+        .await
+        .unwrap()
+        .into_inner();
+    assert!(answer.success);
+    let forwarded = tokio::time::timeout(Duration::from_secs(5), inbound.message())
+        .await
+        .unwrap()
+        .unwrap()
+        .unwrap();
+    assert!(matches!(
+        forwarded.payload,
+        Some(server_message::Payload::ToolApproval(ref a)) if a.id == "tool-1" && !a.approved
+    ));
+    // The agent's own report of the outcome doesn't overwrite the decision
+    respond(
+        &agent_tx,
+        message_response::Event::ToolState(ToolStateUpdate {
+            id: "tool-1".to_string(),
+            state: ToolState::Denied.into(),
+            detail: None,
+        }),
+    )
+    .await;
+
+    // The other times out on the agent's side
+    respond(
+        &agent_tx,
+        message_response::Event::ToolState(ToolStateUpdate {
+            id: "tool-2".to_string(),
+            state: ToolState::Timeout.into(),
+            detail: None,
+        }),
+    )
+    .await;
+    wait_for_pending(&mut client, 0).await;
+
+    let resolved = history(&mut client).await;
+    let decisions: Vec<(&str, Option<&str>, Option<&str>)> = resolved
+        .iter()
+        .map(|a| {
+            (
+                a.tool_id.as_str(),
+                a.decision.as_deref(),
+                a.resolved_by.as_deref(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        decisions,
+        [
+            ("tool-2", Some("timeout"), None),
+            ("tool-1", Some("denied"), Some("local-user")),
+        ]
+    );
+    assert!(resolved.iter().all(|a| a.resolved_at.is_some()));
+
+    // Whatever is pending when the agent disconnects expires
+    respond(&agent_tx, approval_request("tool-3", "bash")).await;
+    wait_for_pending(&mut client, 1).await;
+    drop(agent_tx);
+    drop(inbound);
+    wait_for_pending(&mut client, 0).await;
+    let resolved = history(&mut client).await;
+    assert_eq!(resolved[0].tool_id, "tool-3");
+    assert_eq!(resolved[0].decision.as_deref(), Some("expired"));
+
+    server.shutdown().await.unwrap();
+}
@@ -0,0 +1,186 @@
+// ABOUTME: End-to-end test of the admin TailTraffic RPC against the local gateway.
+// ABOUTME: A fake agent answers a client message while full and redacted tails watch, and tailing is off by default.
+
+use coven_proto::client::{AdminServiceClient, ClientServiceClient, CovenControlClient};
+use coven_proto::server::{AdminServiceServer, ClientServiceServer, CovenControlServer};
+use coven_proto::{
+    agent_message, message_response, server_message, AgentMessage, ClientSendMessageRequest, Done,
+    MessageResponse, RegisterAgent, TailTrafficRequest, TrafficEvent,
+};
+use coven_serve::services::admin::AdminServiceImpl;
+use coven_serve::services::client::ClientServiceImpl;
+use coven_serve::services::control::{ControlState, CovenControlService};
+use coven_serve::store::Store;
+use std::time::Duration;
+use tokio::net::TcpListener;
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::{ReceiverStream, TcpListenerStream};
+use tonic::Streaming;
+
+/// Start a local gateway with the control, client and admin services,
+/// with tailing enabled or not, returning its URL.
+async fn start_gateway(dir: &std::path::Path, tail: bool) -> String {
+    let store = Store::open(&dir.join("gateway.db")).await.unwrap();
+    let control_state = ControlState::new(store.clone(), None);
+    let mut admin = AdminServiceImpl::new(store.clone(), control_state.clone());
+    if tail {
+        admin = admin.with_tail();
+    }
+
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    tokio::spawn(async move {
+        tonic::transport::Server::builder()
+            .add_service(CovenControlServer::new(CovenControlService::new(
+                control_state.clone(),
+            )))
+            .add_service(ClientServiceServer::new(ClientServiceImpl::new(
+                store,
+                control_state,
+            )))
+            .add_service(AdminServiceServer::new(admin))
+            .serve_with_incoming(TcpListenerStream::new(listener))
+            .await
+            .unwrap();
+    });
+    url
+}
+
+async fn tail(url: &str, agent_id: Option<&str>, redact: bool) -> Streaming<TrafficEvent> {
+    let mut admin = AdminServiceClient::connect(url.to_string()).await.unwrap();
+    admin
+        .tail_traffic(TailTrafficRequest {
+            agent_id: agent_id.map(String::from),
+            redact,
+        })
+        .await
+        .unwrap()
+        .into_inner()
+}
+
+async fn next_event(stream: &mut Streaming<TrafficEvent>) -> TrafficEvent {
+    tokio::time::timeout(Duration::from_secs(5), stream.message())
+        .await
+        .expect("traffic event in time")
+        .unwrap()
+        .expect("tail still open")
+}
+
+fn send(agent_id: &str, content: &str) -> ClientSendMessageRequest {
+    ClientSendMessageRequest {
+        conversation_key: agent_id.to_string(),
+        content: content.to_string(),
+        sender_platform_id: Some("U123".to_string()),
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn test_tail_follows_a_message_through_the_gateway() {
+    let dir = tempfile::tempdir().unwrap();
+    let url = start_gateway(dir.path(), true).await;
+
+    let (agent_tx, agent_rx) = mpsc::channel(8);
+    agent_tx
+        .send(AgentMessage {
+            payload: Some(agent_message::Payload::Register(RegisterAgent {
+                agent_id: "agent-1".to_string(),
+                name: "Agent One".to_string(),
+                ..Default::default()
+            })),
+        })
+        .await
+        .unwrap();
+    let mut control = CovenControlClient::connect(url.clone()).await.unwrap();
+    let mut inbound = control
+        .agent_stream(ReceiverStream::new(agent_rx))
+        .await
+        .unwrap()
+        .into_inner();
+    let welcome = inbound.message().await.unwrap().unwrap();
+    assert!(matches!(
+        welcome.payload,
+        Some(server_message::Payload::Welcome(_))
+    ));
+
+    let mut full = tail(&url, None, false).await;
+    let mut redacted = tail(&url, Some("agent-1"), true).await;
+    let mut other_agent = tail(&url, Some("agent-2"), false).await;
+
+    // A message for an unknown agent is refused before routing
+    let mut client = ClientServiceClient::connect(url.clone()).await.unwrap();
+    client
+        .send_message(send("missing", "hello?"))
+        .await
+        .unwrap_err();
+    let rejected = next_event(&mut full).await;
+    assert_eq!(rejected.kind, "rejected");
+    assert_eq!(rejected.agent_id, "missing");
+    assert!(rejected.detail.unwrap().contains("not connected"));
+
+    let accepted = client
+        .send_message(send("agent-1", "deploy staging"))
+        .await
+        .unwrap()
+        .into_inner();
+    let routed = inbound.message().await.unwrap().unwrap();
+    let Some(server_message::Payload::SendMessage(routed)) = routed.payload else {
+        panic!("expected a message for the agent");
+    };
+    agent_tx
+        .send(AgentMessage {
+            payload: Some(agent_message::Payload::Response(MessageResponse {
+                request_id: routed.request_id,
+                event: Some(message_response::Event::Done(Done {
+                    full_response: "deployed".to_string(),
+                })),
+            })),
+        })
+        .await
+        .unwrap();
+
+    let event = next_event(&mut full).await;
+    assert_eq!(event.kind, "inbound");
+    assert_eq!(event.agent_id, "agent-1");
+    assert_eq!(event.request_id, accepted.message_id);
+    assert_eq!(event.sender, "U123");
+    assert_eq!(event.content.as_deref(), Some("deploy staging"));
+    assert_eq!(event.content_bytes, 14);
+    let event = next_event(&mut full).await;
+    assert_eq!(event.kind, "response");
+    assert_eq!(event.request_id, accepted.message_id);
+    assert_eq!(event.content.as_deref(), Some("deployed"));
+    assert!(event.latency_ms.is_some());
+
+    // The redacted tail skips the other agent and sees no content
+    let events = [
+        next_event(&mut redacted).await,
+        next_event(&mut redacted).await,
+    ];
+    assert_eq!(events[0].kind, "inbound");
+    assert_eq!(events[1].kind, "response");
+    for event in &events {
+        assert_eq!(event.content, None);
+        assert!(event.content_bytes > 0);
+    }
+
+    assert!(
+        tokio::time::timeout(Duration::from_millis(100), other_agent.message())
+            .await
+            .is_err()
+    );
+}
+
+#[tokio::test]
+async fn test_tail_is_off_by_default() {
+    let dir = tempfile::tempdir().unwrap();
+    let url = start_gateway(dir.path(), false).await;
+
+    let mut admin = AdminServiceClient::connect(url).await.unwrap();
+    let status = admin
+        .tail_traffic(TailTrafficRequest::default())
+        .await
+        .unwrap_err();
+    assert_eq!(status.code(), tonic::Code::FailedPrecondition);
+    assert!(status.message().contains("--enable-tail"));
+}
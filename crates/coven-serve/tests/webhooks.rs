@@ -0,0 +1,313 @@
+// ABOUTME: Tests webhook delivery of gateway events from the local gateway.
+// ABOUTME: A tiny HTTP server answers with scripted statuses to check retries, signatures and dead letters.
+
+use coven_proto::client::CovenControlClient;
+use coven_proto::{
+    agent_message, message_response, server_message, AgentMessage, MessageResponse, RegisterAgent,
+    ServerMessage, ToolApprovalRequest,
+};
+use coven_serve::webhook::{
+    self, EventDetails, EventKind, RetryPolicy, WebhookPayload, DELIVERY_HEADER, EVENT_HEADER,
+    SIGNATURE_HEADER, TIMESTAMP_HEADER,
+};
+use coven_serve::{RunningServer, ServeConfig, Server, WebhookConfig};
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
+use tonic::Streaming;
+
+const SECRET: &str = "webhook-secret";
+
+/// One POST the webhook received
+struct Received {
+    headers: HashMap<String, String>,
+    body: Vec<u8>,
+}
+
+impl Received {
+    fn payload(&self) -> WebhookPayload {
+        serde_json::from_slice(&self.body).unwrap()
+    }
+}
+
+/// Accept webhook POSTs, answering each with the next scripted status (200
+/// once the script runs out), and pass each request on
+async fn start_webhook(statuses: &[u16]) -> (String, mpsc::Receiver<Received>) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}/hook", listener.local_addr().unwrap());
+    let statuses = Arc::new(Mutex::new(
+        statuses.iter().copied().collect::<VecDeque<_>>(),
+    ));
+    let (tx, rx) = mpsc::channel(32);
+    tokio::spawn(async move {
+        loop {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut request = Vec::new();
+            let mut buf = [0u8; 4096];
+            let (head, body) = loop {
+                let n = socket.read(&mut buf).await.unwrap();
+                assert!(n > 0, "webhook request ended early");
+                request.extend_from_slice(&buf[..n]);
+                let Some(end) = request.windows(4).position(|w| w == b"\r\n\r\n") else {
+                    continue;
+                };
+                let head = String::from_utf8_lossy(&request[..end]).to_string();
+                let length: usize = head
+                    .to_lowercase()
+                    .lines()
+                    .find_map(|line| line.strip_prefix("content-length:"))
+                    .map(|v| v.trim().parse().unwrap())
+                    .unwrap_or(0);
+                if request.len() >= end + 4 + length {
+                    break (head, request[end + 4..end + 4 + length].to_vec());
+                }
+            };
+            let headers = head
+                .lines()
+                .skip(1)
+                .filter_map(|line| line.split_once(':'))
+                .map(|(name, value)| (name.trim().to_lowercase(), value.trim().to_string()))
+                .collect();
+            let status = statuses.lock().unwrap().pop_front().unwrap_or(200);
+            let response = format!(
+                "HTTP/1.1 {} Status\r\ncontent-length: 0\r\nconnection: close\r\n\r\n",
+                status
+            );
+            socket.write_all(response.as_bytes()).await.unwrap();
+            tx.send(Received { headers, body }).await.unwrap();
+        }
+    });
+    (url, rx)
+}
+
+async fn next_request(rx: &mut mpsc::Receiver<Received>) -> Received {
+    tokio::time::timeout(Duration::from_secs(10), rx.recv())
+        .await
+        .expect("timed out waiting for a webhook request")
+        .unwrap()
+}
+
+fn fast_retries(max_attempts: u32) -> RetryPolicy {
+    RetryPolicy {
+        max_attempts,
+        initial_backoff_ms: 10,
+        max_backoff_ms: 50,
+    }
+}
+
+async fn start_gateway(dir: &tempfile::TempDir, webhooks: Vec<WebhookConfig>) -> RunningServer {
+    Server::start(ServeConfig {
+        grpc_addr: "127.0.0.1:0".to_string(),
+        db_path: dir.path().join("gateway.db"),
+        webhooks,
+        ..Default::default()
+    })
+    .await
+    .unwrap()
+}
+
+/// Connect a fake agent, returning its outbound sender and inbound stream
+async fn connect_agent(url: &str) -> (mpsc::Sender<AgentMessage>, Streaming<ServerMessage>) {
+    let (agent_tx, agent_rx) = mpsc::channel(8);
+    agent_tx
+        .send(AgentMessage {
+            payload: Some(agent_message::Payload::Register(RegisterAgent {
+                agent_id: "agent-1".to_string(),
+                name: "Agent One".to_string(),
+                ..Default::default()
+            })),
+        })
+        .await
+        .unwrap();
+    let mut control = CovenControlClient::connect(url.to_string()).await.unwrap();
+    let mut inbound = control
+        .agent_stream(ReceiverStream::new(agent_rx))
+        .await
+        .unwrap()
+        .into_inner();
+    let welcome = inbound.message().await.unwrap().unwrap();
+    assert!(matches!(
+        welcome.payload,
+        Some(server_message::Payload::Welcome(_))
+    ));
+    (agent_tx, inbound)
+}
+
+async fn respond(agent_tx: &mpsc::Sender<AgentMessage>, event: message_response::Event) {
+    agent_tx
+        .send(AgentMessage {
+            payload: Some(agent_message::Payload::Response(MessageResponse {
+                request_id: "req-1".to_string(),
+                event: Some(event),
+            })),
+        })
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn test_events_are_signed_and_retried() {
+    // The first delivery fails once before going through
+    let (hook_url, mut requests) = start_webhook(&[503]).await;
+    let dir = tempfile::tempdir().unwrap();
+    let server = start_gateway(
+        &dir,
+        vec![WebhookConfig {
+            secret: Some(SECRET.to_string()),
+            retry: fast_retries(3),
+            ..WebhookConfig::new(hook_url)
+        }],
+    )
+    .await;
+
+    let (agent_tx, _inbound) = connect_agent(&server.url()).await;
+    let first = next_request(&mut requests).await;
+    let retried = next_request(&mut requests).await;
+    assert_eq!(first.body, retried.body);
+    assert_eq!(
+        first.headers[DELIVERY_HEADER],
+        retried.headers[DELIVERY_HEADER]
+    );
+
+    let payload = retried.payload();
+    assert_eq!(retried.headers[EVENT_HEADER], "agent_connected");
+    assert_eq!(payload.id, retried.headers[DELIVERY_HEADER]);
+    assert_eq!(payload.agent_id, "agent-1");
+    assert_eq!(
+        payload.details,
+        EventDetails::AgentConnected {
+            agent_name: "Agent One".to_string()
+        }
+    );
+    let timestamp: i64 = retried.headers[TIMESTAMP_HEADER].parse().unwrap();
+    assert!(webhook::verify(
+        SECRET,
+        timestamp,
+        &retried.body,
+        &retried.headers[SIGNATURE_HEADER]
+    ));
+    assert!(!webhook::verify(
+        "wrong-secret",
+        timestamp,
+        &retried.body,
+        &retried.headers[SIGNATURE_HEADER]
+    ));
+
+    respond(
+        &agent_tx,
+        message_response::Event::Error("backend crashed".to_string()),
+    )
+    .await;
+    let error = next_request(&mut requests).await;
+    assert_eq!(error.headers[EVENT_HEADER], "error_event");
+    assert_eq!(
+        error.payload().details,
+        EventDetails::ErrorEvent {
+            request_id: "req-1".to_string(),
+            message: "backend crashed".to_string(),
+        }
+    );
+
+    // Closing the stream is a clean disconnect
+    drop(agent_tx);
+    let disconnected = next_request(&mut requests).await;
+    assert_eq!(
+        disconnected.payload().details,
+        EventDetails::AgentDisconnected {
+            agent_name: "Agent One".to_string(),
+            unexpected: false,
+            reason: None,
+        }
+    );
+}
+
+#[tokio::test]
+async fn test_unanswered_approval_is_reported() {
+    let (hook_url, mut requests) = start_webhook(&[]).await;
+    let dir = tempfile::tempdir().unwrap();
+    let server = start_gateway(
+        &dir,
+        vec![WebhookConfig {
+            events: vec![EventKind::ApprovalPendingTimeout],
+            approval_timeout_secs: 1,
+            ..WebhookConfig::new(hook_url)
+        }],
+    )
+    .await;
+
+    let (agent_tx, _inbound) = connect_agent(&server.url()).await;
+    respond(
+        &agent_tx,
+        message_response::Event::ToolApprovalRequest(ToolApprovalRequest {
+            id: "tool-1".to_string(),
+            name: "bash".to_string(),
+            input_json: "{}".to_string(),
+            confirm_message: None,
+        }),
+    )
+    .await;
+
+    // Nothing else is subscribed, so the timeout is the first request
+    let request = next_request(&mut requests).await;
+    let payload = request.payload();
+    assert!(!request.headers.contains_key(SIGNATURE_HEADER));
+    assert_eq!(
+        payload.details,
+        EventDetails::ApprovalPendingTimeout {
+            tool_id: "tool-1".to_string(),
+            request_id: "req-1".to_string(),
+            tool_name: "bash".to_string(),
+            pending_secs: 1,
+        }
+    );
+    assert!(payload.text.contains("bash"), "{}", payload.text);
+    drop(agent_tx);
+}
+
+#[tokio::test]
+async fn test_failed_deliveries_become_dead_letters() {
+    // Retries run out on the first event; the second is refused outright
+    let (hook_url, mut requests) = start_webhook(&[500, 500, 400]).await;
+    let dir = tempfile::tempdir().unwrap();
+    let server = start_gateway(
+        &dir,
+        vec![WebhookConfig {
+            events: vec![EventKind::AgentConnected, EventKind::AgentDisconnected],
+            retry: fast_retries(2),
+            ..WebhookConfig::new(hook_url)
+        }],
+    )
+    .await;
+
+    let (agent_tx, _inbound) = connect_agent(&server.url()).await;
+    drop(agent_tx);
+    for _ in 0..3 {
+        next_request(&mut requests).await;
+    }
+
+    let path = dir.path().join("webhook-dead-letters.jsonl");
+    let lines = tokio::time::timeout(Duration::from_secs(10), async {
+        loop {
+            let text = tokio::fs::read_to_string(&path).await.unwrap_or_default();
+            if text.lines().count() >= 2 {
+                break text;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+    })
+    .await
+    .expect("timed out waiting for dead letters");
+    let letters: Vec<serde_json::Value> = lines
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    assert_eq!(letters[0]["attempts"], 2);
+    assert_eq!(letters[0]["payload"]["event"], "agent_connected");
+    assert_eq!(letters[1]["attempts"], 1);
+    assert_eq!(letters[1]["payload"]["event"], "agent_disconnected");
+    assert!(letters[1]["error"].as_str().unwrap().contains("400"));
+}
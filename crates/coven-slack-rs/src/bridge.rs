@@ -1,18 +1,27 @@
 // ABOUTME: Core bridge logic connecting Slack events to coven-gateway.
-// ABOUTME: Handles message routing, bindings, command processing, and response streaming.
+// ABOUTME: Handles message routing, bindings, command processing, response streaming, and agent-initiated messages.
 
 use crate::commands::{execute_command, Command, CommandContext};
 use crate::config::Config;
 use crate::error::Result;
 use crate::gateway::GatewayClient;
-use crate::slack::{CovenSlackClient, SlackMessageInfo};
-
+use crate::slack::{CovenSlackClient, SlackMessageInfo, MAX_MESSAGE_LEN};
+
+use coven_bridge_core::{
+    format_initiated, open_binding_store, route_initiated, send_notice, split_message,
+    BindingStore, ChannelIdentity, IdentityCache, MessageDeduplicator, OrderedDispatcher,
+    ReplyIndex, RequestOverrides, ResponseAccumulator, SenderIdentity, StoredBinding,
+    DEFAULT_MAX_CONCURRENT, IDENTITY_CACHE_TTL, INITIATED_RESUBSCRIBE_DELAY,
+};
 use coven_proto::client_stream_event::Payload;
+use coven_proto::AgentInitiatedEvent;
 use futures::StreamExt;
+use slack_morphism::prelude::SlackTs;
 use std::collections::HashMap;
 use std::sync::Arc;
+use std::time::Instant;
 use tokio::sync::RwLock;
-use tracing::{debug, error, info};
+use tracing::{debug, error, info, warn};
 use uuid::Uuid;
 
 /// Channel binding information mapping a Slack channel to a gateway conversation.
@@ -20,6 +29,29 @@ use uuid::Uuid;
 pub struct ChannelBinding {
     pub channel_id: String,
     pub conversation_key: String,
+    /// Model/max_tokens settings applied to messages from this channel
+    pub overrides: RequestOverrides,
+}
+
+impl ChannelBinding {
+    /// Convert to the platform-neutral form used by the binding store.
+    pub fn to_stored(&self) -> StoredBinding {
+        StoredBinding {
+            target: self.channel_id.clone(),
+            conversation_key: self.conversation_key.clone(),
+            owner: None,
+            overrides: self.overrides.clone(),
+        }
+    }
+
+    /// Convert from a stored binding.
+    pub fn from_stored(stored: &StoredBinding) -> Self {
+        Self {
+            channel_id: stored.target.clone(),
+            conversation_key: stored.conversation_key.clone(),
+            overrides: stored.overrides.clone(),
+        }
+    }
 }
 
 /// The Bridge ties together Slack and Gateway clients to route messages.
@@ -28,6 +60,13 @@ pub struct Bridge {
     slack: CovenSlackClient,
     gateway: Arc<RwLock<GatewayClient>>,
     bindings: Arc<RwLock<HashMap<String, ChannelBinding>>>,
+    store: Arc<dyn BindingStore>,
+    identities: IdentityCache,
+    seen_messages: MessageDeduplicator,
+    /// Coven ids of forwarded messages, for tagging thread replies
+    replies: ReplyIndex,
+    /// Keeps messages in one thread in arrival order
+    dispatcher: OrderedDispatcher,
 }
 
 impl Bridge {
@@ -40,14 +79,40 @@ impl Bridge {
         let slack = CovenSlackClient::new(&config.slack).await?;
 
         // Connect to Gateway
-        let gateway =
-            GatewayClient::connect(&config.gateway.url, config.gateway.token.clone()).await?;
+        let gateway = GatewayClient::connect(
+            &config.gateway.url,
+            config.gateway.token.clone(),
+            config.gateway.keep_alive()?,
+        )
+        .await?;
+
+        // Restore bindings from the previous run
+        let store = open_binding_store(config.bindings_path().as_deref()).await?;
+        let bindings: HashMap<String, ChannelBinding> = store
+            .load()
+            .await?
+            .iter()
+            .map(|stored| (stored.target.clone(), ChannelBinding::from_stored(stored)))
+            .collect();
+        info!(count = bindings.len(), "Loaded channel bindings");
+
+        let dispatcher = OrderedDispatcher::new(
+            config
+                .bridge
+                .max_concurrent_messages
+                .unwrap_or(DEFAULT_MAX_CONCURRENT),
+        );
 
         Ok(Self {
             config,
             slack,
             gateway: Arc::new(RwLock::new(gateway)),
-            bindings: Arc::new(RwLock::new(HashMap::new())),
+            bindings: Arc::new(RwLock::new(bindings)),
+            store,
+            identities: IdentityCache::new("slack", IDENTITY_CACHE_TTL),
+            seen_messages: MessageDeduplicator::default(),
+            replies: ReplyIndex::default(),
+            dispatcher,
         })
     }
 
@@ -56,6 +121,7 @@ impl Bridge {
         let binding = ChannelBinding {
             channel_id: channel_id.clone(),
             conversation_key,
+            overrides: RequestOverrides::default(),
         };
 
         info!(
@@ -64,12 +130,18 @@ impl Bridge {
             "Binding channel to conversation"
         );
 
+        if let Err(e) = self.store.save(&binding.to_stored()).await {
+            warn!(error = %e, channel_id = %channel_id, "Failed to persist binding");
+        }
         self.bindings.write().await.insert(channel_id, binding);
     }
 
     /// Unbind a Slack channel from any gateway conversation.
     pub async fn unbind_channel(&self, channel_id: &str) -> Option<ChannelBinding> {
         let binding = self.bindings.write().await.remove(channel_id);
+        if let Err(e) = self.store.remove(channel_id).await {
+            warn!(error = %e, channel_id = %channel_id, "Failed to remove persisted binding");
+        }
         if let Some(ref b) = binding {
             info!(
                 channel_id = %channel_id,
@@ -100,66 +172,78 @@ impl Bridge {
         &self.bindings
     }
 
+    /// Get a reference to the binding store.
+    pub fn binding_store(&self) -> &Arc<dyn BindingStore> {
+        &self.store
+    }
+
     /// Get a reference to the config.
     pub fn config(&self) -> &Config {
         &self.config
     }
 
-    /// Handle an incoming Slack message event.
-    pub async fn handle_message(&self, msg_info: SlackMessageInfo) -> Result<()> {
-        let channel_id = &msg_info.channel_id;
-
-        // Check if channel is allowed
-        if !self.config.is_channel_allowed(channel_id) {
-            debug!(channel_id = %channel_id, "Message from non-allowed channel, ignoring");
-            return Ok(());
-        }
-
-        // Ignore messages from the bot itself
-        if msg_info.user_id == self.slack.bot_user_id().to_string() {
-            return Ok(());
-        }
-
-        // Check for /coven commands first (commands work regardless of binding)
-        if let Some(command) = Command::from_message(&msg_info.text) {
-            info!(
-                channel_id = %channel_id,
-                user_id = %msg_info.user_id,
-                "Processing /coven command"
+    /// Record a received message and report whether it was already handled.
+    /// Slack redelivers Socket Mode events it thinks went unacknowledged.
+    pub fn is_redelivery(&self, msg_info: &SlackMessageInfo) -> bool {
+        let duplicate = self.seen_messages.is_duplicate(&msg_info.dedup_key());
+        if duplicate {
+            debug!(
+                channel_id = %msg_info.channel_id,
+                message_ts = %msg_info.message_ts,
+                "Dropping duplicate message event"
             );
+        }
+        duplicate
+    }
 
-            let ctx = CommandContext {
-                gateway: &self.gateway,
-                bindings: &self.bindings,
-                channel_id,
-            };
-
-            let response = match execute_command(command, ctx).await {
-                Ok(resp) => resp,
-                Err(e) => format!(":x: Command error: {}", e),
-            };
-
-            // Reply in thread if original was in thread, otherwise start new thread
-            let thread_ts = msg_info.reply_thread_ts(self.config.bridge.thread_replies);
-            self.slack
-                .post_message(channel_id, &response, thread_ts.as_deref())
-                .await?;
+    /// Handle a message in the background, after any earlier messages in
+    /// the same thread (or channel, for top-level messages) have been handled.
+    pub fn dispatch(self: &Arc<Self>, msg_info: SlackMessageInfo) {
+        let bridge = Arc::clone(self);
+        self.dispatcher.submit(msg_info.ordering_key(), async move {
+            if let Err(e) = bridge.handle_message(msg_info).await {
+                error!(error = %e, "Failed to handle message");
+            }
+        });
+    }
 
-            return Ok(());
-        }
+    /// Handle an incoming Slack message event.
+    pub async fn handle_message(&self, msg_info: SlackMessageInfo) -> Result<()> {
+        let channel_id = &msg_info.channel_id;
+        let bot_user_id = self.slack.bot_user_id().to_string();
+
+        match disposition(&self.config, &bot_user_id, &msg_info) {
+            Disposition::Drop => return Ok(()),
+            Disposition::Command(command) => {
+                info!(
+                    channel_id = %channel_id,
+                    user_id = %msg_info.user_id,
+                    "Processing /coven command"
+                );
+
+                let ctx = CommandContext {
+                    gateway: &self.gateway,
+                    bindings: &self.bindings,
+                    store: self.store.as_ref(),
+                    channel_id,
+                    config: &self.config.bridge,
+                    user_id: &msg_info.user_id,
+                };
+
+                let response = match execute_command(command, ctx).await {
+                    Ok(resp) => resp,
+                    Err(e) => format!(":x: Command error: {}", e),
+                };
+
+                // Reply in thread if original was in thread, otherwise start new thread
+                let thread_ts = msg_info.reply_thread_ts(self.config.bridge.thread_replies);
+                self.slack
+                    .post_message(channel_id, &response, thread_ts.as_deref())
+                    .await?;
 
-        // Check response conditions
-        if !msg_info
-            .context
-            .should_respond(self.config.bridge.response_mode, msg_info.is_mention)
-        {
-            debug!(
-                channel_id = %channel_id,
-                "Message doesn't require response (response_mode={:?}, is_mention={})",
-                self.config.bridge.response_mode,
-                msg_info.is_mention
-            );
-            return Ok(());
+                return Ok(());
+            }
+            Disposition::Forward => {}
         }
 
         // Check for binding (required for forwarding to agent)
@@ -184,10 +268,18 @@ impl Bridge {
             "Processing message"
         );
 
+        // Tell the agent who in the channel is asking
+        let sender = self
+            .identities
+            .resolve(&msg_info.user_id, || {
+                self.slack.user_display_name(&msg_info.user_id)
+            })
+            .await;
+
         // Process the message
         let thread_ts = msg_info.reply_thread_ts(self.config.bridge.thread_replies);
         if let Err(e) = self
-            .process_message(channel_id, thread_ts.as_deref(), &binding, &text)
+            .process_message(&msg_info, thread_ts.as_deref(), &binding, &text, &sender)
             .await
         {
             error!(error = %e, channel_id = %channel_id, "Failed to process message");
@@ -203,14 +295,21 @@ impl Bridge {
     }
 
     /// Process a message by sending to gateway and streaming response back.
+    /// A reply in a thread is tagged with the coven id of the thread's root
+    /// message, when that message was forwarded too.
     async fn process_message(
         &self,
-        channel_id: &str,
+        msg_info: &SlackMessageInfo,
         thread_ts: Option<&str>,
         binding: &ChannelBinding,
         text: &str,
+        sender: &SenderIdentity,
     ) -> Result<()> {
+        let channel_id = msg_info.channel_id.as_str();
         let idempotency_key = Uuid::new_v4().to_string();
+        let reply_to = msg_info
+            .parent_key()
+            .and_then(|key| self.replies.lookup(&key));
 
         // Send message to gateway
         let send_result = {
@@ -220,6 +319,10 @@ impl Bridge {
                     binding.conversation_key.clone(),
                     text.to_string(),
                     idempotency_key,
+                    Some(sender),
+                    Some(&ChannelIdentity::new("slack", channel_id)),
+                    reply_to.as_deref(),
+                    &binding.overrides,
                 )
                 .await
         };
@@ -236,6 +339,16 @@ impl Bridge {
             message_id = %response.message_id,
             "Message sent to gateway"
         );
+        self.replies
+            .record(&msg_info.dedup_key(), &response.message_id);
+
+        // The agent is offline and the gateway will deliver the message
+        // later, or the gateway's content policy blocked it
+        if let Some(notice) = send_notice(&response) {
+            self.send_response(channel_id, thread_ts, None, notice)
+                .await?;
+            return Ok(());
+        }
 
         // Stream events from gateway
         let stream_result = {
@@ -252,8 +365,11 @@ impl Bridge {
             }
         };
 
-        // Accumulate text chunks for final message
-        let mut accumulated_text = String::new();
+        // Accumulate text chunks; with a stream interval configured, the
+        // partial response is posted once and then edited as it grows
+        let mut response =
+            ResponseAccumulator::from_interval_ms(self.config.bridge.stream_interval_ms);
+        let mut live_ts: Option<SlackTs> = None;
         let mut has_sent_message = false;
 
         while let Some(event_result) = stream.next().await {
@@ -268,12 +384,15 @@ impl Bridge {
             // Process the event payload
             match event.payload {
                 Some(Payload::Text(chunk)) => {
-                    accumulated_text.push_str(&chunk.content);
                     debug!(
                         chunk_len = chunk.content.len(),
-                        total_len = accumulated_text.len(),
+                        total_len = response.text().len() + chunk.content.len(),
                         "Received text chunk"
                     );
+                    if let Some(partial) = response.push(&chunk.content, Instant::now()) {
+                        self.show_partial(channel_id, thread_ts, &mut live_ts, partial)
+                            .await;
+                    }
                 }
                 Some(Payload::Thinking(chunk)) => {
                     debug!(
@@ -304,13 +423,10 @@ impl Bridge {
                 Some(Payload::Done(done)) => {
                     info!("Stream completed");
                     // Use full response if available, otherwise accumulated text
-                    let final_text = done
-                        .full_response
-                        .filter(|s| !s.is_empty())
-                        .unwrap_or_else(|| accumulated_text.clone());
+                    let final_text = std::mem::take(&mut response).finish(done.full_response);
 
                     if !final_text.is_empty() && !has_sent_message {
-                        self.send_response(channel_id, thread_ts, &final_text)
+                        self.send_response(channel_id, thread_ts, live_ts.take(), &final_text)
                             .await?;
                         has_sent_message = true;
                     }
@@ -320,7 +436,7 @@ impl Bridge {
                     error!(message = %error.message, "Stream error event");
                     if !has_sent_message {
                         let error_msg = format!(":x: Error: {}", error.message);
-                        self.send_response(channel_id, thread_ts, &error_msg)
+                        self.send_response(channel_id, thread_ts, None, &error_msg)
                             .await?;
                         has_sent_message = true;
                     }
@@ -329,6 +445,11 @@ impl Bridge {
                 Some(Payload::Event(_)) => {
                     debug!("Received full event (history replay)");
                 }
+                Some(Payload::Queued(queued)) => {
+                    // A message of its own, apart from the answer to come
+                    self.send_response(channel_id, thread_ts, None, &queued.notice)
+                        .await?;
+                }
                 Some(Payload::ToolApproval(approval)) => {
                     // Tool approval requests not supported in Slack bridge - auto-deny
                     debug!(tool_name = %approval.tool_name, "Tool approval request (auto-denied in Slack)");
@@ -347,22 +468,131 @@ impl Bridge {
         }
 
         // If we accumulated text but didn't send yet, send now
-        if !accumulated_text.is_empty() && !has_sent_message {
-            self.send_response(channel_id, thread_ts, &accumulated_text)
+        if !response.is_empty() && !has_sent_message {
+            self.send_response(channel_id, thread_ts, live_ts, response.text())
                 .await?;
         }
 
         Ok(())
     }
 
-    /// Send a response to Slack.
+    /// Deliver messages agents send on their own initiative for as long as
+    /// the bridge runs, resubscribing whenever the gateway stream ends.
+    pub async fn run_initiated_listener(self: Arc<Self>) {
+        loop {
+            let stream_result = {
+                let mut gateway = self.gateway.write().await;
+                gateway.stream_agent_initiated().await
+            };
+
+            match stream_result {
+                Ok(mut stream) => {
+                    info!("Listening for agent-initiated messages");
+                    while let Some(event_result) = stream.next().await {
+                        match event_result {
+                            Ok(event) => self.deliver_initiated(event).await,
+                            Err(status) => {
+                                warn!(error = %status, "Agent-initiated stream error");
+                                break;
+                            }
+                        }
+                    }
+                }
+                Err(e) => {
+                    warn!(error = %e, "Failed to subscribe to agent-initiated messages");
+                }
+            }
+
+            tokio::time::sleep(INITIATED_RESUBSCRIBE_DELAY).await;
+        }
+    }
+
+    /// Post an agent-initiated message to every channel bound to the agent,
+    /// or to the notifications channel when none is.
+    async fn deliver_initiated(&self, event: AgentInitiatedEvent) {
+        let deliveries = {
+            let bindings = self.bindings.read().await;
+            route_initiated(
+                &event,
+                bindings
+                    .values()
+                    .map(|b| (b.channel_id.as_str(), b.conversation_key.as_str())),
+                self.config.bridge.notifications_channel.as_deref(),
+            )
+        };
+
+        if deliveries.is_empty() {
+            info!(
+                agent_id = %event.agent_id,
+                message_id = %event.message_id,
+                "Dropping agent-initiated message: agent has no bound channel and no notifications channel is configured"
+            );
+            return;
+        }
+
+        let text = format_initiated(&event);
+        for delivery in deliveries {
+            if let Err(e) = self
+                .send_response(&delivery.target, delivery.thread_id.as_deref(), None, &text)
+                .await
+            {
+                warn!(
+                    error = %e,
+                    channel_id = %delivery.target,
+                    "Failed to deliver agent-initiated message"
+                );
+            }
+        }
+    }
+
+    /// Show a partial response while it streams: post it the first time,
+    /// then edit that message. Failures are logged; the final response is
+    /// still delivered by `send_response`.
+    async fn show_partial(
+        &self,
+        channel_id: &str,
+        thread_ts: Option<&str>,
+        live_ts: &mut Option<SlackTs>,
+        text: &str,
+    ) {
+        // Only the first message-sized piece is shown until the response ends
+        let Some(preview) = split_message(text, MAX_MESSAGE_LEN).into_iter().next() else {
+            return;
+        };
+        let result = match live_ts {
+            Some(ts) => self.slack.update_message(channel_id, ts, &preview).await,
+            None => self
+                .slack
+                .post_message(channel_id, &preview, thread_ts)
+                .await
+                .map(|ts| *live_ts = Some(ts)),
+        };
+        if let Err(e) = result {
+            warn!(error = %e, channel_id = %channel_id, "Failed to show partial response");
+        }
+    }
+
+    /// Send a response to Slack, split into messages that fit the length
+    /// limit. If a partial response is showing, its message is edited to
+    /// hold the first piece.
     async fn send_response(
         &self,
         channel_id: &str,
         thread_ts: Option<&str>,
+        live_ts: Option<SlackTs>,
         text: &str,
     ) -> Result<()> {
-        self.slack.post_message(channel_id, text, thread_ts).await?;
+        let mut live_ts = live_ts;
+        for piece in split_message(text, MAX_MESSAGE_LEN) {
+            match live_ts.take() {
+                Some(ts) => self.slack.update_message(channel_id, &ts, &piece).await?,
+                None => {
+                    self.slack
+                        .post_message(channel_id, &piece, thread_ts)
+                        .await?;
+                }
+            }
+        }
         debug!(
             channel_id = %channel_id,
             thread_ts = ?thread_ts,
@@ -373,19 +603,188 @@ impl Bridge {
     }
 }
 
+/// Whether the bridge drops `msg_info` because of who sent it.
+fn is_ignored_sender(config: &Config, msg_info: &SlackMessageInfo) -> bool {
+    config.is_sender_ignored(&msg_info.user_id, msg_info.is_bot)
+}
+
+/// What the bridge does with a message before the gateway is involved.
+#[derive(Debug, PartialEq, Eq)]
+enum Disposition {
+    /// Dropped without touching the gateway
+    Drop,
+    /// A /coven command to run
+    Command(Command),
+    /// Forwarded to the channel's agent, if the channel is bound
+    Forward,
+}
+
+/// Decide what to do with `msg_info` from the config and the message alone.
+/// Everything that drops a message is decided here, so a dropped message
+/// never reaches the gateway.
+fn disposition(config: &Config, bot_user_id: &str, msg_info: &SlackMessageInfo) -> Disposition {
+    let channel_id = &msg_info.channel_id;
+
+    // Check if channel is allowed
+    if !config.is_channel_allowed(channel_id) {
+        debug!(channel_id = %channel_id, "Message from non-allowed channel, ignoring");
+        return Disposition::Drop;
+    }
+
+    // Ignore messages from the bot itself
+    if msg_info.user_id == bot_user_id {
+        return Disposition::Drop;
+    }
+
+    // Ignored senders and other bots are dropped, mentions and commands included
+    if is_ignored_sender(config, msg_info) {
+        debug!(
+            channel_id = %channel_id,
+            user_id = %msg_info.user_id,
+            is_bot = msg_info.is_bot,
+            "Message from ignored sender, dropping"
+        );
+        return Disposition::Drop;
+    }
+
+    // Commands work regardless of binding or response mode
+    if let Some(command) = Command::from_message(&msg_info.text) {
+        return Disposition::Command(command);
+    }
+
+    if !msg_info
+        .context
+        .should_respond(config.bridge.response_mode, msg_info.is_mention)
+    {
+        debug!(
+            channel_id = %channel_id,
+            "Message doesn't require response (response_mode={:?}, is_mention={})",
+            config.bridge.response_mode,
+            msg_info.is_mention
+        );
+        return Disposition::Drop;
+    }
+
+    Disposition::Forward
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::context::SlackContext;
+
+    fn config_with(bridge: &str) -> Config {
+        toml::from_str(&format!(
+            "[slack]\napp_token = \"xapp-1\"\nbot_token = \"xoxb-1\"\n\
+             [gateway]\nurl = \"http://localhost:6666\"\n[bridge]\n{}",
+            bridge
+        ))
+        .unwrap()
+    }
+
+    fn message(user_id: &str, is_mention: bool, is_bot: bool) -> SlackMessageInfo {
+        SlackMessageInfo {
+            channel_id: "C1".to_string(),
+            user_id: user_id.to_string(),
+            text: "<@UBOT> deploy".to_string(),
+            message_ts: "1700000000.000100".to_string(),
+            thread_ts: None,
+            is_mention,
+            is_bot,
+            context: SlackContext::from_event("C1".to_string(), None, false),
+        }
+    }
+
+    #[test]
+    fn test_ignored_sender_dropped_before_gateway() {
+        let config = config_with("ignore_senders = [\"U_NOISY\"]");
+        // Mentions from an ignored user are dropped too
+        assert!(is_ignored_sender(&config, &message("U_NOISY", true, false)));
+        assert!(is_ignored_sender(
+            &config,
+            &message("U_NOISY", false, false)
+        ));
+        assert!(!is_ignored_sender(
+            &config,
+            &message("U_HUMAN", true, false)
+        ));
+
+        // Bots are ignored by default, and can be let through
+        assert!(is_ignored_sender(
+            &config,
+            &message("U_OTHERBOT", true, true)
+        ));
+        let config = config_with("ignore_bots = false");
+        assert!(!is_ignored_sender(
+            &config,
+            &message("U_OTHERBOT", true, true)
+        ));
+    }
+
+    #[test]
+    fn test_bot_messages_never_reach_the_gateway() {
+        let config = config_with("");
+        let bot_mention = message("U_OTHERBOT", true, true);
+        assert_eq!(
+            disposition(&config, "UBOT", &bot_mention),
+            Disposition::Drop
+        );
+
+        let mut bot_command = message("U_OTHERBOT", false, true);
+        bot_command.text = "/coven status".to_string();
+        assert_eq!(
+            disposition(&config, "UBOT", &bot_command),
+            Disposition::Drop
+        );
+
+        // The same messages from a person are forwarded or run
+        let human_mention = message("U_HUMAN", true, false);
+        assert_eq!(
+            disposition(&config, "UBOT", &human_mention),
+            Disposition::Forward
+        );
+        let mut human_command = message("U_HUMAN", false, false);
+        human_command.text = "/coven status".to_string();
+        assert_eq!(
+            disposition(&config, "UBOT", &human_command),
+            Disposition::Command(Command::Status)
+        );
+
+        // The bridge's own messages are dropped even with bots let through
+        let config = config_with("ignore_bots = false");
+        assert_eq!(
+            disposition(&config, "UBOT", &message("UBOT", true, true)),
+            Disposition::Drop
+        );
+    }
 
     #[test]
     fn test_channel_binding_clone() {
         let binding = ChannelBinding {
             channel_id: "C123".to_string(),
             conversation_key: "test-conversation".to_string(),
+            overrides: RequestOverrides::default(),
         };
 
         let cloned = binding.clone();
         assert_eq!(binding.channel_id, cloned.channel_id);
         assert_eq!(binding.conversation_key, cloned.conversation_key);
     }
+
+    #[test]
+    fn test_channel_binding_stored_round_trip() {
+        let binding = ChannelBinding {
+            channel_id: "C123".to_string(),
+            conversation_key: "agent-1".to_string(),
+            overrides: RequestOverrides {
+                model: Some("claude-haiku-4-5".to_string()),
+                max_tokens: Some(1024),
+            },
+        };
+
+        let restored = ChannelBinding::from_stored(&binding.to_stored());
+        assert_eq!(restored.channel_id, binding.channel_id);
+        assert_eq!(restored.conversation_key, binding.conversation_key);
+        assert_eq!(restored.overrides, binding.overrides);
+    }
 }
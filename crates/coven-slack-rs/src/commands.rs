@@ -1,13 +1,15 @@
 // ABOUTME: Handles /coven slash commands for channel binding management.
-// ABOUTME: Supports bind, unbind, status, agents, and help commands.
+// ABOUTME: Supports bind, unbind, status, set, agents, and help commands.
 
 use crate::bridge::ChannelBinding;
+use crate::config::BridgeConfig;
 use crate::error::Result;
 use crate::gateway::GatewayClient;
+use coven_bridge_core::{BindingStore, RequestOverrides};
 use std::collections::HashMap;
 use std::sync::Arc;
 use tokio::sync::RwLock;
-use tracing::info;
+use tracing::{info, warn};
 
 /// Parsed command from /coven slash command text.
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -18,6 +20,8 @@ pub enum Command {
     Unbind,
     /// Show current binding status: /coven status
     Status,
+    /// Override a setting for this channel: /coven set <model|max_tokens> <value|default>
+    Set(String, String),
     /// List available agents: /coven agents
     Agents,
     /// Show help message: /coven help or /coven
@@ -26,6 +30,21 @@ pub enum Command {
     Unknown(String),
 }
 
+/// One of each known subcommand, in the order help lists them
+static KNOWN_COMMANDS: [Command; 6] = [
+    Command::Bind(String::new()),
+    Command::Unbind,
+    Command::Status,
+    Command::Set(String::new(), String::new()),
+    Command::Agents,
+    Command::Help,
+];
+
+/// Names of the known subcommands, for `allowed_commands`
+pub fn command_names() -> impl Iterator<Item = &'static str> {
+    KNOWN_COMMANDS.iter().filter_map(Command::name)
+}
+
 impl Command {
     /// Parse slash command text into a Command.
     /// The text comes after "/coven" has been stripped.
@@ -46,6 +65,19 @@ impl Command {
             },
             "unbind" => Command::Unbind,
             "status" => Command::Status,
+            "set" => {
+                let args: Vec<&str> = parts
+                    .get(1)
+                    .map(|s| s.split_whitespace().collect())
+                    .unwrap_or_default();
+                match args.as_slice() {
+                    [key, value] => Command::Set(key.to_string(), value.to_string()),
+                    _ => Command::Unknown(
+                        "set (requires a setting and value, e.g., /coven set model claude-sonnet-4-20250514)"
+                            .to_string(),
+                    ),
+                }
+            }
             "agents" => Command::Agents,
             "help" => Command::Help,
             other => Command::Unknown(other.to_string()),
@@ -60,6 +92,36 @@ impl Command {
         trimmed.starts_with("/coven")
     }
 
+    /// The subcommand's name, as `allowed_commands` lists it; None for
+    /// unknown commands.
+    pub fn name(&self) -> Option<&'static str> {
+        match self {
+            Command::Bind(_) => Some("bind"),
+            Command::Unbind => Some("unbind"),
+            Command::Status => Some("status"),
+            Command::Set(..) => Some("set"),
+            Command::Agents => Some("agents"),
+            Command::Help => Some("help"),
+            Command::Unknown(_) => None,
+        }
+    }
+
+    /// The subcommand's lines in the help message; none for unknown commands.
+    fn usage(&self) -> &'static [&'static str] {
+        match self {
+            Command::Bind(_) => &["`/coven bind <agent-id>` - Bind this channel to an agent"],
+            Command::Unbind => &["`/coven unbind` - Unbind this channel from current agent"],
+            Command::Status => &["`/coven status` - Show current binding status"],
+            Command::Set(..) => &[
+                "`/coven set model <name|default>` - Use a different model in this channel",
+                "`/coven set max_tokens <n|default>` - Limit response length in this channel",
+            ],
+            Command::Agents => &["`/coven agents` - List available agents"],
+            Command::Help => &["`/coven help` - Show this help message"],
+            Command::Unknown(_) => &[],
+        }
+    }
+
     /// Parse from raw message text that includes the /coven prefix.
     pub fn from_message(text: &str) -> Option<Command> {
         let trimmed = text.trim();
@@ -72,17 +134,40 @@ impl Command {
 pub struct CommandContext<'a> {
     pub gateway: &'a Arc<RwLock<GatewayClient>>,
     pub bindings: &'a Arc<RwLock<HashMap<String, ChannelBinding>>>,
+    pub store: &'a dyn BindingStore,
+    /// Who may run which commands
+    pub config: &'a BridgeConfig,
+    /// The Slack user running the command
+    pub user_id: &'a str,
     pub channel_id: &'a str,
 }
 
 /// Execute a command and return the response text.
 pub async fn execute_command(command: Command, ctx: CommandContext<'_>) -> Result<String> {
+    if !ctx.config.may_run(ctx.user_id, &command) {
+        let name = command.name().unwrap_or_default();
+        info!(
+            channel_id = %ctx.channel_id,
+            user_id = %ctx.user_id,
+            command = %name,
+            "Command not allowed for user"
+        );
+        return Ok(format!(
+            ":no_entry: You're not allowed to run `/coven {}` here. Ask an admin, or use `/coven help` for the commands you can run.",
+            name
+        ));
+    }
+
     match command {
         Command::Bind(agent_id) => {
             let binding = ChannelBinding {
                 channel_id: ctx.channel_id.to_string(),
                 conversation_key: agent_id.clone(),
+                overrides: RequestOverrides::default(),
             };
+            if let Err(e) = ctx.store.save(&binding.to_stored()).await {
+                warn!(error = %e, channel_id = %ctx.channel_id, "Failed to persist binding");
+            }
             ctx.bindings
                 .write()
                 .await
@@ -100,6 +185,9 @@ pub async fn execute_command(command: Command, ctx: CommandContext<'_>) -> Resul
 
         Command::Unbind => {
             let removed = ctx.bindings.write().await.remove(ctx.channel_id);
+            if let Err(e) = ctx.store.remove(ctx.channel_id).await {
+                warn!(error = %e, channel_id = %ctx.channel_id, "Failed to remove persisted binding");
+            }
             info!(channel_id = %ctx.channel_id, "Channel unbound via command");
             match removed {
                 Some(binding) => Ok(format!(
@@ -116,8 +204,10 @@ pub async fn execute_command(command: Command, ctx: CommandContext<'_>) -> Resul
             let bindings = ctx.bindings.read().await;
             match bindings.get(ctx.channel_id) {
                 Some(binding) => Ok(format!(
-                    ":link: *Status*: Bound to agent `{}`\nChannel: `{}`",
-                    binding.conversation_key, ctx.channel_id
+                    ":link: *Status*: Bound to agent `{}`\nChannel: `{}`\nSettings: {}",
+                    binding.conversation_key,
+                    ctx.channel_id,
+                    binding.overrides.describe()
                 )),
                 None => Ok(format!(
                     ":warning: *Status*: No agent bound to this channel.\nChannel: `{}`\n\nUse `/coven bind <agent-id>` to bind an agent.",
@@ -126,6 +216,36 @@ pub async fn execute_command(command: Command, ctx: CommandContext<'_>) -> Resul
             }
         }
 
+        Command::Set(key, value) => {
+            let mut bindings = ctx.bindings.write().await;
+            let Some(binding) = bindings.get_mut(ctx.channel_id) else {
+                return Ok(
+                    ":warning: No agent bound to this channel.\nUse `/coven bind <agent-id>` first."
+                        .to_string(),
+                );
+            };
+
+            // Validate against a copy so a bad value leaves the binding untouched
+            let mut overrides = binding.overrides.clone();
+            if let Err(e) = overrides.set(&key, &value) {
+                return Ok(format!(":x: {}", e));
+            }
+            binding.overrides = overrides;
+            if let Err(e) = ctx.store.save(&binding.to_stored()).await {
+                warn!(error = %e, channel_id = %ctx.channel_id, "Failed to persist binding");
+            }
+            info!(
+                channel_id = %ctx.channel_id,
+                setting = %key,
+                value = %value,
+                "Channel overrides updated via command"
+            );
+            Ok(format!(
+                ":gear: Settings for this channel: {}",
+                binding.overrides.describe()
+            ))
+        }
+
         Command::Agents => {
             let mut gateway = ctx.gateway.write().await;
             let agents = gateway.list_agents().await?;
@@ -146,16 +266,7 @@ pub async fn execute_command(command: Command, ctx: CommandContext<'_>) -> Resul
             }
         }
 
-        Command::Help => Ok(r#":book: *Coven Bridge Commands:*
-
-• `/coven bind <agent-id>` - Bind this channel to an agent
-• `/coven unbind` - Unbind this channel from current agent
-• `/coven status` - Show current binding status
-• `/coven agents` - List available agents
-• `/coven help` - Show this help message
-
-_Messages in bound channels will be forwarded to the agent._"#
-            .to_string()),
+        Command::Help => Ok(help_text(ctx.config, ctx.user_id)),
 
         Command::Unknown(cmd) => Ok(format!(
             ":question: Unknown command: `{}`\nUse `/coven help` for available commands.",
@@ -164,6 +275,23 @@ _Messages in bound channels will be forwarded to the agent._"#
     }
 }
 
+/// The help message, listing only the commands `user_id` may run.
+fn help_text(config: &BridgeConfig, user_id: &str) -> String {
+    let mut text = String::from(":book: *Coven Bridge Commands:*\n\n");
+    for command in KNOWN_COMMANDS
+        .iter()
+        .filter(|command| config.may_run(user_id, command))
+    {
+        for line in command.usage() {
+            text.push_str("• ");
+            text.push_str(line);
+            text.push('\n');
+        }
+    }
+    text.push_str("\n_Messages in bound channels will be forwarded to the agent._");
+    text
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -209,6 +337,24 @@ mod tests {
         assert_eq!(Command::parse("agents"), Command::Agents);
     }
 
+    #[test]
+    fn test_command_parse_set() {
+        assert_eq!(
+            Command::parse("set model claude-haiku-4-5"),
+            Command::Set("model".to_string(), "claude-haiku-4-5".to_string())
+        );
+        assert_eq!(
+            Command::parse("set  max_tokens   default "),
+            Command::Set("max_tokens".to_string(), "default".to_string())
+        );
+        for text in ["set", "set model", "set model a b"] {
+            match Command::parse(text) {
+                Command::Unknown(msg) => assert!(msg.contains("requires a setting")),
+                other => panic!("Expected Unknown command, got {:?}", other),
+            }
+        }
+    }
+
     #[test]
     fn test_command_parse_unknown() {
         assert_eq!(Command::parse("foo"), Command::Unknown("foo".to_string()));
@@ -232,4 +378,31 @@ mod tests {
         );
         assert!(Command::from_message("hello world").is_none());
     }
+
+    #[test]
+    fn test_command_names_follow_the_enum() {
+        assert_eq!(
+            command_names().collect::<Vec<_>>(),
+            vec!["bind", "unbind", "status", "set", "agents", "help"]
+        );
+    }
+
+    #[test]
+    fn test_help_lists_only_allowed_commands() {
+        let config = BridgeConfig {
+            allowed_commands: Some(vec!["status".to_string()]),
+            admin_users: vec!["UADMIN".into()],
+            ..Default::default()
+        };
+
+        let member = help_text(&config, "UMEMBER");
+        assert!(member.contains("/coven status"));
+        assert!(member.contains("/coven help"));
+        assert!(!member.contains("/coven bind"));
+        assert!(!member.contains("/coven set"));
+
+        let admin = help_text(&config, "UADMIN");
+        assert!(admin.contains("/coven bind"));
+        assert!(admin.contains("/coven set max_tokens"));
+    }
 }
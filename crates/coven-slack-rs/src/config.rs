@@ -1,7 +1,9 @@
 // ABOUTME: Configuration loading and validation for the Slack bridge.
 // ABOUTME: Supports TOML config files with environment variable expansion.
 
+use crate::commands::{command_names, Command};
 use crate::error::{BridgeError, Result};
+use coven_grpc::{KeepAliveConfig, KeepAliveSettings};
 use serde::Deserialize;
 use std::path::PathBuf;
 use tracing::warn;
@@ -41,6 +43,10 @@ pub struct GatewayConfig {
     /// Authentication token for gateway API calls.
     #[serde(default)]
     pub token: Option<String>,
+    /// HTTP/2 keep-alive pings to the gateway, as a `[gateway.keepalive]`
+    /// table (see `KeepAliveSettings`).
+    #[serde(default)]
+    pub keepalive: KeepAliveSettings,
 }
 
 impl std::fmt::Debug for GatewayConfig {
@@ -48,10 +54,20 @@ impl std::fmt::Debug for GatewayConfig {
         f.debug_struct("GatewayConfig")
             .field("url", &self.url)
             .field("token", &self.token.as_ref().map(|_| "[REDACTED]"))
+            .field("keepalive", &self.keepalive)
             .finish()
     }
 }
 
+impl GatewayConfig {
+    /// Validated keep-alive config, or None when disabled.
+    pub fn keep_alive(&self) -> Result<Option<KeepAliveConfig>> {
+        self.keepalive
+            .to_config()
+            .map_err(|e| BridgeError::Config(format!("gateway.keepalive: {}", e)))
+    }
+}
+
 /// Bridge behavior configuration.
 #[derive(Debug, Clone, Deserialize)]
 pub struct BridgeConfig {
@@ -70,6 +86,45 @@ pub struct BridgeConfig {
     /// Always reply in threads (keeps channels cleaner).
     #[serde(default = "default_thread_replies")]
     pub thread_replies: bool,
+
+    /// Persist bindings to this file so they survive restarts
+    /// (`.json` for a JSON file, anything else for SQLite; unset = memory only).
+    #[serde(default)]
+    pub bindings_path: Option<String>,
+
+    /// Show the response while it streams, editing it at most once per this
+    /// many milliseconds (unset or 0 = send once the response is complete).
+    #[serde(default)]
+    pub stream_interval_ms: Option<u64>,
+
+    /// Channel that receives agent-initiated messages from agents with no
+    /// bound channel (unset = drop them with a log line).
+    #[serde(default)]
+    pub notifications_channel: Option<String>,
+
+    /// Most messages handled at once across threads (unset = 16). Messages
+    /// in one thread are always handled one at a time, in arrival order.
+    #[serde(default)]
+    pub max_concurrent_messages: Option<usize>,
+
+    /// `/coven` subcommands anyone may run, e.g. `["status", "agents"]`
+    /// (unset = all). `help` is always allowed.
+    #[serde(default)]
+    pub allowed_commands: Option<Vec<String>>,
+
+    /// Slack user IDs that may run every `/coven` command, whatever
+    /// `allowed_commands` says.
+    #[serde(default)]
+    pub admin_users: Vec<String>,
+
+    /// Slack user IDs whose messages are dropped, mentions included.
+    #[serde(default)]
+    pub ignore_senders: Vec<String>,
+
+    /// Drop messages posted by bots and apps, so two bots in a channel
+    /// can't answer each other forever.
+    #[serde(default = "default_ignore_bots")]
+    pub ignore_bots: bool,
 }
 
 impl Default for BridgeConfig {
@@ -79,10 +134,31 @@ impl Default for BridgeConfig {
             response_mode: ResponseMode::default(),
             typing_indicator: default_typing_indicator(),
             thread_replies: default_thread_replies(),
+            bindings_path: None,
+            stream_interval_ms: None,
+            notifications_channel: None,
+            max_concurrent_messages: None,
+            allowed_commands: None,
+            admin_users: Vec::new(),
+            ignore_senders: Vec::new(),
+            ignore_bots: default_ignore_bots(),
         }
     }
 }
 
+impl BridgeConfig {
+    /// Whether Slack user `user_id` may run `command`. Unknown commands
+    /// only get a usage hint, so anyone may run them.
+    pub fn may_run(&self, user_id: &str, command: &Command) -> bool {
+        let (Some(allowed), Some(name)) = (&self.allowed_commands, command.name()) else {
+            return true;
+        };
+        name == "help"
+            || allowed.iter().any(|c| c == name)
+            || self.admin_users.iter().any(|u| u == user_id)
+    }
+}
+
 fn default_typing_indicator() -> bool {
     true
 }
@@ -91,6 +167,10 @@ fn default_thread_replies() -> bool {
     true
 }
 
+fn default_ignore_bots() -> bool {
+    true
+}
+
 /// Response mode determines when the bot responds to messages.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
 #[serde(rename_all = "lowercase")]
@@ -157,6 +237,16 @@ impl Config {
         if self.gateway.url.is_empty() {
             return Err(BridgeError::Config("gateway.url is required".into()));
         }
+        self.gateway.keep_alive()?;
+        for name in self.bridge.allowed_commands.iter().flatten() {
+            if !command_names().any(|known| known == name) {
+                return Err(BridgeError::Config(format!(
+                    "bridge.allowed_commands: unknown command '{}' (known: {})",
+                    name,
+                    command_names().collect::<Vec<_>>().join(", ")
+                )));
+            }
+        }
         Ok(())
     }
 
@@ -166,6 +256,21 @@ impl Config {
         self.bridge.allowed_channels.is_empty()
             || self.bridge.allowed_channels.iter().any(|c| c == channel_id)
     }
+
+    /// Whether messages from `user_id` are dropped: it's on the ignore
+    /// list, or it's a bot and bots are ignored.
+    pub fn is_sender_ignored(&self, user_id: &str, is_bot: bool) -> bool {
+        (is_bot && self.bridge.ignore_bots)
+            || self.bridge.ignore_senders.iter().any(|u| u == user_id)
+    }
+
+    /// Resolved bindings store path, with `~` expanded.
+    pub fn bindings_path(&self) -> Option<PathBuf> {
+        self.bridge
+            .bindings_path
+            .as_deref()
+            .map(|p| PathBuf::from(shellexpand::tilde(p).into_owned()))
+    }
 }
 
 #[cfg(test)]
@@ -184,5 +289,30 @@ mod tests {
         assert_eq!(config.response_mode, ResponseMode::Mention);
         assert!(config.typing_indicator);
         assert!(config.thread_replies);
+        assert!(config.ignore_senders.is_empty());
+        assert!(config.ignore_bots);
+    }
+
+    #[test]
+    fn test_gateway_keepalive_from_toml() {
+        let gateway: GatewayConfig = toml::from_str(
+            r#"
+            url = "http://localhost:6666"
+
+            [keepalive]
+            interval_secs = 45
+            timeout_secs = 15
+            "#,
+        )
+        .unwrap();
+        let ka = gateway.keep_alive().unwrap().unwrap();
+        assert_eq!(ka.interval, std::time::Duration::from_secs(45));
+        assert_eq!(ka.timeout, std::time::Duration::from_secs(15));
+        assert!(!ka.while_idle);
+
+        let gateway: GatewayConfig =
+            toml::from_str("url = \"http://localhost:6666\"\n[keepalive]\nenabled = false\n")
+                .unwrap();
+        assert_eq!(gateway.keep_alive().unwrap(), None);
     }
 }
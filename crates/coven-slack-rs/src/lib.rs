@@ -19,7 +19,7 @@ pub use slack::{CovenSlackClient, SlackMessageInfo};
 use slack_morphism::prelude::*;
 use std::path::PathBuf;
 use std::sync::Arc;
-use tracing::{error, info};
+use tracing::{debug, error, info};
 
 /// Error handler for Socket Mode events.
 fn socket_mode_error_handler(
@@ -47,6 +47,9 @@ pub async fn run(config_path: Option<PathBuf>) -> anyhow::Result<()> {
     let bridge = Arc::new(Bridge::new(config.clone()).await?);
     info!("Bridge initialized");
 
+    // Relay messages agents send without being asked
+    tokio::spawn(Arc::clone(&bridge).run_initiated_listener());
+
     // Set up Socket Mode listener
     let client = Arc::new(slack_morphism::SlackClient::new(
         SlackClientHyperConnector::new()?,
@@ -131,45 +134,20 @@ async fn handle_push_event(
                 &msg_event,
                 bridge.slack_client().bot_user_id(),
             ) {
-                let bridge = Arc::clone(&bridge);
-                tokio::spawn(async move {
-                    if let Err(e) = bridge.handle_message(msg_info).await {
-                        error!(error = %e, "Failed to handle message");
-                    }
-                });
+                if bridge.is_redelivery(&msg_info) {
+                    return Ok(());
+                }
+                bridge.dispatch(msg_info);
             }
         }
         SlackEventCallbackBody::AppMention(mention_event) => {
-            let channel_id = mention_event.channel.to_string();
-            let user_id = mention_event.user.to_string();
-            let text = mention_event.content.text.clone().unwrap_or_default();
-            let message_ts = mention_event.origin.ts.to_string();
-            let thread_ts = mention_event
-                .origin
-                .thread_ts
-                .as_ref()
-                .map(|ts| ts.to_string());
-
-            let context = bridge
-                .slack_client()
-                .build_context(&channel_id, thread_ts.as_deref());
-
-            let msg_info = SlackMessageInfo {
-                channel_id,
-                user_id,
-                text,
-                message_ts,
-                thread_ts,
-                is_mention: true,
-                context,
-            };
-
-            let bridge = Arc::clone(&bridge);
-            tokio::spawn(async move {
-                if let Err(e) = bridge.handle_message(msg_info).await {
-                    error!(error = %e, "Failed to handle app mention");
-                }
-            });
+            // Every mention also arrives as a message event, which says
+            // whether a bot sent it; that event is the one dispatched
+            debug!(
+                channel_id = %mention_event.channel,
+                message_ts = %mention_event.origin.ts,
+                "Ignoring app_mention in favour of its message event"
+            );
         }
         _ => {}
     }
@@ -197,6 +175,7 @@ async fn handle_slash_command(
     }
 
     let channel_id = event.channel_id.to_string();
+    let user_id = event.user_id.to_string();
     let command_text = event.text.clone().unwrap_or_default();
 
     info!(
@@ -210,7 +189,10 @@ async fn handle_slash_command(
     let ctx = commands::CommandContext {
         gateway: bridge.gateway_client(),
         bindings: bridge.bindings(),
+        store: bridge.binding_store().as_ref(),
         channel_id: &channel_id,
+        config: &bridge.config().bridge,
+        user_id: &user_id,
     };
 
     let response_text = match commands::execute_command(command, ctx).await {
@@ -6,7 +6,11 @@ use crate::context::SlackContext;
 use crate::error::{BridgeError, Result};
 use slack_morphism::prelude::*;
 use std::sync::Arc;
-use tracing::{debug, info};
+use tracing::{debug, info, warn};
+
+/// Longest message text the bridge posts in one message. Slack truncates
+/// text beyond 40,000 characters but recommends staying under 4,000.
+pub const MAX_MESSAGE_LEN: usize = 4000;
 
 /// Slack client wrapper for Socket Mode communication.
 pub struct CovenSlackClient {
@@ -85,6 +89,45 @@ impl CovenSlackClient {
         Ok(response.ts)
     }
 
+    /// Replace the text of a message the bot posted earlier.
+    pub async fn update_message(&self, channel_id: &str, ts: &SlackTs, text: &str) -> Result<()> {
+        debug!(channel_id = %channel_id, message_ts = %ts, "Updating Slack message");
+
+        let session = self.client.open_session(&self.bot_token);
+
+        let request = SlackApiChatUpdateRequest::new(
+            SlackChannelId::new(channel_id.to_string()),
+            SlackMessageContent::new().with_text(text.to_string()),
+            ts.clone(),
+        );
+
+        session.chat_update(&request).await?;
+        Ok(())
+    }
+
+    /// Look up a user's display name, falling back to their real name and
+    /// then their username. Returns None if the lookup fails.
+    pub async fn user_display_name(&self, user_id: &str) -> Option<String> {
+        let session = self.client.open_session(&self.bot_token);
+        let request = SlackApiUsersInfoRequest::new(SlackUserId::new(user_id.to_string()));
+
+        let user = match session.users_info(&request).await {
+            Ok(response) => response.user,
+            Err(e) => {
+                warn!(error = %e, user_id = %user_id, "Failed to look up Slack user");
+                return None;
+            }
+        };
+
+        let profile = user.profile.as_ref();
+        profile
+            .and_then(|p| p.display_name.clone())
+            .filter(|name| !name.is_empty())
+            .or_else(|| profile.and_then(|p| p.real_name.clone()))
+            .filter(|name| !name.is_empty())
+            .or(user.name)
+    }
+
     /// Post a message with Block Kit formatting.
     pub async fn post_blocks(
         &self,
@@ -152,6 +195,8 @@ pub struct SlackMessageInfo {
     pub message_ts: String,
     pub thread_ts: Option<String>,
     pub is_mention: bool,
+    /// Posted by a bot or app rather than a person
+    pub is_bot: bool,
     pub context: SlackContext,
 }
 
@@ -170,6 +215,7 @@ impl SlackMessageInfo {
         // Check for bot mention
         let mention_pattern = format!("<@{}>", bot_user_id);
         let is_mention = text.contains(&mention_pattern);
+        let is_bot = event.sender.bot_id.is_some();
 
         let is_dm = channel_id.starts_with('D') || channel_id.starts_with('G');
         let context = SlackContext::from_event(channel_id.clone(), thread_ts.clone(), is_dm);
@@ -181,10 +227,35 @@ impl SlackMessageInfo {
             message_ts,
             thread_ts,
             is_mention,
+            is_bot,
             context,
         })
     }
 
+    /// Key identifying this message across redeliveries. A message's ts is
+    /// only unique within its channel.
+    pub fn dedup_key(&self) -> String {
+        format!("{}:{}", self.channel_id, self.message_ts)
+    }
+
+    /// Key of the message this one replies to: the thread's root message
+    /// for a reply in a thread, None for top-level messages.
+    pub fn parent_key(&self) -> Option<String> {
+        self.thread_ts
+            .as_ref()
+            .filter(|thread_ts| **thread_ts != self.message_ts)
+            .map(|thread_ts| format!("{}:{}", self.channel_id, thread_ts))
+    }
+
+    /// Key for handling messages in order: the thread for replies, the
+    /// channel for top-level messages.
+    pub fn ordering_key(&self) -> String {
+        match &self.thread_ts {
+            Some(thread_ts) => format!("{}:{}", self.channel_id, thread_ts),
+            None => self.channel_id.clone(),
+        }
+    }
+
     /// Get the thread_ts to use for replies.
     /// If already in a thread, use that. Otherwise use the message_ts to start a new thread.
     pub fn reply_thread_ts(&self, force_thread: bool) -> Option<String> {
@@ -217,4 +288,67 @@ mod tests {
         assert!(CovenSlackClient::is_dm_channel("G12345"));
         assert!(!CovenSlackClient::is_dm_channel("C12345"));
     }
+
+    #[test]
+    fn test_dedup_key_scoped_to_channel() {
+        let msg = |channel_id: &str| SlackMessageInfo {
+            channel_id: channel_id.to_string(),
+            user_id: "U1".to_string(),
+            text: "hi".to_string(),
+            message_ts: "1700000000.000100".to_string(),
+            thread_ts: None,
+            is_mention: false,
+            is_bot: false,
+            context: SlackContext::from_event(channel_id.to_string(), None, false),
+        };
+        assert_eq!(msg("C1").dedup_key(), "C1:1700000000.000100");
+        assert_ne!(msg("C1").dedup_key(), msg("C2").dedup_key());
+    }
+
+    #[test]
+    fn test_ordering_key_groups_thread_replies() {
+        let msg = |message_ts: &str, thread_ts: Option<&str>| SlackMessageInfo {
+            channel_id: "C1".to_string(),
+            user_id: "U1".to_string(),
+            text: "hi".to_string(),
+            message_ts: message_ts.to_string(),
+            thread_ts: thread_ts.map(str::to_string),
+            is_mention: false,
+            is_bot: false,
+            context: SlackContext::from_event("C1".to_string(), None, false),
+        };
+        let thread = Some("1700000000.000100");
+        assert_eq!(
+            msg("1700000000.000200", thread).ordering_key(),
+            msg("1700000000.000300", thread).ordering_key()
+        );
+        assert_eq!(msg("1700000000.000200", None).ordering_key(), "C1");
+        assert_ne!(
+            msg("1700000000.000200", thread).ordering_key(),
+            msg("1700000000.000200", None).ordering_key()
+        );
+    }
+
+    #[test]
+    fn test_parent_key_is_thread_root() {
+        let msg = |message_ts: &str, thread_ts: Option<&str>| SlackMessageInfo {
+            channel_id: "C1".to_string(),
+            user_id: "U1".to_string(),
+            text: "hi".to_string(),
+            message_ts: message_ts.to_string(),
+            thread_ts: thread_ts.map(str::to_string),
+            is_mention: false,
+            is_bot: false,
+            context: SlackContext::from_event("C1".to_string(), None, false),
+        };
+        let root = msg("1700000000.000100", None);
+        assert_eq!(root.parent_key(), None);
+
+        let reply = msg("1700000000.000200", Some("1700000000.000100"));
+        assert_eq!(reply.parent_key(), Some(root.dedup_key()));
+
+        // Slack sets thread_ts on a thread's root message to its own ts
+        let root_in_thread = msg("1700000000.000100", Some("1700000000.000100"));
+        assert_eq!(root_in_thread.parent_key(), None);
+    }
 }
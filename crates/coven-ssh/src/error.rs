@@ -27,6 +27,26 @@ pub enum SshError {
     #[error("failed to generate SSH key: {0}")]
     GenerateKey(#[source] ssh_key::Error),
 
+    /// Failed to encrypt a key with a passphrase.
+    #[error("failed to encrypt SSH key: {0}")]
+    EncryptKey(#[source] ssh_key::Error),
+
+    /// The key on disk is encrypted and no passphrase source was given.
+    #[error("SSH key at {path} is encrypted; configure a passphrase source to unlock it")]
+    PassphraseRequired { path: PathBuf },
+
+    /// The passphrase didn't decrypt the key.
+    #[error("wrong passphrase for SSH key at {path}")]
+    WrongPassphrase { path: PathBuf },
+
+    /// The passphrase source couldn't produce a passphrase.
+    #[error("failed to get SSH key passphrase: {0}")]
+    Passphrase(String),
+
+    /// A key was used for signing while still encrypted.
+    #[error("SSH key is encrypted; load it with a passphrase before signing")]
+    KeyEncrypted,
+
     /// Failed to serialize a key.
     #[error("failed to serialize key: {0}")]
     SerializeKey(#[source] ssh_key::Error),
@@ -56,12 +76,32 @@ pub enum SshError {
     },
 
     /// Unsupported key type for the requested operation.
-    #[error("unsupported key type: {0} (only ed25519 is supported)")]
+    #[error("unsupported key type: {0} (supported: ed25519, rsa, ecdsa-sha2-nistp256)")]
     UnsupportedKeyType(String),
 
     /// Failed to add metadata to gRPC request.
     #[error("invalid metadata value for {field}: {message}")]
     InvalidMetadata { field: String, message: String },
+
+    /// A signature or the credentials carrying it didn't check out.
+    #[error("invalid signature: {0}")]
+    InvalidSignature(String),
+
+    /// Signed credentials whose timestamp is too far from the verifier's
+    /// clock: too old, or from a client clock running ahead.
+    #[error(
+        "credentials expired: timestamp is {skew_secs} seconds from now, outside the {tolerance_secs} allowed either way"
+    )]
+    Expired { skew_secs: i64, tolerance_secs: i64 },
+
+    /// Signed credentials whose nonce was already used.
+    #[error("credentials replayed: nonce already used; sign each request afresh")]
+    Replayed,
+
+    /// Too many requests within the signature window to remember every
+    /// nonce. Retrying once older ones expire will succeed.
+    #[error("too many signed requests to check for replays; try again shortly")]
+    NonceCacheFull,
 }
 
 /// Result type alias using SshError.
@@ -144,11 +184,11 @@ mod tests {
 
     #[test]
     fn test_unsupported_key_type_error_display() {
-        let err = SshError::UnsupportedKeyType("rsa".to_string());
+        let err = SshError::UnsupportedKeyType("ssh-dss".to_string());
         let display = format!("{}", err);
         assert!(display.contains("unsupported key type"));
-        assert!(display.contains("rsa"));
-        assert!(display.contains("only ed25519 is supported"));
+        assert!(display.contains("ssh-dss"));
+        assert!(display.contains("supported: ed25519, rsa, ecdsa-sha2-nistp256"));
     }
 
     #[test]
@@ -163,6 +203,50 @@ mod tests {
         assert!(display.contains("invalid header value"));
     }
 
+    #[test]
+    fn test_invalid_signature_error_display() {
+        let err = SshError::InvalidSignature("timestamp too old".to_string());
+        assert_eq!(err.to_string(), "invalid signature: timestamp too old");
+    }
+
+    #[test]
+    fn test_verification_error_display() {
+        let err = SshError::Expired {
+            skew_secs: -150,
+            tolerance_secs: 120,
+        };
+        assert!(err.to_string().contains("-150 seconds from now"));
+        assert!(err.to_string().contains("120 allowed"));
+        assert!(SshError::Replayed
+            .to_string()
+            .contains("nonce already used"));
+        assert!(SshError::NonceCacheFull.to_string().contains("try again"));
+    }
+
+    #[test]
+    fn test_passphrase_error_display() {
+        let err = SshError::PassphraseRequired {
+            path: PathBuf::from("/path/to/key"),
+        };
+        let display = err.to_string();
+        assert!(display.contains("/path/to/key is encrypted"));
+        assert!(display.contains("passphrase source"));
+
+        let err = SshError::WrongPassphrase {
+            path: PathBuf::from("/path/to/key"),
+        };
+        assert_eq!(
+            err.to_string(),
+            "wrong passphrase for SSH key at /path/to/key"
+        );
+
+        let err = SshError::Passphrase("environment variable X is not set".to_string());
+        assert!(err.to_string().contains("X is not set"));
+
+        let err = SshError::EncryptKey(ssh_key::Error::AlgorithmUnknown);
+        assert!(err.to_string().contains("failed to encrypt SSH key"));
+    }
+
     #[test]
     fn test_error_debug() {
         let err = SshError::UnsupportedKeyType("test".to_string());
@@ -1,7 +1,8 @@
 // ABOUTME: SSH key loading and generation utilities.
-// ABOUTME: Handles ed25519 key pair creation and persistence to filesystem.
+// ABOUTME: Handles ed25519 key pair creation, passphrase encryption, and persistence to filesystem.
 
 use crate::error::{Result, SshError};
+use crate::passphrase::PassphraseSource;
 use ssh_key::{Algorithm, LineEnding, PrivateKey};
 use std::path::{Path, PathBuf};
 
@@ -33,17 +34,44 @@ pub fn default_swarm_key_path() -> Option<PathBuf> {
 /// Load an existing SSH private key from disk.
 ///
 /// # Errors
-/// Returns an error if the file cannot be read or parsed.
+/// Returns an error if the file cannot be read or parsed, or if the key is
+/// encrypted (use [`load_key_with_passphrase`] for those).
 pub fn load_key(key_path: &Path) -> Result<PrivateKey> {
+    load_key_with_passphrase(key_path, None)
+}
+
+/// Load an SSH private key from disk, decrypting it with a passphrase from
+/// `passphrase` if it is encrypted. Unencrypted keys load without asking.
+///
+/// # Errors
+/// Returns an error if the file cannot be read or parsed, if the key is
+/// encrypted and there is no passphrase source, or if the passphrase is wrong.
+pub fn load_key_with_passphrase(
+    key_path: &Path,
+    passphrase: Option<&PassphraseSource>,
+) -> Result<PrivateKey> {
     let key_data = std::fs::read_to_string(key_path).map_err(|e| SshError::ReadKey {
         path: key_path.to_path_buf(),
         source: e,
     })?;
 
-    PrivateKey::from_openssh(&key_data).map_err(|e| SshError::ParseKey {
+    let private_key = PrivateKey::from_openssh(&key_data).map_err(|e| SshError::ParseKey {
         path: key_path.to_path_buf(),
         source: e,
-    })
+    })?;
+    if !private_key.is_encrypted() {
+        return Ok(private_key);
+    }
+
+    let source = passphrase.ok_or_else(|| SshError::PassphraseRequired {
+        path: key_path.to_path_buf(),
+    })?;
+    let passphrase = source.passphrase(key_path)?;
+    private_key
+        .decrypt(passphrase.as_bytes())
+        .map_err(|_| SshError::WrongPassphrase {
+            path: key_path.to_path_buf(),
+        })
 }
 
 /// Generate a new ed25519 SSH key pair and save to disk.
@@ -54,6 +82,20 @@ pub fn load_key(key_path: &Path) -> Result<PrivateKey> {
 /// # Errors
 /// Returns an error if directory creation, key generation, or file writing fails.
 pub fn generate_key(key_path: &Path) -> Result<PrivateKey> {
+    write_new_key(key_path, None)
+}
+
+/// Generate a new ed25519 SSH key pair like [`generate_key`], but write the
+/// private key encrypted with `passphrase`. Returns the decrypted key.
+///
+/// # Errors
+/// Returns an error if directory creation, key generation, encryption, or
+/// file writing fails.
+pub fn generate_key_encrypted(key_path: &Path, passphrase: &str) -> Result<PrivateKey> {
+    write_new_key(key_path, Some(passphrase))
+}
+
+fn write_new_key(key_path: &Path, passphrase: Option<&str>) -> Result<PrivateKey> {
     eprintln!("Generating new SSH key at {}...", key_path.display());
 
     // Ensure parent directory exists
@@ -68,8 +110,14 @@ pub fn generate_key(key_path: &Path) -> Result<PrivateKey> {
     let private_key = PrivateKey::random(&mut rand::thread_rng(), Algorithm::Ed25519)
         .map_err(SshError::GenerateKey)?;
 
-    // Write private key in OpenSSH format
-    let private_key_str = private_key
+    // Write private key in OpenSSH format, encrypted if there's a passphrase
+    let on_disk = match passphrase {
+        Some(passphrase) => private_key
+            .encrypt(&mut rand::thread_rng(), passphrase.as_bytes())
+            .map_err(SshError::EncryptKey)?,
+        None => private_key.clone(),
+    };
+    let private_key_str = on_disk
         .to_openssh(LineEnding::LF)
         .map_err(SshError::SerializeKey)?;
 
@@ -115,10 +163,27 @@ pub fn generate_key(key_path: &Path) -> Result<PrivateKey> {
 /// # Errors
 /// Returns an error if key loading fails (for existing keys) or generation fails.
 pub fn load_or_generate_key(key_path: &Path) -> Result<PrivateKey> {
+    load_or_generate_key_with_passphrase(key_path, None)
+}
+
+/// Load an existing SSH key, decrypting it if needed, or generate a new one
+/// if it doesn't exist.
+///
+/// With a passphrase source, a new key is written encrypted with the
+/// passphrase it gives; without one it is written unencrypted.
+///
+/// # Errors
+/// Returns an error if key loading, decryption, or generation fails.
+pub fn load_or_generate_key_with_passphrase(
+    key_path: &Path,
+    passphrase: Option<&PassphraseSource>,
+) -> Result<PrivateKey> {
     if key_path.exists() {
-        load_key(key_path)
-    } else {
-        generate_key(key_path)
+        return load_key_with_passphrase(key_path, passphrase);
+    }
+    match passphrase {
+        Some(source) => generate_key_encrypted(key_path, &source.passphrase(key_path)?),
+        None => generate_key(key_path),
     }
 }
 
@@ -257,6 +322,69 @@ mod tests {
         assert!(matches!(err, crate::error::SshError::ParseKey { .. }));
     }
 
+    #[test]
+    fn test_encrypted_key_round_trip() {
+        let temp_dir = TempDir::new().expect("should create temp dir");
+        let key_path = temp_dir.path().join("encrypted_key");
+
+        let generated =
+            generate_key_encrypted(&key_path, "correct horse").expect("should generate key");
+        assert!(!generated.is_encrypted());
+        let on_disk = std::fs::read_to_string(&key_path).unwrap();
+        assert!(PrivateKey::from_openssh(&on_disk).unwrap().is_encrypted());
+
+        let source = PassphraseSource::Literal("correct horse".to_string());
+        let loaded = load_key_with_passphrase(&key_path, Some(&source)).expect("should decrypt");
+        assert_eq!(
+            generated.public_key().to_openssh().unwrap(),
+            loaded.public_key().to_openssh().unwrap()
+        );
+
+        // The decrypted key signs like any other
+        let creds = crate::SshAuthCredentials::new(&loaded).expect("should sign");
+        creds.verify(60).expect("signature should verify");
+    }
+
+    #[test]
+    fn test_encrypted_key_wrong_or_missing_passphrase() {
+        let temp_dir = TempDir::new().expect("should create temp dir");
+        let key_path = temp_dir.path().join("encrypted_key");
+        generate_key_encrypted(&key_path, "correct horse").expect("should generate key");
+
+        let wrong = PassphraseSource::Literal("battery staple".to_string());
+        let err = load_key_with_passphrase(&key_path, Some(&wrong)).unwrap_err();
+        assert!(matches!(err, SshError::WrongPassphrase { .. }));
+
+        let err = load_key(&key_path).unwrap_err();
+        assert!(matches!(err, SshError::PassphraseRequired { .. }));
+        let err = load_or_generate_key(&key_path).unwrap_err();
+        assert!(matches!(err, SshError::PassphraseRequired { .. }));
+    }
+
+    #[test]
+    fn test_load_or_generate_with_passphrase() {
+        let temp_dir = TempDir::new().expect("should create temp dir");
+        let key_path = temp_dir.path().join("new_key");
+        let source = PassphraseSource::Literal("correct horse".to_string());
+
+        let generated = load_or_generate_key_with_passphrase(&key_path, Some(&source))
+            .expect("should generate key");
+        let on_disk = std::fs::read_to_string(&key_path).unwrap();
+        assert!(PrivateKey::from_openssh(&on_disk).unwrap().is_encrypted());
+
+        let loaded = load_or_generate_key_with_passphrase(&key_path, Some(&source))
+            .expect("should load key");
+        assert_eq!(
+            generated.public_key().to_openssh().unwrap(),
+            loaded.public_key().to_openssh().unwrap()
+        );
+
+        // Unencrypted keys ignore the passphrase
+        let plain_path = temp_dir.path().join("plain_key");
+        generate_key(&plain_path).expect("should generate key");
+        load_key_with_passphrase(&plain_path, Some(&source)).expect("should load key");
+    }
+
     #[test]
     fn test_xdg_config_home_override() {
         // Save original value
@@ -11,9 +11,12 @@
 //!
 //! ## Features
 //!
-//! - **Key Management**: Load existing SSH keys or generate new ed25519 keys
+//! - **Key Management**: Load existing ed25519, RSA, or ECDSA P-256 keys, or generate new ed25519 keys
+//! - **Passphrases**: Unlock encrypted keys from a literal, env var, prompt, or command
 //! - **Fingerprinting**: Compute SHA256 fingerprints compatible with Go's ssh library
 //! - **gRPC Auth**: Apply SSH authentication credentials to tonic requests
+//! - **Verification**: Check signed requests on the server, with clock-skew tolerance and replay protection
+//! - **Rotation**: Generate a successor key and prove holding it to the gateway
 //!
 //! ## Example
 //!
@@ -41,14 +44,28 @@ mod credentials;
 mod error;
 mod fingerprint;
 mod key;
+mod passphrase;
+mod rotation;
+mod verify;
 
 // Re-export primary types and functions
-pub use credentials::{current_timestamp, generate_nonce, sign_message, SshAuthCredentials};
+pub use credentials::{
+    current_timestamp, generate_nonce, sign_message, verify_signature, SshAuthCredentials,
+    MAX_SIGNATURE_AGE_SECS,
+};
 pub use error::{Result, SshError};
 pub use fingerprint::compute_fingerprint;
 pub use key::{
     default_agent_key_path, default_client_key_path, default_swarm_key_path, generate_key,
-    load_key, load_or_generate_key, xdg_config_dir,
+    generate_key_encrypted, load_key, load_key_with_passphrase, load_or_generate_key,
+    load_or_generate_key_with_passphrase, xdg_config_dir,
+};
+pub use passphrase::{PassphrasePrompt, PassphraseSource};
+pub use rotation::{
+    rotate_key, rotate_key_with_passphrase, KeyRotation, RotationProof, DEFAULT_ROTATION_GRACE_SECS,
+};
+pub use verify::{
+    verify_request, NonceCache, DEFAULT_NONCE_CACHE_CAPACITY, DEFAULT_SKEW_TOLERANCE,
 };
 
 // Re-export ssh_key types for convenience
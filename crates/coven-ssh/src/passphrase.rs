@@ -0,0 +1,226 @@
+// ABOUTME: Where the passphrase for an encrypted SSH key comes from.
+// ABOUTME: Literal strings, env vars, prompt callbacks, or external commands such as a keychain lookup.
+
+use crate::error::{Result, SshError};
+use std::fmt;
+use std::io::{BufRead, IsTerminal, Write};
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::sync::Arc;
+
+/// Callback asked for the passphrase of the key at the given path.
+pub type PassphrasePrompt = Arc<dyn Fn(&Path) -> std::io::Result<String> + Send + Sync>;
+
+/// Source of the passphrase that unlocks an encrypted private key.
+///
+/// Config files name a source with [`PassphraseSource::parse`]:
+///
+/// - `env:NAME` reads the environment variable `NAME`
+/// - `cmd:COMMAND` runs `COMMAND` with `sh -c` and uses the first line it
+///   prints, e.g. `cmd:security find-generic-password -w -s coven`
+/// - `prompt` asks on the terminal
+/// - `pass:PASSPHRASE` is the passphrase itself
+#[derive(Clone)]
+pub enum PassphraseSource {
+    /// The passphrase itself.
+    Literal(String),
+    /// Name of an environment variable holding the passphrase.
+    Env(String),
+    /// Ask for the passphrase, e.g. in a dialog.
+    Prompt(PassphrasePrompt),
+    /// Shell command that prints the passphrase, for keychain integration.
+    Command(String),
+}
+
+impl PassphraseSource {
+    /// Parse a source from its config form (`env:`, `cmd:`, `prompt`, `pass:`).
+    ///
+    /// # Errors
+    /// Returns an error for any other form.
+    pub fn parse(spec: &str) -> Result<Self> {
+        let spec = spec.trim();
+        if spec == "prompt" {
+            return Ok(Self::terminal_prompt());
+        }
+        match spec.split_once(':') {
+            Some(("env", name)) if !name.is_empty() => Ok(Self::Env(name.to_string())),
+            Some(("cmd", command)) if !command.trim().is_empty() => {
+                Ok(Self::Command(command.to_string()))
+            }
+            Some(("pass", passphrase)) => Ok(Self::Literal(passphrase.to_string())),
+            _ => Err(SshError::Passphrase(format!(
+                "unknown passphrase source '{}' (expected env:NAME, cmd:COMMAND, prompt, or pass:PASSPHRASE)",
+                spec
+            ))),
+        }
+    }
+
+    /// A prompt on the controlling terminal, with echo turned off.
+    pub fn terminal_prompt() -> Self {
+        Self::Prompt(Arc::new(read_from_terminal))
+    }
+
+    /// Get the passphrase for the key at `key_path`.
+    ///
+    /// # Errors
+    /// Returns an error if the env var is unset, the prompt fails, or the
+    /// command fails or prints nothing.
+    pub fn passphrase(&self, key_path: &Path) -> Result<String> {
+        match self {
+            Self::Literal(passphrase) => Ok(passphrase.clone()),
+            Self::Env(name) => std::env::var(name).map_err(|_| {
+                SshError::Passphrase(format!("environment variable {} is not set", name))
+            }),
+            Self::Prompt(prompt) => {
+                prompt(key_path).map_err(|e| SshError::Passphrase(format!("prompt failed: {}", e)))
+            }
+            Self::Command(command) => run_command(command),
+        }
+    }
+}
+
+impl fmt::Debug for PassphraseSource {
+    // Never print a literal passphrase
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Literal(_) => f.write_str("Literal(..)"),
+            Self::Env(name) => f.debug_tuple("Env").field(name).finish(),
+            Self::Prompt(_) => f.write_str("Prompt(..)"),
+            Self::Command(command) => f.debug_tuple("Command").field(command).finish(),
+        }
+    }
+}
+
+/// Run `command` through the shell and take the first line of its output.
+fn run_command(command: &str) -> Result<String> {
+    let output = Command::new("sh")
+        .arg("-c")
+        .arg(command)
+        .stdin(Stdio::null())
+        .stderr(Stdio::inherit())
+        .output()
+        .map_err(|e| SshError::Passphrase(format!("failed to run '{}': {}", command, e)))?;
+    if !output.status.success() {
+        return Err(SshError::Passphrase(format!(
+            "'{}' exited with {}",
+            command, output.status
+        )));
+    }
+    let stdout = String::from_utf8(output.stdout)
+        .map_err(|_| SshError::Passphrase(format!("'{}' printed invalid UTF-8", command)))?;
+    match stdout.lines().next() {
+        Some(line) if !line.is_empty() => Ok(line.to_string()),
+        _ => Err(SshError::Passphrase(format!(
+            "'{}' printed nothing",
+            command
+        ))),
+    }
+}
+
+fn read_from_terminal(key_path: &Path) -> std::io::Result<String> {
+    let stdin = std::io::stdin();
+    if !stdin.is_terminal() {
+        return Err(std::io::Error::other("stdin is not a terminal"));
+    }
+    eprint!("Passphrase for {}: ", key_path.display());
+    std::io::stderr().flush()?;
+
+    let echo_off = set_echo(false);
+    let mut line = String::new();
+    let read = stdin.lock().read_line(&mut line);
+    if echo_off {
+        set_echo(true);
+        eprintln!();
+    }
+    read?;
+    Ok(line.trim_end_matches(['\r', '\n']).to_string())
+}
+
+/// Turn terminal echo on or off, returning whether it worked.
+#[cfg(unix)]
+fn set_echo(on: bool) -> bool {
+    Command::new("stty")
+        .arg(if on { "echo" } else { "-echo" })
+        .stdin(Stdio::inherit())
+        .status()
+        .is_ok_and(|s| s.success())
+}
+
+#[cfg(not(unix))]
+fn set_echo(_on: bool) -> bool {
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_sources() {
+        assert!(matches!(
+            PassphraseSource::parse("env:COVEN_KEY_PASS").unwrap(),
+            PassphraseSource::Env(name) if name == "COVEN_KEY_PASS"
+        ));
+        assert!(matches!(
+            PassphraseSource::parse("cmd:pass show coven").unwrap(),
+            PassphraseSource::Command(cmd) if cmd == "pass show coven"
+        ));
+        assert!(matches!(
+            PassphraseSource::parse("pass:a:b").unwrap(),
+            PassphraseSource::Literal(p) if p == "a:b"
+        ));
+        assert!(matches!(
+            PassphraseSource::parse("prompt").unwrap(),
+            PassphraseSource::Prompt(_)
+        ));
+        for bad in ["", "env:", "cmd: ", "file:/tmp/pass", "hunter2"] {
+            assert!(PassphraseSource::parse(bad).is_err(), "{}", bad);
+        }
+    }
+
+    #[test]
+    fn test_debug_hides_literal() {
+        let source = PassphraseSource::Literal("hunter2".to_string());
+        assert_eq!(format!("{:?}", source), "Literal(..)");
+    }
+
+    #[test]
+    fn test_passphrase_from_each_source() {
+        let path = Path::new("/keys/agent_key");
+        let literal = PassphraseSource::Literal("one".to_string());
+        assert_eq!(literal.passphrase(path).unwrap(), "one");
+
+        std::env::set_var("COVEN_SSH_TEST_PASSPHRASE", "two");
+        let env = PassphraseSource::Env("COVEN_SSH_TEST_PASSPHRASE".to_string());
+        assert_eq!(env.passphrase(path).unwrap(), "two");
+        std::env::remove_var("COVEN_SSH_TEST_PASSPHRASE");
+        assert!(matches!(
+            env.passphrase(path),
+            Err(SshError::Passphrase(msg)) if msg.contains("not set")
+        ));
+
+        let prompt = PassphraseSource::Prompt(Arc::new(|p: &Path| {
+            Ok(format!("for {}", p.file_name().unwrap().to_string_lossy()))
+        }));
+        assert_eq!(prompt.passphrase(path).unwrap(), "for agent_key");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_command_source() {
+        let path = Path::new("/keys/agent_key");
+        let command = PassphraseSource::Command("printf 'three\\nignored\\n'".to_string());
+        assert_eq!(command.passphrase(path).unwrap(), "three");
+
+        let failing = PassphraseSource::Command("exit 3".to_string());
+        assert!(matches!(
+            failing.passphrase(path),
+            Err(SshError::Passphrase(_))
+        ));
+        let silent = PassphraseSource::Command("true".to_string());
+        assert!(matches!(
+            silent.passphrase(path),
+            Err(SshError::Passphrase(_))
+        ));
+    }
+}
@@ -0,0 +1,346 @@
+// ABOUTME: Key rotation: a new key generated beside the current one, and the proof tying them together.
+// ABOUTME: The old key signs the rotation request; a RotationProof shows the caller also holds the new key.
+
+use crate::credentials::{current_timestamp, sign_message, verify_signature};
+use crate::error::{Result, SshError};
+use crate::fingerprint::compute_fingerprint;
+use crate::key::{generate_key, generate_key_encrypted, load_key_with_passphrase};
+use crate::passphrase::PassphraseSource;
+use ssh_key::{PrivateKey, PublicKey};
+use std::path::{Path, PathBuf};
+
+/// Default time the gateway keeps accepting the old key after a rotation.
+pub const DEFAULT_ROTATION_GRACE_SECS: u32 = 24 * 60 * 60;
+
+/// Prefix of the message a [`RotationProof`] signs.
+const PROOF_PREFIX: &str = "coven-rotate-key";
+
+/// A rotation in progress: the current key and its successor, written next
+/// to it as `<name>_next` until [`KeyRotation::commit`] swaps them.
+#[derive(Debug)]
+pub struct KeyRotation {
+    /// Where the current key lives, and where the new key ends up.
+    pub key_path: PathBuf,
+    /// Where the new key waits until the rotation is committed.
+    pub next_path: PathBuf,
+    /// The key in use now; it signs the rotation request.
+    pub old_key: PrivateKey,
+    /// The key to switch to.
+    pub new_key: PrivateKey,
+}
+
+/// Start rotating the unencrypted key at `old_key_path`.
+///
+/// See [`rotate_key_with_passphrase`].
+///
+/// # Errors
+/// Returns an error if the current key can't be loaded or the new one can't
+/// be written.
+pub fn rotate_key(old_key_path: &Path) -> Result<KeyRotation> {
+    rotate_key_with_passphrase(old_key_path, None)
+}
+
+/// Start rotating the key at `old_key_path`: generate a new ed25519 key
+/// beside it, or pick up the one an interrupted rotation left there, so a
+/// retry registers the same key. An encrypted current key gets a successor
+/// encrypted with the same passphrase.
+///
+/// Nothing replaces the current key until [`KeyRotation::commit`].
+///
+/// # Errors
+/// Returns an error if either key can't be loaded or decrypted, or the new
+/// key can't be written.
+pub fn rotate_key_with_passphrase(
+    old_key_path: &Path,
+    passphrase: Option<&PassphraseSource>,
+) -> Result<KeyRotation> {
+    // Ask for the passphrase at most once, even though it unlocks two keys
+    let passphrase = match passphrase {
+        Some(source) if is_encrypted(old_key_path)? => Some(source.passphrase(old_key_path)?),
+        _ => None,
+    };
+    let unlock = passphrase.clone().map(PassphraseSource::Literal);
+    let old_key = load_key_with_passphrase(old_key_path, unlock.as_ref())?;
+
+    let next_path = sibling(old_key_path, "next");
+    let new_key = if next_path.exists() {
+        load_key_with_passphrase(&next_path, unlock.as_ref())?
+    } else {
+        match &passphrase {
+            Some(passphrase) => generate_key_encrypted(&next_path, passphrase)?,
+            None => generate_key(&next_path)?,
+        }
+    };
+
+    Ok(KeyRotation {
+        key_path: old_key_path.to_path_buf(),
+        next_path,
+        old_key,
+        new_key,
+    })
+}
+
+impl KeyRotation {
+    /// Proof for the gateway that the caller holds the new key.
+    ///
+    /// # Errors
+    /// Returns an error if either key can't be used.
+    pub fn proof(&self) -> Result<RotationProof> {
+        RotationProof::new(self.old_key.public_key(), &self.new_key)
+    }
+
+    /// Switch to the new key once the gateway knows it: the current key
+    /// moves to `<name>_old` and the new one takes its place, `.pub` files
+    /// included. Returns where the old key went.
+    ///
+    /// # Errors
+    /// Returns an error if a file can't be moved.
+    pub fn commit(self) -> Result<PathBuf> {
+        let old_path = sibling(&self.key_path, "old");
+        rename(&self.key_path, &old_path)?;
+        rename_if_exists(&public_path(&self.key_path), &public_path(&old_path))?;
+        rename(&self.next_path, &self.key_path)?;
+        rename_if_exists(&public_path(&self.next_path), &public_path(&self.key_path))?;
+        Ok(old_path)
+    }
+
+    /// Give up on the rotation, deleting the new key.
+    ///
+    /// # Errors
+    /// Returns an error if the new key's files can't be removed.
+    pub fn abort(self) -> Result<()> {
+        for path in [public_path(&self.next_path), self.next_path.clone()] {
+            match std::fs::remove_file(&path) {
+                Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+                    return Err(SshError::WriteKey { path, source: e });
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The new key's signature over the old key's fingerprint, so a gateway only
+/// registers keys the caller actually holds, and only for this rotation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RotationProof {
+    /// OpenSSH public key to switch to.
+    pub new_public_key: String,
+    /// Unix timestamp when the proof was made.
+    pub timestamp: i64,
+    /// Base64-encoded SSH signature of
+    /// `coven-rotate-key|{timestamp}|{old fingerprint}` by the new key.
+    pub signature: String,
+}
+
+impl RotationProof {
+    /// Sign a proof that `new_key` is to replace `old_key`.
+    ///
+    /// # Errors
+    /// Returns an error if either key type is unsupported or signing fails.
+    pub fn new(old_key: &PublicKey, new_key: &PrivateKey) -> Result<Self> {
+        let timestamp = current_timestamp();
+        let signature = sign_message(
+            new_key,
+            &proof_message(timestamp, &compute_fingerprint(old_key)?),
+        )?;
+        let new_public_key = new_key
+            .public_key()
+            .to_openssh()
+            .map_err(SshError::SerializeKey)?;
+        Ok(Self {
+            new_public_key,
+            timestamp,
+            signature,
+        })
+    }
+
+    /// Check that the proof was signed by its new key, for the key with
+    /// `old_fingerprint`, within `max_age_secs`. Returns the new key.
+    ///
+    /// # Errors
+    /// Returns `SshError::InvalidSignature` for a stale timestamp, an
+    /// unparseable key or a bad signature.
+    pub fn verify(&self, old_fingerprint: &str, max_age_secs: i64) -> Result<PublicKey> {
+        let age = (current_timestamp() - self.timestamp).abs();
+        if age > max_age_secs {
+            return Err(SshError::InvalidSignature(format!(
+                "rotation proof is {} seconds from now, more than {} allowed",
+                age, max_age_secs
+            )));
+        }
+        let new_key = PublicKey::from_openssh(self.new_public_key.trim())
+            .map_err(|e| SshError::InvalidSignature(format!("bad public key: {}", e)))?;
+        verify_signature(
+            &new_key,
+            &proof_message(self.timestamp, old_fingerprint),
+            &self.signature,
+        )?;
+        Ok(new_key)
+    }
+}
+
+fn proof_message(timestamp: i64, old_fingerprint: &str) -> String {
+    format!("{}|{}|{}", PROOF_PREFIX, timestamp, old_fingerprint)
+}
+
+fn is_encrypted(key_path: &Path) -> Result<bool> {
+    let key_data = std::fs::read_to_string(key_path).map_err(|e| SshError::ReadKey {
+        path: key_path.to_path_buf(),
+        source: e,
+    })?;
+    let key = PrivateKey::from_openssh(&key_data).map_err(|e| SshError::ParseKey {
+        path: key_path.to_path_buf(),
+        source: e,
+    })?;
+    Ok(key.is_encrypted())
+}
+
+/// `agent_key` → `agent_key_<tag>`, keeping any extension, so the public key
+/// (`with_extension("pub")`) doesn't collide with the current key's.
+fn sibling(key_path: &Path, tag: &str) -> PathBuf {
+    let mut name = key_path.file_stem().unwrap_or_default().to_os_string();
+    name.push("_");
+    name.push(tag);
+    if let Some(extension) = key_path.extension() {
+        name.push(".");
+        name.push(extension);
+    }
+    key_path.with_file_name(name)
+}
+
+fn public_path(key_path: &Path) -> PathBuf {
+    key_path.with_extension("pub")
+}
+
+fn rename(from: &Path, to: &Path) -> Result<()> {
+    std::fs::rename(from, to).map_err(|e| SshError::WriteKey {
+        path: to.to_path_buf(),
+        source: e,
+    })
+}
+
+fn rename_if_exists(from: &Path, to: &Path) -> Result<()> {
+    if from.exists() {
+        rename(from, to)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::load_key;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_rotation_commit_swaps_keys() {
+        let dir = TempDir::new().unwrap();
+        let key_path = dir.path().join("agent_key");
+        let old_key = generate_key(&key_path).unwrap();
+
+        let rotation = rotate_key(&key_path).unwrap();
+        assert_eq!(rotation.next_path, dir.path().join("agent_key_next"));
+        assert!(dir.path().join("agent_key_next.pub").exists());
+        assert_eq!(
+            rotation.old_key.public_key().key_data(),
+            old_key.public_key().key_data()
+        );
+        let new_public = rotation.new_key.public_key().clone();
+        // The current key is untouched until commit
+        assert_eq!(
+            load_key(&key_path).unwrap().public_key().key_data(),
+            old_key.public_key().key_data()
+        );
+
+        let old_path = rotation.commit().unwrap();
+        assert_eq!(old_path, dir.path().join("agent_key_old"));
+        assert_eq!(
+            load_key(&key_path).unwrap().public_key().key_data(),
+            new_public.key_data()
+        );
+        assert_eq!(
+            load_key(&old_path).unwrap().public_key().key_data(),
+            old_key.public_key().key_data()
+        );
+        let pub_key = std::fs::read_to_string(dir.path().join("agent_key.pub")).unwrap();
+        assert_eq!(pub_key.trim(), new_public.to_openssh().unwrap());
+        assert!(dir.path().join("agent_key_old.pub").exists());
+        assert!(!dir.path().join("agent_key_next").exists());
+    }
+
+    #[test]
+    fn test_interrupted_rotation_reuses_next_key_and_abort_removes_it() {
+        let dir = TempDir::new().unwrap();
+        let key_path = dir.path().join("client_key");
+        generate_key(&key_path).unwrap();
+
+        let first = rotate_key(&key_path).unwrap();
+        let next = first.new_key.public_key().clone();
+        let again = rotate_key(&key_path).unwrap();
+        assert_eq!(again.new_key.public_key().key_data(), next.key_data());
+
+        again.abort().unwrap();
+        assert!(!dir.path().join("client_key_next").exists());
+        assert!(!dir.path().join("client_key_next.pub").exists());
+        assert!(key_path.exists());
+    }
+
+    #[test]
+    fn test_encrypted_key_rotates_to_encrypted_key() {
+        let dir = TempDir::new().unwrap();
+        let key_path = dir.path().join("agent_key");
+        generate_key_encrypted(&key_path, "hunter2").unwrap();
+        let source = PassphraseSource::Literal("hunter2".to_string());
+
+        let rotation = rotate_key_with_passphrase(&key_path, Some(&source)).unwrap();
+        assert!(matches!(
+            load_key(&rotation.next_path),
+            Err(SshError::PassphraseRequired { .. })
+        ));
+        assert!(matches!(
+            rotate_key(&key_path),
+            Err(SshError::PassphraseRequired { .. })
+        ));
+    }
+
+    #[test]
+    fn test_rotation_proof() {
+        let dir = TempDir::new().unwrap();
+        let key_path = dir.path().join("agent_key");
+        generate_key(&key_path).unwrap();
+        let rotation = rotate_key(&key_path).unwrap();
+        let old_fingerprint = compute_fingerprint(rotation.old_key.public_key()).unwrap();
+
+        let proof = rotation.proof().unwrap();
+        let new_key = proof.verify(&old_fingerprint, 300).unwrap();
+        assert_eq!(new_key.key_data(), rotation.new_key.public_key().key_data());
+
+        // Bound to the old key it was made for
+        let other = compute_fingerprint(rotation.new_key.public_key()).unwrap();
+        assert!(matches!(
+            proof.verify(&other, 300),
+            Err(SshError::InvalidSignature(_))
+        ));
+
+        let stale = RotationProof {
+            timestamp: proof.timestamp - 600,
+            ..proof.clone()
+        };
+        let err = stale.verify(&old_fingerprint, 300).unwrap_err();
+        assert!(err.to_string().contains("more than 300 allowed"));
+    }
+
+    #[test]
+    fn test_sibling_keeps_extension() {
+        assert_eq!(
+            sibling(Path::new("/keys/agent_key"), "next"),
+            Path::new("/keys/agent_key_next")
+        );
+        assert_eq!(
+            sibling(Path::new("/keys/id.key"), "old"),
+            Path::new("/keys/id_old.key")
+        );
+    }
+}
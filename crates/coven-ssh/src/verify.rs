@@ -0,0 +1,337 @@
+// ABOUTME: Server-side verification of SSH-signed gRPC requests.
+// ABOUTME: Tolerates bounded clock skew and rejects replayed nonces with a bounded TTL cache.
+
+use crate::credentials::{current_timestamp, verify_signature, SshAuthCredentials};
+use crate::error::{Result, SshError};
+use ssh_key::PublicKey;
+use std::collections::{BTreeSet, HashMap};
+use std::sync::Mutex;
+use std::time::Duration;
+use tonic::metadata::MetadataMap;
+
+/// How far a request's timestamp may be from the server's clock, either way
+pub const DEFAULT_SKEW_TOLERANCE: Duration = Duration::from_secs(120);
+
+/// Nonces a `NonceCache` remembers by default
+pub const DEFAULT_NONCE_CACHE_CAPACITY: usize = 100_000;
+
+/// Nonces of recently verified requests, each kept until its request's
+/// timestamp falls outside the skew tolerance and the request would be
+/// rejected as expired anyway.
+///
+/// The cache holds at most `capacity` nonces. Only expired nonces make
+/// room: evicting a live one would let it be replayed, so while the cache
+/// is full of them new requests are refused. Size it for the request rate
+/// times the window (twice the skew tolerance).
+#[derive(Debug)]
+pub struct NonceCache {
+    capacity: usize,
+    entries: Mutex<NonceEntries>,
+}
+
+#[derive(Debug, Default)]
+struct NonceEntries {
+    /// Expiry of each nonce
+    expiry: HashMap<String, i64>,
+    /// The same entries, soonest to expire first
+    by_expiry: BTreeSet<(i64, String)>,
+}
+
+impl NonceEntries {
+    fn remove_first(&mut self) {
+        if let Some((_, nonce)) = self.by_expiry.pop_first() {
+            self.expiry.remove(&nonce);
+        }
+    }
+}
+
+impl NonceCache {
+    /// A cache remembering up to `capacity` nonces (at least one)
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            entries: Mutex::new(NonceEntries::default()),
+        }
+    }
+
+    /// Nonces currently remembered, including any expired ones not yet
+    /// cleaned up
+    pub fn len(&self) -> usize {
+        self.entries.lock().unwrap().expiry.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Record `nonce` as used through `expires_at` (Unix seconds).
+    ///
+    /// # Errors
+    /// - `SshError::Replayed` if it was already recorded and hasn't expired
+    ///   by `now`
+    /// - `SshError::NonceCacheFull` if every remembered nonce is still live
+    pub fn insert(&self, nonce: &str, expires_at: i64, now: i64) -> Result<()> {
+        let mut entries = self.entries.lock().unwrap();
+        while entries
+            .by_expiry
+            .first()
+            .is_some_and(|(expiry, _)| *expiry < now)
+        {
+            entries.remove_first();
+        }
+        if entries.expiry.contains_key(nonce) {
+            return Err(SshError::Replayed);
+        }
+        if entries.expiry.len() >= self.capacity {
+            return Err(SshError::NonceCacheFull);
+        }
+        entries.expiry.insert(nonce.to_string(), expires_at);
+        entries.by_expiry.insert((expires_at, nonce.to_string()));
+        Ok(())
+    }
+}
+
+impl Default for NonceCache {
+    fn default() -> Self {
+        Self::new(DEFAULT_NONCE_CACHE_CAPACITY)
+    }
+}
+
+/// Verify the SSH signature headers of a request, for servers.
+///
+/// Checks, in order, that the timestamp is within `skew_tolerance` of the
+/// server's clock, that the request is signed by `pubkey` (or by the key it
+/// carries, when `pubkey` is None), and that its nonce hasn't been seen
+/// within the window. Returns the signing key, or None for a request that
+/// isn't signed at all.
+///
+/// Every request must be signed afresh: reusing credentials, even within
+/// the window, is a replay.
+///
+/// # Errors
+/// - `SshError::InvalidMetadata` for missing or malformed headers
+/// - `SshError::Expired` for a timestamp outside the tolerance
+/// - `SshError::InvalidSignature` for an unparseable key, a key other than
+///   `pubkey`, or a bad signature
+/// - `SshError::Replayed` for a nonce already used
+/// - `SshError::NonceCacheFull` when too many requests arrived within the
+///   window to remember them all
+pub fn verify_request(
+    metadata: &MetadataMap,
+    pubkey: Option<&PublicKey>,
+    skew_tolerance: Duration,
+    nonce_cache: &NonceCache,
+) -> Result<Option<PublicKey>> {
+    let Some(credentials) = SshAuthCredentials::from_metadata(metadata)? else {
+        return Ok(None);
+    };
+    verify_at(
+        &credentials,
+        pubkey,
+        skew_tolerance,
+        nonce_cache,
+        current_timestamp(),
+    )
+    .map(Some)
+}
+
+/// `verify_request` for parsed credentials, with the server's clock at `now`
+fn verify_at(
+    credentials: &SshAuthCredentials,
+    pubkey: Option<&PublicKey>,
+    skew_tolerance: Duration,
+    nonce_cache: &NonceCache,
+    now: i64,
+) -> Result<PublicKey> {
+    let tolerance_secs = i64::try_from(skew_tolerance.as_secs()).unwrap_or(i64::MAX);
+    let skew_secs = now.saturating_sub(credentials.timestamp);
+    if skew_secs.saturating_abs() > tolerance_secs {
+        return Err(SshError::Expired {
+            skew_secs,
+            tolerance_secs,
+        });
+    }
+
+    let public_key = PublicKey::from_openssh(&credentials.pubkey)
+        .map_err(|e| SshError::InvalidSignature(format!("bad public key: {}", e)))?;
+    if pubkey.is_some_and(|expected| expected.key_data() != public_key.key_data()) {
+        return Err(SshError::InvalidSignature(
+            "signed by a different key".to_string(),
+        ));
+    }
+    verify_signature(
+        &public_key,
+        &format!("{}|{}", credentials.timestamp, credentials.nonce),
+        &credentials.signature,
+    )?;
+
+    // Only signed nonces are recorded, so nobody can burn another's
+    let expires_at = credentials.timestamp.saturating_add(tolerance_secs);
+    nonce_cache.insert(&credentials.nonce, expires_at, now)?;
+    Ok(public_key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::credentials::{generate_nonce, sign_message};
+    use ssh_key::{Algorithm, PrivateKey};
+
+    fn key() -> PrivateKey {
+        PrivateKey::random(&mut rand::thread_rng(), Algorithm::Ed25519).unwrap()
+    }
+
+    /// Credentials signed by `key` as if at `timestamp`
+    fn signed_at(key: &PrivateKey, timestamp: i64) -> SshAuthCredentials {
+        let nonce = generate_nonce();
+        SshAuthCredentials {
+            pubkey: key.public_key().to_openssh().unwrap(),
+            signature: sign_message(key, &format!("{}|{}", timestamp, nonce)).unwrap(),
+            timestamp,
+            nonce,
+        }
+    }
+
+    #[test]
+    fn test_verify_request_accepts_once_then_rejects_replay() {
+        let key = key();
+        let mut request = tonic::Request::new(());
+        SshAuthCredentials::new(&key)
+            .unwrap()
+            .apply_to_request(&mut request)
+            .unwrap();
+        let cache = NonceCache::default();
+
+        let verified =
+            verify_request(request.metadata(), None, DEFAULT_SKEW_TOLERANCE, &cache).unwrap();
+        assert_eq!(verified.unwrap().key_data(), key.public_key().key_data());
+
+        let err = verify_request(
+            request.metadata(),
+            Some(key.public_key()),
+            DEFAULT_SKEW_TOLERANCE,
+            &cache,
+        )
+        .unwrap_err();
+        assert!(matches!(err, SshError::Replayed));
+
+        // Unsigned requests aren't verified at all
+        let unsigned =
+            verify_request(&MetadataMap::new(), None, DEFAULT_SKEW_TOLERANCE, &cache).unwrap();
+        assert!(unsigned.is_none());
+    }
+
+    #[test]
+    fn test_skew_boundaries() {
+        let key = key();
+        let cache = NonceCache::default();
+        let now = 1_700_000_000;
+        let tolerance = Duration::from_secs(120);
+
+        for timestamp in [now - 120, now + 120, now] {
+            let creds = signed_at(&key, timestamp);
+            assert!(verify_at(&creds, None, tolerance, &cache, now).is_ok());
+        }
+        for (timestamp, skew) in [(now - 121, 121), (now + 121, -121)] {
+            let creds = signed_at(&key, timestamp);
+            match verify_at(&creds, None, tolerance, &cache, now) {
+                Err(SshError::Expired {
+                    skew_secs,
+                    tolerance_secs,
+                }) => {
+                    assert_eq!(skew_secs, skew);
+                    assert_eq!(tolerance_secs, 120);
+                }
+                other => panic!("expected Expired, got {:?}", other),
+            }
+        }
+    }
+
+    #[test]
+    fn test_bad_signature_and_wrong_key() {
+        let key = key();
+        let cache = NonceCache::default();
+        let now = current_timestamp();
+
+        let mut creds = signed_at(&key, now);
+        creds.nonce = generate_nonce();
+        let err = verify_at(&creds, None, DEFAULT_SKEW_TOLERANCE, &cache, now).unwrap_err();
+        assert!(matches!(err, SshError::InvalidSignature(_)));
+        // A rejected request doesn't use up its nonce
+        assert!(cache.is_empty());
+
+        let creds = signed_at(&key, now);
+        let err = verify_at(
+            &creds,
+            Some(self::key().public_key()),
+            DEFAULT_SKEW_TOLERANCE,
+            &cache,
+            now,
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("different key"));
+    }
+
+    #[test]
+    fn test_replay_after_window_is_expired_not_replayed() {
+        let key = key();
+        let cache = NonceCache::default();
+        let now = 1_700_000_000;
+        let tolerance = Duration::from_secs(120);
+        let creds = signed_at(&key, now);
+
+        assert!(verify_at(&creds, None, tolerance, &cache, now).is_ok());
+        assert!(matches!(
+            verify_at(&creds, None, tolerance, &cache, now + 120),
+            Err(SshError::Replayed)
+        ));
+        assert!(matches!(
+            verify_at(&creds, None, tolerance, &cache, now + 121),
+            Err(SshError::Expired { .. })
+        ));
+    }
+
+    #[test]
+    fn test_nonce_cache_expiry() {
+        let cache = NonceCache::new(10);
+        assert!(cache.insert("a", 100, 0).is_ok());
+        assert!(cache.insert("b", 200, 0).is_ok());
+        assert!(matches!(
+            cache.insert("a", 100, 50),
+            Err(SshError::Replayed)
+        ));
+
+        // Expired entries are dropped on the next insert
+        assert!(cache.insert("a", 100, 100).is_err());
+        assert!(cache.insert("c", 300, 101).is_ok());
+        assert_eq!(cache.len(), 2);
+        assert!(cache.insert("a", 400, 150).is_ok());
+    }
+
+    #[test]
+    fn test_full_nonce_cache_refuses_rather_than_forgets() {
+        let cache = NonceCache::new(2);
+        assert!(cache.insert("late", 300, 0).is_ok());
+        assert!(cache.insert("soon", 100, 0).is_ok());
+
+        // Full of live nonces: nothing is evicted, so none can be replayed
+        assert!(matches!(
+            cache.insert("new", 200, 0),
+            Err(SshError::NonceCacheFull)
+        ));
+        assert_eq!(cache.len(), 2);
+        assert!(matches!(
+            cache.insert("soon", 100, 50),
+            Err(SshError::Replayed)
+        ));
+
+        // Once one expires there's room again
+        assert!(cache.insert("new", 200, 101).is_ok());
+        assert!(matches!(
+            cache.insert("late", 300, 101),
+            Err(SshError::Replayed)
+        ));
+
+        assert_eq!(NonceCache::new(0).capacity, 1);
+    }
+}
@@ -0,0 +1,136 @@
+// ABOUTME: Tests signing, verification, and fingerprints for each supported key type.
+// ABOUTME: Uses ssh-keygen fixture keys and signatures captured from Go's ssh package.
+
+use coven_ssh::{
+    compute_fingerprint, sign_message, verify_signature, PrivateKey, SshAuthCredentials, SshError,
+    MAX_SIGNATURE_AGE_SECS,
+};
+
+const MESSAGE: &str = "1700000000|00112233445566778899aabbccddeeff";
+
+fn fixture(name: &str) -> PrivateKey {
+    let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures")
+        .join(name);
+    coven_ssh::load_key(&path).unwrap_or_else(|e| panic!("loading {}: {}", name, e))
+}
+
+#[test]
+fn test_fingerprints_match_go() {
+    for (name, expected) in [
+        (
+            "ed25519",
+            "627a79254a1736910ab3eead14d65079ce04f0e5738e987adf637d41234a139d",
+        ),
+        (
+            "rsa",
+            "52e14866207009a69d7706510dfe044ae3ecd31fbb52510a9f251d1cdfe132cc",
+        ),
+        (
+            "ecdsa_p256",
+            "3fd8e61b60ada1f0a955b2b6cb0168bcbc6b6220aa95369fa968611039f0218e",
+        ),
+    ] {
+        let key = fixture(name);
+        assert_eq!(
+            compute_fingerprint(key.public_key()).unwrap(),
+            expected,
+            "{}",
+            name
+        );
+    }
+}
+
+#[test]
+fn test_deterministic_signatures_match_go() {
+    // Ed25519 and RSA PKCS#1 v1.5 signatures are deterministic, so ours must
+    // be byte-for-byte what Go produces
+    let ed25519 = fixture("ed25519");
+    assert_eq!(
+        sign_message(&ed25519, MESSAGE).unwrap(),
+        "AAAAC3NzaC1lZDI1NTE5AAAAQEqxIZtWaR+mv+aMyAKMxbcZq25VXlKXW7FstNGNamxb2DTRpkwWONLgIc8iBP8BhjmMEv+UGIzoiO7C9Zbziwk="
+    );
+
+    let rsa = fixture("rsa");
+    assert_eq!(
+        sign_message(&rsa, MESSAGE).unwrap(),
+        "AAAADHJzYS1zaGEyLTI1NgAAAQAx3mZWPAr8OfbxbW8kLKG0SJm0Do7x9X9meFrSfUgzDoQob7DOgyuZvhXH0JUY8nGqQshqFdwkqcv3mwmdR9B50F6L1F8+TSFMWNvqcV3pwN98P7b6RTZcpnS5VkPvcoidsqgXQKKPtk2dy50fzregrtrOfxyd5AyWJAe7B3Vx5z7mrDtMpIwz5p73M+IY0UvtbIsm+3IsxfuE78JIMsFqMU82L0vre/p8toHY+R7visxZmFDz85J31J/sLwDkBBbSC9jKxwVl7yWUaesfzjzNClmZ96mocm6Uh0G4EDxmIJy/wsJLSz6ccls2+lSkoAG/0wX/sG4LeXI5OMIxcx++"
+    );
+}
+
+#[test]
+fn test_go_signatures_verify() {
+    let rsa = fixture("rsa");
+    let rsa_sha512 = "AAAADHJzYS1zaGEyLTUxMgAAAQBr/OnXx2pJtuQeLfdY2+nbCuB1V8nvfPA5lzGmEukwBuJNWXcsej+c+Pf3UoxY4ew0z7+m8IChjEPLxjDnAk6v22S7oIBMSUx7kaRxFOkKcFWat84mf9Zd/LnByTusdMxNJ6zNyR9XP5MT+t9ib10KGRcYlCHng4yUjtuxLv/fn1SXQPoAK/btXxtrjModQ/F31Oux9CNAVKon3z1mkkQkpL8eo8mZuXfdvMvgI5qR2qTfQXaMGsH62Pb7koguZTCh3riT/z1D2+xbtMSKyF+yKijmdl1LfiiUzwTwukYQ3dwt2kIM8VXKMcwVnkAI7nxCD3VwANgch3X5xQ6cpeAD";
+    verify_signature(rsa.public_key(), MESSAGE, rsa_sha512).unwrap();
+
+    let p256 = fixture("ecdsa_p256");
+    let ecdsa = "AAAAE2VjZHNhLXNoYTItbmlzdHAyNTYAAABJAAAAIQDfWcAyLzQHhmRvXxvBjLUMhMUuqU5MXk9UJkbATl74jgAAACBdcgjp2BKn+qTvmnZuIEerQNrkFJcuM+IFmCKmNT6I1g==";
+    verify_signature(p256.public_key(), MESSAGE, ecdsa).unwrap();
+
+    // Neither verifies another message or under another key
+    assert!(matches!(
+        verify_signature(p256.public_key(), "1700000000|other", ecdsa),
+        Err(SshError::InvalidSignature(_))
+    ));
+    assert!(matches!(
+        verify_signature(p256.public_key(), MESSAGE, rsa_sha512),
+        Err(SshError::InvalidSignature(_))
+    ));
+}
+
+#[test]
+fn test_each_key_type_round_trips_through_credentials() {
+    for name in ["ed25519", "rsa", "ecdsa_p256"] {
+        let key = fixture(name);
+        let signature = sign_message(&key, MESSAGE).unwrap();
+        verify_signature(key.public_key(), MESSAGE, &signature)
+            .unwrap_or_else(|e| panic!("{}: {}", name, e));
+
+        let mut request = tonic::Request::new(());
+        SshAuthCredentials::new(&key)
+            .unwrap()
+            .apply_to_request(&mut request)
+            .unwrap();
+        let received = SshAuthCredentials::from_metadata(request.metadata())
+            .unwrap()
+            .unwrap();
+        let verified = received.verify(MAX_SIGNATURE_AGE_SECS).unwrap();
+        assert_eq!(verified.key_data(), key.public_key().key_data(), "{}", name);
+    }
+}
+
+#[test]
+fn test_sha1_rsa_signatures_are_refused() {
+    use base64::Engine;
+
+    // Same blob, relabelled as a legacy ssh-rsa (SHA-1) signature
+    let rsa = fixture("rsa");
+    let wire = base64::engine::general_purpose::STANDARD
+        .decode(sign_message(&rsa, MESSAGE).unwrap())
+        .unwrap();
+    let blob = &wire[4 + "rsa-sha2-256".len()..];
+    let mut relabelled = Vec::new();
+    relabelled.extend_from_slice(&7u32.to_be_bytes());
+    relabelled.extend_from_slice(b"ssh-rsa");
+    relabelled.extend_from_slice(blob);
+    let relabelled = base64::engine::general_purpose::STANDARD.encode(relabelled);
+
+    assert!(matches!(
+        verify_signature(rsa.public_key(), MESSAGE, &relabelled),
+        Err(SshError::InvalidSignature(_))
+    ));
+}
+
+#[test]
+fn test_p384_is_unsupported() {
+    let p384 = fixture("ecdsa_p384");
+    assert!(matches!(
+        sign_message(&p384, MESSAGE),
+        Err(SshError::UnsupportedKeyType(_))
+    ));
+    assert!(matches!(
+        SshAuthCredentials::new(&p384),
+        Err(SshError::UnsupportedKeyType(_))
+    ));
+}
@@ -30,6 +30,33 @@ pub struct AcpConfig {
     /// Extra CLI arguments to pass to the ACP binary
     #[serde(default)]
     pub extra_args: Vec<String>,
+    /// Environment variables set for the ACP binary on top of the inherited
+    /// environment. Later entries win over earlier ones, and all of them win
+    /// over inherited variables and `model`.
+    #[serde(default)]
+    pub extra_env: Vec<(String, String)>,
+    /// Model for the agent, exported to the binary as `ANTHROPIC_MODEL`
+    /// (which claude-code-acp reads). Agents that take a model flag instead
+    /// can get one through `extra_args`; the binary then decides which of
+    /// the two wins, since arguments and environment are passed as given.
+    #[serde(default)]
+    pub model: Option<String>,
+}
+
+/// Variable the ACP binary reads its model from
+const MODEL_ENV_VAR: &str = "ANTHROPIC_MODEL";
+
+impl AcpConfig {
+    /// Environment for the ACP binary: `inherited`, then `model`, then
+    /// `extra_env`, each overriding what came before.
+    fn spawn_env(&self, inherited: HashMap<String, String>) -> HashMap<String, String> {
+        let mut env = inherited;
+        if let Some(model) = &self.model {
+            env.insert(MODEL_ENV_VAR.to_string(), model.clone());
+        }
+        env.extend(self.extra_env.iter().cloned());
+        env
+    }
 }
 
 fn default_timeout() -> u64 {
@@ -691,7 +718,7 @@ fn run_persistent_worker(config: AcpConfig, mut cmd_rx: mpsc::Receiver<WorkerCom
         let local = tokio::task::LocalSet::new();
         local
             .run_until(async {
-                let env_vars: HashMap<String, String> = std::env::vars().collect();
+                let env_vars = config.spawn_env(std::env::vars().collect());
 
                 // Create a dummy channel for initial spawn - will be replaced on first prompt
                 let (dummy_tx, _dummy_rx) = mpsc::channel(1);
@@ -866,6 +893,8 @@ impl AcpBackend {
             timeout_secs: default_timeout(),
             working_dir,
             extra_args: vec![],
+            extra_env: vec![],
+            model: None,
         })
     }
 
@@ -919,3 +948,50 @@ impl Backend for AcpBackend {
         Ok(stream.boxed())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> AcpConfig {
+        AcpConfig {
+            binary: "claude-code-acp".to_string(),
+            timeout_secs: default_timeout(),
+            working_dir: PathBuf::from("."),
+            extra_args: vec![],
+            extra_env: vec![],
+            model: None,
+        }
+    }
+
+    #[test]
+    fn test_spawn_env_merges_with_inherited() {
+        let inherited = HashMap::from([
+            ("PATH".to_string(), "/usr/bin".to_string()),
+            ("ANTHROPIC_MODEL".to_string(), "inherited".to_string()),
+        ]);
+
+        let env = config().spawn_env(inherited.clone());
+        assert_eq!(env, inherited);
+
+        let mut with_model = config();
+        with_model.model = Some("claude-opus".to_string());
+        with_model.extra_env = vec![("API_KEY".to_string(), "secret".to_string())];
+        let env = with_model.spawn_env(inherited);
+        assert_eq!(env["PATH"], "/usr/bin");
+        assert_eq!(env["ANTHROPIC_MODEL"], "claude-opus");
+        assert_eq!(env["API_KEY"], "secret");
+    }
+
+    #[test]
+    fn test_extra_env_wins_over_model() {
+        let mut config = config();
+        config.model = Some("from-model".to_string());
+        config.extra_env = vec![
+            ("ANTHROPIC_MODEL".to_string(), "first".to_string()),
+            ("ANTHROPIC_MODEL".to_string(), "last".to_string()),
+        ];
+        let env = config.spawn_env(HashMap::new());
+        assert_eq!(env["ANTHROPIC_MODEL"], "last");
+    }
+}
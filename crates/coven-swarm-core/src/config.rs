@@ -1,9 +1,16 @@
 // ABOUTME: Configuration for coven-swarm supervisor and agents.
 // ABOUTME: Loaded from TOML file with sensible defaults.
 
-use anyhow::{Context, Result};
+use anyhow::{anyhow, Context, Result};
+use coven_grpc::{KeepAliveConfig, KeepAliveSettings};
 use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+use std::fmt;
 use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+/// Per-workspace agent config that may carry a `backend` override
+pub const WORKSPACE_CONFIG: &str = ".coven/agent.toml";
 
 #[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
 #[serde(rename_all = "lowercase")]
@@ -14,6 +21,45 @@ pub enum BackendType {
     Direct,
 }
 
+impl BackendType {
+    /// Names accepted for a backend, as listed in error messages
+    pub const NAMES: &'static str = "acp, mux, direct";
+}
+
+impl FromStr for BackendType {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "acp" => Ok(BackendType::Acp),
+            "mux" => Ok(BackendType::Mux),
+            // coven-agent calls the Claude CLI subprocess backend "cli"
+            "direct" | "cli" => Ok(BackendType::Direct),
+            other => anyhow::bail!(
+                "unknown backend '{}' (expected one of: {})",
+                other,
+                Self::NAMES
+            ),
+        }
+    }
+}
+
+impl fmt::Display for BackendType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            BackendType::Acp => "acp",
+            BackendType::Mux => "mux",
+            BackendType::Direct => "direct",
+        })
+    }
+}
+
+/// The only key swarm reads from a workspace's `.coven/agent.toml`
+#[derive(Debug, Default, Deserialize)]
+struct WorkspaceConfig {
+    backend: Option<String>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Config {
     /// Gateway gRPC URL (e.g., "http://coven.example.com:50051")
@@ -24,18 +70,61 @@ pub struct Config {
     /// Agent name prefix (e.g., "home" -> "home_research")
     pub prefix: String,
 
-    /// Directory containing workspaces
+    /// Directory containing workspaces (supports `~`, `$VAR` and `${VAR}`)
     pub working_directory: String,
 
     /// Default backend for agents
     #[serde(default)]
     pub default_backend: BackendType,
 
+    /// Backend overrides by workspace name (e.g., `research = "mux"`).
+    /// Takes precedence over `backend` in the workspace's `.coven/agent.toml`.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub workspace_backends: BTreeMap<String, String>,
+
+    /// Workspaces each workspace waits for before it starts (e.g.,
+    /// `web = ["db"]`): the supervisor starts a workspace's agent once the
+    /// agents it depends on have registered with the gateway. Dispatch
+    /// always starts first. Cycles are rejected on load.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub depends_on: BTreeMap<String, Vec<String>>,
+
     /// ACP binary path (for acp backend)
     #[serde(default = "default_acp_binary")]
     pub acp_binary: String,
 
-    /// Global soul.md for all swarm agents (e.g., ~/.config/coven/soul.md)
+    /// Model for ACP agents (exported as `ANTHROPIC_MODEL`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub acp_model: Option<String>,
+
+    /// Claude CLI subprocesses each `direct` agent runs at once (unset =
+    /// coven-core's default)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cli_pool_size: Option<usize>,
+
+    /// Claude CLI crashes each `direct` agent tolerates within
+    /// `cli_restart_window_secs` before it stops respawning the CLI (unset =
+    /// coven-core's default)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cli_max_restarts: Option<u32>,
+
+    /// How far back Claude CLI crashes count against `cli_max_restarts`, in
+    /// seconds (unset = coven-core's default)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cli_restart_window_secs: Option<u64>,
+
+    /// Where the passphrase for an encrypted swarm key comes from, in the
+    /// forms of `coven_ssh::PassphraseSource::parse` (e.g. "cmd:pass show
+    /// coven/swarm"); every agent the supervisor starts unlocks it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub key_passphrase: Option<String>,
+
+    /// Extra environment variables for ACP agents, merged over the
+    /// supervisor's own environment
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub acp_env: BTreeMap<String, String>,
+
+    /// Global soul.md for all swarm agents (e.g., ${XDG_CONFIG_HOME}/coven/soul.md)
     #[serde(default)]
     pub global_soul_path: Option<String>,
 
@@ -46,6 +135,124 @@ pub struct Config {
     /// Filenames to search for soul.md in working directories
     #[serde(default = "default_soul_files")]
     pub soul_files: Vec<String>,
+
+    /// Reply sent straight away to a message that has to wait while the
+    /// agent handles another, with `{position}` replaced by its place in
+    /// line (e.g. "Busy with another request; yours is queued (position
+    /// {position})"). Unset = queued messages wait silently.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub busy_reply: Option<String>,
+
+    /// HTTP/2 keep-alive from agents to the gateway, as a `[keepalive]`
+    /// table (unset = coven-grpc defaults)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub keepalive: Option<KeepAliveSettings>,
+
+    /// Where each agent's output is written besides the supervisor's
+    /// stderr or TUI, as an `[agent_logs]` table (unset = defaults)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub agent_logs: Option<AgentLogSettings>,
+
+    /// Automatic git operations in workspaces that are git repos, as a
+    /// `[git]` table (unset = none)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub git: Option<GitAutomation>,
+
+    /// Supervisor TUI colors (unset = default). NO_COLOR, `--no-color`
+    /// and limited terminals fall back to plain.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub theme: Option<TuiTheme>,
+}
+
+/// Color scheme of the supervisor TUI
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TuiTheme {
+    /// The terminal's own ANSI colors
+    #[default]
+    Default,
+    /// coven-agent's neo-terminal palette; needs a truecolor terminal and
+    /// falls back to `default` elsewhere
+    Neo,
+    /// No colors, only bold and plain text
+    Plain,
+}
+
+/// Per-workspace agent log files, `<swarm dir>/<workspace>.log`, as written
+/// in the swarm config:
+///
+/// ```toml
+/// [agent_logs]
+/// max_size_mb = 10
+/// keep = 2
+/// ```
+///
+/// `enabled = false` leaves agent output on the supervisor's stderr or TUI
+/// only.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AgentLogSettings {
+    /// Write agent logs at all
+    pub enabled: bool,
+    /// Rotate a log once it would grow past this many megabytes
+    pub max_size_mb: u64,
+    /// Rotated logs to keep, as `<workspace>.log.1` (newest) onwards
+    pub keep: usize,
+}
+
+impl Default for AgentLogSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            max_size_mb: 10,
+            keep: 2,
+        }
+    }
+}
+
+/// Git operations swarm agents run in their workspace, all off unless
+/// turned on:
+///
+/// ```toml
+/// [git]
+/// auto_pull = true
+/// auto_commit = true
+/// branch = "coven/wip/{workspace}"
+/// commit_interval_secs = 900
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct GitAutomation {
+    /// Fast-forward from the upstream branch when the agent starts
+    pub auto_pull: bool,
+    /// Commit uncommitted work to `branch` while the agent is idle,
+    /// leaving the checked-out branch, index and files as they are
+    pub auto_commit: bool,
+    /// Branch checkpoints go to; `{workspace}` and `{branch}` (the
+    /// checked-out branch) are filled in
+    pub branch: String,
+    /// How often to check for work to commit
+    pub commit_interval_secs: u64,
+}
+
+impl Default for GitAutomation {
+    fn default() -> Self {
+        Self {
+            auto_pull: false,
+            auto_commit: false,
+            branch: "coven/wip/{workspace}".to_string(),
+            commit_interval_secs: 15 * 60,
+        }
+    }
+}
+
+impl GitAutomation {
+    /// `branch` with `workspace` and the checked-out `branch` filled in
+    pub fn branch_for(&self, workspace: &str, branch: &str) -> String {
+        self.branch
+            .replace("{workspace}", workspace)
+            .replace("{branch}", branch)
+    }
 }
 
 fn default_acp_binary() -> String {
@@ -63,6 +270,21 @@ impl Config {
             .with_context(|| format!("Failed to read config from {}", path.display()))?;
         let config: Config = toml::from_str(&content)
             .with_context(|| format!("Failed to parse config from {}", path.display()))?;
+        for (workspace, backend) in &config.workspace_backends {
+            backend.parse::<BackendType>().with_context(|| {
+                format!(
+                    "Invalid backend for workspace '{}' in {}",
+                    workspace,
+                    path.display()
+                )
+            })?;
+        }
+        config
+            .keep_alive()
+            .with_context(|| format!("Invalid keepalive in {}", path.display()))?;
+        config
+            .check_dependencies()
+            .with_context(|| format!("Invalid depends_on in {}", path.display()))?;
         Ok(config)
     }
 
@@ -81,22 +303,149 @@ impl Config {
 
     /// Get the default config file path (~/.config/coven/swarm.toml)
     pub fn default_path() -> Result<PathBuf> {
-        let config_dir = std::env::var("XDG_CONFIG_HOME")
-            .map(PathBuf::from)
-            .unwrap_or_else(|_| {
-                dirs::home_dir()
-                    .map(|h| h.join(".config"))
-                    .unwrap_or_else(|| PathBuf::from("."))
-            })
-            .join("coven");
-        Ok(config_dir.join("swarm.toml"))
+        Ok(coven_config_dir().join("swarm.toml"))
+    }
+
+    /// Directory agent logs are written to (~/.config/coven/swarm)
+    pub fn agent_log_dir() -> PathBuf {
+        coven_config_dir().join("swarm")
+    }
+
+    /// Agent log settings, or None when agent logs are disabled
+    pub fn agent_logs(&self) -> Option<AgentLogSettings> {
+        Some(self.agent_logs.unwrap_or_default()).filter(|logs| logs.enabled)
+    }
+
+    /// Validated keep-alive for agent connections, or None when disabled
+    pub fn keep_alive(&self) -> Result<Option<KeepAliveConfig>> {
+        Ok(self.keepalive.unwrap_or_default().to_config()?)
+    }
+
+    /// Working directory with `~` and environment variables expanded
+    pub fn working_directory_expanded(&self) -> Result<PathBuf> {
+        expand_path(&self.working_directory).context("Invalid working_directory in swarm config")
     }
 
-    /// Expand ~ in working directory path
-    pub fn working_directory_expanded(&self) -> PathBuf {
-        shellexpand::tilde(&self.working_directory)
-            .into_owned()
-            .into()
+    /// `global_soul_path` with `~` and environment variables expanded
+    pub fn global_soul_path_expanded(&self) -> Result<Option<PathBuf>> {
+        self.global_soul_path
+            .as_deref()
+            .map(expand_path)
+            .transpose()
+            .context("Invalid global_soul_path in swarm config")
+    }
+
+    /// `dispatch_soul_path` with `~` and environment variables expanded
+    pub fn dispatch_soul_path_expanded(&self) -> Result<Option<PathBuf>> {
+        self.dispatch_soul_path
+            .as_deref()
+            .map(expand_path)
+            .transpose()
+            .context("Invalid dispatch_soul_path in swarm config")
+    }
+
+    /// Backend for a (non-dispatch) workspace: its entry in
+    /// `workspace_backends`, else `backend` in its `.coven/agent.toml`,
+    /// else `default_backend`. Errors name the workspace.
+    pub fn backend_for(&self, workspace: &str) -> Result<BackendType> {
+        if let Some(backend) = self.workspace_backends.get(workspace) {
+            return backend.parse().with_context(|| {
+                format!(
+                    "Invalid backend for workspace '{}' in swarm config [workspace_backends]",
+                    workspace
+                )
+            });
+        }
+
+        let path = self
+            .working_directory_expanded()?
+            .join(workspace)
+            .join(WORKSPACE_CONFIG);
+        if !path.exists() {
+            return Ok(self.default_backend.clone());
+        }
+        let content = std::fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        let workspace_config: WorkspaceConfig = toml::from_str(&content).with_context(|| {
+            format!(
+                "Failed to parse {} for workspace '{}'",
+                path.display(),
+                workspace
+            )
+        })?;
+        match workspace_config.backend {
+            Some(backend) => backend.parse().with_context(|| {
+                format!(
+                    "Invalid backend for workspace '{}' in {}",
+                    workspace,
+                    path.display()
+                )
+            }),
+            None => Ok(self.default_backend.clone()),
+        }
+    }
+
+    /// Workspaces `workspace` waits for before it starts
+    pub fn dependencies_of(&self, workspace: &str) -> &[String] {
+        self.depends_on
+            .get(workspace)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    /// Fail if any workspace depends, directly or not, on itself, naming
+    /// the cycle
+    pub fn check_dependencies(&self) -> Result<()> {
+        // Depth-first search; `path` is the chain of workspaces being
+        // visited, so meeting one of them again closes a cycle
+        fn visit<'a>(
+            config: &'a Config,
+            workspace: &'a str,
+            path: &mut Vec<&'a str>,
+            done: &mut HashSet<&'a str>,
+        ) -> Result<()> {
+            if done.contains(workspace) {
+                return Ok(());
+            }
+            if let Some(start) = path.iter().position(|w| *w == workspace) {
+                let mut cycle = path[start..].to_vec();
+                cycle.push(workspace);
+                anyhow::bail!("dependency cycle: {}", cycle.join(" -> "));
+            }
+            path.push(workspace);
+            for dependency in config.dependencies_of(workspace) {
+                visit(config, dependency, path, done)?;
+            }
+            path.pop();
+            done.insert(workspace);
+            Ok(())
+        }
+
+        let mut done = HashSet::new();
+        for workspace in self.depends_on.keys() {
+            visit(self, workspace, &mut Vec::new(), &mut done)?;
+        }
+        Ok(())
+    }
+
+    /// Of `pending` workspaces, those whose dependencies are all `settled`
+    /// (started and registered, or given up on), in order. Dependencies
+    /// outside `known` aren't waited for.
+    pub fn ready_to_start(
+        &self,
+        pending: &[String],
+        settled: &HashSet<String>,
+        known: &HashSet<String>,
+    ) -> Vec<String> {
+        pending
+            .iter()
+            .filter(|workspace| {
+                self.dependencies_of(workspace)
+                    .iter()
+                    .all(|dependency| settled.contains(dependency) || !known.contains(dependency))
+            })
+            .cloned()
+            .collect()
     }
 
     /// Get the gateway URL, falling back to coven link config if not set
@@ -119,6 +468,55 @@ impl Config {
     }
 }
 
+/// ~/.config/coven, or $XDG_CONFIG_HOME/coven
+fn coven_config_dir() -> PathBuf {
+    std::env::var("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .unwrap_or_else(|_| {
+            dirs::home_dir()
+                .map(|h| h.join(".config"))
+                .unwrap_or_else(|| PathBuf::from("."))
+        })
+        .join("coven")
+}
+
+/// Expand `~`, `$VAR` and `${VAR}` in a configured path so one config
+/// works across machines. Unset `XDG_*` base directories fall back to
+/// their spec defaults; any other unset variable is an error rather than
+/// a literal `$FOO` in the path.
+pub fn expand_path(path: &str) -> Result<PathBuf> {
+    expand_path_with(path, dirs::home_dir(), |var| std::env::var(var).ok())
+}
+
+fn expand_path_with(
+    path: &str,
+    home: Option<PathBuf>,
+    lookup: impl Fn(&str) -> Option<String>,
+) -> Result<PathBuf> {
+    let expanded = shellexpand::full_with_context(
+        path,
+        || home.clone(),
+        |var: &str| match lookup(var).or_else(|| xdg_default(var, home.as_deref())) {
+            Some(value) => Ok(Some(value)),
+            None => Err(()),
+        },
+    )
+    .map_err(|e| anyhow!("${} is not set (in path '{}')", e.var_name, path))?;
+    Ok(PathBuf::from(expanded.into_owned()))
+}
+
+/// Default for an unset XDG base directory variable
+fn xdg_default(var: &str, home: Option<&Path>) -> Option<String> {
+    let relative = match var {
+        "XDG_CONFIG_HOME" => ".config",
+        "XDG_DATA_HOME" => ".local/share",
+        "XDG_STATE_HOME" => ".local/state",
+        "XDG_CACHE_HOME" => ".cache",
+        _ => return None,
+    };
+    home.map(|home| home.join(relative).display().to_string())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -146,6 +544,129 @@ mod tests {
         );
         assert_eq!(config.prefix, "home");
         assert_eq!(config.default_backend, BackendType::Acp);
+        assert_eq!(config.acp_model, None);
+        assert!(config.acp_env.is_empty());
+    }
+
+    #[test]
+    fn test_load_acp_model_and_env() {
+        let mut file = NamedTempFile::new().unwrap();
+        writeln!(
+            file,
+            r#"
+            prefix = "home"
+            working_directory = "~/workspaces"
+            acp_model = "claude-opus"
+
+            [acp_env]
+            ANTHROPIC_BASE_URL = "https://proxy"
+        "#
+        )
+        .unwrap();
+
+        let config = Config::load(file.path()).unwrap();
+        assert_eq!(config.acp_model.as_deref(), Some("claude-opus"));
+        assert_eq!(config.acp_env["ANTHROPIC_BASE_URL"], "https://proxy");
+    }
+
+    #[test]
+    fn test_load_keepalive() {
+        let mut file = NamedTempFile::new().unwrap();
+        writeln!(
+            file,
+            r#"
+            prefix = "home"
+            working_directory = "~/workspaces"
+
+            [keepalive]
+            interval_secs = 60
+            timeout_secs = 20
+        "#
+        )
+        .unwrap();
+        let config = Config::load(file.path()).unwrap();
+        let ka = config.keep_alive().unwrap().unwrap();
+        assert_eq!(ka.interval, std::time::Duration::from_secs(60));
+        assert_eq!(ka.timeout, std::time::Duration::from_secs(20));
+
+        let mut file = NamedTempFile::new().unwrap();
+        writeln!(
+            file,
+            "prefix = \"home\"\nworking_directory = \"~\"\n[keepalive]\ninterval_secs = 20\n"
+        )
+        .unwrap();
+        let err = Config::load(file.path()).unwrap_err();
+        assert!(format!("{:#}", err).contains("less than the interval"));
+    }
+
+    #[test]
+    fn test_load_agent_logs() {
+        let mut file = NamedTempFile::new().unwrap();
+        writeln!(file, "prefix = \"home\"\nworking_directory = \"~\"\n").unwrap();
+        let config = Config::load(file.path()).unwrap();
+        assert_eq!(config.agent_logs(), Some(AgentLogSettings::default()));
+
+        let mut file = NamedTempFile::new().unwrap();
+        writeln!(
+            file,
+            "prefix = \"home\"\nworking_directory = \"~\"\n[agent_logs]\nkeep = 4\n"
+        )
+        .unwrap();
+        let logs = Config::load(file.path()).unwrap().agent_logs().unwrap();
+        assert_eq!(logs.keep, 4);
+        assert_eq!(logs.max_size_mb, 10);
+
+        let mut file = NamedTempFile::new().unwrap();
+        writeln!(
+            file,
+            "prefix = \"home\"\nworking_directory = \"~\"\n[agent_logs]\nenabled = false\n"
+        )
+        .unwrap();
+        assert_eq!(Config::load(file.path()).unwrap().agent_logs(), None);
+    }
+
+    #[test]
+    fn test_load_git_automation() {
+        let mut file = NamedTempFile::new().unwrap();
+        writeln!(
+            file,
+            "prefix = \"home\"\nworking_directory = \"~\"\n[git]\nauto_commit = true\n"
+        )
+        .unwrap();
+        let git = Config::load(file.path()).unwrap().git.unwrap();
+        assert!(git.auto_commit);
+        assert!(!git.auto_pull);
+        assert_eq!(
+            git.branch_for("research", "main"),
+            "coven/wip/research".to_string()
+        );
+        let git = GitAutomation {
+            branch: "wip/{branch}-{workspace}".to_string(),
+            ..git
+        };
+        assert_eq!(git.branch_for("research", "main"), "wip/main-research");
+    }
+
+    #[test]
+    fn test_load_theme() {
+        let mut file = NamedTempFile::new().unwrap();
+        writeln!(
+            file,
+            "prefix = \"home\"\nworking_directory = \"~\"\ntheme = \"neo\"\n"
+        )
+        .unwrap();
+        assert_eq!(
+            Config::load(file.path()).unwrap().theme,
+            Some(TuiTheme::Neo)
+        );
+
+        let mut file = NamedTempFile::new().unwrap();
+        writeln!(
+            file,
+            "prefix = \"home\"\nworking_directory = \"~\"\ntheme = \"rainbow\"\n"
+        )
+        .unwrap();
+        assert!(Config::load(file.path()).is_err());
     }
 
     #[test]
@@ -158,10 +679,23 @@ mod tests {
             prefix: "test".to_string(),
             working_directory: "~/test-workspaces".to_string(),
             default_backend: BackendType::Mux,
+            workspace_backends: BTreeMap::new(),
+            depends_on: BTreeMap::new(),
             acp_binary: "claude".to_string(),
+            acp_model: None,
+            cli_pool_size: None,
+            cli_max_restarts: None,
+            cli_restart_window_secs: None,
+            key_passphrase: None,
+            acp_env: BTreeMap::new(),
             global_soul_path: None,
             dispatch_soul_path: None,
             soul_files: default_soul_files(),
+            busy_reply: None,
+            keepalive: None,
+            agent_logs: None,
+            git: None,
+            theme: None,
         };
 
         config.save(&path).unwrap();
@@ -179,13 +713,26 @@ mod tests {
             prefix: "home".to_string(),
             working_directory: "~/workspaces".to_string(),
             default_backend: BackendType::Acp,
+            workspace_backends: BTreeMap::new(),
+            depends_on: BTreeMap::new(),
             acp_binary: "claude".to_string(),
+            acp_model: None,
+            cli_pool_size: None,
+            cli_max_restarts: None,
+            cli_restart_window_secs: None,
+            key_passphrase: None,
+            acp_env: BTreeMap::new(),
             global_soul_path: None,
             dispatch_soul_path: None,
             soul_files: default_soul_files(),
+            busy_reply: None,
+            keepalive: None,
+            agent_logs: None,
+            git: None,
+            theme: None,
         };
 
-        let expanded_wd = config.working_directory_expanded();
+        let expanded_wd = config.working_directory_expanded().unwrap();
 
         // Should not contain ~ after expansion
         assert!(!expanded_wd.to_string_lossy().contains('~'));
@@ -195,6 +742,62 @@ mod tests {
         assert!(expanded_wd.to_string_lossy().starts_with(&home));
     }
 
+    fn expand(path: &str) -> Result<PathBuf> {
+        expand_path_with(path, Some(PathBuf::from("/home/ada")), |var| match var {
+            "PROJECTS" => Some("/srv/projects".to_string()),
+            "XDG_DATA_HOME" => Some("/data".to_string()),
+            _ => None,
+        })
+    }
+
+    #[test]
+    fn test_expand_path_forms() {
+        assert_eq!(
+            expand("~/workspaces").unwrap(),
+            PathBuf::from("/home/ada/workspaces")
+        );
+        assert_eq!(
+            expand("$PROJECTS/swarm").unwrap(),
+            PathBuf::from("/srv/projects/swarm")
+        );
+        assert_eq!(
+            expand("${PROJECTS}-old/swarm").unwrap(),
+            PathBuf::from("/srv/projects-old/swarm")
+        );
+        assert_eq!(
+            expand("/opt/coven/workspaces").unwrap(),
+            PathBuf::from("/opt/coven/workspaces")
+        );
+    }
+
+    #[test]
+    fn test_expand_path_xdg_defaults() {
+        assert_eq!(
+            expand("${XDG_CONFIG_HOME}/coven/soul.md").unwrap(),
+            PathBuf::from("/home/ada/.config/coven/soul.md")
+        );
+        // A set XDG variable wins over the default
+        assert_eq!(
+            expand("$XDG_DATA_HOME/coven").unwrap(),
+            PathBuf::from("/data/coven")
+        );
+    }
+
+    #[test]
+    fn test_expand_path_unset_variable_errors() {
+        let err = expand("$NOPE/workspaces").unwrap_err().to_string();
+        assert!(err.contains("$NOPE is not set"), "{err}");
+
+        let mut config = config_in(Path::new("/tmp"));
+        config.working_directory = "${NOPE}/workspaces".to_string();
+        config.global_soul_path = Some("$ALSO_NOPE/soul.md".to_string());
+        let err = format!("{:#}", config.working_directory_expanded().unwrap_err());
+        assert!(err.contains("working_directory"), "{err}");
+        let err = format!("{:#}", config.global_soul_path_expanded().unwrap_err());
+        assert!(err.contains("global_soul_path"), "{err}");
+        assert_eq!(config.dispatch_soul_path_expanded().unwrap(), None);
+    }
+
     #[test]
     fn test_default_backend() {
         let mut file = NamedTempFile::new().unwrap();
@@ -220,10 +823,23 @@ mod tests {
             prefix: "test".to_string(),
             working_directory: "~/workspaces".to_string(),
             default_backend: BackendType::Acp,
+            workspace_backends: BTreeMap::new(),
+            depends_on: BTreeMap::new(),
             acp_binary: "claude".to_string(),
+            acp_model: None,
+            cli_pool_size: None,
+            cli_max_restarts: None,
+            cli_restart_window_secs: None,
+            key_passphrase: None,
+            acp_env: BTreeMap::new(),
             global_soul_path: None,
             dispatch_soul_path: None,
             soul_files: default_soul_files(),
+            busy_reply: None,
+            keepalive: None,
+            agent_logs: None,
+            git: None,
+            theme: None,
         };
 
         // Should return the explicit URL
@@ -237,4 +853,148 @@ mod tests {
     fn test_backend_type_default() {
         assert_eq!(BackendType::default(), BackendType::Acp);
     }
+
+    fn config_in(dir: &Path) -> Config {
+        Config {
+            gateway_url: None,
+            prefix: "home".to_string(),
+            working_directory: dir.display().to_string(),
+            default_backend: BackendType::Acp,
+            workspace_backends: BTreeMap::new(),
+            depends_on: BTreeMap::new(),
+            acp_binary: "claude".to_string(),
+            acp_model: None,
+            cli_pool_size: None,
+            cli_max_restarts: None,
+            cli_restart_window_secs: None,
+            key_passphrase: None,
+            acp_env: BTreeMap::new(),
+            global_soul_path: None,
+            dispatch_soul_path: None,
+            soul_files: default_soul_files(),
+            busy_reply: None,
+            keepalive: None,
+            agent_logs: None,
+            git: None,
+            theme: None,
+        }
+    }
+
+    fn write_workspace_config(dir: &Path, workspace: &str, content: &str) {
+        let coven_dir = dir.join(workspace).join(".coven");
+        std::fs::create_dir_all(&coven_dir).unwrap();
+        std::fs::write(coven_dir.join("agent.toml"), content).unwrap();
+    }
+
+    #[test]
+    fn test_backend_type_from_str() {
+        assert_eq!("mux".parse::<BackendType>().unwrap(), BackendType::Mux);
+        assert_eq!("cli".parse::<BackendType>().unwrap(), BackendType::Direct);
+        let err = "gpt".parse::<BackendType>().unwrap_err();
+        assert!(err.to_string().contains("unknown backend 'gpt'"));
+    }
+
+    #[test]
+    fn test_backend_for_precedence() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut config = config_in(dir.path());
+        write_workspace_config(dir.path(), "api", "backend = \"mux\"\n");
+        write_workspace_config(dir.path(), "both", "backend = \"mux\"\n");
+        write_workspace_config(dir.path(), "named", "name = \"bot\"\n");
+        config
+            .workspace_backends
+            .insert("both".to_string(), "direct".to_string());
+
+        assert_eq!(config.backend_for("plain").unwrap(), BackendType::Acp);
+        assert_eq!(config.backend_for("named").unwrap(), BackendType::Acp);
+        assert_eq!(config.backend_for("api").unwrap(), BackendType::Mux);
+        assert_eq!(config.backend_for("both").unwrap(), BackendType::Direct);
+    }
+
+    #[test]
+    fn test_backend_for_invalid_names_workspace() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut config = config_in(dir.path());
+        write_workspace_config(dir.path(), "research", "backend = \"gpt\"\n");
+        config
+            .workspace_backends
+            .insert("notes".to_string(), "nope".to_string());
+
+        let err = format!("{:#}", config.backend_for("research").unwrap_err());
+        assert!(err.contains("workspace 'research'"), "{err}");
+        assert!(err.contains("unknown backend 'gpt'"), "{err}");
+
+        let err = format!("{:#}", config.backend_for("notes").unwrap_err());
+        assert!(err.contains("workspace 'notes'"), "{err}");
+    }
+
+    #[test]
+    fn test_load_rejects_invalid_workspace_backend() {
+        let mut file = NamedTempFile::new().unwrap();
+        writeln!(
+            file,
+            r#"
+            prefix = "home"
+            working_directory = "~/workspaces"
+
+            [workspace_backends]
+            research = "mux"
+            notes = "gpt"
+        "#
+        )
+        .unwrap();
+
+        let err = format!("{:#}", Config::load(file.path()).unwrap_err());
+        assert!(err.contains("workspace 'notes'"), "{err}");
+    }
+
+    #[test]
+    fn test_load_rejects_dependency_cycle() {
+        let mut file = NamedTempFile::new().unwrap();
+        writeln!(
+            file,
+            r#"
+            prefix = "home"
+            working_directory = "~/workspaces"
+
+            [depends_on]
+            web = ["api"]
+            api = ["db", "web"]
+        "#
+        )
+        .unwrap();
+
+        let err = format!("{:#}", Config::load(file.path()).unwrap_err());
+        assert!(err.contains("dependency cycle: api -> web -> api"), "{err}");
+    }
+
+    #[test]
+    fn test_ready_to_start_waits_for_dependencies() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut config = config_in(dir.path());
+        config
+            .depends_on
+            .insert("web".to_string(), vec!["db".to_string()]);
+        config.depends_on.insert(
+            "api".to_string(),
+            vec!["db".to_string(), "cache".to_string()],
+        );
+        config.check_dependencies().unwrap();
+        let names =
+            |names: &[&str]| -> Vec<String> { names.iter().map(|n| n.to_string()).collect() };
+        let set =
+            |names: &[&str]| -> HashSet<String> { names.iter().map(|n| n.to_string()).collect() };
+        let pending = names(&["api", "db", "notes", "web"]);
+        let known = set(&["api", "db", "notes", "web"]);
+
+        // "cache" isn't a workspace, so only "db" holds anything up
+        assert_eq!(
+            config.ready_to_start(&pending, &HashSet::new(), &known),
+            names(&["db", "notes"])
+        );
+        assert_eq!(
+            config.ready_to_start(&names(&["api", "web"]), &set(&["db"]), &known),
+            names(&["api", "web"])
+        );
+    }
 }
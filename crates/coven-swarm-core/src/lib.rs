@@ -2,5 +2,6 @@
 // ABOUTME: Contains config parsing, protocol types, and common utilities.
 
 pub mod config;
+pub mod process;
 
 pub use config::{BackendType, Config};
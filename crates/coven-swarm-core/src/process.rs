@@ -0,0 +1,637 @@
+// ABOUTME: Process supervision helpers shared by the swarm supervisor and `coven pack run --daemon`.
+// ABOUTME: Spawns children with line-forwarded output and status pipes, rotates log files, and signals pids.
+
+use std::fs::{File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::process::{ExitStatus, Stdio};
+use tokio::io::{AsyncBufReadExt, AsyncRead, BufReader};
+use tokio::process::{Child, Command};
+use tokio::sync::mpsc;
+
+/// A child process whose stdout and stderr are forwarded line by line to a
+/// channel. The child is killed if this is dropped.
+pub struct ManagedChild {
+    child: Child,
+    pid: Option<u32>,
+}
+
+impl ManagedChild {
+    /// Spawn `cmd`, sending every line it prints on stdout or stderr to
+    /// `lines`. Forwarding stops when the child closes its output or the
+    /// receiver is dropped.
+    pub fn spawn(mut cmd: Command, lines: mpsc::Sender<String>) -> io::Result<Self> {
+        cmd.stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true);
+        let mut child = cmd.spawn()?;
+
+        if let Some(stdout) = child.stdout.take() {
+            forward_lines(stdout, lines.clone());
+        }
+        if let Some(stderr) = child.stderr.take() {
+            forward_lines(stderr, lines);
+        }
+
+        let pid = child.id();
+        Ok(Self { child, pid })
+    }
+
+    /// Like `spawn`, and also give the child a pipe of its own to report
+    /// status on; see `StatusReporter`. Each line it reports is sent to
+    /// `status`, apart from its output, so nothing it prints can pass for a
+    /// report.
+    #[cfg(unix)]
+    pub fn spawn_with_status(
+        mut cmd: Command,
+        lines: mpsc::Sender<String>,
+        status: mpsc::Sender<String>,
+    ) -> io::Result<Self> {
+        use std::os::fd::AsRawFd;
+
+        let (reader, writer) = cloexec_pipe()?;
+        let write_fd = writer.as_raw_fd();
+        cmd.env(STATUS_FD_ENV, STATUS_FD.to_string());
+        // SAFETY: only async-signal-safe calls between fork and exec
+        unsafe {
+            cmd.pre_exec(move || {
+                let moved = if write_fd == STATUS_FD {
+                    libc::fcntl(write_fd, libc::F_SETFD, 0)
+                } else {
+                    libc::dup2(write_fd, STATUS_FD)
+                };
+                if moved == -1 {
+                    return Err(io::Error::last_os_error());
+                }
+                Ok(())
+            });
+        }
+        let child = Self::spawn(cmd, lines)?;
+        // The child holds the only write end now, so the pipe closes when
+        // it exits
+        drop(writer);
+
+        let reader = tokio::net::unix::pipe::Receiver::from_owned_fd(reader)?;
+        forward_lines(reader, status);
+        Ok(child)
+    }
+
+    /// Reporting status needs Unix file descriptors
+    #[cfg(not(unix))]
+    pub fn spawn_with_status(
+        _cmd: Command,
+        _lines: mpsc::Sender<String>,
+        _status: mpsc::Sender<String>,
+    ) -> io::Result<Self> {
+        Err(unsupported())
+    }
+
+    pub fn pid(&self) -> Option<u32> {
+        self.pid
+    }
+
+    pub async fn wait(&mut self) -> io::Result<ExitStatus> {
+        self.child.wait().await
+    }
+
+    pub async fn kill(&mut self) -> io::Result<()> {
+        self.child.kill().await
+    }
+}
+
+fn forward_lines<R>(reader: R, tx: mpsc::Sender<String>)
+where
+    R: AsyncRead + Unpin + Send + 'static,
+{
+    tokio::spawn(async move {
+        let mut lines = BufReader::new(reader).lines();
+        while let Ok(Some(line)) = lines.next_line().await {
+            if tx.send(line).await.is_err() {
+                break;
+            }
+        }
+    });
+}
+
+/// Environment variable telling a child started by
+/// `ManagedChild::spawn_with_status` which descriptor to report status on
+pub const STATUS_FD_ENV: &str = "COVEN_STATUS_FD";
+
+/// The descriptor a child's status pipe is given as
+#[cfg(unix)]
+const STATUS_FD: libc::c_int = 3;
+
+/// A pipe whose ends are closed on exec, so other children spawned
+/// meanwhile don't inherit them. macOS has no pipe2, so there a child
+/// forked while the pipe is being made still can.
+#[cfg(unix)]
+fn cloexec_pipe() -> io::Result<(std::os::fd::OwnedFd, std::os::fd::OwnedFd)> {
+    use std::os::fd::FromRawFd;
+
+    let mut fds = [0 as libc::c_int; 2];
+    #[cfg(not(target_os = "macos"))]
+    if unsafe { libc::pipe2(fds.as_mut_ptr(), libc::O_CLOEXEC) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    #[cfg(target_os = "macos")]
+    if unsafe { libc::pipe(fds.as_mut_ptr()) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    // SAFETY: both fds were just created by the pipe call and are owned here
+    let ends = unsafe {
+        (
+            std::os::fd::OwnedFd::from_raw_fd(fds[0]),
+            std::os::fd::OwnedFd::from_raw_fd(fds[1]),
+        )
+    };
+    #[cfg(target_os = "macos")]
+    for fd in fds {
+        if unsafe { libc::fcntl(fd, libc::F_SETFD, libc::FD_CLOEXEC) } == -1 {
+            return Err(io::Error::last_os_error());
+        }
+    }
+    Ok(ends)
+}
+
+/// The pipe a supervisor gave this process to report status on, one line
+/// per report. Only processes started by `ManagedChild::spawn_with_status`
+/// have one.
+pub struct StatusReporter {
+    #[cfg(unix)]
+    pipe: File,
+}
+
+impl StatusReporter {
+    /// Take the pipe named by `STATUS_FD_ENV`, if there is one. Take it
+    /// before spawning anything: the variable is cleared and the pipe
+    /// closed on exec, so this process's own children can't report on it
+    /// or hold it open. Clearing the variable is only sound while this is
+    /// the process's one thread, so call it before starting a runtime.
+    #[cfg(unix)]
+    pub fn from_env() -> Option<Self> {
+        use std::os::fd::FromRawFd;
+
+        let fd = std::env::var(STATUS_FD_ENV).ok()?;
+        std::env::remove_var(STATUS_FD_ENV);
+        let fd: libc::c_int = fd.parse().ok().filter(|fd| *fd > libc::STDERR_FILENO)?;
+        // Fails if the descriptor isn't open
+        if unsafe { libc::fcntl(fd, libc::F_SETFD, libc::FD_CLOEXEC) } == -1 {
+            return None;
+        }
+        // SAFETY: the supervisor handed this descriptor over for our use alone
+        Some(Self {
+            pipe: unsafe { File::from_raw_fd(fd) },
+        })
+    }
+
+    /// There's never a status pipe off Unix
+    #[cfg(not(unix))]
+    pub fn from_env() -> Option<Self> {
+        None
+    }
+
+    /// Report one line of status
+    pub fn report(&self, line: &str) -> io::Result<()> {
+        #[cfg(unix)]
+        {
+            // A single short write, so reports can't interleave
+            (&self.pipe).write_all(format!("{}\n", line).as_bytes())
+        }
+        #[cfg(not(unix))]
+        {
+            let _ = line;
+            Ok(())
+        }
+    }
+}
+
+/// Append-only log file that rotates to `<path>.1`, `<path>.2`, ... once it
+/// grows past `max_bytes`, keeping at most `keep` old files.
+pub struct RotatingLog {
+    path: PathBuf,
+    max_bytes: u64,
+    keep: usize,
+    file: File,
+    written: u64,
+}
+
+impl RotatingLog {
+    /// Default size a log grows to before it is rotated
+    pub const DEFAULT_MAX_BYTES: u64 = 10 * 1024 * 1024;
+    /// Default number of rotated files kept
+    pub const DEFAULT_KEEP: usize = 3;
+
+    pub fn open(path: impl Into<PathBuf>, max_bytes: u64, keep: usize) -> io::Result<Self> {
+        let path = path.into();
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        let written = file.metadata()?.len();
+        Ok(Self {
+            path,
+            max_bytes,
+            keep,
+            file,
+            written,
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Append one line, rotating first if it would overflow the current file
+    pub fn write_line(&mut self, line: &str) -> io::Result<()> {
+        let len = line.len() as u64 + 1;
+        if self.written > 0 && self.written + len > self.max_bytes {
+            self.rotate()?;
+        }
+        writeln!(self.file, "{}", line)?;
+        self.written += len;
+        Ok(())
+    }
+
+    fn rotated(&self, n: usize) -> PathBuf {
+        let mut name = self.path.clone().into_os_string();
+        name.push(format!(".{}", n));
+        PathBuf::from(name)
+    }
+
+    fn rotate(&mut self) -> io::Result<()> {
+        self.file.flush()?;
+        if self.keep == 0 {
+            self.file = File::create(&self.path)?;
+        } else {
+            let _ = std::fs::remove_file(self.rotated(self.keep));
+            for n in (1..self.keep).rev() {
+                let from = self.rotated(n);
+                if from.exists() {
+                    std::fs::rename(&from, self.rotated(n + 1))?;
+                }
+            }
+            std::fs::rename(&self.path, self.rotated(1))?;
+            self.file = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&self.path)?;
+        }
+        self.written = 0;
+        Ok(())
+    }
+}
+
+/// Where detached processes of one `kind` (e.g. "packs" or "agents") keep
+/// their pid files and logs: `$XDG_STATE_HOME/coven/<kind>`, or
+/// `~/.local/state/coven/<kind>`.
+pub fn state_dir(kind: &str) -> Option<PathBuf> {
+    std::env::var_os("XDG_STATE_HOME")
+        .map(PathBuf::from)
+        .filter(|p| p.is_absolute())
+        .or_else(|| dirs::home_dir().map(|h| h.join(".local").join("state")))
+        .map(|state| state.join("coven").join(kind))
+}
+
+/// Check that `name` can name a file or directory inside a state dir: a
+/// name that is empty, contains a path separator, or is `.` or `..` could
+/// read or write outside it.
+pub fn check_state_name(name: &str) -> io::Result<()> {
+    let bad = name.is_empty() || name == "." || name == ".." || name.contains(['/', '\\', '\0']);
+    if bad {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!(
+                "'{}' is not a valid name: it must not contain '/' or be '..'",
+                name
+            ),
+        ));
+    }
+    Ok(())
+}
+
+/// Sends this process's stdout and stderr through `log` until dropped, so
+/// a long-running detached process's output rotates like a supervised
+/// one's. Dropping it writes out everything printed so far and points
+/// stdout and stderr back where they were.
+#[cfg(unix)]
+pub struct CapturedOutput {
+    relay: Option<std::thread::JoinHandle<()>>,
+    /// Copies of the original stdout and stderr
+    saved: [libc::c_int; 2],
+}
+
+/// Point this process's stdout and stderr at a pipe whose lines are
+/// appended to `log` by a background thread.
+#[cfg(unix)]
+pub fn capture_output(mut log: RotatingLog) -> io::Result<CapturedOutput> {
+    use std::io::BufRead;
+    use std::os::unix::io::FromRawFd;
+
+    let mut fds = [0 as libc::c_int; 2];
+    if unsafe { libc::pipe(fds.as_mut_ptr()) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    let [read_fd, write_fd] = fds;
+    // SAFETY: both fds were just created by pipe() and are owned here
+    let reader = unsafe { File::from_raw_fd(read_fd) };
+    let writer = unsafe { File::from_raw_fd(write_fd) };
+    let saved = [unsafe { libc::dup(libc::STDOUT_FILENO) }, unsafe {
+        libc::dup(libc::STDERR_FILENO)
+    }];
+    for target in [libc::STDOUT_FILENO, libc::STDERR_FILENO] {
+        if unsafe { libc::dup2(write_fd, target) } == -1 {
+            return Err(io::Error::last_os_error());
+        }
+    }
+    // stdout and stderr now hold the only write ends
+    drop(writer);
+
+    let relay = std::thread::spawn(move || {
+        for line in io::BufReader::new(reader).lines() {
+            match line {
+                Ok(line) => {
+                    let _ = log.write_line(&line);
+                }
+                Err(_) => break,
+            }
+        }
+    });
+    Ok(CapturedOutput {
+        relay: Some(relay),
+        saved,
+    })
+}
+
+/// Stands in for captured output where it isn't supported
+#[cfg(not(unix))]
+pub struct CapturedOutput {
+    _private: (),
+}
+
+/// Capturing output needs Unix file descriptors
+#[cfg(not(unix))]
+pub fn capture_output(_log: RotatingLog) -> io::Result<CapturedOutput> {
+    Err(unsupported())
+}
+
+#[cfg(unix)]
+impl Drop for CapturedOutput {
+    fn drop(&mut self) {
+        let _ = io::stdout().flush();
+        // Replacing the pipe's write ends lets the relay drain it and
+        // finish; anything printed after this goes to the original outputs
+        for (saved, target) in self
+            .saved
+            .into_iter()
+            .zip([libc::STDOUT_FILENO, libc::STDERR_FILENO])
+        {
+            unsafe {
+                if saved >= 0 {
+                    libc::dup2(saved, target);
+                    libc::close(saved);
+                } else {
+                    libc::close(target);
+                }
+            }
+        }
+        if let Some(relay) = self.relay.take() {
+            let _ = relay.join();
+        }
+    }
+}
+
+/// Whether a process with this pid exists
+#[cfg(unix)]
+pub fn is_alive(pid: u32) -> bool {
+    // Signal 0 checks for existence without delivering anything; EPERM
+    // still means the process exists, it just isn't ours.
+    let rc = unsafe { libc::kill(pid as libc::pid_t, 0) };
+    rc == 0 || io::Error::last_os_error().raw_os_error() == Some(libc::EPERM)
+}
+
+/// Ask a process to shut down (SIGTERM)
+#[cfg(unix)]
+pub fn terminate(pid: u32) -> io::Result<()> {
+    signal(pid, libc::SIGTERM)
+}
+
+/// Kill a process outright (SIGKILL)
+#[cfg(unix)]
+pub fn force_kill(pid: u32) -> io::Result<()> {
+    signal(pid, libc::SIGKILL)
+}
+
+#[cfg(unix)]
+fn signal(pid: u32, sig: libc::c_int) -> io::Result<()> {
+    if unsafe { libc::kill(pid as libc::pid_t, sig) } == 0 {
+        Ok(())
+    } else {
+        Err(io::Error::last_os_error())
+    }
+}
+
+/// Pids can't be checked here, so no process counts as alive
+#[cfg(not(unix))]
+pub fn is_alive(_pid: u32) -> bool {
+    false
+}
+
+#[cfg(not(unix))]
+pub fn terminate(_pid: u32) -> io::Result<()> {
+    Err(unsupported())
+}
+
+#[cfg(not(unix))]
+pub fn force_kill(_pid: u32) -> io::Result<()> {
+    Err(unsupported())
+}
+
+#[cfg(not(unix))]
+fn unsupported() -> io::Error {
+    io::Error::new(
+        io::ErrorKind::Unsupported,
+        "managing detached processes is only supported on Unix",
+    )
+}
+
+/// When `pid` started, as a token that differs for a later process given
+/// the same pid. None if the process is gone or it can't be told.
+#[cfg(target_os = "linux")]
+pub fn start_token(pid: u32) -> Option<String> {
+    // The start time, in clock ticks since boot, is the 22nd field of
+    // /proc/<pid>/stat; the 2nd is the name in parentheses, which may
+    // itself contain spaces or parentheses.
+    let stat = std::fs::read_to_string(format!("/proc/{}/stat", pid)).ok()?;
+    let fields = &stat[stat.rfind(')')? + 1..];
+    fields.split_whitespace().nth(19).map(str::to_string)
+}
+
+/// When `pid` started, as a token that differs for a later process given
+/// the same pid. None if the process is gone or it can't be told.
+#[cfg(all(unix, not(target_os = "linux")))]
+pub fn start_token(pid: u32) -> Option<String> {
+    let output = std::process::Command::new("ps")
+        .args(["-o", "lstart=", "-p", &pid.to_string()])
+        .stderr(Stdio::null())
+        .output()
+        .ok()?;
+    let started = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    (output.status.success() && !started.is_empty()).then_some(started)
+}
+
+#[cfg(not(unix))]
+pub fn start_token(_pid: u32) -> Option<String> {
+    None
+}
+
+/// Whether `pid` is still the process whose `start_token` was `recorded`,
+/// rather than a later one the pid was reused for. Without a recorded
+/// token, or when the current one can't be read, this is `is_alive`.
+pub fn is_running(pid: u32, recorded: Option<&str>) -> bool {
+    if !is_alive(pid) {
+        return false;
+    }
+    match (recorded, start_token(pid)) {
+        (Some(recorded), Some(current)) => recorded == current,
+        _ => true,
+    }
+}
+
+/// Run `cmd` in a new session so it outlives the terminal that started it:
+/// closing the terminal or SSH session no longer sends it SIGHUP.
+#[cfg(unix)]
+pub fn detach(cmd: &mut std::process::Command) -> io::Result<()> {
+    use std::os::unix::process::CommandExt;
+    cmd.stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null());
+    // SAFETY: setsid is async-signal-safe and touches no parent state
+    unsafe {
+        cmd.pre_exec(|| {
+            if libc::setsid() == -1 {
+                return Err(io::Error::last_os_error());
+            }
+            Ok(())
+        });
+    }
+    Ok(())
+}
+
+/// Detaching needs Unix sessions
+#[cfg(not(unix))]
+pub fn detach(_cmd: &mut std::process::Command) -> io::Result<()> {
+    Err(unsupported())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rotating_log_rotates_and_keeps_limit() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("pack.log");
+        let mut log = RotatingLog::open(&path, 10, 2).unwrap();
+
+        for line in ["first-line", "second-line", "third-line", "fourth-line"] {
+            log.write_line(line).unwrap();
+        }
+
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "fourth-line\n");
+        assert_eq!(
+            std::fs::read_to_string(dir.path().join("pack.log.1")).unwrap(),
+            "third-line\n"
+        );
+        assert_eq!(
+            std::fs::read_to_string(dir.path().join("pack.log.2")).unwrap(),
+            "second-line\n"
+        );
+        assert!(!dir.path().join("pack.log.3").exists());
+    }
+
+    #[test]
+    fn test_check_state_name() {
+        assert!(check_state_name("ops-infra").is_ok());
+        assert!(check_state_name("v1.2").is_ok());
+        for bad in ["", ".", "..", "../etc", "a/b", "a\\b"] {
+            assert!(check_state_name(bad).is_err(), "{:?}", bad);
+        }
+    }
+
+    #[test]
+    fn test_rotating_log_appends_to_existing_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("pack.log");
+        std::fs::write(&path, "earlier\n").unwrap();
+
+        let mut log = RotatingLog::open(&path, 1024, 1).unwrap();
+        log.write_line("later").unwrap();
+
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "earlier\nlater\n");
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_managed_child_forwards_output() {
+        let mut cmd = Command::new("sh");
+        cmd.args(["-c", "echo out; echo err >&2"]);
+        let (tx, mut rx) = mpsc::channel(8);
+        let mut child = ManagedChild::spawn(cmd, tx).unwrap();
+        assert!(child.pid().is_some());
+        assert!(child.wait().await.unwrap().success());
+
+        let mut lines = Vec::new();
+        while let Some(line) = rx.recv().await {
+            lines.push(line);
+        }
+        lines.sort();
+        assert_eq!(lines, ["err", "out"]);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_status_reports_stay_apart_from_output() {
+        let mut cmd = Command::new("sh");
+        cmd.args([
+            "-c",
+            "echo ready; echo \"ready on $COVEN_STATUS_FD\" >&\"$COVEN_STATUS_FD\"",
+        ]);
+        let (tx, mut rx) = mpsc::channel(8);
+        let (status_tx, mut status_rx) = mpsc::channel(8);
+        let mut child = ManagedChild::spawn_with_status(cmd, tx, status_tx).unwrap();
+        assert!(child.wait().await.unwrap().success());
+
+        assert_eq!(rx.recv().await.as_deref(), Some("ready"));
+        assert_eq!(rx.recv().await, None);
+        assert_eq!(status_rx.recv().await.as_deref(), Some("ready on 3"));
+        // The pipe closes once the child exits
+        assert_eq!(status_rx.recv().await, None);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_signal_helpers() {
+        let mut child = std::process::Command::new("sleep")
+            .arg("30")
+            .spawn()
+            .unwrap();
+        let pid = child.id();
+        assert!(is_alive(pid));
+
+        terminate(pid).unwrap();
+        child.wait().unwrap();
+        assert!(!is_alive(pid));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_is_running_tells_reused_pids_apart() {
+        let pid = std::process::id();
+        let start = start_token(pid).unwrap();
+        assert_eq!(start_token(pid).as_deref(), Some(start.as_str()));
+
+        assert!(is_running(pid, Some(&start)));
+        assert!(is_running(pid, None));
+        assert!(!is_running(pid, Some("an earlier process")));
+    }
+}
@@ -0,0 +1,377 @@
+// ABOUTME: Optional git automation for workspace agents: pull on start, checkpoint WIP while idle.
+// ABOUTME: Checkpoints commit to a side branch through a scratch index, never touching the checkout.
+
+use super::session::SessionQueue;
+use anyhow::{bail, Context, Result};
+use coven_core::git::GitInfo;
+use coven_swarm_core::config::GitAutomation;
+use std::ffi::OsStr;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::task::JoinHandle;
+
+/// Author and committer of checkpoint commits, so they're never mistaken
+/// for the user's own
+const CHECKPOINT_NAME: &str = "coven-swarm";
+const CHECKPOINT_EMAIL: &str = "coven-swarm@localhost";
+
+/// Scratch index checkpoints are staged in, under the repo's git dir
+const CHECKPOINT_INDEX: &str = "coven-checkpoint-index";
+
+/// Longest a pull may take before it's given up, so a slow remote can't
+/// hold up the agent's start
+const PULL_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Fast-forward the checked-out branch from its upstream. Returns false
+/// without doing anything when `working_dir` isn't a git repo or the branch
+/// has no upstream. Git never prompts for credentials, and the pull is
+/// stopped after `PULL_TIMEOUT`.
+pub fn pull(working_dir: &Path) -> Result<bool> {
+    let upstream = git(
+        working_dir,
+        &["rev-parse", "--abbrev-ref", "--symbolic-full-name", "@{u}"],
+        &[],
+    );
+    if upstream.is_err() {
+        return Ok(false);
+    }
+    let mut child = Command::new("git")
+        .args(["pull", "--ff-only", "--quiet"])
+        .env("GIT_TERMINAL_PROMPT", "0")
+        .current_dir(working_dir)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .context("failed to run git")?;
+    let deadline = Instant::now() + PULL_TIMEOUT;
+    let status = loop {
+        if let Some(status) = child.try_wait().context("failed to wait for git")? {
+            break status;
+        }
+        if Instant::now() >= deadline {
+            let _ = child.kill();
+            let _ = child.wait();
+            bail!("git pull timed out after {}s", PULL_TIMEOUT.as_secs());
+        }
+        std::thread::sleep(Duration::from_millis(100));
+    };
+    if !status.success() {
+        let mut stderr = String::new();
+        if let Some(mut pipe) = child.stderr.take() {
+            let _ = pipe.read_to_string(&mut stderr);
+        }
+        bail!("git pull failed: {}", stderr.trim());
+    }
+    Ok(true)
+}
+
+/// Commit everything uncommitted in `working_dir` (untracked files included,
+/// ignored ones not) to `branch`, on top of its last checkpoint or else the
+/// current HEAD. The checked-out branch, index and files are left alone.
+/// Returns the new commit, or None when the tree is clean or nothing changed
+/// since the last checkpoint.
+pub fn checkpoint(working_dir: &Path, branch: &str, workspace: &str) -> Result<Option<String>> {
+    let Some(info) = GitInfo::gather(working_dir) else {
+        return Ok(None);
+    };
+    if !info.dirty {
+        return Ok(None);
+    }
+    git(working_dir, &["check-ref-format", "--branch", branch], &[])
+        .with_context(|| format!("invalid checkpoint branch {:?}", branch))?;
+    let reference = format!("refs/heads/{}", branch);
+
+    let index = PathBuf::from(git(
+        working_dir,
+        &["rev-parse", "--git-path", CHECKPOINT_INDEX],
+        &[],
+    )?);
+    let index = if index.is_absolute() {
+        index
+    } else {
+        working_dir.join(index)
+    };
+    let head = git(
+        working_dir,
+        &["rev-parse", "--verify", "--quiet", "HEAD"],
+        &[],
+    )
+    .ok();
+    let tree = stage_tree(working_dir, head.as_deref(), &index);
+    let _ = std::fs::remove_file(&index);
+    let tree = tree?;
+
+    let previous = git(
+        working_dir,
+        &["rev-parse", "--verify", "--quiet", &reference],
+        &[],
+    )
+    .ok();
+    let parent = previous.clone().or(head);
+    if let Some(parent) = &parent {
+        let parent_tree = git(
+            working_dir,
+            &["rev-parse", &format!("{}^{{tree}}", parent)],
+            &[],
+        )?;
+        if parent_tree == tree {
+            return Ok(None);
+        }
+    }
+
+    let message = format!(
+        "WIP: automated checkpoint of {}\n\n\
+         Committed by coven-swarm while the agent was idle, from {} at {}.",
+        workspace,
+        if info.branch.is_empty() {
+            "an unborn branch"
+        } else {
+            &info.branch
+        },
+        if info.commit.is_empty() {
+            "no commit"
+        } else {
+            &info.commit
+        }
+    );
+    let mut args = vec!["commit-tree", tree.as_str(), "-m", message.as_str()];
+    if let Some(parent) = &parent {
+        args.extend(["-p", parent.as_str()]);
+    }
+    let identity = [
+        ("GIT_AUTHOR_NAME", OsStr::new(CHECKPOINT_NAME)),
+        ("GIT_AUTHOR_EMAIL", OsStr::new(CHECKPOINT_EMAIL)),
+        ("GIT_COMMITTER_NAME", OsStr::new(CHECKPOINT_NAME)),
+        ("GIT_COMMITTER_EMAIL", OsStr::new(CHECKPOINT_EMAIL)),
+    ];
+    let commit = git(working_dir, &args, &identity)?;
+
+    // Only move the branch if nobody else did in the meantime
+    let expected = previous.unwrap_or_default();
+    git(
+        working_dir,
+        &[
+            "update-ref",
+            "-m",
+            "coven-swarm checkpoint",
+            &reference,
+            &commit,
+            &expected,
+        ],
+        &[],
+    )?;
+    Ok(Some(commit))
+}
+
+/// Stage the working tree over `head` in the scratch `index` and write it
+/// out, returning the tree's ID
+fn stage_tree(working_dir: &Path, head: Option<&str>, index: &Path) -> Result<String> {
+    let env = [("GIT_INDEX_FILE", index.as_os_str())];
+    match head {
+        Some(head) => git(working_dir, &["read-tree", head], &env)?,
+        None => git(working_dir, &["read-tree", "--empty"], &env)?,
+    };
+    git(working_dir, &["add", "--all"], &env)?;
+    git(working_dir, &["write-tree"], &env)
+}
+
+/// Checkpoint `working_dir` every `commit_interval_secs` while `session` is
+/// idle. Failures are logged and tried again next time.
+pub fn spawn_checkpoints(
+    settings: GitAutomation,
+    working_dir: PathBuf,
+    workspace: String,
+    session: Arc<SessionQueue>,
+) -> JoinHandle<()> {
+    let period = Duration::from_secs(settings.commit_interval_secs.max(1));
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(period);
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        // The first tick is immediate; there's nothing to save yet
+        interval.tick().await;
+        loop {
+            interval.tick().await;
+            if !session.is_idle() {
+                continue;
+            }
+            let settings = settings.clone();
+            let dir = working_dir.clone();
+            let ws = workspace.clone();
+            let result = tokio::task::spawn_blocking(move || {
+                let current = GitInfo::gather(&dir).map(|info| info.branch);
+                let branch = settings.branch_for(&ws, current.as_deref().unwrap_or("HEAD"));
+                checkpoint(&dir, &branch, &ws).map(|commit| (branch, commit))
+            })
+            .await;
+            match result {
+                Ok(Ok((branch, Some(commit)))) => {
+                    tracing::info!(branch = %branch, commit = %commit, "Checkpointed workspace")
+                }
+                Ok(Ok((_, None))) => {}
+                Ok(Err(e)) => {
+                    tracing::warn!(error = %format!("{:#}", e), "Workspace checkpoint failed")
+                }
+                Err(e) => tracing::warn!(error = %e, "Workspace checkpoint task failed"),
+            }
+        }
+    })
+}
+
+/// Run git in `dir` with extra environment, returning its trimmed stdout.
+/// Errors carry git's stderr.
+fn git(dir: &Path, args: &[&str], env: &[(&str, &OsStr)]) -> Result<String> {
+    let output = Command::new("git")
+        .args(args)
+        .envs(env.iter().copied())
+        .current_dir(dir)
+        .output()
+        .context("failed to run git")?;
+    if !output.status.success() {
+        bail!(
+            "git {} failed: {}",
+            args.first().copied().unwrap_or_default(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A repo with one commit on `main`
+    fn repo() -> tempfile::TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        let run = |args: &[&str]| {
+            let status = Command::new("git")
+                .args(["-c", "user.name=Test", "-c", "user.email=test@example.com"])
+                .args(args)
+                .current_dir(dir.path())
+                .output()
+                .unwrap()
+                .status;
+            assert!(status.success(), "git {:?}", args);
+        };
+        run(&["init", "--quiet", "--initial-branch=main"]);
+        std::fs::write(dir.path().join("notes.txt"), "first\n").unwrap();
+        std::fs::write(dir.path().join(".gitignore"), "*.log\n").unwrap();
+        run(&["add", "."]);
+        run(&["commit", "--quiet", "-m", "initial"]);
+        dir
+    }
+
+    fn git_in(dir: &Path, args: &[&str]) -> String {
+        git(dir, args, &[]).unwrap()
+    }
+
+    #[test]
+    fn test_checkpoint_leaves_the_checkout_alone() {
+        let dir = repo();
+        let path = dir.path();
+        let head = git_in(path, &["rev-parse", "HEAD"]);
+
+        // Clean tree: nothing to do
+        assert_eq!(checkpoint(path, "wip/research", "research").unwrap(), None);
+
+        std::fs::write(path.join("notes.txt"), "first\nsecond\n").unwrap();
+        std::fs::write(path.join("new.txt"), "new\n").unwrap();
+        std::fs::write(path.join("debug.log"), "ignored\n").unwrap();
+        let commit = checkpoint(path, "wip/research", "research")
+            .unwrap()
+            .expect("a checkpoint");
+
+        assert_eq!(git_in(path, &["rev-parse", "HEAD"]), head);
+        assert_eq!(git_in(path, &["rev-parse", "--abbrev-ref", "HEAD"]), "main");
+        assert_eq!(git_in(path, &["rev-parse", "wip/research"]), commit);
+        assert_eq!(git_in(path, &["rev-parse", "wip/research^"]), head);
+        let files = git_in(path, &["ls-tree", "--name-only", "wip/research"]);
+        assert_eq!(
+            files.lines().collect::<Vec<_>>(),
+            [".gitignore", "new.txt", "notes.txt"]
+        );
+        assert_eq!(
+            git_in(path, &["log", "-1", "--format=%an", "wip/research"]),
+            CHECKPOINT_NAME
+        );
+        // Nothing was staged in the real index
+        assert_eq!(git_in(path, &["diff", "--cached", "--name-only"]), "");
+        assert!(git_in(path, &["status", "--porcelain"]).contains("?? new.txt"));
+
+        // No change since the last checkpoint
+        assert_eq!(checkpoint(path, "wip/research", "research").unwrap(), None);
+
+        // Later work stacks on the previous checkpoint
+        std::fs::write(path.join("new.txt"), "newer\n").unwrap();
+        let next = checkpoint(path, "wip/research", "research")
+            .unwrap()
+            .expect("a second checkpoint");
+        assert_eq!(git_in(path, &["rev-parse", "wip/research^"]), commit);
+        assert_ne!(next, commit);
+    }
+
+    #[test]
+    fn test_checkpoint_rejects_bad_branch_names() {
+        let dir = repo();
+        std::fs::write(dir.path().join("notes.txt"), "changed\n").unwrap();
+        assert!(checkpoint(dir.path(), "wip..bad", "research").is_err());
+    }
+
+    #[test]
+    fn test_outside_a_repo_nothing_happens() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(!pull(dir.path()).unwrap());
+        assert_eq!(checkpoint(dir.path(), "wip/x", "x").unwrap(), None);
+    }
+
+    #[test]
+    fn test_pull_without_upstream_is_skipped() {
+        let dir = repo();
+        assert!(!pull(dir.path()).unwrap());
+    }
+
+    #[test]
+    fn test_pull_fast_forwards_from_upstream() {
+        let origin = repo();
+        let clone = tempfile::tempdir().unwrap();
+        let path = clone.path().join("work");
+        let status = Command::new("git")
+            .args(["clone", "--quiet"])
+            .arg(origin.path())
+            .arg(&path)
+            .status()
+            .unwrap();
+        assert!(status.success());
+
+        std::fs::write(
+            origin.path().join("notes.txt"),
+            "first
+second
+",
+        )
+        .unwrap();
+        git_in(origin.path(), &["add", "notes.txt"]);
+        let identity = [
+            ("GIT_AUTHOR_NAME", OsStr::new("Test")),
+            ("GIT_AUTHOR_EMAIL", OsStr::new("test@example.com")),
+            ("GIT_COMMITTER_NAME", OsStr::new("Test")),
+            ("GIT_COMMITTER_EMAIL", OsStr::new("test@example.com")),
+        ];
+        git(
+            origin.path(),
+            &["commit", "--quiet", "-m", "second"],
+            &identity,
+        )
+        .unwrap();
+
+        assert!(pull(&path).unwrap());
+        assert_eq!(
+            git_in(&path, &["rev-parse", "HEAD"]),
+            git_in(origin.path(), &["rev-parse", "HEAD"])
+        );
+    }
+}
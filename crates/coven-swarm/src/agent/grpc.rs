@@ -2,7 +2,6 @@
 // ABOUTME: Handles registration, message receiving, and real-time response streaming.
 
 use anyhow::{Context, Result};
-use std::time::Duration;
 use tokio::sync::mpsc;
 use tonic::service::Interceptor;
 use tonic::transport::Channel;
@@ -10,7 +9,9 @@ use tonic::transport::Channel;
 // Use shared proto types from coven-proto
 pub use coven_proto::coven;
 
+use coven_grpc::{create_channel, ChannelConfig, KeepAliveConfig};
 use coven_proto::client::CovenControlClient;
+use coven_proto::limits::MessageLimits;
 use coven_proto::{AgentMessage, AgentMetadata, RegisterAgent, ToolDefinition};
 
 use super::pack_tool::{handle_pack_tool_result, PendingPackTools};
@@ -61,6 +62,10 @@ impl Interceptor for AuthInterceptor {
 /// Clone this and use it to send responses as they arrive from the backend.
 pub type ResponseSender = mpsc::Sender<coven::MessageResponse>;
 
+/// Called with the gateway's full pack tool list each time it changes after
+/// Welcome, along with a sender for ExecutePackTool messages.
+pub type ToolsUpdatedCallback = Box<dyn Fn(Vec<ToolDefinition>, mpsc::Sender<AgentMessage>) + Send>;
+
 /// Information extracted from Welcome message for configuring pack tools
 #[derive(Debug, Clone)]
 pub struct WelcomeInfo {
@@ -112,6 +117,8 @@ pub struct GatewayClient {
     workspace: String,
     working_dir: String,
     backend: String,
+    limits: MessageLimits,
+    on_tools_updated: Option<ToolsUpdatedCallback>,
 }
 
 impl GatewayClient {
@@ -121,20 +128,27 @@ impl GatewayClient {
         workspace: &str,
         working_dir: &str,
         backend: &str,
+        keep_alive: Option<&KeepAliveConfig>,
     ) -> Result<Self> {
-        // Load auth token
+        // Load auth token, renewed first if it's about to expire
+        if let Err(e) = coven_link::refresh_token_if_needed().await {
+            tracing::warn!(error = %format!("{:#}", e), "Can't refresh token");
+        }
         let token = load_token()?;
 
-        let channel = Channel::from_shared(gateway_url.to_string())?
-            .http2_keep_alive_interval(Duration::from_secs(10))
-            .keep_alive_timeout(Duration::from_secs(20))
-            .keep_alive_while_idle(true)
-            .connect()
+        let channel_config = ChannelConfig {
+            keep_alive: keep_alive.cloned(),
+            ..ChannelConfig::new(gateway_url)
+        };
+        let channel = create_channel(&channel_config)
             .await
             .context("Failed to connect to coven-gateway")?;
 
         let interceptor = AuthInterceptor { token };
-        let client = CovenControlClient::with_interceptor(channel, interceptor);
+        let limits = channel_config.message_limits;
+        let client = CovenControlClient::with_interceptor(channel, interceptor)
+            .max_decoding_message_size(limits.max_decoding)
+            .max_encoding_message_size(limits.max_encoding);
         let agent_id = format_agent_id(prefix, workspace);
 
         Ok(Self {
@@ -143,9 +157,21 @@ impl GatewayClient {
             workspace: workspace.to_string(),
             working_dir: working_dir.to_string(),
             backend: backend.to_string(),
+            limits,
+            on_tools_updated: None,
         })
     }
 
+    /// Call `callback` whenever the gateway pushes an updated pack tool list,
+    /// e.g. to register and unregister PackTool instances on a mux backend.
+    pub fn on_tools_updated(
+        mut self,
+        callback: impl Fn(Vec<ToolDefinition>, mpsc::Sender<AgentMessage>) + Send + 'static,
+    ) -> Self {
+        self.on_tools_updated = Some(Box::new(callback));
+        self
+    }
+
     /// Run the agent, handling messages from the gateway.
     ///
     /// The handler receives messages and a ResponseSender to stream responses
@@ -205,6 +231,7 @@ impl GatewayClient {
                     os: std::env::consts::OS.to_string(),
                     workspaces: vec![],
                     backend: self.backend.clone(),
+                    presence: None,
                 }),
                 protocol_features: vec!["pack_tools".to_string()],
             })),
@@ -217,11 +244,13 @@ impl GatewayClient {
 
         // Spawn task to forward responses from handlers to the gRPC stream
         let tx_clone = tx.clone();
+        let limits = self.limits;
         let response_forwarder = tokio::spawn(async move {
             while let Some(response) = resp_rx.recv().await {
-                let msg = AgentMessage {
+                // An oversize event would reset the stream; send an error in its place
+                let msg = limits.fit_agent_message(AgentMessage {
                     payload: Some(coven::agent_message::Payload::Response(response)),
-                };
+                });
                 if tx_clone.send(msg).await.is_err() {
                     tracing::warn!("Failed to send response - channel closed");
                     break;
@@ -326,6 +355,26 @@ impl GatewayClient {
                         tracing::debug!("Pack tool result received but no handler registered");
                     }
                 }
+                Some(coven::server_message::Payload::PackToolProgress(progress)) => {
+                    // Pack tools here only report their final result
+                    tracing::trace!(request_id = %progress.request_id, "Ignoring pack tool progress");
+                }
+                Some(coven::server_message::Payload::AvailableTools(update)) => {
+                    tracing::info!(tool_count = update.tools.len(), "Pack tools updated");
+                    if let Some(ref callback) = self.on_tools_updated {
+                        callback(update.tools, tx.clone());
+                    }
+                }
+                Some(coven::server_message::Payload::SetLogLevel(change)) => {
+                    match coven_log::set_level(&change.filter) {
+                        Ok(()) => tracing::info!(filter = %change.filter, "Log filter changed"),
+                        Err(e) => tracing::warn!(error = %e, "Kept the current log filter"),
+                    }
+                }
+                Some(coven::server_message::Payload::ForkThread(fork)) => {
+                    // Not sent, as swarm agents don't register the thread_fork feature
+                    tracing::warn!(thread_id = %fork.thread_id, "Ignoring thread fork");
+                }
                 None => {}
             }
         }
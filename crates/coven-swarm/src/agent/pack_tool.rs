@@ -1,10 +1,11 @@
 // ABOUTME: PackTool wraps pack tools received from the gateway for local execution.
-// ABOUTME: Routes tool calls through gRPC to the gateway for pack execution.
+// ABOUTME: Routes tool calls through gRPC to the gateway for pack execution and applies tool list updates.
 
 use async_trait::async_trait;
+use coven_core::backend::MuxBackend;
 use coven_proto::{agent_message, AgentMessage, ExecutePackTool, PackToolResult, ToolDefinition};
 use mux::tool::{Tool, ToolResult};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 use tokio::sync::{mpsc, oneshot, Mutex};
 use tracing::{info, warn};
@@ -201,3 +202,18 @@ pub async fn handle_pack_tool_result(pending: &PendingPackTools, result: PackToo
         false
     }
 }
+
+/// Apply the gateway's full pack tool list to a mux backend, see
+/// [`MuxBackend::sync_tools`]. Returns the names of the removed tools.
+pub async fn sync_pack_tools(
+    mux: &MuxBackend,
+    registered: &mut HashSet<String>,
+    tools: &[ToolDefinition],
+    tx: &mpsc::Sender<AgentMessage>,
+    pending: &PendingPackTools,
+) -> Vec<String> {
+    mux.sync_tools(registered, tools, |def| {
+        PackTool::new(def, tx.clone(), pending.clone())
+    })
+    .await
+}
@@ -1,10 +1,12 @@
-// ABOUTME: Session manages a backend and handles prompts.
+// ABOUTME: Session manages a backend and handles prompts, one message at a time.
 // ABOUTME: Bridges between gRPC messages and backend events with debounced streaming.
 
 use anyhow::Result;
 use coven_swarm_backend::{BackendEvent, BackendHandle};
 use futures::StreamExt;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
 
 use super::grpc::{coven, ResponseSender};
 
@@ -86,7 +88,9 @@ impl Session {
                                 return Ok(());
                             }
                         }
-                        BackendEvent::ToolApprovalRequest { id, name, input } => {
+                        BackendEvent::ToolApprovalRequest {
+                            id, name, input, ..
+                        } => {
                             // Swarm agents are autonomous - auto-approve all tools
                             tracing::debug!(
                                 tool_id = %id,
@@ -248,6 +252,38 @@ impl Session {
                         } => {
                             tracing::debug!(input_tokens, output_tokens, "Token usage");
                         }
+                        BackendEvent::Budget(report) => {
+                            // The backend logs alerts
+                            tracing::debug!(
+                                day_tokens = report.status.day_tokens,
+                                day_cost_usd = report.status.day_cost_usd,
+                                "Budget update"
+                            );
+                        }
+                        BackendEvent::BudgetExceeded(exceeded) => {
+                            // Flush any pending text before the refusal
+                            if !text_buffer.is_empty() {
+                                let resp = coven::MessageResponse {
+                                    request_id: request_id.clone(),
+                                    event: Some(coven::message_response::Event::Text(
+                                        std::mem::take(&mut text_buffer),
+                                    )),
+                                };
+                                let _ = tx.send(resp).await;
+                            }
+
+                            // The gateway learns of it as an error
+                            let resp = coven::MessageResponse {
+                                request_id: request_id.clone(),
+                                event: Some(coven::message_response::Event::Error(
+                                    exceeded.to_string(),
+                                )),
+                            };
+                            if tx.send(resp).await.is_err() {
+                                tracing::warn!("Failed to send response - channel closed");
+                                return Ok(());
+                            }
+                        }
                     }
                 }
 
@@ -286,3 +322,235 @@ impl Session {
         Ok(())
     }
 }
+
+/// A session shared by all of an agent's messages, handled in arrival
+/// order. A message that arrives while another is being handled waits its
+/// turn; with a busy reply configured, its sender hears so right away.
+pub struct SessionQueue {
+    session: Mutex<Session>,
+    /// Messages being handled or waiting to be
+    in_flight: AtomicUsize,
+    busy_reply: Option<String>,
+}
+
+/// Counts a message as in flight until dropped, even if its handler is
+/// cancelled while waiting
+struct InFlight<'a>(&'a AtomicUsize);
+
+impl Drop for InFlight<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+impl SessionQueue {
+    /// `busy_reply` is a template for the acknowledgment sent to queued
+    /// messages, as in `busy_reply_text`; None sends none
+    pub fn new(session: Session, busy_reply: Option<String>) -> Self {
+        Self {
+            session: Mutex::new(session),
+            in_flight: AtomicUsize::new(0),
+            busy_reply,
+        }
+    }
+
+    /// Handle `msg` once the messages ahead of it are done, acknowledging
+    /// it first if it has to wait
+    pub async fn handle_message(&self, msg: coven::SendMessage, tx: ResponseSender) -> Result<()> {
+        let ahead = self.in_flight.fetch_add(1, Ordering::SeqCst);
+        let _in_flight = InFlight(&self.in_flight);
+
+        if let Some(template) = self.busy_reply.as_ref().filter(|_| ahead > 0) {
+            tracing::info!(
+                request_id = %msg.request_id,
+                position = ahead,
+                "Agent busy, message queued"
+            );
+            // Informational only: the message is handled all the same, and
+            // the notice is kept out of its answer
+            let ack = coven::MessageResponse {
+                request_id: msg.request_id.clone(),
+                event: Some(coven::message_response::Event::Queued(coven::Queued {
+                    position: i32::try_from(ahead).unwrap_or(i32::MAX),
+                    notice: busy_reply_text(template, ahead),
+                })),
+            };
+            if tx.send(ack).await.is_err() {
+                tracing::warn!("Failed to send busy reply - channel closed");
+            }
+        }
+
+        let mut session = self.session.lock().await;
+        session.handle_message(msg, tx).await
+    }
+
+    /// Whether no message is being handled or waiting to be
+    pub fn is_idle(&self) -> bool {
+        self.in_flight.load(Ordering::SeqCst) == 0
+    }
+}
+
+/// The busy reply for a message at `position` in line (1 = next up):
+/// `template` with `{position}` filled in
+pub fn busy_reply_text(template: &str, position: usize) -> String {
+    template.replace("{position}", &position.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_trait::async_trait;
+    use coven_swarm_backend::Backend;
+    use futures::stream::BoxStream;
+    use std::sync::Arc;
+    use tokio::sync::{mpsc, Notify};
+
+    /// Answers "done" to each message, but not before `release` is notified
+    struct GatedBackend {
+        release: Arc<Notify>,
+    }
+
+    #[async_trait]
+    impl Backend for GatedBackend {
+        fn name(&self) -> &'static str {
+            "gated"
+        }
+
+        async fn send(
+            &self,
+            _session_id: &str,
+            _message: &str,
+            _is_new_session: bool,
+        ) -> Result<BoxStream<'static, BackendEvent>> {
+            self.release.notified().await;
+            Ok(Box::pin(futures::stream::iter([BackendEvent::Done {
+                full_response: "done".to_string(),
+            }])))
+        }
+    }
+
+    fn message(request_id: &str) -> coven::SendMessage {
+        coven::SendMessage {
+            request_id: request_id.to_string(),
+            content: "hi".to_string(),
+            ..Default::default()
+        }
+    }
+
+    fn queue(busy_reply: Option<&str>) -> (Arc<SessionQueue>, Arc<Notify>) {
+        let release = Arc::new(Notify::new());
+        let backend = GatedBackend {
+            release: release.clone(),
+        };
+        let session = Session::new(BackendHandle::new(backend));
+        (
+            Arc::new(SessionQueue::new(session, busy_reply.map(str::to_string))),
+            release,
+        )
+    }
+
+    /// Everything sent so far
+    fn drain(rx: &mut mpsc::Receiver<coven::MessageResponse>) -> Vec<coven::MessageResponse> {
+        let mut responses = Vec::new();
+        while let Ok(resp) = rx.try_recv() {
+            responses.push(resp);
+        }
+        responses
+    }
+
+    /// Events for `request_id`, as text: the text itself, "<queued N>
+    /// notice", or "<done>"
+    fn events(responses: &[coven::MessageResponse], request_id: &str) -> Vec<String> {
+        responses
+            .iter()
+            .filter(|resp| resp.request_id == request_id)
+            .map(|resp| match &resp.event {
+                Some(coven::message_response::Event::Text(text)) => text.clone(),
+                Some(coven::message_response::Event::Queued(queued)) => {
+                    format!("<queued {}> {}", queued.position, queued.notice)
+                }
+                Some(coven::message_response::Event::Done(_)) => "<done>".to_string(),
+                other => format!("{:?}", other),
+            })
+            .collect()
+    }
+
+    async fn wait_for_in_flight(queue: &SessionQueue, count: usize) {
+        while queue.in_flight.load(Ordering::SeqCst) < count {
+            tokio::task::yield_now().await;
+        }
+    }
+
+    #[tokio::test]
+    async fn test_queued_messages_are_acknowledged_with_their_position() {
+        let (queue, release) = queue(Some("Busy; you're number {position} in line."));
+        let (tx, mut rx) = mpsc::channel(32);
+
+        let first = tokio::spawn({
+            let (queue, tx) = (queue.clone(), tx.clone());
+            async move { queue.handle_message(message("req-1"), tx).await }
+        });
+        wait_for_in_flight(&queue, 1).await;
+        let second = tokio::spawn({
+            let (queue, tx) = (queue.clone(), tx.clone());
+            async move { queue.handle_message(message("req-2"), tx).await }
+        });
+        wait_for_in_flight(&queue, 2).await;
+        let third = tokio::spawn({
+            let (queue, tx) = (queue.clone(), tx.clone());
+            async move { queue.handle_message(message("req-3"), tx).await }
+        });
+        wait_for_in_flight(&queue, 3).await;
+
+        for task in [first, second, third] {
+            release.notify_one();
+            task.await.unwrap().unwrap();
+        }
+        // Each waiting message hears it's queued before its answer comes,
+        // in an event of its own rather than as answer text
+        let responses = drain(&mut rx);
+        assert_eq!(events(&responses, "req-1"), ["<done>"]);
+        assert_eq!(
+            events(&responses, "req-2"),
+            ["<queued 1> Busy; you're number 1 in line.", "<done>"]
+        );
+        assert_eq!(
+            events(&responses, "req-3"),
+            ["<queued 2> Busy; you're number 2 in line.", "<done>"]
+        );
+        assert_eq!(queue.in_flight.load(Ordering::SeqCst), 0);
+    }
+
+    #[tokio::test]
+    async fn test_no_busy_reply_by_default() {
+        let (queue, release) = queue(None);
+        let (tx, mut rx) = mpsc::channel(32);
+
+        let first = tokio::spawn({
+            let (queue, tx) = (queue.clone(), tx.clone());
+            async move { queue.handle_message(message("req-1"), tx).await }
+        });
+        wait_for_in_flight(&queue, 1).await;
+        let second = tokio::spawn({
+            let (queue, tx) = (queue.clone(), tx.clone());
+            async move { queue.handle_message(message("req-2"), tx).await }
+        });
+        wait_for_in_flight(&queue, 2).await;
+
+        for task in [first, second] {
+            release.notify_one();
+            task.await.unwrap().unwrap();
+        }
+        assert_eq!(events(&drain(&mut rx), "req-2"), ["<done>"]);
+        assert_eq!(queue.in_flight.load(Ordering::SeqCst), 0);
+    }
+
+    #[test]
+    fn test_busy_reply_text() {
+        assert_eq!(
+            busy_reply_text("Queued (position {position})", 2),
+            "Queued (position 2)"
+        );
+        assert_eq!(busy_reply_text("Busy, hang on", 1), "Busy, hang on");
+    }
+}
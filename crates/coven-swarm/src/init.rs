@@ -4,7 +4,6 @@
 use anyhow::{Context, Result};
 use coven_swarm_core::{BackendType, Config};
 use std::io::{self, Write};
-use std::path::PathBuf;
 
 fn prompt(message: &str, default: &str) -> Result<String> {
     print!("{} [{}]: ", message, default);
@@ -107,12 +106,25 @@ pub fn run_init() -> Result<()> {
     let config = Config {
         gateway_url: Some(gateway_url),
         prefix: prefix.clone(),
-        working_directory: working_directory.clone(),
+        working_directory,
         default_backend,
+        workspace_backends: Default::default(),
+        depends_on: Default::default(),
         acp_binary: "claude".to_string(),
+        acp_model: None,
+        cli_pool_size: None,
+        cli_max_restarts: None,
+        cli_restart_window_secs: None,
+        key_passphrase: None,
+        acp_env: Default::default(),
         global_soul_path: None,
         dispatch_soul_path: None,
         soul_files: vec!["soul.md".to_string(), ".coven/soul.md".to_string()],
+        busy_reply: None,
+        keepalive: None,
+        agent_logs: None,
+        git: None,
+        theme: None,
     };
 
     // Save config
@@ -121,7 +133,7 @@ pub fn run_init() -> Result<()> {
     println!("\nConfig written to {}", config_path.display());
 
     // Create dispatch workspace
-    let working_dir = PathBuf::from(shellexpand::tilde(&working_directory).into_owned());
+    let working_dir = config.working_directory_expanded()?;
     let dispatch_dir = working_dir.join("dispatch");
     std::fs::create_dir_all(&dispatch_dir)?;
     println!("Created dispatch workspace at {}", dispatch_dir.display());
@@ -5,19 +5,26 @@ pub mod agent;
 pub mod init;
 pub mod supervisor;
 
-pub use agent::{GatewayClient, Session};
+pub use agent::{GatewayClient, Session, SessionQueue};
 pub use coven_swarm_core::Config;
 pub use init::run_init;
 pub use supervisor::{
-    discover_workspaces, socket, AgentProcess, AgentStatus, SocketClient, SocketCommand,
-    StatusInfo, Tui, TuiEvent,
+    discover_workspaces, socket, AgentLog, AgentProcess, AgentStatus, ColorSupport, SocketClient,
+    SocketCommand, StatusInfo, Theme, Tui, TuiEvent,
 };
 
-use anyhow::Result;
-use std::collections::HashMap;
+use anyhow::{Context, Result};
+use coven_swarm_core::process::StatusReporter;
+use futures::stream::{FuturesUnordered, StreamExt};
+use std::collections::{HashMap, HashSet};
 use std::path::PathBuf;
 use std::sync::Arc;
-use tokio::sync::{mpsc, Mutex};
+use std::time::Duration;
+use tokio::sync::mpsc;
+
+/// How long the supervisor waits for an agent to register before starting
+/// the workspaces that depend on it anyway
+const DEPENDENCY_TIMEOUT: Duration = Duration::from_secs(120);
 
 /// Options for running the supervisor
 pub struct SupervisorOptions {
@@ -25,6 +32,8 @@ pub struct SupervisorOptions {
     pub config_path: Option<PathBuf>,
     /// Run in headless mode (no TUI)
     pub headless: bool,
+    /// Draw the TUI without colors, as NO_COLOR does
+    pub no_color: bool,
 }
 
 /// Options for running a swarm agent (internal, spawned by supervisor)
@@ -35,6 +44,9 @@ pub struct AgentOptions {
     pub dispatch_mode: bool,
     /// Path to configuration file
     pub config_path: Option<PathBuf>,
+    /// Where to tell the supervisor this agent has registered, from
+    /// `StatusReporter::from_env`
+    pub status: Option<StatusReporter>,
 }
 
 /// Run the supervisor daemon
@@ -43,13 +55,21 @@ pub async fn run_supervisor(options: SupervisorOptions) -> Result<()> {
         .config_path
         .unwrap_or_else(|| Config::default_path().expect("Failed to get default config path"));
     let config = Config::load(&config_path)?;
-    let working_dir = config.working_directory_expanded();
+    let working_dir = config.working_directory_expanded()?;
+    let agent_log = config.agent_logs().map(AgentLog::new);
 
     // Create TUI if not headless
     let tui_tx: Option<mpsc::Sender<TuiEvent>> = if options.headless {
         None
+    } else if !std::io::IsTerminal::is_terminal(&std::io::stdout()) {
+        tracing::warn!("stdout is not a terminal, running headless");
+        None
     } else {
-        match Tui::new() {
+        let theme = Theme::select(
+            config.theme.unwrap_or_default(),
+            ColorSupport::detect(options.no_color),
+        );
+        match Tui::new(theme) {
             Ok((tui, tx)) => {
                 // Run TUI in background
                 tokio::spawn(async move {
@@ -88,32 +108,79 @@ pub async fn run_supervisor(options: SupervisorOptions) -> Result<()> {
     // Spawn agents
     let mut agents: HashMap<String, AgentProcess> = HashMap::new();
 
-    for workspace in workspaces {
-        // dispatch workspace gets dispatch_mode=true
-        let dispatch_mode = workspace == "dispatch";
-        let mut agent = AgentProcess::new(workspace.clone(), config_path.clone(), dispatch_mode);
-        agent.spawn_with_tui(tui_tx.clone()).await?;
-        if let Some(ref tx) = tui_tx {
-            let _ = tx.try_send(TuiEvent::AgentSpawned {
-                workspace: workspace.clone(),
-                pid: agent.pid().unwrap_or(0),
-            });
-        }
-        agents.insert(workspace, agent);
+    // Check every workspace's backend before spawning any, so a typo fails
+    // the whole swarm up front instead of one agent after startup
+    for workspace in workspaces.iter().filter(|w| *w != "dispatch") {
+        config.backend_for(workspace)?;
     }
 
-    // Ensure dispatch exists (create if not discovered)
-    if !agents.contains_key("dispatch") {
-        std::fs::create_dir_all(working_dir.join("dispatch"))?;
-        let mut dispatch = AgentProcess::new("dispatch".to_string(), config_path.clone(), true);
-        dispatch.spawn_with_tui(tui_tx.clone()).await?;
-        if let Some(ref tx) = tui_tx {
-            let _ = tx.try_send(TuiEvent::AgentSpawned {
-                workspace: "dispatch".to_string(),
-                pid: dispatch.pid().unwrap_or(0),
-            });
+    // Dispatch starts first, creating its workspace if it wasn't discovered
+    std::fs::create_dir_all(working_dir.join("dispatch"))?;
+    let mut dispatch = AgentProcess::new("dispatch".to_string(), config_path.clone(), true)
+        .with_log(agent_log.clone());
+    dispatch.spawn_with_tui(tui_tx.clone()).await?;
+    if let Some(ref tx) = tui_tx {
+        let _ = tx.try_send(TuiEvent::AgentSpawned {
+            workspace: "dispatch".to_string(),
+            pid: dispatch.pid().unwrap_or(0),
+        });
+    }
+    agents.insert("dispatch".to_string(), dispatch);
+
+    // The rest start as soon as the workspaces they depend on have
+    // registered (or failed to), so without dependencies all start at once
+    let known: HashSet<String> = workspaces.iter().cloned().collect();
+    let depended_on: HashSet<&String> = config.depends_on.values().flatten().collect();
+    let mut pending: Vec<String> = workspaces.into_iter().filter(|w| w != "dispatch").collect();
+    let mut settled: HashSet<String> = ["dispatch".to_string()].into();
+    let mut registering = FuturesUnordered::new();
+    for (workspace, dependencies) in &config.depends_on {
+        for dependency in dependencies.iter().filter(|d| !known.contains(*d)) {
+            tracing::warn!(
+                workspace = %workspace,
+                dependency = %dependency,
+                "Dependency is not a workspace, not waiting for it"
+            );
         }
-        agents.insert("dispatch".to_string(), dispatch);
+    }
+    loop {
+        let ready = config.ready_to_start(&pending, &settled, &known);
+        pending.retain(|workspace| !ready.contains(workspace));
+        for workspace in ready {
+            let mut agent = AgentProcess::new(workspace.clone(), config_path.clone(), false)
+                .with_log(agent_log.clone());
+            agent.spawn_with_tui(tui_tx.clone()).await?;
+            if let Some(ref tx) = tui_tx {
+                let _ = tx.try_send(TuiEvent::AgentSpawned {
+                    workspace: workspace.clone(),
+                    pid: agent.pid().unwrap_or(0),
+                });
+            }
+            if depended_on.contains(&workspace) {
+                let wait = agent.wait_registered(DEPENDENCY_TIMEOUT);
+                let name = workspace.clone();
+                registering.push(async move { (name, wait.await) });
+            }
+            agents.insert(workspace, agent);
+        }
+        if pending.is_empty() {
+            break;
+        }
+        // Cycles are rejected on load, so something pending is always
+        // waiting on an agent still registering
+        let Some((workspace, result)) = registering.next().await else {
+            break;
+        };
+        if let Err(e) = result {
+            let message = format!("{:#}; starting its dependents anyway", e);
+            match tui_tx {
+                Some(ref tx) => {
+                    let _ = tx.try_send(TuiEvent::System { message });
+                }
+                None => tracing::warn!("{}", message),
+            }
+        }
+        settled.insert(workspace);
     }
 
     // Start socket server
@@ -165,7 +232,8 @@ pub async fn run_supervisor(options: SupervisorOptions) -> Result<()> {
                     continue;
                 }
                 std::fs::create_dir_all(working_dir.join(&name))?;
-                let mut agent = AgentProcess::new(name.clone(), config_path.clone(), false);
+                let mut agent = AgentProcess::new(name.clone(), config_path.clone(), false)
+                    .with_log(agent_log.clone());
                 if let Err(e) = agent.spawn_with_tui(tui_tx.clone()).await {
                     let _ = reply.send(Err(e));
                     continue;
@@ -250,21 +318,22 @@ pub async fn run_supervisor(options: SupervisorOptions) -> Result<()> {
 
 /// Run a swarm agent (internal, spawned by supervisor)
 pub async fn run_agent(options: AgentOptions) -> Result<()> {
-    use coven_core::backend::{DirectCliBackend, DirectCliConfig, MuxBackend, MuxConfig};
+    use coven_core::backend::{
+        CliRestartPolicy, DirectCliBackend, DirectCliConfig, MuxBackend, MuxConfig,
+        DEFAULT_CLI_POOL_SIZE,
+    };
     use coven_swarm_backend::dispatch_tools::{
         CreateWorkspaceTool, DeleteWorkspaceTool, ListAgentsTool,
     };
     use coven_swarm_backend::BackendHandle;
     use coven_swarm_core::BackendType;
 
-    use crate::agent::{new_pending_pack_tools, PackTool};
+    use crate::agent::{new_pending_pack_tools, sync_pack_tools};
+    use coven_proto::{AgentMessage, ToolDefinition};
+    use std::collections::HashSet;
 
-    // Load or generate SSH key
-    let key_path = coven_ssh::default_swarm_key_path()
-        .ok_or_else(|| anyhow::anyhow!("Could not determine SSH key path"))?;
-    let private_key = coven_ssh::load_or_generate_key(&key_path)?;
-    let fingerprint = coven_ssh::compute_fingerprint(private_key.public_key())?;
-    tracing::info!(fingerprint = %fingerprint, "SSH key loaded");
+    // Only there when the supervisor started this agent
+    let status = options.status;
 
     // Load config
     let config_path = options
@@ -272,7 +341,24 @@ pub async fn run_agent(options: AgentOptions) -> Result<()> {
         .unwrap_or_else(|| Config::default_path().expect("Failed to get default config path"));
     let config = Config::load(&config_path)?;
 
-    let working_dir = config.working_directory_expanded().join(&options.workspace);
+    // Load or generate SSH key, encrypted when the config names a passphrase
+    let key_path = coven_ssh::default_swarm_key_path()
+        .ok_or_else(|| anyhow::anyhow!("Could not determine SSH key path"))?;
+    let passphrase = config
+        .key_passphrase
+        .as_deref()
+        .map(coven_ssh::PassphraseSource::parse)
+        .transpose()
+        .context("Invalid key_passphrase in config")?;
+    let private_key =
+        coven_ssh::load_or_generate_key_with_passphrase(&key_path, passphrase.as_ref())?;
+    let fingerprint = coven_ssh::compute_fingerprint(private_key.public_key())?;
+    tracing::info!(fingerprint = %fingerprint, "SSH key loaded");
+
+    let working_dir = config
+        .working_directory_expanded()?
+        .join(&options.workspace);
+    let global_soul_path = config.global_soul_path_expanded()?;
 
     // Validate working directory exists
     if !working_dir.exists() {
@@ -282,6 +368,17 @@ pub async fn run_agent(options: AgentOptions) -> Result<()> {
         );
     }
 
+    // Optionally bring the workspace up to date before the backend sees it
+    if config.git.as_ref().is_some_and(|git| git.auto_pull) {
+        let dir = working_dir.clone();
+        match tokio::task::spawn_blocking(move || agent::git::pull(&dir)).await {
+            Ok(Ok(true)) => tracing::info!("Pulled workspace from upstream"),
+            Ok(Ok(false)) => {}
+            Ok(Err(e)) => tracing::warn!(error = %format!("{:#}", e), "Workspace pull failed"),
+            Err(e) => tracing::warn!(error = %e, "Workspace pull task failed"),
+        }
+    }
+
     // Track concrete backend types for pack tool setup
     // We keep Arc references so we can configure them in the on_welcome callback
     let mut mux_backend: Option<Arc<MuxBackend>> = None;
@@ -296,8 +393,8 @@ pub async fn run_agent(options: AgentOptions) -> Result<()> {
             working_dir: working_dir.clone(),
             global_system_prompt_path: None,
             local_prompt_files: vec!["claude.md".to_string(), "CLAUDE.md".to_string()],
-            global_soul_path: config.global_soul_path.as_ref().map(PathBuf::from),
-            agent_soul_path: config.dispatch_soul_path.as_ref().map(PathBuf::from),
+            global_soul_path: global_soul_path.clone(),
+            agent_soul_path: config.dispatch_soul_path_expanded()?,
             soul_files: config.soul_files.clone(),
             skip_default_tools: false,
             ..MuxConfig::default()
@@ -323,15 +420,25 @@ pub async fn run_agent(options: AgentOptions) -> Result<()> {
         let name = handle.name();
         (handle, name)
     } else {
-        // Normal workspace - use backend from config
-        match config.default_backend {
+        // Normal workspace - use the workspace's backend, else the default
+        match config.backend_for(&options.workspace)? {
             BackendType::Direct => {
                 // DirectCliBackend spawns Claude CLI subprocess
+                let restart = CliRestartPolicy::default();
                 let cli_config = DirectCliConfig {
                     binary: config.acp_binary.clone(), // reuse acp_binary setting
                     working_dir: working_dir.clone(),
                     timeout_secs: 300,
                     mcp_endpoint: None, // Set after receiving Welcome with mcp_token
+                    restart: CliRestartPolicy {
+                        max_restarts: config.cli_max_restarts.unwrap_or(restart.max_restarts),
+                        window: config
+                            .cli_restart_window_secs
+                            .map(Duration::from_secs)
+                            .unwrap_or(restart.window),
+                        ..restart
+                    },
+                    pool_size: config.cli_pool_size.unwrap_or(DEFAULT_CLI_POOL_SIZE),
                 };
                 let backend = Arc::new(DirectCliBackend::new(cli_config));
                 cli_backend = Some(backend.clone());
@@ -347,7 +454,7 @@ pub async fn run_agent(options: AgentOptions) -> Result<()> {
                     working_dir: working_dir.clone(),
                     global_system_prompt_path: None,
                     local_prompt_files: vec!["claude.md".to_string(), "CLAUDE.md".to_string()],
-                    global_soul_path: config.global_soul_path.as_ref().map(PathBuf::from),
+                    global_soul_path: global_soul_path.clone(),
                     agent_soul_path: None, // Per-agent soul loaded from working_dir
                     soul_files: config.soul_files.clone(),
                     skip_default_tools: false,
@@ -369,6 +476,12 @@ pub async fn run_agent(options: AgentOptions) -> Result<()> {
                         timeout_secs: 300,
                         working_dir: working_dir.clone(),
                         extra_args: vec![],
+                        extra_env: config
+                            .acp_env
+                            .iter()
+                            .map(|(k, v)| (k.clone(), v.clone()))
+                            .collect(),
+                        model: config.acp_model.clone(),
                     };
                     let backend = AcpBackend::new(acp_config);
                     let handle = BackendHandle::new(backend);
@@ -387,7 +500,7 @@ pub async fn run_agent(options: AgentOptions) -> Result<()> {
                         working_dir: working_dir.clone(),
                         global_system_prompt_path: None,
                         local_prompt_files: vec!["claude.md".to_string(), "CLAUDE.md".to_string()],
-                        global_soul_path: config.global_soul_path.as_ref().map(PathBuf::from),
+                        global_soul_path: global_soul_path.clone(),
                         agent_soul_path: None, // Per-agent soul loaded from working_dir
                         soul_files: config.soul_files.clone(),
                         skip_default_tools: false,
@@ -410,7 +523,18 @@ pub async fn run_agent(options: AgentOptions) -> Result<()> {
         None
     };
 
-    let session = Arc::new(Mutex::new(Session::new(handle)));
+    let session = Arc::new(SessionQueue::new(
+        Session::new(handle),
+        config.busy_reply.clone(),
+    ));
+    let checkpoints = config.git.clone().filter(|git| git.auto_commit).map(|git| {
+        agent::git::spawn_checkpoints(
+            git,
+            working_dir.clone(),
+            options.workspace.clone(),
+            Arc::clone(&session),
+        )
+    });
 
     let gateway_url = config.gateway_url()?;
 
@@ -423,29 +547,64 @@ pub async fn run_agent(options: AgentOptions) -> Result<()> {
         "Starting agent"
     );
 
+    // Pack tool lists from Welcome and later updates, applied to the mux
+    // backend in the order they arrive
+    let (tool_lists_tx, mut tool_lists_rx) =
+        mpsc::unbounded_channel::<(Vec<ToolDefinition>, mpsc::Sender<AgentMessage>)>();
+    if let (Some(mux), Some(pending)) = (mux_backend.clone(), pending_pack_tools.clone()) {
+        tokio::spawn(async move {
+            let mut registered = HashSet::new();
+            while let Some((tools, grpc_tx)) = tool_lists_rx.recv().await {
+                let removed =
+                    sync_pack_tools(&mux, &mut registered, &tools, &grpc_tx, &pending).await;
+                tracing::info!(
+                    tool_count = tools.len(),
+                    removed = ?removed,
+                    "Synced pack tools with mux backend"
+                );
+            }
+        });
+    }
+
     // Connect to gateway
-    let client = GatewayClient::connect(
+    let mut client = GatewayClient::connect(
         &gateway_url,
         &config.prefix,
         &options.workspace,
         &working_dir.to_string_lossy(),
         backend_name,
+        config.keep_alive()?.as_ref(),
     )
     .await?;
+    if mux_backend.is_some() {
+        let tool_lists_tx = tool_lists_tx.clone();
+        client = client.on_tools_updated(move |tools, grpc_tx| {
+            let _ = tool_lists_tx.send((tools, grpc_tx));
+        });
+    }
 
     // Run the agent with pack tool support
-    let pending_for_callback = pending_pack_tools.clone();
-    client
+    let result = client
         .run_with_pack_tools(
             |msg, tx| {
                 let session = Arc::clone(&session);
-                async move {
-                    let mut session = session.lock().await;
-                    session.handle_message(msg, tx).await
-                }
+                async move { session.handle_message(msg, tx).await }
             },
             pending_pack_tools,
             move |welcome_info, grpc_tx| {
+                // Tells the supervisor this agent is up, for workspaces
+                // that depend on it
+                if let Some(status) = &status {
+                    let report = format!(
+                        "{}{}",
+                        supervisor::REGISTERED_STATUS,
+                        welcome_info.instance_id
+                    );
+                    if let Err(e) = status.report(&report) {
+                        tracing::warn!(error = %e, "Failed to tell the supervisor of registration");
+                    }
+                }
+
                 // Configure pack tools based on backend type
                 let tool_count = welcome_info.available_tools.len();
 
@@ -461,34 +620,22 @@ pub async fn run_agent(options: AgentOptions) -> Result<()> {
                     } else {
                         tracing::warn!("No MCP endpoint available - pack tools will not work");
                     }
-                } else if let Some(ref mux) = mux_backend {
-                    // Mux backend: Register PackTool instances for gRPC-routed execution
-                    if tool_count > 0 {
-                        if let Some(ref pending) = pending_for_callback {
-                            tracing::info!(
-                                tool_count = tool_count,
-                                "Registering pack tools for mux backend"
-                            );
-
-                            // We need to spawn this because register_tool is async
-                            // but on_welcome is a sync callback
-                            let mux = mux.clone();
-                            let pending = pending.clone();
-                            let tools = welcome_info.available_tools.clone();
-                            tokio::spawn(async move {
-                                for tool_def in &tools {
-                                    let pack_tool =
-                                        PackTool::new(tool_def, grpc_tx.clone(), pending.clone());
-                                    tracing::debug!(tool = %tool_def.name, "Registering pack tool");
-                                    mux.register_tool(pack_tool).await;
-                                }
-                            });
-                        }
-                    }
+                } else if mux_backend.is_some() {
+                    // Mux backend: Register PackTool instances for gRPC-routed execution.
+                    // The sync task does the async registration, since on_welcome is a
+                    // sync callback
+                    tracing::info!(
+                        tool_count = tool_count,
+                        "Registering pack tools for mux backend"
+                    );
+                    let _ = tool_lists_tx.send((welcome_info.available_tools, grpc_tx));
                 }
             },
         )
-        .await?;
+        .await;
 
-    Ok(())
+    if let Some(checkpoints) = checkpoints {
+        checkpoints.abort();
+    }
+    result
 }
@@ -3,6 +3,7 @@
 
 use clap::{Parser, Subcommand};
 use coven_swarm::{run_agent, run_init, run_supervisor, AgentOptions, SupervisorOptions};
+use coven_swarm_core::process::StatusReporter;
 use std::path::PathBuf;
 
 #[derive(Parser)]
@@ -25,6 +26,9 @@ enum Commands {
         /// Headless mode (minimal output, no TUI)
         #[arg(long)]
         headless: bool,
+        /// Draw the TUI without colors (also set by NO_COLOR)
+        #[arg(long)]
+        no_color: bool,
     },
     /// Run a single workspace agent (internal, spawned by supervisor)
     Agent {
@@ -40,12 +44,24 @@ enum Commands {
     },
 }
 
-#[tokio::main]
-async fn main() -> anyhow::Result<()> {
+fn main() -> anyhow::Result<()> {
+    // Clears an environment variable, so it's taken before the runtime
+    // starts any threads
+    let status = StatusReporter::from_env();
+    tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()?
+        .block_on(run(status))
+}
+
+async fn run(status: Option<StatusReporter>) -> anyhow::Result<()> {
     // Load .env file if present (ignore errors if not found)
     let _ = dotenvy::dotenv();
 
     coven_log::init();
+    // Re-read ~/.config/coven/log-level on SIGHUP
+    #[cfg(unix)]
+    coven_log::reload_on_sighup();
 
     let cli = Cli::parse();
 
@@ -54,10 +70,12 @@ async fn main() -> anyhow::Result<()> {
         Commands::Supervisor {
             config: config_path,
             headless,
+            no_color,
         } => {
             run_supervisor(SupervisorOptions {
                 config_path,
                 headless,
+                no_color,
             })
             .await
         }
@@ -70,6 +88,7 @@ async fn main() -> anyhow::Result<()> {
                 workspace,
                 dispatch_mode,
                 config_path,
+                status,
             })
             .await
         }
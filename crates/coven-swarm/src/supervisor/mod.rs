@@ -4,9 +4,11 @@
 pub mod discover;
 pub mod socket;
 pub mod spawn;
+pub mod theme;
 pub mod tui;
 
 pub use discover::discover_workspaces;
 pub use socket::{AgentStatus, Request, Response, SocketClient, SocketCommand, StatusInfo};
-pub use spawn::AgentProcess;
+pub use spawn::{AgentLog, AgentProcess, REGISTERED_STATUS};
+pub use theme::{ColorSupport, Theme};
 pub use tui::{Tui, TuiEvent};
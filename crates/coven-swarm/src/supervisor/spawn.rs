@@ -1,20 +1,63 @@
 // ABOUTME: Spawns and manages workspace agent child processes.
 // ABOUTME: Tracks process state and handles restarts.
+// ABOUTME: Copies each agent's output to a rotating <workspace>.log alongside the TUI or stderr.
 
 use super::tui::TuiEvent;
 use anyhow::{Context, Result};
+use coven_log::{RollingFile, RotationPolicy};
+use coven_swarm_core::config::AgentLogSettings;
+use coven_swarm_core::process::ManagedChild;
+use coven_swarm_core::Config;
+use std::io::Write;
 use std::path::PathBuf;
-use std::process::Stdio;
-use tokio::io::{AsyncBufReadExt, BufReader};
-use tokio::process::{Child, Command};
-use tokio::sync::mpsc;
+use std::time::Duration;
+use tokio::process::Command;
+use tokio::sync::{mpsc, watch};
+use tokio::task::JoinHandle;
+
+/// How long `kill` waits for an agent's last output to be written before
+/// dropping the rest
+const DRAIN_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Start of the status an agent reports once it has registered with the
+/// gateway, followed by its instance ID. Agents report on a status pipe
+/// of their own, which they only have when this supervisor started them.
+pub const REGISTERED_STATUS: &str = "registered instance=";
+
+/// Where agents' output is written besides the TUI or stderr
+#[derive(Debug, Clone)]
+pub struct AgentLog {
+    pub dir: PathBuf,
+    pub policy: RotationPolicy,
+}
+
+impl AgentLog {
+    /// Logs in `Config::agent_log_dir()`, rotated by size only
+    pub fn new(settings: AgentLogSettings) -> Self {
+        Self {
+            dir: Config::agent_log_dir(),
+            policy: RotationPolicy {
+                max_size: settings.max_size_mb.max(1).saturating_mul(1024 * 1024),
+                max_age: Duration::MAX,
+                keep: settings.keep,
+                compress: false,
+            },
+        }
+    }
+}
 
 pub struct AgentProcess {
     pub workspace: String,
     pub dispatch_mode: bool,
-    child: Option<Child>,
+    child: Option<ManagedChild>,
     config_path: PathBuf,
     pid: Option<u32>,
+    log: Option<AgentLog>,
+    /// Forwards the child's output; holds the log file open until it ends
+    output: Option<JoinHandle<()>>,
+    /// Whether the agent has registered with the gateway; the sender is
+    /// dropped when its status pipe closes
+    registered: Option<watch::Receiver<bool>>,
 }
 
 impl AgentProcess {
@@ -25,9 +68,18 @@ impl AgentProcess {
             child: None,
             config_path,
             pid: None,
+            log: None,
+            output: None,
+            registered: None,
         }
     }
 
+    /// Also write the agent's output to `<dir>/<workspace>.log`
+    pub fn with_log(mut self, log: Option<AgentLog>) -> Self {
+        self.log = log;
+        self
+    }
+
     pub fn pid(&self) -> Option<u32> {
         self.pid
     }
@@ -54,68 +106,56 @@ impl AgentProcess {
             .arg("--workspace")
             .arg(&self.workspace)
             .arg("--config")
-            .arg(&self.config_path)
-            .stdout(Stdio::piped())
-            .stderr(Stdio::piped())
-            .kill_on_drop(true);
+            .arg(&self.config_path);
 
         if self.dispatch_mode {
             cmd.arg("--dispatch-mode");
         }
 
-        let mut child = cmd
-            .spawn()
+        // Forward stdout/stderr with workspace prefix, and status apart
+        let (line_tx, line_rx) = mpsc::channel::<String>(256);
+        let (status_tx, status_rx) = mpsc::channel::<String>(16);
+        let child = ManagedChild::spawn_with_status(cmd, line_tx, status_tx)
             .with_context(|| format!("Failed to spawn agent for {}", self.workspace))?;
 
-        self.pid = child.id();
+        self.pid = child.pid();
 
         if tui_tx.is_none() {
-            tracing::info!(workspace = %self.workspace, pid = ?child.id(), "Spawned agent");
+            tracing::info!(workspace = %self.workspace, pid = ?child.pid(), "Spawned agent");
         }
 
-        // Spawn tasks to forward stdout/stderr with workspace prefix
-        let workspace_name = self.workspace.clone();
-        if let Some(stdout) = child.stdout.take() {
-            let ws = workspace_name.clone();
-            let tx = tui_tx.clone();
-            tokio::spawn(async move {
-                let reader = BufReader::new(stdout);
-                let mut lines = reader.lines();
-                while let Ok(Some(line)) = lines.next_line().await {
-                    if let Some(ref tx) = tx {
-                        let _ = tx
-                            .send(TuiEvent::AgentLog {
-                                workspace: ws.clone(),
-                                line: line.clone(),
-                            })
-                            .await;
-                    } else {
-                        eprintln!("[{}] {}", ws, line);
+        // A log that can't be opened shouldn't keep the agent from running
+        let file = self.log.as_ref().and_then(|log| {
+            match RollingFile::open(&log.dir, &self.workspace, log.policy.clone()) {
+                Ok(file) => Some(file),
+                Err(e) => {
+                    let message = format!(
+                        "Failed to open agent log for {} in {}: {}",
+                        self.workspace,
+                        log.dir.display(),
+                        e
+                    );
+                    match tui_tx {
+                        Some(ref tx) => {
+                            let _ = tx.try_send(TuiEvent::System { message });
+                        }
+                        None => tracing::warn!("{}", message),
                     }
+                    None
                 }
-            });
-        }
+            }
+        });
 
-        if let Some(stderr) = child.stderr.take() {
-            let ws = workspace_name;
-            let tx = tui_tx;
-            tokio::spawn(async move {
-                let reader = BufReader::new(stderr);
-                let mut lines = reader.lines();
-                while let Ok(Some(line)) = lines.next_line().await {
-                    if let Some(ref tx) = tx {
-                        let _ = tx
-                            .send(TuiEvent::AgentLog {
-                                workspace: ws.clone(),
-                                line: line.clone(),
-                            })
-                            .await;
-                    } else {
-                        eprintln!("[{}] {}", ws, line);
-                    }
-                }
-            });
-        }
+        let ws = self.workspace.clone();
+        let (registered_tx, registered_rx) = watch::channel(false);
+        self.registered = Some(registered_rx);
+        tokio::spawn(forward_status(
+            ws.clone(),
+            status_rx,
+            tui_tx.clone(),
+            registered_tx,
+        ));
+        self.output = Some(tokio::spawn(forward_output(ws, line_rx, tui_tx, file)));
 
         self.child = Some(child);
         Ok(())
@@ -127,6 +167,31 @@ impl AgentProcess {
         self.spawn_with_tui(None).await
     }
 
+    /// Wait until the agent has registered with the gateway, up to
+    /// `timeout`. Fails if it exits first, times out, or was never spawned.
+    /// The wait doesn't borrow the agent, so it can be moved meanwhile.
+    pub fn wait_registered(
+        &self,
+        timeout: Duration,
+    ) -> impl std::future::Future<Output = Result<()>> + Send + 'static {
+        let registered = self.registered.clone();
+        let workspace = self.workspace.clone();
+        async move {
+            let Some(mut registered) = registered else {
+                anyhow::bail!("agent for {} was not spawned", workspace);
+            };
+            match tokio::time::timeout(timeout, registered.wait_for(|done| *done)).await {
+                Ok(Ok(_)) => Ok(()),
+                Ok(Err(_)) => anyhow::bail!("agent for {} exited before registering", workspace),
+                Err(_) => anyhow::bail!(
+                    "agent for {} did not register within {}s",
+                    workspace,
+                    timeout.as_secs()
+                ),
+            }
+        }
+    }
+
     pub fn is_running(&self) -> bool {
         // If we have a pid, assume the process is running
         // Accurate check would require try_wait but that requires &mut
@@ -141,10 +206,180 @@ impl AgentProcess {
         }
     }
 
+    /// Kill the agent, then close its log once its last output is written
     pub async fn kill(&mut self) -> Result<()> {
         if let Some(child) = &mut self.child {
             child.kill().await?;
         }
+        if let Some(mut output) = self.output.take() {
+            // Output ends when the agent's pipes close; a grandchild still
+            // holding them open mustn't keep the log file open too
+            if tokio::time::timeout(DRAIN_TIMEOUT, &mut output)
+                .await
+                .is_err()
+            {
+                output.abort();
+                let _ = output.await;
+            }
+        }
         Ok(())
     }
 }
+
+/// Send each line of an agent's output to the TUI, or stderr with a
+/// workspace prefix, and to its log file. Ends when the agent's output does.
+async fn forward_output(
+    ws: String,
+    mut line_rx: mpsc::Receiver<String>,
+    tui_tx: Option<mpsc::Sender<TuiEvent>>,
+    mut file: Option<RollingFile>,
+) {
+    while let Some(line) = line_rx.recv().await {
+        if let Some(log) = &mut file {
+            if let Err(e) = writeln!(log, "{}", line) {
+                tracing::warn!(workspace = %ws, error = %e, "Failed to write agent log; stopping it");
+                file = None;
+            }
+        }
+        if let Some(ref tx) = tui_tx {
+            let _ = tx
+                .send(TuiEvent::AgentLog {
+                    workspace: ws.clone(),
+                    line,
+                })
+                .await;
+        } else {
+            eprintln!("[{}] {}", ws, line);
+        }
+    }
+}
+
+/// Flag `registered`, and tell the TUI, when an agent reports registering
+/// on its status pipe. Ends when the pipe closes, as the agent exits.
+async fn forward_status(
+    ws: String,
+    mut status_rx: mpsc::Receiver<String>,
+    tui_tx: Option<mpsc::Sender<TuiEvent>>,
+    registered: watch::Sender<bool>,
+) {
+    while let Some(status) = status_rx.recv().await {
+        let Some(instance_id) = status.strip_prefix(REGISTERED_STATUS) else {
+            tracing::debug!(workspace = %ws, status = %status, "Ignoring unknown agent status");
+            continue;
+        };
+        registered.send_replace(true);
+        match tui_tx {
+            Some(ref tx) => {
+                let _ = tx
+                    .send(TuiEvent::AgentRegistered {
+                        workspace: ws.clone(),
+                        instance_id: instance_id.to_string(),
+                    })
+                    .await;
+            }
+            None => tracing::info!(workspace = %ws, instance_id, "Agent registered"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_output_goes_to_rotating_log() {
+        let dir = tempfile::tempdir().unwrap();
+        let policy = RotationPolicy {
+            max_size: 64,
+            max_age: Duration::MAX,
+            keep: 2,
+            compress: false,
+        };
+        let file = RollingFile::open(dir.path(), "research", policy.clone()).unwrap();
+        let (line_tx, line_rx) = mpsc::channel(16);
+        let (tui_tx, mut tui_rx) = mpsc::channel(16);
+        let output = tokio::spawn(forward_output(
+            "research".to_string(),
+            line_rx,
+            Some(tui_tx),
+            Some(file),
+        ));
+        for n in 0..10 {
+            line_tx
+                .send(format!("line {:02} of output", n))
+                .await
+                .unwrap();
+        }
+        drop(line_tx);
+        output.await.unwrap();
+
+        let log = std::fs::read_to_string(dir.path().join("research.log")).unwrap();
+        assert!(log.ends_with("line 09 of output\n"), "{log}");
+        assert!(dir.path().join("research.log.2").exists());
+        assert!(!dir.path().join("research.log.3").exists());
+        let mut seen = 0;
+        while let Ok(TuiEvent::AgentLog { workspace, .. }) = tui_rx.try_recv() {
+            assert_eq!(workspace, "research");
+            seen += 1;
+        }
+        assert_eq!(seen, 10);
+
+        // Once forwarding ends the log is free for a respawned agent
+        let reopened = RollingFile::open(dir.path(), "research", policy).unwrap();
+        assert_eq!(reopened.path(), dir.path().join("research.log"));
+    }
+
+    #[tokio::test]
+    async fn test_registration_status_flags_agent_registered() {
+        let (status_tx, status_rx) = mpsc::channel(16);
+        let (tui_tx, mut tui_rx) = mpsc::channel(16);
+        let (registered_tx, mut registered_rx) = watch::channel(false);
+        let status = tokio::spawn(forward_status(
+            "db".to_string(),
+            status_rx,
+            Some(tui_tx),
+            registered_tx,
+        ));
+
+        status_tx.send("connecting".to_string()).await.unwrap();
+        assert!(!*registered_rx.borrow());
+        status_tx
+            .send(format!("{}inst-1", REGISTERED_STATUS))
+            .await
+            .unwrap();
+        registered_rx.wait_for(|done| *done).await.unwrap();
+        drop(status_tx);
+        status.await.unwrap();
+
+        assert!(matches!(
+            tui_rx.try_recv().unwrap(),
+            TuiEvent::AgentRegistered { workspace, instance_id }
+                if workspace == "db" && instance_id == "inst-1"
+        ));
+        assert!(tui_rx.try_recv().is_err());
+        // Once the status pipe closes, waiting for registration stops too
+        assert!(registered_rx.changed().await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_output_cannot_pass_for_registration() {
+        let (line_tx, line_rx) = mpsc::channel(16);
+        let (tui_tx, mut tui_rx) = mpsc::channel(16);
+        let output = tokio::spawn(forward_output(
+            "db".to_string(),
+            line_rx,
+            Some(tui_tx),
+            None,
+        ));
+
+        let line = format!("{}inst-1", REGISTERED_STATUS);
+        line_tx.send(line.clone()).await.unwrap();
+        drop(line_tx);
+        output.await.unwrap();
+
+        assert!(matches!(
+            tui_rx.try_recv().unwrap(),
+            TuiEvent::AgentLog { line: logged, .. } if logged == line
+        ));
+    }
+}
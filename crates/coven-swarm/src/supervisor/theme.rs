@@ -0,0 +1,209 @@
+// ABOUTME: Color themes for the supervisor TUI, degraded to what the terminal can show.
+// ABOUTME: Honors NO_COLOR and --no-color by rendering plain text with bold only.
+
+use coven_agent::single::theme::{
+    ACCENT_CORAL, ACCENT_SAGE, ACCENT_SKY, DIM_INK, ERROR_RUBY, MUTED_PAPER, SOFT_PAPER,
+    SUCCESS_JADE, WARNING_AMBER,
+};
+use coven_swarm_core::config::TuiTheme;
+use ratatui::style::{Color, Modifier, Style};
+
+/// What a piece of the TUI means, so themes can color it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Role {
+    /// Titles and system messages
+    Accent,
+    /// Spawned and connected agents
+    Success,
+    /// Workspaces and registrations
+    Info,
+    /// Messages, key hints and the dispatch agent
+    Warning,
+    Error,
+    /// Exited agents
+    Special,
+    /// Agent names
+    Text,
+    /// Secondary details such as uptime
+    Muted,
+    /// Borders, timestamps and IDs
+    Dim,
+}
+
+/// Colors for each role
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Palette {
+    accent: Color,
+    success: Color,
+    info: Color,
+    warning: Color,
+    error: Color,
+    special: Color,
+    text: Color,
+    muted: Color,
+    dim: Color,
+}
+
+/// The terminal's ANSI colors, as the supervisor has always used
+const DEFAULT_PALETTE: Palette = Palette {
+    accent: Color::Cyan,
+    success: Color::Green,
+    info: Color::Blue,
+    warning: Color::Yellow,
+    error: Color::Red,
+    special: Color::Magenta,
+    text: Color::White,
+    muted: Color::Gray,
+    dim: Color::DarkGray,
+};
+
+/// The neo-terminal palette of coven-agent's single mode
+const NEO_PALETTE: Palette = Palette {
+    accent: SUCCESS_JADE,
+    success: ACCENT_SAGE,
+    info: ACCENT_SKY,
+    warning: WARNING_AMBER,
+    error: ERROR_RUBY,
+    special: ACCENT_CORAL,
+    text: SOFT_PAPER,
+    muted: MUTED_PAPER,
+    dim: DIM_INK,
+};
+
+/// Colors the terminal can show
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorSupport {
+    /// None: NO_COLOR is set, `--no-color` was given, or the terminal is dumb
+    None,
+    /// The 16 ANSI colors
+    Basic,
+    /// 24-bit RGB
+    TrueColor,
+}
+
+impl ColorSupport {
+    /// Work out color support from the environment. `no_color` is the
+    /// `--no-color` flag.
+    pub fn detect(no_color: bool) -> Self {
+        if no_color {
+            return ColorSupport::None;
+        }
+        Self::from_env(
+            std::env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty()),
+            std::env::var("TERM").ok().as_deref(),
+            std::env::var("COLORTERM").ok().as_deref(),
+        )
+    }
+
+    fn from_env(no_color: bool, term: Option<&str>, colorterm: Option<&str>) -> Self {
+        if no_color {
+            return ColorSupport::None;
+        }
+        match term.map(str::trim) {
+            None | Some("") | Some("dumb") => ColorSupport::None,
+            _ if matches!(colorterm, Some("truecolor") | Some("24bit")) => ColorSupport::TrueColor,
+            _ => ColorSupport::Basic,
+        }
+    }
+}
+
+/// How the supervisor TUI is drawn
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Theme {
+    /// None renders plain text
+    palette: Option<Palette>,
+}
+
+impl Theme {
+    /// The `theme` asked for, as far as the terminal can show it
+    pub fn select(theme: TuiTheme, support: ColorSupport) -> Self {
+        let palette = match (theme, support) {
+            (TuiTheme::Plain, _) | (_, ColorSupport::None) => None,
+            (TuiTheme::Neo, ColorSupport::TrueColor) => Some(NEO_PALETTE),
+            (TuiTheme::Neo, ColorSupport::Basic) | (TuiTheme::Default, _) => Some(DEFAULT_PALETTE),
+        };
+        Self { palette }
+    }
+
+    /// Whether anything is colored
+    pub fn is_plain(&self) -> bool {
+        self.palette.is_none()
+    }
+
+    /// Text in `role`'s color
+    pub fn fg(&self, role: Role) -> Style {
+        let Some(palette) = &self.palette else {
+            return Style::default();
+        };
+        let color = match role {
+            Role::Accent => palette.accent,
+            Role::Success => palette.success,
+            Role::Info => palette.info,
+            Role::Warning => palette.warning,
+            Role::Error => palette.error,
+            Role::Special => palette.special,
+            Role::Text => palette.text,
+            Role::Muted => palette.muted,
+            Role::Dim => palette.dim,
+        };
+        Style::default().fg(color)
+    }
+
+    /// Bold text in `role`'s color; plain themes keep the bold
+    pub fn bold(&self, role: Role) -> Style {
+        self.fg(role).add_modifier(Modifier::BOLD)
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self::select(TuiTheme::Default, ColorSupport::Basic)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_color_support_from_env() {
+        let detect = ColorSupport::from_env;
+        assert_eq!(
+            detect(false, Some("xterm-256color"), None),
+            ColorSupport::Basic
+        );
+        assert_eq!(
+            detect(false, Some("xterm-256color"), Some("truecolor")),
+            ColorSupport::TrueColor
+        );
+        assert_eq!(
+            detect(true, Some("xterm-256color"), Some("truecolor")),
+            ColorSupport::None
+        );
+        assert_eq!(detect(false, Some("dumb"), None), ColorSupport::None);
+        assert_eq!(detect(false, None, None), ColorSupport::None);
+        assert_eq!(ColorSupport::detect(true), ColorSupport::None);
+    }
+
+    #[test]
+    fn test_themes_degrade_to_the_terminal() {
+        let neo = Theme::select(TuiTheme::Neo, ColorSupport::TrueColor);
+        assert_eq!(neo.fg(Role::Error).fg, Some(NEO_PALETTE.error));
+        assert_eq!(
+            Theme::select(TuiTheme::Neo, ColorSupport::Basic),
+            Theme::default()
+        );
+        assert_eq!(Theme::default().fg(Role::Error).fg, Some(Color::Red));
+
+        for theme in [TuiTheme::Default, TuiTheme::Neo, TuiTheme::Plain] {
+            let plain = Theme::select(theme, ColorSupport::None);
+            assert!(plain.is_plain());
+            assert_eq!(plain.fg(Role::Accent), Style::default());
+            assert!(plain
+                .bold(Role::Accent)
+                .add_modifier
+                .contains(Modifier::BOLD));
+        }
+        assert!(Theme::select(TuiTheme::Plain, ColorSupport::TrueColor).is_plain());
+    }
+}
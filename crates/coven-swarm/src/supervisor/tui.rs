@@ -1,6 +1,7 @@
 // ABOUTME: TUI display for supervisor showing workspace agent activity.
-// ABOUTME: Uses ratatui for terminal rendering with color-coded log blocks.
+// ABOUTME: Uses ratatui for terminal rendering with log blocks colored by the selected theme.
 
+use super::theme::{Role, Theme};
 use crossterm::{
     event::{self, Event, KeyCode, KeyModifiers},
     execute,
@@ -9,7 +10,6 @@ use crossterm::{
 use ratatui::{
     backend::CrosstermBackend,
     layout::{Constraint, Direction, Layout, Rect},
-    style::{Color, Modifier, Style},
     text::{Line, Span},
     widgets::{Block, Borders, List, ListItem, Paragraph},
     Frame, Terminal,
@@ -71,16 +71,16 @@ enum LogKind {
 }
 
 impl LogKind {
-    fn color(&self) -> Color {
+    fn role(&self) -> Role {
         match self {
-            LogKind::System => Color::Cyan,
-            LogKind::Spawned => Color::Green,
-            LogKind::Registered => Color::Blue,
-            LogKind::Message => Color::Yellow,
-            LogKind::Log => Color::White,
-            LogKind::Error => Color::Red,
-            LogKind::Exited => Color::Magenta,
-            LogKind::Socket => Color::Gray,
+            LogKind::System => Role::Accent,
+            LogKind::Spawned => Role::Success,
+            LogKind::Registered => Role::Info,
+            LogKind::Message => Role::Warning,
+            LogKind::Log => Role::Text,
+            LogKind::Error => Role::Error,
+            LogKind::Exited => Role::Special,
+            LogKind::Socket => Role::Muted,
         }
     }
 
@@ -119,13 +119,13 @@ enum AgentStatus {
 }
 
 impl AgentStatus {
-    fn color(&self) -> Color {
+    fn role(&self) -> Role {
         match self {
-            AgentStatus::Starting => Color::Yellow,
-            AgentStatus::Connected => Color::Green,
-            AgentStatus::Active => Color::Cyan,
-            AgentStatus::Error => Color::Red,
-            AgentStatus::Exited => Color::Gray,
+            AgentStatus::Starting => Role::Warning,
+            AgentStatus::Connected => Role::Success,
+            AgentStatus::Active => Role::Accent,
+            AgentStatus::Error => Role::Error,
+            AgentStatus::Exited => Role::Muted,
         }
     }
 
@@ -149,11 +149,12 @@ pub struct Tui {
     max_logs: usize,
     event_rx: mpsc::Receiver<TuiEvent>,
     start_time: Instant,
+    theme: Theme,
 }
 
 impl Tui {
-    /// Create a new TUI and return the event sender
-    pub fn new() -> io::Result<(Self, mpsc::Sender<TuiEvent>)> {
+    /// Create a new TUI drawn with `theme` and return the event sender
+    pub fn new(theme: Theme) -> io::Result<(Self, mpsc::Sender<TuiEvent>)> {
         enable_raw_mode()?;
         let mut stdout = io::stdout();
         execute!(stdout, EnterAlternateScreen)?;
@@ -171,6 +172,7 @@ impl Tui {
                 max_logs: 1000,
                 event_rx,
                 start_time: Instant::now(),
+                theme,
             },
             event_tx,
         ))
@@ -184,9 +186,10 @@ impl Tui {
             let logs = self.logs.clone();
             let scroll_offset = self.scroll_offset;
             let start_time = self.start_time;
+            let theme = self.theme;
 
             self.terminal.draw(|f| {
-                render_ui(f, &agents, &logs, scroll_offset, start_time);
+                render_ui(f, &theme, &agents, &logs, scroll_offset, start_time);
             })?;
 
             // Check for keyboard input (non-blocking)
@@ -336,6 +339,7 @@ impl Drop for Tui {
 /// Render the UI (standalone function to avoid borrow issues)
 fn render_ui(
     frame: &mut Frame,
+    theme: &Theme,
     agents: &HashMap<String, AgentState>,
     logs: &[LogEntry],
     scroll_offset: usize,
@@ -351,13 +355,19 @@ fn render_ui(
         ])
         .split(frame.area());
 
-    render_header(frame, chunks[0], agents.len(), start_time);
-    render_agents(frame, chunks[1], agents);
-    render_logs(frame, chunks[2], logs, scroll_offset, start_time);
-    render_help(frame, chunks[3]);
+    render_header(frame, chunks[0], theme, agents.len(), start_time);
+    render_agents(frame, chunks[1], theme, agents);
+    render_logs(frame, chunks[2], theme, logs, scroll_offset, start_time);
+    render_help(frame, chunks[3], theme);
 }
 
-fn render_header(frame: &mut Frame, area: Rect, agent_count: usize, start_time: Instant) {
+fn render_header(
+    frame: &mut Frame,
+    area: Rect,
+    theme: &Theme,
+    agent_count: usize,
+    start_time: Instant,
+) {
     let elapsed = start_time.elapsed();
     let uptime = format!(
         "{:02}:{:02}:{:02}",
@@ -367,43 +377,35 @@ fn render_header(frame: &mut Frame, area: Rect, agent_count: usize, start_time:
     );
 
     let title = Line::from(vec![
-        Span::styled(
-            " coven-swarm ",
-            Style::default()
-                .fg(Color::Cyan)
-                .add_modifier(Modifier::BOLD),
-        ),
+        Span::styled(" coven-swarm ", theme.bold(Role::Accent)),
         Span::raw(" | "),
-        Span::styled(
-            format!("{} agents", agent_count),
-            Style::default().fg(Color::Green),
-        ),
+        Span::styled(format!("{} agents", agent_count), theme.fg(Role::Success)),
         Span::raw(" | "),
-        Span::styled(
-            format!("uptime {}", uptime),
-            Style::default().fg(Color::Gray),
-        ),
+        Span::styled(format!("uptime {}", uptime), theme.fg(Role::Muted)),
     ]);
 
     let block = Block::default()
         .borders(Borders::ALL)
-        .border_style(Style::default().fg(Color::DarkGray));
+        .border_style(theme.fg(Role::Dim));
 
     let paragraph = Paragraph::new(title).block(block);
     frame.render_widget(paragraph, area);
 }
 
-fn render_agents(frame: &mut Frame, area: Rect, agents: &HashMap<String, AgentState>) {
+fn render_agents(
+    frame: &mut Frame,
+    area: Rect,
+    theme: &Theme,
+    agents: &HashMap<String, AgentState>,
+) {
     let mut items: Vec<ListItem> = agents
         .values()
         .map(|agent| {
-            let status_style = Style::default().fg(agent.status.color());
+            let status_style = theme.fg(agent.status.role());
             let name_style = if agent.workspace == "dispatch" {
-                Style::default()
-                    .fg(Color::Yellow)
-                    .add_modifier(Modifier::BOLD)
+                theme.bold(Role::Warning)
             } else {
-                Style::default().fg(Color::White)
+                theme.fg(Role::Text)
             };
 
             let line = Line::from(vec![
@@ -418,7 +420,7 @@ fn render_agents(frame: &mut Frame, area: Rect, agents: &HashMap<String, AgentSt
                         .chars()
                         .take(11)
                         .collect::<String>(),
-                    Style::default().fg(Color::DarkGray),
+                    theme.fg(Role::Dim),
                 ),
             ]);
             ListItem::new(line)
@@ -441,7 +443,7 @@ fn render_agents(frame: &mut Frame, area: Rect, agents: &HashMap<String, AgentSt
     let block = Block::default()
         .title(" Agents ")
         .borders(Borders::ALL)
-        .border_style(Style::default().fg(Color::DarkGray));
+        .border_style(theme.fg(Role::Dim));
 
     let list = List::new(items).block(block);
     frame.render_widget(list, area);
@@ -450,6 +452,7 @@ fn render_agents(frame: &mut Frame, area: Rect, agents: &HashMap<String, AgentSt
 fn render_logs(
     frame: &mut Frame,
     area: Rect,
+    theme: &Theme,
     logs: &[LogEntry],
     scroll_offset: usize,
     start_time: Instant,
@@ -475,21 +478,21 @@ fn render_logs(
             let workspace_span = match &entry.workspace {
                 Some(ws) => Span::styled(
                     format!("[{}]", ws),
-                    Style::default().fg(if ws == "dispatch" {
-                        Color::Yellow
+                    theme.fg(if ws == "dispatch" {
+                        Role::Warning
                     } else {
-                        Color::Blue
+                        Role::Info
                     }),
                 ),
-                None => Span::styled("[*]", Style::default().fg(Color::Gray)),
+                None => Span::styled("[*]", theme.fg(Role::Muted)),
             };
 
             let line = Line::from(vec![
-                Span::styled(timestamp, Style::default().fg(Color::DarkGray)),
+                Span::styled(timestamp, theme.fg(Role::Dim)),
                 Span::raw(" "),
                 Span::styled(
                     format!("{:8}", entry.kind.label()),
-                    Style::default().fg(entry.kind.color()),
+                    theme.fg(entry.kind.role()),
                 ),
                 Span::raw(" "),
                 workspace_span,
@@ -509,23 +512,29 @@ fn render_logs(
     let block = Block::default()
         .title(title)
         .borders(Borders::ALL)
-        .border_style(Style::default().fg(Color::DarkGray));
+        .border_style(theme.fg(Role::Dim));
 
     let list = List::new(items).block(block);
     frame.render_widget(list, area);
 }
 
-fn render_help(frame: &mut Frame, area: Rect) {
+fn render_help(frame: &mut Frame, area: Rect, theme: &Theme) {
+    // Plain themes bold the keys so they still stand out
+    let key = if theme.is_plain() {
+        theme.bold(Role::Warning)
+    } else {
+        theme.fg(Role::Warning)
+    };
     let help = Line::from(vec![
-        Span::styled(" q", Style::default().fg(Color::Yellow)),
+        Span::styled(" q", key),
         Span::raw(" quit  "),
-        Span::styled("^/k", Style::default().fg(Color::Yellow)),
+        Span::styled("^/k", key),
         Span::raw(" scroll up  "),
-        Span::styled("v/j", Style::default().fg(Color::Yellow)),
+        Span::styled("v/j", key),
         Span::raw(" scroll down  "),
-        Span::styled("PgUp/PgDn", Style::default().fg(Color::Yellow)),
+        Span::styled("PgUp/PgDn", key),
         Span::raw(" page  "),
-        Span::styled("Home/End", Style::default().fg(Color::Yellow)),
+        Span::styled("Home/End", key),
         Span::raw(" top/bottom"),
     ]);
 
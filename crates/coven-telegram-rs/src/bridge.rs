@@ -1,19 +1,27 @@
 // ABOUTME: Core bridge logic connecting Telegram events to coven-gateway.
-// ABOUTME: Handles message routing, bindings, command processing, and response streaming.
+// ABOUTME: Handles message routing, bindings, command processing, response streaming, and agent-initiated messages.
 
 use crate::commands::{execute_command, Command, CommandContext};
 use crate::config::Config;
 use crate::error::Result;
 use crate::gateway::GatewayClient;
-use crate::telegram::{CovenTelegramBot, TelegramMessageInfo};
-
+use crate::telegram::{CovenTelegramBot, TelegramMessageInfo, MAX_MESSAGE_LEN};
+
+use coven_bridge_core::{
+    format_initiated, open_binding_store, route_initiated, send_notice, split_message,
+    BindingStore, ChannelIdentity, MessageDeduplicator, OrderedDispatcher, RequestOverrides,
+    ResponseAccumulator, SenderIdentity, StoredBinding, DEFAULT_MAX_CONCURRENT,
+    INITIATED_RESUBSCRIBE_DELAY,
+};
 use coven_proto::client_stream_event::Payload;
+use coven_proto::AgentInitiatedEvent;
 use futures::StreamExt;
 use std::collections::HashMap;
 use std::sync::Arc;
+use std::time::Instant;
 use teloxide::types::{ChatId, MessageId};
 use tokio::sync::RwLock;
-use tracing::{debug, error, info};
+use tracing::{debug, error, info, warn};
 use uuid::Uuid;
 
 /// Chat binding information mapping a Telegram chat to a gateway conversation.
@@ -21,6 +29,34 @@ use uuid::Uuid;
 pub struct ChatBinding {
     pub chat_id: i64,
     pub conversation_key: String,
+    /// Model/max_tokens settings applied to messages from this chat
+    pub overrides: RequestOverrides,
+}
+
+impl ChatBinding {
+    /// Convert to the platform-neutral form used by the binding store.
+    pub fn to_stored(&self) -> StoredBinding {
+        StoredBinding {
+            target: self.chat_id.to_string(),
+            conversation_key: self.conversation_key.clone(),
+            owner: None,
+            overrides: self.overrides.clone(),
+        }
+    }
+
+    /// Convert from a stored binding, or None if the target isn't a chat ID.
+    pub fn from_stored(stored: &StoredBinding) -> Option<Self> {
+        Some(Self {
+            chat_id: stored.target.parse().ok()?,
+            conversation_key: stored.conversation_key.clone(),
+            overrides: stored.overrides.clone(),
+        })
+    }
+}
+
+/// Forum topic ID for an agent-initiated message's thread hint, if it names one.
+fn topic_id(thread_id: Option<&str>) -> Option<i32> {
+    thread_id.and_then(|id| id.trim().parse().ok())
 }
 
 /// The Bridge ties together Telegram and Gateway clients to route messages.
@@ -29,6 +65,10 @@ pub struct Bridge {
     telegram: CovenTelegramBot,
     gateway: Arc<RwLock<GatewayClient>>,
     bindings: Arc<RwLock<HashMap<i64, ChatBinding>>>,
+    store: Arc<dyn BindingStore>,
+    seen_updates: MessageDeduplicator,
+    /// Keeps messages in one chat or topic in arrival order
+    dispatcher: OrderedDispatcher,
 }
 
 impl Bridge {
@@ -41,14 +81,43 @@ impl Bridge {
         let telegram = CovenTelegramBot::new(&config.telegram).await?;
 
         // Connect to Gateway
-        let gateway =
-            GatewayClient::connect(&config.gateway.url, config.gateway.token.clone()).await?;
+        let gateway = GatewayClient::connect(
+            &config.gateway.url,
+            config.gateway.token.clone(),
+            config.gateway.keep_alive()?,
+        )
+        .await?;
+
+        // Restore bindings from the previous run
+        let store = open_binding_store(config.bindings_path().as_deref()).await?;
+        let mut bindings = HashMap::new();
+        for stored in store.load().await? {
+            match ChatBinding::from_stored(&stored) {
+                Some(binding) => {
+                    bindings.insert(binding.chat_id, binding);
+                }
+                None => {
+                    warn!(binding_target = %stored.target, "Ignoring stored binding with invalid chat ID")
+                }
+            }
+        }
+        info!(count = bindings.len(), "Loaded chat bindings");
+
+        let dispatcher = OrderedDispatcher::new(
+            config
+                .bridge
+                .max_concurrent_messages
+                .unwrap_or(DEFAULT_MAX_CONCURRENT),
+        );
 
         Ok(Self {
             config,
             telegram,
             gateway: Arc::new(RwLock::new(gateway)),
-            bindings: Arc::new(RwLock::new(HashMap::new())),
+            bindings: Arc::new(RwLock::new(bindings)),
+            store,
+            seen_updates: MessageDeduplicator::default(),
+            dispatcher,
         })
     }
 
@@ -57,6 +126,7 @@ impl Bridge {
         let binding = ChatBinding {
             chat_id,
             conversation_key: conversation_key.clone(),
+            overrides: RequestOverrides::default(),
         };
 
         info!(
@@ -65,12 +135,18 @@ impl Bridge {
             "Binding chat to conversation"
         );
 
+        if let Err(e) = self.store.save(&binding.to_stored()).await {
+            warn!(error = %e, chat_id = %chat_id, "Failed to persist binding");
+        }
         self.bindings.write().await.insert(chat_id, binding);
     }
 
     /// Unbind a Telegram chat from any gateway conversation.
     pub async fn unbind_chat(&self, chat_id: i64) -> Option<ChatBinding> {
         let binding = self.bindings.write().await.remove(&chat_id);
+        if let Err(e) = self.store.remove(&chat_id.to_string()).await {
+            warn!(error = %e, chat_id = %chat_id, "Failed to remove persisted binding");
+        }
         if let Some(ref b) = binding {
             info!(
                 chat_id = %chat_id,
@@ -101,11 +177,38 @@ impl Bridge {
         &self.bindings
     }
 
+    /// Get a reference to the binding store.
+    pub fn binding_store(&self) -> &Arc<dyn BindingStore> {
+        &self.store
+    }
+
     /// Get a reference to the config.
     pub fn config(&self) -> &Config {
         &self.config
     }
 
+    /// Record a received update and report whether it was already handled.
+    /// Long polling hands back an update again when the poll that carried it
+    /// failed before its offset was confirmed.
+    pub fn is_redelivery(&self, update_id: u32) -> bool {
+        let duplicate = self.seen_updates.is_duplicate(&update_id.to_string());
+        if duplicate {
+            debug!(update_id, "Dropping duplicate update");
+        }
+        duplicate
+    }
+
+    /// Handle a message in the background, after any earlier messages in
+    /// the same chat or topic have been handled.
+    pub fn dispatch(self: &Arc<Self>, msg_info: TelegramMessageInfo) {
+        let bridge = Arc::clone(self);
+        self.dispatcher.submit(msg_info.ordering_key(), async move {
+            if let Err(e) = bridge.handle_message(msg_info).await {
+                error!(error = %e, "Failed to handle message");
+            }
+        });
+    }
+
     /// Handle an incoming Telegram message event.
     pub async fn handle_message(&self, msg_info: TelegramMessageInfo) -> Result<()> {
         let chat_id = msg_info.chat_id;
@@ -121,6 +224,18 @@ impl Bridge {
             return Ok(());
         }
 
+        // Ignored senders and other bots are dropped before anything reaches
+        // the gateway, mentions and commands included
+        if is_ignored_sender(&self.config, &msg_info) {
+            debug!(
+                chat_id = %chat_id,
+                user_id = %msg_info.user_id,
+                is_bot = msg_info.is_bot,
+                "Message from ignored sender, dropping"
+            );
+            return Ok(());
+        }
+
         // Check for /coven commands first (commands work regardless of binding)
         if let Some(command) = Command::from_message(&msg_info.text) {
             info!(
@@ -132,7 +247,10 @@ impl Bridge {
             let ctx = CommandContext {
                 gateway: &self.gateway,
                 bindings: &self.bindings,
+                store: self.store.as_ref(),
                 chat_id,
+                config: &self.config.bridge,
+                user_id: msg_info.user_id,
             };
 
             let response = match execute_command(command, ctx).await {
@@ -189,7 +307,7 @@ impl Bridge {
         // Process the message
         let reply_to = msg_info.reply_message_id(self.config.bridge.thread_replies);
         if let Err(e) = self
-            .process_message(chat_id, reply_to, &binding, &text)
+            .process_message(chat_id, reply_to, &binding, &text, &msg_info.sender())
             .await
         {
             error!(error = %e, chat_id = %chat_id, "Failed to process message");
@@ -211,8 +329,10 @@ impl Bridge {
         reply_to: Option<MessageId>,
         binding: &ChatBinding,
         text: &str,
+        sender: &SenderIdentity,
     ) -> Result<()> {
         let idempotency_key = Uuid::new_v4().to_string();
+        let channel = ChannelIdentity::new("telegram", chat_id.to_string());
 
         // Send message to gateway
         let send_result = {
@@ -222,6 +342,10 @@ impl Bridge {
                     binding.conversation_key.clone(),
                     text.to_string(),
                     idempotency_key,
+                    Some(sender),
+                    Some(&channel),
+                    None,
+                    &binding.overrides,
                 )
                 .await
         };
@@ -239,6 +363,13 @@ impl Bridge {
             "Message sent to gateway"
         );
 
+        // The agent is offline and the gateway will deliver the message
+        // later, or the gateway's content policy blocked it
+        if let Some(notice) = send_notice(&response) {
+            self.send_response(chat_id, reply_to, None, notice).await?;
+            return Ok(());
+        }
+
         // Stream events from gateway
         let stream_result = {
             let mut gateway = self.gateway.write().await;
@@ -254,8 +385,11 @@ impl Bridge {
             }
         };
 
-        // Accumulate text chunks for final message
-        let mut accumulated_text = String::new();
+        // Accumulate text chunks; with a stream interval configured, the
+        // partial response is sent once and then edited as it grows
+        let mut response =
+            ResponseAccumulator::from_interval_ms(self.config.bridge.stream_interval_ms);
+        let mut live_message: Option<MessageId> = None;
         let mut has_sent_message = false;
 
         while let Some(event_result) = stream.next().await {
@@ -270,12 +404,15 @@ impl Bridge {
             // Process the event payload
             match event.payload {
                 Some(Payload::Text(chunk)) => {
-                    accumulated_text.push_str(&chunk.content);
                     debug!(
                         chunk_len = chunk.content.len(),
-                        total_len = accumulated_text.len(),
+                        total_len = response.text().len() + chunk.content.len(),
                         "Received text chunk"
                     );
+                    if let Some(partial) = response.push(&chunk.content, Instant::now()) {
+                        self.show_partial(chat_id, reply_to, &mut live_message, partial)
+                            .await;
+                    }
                 }
                 Some(Payload::Thinking(chunk)) => {
                     debug!(
@@ -306,13 +443,11 @@ impl Bridge {
                 Some(Payload::Done(done)) => {
                     info!("Stream completed");
                     // Use full response if available, otherwise accumulated text
-                    let final_text = done
-                        .full_response
-                        .filter(|s| !s.is_empty())
-                        .unwrap_or_else(|| accumulated_text.clone());
+                    let final_text = std::mem::take(&mut response).finish(done.full_response);
 
                     if !final_text.is_empty() && !has_sent_message {
-                        self.send_response(chat_id, reply_to, &final_text).await?;
+                        self.send_response(chat_id, reply_to, live_message.take(), &final_text)
+                            .await?;
                         has_sent_message = true;
                     }
                     break;
@@ -321,7 +456,8 @@ impl Bridge {
                     error!(message = %error.message, "Stream error event");
                     if !has_sent_message {
                         let error_msg = format!("❌ Error: {}", error.message);
-                        self.send_response(chat_id, reply_to, &error_msg).await?;
+                        self.send_response(chat_id, reply_to, None, &error_msg)
+                            .await?;
                         has_sent_message = true;
                     }
                     break;
@@ -329,6 +465,11 @@ impl Bridge {
                 Some(Payload::Event(_)) => {
                     debug!("Received full event (history replay)");
                 }
+                Some(Payload::Queued(queued)) => {
+                    // A message of its own, apart from the answer to come
+                    self.send_response(chat_id, reply_to, None, &queued.notice)
+                        .await?;
+                }
                 Some(Payload::ToolApproval(approval)) => {
                     // Tool approval requests not supported in Telegram bridge - auto-deny
                     debug!(tool_name = %approval.tool_name, "Tool approval request (auto-denied in Telegram)");
@@ -347,24 +488,155 @@ impl Bridge {
         }
 
         // If we accumulated text but didn't send yet, send now
-        if !accumulated_text.is_empty() && !has_sent_message {
-            self.send_response(chat_id, reply_to, &accumulated_text)
+        if !response.is_empty() && !has_sent_message {
+            self.send_response(chat_id, reply_to, live_message, response.text())
                 .await?;
         }
 
         Ok(())
     }
 
-    /// Send a response to Telegram.
+    /// Deliver messages agents send on their own initiative for as long as
+    /// the bridge runs, resubscribing whenever the gateway stream ends.
+    pub async fn run_initiated_listener(self: Arc<Self>) {
+        loop {
+            let stream_result = {
+                let mut gateway = self.gateway.write().await;
+                gateway.stream_agent_initiated().await
+            };
+
+            match stream_result {
+                Ok(mut stream) => {
+                    info!("Listening for agent-initiated messages");
+                    while let Some(event_result) = stream.next().await {
+                        match event_result {
+                            Ok(event) => self.deliver_initiated(event).await,
+                            Err(status) => {
+                                warn!(error = %status, "Agent-initiated stream error");
+                                break;
+                            }
+                        }
+                    }
+                }
+                Err(e) => {
+                    warn!(error = %e, "Failed to subscribe to agent-initiated messages");
+                }
+            }
+
+            tokio::time::sleep(INITIATED_RESUBSCRIBE_DELAY).await;
+        }
+    }
+
+    /// Post an agent-initiated message to every chat bound to the agent, or
+    /// to the notifications chat when none is. The agent's thread hint is
+    /// used as the forum topic.
+    async fn deliver_initiated(&self, event: AgentInitiatedEvent) {
+        let notifications = self
+            .config
+            .bridge
+            .notifications_chat
+            .map(|chat_id| chat_id.to_string());
+        let deliveries = {
+            let bindings = self.bindings.read().await;
+            let targets: Vec<(String, &str)> = bindings
+                .values()
+                .map(|b| (b.chat_id.to_string(), b.conversation_key.as_str()))
+                .collect();
+            route_initiated(
+                &event,
+                targets.iter().map(|(chat, key)| (chat.as_str(), *key)),
+                notifications.as_deref(),
+            )
+        };
+
+        if deliveries.is_empty() {
+            info!(
+                agent_id = %event.agent_id,
+                message_id = %event.message_id,
+                "Dropping agent-initiated message: agent has no bound chat and no notifications chat is configured"
+            );
+            return;
+        }
+
+        let text = format_initiated(&event);
+        for delivery in deliveries {
+            let Ok(chat_id) = delivery.target.parse::<i64>() else {
+                continue;
+            };
+            let topic = topic_id(delivery.thread_id.as_deref());
+            for piece in split_message(&text, MAX_MESSAGE_LEN) {
+                if let Err(e) = self
+                    .telegram
+                    .send_to_topic(ChatId(chat_id), topic, &piece)
+                    .await
+                {
+                    warn!(
+                        error = %e,
+                        chat_id = %chat_id,
+                        "Failed to deliver agent-initiated message"
+                    );
+                    break;
+                }
+            }
+        }
+    }
+
+    /// Show a partial response while it streams: send it the first time,
+    /// then edit that message. Failures are logged; the final response is
+    /// still delivered by `send_response`.
+    async fn show_partial(
+        &self,
+        chat_id: i64,
+        reply_to: Option<MessageId>,
+        live_message: &mut Option<MessageId>,
+        text: &str,
+    ) {
+        // Only the first message-sized piece is shown until the response ends
+        let Some(preview) = split_message(text, MAX_MESSAGE_LEN).into_iter().next() else {
+            return;
+        };
+        let result = match live_message {
+            Some(message_id) => {
+                self.telegram
+                    .edit_message(ChatId(chat_id), *message_id, &preview)
+                    .await
+            }
+            None => self
+                .telegram
+                .send_message(ChatId(chat_id), &preview, reply_to)
+                .await
+                .map(|message| *live_message = Some(message.id)),
+        };
+        if let Err(e) = result {
+            warn!(error = %e, chat_id = %chat_id, "Failed to show partial response");
+        }
+    }
+
+    /// Send a response to Telegram, split into messages that fit the length
+    /// limit. If a partial response is showing, its message is edited to
+    /// hold the first piece.
     async fn send_response(
         &self,
         chat_id: i64,
         reply_to: Option<MessageId>,
+        live_message: Option<MessageId>,
         text: &str,
     ) -> Result<()> {
-        self.telegram
-            .send_message(ChatId(chat_id), text, reply_to)
-            .await?;
+        let mut live_message = live_message;
+        for piece in split_message(text, MAX_MESSAGE_LEN) {
+            match live_message.take() {
+                Some(message_id) => {
+                    self.telegram
+                        .edit_message(ChatId(chat_id), message_id, &piece)
+                        .await?
+                }
+                None => {
+                    self.telegram
+                        .send_message(ChatId(chat_id), &piece, reply_to)
+                        .await?;
+                }
+            }
+        }
         debug!(
             chat_id = %chat_id,
             reply_to = ?reply_to,
@@ -375,19 +647,103 @@ impl Bridge {
     }
 }
 
+/// Whether the bridge drops `msg_info` because of who sent it.
+fn is_ignored_sender(config: &Config, msg_info: &TelegramMessageInfo) -> bool {
+    config.is_sender_ignored(msg_info.user_id, msg_info.is_bot)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::context::TelegramContext;
+    use teloxide::types::MessageId;
+
+    fn config_with(bridge: &str) -> Config {
+        toml::from_str(&format!(
+            "[telegram]\nbot_token = \"123456:ABC\"\n\
+             [gateway]\nurl = \"http://localhost:6666\"\n[bridge]\n{}",
+            bridge
+        ))
+        .unwrap()
+    }
+
+    fn message(user_id: i64, is_mention: bool, is_bot: bool) -> TelegramMessageInfo {
+        TelegramMessageInfo {
+            chat_id: -100,
+            user_id,
+            sender_name: "someone".to_string(),
+            text: "@coven_bot deploy".to_string(),
+            message_id: MessageId(1),
+            thread_id: None,
+            is_mention,
+            is_reply_to_bot: false,
+            is_bot,
+            context: TelegramContext::from_message(-100, None, false),
+        }
+    }
+
+    #[test]
+    fn test_ignored_sender_dropped_before_gateway() {
+        let config = config_with("ignore_senders = [42]");
+        // Mentions from an ignored user are dropped too
+        assert!(is_ignored_sender(&config, &message(42, true, false)));
+        assert!(is_ignored_sender(&config, &message(42, false, false)));
+        assert!(!is_ignored_sender(&config, &message(7, true, false)));
+
+        // Other bots get through unless ignore_bots is set
+        assert!(!is_ignored_sender(&config, &message(99, true, true)));
+        let config = config_with("ignore_bots = true");
+        assert!(is_ignored_sender(&config, &message(99, true, true)));
+    }
+
+    #[test]
+    fn test_topic_id_from_thread_hint() {
+        assert_eq!(topic_id(Some("42")), Some(42));
+        assert_eq!(topic_id(None), None);
+        // Thread hints from other platforms aren't topic IDs
+        assert_eq!(topic_id(Some("1700000000.000100")), None);
+    }
 
     #[test]
     fn test_chat_binding_clone() {
         let binding = ChatBinding {
             chat_id: 12345,
             conversation_key: "test-conversation".to_string(),
+            overrides: RequestOverrides::default(),
         };
 
         let cloned = binding.clone();
         assert_eq!(binding.chat_id, cloned.chat_id);
         assert_eq!(binding.conversation_key, cloned.conversation_key);
     }
+
+    #[test]
+    fn test_chat_binding_stored_round_trip() {
+        let binding = ChatBinding {
+            chat_id: -100123,
+            conversation_key: "agent-1".to_string(),
+            overrides: RequestOverrides {
+                model: None,
+                max_tokens: Some(1024),
+            },
+        };
+
+        let stored = binding.to_stored();
+        assert_eq!(stored.target, "-100123");
+        let restored = ChatBinding::from_stored(&stored).unwrap();
+        assert_eq!(restored.chat_id, binding.chat_id);
+        assert_eq!(restored.conversation_key, binding.conversation_key);
+        assert_eq!(restored.overrides, binding.overrides);
+    }
+
+    #[test]
+    fn test_chat_binding_from_invalid_stored_target() {
+        let stored = StoredBinding {
+            target: "not-a-chat".to_string(),
+            conversation_key: "agent-1".to_string(),
+            owner: None,
+            overrides: RequestOverrides::default(),
+        };
+        assert!(ChatBinding::from_stored(&stored).is_none());
+    }
 }
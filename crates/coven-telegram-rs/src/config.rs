@@ -1,7 +1,9 @@
 // ABOUTME: Configuration loading and validation for the Telegram bridge.
 // ABOUTME: Supports TOML config files with environment variable expansion.
 
+use crate::commands::{command_names, Command};
 use crate::error::{BridgeError, Result};
+use coven_grpc::{KeepAliveConfig, KeepAliveSettings};
 use serde::Deserialize;
 use std::path::PathBuf;
 use tracing::warn;
@@ -38,6 +40,10 @@ pub struct GatewayConfig {
     /// Authentication token for gateway API calls.
     #[serde(default)]
     pub token: Option<String>,
+    /// HTTP/2 keep-alive pings to the gateway, as a `[gateway.keepalive]`
+    /// table (see `KeepAliveSettings`).
+    #[serde(default)]
+    pub keepalive: KeepAliveSettings,
 }
 
 impl std::fmt::Debug for GatewayConfig {
@@ -45,10 +51,20 @@ impl std::fmt::Debug for GatewayConfig {
         f.debug_struct("GatewayConfig")
             .field("url", &self.url)
             .field("token", &self.token.as_ref().map(|_| "[REDACTED]"))
+            .field("keepalive", &self.keepalive)
             .finish()
     }
 }
 
+impl GatewayConfig {
+    /// Validated keep-alive config, or None when disabled.
+    pub fn keep_alive(&self) -> Result<Option<KeepAliveConfig>> {
+        self.keepalive
+            .to_config()
+            .map_err(|e| BridgeError::Config(format!("gateway.keepalive: {}", e)))
+    }
+}
+
 /// Bridge behavior configuration.
 #[derive(Debug, Clone, Deserialize)]
 pub struct BridgeConfig {
@@ -63,6 +79,44 @@ pub struct BridgeConfig {
     /// Reply in threads using Telegram's reply-to feature.
     #[serde(default = "default_thread_replies")]
     pub thread_replies: bool,
+
+    /// Persist bindings to this file so they survive restarts
+    /// (`.json` for a JSON file, anything else for SQLite; unset = memory only).
+    #[serde(default)]
+    pub bindings_path: Option<String>,
+
+    /// Show the response while it streams, editing it at most once per this
+    /// many milliseconds (unset or 0 = send once the response is complete).
+    #[serde(default)]
+    pub stream_interval_ms: Option<u64>,
+
+    /// Chat that receives agent-initiated messages from agents with no
+    /// bound chat (unset = drop them with a log line).
+    #[serde(default)]
+    pub notifications_chat: Option<i64>,
+
+    /// Most messages handled at once across chats (unset = 16). Messages in
+    /// one chat or forum topic are always handled one at a time, in arrival order.
+    #[serde(default)]
+    pub max_concurrent_messages: Option<usize>,
+
+    /// `/coven` subcommands anyone may run, e.g. `["status", "agents"]`
+    /// (unset = all). `help` is always allowed.
+    #[serde(default)]
+    pub allowed_commands: Option<Vec<String>>,
+
+    /// Telegram user IDs that may run every `/coven` command, whatever
+    /// `allowed_commands` says.
+    #[serde(default)]
+    pub admin_users: Vec<i64>,
+
+    /// Telegram user IDs whose messages are dropped, mentions included.
+    #[serde(default)]
+    pub ignore_senders: Vec<i64>,
+
+    /// Drop messages sent by other bots.
+    #[serde(default)]
+    pub ignore_bots: bool,
 }
 
 impl Default for BridgeConfig {
@@ -71,10 +125,29 @@ impl Default for BridgeConfig {
             allowed_chats: Vec::new(),
             response_mode: ResponseMode::default(),
             thread_replies: default_thread_replies(),
+            bindings_path: None,
+            stream_interval_ms: None,
+            notifications_chat: None,
+            max_concurrent_messages: None,
+            allowed_commands: None,
+            admin_users: Vec::new(),
+            ignore_senders: Vec::new(),
+            ignore_bots: false,
         }
     }
 }
 
+impl BridgeConfig {
+    /// Whether Telegram user `user_id` may run `command`. Unknown commands
+    /// only get a usage hint, so anyone may run them.
+    pub fn may_run(&self, user_id: i64, command: &Command) -> bool {
+        let (Some(allowed), Some(name)) = (&self.allowed_commands, command.name()) else {
+            return true;
+        };
+        name == "help" || allowed.iter().any(|c| c == name) || self.admin_users.contains(&user_id)
+    }
+}
+
 fn default_thread_replies() -> bool {
     true
 }
@@ -138,6 +211,16 @@ impl Config {
         if self.gateway.url.is_empty() {
             return Err(BridgeError::Config("gateway.url is required".into()));
         }
+        self.gateway.keep_alive()?;
+        for name in self.bridge.allowed_commands.iter().flatten() {
+            if !command_names().any(|known| known == name) {
+                return Err(BridgeError::Config(format!(
+                    "bridge.allowed_commands: unknown command '{}' (known: {})",
+                    name,
+                    command_names().collect::<Vec<_>>().join(", ")
+                )));
+            }
+        }
         Ok(())
     }
 
@@ -146,6 +229,20 @@ impl Config {
     pub fn is_chat_allowed(&self, chat_id: i64) -> bool {
         self.bridge.allowed_chats.is_empty() || self.bridge.allowed_chats.contains(&chat_id)
     }
+
+    /// Whether messages from `user_id` are dropped: it's on the ignore
+    /// list, or it's a bot and bots are ignored.
+    pub fn is_sender_ignored(&self, user_id: i64, is_bot: bool) -> bool {
+        (is_bot && self.bridge.ignore_bots) || self.bridge.ignore_senders.contains(&user_id)
+    }
+
+    /// Resolved bindings store path, with `~` expanded.
+    pub fn bindings_path(&self) -> Option<PathBuf> {
+        self.bridge
+            .bindings_path
+            .as_deref()
+            .map(|p| PathBuf::from(shellexpand::tilde(p).into_owned()))
+    }
 }
 
 #[cfg(test)]
@@ -163,6 +260,8 @@ mod tests {
         assert!(config.allowed_chats.is_empty());
         assert_eq!(config.response_mode, ResponseMode::Mention);
         assert!(config.thread_replies);
+        assert!(config.ignore_senders.is_empty());
+        assert!(!config.ignore_bots);
     }
 
     #[test]
@@ -174,6 +273,7 @@ mod tests {
             gateway: GatewayConfig {
                 url: "http://localhost:6666".to_string(),
                 token: None,
+                keepalive: KeepAliveSettings::default(),
             },
             bridge: BridgeConfig::default(),
         };
@@ -191,11 +291,18 @@ mod tests {
             gateway: GatewayConfig {
                 url: "http://localhost:6666".to_string(),
                 token: None,
+                keepalive: KeepAliveSettings::default(),
             },
             bridge: BridgeConfig {
                 allowed_chats: vec![12345, -67890],
                 response_mode: ResponseMode::Mention,
                 thread_replies: true,
+                bindings_path: None,
+                stream_interval_ms: None,
+                notifications_chat: None,
+                max_concurrent_messages: None,
+                allowed_commands: None,
+                admin_users: Vec::new(),
             },
         };
 
@@ -213,6 +320,7 @@ mod tests {
             gateway: GatewayConfig {
                 url: "http://localhost:6666".to_string(),
                 token: None,
+                keepalive: KeepAliveSettings::default(),
             },
             bridge: BridgeConfig::default(),
         };
@@ -231,6 +339,7 @@ mod tests {
             gateway: GatewayConfig {
                 url: "http://localhost:6666".to_string(),
                 token: None,
+                keepalive: KeepAliveSettings::default(),
             },
             bridge: BridgeConfig::default(),
         };
@@ -252,6 +361,7 @@ mod tests {
             gateway: GatewayConfig {
                 url: String::new(),
                 token: None,
+                keepalive: KeepAliveSettings::default(),
             },
             bridge: BridgeConfig::default(),
         };
@@ -270,10 +380,34 @@ mod tests {
             gateway: GatewayConfig {
                 url: "http://localhost:6666".to_string(),
                 token: Some("test-token".to_string()),
+                keepalive: KeepAliveSettings::default(),
             },
             bridge: BridgeConfig::default(),
         };
 
         assert!(config.validate().is_ok());
     }
+
+    #[test]
+    fn test_config_validates_keepalive_timeout_below_interval() {
+        let config = Config {
+            telegram: TelegramConfig {
+                bot_token: "123456:ABC".to_string(),
+            },
+            gateway: GatewayConfig {
+                url: "http://localhost:6666".to_string(),
+                token: None,
+                keepalive: KeepAliveSettings {
+                    interval_secs: 30,
+                    timeout_secs: 30,
+                    ..Default::default()
+                },
+            },
+            bridge: BridgeConfig::default(),
+        };
+
+        let err = config.validate().unwrap_err().to_string();
+        assert!(err.contains("gateway.keepalive"));
+        assert!(err.contains("less than the interval"));
+    }
 }
@@ -29,6 +29,10 @@ pub enum BridgeError {
     /// IO error for file operations.
     #[error("IO error: {0}")]
     Io(#[from] std::io::Error),
+
+    /// Binding persistence error.
+    #[error("Binding store error: {0}")]
+    Store(String),
 }
 
 impl From<tonic::Status> for BridgeError {
@@ -43,5 +47,18 @@ impl From<tonic::transport::Error> for BridgeError {
     }
 }
 
+impl From<coven_bridge_core::BridgeCoreError> for BridgeError {
+    fn from(e: coven_bridge_core::BridgeCoreError) -> Self {
+        use coven_bridge_core::BridgeCoreError as Core;
+        match e {
+            Core::Config(msg) => BridgeError::Config(msg),
+            Core::Gateway(status) => BridgeError::Gateway(status),
+            Core::Connection(err) => BridgeError::Connection(err),
+            Core::Io(err) => BridgeError::Io(err),
+            other => BridgeError::Store(other.to_string()),
+        }
+    }
+}
+
 /// Result type alias using BridgeError.
 pub type Result<T> = std::result::Result<T, BridgeError>;
@@ -19,7 +19,7 @@ pub use telegram::{CovenTelegramBot, TelegramMessageInfo};
 use std::path::PathBuf;
 use std::sync::Arc;
 use teloxide::prelude::*;
-use tracing::{error, info};
+use tracing::info;
 
 /// Run the Telegram bridge with the given config path.
 pub async fn run(config_path: Option<PathBuf>) -> anyhow::Result<()> {
@@ -37,18 +37,22 @@ pub async fn run(config_path: Option<PathBuf>) -> anyhow::Result<()> {
     let bridge = Arc::new(Bridge::new(config.clone()).await?);
     info!("Bridge initialized");
 
+    // Relay messages agents send without being asked
+    tokio::spawn(Arc::clone(&bridge).run_initiated_listener());
+
     // Get a reference to the telegram bot for the dispatcher
     let bot = bridge.telegram_bot().inner().clone();
 
     // Create the message handler
     let bridge_for_handler = Arc::clone(&bridge);
-    let handler = Update::filter_message().endpoint(move |msg: Message, _bot: Bot| {
+    let handler = Update::filter_message().endpoint(move |update: Update, msg: Message| {
         let bridge = Arc::clone(&bridge_for_handler);
         async move {
+            if bridge.is_redelivery(update.id.0) {
+                return Ok(());
+            }
             if let Some(msg_info) = TelegramMessageInfo::from_message(&msg, bridge.telegram_bot()) {
-                if let Err(e) = bridge.handle_message(msg_info).await {
-                    error!(error = %e, "Failed to handle message");
-                }
+                bridge.dispatch(msg_info);
             }
             Ok::<(), std::convert::Infallible>(())
         }
@@ -11,12 +11,27 @@ struct Cli {
     /// Config file path
     #[arg(short, long, env = "COVEN_TELEGRAM_CONFIG")]
     config: Option<std::path::PathBuf>,
+
+    /// Only log warnings and errors
+    #[arg(short, long, global = true, conflicts_with = "verbose")]
+    quiet: bool,
+
+    /// Log more detail (-v for debug, -vv for trace)
+    #[arg(short, long, action = clap::ArgAction::Count, global = true)]
+    verbose: u8,
 }
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    coven_log::init_for("coven_telegram_rs");
-
     let cli = Cli::parse();
+    let level = coven_log::level_from_flags(cli.quiet, cli.verbose);
+    #[cfg(feature = "otlp")]
+    let _otlp = coven_log::init_otlp_for("coven_telegram_rs", level);
+    #[cfg(not(feature = "otlp"))]
+    coven_log::init_for_with_level("coven_telegram_rs", level);
+    // Re-read ~/.config/coven/log-level on SIGHUP
+    #[cfg(unix)]
+    coven_log::reload_on_sighup();
+
     coven_telegram_rs::run(cli.config).await
 }
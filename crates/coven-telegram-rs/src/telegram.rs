@@ -4,10 +4,16 @@
 use crate::config::TelegramConfig;
 use crate::context::TelegramContext;
 use crate::error::{BridgeError, Result};
+use coven_bridge_core::SenderIdentity;
 use teloxide::prelude::*;
-use teloxide::types::{Chat, ChatKind, Me, MessageId, ParseMode, ReplyParameters};
+use teloxide::types::{Chat, ChatKind, Me, MessageId, ParseMode, ReplyParameters, ThreadId};
+use teloxide::{ApiError, RequestError};
 use tracing::{debug, info};
 
+/// Longest message text the bridge sends in one message. Telegram's limit
+/// is 4096 UTF-16 code units; counting bytes keeps every piece under it.
+pub const MAX_MESSAGE_LEN: usize = 4096;
+
 /// Telegram bot wrapper for Long Polling communication.
 pub struct CovenTelegramBot {
     bot: Bot,
@@ -83,6 +89,52 @@ impl CovenTelegramBot {
         Ok(message)
     }
 
+    /// Send a plain-text message to a chat, inside a forum topic if given.
+    pub async fn send_to_topic(
+        &self,
+        chat_id: ChatId,
+        topic_id: Option<i32>,
+        text: &str,
+    ) -> Result<Message> {
+        debug!(
+            chat_id = chat_id.0,
+            topic_id = ?topic_id,
+            "Sending message to Telegram topic"
+        );
+
+        let mut request = self.bot.send_message(chat_id, text);
+
+        if let Some(topic) = topic_id {
+            request = request.message_thread_id(ThreadId(MessageId(topic)));
+        }
+
+        let message = request.await?;
+
+        debug!(message_id = message.id.0, "Message sent successfully");
+        Ok(message)
+    }
+
+    /// Replace the text of a message the bot sent earlier.
+    pub async fn edit_message(
+        &self,
+        chat_id: ChatId,
+        message_id: MessageId,
+        text: &str,
+    ) -> Result<()> {
+        debug!(
+            chat_id = chat_id.0,
+            message_id = message_id.0,
+            "Editing Telegram message"
+        );
+
+        let result = self
+            .bot
+            .edit_message_text(chat_id, message_id, text)
+            .parse_mode(ParseMode::MarkdownV2)
+            .await;
+        ignore_not_modified(result)
+    }
+
     /// Check if the bot was mentioned in the message text.
     /// Telegram uses @username format for mentions.
     pub fn is_mentioned(&self, text: &str) -> bool {
@@ -113,11 +165,15 @@ impl CovenTelegramBot {
 pub struct TelegramMessageInfo {
     pub chat_id: i64,
     pub user_id: i64,
+    /// Sender's @username, or their full name if they have none
+    pub sender_name: String,
     pub text: String,
     pub message_id: MessageId,
     pub thread_id: Option<i32>,
     pub is_mention: bool,
     pub is_reply_to_bot: bool,
+    /// Sent by a bot rather than a person
+    pub is_bot: bool,
     pub context: TelegramContext,
 }
 
@@ -125,7 +181,9 @@ impl TelegramMessageInfo {
     /// Create message info from a Telegram message.
     pub fn from_message(msg: &Message, bot: &CovenTelegramBot) -> Option<Self> {
         let chat_id = msg.chat.id.0;
-        let user_id = msg.from.as_ref()?.id.0 as i64;
+        let from = msg.from.as_ref()?;
+        let user_id = from.id.0 as i64;
+        let sender_name = sender_display_name(from.username.as_deref(), &from.full_name());
         let text = msg.text()?.to_string();
         let message_id = msg.id;
         // thread_id in teloxide is Option<ThreadId> where ThreadId wraps MessageId which wraps i32
@@ -147,15 +205,23 @@ impl TelegramMessageInfo {
         Some(Self {
             chat_id,
             user_id,
+            sender_name,
             text,
             message_id,
             thread_id,
             is_mention,
             is_reply_to_bot,
+            is_bot: from.is_bot,
             context,
         })
     }
 
+    /// Key for handling messages in order: the forum topic or reply thread
+    /// when there is one, else the chat.
+    pub fn ordering_key(&self) -> String {
+        ordering_key(self.chat_id, self.thread_id)
+    }
+
     /// Get the message ID to use for replies.
     /// If thread_replies is enabled and not already in a thread, use this message to start one.
     pub fn reply_message_id(&self, thread_replies: bool) -> Option<MessageId> {
@@ -166,6 +232,11 @@ impl TelegramMessageInfo {
         }
     }
 
+    /// Identity of the sender to forward to the agent.
+    pub fn sender(&self) -> SenderIdentity {
+        SenderIdentity::new("telegram", self.user_id.to_string(), &self.sender_name)
+    }
+
     /// Strip the bot mention from the text.
     pub fn text_without_mention(&self, bot: &CovenTelegramBot) -> String {
         bot.strip_mention(&self.text)
@@ -177,8 +248,72 @@ impl TelegramMessageInfo {
     }
 }
 
+fn ordering_key(chat_id: i64, thread_id: Option<i32>) -> String {
+    match thread_id {
+        Some(thread_id) => format!("{}:{}", chat_id, thread_id),
+        None => chat_id.to_string(),
+    }
+}
+
+/// Display name for a Telegram user: `@username` when set, else their full name.
+fn sender_display_name(username: Option<&str>, full_name: &str) -> String {
+    match username {
+        Some(name) if !name.is_empty() => format!("@{}", name),
+        _ => full_name.to_string(),
+    }
+}
+
+/// Telegram rejects an edit that leaves the text unchanged. The message
+/// already shows what we wanted, so that error counts as success.
+fn ignore_not_modified<T>(result: std::result::Result<T, RequestError>) -> Result<()> {
+    match result {
+        Ok(_) | Err(RequestError::Api(ApiError::MessageNotModified)) => Ok(()),
+        Err(e) => Err(e.into()),
+    }
+}
+
 #[cfg(test)]
 mod tests {
+    use super::{ignore_not_modified, ordering_key, sender_display_name};
+    use crate::error::BridgeError;
+    use teloxide::{ApiError, RequestError};
+
+    #[test]
+    fn test_unchanged_edit_counts_as_success() {
+        let result: Result<(), RequestError> = Err(RequestError::Api(ApiError::MessageNotModified));
+        assert!(ignore_not_modified(result).is_ok());
+        assert!(ignore_not_modified(Ok::<_, RequestError>(())).is_ok());
+    }
+
+    #[test]
+    fn test_other_edit_errors_are_returned() {
+        let result: Result<(), RequestError> =
+            Err(RequestError::Api(ApiError::MessageToEditNotFound));
+        assert!(matches!(
+            ignore_not_modified(result),
+            Err(BridgeError::TeloxideRequest(RequestError::Api(
+                ApiError::MessageToEditNotFound
+            )))
+        ));
+    }
+
+    #[test]
+    fn test_sender_display_name() {
+        assert_eq!(sender_display_name(Some("alice"), "Alice Smith"), "@alice");
+        assert_eq!(sender_display_name(None, "Alice Smith"), "Alice Smith");
+        assert_eq!(sender_display_name(Some(""), "Alice"), "Alice");
+    }
+
+    #[test]
+    fn test_ordering_key_separates_topics() {
+        assert_eq!(ordering_key(-100123, None), "-100123");
+        assert_eq!(ordering_key(-100123, Some(7)), "-100123:7");
+        assert_ne!(
+            ordering_key(-100123, Some(7)),
+            ordering_key(-100123, Some(8))
+        );
+    }
+
     #[test]
     fn test_mention_detection() {
         // This is a simple unit test for the mention pattern logic
@@ -65,6 +65,22 @@ fn test_command_parsing_bind_without_agent_id() {
     }
 }
 
+#[test]
+fn test_command_parsing_set() {
+    assert_eq!(
+        Command::parse("set model claude-sonnet-4-20250514"),
+        Command::Set("model".to_string(), "claude-sonnet-4-20250514".to_string())
+    );
+    assert_eq!(
+        Command::parse("set max_tokens 4096"),
+        Command::Set("max_tokens".to_string(), "4096".to_string())
+    );
+    match Command::parse("set max_tokens") {
+        Command::Unknown(msg) => assert!(msg.contains("requires a setting")),
+        _ => panic!("Expected Unknown command for set without a value"),
+    }
+}
+
 #[test]
 fn test_command_parsing_unknown() {
     assert_eq!(Command::parse("foo"), Command::Unknown("foo".to_string()));
@@ -111,6 +127,7 @@ token = "test-token"
 allowed_chats = [12345, -67890]
 response_mode = "all"
 thread_replies = false
+notifications_chat = -1001234567890
 "#;
 
     let mut file = NamedTempFile::new().unwrap();
@@ -124,6 +141,7 @@ thread_replies = false
     assert_eq!(config.bridge.allowed_chats.len(), 2);
     assert_eq!(config.bridge.response_mode, ResponseMode::All);
     assert!(!config.bridge.thread_replies);
+    assert_eq!(config.bridge.notifications_chat, Some(-1001234567890));
 }
 
 #[test]
@@ -170,6 +188,66 @@ allowed_chats = [12345, -67890]
     assert!(!config.is_chat_allowed(99999));
 }
 
+#[test]
+fn test_config_command_allowlist() {
+    let config_content = r#"
+[telegram]
+bot_token = "123456789:ABC-DEF-test-token"
+
+[gateway]
+url = "http://localhost:6666"
+
+[bridge]
+allowed_commands = ["status", "agents"]
+admin_users = [1001]
+"#;
+
+    let mut file = NamedTempFile::new().unwrap();
+    file.write_all(config_content.as_bytes()).unwrap();
+
+    let config = Config::load(Some(file.path().to_path_buf())).unwrap();
+    let bind = Command::Bind("agent-1".to_string());
+
+    assert!(config.bridge.may_run(2002, &Command::Status));
+    assert!(!config.bridge.may_run(2002, &bind));
+    assert!(!config.bridge.may_run(2002, &Command::Unbind));
+    // Help and unknown commands only print usage, so anyone can run them
+    assert!(config.bridge.may_run(2002, &Command::Help));
+    assert!(config
+        .bridge
+        .may_run(2002, &Command::Unknown("x".to_string())));
+    // Admins bypass the allowlist
+    assert!(config.bridge.may_run(1001, &bind));
+
+    // Without an allowlist, everyone may run everything
+    let open = coven_telegram_rs::config::BridgeConfig::default();
+    assert!(open.may_run(2002, &bind));
+}
+
+#[test]
+fn test_config_rejects_unknown_allowed_command() {
+    let config_content = r#"
+[telegram]
+bot_token = "123456789:ABC-DEF-test-token"
+
+[gateway]
+url = "http://localhost:6666"
+
+[bridge]
+allowed_commands = ["status", "bnid"]
+"#;
+
+    let mut file = NamedTempFile::new().unwrap();
+    file.write_all(config_content.as_bytes()).unwrap();
+
+    let err = Config::load(Some(file.path().to_path_buf())).unwrap_err();
+    assert!(
+        err.to_string().contains("unknown command 'bnid'"),
+        "{}",
+        err
+    );
+}
+
 #[test]
 fn test_config_empty_allowed_chats_allows_all() {
     let config_content = r#"
@@ -360,6 +438,7 @@ fn test_chat_binding_clone() {
     let binding = ChatBinding {
         chat_id: -100123456,
         conversation_key: "test-conversation".to_string(),
+        overrides: Default::default(),
     };
 
     let cloned = binding.clone();
@@ -1,11 +1,13 @@
 // ABOUTME: Central application state and event handling
 // ABOUTME: Single struct holds all state, mutations happen in handle_* methods
 
+use crate::attach::load_attachment;
 use crate::client::Response;
 use crate::types::{
-    Agent, Message, Mode, PendingApproval, PersistedState, Role, SessionMetadata, StreamBlock,
-    StreamingMessage, ToolStatus, ToolUse,
+    Agent, Message, Mode, OutgoingMessage, PendingApproval, PersistedState, Role, SessionMetadata,
+    StreamBlock, StreamingMessage, ToolStatus, ToolUse,
 };
+use coven_client::{Attachment, DiagnosticReport};
 use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
 use ratatui::style::{Color, Style};
 use std::collections::VecDeque;
@@ -27,7 +29,7 @@ fn styled_textarea() -> TextArea<'static> {
 /// Actions that need async handling (returned from handle_key)
 pub enum Action {
     Quit,
-    SendMessage(String),
+    SendMessage(OutgoingMessage),
     RefreshAgents,
     /// Load conversation history for an agent from the gateway
     LoadHistory(String),
@@ -37,6 +39,13 @@ pub enum Action {
     DenySelected,
     /// Approve all future uses of this tool from this agent
     ApproveAllSelected,
+    /// Run connection diagnostics and show the report
+    Diagnose,
+    /// Fork a conversation from one of its messages and switch to the fork
+    ForkThread {
+        conversation_key: String,
+        message_id: String,
+    },
 }
 
 /// Central application state
@@ -47,9 +56,13 @@ pub struct App {
     // Agents
     pub agents: Vec<Agent>,
     pub selected_agent: Option<String>,
+    // Key of a conversation forked from the agent's, while chatting in it
+    pub conversation: Option<String>,
 
     // Chat state
     pub messages: Vec<Message>,
+    // Message picked with Alt+Up/Down, to fork from
+    pub selected_message: Option<usize>,
     pub streaming: Option<StreamingMessage>,
     pub scroll_offset: usize,
 
@@ -69,11 +82,18 @@ pub struct App {
     pub connected: bool,
     pub error: Option<String>,
 
+    // Connection doctor: a report on screen, or one being run
+    pub diagnostics: Option<DiagnosticReport>,
+    pub diagnosing: bool,
+
     // Quit handling
     pub last_ctrl_c: Option<Instant>,
 
     // Queued messages to send after current response completes
-    pub pending_messages: VecDeque<String>,
+    pub pending_messages: VecDeque<OutgoingMessage>,
+
+    // Files attached with :attach, sent with the next message
+    pub pending_attachments: Vec<Attachment>,
 
     // Action to execute after response handling (for queued message drain)
     pub queued_action: Option<Action>,
@@ -81,6 +101,9 @@ pub struct App {
     // Throbber animation frame
     pub throbber_frame: usize,
 
+    // Show tool results in full instead of their first few lines
+    pub expand_tool_results: bool,
+
     // Tool approval state
     pub pending_approvals: Vec<crate::types::PendingApproval>,
     pub selected_approval: Option<usize>,
@@ -96,7 +119,9 @@ impl App {
             },
             agents: vec![],
             selected_agent: initial_agent,
+            conversation: None,
             messages: vec![],
+            selected_message: None,
             streaming: None,
             scroll_offset: 0,
             input: styled_textarea(),
@@ -107,10 +132,14 @@ impl App {
             session: SessionMetadata::default(),
             connected: false,
             error: None,
+            diagnostics: None,
+            diagnosing: false,
             last_ctrl_c: None,
             pending_messages: VecDeque::new(),
+            pending_attachments: vec![],
             queued_action: None,
             throbber_frame: 0,
+            expand_tool_results: false,
             pending_approvals: vec![],
             selected_approval: None,
         }
@@ -159,6 +188,23 @@ impl App {
         THROBBER[self.throbber_frame]
     }
 
+    /// Key of the conversation on screen: a fork, or the agent's own
+    pub fn conversation_key(&self) -> Option<&str> {
+        self.conversation
+            .as_deref()
+            .or(self.selected_agent.as_deref())
+    }
+
+    /// Switch to a conversation just forked from this one. Its history is
+    /// loaded next.
+    pub fn open_fork(&mut self, conversation_key: String) {
+        self.conversation = Some(conversation_key);
+        self.messages.clear();
+        self.selected_message = None;
+        self.scroll_offset = 0;
+        self.error = None;
+    }
+
     /// Get agents filtered by picker search
     pub fn filtered_agents(&self) -> Vec<&Agent> {
         self.agents
@@ -193,9 +239,21 @@ impl App {
                 self.picker_index = 0;
                 return None;
             }
+            KeyCode::Char('o') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                self.expand_tool_results = !self.expand_tool_results;
+                return None;
+            }
+            KeyCode::Char('d') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                return self.start_diagnosis();
+            }
             _ => {}
         }
 
+        // The doctor report covers everything until it's closed
+        if self.diagnostics.is_some() || self.diagnosing {
+            return self.handle_doctor_key(key);
+        }
+
         // If there are pending approvals, handle approval keys first
         if !self.pending_approvals.is_empty() {
             if let Some(action) = self.handle_approval_key(key) {
@@ -210,6 +268,32 @@ impl App {
         }
     }
 
+    fn handle_doctor_key(&mut self, key: KeyEvent) -> Option<Action> {
+        match key.code {
+            KeyCode::Esc | KeyCode::Enter => {
+                self.diagnostics = None;
+                None
+            }
+            KeyCode::Char('r') => self.start_diagnosis(),
+            _ => None,
+        }
+    }
+
+    /// Ask for a diagnosis unless one is already running
+    fn start_diagnosis(&mut self) -> Option<Action> {
+        if self.diagnosing {
+            return None;
+        }
+        self.diagnosing = true;
+        Some(Action::Diagnose)
+    }
+
+    /// Show a finished diagnosis
+    pub fn set_diagnostics(&mut self, report: DiagnosticReport) {
+        self.diagnosing = false;
+        self.diagnostics = Some(report);
+    }
+
     fn handle_picker_key(&mut self, key: KeyEvent) -> Option<Action> {
         match key.code {
             KeyCode::Esc => {
@@ -224,9 +308,11 @@ impl App {
                     let agent_model = agent.model.clone().unwrap_or_default();
                     drop(filtered);
                     self.selected_agent = Some(agent_id.clone());
+                    self.conversation = None;
                     self.session.model = agent_model;
                     self.mode = Mode::Chat;
                     self.messages.clear();
+                    self.selected_message = None;
                     return Some(Action::LoadHistory(agent_id));
                 }
             }
@@ -252,6 +338,20 @@ impl App {
 
     fn handle_chat_key(&mut self, key: KeyEvent) -> Option<Action> {
         match key.code {
+            // Pick a message to fork from
+            KeyCode::Up if key.modifiers.contains(KeyModifiers::ALT) => {
+                self.select_message(-1);
+            }
+            KeyCode::Down if key.modifiers.contains(KeyModifiers::ALT) => {
+                self.select_message(1);
+            }
+            KeyCode::Esc if self.selected_message.is_some() => {
+                self.selected_message = None;
+            }
+            KeyCode::Char('f') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                return self.fork_selected();
+            }
+
             // Scroll
             KeyCode::Up if key.modifiers.contains(KeyModifiers::CONTROL) => {
                 self.scroll_offset = self.scroll_offset.saturating_add(1);
@@ -277,15 +377,22 @@ impl App {
             // Send message
             KeyCode::Enter if !key.modifiers.contains(KeyModifiers::SHIFT) => {
                 let content = self.input.lines().join("\n").trim().to_string();
-                if !content.is_empty() && self.selected_agent.is_some() {
-                    self.input_history.push(content.clone());
+                if self.run_attach_command(&content) {
+                    return None;
+                }
+                let has_files = !self.pending_attachments.is_empty();
+                if (!content.is_empty() || has_files) && self.selected_agent.is_some() {
+                    if !content.is_empty() {
+                        self.input_history.push(content.clone());
+                    }
                     self.history_index = None;
                     self.input = styled_textarea();
                     self.mode = Mode::Sending;
                     self.streaming = Some(StreamingMessage::default());
                     self.scroll_offset = 0;
-                    self.messages.push(Message::user(content.clone()));
-                    return Some(Action::SendMessage(content));
+                    let outgoing = self.outgoing(content);
+                    self.messages.push(outgoing.to_message());
+                    return Some(Action::SendMessage(outgoing));
                 }
             }
 
@@ -315,10 +422,16 @@ impl App {
             // Queue message for sending after current response completes
             KeyCode::Enter if !key.modifiers.contains(KeyModifiers::SHIFT) => {
                 let content = self.input.lines().join("\n").trim().to_string();
-                if !content.is_empty() {
-                    self.input_history.push(content.clone());
+                if self.run_attach_command(&content) {
+                    return None;
+                }
+                if !content.is_empty() || !self.pending_attachments.is_empty() {
+                    if !content.is_empty() {
+                        self.input_history.push(content.clone());
+                    }
                     self.history_index = None;
-                    self.pending_messages.push_back(content);
+                    let outgoing = self.outgoing(content);
+                    self.pending_messages.push_back(outgoing);
                     self.input = styled_textarea();
                 }
             }
@@ -330,6 +443,82 @@ impl App {
         None
     }
 
+    /// Move the message selection toward older (negative) or newer
+    /// messages. Moving up from no selection picks the newest message;
+    /// moving down past it clears the selection.
+    fn select_message(&mut self, direction: i32) {
+        let Some(last) = self.messages.len().checked_sub(1) else {
+            return;
+        };
+        self.selected_message = match self.selected_message {
+            None if direction < 0 => Some(last),
+            None => None,
+            Some(i) if direction < 0 => Some(i.saturating_sub(1)),
+            Some(i) if i < last => Some(i + 1),
+            Some(_) => None,
+        };
+    }
+
+    /// Fork from the selected message, or from the newest one
+    fn fork_selected(&mut self) -> Option<Action> {
+        let conversation_key = self.conversation_key()?.to_string();
+        let index = self
+            .selected_message
+            .or_else(|| self.messages.len().checked_sub(1))?;
+        // Messages get their gateway IDs when the history is loaded
+        let Some(message_id) = self.messages[index].id.clone() else {
+            self.error = Some("Can't fork from a message sent in this session yet".to_string());
+            return None;
+        };
+        Some(Action::ForkThread {
+            conversation_key,
+            message_id,
+        })
+    }
+
+    /// Run `:attach <path>` or `:detach` typed into the input, returning
+    /// whether it was one of them. A file that can't be attached leaves the
+    /// command in the input so the path can be fixed.
+    fn run_attach_command(&mut self, content: &str) -> bool {
+        let (command, arg) = content
+            .split_once(char::is_whitespace)
+            .unwrap_or((content, ""));
+        match command {
+            ":attach" => {
+                let attached = self
+                    .pending_attachments
+                    .iter()
+                    .map(|a| a.data.len() as u64)
+                    .sum();
+                self.input_history.push(content.to_string());
+                self.history_index = None;
+                match load_attachment(arg, attached) {
+                    Ok(attachment) => {
+                        self.pending_attachments.push(attachment);
+                        self.error = None;
+                        self.input = styled_textarea();
+                    }
+                    Err(e) => self.error = Some(e),
+                }
+            }
+            ":detach" => {
+                self.pending_attachments.clear();
+                self.error = None;
+                self.input = styled_textarea();
+            }
+            _ => return false,
+        }
+        true
+    }
+
+    /// A message with `content` and the files attached so far
+    fn outgoing(&mut self, content: String) -> OutgoingMessage {
+        OutgoingMessage {
+            content,
+            attachments: std::mem::take(&mut self.pending_attachments),
+        }
+    }
+
     fn navigate_history(&mut self, direction: i32) {
         if self.input_history.is_empty() {
             return;
@@ -447,6 +636,9 @@ impl App {
                         thinking: streaming.thinking,
                         timestamp: chrono::Utc::now(),
                         tokens: None,
+                        id: None,
+                        reply_to: None,
+                        attachments: vec![],
                     });
                 }
                 // Check for queued messages before returning to Chat mode
@@ -454,7 +646,7 @@ impl App {
                     self.mode = Mode::Sending;
                     self.streaming = Some(StreamingMessage::default());
                     self.scroll_offset = 0;
-                    self.messages.push(Message::user(queued.clone()));
+                    self.messages.push(queued.to_message());
                     self.queued_action = Some(Action::SendMessage(queued));
                 } else {
                     self.mode = Mode::Chat;
@@ -472,6 +664,7 @@ impl App {
                 tool_id,
                 tool_name,
                 input_json,
+                confirm_message,
             } => {
                 const MAX_PENDING_APPROVALS: usize = 100;
                 if self.pending_approvals.len() >= MAX_PENDING_APPROVALS {
@@ -487,6 +680,7 @@ impl App {
                     tool_id,
                     tool_name,
                     input_json,
+                    confirm_message,
                     timestamp: chrono::Utc::now(),
                 };
                 self.pending_approvals.push(approval);
@@ -589,6 +783,93 @@ mod tests {
         assert_eq!(app.selected_agent, Some("agent-1".to_string()));
     }
 
+    fn saved(id: &str, content: &str) -> Message {
+        Message {
+            id: Some(id.to_string()),
+            ..Message::user(content.to_string())
+        }
+    }
+
+    #[test]
+    fn test_fork_from_selected_message() {
+        let mut app = App::new(Some("agent-1".to_string()));
+        app.messages = vec![saved("msg-1", "first"), saved("msg-2", "one")];
+        let alt_up = KeyEvent::new(KeyCode::Up, KeyModifiers::ALT);
+        let alt_down = KeyEvent::new(KeyCode::Down, KeyModifiers::ALT);
+        let ctrl_f = KeyEvent::new(KeyCode::Char('f'), KeyModifiers::CONTROL);
+
+        app.handle_key(alt_up);
+        assert_eq!(app.selected_message, Some(1));
+        app.handle_key(alt_up);
+        app.handle_key(alt_up);
+        assert_eq!(app.selected_message, Some(0));
+        assert!(matches!(
+            app.handle_key(ctrl_f),
+            Some(Action::ForkThread { conversation_key, message_id })
+                if conversation_key == "agent-1" && message_id == "msg-1"
+        ));
+
+        app.handle_key(alt_down);
+        app.handle_key(alt_down);
+        assert_eq!(app.selected_message, None);
+
+        // The fork is where messages go from now on
+        app.open_fork("fork-1".to_string());
+        assert_eq!(app.conversation_key(), Some("fork-1"));
+        assert!(app.messages.is_empty());
+        app.messages = vec![saved("msg-3", "first"), Message::user("unsaved".into())];
+        assert!(app.handle_key(ctrl_f).is_none());
+        assert!(app.error.is_some());
+        app.handle_key(alt_up);
+        app.handle_key(alt_up);
+        assert!(matches!(
+            app.handle_key(ctrl_f),
+            Some(Action::ForkThread { conversation_key, message_id })
+                if conversation_key == "fork-1" && message_id == "msg-3"
+        ));
+    }
+
+    #[test]
+    fn test_ctrl_o_toggles_full_tool_results() {
+        let mut app = App::new(Some("agent-1".to_string()));
+        let key = KeyEvent::new(KeyCode::Char('o'), KeyModifiers::CONTROL);
+
+        assert!(app.handle_key(key).is_none());
+        assert!(app.expand_tool_results);
+        assert!(app.input.is_empty());
+
+        app.handle_key(key);
+        assert!(!app.expand_tool_results);
+    }
+
+    #[test]
+    fn test_ctrl_d_runs_the_doctor() {
+        let mut app = App::new(Some("agent-1".to_string()));
+        let ctrl_d = KeyEvent::new(KeyCode::Char('d'), KeyModifiers::CONTROL);
+        assert!(matches!(app.handle_key(ctrl_d), Some(Action::Diagnose)));
+        assert!(app.diagnosing);
+        // Only one diagnosis at a time
+        assert!(app.handle_key(ctrl_d).is_none());
+
+        app.set_diagnostics(DiagnosticReport {
+            gateway_url: "http://localhost:50051".to_string(),
+            steps: vec![],
+            clock_skew_ms: None,
+            healthy: true,
+        });
+        assert!(!app.diagnosing);
+
+        // The report takes keys until it's closed
+        let typed = KeyEvent::new(KeyCode::Char('x'), KeyModifiers::NONE);
+        assert!(app.handle_key(typed).is_none());
+        assert!(app.input.is_empty());
+        let rerun = KeyEvent::new(KeyCode::Char('r'), KeyModifiers::NONE);
+        assert!(matches!(app.handle_key(rerun), Some(Action::Diagnose)));
+        app.diagnosing = false;
+        app.handle_key(KeyEvent::new(KeyCode::Esc, KeyModifiers::NONE));
+        assert!(app.diagnostics.is_none());
+    }
+
     #[test]
     fn test_throbber_cycles() {
         let mut app = App::new(None);
@@ -642,6 +923,8 @@ mod tests {
                 working_dir: String::new(),
                 capabilities: vec![],
                 connected: true,
+                status: "available".to_string(),
+                status_emoji: String::new(),
             },
             Agent {
                 id: "2".to_string(),
@@ -651,6 +934,8 @@ mod tests {
                 working_dir: String::new(),
                 capabilities: vec![],
                 connected: true,
+                status: "available".to_string(),
+                status_emoji: String::new(),
             },
         ];
         app.picker_filter = "clau".to_string();
@@ -671,6 +956,7 @@ mod tests {
             tool_id: "tool-1".to_string(),
             tool_name: "bash".to_string(),
             input_json: r#"{"command": "ls"}"#.to_string(),
+            confirm_message: None,
         });
 
         assert_eq!(app.pending_approvals.len(), 1);
@@ -689,6 +975,7 @@ mod tests {
             tool_id: "tool-1".to_string(),
             tool_name: "bash".to_string(),
             input_json: "{}".to_string(),
+            confirm_message: None,
             timestamp: chrono::Utc::now(),
         });
         app.selected_approval = Some(0);
@@ -707,6 +994,7 @@ mod tests {
             tool_id: "tool-1".to_string(),
             tool_name: "bash".to_string(),
             input_json: "{}".to_string(),
+            confirm_message: None,
             timestamp: chrono::Utc::now(),
         });
         app.pending_approvals.push(PendingApproval {
@@ -715,6 +1003,7 @@ mod tests {
             tool_id: "tool-2".to_string(),
             tool_name: "read".to_string(),
             input_json: "{}".to_string(),
+            confirm_message: None,
             timestamp: chrono::Utc::now(),
         });
         app.selected_approval = Some(1);
@@ -741,6 +1030,7 @@ mod tests {
             tool_id: "tool-1".to_string(),
             tool_name: "bash".to_string(),
             input_json: "{}".to_string(),
+            confirm_message: None,
             timestamp: chrono::Utc::now(),
         });
         assert!(app.has_pending_approvals());
@@ -757,16 +1047,70 @@ mod tests {
         let action = app.handle_sending_key(key);
         assert!(action.is_none()); // No action returned - message is queued
         assert_eq!(app.pending_messages.len(), 1);
-        assert_eq!(app.pending_messages[0], "queued message");
+        assert_eq!(app.pending_messages[0].content, "queued message");
         assert!(app.input.is_empty()); // Input cleared
     }
 
+    #[test]
+    fn test_attach_command_adds_files_to_next_message() {
+        let path =
+            std::env::temp_dir().join(format!("coven-tui-app-attach-{}.txt", std::process::id()));
+        std::fs::write(&path, "hello").unwrap();
+        let mut app = App::new(Some("agent-1".to_string()));
+        let enter = KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE);
+
+        for _ in 0..2 {
+            app.input.insert_str(&format!(":attach {}", path.display()));
+            assert!(app.handle_key(enter).is_none());
+        }
+        assert_eq!(app.pending_attachments.len(), 2);
+        assert!(app.input.is_empty());
+
+        // A bad path is reported and left in the input to fix
+        app.input.insert_str(":attach /no/such/file.txt");
+        assert!(app.handle_key(enter).is_none());
+        assert!(app.error.as_deref().unwrap().starts_with("No such file"));
+        assert!(!app.input.is_empty());
+        app.input = styled_textarea();
+
+        // Files can be sent without text, and the sent message shows them
+        let Some(Action::SendMessage(outgoing)) = app.handle_key(enter) else {
+            panic!("expected the attachments to be sent");
+        };
+        assert_eq!(outgoing.content, "");
+        assert_eq!(outgoing.attachments.len(), 2);
+        assert_eq!(outgoing.attachments[0].data, b"hello");
+        assert!(app.pending_attachments.is_empty());
+        assert_eq!(app.messages.last().unwrap().attachments.len(), 2);
+        assert_eq!(app.mode, Mode::Sending);
+
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_detach_clears_attachments() {
+        let mut app = App::new(Some("agent-1".to_string()));
+        app.pending_attachments.push(Attachment {
+            filename: "a.txt".to_string(),
+            mime_type: "text/plain".to_string(),
+            data: vec![],
+        });
+        app.input.insert_str(":detach");
+        let enter = KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE);
+        assert!(app.handle_key(enter).is_none());
+        assert!(app.pending_attachments.is_empty());
+        assert!(app.messages.is_empty());
+    }
+
     #[test]
     fn test_queued_message_drains_on_done() {
         let mut app = App::new(Some("agent-1".to_string()));
         app.mode = Mode::Sending;
         app.streaming = Some(StreamingMessage::default());
-        app.pending_messages.push_back("queued msg".to_string());
+        app.pending_messages.push_back(OutgoingMessage {
+            content: "queued msg".to_string(),
+            attachments: vec![],
+        });
 
         app.handle_response(Response::Done);
 
@@ -785,6 +1129,7 @@ mod tests {
             tool_id: "tool-1".to_string(),
             tool_name: "bash".to_string(),
             input_json: "{}".to_string(),
+            confirm_message: None,
             timestamp: chrono::Utc::now(),
         });
         app.selected_approval = Some(0);
@@ -803,6 +1148,7 @@ mod tests {
             tool_id: "tool-1".to_string(),
             tool_name: "bash".to_string(),
             input_json: "{}".to_string(),
+            confirm_message: None,
             timestamp: chrono::Utc::now(),
         });
         app.selected_approval = Some(0);
@@ -821,6 +1167,7 @@ mod tests {
             tool_id: "tool-1".to_string(),
             tool_name: "bash".to_string(),
             input_json: "{}".to_string(),
+            confirm_message: None,
             timestamp: chrono::Utc::now(),
         });
         app.selected_approval = Some(0);
@@ -839,6 +1186,7 @@ mod tests {
             tool_id: "tool-1".to_string(),
             tool_name: "bash".to_string(),
             input_json: "{}".to_string(),
+            confirm_message: None,
             timestamp: chrono::Utc::now(),
         });
         app.pending_approvals.push(PendingApproval {
@@ -847,6 +1195,7 @@ mod tests {
             tool_id: "tool-2".to_string(),
             tool_name: "read".to_string(),
             input_json: "{}".to_string(),
+            confirm_message: None,
             timestamp: chrono::Utc::now(),
         });
         app.selected_approval = Some(0);
@@ -0,0 +1,187 @@
+// ABOUTME: Reading local files into message attachments for the :attach command
+// ABOUTME: Validates path and size, guesses a MIME type, and formats sizes for chips
+
+use coven_client::{Attachment, MAX_ATTACHMENT_BYTES};
+use std::path::{Path, PathBuf};
+
+/// Read the file at `path` (`~/` expands to the home directory) into an
+/// attachment. `attached_bytes` is the size of the files already attached
+/// to the same message, which share its size limit. Errors are ready to
+/// show to the user.
+pub fn load_attachment(path: &str, attached_bytes: u64) -> Result<Attachment, String> {
+    let path = path.trim();
+    if path.is_empty() {
+        return Err("Usage: :attach <path>".to_string());
+    }
+    let resolved = expand_home(path);
+
+    let metadata = std::fs::metadata(&resolved).map_err(|e| match e.kind() {
+        std::io::ErrorKind::NotFound => format!("No such file: {}", path),
+        _ => format!("Can't read {}: {}", path, e),
+    })?;
+    if !metadata.is_file() {
+        return Err(format!("Not a file: {}", path));
+    }
+    let remaining = MAX_ATTACHMENT_BYTES.saturating_sub(attached_bytes);
+    if metadata.len() > remaining {
+        return Err(if attached_bytes == 0 {
+            format!(
+                "{} is {}; attachments are limited to {} per message",
+                path,
+                format_size(metadata.len()),
+                format_size(MAX_ATTACHMENT_BYTES)
+            )
+        } else {
+            format!(
+                "{} is {}, but only {} of the {} per message is left",
+                path,
+                format_size(metadata.len()),
+                format_size(remaining),
+                format_size(MAX_ATTACHMENT_BYTES)
+            )
+        });
+    }
+
+    let data = std::fs::read(&resolved).map_err(|e| format!("Can't read {}: {}", path, e))?;
+    let filename = resolved
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_else(|| path.to_string());
+    Ok(Attachment {
+        mime_type: mime_type_for(&resolved).to_string(),
+        filename,
+        data,
+    })
+}
+
+fn expand_home(path: &str) -> PathBuf {
+    match (path.strip_prefix("~/"), dirs::home_dir()) {
+        (Some(rest), Some(home)) => home.join(rest),
+        _ => PathBuf::from(path),
+    }
+}
+
+/// MIME type for common file extensions, falling back to
+/// "application/octet-stream"
+pub fn mime_type_for(path: &Path) -> &'static str {
+    let extension = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(str::to_ascii_lowercase)
+        .unwrap_or_default();
+    match extension.as_str() {
+        "txt" | "log" => "text/plain",
+        "md" | "markdown" => "text/markdown",
+        "csv" => "text/csv",
+        "html" | "htm" => "text/html",
+        "json" => "application/json",
+        "toml" => "application/toml",
+        "yaml" | "yml" => "application/yaml",
+        "xml" => "application/xml",
+        "rs" | "py" | "go" | "js" | "ts" | "swift" | "kt" | "c" | "h" | "sh" => "text/plain",
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "svg" => "image/svg+xml",
+        "pdf" => "application/pdf",
+        "zip" => "application/zip",
+        _ => "application/octet-stream",
+    }
+}
+
+/// Human-readable size, e.g. "512 B", "1.5 KB", "10.0 MB"
+pub fn format_size(bytes: u64) -> String {
+    const KB: f64 = 1024.0;
+    let b = bytes as f64;
+    if b < KB {
+        format!("{} B", bytes)
+    } else if b < KB * KB {
+        format!("{:.1} KB", b / KB)
+    } else {
+        format!("{:.1} MB", b / (KB * KB))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A file under the system temp dir, removed when dropped
+    struct TempFile(PathBuf);
+
+    impl TempFile {
+        fn new(name: &str, len: u64) -> Self {
+            let dir = std::env::temp_dir().join(format!("coven-tui-attach-{}", std::process::id()));
+            std::fs::create_dir_all(&dir).unwrap();
+            let path = dir.join(name);
+            let file = std::fs::File::create(&path).unwrap();
+            file.set_len(len).unwrap();
+            Self(path)
+        }
+
+        fn path(&self) -> &str {
+            self.0.to_str().unwrap()
+        }
+    }
+
+    impl Drop for TempFile {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_file(&self.0);
+        }
+    }
+
+    #[test]
+    fn test_load_attachment() {
+        let file = TempFile::new("notes.md", 12);
+        let attachment = load_attachment(file.path(), 0).unwrap();
+        assert_eq!(attachment.filename, "notes.md");
+        assert_eq!(attachment.mime_type, "text/markdown");
+        assert_eq!(attachment.data.len(), 12);
+    }
+
+    #[test]
+    fn test_load_attachment_errors() {
+        assert_eq!(
+            load_attachment("  ", 0).unwrap_err(),
+            "Usage: :attach <path>"
+        );
+        assert!(load_attachment("/no/such/file.txt", 0)
+            .unwrap_err()
+            .starts_with("No such file"));
+        let dir = std::env::temp_dir();
+        assert!(load_attachment(dir.to_str().unwrap(), 0)
+            .unwrap_err()
+            .starts_with("Not a file"));
+    }
+
+    #[test]
+    fn test_load_attachment_respects_size_limit() {
+        let big = TempFile::new("big.bin", MAX_ATTACHMENT_BYTES + 1);
+        let err = load_attachment(big.path(), 0).unwrap_err();
+        assert!(err.contains("limited to 10.0 MB"), "{}", err);
+
+        // Files already attached to the message count toward the limit
+        let small = TempFile::new("small.bin", 1024);
+        assert!(load_attachment(small.path(), MAX_ATTACHMENT_BYTES - 1024).is_ok());
+        let err = load_attachment(small.path(), MAX_ATTACHMENT_BYTES - 10).unwrap_err();
+        assert!(err.contains("only 10 B"), "{}", err);
+    }
+
+    #[test]
+    fn test_mime_type_for() {
+        assert_eq!(mime_type_for(Path::new("a/Photo.PNG")), "image/png");
+        assert_eq!(mime_type_for(Path::new("main.rs")), "text/plain");
+        assert_eq!(
+            mime_type_for(Path::new("Makefile")),
+            "application/octet-stream"
+        );
+    }
+
+    #[test]
+    fn test_format_size() {
+        assert_eq!(format_size(512), "512 B");
+        assert_eq!(format_size(1536), "1.5 KB");
+        assert_eq!(format_size(MAX_ATTACHMENT_BYTES), "10.0 MB");
+    }
+}
@@ -0,0 +1,360 @@
+// ABOUTME: Export command that writes an agent's conversation history to Markdown or JSON.
+// ABOUTME: Pages through the gateway's event log and writes each page out as it arrives.
+
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::PathBuf;
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use coven_client::HistoryEvent;
+use serde::Serialize;
+
+use crate::client::Client;
+
+/// Output format for an export
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum)]
+pub enum ExportFormat {
+    /// Markdown, for reading and sharing
+    #[default]
+    #[value(name = "md")]
+    Markdown,
+    /// JSON, for tooling
+    Json,
+}
+
+/// What to export and where to write it
+#[derive(Debug, Clone)]
+pub struct ExportOptions {
+    /// Agent ID or name
+    pub agent: String,
+    /// Conversation to export instead of the agent's main one
+    pub thread: Option<String>,
+    pub format: ExportFormat,
+    /// File to write; stdout when `None`
+    pub out: Option<PathBuf>,
+}
+
+/// Describes an export; written before the first event
+#[derive(Debug, Clone, Serialize)]
+pub struct ExportHeader {
+    pub agent_id: String,
+    pub agent_name: String,
+    pub conversation_key: String,
+    /// ISO-8601
+    pub exported_at: String,
+}
+
+/// Writes events one at a time, so a history never has to fit in memory.
+pub struct Exporter<W: Write> {
+    out: W,
+    format: ExportFormat,
+    header: ExportHeader,
+    events: usize,
+}
+
+impl<W: Write> Exporter<W> {
+    /// Write the header and get ready for events.
+    pub fn begin(mut out: W, format: ExportFormat, header: ExportHeader) -> io::Result<Self> {
+        match format {
+            ExportFormat::Markdown => {
+                writeln!(out, "# Conversation with {}", header.agent_name)?;
+                writeln!(out)?;
+                writeln!(out, "- Agent: `{}`", header.agent_id)?;
+                if header.conversation_key != header.agent_id {
+                    writeln!(out, "- Thread: `{}`", header.conversation_key)?;
+                }
+                writeln!(out, "- Exported: {}", format_timestamp(&header.exported_at))?;
+            }
+            ExportFormat::Json => {
+                // The events array is left open and filled in by write_event
+                out.write_all(b"{\"export\":")?;
+                serde_json::to_writer(&mut out, &header)?;
+                out.write_all(b",\"events\":[")?;
+            }
+        }
+        Ok(Self {
+            out,
+            format,
+            header,
+            events: 0,
+        })
+    }
+
+    /// Append one event.
+    pub fn write_event(&mut self, event: &HistoryEvent) -> io::Result<()> {
+        match self.format {
+            ExportFormat::Markdown => self.write_markdown(event)?,
+            ExportFormat::Json => {
+                if self.events > 0 {
+                    self.out.write_all(b",")?;
+                }
+                serde_json::to_writer(&mut self.out, event)?;
+            }
+        }
+        self.events += 1;
+        Ok(())
+    }
+
+    fn write_markdown(&mut self, event: &HistoryEvent) -> io::Result<()> {
+        let text = event.text.as_deref().unwrap_or_default();
+        let time = format_timestamp(&event.timestamp);
+        let out = &mut self.out;
+
+        writeln!(out)?;
+        match event.kind.as_str() {
+            "message" => {
+                let sender = if event.is_user {
+                    "You"
+                } else {
+                    self.header.agent_name.as_str()
+                };
+                writeln!(out, "### {} · {}", sender, time)?;
+                writeln!(out)?;
+                // Message text is already Markdown, code blocks included
+                writeln!(out, "{}", text.trim_end())?;
+            }
+            kind @ ("tool_call" | "tool_result") => {
+                let label = if kind == "tool_call" {
+                    "Tool call"
+                } else {
+                    "Tool result"
+                };
+                writeln!(out, "#### {} · {}", label, time)?;
+                writeln!(out)?;
+                write_code_block(out, text)?;
+            }
+            kind => {
+                let label = match kind {
+                    "error" => "Error",
+                    "system" => "System",
+                    other => other,
+                };
+                writeln!(out, "> **{}** · {}", label, time)?;
+                if !text.is_empty() {
+                    writeln!(out, ">")?;
+                    for line in text.lines() {
+                        writeln!(out, "> {}", line)?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Close the document and flush. Returns how many events were written.
+    pub fn finish(mut self) -> io::Result<usize> {
+        if self.format == ExportFormat::Json {
+            self.out.write_all(b"]}\n")?;
+        }
+        self.out.flush()?;
+        Ok(self.events)
+    }
+}
+
+/// Fence `text` as a code block, pretty-printed when it is JSON. The fence
+/// is longer than any run of backticks in the text so it can't end early.
+fn write_code_block(out: &mut impl Write, text: &str) -> io::Result<()> {
+    let (language, body) = match serde_json::from_str::<serde_json::Value>(text) {
+        Ok(value) => (
+            "json",
+            serde_json::to_string_pretty(&value).unwrap_or_else(|_| text.to_string()),
+        ),
+        Err(_) => ("", text.to_string()),
+    };
+    let longest_run = body.split(|c| c != '`').map(str::len).max().unwrap_or(0);
+    let fence = "`".repeat(longest_run.max(2) + 1);
+
+    writeln!(out, "{}{}", fence, language)?;
+    writeln!(out, "{}", body.trim_end())?;
+    writeln!(out, "{}", fence)
+}
+
+/// ISO-8601 timestamps as "2026-01-02 03:04:05 UTC"; anything else as given.
+fn format_timestamp(timestamp: &str) -> String {
+    DateTime::parse_from_rfc3339(timestamp)
+        .map(|t| {
+            t.with_timezone(&Utc)
+                .format("%Y-%m-%d %H:%M:%S UTC")
+                .to_string()
+        })
+        .unwrap_or_else(|_| timestamp.to_string())
+}
+
+/// Run the export command
+/// ID and name of the agent `agent` names, by ID or name. Agents that
+/// aren't registered any more can still have history, so anything else is
+/// used as the ID.
+pub(crate) async fn resolve_agent(client: &Client, agent: &str) -> Result<(String, String)> {
+    let agents = client.list_agents().await?;
+    let found = agents
+        .iter()
+        .find(|a| a.id == agent)
+        .or_else(|| agents.iter().find(|a| a.name.eq_ignore_ascii_case(agent)));
+    Ok(match found {
+        Some(found) => (found.id.clone(), found.name.clone()),
+        None => (agent.to_string(), agent.to_string()),
+    })
+}
+
+pub async fn run(options: ExportOptions) -> Result<()> {
+    let client = Client::new(&crate::run::gateway_url()?, &crate::run::ssh_key_path()?)?;
+
+    let (agent_id, agent_name) = resolve_agent(&client, &options.agent).await?;
+    let conversation_key = options.thread.clone().unwrap_or_else(|| agent_id.clone());
+
+    let out: Box<dyn Write + Send> = match &options.out {
+        Some(path) => {
+            Box::new(File::create(path).with_context(|| format!("creating {}", path.display()))?)
+        }
+        None => Box::new(io::stdout()),
+    };
+    let header = ExportHeader {
+        agent_id,
+        agent_name,
+        conversation_key: conversation_key.clone(),
+        exported_at: Utc::now().to_rfc3339(),
+    };
+    let mut exporter = Exporter::begin(BufWriter::new(out), options.format, header)?;
+
+    let mut cursor = None;
+    loop {
+        let page = client.load_history_page(&conversation_key, cursor).await?;
+        for event in &page.events {
+            exporter.write_event(event)?;
+        }
+        cursor = page.next_cursor;
+        if cursor.is_none() || page.events.is_empty() {
+            break;
+        }
+    }
+
+    let count = exporter.finish()?;
+    if let Some(path) = &options.out {
+        eprintln!("Exported {} events to {}", count, path.display());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn header() -> ExportHeader {
+        ExportHeader {
+            agent_id: "agent-1".to_string(),
+            agent_name: "Hex".to_string(),
+            conversation_key: "agent-1".to_string(),
+            exported_at: "2026-01-02T10:00:00Z".to_string(),
+        }
+    }
+
+    fn event(kind: &str, is_user: bool, text: &str) -> HistoryEvent {
+        HistoryEvent {
+            id: format!("evt-{}", kind),
+            direction: if is_user {
+                "inbound_to_agent".to_string()
+            } else {
+                "outbound_from_agent".to_string()
+            },
+            author: "someone".to_string(),
+            timestamp: "2026-01-02T03:04:05Z".to_string(),
+            kind: kind.to_string(),
+            text: Some(text.to_string()),
+            is_user,
+        }
+    }
+
+    fn export(format: ExportFormat, header: ExportHeader, events: &[HistoryEvent]) -> String {
+        let mut out = Vec::new();
+        let mut exporter = Exporter::begin(&mut out, format, header).unwrap();
+        for event in events {
+            exporter.write_event(event).unwrap();
+        }
+        assert_eq!(exporter.finish().unwrap(), events.len());
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn test_markdown_renders_roles_tools_and_timestamps() {
+        let markdown = export(
+            ExportFormat::Markdown,
+            header(),
+            &[
+                event("message", true, "List the files"),
+                event(
+                    "tool_call",
+                    false,
+                    r#"{"name":"bash","input":{"cmd":"ls"}}"#,
+                ),
+                event("tool_result", false, "Cargo.toml\nsrc"),
+                event("message", false, "Here you go:\n\n```\nCargo.toml\n```\n"),
+                event("error", false, "rate limited"),
+            ],
+        );
+
+        assert!(markdown.starts_with("# Conversation with Hex\n"));
+        assert!(!markdown.contains("Thread:"));
+        assert!(markdown.contains("- Exported: 2026-01-02 10:00:00 UTC"));
+        assert!(markdown.contains("### You · 2026-01-02 03:04:05 UTC\n\nList the files\n"));
+        assert!(markdown.contains("#### Tool call · 2026-01-02 03:04:05 UTC"));
+        assert!(markdown.contains("```json\n{\n  \"input\""));
+        assert!(markdown
+            .contains("#### Tool result · 2026-01-02 03:04:05 UTC\n\n```\nCargo.toml\nsrc\n```\n"));
+        assert!(markdown.contains(
+            "### Hex · 2026-01-02 03:04:05 UTC\n\nHere you go:\n\n```\nCargo.toml\n```\n"
+        ));
+        assert!(markdown.contains("> **Error** · 2026-01-02 03:04:05 UTC\n>\n> rate limited\n"));
+    }
+
+    #[test]
+    fn test_markdown_fence_outlasts_backticks_in_output() {
+        let mut out = Vec::new();
+        write_code_block(&mut out, "a ```` b").unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), "`````\na ```` b\n`````\n");
+    }
+
+    #[test]
+    fn test_markdown_names_thread() {
+        let mut header = header();
+        header.conversation_key = "thread-9".to_string();
+        let markdown = export(ExportFormat::Markdown, header, &[]);
+        assert!(markdown.contains("- Thread: `thread-9`"));
+    }
+
+    #[test]
+    fn test_json_is_one_valid_document() {
+        let json = export(
+            ExportFormat::Json,
+            header(),
+            &[
+                event("message", true, "hi"),
+                event("tool_call", false, "{}"),
+            ],
+        );
+
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(value["export"]["agent_name"], "Hex");
+        assert_eq!(value["events"].as_array().unwrap().len(), 2);
+        assert_eq!(value["events"][0]["text"], "hi");
+        assert_eq!(value["events"][0]["is_user"], true);
+        assert_eq!(value["events"][1]["type"], "tool_call");
+        assert_eq!(value["events"][1]["timestamp"], "2026-01-02T03:04:05Z");
+    }
+
+    #[test]
+    fn test_json_without_events() {
+        let json = export(ExportFormat::Json, header(), &[]);
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert!(value["events"].as_array().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_format_timestamp() {
+        assert_eq!(
+            format_timestamp("2026-01-02T04:04:05+01:00"),
+            "2026-01-02 03:04:05 UTC"
+        );
+        assert_eq!(format_timestamp("yesterday"), "yesterday");
+    }
+}
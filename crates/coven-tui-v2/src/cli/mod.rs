@@ -1,4 +1,6 @@
 // ABOUTME: CLI subcommand implementations.
-// ABOUTME: Handles send command.
+// ABOUTME: Handles send, export and threads commands.
 
+pub mod export;
 pub mod send;
+pub mod threads;
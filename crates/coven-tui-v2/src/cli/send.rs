@@ -36,8 +36,13 @@ impl StateCallback for NoOpStateCallback {
 /// Run the send command
 pub fn run(gateway_url: &str, key_path: &Path, message: &str, agent: Option<&str>) -> Result<()> {
     // Create client
-    let client = CovenClient::new_with_auth(gateway_url.to_string(), key_path)
-        .map_err(|e| anyhow::anyhow!("Failed to initialize client: {}", e))?;
+    let passphrase = CovenConfig::passphrase_source()?;
+    let client = CovenClient::new_with_auth_passphrase(
+        gateway_url.to_string(),
+        key_path,
+        passphrase.as_ref(),
+    )
+    .map_err(|e| anyhow::anyhow!("Failed to initialize client: {}", e))?;
 
     // Determine agent to use
     let agent_name = if let Some(name) = agent {
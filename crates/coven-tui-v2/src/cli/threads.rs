@@ -0,0 +1,63 @@
+// ABOUTME: Threads command that lists an agent's conversations by title.
+// ABOUTME: Untitled threads show their conversation key; keys work with `export --thread`.
+
+use anyhow::Result;
+use chrono::{DateTime, Local};
+use coven_client::ThreadSummary;
+
+use crate::cli::export::resolve_agent;
+use crate::client::Client;
+
+/// One line per thread: when it was last active, its title, and its key
+pub fn format_threads(threads: &[ThreadSummary]) -> String {
+    threads
+        .iter()
+        .map(|thread| {
+            let updated = DateTime::from_timestamp_millis(thread.updated_at)
+                .map(|t| t.with_timezone(&Local).format("%Y-%m-%d %H:%M").to_string())
+                .unwrap_or_default();
+            match &thread.title {
+                Some(title) => format!("{}  {}  ({})\n", updated, title, thread.conversation_key),
+                None => format!("{}  {}\n", updated, thread.conversation_key),
+            }
+        })
+        .collect()
+}
+
+pub async fn run(agent: &str) -> Result<()> {
+    let client = Client::new(&crate::run::gateway_url()?, &crate::run::ssh_key_path()?)?;
+    let (agent_id, agent_name) = resolve_agent(&client, agent).await?;
+    let threads = client.list_threads(&agent_id).await?;
+    if threads.is_empty() {
+        eprintln!("{} has no threads yet", agent_name);
+        return Ok(());
+    }
+    print!("{}", format_threads(&threads));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_titled_threads_show_title_and_key() {
+        let threads = [
+            ThreadSummary {
+                conversation_key: "fork-1".to_string(),
+                title: Some("Deploy failures".to_string()),
+                updated_at: 0,
+            },
+            ThreadSummary {
+                conversation_key: "agent-1".to_string(),
+                title: None,
+                updated_at: 0,
+            },
+        ];
+        let lines: Vec<String> = format_threads(&threads)
+            .lines()
+            .map(|line| line.split_once("  ").unwrap().1.to_string())
+            .collect();
+        assert_eq!(lines, ["Deploy failures  (fork-1)", "agent-1"]);
+    }
+}
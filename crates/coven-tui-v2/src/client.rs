@@ -1,12 +1,14 @@
 // ABOUTME: Thin wrapper around coven-client for TUI use
-// ABOUTME: Bridges callback-based API to channels
+// ABOUTME: Exposes the client's event streams as TUI responses
 
-use crate::types::Agent;
+use crate::types::{Agent, OutgoingMessage};
 use anyhow::{anyhow, Result};
-use coven_client::{ConnectionStatus, CovenClient, StateCallback, StreamCallback, StreamEvent};
+use coven_client::{ConnectionStatus, CovenClient, DiagnosticReport, StreamEvent};
+use coven_link::config::CovenConfig;
+use futures::{Stream, StreamExt};
 use std::path::Path;
 use std::sync::Arc;
-use tokio::sync::mpsc;
+use tokio::sync::watch;
 
 /// Response events sent through channel
 #[derive(Debug, Clone)]
@@ -31,28 +33,16 @@ pub enum Response {
         tool_id: String,
         tool_name: String,
         input_json: String,
+        confirm_message: Option<String>,
     },
     Done,
     Error(String),
 }
 
-/// State change events
-#[derive(Debug, Clone)]
-pub enum StateChange {
-    ConnectionStatus(bool),
-    StreamingChanged(String, bool),
-    MessagesChanged(String),
-}
-
-/// Callback bridge that sends to channels
-struct CallbackBridge {
-    response_tx: mpsc::Sender<Response>,
-    state_tx: mpsc::Sender<StateChange>,
-}
-
-impl StreamCallback for CallbackBridge {
-    fn on_event(&self, _agent_id: String, event: StreamEvent) {
-        let response = match event {
+impl Response {
+    /// The TUI's view of a stream event, if it shows it at all
+    fn from_event(event: StreamEvent) -> Option<Self> {
+        Some(match event {
             StreamEvent::Text { content } => Response::Text(content),
             StreamEvent::Thinking { content } => Response::Thinking(content),
             StreamEvent::ToolUse { name, input } => Response::ToolStart { name, input },
@@ -60,7 +50,7 @@ impl StreamCallback for CallbackBridge {
             StreamEvent::ToolState { state, detail } => match state.as_str() {
                 "completed" => Response::ToolComplete(detail),
                 "failed" => Response::ToolError(detail.clone(), detail),
-                _ => return,
+                _ => return None,
             },
             StreamEvent::ToolApprovalRequest {
                 agent_id,
@@ -68,12 +58,14 @@ impl StreamCallback for CallbackBridge {
                 tool_id,
                 tool_name,
                 input_json,
+                confirm_message,
             } => Response::ToolApprovalRequest {
                 agent_id,
                 request_id,
                 tool_id,
                 tool_name,
                 input_json,
+                confirm_message,
             },
             StreamEvent::Usage { info } => Response::Usage {
                 // Saturate negative values to 0 to prevent integer overflow
@@ -82,82 +74,40 @@ impl StreamCallback for CallbackBridge {
             },
             StreamEvent::Done => Response::Done,
             StreamEvent::Error { message } => Response::Error(message),
-        };
-        // Use try_send to avoid blocking the callback thread
-        if self.response_tx.try_send(response).is_err() {
-            tracing::warn!("Response channel full, dropping stream event");
-        }
-    }
-}
-
-impl StateCallback for CallbackBridge {
-    fn on_connection_status(&self, status: ConnectionStatus) {
-        let connected = matches!(status, ConnectionStatus::Connected);
-        // Use try_send to avoid blocking the callback thread
-        if self
-            .state_tx
-            .try_send(StateChange::ConnectionStatus(connected))
-            .is_err()
-        {
-            tracing::warn!("State channel full, dropping connection status");
-        }
-    }
-
-    fn on_messages_changed(&self, agent_id: String) {
-        if self
-            .state_tx
-            .try_send(StateChange::MessagesChanged(agent_id))
-            .is_err()
-        {
-            tracing::warn!("State channel full, dropping messages changed");
-        }
-    }
-
-    fn on_queue_changed(&self, _agent_id: String, _count: u32) {}
-
-    fn on_unread_changed(&self, _agent_id: String, _count: u32) {}
-
-    fn on_streaming_changed(&self, agent_id: String, is_streaming: bool) {
-        if self
-            .state_tx
-            .try_send(StateChange::StreamingChanged(agent_id, is_streaming))
-            .is_err()
-        {
-            tracing::warn!("State channel full, dropping streaming changed");
-        }
+        })
     }
 }
 
 /// TUI client wrapper
+#[derive(Clone)]
 pub struct Client {
     inner: Arc<CovenClient>,
 }
 
 impl Client {
     pub fn new(gateway_url: &str, ssh_key_path: &Path) -> Result<Self> {
-        let inner = CovenClient::new_with_auth(gateway_url.to_string(), ssh_key_path)
-            .map_err(|e| anyhow!("Failed to create client: {}", e))?;
+        let passphrase = CovenConfig::passphrase_source()?;
+        let inner = CovenClient::new_with_auth_passphrase(
+            gateway_url.to_string(),
+            ssh_key_path,
+            passphrase.as_ref(),
+        )
+        .map_err(|e| anyhow!("Failed to create client: {}", e))?;
         Ok(Self {
             inner: Arc::new(inner),
         })
     }
 
-    pub fn setup_callbacks(
-        &self,
-        response_tx: mpsc::Sender<Response>,
-        state_tx: mpsc::Sender<StateChange>,
-    ) {
-        let bridge = CallbackBridge {
-            response_tx,
-            state_tx,
-        };
-        // Note: CovenClient takes Box<dyn Callback>, so we need to clone for each
-        // For now, we'll set up stream callback only
-        self.inner.set_stream_callback(Box::new(CallbackBridge {
-            response_tx: bridge.response_tx.clone(),
-            state_tx: bridge.state_tx.clone(),
-        }));
-        self.inner.set_state_callback(Box::new(bridge));
+    /// Responses from every agent's stream, from now on
+    pub fn responses(&self) -> impl Stream<Item = Response> + Send {
+        self.inner
+            .subscribe_all_events()
+            .filter_map(|(_agent_id, event)| futures::future::ready(Response::from_event(event)))
+    }
+
+    /// Whether the gateway is connected, updated as it changes
+    pub fn connection_events(&self) -> watch::Receiver<ConnectionStatus> {
+        self.inner.connection_events()
     }
 
     pub async fn list_agents(&self) -> Result<Vec<Agent>> {
@@ -169,9 +119,13 @@ impl Client {
         Ok(agents.into_iter().map(Agent::from).collect())
     }
 
-    pub fn send_message(&self, agent_id: &str, content: &str) -> Result<()> {
+    pub fn send_message(&self, agent_id: &str, message: OutgoingMessage) -> Result<()> {
         self.inner
-            .send_message(agent_id.to_string(), content.to_string())
+            .send_message_with_attachments(
+                agent_id.to_string(),
+                message.content,
+                message.attachments,
+            )
             .map_err(|e| anyhow!("Failed to send message: {}", e))
     }
 
@@ -182,6 +136,35 @@ impl Client {
             .map_err(|e| anyhow!("Failed to load history: {}", e))
     }
 
+    /// Fork a conversation from one of its messages, returning the fork's key
+    pub async fn fork_thread(&self, conversation_key: &str, message_id: &str) -> Result<String> {
+        self.inner
+            .fork_thread_async(conversation_key.to_string(), message_id.to_string())
+            .await
+            .map_err(|e| anyhow!("Failed to fork conversation: {}", e))
+    }
+
+    /// An agent's conversations with their titles, most recent first
+    pub async fn list_threads(&self, agent_id: &str) -> Result<Vec<coven_client::ThreadSummary>> {
+        self.inner
+            .list_threads_async(agent_id.to_string())
+            .await
+            .map_err(|e| anyhow!("Failed to list threads: {}", e))
+    }
+
+    /// One page of a conversation's full event log; see
+    /// `CovenClient::load_history_page_async`.
+    pub async fn load_history_page(
+        &self,
+        conversation_key: &str,
+        cursor: Option<String>,
+    ) -> Result<coven_client::HistoryPage> {
+        self.inner
+            .load_history_page_async(conversation_key.to_string(), cursor)
+            .await
+            .map_err(|e| anyhow!("Failed to load history: {}", e))
+    }
+
     pub fn get_session_usage(&self) -> (u32, u32) {
         let usage = self.inner.get_session_usage();
         (usage.input_tokens as u32, usage.output_tokens as u32)
@@ -201,6 +184,11 @@ impl Client {
             .map_err(|e| anyhow!("Health check failed: {}", e))
     }
 
+    /// Check the connection step by step, for the doctor screen
+    pub async fn diagnose(&self) -> DiagnosticReport {
+        self.inner.diagnose_async().await
+    }
+
     /// Respond to a tool approval request (async version for tokio context)
     pub async fn approve_tool_async(
         &self,
@@ -14,6 +14,10 @@ struct Args {
     #[arg(short, long)]
     agent: Option<String>,
 
+    /// Config profile to connect with (default: COVEN_PROFILE, then the config's default)
+    #[arg(long, global = true)]
+    profile: Option<String>,
+
     #[command(subcommand)]
     command: Option<Command>,
 }
@@ -28,10 +32,32 @@ enum Command {
         #[arg(short, long)]
         print: bool,
     },
+    /// Export an agent's conversation history to Markdown or JSON
+    Export {
+        /// Agent ID or name
+        agent: String,
+        /// Output format
+        #[arg(long, value_enum, default_value = "md")]
+        format: coven_tui_v2::cli::export::ExportFormat,
+        /// File to write (default: stdout)
+        #[arg(long)]
+        out: Option<std::path::PathBuf>,
+        /// Conversation to export instead of the agent's main one
+        #[arg(long)]
+        thread: Option<String>,
+    },
+    /// List an agent's conversations by title
+    Threads {
+        /// Agent ID or name
+        agent: String,
+    },
 }
 
 fn main() -> Result<()> {
     let args = Args::parse();
+    if let Some(profile) = &args.profile {
+        coven_link::config::select_profile(profile.as_str());
+    }
 
     // Handle subcommands (these don't need the TUI)
     match args.command {
@@ -54,6 +80,32 @@ fn main() -> Result<()> {
             coven_tui_v2::cli::send::run(&gw_url, &key_path, &message, args.agent.as_deref())?;
             Ok(())
         }
+        Some(Command::Export {
+            agent,
+            format,
+            out,
+            thread,
+        }) => {
+            coven_log::init_file("tui");
+
+            let options = coven_tui_v2::cli::export::ExportOptions {
+                agent,
+                thread,
+                format,
+                out,
+            };
+            tokio::runtime::Builder::new_multi_thread()
+                .enable_all()
+                .build()?
+                .block_on(coven_tui_v2::cli::export::run(options))
+        }
+        Some(Command::Threads { agent }) => {
+            coven_log::init_file("tui");
+            tokio::runtime::Builder::new_multi_thread()
+                .enable_all()
+                .build()?
+                .block_on(coven_tui_v2::cli::threads::run(&agent))
+        }
         None => {
             // Run interactive TUI via the library entry point
             coven_tui_v2::run::run(args.agent)
@@ -5,13 +5,15 @@ use anyhow::{Context, Result};
 use coven_link::config::CovenConfig;
 
 use crate::app::{Action, App};
-use crate::client::{Client, Response, StateChange};
+use crate::client::Client;
 use crate::ui;
+use coven_client::DiagnosticReport;
 use crossterm::{
     event::{self, Event, KeyEvent},
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
+use futures::StreamExt;
 use ratatui::prelude::*;
 use std::io::{self, Stdout};
 use std::path::PathBuf;
@@ -65,7 +67,7 @@ fn state_dir() -> Result<PathBuf> {
 }
 
 /// Get the gateway URL from coven config
-fn gateway_url() -> Result<String> {
+pub(crate) fn gateway_url() -> Result<String> {
     let config = CovenConfig::load()
         .context("No coven config found. Run 'coven link' first to set up gateway connection.")?;
 
@@ -79,7 +81,7 @@ fn gateway_url() -> Result<String> {
 }
 
 /// Get the SSH key path for authentication (use coven's device key)
-fn ssh_key_path() -> Result<PathBuf> {
+pub(crate) fn ssh_key_path() -> Result<PathBuf> {
     CovenConfig::key_path()
 }
 
@@ -110,13 +112,12 @@ async fn run_main_loop(
     initial_agent: Option<String>,
     client: Client,
 ) -> Result<()> {
-    // Create channels
-    let (response_tx, mut response_rx) = mpsc::channel::<Response>(32);
-    let (state_tx, mut state_rx) = mpsc::channel::<StateChange>(32);
+    // Subscribe before connecting so no event is missed
+    let responses = client.responses();
+    tokio::pin!(responses);
+    let mut connection = client.connection_events();
     let (key_tx, mut key_rx) = mpsc::channel::<KeyEvent>(32);
-
-    // Set up callbacks
-    client.setup_callbacks(response_tx, state_tx);
+    let (report_tx, mut report_rx) = mpsc::channel::<DiagnosticReport>(1);
 
     // Create app with persisted state
     let state_dir = state_dir()?;
@@ -174,9 +175,9 @@ async fn run_main_loop(
                             }
                             break;
                         }
-                        Action::SendMessage(content) => {
-                            if let Some(agent_id) = &app.selected_agent {
-                                if let Err(e) = client.send_message(agent_id, &content) {
+                        Action::SendMessage(message) => {
+                            if let Some(key) = app.conversation_key() {
+                                if let Err(e) = client.send_message(key, message) {
                                     app.error = Some(format!("Failed to send: {}", e));
                                     app.streaming = None;
                                     app.mode = crate::types::Mode::Chat;
@@ -267,17 +268,46 @@ async fn run_main_loop(
                                 }
                             }
                         }
+                        Action::ForkThread { conversation_key, message_id } => {
+                            match client.fork_thread(&conversation_key, &message_id).await {
+                                Ok(fork) => {
+                                    app.open_fork(fork.clone());
+                                    match client.load_history(&fork).await {
+                                        Ok(messages) => {
+                                            app.messages = messages
+                                                .into_iter()
+                                                .map(crate::types::Message::from)
+                                                .collect();
+                                        }
+                                        Err(e) => {
+                                            tracing::warn!("Failed to load fork history: {}", e);
+                                        }
+                                    }
+                                }
+                                Err(e) => {
+                                    app.error = Some(format!("Failed to fork: {}", e));
+                                }
+                            }
+                        }
+                        Action::Diagnose => {
+                            // The steps can take a few seconds; keep drawing meanwhile
+                            let client = client.clone();
+                            let report_tx = report_tx.clone();
+                            tokio::spawn(async move {
+                                let _ = report_tx.send(client.diagnose().await).await;
+                            });
+                        }
                     }
                 }
             }
 
             // Response events from client
-            Some(response) = response_rx.recv() => {
+            Some(response) = responses.next() => {
                 app.handle_response(response);
                 // Drain queued messages after response handling
-                if let Some(Action::SendMessage(content)) = app.take_queued_action() {
-                    if let Some(agent_id) = &app.selected_agent {
-                        if let Err(e) = client.send_message(agent_id, &content) {
+                if let Some(Action::SendMessage(message)) = app.take_queued_action() {
+                    if let Some(key) = app.conversation_key() {
+                        if let Err(e) = client.send_message(key, message) {
                             app.error = Some(format!("Failed to send: {}", e));
                             app.streaming = None;
                             app.mode = crate::types::Mode::Chat;
@@ -290,19 +320,17 @@ async fn run_main_loop(
                 }
             }
 
-            // State change events from client
-            Some(state_change) = state_rx.recv() => {
-                match state_change {
-                    StateChange::ConnectionStatus(connected) => {
-                        app.connected = connected;
-                    }
-                    StateChange::StreamingChanged(_agent_id, _is_streaming) => {
-                        // Streaming state is handled by Response events
-                    }
-                    StateChange::MessagesChanged(_agent_id) => {
-                        // Messages changed externally - could refresh here
-                    }
-                }
+            // A diagnosis finished
+            Some(report) = report_rx.recv() => {
+                app.set_diagnostics(report);
+            }
+
+            // Connection status changes from client
+            Ok(()) = connection.changed() => {
+                app.connected = matches!(
+                    *connection.borrow_and_update(),
+                    coven_client::ConnectionStatus::Connected
+                );
             }
 
             // Tick for throbber animation
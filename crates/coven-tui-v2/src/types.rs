@@ -1,8 +1,12 @@
 // ABOUTME: Core types for coven-tui-v2
-// ABOUTME: Mode, Agent, Message, StreamingMessage, and metadata types
+// ABOUTME: Mode, Agent, Message, attachments, StreamingMessage, and metadata types
 
+use crate::attach::format_size;
 use chrono::{DateTime, Utc};
+use coven_client::{Attachment, Threaded};
+use coven_proto::AgentPresence;
 use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
 
 /// Application mode / screen state
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -41,6 +45,16 @@ pub struct Agent {
     pub working_dir: String,
     pub capabilities: Vec<String>,
     pub connected: bool,
+    /// Agent-set presence, e.g. "available" or "busy"
+    pub status: String,
+    pub status_emoji: String,
+}
+
+impl Agent {
+    /// Presence as shown in the picker, emoji first when set
+    pub fn presence_label(&self) -> String {
+        AgentPresence::format_label(&self.status, &self.status_emoji)
+    }
 }
 
 impl From<coven_client::Agent> for Agent {
@@ -53,6 +67,8 @@ impl From<coven_client::Agent> for Agent {
             working_dir: a.working_dir,
             capabilities: vec![],
             connected: a.connected,
+            status: a.status,
+            status_emoji: a.status_emoji,
         }
     }
 }
@@ -82,6 +98,12 @@ pub struct Message {
     pub thinking: Option<String>,
     pub timestamp: DateTime<Utc>,
     pub tokens: Option<MessageTokens>,
+    /// Gateway ID, for messages loaded from history
+    pub id: Option<String>,
+    /// Gateway ID of the message this one replies to
+    pub reply_to: Option<String>,
+    /// Files sent with the message, shown as chips under it
+    pub attachments: Vec<AttachedFile>,
 }
 
 impl Message {
@@ -92,6 +114,9 @@ impl Message {
             thinking: None,
             timestamp: Utc::now(),
             tokens: None,
+            id: None,
+            reply_to: None,
+            attachments: vec![],
         }
     }
 
@@ -102,6 +127,9 @@ impl Message {
             thinking: None,
             timestamp: Utc::now(),
             tokens: None,
+            id: None,
+            reply_to: None,
+            attachments: vec![],
         }
     }
 
@@ -132,8 +160,73 @@ impl From<coven_client::Message> for Message {
             thinking: None,
             timestamp,
             tokens: None,
+            id: Some(m.id),
+            reply_to: m.reply_to_message_id,
+            attachments: vec![],
+        }
+    }
+}
+
+/// A file attached to a sent message
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AttachedFile {
+    pub filename: String,
+    pub size: u64,
+}
+
+impl AttachedFile {
+    /// Label shown for the file in the chat and input areas
+    pub fn chip(&self) -> String {
+        format!("📎 {} ({})", self.filename, format_size(self.size))
+    }
+}
+
+impl From<&Attachment> for AttachedFile {
+    fn from(a: &Attachment) -> Self {
+        Self {
+            filename: a.filename.clone(),
+            size: a.data.len() as u64,
+        }
+    }
+}
+
+/// A message on its way out, with any files attached to it
+#[derive(Debug, Clone, Default)]
+pub struct OutgoingMessage {
+    pub content: String,
+    pub attachments: Vec<Attachment>,
+}
+
+impl OutgoingMessage {
+    /// The user's copy of the message, as shown in the conversation
+    pub fn to_message(&self) -> Message {
+        Message {
+            attachments: self.attachments.iter().map(AttachedFile::from).collect(),
+            ..Message::user(self.content.clone())
+        }
+    }
+}
+
+impl Threaded for Message {
+    fn message_id(&self) -> Option<&str> {
+        self.id.as_deref()
+    }
+
+    fn reply_to(&self) -> Option<&str> {
+        self.reply_to.as_deref()
+    }
+
+    fn quote_sender(&self) -> &str {
+        match self.role {
+            Role::User => "You",
+            Role::Assistant => "Agent",
+            Role::System => "System",
         }
     }
+
+    fn quote_text(&self) -> Cow<'_, str> {
+        Cow::Owned(self.content())
+    }
 }
 
 /// A block in a streaming message (text or tool use, in order)
@@ -176,6 +269,8 @@ pub struct PendingApproval {
     pub tool_id: String,
     pub tool_name: String,
     pub input_json: String,
+    /// Human-readable confirmation text from the tool definition, if any
+    pub confirm_message: Option<String>,
     pub timestamp: DateTime<Utc>,
 }
 
@@ -193,6 +288,7 @@ pub enum ApprovalDecision {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use coven_client::reply_quote;
 
     #[test]
     fn test_mode_equality() {
@@ -208,12 +304,79 @@ mod tests {
         assert!(msg.thinking.is_none());
     }
 
+    #[test]
+    fn test_reply_quote() {
+        let with_ids = |mut msg: Message, id: &str, reply_to: Option<&str>| {
+            msg.id = Some(id.to_string());
+            msg.reply_to = reply_to.map(str::to_string);
+            msg
+        };
+        let messages = vec![
+            with_ids(
+                Message::user("first question\nmore".to_string()),
+                "m1",
+                None,
+            ),
+            with_ids(Message::assistant("answer".to_string()), "m2", Some("m1")),
+            with_ids(Message::user("back to that".to_string()), "m3", Some("m1")),
+            with_ids(Message::user("and this".to_string()), "m4", Some("gone")),
+        ];
+
+        assert_eq!(reply_quote(&messages, 0), None);
+        // Replying to the message right above needs no quote
+        assert_eq!(reply_quote(&messages, 1), None);
+        assert_eq!(
+            reply_quote(&messages, 2).as_deref(),
+            Some("↳ You: first question…")
+        );
+        assert_eq!(
+            reply_quote(&messages, 3).as_deref(),
+            Some("↳ reply to an earlier message")
+        );
+    }
+
+    #[test]
+    fn test_outgoing_message_shows_attachments() {
+        let outgoing = OutgoingMessage {
+            content: "see attached".to_string(),
+            attachments: vec![Attachment {
+                filename: "notes.md".to_string(),
+                mime_type: "text/markdown".to_string(),
+                data: vec![0; 1536],
+            }],
+        };
+        let msg = outgoing.to_message();
+        assert_eq!(msg.role, Role::User);
+        assert_eq!(msg.content(), "see attached");
+        assert_eq!(msg.attachments[0].chip(), "📎 notes.md (1.5 KB)");
+    }
+
     #[test]
     fn test_message_assistant() {
         let msg = Message::assistant("hi".to_string());
         assert_eq!(msg.role, Role::Assistant);
     }
 
+    #[test]
+    fn test_agent_presence_label() {
+        let mut agent = Agent {
+            id: "1".to_string(),
+            name: "Claude".to_string(),
+            backend: "mux".to_string(),
+            model: None,
+            working_dir: String::new(),
+            capabilities: vec![],
+            connected: true,
+            status: "available".to_string(),
+            status_emoji: String::new(),
+        };
+        assert_eq!(agent.presence_label(), "available");
+
+        agent.status = "busy".to_string();
+        agent.status_emoji = "🔥".to_string();
+        assert_eq!(agent.presence_label(), "🔥 busy");
+    }
+
     #[test]
     fn test_streaming_message_default() {
         let sm = StreamingMessage::default();
@@ -229,6 +392,7 @@ mod tests {
             tool_id: "tool-1".to_string(),
             tool_name: "bash".to_string(),
             input_json: r#"{"command": "ls"}"#.to_string(),
+            confirm_message: None,
             timestamp: Utc::now(),
         };
         assert_eq!(approval.agent_id, "agent-1");
@@ -25,19 +25,17 @@ pub fn render(f: &mut Frame, app: &App) {
         None => return,
     };
 
-    // Format input JSON nicely (with indentation)
-    let formatted_input = format_json(&approval.input_json);
-
     // Build the content
     let approval_count = app.pending_approvals.len();
     let current_idx = app.selected_approval.unwrap_or(0) + 1;
 
     let content = format!(
         "Tool: {}\n\n\
-         Input:\n{}\n\n\
+         {}\n\n\
          ─────────────────────────────────────────\n\
          [y/Enter] Approve  [n/Esc] Deny  [a] Approve All",
-        approval.tool_name, formatted_input
+        approval.tool_name,
+        approval_detail(approval.confirm_message.as_deref(), &approval.input_json)
     );
 
     let title = if approval_count > 1 {
@@ -60,6 +58,15 @@ pub fn render(f: &mut Frame, app: &App) {
     f.render_widget(paragraph, area);
 }
 
+/// Describe what the tool will do: the tool's confirmation message when it
+/// supplies one, otherwise the formatted input JSON.
+fn approval_detail(confirm_message: Option<&str>, input_json: &str) -> String {
+    match confirm_message {
+        Some(message) => format!("⚠ {}", message),
+        None => format!("Input:\n{}", format_json(input_json)),
+    }
+}
+
 /// Format JSON string with indentation for display
 fn format_json(json_str: &str) -> String {
     // Try to parse and pretty-print
@@ -88,6 +95,20 @@ pub(crate) mod tests {
         assert_eq!(formatted, "not json");
     }
 
+    #[test]
+    fn test_approval_detail_prefers_confirm_message() {
+        let detail = approval_detail(Some("This will delete 3 files"), r#"{"count": 3}"#);
+        assert!(detail.contains("This will delete 3 files"));
+        assert!(!detail.contains("Input:"));
+    }
+
+    #[test]
+    fn test_approval_detail_falls_back_to_input() {
+        let detail = approval_detail(None, r#"{"command": "ls -la"}"#);
+        assert!(detail.starts_with("Input:"));
+        assert!(detail.contains("ls -la"));
+    }
+
     #[test]
     fn test_centered_rect() {
         let area = Rect::new(0, 0, 100, 100);
@@ -4,6 +4,7 @@
 use crate::app::App;
 use crate::types::{Mode, Role, StreamBlock, ToolStatus, ToolUse};
 use chrono::Local;
+use coven_client::reply_quote;
 use ratatui::prelude::*;
 use ratatui::widgets::Paragraph;
 use ratatui::Frame;
@@ -87,10 +88,13 @@ fn format_tool_header(name: &str, input: &str) -> (String, String) {
 ///           ⎿  result line 1
 ///              result line 2
 ///              … +N lines
+///
+/// With `expanded` set every result line is shown.
 fn render_tool<'a>(
     tool: &'a ToolUse,
     time: &str,
     throbber: Option<char>,
+    expanded: bool,
     lines: &mut Vec<Line<'a>>,
 ) {
     let dot_style = match tool.status {
@@ -116,11 +120,16 @@ fn render_tool<'a>(
         Span::styled(format!("({})", input_display), Style::default().dim()),
     ]));
 
-    // Tool result (truncated)
+    // Tool result (truncated unless expanded)
     if let Some(result) = &tool.result {
         let result_lines: Vec<&str> = result.lines().collect();
-        let show_count = result_lines.len().min(MAX_RESULT_LINES);
-        let remaining = result_lines.len().saturating_sub(MAX_RESULT_LINES);
+        let limit = if expanded {
+            result_lines.len()
+        } else {
+            MAX_RESULT_LINES
+        };
+        let show_count = result_lines.len().min(limit);
+        let remaining = result_lines.len().saturating_sub(limit);
 
         for line in result_lines.iter().take(show_count) {
             lines.push(Line::from(vec![
@@ -131,7 +140,7 @@ fn render_tool<'a>(
 
         if remaining > 0 {
             lines.push(Line::from(Span::styled(
-                format!("{}  … +{} lines", INDENT, remaining),
+                format!("{}  … +{} lines (ctrl+o to expand)", INDENT, remaining),
                 Style::default().dim(),
             )));
         }
@@ -142,13 +151,21 @@ pub fn render(f: &mut Frame, area: Rect, app: &App) {
     let mut lines: Vec<Line> = vec![];
 
     // Render past messages
-    for msg in &app.messages {
+    for (index, msg) in app.messages.iter().enumerate() {
+        let start = lines.len();
         let time = msg
             .timestamp
             .with_timezone(&Local)
             .format("%H:%M")
             .to_string();
 
+        if let Some(quote) = reply_quote(&app.messages, index) {
+            lines.push(Line::from(Span::styled(
+                format!("{}{}", INDENT, quote),
+                Style::default().dim().italic(),
+            )));
+        }
+
         match msg.role {
             Role::User => {
                 let bg = Style::default().bg(Color::Rgb(40, 40, 40));
@@ -169,6 +186,12 @@ pub fn render(f: &mut Frame, area: Rect, app: &App) {
                 for line in content_lines.iter().skip(1) {
                     lines.push(Line::from(Span::styled(format!("{}{}", INDENT, line), bg)));
                 }
+                for file in &msg.attachments {
+                    lines.push(Line::from(Span::styled(
+                        format!("{}{}", INDENT, file.chip()),
+                        bg.fg(Color::Cyan),
+                    )));
+                }
             }
             Role::Assistant | Role::System => {
                 let mut first_text_seen = false;
@@ -203,13 +226,20 @@ pub fn render(f: &mut Frame, area: Rect, app: &App) {
                             }
                         }
                         StreamBlock::Tool(tool) => {
-                            render_tool(tool, &time, None, &mut lines);
+                            render_tool(tool, &time, None, app.expand_tool_results, &mut lines);
                         }
                     }
                 }
             }
         }
 
+        // Mark the message picked to fork from
+        if app.selected_message == Some(index) {
+            for line in &mut lines[start..] {
+                *line = std::mem::take(line).patch_style(Style::default().on_blue());
+            }
+        }
+
         lines.push(Line::from(""));
     }
 
@@ -262,7 +292,13 @@ pub fn render(f: &mut Frame, area: Rect, app: &App) {
                         }
                     }
                     StreamBlock::Tool(tool) => {
-                        render_tool(tool, &now, Some(app.throbber_char()), &mut lines);
+                        render_tool(
+                            tool,
+                            &now,
+                            Some(app.throbber_char()),
+                            app.expand_tool_results,
+                            &mut lines,
+                        );
                     }
                 }
             }
@@ -0,0 +1,94 @@
+// ABOUTME: Connection doctor rendering
+// ABOUTME: Centered modal listing each diagnostic step with its outcome
+
+use super::centered_rect;
+use crate::app::App;
+use coven_client::{DiagnosticReport, DiagnosticStatus};
+use ratatui::prelude::*;
+use ratatui::widgets::{Block, Borders, Clear, Paragraph, Wrap};
+use ratatui::Frame;
+
+/// Render the connection doctor overlay
+pub fn render(f: &mut Frame, app: &App) {
+    // Center overlay: 80% width, 60% height
+    let area = centered_rect(80, 60, f.area());
+
+    // Clear background
+    f.render_widget(Clear, area);
+
+    let (lines, border) = match &app.diagnostics {
+        Some(report) if !app.diagnosing => {
+            let border = if report.healthy {
+                Style::default().green()
+            } else {
+                Style::default().red()
+            };
+            (report_lines(report), border)
+        }
+        _ => (
+            vec![Line::from(format!(
+                "{} Checking the connection…",
+                app.throbber_char()
+            ))],
+            Style::default().yellow(),
+        ),
+    };
+
+    let paragraph = Paragraph::new(lines).wrap(Wrap { trim: false }).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .border_style(border)
+            .title(" Connection Doctor ")
+            .title_style(border.bold())
+            .title_bottom(" [r] Run again  [Esc] Close "),
+    );
+
+    f.render_widget(paragraph, area);
+}
+
+/// One line per step, then a summary
+fn report_lines(report: &DiagnosticReport) -> Vec<Line<'static>> {
+    let mut lines = vec![
+        Line::styled(
+            format!("Gateway: {}", report.gateway_url),
+            Style::default().dim(),
+        ),
+        Line::raw(""),
+    ];
+
+    for step in &report.steps {
+        let (symbol, style) = status_symbol(step.status);
+        let mut spans = vec![
+            Span::styled(format!("{} ", symbol), style),
+            Span::styled(format!("{:<6}", step.name), Style::default().bold()),
+            Span::raw(step.message.clone()),
+        ];
+        if step.duration_ms > 0 {
+            spans.push(Span::styled(
+                format!(" ({} ms)", step.duration_ms),
+                Style::default().dim(),
+            ));
+        }
+        lines.push(Line::from(spans));
+    }
+
+    lines.push(Line::raw(""));
+    lines.push(if report.healthy {
+        Line::styled("The connection looks fine.", Style::default().green())
+    } else {
+        Line::styled(
+            "Fix the first failed step; the ones after it couldn't run.",
+            Style::default().red(),
+        )
+    });
+    lines
+}
+
+fn status_symbol(status: DiagnosticStatus) -> (&'static str, Style) {
+    match status {
+        DiagnosticStatus::Ok => ("✓", Style::default().green()),
+        DiagnosticStatus::Warning => ("⚠", Style::default().yellow()),
+        DiagnosticStatus::Failed => ("✗", Style::default().red()),
+        DiagnosticStatus::Skipped => ("-", Style::default().dim()),
+    }
+}
@@ -1,7 +1,8 @@
 // ABOUTME: Input area rendering
-// ABOUTME: Black background with top/bottom borders
+// ABOUTME: Black background with top/bottom borders, queue count and attachment chips
 
 use crate::app::App;
+use crate::types::AttachedFile;
 use ratatui::prelude::*;
 use ratatui::widgets::{Block, Borders, Clear};
 use ratatui::Frame;
@@ -21,6 +22,21 @@ pub fn render(f: &mut Frame, area: Rect, app: &App) {
         block
     };
 
+    // Files attached with :attach, sent with the next message
+    let block = if app.pending_attachments.is_empty() {
+        block
+    } else {
+        let chips: Vec<String> = app
+            .pending_attachments
+            .iter()
+            .map(|a| AttachedFile::from(a).chip())
+            .collect();
+        block.title_bottom(
+            Line::from(format!(" {} ", chips.join("  ")))
+                .style(Style::default().fg(Color::Cyan).bg(Color::Rgb(0, 0, 0))),
+        )
+    };
+
     // Clear the area first so the background fills completely
     f.render_widget(Clear, area);
 
@@ -3,6 +3,7 @@
 
 mod approval;
 mod chat;
+mod doctor;
 mod input;
 mod picker;
 mod status;
@@ -46,8 +47,13 @@ pub fn render(f: &mut Frame, app: &App) {
         picker::render(f, app);
     }
 
-    // Approval dialog is an overlay (shown on top of everything)
+    // Approval dialog is an overlay
     if app.has_pending_approvals() {
         approval::render(f, app);
     }
+
+    // The connection doctor was asked for, so it goes on top of everything
+    if app.diagnostics.is_some() || app.diagnosing {
+        doctor::render(f, app);
+    }
 }
@@ -23,7 +23,18 @@ pub fn render(f: &mut Frame, app: &App) {
         .map(|(i, agent)| {
             let icon = if agent.connected { "●" } else { "○" };
             let model = agent.model.as_deref().unwrap_or(&agent.backend);
-            let text = format!(" {} {} ({})", icon, agent.name, model);
+            // Presence only means something while the agent is connected
+            let text = if agent.connected {
+                format!(
+                    " {} {} ({}) · {}",
+                    icon,
+                    agent.name,
+                    model,
+                    agent.presence_label()
+                )
+            } else {
+                format!(" {} {} ({})", icon, agent.name, model)
+            };
 
             let style = if i == app.picker_index {
                 Style::default().reversed()
@@ -18,6 +18,9 @@ pub fn render(f: &mut Frame, area: Rect, app: &App) {
                 Style::default().bold(),
             ));
         }
+        if app.conversation.is_some() {
+            spans.push(Span::styled("fork ", Style::default().cyan()));
+        }
     } else {
         spans.push(Span::styled(" No agent ", Style::default().dim()));
     }
@@ -62,6 +65,21 @@ pub fn render(f: &mut Frame, area: Rect, app: &App) {
         ));
     }
 
+    // Point at the doctor when something's wrong with the connection
+    if !app.connected || app.error.is_some() {
+        spans.push(Span::styled(
+            "│ Ctrl+D: diagnose ",
+            Style::default().yellow(),
+        ));
+    }
+
+    if app.selected_message.is_some() {
+        spans.push(Span::styled(
+            "│ Ctrl+F: fork from here │ Esc: unselect ",
+            Style::default().cyan(),
+        ));
+    }
+
     // Keybinds (right side - we'll just append for now)
     spans.push(Span::styled(
         "│ Ctrl+Space: agents │ Ctrl+Q: quit ",
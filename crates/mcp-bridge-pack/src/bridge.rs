@@ -0,0 +1,476 @@
+// ABOUTME: Tool handler that fronts one or more MCP servers as a single pack.
+// ABOUTME: Routes each call to the server owning the tool's prefix and isolates servers that crash.
+
+use crate::config::{parse_mcp_command, ServerConfig, TransportKind, PREFIX_SEPARATOR};
+use crate::mcp_client::{McpClient, McpTool};
+use crate::tools;
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use coven_pack::{
+    HealthStatus, ManifestBuilder, PackClient, PackManifest, ToolError, ToolHandler,
+    RECONNECTING_REASON,
+};
+use coven_proto::ToolDefinition;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::RwLock;
+use tracing::{error, info, warn};
+
+/// One MCP server behind the bridge.
+pub struct McpServer {
+    name: String,
+    /// Prepended to this server's tool names; empty for none
+    prefix: String,
+    client: RwLock<McpClient>,
+    has_resources: bool,
+    has_prompts: bool,
+    /// MCP tools currently offered, updated when the server's list changes
+    tools: RwLock<Vec<McpTool>>,
+    /// Why the server stopped working, once it has exited
+    failure: RwLock<Option<String>>,
+}
+
+impl McpServer {
+    /// Spawn or connect to a server, initialize it, and read its tools.
+    pub async fn start(config: &ServerConfig) -> Result<Self> {
+        let mut client = match config.transport {
+            TransportKind::Stdio => {
+                let (command, args) =
+                    parse_mcp_command(config.command.as_deref().unwrap_or_default())?;
+                let args_refs: Vec<&str> = args.iter().map(|s| s.as_str()).collect();
+
+                info!(server = %config.name, command = %command, args = ?args_refs, "Spawning MCP server");
+                McpClient::spawn(&command, &args_refs, Some(config.env.clone())).await?
+            }
+            TransportKind::Http => {
+                let url = config.url.as_deref().unwrap_or_default();
+                info!(server = %config.name, url = %url, "Connecting to MCP server");
+                McpClient::connect_http(url, config.bearer_token.clone())?
+            }
+        };
+
+        let init_result = client
+            .initialize()
+            .await
+            .with_context(|| format!("initializing MCP server '{}'", config.name))?;
+        info!(
+            server = %config.name,
+            server_name = %init_result.server_info.name,
+            server_version = ?init_result.server_info.version,
+            "MCP server initialized"
+        );
+
+        let mcp_tools = client
+            .list_tools()
+            .await
+            .with_context(|| format!("listing tools of MCP server '{}'", config.name))?;
+        info!(server = %config.name, count = mcp_tools.len(), "Discovered MCP tools");
+        if client.has_resources() {
+            info!(server = %config.name, "Server supports resources, adding resource tools");
+        }
+        if client.has_prompts() {
+            info!(server = %config.name, "Server supports prompts, adding prompt tools");
+        }
+
+        Ok(Self {
+            name: config.name.clone(),
+            prefix: config.prefix().to_string(),
+            has_resources: client.has_resources(),
+            has_prompts: client.has_prompts(),
+            client: RwLock::new(client),
+            tools: RwLock::new(mcp_tools),
+            failure: RwLock::new(None),
+        })
+    }
+
+    /// The tools this server contributes to the manifest.
+    pub async fn tool_definitions(&self) -> Vec<ToolDefinition> {
+        tools::server_tool_definitions(
+            &self.prefix,
+            &self.tools.read().await,
+            self.has_resources,
+            self.has_prompts,
+            self.failure.read().await.as_deref(),
+        )
+    }
+
+    /// Why the server stopped working, checking first whether its process
+    /// has exited since the last call.
+    async fn failure(&self) -> Option<String> {
+        if let Some(reason) = self.failure.read().await.clone() {
+            return Some(reason);
+        }
+        if !self.client.read().await.has_exited().await {
+            return None;
+        }
+
+        let reason = format!("MCP server '{}' exited", self.name);
+        error!(server = %self.name, "MCP server exited, its tools will fail until the bridge restarts");
+        *self.failure.write().await = Some(reason.clone());
+        Some(reason)
+    }
+
+    /// Re-read the server's tool list, keeping the last one if that fails.
+    async fn refresh_tools(&self) {
+        if self.failure().await.is_some() {
+            return;
+        }
+        match self.client.read().await.list_tools().await {
+            // Offer new tools to the handler before agents hear about them
+            Ok(mcp_tools) => *self.tools.write().await = mcp_tools,
+            Err(e) => warn!(server = %self.name, error = %e, "Failed to re-list MCP tools"),
+        }
+    }
+
+    /// Run `tool_name` (without the prefix) on this server.
+    async fn execute(&self, tool_name: &str, input: Value) -> Result<Value, ToolError> {
+        if let Some(reason) = self.failure().await {
+            return Err(ToolError::ExecutionFailed(reason));
+        }
+
+        let result = self.call(tool_name, input).await;
+        if let Err(ToolError::ExecutionFailed(_)) = &result {
+            // Report a crash as such rather than as a broken pipe
+            if let Some(reason) = self.failure().await {
+                return Err(ToolError::ExecutionFailed(reason));
+            }
+        }
+        result
+    }
+
+    async fn call(&self, tool_name: &str, input: Value) -> Result<Value, ToolError> {
+        let client = self.client.read().await;
+
+        match tool_name {
+            "mcp_list_resources" if self.has_resources => {
+                let resources = client
+                    .list_resources()
+                    .await
+                    .map_err(|e| ToolError::ExecutionFailed(e.to_string()))?;
+                serde_json::to_value(&resources)
+                    .map_err(|e| ToolError::ExecutionFailed(e.to_string()))
+            }
+            "mcp_read_resource" if self.has_resources => {
+                let uri = input
+                    .get("uri")
+                    .and_then(|v| v.as_str())
+                    .ok_or_else(|| ToolError::InvalidInput("uri required".to_string()))?;
+                let result = client
+                    .read_resource(uri)
+                    .await
+                    .map_err(|e| ToolError::ExecutionFailed(e.to_string()))?;
+                serde_json::to_value(&result).map_err(|e| ToolError::ExecutionFailed(e.to_string()))
+            }
+            "mcp_list_prompts" if self.has_prompts => {
+                let prompts = client
+                    .list_prompts()
+                    .await
+                    .map_err(|e| ToolError::ExecutionFailed(e.to_string()))?;
+                serde_json::to_value(&prompts)
+                    .map_err(|e| ToolError::ExecutionFailed(e.to_string()))
+            }
+            "mcp_get_prompt" if self.has_prompts => {
+                let name = input
+                    .get("name")
+                    .and_then(|v| v.as_str())
+                    .ok_or_else(|| ToolError::InvalidInput("name required".to_string()))?;
+                let arguments: Option<HashMap<String, String>> = input
+                    .get("arguments")
+                    .and_then(|v| serde_json::from_value(v.clone()).ok());
+                let result = client
+                    .get_prompt(name, arguments)
+                    .await
+                    .map_err(|e| ToolError::ExecutionFailed(e.to_string()))?;
+                serde_json::to_value(&result).map_err(|e| ToolError::ExecutionFailed(e.to_string()))
+            }
+            _ => {
+                // All other tools are proxied to MCP tools/call
+                if !self
+                    .tools
+                    .read()
+                    .await
+                    .iter()
+                    .any(|tool| tool.name == tool_name)
+                {
+                    return Err(ToolError::UnknownTool(tools::prefixed_name(
+                        &self.prefix,
+                        tool_name,
+                    )));
+                }
+
+                let arguments = if input.is_object() && !input.as_object().unwrap().is_empty() {
+                    Some(input)
+                } else {
+                    None
+                };
+
+                let result = client
+                    .call_tool(tool_name, arguments)
+                    .await
+                    .map_err(|e| ToolError::ExecutionFailed(e.to_string()))?;
+
+                serde_json::to_value(&result).map_err(|e| ToolError::ExecutionFailed(e.to_string()))
+            }
+        }
+    }
+}
+
+/// Handler that proxies tool calls to the MCP servers behind the bridge.
+#[derive(Clone)]
+pub struct McpBridgeHandler {
+    servers: Vec<Arc<McpServer>>,
+}
+
+impl McpBridgeHandler {
+    pub fn new(servers: Vec<Arc<McpServer>>) -> Self {
+        Self { servers }
+    }
+
+    /// The server that owns `tool_name`, and the tool's name on that server.
+    /// Names without a known prefix go to the unprefixed server, if any.
+    fn route<'a>(&self, tool_name: &'a str) -> Option<(&McpServer, &'a str)> {
+        if let Some((prefix, name)) = tool_name.split_once(PREFIX_SEPARATOR) {
+            if let Some(server) = self
+                .servers
+                .iter()
+                .find(|s| !s.prefix.is_empty() && s.prefix == prefix)
+            {
+                return Some((server, name));
+            }
+        }
+        self.servers
+            .iter()
+            .find(|s| s.prefix.is_empty())
+            .map(|server| (server.as_ref(), tool_name))
+    }
+
+    /// The manifest covering every server's tools.
+    pub async fn manifest(&self, pack_id: &str) -> PackManifest {
+        let mut definitions = Vec::new();
+        for server in &self.servers {
+            definitions.extend(server.tool_definitions().await);
+        }
+        build_manifest(pack_id, definitions)
+    }
+}
+
+#[async_trait]
+impl ToolHandler for McpBridgeHandler {
+    async fn execute(&self, tool_name: &str, input_json: &str) -> Result<String, ToolError> {
+        let (server, name) = self
+            .route(tool_name)
+            .ok_or_else(|| ToolError::UnknownTool(tool_name.to_string()))?;
+        info!(tool = %tool_name, server = %server.name, "Executing MCP tool");
+
+        let input: Value =
+            serde_json::from_str(input_json).map_err(|e| ToolError::InvalidInput(e.to_string()))?;
+        let result = server.execute(name, input).await?;
+
+        serde_json::to_string(&result).map_err(|e| ToolError::ExecutionFailed(e.to_string()))
+    }
+
+    /// Healthy while any server responds: tools of a server that has exited
+    /// are marked degraded in the manifest instead.
+    async fn health_check(&self) -> HealthStatus {
+        let mut problems = Vec::new();
+        for server in &self.servers {
+            if let Some(reason) = server.failure().await {
+                problems.push(reason);
+            } else if let Err(e) = server.client.read().await.ping().await {
+                problems.push(format!(
+                    "MCP server '{}' not responding: {}",
+                    server.name, e
+                ));
+            }
+        }
+
+        if problems.len() < self.servers.len() {
+            HealthStatus::Healthy
+        } else {
+            HealthStatus::unhealthy(problems.join("; "))
+        }
+    }
+
+    async fn on_registered(&self, pack_id: &str, rejected_tools: &[String]) {
+        info!(pack_id = %pack_id, "MCP bridge pack registered");
+        if !rejected_tools.is_empty() {
+            warn!(rejected = ?rejected_tools, "Some tools were rejected due to name collisions");
+        }
+    }
+
+    async fn on_closing(&self, reason: Option<&str>) {
+        if reason == Some(RECONNECTING_REASON) {
+            // The MCP servers are still needed once the gateway is back
+            info!("Gateway connection lost, keeping MCP clients for reconnect");
+            return;
+        }
+        info!(reason = ?reason, "MCP bridge pack closing");
+        for server in &self.servers {
+            let mut client = server.client.write().await;
+            if let Err(e) = client.shutdown().await {
+                error!(server = %server.name, error = %e, "Failed to shutdown MCP client");
+            }
+        }
+    }
+}
+
+/// Build the pack manifest from every server's tool definitions.
+pub fn build_manifest(pack_id: &str, definitions: Vec<ToolDefinition>) -> PackManifest {
+    let mut builder = ManifestBuilder::new(pack_id, env!("CARGO_PKG_VERSION"));
+    for tool in definitions {
+        builder = builder.add_tool(tool);
+    }
+    builder.build()
+}
+
+/// Re-read each MCP server's tool list every `interval` and update the
+/// registered manifest when it changes, e.g. after a server adds tools
+/// once the user has authenticated, or when a server exits and its tools
+/// become degraded.
+pub async fn watch_tools(
+    pack_client: Arc<PackClient>,
+    handler: McpBridgeHandler,
+    mut current: PackManifest,
+    interval: Duration,
+) {
+    loop {
+        tokio::time::sleep(interval).await;
+
+        for server in &handler.servers {
+            server.refresh_tools().await;
+        }
+        let manifest = handler.manifest(&current.pack_id).await;
+        if manifest.tools == current.tools {
+            continue;
+        }
+
+        info!(
+            before = current.tools.len(),
+            after = manifest.tools.len(),
+            "MCP server tools changed, updating manifest"
+        );
+        if let Err(e) = pack_client.update_manifest(manifest.clone()).await {
+            // The client registers the new manifest when it reconnects
+            warn!(error = %e, "Failed to update manifest");
+        }
+        current = manifest;
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    /// Shell script speaking just enough MCP to list and call tools
+    const FAKE_SERVER: &str = concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/fixtures/fake-mcp-server.sh"
+    );
+
+    fn fake_server(name: &str, prefix: &str) -> ServerConfig {
+        ServerConfig {
+            name: name.to_string(),
+            transport: TransportKind::Stdio,
+            command: Some(format!("sh {}", FAKE_SERVER)),
+            url: None,
+            bearer_token: None,
+            env: HashMap::from([("FAKE_MCP_NAME".to_string(), name.to_string())]),
+            prefix: Some(prefix.to_string()),
+        }
+    }
+
+    async fn bridge(servers: &[ServerConfig]) -> McpBridgeHandler {
+        let mut started = Vec::new();
+        for config in servers {
+            started.push(Arc::new(McpServer::start(config).await.unwrap()));
+        }
+        McpBridgeHandler::new(started)
+    }
+
+    #[tokio::test]
+    async fn test_routes_prefixed_tools_to_owning_server() {
+        let handler = bridge(&[fake_server("alpha", "alpha"), fake_server("beta", "beta")]).await;
+
+        let manifest = handler.manifest("bridge").await;
+        let names: Vec<&str> = manifest.tools.iter().map(|t| t.name.as_str()).collect();
+        assert_eq!(
+            names,
+            vec!["alpha__echo", "alpha__crash", "beta__echo", "beta__crash"]
+        );
+
+        let alpha = handler.execute("alpha__echo", "{}").await.unwrap();
+        assert!(alpha.contains("alpha:echo"), "{alpha}");
+        let beta = handler.execute("beta__echo", "{}").await.unwrap();
+        assert!(beta.contains("beta:echo"), "{beta}");
+
+        assert!(matches!(
+            handler.execute("gamma__echo", "{}").await,
+            Err(ToolError::UnknownTool(name)) if name == "gamma__echo"
+        ));
+        assert!(matches!(
+            handler.execute("alpha__missing", "{}").await,
+            Err(ToolError::UnknownTool(name)) if name == "alpha__missing"
+        ));
+        // Unprefixed names don't reach prefixed servers
+        assert!(matches!(
+            handler.execute("echo", "{}").await,
+            Err(ToolError::UnknownTool(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_unprefixed_server_takes_unmatched_names() {
+        let handler = bridge(&[fake_server("alpha", "alpha"), fake_server("plain", "")]).await;
+
+        let plain = handler.execute("echo", "{}").await.unwrap();
+        assert!(plain.contains("plain:echo"), "{plain}");
+        let alpha = handler.execute("alpha__echo", "{}").await.unwrap();
+        assert!(alpha.contains("alpha:echo"), "{alpha}");
+    }
+
+    #[tokio::test]
+    async fn test_crashed_server_only_fails_its_own_tools() {
+        let handler = bridge(&[fake_server("alpha", "alpha"), fake_server("beta", "beta")]).await;
+
+        let crashed = handler.execute("alpha__crash", "{}").await;
+        assert!(
+            matches!(
+                &crashed,
+                Err(ToolError::ExecutionFailed(reason)) if reason == "MCP server 'alpha' exited"
+            ),
+            "{crashed:?}"
+        );
+        assert!(matches!(
+            handler.execute("alpha__echo", "{}").await,
+            Err(ToolError::ExecutionFailed(_))
+        ));
+
+        // The other server keeps working and the pack stays healthy
+        let beta = handler.execute("beta__echo", "{}").await.unwrap();
+        assert!(beta.contains("beta:echo"), "{beta}");
+        assert_eq!(handler.health_check().await, HealthStatus::Healthy);
+
+        // Only the crashed server's tools are marked degraded
+        for server in &handler.servers {
+            server.refresh_tools().await;
+        }
+        let manifest = handler.manifest("bridge").await;
+        for tool in &manifest.tools {
+            let degraded = tool.degraded_reason.as_deref();
+            if tool.name.starts_with("alpha__") {
+                assert_eq!(degraded, Some("MCP server 'alpha' exited"), "{}", tool.name);
+            } else {
+                assert_eq!(degraded, None, "{}", tool.name);
+            }
+        }
+
+        // Once every server is down, so is the pack
+        let _ = handler.execute("beta__crash", "{}").await;
+        assert!(matches!(
+            handler.health_check().await,
+            HealthStatus::Unhealthy(_)
+        ));
+    }
+}
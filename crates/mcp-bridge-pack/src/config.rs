@@ -0,0 +1,416 @@
+// ABOUTME: Bridge configuration: the MCP servers to reach, from a TOML file or MCP_SERVER_COMMAND/URL.
+// ABOUTME: Validates server names and tool prefixes so every bridged tool routes to one server.
+
+use anyhow::{anyhow, bail, Context, Result};
+use serde::Deserialize;
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+
+/// Separator between a server's prefix and its tool names, e.g. `github__search`
+pub const PREFIX_SEPARATOR: &str = "__";
+
+/// MCP servers the bridge exposes as one pack.
+///
+/// ```toml
+/// [[servers]]
+/// name = "github"
+/// command = "npx -y @modelcontextprotocol/server-github"
+/// prefix = "gh"
+///
+/// [servers.env]
+/// GITHUB_PERSONAL_ACCESS_TOKEN = "ghp_..."
+///
+/// [[servers]]
+/// name = "memory"
+/// command = "npx -y @modelcontextprotocol/server-memory"
+///
+/// [[servers]]
+/// name = "search"
+/// transport = "http"
+/// url = "https://mcp.example.com/mcp"
+/// bearer_token = "..."
+/// ```
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BridgeConfig {
+    pub servers: Vec<ServerConfig>,
+}
+
+/// How the bridge reaches an MCP server.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TransportKind {
+    /// Spawn `command` and talk over its stdin and stdout
+    #[default]
+    Stdio,
+    /// Streamable HTTP at `url`
+    Http,
+}
+
+/// One MCP server, spawned over stdio or reached over HTTP.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ServerConfig {
+    /// Name used in logs and errors
+    pub name: String,
+    #[serde(default)]
+    pub transport: TransportKind,
+    /// Command line for stdio servers, split on whitespace like MCP_SERVER_COMMAND
+    pub command: Option<String>,
+    /// Extra environment variables for a stdio server process
+    #[serde(default)]
+    pub env: HashMap<String, String>,
+    /// Endpoint of an HTTP server
+    pub url: Option<String>,
+    /// Sent as `Authorization: Bearer <token>` to an HTTP server
+    pub bearer_token: Option<String>,
+    /// Prepended to the server's tool names; defaults to `name`. An empty
+    /// prefix exposes the tools unchanged.
+    pub prefix: Option<String>,
+}
+
+impl ServerConfig {
+    /// The prefix this server's tools are exposed under.
+    pub fn prefix(&self) -> &str {
+        self.prefix.as_deref().unwrap_or(&self.name)
+    }
+}
+
+impl BridgeConfig {
+    /// Read and validate a config file.
+    pub fn load(path: &Path) -> Result<Self> {
+        let text = std::fs::read_to_string(path)
+            .with_context(|| format!("reading bridge config: {}", path.display()))?;
+        let config: Self = toml::from_str(&text)
+            .with_context(|| format!("parsing bridge config: {}", path.display()))?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// A single unprefixed server, as configured by MCP_SERVER_COMMAND.
+    pub fn single(command: &str) -> Self {
+        Self {
+            servers: vec![ServerConfig {
+                command: Some(command.to_string()),
+                ..Self::unprefixed(TransportKind::Stdio)
+            }],
+        }
+    }
+
+    /// A single unprefixed HTTP server, as configured by MCP_SERVER_URL.
+    pub fn single_http(url: &str, bearer_token: Option<String>) -> Self {
+        Self {
+            servers: vec![ServerConfig {
+                url: Some(url.to_string()),
+                bearer_token,
+                ..Self::unprefixed(TransportKind::Http)
+            }],
+        }
+    }
+
+    fn unprefixed(transport: TransportKind) -> ServerConfig {
+        ServerConfig {
+            name: "mcp".to_string(),
+            transport,
+            command: None,
+            env: HashMap::new(),
+            url: None,
+            bearer_token: None,
+            prefix: Some(String::new()),
+        }
+    }
+
+    /// Check that every tool name the bridge exposes belongs to exactly one
+    /// server.
+    pub fn validate(&self) -> Result<()> {
+        if self.servers.is_empty() {
+            bail!("bridge config lists no servers");
+        }
+
+        let mut names = HashSet::new();
+        let mut prefixes = HashSet::new();
+        for server in &self.servers {
+            if server.name.is_empty() {
+                bail!("every server needs a name");
+            }
+            if !names.insert(server.name.as_str()) {
+                bail!("server '{}' is listed twice", server.name);
+            }
+            match server.transport {
+                TransportKind::Stdio => {
+                    if server.url.is_some() || server.bearer_token.is_some() {
+                        bail!(
+                            "server '{}' sets url or bearer_token, which need transport = \"http\"",
+                            server.name
+                        );
+                    }
+                    let command = server
+                        .command
+                        .as_deref()
+                        .ok_or_else(|| anyhow!("server '{}' needs a command", server.name))?;
+                    parse_mcp_command(command)
+                        .with_context(|| format!("server '{}'", server.name))?;
+                }
+                TransportKind::Http => {
+                    if server.command.is_some() || !server.env.is_empty() {
+                        bail!(
+                            "server '{}' uses transport = \"http\", which takes a url instead of a command and env",
+                            server.name
+                        );
+                    }
+                    let url = server
+                        .url
+                        .as_deref()
+                        .ok_or_else(|| anyhow!("server '{}' needs a url", server.name))?;
+                    if !(url.starts_with("http://") || url.starts_with("https://")) {
+                        bail!(
+                            "server '{}' url must be http or https: {}",
+                            server.name,
+                            url
+                        );
+                    }
+                }
+            }
+
+            let prefix = server.prefix();
+            if !prefix
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-'))
+                || prefix.contains(PREFIX_SEPARATOR)
+                || prefix.starts_with('_')
+                || prefix.ends_with('_')
+            {
+                bail!(
+                    "server '{}' prefix '{}' may only contain letters, digits, '-', and '_' between other characters",
+                    server.name,
+                    prefix
+                );
+            }
+            if !prefixes.insert(prefix) {
+                if prefix.is_empty() {
+                    bail!("only one server may have an empty prefix");
+                }
+                bail!("prefix '{}' is used by more than one server", prefix);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Parse the MCP server command from environment variable.
+/// Expected format: "command arg1 arg2 ..." or just "command"
+pub fn parse_mcp_command(cmd_str: &str) -> Result<(String, Vec<String>)> {
+    let parts: Vec<&str> = cmd_str.split_whitespace().collect();
+    if parts.is_empty() {
+        return Err(anyhow!("MCP server command is empty"));
+    }
+
+    let command = parts[0].to_string();
+    let args: Vec<String> = parts[1..].iter().map(|s| s.to_string()).collect();
+
+    Ok((command, args))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_mcp_command_simple() {
+        let (cmd, args) = parse_mcp_command("node").unwrap();
+        assert_eq!(cmd, "node");
+        assert!(args.is_empty());
+    }
+
+    #[test]
+    fn test_parse_mcp_command_with_args() {
+        let (cmd, args) = parse_mcp_command("npx -y @modelcontextprotocol/server-memory").unwrap();
+        assert_eq!(cmd, "npx");
+        assert_eq!(args, vec!["-y", "@modelcontextprotocol/server-memory"]);
+    }
+
+    #[test]
+    fn test_parse_mcp_command_empty() {
+        let result = parse_mcp_command("");
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_parse_mcp_command_whitespace_only() {
+        let result = parse_mcp_command("   ");
+        assert!(result.is_err());
+    }
+
+    fn parse(text: &str) -> Result<BridgeConfig> {
+        let config: BridgeConfig = toml::from_str(text)?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    #[test]
+    fn test_load_servers_with_env_and_prefixes() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("bridge.toml");
+        std::fs::write(
+            &path,
+            r#"
+            [[servers]]
+            name = "github"
+            command = "npx -y @modelcontextprotocol/server-github"
+            prefix = "gh"
+
+            [servers.env]
+            GITHUB_PERSONAL_ACCESS_TOKEN = "ghp_test"
+
+            [[servers]]
+            name = "memory"
+            command = "npx -y @modelcontextprotocol/server-memory"
+            "#,
+        )
+        .unwrap();
+
+        let config = BridgeConfig::load(&path).unwrap();
+        assert_eq!(config.servers.len(), 2);
+        assert_eq!(config.servers[0].prefix(), "gh");
+        assert_eq!(
+            config.servers[0].env["GITHUB_PERSONAL_ACCESS_TOKEN"],
+            "ghp_test"
+        );
+        assert_eq!(config.servers[1].prefix(), "memory");
+        assert!(config.servers[1].env.is_empty());
+    }
+
+    #[test]
+    fn test_single_server_is_unprefixed() {
+        let config = BridgeConfig::single("node server.js");
+        config.validate().unwrap();
+        assert_eq!(config.servers[0].prefix(), "");
+    }
+
+    #[test]
+    fn test_http_servers_need_a_url() {
+        let config = parse(
+            r#"
+            [[servers]]
+            name = "remote"
+            transport = "http"
+            url = "https://mcp.example.com/mcp"
+            bearer_token = "secret"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(config.servers[0].transport, TransportKind::Http);
+        assert_eq!(config.servers[0].bearer_token.as_deref(), Some("secret"));
+
+        let no_url = r#"
+            [[servers]]
+            name = "remote"
+            transport = "http"
+        "#;
+        assert!(parse(no_url)
+            .unwrap_err()
+            .to_string()
+            .contains("needs a url"));
+
+        let with_command = r#"
+            [[servers]]
+            name = "remote"
+            transport = "http"
+            url = "http://localhost:3000/mcp"
+            command = "node server.js"
+        "#;
+        assert!(parse(with_command).is_err());
+
+        let url_without_http = r#"
+            [[servers]]
+            name = "local"
+            command = "node server.js"
+            url = "http://localhost:3000/mcp"
+        "#;
+        assert!(parse(url_without_http).is_err());
+
+        let bad_scheme = r#"
+            [[servers]]
+            name = "remote"
+            transport = "http"
+            url = "ws://localhost:3000/mcp"
+        "#;
+        assert!(parse(bad_scheme).is_err());
+
+        BridgeConfig::single_http("http://localhost:3000/mcp", None)
+            .validate()
+            .unwrap();
+    }
+
+    #[test]
+    fn test_rejects_ambiguous_servers() {
+        let duplicate_prefix = r#"
+            [[servers]]
+            name = "a"
+            command = "a"
+            prefix = "x"
+            [[servers]]
+            name = "b"
+            command = "b"
+            prefix = "x"
+        "#;
+        assert!(parse(duplicate_prefix)
+            .unwrap_err()
+            .to_string()
+            .contains("more than one server"));
+
+        let two_unprefixed = r#"
+            [[servers]]
+            name = "a"
+            command = "a"
+            prefix = ""
+            [[servers]]
+            name = "b"
+            command = "b"
+            prefix = ""
+        "#;
+        assert!(parse(two_unprefixed).is_err());
+
+        let duplicate_name = r#"
+            [[servers]]
+            name = "a"
+            command = "a"
+            [[servers]]
+            name = "a"
+            command = "b"
+            prefix = "b"
+        "#;
+        assert!(parse(duplicate_name).is_err());
+
+        let separator_in_prefix = r#"
+            [[servers]]
+            name = "a"
+            command = "a"
+            prefix = "x__y"
+        "#;
+        assert!(parse(separator_in_prefix).is_err());
+
+        let trailing_underscore = r#"
+            [[servers]]
+            name = "a"
+            command = "a"
+            prefix = "x_"
+        "#;
+        assert!(parse(trailing_underscore).is_err());
+
+        let empty_command = r#"
+            [[servers]]
+            name = "a"
+            command = " "
+        "#;
+        assert!(parse(empty_command).is_err());
+
+        let no_command = r#"
+            [[servers]]
+            name = "a"
+        "#;
+        assert!(parse(no_command).is_err());
+
+        assert!(parse("servers = []").is_err());
+    }
+}
@@ -0,0 +1,812 @@
+// ABOUTME: MCP streamable HTTP transport: requests are POSTed, server messages arrive over SSE.
+// ABOUTME: Tracks the MCP session, resumes dropped event streams, and parses Server-Sent Events.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+use anyhow::{anyhow, bail, Context, Result};
+use async_trait::async_trait;
+use futures::StreamExt;
+use reqwest::header::{ACCEPT, CONTENT_TYPE};
+use reqwest::{Method, RequestBuilder, Response, StatusCode};
+use serde::Serialize;
+use serde_json::{json, Value};
+use tokio::task::JoinHandle;
+use tracing::{debug, trace, warn};
+
+use crate::mcp_client::{JsonRpcRequest, JsonRpcResponse, Transport};
+
+/// Header carrying the session id the server assigns during initialize.
+const SESSION_HEADER: &str = "Mcp-Session-Id";
+
+/// Header asking the server to replay events after the given id.
+const LAST_EVENT_ID_HEADER: &str = "Last-Event-ID";
+
+/// How many times a dropped response stream is resumed before the request fails.
+const MAX_RESUME_ATTEMPTS: u32 = 3;
+
+/// Delay before reconnecting a dropped stream, doubled per failed attempt.
+const RECONNECT_DELAY: Duration = Duration::from_millis(250);
+
+/// Upper bound for the reconnect delay.
+const MAX_RECONNECT_DELAY: Duration = Duration::from_secs(30);
+
+/// JSON-RPC error code for methods we don't implement.
+const METHOD_NOT_FOUND: i64 = -32601;
+
+/// Talks to an MCP server over the streamable HTTP transport.
+///
+/// Each request is POSTed and answered either with a JSON body or with an
+/// SSE stream that ends with the response. After initialize, a GET stream
+/// stays open for messages the server sends on its own.
+pub struct HttpTransport {
+    inner: Arc<HttpInner>,
+    listener: std::sync::Mutex<Option<JoinHandle<()>>>,
+}
+
+struct HttpInner {
+    http: reqwest::Client,
+    url: String,
+    bearer_token: Option<String>,
+    session_id: RwLock<Option<String>>,
+    closed: AtomicBool,
+}
+
+impl HttpTransport {
+    /// Create a transport for the MCP endpoint at `url`.
+    pub fn new(url: &str, bearer_token: Option<String>) -> Result<Self> {
+        let parsed =
+            reqwest::Url::parse(url).with_context(|| format!("Invalid MCP URL: {}", url))?;
+        if !matches!(parsed.scheme(), "http" | "https") {
+            bail!("MCP URL must be http or https: {}", url);
+        }
+        debug!(url = %url, "Connecting to MCP server over HTTP");
+
+        let http = reqwest::Client::builder()
+            .build()
+            .context("Failed to create HTTP client")?;
+
+        Ok(Self {
+            inner: Arc::new(HttpInner {
+                http,
+                url: url.to_string(),
+                bearer_token,
+                session_id: RwLock::new(None),
+                closed: AtomicBool::new(false),
+            }),
+            listener: std::sync::Mutex::new(None),
+        })
+    }
+}
+
+#[async_trait]
+impl Transport for HttpTransport {
+    async fn request(&self, request: JsonRpcRequest) -> Result<JsonRpcResponse> {
+        self.inner.ensure_open()?;
+        let expected_id = request
+            .id
+            .clone()
+            .ok_or_else(|| anyhow!("JSON-RPC request has no id"))?;
+
+        let response = self.inner.post(&request).await?;
+        if is_event_stream(&response) {
+            return self.inner.await_on_stream(response, &expected_id).await;
+        }
+
+        let body: Value = response.json().await.context("Failed to parse response")?;
+        let messages = match body {
+            Value::Array(batch) => batch,
+            message => vec![message],
+        };
+        let mut found = None;
+        for message in messages {
+            if let Some(response) = self.inner.handle_message(message, Some(&expected_id)).await {
+                found = Some(response);
+            }
+        }
+        found.ok_or_else(|| anyhow!("MCP server sent no response for request {}", expected_id))
+    }
+
+    async fn notify(&self, notification: JsonRpcRequest) -> Result<()> {
+        self.inner.ensure_open()?;
+        // The server answers 202 Accepted with no body
+        self.inner.post(&notification).await?;
+        Ok(())
+    }
+
+    async fn on_initialized(&self) {
+        let mut listener = self.listener.lock().unwrap();
+        if listener.is_none() {
+            *listener = Some(tokio::spawn(listen(Arc::clone(&self.inner))));
+        }
+    }
+
+    async fn has_exited(&self) -> bool {
+        self.inner.closed.load(Ordering::SeqCst)
+    }
+
+    async fn shutdown(&self) -> Result<()> {
+        self.inner.closed.store(true, Ordering::SeqCst);
+        if let Some(listener) = self.listener.lock().unwrap().take() {
+            listener.abort();
+        }
+
+        // Tell the server the session is over; it may not support this
+        if self.inner.session_id.read().unwrap().is_some() {
+            if let Err(e) = self.inner.build(Method::DELETE).send().await {
+                debug!(error = %e, "Failed to end MCP session");
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Drop for HttpTransport {
+    fn drop(&mut self) {
+        if let Some(listener) = self.listener.lock().unwrap().take() {
+            listener.abort();
+        }
+    }
+}
+
+impl HttpInner {
+    fn ensure_open(&self) -> Result<()> {
+        if self.closed.load(Ordering::SeqCst) {
+            bail!("MCP session closed");
+        }
+        Ok(())
+    }
+
+    /// Start a request to the endpoint with auth and session headers.
+    fn build(&self, method: Method) -> RequestBuilder {
+        let mut request = self.http.request(method, &self.url);
+        if let Some(token) = &self.bearer_token {
+            request = request.bearer_auth(token);
+        }
+        if let Some(session_id) = self.session_id.read().unwrap().as_deref() {
+            request = request.header(SESSION_HEADER, session_id);
+        }
+        request
+    }
+
+    /// POST one JSON-RPC message.
+    async fn post<T: Serialize>(&self, message: &T) -> Result<Response> {
+        trace!(message = %serde_json::to_string(message).unwrap_or_default(), "Sending message");
+        let response = self
+            .build(Method::POST)
+            .header(ACCEPT, "application/json, text/event-stream")
+            .json(message)
+            .send()
+            .await
+            .context("Failed to send to MCP server")?;
+        self.check(response).await
+    }
+
+    /// GET the server's event stream, replaying events after `last_event_id`.
+    async fn open_stream(&self, last_event_id: Option<&str>) -> reqwest::Result<Response> {
+        let mut request = self.build(Method::GET).header(ACCEPT, "text/event-stream");
+        if let Some(id) = last_event_id {
+            request = request.header(LAST_EVENT_ID_HEADER, id);
+        }
+        request.send().await
+    }
+
+    /// Record the session id and turn error statuses into errors.
+    async fn check(&self, response: Response) -> Result<Response> {
+        if let Some(session_id) = response
+            .headers()
+            .get(SESSION_HEADER)
+            .and_then(|v| v.to_str().ok())
+        {
+            *self.session_id.write().unwrap() = Some(session_id.to_string());
+        }
+
+        let status = response.status();
+        if status.is_success() {
+            return Ok(response);
+        }
+        if status == StatusCode::NOT_FOUND && self.session_id.read().unwrap().is_some() {
+            // The server has forgotten our session; nothing will work again
+            self.closed.store(true, Ordering::SeqCst);
+            bail!("MCP session expired");
+        }
+        let body = response.text().await.unwrap_or_default();
+        bail!("MCP server returned {}: {}", status, body.trim())
+    }
+
+    /// Read an SSE response until the response to `expected_id` arrives,
+    /// resuming the stream from the last event id if it drops first.
+    async fn await_on_stream(
+        &self,
+        response: Response,
+        expected_id: &Value,
+    ) -> Result<JsonRpcResponse> {
+        let mut last_event_id = None;
+        let mut response = response;
+        let mut attempts = 0;
+        loop {
+            if let Some(found) = self
+                .read_stream(response, Some(expected_id), &mut last_event_id)
+                .await
+            {
+                return Ok(found);
+            }
+
+            // Without an event id there is nothing the server could replay
+            let Some(event_id) = last_event_id.clone() else {
+                bail!("MCP server closed the stream before responding");
+            };
+            attempts += 1;
+            if attempts > MAX_RESUME_ATTEMPTS {
+                bail!(
+                    "MCP server stream dropped {} times before responding",
+                    attempts
+                );
+            }
+            warn!(event_id = %event_id, attempt = attempts, "MCP response stream dropped, resuming");
+            tokio::time::sleep(reconnect_delay(attempts - 1)).await;
+
+            let resumed = self
+                .open_stream(Some(&event_id))
+                .await
+                .context("Failed to resume MCP response stream")?;
+            response = self.check(resumed).await?;
+        }
+    }
+
+    /// Handle every event on an SSE response. Returns the response to
+    /// `expected_id` if it arrives, or `None` once the stream ends.
+    async fn read_stream(
+        &self,
+        response: Response,
+        expected_id: Option<&Value>,
+        last_event_id: &mut Option<String>,
+    ) -> Option<JsonRpcResponse> {
+        let mut parser = SseParser::default();
+        let mut body = response.bytes_stream();
+        while let Some(chunk) = body.next().await {
+            let chunk = match chunk {
+                Ok(chunk) => chunk,
+                Err(e) => {
+                    debug!(error = %e, "MCP event stream broke");
+                    return None;
+                }
+            };
+            for event in parser.push(&chunk) {
+                if let Some(id) = event.id {
+                    *last_event_id = Some(id);
+                }
+                if event.data.is_empty() || event.event.as_deref().is_some_and(|e| e != "message") {
+                    continue;
+                }
+                let message = match serde_json::from_str::<Value>(&event.data) {
+                    Ok(message) => message,
+                    Err(e) => {
+                        warn!(error = %e, "Ignoring unparseable MCP event");
+                        continue;
+                    }
+                };
+                let messages = match message {
+                    Value::Array(batch) => batch,
+                    message => vec![message],
+                };
+                for message in messages {
+                    if let Some(found) = self.handle_message(message, expected_id).await {
+                        return Some(found);
+                    }
+                }
+            }
+        }
+        None
+    }
+
+    /// Handle one message from the server, returning it if it is the
+    /// response to `expected_id`.
+    async fn handle_message(
+        &self,
+        message: Value,
+        expected_id: Option<&Value>,
+    ) -> Option<JsonRpcResponse> {
+        trace!(message = %message, "Received message");
+
+        if let Some(method) = message.get("method").and_then(Value::as_str) {
+            match message.get("id") {
+                // The bridge offers no client features (sampling, roots), so
+                // server requests get a method-not-found error
+                Some(id) => {
+                    debug!(method = %method, "Declining MCP server request");
+                    let reply = json!({
+                        "jsonrpc": "2.0",
+                        "id": id,
+                        "error": { "code": METHOD_NOT_FOUND, "message": format!("Method not found: {}", method) },
+                    });
+                    if let Err(e) = self.post(&reply).await {
+                        warn!(error = %e, "Failed to answer MCP server request");
+                    }
+                }
+                None => debug!(method = %method, "MCP server notification"),
+            }
+            return None;
+        }
+
+        let response: JsonRpcResponse = match serde_json::from_value(message) {
+            Ok(response) => response,
+            Err(e) => {
+                warn!(error = %e, "Ignoring malformed MCP message");
+                return None;
+            }
+        };
+        match expected_id {
+            Some(expected) if response.id.as_ref() == Some(expected) => Some(response),
+            _ => {
+                warn!(expected = ?expected_id, received = ?response.id, "Received response with unexpected id");
+                None
+            }
+        }
+    }
+}
+
+/// Keep the server's GET event stream open, reconnecting when it drops,
+/// until the session closes or the server says it has no such stream.
+async fn listen(inner: Arc<HttpInner>) {
+    let mut last_event_id = None;
+    let mut failures = 0;
+    while !inner.closed.load(Ordering::SeqCst) {
+        match inner.open_stream(last_event_id.as_deref()).await {
+            Ok(response) if response.status() == StatusCode::METHOD_NOT_ALLOWED => {
+                debug!("MCP server offers no event stream");
+                return;
+            }
+            Ok(response) if response.status().is_success() && is_event_stream(&response) => {
+                failures = 0;
+                inner.read_stream(response, None, &mut last_event_id).await;
+                debug!("MCP event stream closed, reconnecting");
+            }
+            Ok(response) => {
+                failures += 1;
+                warn!(status = %response.status(), "MCP server refused event stream");
+            }
+            Err(e) => {
+                failures += 1;
+                warn!(error = %e, "Failed to open MCP event stream");
+            }
+        }
+        tokio::time::sleep(reconnect_delay(failures)).await;
+    }
+}
+
+fn reconnect_delay(failures: u32) -> Duration {
+    RECONNECT_DELAY
+        .saturating_mul(2u32.saturating_pow(failures))
+        .min(MAX_RECONNECT_DELAY)
+}
+
+fn is_event_stream(response: &Response) -> bool {
+    response
+        .headers()
+        .get(CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.starts_with("text/event-stream"))
+}
+
+/// One Server-Sent Event.
+#[derive(Debug, Clone, Default, PartialEq)]
+struct SseEvent {
+    id: Option<String>,
+    event: Option<String>,
+    data: String,
+}
+
+/// Incremental Server-Sent Events parser; feed it chunks as they arrive.
+#[derive(Debug, Default)]
+struct SseParser {
+    /// Bytes of a line that hasn't ended yet
+    pending: Vec<u8>,
+    id: Option<String>,
+    event: Option<String>,
+    data: Vec<String>,
+}
+
+impl SseParser {
+    /// Parse `chunk`, returning every event it completes.
+    fn push(&mut self, chunk: &[u8]) -> Vec<SseEvent> {
+        self.pending.extend_from_slice(chunk);
+        let mut events = Vec::new();
+        while let Some(end) = self.pending.iter().position(|&b| b == b'\n') {
+            let mut line: Vec<u8> = self.pending.drain(..=end).collect();
+            line.pop();
+            if line.last() == Some(&b'\r') {
+                line.pop();
+            }
+            if let Some(event) = self.line(&String::from_utf8_lossy(&line)) {
+                events.push(event);
+            }
+        }
+        events
+    }
+
+    fn line(&mut self, line: &str) -> Option<SseEvent> {
+        if line.is_empty() {
+            // A blank line ends the event
+            if self.data.is_empty() && self.id.is_none() {
+                self.event = None;
+                return None;
+            }
+            return Some(SseEvent {
+                id: self.id.take(),
+                event: self.event.take(),
+                data: std::mem::take(&mut self.data).join("\n"),
+            });
+        }
+        if line.starts_with(':') {
+            // Comment, used as a keep-alive
+            return None;
+        }
+
+        let (field, value) = match line.split_once(':') {
+            Some((field, value)) => (field, value.strip_prefix(' ').unwrap_or(value)),
+            None => (line, ""),
+        };
+        match field {
+            "data" => self.data.push(value.to_string()),
+            "event" => self.event = Some(value.to_string()),
+            "id" if !value.contains('\0') => self.id = Some(value.to_string()),
+            _ => {}
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mcp_client::{CallToolResult, McpClient, ToolContent};
+    use std::collections::HashMap;
+    use std::sync::Mutex;
+    use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+    use tokio::net::{TcpListener, TcpStream};
+
+    #[test]
+    fn test_sse_parser_handles_fields_comments_and_split_chunks() {
+        let mut parser = SseParser::default();
+        assert!(parser
+            .push(b": keep-alive\n\nid: 1\r\nevent: mess")
+            .is_empty());
+        let events = parser.push(b"age\ndata: {\"a\":\ndata:1}\n\ndata: x\n");
+        assert_eq!(
+            events,
+            vec![SseEvent {
+                id: Some("1".to_string()),
+                event: Some("message".to_string()),
+                data: "{\"a\":\n1}".to_string(),
+            }]
+        );
+        assert_eq!(
+            parser.push(b"\n"),
+            vec![SseEvent {
+                data: "x".to_string(),
+                ..Default::default()
+            }]
+        );
+    }
+
+    #[test]
+    fn test_sse_parser_reports_id_only_events() {
+        let mut parser = SseParser::default();
+        let events = parser.push(b"id: 7\n\n");
+        assert_eq!(events[0].id.as_deref(), Some("7"));
+        assert!(events[0].data.is_empty());
+    }
+
+    #[test]
+    fn test_reconnect_delay_backs_off_to_a_limit() {
+        assert_eq!(reconnect_delay(0), RECONNECT_DELAY);
+        assert_eq!(reconnect_delay(2), RECONNECT_DELAY * 4);
+        assert_eq!(reconnect_delay(40), MAX_RECONNECT_DELAY);
+    }
+
+    #[test]
+    fn test_rejects_non_http_urls() {
+        assert!(HttpTransport::new("ftp://example.com/mcp", None).is_err());
+        assert!(HttpTransport::new("not a url", None).is_err());
+    }
+
+    const TOKEN: &str = "stub-secret";
+    const SESSION: &str = "session-1";
+
+    /// What the stub server has seen.
+    #[derive(Default)]
+    struct StubState {
+        /// Id of the flaky tools/call whose stream was cut short
+        flaky_id: Option<Value>,
+        /// Last-Event-ID of every GET on the standalone stream
+        listens: Vec<Option<String>>,
+        /// Replies the client sent to server requests
+        replies: Vec<Value>,
+        deleted: bool,
+    }
+
+    struct StubRequest {
+        method: String,
+        headers: HashMap<String, String>,
+        body: Vec<u8>,
+    }
+
+    /// A minimal MCP server speaking the streamable HTTP transport, one
+    /// request per connection.
+    async fn start_stub() -> (String, Arc<Mutex<StubState>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/mcp", listener.local_addr().unwrap());
+        let state = Arc::new(Mutex::new(StubState::default()));
+        let shared = Arc::clone(&state);
+        tokio::spawn(async move {
+            loop {
+                let (stream, _) = listener.accept().await.unwrap();
+                tokio::spawn(serve(stream, Arc::clone(&shared)));
+            }
+        });
+        (url, state)
+    }
+
+    async fn read_request(stream: &mut BufReader<TcpStream>) -> Option<StubRequest> {
+        let mut line = String::new();
+        stream.read_line(&mut line).await.ok()?;
+        let method = line.split_whitespace().next()?.to_string();
+
+        let mut headers = HashMap::new();
+        loop {
+            line.clear();
+            stream.read_line(&mut line).await.ok()?;
+            let line = line.trim_end();
+            if line.is_empty() {
+                break;
+            }
+            let (name, value) = line.split_once(':')?;
+            headers.insert(name.to_ascii_lowercase(), value.trim().to_string());
+        }
+
+        let length = headers
+            .get("content-length")
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(0);
+        let mut body = vec![0; length];
+        stream.read_exact(&mut body).await.ok()?;
+        Some(StubRequest {
+            method,
+            headers,
+            body,
+        })
+    }
+
+    fn reply(status: &str, headers: &[(&str, &str)], body: &str) -> String {
+        let mut out = format!("HTTP/1.1 {}\r\nConnection: close\r\n", status);
+        for (name, value) in headers {
+            out.push_str(&format!("{}: {}\r\n", name, value));
+        }
+        format!("{}Content-Length: {}\r\n\r\n{}", out, body.len(), body)
+    }
+
+    /// Headers for an SSE body, which runs until the connection closes.
+    fn sse_head() -> String {
+        "HTTP/1.1 200 OK\r\nConnection: close\r\nContent-Type: text/event-stream\r\n\r\n"
+            .to_string()
+    }
+
+    fn event(id: &str, message: Value) -> String {
+        format!("id: {}\ndata: {}\n\n", id, message)
+    }
+
+    fn result(id: &Value, result: Value) -> Value {
+        json!({ "jsonrpc": "2.0", "id": id, "result": result })
+    }
+
+    async fn serve(stream: TcpStream, state: Arc<Mutex<StubState>>) {
+        let mut stream = BufReader::new(stream);
+        let Some(request) = read_request(&mut stream).await else {
+            return;
+        };
+        let out = respond(request, &state);
+        let _ = stream.get_mut().write_all(out.as_bytes()).await;
+        let _ = stream.get_mut().shutdown().await;
+    }
+
+    fn respond(request: StubRequest, state: &Mutex<StubState>) -> String {
+        let header = |name: &str| request.headers.get(name).map(String::as_str);
+        if header("authorization") != Some(&format!("Bearer {}", TOKEN)) {
+            return reply("401 Unauthorized", &[], "bad token");
+        }
+
+        let message: Value = serde_json::from_slice(&request.body).unwrap_or(Value::Null);
+        let is_initialize = message["method"] == "initialize";
+        if !is_initialize && header("mcp-session-id") != Some(SESSION) {
+            return reply("400 Bad Request", &[], "missing session");
+        }
+
+        match request.method.as_str() {
+            "DELETE" => {
+                state.lock().unwrap().deleted = true;
+                return reply("200 OK", &[], "");
+            }
+            "GET" => {
+                let last_event_id = header("last-event-id").map(str::to_string);
+                if last_event_id.as_deref() == Some("flaky-1") {
+                    // Replay what the flaky call's stream lost
+                    let id = state.lock().unwrap().flaky_id.clone().unwrap();
+                    let content = json!({ "content": [{ "type": "text", "text": "recovered" }] });
+                    return sse_head() + &event("flaky-2", result(&id, content));
+                }
+                state.lock().unwrap().listens.push(last_event_id);
+                let notification =
+                    json!({ "jsonrpc": "2.0", "method": "notifications/tools/list_changed" });
+                return sse_head() + &event("listen-1", notification);
+            }
+            _ => {}
+        }
+
+        let id = message["id"].clone();
+        let Some(method) = message["method"].as_str() else {
+            // A reply to one of our requests
+            state.lock().unwrap().replies.push(message);
+            return reply("202 Accepted", &[], "");
+        };
+        if id.is_null() {
+            return reply("202 Accepted", &[], "");
+        }
+
+        let json_reply = |value: Value| {
+            reply(
+                "200 OK",
+                &[("Content-Type", "application/json")],
+                &value.to_string(),
+            )
+        };
+        match method {
+            "initialize" => reply(
+                "200 OK",
+                &[
+                    ("Content-Type", "application/json"),
+                    ("Mcp-Session-Id", SESSION),
+                ],
+                &result(
+                    &id,
+                    json!({
+                        "protocolVersion": "2024-11-05",
+                        "capabilities": { "tools": {}, "resources": {}, "prompts": {} },
+                        "serverInfo": { "name": "stub", "version": "1.0" },
+                    }),
+                )
+                .to_string(),
+            ),
+            "ping" => json_reply(result(&id, json!({}))),
+            "tools/list" => {
+                // Streamed, with a server request and a notification first
+                let roots = json!({ "jsonrpc": "2.0", "id": "srv-1", "method": "roots/list" });
+                let progress = json!({ "jsonrpc": "2.0", "method": "notifications/progress" });
+                let tools =
+                    json!({ "tools": [{ "name": "echo", "inputSchema": { "type": "object" } }] });
+                sse_head()
+                    + ": keep-alive\n\n"
+                    + &event("list-1", roots)
+                    + &event("list-2", progress)
+                    + &event("list-3", result(&id, tools))
+            }
+            "tools/call" if message["params"]["name"] == "flaky" => {
+                // The stream drops after the first event
+                state.lock().unwrap().flaky_id = Some(id);
+                let progress = json!({ "jsonrpc": "2.0", "method": "notifications/progress" });
+                sse_head() + &event("flaky-1", progress)
+            }
+            "tools/call" => {
+                let text = message["params"]["arguments"]["text"].clone();
+                json_reply(result(
+                    &id,
+                    json!({ "content": [{ "type": "text", "text": text }] }),
+                ))
+            }
+            "resources/list" => json_reply(result(
+                &id,
+                json!({ "resources": [{ "uri": "file:///a.txt", "name": "a" }] }),
+            )),
+            "resources/read" => json_reply(result(
+                &id,
+                json!({ "contents": [{ "uri": message["params"]["uri"], "text": "hello" }] }),
+            )),
+            "prompts/list" => json_reply(result(&id, json!({ "prompts": [{ "name": "greet" }] }))),
+            "prompts/get" => json_reply(result(
+                &id,
+                json!({ "messages": [{ "role": "user", "content": { "type": "text", "text": "hi" } }] }),
+            )),
+            other => json_reply(json!({
+                "jsonrpc": "2.0",
+                "id": id,
+                "error": { "code": METHOD_NOT_FOUND, "message": other },
+            })),
+        }
+    }
+
+    async fn connect(url: &str) -> McpClient {
+        let mut client = McpClient::connect_http(url, Some(TOKEN.to_string())).unwrap();
+        client.initialize().await.unwrap();
+        client
+    }
+
+    fn text_of(result: &CallToolResult) -> String {
+        match &result.content[0] {
+            ToolContent::Text { text } => text.clone(),
+            other => panic!("expected text, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_http_client_speaks_mcp() {
+        let (url, state) = start_stub().await;
+        let mut client = connect(&url).await;
+        assert_eq!(client.server_info().unwrap().name, "stub");
+        client.ping().await.unwrap();
+
+        let tools = client.list_tools().await.unwrap();
+        assert_eq!(tools.len(), 1);
+        assert_eq!(tools[0].name, "echo");
+        // The server request inside the stream was declined
+        let replies = state.lock().unwrap().replies.clone();
+        assert_eq!(replies.len(), 1);
+        assert_eq!(replies[0]["id"], "srv-1");
+        assert_eq!(replies[0]["error"]["code"], METHOD_NOT_FOUND);
+
+        let result = client
+            .call_tool("echo", Some(json!({ "text": "over http" })))
+            .await
+            .unwrap();
+        assert_eq!(text_of(&result), "over http");
+
+        assert_eq!(client.list_resources().await.unwrap().len(), 1);
+        let read = client.read_resource("file:///a.txt").await.unwrap();
+        assert_eq!(read.contents.len(), 1);
+        assert_eq!(client.list_prompts().await.unwrap()[0].name, "greet");
+        let prompt = client.get_prompt("greet", None).await.unwrap();
+        assert_eq!(prompt.messages.len(), 1);
+
+        client.shutdown().await.unwrap();
+        assert!(client.has_exited().await);
+        assert!(state.lock().unwrap().deleted);
+        assert!(client.ping().await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_http_client_resumes_dropped_response_stream() {
+        let (url, _state) = start_stub().await;
+        let client = connect(&url).await;
+        let result = client.call_tool("flaky", None).await.unwrap();
+        assert_eq!(text_of(&result), "recovered");
+    }
+
+    #[tokio::test]
+    async fn test_http_client_reconnects_event_stream() {
+        let (url, state) = start_stub().await;
+        let _client = connect(&url).await;
+
+        // Each stub stream ends after one event; the listener must come
+        // back asking for what followed it
+        let mut resumed = false;
+        for _ in 0..50 {
+            let listens = state.lock().unwrap().listens.clone();
+            if listens.len() >= 2 {
+                assert_eq!(listens[0], None);
+                assert_eq!(listens[1].as_deref(), Some("listen-1"));
+                resumed = true;
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+        assert!(resumed, "event stream was not reopened");
+    }
+
+    #[tokio::test]
+    async fn test_http_client_reports_auth_failure() {
+        let (url, _state) = start_stub().await;
+        let mut client = McpClient::connect_http(&url, Some("wrong".to_string())).unwrap();
+        let err = client.initialize().await.unwrap_err();
+        assert!(err.to_string().contains("401"), "{}", err);
+    }
+}
@@ -1,18 +1,20 @@
-// ABOUTME: MCP JSON-RPC client for communicating with MCP servers via stdio.
+// ABOUTME: MCP JSON-RPC client for communicating with MCP servers over stdio or HTTP.
 // ABOUTME: Implements the Model Context Protocol for tool discovery and invocation.
 
 use anyhow::{anyhow, Context, Result};
+use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use std::collections::HashMap;
 use std::process::Stdio;
-use std::sync::atomic::{AtomicU64, Ordering};
-use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
 use tokio::process::{Child, ChildStdin, ChildStdout, Command};
 use tokio::sync::Mutex;
 use tracing::{debug, trace, warn};
 
+use crate::http_transport::HttpTransport;
+
 /// MCP protocol version we support.
 const MCP_PROTOCOL_VERSION: &str = "2024-11-05";
 
@@ -331,20 +333,37 @@ pub enum PromptContent {
     Resource { resource: ResourceContent },
 }
 
-/// MCP client for communicating with an MCP server via stdio.
-pub struct McpClient {
-    stdin: Arc<Mutex<ChildStdin>>,
-    stdout: Arc<Mutex<BufReader<ChildStdout>>>,
-    child: Arc<Mutex<Child>>,
-    request_id: AtomicU64,
-    server_capabilities: ServerCapabilities,
-    server_info: Option<ServerInfo>,
-    initialized: bool,
+/// How JSON-RPC messages reach an MCP server.
+#[async_trait]
+pub trait Transport: Send + Sync {
+    /// Send a request and wait for the response with the same id.
+    async fn request(&self, request: JsonRpcRequest) -> Result<JsonRpcResponse>;
+
+    /// Send a notification; no response is expected.
+    async fn notify(&self, notification: JsonRpcRequest) -> Result<()>;
+
+    /// Called once the initialize handshake has completed.
+    async fn on_initialized(&self) {}
+
+    /// Whether the server has gone away for good.
+    async fn has_exited(&self) -> bool;
+
+    /// Stop talking to the server and release what the transport holds.
+    async fn shutdown(&self) -> Result<()>;
 }
 
-impl McpClient {
-    /// Spawn an MCP server as a subprocess and return a client.
-    pub async fn spawn(
+/// Talks to an MCP server subprocess over its stdin and stdout.
+pub struct StdioTransport {
+    stdin: Mutex<ChildStdin>,
+    stdout: Mutex<BufReader<ChildStdout>>,
+    child: Mutex<Child>,
+    /// Set once the server closes its stdout
+    closed: AtomicBool,
+}
+
+impl StdioTransport {
+    /// Spawn an MCP server as a subprocess.
+    pub fn spawn(
         command: &str,
         args: &[&str],
         env: Option<HashMap<String, String>>,
@@ -381,14 +400,148 @@ impl McpClient {
             .ok_or_else(|| anyhow!("Failed to capture stdout"))?;
 
         Ok(Self {
-            stdin: Arc::new(Mutex::new(stdin)),
-            stdout: Arc::new(Mutex::new(BufReader::new(stdout))),
-            child: Arc::new(Mutex::new(child)),
+            stdin: Mutex::new(stdin),
+            stdout: Mutex::new(BufReader::new(stdout)),
+            child: Mutex::new(child),
+            closed: AtomicBool::new(false),
+        })
+    }
+
+    /// Send a message to the server.
+    async fn send_message<T: Serialize>(&self, message: &T) -> Result<()> {
+        let json = serde_json::to_string(message).context("Failed to serialize message")?;
+        trace!(message = %json, "Sending message");
+
+        let mut stdin = self.stdin.lock().await;
+        stdin
+            .write_all(json.as_bytes())
+            .await
+            .context("Failed to write message")?;
+        stdin
+            .write_all(b"\n")
+            .await
+            .context("Failed to write newline")?;
+        stdin.flush().await.context("Failed to flush stdin")?;
+
+        Ok(())
+    }
+
+    /// Read a response from the server.
+    async fn read_response(&self) -> Result<JsonRpcResponse> {
+        let mut stdout = self.stdout.lock().await;
+        let mut line = String::new();
+
+        loop {
+            line.clear();
+            let bytes_read = stdout
+                .read_line(&mut line)
+                .await
+                .context("Failed to read from stdout")?;
+
+            if bytes_read == 0 {
+                self.closed.store(true, Ordering::SeqCst);
+                return Err(anyhow!("MCP server closed connection"));
+            }
+
+            let trimmed = line.trim();
+            if trimmed.is_empty() {
+                continue;
+            }
+
+            trace!(message = %trimmed, "Received message");
+
+            let response: JsonRpcResponse =
+                serde_json::from_str(trimmed).context("Failed to parse response")?;
+
+            return Ok(response);
+        }
+    }
+}
+
+#[async_trait]
+impl Transport for StdioTransport {
+    async fn request(&self, request: JsonRpcRequest) -> Result<JsonRpcResponse> {
+        let expected_id = request.id.clone();
+
+        self.send_message(&request).await?;
+
+        // Read responses until we get the one we're looking for
+        loop {
+            let response = self.read_response().await?;
+
+            // Check if this is a notification (no id)
+            if response.id.is_none() {
+                trace!("Received notification, continuing to wait for response");
+                continue;
+            }
+
+            // Check if this is our response
+            if response.id == expected_id {
+                return Ok(response);
+            }
+
+            warn!(
+                expected = ?expected_id,
+                received = ?response.id,
+                "Received response with unexpected id"
+            );
+        }
+    }
+
+    async fn notify(&self, notification: JsonRpcRequest) -> Result<()> {
+        self.send_message(&notification).await
+    }
+
+    async fn has_exited(&self) -> bool {
+        self.closed.load(Ordering::SeqCst)
+            || matches!(self.child.lock().await.try_wait(), Ok(Some(_)))
+    }
+
+    async fn shutdown(&self) -> Result<()> {
+        let mut child = self.child.lock().await;
+        if let Err(e) = child.kill().await {
+            warn!(error = %e, "Failed to kill MCP server process");
+        }
+        Ok(())
+    }
+}
+
+/// MCP client for communicating with an MCP server over a [`Transport`].
+pub struct McpClient {
+    transport: Box<dyn Transport>,
+    request_id: AtomicU64,
+    server_capabilities: ServerCapabilities,
+    server_info: Option<ServerInfo>,
+    initialized: bool,
+}
+
+impl McpClient {
+    /// Spawn an MCP server as a subprocess and return a client.
+    pub async fn spawn(
+        command: &str,
+        args: &[&str],
+        env: Option<HashMap<String, String>>,
+    ) -> Result<Self> {
+        let transport = StdioTransport::spawn(command, args, env)?;
+        Ok(Self::with_transport(Box::new(transport)))
+    }
+
+    /// Return a client for an MCP server speaking the streamable HTTP
+    /// transport at `url`.
+    pub fn connect_http(url: &str, bearer_token: Option<String>) -> Result<Self> {
+        let transport = HttpTransport::new(url, bearer_token)?;
+        Ok(Self::with_transport(Box::new(transport)))
+    }
+
+    /// Return a client that talks over `transport`.
+    pub fn with_transport(transport: Box<dyn Transport>) -> Self {
+        Self {
+            transport,
             request_id: AtomicU64::new(1),
             server_capabilities: ServerCapabilities::default(),
             server_info: None,
             initialized: false,
-        })
+        }
     }
 
     /// Perform the initialize handshake with the server.
@@ -412,6 +565,7 @@ impl McpClient {
 
         // Send initialized notification
         self.notify("notifications/initialized", None::<()>).await?;
+        self.transport.on_initialized().await;
 
         self.initialized = true;
 
@@ -451,6 +605,18 @@ impl McpClient {
         self.server_capabilities.prompts.is_some()
     }
 
+    /// Whether the server has gone away for good, after which every call
+    /// fails.
+    pub async fn has_exited(&self) -> bool {
+        self.transport.has_exited().await
+    }
+
+    /// Ping the server to check it is still responding.
+    pub async fn ping(&self) -> Result<()> {
+        let _: Value = self.call("ping", None::<()>).await?;
+        Ok(())
+    }
+
     /// List available tools from the server.
     pub async fn list_tools(&self) -> Result<Vec<McpTool>> {
         if !self.has_tools() {
@@ -546,7 +712,7 @@ impl McpClient {
             .context("Failed to serialize params")?;
 
         let request = JsonRpcRequest::new(id, method, params_value);
-        let response = self.send_request(request).await?;
+        let response = self.transport.request(request).await?;
 
         if let Some(error) = response.error {
             return Err(error.into());
@@ -570,98 +736,13 @@ impl McpClient {
             .context("Failed to serialize params")?;
 
         let notification = JsonRpcRequest::notification(method, params_value);
-        self.send_message(&notification).await
-    }
-
-    /// Send a request and read the response.
-    async fn send_request(&self, request: JsonRpcRequest) -> Result<JsonRpcResponse> {
-        let expected_id = request.id.clone();
-
-        self.send_message(&request).await?;
-
-        // Read responses until we get the one we're looking for
-        loop {
-            let response = self.read_response().await?;
-
-            // Check if this is a notification (no id)
-            if response.id.is_none() {
-                trace!("Received notification, continuing to wait for response");
-                continue;
-            }
-
-            // Check if this is our response
-            if response.id == expected_id {
-                return Ok(response);
-            }
-
-            warn!(
-                expected = ?expected_id,
-                received = ?response.id,
-                "Received response with unexpected id"
-            );
-        }
-    }
-
-    /// Send a message to the server.
-    async fn send_message<T: Serialize>(&self, message: &T) -> Result<()> {
-        let json = serde_json::to_string(message).context("Failed to serialize message")?;
-        trace!(message = %json, "Sending message");
-
-        let mut stdin = self.stdin.lock().await;
-        stdin
-            .write_all(json.as_bytes())
-            .await
-            .context("Failed to write message")?;
-        stdin
-            .write_all(b"\n")
-            .await
-            .context("Failed to write newline")?;
-        stdin.flush().await.context("Failed to flush stdin")?;
-
-        Ok(())
-    }
-
-    /// Read a response from the server.
-    async fn read_response(&self) -> Result<JsonRpcResponse> {
-        let mut stdout = self.stdout.lock().await;
-        let mut line = String::new();
-
-        loop {
-            line.clear();
-            let bytes_read = stdout
-                .read_line(&mut line)
-                .await
-                .context("Failed to read from stdout")?;
-
-            if bytes_read == 0 {
-                return Err(anyhow!("MCP server closed connection"));
-            }
-
-            let trimmed = line.trim();
-            if trimmed.is_empty() {
-                continue;
-            }
-
-            trace!(message = %trimmed, "Received message");
-
-            let response: JsonRpcResponse =
-                serde_json::from_str(trimmed).context("Failed to parse response")?;
-
-            return Ok(response);
-        }
+        self.transport.notify(notification).await
     }
 
     /// Shutdown the MCP server gracefully.
     pub async fn shutdown(&mut self) -> Result<()> {
         debug!("Shutting down MCP client");
-
-        // Try to kill the child process
-        let mut child = self.child.lock().await;
-        if let Err(e) = child.kill().await {
-            warn!(error = %e, "Failed to kill MCP server process");
-        }
-
-        Ok(())
+        self.transport.shutdown().await
     }
 }
 
@@ -1,9 +1,45 @@
 // ABOUTME: Dynamic tool registration from MCP server capabilities.
 // ABOUTME: Converts MCP tools/resources/prompts to coven-pack tool definitions.
 
+use crate::config::PREFIX_SEPARATOR;
 use crate::mcp_client::McpTool;
 use coven_proto::ToolDefinition;
 
+/// The name a server's tool is exposed under: `<prefix>__<name>`, or just
+/// `name` for an unprefixed server.
+pub fn prefixed_name(prefix: &str, name: &str) -> String {
+    if prefix.is_empty() {
+        name.to_string()
+    } else {
+        format!("{}{}{}", prefix, PREFIX_SEPARATOR, name)
+    }
+}
+
+/// Everything one MCP server contributes to the manifest: its tools plus
+/// the synthetic resource and prompt tools when it supports those, all
+/// under its prefix. While the server is down (`failure`), its tools are
+/// still listed but marked degraded.
+pub fn server_tool_definitions(
+    prefix: &str,
+    mcp_tools: &[McpTool],
+    has_resources: bool,
+    has_prompts: bool,
+    failure: Option<&str>,
+) -> Vec<ToolDefinition> {
+    let mut definitions = mcp_tools_to_definitions(mcp_tools);
+    if has_resources {
+        definitions.extend(resource_tools());
+    }
+    if has_prompts {
+        definitions.extend(prompt_tools());
+    }
+    for definition in &mut definitions {
+        definition.name = prefixed_name(prefix, &definition.name);
+        definition.degraded_reason = failure.map(str::to_string);
+    }
+    definitions
+}
+
 /// Convert MCP tools to coven ToolDefinitions.
 pub fn mcp_tools_to_definitions(tools: &[McpTool]) -> Vec<ToolDefinition> {
     tools
@@ -18,6 +54,8 @@ pub fn mcp_tools_to_definitions(tools: &[McpTool]) -> Vec<ToolDefinition> {
                 .unwrap_or_else(|| r#"{"type": "object"}"#.to_string()),
             required_capabilities: vec![],
             timeout_seconds: 60,
+            confirm_message: None,
+            degraded_reason: None,
         })
         .collect()
 }
@@ -31,6 +69,8 @@ pub fn resource_tools() -> Vec<ToolDefinition> {
             input_schema_json: r#"{"type": "object"}"#.to_string(),
             required_capabilities: vec![],
             timeout_seconds: 30,
+            confirm_message: None,
+            degraded_reason: None,
         },
         ToolDefinition {
             name: "mcp_read_resource".to_string(),
@@ -38,6 +78,8 @@ pub fn resource_tools() -> Vec<ToolDefinition> {
             input_schema_json: r#"{"type": "object", "properties": {"uri": {"type": "string", "description": "Resource URI"}}, "required": ["uri"]}"#.to_string(),
             required_capabilities: vec![],
             timeout_seconds: 60,
+            confirm_message: None,
+            degraded_reason: None,
         },
     ]
 }
@@ -51,6 +93,8 @@ pub fn prompt_tools() -> Vec<ToolDefinition> {
             input_schema_json: r#"{"type": "object"}"#.to_string(),
             required_capabilities: vec![],
             timeout_seconds: 30,
+            confirm_message: None,
+            degraded_reason: None,
         },
         ToolDefinition {
             name: "mcp_get_prompt".to_string(),
@@ -58,6 +102,8 @@ pub fn prompt_tools() -> Vec<ToolDefinition> {
             input_schema_json: r#"{"type": "object", "properties": {"name": {"type": "string", "description": "Prompt name"}, "arguments": {"type": "object", "description": "Prompt arguments"}}, "required": ["name"]}"#.to_string(),
             required_capabilities: vec![],
             timeout_seconds: 60,
+            confirm_message: None,
+            degraded_reason: None,
         },
     ]
 }
@@ -110,6 +156,62 @@ mod tests {
         assert_eq!(definitions[0].input_schema_json, r#"{"type": "object"}"#);
     }
 
+    fn mcp_tool(name: &str) -> McpTool {
+        serde_json::from_value(json!({ "name": name })).unwrap()
+    }
+
+    #[test]
+    fn test_server_tool_definitions_prefixes_every_tool() {
+        let names = |definitions: Vec<ToolDefinition>| -> Vec<String> {
+            definitions.into_iter().map(|d| d.name).collect()
+        };
+
+        assert_eq!(
+            names(server_tool_definitions(
+                "",
+                &[mcp_tool("search")],
+                false,
+                false,
+                None
+            )),
+            vec!["search"]
+        );
+        assert_eq!(
+            names(server_tool_definitions(
+                "gh",
+                &[mcp_tool("search"), mcp_tool("create_issue")],
+                true,
+                false,
+                None
+            )),
+            vec![
+                "gh__search",
+                "gh__create_issue",
+                "gh__mcp_list_resources",
+                "gh__mcp_read_resource"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_server_tool_definitions_marks_failed_server() {
+        let healthy = server_tool_definitions("gh", &[mcp_tool("search")], false, true, None);
+        assert!(healthy.iter().all(|d| d.degraded_reason.is_none()));
+
+        let failed = server_tool_definitions(
+            "gh",
+            &[mcp_tool("search")],
+            false,
+            true,
+            Some("MCP server 'github' exited"),
+        );
+        assert_eq!(failed.len(), 3);
+        assert!(failed
+            .iter()
+            .all(|d| d.degraded_reason.as_deref() == Some("MCP server 'github' exited")));
+        assert_ne!(failed, healthy);
+    }
+
     #[test]
     fn test_resource_tools() {
         let tools = resource_tools();
@@ -0,0 +1,32 @@
+#!/bin/sh
+# ABOUTME: Minimal MCP server over stdio for mcp-bridge-pack tests.
+# ABOUTME: Offers "echo", which answers with $FAKE_MCP_NAME:<tool>, and "crash", which exits.
+
+name="${FAKE_MCP_NAME:-fake}"
+
+while IFS= read -r line; do
+    id=$(printf '%s\n' "$line" | sed -n 's/.*"id":\([0-9][0-9]*\).*/\1/p')
+    case "$line" in
+        *'"method":"initialize"'*)
+            result='{"protocolVersion":"2024-11-05","capabilities":{"tools":{}},"serverInfo":{"name":"'"$name"'"}}'
+            ;;
+        *'"method":"ping"'*)
+            result='{}'
+            ;;
+        *'"method":"tools/list"'*)
+            result='{"tools":[{"name":"echo","inputSchema":{"type":"object"}},{"name":"crash"}]}'
+            ;;
+        *'"method":"tools/call"'*)
+            tool=$(printf '%s\n' "$line" | sed -n 's/.*"params":{"name":"\([^"]*\)".*/\1/p')
+            if [ "$tool" = "crash" ]; then
+                exit 1
+            fi
+            result='{"content":[{"type":"text","text":"'"$name:$tool"'"}]}'
+            ;;
+        *)
+            # Notifications need no answer
+            continue
+            ;;
+    esac
+    printf '{"jsonrpc":"2.0","id":%s,"result":%s}\n' "$id" "$result"
+done
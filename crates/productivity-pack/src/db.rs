@@ -1,10 +1,44 @@
 // ABOUTME: SQLite database layer for productivity-pack.
-// ABOUTME: Manages todos and notes tables with CRUD operations.
+// ABOUTME: Manages todos and notes tables with CRUD operations, optionally scoped per agent.
 
-use anyhow::Result;
+use anyhow::{anyhow, bail, Result};
 use serde::{Deserialize, Serialize};
-use sqlx::{sqlite::SqlitePoolOptions, FromRow, SqlitePool};
+use sqlx::sqlite::{SqliteConnectOptions, SqliteJournalMode, SqlitePoolOptions, SqliteSynchronous};
+use sqlx::{FromRow, SqlitePool};
 use std::path::Path;
+use std::str::FromStr;
+use std::time::Duration;
+
+/// How long a write waits on a locked database before failing.
+pub const DEFAULT_BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// `agent_scope` of rows shared by every agent.
+const GLOBAL_SCOPE: &str = "";
+
+/// Whose todos and notes a tool call sees.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Scope {
+    /// Every agent shares one set of todos and notes.
+    #[default]
+    Global,
+    /// Each agent sees only what it created.
+    PerAgent,
+}
+
+impl FromStr for Scope {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim() {
+            "global" => Ok(Self::Global),
+            "per-agent" => Ok(Self::PerAgent),
+            other => Err(anyhow!(
+                "unknown scope '{}' (expected 'global' or 'per-agent')",
+                other
+            )),
+        }
+    }
+}
 
 /// Escape SQL LIKE pattern metacharacters to prevent pattern injection.
 fn escape_like_pattern(s: &str) -> String {
@@ -46,26 +80,73 @@ pub struct Note {
 
 pub struct Database {
     pool: SqlitePool,
+    scope: Scope,
 }
 
 impl Database {
     pub async fn new(path: &Path) -> Result<Self> {
+        Self::open(path, DEFAULT_BUSY_TIMEOUT).await
+    }
+
+    /// Open the database, waiting up to `busy_timeout` for a lock. Each
+    /// connection runs in WAL mode with `synchronous = NORMAL`, so reads
+    /// don't block writes and concurrent tool calls queue instead of failing.
+    pub async fn open(path: &Path, busy_timeout: Duration) -> Result<Self> {
         // Ensure parent directory exists
         if let Some(parent) = path.parent() {
             std::fs::create_dir_all(parent)?;
         }
 
-        let url = format!("sqlite:{}?mode=rwc", path.display());
+        let options = SqliteConnectOptions::new()
+            .filename(path)
+            .create_if_missing(true)
+            .journal_mode(SqliteJournalMode::Wal)
+            .synchronous(SqliteSynchronous::Normal)
+            .busy_timeout(busy_timeout);
         let pool = SqlitePoolOptions::new()
             .max_connections(5)
-            .connect(&url)
+            .connect_with(options)
             .await?;
 
-        let db = Self { pool };
+        let db = Self {
+            pool,
+            scope: Scope::Global,
+        };
         db.run_migrations().await?;
         Ok(db)
     }
 
+    /// Keep data shared or separate per agent.
+    pub fn with_scope(mut self, scope: Scope) -> Self {
+        self.scope = scope;
+        self
+    }
+
+    /// The `agent_scope` a call from `agent_id` reads and writes. Per-agent
+    /// scoping needs a gateway that says which agent is calling; without
+    /// one the call is refused rather than falling back to shared data.
+    pub fn scope_key(&self, agent_id: Option<&str>) -> Result<String> {
+        match (self.scope, agent_id) {
+            (Scope::Global, _) => Ok(GLOBAL_SCOPE.to_string()),
+            (Scope::PerAgent, Some(agent_id)) if !agent_id.is_empty() => Ok(agent_id.to_string()),
+            (Scope::PerAgent, _) => {
+                bail!("per-agent scope is on but the gateway didn't say which agent is calling")
+            }
+        }
+    }
+
+    /// Run a trivial query to check the database is reachable.
+    pub async fn ping(&self) -> Result<()> {
+        sqlx::query("SELECT 1").execute(&self.pool).await?;
+        Ok(())
+    }
+
+    /// Close the connection pool; later queries fail.
+    #[cfg(test)]
+    pub async fn close(&self) {
+        self.pool.close().await;
+    }
+
     async fn run_migrations(&self) -> Result<()> {
         sqlx::query(
             r#"
@@ -74,7 +155,8 @@ impl Database {
                 title TEXT NOT NULL,
                 due_date TEXT,
                 completed BOOLEAN NOT NULL DEFAULT FALSE,
-                created_at TEXT NOT NULL DEFAULT (datetime('now'))
+                created_at TEXT NOT NULL DEFAULT (datetime('now')),
+                agent_scope TEXT NOT NULL DEFAULT ''
             )
             "#,
         )
@@ -89,7 +171,8 @@ impl Database {
                 content TEXT NOT NULL,
                 tags TEXT NOT NULL DEFAULT '[]',
                 created_at TEXT NOT NULL DEFAULT (datetime('now')),
-                updated_at TEXT NOT NULL DEFAULT (datetime('now'))
+                updated_at TEXT NOT NULL DEFAULT (datetime('now')),
+                agent_scope TEXT NOT NULL DEFAULT ''
             )
             "#,
         )
@@ -104,44 +187,73 @@ impl Database {
         .execute(&self.pool)
         .await?;
 
+        // Databases from before scoping lack the column; their rows stay global
+        for table in ["todos", "notes"] {
+            let columns: Vec<String> =
+                sqlx::query_scalar(&format!("SELECT name FROM pragma_table_info('{}')", table))
+                    .fetch_all(&self.pool)
+                    .await?;
+            if !columns.iter().any(|c| c == "agent_scope") {
+                sqlx::query(&format!(
+                    "ALTER TABLE {} ADD COLUMN agent_scope TEXT NOT NULL DEFAULT ''",
+                    table
+                ))
+                .execute(&self.pool)
+                .await?;
+            }
+        }
+
+        sqlx::query("CREATE INDEX IF NOT EXISTS idx_todos_scope ON todos(agent_scope)")
+            .execute(&self.pool)
+            .await?;
+        sqlx::query("CREATE INDEX IF NOT EXISTS idx_notes_scope ON notes(agent_scope)")
+            .execute(&self.pool)
+            .await?;
+
         Ok(())
     }
 
     // Todo operations
-    pub async fn add_todo(&self, title: &str, due_date: Option<&str>) -> Result<Todo> {
+    pub async fn add_todo(&self, scope: &str, title: &str, due_date: Option<&str>) -> Result<Todo> {
         let result = sqlx::query_as::<_, Todo>(
             r#"
-            INSERT INTO todos (title, due_date)
-            VALUES (?, ?)
+            INSERT INTO todos (title, due_date, agent_scope)
+            VALUES (?, ?, ?)
             RETURNING id, title, due_date, completed, created_at
             "#,
         )
         .bind(title)
         .bind(due_date)
+        .bind(scope)
         .fetch_one(&self.pool)
         .await?;
 
         Ok(result)
     }
 
-    pub async fn list_todos(&self, filter: TodoFilter) -> Result<Vec<Todo>> {
+    pub async fn list_todos(&self, scope: &str, filter: TodoFilter) -> Result<Vec<Todo>> {
         let todos = match filter {
             TodoFilter::All => {
-                sqlx::query_as::<_, Todo>("SELECT * FROM todos ORDER BY created_at DESC")
-                    .fetch_all(&self.pool)
-                    .await?
+                sqlx::query_as::<_, Todo>(
+                    "SELECT * FROM todos WHERE agent_scope = ? ORDER BY created_at DESC",
+                )
+                .bind(scope)
+                .fetch_all(&self.pool)
+                .await?
             }
             TodoFilter::Pending => {
                 sqlx::query_as::<_, Todo>(
-                    "SELECT * FROM todos WHERE completed = FALSE ORDER BY created_at DESC",
+                    "SELECT * FROM todos WHERE agent_scope = ? AND completed = FALSE ORDER BY created_at DESC",
                 )
+                .bind(scope)
                 .fetch_all(&self.pool)
                 .await?
             }
             TodoFilter::Done => {
                 sqlx::query_as::<_, Todo>(
-                    "SELECT * FROM todos WHERE completed = TRUE ORDER BY created_at DESC",
+                    "SELECT * FROM todos WHERE agent_scope = ? AND completed = TRUE ORDER BY created_at DESC",
                 )
+                .bind(scope)
                 .fetch_all(&self.pool)
                 .await?
             }
@@ -152,24 +264,26 @@ impl Database {
 
     // Reserved for future todo_delete tool implementation
     #[allow(dead_code)]
-    pub async fn delete_todo(&self, id: i64) -> Result<bool> {
-        let result = sqlx::query("DELETE FROM todos WHERE id = ?")
+    pub async fn delete_todo(&self, scope: &str, id: i64) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM todos WHERE id = ? AND agent_scope = ?")
             .bind(id)
+            .bind(scope)
             .execute(&self.pool)
             .await?;
 
         Ok(result.rows_affected() > 0)
     }
 
-    pub async fn complete_todo(&self, id: i64) -> Result<Option<Todo>> {
+    pub async fn complete_todo(&self, scope: &str, id: i64) -> Result<Option<Todo>> {
         let result = sqlx::query_as::<_, Todo>(
             r#"
             UPDATE todos SET completed = TRUE
-            WHERE id = ?
+            WHERE id = ? AND agent_scope = ?
             RETURNING id, title, due_date, completed, created_at
             "#,
         )
         .bind(id)
+        .bind(scope)
         .fetch_optional(&self.pool)
         .await?;
 
@@ -177,25 +291,37 @@ impl Database {
     }
 
     // Note operations
-    pub async fn create_note(&self, title: &str, content: &str, tags: &[String]) -> Result<Note> {
+    pub async fn create_note(
+        &self,
+        scope: &str,
+        title: &str,
+        content: &str,
+        tags: &[String],
+    ) -> Result<Note> {
         let tags_json = serde_json::to_string(tags)?;
         let result = sqlx::query_as::<_, Note>(
             r#"
-            INSERT INTO notes (title, content, tags)
-            VALUES (?, ?, ?)
+            INSERT INTO notes (title, content, tags, agent_scope)
+            VALUES (?, ?, ?, ?)
             RETURNING id, title, content, tags, created_at, updated_at
             "#,
         )
         .bind(title)
         .bind(content)
         .bind(&tags_json)
+        .bind(scope)
         .fetch_one(&self.pool)
         .await?;
 
         Ok(result)
     }
 
-    pub async fn search_notes(&self, query: &str, tags: Option<&[String]>) -> Result<Vec<Note>> {
+    pub async fn search_notes(
+        &self,
+        scope: &str,
+        query: &str,
+        tags: Option<&[String]>,
+    ) -> Result<Vec<Note>> {
         // Escape LIKE metacharacters to prevent pattern injection
         let search_pattern = format!("%{}%", escape_like_pattern(query));
 
@@ -206,10 +332,11 @@ impl Database {
                 let candidates = sqlx::query_as::<_, Note>(
                     r#"
                     SELECT * FROM notes
-                    WHERE (title LIKE ? OR content LIKE ?)
+                    WHERE agent_scope = ? AND (title LIKE ? OR content LIKE ?)
                     ORDER BY updated_at DESC
                     "#,
                 )
+                .bind(scope)
                 .bind(&search_pattern)
                 .bind(&search_pattern)
                 .fetch_all(&self.pool)
@@ -233,10 +360,11 @@ impl Database {
                 sqlx::query_as::<_, Note>(
                     r#"
                     SELECT * FROM notes
-                    WHERE (title LIKE ? OR content LIKE ?)
+                    WHERE agent_scope = ? AND (title LIKE ? OR content LIKE ?)
                     ORDER BY updated_at DESC
                     "#,
                 )
+                .bind(scope)
                 .bind(&search_pattern)
                 .bind(&search_pattern)
                 .fetch_all(&self.pool)
@@ -247,23 +375,113 @@ impl Database {
         Ok(notes)
     }
 
-    pub async fn read_note(&self, id: i64) -> Result<Option<Note>> {
-        let note = sqlx::query_as::<_, Note>("SELECT * FROM notes WHERE id = ?")
-            .bind(id)
-            .fetch_optional(&self.pool)
-            .await?;
+    pub async fn read_note(&self, scope: &str, id: i64) -> Result<Option<Note>> {
+        let note =
+            sqlx::query_as::<_, Note>("SELECT * FROM notes WHERE id = ? AND agent_scope = ?")
+                .bind(id)
+                .bind(scope)
+                .fetch_optional(&self.pool)
+                .await?;
 
         Ok(note)
     }
 
     // Reserved for future note_delete tool implementation
     #[allow(dead_code)]
-    pub async fn delete_note(&self, id: i64) -> Result<bool> {
-        let result = sqlx::query("DELETE FROM notes WHERE id = ?")
+    pub async fn delete_note(&self, scope: &str, id: i64) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM notes WHERE id = ? AND agent_scope = ?")
             .bind(id)
+            .bind(scope)
             .execute(&self.pool)
             .await?;
 
         Ok(result.rows_affected() > 0)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scope_parse() {
+        assert_eq!("global".parse::<Scope>().unwrap(), Scope::Global);
+        assert_eq!(" per-agent ".parse::<Scope>().unwrap(), Scope::PerAgent);
+        assert!("per-thread".parse::<Scope>().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_pre_scoping_rows_stay_global() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("productivity.db");
+
+        // A database created before the agent_scope column existed
+        {
+            let pool = SqlitePoolOptions::new()
+                .connect(&format!("sqlite:{}?mode=rwc", path.display()))
+                .await
+                .unwrap();
+            sqlx::query(
+                "CREATE TABLE todos (id INTEGER PRIMARY KEY AUTOINCREMENT, title TEXT NOT NULL, \
+                 due_date TEXT, completed BOOLEAN NOT NULL DEFAULT FALSE, \
+                 created_at TEXT NOT NULL DEFAULT (datetime('now')))",
+            )
+            .execute(&pool)
+            .await
+            .unwrap();
+            sqlx::query("INSERT INTO todos (title) VALUES ('old todo')")
+                .execute(&pool)
+                .await
+                .unwrap();
+            pool.close().await;
+        }
+
+        let db = Database::new(&path).await.unwrap();
+        let todos = db.list_todos(GLOBAL_SCOPE, TodoFilter::All).await.unwrap();
+        assert_eq!(todos.len(), 1);
+        assert_eq!(todos[0].title, "old todo");
+        assert!(db
+            .list_todos("agent-a", TodoFilter::All)
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_writers_do_not_hit_lock_errors() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("productivity.db");
+        // Separate pools on one file, as when two pack processes share a database
+        let first = std::sync::Arc::new(Database::new(&path).await.unwrap());
+        let second = std::sync::Arc::new(Database::new(&path).await.unwrap());
+
+        let mut writers = Vec::new();
+        for i in 0..20 {
+            let db = if i % 2 == 0 {
+                first.clone()
+            } else {
+                second.clone()
+            };
+            writers.push(tokio::spawn(async move {
+                for n in 0..10 {
+                    let todo = db
+                        .add_todo(GLOBAL_SCOPE, &format!("todo {i}-{n}"), None)
+                        .await?;
+                    db.complete_todo(GLOBAL_SCOPE, todo.id).await?;
+                    db.create_note(GLOBAL_SCOPE, &format!("note {i}-{n}"), "body", &[])
+                        .await?;
+                }
+                anyhow::Ok(())
+            }));
+        }
+        for writer in writers {
+            writer.await.unwrap().expect("writer hit a database error");
+        }
+
+        let todos = first
+            .list_todos(GLOBAL_SCOPE, TodoFilter::Done)
+            .await
+            .unwrap();
+        assert_eq!(todos.len(), 200);
+    }
+}
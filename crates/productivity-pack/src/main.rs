@@ -1,25 +1,22 @@
 // ABOUTME: Productivity pack providing todo and notes tools.
-// ABOUTME: Uses SQLite for persistent storage.
+// ABOUTME: Uses SQLite for persistent storage, shared by all agents or kept per agent.
 
 mod db;
 mod notes;
 mod todo;
 
 use anyhow::{anyhow, Result};
-use async_trait::async_trait;
-use coven_pack::{ManifestBuilder, PackClient, SchemaBuilder, ToolError, ToolHandler};
-use coven_ssh::load_or_generate_key;
-use db::{Database, TodoFilter};
-use notes::{
-    NoteCreateInput, NoteCreateOutput, NoteReadInput, NoteReadOutput, NoteSearchInput,
-    NoteSearchOutput,
+use coven_pack::{
+    ExecutionContext, HealthStatus, ManifestBuilder, PackClient, RetryPolicy, ToolError,
+    TypedHandler,
 };
+use coven_ssh::load_or_generate_key_with_passphrase;
+use db::{Database, Scope};
+use notes::{NoteCreateInput, NoteReadInput, NoteSearchInput};
 use std::path::PathBuf;
 use std::sync::Arc;
-use todo::{
-    TodoAddInput, TodoAddOutput, TodoCompleteInput, TodoCompleteOutput, TodoListInput,
-    TodoListOutput,
-};
+use std::time::Duration;
+use todo::{TodoAddInput, TodoCompleteInput, TodoListInput};
 use tracing::info;
 
 const PACK_NAME: &str = "productivity-pack";
@@ -32,236 +29,51 @@ fn xdg_data_dir() -> Option<PathBuf> {
         .map(|p| p.join("coven"))
 }
 
-struct ProductivityHandler {
-    db: Arc<Database>,
+/// The `agent_scope` a tool call reads and writes.
+pub(crate) fn call_scope(db: &Database, ctx: &ExecutionContext) -> Result<String, ToolError> {
+    db.scope_key(ctx.agent_id.as_deref())
+        .map_err(|e| ToolError::ExecutionFailed(e.to_string()))
 }
 
-#[async_trait]
-impl ToolHandler for ProductivityHandler {
-    async fn execute(&self, tool_name: &str, input_json: &str) -> Result<String, ToolError> {
-        info!("Executing tool: {} with input: {}", tool_name, input_json);
-
-        match tool_name {
-            "todo_add" => {
-                let input: TodoAddInput = serde_json::from_str(input_json)
-                    .map_err(|e| ToolError::InvalidInput(e.to_string()))?;
-
-                let todo = self
-                    .db
-                    .add_todo(&input.title, input.due_date.as_deref())
-                    .await
-                    .map_err(|e| ToolError::ExecutionFailed(e.to_string()))?;
-
-                let output = TodoAddOutput {
-                    message: format!("Created todo #{}", todo.id),
-                    todo,
-                };
-
-                serde_json::to_string(&output)
-                    .map_err(|e| ToolError::ExecutionFailed(e.to_string()))
-            }
-
-            "todo_list" => {
-                let input: TodoListInput = serde_json::from_str(input_json)
-                    .map_err(|e| ToolError::InvalidInput(e.to_string()))?;
-
-                let filter = match input.filter.as_str() {
-                    "pending" => TodoFilter::Pending,
-                    "done" => TodoFilter::Done,
-                    _ => TodoFilter::All,
-                };
-
-                let todos = self
-                    .db
-                    .list_todos(filter)
-                    .await
-                    .map_err(|e| ToolError::ExecutionFailed(e.to_string()))?;
-
-                let count = todos.len();
-                let output = TodoListOutput { todos, count };
-
-                serde_json::to_string(&output)
-                    .map_err(|e| ToolError::ExecutionFailed(e.to_string()))
-            }
-
-            "todo_complete" => {
-                let input: TodoCompleteInput = serde_json::from_str(input_json)
-                    .map_err(|e| ToolError::InvalidInput(e.to_string()))?;
-
-                let todo = self
-                    .db
-                    .complete_todo(input.id)
-                    .await
-                    .map_err(|e| ToolError::ExecutionFailed(e.to_string()))?;
-
-                let message = match &todo {
-                    Some(t) => format!("Completed todo #{}: {}", t.id, t.title),
-                    None => format!("Todo #{} not found", input.id),
-                };
-
-                let output = TodoCompleteOutput { todo, message };
-
-                serde_json::to_string(&output)
-                    .map_err(|e| ToolError::ExecutionFailed(e.to_string()))
-            }
-
-            "note_create" => {
-                let input: NoteCreateInput = serde_json::from_str(input_json)
-                    .map_err(|e| ToolError::InvalidInput(e.to_string()))?;
-
-                let note = self
-                    .db
-                    .create_note(&input.title, &input.content, &input.tags)
-                    .await
-                    .map_err(|e| ToolError::ExecutionFailed(e.to_string()))?;
-
-                let output = NoteCreateOutput {
-                    message: format!("Created note #{}", note.id),
-                    note,
-                };
-
-                serde_json::to_string(&output)
-                    .map_err(|e| ToolError::ExecutionFailed(e.to_string()))
-            }
-
-            "note_search" => {
-                let input: NoteSearchInput = serde_json::from_str(input_json)
-                    .map_err(|e| ToolError::InvalidInput(e.to_string()))?;
-
-                let notes = self
-                    .db
-                    .search_notes(&input.query, input.tags.as_deref())
-                    .await
-                    .map_err(|e| ToolError::ExecutionFailed(e.to_string()))?;
-
-                let count = notes.len();
-                let output = NoteSearchOutput { notes, count };
-
-                serde_json::to_string(&output)
-                    .map_err(|e| ToolError::ExecutionFailed(e.to_string()))
-            }
-
-            "note_read" => {
-                let input: NoteReadInput = serde_json::from_str(input_json)
-                    .map_err(|e| ToolError::InvalidInput(e.to_string()))?;
-
-                let note = self
-                    .db
-                    .read_note(input.id)
-                    .await
-                    .map_err(|e| ToolError::ExecutionFailed(e.to_string()))?;
-
-                let output = NoteReadOutput { note };
-
-                serde_json::to_string(&output)
-                    .map_err(|e| ToolError::ExecutionFailed(e.to_string()))
-            }
-
-            _ => Err(ToolError::UnknownTool(tool_name.to_string())),
-        }
+/// Healthy while the database answers `SELECT 1`.
+async fn health(db: Arc<Database>) -> HealthStatus {
+    match db.ping().await {
+        Ok(()) => HealthStatus::Healthy,
+        Err(e) => HealthStatus::unhealthy(format!("database unavailable: {}", e)),
     }
 }
 
-fn build_manifest() -> coven_proto::PackManifest {
-    // Todo schemas
-    let todo_add_schema = SchemaBuilder::object()
-        .property(
-            "title",
-            SchemaBuilder::string().description("Title of the todo item"),
-        )
-        .property(
-            "due_date",
-            SchemaBuilder::string().description("Optional due date (ISO 8601 format)"),
-        )
-        .required(&["title"])
-        .build();
-
-    let todo_list_schema = SchemaBuilder::object()
-        .property(
-            "filter",
-            SchemaBuilder::string()
-                .description("Filter todos: 'all', 'pending', or 'done'")
-                .default_value("all"),
-        )
-        .build();
-
-    let todo_complete_schema = SchemaBuilder::object()
-        .property(
-            "id",
-            SchemaBuilder::integer().description("ID of the todo to complete"),
-        )
-        .required(&["id"])
-        .build();
-
-    // Note schemas
-    let note_create_schema = SchemaBuilder::object()
-        .property(
-            "title",
-            SchemaBuilder::string().description("Title of the note"),
-        )
-        .property(
-            "content",
-            SchemaBuilder::string().description("Content of the note"),
-        )
-        .property(
-            "tags",
-            SchemaBuilder::array(SchemaBuilder::string()).description("Optional tags for the note"),
-        )
-        .required(&["title", "content"])
-        .build();
-
-    let note_search_schema = SchemaBuilder::object()
-        .property(
-            "query",
-            SchemaBuilder::string().description("Search query for title and content"),
-        )
-        .property(
-            "tags",
-            SchemaBuilder::array(SchemaBuilder::string()).description("Optional tags to filter by"),
-        )
-        .required(&["query"])
-        .build();
-
-    let note_read_schema = SchemaBuilder::object()
-        .property(
-            "id",
-            SchemaBuilder::integer().description("ID of the note to read"),
-        )
-        .required(&["id"])
-        .build();
+fn build_handler(db: Database) -> TypedHandler<Database> {
+    TypedHandler::with_state(db)
+        .with_health_check(health)
+        .tool_with_context("todo_add", todo::add)
+        .tool_with_context("todo_list", todo::list)
+        .tool_with_context("todo_complete", todo::complete)
+        .tool_with_context("note_create", notes::create)
+        .tool_with_context("note_search", notes::search)
+        .tool_with_context("note_read", notes::read)
+}
 
+fn build_manifest() -> coven_proto::PackManifest {
     ManifestBuilder::new(PACK_NAME, "0.1.0")
-        .tool(
-            "todo_add",
-            "Add a todo item with optional due date",
-            &todo_add_schema,
-            &[],
-        )
-        .tool(
+        .typed_tool::<TodoAddInput>("todo_add", "Add a todo item with optional due date", &[])
+        .typed_tool::<TodoListInput>(
             "todo_list",
             "List todos with optional filter (all, pending, done)",
-            &todo_list_schema,
-            &[],
-        )
-        .tool(
-            "todo_complete",
-            "Mark a todo item as complete",
-            &todo_complete_schema,
             &[],
         )
-        .tool(
+        .typed_tool::<TodoCompleteInput>("todo_complete", "Mark a todo item as complete", &[])
+        .typed_tool::<NoteCreateInput>(
             "note_create",
             "Create a note with title, content, and optional tags",
-            &note_create_schema,
             &[],
         )
-        .tool(
+        .typed_tool::<NoteSearchInput>(
             "note_search",
             "Search notes by query and optional tags",
-            &note_search_schema,
             &[],
         )
-        .tool("note_read", "Read a note by ID", &note_read_schema, &[])
+        .typed_tool::<NoteReadInput>("note_read", "Read a note by ID", &[])
         .build()
 }
 
@@ -280,22 +92,233 @@ async fn main() -> Result<()> {
                 .ok_or_else(|| anyhow!("Could not determine data directory"))
         })?;
 
+    // PRODUCTIVITY_SCOPE: "global" (default) shares data between agents,
+    // "per-agent" gives each agent its own todos and notes
+    let scope = match std::env::var("PRODUCTIVITY_SCOPE") {
+        Ok(scope) if !scope.trim().is_empty() => scope.parse::<Scope>()?,
+        _ => Scope::Global,
+    };
+
+    // PRODUCTIVITY_DB_BUSY_TIMEOUT_MS: how long a write waits on a locked database
+    let busy_timeout = match std::env::var("PRODUCTIVITY_DB_BUSY_TIMEOUT_MS") {
+        Ok(ms) if !ms.trim().is_empty() => Duration::from_millis(
+            ms.trim()
+                .parse()
+                .map_err(|_| anyhow!("invalid PRODUCTIVITY_DB_BUSY_TIMEOUT_MS '{}'", ms))?,
+        ),
+        _ => db::DEFAULT_BUSY_TIMEOUT,
+    };
+
     info!("Starting {}", PACK_NAME);
     info!("Gateway: {}", config.gateway_url);
     info!("SSH key: {}", config.ssh_key_path.display());
     info!("Database: {}", db_path.display());
+    info!("Scope: {:?}", scope);
 
     // Load existing key or generate one
-    let _private_key = load_or_generate_key(&config.ssh_key_path)?;
-
-    let db = Database::new(&db_path).await?;
-    let handler = ProductivityHandler { db: Arc::new(db) };
+    let _private_key = load_or_generate_key_with_passphrase(
+        &config.ssh_key_path,
+        config.ssh_key_passphrase.as_ref(),
+    )?;
+
+    let db = Database::open(&db_path, busy_timeout)
+        .await?
+        .with_scope(scope);
+    let handler = build_handler(db);
     let manifest = build_manifest();
 
     info!("Registering {} tools", manifest.tools.len());
 
-    let client = PackClient::connect(&config.gateway_url, &config.ssh_key_path).await?;
+    let client = PackClient::connect_with_config_retry(&config, RetryPolicy::default())
+        .await?
+        .on_connection_state(|state| info!(?state, "Gateway connection state changed"));
     client.run(manifest, handler).await?;
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use coven_pack::{SchemaBuilder, ToolInput};
+    use serde_json::Value;
+
+    fn parse(schema: &str) -> Value {
+        serde_json::from_str(schema).unwrap()
+    }
+
+    /// The derived schemas must match the ones this pack used to build by hand.
+    #[test]
+    fn test_derived_schemas_match_hand_written() {
+        let todo_add = SchemaBuilder::object()
+            .property(
+                "title",
+                SchemaBuilder::string().description("Title of the todo item"),
+            )
+            .property(
+                "due_date",
+                SchemaBuilder::string().description("Optional due date (ISO 8601 format)"),
+            )
+            .required(&["title"])
+            .build();
+        assert_eq!(TodoAddInput::input_schema(), parse(&todo_add));
+
+        let todo_list = SchemaBuilder::object()
+            .property(
+                "filter",
+                SchemaBuilder::string()
+                    .description("Filter todos: 'all', 'pending', or 'done'")
+                    .default_value("all"),
+            )
+            .build();
+        assert_eq!(TodoListInput::input_schema(), parse(&todo_list));
+
+        let todo_complete = SchemaBuilder::object()
+            .property(
+                "id",
+                SchemaBuilder::integer().description("ID of the todo to complete"),
+            )
+            .required(&["id"])
+            .build();
+        assert_eq!(TodoCompleteInput::input_schema(), parse(&todo_complete));
+
+        let note_create = SchemaBuilder::object()
+            .property(
+                "title",
+                SchemaBuilder::string().description("Title of the note"),
+            )
+            .property(
+                "content",
+                SchemaBuilder::string().description("Content of the note"),
+            )
+            .property(
+                "tags",
+                SchemaBuilder::array(SchemaBuilder::string())
+                    .description("Optional tags for the note"),
+            )
+            .required(&["title", "content"])
+            .build();
+        assert_eq!(NoteCreateInput::input_schema(), parse(&note_create));
+
+        let note_search = SchemaBuilder::object()
+            .property(
+                "query",
+                SchemaBuilder::string().description("Search query for title and content"),
+            )
+            .property(
+                "tags",
+                SchemaBuilder::array(SchemaBuilder::string())
+                    .description("Optional tags to filter by"),
+            )
+            .required(&["query"])
+            .build();
+        assert_eq!(NoteSearchInput::input_schema(), parse(&note_search));
+
+        let note_read = SchemaBuilder::object()
+            .property(
+                "id",
+                SchemaBuilder::integer().description("ID of the note to read"),
+            )
+            .required(&["id"])
+            .build();
+        assert_eq!(NoteReadInput::input_schema(), parse(&note_read));
+    }
+
+    async fn call(
+        handler: &TypedHandler<Database>,
+        agent_id: Option<&str>,
+        tool: &str,
+        input: &str,
+    ) -> Result<Value, ToolError> {
+        use coven_pack::ToolHandler;
+        let mut ctx = ExecutionContext::default();
+        ctx.agent_id = agent_id.map(str::to_string);
+        let output = handler.execute_with_context(tool, input, ctx).await?;
+        Ok(serde_json::from_str(&output).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_per_agent_scope_isolates_todos() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = Database::new(&dir.path().join("productivity.db"))
+            .await
+            .unwrap()
+            .with_scope(Scope::PerAgent);
+        let handler = build_handler(db);
+
+        let added = call(
+            &handler,
+            Some("agent-a"),
+            "todo_add",
+            r#"{"title": "ship it"}"#,
+        )
+        .await
+        .unwrap();
+        let id = added["todo"]["id"].as_i64().unwrap();
+
+        let mine = call(&handler, Some("agent-a"), "todo_list", "{}")
+            .await
+            .unwrap();
+        assert_eq!(mine["count"], 1);
+        let theirs = call(&handler, Some("agent-b"), "todo_list", "{}")
+            .await
+            .unwrap();
+        assert_eq!(theirs["count"], 0);
+
+        // Nor can another agent complete it by id
+        let completed = call(
+            &handler,
+            Some("agent-b"),
+            "todo_complete",
+            &format!(r#"{{"id": {}}}"#, id),
+        )
+        .await
+        .unwrap();
+        assert!(completed["todo"].is_null());
+
+        // Without a caller, per-agent scope refuses instead of sharing
+        let err = call(&handler, None, "todo_list", "{}").await.unwrap_err();
+        assert!(matches!(err, ToolError::ExecutionFailed(_)));
+    }
+
+    #[tokio::test]
+    async fn test_global_scope_shares_todos() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = Database::new(&dir.path().join("productivity.db"))
+            .await
+            .unwrap();
+        let handler = build_handler(db);
+
+        call(
+            &handler,
+            Some("agent-a"),
+            "todo_add",
+            r#"{"title": "ship it"}"#,
+        )
+        .await
+        .unwrap();
+        let listed = call(&handler, Some("agent-b"), "todo_list", "{}")
+            .await
+            .unwrap();
+        assert_eq!(listed["count"], 1);
+        let listed = call(&handler, None, "todo_list", "{}").await.unwrap();
+        assert_eq!(listed["count"], 1);
+    }
+
+    #[tokio::test]
+    async fn test_health_follows_database() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = Arc::new(
+            Database::new(&dir.path().join("productivity.db"))
+                .await
+                .unwrap(),
+        );
+        assert!(health(Arc::clone(&db)).await.is_healthy());
+
+        db.close().await;
+        let HealthStatus::Unhealthy(reason) = health(db).await else {
+            panic!("closed database reported healthy");
+        };
+        assert!(reason.starts_with("database unavailable"));
+    }
+}
@@ -1,40 +1,96 @@
 // ABOUTME: Notes tool handlers for productivity-pack.
 // ABOUTME: Implements create, search, and read operations.
 
+use crate::call_scope;
+use crate::db::{Database, Note};
+use coven_pack::{ExecutionContext, ToolError, ToolInput};
 use serde::{Deserialize, Serialize};
+use std::sync::Arc;
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, ToolInput)]
 pub struct NoteCreateInput {
+    /// Title of the note
     pub title: String,
+    /// Content of the note
     pub content: String,
+    /// Optional tags for the note
     #[serde(default)]
     pub tags: Vec<String>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, ToolInput)]
 pub struct NoteSearchInput {
+    /// Search query for title and content
     pub query: String,
+    /// Optional tags to filter by
     pub tags: Option<Vec<String>>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, ToolInput)]
 pub struct NoteReadInput {
+    /// ID of the note to read
     pub id: i64,
 }
 
 #[derive(Debug, Serialize)]
 pub struct NoteCreateOutput {
-    pub note: crate::db::Note,
+    pub note: Note,
     pub message: String,
 }
 
 #[derive(Debug, Serialize)]
 pub struct NoteSearchOutput {
-    pub notes: Vec<crate::db::Note>,
+    pub notes: Vec<Note>,
     pub count: usize,
 }
 
 #[derive(Debug, Serialize)]
 pub struct NoteReadOutput {
-    pub note: Option<crate::db::Note>,
+    pub note: Option<Note>,
+}
+
+pub async fn create(
+    db: Arc<Database>,
+    ctx: ExecutionContext,
+    input: NoteCreateInput,
+) -> Result<NoteCreateOutput, ToolError> {
+    let scope = call_scope(&db, &ctx)?;
+    let note = db
+        .create_note(&scope, &input.title, &input.content, &input.tags)
+        .await
+        .map_err(|e| ToolError::ExecutionFailed(e.to_string()))?;
+
+    Ok(NoteCreateOutput {
+        message: format!("Created note #{}", note.id),
+        note,
+    })
+}
+
+pub async fn search(
+    db: Arc<Database>,
+    ctx: ExecutionContext,
+    input: NoteSearchInput,
+) -> Result<NoteSearchOutput, ToolError> {
+    let scope = call_scope(&db, &ctx)?;
+    let notes = db
+        .search_notes(&scope, &input.query, input.tags.as_deref())
+        .await
+        .map_err(|e| ToolError::ExecutionFailed(e.to_string()))?;
+
+    let count = notes.len();
+    Ok(NoteSearchOutput { notes, count })
+}
+
+pub async fn read(
+    db: Arc<Database>,
+    ctx: ExecutionContext,
+    input: NoteReadInput,
+) -> Result<NoteReadOutput, ToolError> {
+    let scope = call_scope(&db, &ctx)?;
+    let note = db
+        .read_note(&scope, input.id)
+        .await
+        .map_err(|e| ToolError::ExecutionFailed(e.to_string()))?;
+
+    Ok(NoteReadOutput { note })
 }
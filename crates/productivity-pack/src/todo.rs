@@ -1,17 +1,25 @@
 // ABOUTME: Todo tool handlers for productivity-pack.
 // ABOUTME: Implements add, list, and complete operations.
 
+use crate::call_scope;
+use crate::db::{Database, Todo, TodoFilter};
+use coven_pack::{ExecutionContext, ToolError, ToolInput};
 use serde::{Deserialize, Serialize};
+use std::sync::Arc;
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, ToolInput)]
 pub struct TodoAddInput {
+    /// Title of the todo item
     pub title: String,
+    /// Optional due date (ISO 8601 format)
     pub due_date: Option<String>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, ToolInput)]
 pub struct TodoListInput {
+    /// Filter todos: 'all', 'pending', or 'done'
     #[serde(default = "default_filter")]
+    #[tool(default = "all")]
     pub filter: String, // Will be converted to TodoFilter enum in handler
 }
 
@@ -19,25 +27,83 @@ fn default_filter() -> String {
     "all".to_string()
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, ToolInput)]
 pub struct TodoCompleteInput {
+    /// ID of the todo to complete
     pub id: i64,
 }
 
 #[derive(Debug, Serialize)]
 pub struct TodoAddOutput {
-    pub todo: crate::db::Todo,
+    pub todo: Todo,
     pub message: String,
 }
 
 #[derive(Debug, Serialize)]
 pub struct TodoListOutput {
-    pub todos: Vec<crate::db::Todo>,
+    pub todos: Vec<Todo>,
     pub count: usize,
 }
 
 #[derive(Debug, Serialize)]
 pub struct TodoCompleteOutput {
-    pub todo: Option<crate::db::Todo>,
+    pub todo: Option<Todo>,
     pub message: String,
 }
+
+pub async fn add(
+    db: Arc<Database>,
+    ctx: ExecutionContext,
+    input: TodoAddInput,
+) -> Result<TodoAddOutput, ToolError> {
+    let scope = call_scope(&db, &ctx)?;
+    let todo = db
+        .add_todo(&scope, &input.title, input.due_date.as_deref())
+        .await
+        .map_err(|e| ToolError::ExecutionFailed(e.to_string()))?;
+
+    Ok(TodoAddOutput {
+        message: format!("Created todo #{}", todo.id),
+        todo,
+    })
+}
+
+pub async fn list(
+    db: Arc<Database>,
+    ctx: ExecutionContext,
+    input: TodoListInput,
+) -> Result<TodoListOutput, ToolError> {
+    let scope = call_scope(&db, &ctx)?;
+    let filter = match input.filter.as_str() {
+        "pending" => TodoFilter::Pending,
+        "done" => TodoFilter::Done,
+        _ => TodoFilter::All,
+    };
+
+    let todos = db
+        .list_todos(&scope, filter)
+        .await
+        .map_err(|e| ToolError::ExecutionFailed(e.to_string()))?;
+
+    let count = todos.len();
+    Ok(TodoListOutput { todos, count })
+}
+
+pub async fn complete(
+    db: Arc<Database>,
+    ctx: ExecutionContext,
+    input: TodoCompleteInput,
+) -> Result<TodoCompleteOutput, ToolError> {
+    let scope = call_scope(&db, &ctx)?;
+    let todo = db
+        .complete_todo(&scope, input.id)
+        .await
+        .map_err(|e| ToolError::ExecutionFailed(e.to_string()))?;
+
+    let message = match &todo {
+        Some(t) => format!("Completed todo #{}: {}", t.id, t.title),
+        None => format!("Todo #{} not found", input.id),
+    };
+
+    Ok(TodoCompleteOutput { todo, message })
+}
@@ -0,0 +1,384 @@
+// ABOUTME: Five-field cron expressions (minute hour day-of-month month day-of-week).
+// ABOUTME: Parses standard cron syntax and finds the next run time in a given timezone.
+
+use chrono::{DateTime, Datelike, LocalResult, NaiveDate, TimeZone, Utc};
+use chrono_tz::Tz;
+use std::fmt;
+use std::str::FromStr;
+
+/// How far ahead to look for the next run before deciding an expression
+/// never fires (e.g. "0 0 30 2 *"). Covers every leap-day/weekday combination.
+const MAX_SEARCH_DAYS: u32 = 366 * 8;
+
+const MONTH_NAMES: &[&str] = &[
+    "jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec",
+];
+const DAY_NAMES: &[&str] = &["sun", "mon", "tue", "wed", "thu", "fri", "sat"];
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("invalid cron expression '{expr}': {reason}")]
+pub struct CronError {
+    pub expr: String,
+    pub reason: String,
+}
+
+/// A parsed cron expression. Each field is a bitmask of the values it
+/// matches.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CronSchedule {
+    expr: String,
+    minutes: u64,
+    hours: u64,
+    days_of_month: u64,
+    months: u64,
+    days_of_week: u64,
+    /// Day-of-month field was `*`
+    any_day_of_month: bool,
+    /// Day-of-week field was `*`
+    any_day_of_week: bool,
+}
+
+impl CronSchedule {
+    /// Parse a five-field expression or one of the `@hourly`, `@daily`,
+    /// `@midnight`, `@weekly`, `@monthly`, `@yearly` and `@annually` macros.
+    ///
+    /// Fields accept `*`, single values, ranges (`1-5`), lists (`1,15`) and
+    /// steps (`*/15`, `0-30/10`, `5/20`). Months and weekdays also accept
+    /// three-letter names; Sunday is both 0 and 7.
+    pub fn parse(expr: &str) -> Result<Self, CronError> {
+        let err = |reason: String| CronError {
+            expr: expr.to_string(),
+            reason,
+        };
+
+        let trimmed = expr.trim();
+        let expanded = match trimmed.to_ascii_lowercase().as_str() {
+            "@yearly" | "@annually" => "0 0 1 1 *",
+            "@monthly" => "0 0 1 * *",
+            "@weekly" => "0 0 * * 0",
+            "@daily" | "@midnight" => "0 0 * * *",
+            "@hourly" => "0 * * * *",
+            other if other.starts_with('@') => {
+                return Err(err(format!("unknown macro '{}'", trimmed)));
+            }
+            _ => trimmed,
+        };
+
+        let fields: Vec<&str> = expanded.split_whitespace().collect();
+        let [minute, hour, day_of_month, month, day_of_week] = fields[..] else {
+            return Err(err(format!("expected 5 fields, found {}", fields.len())));
+        };
+
+        let field = |spec: &str, name: &str, min: u32, max: u32, names: &[&str]| {
+            parse_field(spec, min, max, names)
+                .map_err(|reason| err(format!("{}: {}", name, reason)))
+        };
+
+        let mut days_of_week = field(day_of_week, "day of week", 0, 7, DAY_NAMES)?;
+        // 7 is another name for Sunday
+        if days_of_week & (1 << 7) != 0 {
+            days_of_week = (days_of_week & !(1 << 7)) | 1;
+        }
+
+        Ok(Self {
+            expr: trimmed.to_string(),
+            minutes: field(minute, "minute", 0, 59, &[])?,
+            hours: field(hour, "hour", 0, 23, &[])?,
+            days_of_month: field(day_of_month, "day of month", 1, 31, &[])?,
+            months: field(month, "month", 1, 12, MONTH_NAMES)?,
+            days_of_week,
+            any_day_of_month: day_of_month.starts_with('*'),
+            any_day_of_week: day_of_week.starts_with('*'),
+        })
+    }
+
+    /// The expression as written
+    pub fn as_str(&self) -> &str {
+        &self.expr
+    }
+
+    /// Whether the schedule fires at some time on `date`. As in classic cron,
+    /// when both day fields are restricted a date matching either one counts.
+    fn matches_date(&self, date: NaiveDate) -> bool {
+        if !has(self.months, date.month()) {
+            return false;
+        }
+        let dom = has(self.days_of_month, date.day());
+        let dow = has(self.days_of_week, date.weekday().num_days_from_sunday());
+        match (self.any_day_of_month, self.any_day_of_week) {
+            (false, false) => dom || dow,
+            _ => dom && dow,
+        }
+    }
+
+    /// The first run strictly after `after`, with the fields read as wall-clock
+    /// time in `tz`.
+    ///
+    /// Local times skipped by a daylight-saving jump don't fire that day;
+    /// local times that happen twice when clocks go back fire once, at the
+    /// first occurrence. Returns None if the expression can never fire.
+    pub fn next_after(&self, after: DateTime<Utc>, tz: Tz) -> Option<DateTime<Utc>> {
+        let mut date = after.with_timezone(&tz).date_naive();
+        for _ in 0..MAX_SEARCH_DAYS {
+            if self.matches_date(date) {
+                for hour in values(self.hours, 23) {
+                    for minute in values(self.minutes, 59) {
+                        let naive = date.and_hms_opt(hour, minute, 0)?;
+                        let local = match tz.from_local_datetime(&naive) {
+                            LocalResult::Single(t) => t,
+                            LocalResult::Ambiguous(earliest, _) => earliest,
+                            LocalResult::None => continue,
+                        };
+                        let candidate = local.with_timezone(&Utc);
+                        if candidate > after {
+                            return Some(candidate);
+                        }
+                    }
+                }
+            }
+            date = date.succ_opt()?;
+        }
+        None
+    }
+}
+
+impl FromStr for CronSchedule {
+    type Err = CronError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
+}
+
+impl fmt::Display for CronSchedule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.expr)
+    }
+}
+
+fn has(mask: u64, value: u32) -> bool {
+    mask & (1 << value) != 0
+}
+
+fn values(mask: u64, max: u32) -> impl Iterator<Item = u32> {
+    (0..=max).filter(move |v| has(mask, *v))
+}
+
+/// Parse one field into a bitmask of the values it matches.
+fn parse_field(spec: &str, min: u32, max: u32, names: &[&str]) -> Result<u64, String> {
+    let value = |s: &str| -> Result<u32, String> {
+        if let Some(index) = names.iter().position(|n| n.eq_ignore_ascii_case(s)) {
+            // Month names start at 1, weekday names at 0
+            return Ok(index as u32 + min.min(1));
+        }
+        let v: u32 = s.parse().map_err(|_| format!("'{}' is not a number", s))?;
+        if v < min || v > max {
+            return Err(format!("{} is outside {}-{}", v, min, max));
+        }
+        Ok(v)
+    };
+
+    let mut mask = 0u64;
+    for part in spec.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => {
+                let step: u32 = step
+                    .parse()
+                    .map_err(|_| format!("'{}' is not a valid step", step))?;
+                if step == 0 {
+                    return Err("step must be at least 1".to_string());
+                }
+                (range, Some(step))
+            }
+            None => (part, None),
+        };
+
+        let (lo, hi) = if range == "*" {
+            (min, max)
+        } else if let Some((lo, hi)) = range.split_once('-') {
+            (value(lo)?, value(hi)?)
+        } else if range.is_empty() {
+            return Err(format!("empty value in '{}'", spec));
+        } else {
+            let v = value(range)?;
+            // "5/20" means every 20 starting at 5
+            if step.is_some() {
+                (v, max)
+            } else {
+                (v, v)
+            }
+        };
+        if lo > hi {
+            return Err(format!("range {}-{} is backwards", lo, hi));
+        }
+
+        for v in (lo..=hi).step_by(step.unwrap_or(1) as usize) {
+            mask |= 1 << v;
+        }
+    }
+    Ok(mask)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn utc(s: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(s).unwrap().with_timezone(&Utc)
+    }
+
+    fn next(expr: &str, after: &str, tz: Tz) -> String {
+        CronSchedule::parse(expr)
+            .unwrap()
+            .next_after(utc(after), tz)
+            .unwrap()
+            .to_rfc3339()
+    }
+
+    #[test]
+    fn test_parse_fields() {
+        let every_15 = CronSchedule::parse("*/15 9-17 * * mon-fri").unwrap();
+        assert_eq!(
+            values(every_15.minutes, 59).collect::<Vec<_>>(),
+            [0, 15, 30, 45]
+        );
+        assert_eq!(values(every_15.hours, 23).count(), 9);
+        assert_eq!(
+            values(every_15.days_of_week, 6).collect::<Vec<_>>(),
+            [1, 2, 3, 4, 5]
+        );
+
+        let listed = CronSchedule::parse("0,30 8 1,15 JAN,jul *").unwrap();
+        assert_eq!(values(listed.minutes, 59).collect::<Vec<_>>(), [0, 30]);
+        assert_eq!(
+            values(listed.days_of_month, 31).collect::<Vec<_>>(),
+            [1, 15]
+        );
+        assert_eq!(values(listed.months, 12).collect::<Vec<_>>(), [1, 7]);
+
+        let offset_step = CronSchedule::parse("5/20 0-10/5 * * *").unwrap();
+        assert_eq!(
+            values(offset_step.minutes, 59).collect::<Vec<_>>(),
+            [5, 25, 45]
+        );
+        assert_eq!(
+            values(offset_step.hours, 23).collect::<Vec<_>>(),
+            [0, 5, 10]
+        );
+    }
+
+    #[test]
+    fn test_sunday_is_zero_and_seven() {
+        assert_eq!(
+            CronSchedule::parse("0 0 * * 7").unwrap().days_of_week,
+            CronSchedule::parse("0 0 * * sun").unwrap().days_of_week
+        );
+    }
+
+    #[test]
+    fn test_macros() {
+        assert_eq!(
+            CronSchedule::parse("@daily")
+                .unwrap()
+                .next_after(utc("2026-03-04T10:00:00Z"), Tz::UTC),
+            Some(utc("2026-03-05T00:00:00Z"))
+        );
+        assert_eq!(
+            CronSchedule::parse("@hourly").unwrap().minutes,
+            CronSchedule::parse("0 * * * *").unwrap().minutes
+        );
+        assert_eq!(CronSchedule::parse("@weekly").unwrap().as_str(), "@weekly");
+    }
+
+    #[test]
+    fn test_invalid_expressions() {
+        for (expr, reason) in [
+            ("* * * *", "expected 5 fields, found 4"),
+            ("60 * * * *", "minute: 60 is outside 0-59"),
+            ("* 24 * * *", "hour: 24 is outside 0-23"),
+            ("* * 0 * *", "day of month: 0 is outside 1-31"),
+            ("* * * foo *", "month: 'foo' is not a number"),
+            ("*/0 * * * *", "minute: step must be at least 1"),
+            ("10-5 * * * *", "minute: range 10-5 is backwards"),
+            ("1,,2 * * * *", "minute: empty value in '1,,2'"),
+            ("@reboot", "unknown macro '@reboot'"),
+        ] {
+            let err = CronSchedule::parse(expr).unwrap_err();
+            assert_eq!(err.reason, reason, "{expr}");
+        }
+    }
+
+    #[test]
+    fn test_next_after_is_strictly_later() {
+        assert_eq!(
+            next("0 9 * * *", "2026-03-04T09:00:00Z", Tz::UTC),
+            "2026-03-05T09:00:00+00:00"
+        );
+        assert_eq!(
+            next("0 9 * * *", "2026-03-04T08:59:59Z", Tz::UTC),
+            "2026-03-04T09:00:00+00:00"
+        );
+        assert_eq!(
+            next("*/15 * * * *", "2026-12-31T23:50:00Z", Tz::UTC),
+            "2027-01-01T00:00:00+00:00"
+        );
+    }
+
+    #[test]
+    fn test_day_fields_match_either_when_both_restricted() {
+        // The 13th, or any Friday: 2026-03-06 is a Friday
+        assert_eq!(
+            next("0 0 13 * fri", "2026-03-01T00:00:00Z", Tz::UTC),
+            "2026-03-06T00:00:00+00:00"
+        );
+        // Only the weekday is restricted, so the day of month doesn't widen it
+        assert_eq!(
+            next("0 0 * * fri", "2026-03-07T00:00:00Z", Tz::UTC),
+            "2026-03-13T00:00:00+00:00"
+        );
+    }
+
+    #[test]
+    fn test_leap_day_and_impossible_dates() {
+        assert_eq!(
+            next("0 0 29 2 *", "2026-03-01T00:00:00Z", Tz::UTC),
+            "2028-02-29T00:00:00+00:00"
+        );
+        assert_eq!(
+            CronSchedule::parse("0 0 30 2 *")
+                .unwrap()
+                .next_after(utc("2026-01-01T00:00:00Z"), Tz::UTC),
+            None
+        );
+    }
+
+    #[test]
+    fn test_fields_are_wall_clock_time_in_timezone() {
+        // 9am in New York is 14:00 UTC in winter and 13:00 UTC in summer
+        let tz: Tz = "America/New_York".parse().unwrap();
+        assert_eq!(
+            next("0 9 * * *", "2026-01-15T00:00:00Z", tz),
+            "2026-01-15T14:00:00+00:00"
+        );
+        assert_eq!(
+            next("0 9 * * *", "2026-07-15T00:00:00Z", tz),
+            "2026-07-15T13:00:00+00:00"
+        );
+    }
+
+    #[test]
+    fn test_daylight_saving_gaps_and_overlaps() {
+        let tz: Tz = "America/New_York".parse().unwrap();
+
+        // 2:30am doesn't exist on 2026-03-08, so that day is skipped
+        assert_eq!(
+            next("30 2 * * *", "2026-03-08T00:00:00Z", tz),
+            "2026-03-09T06:30:00+00:00"
+        );
+
+        // 1:30am happens twice on 2026-11-01; only the first one fires
+        let first = next("30 1 * * *", "2026-11-01T00:00:00Z", tz);
+        assert_eq!(first, "2026-11-01T05:30:00+00:00");
+        assert_eq!(next("30 1 * * *", &first, tz), "2026-11-02T06:30:00+00:00");
+    }
+}
@@ -0,0 +1,218 @@
+// ABOUTME: SQLite database layer for scheduler-pack.
+// ABOUTME: Stores scheduled jobs with their next and last run times.
+
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::sqlite::{SqliteConnectOptions, SqliteJournalMode, SqlitePoolOptions, SqliteSynchronous};
+use sqlx::{FromRow, SqlitePool};
+use std::path::Path;
+use std::time::Duration;
+
+/// How long a write waits on a locked database before failing.
+pub const DEFAULT_BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Fixed-width RFC 3339 timestamp so stored values compare correctly as text.
+pub fn timestamp(t: DateTime<Utc>) -> String {
+    t.to_rfc3339_opts(SecondsFormat::Secs, true)
+}
+
+fn parse_timestamp(s: &str) -> Result<DateTime<Utc>> {
+    Ok(DateTime::parse_from_rfc3339(s)
+        .map_err(|e| anyhow!("bad timestamp '{}': {}", s, e))?
+        .with_timezone(&Utc))
+}
+
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct Job {
+    pub id: i64,
+    pub cron: String,
+    /// IANA timezone the cron fields are read in
+    pub timezone: String,
+    pub agent_id: String,
+    pub prompt: String,
+    /// Agent that created the schedule, when known
+    pub created_by: Option<String>,
+    pub created_at: String,
+    pub next_run_at: String,
+    pub last_run_at: Option<String>,
+    /// Why the last run couldn't be delivered, cleared by the next success
+    pub last_error: Option<String>,
+}
+
+impl Job {
+    pub fn next_run(&self) -> Result<DateTime<Utc>> {
+        parse_timestamp(&self.next_run_at)
+    }
+}
+
+/// Fields of a job the caller chooses.
+#[derive(Debug, Clone)]
+pub struct NewJob<'a> {
+    pub cron: &'a str,
+    pub timezone: &'a str,
+    pub agent_id: &'a str,
+    pub prompt: &'a str,
+    pub created_by: Option<&'a str>,
+    pub next_run_at: DateTime<Utc>,
+}
+
+pub struct Database {
+    pool: SqlitePool,
+}
+
+impl Database {
+    pub async fn new(path: &Path) -> Result<Self> {
+        Self::open(path, DEFAULT_BUSY_TIMEOUT).await
+    }
+
+    /// Open the database, waiting up to `busy_timeout` for a lock. Each
+    /// connection runs in WAL mode with `synchronous = NORMAL`.
+    pub async fn open(path: &Path, busy_timeout: Duration) -> Result<Self> {
+        // Ensure parent directory exists
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        let options = SqliteConnectOptions::new()
+            .filename(path)
+            .create_if_missing(true)
+            .journal_mode(SqliteJournalMode::Wal)
+            .synchronous(SqliteSynchronous::Normal)
+            .busy_timeout(busy_timeout);
+        let pool = SqlitePoolOptions::new()
+            .max_connections(5)
+            .connect_with(options)
+            .await?;
+
+        let db = Self { pool };
+        db.run_migrations().await?;
+        Ok(db)
+    }
+
+    /// Run a trivial query to check the database is reachable.
+    pub async fn ping(&self) -> Result<()> {
+        sqlx::query("SELECT 1").execute(&self.pool).await?;
+        Ok(())
+    }
+
+    async fn run_migrations(&self) -> Result<()> {
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS jobs (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                cron TEXT NOT NULL,
+                timezone TEXT NOT NULL,
+                agent_id TEXT NOT NULL,
+                prompt TEXT NOT NULL,
+                created_by TEXT,
+                created_at TEXT NOT NULL,
+                next_run_at TEXT NOT NULL,
+                last_run_at TEXT,
+                last_error TEXT
+            )
+            "#,
+        )
+        .execute(&self.pool)
+        .await?;
+
+        sqlx::query("CREATE INDEX IF NOT EXISTS idx_jobs_next_run ON jobs(next_run_at)")
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn create_job(&self, job: &NewJob<'_>) -> Result<Job> {
+        let result = sqlx::query_as::<_, Job>(
+            r#"
+            INSERT INTO jobs (cron, timezone, agent_id, prompt, created_by, created_at, next_run_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?)
+            RETURNING *
+            "#,
+        )
+        .bind(job.cron)
+        .bind(job.timezone)
+        .bind(job.agent_id)
+        .bind(job.prompt)
+        .bind(job.created_by)
+        .bind(timestamp(Utc::now()))
+        .bind(timestamp(job.next_run_at))
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(result)
+    }
+
+    /// All jobs, or only those sending to `agent_id`, soonest first.
+    pub async fn list_jobs(&self, agent_id: Option<&str>) -> Result<Vec<Job>> {
+        let jobs = match agent_id {
+            Some(agent_id) => {
+                sqlx::query_as::<_, Job>(
+                    "SELECT * FROM jobs WHERE agent_id = ? ORDER BY next_run_at, id",
+                )
+                .bind(agent_id)
+                .fetch_all(&self.pool)
+                .await?
+            }
+            None => {
+                sqlx::query_as::<_, Job>("SELECT * FROM jobs ORDER BY next_run_at, id")
+                    .fetch_all(&self.pool)
+                    .await?
+            }
+        };
+        Ok(jobs)
+    }
+
+    pub async fn delete_job(&self, id: i64) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM jobs WHERE id = ?")
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Jobs whose next run is at or before `now`.
+    pub async fn due_jobs(&self, now: DateTime<Utc>) -> Result<Vec<Job>> {
+        let jobs = sqlx::query_as::<_, Job>(
+            "SELECT * FROM jobs WHERE next_run_at <= ? ORDER BY next_run_at, id",
+        )
+        .bind(timestamp(now))
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(jobs)
+    }
+
+    /// When the soonest job is due, if there are any jobs.
+    pub async fn next_due_at(&self) -> Result<Option<DateTime<Utc>>> {
+        let next: Option<String> = sqlx::query_scalar("SELECT MIN(next_run_at) FROM jobs")
+            .fetch_one(&self.pool)
+            .await?;
+        next.as_deref().map(parse_timestamp).transpose()
+    }
+
+    /// Record a pass over a due job: when it last ran (if it did), when it
+    /// runs next, and why delivery failed (if it did).
+    pub async fn record_run(
+        &self,
+        id: i64,
+        last_run_at: Option<DateTime<Utc>>,
+        next_run_at: DateTime<Utc>,
+        last_error: Option<&str>,
+    ) -> Result<()> {
+        sqlx::query(
+            r#"
+            UPDATE jobs
+            SET last_run_at = COALESCE(?, last_run_at), next_run_at = ?, last_error = ?
+            WHERE id = ?
+            "#,
+        )
+        .bind(last_run_at.map(timestamp))
+        .bind(timestamp(next_run_at))
+        .bind(last_error)
+        .bind(id)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+}
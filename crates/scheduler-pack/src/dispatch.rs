@@ -0,0 +1,121 @@
+// ABOUTME: Delivers scheduled prompts to agents through the gateway's ClientService.
+// ABOUTME: Each run gets an idempotency key so a retried send isn't delivered twice.
+
+use crate::db::Job;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use coven_proto::client::ClientServiceClient;
+use coven_proto::ClientSendMessageRequest;
+use tonic::service::interceptor::InterceptedService;
+use tonic::transport::Channel;
+use tonic::{Code, Request, Status};
+use tracing::info;
+
+/// Name scheduled prompts are attributed to in the agent's conversation.
+pub const SENDER_NAME: &str = "scheduler";
+
+#[derive(Debug, thiserror::Error)]
+pub enum DispatchError {
+    /// The gateway couldn't be reached; the run is retried on the next pass
+    #[error("gateway unreachable: {0}")]
+    Unreachable(String),
+    /// The agent isn't connected and the gateway couldn't queue the prompt
+    /// for it; the run stays due and is retried shortly
+    #[error("agent offline: {0}")]
+    AgentOffline(String),
+    /// The gateway refused the message (e.g. blocked by policy); the run is dropped
+    #[error("gateway rejected the prompt: {0}")]
+    Rejected(String),
+}
+
+impl From<Status> for DispatchError {
+    fn from(status: Status) -> Self {
+        match status.code() {
+            Code::Unavailable | Code::DeadlineExceeded | Code::Unknown => {
+                Self::Unreachable(status.message().to_string())
+            }
+            // The gateway's answer for an agent that isn't connected
+            Code::NotFound => Self::AgentOffline(status.message().to_string()),
+            _ => Self::Rejected(status.message().to_string()),
+        }
+    }
+}
+
+/// Sends a job's prompt to its agent.
+#[async_trait]
+pub trait Dispatcher: Send + Sync {
+    /// Deliver one run of `job`, scheduled for `run_at`.
+    async fn send(&self, job: &Job, run_at: DateTime<Utc>) -> Result<(), DispatchError>;
+}
+
+/// Idempotency key for one run of a job, stable across retries.
+pub fn idempotency_key(job: &Job, run_at: DateTime<Utc>) -> String {
+    format!("schedule-{}-{}", job.id, run_at.timestamp())
+}
+
+/// Interceptor that adds Bearer token authentication to outgoing requests.
+#[derive(Clone)]
+struct AuthInterceptor {
+    token: Option<String>,
+}
+
+impl tonic::service::Interceptor for AuthInterceptor {
+    fn call(&mut self, mut req: Request<()>) -> Result<Request<()>, Status> {
+        if let Some(ref token) = self.token {
+            let auth_value = format!("Bearer {}", token)
+                .parse()
+                .map_err(|_| Status::internal("invalid token format"))?;
+            req.metadata_mut().insert("authorization", auth_value);
+        }
+        Ok(req)
+    }
+}
+
+/// Dispatcher that sends prompts as client messages to the gateway, the same
+/// way a chat bridge relays a user's message.
+pub struct GatewayDispatcher {
+    client: ClientServiceClient<InterceptedService<Channel, AuthInterceptor>>,
+}
+
+impl GatewayDispatcher {
+    /// Create a dispatcher for the gateway at `url`. The connection is made
+    /// on first use, so the gateway needn't be up yet.
+    pub fn new(url: &str, token: Option<String>) -> anyhow::Result<Self> {
+        let channel = Channel::from_shared(url.to_string())
+            .map_err(|e| anyhow::anyhow!("invalid gateway URL: {}", e))?
+            .connect_lazy();
+        Ok(Self {
+            client: ClientServiceClient::with_interceptor(channel, AuthInterceptor { token }),
+        })
+    }
+}
+
+#[async_trait]
+impl Dispatcher for GatewayDispatcher {
+    async fn send(&self, job: &Job, run_at: DateTime<Utc>) -> Result<(), DispatchError> {
+        let request = ClientSendMessageRequest {
+            conversation_key: job.agent_id.clone(),
+            content: job.prompt.clone(),
+            attachments: vec![],
+            idempotency_key: idempotency_key(job, run_at),
+            sender_display: Some(SENDER_NAME.to_string()),
+            sender_platform_id: Some(format!("schedule-{}", job.id)),
+            sender_platform: Some(SENDER_NAME.to_string()),
+            model: None,
+            max_tokens: None,
+            reply_to_message_id: None,
+            channel_frontend: None,
+            channel_id: None,
+            channel_name: None,
+        };
+
+        let response = self.client.clone().send_message(request).await?;
+        info!(
+            job_id = job.id,
+            agent_id = %job.agent_id,
+            status = %response.get_ref().status,
+            "Sent scheduled prompt"
+        );
+        Ok(())
+    }
+}
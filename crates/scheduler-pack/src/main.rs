@@ -0,0 +1,263 @@
+// ABOUTME: Scheduler pack that sends agents prompts on cron schedules.
+// ABOUTME: Jobs persist in SQLite; a background loop delivers due prompts through the gateway.
+
+mod cron;
+mod db;
+mod dispatch;
+mod runner;
+mod schedule;
+
+use anyhow::{anyhow, Result};
+use chrono_tz::Tz;
+use coven_pack::{HealthStatus, ManifestBuilder, PackClient, RetryPolicy, TypedHandler};
+use coven_ssh::load_or_generate_key_with_passphrase;
+use db::Database;
+use dispatch::GatewayDispatcher;
+use runner::{CatchUp, Scheduler};
+use schedule::{ScheduleCreateInput, ScheduleDeleteInput, ScheduleListInput};
+use std::path::PathBuf;
+use std::sync::Arc;
+use tracing::info;
+
+const PACK_NAME: &str = "scheduler-pack";
+
+/// Get XDG-style data directory (~/.local/share/coven).
+fn xdg_data_dir() -> Option<PathBuf> {
+    std::env::var_os("XDG_DATA_HOME")
+        .map(PathBuf::from)
+        .or_else(|| dirs::home_dir().map(|h| h.join(".local").join("share")))
+        .map(|p| p.join("coven"))
+}
+
+/// Healthy while the database answers `SELECT 1`.
+async fn health(scheduler: Arc<Scheduler>) -> HealthStatus {
+    match scheduler.db.ping().await {
+        Ok(()) => HealthStatus::Healthy,
+        Err(e) => HealthStatus::unhealthy(format!("database unavailable: {}", e)),
+    }
+}
+
+fn build_handler(scheduler: Arc<Scheduler>) -> TypedHandler<Scheduler> {
+    TypedHandler::with_shared_state(scheduler)
+        .with_health_check(health)
+        .tool_with_context("schedule_create", schedule::create)
+        .tool("schedule_list", schedule::list)
+        .tool("schedule_delete", schedule::delete)
+}
+
+fn build_manifest() -> coven_proto::PackManifest {
+    ManifestBuilder::new(PACK_NAME, "0.1.0")
+        .typed_tool::<ScheduleCreateInput>(
+            "schedule_create",
+            "Schedule a prompt to be sent to an agent on a cron schedule",
+            &[],
+        )
+        .typed_tool::<ScheduleListInput>(
+            "schedule_list",
+            "List scheduled prompts with their next run times",
+            &[],
+        )
+        .typed_tool::<ScheduleDeleteInput>("schedule_delete", "Delete a scheduled prompt", &[])
+        .build()
+}
+
+/// Read an optional setting, treating an empty value as unset.
+fn env_setting(name: &str) -> Option<String> {
+    std::env::var(name).ok().filter(|v| !v.trim().is_empty())
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    coven_log::init();
+
+    let config = coven_pack::PackConfig::load(PACK_NAME).map_err(|e| anyhow!("{}", e))?;
+
+    // Use SCHEDULER_DB_PATH env var if set, otherwise use XDG data path
+    let db_path = std::env::var("SCHEDULER_DB_PATH")
+        .map(PathBuf::from)
+        .or_else(|_| {
+            xdg_data_dir()
+                .map(|d| d.join("packs").join(PACK_NAME).join("scheduler.db"))
+                .ok_or_else(|| anyhow!("Could not determine data directory"))
+        })?;
+
+    // SCHEDULER_TIMEZONE: timezone for schedules created without one (default UTC)
+    let default_timezone = match env_setting("SCHEDULER_TIMEZONE") {
+        Some(name) => name
+            .trim()
+            .parse::<Tz>()
+            .map_err(|_| anyhow!("unknown SCHEDULER_TIMEZONE '{}'", name))?,
+        None => Tz::UTC,
+    };
+
+    // SCHEDULER_CATCH_UP: what to do with runs missed while the pack was down
+    let catch_up = match env_setting("SCHEDULER_CATCH_UP") {
+        Some(policy) => policy.parse::<CatchUp>()?,
+        None => CatchUp::default(),
+    };
+
+    info!("Starting {}", PACK_NAME);
+    info!("Gateway: {}", config.gateway_url);
+    info!("SSH key: {}", config.ssh_key_path.display());
+    info!("Database: {}", db_path.display());
+    info!("Default timezone: {}", default_timezone);
+    info!("Catch-up policy: {:?}", catch_up);
+
+    // Load existing key or generate one
+    let _private_key = load_or_generate_key_with_passphrase(
+        &config.ssh_key_path,
+        config.ssh_key_passphrase.as_ref(),
+    )?;
+
+    let db = Database::new(&db_path).await?;
+    let scheduler = Arc::new(Scheduler::new(db, default_timezone, catch_up));
+
+    // Prompts go out as client messages, so a gateway that requires auth
+    // needs a client token in SCHEDULER_GATEWAY_TOKEN
+    let dispatcher =
+        GatewayDispatcher::new(&config.gateway_url, env_setting("SCHEDULER_GATEWAY_TOKEN"))?;
+    {
+        let scheduler = Arc::clone(&scheduler);
+        tokio::spawn(async move { scheduler.run(&dispatcher).await });
+    }
+
+    let handler = build_handler(scheduler);
+    let manifest = build_manifest();
+
+    info!("Registering {} tools", manifest.tools.len());
+
+    let client = PackClient::connect_with_config_retry(&config, RetryPolicy::default())
+        .await?
+        .on_connection_state(|state| info!(?state, "Gateway connection state changed"));
+    client.run(manifest, handler).await?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use coven_pack::{ExecutionContext, ToolError, ToolHandler};
+    use serde_json::Value;
+
+    async fn handler(dir: &tempfile::TempDir) -> TypedHandler<Scheduler> {
+        let db = Database::new(&dir.path().join("scheduler.db"))
+            .await
+            .unwrap();
+        let tz: Tz = "Europe/Berlin".parse().unwrap();
+        build_handler(Arc::new(Scheduler::new(db, tz, CatchUp::Latest)))
+    }
+
+    async fn call(
+        handler: &TypedHandler<Scheduler>,
+        agent_id: Option<&str>,
+        tool: &str,
+        input: &str,
+    ) -> Result<Value, ToolError> {
+        let mut ctx = ExecutionContext::default();
+        ctx.agent_id = agent_id.map(str::to_string);
+        let output = handler.execute_with_context(tool, input, ctx).await?;
+        Ok(serde_json::from_str(&output).unwrap())
+    }
+
+    #[test]
+    fn test_manifest_lists_every_tool() {
+        let manifest = build_manifest();
+        let names: Vec<&str> = manifest.tools.iter().map(|t| t.name.as_str()).collect();
+        assert_eq!(
+            names,
+            ["schedule_create", "schedule_list", "schedule_delete"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_create_list_delete() {
+        let dir = tempfile::tempdir().unwrap();
+        let handler = handler(&dir).await;
+
+        // Without an agent_id the schedule targets the caller, in the pack's timezone
+        let created = call(
+            &handler,
+            Some("agent-a"),
+            "schedule_create",
+            r#"{"cron": "0 8 * * mon-fri", "prompt": "Plan my day"}"#,
+        )
+        .await
+        .unwrap();
+        let schedule = &created["schedule"];
+        assert_eq!(schedule["agent_id"], "agent-a");
+        assert_eq!(schedule["created_by"], "agent-a");
+        assert_eq!(schedule["timezone"], "Europe/Berlin");
+        let id = schedule["id"].as_i64().unwrap();
+
+        call(
+            &handler,
+            Some("agent-a"),
+            "schedule_create",
+            r#"{"cron": "@daily", "agent_id": "agent-b", "prompt": "Tidy up", "timezone": "Asia/Tokyo"}"#,
+        )
+        .await
+        .unwrap();
+
+        let all = call(&handler, None, "schedule_list", "{}").await.unwrap();
+        assert_eq!(all["count"], 2);
+        let for_b = call(
+            &handler,
+            None,
+            "schedule_list",
+            r#"{"agent_id": "agent-b"}"#,
+        )
+        .await
+        .unwrap();
+        assert_eq!(for_b["count"], 1);
+        assert_eq!(for_b["schedules"][0]["timezone"], "Asia/Tokyo");
+
+        let deleted = call(
+            &handler,
+            None,
+            "schedule_delete",
+            &format!(r#"{{"id": {}}}"#, id),
+        )
+        .await
+        .unwrap();
+        assert_eq!(deleted["deleted"], true);
+        let again = call(
+            &handler,
+            None,
+            "schedule_delete",
+            &format!(r#"{{"id": {}}}"#, id),
+        )
+        .await
+        .unwrap();
+        assert_eq!(again["deleted"], false);
+    }
+
+    #[tokio::test]
+    async fn test_create_rejects_bad_input() {
+        let dir = tempfile::tempdir().unwrap();
+        let handler = handler(&dir).await;
+
+        for input in [
+            r#"{"cron": "every morning", "prompt": "hi"}"#,
+            r#"{"cron": "0 0 30 2 *", "prompt": "hi"}"#,
+            r#"{"cron": "@daily", "prompt": "hi", "timezone": "Mars/Olympus"}"#,
+            r#"{"cron": "@daily", "prompt": "  "}"#,
+        ] {
+            let err = call(&handler, Some("agent-a"), "schedule_create", input)
+                .await
+                .unwrap_err();
+            assert!(matches!(err, ToolError::InvalidInput(_)), "{input}");
+        }
+
+        // No agent_id and no known caller
+        let err = call(
+            &handler,
+            None,
+            "schedule_create",
+            r#"{"cron": "@daily", "prompt": "hi"}"#,
+        )
+        .await
+        .unwrap_err();
+        assert!(matches!(err, ToolError::InvalidInput(_)));
+    }
+}
@@ -0,0 +1,484 @@
+// ABOUTME: Background loop that fires due jobs and applies the catch-up policy for missed runs.
+// ABOUTME: Holds the Scheduler state shared with the tool handlers.
+
+use crate::cron::CronSchedule;
+use crate::db::{Database, Job};
+use crate::dispatch::{DispatchError, Dispatcher};
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Utc};
+use chrono_tz::Tz;
+use std::collections::VecDeque;
+use std::str::FromStr;
+use std::time::Duration;
+use tokio::sync::Notify;
+use tracing::{debug, info, warn};
+
+/// Longest the loop sleeps without checking for due jobs.
+const MAX_SLEEP: Duration = Duration::from_secs(60);
+
+/// Pause before retrying after the gateway was unreachable.
+const RETRY_DELAY: Duration = Duration::from_secs(30);
+
+/// A run this late or less counts as on time rather than missed.
+pub const ON_TIME_GRACE: chrono::Duration = chrono::Duration::seconds(60);
+
+/// Most missed runs of one job replayed by the `all` policy.
+pub const MAX_CATCH_UP_RUNS: usize = 24;
+
+/// What to do with runs that were missed while the pack was down.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CatchUp {
+    /// Drop missed runs; only runs that are due on time fire.
+    Skip,
+    /// Fire the most recent missed run once.
+    #[default]
+    Latest,
+    /// Fire every missed run, up to `MAX_CATCH_UP_RUNS` of the most recent.
+    All,
+}
+
+impl FromStr for CatchUp {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim() {
+            "skip" => Ok(Self::Skip),
+            "latest" => Ok(Self::Latest),
+            "all" => Ok(Self::All),
+            other => Err(anyhow!(
+                "unknown catch-up policy '{}' (expected 'skip', 'latest' or 'all')",
+                other
+            )),
+        }
+    }
+}
+
+/// Runs of a job to fire at `now`, oldest first, given its next scheduled
+/// run and the catch-up policy.
+pub fn runs_to_fire(
+    schedule: &CronSchedule,
+    tz: Tz,
+    next_run_at: DateTime<Utc>,
+    now: DateTime<Utc>,
+    policy: CatchUp,
+) -> Vec<DateTime<Utc>> {
+    // Every run from the stored next run up to now, keeping only the most
+    // recent ones the policy could fire
+    let keep = match policy {
+        CatchUp::All => MAX_CATCH_UP_RUNS,
+        CatchUp::Skip | CatchUp::Latest => 1,
+    };
+    let mut due = VecDeque::with_capacity(keep);
+    let mut run = Some(next_run_at);
+    while let Some(at) = run.filter(|at| *at <= now) {
+        if due.len() == keep {
+            due.pop_front();
+        }
+        due.push_back(at);
+        run = schedule.next_after(at, tz);
+    }
+
+    match policy {
+        CatchUp::Skip => due
+            .into_iter()
+            .filter(|at| now - *at <= ON_TIME_GRACE)
+            .collect(),
+        CatchUp::Latest | CatchUp::All => due.into(),
+    }
+}
+
+/// Parse a job's stored cron expression and timezone.
+pub fn job_schedule(job: &Job) -> Result<(CronSchedule, Tz)> {
+    let schedule = CronSchedule::parse(&job.cron)?;
+    let tz = job
+        .timezone
+        .parse::<Tz>()
+        .map_err(|_| anyhow!("unknown timezone '{}'", job.timezone))?;
+    Ok((schedule, tz))
+}
+
+/// Scheduler state shared by the tool handlers and the background loop.
+pub struct Scheduler {
+    pub db: Database,
+    /// Timezone for schedules created without one
+    pub default_timezone: Tz,
+    pub catch_up: CatchUp,
+    /// Woken when a job is created so the loop can shorten its sleep
+    wake: Notify,
+}
+
+impl Scheduler {
+    pub fn new(db: Database, default_timezone: Tz, catch_up: CatchUp) -> Self {
+        Self {
+            db,
+            default_timezone,
+            catch_up,
+            wake: Notify::new(),
+        }
+    }
+
+    /// Tell the loop the schedule changed.
+    pub fn wake(&self) {
+        self.wake.notify_one();
+    }
+
+    /// Fire every job due at `now`. Returns false if a run must be retried
+    /// soon: the gateway was unreachable, leaving the remaining runs for the
+    /// next pass, or a job's agent was offline, leaving that job due.
+    pub async fn tick(&self, dispatcher: &dyn Dispatcher, now: DateTime<Utc>) -> Result<bool> {
+        let mut all_sent = true;
+        'jobs: for job in self.db.due_jobs(now).await? {
+            let (schedule, tz) = match job_schedule(&job) {
+                Ok(parsed) => parsed,
+                Err(e) => {
+                    warn!(job_id = job.id, error = %e, "Deleting job with invalid schedule");
+                    self.db.delete_job(job.id).await?;
+                    continue;
+                }
+            };
+
+            let runs = runs_to_fire(&schedule, tz, job.next_run()?, now, self.catch_up);
+            let skipped_all = runs.is_empty();
+            let mut last_run = None;
+            let mut last_error = None;
+            for run_at in runs {
+                match dispatcher.send(&job, run_at).await {
+                    Ok(()) => {
+                        last_run = Some(run_at);
+                        last_error = None;
+                    }
+                    Err(DispatchError::Unreachable(e)) => {
+                        // Keep the job due so the run is retried (and caught
+                        // up per policy) once the gateway is back
+                        warn!(job_id = job.id, error = %e, "Gateway unreachable, will retry");
+                        if last_run.is_some() {
+                            self.db.record_run(job.id, last_run, run_at, None).await?;
+                        }
+                        return Ok(false);
+                    }
+                    Err(e @ DispatchError::AgentOffline(_)) => {
+                        // Keep this job due for a retry once the agent is
+                        // back; other agents' jobs still fire
+                        warn!(
+                            job_id = job.id,
+                            agent_id = %job.agent_id,
+                            error = %e,
+                            "Agent offline, will retry"
+                        );
+                        self.db
+                            .record_run(job.id, last_run, run_at, Some(&e.to_string()))
+                            .await?;
+                        all_sent = false;
+                        continue 'jobs;
+                    }
+                    Err(e @ DispatchError::Rejected(_)) => {
+                        warn!(
+                            job_id = job.id,
+                            agent_id = %job.agent_id,
+                            error = %e,
+                            "Scheduled prompt not delivered"
+                        );
+                        last_error = Some(e.to_string());
+                    }
+                }
+            }
+            if skipped_all {
+                info!(job_id = job.id, "Skipping missed runs");
+            }
+
+            let Some(next_run) = schedule.next_after(now, tz) else {
+                info!(job_id = job.id, "Schedule never fires again, deleting job");
+                self.db.delete_job(job.id).await?;
+                continue;
+            };
+            self.db
+                .record_run(job.id, last_run, next_run, last_error.as_deref())
+                .await?;
+        }
+        Ok(all_sent)
+    }
+
+    /// Fire jobs as they come due, forever.
+    pub async fn run(&self, dispatcher: &dyn Dispatcher) {
+        loop {
+            let sleep = match self.tick(dispatcher, Utc::now()).await {
+                Ok(true) => self.until_next_due().await,
+                Ok(false) => RETRY_DELAY,
+                Err(e) => {
+                    warn!(error = %e, "Scheduler pass failed");
+                    RETRY_DELAY
+                }
+            };
+            debug!(?sleep, "Scheduler sleeping");
+            tokio::select! {
+                _ = tokio::time::sleep(sleep) => {}
+                _ = self.wake.notified() => {}
+            }
+        }
+    }
+
+    /// Time until the soonest job is due, capped at `MAX_SLEEP`.
+    async fn until_next_due(&self) -> Duration {
+        match self.db.next_due_at().await {
+            Ok(Some(at)) => (at - Utc::now())
+                .to_std()
+                .unwrap_or(Duration::ZERO)
+                .min(MAX_SLEEP),
+            Ok(None) => MAX_SLEEP,
+            Err(e) => {
+                warn!(error = %e, "Couldn't read next due job");
+                RETRY_DELAY
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::NewJob;
+    use async_trait::async_trait;
+    use std::sync::Mutex;
+
+    fn utc(s: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(s).unwrap().with_timezone(&Utc)
+    }
+
+    fn hourly_runs(next_run_at: &str, now: &str, policy: CatchUp) -> Vec<String> {
+        let schedule = CronSchedule::parse("0 * * * *").unwrap();
+        runs_to_fire(&schedule, Tz::UTC, utc(next_run_at), utc(now), policy)
+            .into_iter()
+            .map(|at| at.format("%H:%M").to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_catch_up_policy_parse() {
+        assert_eq!("skip".parse::<CatchUp>().unwrap(), CatchUp::Skip);
+        assert_eq!(" latest ".parse::<CatchUp>().unwrap(), CatchUp::Latest);
+        assert_eq!("all".parse::<CatchUp>().unwrap(), CatchUp::All);
+        assert!("some".parse::<CatchUp>().is_err());
+    }
+
+    #[test]
+    fn test_on_time_run_fires_under_every_policy() {
+        for policy in [CatchUp::Skip, CatchUp::Latest, CatchUp::All] {
+            assert_eq!(
+                hourly_runs("2026-03-04T09:00:00Z", "2026-03-04T09:00:20Z", policy),
+                ["09:00"],
+                "{policy:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_nothing_fires_before_next_run() {
+        assert!(
+            hourly_runs("2026-03-04T09:00:00Z", "2026-03-04T08:59:00Z", CatchUp::All).is_empty()
+        );
+    }
+
+    #[test]
+    fn test_missed_runs_after_downtime() {
+        // Down from before 09:00 until 12:30
+        let (next, now) = ("2026-03-04T09:00:00Z", "2026-03-04T12:30:00Z");
+        assert!(hourly_runs(next, now, CatchUp::Skip).is_empty());
+        assert_eq!(hourly_runs(next, now, CatchUp::Latest), ["12:00"]);
+        assert_eq!(
+            hourly_runs(next, now, CatchUp::All),
+            ["09:00", "10:00", "11:00", "12:00"]
+        );
+    }
+
+    #[test]
+    fn test_skip_still_fires_a_run_that_is_due_now() {
+        // Down for hours, but back just after a run was due
+        assert_eq!(
+            hourly_runs(
+                "2026-03-04T09:00:00Z",
+                "2026-03-04T12:00:30Z",
+                CatchUp::Skip
+            ),
+            ["12:00"]
+        );
+    }
+
+    #[test]
+    fn test_catch_up_all_is_capped() {
+        // Two days of hourly runs missed
+        let runs = hourly_runs("2026-03-04T00:00:00Z", "2026-03-05T23:30:00Z", CatchUp::All);
+        assert_eq!(runs.len(), MAX_CATCH_UP_RUNS);
+        assert_eq!(runs.first().unwrap(), "00:00");
+        assert_eq!(runs.last().unwrap(), "23:00");
+    }
+
+    /// Dispatcher that records sends, optionally failing them.
+    #[derive(Default)]
+    struct RecordingDispatcher {
+        sent: Mutex<Vec<(i64, DateTime<Utc>)>>,
+        fail: Mutex<Option<fn(String) -> DispatchError>>,
+    }
+
+    #[async_trait]
+    impl Dispatcher for RecordingDispatcher {
+        async fn send(&self, job: &Job, run_at: DateTime<Utc>) -> Result<(), DispatchError> {
+            if let Some(fail) = *self.fail.lock().unwrap() {
+                return Err(fail("nope".to_string()));
+            }
+            self.sent.lock().unwrap().push((job.id, run_at));
+            Ok(())
+        }
+    }
+
+    async fn scheduler(dir: &tempfile::TempDir, catch_up: CatchUp) -> Scheduler {
+        let db = Database::new(&dir.path().join("scheduler.db"))
+            .await
+            .unwrap();
+        Scheduler::new(db, Tz::UTC, catch_up)
+    }
+
+    async fn create(scheduler: &Scheduler, cron: &str, next_run_at: &str) -> Job {
+        scheduler
+            .db
+            .create_job(&NewJob {
+                cron,
+                timezone: "UTC",
+                agent_id: "agent-1",
+                prompt: "Summarize my inbox",
+                created_by: None,
+                next_run_at: utc(next_run_at),
+            })
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_tick_fires_due_jobs_and_reschedules() {
+        let dir = tempfile::tempdir().unwrap();
+        let scheduler = scheduler(&dir, CatchUp::Latest).await;
+        let daily = create(&scheduler, "0 9 * * *", "2026-03-04T09:00:00Z").await;
+        create(&scheduler, "0 10 * * *", "2026-03-04T10:00:00Z").await;
+        let dispatcher = RecordingDispatcher::default();
+
+        assert!(scheduler
+            .tick(&dispatcher, utc("2026-03-04T09:00:05Z"))
+            .await
+            .unwrap());
+        assert_eq!(
+            *dispatcher.sent.lock().unwrap(),
+            [(daily.id, utc("2026-03-04T09:00:00Z"))]
+        );
+
+        let jobs = scheduler.db.list_jobs(None).await.unwrap();
+        let daily = jobs.iter().find(|j| j.id == daily.id).unwrap();
+        assert_eq!(daily.next_run_at, "2026-03-05T09:00:00Z");
+        assert_eq!(daily.last_run_at.as_deref(), Some("2026-03-04T09:00:00Z"));
+
+        // Nothing new is due yet
+        scheduler
+            .tick(&dispatcher, utc("2026-03-04T09:30:00Z"))
+            .await
+            .unwrap();
+        assert_eq!(dispatcher.sent.lock().unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_tick_catches_up_after_downtime() {
+        let dir = tempfile::tempdir().unwrap();
+        let scheduler = scheduler(&dir, CatchUp::All).await;
+        let job = create(&scheduler, "0 * * * *", "2026-03-04T09:00:00Z").await;
+        let dispatcher = RecordingDispatcher::default();
+
+        scheduler
+            .tick(&dispatcher, utc("2026-03-04T11:15:00Z"))
+            .await
+            .unwrap();
+        let sent: Vec<_> = dispatcher.sent.lock().unwrap().clone();
+        assert_eq!(
+            sent,
+            [
+                (job.id, utc("2026-03-04T09:00:00Z")),
+                (job.id, utc("2026-03-04T10:00:00Z")),
+                (job.id, utc("2026-03-04T11:00:00Z")),
+            ]
+        );
+        let jobs = scheduler.db.list_jobs(None).await.unwrap();
+        assert_eq!(jobs[0].next_run_at, "2026-03-04T12:00:00Z");
+    }
+
+    #[tokio::test]
+    async fn test_unreachable_gateway_keeps_run_due() {
+        let dir = tempfile::tempdir().unwrap();
+        let scheduler = scheduler(&dir, CatchUp::Latest).await;
+        create(&scheduler, "0 9 * * *", "2026-03-04T09:00:00Z").await;
+        let dispatcher = RecordingDispatcher::default();
+        *dispatcher.fail.lock().unwrap() = Some(DispatchError::Unreachable);
+
+        assert!(!scheduler
+            .tick(&dispatcher, utc("2026-03-04T09:00:05Z"))
+            .await
+            .unwrap());
+        let jobs = scheduler.db.list_jobs(None).await.unwrap();
+        assert_eq!(jobs[0].next_run_at, "2026-03-04T09:00:00Z");
+
+        // Delivered once the gateway is back
+        *dispatcher.fail.lock().unwrap() = None;
+        scheduler
+            .tick(&dispatcher, utc("2026-03-04T09:02:00Z"))
+            .await
+            .unwrap();
+        assert_eq!(dispatcher.sent.lock().unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_offline_agent_keeps_run_due() {
+        let dir = tempfile::tempdir().unwrap();
+        let scheduler = scheduler(&dir, CatchUp::Latest).await;
+        create(&scheduler, "0 9 * * *", "2026-03-04T09:00:00Z").await;
+        let dispatcher = RecordingDispatcher::default();
+        *dispatcher.fail.lock().unwrap() = Some(DispatchError::AgentOffline);
+
+        assert!(!scheduler
+            .tick(&dispatcher, utc("2026-03-04T09:00:05Z"))
+            .await
+            .unwrap());
+        let jobs = scheduler.db.list_jobs(None).await.unwrap();
+        assert_eq!(jobs[0].next_run_at, "2026-03-04T09:00:00Z");
+        assert_eq!(jobs[0].last_error.as_deref(), Some("agent offline: nope"));
+
+        // Delivered once the agent reconnects
+        *dispatcher.fail.lock().unwrap() = None;
+        assert!(scheduler
+            .tick(&dispatcher, utc("2026-03-04T09:00:40Z"))
+            .await
+            .unwrap());
+        assert_eq!(
+            dispatcher.sent.lock().unwrap().as_slice(),
+            [(jobs[0].id, utc("2026-03-04T09:00:00Z"))]
+        );
+        let jobs = scheduler.db.list_jobs(None).await.unwrap();
+        assert_eq!(jobs[0].next_run_at, "2026-03-05T09:00:00Z");
+        assert_eq!(jobs[0].last_error, None);
+    }
+
+    #[tokio::test]
+    async fn test_rejected_run_is_recorded_and_dropped() {
+        let dir = tempfile::tempdir().unwrap();
+        let scheduler = scheduler(&dir, CatchUp::Latest).await;
+        create(&scheduler, "0 9 * * *", "2026-03-04T09:00:00Z").await;
+        let dispatcher = RecordingDispatcher::default();
+        *dispatcher.fail.lock().unwrap() = Some(DispatchError::Rejected);
+
+        scheduler
+            .tick(&dispatcher, utc("2026-03-04T09:00:05Z"))
+            .await
+            .unwrap();
+        let jobs = scheduler.db.list_jobs(None).await.unwrap();
+        assert_eq!(jobs[0].next_run_at, "2026-03-05T09:00:00Z");
+        assert_eq!(jobs[0].last_run_at, None);
+        assert_eq!(
+            jobs[0].last_error.as_deref(),
+            Some("gateway rejected the prompt: nope")
+        );
+    }
+}
@@ -0,0 +1,139 @@
+// ABOUTME: Schedule tool handlers for scheduler-pack.
+// ABOUTME: Implements create, list, and delete operations.
+
+use crate::cron::CronSchedule;
+use crate::db::{Job, NewJob};
+use crate::runner::Scheduler;
+use chrono::Utc;
+use chrono_tz::Tz;
+use coven_pack::{ExecutionContext, ToolError, ToolInput};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+#[derive(Debug, Deserialize, ToolInput)]
+pub struct ScheduleCreateInput {
+    /// Cron expression: minute hour day-of-month month day-of-week, or a macro like @daily
+    pub cron: String,
+    /// Agent to send the prompt to (defaults to the calling agent)
+    pub agent_id: Option<String>,
+    /// Prompt sent to the agent each time the schedule fires
+    pub prompt: String,
+    /// IANA timezone the cron fields are read in, e.g. 'Europe/Berlin' (defaults to the pack's timezone)
+    pub timezone: Option<String>,
+}
+
+#[derive(Debug, Deserialize, ToolInput)]
+pub struct ScheduleListInput {
+    /// Only list schedules that send to this agent
+    pub agent_id: Option<String>,
+}
+
+#[derive(Debug, Deserialize, ToolInput)]
+pub struct ScheduleDeleteInput {
+    /// ID of the schedule to delete
+    pub id: i64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ScheduleCreateOutput {
+    pub schedule: Job,
+    pub message: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ScheduleListOutput {
+    pub schedules: Vec<Job>,
+    pub count: usize,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ScheduleDeleteOutput {
+    pub deleted: bool,
+    pub message: String,
+}
+
+pub async fn create(
+    scheduler: Arc<Scheduler>,
+    ctx: ExecutionContext,
+    input: ScheduleCreateInput,
+) -> Result<ScheduleCreateOutput, ToolError> {
+    let schedule =
+        CronSchedule::parse(&input.cron).map_err(|e| ToolError::InvalidInput(e.to_string()))?;
+    let tz = match input.timezone.as_deref().map(str::trim) {
+        Some(name) if !name.is_empty() => name
+            .parse::<Tz>()
+            .map_err(|_| ToolError::InvalidInput(format!("unknown timezone '{}'", name)))?,
+        _ => scheduler.default_timezone,
+    };
+    let agent_id = input
+        .agent_id
+        .filter(|id| !id.is_empty())
+        .or_else(|| ctx.agent_id.clone())
+        .ok_or_else(|| {
+            ToolError::InvalidInput("agent_id is required when the caller is unknown".to_string())
+        })?;
+    if input.prompt.trim().is_empty() {
+        return Err(ToolError::InvalidInput(
+            "prompt must not be empty".to_string(),
+        ));
+    }
+
+    let next_run_at = schedule
+        .next_after(Utc::now(), tz)
+        .ok_or_else(|| ToolError::InvalidInput(format!("'{}' never fires", schedule)))?;
+
+    let job = scheduler
+        .db
+        .create_job(&NewJob {
+            cron: schedule.as_str(),
+            timezone: tz.name(),
+            agent_id: &agent_id,
+            prompt: &input.prompt,
+            created_by: ctx.agent_id.as_deref(),
+            next_run_at,
+        })
+        .await
+        .map_err(|e| ToolError::ExecutionFailed(e.to_string()))?;
+    scheduler.wake();
+
+    Ok(ScheduleCreateOutput {
+        message: format!(
+            "Created schedule #{} for {}; next run at {}",
+            job.id, job.agent_id, job.next_run_at
+        ),
+        schedule: job,
+    })
+}
+
+pub async fn list(
+    scheduler: Arc<Scheduler>,
+    input: ScheduleListInput,
+) -> Result<ScheduleListOutput, ToolError> {
+    let schedules = scheduler
+        .db
+        .list_jobs(input.agent_id.as_deref().filter(|id| !id.is_empty()))
+        .await
+        .map_err(|e| ToolError::ExecutionFailed(e.to_string()))?;
+
+    let count = schedules.len();
+    Ok(ScheduleListOutput { schedules, count })
+}
+
+pub async fn delete(
+    scheduler: Arc<Scheduler>,
+    input: ScheduleDeleteInput,
+) -> Result<ScheduleDeleteOutput, ToolError> {
+    let deleted = scheduler
+        .db
+        .delete_job(input.id)
+        .await
+        .map_err(|e| ToolError::ExecutionFailed(e.to_string()))?;
+
+    let message = if deleted {
+        format!("Deleted schedule #{}", input.id)
+    } else {
+        format!("Schedule #{} not found", input.id)
+    };
+
+    Ok(ScheduleDeleteOutput { deleted, message })
+}
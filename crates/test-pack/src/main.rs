@@ -2,16 +2,18 @@
 // ABOUTME: Registers echo and admin_echo tools with the gateway.
 
 use anyhow::{anyhow, Result};
-use async_trait::async_trait;
-use coven_pack::{ManifestBuilder, PackClient, SchemaBuilder, ToolError, ToolHandler};
-use coven_ssh::load_or_generate_key;
+use coven_pack::{ManifestBuilder, PackClient, RetryPolicy, ToolError, ToolInput, TypedHandler};
+use coven_ssh::load_or_generate_key_with_passphrase;
 use serde::{Deserialize, Serialize};
+use std::future::{ready, Ready};
+use std::sync::Arc;
 use tracing::info;
 
 const PACK_NAME: &str = "test-pack";
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, ToolInput)]
 struct EchoInput {
+    /// Message to echo
     message: String,
 }
 
@@ -21,29 +23,31 @@ struct EchoOutput {
     tool: String,
 }
 
-struct TestHandler;
-
-#[async_trait]
-impl ToolHandler for TestHandler {
-    async fn execute(&self, tool_name: &str, input_json: &str) -> Result<String, ToolError> {
-        info!("Executing tool: {} with input: {}", tool_name, input_json);
-
-        match tool_name {
-            "echo" | "admin_echo" => {
-                let input: EchoInput = serde_json::from_str(input_json)
-                    .map_err(|e| ToolError::InvalidInput(e.to_string()))?;
+/// Build an echo tool that reports `tool` as its name.
+fn echo(tool: &'static str) -> impl Fn(Arc<()>, EchoInput) -> Ready<Result<EchoOutput, ToolError>> {
+    move |_, input| {
+        ready(Ok(EchoOutput {
+            echoed: input.message,
+            tool: tool.to_string(),
+        }))
+    }
+}
 
-                let output = EchoOutput {
-                    echoed: input.message,
-                    tool: tool_name.to_string(),
-                };
+fn build_handler() -> TypedHandler {
+    TypedHandler::new()
+        .tool("echo", echo("echo"))
+        .tool("admin_echo", echo("admin_echo"))
+}
 
-                serde_json::to_string(&output)
-                    .map_err(|e| ToolError::ExecutionFailed(e.to_string()))
-            }
-            _ => Err(ToolError::UnknownTool(tool_name.to_string())),
-        }
-    }
+fn build_manifest() -> coven_proto::PackManifest {
+    ManifestBuilder::new(PACK_NAME, "0.1.0")
+        .typed_tool::<EchoInput>("echo", "Echoes back the input message", &[])
+        .typed_tool::<EchoInput>(
+            "admin_echo",
+            "Admin-only echo (requires admin capability)",
+            &["admin"],
+        )
+        .build()
 }
 
 #[tokio::main]
@@ -57,30 +61,53 @@ async fn main() -> Result<()> {
     info!("SSH key: {}", config.ssh_key_path.display());
 
     // Load existing key or generate one
-    let _private_key = load_or_generate_key(&config.ssh_key_path)?;
+    let _private_key = load_or_generate_key_with_passphrase(
+        &config.ssh_key_path,
+        config.ssh_key_passphrase.as_ref(),
+    )?;
 
-    let echo_schema = SchemaBuilder::object()
-        .property(
-            "message",
-            SchemaBuilder::string().description("Message to echo"),
-        )
-        .required(&["message"])
-        .build();
-
-    let manifest = ManifestBuilder::new(PACK_NAME, "0.1.0")
-        .tool("echo", "Echoes back the input message", &echo_schema, &[])
-        .tool(
-            "admin_echo",
-            "Admin-only echo (requires admin capability)",
-            &echo_schema,
-            &["admin"],
-        )
-        .build();
+    let manifest = build_manifest();
 
     info!("Registering {} tools", manifest.tools.len());
 
-    let client = PackClient::connect(&config.gateway_url, &config.ssh_key_path).await?;
-    client.run(manifest, TestHandler).await?;
+    let client = PackClient::connect_with_config_retry(&config, RetryPolicy::default())
+        .await?
+        .on_connection_state(|state| info!(?state, "Gateway connection state changed"));
+    client.run(manifest, build_handler()).await?;
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use coven_pack::{SchemaBuilder, ToolHandler};
+
+    #[test]
+    fn test_echo_schema_matches_hand_written() {
+        let expected = SchemaBuilder::object()
+            .property(
+                "message",
+                SchemaBuilder::string().description("Message to echo"),
+            )
+            .required(&["message"])
+            .build();
+        assert_eq!(
+            EchoInput::input_schema(),
+            serde_json::from_str::<serde_json::Value>(&expected).unwrap()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_echo_tools_report_their_name() {
+        let handler = build_handler();
+        let output = handler
+            .execute("admin_echo", r#"{"message": "hi"}"#)
+            .await
+            .unwrap();
+        assert_eq!(output, r#"{"echoed":"hi","tool":"admin_echo"}"#);
+
+        let err = handler.execute("echo", "{}").await.unwrap_err();
+        assert!(matches!(err, ToolError::InvalidInput(_)));
+    }
+}